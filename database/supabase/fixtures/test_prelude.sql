@@ -0,0 +1,7 @@
+-- ============================================================================
+-- Prelude test database (#[sqlx::test] fixtures, sebelum schema.sql)
+-- ============================================================================
+-- Objek yang dipakai schema.sql tapi tidak dibuat di dalamnya (di Supabase sudah ada):
+-- security_incidents.incident_number memakai sequence ini sebagai default.
+
+CREATE SEQUENCE IF NOT EXISTS security_incident_seq;
@@ -0,0 +1,21 @@
+-- ============================================================================
+-- Data minimal untuk test database (#[sqlx::test] fixtures, setelah schema.sql)
+-- ============================================================================
+-- id tetap supaya test bisa merujuk langsung: user 1 customer, user 2 & 3 seller,
+-- vehicle 1 rental milik seller 2, vehicle 2 sale milik seller 2.
+
+INSERT INTO users (id, email, password_hash, name, phone, is_seller) VALUES
+    (1, 'customer@test.local', 'x', 'Customer Test', '081200000001', false),
+    (2, 'seller@test.local', 'x', 'Seller Test', '081200000002', true),
+    (3, 'seller2@test.local', 'x', 'Seller Lain', '081200000003', true);
+SELECT setval(pg_get_serial_sequence('users', 'id'), 3);
+
+INSERT INTO vehicles (
+    id, seller_id, title, category, price, brand, model, year, seats, vehicle_type,
+    city, address, photos, deposit_amount
+) VALUES
+    (1, 2, 'Toyota Avanza 2022', 'rental', 350000, 'Toyota', 'Avanza', 2022, 7, 'MPV',
+     'Jakarta', 'Jl. Sudirman No. 1', '[]', 1000000),
+    (2, 2, 'Honda Jazz 2019', 'sale', 180000000, 'Honda', 'Jazz', 2019, 5, 'Hatchback',
+     'Jakarta', 'Jl. Sudirman No. 1', '[]', 0);
+SELECT setval(pg_get_serial_sequence('vehicles', 'id'), 2);
@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Migrasi: index audit_logs untuk query admin per user
+-- ============================================================================
+-- schema.sql sudah berisi index ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy payment-service versi baru.
+--
+-- Endpoint GET /api/admin/audit-logs memfilter user_id dan mengurutkan created_at terbaru,
+-- index (user_id, created_at DESC) menggantikan index user_id saja.
+
+BEGIN;
+
+CREATE INDEX IF NOT EXISTS idx_audit_user_created ON audit_logs(user_id, created_at DESC);
+DROP INDEX IF EXISTS idx_audit_user;
+
+COMMIT;
@@ -0,0 +1,39 @@
+-- ============================================================================
+-- Migrasi: blacklist token idempotent untuk logout bersamaan
+-- ============================================================================
+-- schema.sql sudah berisi fungsi ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy auth-service versi baru.
+--
+-- Logout memanggil blacklist_token() untuk refresh + access token; JTI yang sudah diblacklist
+-- (logout kedua dari device yang sama) tidak lagi gagal di unique token_jti.
+
+BEGIN;
+
+-- Blacklist satu token, idempotent: JTI yang sudah diblacklist tidak diubah (return false)
+-- Expiry mengikuti umur maksimal refresh token (JWT_REFRESH_TOKEN_EXPIRY default 7 hari)
+CREATE OR REPLACE FUNCTION blacklist_token(
+    p_token_jti TEXT,
+    p_token_type TEXT,
+    p_reason TEXT
+)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path TO public
+AS $$
+DECLARE
+    v_inserted INTEGER;
+BEGIN
+    INSERT INTO jwt_blacklist (token_jti, token_type, reason, expires_at, user_id)
+    SELECT p_token_jti, p_token_type, p_reason, NOW() + INTERVAL '7 days',
+           (SELECT user_id FROM user_sessions
+            WHERE refresh_token = p_token_jti OR access_token_jti = p_token_jti
+            LIMIT 1)
+    ON CONFLICT (token_jti) DO NOTHING;
+
+    GET DIAGNOSTICS v_inserted = ROW_COUNT;
+    RETURN v_inserted > 0;
+END;
+$$;
+
+COMMIT;
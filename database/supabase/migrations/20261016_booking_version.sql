@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migrasi: optimistic concurrency sale order dan test drive
+-- ============================================================================
+-- schema.sql sudah berisi kolom ini untuk database baru. Jalankan file ini sekali di database yang
+-- sudah ada sebelum deploy booking-service versi baru: setiap transisi status memakai
+-- WHERE version = $n dan REQUIRED_SCHEMA mengecek kolom version. Baris lama mulai dari version 0.
+
+BEGIN;
+
+-- Optimistic concurrency: naik setiap transisi status
+ALTER TABLE sale_orders ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE testdrive_bookings ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
@@ -0,0 +1,30 @@
+-- ============================================================================
+-- Migrasi: satu conversation umum (tanpa vehicle) per customer-seller
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan index ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy chat-service versi baru.
+--
+-- Conversation lama dengan vehicle_id NULL tidak bisa dibedakan antara conversation umum dan
+-- conversation yang vehicle-nya sudah dihapus. Yang tertua per customer-seller dijadikan
+-- conversation umum; duplikat lain tetap ada (bisa dibuka by id) tapi tidak dipakai ulang saat create.
+
+BEGIN;
+
+ALTER TABLE conversations
+    ADD COLUMN is_general BOOLEAN NOT NULL DEFAULT false,
+    ADD CHECK (NOT is_general OR vehicle_id IS NULL);
+
+UPDATE conversations c
+SET is_general = true
+FROM (
+    SELECT DISTINCT ON (customer_id, seller_id) id
+    FROM conversations
+    WHERE vehicle_id IS NULL
+    ORDER BY customer_id, seller_id, created_at, id
+) oldest
+WHERE c.id = oldest.id;
+
+CREATE UNIQUE INDEX uniq_conversations_general ON conversations(customer_id, seller_id)
+    WHERE is_general;
+
+COMMIT;
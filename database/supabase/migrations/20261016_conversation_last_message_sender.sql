@@ -0,0 +1,24 @@
+-- ============================================================================
+-- Migrasi: pengirim last_message di conversation
+-- ============================================================================
+-- schema.sql sudah berisi kolom ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy chat-service versi baru.
+--
+-- Conversation lama diisi dari pengirim message terbaru, supaya prefix "Anda:" di inbox
+-- langsung benar tanpa menunggu message berikutnya.
+
+BEGIN;
+
+ALTER TABLE conversations
+    ADD COLUMN last_message_sender_id INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+UPDATE conversations c
+SET last_message_sender_id = latest.sender_id
+FROM (
+    SELECT DISTINCT ON (conversation_id) conversation_id, sender_id
+    FROM messages
+    ORDER BY conversation_id, created_at DESC, id DESC
+) latest
+WHERE c.id = latest.conversation_id;
+
+COMMIT;
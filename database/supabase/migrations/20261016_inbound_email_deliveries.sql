@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migrasi: dedupe delivery webhook inbound email (reply-by-email chat)
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy chat-service versi baru.
+
+CREATE TABLE IF NOT EXISTS inbound_email_deliveries (
+    provider VARCHAR(20) NOT NULL,
+    delivery_id VARCHAR(255) NOT NULL,
+    message_id INTEGER REFERENCES messages(id) ON DELETE CASCADE,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (provider, delivery_id)
+);
@@ -0,0 +1,12 @@
+-- ============================================================================
+-- Migrasi: quote/balasan message
+-- ============================================================================
+-- schema.sql sudah berisi kolom ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy chat-service versi baru.
+
+BEGIN;
+
+ALTER TABLE messages
+    ADD COLUMN reply_to_message_id INTEGER REFERENCES messages(id) ON DELETE SET NULL;
+
+COMMIT;
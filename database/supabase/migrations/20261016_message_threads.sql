@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Migrasi: thread balasan message
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan index ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy chat-service versi baru.
+
+BEGIN;
+
+ALTER TABLE messages
+    ADD COLUMN thread_root_id INTEGER REFERENCES messages(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_messages_thread_root ON messages(thread_root_id, created_at)
+    WHERE thread_root_id IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,13 @@
+-- ============================================================================
+-- Migrasi: message bertipe document
+-- ============================================================================
+-- schema.sql sudah berisi CHECK ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy chat-service versi baru.
+
+BEGIN;
+
+ALTER TABLE messages
+    DROP CONSTRAINT messages_message_type_check,
+    ADD CONSTRAINT messages_message_type_check CHECK (message_type IN ('text', 'image', 'document'));
+
+COMMIT;
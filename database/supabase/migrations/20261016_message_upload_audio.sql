@@ -0,0 +1,13 @@
+-- ============================================================================
+-- Migrasi: message bertipe audio
+-- ============================================================================
+-- schema.sql sudah berisi CHECK ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy chat-service versi baru, setelah 20261016_message_type_document.sql.
+
+BEGIN;
+
+ALTER TABLE messages
+    DROP CONSTRAINT messages_message_type_check,
+    ADD CONSTRAINT messages_message_type_check CHECK (message_type IN ('text', 'image', 'document', 'audio'));
+
+COMMIT;
@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Migrasi: dispatch push notifikasi (quiet hours)
+-- ============================================================================
+-- schema.sql sudah berisi kolom ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy notification-service versi baru. Notifikasi lama ditandai
+-- sudah di-dispatch agar tidak di-push ulang.
+
+ALTER TABLE notifications
+    ADD COLUMN IF NOT EXISTS dispatched_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS push_status VARCHAR(20)
+        CHECK (push_status IN ('sent', 'failed', 'skipped', 'suppressed', 'digest'));
+
+UPDATE notifications SET dispatched_at = COALESCE(created_at, NOW()) WHERE dispatched_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_undispatched ON notifications(id) WHERE dispatched_at IS NULL;
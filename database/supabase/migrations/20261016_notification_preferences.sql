@@ -0,0 +1,50 @@
+-- ============================================================================
+-- Migrasi: quiet hours per user + dispatch push notifikasi
+-- ============================================================================
+-- schema.sql sudah berisi tabel dan kolom ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy notification-service versi baru: endpoint preferensi
+-- membaca notification_preferences dan scheduler dispatch memakai kolom dispatch di notifications.
+
+BEGIN;
+
+-- Preferensi notifikasi per user: quiet hours dalam jam lokal timezone user (IANA)
+-- Selama quiet hours push/SMS ditahan, notifikasi in-app tetap dibuat
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta',
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    -- Kirim ringkasan notifikasi yang ditahan setelah quiet hours selesai
+    quiet_hours_digest BOOLEAN NOT NULL DEFAULT true,
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT notification_quiet_hours_pair CHECK ((quiet_hours_start IS NULL) = (quiet_hours_end IS NULL))
+);
+
+DROP TRIGGER IF EXISTS trigger_notification_preferences_updated_at ON notification_preferences;
+CREATE TRIGGER trigger_notification_preferences_updated_at BEFORE UPDATE ON notification_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
+
+-- Notifikasi lama sudah dibaca lewat in-app sebelum ada dispatcher: tandai sudah di-dispatch
+-- (hanya saat kolom baru dibuat) agar scheduler tidak mengirim push untuk seluruh riwayat
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'notifications' AND column_name = 'dispatched_at'
+    ) THEN
+        ALTER TABLE notifications ADD COLUMN dispatched_at TIMESTAMPTZ;
+        UPDATE notifications SET dispatched_at = COALESCE(created_at, NOW());
+    END IF;
+END $$;
+
+ALTER TABLE notifications
+    ADD COLUMN IF NOT EXISTS push_status VARCHAR(20)
+        CHECK (push_status IN ('sent', 'failed', 'skipped', 'suppressed', 'digest')),
+    -- Push yang gagal dicoba ulang dengan backoff; next_push_at = lease dispatcher yang sedang
+    -- mengirim atau jadwal retry berikutnya (NULL = siap diambil)
+    ADD COLUMN IF NOT EXISTS push_attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS next_push_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_undispatched ON notifications(id) WHERE dispatched_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,51 @@
+-- ============================================================================
+-- Migrasi: mode digest notifikasi per tipe
+-- ============================================================================
+-- schema.sql sudah berisi kolom, tabel, dan trigger ini untuk database baru. Jalankan file ini
+-- sekali di database yang sudah ada (setelah 20261016_notification_preferences.sql) sebelum deploy
+-- notification-service versi baru: endpoint preferensi membaca digest_types dan scheduler digest
+-- mengosongkan notification_digest_events.
+
+BEGIN;
+
+-- Tipe notifikasi mode digest (dirangkum berkala), tipe lain dikirim langsung
+ALTER TABLE notification_preferences
+    ADD COLUMN IF NOT EXISTS digest_types TEXT[] NOT NULL DEFAULT '{}';
+
+-- Antrean notifikasi mode digest, dikosongkan scheduler notification-service
+CREATE TABLE IF NOT EXISTS notification_digest_events (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    type VARCHAR(50) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    message TEXT NOT NULL,
+    related_id INTEGER,
+    related_type VARCHAR(50),
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_digest_events_user ON notification_digest_events(user_id, created_at);
+
+-- Notifikasi bertipe mode digest tetap dibuat (in-app), push-nya dialihkan ke antrean digest
+-- (service pengirim tidak perlu tahu)
+CREATE OR REPLACE FUNCTION divert_digest_notification()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.type <> 'digest' AND EXISTS (
+        SELECT 1 FROM notification_preferences
+        WHERE user_id = NEW.user_id AND NEW.type = ANY(digest_types)
+    ) THEN
+        INSERT INTO notification_digest_events (user_id, type, title, message, related_id, related_type, created_at)
+        VALUES (NEW.user_id, NEW.type, NEW.title, NEW.message, NEW.related_id, NEW.related_type, COALESCE(NEW.created_at, NOW()));
+        NEW.dispatched_at := NOW();
+        NEW.push_status := 'digest';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_notifications_digest ON notifications;
+CREATE TRIGGER trigger_notifications_digest BEFORE INSERT ON notifications
+    FOR EACH ROW EXECUTE FUNCTION divert_digest_notification();
+
+COMMIT;
@@ -0,0 +1,145 @@
+-- ============================================================================
+-- Migrasi: outbound webhook seller (endpoint, antrian delivery, trigger event)
+-- ============================================================================
+-- schema.sql sudah berisi tabel, function dan trigger ini untuk database baru. Jalankan file ini
+-- sekali di database yang sudah ada sebelum deploy booking-service versi baru. Event hanya
+-- diantrikan untuk perubahan setelah migrasi, tidak ada backfill.
+
+BEGIN;
+
+-- Outbound webhook: endpoint back-office dealer milik seller
+CREATE TABLE outbound_webhooks (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- Shared secret HMAC, bisa di-rotate oleh seller
+    secret VARCHAR(100) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    secret_rotated_at TIMESTAMPTZ,
+    disabled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_outbound_webhooks_seller ON outbound_webhooks(seller_id, is_active);
+
+-- Antrian + log delivery webhook (retry dengan backoff sampai max attempts)
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES outbound_webhooks(id) ON DELETE CASCADE,
+    event_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (
+        status IN ('pending', 'delivered', 'failed')
+    ),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_deliveries_pending ON webhook_deliveries(next_attempt_at, id)
+    WHERE status = 'pending';
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at DESC);
+
+CREATE TRIGGER trigger_outbound_webhooks_updated_at BEFORE UPDATE ON outbound_webhooks
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
+
+-- Antrikan event ke semua webhook aktif milik seller
+CREATE OR REPLACE FUNCTION enqueue_seller_webhook(p_seller_id INTEGER, p_event VARCHAR, p_data JSONB)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO webhook_deliveries (webhook_id, event_type, payload)
+    SELECT id, p_event, jsonb_build_object('event', p_event, 'data', p_data, 'occurred_at', NOW())
+    FROM outbound_webhooks
+    WHERE seller_id = p_seller_id AND is_active = true;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Event order (sale/rental) dan test drive: dibuat atau status berubah
+CREATE OR REPLACE FUNCTION webhook_on_booking_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    event_prefix VARCHAR;
+BEGIN
+    IF TG_OP = 'UPDATE' AND NEW.status IS NOT DISTINCT FROM OLD.status THEN
+        RETURN NEW;
+    END IF;
+
+    event_prefix := CASE TG_TABLE_NAME
+        WHEN 'sale_orders' THEN 'sale_order'
+        WHEN 'rental_bookings' THEN 'rental_booking'
+        ELSE 'testdrive'
+    END;
+
+    -- Hanya field non-sensitif (tanpa KTP/kontak customer)
+    PERFORM enqueue_seller_webhook(
+        NEW.seller_id,
+        event_prefix || CASE WHEN TG_OP = 'INSERT' THEN '.created' ELSE '.status_changed' END,
+        jsonb_build_object(
+            'id', NEW.id,
+            'order_id', to_jsonb(NEW)->>'order_id',
+            'vehicle_id', NEW.vehicle_id,
+            'status', NEW.status,
+            'previous_status', CASE WHEN TG_OP = 'UPDATE' THEN OLD.status END
+        )
+    );
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sale_webhook AFTER INSERT OR UPDATE OF status ON sale_orders
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_booking_event();
+
+CREATE TRIGGER trigger_rental_webhook AFTER INSERT OR UPDATE OF status ON rental_bookings
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_booking_event();
+
+CREATE TRIGGER trigger_testdrive_webhook AFTER INSERT OR UPDATE OF status ON testdrive_bookings
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_booking_event();
+
+-- Event payment untuk seller pemilik order
+CREATE OR REPLACE FUNCTION webhook_on_payment_status()
+RETURNS TRIGGER AS $$
+DECLARE
+    order_seller_id INTEGER;
+BEGIN
+    IF NEW.status IS NOT DISTINCT FROM OLD.status THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.sale_order_id IS NOT NULL THEN
+        SELECT seller_id INTO order_seller_id FROM sale_orders WHERE id = NEW.sale_order_id;
+    ELSE
+        SELECT seller_id INTO order_seller_id FROM rental_bookings WHERE id = NEW.rental_booking_id;
+    END IF;
+
+    IF order_seller_id IS NULL THEN
+        RETURN NEW;
+    END IF;
+
+    PERFORM enqueue_seller_webhook(
+        order_seller_id,
+        'payment.status_changed',
+        jsonb_build_object(
+            'id', NEW.id,
+            'order_id', NEW.order_id,
+            'payment_for_type', NEW.payment_for_type,
+            'sale_order_id', NEW.sale_order_id,
+            'rental_booking_id', NEW.rental_booking_id,
+            'gross_amount', NEW.gross_amount,
+            'status', NEW.status,
+            'previous_status', OLD.status
+        )
+    );
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_payment_webhook AFTER UPDATE OF status ON payments
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_payment_status();
+
+COMMIT;
@@ -0,0 +1,19 @@
+-- ============================================================================
+-- Migrasi: refund ke channel asal + status refund pending di payments
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan constraint ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy payment-service versi baru (refund dicatat pending sebelum
+-- Midtrans dipanggil).
+
+BEGIN;
+
+ALTER TABLE payments
+    ADD COLUMN IF NOT EXISTS refund_status VARCHAR(20),
+    ADD COLUMN IF NOT EXISTS refund_reference VARCHAR(100);
+
+ALTER TABLE payments DROP CONSTRAINT IF EXISTS payments_refund_status_check;
+ALTER TABLE payments
+    ADD CONSTRAINT payments_refund_status_check
+    CHECK (refund_status IN ('pending', 'completed', 'manual_required'));
+
+COMMIT;
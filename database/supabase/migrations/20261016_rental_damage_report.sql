@@ -0,0 +1,39 @@
+-- ============================================================================
+-- Migrasi: laporan pengembalian rental + tagihan kerusakan
+-- ============================================================================
+-- schema.sql sudah berisi tabel dan constraint ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy booking-service/payment-service versi baru.
+
+BEGIN;
+
+-- Laporan kondisi kendaraan saat rental dikembalikan (diisi seller)
+CREATE TABLE rental_return_reports (
+    id SERIAL PRIMARY KEY,
+    rental_booking_id INTEGER NOT NULL UNIQUE REFERENCES rental_bookings(id) ON DELETE RESTRICT,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    customer_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    condition_notes TEXT NOT NULL,
+    odometer_km INTEGER NOT NULL CHECK (odometer_km >= 0),
+    fuel_level INTEGER NOT NULL CHECK (fuel_level BETWEEN 0 AND 100),
+    -- [{"description": "...", "amount": 350000}]
+    damage_items JSONB NOT NULL DEFAULT '[]',
+    damage_total NUMERIC(15, 2) NOT NULL DEFAULT 0,
+    -- Tagihan kerusakan dibayar customer lewat payment-service (rental_damage)
+    charge_status VARCHAR(20) NOT NULL DEFAULT 'none' CHECK (
+        charge_status IN ('none', 'pending', 'paid')
+    ),
+    charge_paid_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_return_report_customer ON rental_return_reports(customer_id);
+
+ALTER TABLE rental_return_reports ENABLE ROW LEVEL SECURITY;
+
+-- Payment tagihan kerusakan
+ALTER TABLE payments DROP CONSTRAINT payments_payment_for_type_check;
+ALTER TABLE payments ADD CONSTRAINT payments_payment_for_type_check
+    CHECK (payment_for_type IN ('rental', 'sale', 'rental_damage'));
+
+COMMIT;
@@ -0,0 +1,32 @@
+-- ============================================================================
+-- Migrasi: deposit jaminan rental (vehicle, booking, payment deposit, potongan kerusakan)
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan constraint ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada (setelah 20261016_rental_damage_report.sql) sebelum deploy
+-- vehicle-service/booking-service/payment-service versi baru. Booking lama tanpa deposit
+-- (deposit_amount 0, deposit_status 'none').
+
+BEGIN;
+
+-- Deposit yang ditetapkan seller, disalin ke rental_bookings saat booking
+ALTER TABLE vehicles
+    ADD COLUMN IF NOT EXISTS deposit_amount NUMERIC(15, 2) NOT NULL DEFAULT 0
+    CHECK (deposit_amount >= 0);
+
+ALTER TABLE rental_bookings
+    ADD COLUMN deposit_amount NUMERIC(15, 2) NOT NULL DEFAULT 0 CHECK (deposit_amount >= 0),
+    ADD COLUMN deposit_status VARCHAR(20) NOT NULL DEFAULT 'none' CHECK (
+        deposit_status IN ('none', 'pending', 'held', 'refunded', 'partially_refunded', 'forfeited')
+    ),
+    ADD COLUMN deposit_refunded_amount NUMERIC(15, 2),
+    ADD COLUMN deposit_settled_at TIMESTAMPTZ;
+
+ALTER TABLE rental_return_reports
+    ADD COLUMN deposit_deducted NUMERIC(15, 2) NOT NULL DEFAULT 0;
+
+-- Deposit dibayar sebagai payment terpisah
+ALTER TABLE payments DROP CONSTRAINT payments_payment_for_type_check;
+ALTER TABLE payments ADD CONSTRAINT payments_payment_for_type_check
+    CHECK (payment_for_type IN ('rental', 'sale', 'rental_damage', 'rental_deposit'));
+
+COMMIT;
@@ -0,0 +1,28 @@
+-- ============================================================================
+-- Migrasi: serah terima rental (check-in / check-out)
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database yang
+-- sudah ada sebelum deploy booking-service versi baru (REQUIRED_SCHEMA mengecek rental_handovers).
+
+BEGIN;
+
+-- Serah terima kendaraan rental: check-in (paid/akan_datang/berjalan -> berjalan) dan check-out (berjalan/selesai -> selesai)
+CREATE TABLE IF NOT EXISTS rental_handovers (
+    id SERIAL PRIMARY KEY,
+    rental_booking_id INTEGER NOT NULL REFERENCES rental_bookings(id) ON DELETE CASCADE,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('checkin', 'checkout')),
+    odometer_km INTEGER NOT NULL CHECK (odometer_km >= 0),
+    fuel_level INTEGER NOT NULL CHECK (fuel_level BETWEEN 0 AND 100),
+    -- ["https://res.cloudinary.com/..."]
+    photos JSONB NOT NULL DEFAULT '[]',
+    notes TEXT,
+    recorded_by INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Denda keterlambatan saat check-out, ditagihkan lewat laporan pengembalian
+    late_fee NUMERIC(15, 2) NOT NULL DEFAULT 0 CHECK (late_fee >= 0),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+
+    CONSTRAINT rental_handover_once UNIQUE (rental_booking_id, kind)
+);
+
+COMMIT;
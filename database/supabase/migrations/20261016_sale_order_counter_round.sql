@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Migrasi: batas ronde counter offer sale order
+-- ============================================================================
+-- schema.sql sudah berisi kolom ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy booking-service versi baru.
+--
+-- Order lama yang sudah punya counter_offer_price dihitung satu ronde, karena riwayat counter
+-- sebelumnya tidak tersimpan; sisanya mulai dari 0 (batas MAX_COUNTER_OFFER_ROUNDS).
+
+BEGIN;
+
+ALTER TABLE sale_orders ADD COLUMN counter_round INTEGER NOT NULL DEFAULT 0;
+
+UPDATE sale_orders SET counter_round = 1 WHERE counter_offer_price IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,40 @@
+-- ============================================================================
+-- Migrasi: checklist dokumen serah terima sale order
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan tabel ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy booking-service versi baru: 4 kolom boolean dokumen
+-- (bpkb/stnk/faktur/pajak_transferred) dipindah ke sale_orders.document_checklist lalu dihapus.
+
+BEGIN;
+
+ALTER TABLE sale_orders
+    ADD COLUMN document_checklist JSONB NOT NULL DEFAULT '[
+        {"key": "bpkb", "label": "BPKB (Buku Pemilik Kendaraan Bermotor)", "required": true, "transferred": false},
+        {"key": "stnk", "label": "STNK (Surat Tanda Nomor Kendaraan)", "required": true, "transferred": false},
+        {"key": "faktur", "label": "Faktur kendaraan", "required": true, "transferred": false},
+        {"key": "pajak", "label": "Bukti pajak kendaraan", "required": true, "transferred": false}
+    ]' CHECK (jsonb_typeof(document_checklist) = 'array');
+
+-- Order lama: checklist default dengan status serah terima yang sama
+UPDATE sale_orders
+SET document_checklist = jsonb_build_array(
+    jsonb_build_object('key', 'bpkb', 'label', 'BPKB (Buku Pemilik Kendaraan Bermotor)', 'required', true, 'transferred', COALESCE(bpkb_transferred, false)),
+    jsonb_build_object('key', 'stnk', 'label', 'STNK (Surat Tanda Nomor Kendaraan)', 'required', true, 'transferred', COALESCE(stnk_transferred, false)),
+    jsonb_build_object('key', 'faktur', 'label', 'Faktur kendaraan', 'required', true, 'transferred', COALESCE(faktur_transferred, false)),
+    jsonb_build_object('key', 'pajak', 'label', 'Bukti pajak kendaraan', 'required', true, 'transferred', COALESCE(pajak_transferred, false))
+);
+
+ALTER TABLE sale_orders
+    DROP COLUMN bpkb_transferred,
+    DROP COLUMN stnk_transferred,
+    DROP COLUMN faktur_transferred,
+    DROP COLUMN pajak_transferred;
+
+CREATE TABLE IF NOT EXISTS seller_document_checklists (
+    seller_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- [{key, label, required}]
+    items JSONB NOT NULL CHECK (jsonb_typeof(items) = 'array'),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+COMMIT;
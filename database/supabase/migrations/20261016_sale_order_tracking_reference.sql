@@ -0,0 +1,31 @@
+-- ============================================================================
+-- Migrasi: kode tracking publik sale order
+-- ============================================================================
+-- schema.sql sudah berisi kolom ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy booking-service versi baru.
+--
+-- Order lama diberi kode acak dengan format yang sama dengan utils::order_tracking::generate_reference
+-- (16 karakter Crockford base32, 5 bit acak per karakter dari pgcrypto, dikelompokkan 4-4-4-4),
+-- jadi bisa ditrack seperti order baru.
+
+BEGIN;
+
+ALTER TABLE sale_orders ADD COLUMN tracking_reference VARCHAR(32);
+
+CREATE FUNCTION pg_temp.sale_tracking_reference() RETURNS VARCHAR AS $$
+    SELECT string_agg(
+        CASE WHEN i > 0 AND i % 4 = 0 THEN '-' ELSE '' END
+            || substr('0123456789ABCDEFGHJKMNPQRSTVWXYZ', (get_byte(bytes, i) & 31) + 1, 1),
+        '' ORDER BY i
+    )
+    FROM (SELECT gen_random_bytes(16) AS bytes) random_bytes,
+         generate_series(0, 15) AS i
+$$ LANGUAGE sql VOLATILE;
+
+UPDATE sale_orders SET tracking_reference = pg_temp.sale_tracking_reference();
+
+ALTER TABLE sale_orders
+    ALTER COLUMN tracking_reference SET NOT NULL,
+    ADD UNIQUE (tracking_reference);
+
+COMMIT;
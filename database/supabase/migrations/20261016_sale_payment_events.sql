@@ -0,0 +1,32 @@
+-- ============================================================================
+-- Migrasi: event sale order lunas dari payment-service ke booking-service
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy payment-service dan booking-service versi baru.
+--
+-- payment-service tidak lagi mengubah sale_orders langsung. Payment sale yang sudah sukses
+-- sementara order-nya masih pending_payment dicatat sebagai event, supaya diproses booking-service.
+
+BEGIN;
+
+CREATE TABLE sale_payment_events (
+    id BIGSERIAL PRIMARY KEY,
+    sale_order_id INTEGER NOT NULL REFERENCES sale_orders(id) ON DELETE CASCADE,
+    payment_order_id VARCHAR(50) NOT NULL UNIQUE,
+    processed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_sale_payment_events_pending ON sale_payment_events(id)
+    WHERE processed_at IS NULL;
+
+INSERT INTO sale_payment_events (sale_order_id, payment_order_id)
+SELECT p.sale_order_id, p.order_id
+FROM payments p
+JOIN sale_orders so ON so.id = p.sale_order_id
+WHERE p.payment_for_type = 'sale'
+  AND p.status = 'success'
+  AND so.status = 'pending_payment'
+ON CONFLICT (payment_order_id) DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,44 @@
+-- ============================================================================
+-- Migrasi: auto-reply (away message) seller
+-- ============================================================================
+-- schema.sql sudah berisi tabel, kolom, dan trigger ini untuk database baru. Jalankan file ini
+-- sekali di database yang sudah ada sebelum deploy chat-service versi baru.
+
+BEGIN;
+
+ALTER TABLE conversations
+    ADD COLUMN auto_replied_at TIMESTAMPTZ;
+
+ALTER TABLE messages
+    ADD COLUMN is_auto_reply BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE seller_auto_replies (
+    seller_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    message TEXT NOT NULL DEFAULT '',
+    active_start TIME,
+    active_end TIME,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta',
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT seller_auto_replies_hours CHECK ((active_start IS NULL) = (active_end IS NULL))
+);
+
+-- Auto-reply tidak dihitung sebagai respon pertama seller
+CREATE OR REPLACE FUNCTION update_conversation_last_message()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE conversations
+    SET
+        last_message = NEW.content,
+        last_message_at = NEW.created_at,
+        first_response_at = CASE
+            WHEN first_response_at IS NULL AND seller_id = NEW.sender_id AND NOT NEW.is_auto_reply THEN NEW.created_at
+            ELSE first_response_at
+        END,
+        updated_at = NOW()
+    WHERE id = NEW.conversation_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMIT;
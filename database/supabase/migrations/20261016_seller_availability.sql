@@ -0,0 +1,32 @@
+-- ============================================================================
+-- Migrasi: slot ketersediaan test drive seller
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database yang
+-- sudah ada sebelum deploy booking-service versi baru (REQUIRED_SCHEMA mengecek seller_availability).
+-- UNIQUE NULLS NOT DISTINCT butuh PostgreSQL 15 ke atas.
+
+BEGIN;
+
+-- Slot test drive yang bisa dibooking customer, jam lokal di timezone seller_testdrive_hours.
+-- weekday terisi = slot mingguan berulang (0 = Senin ... 6 = Minggu).
+-- date terisi = pengecualian: is_available = false menutup slot mingguan di tanggal itu
+-- (start_time NULL = tutup seharian), is_available = true menambah slot ekstra
+CREATE TABLE IF NOT EXISTS seller_availability (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    weekday SMALLINT CHECK (weekday BETWEEN 0 AND 6),
+    date DATE,
+    start_time TIME,
+    -- Jumlah test drive aktif yang boleh di slot yang sama
+    capacity INTEGER NOT NULL DEFAULT 1 CHECK (capacity BETWEEN 1 AND 20),
+    is_available BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    CHECK ((weekday IS NULL) <> (date IS NULL)),
+    CHECK (start_time IS NOT NULL OR (date IS NOT NULL AND NOT is_available)),
+    -- Satu baris per slot, jadi id slot tetap sama saat jadwal disimpan ulang
+    UNIQUE NULLS NOT DISTINCT (seller_id, weekday, date, start_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_seller_availability_seller ON seller_availability(seller_id);
+
+COMMIT;
@@ -0,0 +1,20 @@
+-- ============================================================================
+-- Migrasi: blocklist buyer per seller
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database yang
+-- sudah ada sebelum deploy booking-service versi baru (REQUIRED_SCHEMA mengecek seller_buyer_blocks).
+
+BEGIN;
+
+-- Blocklist buyer per seller untuk transaksi (order pembelian & test drive)
+-- Terpisah dari blokir chat: hanya mengatur transaksi, bukan pesan
+CREATE TABLE IF NOT EXISTS seller_buyer_blocks (
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    buyer_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (seller_id, buyer_id),
+    CONSTRAINT seller_buyer_blocks_not_self CHECK (seller_id <> buyer_id)
+);
+
+COMMIT;
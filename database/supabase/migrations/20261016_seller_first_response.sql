@@ -0,0 +1,66 @@
+-- ============================================================================
+-- Migrasi: waktu respon pertama seller dan SLA breach
+-- ============================================================================
+-- schema.sql sudah berisi kolom, trigger, dan view ini untuk database baru. Jalankan file ini
+-- sekali di database yang sudah ada sebelum deploy chat-service dan booking-service versi baru.
+--
+-- Respon lama diisi dari data yang ada: conversation dari pesan pertama seller, sale order yang
+-- sudah lewat pending_confirmation dari confirmed_at/rejected_at. sla_breached_at dibiarkan
+-- kosong, scheduler hanya menandai breach baru.
+
+BEGIN;
+
+ALTER TABLE conversations
+    ADD COLUMN first_response_at TIMESTAMPTZ,
+    ADD COLUMN sla_breached_at TIMESTAMPTZ;
+
+ALTER TABLE sale_orders
+    ADD COLUMN first_response_at TIMESTAMPTZ,
+    ADD COLUMN sla_breached_at TIMESTAMPTZ;
+
+UPDATE conversations c
+SET first_response_at = (
+    SELECT MIN(m.created_at) FROM messages m
+    WHERE m.conversation_id = c.id AND m.sender_id = c.seller_id
+);
+
+UPDATE sale_orders
+SET first_response_at = COALESCE(confirmed_at, rejected_at)
+WHERE status <> 'pending_confirmation';
+
+-- Catat respon pertama seller pada order pending_confirmation (confirm, counter, reject)
+CREATE OR REPLACE FUNCTION record_sale_first_response()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF OLD.status = 'pending_confirmation' AND NEW.first_response_at IS NULL AND (
+        NEW.status IN ('pending_payment', 'rejected')
+        OR NEW.counter_offer_price IS DISTINCT FROM OLD.counter_offer_price
+    ) THEN
+        NEW.first_response_at := NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sale_first_response
+BEFORE UPDATE ON sale_orders
+FOR EACH ROW EXECUTE FUNCTION record_sale_first_response();
+
+-- Rata-rata waktu respon seller (rolling 90 hari) dari chat & sale order
+CREATE VIEW seller_response_stats AS
+SELECT
+    seller_id,
+    COUNT(*) AS response_count,
+    AVG(EXTRACT(EPOCH FROM (first_response_at - created_at)) / 60) AS avg_response_minutes
+FROM (
+    SELECT seller_id, created_at, first_response_at
+    FROM conversations
+    WHERE first_response_at IS NOT NULL AND created_at > NOW() - INTERVAL '90 days'
+    UNION ALL
+    SELECT seller_id, created_at, first_response_at
+    FROM sale_orders
+    WHERE first_response_at IS NOT NULL AND created_at > NOW() - INTERVAL '90 days'
+) responses
+GROUP BY seller_id;
+
+COMMIT;
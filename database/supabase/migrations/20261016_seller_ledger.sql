@@ -0,0 +1,248 @@
+-- ============================================================================
+-- Migrasi: ledger saldo seller + backfill dari transaksi yang sudah ada
+-- ============================================================================
+-- schema.sql sudah berisi tabel, view dan trigger ini untuk database baru. Jalankan file ini sekali
+-- di database yang sudah ada sebelum deploy financial-service versi baru.
+--
+-- Backfill: sale order completed di-credit (net setelah komisi sale yang aktif saat migrasi),
+-- withdrawal yang tidak gagal dan refund penjualan di-debit. seller_balance lalu disamakan dengan
+-- hasil ledger. Migrasi dibatalkan jika ada seller yang saldonya negatif menurut ledger, saldo
+-- tersebut harus direkonsiliasi manual dulu.
+
+BEGIN;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN DEFAULT false;
+
+ALTER TABLE withdrawals
+    ADD COLUMN IF NOT EXISTS processed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS rejection_reason TEXT;
+
+-- Ledger saldo seller: credit dari penjualan selesai, debit dari refund & withdrawal
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    entry_type VARCHAR(10) NOT NULL CHECK (entry_type IN ('credit', 'debit')),
+    source_type VARCHAR(30) NOT NULL CHECK (
+        source_type IN ('sale_completed', 'sale_refund', 'withdrawal', 'withdrawal_rejected', 'payout', 'payout_failed')
+    ),
+    source_id INTEGER NOT NULL,
+    amount NUMERIC(15, 2) NOT NULL CHECK (amount > 0),
+    description TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    -- Satu sumber hanya boleh tercatat sekali (idempotent)
+    UNIQUE (source_type, source_id, entry_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_seller ON ledger_entries(seller_id, created_at DESC);
+
+-- Saldo seller hasil agregasi ledger
+CREATE OR REPLACE VIEW seller_ledger_balance AS
+SELECT
+    seller_id,
+    COALESCE(SUM(CASE WHEN entry_type = 'credit' THEN amount ELSE -amount END), 0) AS balance
+FROM ledger_entries
+GROUP BY seller_id;
+
+CREATE OR REPLACE FUNCTION apply_ledger_entry()
+RETURNS TRIGGER AS $$
+DECLARE
+    signed_amount NUMERIC(15, 2);
+    new_balance NUMERIC(15, 2);
+BEGIN
+    signed_amount := CASE WHEN NEW.entry_type = 'credit' THEN NEW.amount ELSE -NEW.amount END;
+
+    INSERT INTO seller_balance (seller_id, available_balance, total_earned)
+    VALUES (
+        NEW.seller_id,
+        signed_amount,
+        CASE WHEN NEW.source_type = 'sale_completed' THEN NEW.amount ELSE 0 END
+    )
+    ON CONFLICT (seller_id) DO UPDATE
+    SET
+        available_balance = seller_balance.available_balance + EXCLUDED.available_balance,
+        total_earned = seller_balance.total_earned + EXCLUDED.total_earned
+    RETURNING available_balance INTO new_balance;
+
+    IF new_balance < 0 THEN
+        RAISE EXCEPTION 'Saldo seller % tidak boleh negatif (% %: %)',
+            NEW.seller_id, NEW.source_type, NEW.source_id, new_balance
+            USING ERRCODE = 'check_violation';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_ledger_apply ON ledger_entries;
+CREATE TRIGGER trigger_ledger_apply AFTER INSERT ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION apply_ledger_entry();
+
+CREATE OR REPLACE FUNCTION sale_commission(p_final_price NUMERIC)
+RETURNS NUMERIC AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+    setting RECORD;
+BEGIN
+    SELECT commission_percentage, min_commission, max_commission INTO setting
+    FROM commission_settings
+    WHERE transaction_type = 'sale' AND is_active = true
+        AND effective_from <= NOW()
+        AND (effective_until IS NULL OR effective_until > NOW())
+    ORDER BY effective_from DESC
+    LIMIT 1;
+
+    commission := ROUND(p_final_price * COALESCE(setting.commission_percentage, 0) / 100, 2);
+    commission := GREATEST(commission, COALESCE(setting.min_commission, 0));
+    IF setting.max_commission IS NOT NULL THEN
+        commission := LEAST(commission, setting.max_commission);
+    END IF;
+
+    RETURN commission;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION credit_seller_on_sale_completed()
+RETURNS TRIGGER AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'completed' OR OLD.status = 'completed' THEN
+        RETURN NEW;
+    END IF;
+
+    commission := sale_commission(NEW.final_price);
+
+    IF NEW.final_price - commission <= 0 THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (NEW.seller_id, 'credit', 'sale_completed', NEW.id, NEW.final_price - commission,
+            'Penjualan ' || NEW.order_id)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    IF FOUND THEN
+        INSERT INTO transaction_logs (transaction_type, user_id, sale_order_id, amount, commission_amount, net_amount, status, notes)
+        VALUES ('seller_credit', NEW.seller_id, NEW.id, NEW.final_price, commission,
+                NEW.final_price - commission, 'completed', 'Penjualan ' || NEW.order_id);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_sale_completed_credit ON sale_orders;
+CREATE TRIGGER trigger_sale_completed_credit AFTER UPDATE OF status ON sale_orders
+    FOR EACH ROW EXECUTE FUNCTION credit_seller_on_sale_completed();
+
+CREATE OR REPLACE FUNCTION debit_seller_on_sale_refund()
+RETURNS TRIGGER AS $$
+DECLARE
+    credit RECORD;
+    available NUMERIC(15, 2);
+    refund NUMERIC(15, 2);
+    debit NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'refunded' OR OLD.status = 'refunded' OR NEW.sale_order_id IS NULL THEN
+        RETURN NEW;
+    END IF;
+
+    SELECT seller_id, amount INTO credit
+    FROM ledger_entries
+    WHERE source_type = 'sale_completed' AND source_id = NEW.sale_order_id AND entry_type = 'credit';
+
+    IF NOT FOUND THEN
+        RETURN NEW;
+    END IF;
+
+    -- Lock saldo seller agar tidak balapan dengan withdrawal/payout
+    SELECT available_balance INTO available
+    FROM seller_balance
+    WHERE seller_id = credit.seller_id
+    FOR UPDATE;
+
+    refund := LEAST(COALESCE(NEW.refund_amount, NEW.gross_amount), credit.amount);
+    debit := LEAST(refund, GREATEST(COALESCE(available, 0), 0));
+
+    IF debit <= 0 THEN
+        RAISE WARNING 'Refund % tidak bisa didebit dari saldo seller % (saldo habis), selisih Rp %',
+            NEW.order_id, credit.seller_id, refund;
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (credit.seller_id, 'debit', 'sale_refund', NEW.sale_order_id, debit,
+            CASE WHEN debit < refund
+                THEN 'Refund ' || NEW.order_id || ' (saldo kurang Rp ' || (refund - debit) || ')'
+                ELSE 'Refund ' || NEW.order_id
+            END)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_payment_refund_debit ON payments;
+CREATE TRIGGER trigger_payment_refund_debit AFTER UPDATE OF status ON payments
+    FOR EACH ROW EXECUTE FUNCTION debit_seller_on_sale_refund();
+
+-- ----------------------------------------------------------------------------
+-- Backfill (trigger saldo dimatikan, seller_balance dihitung ulang di akhir)
+-- ----------------------------------------------------------------------------
+
+ALTER TABLE ledger_entries DISABLE TRIGGER trigger_ledger_apply;
+
+-- Penjualan yang sudah completed
+INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description, created_at)
+SELECT seller_id, 'credit', 'sale_completed', id, final_price - sale_commission(final_price),
+       'Penjualan ' || order_id, COALESCE(completed_at, updated_at, NOW())
+FROM sale_orders
+WHERE status = 'completed' AND final_price - sale_commission(final_price) > 0
+ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+-- Withdrawal lama sudah langsung mengurangi saldo saat dibuat (kecuali yang gagal)
+INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description, created_at)
+SELECT seller_id, 'debit', 'withdrawal', id, amount, 'Withdrawal request #' || id, COALESCE(requested_at, NOW())
+FROM withdrawals
+WHERE status <> 'failed' AND amount > 0
+ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+-- Refund penjualan yang sudah di-credit
+INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description, created_at)
+SELECT le.seller_id, 'debit', 'sale_refund', p.sale_order_id,
+       LEAST(COALESCE(p.refund_amount, p.gross_amount), le.amount),
+       'Refund ' || p.order_id, COALESCE(p.refunded_at, NOW())
+FROM payments p
+JOIN ledger_entries le
+    ON le.source_type = 'sale_completed' AND le.entry_type = 'credit' AND le.source_id = p.sale_order_id
+WHERE p.status = 'refunded' AND COALESCE(p.refund_amount, p.gross_amount) > 0
+ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+DO $$
+DECLARE
+    negative TEXT;
+BEGIN
+    SELECT string_agg(seller_id || ' (Rp ' || balance || ')', ', ') INTO negative
+    FROM seller_ledger_balance
+    WHERE balance < 0;
+
+    IF negative IS NOT NULL THEN
+        RAISE EXCEPTION 'Saldo ledger negatif untuk seller: %. Rekonsiliasi manual sebelum migrasi', negative;
+    END IF;
+END $$;
+
+INSERT INTO seller_balance (seller_id)
+SELECT seller_id FROM seller_ledger_balance
+ON CONFLICT (seller_id) DO NOTHING;
+
+UPDATE seller_balance sb
+SET
+    available_balance = COALESCE((SELECT balance FROM seller_ledger_balance slb WHERE slb.seller_id = sb.seller_id), 0),
+    total_earned = COALESCE((
+        SELECT SUM(amount) FROM ledger_entries le
+        WHERE le.seller_id = sb.seller_id AND le.source_type = 'sale_completed'
+    ), 0);
+
+ALTER TABLE ledger_entries ENABLE TRIGGER trigger_ledger_apply;
+
+COMMIT;
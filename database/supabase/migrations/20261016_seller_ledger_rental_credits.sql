@@ -0,0 +1,262 @@
+-- ============================================================================
+-- Migrasi: credit ledger dari rental selesai + debit refund biaya sewa
+-- ============================================================================
+-- schema.sql sudah berisi perubahan ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada (setelah 20261016_seller_ledger.sql) sebelum deploy financial-service versi baru.
+--
+-- Backfill: rental yang sudah selesai di-credit (net setelah komisi rental yang aktif saat migrasi)
+-- dan refund biaya sewanya di-debit, lalu seller_balance dihitung ulang dari ledger. Migrasi
+-- dibatalkan jika ada seller yang saldonya negatif menurut ledger.
+
+BEGIN;
+
+ALTER TABLE ledger_entries DROP CONSTRAINT ledger_entries_source_type_check;
+ALTER TABLE ledger_entries ADD CONSTRAINT ledger_entries_source_type_check CHECK (
+    source_type IN (
+        'sale_completed', 'sale_refund', 'rental_completed', 'rental_refund',
+        'withdrawal', 'withdrawal_rejected', 'payout', 'payout_failed'
+    )
+);
+
+CREATE OR REPLACE FUNCTION apply_ledger_entry()
+RETURNS TRIGGER AS $$
+DECLARE
+    signed_amount NUMERIC(15, 2);
+    new_balance NUMERIC(15, 2);
+BEGIN
+    signed_amount := CASE WHEN NEW.entry_type = 'credit' THEN NEW.amount ELSE -NEW.amount END;
+
+    INSERT INTO seller_balance (seller_id, available_balance, total_earned)
+    VALUES (
+        NEW.seller_id,
+        signed_amount,
+        CASE WHEN NEW.source_type IN ('sale_completed', 'rental_completed') THEN NEW.amount ELSE 0 END
+    )
+    ON CONFLICT (seller_id) DO UPDATE
+    SET
+        available_balance = seller_balance.available_balance + EXCLUDED.available_balance,
+        total_earned = seller_balance.total_earned + EXCLUDED.total_earned
+    RETURNING available_balance INTO new_balance;
+
+    IF new_balance < 0 THEN
+        RAISE EXCEPTION 'Saldo seller % tidak boleh negatif (% %: %)',
+            NEW.seller_id, NEW.source_type, NEW.source_id, new_balance
+            USING ERRCODE = 'check_violation';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION platform_commission(p_transaction_type VARCHAR, p_amount NUMERIC)
+RETURNS NUMERIC AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+    setting RECORD;
+BEGIN
+    SELECT commission_percentage, min_commission, max_commission INTO setting
+    FROM commission_settings
+    WHERE transaction_type = p_transaction_type AND is_active = true
+        AND effective_from <= NOW()
+        AND (effective_until IS NULL OR effective_until > NOW())
+    ORDER BY effective_from DESC
+    LIMIT 1;
+
+    commission := ROUND(p_amount * COALESCE(setting.commission_percentage, 0) / 100, 2);
+    commission := GREATEST(commission, COALESCE(setting.min_commission, 0));
+    IF setting.max_commission IS NOT NULL THEN
+        commission := LEAST(commission, setting.max_commission);
+    END IF;
+
+    RETURN commission;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION credit_seller_on_sale_completed()
+RETURNS TRIGGER AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'completed' OR OLD.status = 'completed' THEN
+        RETURN NEW;
+    END IF;
+
+    commission := platform_commission('sale', NEW.final_price);
+
+    IF NEW.final_price - commission <= 0 THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (NEW.seller_id, 'credit', 'sale_completed', NEW.id, NEW.final_price - commission,
+            'Penjualan ' || NEW.order_id)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    IF FOUND THEN
+        INSERT INTO transaction_logs (transaction_type, user_id, sale_order_id, amount, commission_amount, net_amount, status, notes)
+        VALUES ('seller_credit', NEW.seller_id, NEW.id, NEW.final_price, commission,
+                NEW.final_price - commission, 'completed', 'Penjualan ' || NEW.order_id);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP FUNCTION IF EXISTS sale_commission(NUMERIC);
+
+CREATE OR REPLACE FUNCTION credit_seller_on_rental_completed()
+RETURNS TRIGGER AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'selesai' OR OLD.status = 'selesai' THEN
+        RETURN NEW;
+    END IF;
+
+    commission := platform_commission('rental', NEW.total_price);
+
+    IF NEW.total_price - commission <= 0 THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (NEW.seller_id, 'credit', 'rental_completed', NEW.id, NEW.total_price - commission,
+            'Sewa ' || NEW.order_id)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    IF FOUND THEN
+        INSERT INTO transaction_logs (transaction_type, user_id, booking_id, amount, commission_amount, net_amount, status, notes)
+        VALUES ('seller_credit', NEW.seller_id, NEW.id, NEW.total_price, commission,
+                NEW.total_price - commission, 'completed', 'Sewa ' || NEW.order_id);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_rental_completed_credit ON rental_bookings;
+CREATE TRIGGER trigger_rental_completed_credit AFTER UPDATE OF status ON rental_bookings
+    FOR EACH ROW EXECUTE FUNCTION credit_seller_on_rental_completed();
+
+CREATE OR REPLACE FUNCTION debit_seller_on_payment_refund()
+RETURNS TRIGGER AS $$
+DECLARE
+    credit_source VARCHAR;
+    refund_source VARCHAR;
+    credited_id INTEGER;
+    credit RECORD;
+    available NUMERIC(15, 2);
+    refund NUMERIC(15, 2);
+    debit NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'refunded' OR OLD.status = 'refunded' THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.sale_order_id IS NOT NULL THEN
+        credit_source := 'sale_completed';
+        refund_source := 'sale_refund';
+        credited_id := NEW.sale_order_id;
+    ELSIF NEW.rental_booking_id IS NOT NULL AND COALESCE(NEW.payment_for_type, 'rental') = 'rental' THEN
+        credit_source := 'rental_completed';
+        refund_source := 'rental_refund';
+        credited_id := NEW.rental_booking_id;
+    ELSE
+        RETURN NEW;
+    END IF;
+
+    SELECT seller_id, amount INTO credit
+    FROM ledger_entries
+    WHERE source_type = credit_source AND source_id = credited_id AND entry_type = 'credit';
+
+    IF NOT FOUND THEN
+        RETURN NEW;
+    END IF;
+
+    -- Lock saldo seller agar tidak balapan dengan withdrawal/payout
+    SELECT available_balance INTO available
+    FROM seller_balance
+    WHERE seller_id = credit.seller_id
+    FOR UPDATE;
+
+    refund := LEAST(COALESCE(NEW.refund_amount, NEW.gross_amount), credit.amount);
+    debit := LEAST(refund, GREATEST(COALESCE(available, 0), 0));
+
+    IF debit <= 0 THEN
+        RAISE WARNING 'Refund % tidak bisa didebit dari saldo seller % (saldo habis), selisih Rp %',
+            NEW.order_id, credit.seller_id, refund;
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (credit.seller_id, 'debit', refund_source, credited_id, debit,
+            CASE WHEN debit < refund
+                THEN 'Refund ' || NEW.order_id || ' (saldo kurang Rp ' || (refund - debit) || ')'
+                ELSE 'Refund ' || NEW.order_id
+            END)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_payment_refund_debit ON payments;
+CREATE TRIGGER trigger_payment_refund_debit AFTER UPDATE OF status ON payments
+    FOR EACH ROW EXECUTE FUNCTION debit_seller_on_payment_refund();
+
+DROP FUNCTION IF EXISTS debit_seller_on_sale_refund();
+
+-- ----------------------------------------------------------------------------
+-- Backfill (trigger saldo dimatikan, seller_balance dihitung ulang di akhir)
+-- ----------------------------------------------------------------------------
+
+ALTER TABLE ledger_entries DISABLE TRIGGER trigger_ledger_apply;
+
+-- Rental yang sudah selesai
+INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description, created_at)
+SELECT seller_id, 'credit', 'rental_completed', id, total_price - platform_commission('rental', total_price),
+       'Sewa ' || order_id, COALESCE(actual_return_at, updated_at, NOW())
+FROM rental_bookings
+WHERE status = 'selesai' AND total_price - platform_commission('rental', total_price) > 0
+ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+-- Refund biaya sewa yang sudah di-credit
+INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description, created_at)
+SELECT le.seller_id, 'debit', 'rental_refund', p.rental_booking_id,
+       LEAST(COALESCE(p.refund_amount, p.gross_amount), le.amount),
+       'Refund ' || p.order_id, COALESCE(p.refunded_at, NOW())
+FROM payments p
+JOIN ledger_entries le
+    ON le.source_type = 'rental_completed' AND le.entry_type = 'credit' AND le.source_id = p.rental_booking_id
+WHERE p.status = 'refunded' AND COALESCE(p.payment_for_type, 'rental') = 'rental'
+    AND COALESCE(p.refund_amount, p.gross_amount) > 0
+ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+DO $$
+DECLARE
+    negative TEXT;
+BEGIN
+    SELECT string_agg(seller_id || ' (Rp ' || balance || ')', ', ') INTO negative
+    FROM seller_ledger_balance
+    WHERE balance < 0;
+
+    IF negative IS NOT NULL THEN
+        RAISE EXCEPTION 'Saldo ledger negatif untuk seller: %. Rekonsiliasi manual sebelum migrasi', negative;
+    END IF;
+END $$;
+
+INSERT INTO seller_balance (seller_id)
+SELECT seller_id FROM seller_ledger_balance
+ON CONFLICT (seller_id) DO NOTHING;
+
+UPDATE seller_balance sb
+SET
+    available_balance = COALESCE((SELECT balance FROM seller_ledger_balance slb WHERE slb.seller_id = sb.seller_id), 0),
+    total_earned = COALESCE((
+        SELECT SUM(amount) FROM ledger_entries le
+        WHERE le.seller_id = sb.seller_id AND le.source_type IN ('sale_completed', 'rental_completed')
+    ), 0);
+
+ALTER TABLE ledger_entries ENABLE TRIGGER trigger_ledger_apply;
+
+COMMIT;
@@ -0,0 +1,45 @@
+-- ============================================================================
+-- Migrasi: payout terjadwal (payouts, payout_items)
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada (setelah 20261016_seller_ledger.sql, payout_items mereferensikan ledger_entries)
+-- sebelum deploy financial-service versi baru: scheduler payout dan endpoint payout seller/admin
+-- membaca dan menulis kedua tabel ini.
+
+BEGIN;
+
+-- Payout terjadwal: credit ledger yang lewat masa clearing digabung per seller (debit ledger 'payout')
+CREATE TABLE IF NOT EXISTS payouts (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    amount NUMERIC(15, 2) NOT NULL CHECK (amount > 0),
+    item_count INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'processing' CHECK (
+        status IN ('processing', 'paid', 'failed')
+    ),
+    -- Credit yang dibuat sebelum waktu ini sudah lewat masa clearing
+    cleared_until TIMESTAMPTZ NOT NULL,
+    failure_reason TEXT,
+    -- Admin yang menandai paid/failed
+    processed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    paid_at TIMESTAMPTZ,
+    failed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_payouts_seller ON payouts(seller_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_payouts_status ON payouts(status);
+
+-- Credit ledger yang masuk batch payout (satu credit hanya di satu payout aktif)
+CREATE TABLE IF NOT EXISTS payout_items (
+    id SERIAL PRIMARY KEY,
+    payout_id INTEGER NOT NULL REFERENCES payouts(id) ON DELETE CASCADE,
+    ledger_entry_id INTEGER NOT NULL UNIQUE REFERENCES ledger_entries(id) ON DELETE RESTRICT,
+    amount NUMERIC(15, 2) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_payout_items_payout ON payout_items(payout_id);
+
+ALTER TABLE payouts ENABLE ROW LEVEL SECURITY;
+
+COMMIT;
@@ -0,0 +1,46 @@
+-- ============================================================================
+-- Migrasi: staff dealer dan assignment conversation
+-- ============================================================================
+-- schema.sql sudah berisi tabel, kolom, dan trigger ini untuk database baru. Jalankan file ini
+-- sekali di database yang sudah ada sebelum deploy chat-service versi baru, setelah
+-- 20261016_seller_auto_reply.sql (trigger di bawah memakai messages.is_auto_reply).
+
+BEGIN;
+
+ALTER TABLE conversations
+    ADD COLUMN assigned_to INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN assigned_at TIMESTAMPTZ;
+
+CREATE INDEX idx_conversations_assigned ON conversations(assigned_to)
+    WHERE assigned_to IS NOT NULL;
+
+CREATE TABLE seller_staff (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    staff_user_id INTEGER NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT seller_staff_not_self CHECK (seller_id <> staff_user_id)
+);
+
+CREATE INDEX idx_seller_staff_seller ON seller_staff(seller_id);
+
+-- Respon pertama staff yang di-assign juga dihitung untuk SLA
+CREATE OR REPLACE FUNCTION update_conversation_last_message()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE conversations
+    SET
+        last_message = NEW.content,
+        last_message_at = NEW.created_at,
+        first_response_at = CASE
+            WHEN first_response_at IS NULL AND (seller_id = NEW.sender_id OR assigned_to = NEW.sender_id)
+                 AND NOT NEW.is_auto_reply THEN NEW.created_at
+            ELSE first_response_at
+        END,
+        updated_at = NOW()
+    WHERE id = NEW.conversation_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMIT;
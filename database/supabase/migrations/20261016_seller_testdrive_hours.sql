@@ -0,0 +1,24 @@
+-- ============================================================================
+-- Migrasi: jam operasional test drive per seller
+-- ============================================================================
+-- schema.sql sudah berisi tabel dan trigger ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy booking-service versi baru. Seller lama tidak punya baris
+-- dan memakai default global booking-service.
+
+BEGIN;
+
+-- Jam operasional test drive per seller dalam jam lokal timezone seller (IANA)
+CREATE TABLE IF NOT EXISTS seller_testdrive_hours (
+    seller_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    open_time TIME NOT NULL,
+    close_time TIME NOT NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta',
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT seller_testdrive_hours_range CHECK (close_time > open_time)
+);
+
+DROP TRIGGER IF EXISTS trigger_seller_testdrive_hours_updated_at ON seller_testdrive_hours;
+CREATE TRIGGER trigger_seller_testdrive_hours_updated_at BEFORE UPDATE ON seller_testdrive_hours
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
+
+COMMIT;
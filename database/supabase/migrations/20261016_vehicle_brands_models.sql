@@ -0,0 +1,88 @@
+-- ============================================================================
+-- Migrasi: referensi brand/model vehicle
+-- ============================================================================
+-- schema.sql sudah berisi tabel dan seed ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy vehicle-service versi baru: GET /api/vehicles/brands dan filter brand
+-- membaca vehicle_brands/vehicle_models, dan listing lama dinormalisasi ke nama referensi.
+-- Aman dijalankan ulang (seed ON CONFLICT DO NOTHING, UPDATE hanya baris yang belum kanonik).
+
+BEGIN;
+
+-- Master data: Merk mobil
+CREATE TABLE IF NOT EXISTS vehicle_brands (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    logo_url TEXT,
+    is_active BOOLEAN DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Master data: Model mobil
+CREATE TABLE IF NOT EXISTS vehicle_models (
+    id SERIAL PRIMARY KEY,
+    brand_id INTEGER NOT NULL REFERENCES vehicle_brands(id) ON DELETE RESTRICT,
+    name VARCHAR(100) NOT NULL,
+    vehicle_type VARCHAR(50),
+    is_active BOOLEAN DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(brand_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vehicle_models_brand ON vehicle_models(brand_id) WHERE is_active = true;
+
+-- Seed referensi brand & model (pasar Indonesia)
+INSERT INTO vehicle_brands (name) VALUES
+    ('Toyota'), ('Daihatsu'), ('Honda'), ('Mitsubishi'), ('Suzuki'), ('Nissan'),
+    ('Hyundai'), ('Wuling'), ('Mazda'), ('Isuzu'), ('Kia'), ('BMW'), ('Mercedes-Benz')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO vehicle_models (brand_id, name, vehicle_type)
+SELECT b.id, m.name, m.vehicle_type
+FROM (VALUES
+    ('Toyota', 'Avanza', 'MPV'), ('Toyota', 'Veloz', 'MPV'), ('Toyota', 'Innova', 'MPV'),
+    ('Toyota', 'Alphard', 'MPV'), ('Toyota', 'Calya', 'MPV'), ('Toyota', 'Rush', 'SUV'),
+    ('Toyota', 'Fortuner', 'SUV'), ('Toyota', 'Raize', 'SUV'), ('Toyota', 'Yaris', 'Hatchback'),
+    ('Toyota', 'Agya', 'Hatchback'), ('Toyota', 'Camry', 'Sedan'), ('Toyota', 'Hiace', 'Van'),
+    ('Daihatsu', 'Xenia', 'MPV'), ('Daihatsu', 'Sigra', 'MPV'), ('Daihatsu', 'Terios', 'SUV'),
+    ('Daihatsu', 'Rocky', 'SUV'), ('Daihatsu', 'Ayla', 'Hatchback'), ('Daihatsu', 'Gran Max', 'Van'),
+    ('Honda', 'Brio', 'Hatchback'), ('Honda', 'Jazz', 'Hatchback'), ('Honda', 'Mobilio', 'MPV'),
+    ('Honda', 'BR-V', 'SUV'), ('Honda', 'HR-V', 'SUV'), ('Honda', 'CR-V', 'SUV'),
+    ('Honda', 'City', 'Sedan'), ('Honda', 'Civic', 'Sedan'),
+    ('Mitsubishi', 'Xpander', 'MPV'), ('Mitsubishi', 'Pajero Sport', 'SUV'),
+    ('Mitsubishi', 'Outlander', 'SUV'), ('Mitsubishi', 'L300', 'Pickup'),
+    ('Suzuki', 'Ertiga', 'MPV'), ('Suzuki', 'XL7', 'SUV'), ('Suzuki', 'Jimny', 'SUV'),
+    ('Suzuki', 'Baleno', 'Hatchback'), ('Suzuki', 'Carry', 'Pickup'),
+    ('Nissan', 'Livina', 'MPV'), ('Nissan', 'Serena', 'MPV'), ('Nissan', 'X-Trail', 'SUV'),
+    ('Nissan', 'Kicks', 'SUV'),
+    ('Hyundai', 'Stargazer', 'MPV'), ('Hyundai', 'Creta', 'SUV'), ('Hyundai', 'Santa Fe', 'SUV'),
+    ('Hyundai', 'Ioniq 5', 'SUV'),
+    ('Wuling', 'Confero', 'MPV'), ('Wuling', 'Cortez', 'MPV'), ('Wuling', 'Almaz', 'SUV'),
+    ('Wuling', 'Air ev', 'Hatchback'),
+    ('Mazda', 'Mazda2', 'Hatchback'), ('Mazda', 'CX-3', 'SUV'), ('Mazda', 'CX-5', 'SUV'),
+    ('Isuzu', 'Panther', 'MPV'), ('Isuzu', 'MU-X', 'SUV'), ('Isuzu', 'D-Max', 'Pickup'),
+    ('Kia', 'Sonet', 'SUV'), ('Kia', 'Seltos', 'SUV'), ('Kia', 'Carnival', 'MPV'),
+    ('BMW', '3 Series', 'Sedan'), ('BMW', '5 Series', 'Sedan'), ('BMW', 'X1', 'SUV'),
+    ('BMW', 'X5', 'SUV'),
+    ('Mercedes-Benz', 'C-Class', 'Sedan'), ('Mercedes-Benz', 'E-Class', 'Sedan'),
+    ('Mercedes-Benz', 'GLA', 'SUV'), ('Mercedes-Benz', 'GLC', 'SUV')
+) AS m(brand, name, vehicle_type)
+JOIN vehicle_brands b ON b.name = m.brand
+ON CONFLICT (brand_id, name) DO NOTHING;
+
+-- Normalisasi brand/model lama ke nama referensi ("toyota", "TOYOTA" -> "Toyota").
+-- Pencocokan sama dengan service: huruf kecil, tanpa spasi/tanda baca
+UPDATE vehicles v
+SET brand = b.name, updated_at = NOW()
+FROM vehicle_brands b
+WHERE lower(regexp_replace(v.brand, '[^[:alnum:]]', '', 'g')) = lower(regexp_replace(b.name, '[^[:alnum:]]', '', 'g'))
+  AND v.brand <> b.name;
+
+UPDATE vehicles v
+SET model = m.name, updated_at = NOW()
+FROM vehicle_models m
+JOIN vehicle_brands b ON b.id = m.brand_id
+WHERE v.brand = b.name
+  AND lower(regexp_replace(v.model, '[^[:alnum:]]', '', 'g')) = lower(regexp_replace(m.name, '[^[:alnum:]]', '', 'g'))
+  AND v.model <> m.name;
+
+COMMIT;
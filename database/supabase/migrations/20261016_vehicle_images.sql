@@ -0,0 +1,38 @@
+-- ============================================================================
+-- Migrasi: gallery foto vehicle (vehicle_images) + backfill dari vehicles.photos
+-- ============================================================================
+-- schema.sql sudah berisi tabel ini untuk database baru. Jalankan file ini sekali di database
+-- yang sudah ada sebelum deploy vehicle-service versi baru: endpoint foto membaca dan menulis
+-- gallery dari vehicle_images, bukan lagi dari vehicles.photos.
+
+BEGIN;
+
+-- Gallery foto vehicle dengan urutan & cover photo
+-- vehicles.photos tetap disimpan sebagai salinan URL (primary dulu, lalu position) untuk service lain
+CREATE TABLE IF NOT EXISTS vehicle_images (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INTEGER NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    position INTEGER NOT NULL CHECK (position >= 0),
+    is_primary BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    -- Deferred agar reorder bisa menukar position dalam satu transaksi
+    CONSTRAINT vehicle_images_position_unique UNIQUE (vehicle_id, position) DEFERRABLE INITIALLY DEFERRED
+);
+
+-- Maksimal satu primary per vehicle (service selalu menandai satu primary)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vehicle_images_primary ON vehicle_images(vehicle_id) WHERE is_primary = true;
+
+-- vehicles.photos tidak boleh berubah antara dibaca dan disalin (upload/hapus foto versi lama
+-- yang masih jalan menunggu sampai COMMIT)
+LOCK TABLE vehicles, vehicle_images IN SHARE ROW EXCLUSIVE MODE;
+
+-- Backfill dari vehicles.photos: foto pertama jadi primary. Vehicle yang sudah punya gallery
+-- dilewati, jadi file ini aman dijalankan ulang.
+INSERT INTO vehicle_images (vehicle_id, url, position, is_primary)
+SELECT v.id, p.url, (p.ord - 1)::INTEGER, p.ord = 1
+FROM vehicles v
+CROSS JOIN LATERAL jsonb_array_elements_text(v.photos) WITH ORDINALITY AS p(url, ord)
+WHERE NOT EXISTS (SELECT 1 FROM vehicle_images i WHERE i.vehicle_id = v.id);
+
+COMMIT;
@@ -0,0 +1,30 @@
+-- ============================================================================
+-- Migrasi: laporan inspeksi dan grade kondisi vehicle
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan tabel ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy vehicle-service versi baru: query vehicle memetakan
+-- condition_grade ke struct Vehicle dan REQUIRED_SCHEMA mengecek vehicle_inspections.
+
+BEGIN;
+
+-- Grade kondisi dari laporan inspeksi terakhir (A terbaik), NULL jika belum diinspeksi
+ALTER TABLE vehicles
+    ADD COLUMN IF NOT EXISTS condition_grade VARCHAR(1) CHECK (condition_grade IN ('A', 'B', 'C', 'D'));
+
+CREATE INDEX IF NOT EXISTS idx_vehicles_condition_grade ON vehicles(condition_grade) WHERE condition_grade IS NOT NULL;
+
+-- Laporan inspeksi kondisi vehicle (satu laporan aktif per vehicle)
+CREATE TABLE IF NOT EXISTS vehicle_inspections (
+    vehicle_id INTEGER PRIMARY KEY REFERENCES vehicles(id) ON DELETE CASCADE,
+    overall_score INTEGER NOT NULL CHECK (overall_score BETWEEN 0 AND 100),
+    condition_grade VARCHAR(1) NOT NULL CHECK (condition_grade IN ('A', 'B', 'C', 'D')),
+    -- {"mesin": 5, "interior": 4, ...} rating 1-5 per komponen
+    component_ratings JSONB NOT NULL,
+    inspector_name VARCHAR(100) NOT NULL,
+    inspected_at DATE NOT NULL,
+    report_url TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Migrasi: antrean notifikasi penurunan harga dan mute per favorite
+-- ============================================================================
+-- schema.sql sudah berisi kolom dan tabel ini untuk database baru. Jalankan file ini sekali di
+-- database yang sudah ada sebelum deploy vehicle-service versi baru: update harga mengisi
+-- vehicle_price_drops dan scheduler price drop membaca favorites.price_alerts_muted.
+
+BEGIN;
+
+-- Matikan notifikasi penurunan harga untuk favorite ini
+ALTER TABLE favorites
+    ADD COLUMN IF NOT EXISTS price_alerts_muted BOOLEAN NOT NULL DEFAULT false;
+
+-- Antrean notifikasi penurunan harga (debounce): satu baris per vehicle selama window berjalan
+-- previous_price = harga sebelum penurunan pertama, current_price = harga terbaru dalam window
+CREATE TABLE IF NOT EXISTS vehicle_price_drops (
+    vehicle_id INTEGER PRIMARY KEY REFERENCES vehicles(id) ON DELETE CASCADE,
+    previous_price NUMERIC(15, 2) NOT NULL,
+    current_price NUMERIC(15, 2) NOT NULL,
+    first_dropped_at TIMESTAMPTZ DEFAULT NOW(),
+    notify_after TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_vehicle_price_drops_due ON vehicle_price_drops(notify_after);
+
+COMMIT;
@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Migrasi: status vehicle 'unavailable' (bulk availability seller)
+-- ============================================================================
+-- schema.sql sudah berisi CHECK ini untuk database baru. Jalankan file ini sekali di database yang
+-- sudah ada sebelum deploy vehicle-service versi baru: POST /api/vehicles/bulk-availability
+-- mengubah status ke 'unavailable', yang ditolak CHECK lama.
+
+BEGIN;
+
+-- unavailable: di-offline-kan sementara oleh seller (bulk availability)
+ALTER TABLE vehicles DROP CONSTRAINT IF EXISTS vehicles_status_check;
+ALTER TABLE vehicles ADD CONSTRAINT vehicles_status_check CHECK (
+    status IN ('available', 'unavailable', 'pending_sale', 'sold')
+);
+
+COMMIT;
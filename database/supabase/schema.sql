@@ -80,6 +80,48 @@ CREATE TABLE vehicle_models (
     UNIQUE(brand_id, name)
 );
 
+CREATE INDEX idx_vehicle_models_brand ON vehicle_models(brand_id) WHERE is_active = true;
+
+-- Seed referensi brand & model (pasar Indonesia); vehicles.brand/model dinormalisasi ke sini.
+-- Database yang sudah ada di-seed lewat migrations/20261016_vehicle_brands_models.sql
+INSERT INTO vehicle_brands (name) VALUES
+    ('Toyota'), ('Daihatsu'), ('Honda'), ('Mitsubishi'), ('Suzuki'), ('Nissan'),
+    ('Hyundai'), ('Wuling'), ('Mazda'), ('Isuzu'), ('Kia'), ('BMW'), ('Mercedes-Benz')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO vehicle_models (brand_id, name, vehicle_type)
+SELECT b.id, m.name, m.vehicle_type
+FROM (VALUES
+    ('Toyota', 'Avanza', 'MPV'), ('Toyota', 'Veloz', 'MPV'), ('Toyota', 'Innova', 'MPV'),
+    ('Toyota', 'Alphard', 'MPV'), ('Toyota', 'Calya', 'MPV'), ('Toyota', 'Rush', 'SUV'),
+    ('Toyota', 'Fortuner', 'SUV'), ('Toyota', 'Raize', 'SUV'), ('Toyota', 'Yaris', 'Hatchback'),
+    ('Toyota', 'Agya', 'Hatchback'), ('Toyota', 'Camry', 'Sedan'), ('Toyota', 'Hiace', 'Van'),
+    ('Daihatsu', 'Xenia', 'MPV'), ('Daihatsu', 'Sigra', 'MPV'), ('Daihatsu', 'Terios', 'SUV'),
+    ('Daihatsu', 'Rocky', 'SUV'), ('Daihatsu', 'Ayla', 'Hatchback'), ('Daihatsu', 'Gran Max', 'Van'),
+    ('Honda', 'Brio', 'Hatchback'), ('Honda', 'Jazz', 'Hatchback'), ('Honda', 'Mobilio', 'MPV'),
+    ('Honda', 'BR-V', 'SUV'), ('Honda', 'HR-V', 'SUV'), ('Honda', 'CR-V', 'SUV'),
+    ('Honda', 'City', 'Sedan'), ('Honda', 'Civic', 'Sedan'),
+    ('Mitsubishi', 'Xpander', 'MPV'), ('Mitsubishi', 'Pajero Sport', 'SUV'),
+    ('Mitsubishi', 'Outlander', 'SUV'), ('Mitsubishi', 'L300', 'Pickup'),
+    ('Suzuki', 'Ertiga', 'MPV'), ('Suzuki', 'XL7', 'SUV'), ('Suzuki', 'Jimny', 'SUV'),
+    ('Suzuki', 'Baleno', 'Hatchback'), ('Suzuki', 'Carry', 'Pickup'),
+    ('Nissan', 'Livina', 'MPV'), ('Nissan', 'Serena', 'MPV'), ('Nissan', 'X-Trail', 'SUV'),
+    ('Nissan', 'Kicks', 'SUV'),
+    ('Hyundai', 'Stargazer', 'MPV'), ('Hyundai', 'Creta', 'SUV'), ('Hyundai', 'Santa Fe', 'SUV'),
+    ('Hyundai', 'Ioniq 5', 'SUV'),
+    ('Wuling', 'Confero', 'MPV'), ('Wuling', 'Cortez', 'MPV'), ('Wuling', 'Almaz', 'SUV'),
+    ('Wuling', 'Air ev', 'Hatchback'),
+    ('Mazda', 'Mazda2', 'Hatchback'), ('Mazda', 'CX-3', 'SUV'), ('Mazda', 'CX-5', 'SUV'),
+    ('Isuzu', 'Panther', 'MPV'), ('Isuzu', 'MU-X', 'SUV'), ('Isuzu', 'D-Max', 'Pickup'),
+    ('Kia', 'Sonet', 'SUV'), ('Kia', 'Seltos', 'SUV'), ('Kia', 'Carnival', 'MPV'),
+    ('BMW', '3 Series', 'Sedan'), ('BMW', '5 Series', 'Sedan'), ('BMW', 'X1', 'SUV'),
+    ('BMW', 'X5', 'SUV'),
+    ('Mercedes-Benz', 'C-Class', 'Sedan'), ('Mercedes-Benz', 'E-Class', 'Sedan'),
+    ('Mercedes-Benz', 'GLA', 'SUV'), ('Mercedes-Benz', 'GLC', 'SUV')
+) AS m(brand, name, vehicle_type)
+JOIN vehicle_brands b ON b.name = m.brand
+ON CONFLICT (brand_id, name) DO NOTHING;
+
 -- ============================================================================
 -- SECTION 4: USERS & AUTHENTICATION
 -- ============================================================================
@@ -108,6 +150,9 @@ CREATE TABLE users (
     -- User roles (hybrid: bisa jadi customer & seller sekaligus)
     is_seller BOOLEAN DEFAULT false,
 
+    -- Admin platform (approval withdrawal, dll)
+    is_admin BOOLEAN DEFAULT false,
+
     -- Profile data
     address TEXT,
     city VARCHAR(100),
@@ -332,6 +377,33 @@ BEGIN
 END;
 $$;
 
+-- Blacklist satu token, idempotent: JTI yang sudah diblacklist tidak diubah (return false)
+-- Expiry mengikuti umur maksimal refresh token (JWT_REFRESH_TOKEN_EXPIRY default 7 hari)
+CREATE OR REPLACE FUNCTION blacklist_token(
+    p_token_jti TEXT,
+    p_token_type TEXT,
+    p_reason TEXT
+)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path TO public
+AS $$
+DECLARE
+    v_inserted INTEGER;
+BEGIN
+    INSERT INTO jwt_blacklist (token_jti, token_type, reason, expires_at, user_id)
+    SELECT p_token_jti, p_token_type, p_reason, NOW() + INTERVAL '7 days',
+           (SELECT user_id FROM user_sessions
+            WHERE refresh_token = p_token_jti OR access_token_jti = p_token_jti
+            LIMIT 1)
+    ON CONFLICT (token_jti) DO NOTHING;
+
+    GET DIAGNOSTICS v_inserted = ROW_COUNT;
+    RETURN v_inserted > 0;
+END;
+$$;
+
 -- ============================================================================
 -- SECTION 7: VEHICLES
 -- ============================================================================
@@ -360,17 +432,22 @@ CREATE TABLE vehicles (
     has_stnk BOOLEAN DEFAULT false,
     description TEXT,
     rental_terms TEXT,
+    -- Deposit jaminan rental yang ditetapkan seller, disalin ke rental_bookings saat booking
+    deposit_amount NUMERIC(15, 2) NOT NULL DEFAULT 0 CHECK (deposit_amount >= 0),
     city VARCHAR(100) NOT NULL,
     address TEXT NOT NULL,
     latitude NUMERIC,
     longitude NUMERIC,
     area_coverage JSONB,
     photos JSONB NOT NULL,
+    -- unavailable: di-offline-kan sementara oleh seller (bulk availability)
     status VARCHAR(20) DEFAULT 'available' CHECK (
-        status IN ('available', 'pending_sale', 'sold')
+        status IN ('available', 'unavailable', 'pending_sale', 'sold')
     ),
     rating NUMERIC(3, 2) DEFAULT 0.0,
     review_count INTEGER DEFAULT 0,
+    -- Grade kondisi dari laporan inspeksi terakhir (A terbaik), NULL jika belum diinspeksi
+    condition_grade VARCHAR(1) CHECK (condition_grade IN ('A', 'B', 'C', 'D')),
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
@@ -385,6 +462,7 @@ CREATE INDEX idx_vehicles_luxury ON vehicles(is_luxury) WHERE is_luxury = true;
 CREATE INDEX idx_vehicles_brand_model ON vehicles(brand, model);
 CREATE INDEX idx_vehicles_year ON vehicles(year);
 CREATE INDEX idx_vehicles_rating ON vehicles(rating DESC);
+CREATE INDEX idx_vehicles_condition_grade ON vehicles(condition_grade) WHERE condition_grade IS NOT NULL;
 
 -- Full text search index
 CREATE INDEX idx_vehicles_search ON vehicles USING gin(
@@ -396,6 +474,50 @@ CREATE INDEX idx_vehicles_search ON vehicles USING gin(
     )
 );
 
+-- Gallery foto vehicle dengan urutan & cover photo
+-- vehicles.photos tetap disimpan sebagai salinan URL (primary dulu, lalu position) untuk service lain
+CREATE TABLE vehicle_images (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INTEGER NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    position INTEGER NOT NULL CHECK (position >= 0),
+    is_primary BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    -- Deferred agar reorder bisa menukar position dalam satu transaksi
+    CONSTRAINT vehicle_images_position_unique UNIQUE (vehicle_id, position) DEFERRABLE INITIALLY DEFERRED
+);
+
+-- Maksimal satu primary per vehicle (service selalu menandai satu primary)
+CREATE UNIQUE INDEX idx_vehicle_images_primary ON vehicle_images(vehicle_id) WHERE is_primary = true;
+
+-- Database lama: gallery diisi dari vehicles.photos oleh migrations/20261016_vehicle_images.sql
+
+-- Laporan inspeksi kondisi vehicle (satu laporan aktif per vehicle)
+CREATE TABLE vehicle_inspections (
+    vehicle_id INTEGER PRIMARY KEY REFERENCES vehicles(id) ON DELETE CASCADE,
+    overall_score INTEGER NOT NULL CHECK (overall_score BETWEEN 0 AND 100),
+    condition_grade VARCHAR(1) NOT NULL CHECK (condition_grade IN ('A', 'B', 'C', 'D')),
+    -- {"mesin": 5, "interior": 4, ...} rating 1-5 per komponen
+    component_ratings JSONB NOT NULL,
+    inspector_name VARCHAR(100) NOT NULL,
+    inspected_at DATE NOT NULL,
+    report_url TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Antrean notifikasi penurunan harga (debounce): satu baris per vehicle selama window berjalan
+-- previous_price = harga sebelum penurunan pertama, current_price = harga terbaru dalam window
+CREATE TABLE vehicle_price_drops (
+    vehicle_id INTEGER PRIMARY KEY REFERENCES vehicles(id) ON DELETE CASCADE,
+    previous_price NUMERIC(15, 2) NOT NULL,
+    current_price NUMERIC(15, 2) NOT NULL,
+    first_dropped_at TIMESTAMPTZ DEFAULT NOW(),
+    notify_after TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_vehicle_price_drops_due ON vehicle_price_drops(notify_after);
+
 -- ============================================================================
 -- SECTION 8: RENTAL BOOKINGS
 -- ============================================================================
@@ -426,6 +548,13 @@ CREATE TABLE rental_bookings (
     ),
     cancel_reason TEXT,
     cancelled_at TIMESTAMPTZ,
+    -- Deposit jaminan, dibayar terpisah dari biaya sewa (payment_for_type = rental_deposit)
+    deposit_amount NUMERIC(15, 2) NOT NULL DEFAULT 0 CHECK (deposit_amount >= 0),
+    deposit_status VARCHAR(20) NOT NULL DEFAULT 'none' CHECK (
+        deposit_status IN ('none', 'pending', 'held', 'refunded', 'partially_refunded', 'forfeited')
+    ),
+    deposit_refunded_amount NUMERIC(15, 2),
+    deposit_settled_at TIMESTAMPTZ,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW(),
 
@@ -439,6 +568,50 @@ CREATE INDEX idx_rental_seller ON rental_bookings(seller_id);
 CREATE INDEX idx_rental_status ON rental_bookings(status);
 CREATE INDEX idx_rental_dates ON rental_bookings(pickup_date, return_date);
 
+-- Laporan kondisi kendaraan saat rental dikembalikan (diisi seller)
+CREATE TABLE rental_return_reports (
+    id SERIAL PRIMARY KEY,
+    rental_booking_id INTEGER NOT NULL UNIQUE REFERENCES rental_bookings(id) ON DELETE RESTRICT,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    customer_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    condition_notes TEXT NOT NULL,
+    odometer_km INTEGER NOT NULL CHECK (odometer_km >= 0),
+    fuel_level INTEGER NOT NULL CHECK (fuel_level BETWEEN 0 AND 100),
+    -- [{"description": "...", "amount": 350000}]
+    damage_items JSONB NOT NULL DEFAULT '[]',
+    damage_total NUMERIC(15, 2) NOT NULL DEFAULT 0,
+    -- Tagihan kerusakan dibayar customer lewat payment-service (rental_damage)
+    charge_status VARCHAR(20) NOT NULL DEFAULT 'none' CHECK (
+        charge_status IN ('none', 'pending', 'paid')
+    ),
+    charge_paid_at TIMESTAMPTZ,
+    -- Bagian tagihan kerusakan yang dipotong dari deposit saat refund deposit
+    deposit_deducted NUMERIC(15, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_return_report_customer ON rental_return_reports(customer_id);
+
+-- Serah terima kendaraan rental: check-in (paid/akan_datang/berjalan -> berjalan) dan check-out (berjalan/selesai -> selesai)
+CREATE TABLE rental_handovers (
+    id SERIAL PRIMARY KEY,
+    rental_booking_id INTEGER NOT NULL REFERENCES rental_bookings(id) ON DELETE CASCADE,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('checkin', 'checkout')),
+    odometer_km INTEGER NOT NULL CHECK (odometer_km >= 0),
+    fuel_level INTEGER NOT NULL CHECK (fuel_level BETWEEN 0 AND 100),
+    -- ["https://res.cloudinary.com/..."]
+    photos JSONB NOT NULL DEFAULT '[]',
+    notes TEXT,
+    recorded_by INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Denda keterlambatan saat check-out, ditagihkan lewat laporan pengembalian
+    late_fee NUMERIC(15, 2) NOT NULL DEFAULT 0 CHECK (late_fee >= 0),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+
+    CONSTRAINT rental_handover_once UNIQUE (rental_booking_id, kind)
+);
+
 -- ============================================================================
 -- SECTION 9: TEST DRIVE BOOKINGS
 -- ============================================================================
@@ -465,7 +638,20 @@ CREATE TABLE testdrive_bookings (
     cancel_reason TEXT,
     cancelled_at TIMESTAMPTZ,
     created_at TIMESTAMPTZ DEFAULT NOW(),
-    updated_at TIMESTAMPTZ DEFAULT NOW()
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    -- Lokasi pertemuan: showroom seller atau alamat customer
+    location VARCHAR(20) NOT NULL DEFAULT 'showroom' CHECK (
+        location IN ('showroom', 'customer_address')
+    ),
+    address TEXT,
+    lat DOUBLE PRECISION,
+    lng DOUBLE PRECISION,
+    -- Lokasi alternatif dari seller saat reschedule
+    proposed_location JSONB,
+    reminder_sent_at TIMESTAMPTZ,
+    -- Optimistic concurrency: naik setiap transisi status
+    version INTEGER NOT NULL DEFAULT 0,
+    CHECK (location <> 'customer_address' OR address IS NOT NULL)
 );
 
 CREATE INDEX idx_testdrive_vehicle ON testdrive_bookings(vehicle_id);
@@ -473,6 +659,39 @@ CREATE INDEX idx_testdrive_customer ON testdrive_bookings(customer_id);
 CREATE INDEX idx_testdrive_seller ON testdrive_bookings(seller_id);
 CREATE INDEX idx_testdrive_status ON testdrive_bookings(status);
 
+-- Jam operasional test drive per seller dalam jam lokal timezone seller (IANA)
+-- Seller tanpa baris di sini memakai default global booking-service
+CREATE TABLE seller_testdrive_hours (
+    seller_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    open_time TIME NOT NULL,
+    close_time TIME NOT NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta',
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT seller_testdrive_hours_range CHECK (close_time > open_time)
+);
+
+-- Slot test drive yang bisa dibooking customer, jam lokal di timezone seller_testdrive_hours.
+-- weekday terisi = slot mingguan berulang (0 = Senin ... 6 = Minggu).
+-- date terisi = pengecualian: is_available = false menutup slot mingguan di tanggal itu
+-- (start_time NULL = tutup seharian), is_available = true menambah slot ekstra
+CREATE TABLE seller_availability (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    weekday SMALLINT CHECK (weekday BETWEEN 0 AND 6),
+    date DATE,
+    start_time TIME,
+    -- Jumlah test drive aktif yang boleh di slot yang sama
+    capacity INTEGER NOT NULL DEFAULT 1 CHECK (capacity BETWEEN 1 AND 20),
+    is_available BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    CHECK ((weekday IS NULL) <> (date IS NULL)),
+    CHECK (start_time IS NOT NULL OR (date IS NOT NULL AND NOT is_available)),
+    -- Satu baris per slot, jadi id slot tetap sama saat jadwal disimpan ulang
+    UNIQUE NULLS NOT DISTINCT (seller_id, weekday, date, start_time)
+);
+
+CREATE INDEX idx_seller_availability_seller ON seller_availability(seller_id);
+
 -- ============================================================================
 -- SECTION 10: SALE ORDERS (JUAL BELI)
 -- ============================================================================
@@ -484,6 +703,8 @@ CREATE TABLE sale_orders (
     seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
     testdrive_booking_id INTEGER REFERENCES testdrive_bookings(id) ON DELETE SET NULL,
     order_id VARCHAR(50) NOT NULL UNIQUE,
+    -- Kode acak untuk tracking publik tanpa login (bukan order_id yang bisa ditebak)
+    tracking_reference VARCHAR(32) NOT NULL UNIQUE,
     asking_price NUMERIC(15, 2) NOT NULL,
     offer_price NUMERIC(15, 2),
     counter_offer_price NUMERIC(15, 2),
@@ -499,11 +720,14 @@ CREATE TABLE sale_orders (
             'document_processing', 'completed', 'cancelled', 'rejected'
         )
     ),
-    -- Document transfer tracking
-    bpkb_transferred BOOLEAN DEFAULT false,
-    stnk_transferred BOOLEAN DEFAULT false,
-    faktur_transferred BOOLEAN DEFAULT false,
-    pajak_transferred BOOLEAN DEFAULT false,
+    -- Checklist serah terima dokumen: snapshot checklist seller saat order dibuat
+    -- [{key, label, required, transferred}], default BPKB/STNK/faktur/pajak
+    document_checklist JSONB NOT NULL DEFAULT '[
+        {"key": "bpkb", "label": "BPKB (Buku Pemilik Kendaraan Bermotor)", "required": true, "transferred": false},
+        {"key": "stnk", "label": "STNK (Surat Tanda Nomor Kendaraan)", "required": true, "transferred": false},
+        {"key": "faktur", "label": "Faktur kendaraan", "required": true, "transferred": false},
+        {"key": "pajak", "label": "Bukti pajak kendaraan", "required": true, "transferred": false}
+    ]' CHECK (jsonb_typeof(document_checklist) = 'array'),
     created_at TIMESTAMPTZ DEFAULT NOW(),
     confirmed_at TIMESTAMPTZ,
     paid_at TIMESTAMPTZ,
@@ -515,7 +739,14 @@ CREATE TABLE sale_orders (
     reject_reason TEXT,
     rejected_at TIMESTAMPTZ,
     buyer_notes TEXT,
-    seller_notes TEXT
+    seller_notes TEXT,
+    -- Jumlah counter offer seller (dibatasi MAX_COUNTER_OFFER_ROUNDS)
+    counter_round INTEGER NOT NULL DEFAULT 0,
+    -- SLA respon seller
+    first_response_at TIMESTAMPTZ,
+    sla_breached_at TIMESTAMPTZ,
+    -- Optimistic concurrency: naik setiap transisi status
+    version INTEGER NOT NULL DEFAULT 0
 );
 
 -- Index untuk sale order queries
@@ -525,6 +756,42 @@ CREATE INDEX idx_sale_seller ON sale_orders(seller_id);
 CREATE INDEX idx_sale_status ON sale_orders(status);
 CREATE INDEX idx_sale_testdrive ON sale_orders(testdrive_booking_id);
 
+-- Checklist dokumen serah terima per seller untuk order pembelian baru
+-- Tanpa baris di sini, order memakai checklist default BPKB/STNK/faktur/pajak
+CREATE TABLE seller_document_checklists (
+    seller_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- [{key, label, required}]
+    items JSONB NOT NULL CHECK (jsonb_typeof(items) = 'array'),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Nomor invoice monotonic (nextval aman untuk concurrent request)
+CREATE SEQUENCE sale_invoice_number_seq START 1;
+
+-- Invoice (faktur) PDF untuk sale order yang completed
+CREATE TABLE sale_invoices (
+    id SERIAL PRIMARY KEY,
+    sale_order_id INTEGER NOT NULL UNIQUE REFERENCES sale_orders(id) ON DELETE RESTRICT,
+    invoice_number VARCHAR(50) NOT NULL UNIQUE,
+    subtotal NUMERIC(15, 2) NOT NULL,
+    tax_percentage NUMERIC(5, 2) NOT NULL,
+    tax_amount NUMERIC(15, 2) NOT NULL,
+    total_amount NUMERIC(15, 2) NOT NULL,
+    pdf_data BYTEA NOT NULL,
+    issued_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Blocklist buyer per seller untuk transaksi (order pembelian & test drive)
+-- Terpisah dari blokir chat: hanya mengatur transaksi, bukan pesan
+CREATE TABLE seller_buyer_blocks (
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    buyer_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (seller_id, buyer_id),
+    CONSTRAINT seller_buyer_blocks_not_self CHECK (seller_id <> buyer_id)
+);
+
 -- ============================================================================
 -- SECTION 11: PAYMENTS (POLYMORPHIC)
 -- ============================================================================
@@ -545,13 +812,17 @@ CREATE TABLE payments (
     ),
     refund_amount NUMERIC(15, 2),
     refund_reason TEXT,
+    -- Refund ke channel asal: completed (via Midtrans) atau manual_required (mis. virtual account),
+    -- pending selama refund sudah dicatat dan sedang dikirim ke Midtrans
+    refund_status VARCHAR(20) CHECK (refund_status IN ('pending', 'completed', 'manual_required')),
+    refund_reference VARCHAR(100),
     paid_at TIMESTAMPTZ,
     expired_at TIMESTAMPTZ,
     refunded_at TIMESTAMPTZ,
     receipt_pdf_path TEXT,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW(),
-    payment_for_type VARCHAR(20) CHECK (payment_for_type IN ('rental', 'sale'))
+    payment_for_type VARCHAR(20) CHECK (payment_for_type IN ('rental', 'sale', 'rental_damage', 'rental_deposit'))
 );
 
 -- Constraint: must reference exactly one booking type
@@ -565,6 +836,20 @@ CREATE INDEX idx_payment_sale ON payments(sale_order_id);
 CREATE INDEX idx_payment_order ON payments(order_id);
 CREATE INDEX idx_payment_status ON payments(status);
 
+-- Event "sale order lunas" dari payment-service (webhook, resend, rekonsiliasi). Status sale_orders
+-- hanya diubah booking-service: scheduler booking memproses event yang processed_at-nya masih NULL.
+-- Satu event per payment, webhook duplikat tidak menambah event.
+CREATE TABLE sale_payment_events (
+    id BIGSERIAL PRIMARY KEY,
+    sale_order_id INTEGER NOT NULL REFERENCES sale_orders(id) ON DELETE CASCADE,
+    payment_order_id VARCHAR(50) NOT NULL UNIQUE,
+    processed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_sale_payment_events_pending ON sale_payment_events(id)
+    WHERE processed_at IS NULL;
+
 -- ============================================================================
 -- SECTION 12: REVIEWS (POLYMORPHIC)
 -- ============================================================================
@@ -611,33 +896,126 @@ CREATE TABLE conversations (
     vehicle_id INTEGER REFERENCES vehicles(id) ON DELETE SET NULL,
     last_message TEXT,
     last_message_at TIMESTAMPTZ,
+    -- Pengirim last_message, untuk prefix "Anda:" di inbox
+    last_message_sender_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    -- SLA respon seller
+    first_response_at TIMESTAMPTZ,
+    sla_breached_at TIMESTAMPTZ,
+    -- Unread denormalized per participant, diupdate bersama insert message / mark read
+    customer_unread_count INTEGER NOT NULL DEFAULT 0 CHECK (customer_unread_count >= 0),
+    seller_unread_count INTEGER NOT NULL DEFAULT 0 CHECK (seller_unread_count >= 0),
+    -- Auto-purge message (hari), NULL = simpan selamanya
+    retention_days INTEGER CHECK (retention_days IS NULL OR retention_days BETWEEN 1 AND 365),
+    -- Auto-reply seller terakhir, untuk cooldown per conversation
+    auto_replied_at TIMESTAMPTZ,
+    -- Staff dealer yang menangani conversation (seller_staff), NULL = ditangani seller langsung
+    assigned_to INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    assigned_at TIMESTAMPTZ,
+    -- Conversation umum (dibuat tanpa vehicle). Tetap false jika vehicle_id menjadi NULL
+    -- karena vehicle dihapus, agar cleanup vehicle tidak bentrok dengan unique index di bawah
+    is_general BOOLEAN NOT NULL DEFAULT false,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW(),
 
-    -- Prevent duplicate conversations
-    UNIQUE(customer_id, seller_id, vehicle_id)
+    -- Prevent duplicate conversations (NULL vehicle_id dijaga uniq_conversations_general)
+    UNIQUE(customer_id, seller_id, vehicle_id),
+    CHECK (NOT is_general OR vehicle_id IS NULL)
 );
 
 CREATE INDEX idx_conversations_customer ON conversations(customer_id);
 CREATE INDEX idx_conversations_seller ON conversations(seller_id);
 CREATE INDEX idx_conversations_updated ON conversations(updated_at DESC);
+CREATE INDEX idx_conversations_retention ON conversations(id)
+    WHERE retention_days IS NOT NULL;
+CREATE INDEX idx_conversations_assigned ON conversations(assigned_to)
+    WHERE assigned_to IS NOT NULL;
+-- Satu conversation umum per customer-seller, mencegah duplikat saat create bersamaan
+CREATE UNIQUE INDEX uniq_conversations_general ON conversations(customer_id, seller_id)
+    WHERE is_general;
 
 CREATE TABLE messages (
     id SERIAL PRIMARY KEY,
     conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
     sender_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
     content TEXT NOT NULL,
-    message_type VARCHAR(20) DEFAULT 'text' CHECK (message_type IN ('text', 'image')),
+    message_type VARCHAR(20) DEFAULT 'text' CHECK (message_type IN ('text', 'image', 'document', 'audio')),
     media_url TEXT,
     thumbnail_url TEXT,
     is_read BOOLEAN DEFAULT false,
     read_at TIMESTAMPTZ,
+    -- Soft delete: row tetap ada sebagai tombstone agar transcript sengketa utuh
+    is_deleted BOOLEAN NOT NULL DEFAULT false,
+    deleted_at TIMESTAMPTZ,
+    -- Isi asli untuk moderasi admin, hanya terisi jika CHAT_RETAIN_DELETED_CONTENT aktif
+    original_content TEXT,
+    original_media_url TEXT,
+    -- Quote/balasan ke message lain di conversation yang sama
+    reply_to_message_id INTEGER REFERENCES messages(id) ON DELETE SET NULL,
+    -- Root thread jika message adalah balasan thread (thread datar, selalu menunjuk root)
+    thread_root_id INTEGER REFERENCES messages(id) ON DELETE SET NULL,
+    -- Balasan otomatis seller (away message), tidak dihitung sebagai respon SLA
+    is_auto_reply BOOLEAN NOT NULL DEFAULT false,
     created_at TIMESTAMPTZ DEFAULT NOW()
 );
 
 CREATE INDEX idx_messages_conversation ON messages(conversation_id, created_at DESC);
 CREATE INDEX idx_messages_unread ON messages(conversation_id)
     WHERE is_read = false;
+CREATE INDEX idx_messages_thread_root ON messages(thread_root_id, created_at)
+    WHERE thread_root_id IS NOT NULL;
+
+-- Auto-reply (away message) seller: dikirim saat message pertama customer
+-- atau message di luar jam aktif seller
+CREATE TABLE seller_auto_replies (
+    seller_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    message TEXT NOT NULL DEFAULT '',
+    -- Jam aktif lokal, NULL = tanpa jam aktif (hanya message pertama)
+    active_start TIME,
+    active_end TIME,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta',
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT seller_auto_replies_hours CHECK ((active_start IS NULL) = (active_end IS NULL))
+);
+
+-- Organisasi seller (dealer multi-agent): seller adalah pemilik org, staff bisa
+-- menangani conversation yang di-assign ke mereka. Satu user hanya staff di satu org
+CREATE TABLE seller_staff (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    staff_user_id INTEGER NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT seller_staff_not_self CHECK (seller_id <> staff_user_id)
+);
+
+CREATE INDEX idx_seller_staff_seller ON seller_staff(seller_id);
+
+-- Transactional outbox: event real-time chat ditulis bersama message,
+-- lalu dipublish ke NATS oleh relay (at-least-once, retry saat NATS down)
+CREATE TABLE chat_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    subject VARCHAR(255) NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_chat_outbox_pending ON chat_outbox(next_attempt_at, id)
+    WHERE sent_at IS NULL;
+
+-- Delivery webhook inbound email yang sudah diproses (provider bisa retry delivery yang sama).
+-- message_id NULL = delivery sedang diproses
+CREATE TABLE inbound_email_deliveries (
+    provider VARCHAR(20) NOT NULL,
+    delivery_id VARCHAR(255) NOT NULL,
+    message_id INTEGER REFERENCES messages(id) ON DELETE CASCADE,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (provider, delivery_id)
+);
 
 -- ============================================================================
 -- SECTION 14: USER FAVORITES
@@ -647,6 +1025,8 @@ CREATE TABLE favorites (
     id SERIAL PRIMARY KEY,
     customer_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
     vehicle_id INTEGER NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    -- Matikan notifikasi penurunan harga untuk favorite ini
+    price_alerts_muted BOOLEAN NOT NULL DEFAULT false,
     created_at TIMESTAMPTZ DEFAULT NOW(),
 
     UNIQUE(customer_id, vehicle_id)
@@ -680,7 +1060,10 @@ CREATE TABLE withdrawals (
     ),
     requested_at TIMESTAMPTZ DEFAULT NOW(),
     processed_at TIMESTAMPTZ,
-    completed_at TIMESTAMPTZ
+    completed_at TIMESTAMPTZ,
+    -- Admin yang approve/reject withdrawal
+    processed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    rejection_reason TEXT
 );
 
 CREATE INDEX idx_withdrawals_seller ON withdrawals(seller_id);
@@ -708,6 +1091,67 @@ CREATE TABLE transaction_logs (
 CREATE INDEX idx_transactions_user ON transaction_logs(user_id);
 CREATE INDEX idx_transactions_type ON transaction_logs(transaction_type);
 
+-- Ledger saldo seller: credit dari penjualan & sewa selesai, debit dari refund & withdrawal
+CREATE TABLE ledger_entries (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    entry_type VARCHAR(10) NOT NULL CHECK (entry_type IN ('credit', 'debit')),
+    source_type VARCHAR(30) NOT NULL CHECK (
+        source_type IN (
+            'sale_completed', 'sale_refund', 'rental_completed', 'rental_refund',
+            'withdrawal', 'withdrawal_rejected', 'payout', 'payout_failed'
+        )
+    ),
+    source_id INTEGER NOT NULL,
+    amount NUMERIC(15, 2) NOT NULL CHECK (amount > 0),
+    description TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    -- Satu sumber hanya boleh tercatat sekali (idempotent)
+    UNIQUE (source_type, source_id, entry_type)
+);
+
+CREATE INDEX idx_ledger_seller ON ledger_entries(seller_id, created_at DESC);
+
+-- Saldo seller hasil agregasi ledger
+CREATE VIEW seller_ledger_balance AS
+SELECT
+    seller_id,
+    COALESCE(SUM(CASE WHEN entry_type = 'credit' THEN amount ELSE -amount END), 0) AS balance
+FROM ledger_entries
+GROUP BY seller_id;
+
+-- Payout terjadwal: credit ledger yang lewat masa clearing digabung per seller (debit ledger 'payout')
+CREATE TABLE payouts (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    amount NUMERIC(15, 2) NOT NULL CHECK (amount > 0),
+    item_count INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'processing' CHECK (
+        status IN ('processing', 'paid', 'failed')
+    ),
+    -- Credit yang dibuat sebelum waktu ini sudah lewat masa clearing
+    cleared_until TIMESTAMPTZ NOT NULL,
+    failure_reason TEXT,
+    -- Admin yang menandai paid/failed
+    processed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    paid_at TIMESTAMPTZ,
+    failed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_payouts_seller ON payouts(seller_id, created_at DESC);
+CREATE INDEX idx_payouts_status ON payouts(status);
+
+-- Credit ledger yang masuk batch payout (satu credit hanya di satu payout aktif)
+CREATE TABLE payout_items (
+    id SERIAL PRIMARY KEY,
+    payout_id INTEGER NOT NULL REFERENCES payouts(id) ON DELETE CASCADE,
+    ledger_entry_id INTEGER NOT NULL UNIQUE REFERENCES ledger_entries(id) ON DELETE RESTRICT,
+    amount NUMERIC(15, 2) NOT NULL
+);
+
+CREATE INDEX idx_payout_items_payout ON payout_items(payout_id);
+
 CREATE TABLE commission_settings (
     id SERIAL PRIMARY KEY,
     transaction_type VARCHAR(20) NOT NULL CHECK (transaction_type IN ('rental', 'sale')),
@@ -744,11 +1188,85 @@ CREATE TABLE notifications (
     related_type VARCHAR(50),
     is_read BOOLEAN DEFAULT false,
     read_at TIMESTAMPTZ,
+    -- Dispatch push oleh notification-service (NULL = belum di-dispatch), quiet hours dievaluasi saat dispatch
+    dispatched_at TIMESTAMPTZ,
+    push_status VARCHAR(20) CHECK (push_status IN ('sent', 'failed', 'skipped', 'suppressed', 'digest')),
+    -- Push yang gagal dicoba ulang dengan backoff; next_push_at = lease dispatcher yang sedang
+    -- mengirim atau jadwal retry berikutnya (NULL = siap diambil)
+    push_attempts INTEGER NOT NULL DEFAULT 0,
+    next_push_at TIMESTAMPTZ,
     created_at TIMESTAMPTZ DEFAULT NOW()
 );
 
 CREATE INDEX idx_notifications_user ON notifications(user_id, is_read);
 CREATE INDEX idx_notifications_type ON notifications(type);
+CREATE INDEX idx_notifications_undispatched ON notifications(id) WHERE dispatched_at IS NULL;
+
+-- Preferensi notifikasi per user: quiet hours dalam jam lokal timezone user (IANA)
+-- Selama quiet hours push/SMS ditahan, notifikasi in-app tetap dibuat
+CREATE TABLE notification_preferences (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta',
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    -- Kirim ringkasan notifikasi yang ditahan setelah quiet hours selesai
+    quiet_hours_digest BOOLEAN NOT NULL DEFAULT true,
+    -- Tipe notifikasi mode digest (dirangkum berkala), tipe lain dikirim langsung
+    digest_types TEXT[] NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    CONSTRAINT notification_quiet_hours_pair CHECK ((quiet_hours_start IS NULL) = (quiet_hours_end IS NULL))
+);
+
+-- Antrean notifikasi mode digest, dikosongkan scheduler notification-service
+CREATE TABLE notification_digest_events (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    type VARCHAR(50) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    message TEXT NOT NULL,
+    related_id INTEGER,
+    related_type VARCHAR(50),
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_notification_digest_events_user ON notification_digest_events(user_id, created_at);
+
+-- Outbound webhook: endpoint back-office dealer milik seller
+CREATE TABLE outbound_webhooks (
+    id SERIAL PRIMARY KEY,
+    seller_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- Shared secret HMAC, bisa di-rotate oleh seller
+    secret VARCHAR(100) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    secret_rotated_at TIMESTAMPTZ,
+    disabled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_outbound_webhooks_seller ON outbound_webhooks(seller_id, is_active);
+
+-- Antrian + log delivery webhook (retry dengan backoff sampai max attempts)
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES outbound_webhooks(id) ON DELETE CASCADE,
+    event_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (
+        status IN ('pending', 'delivered', 'failed')
+    ),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_deliveries_pending ON webhook_deliveries(next_attempt_at, id)
+    WHERE status = 'pending';
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at DESC);
 
 -- ============================================================================
 -- SECTION 17: RATE LIMITING & AUDIT
@@ -796,7 +1314,7 @@ CREATE TABLE audit_logs (
     created_at TIMESTAMPTZ DEFAULT NOW()
 );
 
-CREATE INDEX idx_audit_user ON audit_logs(user_id);
+CREATE INDEX idx_audit_user_created ON audit_logs(user_id, created_at DESC);
 CREATE INDEX idx_audit_action ON audit_logs(action);
 CREATE INDEX idx_audit_entity ON audit_logs(entity_type, entity_id);
 CREATE INDEX idx_audit_created ON audit_logs(created_at DESC);
@@ -840,6 +1358,9 @@ CREATE TRIGGER trigger_rental_updated_at BEFORE UPDATE ON rental_bookings
 CREATE TRIGGER trigger_testdrive_updated_at BEFORE UPDATE ON testdrive_bookings
     FOR EACH ROW EXECUTE FUNCTION update_updated_at();
 
+CREATE TRIGGER trigger_seller_testdrive_hours_updated_at BEFORE UPDATE ON seller_testdrive_hours
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
+
 CREATE TRIGGER trigger_sale_updated_at BEFORE UPDATE ON sale_orders
     FOR EACH ROW EXECUTE FUNCTION update_updated_at();
 
@@ -849,12 +1370,39 @@ CREATE TRIGGER trigger_payments_updated_at BEFORE UPDATE ON payments
 CREATE TRIGGER trigger_reviews_updated_at BEFORE UPDATE ON reviews
     FOR EACH ROW EXECUTE FUNCTION update_updated_at();
 
+CREATE TRIGGER trigger_outbound_webhooks_updated_at BEFORE UPDATE ON outbound_webhooks
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
+
 CREATE TRIGGER trigger_conversations_updated_at BEFORE UPDATE ON conversations
     FOR EACH ROW EXECUTE FUNCTION update_updated_at();
 
 CREATE TRIGGER trigger_seller_balance_updated_at BEFORE UPDATE ON seller_balance
     FOR EACH ROW EXECUTE FUNCTION update_updated_at();
 
+CREATE TRIGGER trigger_notification_preferences_updated_at BEFORE UPDATE ON notification_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
+
+-- Notifikasi bertipe mode digest tetap dibuat (in-app), push-nya dialihkan ke antrean digest
+-- (service pengirim tidak perlu tahu)
+CREATE OR REPLACE FUNCTION divert_digest_notification()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.type <> 'digest' AND EXISTS (
+        SELECT 1 FROM notification_preferences
+        WHERE user_id = NEW.user_id AND NEW.type = ANY(digest_types)
+    ) THEN
+        INSERT INTO notification_digest_events (user_id, type, title, message, related_id, related_type, created_at)
+        VALUES (NEW.user_id, NEW.type, NEW.title, NEW.message, NEW.related_id, NEW.related_type, COALESCE(NEW.created_at, NOW()));
+        NEW.dispatched_at := NOW();
+        NEW.push_status := 'digest';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_notifications_digest BEFORE INSERT ON notifications
+    FOR EACH ROW EXECUTE FUNCTION divert_digest_notification();
+
 -- Vehicle rating calculation trigger
 CREATE OR REPLACE FUNCTION update_vehicle_rating()
 RETURNS TRIGGER AS $$
@@ -871,6 +1419,298 @@ $$ LANGUAGE plpgsql;
 CREATE TRIGGER trigger_vehicle_rating_update AFTER INSERT OR UPDATE ON reviews
     FOR EACH ROW EXECUTE FUNCTION update_vehicle_rating();
 
+-- Sinkronisasi seller_balance dari setiap ledger entry
+-- Upsert mengunci row seller_balance, jadi debit paralel dicek berurutan. Debit yang membuat
+-- saldo negatif ditolak (seluruh transaksi pemanggil di-rollback).
+CREATE OR REPLACE FUNCTION apply_ledger_entry()
+RETURNS TRIGGER AS $$
+DECLARE
+    signed_amount NUMERIC(15, 2);
+    new_balance NUMERIC(15, 2);
+BEGIN
+    signed_amount := CASE WHEN NEW.entry_type = 'credit' THEN NEW.amount ELSE -NEW.amount END;
+
+    INSERT INTO seller_balance (seller_id, available_balance, total_earned)
+    VALUES (
+        NEW.seller_id,
+        signed_amount,
+        CASE WHEN NEW.source_type IN ('sale_completed', 'rental_completed') THEN NEW.amount ELSE 0 END
+    )
+    ON CONFLICT (seller_id) DO UPDATE
+    SET
+        available_balance = seller_balance.available_balance + EXCLUDED.available_balance,
+        total_earned = seller_balance.total_earned + EXCLUDED.total_earned
+    RETURNING available_balance INTO new_balance;
+
+    IF new_balance < 0 THEN
+        RAISE EXCEPTION 'Saldo seller % tidak boleh negatif (% %: %)',
+            NEW.seller_id, NEW.source_type, NEW.source_id, new_balance
+            USING ERRCODE = 'check_violation';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_ledger_apply AFTER INSERT ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION apply_ledger_entry();
+
+-- Komisi platform sesuai commission_settings yang aktif untuk tipe transaksi ('sale' / 'rental')
+CREATE OR REPLACE FUNCTION platform_commission(p_transaction_type VARCHAR, p_amount NUMERIC)
+RETURNS NUMERIC AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+    setting RECORD;
+BEGIN
+    SELECT commission_percentage, min_commission, max_commission INTO setting
+    FROM commission_settings
+    WHERE transaction_type = p_transaction_type AND is_active = true
+        AND effective_from <= NOW()
+        AND (effective_until IS NULL OR effective_until > NOW())
+    ORDER BY effective_from DESC
+    LIMIT 1;
+
+    commission := ROUND(p_amount * COALESCE(setting.commission_percentage, 0) / 100, 2);
+    commission := GREATEST(commission, COALESCE(setting.min_commission, 0));
+    IF setting.max_commission IS NOT NULL THEN
+        commission := LEAST(commission, setting.max_commission);
+    END IF;
+
+    RETURN commission;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Credit seller saat sale order completed (net setelah komisi)
+CREATE OR REPLACE FUNCTION credit_seller_on_sale_completed()
+RETURNS TRIGGER AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'completed' OR OLD.status = 'completed' THEN
+        RETURN NEW;
+    END IF;
+
+    commission := platform_commission('sale', NEW.final_price);
+
+    IF NEW.final_price - commission <= 0 THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (NEW.seller_id, 'credit', 'sale_completed', NEW.id, NEW.final_price - commission,
+            'Penjualan ' || NEW.order_id)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    IF FOUND THEN
+        INSERT INTO transaction_logs (transaction_type, user_id, sale_order_id, amount, commission_amount, net_amount, status, notes)
+        VALUES ('seller_credit', NEW.seller_id, NEW.id, NEW.final_price, commission,
+                NEW.final_price - commission, 'completed', 'Penjualan ' || NEW.order_id);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sale_completed_credit AFTER UPDATE OF status ON sale_orders
+    FOR EACH ROW EXECUTE FUNCTION credit_seller_on_sale_completed();
+
+-- Credit seller saat rental booking selesai (biaya sewa net setelah komisi, tanpa deposit)
+CREATE OR REPLACE FUNCTION credit_seller_on_rental_completed()
+RETURNS TRIGGER AS $$
+DECLARE
+    commission NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'selesai' OR OLD.status = 'selesai' THEN
+        RETURN NEW;
+    END IF;
+
+    commission := platform_commission('rental', NEW.total_price);
+
+    IF NEW.total_price - commission <= 0 THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (NEW.seller_id, 'credit', 'rental_completed', NEW.id, NEW.total_price - commission,
+            'Sewa ' || NEW.order_id)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    IF FOUND THEN
+        INSERT INTO transaction_logs (transaction_type, user_id, booking_id, amount, commission_amount, net_amount, status, notes)
+        VALUES ('seller_credit', NEW.seller_id, NEW.id, NEW.total_price, commission,
+                NEW.total_price - commission, 'completed', 'Sewa ' || NEW.order_id);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_rental_completed_credit AFTER UPDATE OF status ON rental_bookings
+    FOR EACH ROW EXECUTE FUNCTION credit_seller_on_rental_completed();
+
+-- Debit seller saat payment sale order / biaya sewa di-refund setelah saldo di-credit
+-- (refund deposit dan denda kerusakan tidak menyentuh ledger). Debit dipotong ke saldo yang masih tersedia (dana yang sudah ditarik seller tidak bisa
+-- didebit lagi), selisihnya dicatat di deskripsi ledger untuk ditagih manual.
+CREATE OR REPLACE FUNCTION debit_seller_on_payment_refund()
+RETURNS TRIGGER AS $$
+DECLARE
+    credit_source VARCHAR;
+    refund_source VARCHAR;
+    credited_id INTEGER;
+    credit RECORD;
+    available NUMERIC(15, 2);
+    refund NUMERIC(15, 2);
+    debit NUMERIC(15, 2);
+BEGIN
+    IF NEW.status <> 'refunded' OR OLD.status = 'refunded' THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.sale_order_id IS NOT NULL THEN
+        credit_source := 'sale_completed';
+        refund_source := 'sale_refund';
+        credited_id := NEW.sale_order_id;
+    ELSIF NEW.rental_booking_id IS NOT NULL AND COALESCE(NEW.payment_for_type, 'rental') = 'rental' THEN
+        credit_source := 'rental_completed';
+        refund_source := 'rental_refund';
+        credited_id := NEW.rental_booking_id;
+    ELSE
+        RETURN NEW;
+    END IF;
+
+    SELECT seller_id, amount INTO credit
+    FROM ledger_entries
+    WHERE source_type = credit_source AND source_id = credited_id AND entry_type = 'credit';
+
+    IF NOT FOUND THEN
+        RETURN NEW;
+    END IF;
+
+    -- Lock saldo seller agar tidak balapan dengan withdrawal/payout
+    SELECT available_balance INTO available
+    FROM seller_balance
+    WHERE seller_id = credit.seller_id
+    FOR UPDATE;
+
+    refund := LEAST(COALESCE(NEW.refund_amount, NEW.gross_amount), credit.amount);
+    debit := LEAST(refund, GREATEST(COALESCE(available, 0), 0));
+
+    IF debit <= 0 THEN
+        RAISE WARNING 'Refund % tidak bisa didebit dari saldo seller % (saldo habis), selisih Rp %',
+            NEW.order_id, credit.seller_id, refund;
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+    VALUES (credit.seller_id, 'debit', refund_source, credited_id, debit,
+            CASE WHEN debit < refund
+                THEN 'Refund ' || NEW.order_id || ' (saldo kurang Rp ' || (refund - debit) || ')'
+                ELSE 'Refund ' || NEW.order_id
+            END)
+    ON CONFLICT (source_type, source_id, entry_type) DO NOTHING;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_payment_refund_debit AFTER UPDATE OF status ON payments
+    FOR EACH ROW EXECUTE FUNCTION debit_seller_on_payment_refund();
+
+-- Antrikan event ke semua webhook aktif milik seller
+CREATE OR REPLACE FUNCTION enqueue_seller_webhook(p_seller_id INTEGER, p_event VARCHAR, p_data JSONB)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO webhook_deliveries (webhook_id, event_type, payload)
+    SELECT id, p_event, jsonb_build_object('event', p_event, 'data', p_data, 'occurred_at', NOW())
+    FROM outbound_webhooks
+    WHERE seller_id = p_seller_id AND is_active = true;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Event order (sale/rental) dan test drive: dibuat atau status berubah
+CREATE OR REPLACE FUNCTION webhook_on_booking_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    event_prefix VARCHAR;
+BEGIN
+    IF TG_OP = 'UPDATE' AND NEW.status IS NOT DISTINCT FROM OLD.status THEN
+        RETURN NEW;
+    END IF;
+
+    event_prefix := CASE TG_TABLE_NAME
+        WHEN 'sale_orders' THEN 'sale_order'
+        WHEN 'rental_bookings' THEN 'rental_booking'
+        ELSE 'testdrive'
+    END;
+
+    -- Hanya field non-sensitif (tanpa KTP/kontak customer)
+    PERFORM enqueue_seller_webhook(
+        NEW.seller_id,
+        event_prefix || CASE WHEN TG_OP = 'INSERT' THEN '.created' ELSE '.status_changed' END,
+        jsonb_build_object(
+            'id', NEW.id,
+            'order_id', to_jsonb(NEW)->>'order_id',
+            'vehicle_id', NEW.vehicle_id,
+            'status', NEW.status,
+            'previous_status', CASE WHEN TG_OP = 'UPDATE' THEN OLD.status END
+        )
+    );
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sale_webhook AFTER INSERT OR UPDATE OF status ON sale_orders
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_booking_event();
+
+CREATE TRIGGER trigger_rental_webhook AFTER INSERT OR UPDATE OF status ON rental_bookings
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_booking_event();
+
+CREATE TRIGGER trigger_testdrive_webhook AFTER INSERT OR UPDATE OF status ON testdrive_bookings
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_booking_event();
+
+-- Event payment untuk seller pemilik order
+CREATE OR REPLACE FUNCTION webhook_on_payment_status()
+RETURNS TRIGGER AS $$
+DECLARE
+    order_seller_id INTEGER;
+BEGIN
+    IF NEW.status IS NOT DISTINCT FROM OLD.status THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.sale_order_id IS NOT NULL THEN
+        SELECT seller_id INTO order_seller_id FROM sale_orders WHERE id = NEW.sale_order_id;
+    ELSE
+        SELECT seller_id INTO order_seller_id FROM rental_bookings WHERE id = NEW.rental_booking_id;
+    END IF;
+
+    IF order_seller_id IS NULL THEN
+        RETURN NEW;
+    END IF;
+
+    PERFORM enqueue_seller_webhook(
+        order_seller_id,
+        'payment.status_changed',
+        jsonb_build_object(
+            'id', NEW.id,
+            'order_id', NEW.order_id,
+            'payment_for_type', NEW.payment_for_type,
+            'sale_order_id', NEW.sale_order_id,
+            'rental_booking_id', NEW.rental_booking_id,
+            'gross_amount', NEW.gross_amount,
+            'status', NEW.status,
+            'previous_status', OLD.status
+        )
+    );
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_payment_webhook AFTER UPDATE OF status ON payments
+    FOR EACH ROW EXECUTE FUNCTION webhook_on_payment_status();
+
 -- ============================================================================
 -- SECTION 9: ROW LEVEL SECURITY (RLS)
 -- ============================================================================
@@ -879,6 +1719,7 @@ CREATE TRIGGER trigger_vehicle_rating_update AFTER INSERT OR UPDATE ON reviews
 ALTER TABLE users ENABLE ROW LEVEL SECURITY;
 ALTER TABLE vehicles ENABLE ROW LEVEL SECURITY;
 ALTER TABLE rental_bookings ENABLE ROW LEVEL SECURITY;
+ALTER TABLE rental_return_reports ENABLE ROW LEVEL SECURITY;
 ALTER TABLE testdrive_bookings ENABLE ROW LEVEL SECURITY;
 ALTER TABLE sale_orders ENABLE ROW LEVEL SECURITY;
 ALTER TABLE payments ENABLE ROW LEVEL SECURITY;
@@ -888,6 +1729,7 @@ ALTER TABLE messages ENABLE ROW LEVEL SECURITY;
 ALTER TABLE favorites ENABLE ROW LEVEL SECURITY;
 ALTER TABLE seller_balance ENABLE ROW LEVEL SECURITY;
 ALTER TABLE withdrawals ENABLE ROW LEVEL SECURITY;
+ALTER TABLE payouts ENABLE ROW LEVEL SECURITY;
 ALTER TABLE notifications ENABLE ROW LEVEL SECURITY;
 
 -- Drop existing policies jika ada
@@ -980,6 +1822,12 @@ BEGIN
     SET
         last_message = NEW.content,
         last_message_at = NEW.created_at,
+        -- Pesan pertama dari seller/staff yang di-assign = waktu respon pertama (auto-reply tidak dihitung)
+        first_response_at = CASE
+            WHEN first_response_at IS NULL AND (seller_id = NEW.sender_id OR assigned_to = NEW.sender_id)
+                 AND NOT NEW.is_auto_reply THEN NEW.created_at
+            ELSE first_response_at
+        END,
         updated_at = NOW()
     WHERE id = NEW.conversation_id;
     RETURN NEW;
@@ -990,6 +1838,41 @@ CREATE TRIGGER trigger_conversation_last_message
 AFTER INSERT ON messages
 FOR EACH ROW EXECUTE FUNCTION update_conversation_last_message();
 
+-- Catat respon pertama seller pada order pending_confirmation (confirm, counter, reject)
+CREATE OR REPLACE FUNCTION record_sale_first_response()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF OLD.status = 'pending_confirmation' AND NEW.first_response_at IS NULL AND (
+        NEW.status IN ('pending_payment', 'rejected')
+        OR NEW.counter_offer_price IS DISTINCT FROM OLD.counter_offer_price
+    ) THEN
+        NEW.first_response_at := NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sale_first_response
+BEFORE UPDATE ON sale_orders
+FOR EACH ROW EXECUTE FUNCTION record_sale_first_response();
+
+-- Rata-rata waktu respon seller (rolling 90 hari) dari chat & sale order
+CREATE VIEW seller_response_stats AS
+SELECT
+    seller_id,
+    COUNT(*) AS response_count,
+    AVG(EXTRACT(EPOCH FROM (first_response_at - created_at)) / 60) AS avg_response_minutes
+FROM (
+    SELECT seller_id, created_at, first_response_at
+    FROM conversations
+    WHERE first_response_at IS NOT NULL AND created_at > NOW() - INTERVAL '90 days'
+    UNION ALL
+    SELECT seller_id, created_at, first_response_at
+    FROM sale_orders
+    WHERE first_response_at IS NOT NULL AND created_at > NOW() - INTERVAL '90 days'
+) responses
+GROUP BY seller_id;
+
 -- ============================================================================
 -- END OF SCHEMA
 -- ============================================================================
@@ -8,7 +8,22 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::str::FromStr;
 use crate::utils::email::EmailConfig;
+use crate::utils::otp_channel::{self, OtpChannel, SmsConfig};
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
 use crate::middleware::rate_limit::AuthRateLimiter;
+use shared::auth::JwtConfig;
+use shared::utils::bind_addr;
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("users", &["id", "email", "password_hash", "email_verified", "is_active", "otp_blocked_until"]),
+    ("user_sessions", &["id", "user_id", "refresh_token", "access_token_jti", "is_active", "expires_at"]),
+    ("login_otps", &["id", "user_id", "otp_hash", "attempt_count", "blocked_until"]),
+    ("email_verifications", &["id", "user_id", "token", "sent_count", "last_sent_at"]),
+    ("jwt_blacklist", &["token_jti", "token_type", "expires_at", "user_id"]),
+    ("audit_logs", &["id", "user_id", "action", "entity_type", "new_values"]),
+];
 
 // Konfigurasi utama aplikasi yang di-load dari environment variables
 #[derive(Debug, Clone)]
@@ -16,12 +31,17 @@ pub struct AppConfig {
     pub database_url: String,
     pub redis_url: String,
     pub jwt_secret: String,
+    pub jwt: JwtConfig,
     pub jwt_access_expiry: i64,
     pub jwt_refresh_expiry: i64,
     pub server_host: String,
     pub server_port: u16,
     pub environment: String,
     pub email_config: EmailConfig,
+    // Urutan channel OTP login, channel berikutnya dipakai jika sebelumnya gagal
+    pub otp_channels: Vec<OtpChannel>,
+    pub sms_config: Option<SmsConfig>,
+    pub chat_service_url: Option<String>,
 }
 
 impl AppConfig {
@@ -41,6 +61,9 @@ impl AppConfig {
             return Err("JWT_SECRET masih menggunakan default value! Ganti dengan value yang aman untuk production".to_string());
         }
 
+        // Issuer/audience opsional (JWT_ISSUER/JWT_AUDIENCE), ikut ditandatangani di token baru
+        let jwt = JwtConfig::from_env(jwt_secret.clone());
+
         let jwt_access_expiry = env::var("JWT_ACCESS_TOKEN_EXPIRY")
             .ok()
             .and_then(|s| s.parse().ok())
@@ -51,29 +74,47 @@ impl AppConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(604800);
 
-        let server_host = env::var("AUTH_SERVICE_HOST")
-            .unwrap_or_else(|_| "0.0.0.0".to_string());
+        // Fail fast jika kombinasi expiry menghasilkan session yang rusak
+        crate::utils::jwt::validate_expiry_config(jwt_access_expiry, jwt_refresh_expiry)?;
 
-        let server_port = env::var("AUTH_SERVICE_PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3001);
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("AUTH_SERVICE_HOST", Some(bind_addr::DEFAULT_HOST))?;
+        let server_port = bind_addr::port_from_env("AUTH_SERVICE_PORT", Some(3001))?;
+        bind_addr::resolve("AUTH_SERVICE_HOST", &server_host, server_port)?;
 
         let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
 
         let email_config = EmailConfig::from_env()
             .map_err(|e| format!("Email config error: {}", e))?;
 
+        let otp_channels = otp_channel::parse_channels(
+            &env::var("OTP_CHANNELS").unwrap_or_else(|_| otp_channel::DEFAULT_OTP_CHANNELS.to_string()),
+        )?;
+
+        // SMS gateway hanya wajib dikonfigurasi jika channel sms dipakai
+        let sms_config = if otp_channels.contains(&OtpChannel::Sms) {
+            Some(SmsConfig::from_env()?)
+        } else {
+            None
+        };
+
+        // Opsional: dipakai admin deactivate untuk memutus WebSocket chat user
+        let chat_service_url = env::var("CHAT_SERVICE_URL").ok();
+
         Ok(AppConfig {
             database_url,
             redis_url,
             jwt_secret,
+            jwt,
             jwt_access_expiry,
             jwt_refresh_expiry,
             server_host,
             server_port,
             environment,
             email_config,
+            otp_channels,
+            sms_config,
+            chat_service_url,
         })
     }
 
@@ -119,19 +160,21 @@ pub async fn init_redis_manager(redis_url: &str) -> Result<ConnectionManager, re
 }
 
 // Health check untuk database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
         .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 // Health check untuk Redis connection
-pub async fn check_redis_health(manager: &mut ConnectionManager) -> bool {
+pub async fn check_redis_health(manager: &mut ConnectionManager) -> Result<(), String> {
     use redis::AsyncCommands;
     manager.get::<_, Option<String>>("__health_check__")
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Redis error: {}", e))
 }
 
 // State aplikasi yang akan di-share ke semua handlers
@@ -151,6 +194,9 @@ impl AppState {
         let db = init_db_pool(&config.database_url)
             .await
             .map_err(|e| format!("Gagal menginisialisasi database: {}", e))?;
+
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "auth-service", REQUIRED_SCHEMA, config.is_production()).await?;
         let redis = init_redis_manager(&config.redis_url)
             .await
             .map_err(|e| format!("Gagal menginisialisasi Redis: {}", e))?;
@@ -168,28 +214,26 @@ impl AppState {
     }
 
     // Health check untuk semua dependencies
+    // Probe dijalankan paralel dan masing-masing diukur latency-nya
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
         let mut redis_conn = self.redis.clone();
-        let redis_healthy = check_redis_health(&mut redis_conn).await;
+        let (database, redis) = tokio::join!(
+            health::probe("database", check_db_health(&self.db)),
+            health::probe("redis", check_redis_health(&mut redis_conn)),
+        );
 
         HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            redis: if redis_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy && redis_healthy {
-                "healthy"
-            } else {
-                "degraded"
-            }
-            .to_string(),
+            overall: health::worst_status(&[&database, &redis]),
+            database,
+            redis,
         }
     }
 }
 
 // Struktur untuk response health check endpoint
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug)]
 pub struct HealthStatus {
-    pub database: String,
-    pub redis: String,
-    pub overall: String,
+    pub database: DependencyHealth,
+    pub redis: DependencyHealth,
+    pub overall: HealthLevel,
 }
\ No newline at end of file
@@ -0,0 +1,527 @@
+use crate::config::AppState;
+use crate::domain::auth::blacklist_jwt_token;
+use crate::error::AppError;
+use crate::models::auth_event::{AuthEvent, AuthEventRecord, AuthEventType};
+use crate::models::session::UserSession;
+use crate::models::user::{AdminUserRecord, User};
+use redis::AsyncCommands;
+use serde_json::json;
+use sqlx::PgPool;
+
+// Batas page size daftar user admin
+const MAX_PAGE_SIZE: i64 = 100;
+
+// Aksi admin terhadap akun user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminUserAction {
+    Deactivate,
+    Reactivate,
+    ClearOtpBlock,
+}
+
+impl AdminUserAction {
+    // Nama action di audit_logs
+    pub fn audit_action(&self) -> &'static str {
+        match self {
+            AdminUserAction::Deactivate => "ADMIN_USER_DEACTIVATED",
+            AdminUserAction::Reactivate => "ADMIN_USER_REACTIVATED",
+            AdminUserAction::ClearOtpBlock => "ADMIN_USER_OTP_UNBLOCKED",
+        }
+    }
+
+    // Segment path endpoint untuk audit trail
+    fn path_segment(&self) -> &'static str {
+        match self {
+            AdminUserAction::Deactivate => "deactivate",
+            AdminUserAction::Reactivate => "reactivate",
+            AdminUserAction::ClearOtpBlock => "clear-otp-block",
+        }
+    }
+}
+
+// Filter daftar user admin
+#[derive(Debug, serde::Deserialize)]
+pub struct AdminUserSearchInput {
+    pub search: Option<String>,
+    pub is_active: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Hasil pencarian user admin dengan pagination
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct AdminUserListResponse {
+    pub users: Vec<AdminUserRecord>,
+    #[schema(example = 42)]
+    pub total: i64,
+    #[schema(example = 20)]
+    pub limit: i64,
+    #[schema(example = 0)]
+    pub offset: i64,
+}
+
+// Filter riwayat auth event user
+#[derive(Debug, serde::Deserialize)]
+pub struct AuthEventSearchInput {
+    pub event_type: Option<AuthEventType>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Riwayat keamanan user dengan pagination
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct AuthEventListResponse {
+    #[schema(example = 7)]
+    pub user_id: i32,
+    pub events: Vec<AuthEventRecord>,
+    #[schema(example = 42)]
+    pub total: i64,
+    #[schema(example = 20)]
+    pub limit: i64,
+    #[schema(example = 0)]
+    pub offset: i64,
+}
+
+// Hasil aksi admin terhadap satu user
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct AdminUserActionResponse {
+    pub user: AdminUserRecord,
+    /// Jumlah session yang dicabut (hanya deactivate)
+    #[schema(example = 2)]
+    pub sessions_revoked: usize,
+    /// Koneksi WebSocket chat berhasil diputus (hanya deactivate)
+    #[schema(example = true)]
+    pub websocket_disconnected: bool,
+    #[schema(example = "Akun berhasil dinonaktifkan")]
+    pub message: String,
+}
+
+// Validasi aksi terhadap state user saat ini
+pub fn check_action(
+    admin_id: i32,
+    target: &AdminUserRecord,
+    action: AdminUserAction,
+) -> Result<(), AppError> {
+    let is_active = target.is_active.unwrap_or(true);
+
+    match action {
+        AdminUserAction::Deactivate => {
+            if target.id == admin_id {
+                return Err(AppError::validation("Admin tidak bisa menonaktifkan akunnya sendiri"));
+            }
+            if !is_active {
+                return Err(AppError::conflict("Akun sudah nonaktif"));
+            }
+        }
+        AdminUserAction::Reactivate => {
+            if is_active {
+                return Err(AppError::conflict("Akun sudah aktif"));
+            }
+        }
+        AdminUserAction::ClearOtpBlock => {}
+    }
+
+    Ok(())
+}
+
+// Key Redis counter OTP yang ikut di-reset saat blokir OTP dihapus
+pub fn otp_redis_keys(user_id: i32) -> [String; 2] {
+    [
+        format!("otp_request:{}", user_id),
+        format!("otp_cooldown:{}", user_id),
+    ]
+}
+
+// Cari user dengan pagination untuk panel admin
+pub async fn search_users(
+    state: &AppState,
+    input: AdminUserSearchInput,
+) -> Result<AdminUserListResponse, AppError> {
+    let limit = input.limit.unwrap_or(20).clamp(1, MAX_PAGE_SIZE);
+    let offset = input.offset.unwrap_or(0).max(0);
+    let search = input.search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    let users = User::search_for_admin(&state.db, search, input.is_active, limit, offset).await?;
+    let total = User::count_for_admin(&state.db, search, input.is_active).await?;
+
+    Ok(AdminUserListResponse { users, total, limit, offset })
+}
+
+// Nonaktifkan akun: cabut semua session, blacklist access token aktif, lalu putus WebSocket chat
+pub async fn deactivate_user(
+    state: &AppState,
+    admin_id: i32,
+    user_id: i32,
+    authorization: Option<&str>,
+) -> Result<AdminUserActionResponse, AppError> {
+    let target = load_target(state, user_id).await?;
+    check_action(admin_id, &target, AdminUserAction::Deactivate)?;
+
+    let sessions_revoked = apply_deactivation(&state.db, admin_id, user_id).await?;
+
+    // Best effort: akun sudah nonaktif walau chat-service tidak bisa dihubungi
+    let websocket_disconnected = disconnect_chat_websockets(
+        &state.http_client,
+        state.config.chat_service_url.as_deref(),
+        user_id,
+        authorization,
+    ).await;
+
+    tracing::info!(
+        "Admin {} deactivated user {} ({} sessions revoked, websocket disconnected: {})",
+        admin_id, user_id, sessions_revoked, websocket_disconnected
+    );
+
+    Ok(AdminUserActionResponse {
+        user: load_target(state, user_id).await?,
+        sessions_revoked,
+        websocket_disconnected,
+        message: "Akun berhasil dinonaktifkan".to_string(),
+    })
+}
+
+// Aktifkan kembali akun yang dinonaktifkan
+pub async fn reactivate_user(
+    state: &AppState,
+    admin_id: i32,
+    user_id: i32,
+) -> Result<AdminUserActionResponse, AppError> {
+    let target = load_target(state, user_id).await?;
+    check_action(admin_id, &target, AdminUserAction::Reactivate)?;
+
+    let mut tx = state.db.begin().await?;
+
+    User::set_active_status(&mut tx, user_id, true).await?;
+
+    write_audit_log(
+        &mut tx,
+        admin_id,
+        AdminUserAction::Reactivate,
+        user_id,
+        json!({ "is_active": false, "deactivated_at": target.deactivated_at }),
+        json!({ "is_active": true }),
+    ).await?;
+
+    tx.commit().await?;
+
+    tracing::info!("Admin {} reactivated user {}", admin_id, user_id);
+
+    Ok(AdminUserActionResponse {
+        user: load_target(state, user_id).await?,
+        sessions_revoked: 0,
+        websocket_disconnected: false,
+        message: "Akun berhasil diaktifkan kembali".to_string(),
+    })
+}
+
+// Hapus blokir OTP (database + counter Redis) agar user bisa login lagi
+pub async fn clear_otp_block(
+    state: &AppState,
+    admin_id: i32,
+    user_id: i32,
+) -> Result<AdminUserActionResponse, AppError> {
+    let target = load_target(state, user_id).await?;
+    check_action(admin_id, &target, AdminUserAction::ClearOtpBlock)?;
+
+    let mut tx = state.db.begin().await?;
+
+    User::clear_otp_block(&mut tx, user_id).await?;
+
+    write_audit_log(
+        &mut tx,
+        admin_id,
+        AdminUserAction::ClearOtpBlock,
+        user_id,
+        json!({
+            "otp_blocked_until": target.otp_blocked_until,
+            "otp_request_count": target.otp_request_count,
+        }),
+        json!({ "otp_blocked_until": null, "otp_request_count": 0 }),
+    ).await?;
+
+    tx.commit().await?;
+
+    let mut redis = state.redis.clone();
+    let _: () = redis.del(&otp_redis_keys(user_id)[..]).await?;
+
+    tracing::info!("Admin {} cleared OTP block for user {}", admin_id, user_id);
+
+    Ok(AdminUserActionResponse {
+        user: load_target(state, user_id).await?,
+        sessions_revoked: 0,
+        websocket_disconnected: false,
+        message: "Blokir OTP berhasil dihapus".to_string(),
+    })
+}
+
+// Riwayat login/logout/blokir OTP user untuk investigasi pengambilalihan akun
+pub async fn list_auth_events(
+    state: &AppState,
+    user_id: i32,
+    input: AuthEventSearchInput,
+) -> Result<AuthEventListResponse, AppError> {
+    // 404 untuk user yang tidak ada, bukan daftar kosong
+    load_target(state, user_id).await?;
+
+    let limit = input.limit.unwrap_or(20).clamp(1, MAX_PAGE_SIZE);
+    let offset = input.offset.unwrap_or(0).max(0);
+
+    let events = AuthEvent::list_for_user(&state.db, user_id, input.event_type, limit, offset).await?;
+    let total = AuthEvent::count_for_user(&state.db, user_id, input.event_type).await?;
+
+    Ok(AuthEventListResponse { user_id, events, total, limit, offset })
+}
+
+// Nonaktifkan user, cabut semua session + blacklist access token, dan catat audit log dalam satu transaksi.
+// Mengembalikan jumlah session yang dicabut.
+async fn apply_deactivation(db: &PgPool, admin_id: i32, user_id: i32) -> Result<usize, AppError> {
+    let mut tx = db.begin().await?;
+
+    User::set_active_status(&mut tx, user_id, false).await?;
+
+    let access_jtis = UserSession::revoke_all_by_user(&mut tx, user_id).await?;
+    for jti in access_jtis.iter().flatten() {
+        // Reason harus salah satu nilai CHECK jwt_blacklist.reason
+        blacklist_jwt_token(&mut tx, jti, "access", user_id, "account_suspension").await?;
+    }
+
+    write_audit_log(
+        &mut tx,
+        admin_id,
+        AdminUserAction::Deactivate,
+        user_id,
+        json!({ "is_active": true }),
+        json!({ "is_active": false, "sessions_revoked": access_jtis.len() }),
+    ).await?;
+
+    tx.commit().await?;
+
+    Ok(access_jtis.len())
+}
+
+async fn load_target(state: &AppState, user_id: i32) -> Result<AdminUserRecord, AppError> {
+    User::find_for_admin(&state.db, user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User tidak ditemukan"))
+}
+
+// Catat aksi admin ke audit_logs dengan user_id = admin pelaku
+async fn write_audit_log(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    admin_id: i32,
+    action: AdminUserAction,
+    target_user_id: i32,
+    old_values: serde_json::Value,
+    new_values: serde_json::Value,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO audit_logs (user_id, action, entity_type, entity_id, old_values, new_values, service_name, endpoint, http_method)
+         VALUES ($1, $2, 'user', $3, $4, $5, 'auth-service', $6, 'POST')"
+    )
+    .bind(admin_id)
+    .bind(action.audit_action())
+    .bind(target_user_id)
+    .bind(old_values)
+    .bind(new_values)
+    .bind(format!("/api/admin/users/{}/{}", target_user_id, action.path_segment()))
+    .execute(tx.as_mut())
+    .await?;
+
+    Ok(())
+}
+
+// Minta chat-service memutus semua WebSocket user (chat-service broadcast ke instance lain via NATS)
+async fn disconnect_chat_websockets(
+    http_client: &reqwest::Client,
+    chat_service_url: Option<&str>,
+    user_id: i32,
+    authorization: Option<&str>,
+) -> bool {
+    let Some(chat_service_url) = chat_service_url else {
+        tracing::warn!("CHAT_SERVICE_URL tidak diset, WebSocket user {} tidak diputus", user_id);
+        return false;
+    };
+    // chat-service butuh token admin yang sama untuk endpoint disconnect
+    let Some(authorization) = authorization else {
+        tracing::warn!("Request admin tanpa header Authorization, WebSocket user {} tidak diputus", user_id);
+        return false;
+    };
+
+    let result = http_client
+        .post(format!("{}/api/ws/disconnect-user/{}", chat_service_url, user_id))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => true,
+        Ok(response) => {
+            tracing::warn!("chat-service menolak disconnect user {}: {}", user_id, response.status());
+            false
+        }
+        Err(e) => {
+            tracing::warn!("Gagal menghubungi chat-service untuk disconnect user {}: {}", user_id, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(id: i32, is_active: Option<bool>) -> AdminUserRecord {
+        AdminUserRecord {
+            id,
+            email: format!("user{}@example.com", id),
+            name: "Test User".to_string(),
+            phone: "081234567890".to_string(),
+            is_seller: Some(false),
+            is_admin: Some(false),
+            is_active,
+            deactivated_at: None,
+            otp_request_count: Some(5),
+            otp_blocked_until: None,
+            last_login_at: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_deactivate_transitions() {
+        assert!(check_action(1, &target(7, Some(true)), AdminUserAction::Deactivate).is_ok());
+        // NULL is_active diperlakukan sebagai aktif (default kolom)
+        assert!(check_action(1, &target(7, None), AdminUserAction::Deactivate).is_ok());
+
+        assert!(matches!(
+            check_action(1, &target(7, Some(false)), AdminUserAction::Deactivate),
+            Err(AppError::ConflictError(_))
+        ));
+        assert!(matches!(
+            check_action(7, &target(7, Some(true)), AdminUserAction::Deactivate),
+            Err(AppError::ValidationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reactivate_transitions() {
+        assert!(check_action(1, &target(7, Some(false)), AdminUserAction::Reactivate).is_ok());
+        assert!(matches!(
+            check_action(1, &target(7, Some(true)), AdminUserAction::Reactivate),
+            Err(AppError::ConflictError(_))
+        ));
+    }
+
+    #[test]
+    fn test_clear_otp_block_always_allowed() {
+        assert!(check_action(1, &target(7, Some(true)), AdminUserAction::ClearOtpBlock).is_ok());
+        assert!(check_action(1, &target(7, Some(false)), AdminUserAction::ClearOtpBlock).is_ok());
+        assert!(check_action(7, &target(7, Some(true)), AdminUserAction::ClearOtpBlock).is_ok());
+    }
+
+    #[test]
+    fn test_otp_redis_keys_match_login_counters() {
+        assert_eq!(otp_redis_keys(42), ["otp_request:42".to_string(), "otp_cooldown:42".to_string()]);
+    }
+
+    #[test]
+    fn test_audit_action_names() {
+        assert_eq!(AdminUserAction::Deactivate.audit_action(), "ADMIN_USER_DEACTIVATED");
+        assert_eq!(AdminUserAction::Reactivate.audit_action(), "ADMIN_USER_REACTIVATED");
+        assert_eq!(AdminUserAction::ClearOtpBlock.audit_action(), "ADMIN_USER_OTP_UNBLOCKED");
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_deactivation_revokes_sessions_and_writes_audit(db: PgPool) {
+        sqlx::query(
+            "INSERT INTO user_sessions (user_id, refresh_token, access_token_jti, expires_at) VALUES
+                (2, 'refresh-a', 'jti-a', NOW() + INTERVAL '1 day'),
+                (2, 'refresh-b', NULL, NOW() + INTERVAL '1 day'),
+                (3, 'refresh-c', 'jti-c', NOW() + INTERVAL '1 day')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        assert_eq!(apply_deactivation(&db, 1, 2).await.unwrap(), 2);
+
+        let target = User::find_for_admin(&db, 2).await.unwrap().unwrap();
+        assert_eq!(target.is_active, Some(false));
+
+        let active_sessions: Vec<String> = sqlx::query_scalar(
+            "SELECT refresh_token FROM user_sessions WHERE is_active = true"
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(active_sessions, vec!["refresh-c".to_string()]);
+
+        let blacklisted: Vec<String> = sqlx::query_scalar("SELECT token_jti FROM jwt_blacklist")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(blacklisted, vec!["jti-a".to_string()]);
+
+        let (actor, action, new_values): (Option<i32>, String, Option<serde_json::Value>) = sqlx::query_as(
+            "SELECT user_id, action, new_values FROM audit_logs WHERE entity_type = 'user' AND entity_id = 2"
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(actor, Some(1));
+        assert_eq!(action, "ADMIN_USER_DEACTIVATED");
+        assert_eq!(new_values.unwrap()["sessions_revoked"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_calls_chat_service_with_admin_token() {
+        use axum::{extract::Path, http::{HeaderMap, StatusCode}, routing::post, Router};
+        use std::sync::{Arc, Mutex};
+
+        // (user_id, header Authorization) tiap request yang diterima stub chat-service
+        type Calls = Arc<Mutex<Vec<(i32, Option<String>)>>>;
+        let calls = Calls::default();
+        let recorded = calls.clone();
+        let app = Router::new().route(
+            "/api/ws/disconnect-user/{user_id}",
+            post(move |Path(user_id): Path<i32>, headers: HeaderMap| async move {
+                let authorization = headers
+                    .get("authorization")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                recorded.lock().unwrap().push((user_id, authorization));
+                if user_id == 404 { StatusCode::NOT_FOUND } else { StatusCode::OK }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+
+        assert!(disconnect_chat_websockets(&client, Some(&url), 7, Some("Bearer admin-token")).await);
+        assert!(!disconnect_chat_websockets(&client, Some(&url), 404, Some("Bearer admin-token")).await);
+
+        // Tanpa URL atau tanpa token admin, chat-service tidak dipanggil
+        assert!(!disconnect_chat_websockets(&client, None, 8, Some("Bearer admin-token")).await);
+        assert!(!disconnect_chat_websockets(&client, Some(&url), 9, None).await);
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                (7, Some("Bearer admin-token".to_string())),
+                (404, Some("Bearer admin-token".to_string())),
+            ]
+        );
+    }
+}
@@ -1,15 +1,17 @@
 use crate::config::AppState;
 use crate::error::AppError;
 use crate::models::{
+    auth_event::{AuthEvent, AuthEventType, NewAuthEvent},
     email_verification::{EmailVerification, NewEmailVerification},
     login_otp::{LoginOtp, NewLoginOtp},
     session::{NewUserSession, UserSession},
     user::{NewUser, User},
 };
 // Import utilities directly from submodules
-use crate::utils::{email, hash, jwt, otp, validation};
+use crate::utils::{email, hash, jwt, otp, otp_channel, validation};
 use chrono::{Duration, Utc};
 use redis::AsyncCommands;
+use shared::utils::validation::{FieldError, FromFieldErrors};
 use uuid::Uuid;
 use sha2::{Digest, Sha256};
 // Menentukan role user untuk JWT claims berdasarkan status customer/seller
@@ -17,6 +19,15 @@ fn determine_user_role(user: &User) -> String {
     user.get_jwt_role()
 }
 
+// Sisa TTL key rate limit untuk Retry-After, fallback jika TTL tidak bisa dibaca
+async fn retry_after_from_ttl(redis: &mut redis::aio::ConnectionManager, key: &str, fallback_secs: u64) -> u64 {
+    let ttl: redis::RedisResult<i64> = redis.ttl(key).await;
+    match ttl {
+        Ok(ttl) if ttl > 0 => ttl as u64,
+        _ => fallback_secs,
+    }
+}
+
 
 // struktur data untuk registrasi
 #[derive(Debug, serde::Deserialize)]
@@ -49,6 +60,15 @@ pub struct LoginStep1Input {
     pub password: String,
 }
 
+// Hasil login step 1: user dan channel yang berhasil mengirim OTP
+#[derive(Debug)]
+pub struct OtpSent {
+    pub user_id: i32,
+    pub channel: otp_channel::OtpChannel,
+    // Id opaque untuk cek status pengiriman (GET /api/auth/otp/status/{request_id})
+    pub request_id: String,
+}
+
 // Struktur data untuk input login step 2
 #[derive(Debug, serde::Deserialize)]
 pub struct LoginStep2Input {
@@ -96,19 +116,37 @@ impl From<User> for UserData {
     }
 }
 
+// Validasi field registrasi, return nomor telepon yang sudah dinormalisasi
+fn validate_register_input(input: &RegisterInput) -> Result<String, Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if let Err(e) = validation::validate_email(&input.email) {
+        errors.push(e);
+    }
+    if let Err(e) = validation::validate_password(&input.password) {
+        errors.push(e);
+    }
+    if input.name.trim().is_empty() {
+        errors.push(FieldError::new("name", "Nama tidak boleh kosong"));
+    }
+
+    match validation::normalize_phone(&input.phone) {
+        Ok(phone) if errors.is_empty() => Ok(phone),
+        Ok(_) => Err(errors),
+        Err(e) => {
+            errors.push(e);
+            Err(errors)
+        }
+    }
+}
+
 // Registrasi user baru dan kirim email verifikasi
 pub async fn register_user(
     state: &AppState,
     input: RegisterInput,
 ) -> Result<RegisterResponse, AppError> {
-    // Validasi input data
-    validation::validate_email(&input.email)
-        .map_err(|e| AppError::ValidationError(e))?;
-    validation::validate_password(&input.password)
-        .map_err(|e| AppError::ValidationError(e))?;
-    validation::validate_phone(&input.phone)
-        .map_err(|e| AppError::ValidationError(e))?;
-
+    // Validasi input data, semua field sekaligus agar form bisa menandai setiap input
+    let phone = validate_register_input(&input).map_err(AppError::fields)?;
 
     // Cek apakah email sudah terdaftar
     if User::find_by_email(&state.db, &input.email).await?.is_some() {
@@ -124,7 +162,7 @@ pub async fn register_user(
         email: input.email.to_lowercase().trim().to_string(),
         password_hash,
         name: input.name.trim().to_string(),
-        phone: validation::normalize_phone(&input.phone),
+        phone,
         address: input.address.map(|a| a.trim().to_string()),
         city: input.city.map(|c| c.trim().to_string()),
     };
@@ -155,13 +193,11 @@ pub async fn register_user(
 
     // Kirim email verifikasi (async, non-blocking)
     let http_client = state.http_client.clone();
-    let api_key = state.config.email_config.resend_api_key.clone();
-    let from_email = state.config.email_config.email_from.clone();
+    let email_config = state.config.email_config.clone();
     tokio::spawn(async move {
         if let Err(e) = email::send_verification_email(
             &http_client,
-            &api_key,
-            &from_email,
+            &email_config,
             &user.email,
             &user.name,
             &verification_token,
@@ -218,9 +254,7 @@ pub async fn resend_verification(
 
     // Cek apakah email sudah verified
     if user.email_verified.unwrap_or(false) {
-        return Err(AppError::ValidationError(
-            "Email sudah diverifikasi".to_string(),
-        ));
+        return Err(AppError::validation("Email sudah diverifikasi"));
     }
 
     // Rate limiting: cek apakah user sudah request terlalu sering
@@ -230,8 +264,10 @@ pub async fn resend_verification(
 
     if let Some(c) = count {
         if c >= 3 {
-            return Err(AppError::RateLimitError(
-                "Terlalu banyak permintaan. Coba lagi dalam 1 jam.".to_string(),
+            let retry_after = retry_after_from_ttl(&mut redis, &rate_key, 3600).await;
+            return Err(AppError::rate_limit(
+                "Terlalu banyak permintaan. Coba lagi dalam 1 jam.",
+                retry_after,
             ));
         }
     }
@@ -260,13 +296,11 @@ pub async fn resend_verification(
 
     // Kirim Email
     let http_client = state.http_client.clone();
-    let api_key = state.config.email_config.resend_api_key.clone();
-    let from_email = state.config.email_config.email_from.clone();
+    let email_config = state.config.email_config.clone();
     tokio::spawn(async move {
         if let Err(e) = email::send_verification_email(
             &http_client,
-            &api_key,
-            &from_email,
+            &email_config,
             &user.email,
             &user.name,
             &verification_token
@@ -279,13 +313,13 @@ pub async fn resend_verification(
     
 }
 
-// Login ste 1: validasi kredensial dan kriim OTP ke email
+// Login ste 1: validasi kredensial dan kirim OTP lewat channel sesuai OTP_CHANNELS
 pub async fn login_step1_send_otp(
     state: &AppState,
     input: LoginStep1Input,
     ip_address: Option<String>,
     user_agent: Option<String>,
-) -> Result<i32, AppError> {
+) -> Result<OtpSent, AppError> {
     // cari user berdasarkan email
     let user = User::find_by_email(&state.db, &input.email)
         .await?
@@ -296,6 +330,15 @@ pub async fn login_step1_send_otp(
         .map_err(|e| AppError::InternalError(format!("Gagal verifikasi password: {}", e)))?;
 
     if !password_valid {
+        record_auth_event(state, NewAuthEvent {
+            user_id: user.id,
+            event_type: AuthEventType::LoginFailed,
+            ip_address: ip_address.clone(),
+            user_agent: user_agent.clone(),
+            details: serde_json::json!({ "reason": "invalid_password" }),
+            endpoint: "/api/auth/login",
+        }).await;
+
         return Err(AppError::AuthenticationError(
             "Email atau password salah".to_string(),
         ));
@@ -319,11 +362,14 @@ pub async fn login_step1_send_otp(
     // Rate limiting OTP: cek apakah user sedang di blok 
     if let Some(blocked_until) = user.otp_blocked_until {
         if blocked_until > Utc::now() {
-            let remaining = (blocked_until - Utc::now()).num_minutes();
-            return Err(AppError::RateLimitError(format!(
-                "Akun diblokir karena terlalu banyak percobaan. Coba lagi dalam {} menit.",
-                remaining
-            )));
+            let remaining = blocked_until - Utc::now();
+            return Err(AppError::rate_limit(
+                format!(
+                    "Akun diblokir karena terlalu banyak percobaan. Coba lagi dalam {} menit.",
+                    remaining.num_minutes()
+                ),
+                remaining.num_seconds() as u64,
+            ));
         }
     }
 
@@ -336,8 +382,17 @@ pub async fn login_step1_send_otp(
         if cnt >= 5 {
             // Block user selama 1 jam (60 menit)
             User::block_otp_requests(&state.db, user.id, 60).await?;
-            return Err(AppError::RateLimitError(
-                "Terlalu banyak permintaan OTP. Akun diblokir selama 1 jam.".to_string(),
+            record_auth_event(state, NewAuthEvent {
+                user_id: user.id,
+                event_type: AuthEventType::OtpBlocked,
+                ip_address: ip_address.clone(),
+                user_agent: user_agent.clone(),
+                details: serde_json::json!({ "reason": "otp_request_limit", "blocked_minutes": 60 }),
+                endpoint: "/api/auth/login",
+            }).await;
+            return Err(AppError::rate_limit(
+                "Terlalu banyak permintaan OTP. Akun diblokir selama 1 jam.",
+                3600,
             ));
         }
     }
@@ -369,26 +424,87 @@ pub async fn login_step1_send_otp(
     // Update last_otp_request_at
     User::increment_otp_request(&state.db, user.id).await?;
 
-    // Kirim OTP via email (async)
-    let http_client = state.http_client.clone();
-    let api_key = state.config.email_config.resend_api_key.clone();
-    let from_email = state.config.email_config.email_from.clone();
-    let user_email = user.email.clone();
-    let user_name = user.name.clone();
-    tokio::spawn(async move {
-        if let Err(e) = email::send_otp_email(
-            &http_client,
-            &api_key,
-            &from_email,
-            &user_email,
-            &user_name,
-            &otp_code
-        ).await {
-            tracing::error!("Gagal mengirim OTP email: {}", e);
+    // Kirim OTP sesuai urutan channel, status pengiriman dicatat di Redis
+    send_otp_tracked(state, &user, &otp_code).await
+}
+
+// Kirim OTP lewat channel fallback dan catat status queued -> sent/failed di bawah request id
+// acak, bukan user id, agar status tidak bisa dienumerasi. Error hanya jika semua channel gagal
+async fn send_otp_tracked(
+    state: &AppState,
+    user: &User,
+    otp_code: &str,
+) -> Result<OtpSent, AppError> {
+    let mut redis = state.redis.clone();
+    let request_id = otp::generate_request_id();
+    set_otp_delivery_status(&mut redis, &request_id, otp::OtpDeliveryStatus::Queued).await;
+
+    let config = &state.config;
+    let delivered = otp_channel::deliver_with_fallback(&config.otp_channels, |channel| async move {
+        match channel {
+            otp_channel::OtpChannel::Email => {
+                email::send_otp_email(&state.http_client, &config.email_config, &user.email, &user.name, otp_code).await
+            }
+            otp_channel::OtpChannel::Sms => {
+                otp_channel::send_otp_sms(&state.http_client, config.sms_config.as_ref(), &user.phone, otp_code).await
+            }
         }
-    });
+    })
+    .await;
+
+    match delivered {
+        Ok(channel) => {
+            set_otp_delivery_status(&mut redis, &request_id, otp::OtpDeliveryStatus::Sent).await;
+            tracing::info!("OTP user {} terkirim via {}", user.id, channel.as_str());
+            Ok(OtpSent { user_id: user.id, channel, request_id })
+        }
+        Err(failures) => {
+            for (channel, e) in &failures {
+                tracing::error!("Gagal mengirim OTP {} ke user {}: {}", channel.as_str(), user.id, e);
+            }
+            set_otp_delivery_status(&mut redis, &request_id, otp::OtpDeliveryStatus::Failed).await;
+            Err(AppError::email("Gagal mengirim OTP. Silakan coba lagi beberapa saat."))
+        }
+    }
+}
+
+// Best effort: gagal tulis status tidak boleh menggagalkan login
+async fn set_otp_delivery_status(
+    redis: &mut redis::aio::ConnectionManager,
+    request_id: &str,
+    status: otp::OtpDeliveryStatus,
+) {
+    let result: Result<(), redis::RedisError> = redis
+        .set_ex(otp::delivery_key(request_id), status.as_str(), otp::OTP_DELIVERY_TTL_SECS)
+        .await;
+
+    if let Err(e) = result {
+        tracing::warn!("Gagal menyimpan status pengiriman OTP {}: {}", request_id, e);
+    }
+}
+
+// Status pengiriman OTP untuk request id dari login step 1 / resend OTP.
+// Request id yang formatnya salah langsung dianggap unknown tanpa query Redis
+pub async fn get_otp_delivery_status(
+    state: &AppState,
+    request_id: &str,
+) -> Result<otp::OtpDeliveryStatus, AppError> {
+    if !otp::is_valid_request_id(request_id) {
+        return Ok(otp::OtpDeliveryStatus::Unknown);
+    }
+
+    let mut redis = state.redis.clone();
+    let value: Option<String> = redis.get(otp::delivery_key(request_id)).await?;
+    Ok(otp::OtpDeliveryStatus::parse(value.as_deref()))
+}
 
-    Ok(user.id)
+// Catat event keamanan auth; best effort agar kegagalan audit tidak memblokir login/logout
+async fn record_auth_event(state: &AppState, event: NewAuthEvent) {
+    let user_id = event.user_id;
+    let action = event.event_type.audit_action();
+    if let Err(e) = AuthEvent::record(&state.db, event).await {
+        tracing::warn!("Gagal mencatat auth event {} untuk user {}: {}", action, user_id, e);
+    }
 }
 
 // Login step 2: verifikasi OTP dan generate JWT tokens
@@ -408,8 +524,10 @@ pub async fn login_step2_verify_otp(
     // Gunakan method is_valid() untuk validasi OTP
     if !otp_record.is_valid() {
         if otp_record.is_blocked() {
+            let remaining = otp_record.blocked_until.map_or(0, |blocked| (blocked - Utc::now()).num_seconds());
             return Err(AppError::rate_limit(
-                "OTP diblokir karena terlalu banyak percobaan gagal."
+                "OTP diblokir karena terlalu banyak percobaan gagal.",
+                remaining.max(1) as u64,
             ));
         } else {
             return Err(AppError::authentication(
@@ -420,10 +538,18 @@ pub async fn login_step2_verify_otp(
 
     let attempt_count = otp_record.attempt_count.unwrap_or(0);
     if attempt_count >= 3 {
-        
         LoginOtp::block_otp(&state.db, otp_record.id, 15).await?;
-        return Err(AppError::RateLimitError(
-            "Terlalu banyak percobaan gagal. OTP diblokir selama 15 menit.".to_string(),
+        record_auth_event(state, NewAuthEvent {
+            user_id: input.user_id,
+            event_type: AuthEventType::OtpBlocked,
+            ip_address: ip_address.clone(),
+            user_agent: user_agent.clone(),
+            details: serde_json::json!({ "reason": "otp_attempt_limit", "blocked_minutes": 15 }),
+            endpoint: "/api/auth/verify-otp",
+        }).await;
+        return Err(AppError::rate_limit(
+            "Terlalu banyak percobaan gagal. OTP diblokir selama 15 menit.",
+            15 * 60,
         ));
     }
 
@@ -434,6 +560,14 @@ pub async fn login_step2_verify_otp(
     if !otp_valid {
         // Increment attempt count
         LoginOtp::increment_attempt(&state.db, otp_record.id).await?;
+        record_auth_event(state, NewAuthEvent {
+            user_id: input.user_id,
+            event_type: AuthEventType::LoginFailed,
+            ip_address: ip_address.clone(),
+            user_agent: user_agent.clone(),
+            details: serde_json::json!({ "reason": "invalid_otp", "attempt": attempt_count + 1 }),
+            endpoint: "/api/auth/verify-otp",
+        }).await;
 
         let remaining = 3 - attempt_count - 1;
         return Err(AppError::AuthenticationError(format!(
@@ -459,19 +593,19 @@ pub async fn login_step2_verify_otp(
         user.id,
         &user.email,
         &role,
-        &state.config.jwt_secret,
+        &state.config.jwt,
         state.config.jwt_access_expiry
     )?;
 
     // Extract JTI dari access token untuk tracking
-    let claims = jwt::validate_token(&access_token, &state.config.jwt_secret, &state.db)
+    let claims = jwt::validate_token(&access_token, &state.config.jwt, &state.db, jwt::TokenType::Access)
         .await?;
     let access_jti = claims.jti.clone();
     let refresh_token = jwt::generate_refresh_token(
         user.id,
         &user.email,
         &role,
-        &state.config.jwt_secret,
+        &state.config.jwt,
         state.config.jwt_refresh_expiry
     )?;
 
@@ -480,16 +614,24 @@ pub async fn login_step2_verify_otp(
         user_id: user.id,
         refresh_token: refresh_token.clone(),
         access_token_jti: Some(access_jti.clone()),
-        user_agent,
-        ip_address,
+        user_agent: user_agent.clone(),
+        ip_address: ip_address.clone(),
         device_name: None,
-        expires_at: Utc::now() + Duration::days(7),
+        expires_at: Utc::now() + Duration::seconds(jwt::SESSION_MAX_AGE_SECS),
     };
 
     let _session = UserSession::create(&state.db, session_data).await?;
 
     // Update user login statistics
     User::update_login_tracking(&state.db, user.id).await?;
+    record_auth_event(state, NewAuthEvent {
+        user_id: user.id,
+        event_type: AuthEventType::LoginSuccess,
+        ip_address,
+        user_agent,
+        details: serde_json::json!({ "access_jti": &access_jti }),
+        endpoint: "/api/auth/verify-otp",
+    }).await;
 
     tracing::info!(
         "User {} logged in successfully with JTI: {}",
@@ -517,7 +659,7 @@ pub async fn resend_otp(
     user_id: i32,
     ip_address: Option<String>,
     user_agent: Option<String>,
-) -> Result<String, AppError> {
+) -> Result<OtpSent, AppError> {
     // Load user
     let user = User::find_by_id(&state.db, user_id)
         .await?
@@ -529,8 +671,10 @@ pub async fn resend_otp(
     let exists: bool = redis.exists(&cooldown_key).await?;
 
     if exists {
-        return Err(AppError::RateLimitError(
-            "Tunggu 60 detik sebelum request OTP lagi.".to_string(),
+        let retry_after = retry_after_from_ttl(&mut redis, &cooldown_key, 60).await;
+        return Err(AppError::rate_limit(
+            "Tunggu 60 detik sebelum request OTP lagi.",
+            retry_after,
         ));
     }
 
@@ -543,8 +687,10 @@ pub async fn resend_otp(
 
     if let Some(cnt) = count {
         if cnt >= 5 {
-            return Err(AppError::RateLimitError(
-                "Terlalu banyak permintaan OTP. Coba lagi dalam 1 jam.".to_string(),
+            let retry_after = retry_after_from_ttl(&mut redis, &rate_key, 3600).await;
+            return Err(AppError::rate_limit(
+                "Terlalu banyak permintaan OTP. Coba lagi dalam 1 jam.",
+                retry_after,
             ));
         }
     }
@@ -572,26 +718,8 @@ pub async fn resend_otp(
 
     LoginOtp::create(&state.db, otp_data).await?;
 
-    // Kirim email
-    let http_client = state.http_client.clone();
-    let api_key = state.config.email_config.resend_api_key.clone();
-    let from_email = state.config.email_config.email_from.clone();
-    let user_email = user.email.clone();
-    let user_name = user.name.clone();
-    tokio::spawn(async move {
-        if let Err(e) = email::send_otp_email(
-            &http_client,
-            &api_key,
-            &from_email,
-            &user_email,
-            &user_name,
-            &otp_code
-        ).await {
-            tracing::error!("Gagal mengirim OTP: {}", e);
-        }
-    });
-
-    Ok("OTP baru telah dikirim ke email Anda.".to_string())
+    // Kirim OTP sesuai urutan channel, status pengiriman dicatat di Redis
+    send_otp_tracked(state, &user, &otp_code).await
 }
 
 // Refresh access token menggunakan refresh token
@@ -600,16 +728,10 @@ pub async fn refresh_access_token(
     refresh_token: &str,
 ) -> Result<String, AppError> {
     // Validasi refresh token
-    let claims = jwt::validate_token(refresh_token, &state.config.jwt_secret, &state.db)
+    // Token type refresh ditegakkan di shared::auth
+    let claims = jwt::validate_token(refresh_token, &state.config.jwt, &state.db, jwt::TokenType::Refresh)
         .await?;
 
-    // Cek apakah token type = refresh
-    if claims.token_type != "refresh" {
-        return Err(AppError::TokenError(
-            "Token bukan refresh token".to_string(),
-        ));
-    }
-
     // cari session bedasarkan refresh token 
     let session = UserSession::find_by_refresh_token(&state.db, refresh_token)
         .await?
@@ -635,12 +757,12 @@ pub async fn refresh_access_token(
         user.id,
         &user.email,
         &role,
-        &state.config.jwt_secret,
+        &state.config.jwt,
         state.config.jwt_access_expiry
     )?;
 
     // Extract JTI dari new access token
-    let new_claims = jwt::validate_token(&new_access_token, &state.config.jwt_secret, &state.db)
+    let new_claims = jwt::validate_token(&new_access_token, &state.config.jwt, &state.db, jwt::TokenType::Access)
         .await?;
     let new_jti = new_claims.jti.clone();
 
@@ -658,33 +780,41 @@ pub async fn refresh_access_token(
 
 
 /// Logout user dengan keamanan enterprise: blacklist semua JWT tokens
-pub async fn logout(state: &AppState, refresh_token: &str) -> Result<String, AppError> {
+pub async fn logout(
+    state: &AppState,
+    refresh_token: &str,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+) -> Result<String, AppError> {
     let token_hash = hash_token_for_logging(refresh_token);
     tracing::info!("Processing logout request - token: {}...", token_hash);
 
     // Extract data dari refresh token untuk tracking
-    let token_claims = validate_refresh_token(refresh_token, &state.config.jwt_secret, &state.db).await?;
+    let token_claims = validate_refresh_token(refresh_token, &state.config.jwt, &state.db).await?;
     let user_id = token_claims.sub;
     let refresh_jti = token_claims.jti;
 
     // Execute security-critical operations secara atomik
-    execute_logout_security_procedures(&state, user_id, &refresh_jti).await?;
+    execute_logout_security_procedures(&state.db, user_id, &refresh_jti).await?;
+    record_auth_event(state, NewAuthEvent {
+        user_id,
+        event_type: AuthEventType::Logout,
+        ip_address,
+        user_agent,
+        details: serde_json::json!({ "refresh_jti": &refresh_jti }),
+        endpoint: "/api/auth/logout",
+    }).await;
 
     tracing::info!("Logout completed successfully - user_id: {}", user_id);
     Ok("Logout berhasil. Semua token telah diblacklist.".to_string())
 }
 
 /// Validasi refresh token sebelum proses logout
-async fn validate_refresh_token(token: &str, jwt_secret: &str, db: &sqlx::PgPool) -> Result<crate::utils::jwt::TokenClaims, AppError> {
-    let claims = crate::utils::jwt::validate_token(token, jwt_secret, db)
+async fn validate_refresh_token(token: &str, jwt_config: &shared::auth::JwtConfig, db: &sqlx::PgPool) -> Result<crate::utils::jwt::TokenClaims, AppError> {
+    // Access token ditolak sebagai InvalidTokenType oleh shared::auth
+    crate::utils::jwt::validate_token(token, jwt_config, db, crate::utils::jwt::TokenType::Refresh)
         .await
-        .map_err(|e| AppError::AuthenticationError(format!("Token tidak valid: {}", e)))?;
-
-    if claims.token_type != "refresh" {
-        return Err(AppError::TokenError("Token type tidak sesuai untuk logout".to_string()));
-    }
-
-    Ok(claims)
+        .map_err(|e| AppError::AuthenticationError(format!("Token tidak valid: {}", e)))
 }
 
 /// Hash token untuk logging aman 
@@ -695,38 +825,46 @@ fn hash_token_for_logging(token: &str) -> String {
     format!("{:x}", hash)[..16].to_string()
 }
 
-/// Eksekusi prosedur keamanan logout secara atomik
+/// Namespace advisory lock logout, dipasangkan dengan user_id
+const LOGOUT_LOCK_NAMESPACE: i32 = 4001;
+
+/// Eksekusi prosedur keamanan logout secara atomik dalam satu transaksi.
+/// Logout milik user yang sama diserialisasi advisory lock: logout kedua menunggu yang pertama
+/// commit, lalu hanya menemukan token yang sudah diblacklist dan session yang sudah nonaktif (no-op)
 async fn execute_logout_security_procedures(
-    state: &AppState,
+    db: &sqlx::PgPool,
     user_id: i32,
     refresh_jti: &str,
 ) -> Result<(), AppError> {
-    let mut tx = state.db.begin().await
+    let mut tx = db.begin().await
         .map_err(|e| AppError::InternalError(format!("Gagal memulai transaksi database: {}", e)))?;
 
+    // Lock dilepas otomatis saat commit/rollback
+    sqlx::query("SELECT pg_advisory_xact_lock($1, $2)")
+        .bind(LOGOUT_LOCK_NAMESPACE)
+        .bind(user_id)
+        .execute(tx.as_mut())
+        .await
+        .map_err(|e| AppError::InternalError(format!("Gagal mengambil lock logout: {}", e)))?;
+
     // Blacklist refresh token dengan alasan dan expiry
     blacklist_jwt_token(&mut tx, refresh_jti, "refresh", user_id, "user_logout").await?;
 
     // Cari dan blacklist semua access tokens terkait
     blacklist_related_access_tokens(&mut tx, user_id, refresh_jti).await?;
 
+    // Multi-device logout di transaksi yang sama, tidak lagi di background task
+    invalidate_user_sessions(&mut tx, user_id).await?;
+
     // Commit semua perubahan keamanan
     tx.commit().await
         .map_err(|e| AppError::InternalError(format!("Gagal commit transaksi logout: {}", e)))?;
 
-    // Invalidate user sessions di background untuk performance
-    let db_clone = state.db.clone();
-    tokio::spawn(async move {
-        if let Err(e) = invalidate_user_sessions(&db_clone, user_id).await {
-            tracing::error!("Failed to invalidate user sessions: {}", e);
-        }
-    });
-
     Ok(())
 }
 
 /// Blacklist JWT token menggunakan secure function
-async fn blacklist_jwt_token(
+pub(crate) async fn blacklist_jwt_token(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     jti: &str,
     token_type: &str,
@@ -791,16 +929,74 @@ async fn blacklist_related_access_tokens(
 }
 
 /// Invalidate semua session user untuk multi-device logout
-async fn invalidate_user_sessions(db: &sqlx::PgPool, user_id: i32) -> Result<(), AppError> {
+async fn invalidate_user_sessions(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+) -> Result<(), AppError> {
     // Update semua session user menjadi tidak aktif
     sqlx::query(
         "UPDATE user_sessions SET is_active = false, updated_at = NOW() WHERE user_id = $1 AND is_active = true"
     )
     .bind(user_id)
-    .execute(db)
+    .execute(tx.as_mut())
     .await
     .map_err(|e| AppError::InternalError(format!("Gagal invalidate user sessions: {}", e)))?;
 
     tracing::info!("Invalidated all sessions for user_id: {}", user_id);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Dua logout bersamaan untuk session yang sama: keduanya sukses, token diblacklist sekali
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_concurrent_logout_is_idempotent(db: sqlx::PgPool) {
+        let user_id = 1;
+        let refresh_jti = "refresh-logout-1";
+        let access_jti = "access-logout-1";
+
+        // Session yang di-logout + session device lain
+        sqlx::query(
+            "INSERT INTO user_sessions (user_id, refresh_token, access_token_jti, expires_at)
+             VALUES ($1, $2, $3, NOW() + INTERVAL '1 day'), ($1, $4, NULL, NOW() + INTERVAL '1 day')"
+        )
+        .bind(user_id)
+        .bind(refresh_jti)
+        .bind(access_jti)
+        .bind("other-logout-1")
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let (first, second) = tokio::join!(
+            execute_logout_security_procedures(&db, user_id, refresh_jti),
+            execute_logout_security_procedures(&db, user_id, refresh_jti),
+        );
+        assert!(first.is_ok(), "logout pertama gagal: {:?}", first.err());
+        assert!(second.is_ok(), "logout kedua gagal: {:?}", second.err());
+
+        let blacklisted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jwt_blacklist WHERE token_jti IN ($1, $2)")
+            .bind(refresh_jti)
+            .bind(access_jti)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        let active_sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_sessions WHERE user_id = $1 AND is_active = true")
+            .bind(user_id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(blacklisted, 2);
+        assert_eq!(active_sessions, 0);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod auth;
 pub mod session;
 pub mod user;
+pub mod admin;
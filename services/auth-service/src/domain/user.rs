@@ -3,6 +3,7 @@ use crate::error::AppError;
 use crate::models::user::{UpdateUserProfile, User};
 // Import validation utilities directly from submodule
 use crate::utils::validation;
+use shared::utils::validation::FieldError;
 
 // Struktur data response profile user
 #[derive(Debug, serde::Serialize, utoipa::ToSchema)]
@@ -79,25 +80,30 @@ pub async fn update_profile(
     user_id: i32,
     input: UpdateProfileInput,
 ) -> Result<ProfileResponse, AppError> {
-    // Validasi phone jika diupdate
-    if let Some(ref phone) = input.phone {
-        validation::validate_phone(phone)
-            .map_err(|e| AppError::ValidationError(e.to_string()))?;
-    }
+    // Validasi dan normalisasi phone jika diupdate
+    let phone = input.phone
+        .as_deref()
+        .map(validation::normalize_phone)
+        .transpose()?;
 
     // Validasi name tidak boleh kosong
     if let Some(ref name) = input.name {
         if name.trim().is_empty() {
-            return Err(AppError::ValidationError(
-                "Nama tidak boleh kosong".to_string(),
-            ));
+            return Err(FieldError::new("name", "Nama tidak boleh kosong").into());
         }
     }
 
+    // Validasi foto profile harus dari storage kita
+    if let Some(ref photo) = input.profile_photo {
+        shared::utils::validation::validate_uploaded_image_url(photo)
+            .await
+            .map_err(|e| FieldError::new("profile_photo", e))?;
+    }
+
     // Prepare update data
     let update_data = UpdateUserProfile {
         name: input.name.map(|n| n.trim().to_string()),
-        phone: input.phone.map(|p| validation::normalize_phone(&p)),
+        phone,
         address: input.address.map(|a| a.trim().to_string()),
         city: input.city.map(|c| c.trim().to_string()),
         profile_photo: input.profile_photo,
@@ -124,9 +130,7 @@ pub async fn upgrade_to_seller(
 ) -> Result<ProfileResponse, AppError> {
     // Validasi business_name tidak boleh kosong
     if input.business_name.trim().is_empty() {
-        return Err(AppError::ValidationError(
-            "Nama bisnis tidak boleh kosong".to_string(),
-        ));
+        return Err(FieldError::new("business_name", "Nama bisnis tidak boleh kosong").into());
     }
 
     // Load user untuk cek status
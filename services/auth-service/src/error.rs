@@ -1,9 +1,12 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
+use shared::utils::request_id;
+use shared::utils::validation::{errors_by_field, FieldError, FromFieldErrors};
+use std::collections::BTreeMap;
 use std::fmt;
 
 // Struktur response error yang konsisten untuk semua endpoint
@@ -13,6 +16,13 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    // Pesan error per field untuk validasi form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<BTreeMap<String, String>>,
+    // Detik sampai client boleh mencoba lagi (sama dengan header Retry-After)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    pub request_id: String,
 }
 
 // Enum untuk semua jenis error yang mungkin terjadi di aplikasi
@@ -20,12 +30,12 @@ pub struct ErrorResponse {
 pub enum AppError {
     DatabaseError(sqlx::Error),
     RedisError(redis::RedisError),
-    ValidationError(String),
+    ValidationError { message: String, errors: Vec<FieldError> },
     AuthenticationError(String),
     AuthorizationError(String),
     NotFoundError(String),
     ConflictError(String),
-    RateLimitError(String),
+    RateLimitError { message: String, retry_after_secs: u64 },
     InternalError(String),
     EmailError(String),
     TokenError(String),
@@ -36,12 +46,12 @@ impl fmt::Display for AppError {
         match self {
             AppError::DatabaseError(e) => write!(f, "Database error: {}", e),
             AppError::RedisError(e) => write!(f, "Redis error: {}", e),
-            AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+            AppError::ValidationError { message, .. } => write!(f, "Validation error: {}", message),
             AppError::AuthenticationError(msg) => write!(f, "Authentication error: {}", msg),
             AppError::AuthorizationError(msg) => write!(f, "Authorization error: {}", msg),
             AppError::NotFoundError(msg) => write!(f, "Not found: {}", msg),
             AppError::ConflictError(msg) => write!(f, "Conflict: {}", msg),
-            AppError::RateLimitError(msg) => write!(f, "Rate limit exceeded: {}", msg),
+            AppError::RateLimitError { message, .. } => write!(f, "Rate limit exceeded: {}", message),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
             AppError::EmailError(msg) => write!(f, "Email error: {}", msg),
             AppError::TokenError(msg) => write!(f, "Token error: {}", msg),
@@ -51,6 +61,20 @@ impl fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
+// Error validasi per field (AppError::fields dari shared)
+impl FromFieldErrors for AppError {
+    fn from_field_errors(message: String, errors: Vec<FieldError>) -> Self {
+        AppError::ValidationError { message, errors }
+    }
+}
+
+// Konversi dari error validasi satu field
+impl From<FieldError> for AppError {
+    fn from(err: FieldError) -> Self {
+        AppError::fields(vec![err])
+    }
+}
+
 // Konversi dari sqlx::Error ke AppError
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
@@ -72,6 +96,17 @@ impl From<jsonwebtoken::errors::Error> for AppError {
     }
 }
 
+// Konversi dari error autentikasi bersama (extractor AuthAdmin)
+impl From<shared::auth::AuthError> for AppError {
+    fn from(err: shared::auth::AuthError) -> Self {
+        match err.status_code() {
+            StatusCode::FORBIDDEN => AppError::authorization(err.to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR => AppError::internal(err.to_string()),
+            _ => AppError::authentication(err.to_string()),
+        }
+    }
+}
+
 // Implementasi IntoResponse untuk AppError agar bisa langsung digunakan sebagai response di axum
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
@@ -102,10 +137,10 @@ impl IntoResponse for AppError {
                     },
                 )
             }
-            AppError::ValidationError(msg) => (
+            AppError::ValidationError { message, .. } => (
                 StatusCode::BAD_REQUEST,
                 "validation_error",
-                msg.as_str(),
+                message.as_str(),
                 None,
             ),
             AppError::AuthenticationError(msg) => (
@@ -126,10 +161,10 @@ impl IntoResponse for AppError {
             AppError::ConflictError(msg) => {
                 (StatusCode::CONFLICT, "conflict", msg.as_str(), None)
             }
-            AppError::RateLimitError(msg) => (
+            AppError::RateLimitError { message, .. } => (
                 StatusCode::TOO_MANY_REQUESTS,
                 "rate_limit_exceeded",
-                msg.as_str(),
+                message.as_str(),
                 None,
             ),
             AppError::InternalError(msg) => {
@@ -170,13 +205,36 @@ impl IntoResponse for AppError {
             ),
         };
 
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
         let error_response = ErrorResponse {
             error: error_type.to_string(),
             message: message.to_string(),
             details,
+            errors: match &self {
+                AppError::ValidationError { errors, .. } if !errors.is_empty() => {
+                    Some(errors_by_field(errors))
+                }
+                _ => None,
+            },
+            retry_after: match &self {
+                AppError::RateLimitError { retry_after_secs, .. } => Some(*retry_after_secs),
+                _ => None,
+            },
+            request_id: request_id.clone(),
         };
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        // Beri tahu client kapan boleh mencoba lagi
+        if let AppError::RateLimitError { retry_after_secs, .. } = &self {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(*retry_after_secs));
+        }
+
+        response
     }
 }
 
@@ -184,7 +242,10 @@ impl IntoResponse for AppError {
 impl AppError {
     // Buat error validasi dengan pesan custom
     pub fn validation(msg: impl Into<String>) -> Self {
-        AppError::ValidationError(msg.into())
+        AppError::ValidationError {
+            message: msg.into(),
+            errors: Vec::new(),
+        }
     }
 
     // Buat error authentication dengan pesan custom
@@ -197,14 +258,22 @@ impl AppError {
         AppError::AuthorizationError(msg.into())
     }
 
+    // Buat error not found dengan pesan custom
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        AppError::NotFoundError(msg.into())
+    }
+
     // Buat error conflict dengan pesan custom
     pub fn conflict(msg: impl Into<String>) -> Self {
         AppError::ConflictError(msg.into())
     }
 
-    // Buat error rate limit dengan pesan custom
-    pub fn rate_limit(msg: impl Into<String>) -> Self {
-        AppError::RateLimitError(msg.into())
+    // Buat error rate limit dengan Retry-After (detik, minimal 1)
+    pub fn rate_limit(msg: impl Into<String>, retry_after_secs: u64) -> Self {
+        AppError::RateLimitError {
+            message: msg.into(),
+            retry_after_secs: retry_after_secs.max(1),
+        }
     }
 
     // Buat error internal dengan pesan custom
@@ -233,3 +302,33 @@ impl From<&str> for AppError {
 
 // Type alias untuk Result dengan AppError sebagai error type
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_sets_retry_after_header() {
+        let response = AppError::rate_limit("Tunggu 60 detik sebelum request OTP lagi.", 42).into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "42");
+    }
+
+    #[test]
+    fn test_other_errors_have_no_retry_after() {
+        let response = AppError::not_found("User tidak ditemukan").into_response();
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("User tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
+    }
+}
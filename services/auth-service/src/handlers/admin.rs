@@ -0,0 +1,208 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    config::AppState,
+    domain::admin::{
+        self as admin_domain, AdminUserActionResponse, AdminUserListResponse, AdminUserSearchInput,
+        AuthEventListResponse, AuthEventSearchInput,
+    },
+    error::AppResult,
+    middleware::auth_extractor::AuthAdmin,
+    models::auth_event::AuthEventType,
+};
+
+// ===== REQUEST DTOs =====
+
+/// Query untuk daftar user admin
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct AdminUserQuery {
+    /// Cari berdasarkan email, nama, atau nomor telepon
+    pub search: Option<String>,
+    /// Filter status akun
+    pub is_active: Option<bool>,
+    /// Jumlah data per halaman (maks 100)
+    pub limit: Option<i64>,
+    /// Offset pagination
+    pub offset: Option<i64>,
+}
+
+/// Query untuk riwayat auth event user
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct AuthEventQuery {
+    /// Filter jenis event (login_success, login_failed, logout, otp_blocked)
+    pub event_type: Option<AuthEventType>,
+    /// Jumlah data per halaman (maks 100)
+    pub limit: Option<i64>,
+    /// Offset pagination
+    pub offset: Option<i64>,
+}
+
+// ===== HANDLER FUNCTIONS =====
+
+/// Search and paginate users (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    params(AdminUserQuery),
+    responses(
+        (status = 200, description = "Successfully retrieved users", body = AdminUserListResponse),
+        (status = 403, description = "Admin access required"),
+    ),
+    tag = "Admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_users_handler(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Query(query): Query<AdminUserQuery>,
+) -> AppResult<impl IntoResponse> {
+    let input = AdminUserSearchInput {
+        search: query.search,
+        is_active: query.is_active,
+        limit: query.limit,
+        offset: query.offset,
+    };
+
+    let response = admin_domain::search_users(&state, input).await?;
+
+    tracing::info!("Admin {} listed users ({} total)", admin.user_id, response.total);
+
+    Ok(Json(response))
+}
+
+/// Deactivate a user account, revoke its sessions and disconnect chat WebSockets (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/deactivate",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User deactivated", body = AdminUserActionResponse),
+        (status = 400, description = "Admin cannot deactivate own account"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "User already inactive"),
+    ),
+    tag = "Admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn deactivate_user_handler(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    headers: HeaderMap,
+    Path(user_id): Path<i32>,
+) -> AppResult<impl IntoResponse> {
+    // Token admin diteruskan ke chat-service yang juga mensyaratkan admin
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+
+    let response = admin_domain::deactivate_user(&state, admin.user_id, user_id, authorization).await?;
+
+    Ok(Json(response))
+}
+
+/// Reactivate a deactivated user account (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/reactivate",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User reactivated", body = AdminUserActionResponse),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "User already active"),
+    ),
+    tag = "Admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn reactivate_user_handler(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(user_id): Path<i32>,
+) -> AppResult<impl IntoResponse> {
+    let response = admin_domain::reactivate_user(&state, admin.user_id, user_id).await?;
+
+    Ok(Json(response))
+}
+
+/// Clear a user's OTP block and Redis OTP counters (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/clear-otp-block",
+    params(
+        ("id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "OTP block cleared", body = AdminUserActionResponse),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "User not found"),
+    ),
+    tag = "Admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn clear_otp_block_handler(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(user_id): Path<i32>,
+) -> AppResult<impl IntoResponse> {
+    let response = admin_domain::clear_otp_block(&state, admin.user_id, user_id).await?;
+
+    Ok(Json(response))
+}
+
+/// List a user's login, logout and OTP block history (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/auth-events",
+    params(
+        ("id" = i32, Path, description = "User ID"),
+        AuthEventQuery
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved auth events", body = AuthEventListResponse),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "User not found"),
+    ),
+    tag = "Admin",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_auth_events_handler(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(user_id): Path<i32>,
+    Query(query): Query<AuthEventQuery>,
+) -> AppResult<impl IntoResponse> {
+    let input = AuthEventSearchInput {
+        event_type: query.event_type,
+        limit: query.limit,
+        offset: query.offset,
+    };
+
+    let response = admin_domain::list_auth_events(&state, user_id, input).await?;
+
+    tracing::info!("Admin {} reviewed auth events of user {} ({} total)",
+                   admin.user_id, user_id, response.total);
+
+    Ok(Json(response))
+}
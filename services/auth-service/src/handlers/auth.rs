@@ -14,6 +14,7 @@ use crate::{
         RegisterResponse, UserData,
     },
     error::{AppError, AppResult},
+    utils::{otp::OtpDeliveryStatus, otp_channel::OtpChannel},
 };
 
 // ===== REQUEST DTOs =====
@@ -75,6 +76,16 @@ pub struct ResendOtpRequest {
 }
 
 
+/// Response resend OTP
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResendOtpResponse {
+    #[schema(example = "OTP baru telah dikirim via email.")]
+    pub message: String,
+    /// Request id baru untuk GET /api/auth/otp/status/{otp_request_id}
+    #[schema(example = "3f2b9c1e8d7a4f6b9e0c1d2a3b4c5d6e")]
+    pub otp_request_id: String,
+}
+
 /// Request body untuk logout
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct LogoutRequest {
@@ -94,10 +105,16 @@ pub struct MessageResponse {
 /// Response login step 1
 #[derive(Debug, Serialize, ToSchema)]
 pub struct LoginStep1Response {
-    #[schema(example = "OTP telah dikirim ke email Anda")]
+    #[schema(example = "OTP telah dikirim via SMS. Kode berlaku 5 menit.")]
     pub message: String,
     #[schema(example = 1)]
     pub user_id: i32,
+    /// Status pengiriman OTP; juga bisa dicek via GET /api/auth/otp/status/{otp_request_id}
+    pub otp_delivery: OtpDeliveryStatus,
+    #[schema(example = "3f2b9c1e8d7a4f6b9e0c1d2a3b4c5d6e")]
+    pub otp_request_id: String,
+    /// Channel yang berhasil mengirim OTP (sesuai urutan fallback OTP_CHANNELS)
+    pub otp_channel: OtpChannel,
 }
 
 /// Response login step 2 (dengan tokens)
@@ -278,15 +295,16 @@ pub async fn resend_verification_handler(
     Ok(Json(response))
 }
 
-/// Login step 1: Validasi email+password dan kirim OTP ke email
+/// Login step 1: Validasi email+password dan kirim OTP (SMS/email sesuai OTP_CHANNELS)
 #[utoipa::path(
     post,
     path = "/api/auth/login",
     request_body = LoginRequestBody,
     responses(
-        (status = 200, description = "OTP berhasil dikirim ke email", body = LoginStep1Response),
+        (status = 200, description = "OTP berhasil dikirim", body = LoginStep1Response),
         (status = 400, description = "Email atau password salah"),
-        (status = 403, description = "Email belum diverifikasi")
+        (status = 403, description = "Email belum diverifikasi"),
+        (status = 500, description = "OTP gagal dikirim lewat semua channel")
     ),
     tag = "Authentication"
 )]
@@ -306,11 +324,14 @@ pub async fn login_step1_handler(
     };
 
     // Call domain layer untuk validasi dan kirim OTP
-    let user_id = auth_domain::login_step1_send_otp(&state, input, ip_address, user_agent).await?;
+    let sent = auth_domain::login_step1_send_otp(&state, input, ip_address, user_agent).await?;
 
     let response = LoginStep1Response {
-        message: "OTP telah dikirim ke email Anda. Kode berlaku 5 menit.".to_string(),
-        user_id,
+        message: format!("OTP telah dikirim via {}. Kode berlaku 5 menit.", sent.channel.label()),
+        user_id: sent.user_id,
+        otp_delivery: OtpDeliveryStatus::Sent,
+        otp_request_id: sent.request_id,
+        otp_channel: sent.channel,
     };
 
     Ok(Json(response))
@@ -363,7 +384,7 @@ pub async fn login_step2_handler(
     path = "/api/auth/resend-otp",
     request_body = ResendOtpRequest,
     responses(
-        (status = 200, description = "OTP baru berhasil dikirim ke email", body = MessageResponse),
+        (status = 200, description = "OTP baru berhasil dikirim ke email", body = ResendOtpResponse),
         (status = 400, description = "User ID tidak valid atau tidak dalam proses login"),
         (status = 429, description = "Cooldown aktif, tunggu 60 detik sebelum request ulang")
     ),
@@ -379,10 +400,11 @@ pub async fn resend_otp_handler(
     let user_agent = extract_user_agent(&headers);
 
     // Resend OTP melalui domain layer (includes cooldown 60 detik)
-    auth_domain::resend_otp(&state, req.user_id, ip_address, user_agent).await?;
+    let sent = auth_domain::resend_otp(&state, req.user_id, ip_address, user_agent).await?;
 
-    let response = MessageResponse {
-        message: "OTP baru telah dikirim ke email Anda.".to_string(),
+    let response = ResendOtpResponse {
+        message: format!("OTP baru telah dikirim via {}.", sent.channel.label()),
+        otp_request_id: sent.request_id,
     };
 
     Ok(Json(response))
@@ -444,11 +466,15 @@ pub async fn logout_handler(
     // Ekstrak access token dari Authorization header untuk blacklist compliance
     let access_token = extract_bearer_token_from_header(&headers)?;
 
+    // Ekstrak IP dan user agent untuk riwayat keamanan
+    let ip_address = extract_ip_address(&headers);
+    let user_agent = extract_user_agent(&headers);
+
     // Execute logout dengan keamanan enterprise compliance
-    auth_domain::logout(&state, &req.refresh_token).await?;
+    auth_domain::logout(&state, &req.refresh_token, ip_address, user_agent).await?;
 
     // Ekstrak dan blacklist access token
-    if let Ok(claims) = crate::utils::jwt::validate_token_signature(&access_token, &state.config.jwt_secret) {
+    if let Ok(claims) = crate::utils::jwt::validate_token_signature(&access_token, &state.config.jwt, crate::utils::jwt::TokenType::Access) {
         // Blacklist access token via secure function
         let _: Option<bool> = sqlx::query_scalar::<_, bool>(
             "SELECT blacklist_token($1, $2, $3)"
@@ -2,4 +2,5 @@
 pub mod auth;
 pub mod user;
 pub mod session;
-pub mod otp;
\ No newline at end of file
+pub mod otp;
+pub mod admin;
\ No newline at end of file
@@ -1,5 +1,5 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::HeaderMap,
     response::IntoResponse,
     Json,
@@ -10,9 +10,11 @@ use utoipa::ToSchema;
 
 use crate::{
     config::AppState,
+    domain::auth as auth_domain,
     error::AppResult,
     middleware::auth::extract_authenticated_user,
     models::user::User,
+    utils::otp::OtpDeliveryStatus,
 };
 
 /// Response untuk OTP status check
@@ -27,6 +29,15 @@ pub struct OtpStatusResponse {
     pub message: String,
 }
 
+/// Response status pengiriman email OTP
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OtpDeliveryStatusResponse {
+    #[schema(example = "3f2b9c1e8d7a4f6b9e0c1d2a3b4c5d6e")]
+    pub request_id: String,
+    pub status: OtpDeliveryStatus,
+    #[schema(example = "OTP sudah terkirim.")]
+    pub message: String,
+}
 
 /// Check OTP status for current user
 #[utoipa::path(
@@ -45,7 +56,7 @@ pub async fn check_otp_status_handler(
     headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
@@ -96,4 +107,38 @@ pub async fn check_otp_status_handler(
     );
 
     Ok(Json(response))
-}
\ No newline at end of file
+}
+
+/// Cek status pengiriman email OTP (dipakai UI untuk menampilkan tombol resend).
+/// Dikunci dengan otp_request_id acak dari login step 1 / resend OTP, bukan user id,
+/// agar keberadaan akun dan status OTP tidak bisa dienumerasi
+#[utoipa::path(
+    get,
+    path = "/api/auth/otp/status/{request_id}",
+    params(
+        ("request_id" = String, Path, description = "otp_request_id dari response login step 1 atau resend OTP")
+    ),
+    responses(
+        (status = 200, description = "Status pengiriman OTP", body = OtpDeliveryStatusResponse),
+    ),
+    tag = "Authentication"
+)]
+pub async fn otp_delivery_status_handler(
+    State(state): State<AppState>,
+    Path(request_id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let status = auth_domain::get_otp_delivery_status(&state, &request_id).await?;
+
+    let message = match status {
+        OtpDeliveryStatus::Queued => "OTP sedang dikirim. Mohon tunggu sebentar.",
+        OtpDeliveryStatus::Sent => "OTP sudah terkirim.",
+        OtpDeliveryStatus::Failed => "OTP gagal dikirim. Silakan kirim ulang OTP.",
+        OtpDeliveryStatus::Unknown => "Tidak ada pengiriman OTP yang aktif. Silakan login ulang.",
+    };
+
+    Ok(Json(OtpDeliveryStatusResponse {
+        request_id,
+        status,
+        message: message.to_string(),
+    }))
+}
@@ -68,7 +68,7 @@ pub async fn get_sessions_handler(
     headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
@@ -113,7 +113,7 @@ pub async fn invalidate_session_handler(
     Path(session_id): Path<i32>,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
@@ -144,7 +144,7 @@ pub async fn invalidate_all_sessions_handler(
     headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
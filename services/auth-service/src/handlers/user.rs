@@ -74,7 +74,7 @@ pub async fn get_profile_handler(
     headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
@@ -103,7 +103,7 @@ pub async fn update_profile_handler(
     Json(req): Json<UpdateProfileRequestBody>,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
@@ -141,7 +141,7 @@ pub async fn upgrade_to_seller_handler(
     Json(req): Json<UpgradeToSellerRequestBody>,
 ) -> AppResult<impl IntoResponse> {
     // Extract user dari JWT token
-    let auth_user = extract_authenticated_user(&headers, &state.config.jwt_secret, &state.db)
+    let auth_user = extract_authenticated_user(&headers, &state.config.jwt, &state.db)
         .await
         .map_err(|(_status, msg)| crate::error::AppError::authentication(&msg))?;
 
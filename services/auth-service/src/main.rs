@@ -1,8 +1,7 @@
 use axum::Router;
 use dotenvy::dotenv;
-use std::net::SocketAddr;
 use tokio::signal;
-use tower_http::trace::TraceLayer;
+use shared::utils::{bind_addr, logging, request_id, request_timeout, startup_gate::StartupGate};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
@@ -17,6 +16,7 @@ mod utils;
 
 use config::{AppConfig, AppState};
 use error::AppResult;
+use shared::utils::health::HealthLevel;
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
@@ -32,7 +32,7 @@ async fn main() -> AppResult<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "auth_service=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(logging::fmt_layer())
         .init();
 
     tracing::info!("🚀 Starting Big Auto - Auth Service");
@@ -51,6 +51,26 @@ async fn main() -> AppResult<()> {
         tracing::warn!("⚠️  SECURITY WARNING: Using default JWT secret in production!");
     }
 
+    // Server address dari config (AUTH_SERVICE_HOST/AUTH_SERVICE_PORT, sudah divalidasi)
+    let addr = bind_addr::resolve("AUTH_SERVICE_HOST", &config.server_host, config.server_port)
+        .map_err(error::AppError::InternalError)?;
+
+    // Listener dibind sebelum koneksi database/Redis, request dijawab 503 sampai state siap
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let gate = StartupGate::new(&["/health"]);
+    let server = tokio::spawn({
+        let router = gate.router();
+        async move {
+            axum::serve(listener, router)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+        }
+    });
+
+    tracing::info!("🎧 Server listening on {}", addr);
+    tracing::info!("📚 Swagger UI available at http://localhost:{}/swagger-ui", config.server_port);
+    tracing::info!("📖 Health check available at http://localhost:{}/health", config.server_port);
+
     // Initialize application state (database pool + redis connection)
     tracing::info!("🔌 Connecting to database and Redis...");
     let state = AppState::new()
@@ -64,7 +84,7 @@ async fn main() -> AppResult<()> {
     let health = state.health_check().await;
     tracing::info!("💚 Health Check: {:?}", health);
 
-    if health.overall != "healthy" {
+    if health.overall != HealthLevel::Healthy {
         tracing::warn!("⚠️ Some services are not fully healthy: {:?}", health);
     }
 
@@ -80,33 +100,22 @@ async fn main() -> AppResult<()> {
 
     // Create application router with all routes
     let app = create_app(state);
-
-    // Create server address dari config
-    let addr = format!("{}:{}", config.server_host, config.server_port);
-    let addr = addr.parse::<SocketAddr>().unwrap_or_else(|_| {
-        SocketAddr::from(([0, 0, 0, 0], config.server_port))
-    });
-
-    tracing::info!("🎧 Server listening on {}", addr);
-    tracing::info!("📚 Swagger UI available at http://localhost:{}/swagger-ui", config.server_port);
-    tracing::info!("📖 Health check available at http://localhost:{}/health", config.server_port);
-
-    // Start server with graceful shutdown
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    gate.ready(app);
     tracing::info!("✅ Auth Service is ready to accept requests!");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    // Tunggu server selesai (graceful shutdown)
+    server.await.unwrap().unwrap();
 
     Ok(())
 }
 
 /// Create application 
 fn create_app(state: AppState) -> Router {
-    routes::create_router(state)
-        .layer(TraceLayer::new_for_http())
+    request_id::apply(
+        routes::create_router(state)
+            // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+            .layer(request_timeout::layer(request_timeout::default_timeout())),
+    )
 }
 
 /// Graceful shutdown signal handler
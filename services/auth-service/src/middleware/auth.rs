@@ -4,13 +4,14 @@ use sqlx::PgPool;
 use crate::{
     handlers::user::AuthenticatedUser,
     models::user::User,
-    utils::jwt::validate_token,
+    utils::jwt::{validate_token, TokenType},
 };
+use shared::auth::JwtConfig;
 
 /// Ekstrak dan validasi JWT token dari Authorization header
 pub async fn extract_authenticated_user(
     headers: &HeaderMap,
-    jwt_secret: &str,
+    jwt_config: &JwtConfig,
     db: &PgPool,
 ) -> Result<AuthenticatedUser, (StatusCode, String)> {
     // Ekstrak Bearer token menggunakan shared library
@@ -30,7 +31,7 @@ pub async fn extract_authenticated_user(
     }
 
     // Validasi JWT signature dan blacklist check
-    let claims = validate_token(&token, jwt_secret, db)
+    let claims = validate_token(&token, jwt_config, db, TokenType::Access)
         .await
         .map_err(|msg| (StatusCode::UNAUTHORIZED, msg))?;
 
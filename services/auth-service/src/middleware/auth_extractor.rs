@@ -9,6 +9,8 @@ use axum::{
 use serde_json::json;
 
 use crate::config::AppState;
+use shared::auth::AdminState;
+use crate::error::AppError;
 use crate::middleware::auth::extract_authenticated_user;
 use crate::handlers::user::AuthenticatedUser;
 
@@ -26,7 +28,7 @@ pub async fn jwt_auth_extractor_middleware(
     }
 
     // Ekstrak dan validasi JWT dengan enterprise security
-    let user_data = extract_authenticated_user(request.headers(), &state.config.jwt_secret, &state.db)
+    let user_data = extract_authenticated_user(request.headers(), &state.config.jwt, &state.db)
         .await
         .map_err(|(status, message)| create_json_error_response(status, &message))?;
 
@@ -84,4 +86,20 @@ where
             Err(create_json_error_response(StatusCode::UNAUTHORIZED, "Authentication required - JWT token missing or invalid"))
         }
     }
-}
\ No newline at end of file
+}
+
+// Admin platform terautentikasi (manajemen user), flag is_admin dicek oleh shared::auth
+pub use shared::auth::AuthAdmin;
+
+impl AdminState for AppState {
+    type Rejection = AppError;
+
+    fn admin_db(&self) -> &sqlx::PgPool {
+        &self.db
+    }
+
+    // JWT middleware auth-service menginject AuthenticatedUser, bukan AuthUser bersama
+    fn authenticated_user_id(parts: &Parts) -> Option<i32> {
+        parts.extensions.get::<AuthenticatedUser>().map(|user| user.user_id)
+    }
+}
@@ -1,26 +1,21 @@
 // JWT-Only CORS Configuration untuk Auth Service
 
-use axum::http::{header, HeaderValue, Method};
+use axum::http::{header, Method};
+use shared::utils::cors::CorsPolicy;
 use tower_http::cors::CorsLayer;
 
 /// Build CORS
 pub fn configure_cors() -> CorsLayer {
-    let frontend_urls = std::env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable harus diset");
-
-    let allowed_origins: Vec<HeaderValue> = frontend_urls
-        .split(',')
-        .map(|s| s.trim().parse::<HeaderValue>().expect("Invalid FRONTEND_URL format"))
-        .collect();
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     let allowed_methods = build_allowed_methods();
     let allowed_headers = build_jwt_headers();
 
-    CorsLayer::new()
-        .allow_origin(allowed_origins)
+    policy
+        .apply(CorsLayer::new())
         .allow_methods(allowed_methods)
         .allow_headers(allowed_headers)
-        .allow_credentials(false)
         .max_age(build_cors_max_age())
 }
 
@@ -2,13 +2,14 @@
 
 use axum::{
     extract::{Request, State},
-    http::{StatusCode, header},
+    http::header,
     middleware::Next,
     response::Response,
 };
 use serde::{Deserialize, Serialize};
+use shared::utils::rate_limit::too_many_requests;
 use std::env;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Rate Limiter structure dengan Redis
 #[derive(Clone)]
@@ -87,8 +88,8 @@ impl AuthRateLimiter {
         }
     }
 
-    /// Check rate limit menggunakan Redis
-    pub async fn check_rate_limit(&self, key: &str, role: &str, endpoint: &str) -> bool {
+    /// Check rate limit menggunakan Redis, Err berisi waktu reset window (unix detik)
+    pub async fn check_rate_limit(&self, key: &str, role: &str, endpoint: &str) -> Result<(), u64> {
         let max_requests = self.get_rate_limit_for_role(role, endpoint);
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -103,7 +104,7 @@ impl AuthRateLimiter {
             Ok(conn) => conn,
             Err(e) => {
                 tracing::error!("Redis connection failed: {}. Failing open for security.", e);
-                return true; 
+                return Ok(());
             }
         };
 
@@ -116,7 +117,7 @@ impl AuthRateLimiter {
             Ok(data) => data,
             Err(e) => {
                 tracing::warn!("Redis GET failed: {}. Failing open for security.", e);
-                return true;
+                return Ok(());
             }
         };
 
@@ -128,7 +129,7 @@ impl AuthRateLimiter {
                     if data.window_start == window_start {
                         // Same window - check count
                         if data.count >= max_requests {
-                            return false; 
+                            return Err(window_start + self.window_seconds);
                         } else {
                             // Increment count
                             let new_data = RateLimitData {
@@ -147,7 +148,7 @@ impl AuthRateLimiter {
                                 tracing::warn!("Redis SET failed: {}. Continuing for safety.", e);
                             }
 
-                            return true;
+                            return Ok(());
                         }
                     }
                 }
@@ -174,7 +175,7 @@ impl AuthRateLimiter {
             tracing::warn!("Redis SET failed: {}. Continuing for safety.", e);
         }
 
-        true
+        Ok(())
     }
 }
 
@@ -214,7 +215,7 @@ pub async fn auth_rate_limit_middleware(
         if let Ok(auth_str) = auth_header.to_str() {
             if auth_str.starts_with("Bearer ") {
                 let token = &auth_str[7..];
-                match crate::utils::jwt::validate_token(token, &state.config.jwt_secret, &state.db).await {
+                match crate::utils::jwt::validate_token(token, &state.config.jwt, &state.db, crate::utils::jwt::TokenType::Access).await {
                     Ok(claims) => claims.role,
                     Err(_) => "guest".to_string(),
                 }
@@ -232,28 +233,17 @@ pub async fn auth_rate_limit_middleware(
     let rate_limit_key = format!("{}:{}", client_ip, user_role);
 
     // Check rate limit dengan Redis backend 
-    if !state.rate_limiter.check_rate_limit(&rate_limit_key, &user_role, endpoint).await {
+    if let Err(reset_time) = state.rate_limiter.check_rate_limit(&rate_limit_key, &user_role, endpoint).await {
         tracing::warn!("Rate limit exceeded for IP: {} with role: {} on endpoint: {}",
             client_ip, user_role, endpoint);
 
-        let mut response = axum::response::Response::new(axum::body::Body::from("Rate limit exceeded"));
-        *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
-
-        let headers = response.headers_mut();
-        headers.insert("Retry-After", "60".parse().unwrap());
-        headers.insert("X-RateLimit-Limit",
+        // Retry-After dihitung dari reset window limiter
+        let mut response = too_many_requests(reset_time, serde_json::json!({
+            "error": "rate_limit_exceeded",
+            "message": "Terlalu banyak permintaan. Silakan coba lagi nanti."
+        }));
+        response.headers_mut().insert("X-RateLimit-Limit",
             state.rate_limiter.get_rate_limit_for_role(&user_role, endpoint).to_string().parse().unwrap());
-        headers.insert("X-RateLimit-Remaining", "0".parse().unwrap());
-        headers.insert(
-            "X-RateLimit-Reset",
-            (std::time::SystemTime::now() + Duration::from_secs(60))
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                .to_string()
-                .parse()
-                .unwrap(),
-        );
 
         return Ok(response);
     }
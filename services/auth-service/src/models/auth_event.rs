@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use utoipa::ToSchema;
+
+// Event keamanan auth disimpan di audit_logs dengan entity_type ini
+pub const AUTH_EVENT_ENTITY: &str = "auth_event";
+
+// Jenis event keamanan akun yang bisa direview admin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthEventType {
+    LoginSuccess,
+    LoginFailed,
+    Logout,
+    OtpBlocked,
+}
+
+impl AuthEventType {
+    // Nama action di audit_logs
+    pub fn audit_action(&self) -> &'static str {
+        match self {
+            AuthEventType::LoginSuccess => "AUTH_LOGIN_SUCCESS",
+            AuthEventType::LoginFailed => "AUTH_LOGIN_FAILED",
+            AuthEventType::Logout => "AUTH_LOGOUT",
+            AuthEventType::OtpBlocked => "AUTH_OTP_BLOCKED",
+        }
+    }
+}
+
+// Data event baru yang akan dicatat
+#[derive(Debug)]
+pub struct NewAuthEvent {
+    pub user_id: i32,
+    pub event_type: AuthEventType,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+    pub endpoint: &'static str,
+}
+
+// Satu event auth untuk riwayat keamanan user
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct AuthEventRecord {
+    #[schema(example = 1)]
+    pub id: i32,
+    #[schema(example = "AUTH_LOGIN_FAILED")]
+    pub action: String,
+    #[schema(example = "203.0.113.10")]
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: Option<serde_json::Value>,
+    pub endpoint: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// Kolom ip_address bertipe INET: IP yang tidak bisa di-parse disimpan NULL agar insert tidak gagal
+pub fn sanitize_ip(ip_address: Option<&str>) -> Option<String> {
+    ip_address
+        .map(str::trim)
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+        .map(|ip| ip.to_string())
+}
+
+pub struct AuthEvent;
+
+impl AuthEvent {
+    // Catat event auth ke audit_logs
+    pub async fn record(pool: &PgPool, event: NewAuthEvent) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_logs (user_id, ip_address, user_agent, action, entity_type, entity_id, new_values, service_name, endpoint, http_method)
+            VALUES ($1, $2::INET, $3, $4, $5, $1, $6, 'auth-service', $7, 'POST')
+            "#
+        )
+        .bind(event.user_id)
+        .bind(sanitize_ip(event.ip_address.as_deref()))
+        .bind(event.user_agent)
+        .bind(event.event_type.audit_action())
+        .bind(AUTH_EVENT_ENTITY)
+        .bind(event.details)
+        .bind(event.endpoint)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Riwayat event auth user, terbaru dulu (index (entity_type, entity_id))
+    pub async fn list_for_user(
+        pool: &PgPool,
+        user_id: i32,
+        event_type: Option<AuthEventType>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuthEventRecord>, sqlx::Error> {
+        sqlx::query_as::<_, AuthEventRecord>(
+            r#"
+            SELECT id, action, host(ip_address) AS ip_address, user_agent, new_values AS details, endpoint, created_at
+            FROM audit_logs
+            WHERE entity_type = $1 AND entity_id = $2
+              AND ($3::TEXT IS NULL OR action = $3)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4 OFFSET $5
+            "#
+        )
+        .bind(AUTH_EVENT_ENTITY)
+        .bind(user_id)
+        .bind(event_type.map(|t| t.audit_action()))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Total event dengan filter yang sama seperti list_for_user
+    pub async fn count_for_user(
+        pool: &PgPool,
+        user_id: i32,
+        event_type: Option<AuthEventType>,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM audit_logs
+            WHERE entity_type = $1 AND entity_id = $2
+              AND ($3::TEXT IS NULL OR action = $3)
+            "#
+        )
+        .bind(AUTH_EVENT_ENTITY)
+        .bind(user_id)
+        .bind(event_type.map(|t| t.audit_action()))
+        .fetch_one(pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_ip() {
+        assert_eq!(sanitize_ip(Some("203.0.113.10")), Some("203.0.113.10".to_string()));
+        assert_eq!(sanitize_ip(Some(" 2001:db8::1 ")), Some("2001:db8::1".to_string()));
+        assert_eq!(sanitize_ip(Some("unknown")), None);
+        assert_eq!(sanitize_ip(None), None);
+    }
+
+    #[test]
+    fn test_event_type_query_value() {
+        let parsed: AuthEventType = serde_json::from_str("\"otp_blocked\"").unwrap();
+        assert_eq!(parsed, AuthEventType::OtpBlocked);
+        assert_eq!(parsed.audit_action(), "AUTH_OTP_BLOCKED");
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_list_returns_plain_ip(pool: PgPool) {
+        for ip in ["203.0.113.10", "2001:db8::1"] {
+            AuthEvent::record(&pool, NewAuthEvent {
+                user_id: 1,
+                event_type: AuthEventType::LoginFailed,
+                ip_address: Some(ip.to_string()),
+                user_agent: None,
+                details: serde_json::json!({}),
+                endpoint: "/api/auth/login",
+            })
+            .await
+            .unwrap();
+        }
+
+        let events = AuthEvent::list_for_user(&pool, 1, None, 10, 0).await.unwrap();
+        let ips: Vec<_> = events.iter().map(|e| e.ip_address.as_deref()).collect();
+
+        // Tanpa suffix netmask (/32, /128) dari cast INET ke TEXT
+        assert_eq!(ips, vec![Some("2001:db8::1"), Some("203.0.113.10")]);
+    }
+}
@@ -2,3 +2,4 @@ pub mod user;
 pub mod email_verification;
 pub mod login_otp;
 pub mod session;
+pub mod auth_event;
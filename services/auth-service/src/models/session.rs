@@ -178,6 +178,25 @@ impl UserSession {
         Ok(())
     }
 
+    // Nonaktifkan semua session aktif user (refresh token ikut mati), return access JTI untuk di-blacklist
+    pub async fn revoke_all_by_user(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i32,
+    ) -> Result<Vec<Option<String>>, sqlx::Error> {
+        sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            UPDATE user_sessions
+            SET is_active = false,
+                updated_at = NOW()
+            WHERE user_id = $1 AND is_active = true
+            RETURNING access_token_jti
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(tx.as_mut())
+        .await
+    }
+
     // Cek apakah session valid
     pub fn is_valid(&self) -> bool {
         self.is_active.unwrap_or(false) && self.expires_at > Utc::now()
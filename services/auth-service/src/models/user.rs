@@ -53,6 +53,23 @@ pub struct UpdateUserProfile {
 
 // Response model dengan guaranteed timestamps untuk API
 
+// Ringkasan user untuk panel admin (termasuk akun nonaktif)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct AdminUserRecord {
+    pub id: i32,
+    pub email: String,
+    pub name: String,
+    pub phone: String,
+    pub is_seller: Option<bool>,
+    pub is_admin: Option<bool>,
+    pub is_active: Option<bool>,
+    pub deactivated_at: Option<DateTime<Utc>>,
+    pub otp_request_count: Option<i32>,
+    pub otp_blocked_until: Option<DateTime<Utc>>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
 impl User {
     // Cari user berdasarkan email
     pub async fn find_by_email(pool: &PgPool, email: &str) -> Result<Option<Self>, sqlx::Error> {
@@ -284,4 +301,109 @@ impl User {
         }
     }
 
-}
\ No newline at end of file
+    // ===== ADMIN USER MANAGEMENT =====
+
+    // Cari user untuk admin: filter email/nama/telepon dan status aktif, tanpa mengecualikan akun nonaktif
+    pub async fn search_for_admin(
+        pool: &PgPool,
+        search: Option<&str>,
+        is_active: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AdminUserRecord>, sqlx::Error> {
+        sqlx::query_as::<_, AdminUserRecord>(
+            r#"
+            SELECT id, email, name, phone, is_seller, is_admin, is_active, deactivated_at,
+                   otp_request_count, otp_blocked_until, last_login_at, created_at
+            FROM users
+            WHERE ($1::TEXT IS NULL OR email ILIKE '%' || $1 || '%' OR name ILIKE '%' || $1 || '%' OR phone ILIKE '%' || $1 || '%')
+              AND ($2::BOOLEAN IS NULL OR COALESCE(is_active, true) = $2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3 OFFSET $4
+            "#
+        )
+        .bind(search)
+        .bind(is_active)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Total user dengan filter yang sama seperti search_for_admin
+    pub async fn count_for_admin(
+        pool: &PgPool,
+        search: Option<&str>,
+        is_active: Option<bool>,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM users
+            WHERE ($1::TEXT IS NULL OR email ILIKE '%' || $1 || '%' OR name ILIKE '%' || $1 || '%' OR phone ILIKE '%' || $1 || '%')
+              AND ($2::BOOLEAN IS NULL OR COALESCE(is_active, true) = $2)
+            "#
+        )
+        .bind(search)
+        .bind(is_active)
+        .fetch_one(pool)
+        .await
+    }
+
+    // Ambil user untuk aksi admin, termasuk yang sudah nonaktif
+    pub async fn find_for_admin(pool: &PgPool, user_id: i32) -> Result<Option<AdminUserRecord>, sqlx::Error> {
+        sqlx::query_as::<_, AdminUserRecord>(
+            r#"
+            SELECT id, email, name, phone, is_seller, is_admin, is_active, deactivated_at,
+                   otp_request_count, otp_blocked_until, last_login_at, created_at
+            FROM users
+            WHERE id = $1
+            "#
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    // Aktifkan/nonaktifkan akun (deactivated_at ikut diset/dikosongkan)
+    pub async fn set_active_status(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i32,
+        is_active: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET is_active = $2,
+                deactivated_at = CASE WHEN $2 THEN NULL ELSE NOW() END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(user_id)
+        .bind(is_active)
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    // Hapus blokir OTP dan reset counter request
+    pub async fn clear_otp_block(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET otp_blocked_until = NULL,
+                otp_request_count = 0,
+                updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(user_id)
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+}
@@ -5,6 +5,8 @@ use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::config::AppState;
+use shared::utils::health::{DependencyHealth, HealthLevel};
+use crate::error::AppError;
 use crate::middleware::{
     rate_limit::auth_rate_limit_middleware,
     cors::configure_cors,
@@ -50,6 +52,7 @@ impl Modify for SecurityAddon {
         crate::handlers::auth::logout_handler,
         // OTP endpoints
         crate::handlers::otp::check_otp_status_handler,
+        crate::handlers::otp::otp_delivery_status_handler,
         // Session endpoints
         crate::handlers::session::get_sessions_handler,
         crate::handlers::session::invalidate_session_handler,
@@ -58,6 +61,12 @@ impl Modify for SecurityAddon {
         crate::handlers::user::get_profile_handler,
         crate::handlers::user::update_profile_handler,
         crate::handlers::user::upgrade_to_seller_handler,
+        // Admin endpoints
+        crate::handlers::admin::list_users_handler,
+        crate::handlers::admin::deactivate_user_handler,
+        crate::handlers::admin::reactivate_user_handler,
+        crate::handlers::admin::clear_otp_block_handler,
+        crate::handlers::admin::list_auth_events_handler,
     ),
     modifiers(&SecurityAddon),
     components(
@@ -72,6 +81,7 @@ impl Modify for SecurityAddon {
             crate::handlers::auth::LogoutRequest,
             crate::handlers::auth::MessageResponse,
             crate::handlers::auth::LoginStep1Response,
+            crate::handlers::auth::ResendOtpResponse,
             crate::handlers::auth::LoginStep2Response,
             crate::handlers::auth::RefreshTokenResponse,
             crate::domain::auth::UserData,
@@ -88,9 +98,24 @@ impl Modify for SecurityAddon {
 
             // OTP DTOs
             crate::handlers::otp::OtpStatusResponse,
+            crate::handlers::otp::OtpDeliveryStatusResponse,
+            crate::utils::otp::OtpDeliveryStatus,
+            crate::utils::otp_channel::OtpChannel,
+
+            // Admin DTOs
+            crate::handlers::admin::AdminUserQuery,
+            crate::domain::admin::AdminUserListResponse,
+            crate::domain::admin::AdminUserActionResponse,
+            crate::models::user::AdminUserRecord,
+            crate::handlers::admin::AuthEventQuery,
+            crate::domain::admin::AuthEventListResponse,
+            crate::models::auth_event::AuthEventRecord,
+            crate::models::auth_event::AuthEventType,
 
             // Health Check
             HealthCheckResponse,
+            HealthLevel,
+            DependencyHealth,
         )
     )
 )]
@@ -109,6 +134,7 @@ fn create_public_routes(state: AppState) -> Router {
         .route("/api/auth/login", axum::routing::post(crate::handlers::auth::login_step1_handler))
         .route("/api/auth/verify-otp", axum::routing::post(crate::handlers::auth::login_step2_handler))
         .route("/api/auth/resend-otp", axum::routing::post(crate::handlers::auth::resend_otp_handler))
+        .route("/api/auth/otp/status/{request_id}", axum::routing::get(crate::handlers::otp::otp_delivery_status_handler))
         
 
         .with_state(state.clone())
@@ -136,6 +162,13 @@ fn create_jwt_protected_routes(state: AppState) -> Router {
         .route("/api/users/me", axum::routing::put(crate::handlers::user::update_profile_handler))
         .route("/api/users/me/upgrade-seller", axum::routing::post(crate::handlers::user::upgrade_to_seller_handler))
 
+        // Admin endpoints - JWT + is_admin (dicek di extractor AuthAdmin)
+        .route("/api/admin/users", axum::routing::get(crate::handlers::admin::list_users_handler))
+        .route("/api/admin/users/{id}/deactivate", axum::routing::post(crate::handlers::admin::deactivate_user_handler))
+        .route("/api/admin/users/{id}/reactivate", axum::routing::post(crate::handlers::admin::reactivate_user_handler))
+        .route("/api/admin/users/{id}/clear-otp-block", axum::routing::post(crate::handlers::admin::clear_otp_block_handler))
+        .route("/api/admin/users/{id}/auth-events", axum::routing::get(crate::handlers::admin::list_auth_events_handler))
+
         .with_state(state.clone())
         // Apply JWT middleware untuk protected routes
         .layer(axum::middleware::from_fn_with_state(
@@ -147,16 +180,21 @@ fn create_jwt_protected_routes(state: AppState) -> Router {
 /// Health check endpoint
 async fn health_check(
     axum::extract::State(state): axum::extract::State<AppState>,
-) -> axum::response::Json<serde_json::Value> {
+) -> axum::response::Json<HealthCheckResponse> {
     let health = state.health_check().await;
 
-    axum::response::Json(serde_json::json!({
-        "status": health.overall,
-        "service": "auth-service",
-        "version": env!("CARGO_PKG_VERSION"),
-        "database": health.database,
-        "redis": health.redis
-    }))
+    axum::response::Json(HealthCheckResponse {
+        status: health.overall,
+        service: "auth-service".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        database: health.database,
+        redis: health.redis,
+    })
+}
+
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
 }
 
 /// Create the main application router
@@ -166,6 +204,8 @@ pub fn create_router(state: AppState) -> Router {
         .merge(create_public_routes(state.clone()))
         .merge(create_jwt_protected_routes(state.clone()))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        // Endpoint tidak dikenal tetap JSON
+        .fallback(not_found_handler)
         // Apply CORS
         .layer(configure_cors())
         // Security headers
@@ -179,17 +219,14 @@ pub fn create_router(state: AppState) -> Router {
 
 
 
-/// Health check response
+/// Health check response, `status` adalah status terburuk dari semua dependency
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 struct HealthCheckResponse {
-    #[schema(example = "healthy")]
-    status: String,
+    status: HealthLevel,
     #[schema(example = "auth-service")]
     service: String,
     #[schema(example = "0.1.0")]
     version: String,
-    #[schema(example = "healthy")]
-    database: String,
-    #[schema(example = "healthy")]
-    redis: String,
+    database: DependencyHealth,
+    redis: DependencyHealth,
 }
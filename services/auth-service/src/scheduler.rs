@@ -1,6 +1,7 @@
 use crate::config::AppState;
 use crate::models::{email_verification::EmailVerification, login_otp::LoginOtp, session::UserSession};
-use std::time::Duration;
+use sqlx::PgPool;
+use shared::utils::scheduler::{run_job, spawn_job, JobSchedule, Ticker};
 
 /// Background scheduler untuk cleanup expired data
 pub struct CleanupScheduler {
@@ -14,105 +15,79 @@ impl CleanupScheduler {
 
     /// Start background cleanup tasks
     pub fn start(self) {
-        // Spawn task untuk cleanup email verifications
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(3600)); // Every 1 hour
+        // Cleanup data expired, default every 1 hour
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("AUTH_CLEANUP_INTERVAL_SECS", 3600)),
+            self.state.db,
+            |db| async move { run_cleanup(&db).await },
+        );
+    }
+}
 
-            loop {
-                interval.tick().await;
+// Satu tick cleanup (masing-masing dengan retry)
+async fn run_cleanup(db: &PgPool) {
+    tracing::info!("🧹 Running background cleanup tasks...");
 
-                tracing::info!("🧹 Running background cleanup tasks...");
+    let _ = run_job("Cleanup expired email verifications", 3, || EmailVerification::cleanup_expired(db)).await;
+    let _ = run_job("Cleanup expired OTPs", 3, || LoginOtp::cleanup_expired(db)).await;
+    let _ = run_job("Cleanup expired sessions", 3, || UserSession::cleanup_expired(db)).await;
+    let _ = run_job("Cleanup inactive sessions", 3, || UserSession::cleanup_inactive(db)).await;
+
+    tracing::info!("✅ Background cleanup tasks completed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn count(db: &PgPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
 
-                // Cleanup expired email verifications (with retry logic)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match EmailVerification::cleanup_expired(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} expired email verifications", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup email verifications after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_single_manual_tick_runs_cleanup(db: PgPool) {
+        sqlx::query(
+            "INSERT INTO email_verifications (user_id, token, email, expires_at) VALUES
+                (1, 'expired-token', 'customer@test.local', NOW() - INTERVAL '1 hour'),
+                (1, 'valid-token', 'customer@test.local', NOW() + INTERVAL '1 hour')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO login_otps (user_id, otp_code, otp_hash, expires_at) VALUES
+                (1, '******', 'hash-old', NOW() - INTERVAL '2 days'),
+                (1, '******', 'hash-new', NOW() + INTERVAL '5 minutes')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
 
-                // Cleanup expired OTPs (with retry logic)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match LoginOtp::cleanup_expired(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} expired OTPs", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup OTPs after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+        let (trigger, ticker) = Ticker::manual();
+        let handle = spawn_job(ticker, db.clone(), |db| async move { run_cleanup(&db).await });
 
-                // Cleanup expired sessions (with retry logic)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match UserSession::cleanup_expired(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} expired sessions", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup expired sessions after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+        // Belum ada tick, belum ada yang dihapus
+        assert_eq!(count(&db, "email_verifications").await, 2);
 
-                // Cleanup inactive sessions (with retry logic)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match UserSession::cleanup_inactive(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} inactive sessions", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup inactive sessions after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+        assert!(trigger.fire());
+        drop(trigger);
+        handle.await.unwrap();
 
-                tracing::info!("✅ Background cleanup tasks completed");
-            }
-        });
+        let tokens: Vec<String> = sqlx::query_scalar("SELECT token FROM email_verifications")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(tokens, vec!["valid-token".to_string()]);
+        assert_eq!(count(&db, "login_otps").await, 1);
     }
 }
@@ -1,27 +1,74 @@
 use serde_json::json;
 use std::env;
+use std::path::PathBuf;
+
+use crate::utils::email_template::{EmailTemplate, EmailTemplates, RenderedEmail, DEFAULT_LOCALE};
 
 #[derive(Debug, Clone)]
 pub struct EmailConfig {
     pub resend_api_key: String,
     pub email_from: String,
+    pub from_name: String,
+    // Dipakai untuk banner di email non-production
+    pub environment: String,
+    // Email hanya di-log (tidak dikirim), untuk test dan development
+    pub dry_run: bool,
+    pub templates: EmailTemplates,
 }
 
 impl EmailConfig {
     // Load konfigurasi email dari environment variables
     pub fn from_env() -> Result<Self, crate::error::AppError> {
+        let dry_run = env::var("EMAIL_DRY_RUN")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        // API key tidak dibutuhkan jika email tidak benar-benar dikirim
+        let resend_api_key = match env::var("RESEND_API_KEY") {
+            Ok(key) => key,
+            Err(_) if dry_run => String::new(),
+            Err(_) => return Err(crate::error::AppError::email("RESEND_API_KEY tidak ditemukan")),
+        };
+
+        let template_dir = env::var("EMAIL_TEMPLATE_DIR").ok().filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        let locale = env::var("EMAIL_LOCALE").unwrap_or_else(|_| DEFAULT_LOCALE.to_string());
+        let templates = EmailTemplates::load(template_dir.as_deref(), &locale)
+            .map_err(crate::error::AppError::email)?;
+
+        let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
+        reject_dry_run_in_production("EMAIL_DRY_RUN", dry_run, &environment)
+            .map_err(crate::error::AppError::email)?;
+
         Ok(EmailConfig {
-            resend_api_key: env::var("RESEND_API_KEY").map_err(|_| crate::error::AppError::email("RESEND_API_KEY tidak ditemukan"))?,
+            resend_api_key,
             email_from: env::var("RESEND_FROM_EMAIL").unwrap_or_else(|_| "onboarding@resend.dev".to_string()),
+            from_name: env::var("EMAIL_FROM_NAME").unwrap_or_else(|_| "Big Auto".to_string()),
+            environment,
+            dry_run,
+            templates,
         })
     }
+
+    // Format "Nama <alamat>" untuk field from
+    pub fn sender(&self) -> String {
+        if self.from_name.trim().is_empty() {
+            self.email_from.clone()
+        } else {
+            format!("{} <{}>", self.from_name.trim(), self.email_from)
+        }
+    }
+
+    fn render(&self, template: EmailTemplate, vars: &[(&str, &str)]) -> Result<RenderedEmail, crate::error::AppError> {
+        self.templates
+            .render(template, vars, &self.environment)
+            .map_err(crate::error::AppError::email)
+    }
 }
 
 // Kirim email verifikasi dengan link aktivasi akun menggunakan Resend API
 pub async fn send_verification_email(
     http_client: &reqwest::Client,
-    api_key: &str,
-    from_email: &str,
+    config: &EmailConfig,
     to_email: &str,
     to_name: &str,
     verification_token: &str,
@@ -32,113 +79,54 @@ pub async fn send_verification_email(
         verification_token
     );
 
-    let html_body = format!(
-        r#"
-        <!DOCTYPE html>
-        <html>
-        <head>
-            <meta charset="UTF-8">
-            <style>
-                body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; }}
-                .container {{ max-width: 600px; margin: 0 auto; padding: 20px; }}
-                .header {{ background: #4F46E5; color: white; padding: 20px; text-align: center; }}
-                .content {{ background: #f9fafb; padding: 30px; }}
-                .button {{ display: inline-block; padding: 12px 30px; background: #4F46E5; color: white; text-decoration: none; border-radius: 5px; }}
-                .footer {{ text-align: center; padding: 20px; color: #666; font-size: 12px; }}
-            </style>
-        </head>
-        <body>
-            <div class="container">
-                <div class="header">
-                    <h1>Verifikasi Email Anda</h1>
-                </div>
-                <div class="content">
-                    <p>Halo <strong>{}</strong>,</p>
-                    <p>Terima kasih telah mendaftar di Big Auto!</p>
-                    <p>Untuk mengaktifkan akun Anda, silakan klik tombol di bawah ini:</p>
-                    <p style="text-align: center; margin: 30px 0;">
-                        <a href="{}" class="button">Verifikasi Email</a>
-                    </p>
-                    <p>Atau copy link berikut ke browser Anda:</p>
-                    <p style="word-break: break-all; color: #4F46E5;">{}</p>
-                    <p><strong>Link ini akan kadaluarsa dalam 24 jam.</strong></p>
-                </div>
-                <div class="footer">
-                    <p>Jika Anda tidak mendaftar di Big Auto, abaikan email ini.</p>
-                    <p>&copy; 2025 Big Auto. All rights reserved.</p>
-                </div>
-            </div>
-        </body>
-        </html>
-        "#,
-        to_name, verification_link, verification_link
-    );
+    let email = config.render(
+        EmailTemplate::Verification,
+        &[("name", to_name), ("link", &verification_link)],
+    )?;
 
-    send_email_via_resend(
-        http_client,
-        api_key,
-        from_email,
-        to_email,
-        "Verifikasi Email Anda - Big Auto",
-        &html_body
-    ).await
+    send_email(http_client, config, to_email, &email).await
 }
 
 // Kirim OTP untuk login melalui email menggunakan Resend API
 pub async fn send_otp_email(
     http_client: &reqwest::Client,
-    api_key: &str,
-    from_email: &str,
+    config: &EmailConfig,
     to_email: &str,
     to_name: &str,
     otp: &str,
 ) -> Result<(), crate::error::AppError> {
-    let html_body = format!(
-        r#"
-        <!DOCTYPE html>
-        <html>
-        <head>
-            <meta charset="UTF-8">
-            <style>
-                body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; }}
-                .container {{ max-width: 600px; margin: 0 auto; padding: 20px; }}
-                .header {{ background: #4F46E5; color: white; padding: 20px; text-align: center; }}
-                .content {{ background: #f9fafb; padding: 30px; }}
-                .otp-box {{ background: white; border: 2px dashed #4F46E5; padding: 20px; text-align: center; font-size: 32px; font-weight: bold; letter-spacing: 8px; color: #4F46E5; margin: 20px 0; }}
-                .footer {{ text-align: center; padding: 20px; color: #666; font-size: 12px; }}
-            </style>
-        </head>
-        <body>
-            <div class="container">
-                <div class="header">
-                    <h1>Kode OTP Login Anda</h1>
-                </div>
-                <div class="content">
-                    <p>Halo <strong>{}</strong>,</p>
-                    <p>Gunakan kode OTP berikut untuk menyelesaikan proses login Anda:</p>
-                    <div class="otp-box">{}</div>
-                    <p><strong>Kode ini berlaku selama 5 menit.</strong></p>
-                    <p>Jangan bagikan kode ini kepada siapa pun, termasuk tim Big Auto.</p>
-                    <p>Jika Anda tidak mencoba login, segera abaikan email ini dan hubungi kami.</p>
-                </div>
-                <div class="footer">
-                    <p>Email otomatis, mohon tidak membalas.</p>
-                    <p>&copy; 2025 Big Auto. All rights reserved.</p>
-                </div>
-            </div>
-        </body>
-        </html>
-        "#,
-        to_name, otp
-    );
+    let email = config.render(EmailTemplate::Otp, &[("name", to_name), ("otp", otp)])?;
+
+    send_email(http_client, config, to_email, &email).await
+}
+
+// Dry-run tidak pernah boleh aktif di production: OTP dan link verifikasi tidak akan terkirim
+pub fn reject_dry_run_in_production(var: &str, dry_run: bool, environment: &str) -> Result<(), String> {
+    if dry_run && environment == "production" {
+        return Err(format!("{} tidak boleh aktif di production", var));
+    }
+    Ok(())
+}
+
+// Dry-run hanya mencatat penerima dan subject; isi email (OTP, link verifikasi) tidak ditulis ke log
+async fn send_email(
+    http_client: &reqwest::Client,
+    config: &EmailConfig,
+    to_email: &str,
+    email: &RenderedEmail,
+) -> Result<(), crate::error::AppError> {
+    if config.dry_run {
+        tracing::info!("📭 [EMAIL_DRY_RUN] to: {} | subject: {}", to_email, email.subject);
+        return Ok(());
+    }
 
     send_email_via_resend(
         http_client,
-        api_key,
-        from_email,
+        &config.resend_api_key,
+        &config.sender(),
         to_email,
-        "Kode OTP Login Anda - Big Auto",
-        &html_body
+        &email.subject,
+        &email.html
     ).await
 }
 
@@ -204,4 +192,24 @@ mod tests {
 
         assert_eq!(config.email_from, "onboarding@resend.dev", "Default email harus onboarding@resend.dev");
     }
+
+    #[test]
+    fn test_sender_includes_from_name() {
+        env::set_var("RESEND_API_KEY", "re_test_key");
+        let mut config = EmailConfig::from_env().expect("Failed to load config");
+        config.email_from = "noreply@bigauto.id".to_string();
+
+        config.from_name = "Big Auto Staging".to_string();
+        assert_eq!(config.sender(), "Big Auto Staging <noreply@bigauto.id>");
+
+        config.from_name = String::new();
+        assert_eq!(config.sender(), "noreply@bigauto.id");
+    }
+
+    #[test]
+    fn test_dry_run_refused_in_production() {
+        assert!(reject_dry_run_in_production("EMAIL_DRY_RUN", true, "production").is_err());
+        assert!(reject_dry_run_in_production("EMAIL_DRY_RUN", true, "development").is_ok());
+        assert!(reject_dry_run_in_production("EMAIL_DRY_RUN", false, "production").is_ok());
+    }
 }
\ No newline at end of file
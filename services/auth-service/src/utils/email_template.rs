@@ -0,0 +1,202 @@
+// Template HTML email auth per locale
+//
+// Template bawaan (templates/email/{locale}/{nama}.html) ikut di-compile agar service tetap jalan
+// tanpa file eksternal. EMAIL_TEMPLATE_DIR dengan struktur folder yang sama meng-override template
+// bawaan, jadi perubahan branding cukup edit file. Subject email diambil dari tag <title>.
+
+use std::path::Path;
+
+// Locale fallback jika template untuk locale yang diminta tidak ada
+pub const DEFAULT_LOCALE: &str = "id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    Verification,
+    Otp,
+}
+
+impl EmailTemplate {
+    fn file_name(self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => "verification.html",
+            EmailTemplate::Otp => "otp.html",
+        }
+    }
+
+    fn builtin(self, locale: &str) -> Option<&'static str> {
+        match (self, locale) {
+            (EmailTemplate::Verification, "id") => Some(include_str!("../../templates/email/id/verification.html")),
+            (EmailTemplate::Verification, "en") => Some(include_str!("../../templates/email/en/verification.html")),
+            (EmailTemplate::Otp, "id") => Some(include_str!("../../templates/email/id/otp.html")),
+            (EmailTemplate::Otp, "en") => Some(include_str!("../../templates/email/en/otp.html")),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+}
+
+// Template yang sudah di-load saat startup
+#[derive(Debug, Clone)]
+pub struct EmailTemplates {
+    verification: String,
+    otp: String,
+}
+
+impl EmailTemplates {
+    // Urutan pencarian: dir eksternal (locale), bawaan (locale), dir eksternal (default), bawaan (default)
+    pub fn load(template_dir: Option<&Path>, locale: &str) -> Result<Self, String> {
+        if locale.is_empty() || !locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Locale email tidak valid: {}", locale));
+        }
+
+        Ok(Self {
+            verification: load_template(EmailTemplate::Verification, template_dir, locale)?,
+            otp: load_template(EmailTemplate::Otp, template_dir, locale)?,
+        })
+    }
+
+    // Isi placeholder {{key}} (nilai di-escape HTML), tambahkan banner environment non-production
+    pub fn render(
+        &self,
+        template: EmailTemplate,
+        vars: &[(&str, &str)],
+        environment: &str,
+    ) -> Result<RenderedEmail, String> {
+        let source = match template {
+            EmailTemplate::Verification => &self.verification,
+            EmailTemplate::Otp => &self.otp,
+        };
+
+        let mut html = vars.iter().fold(source.clone(), |html, (key, value)| {
+            html.replace(&format!("{{{{{}}}}}", key), &escape_html(value))
+        });
+
+        let mut subject = extract_title(&html)
+            .ok_or_else(|| format!("Template {} tidak memiliki <title>", template.file_name()))?;
+
+        if environment != "production" {
+            subject = format!("[{}] {}", environment.to_uppercase(), subject);
+            html = insert_environment_banner(&html, environment);
+        }
+
+        Ok(RenderedEmail { subject, html })
+    }
+}
+
+fn load_template(template: EmailTemplate, template_dir: Option<&Path>, locale: &str) -> Result<String, String> {
+    let from_dir = |locale: &str| -> Result<Option<String>, String> {
+        let Some(dir) = template_dir else {
+            return Ok(None);
+        };
+
+        let path = dir.join(locale).join(template.file_name());
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        std::fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| format!("Gagal membaca template email {}: {}", path.display(), e))
+    };
+
+    let source = match from_dir(locale)? {
+        Some(source) => source,
+        None => match template.builtin(locale) {
+            Some(source) => source.to_string(),
+            None => match from_dir(DEFAULT_LOCALE)? {
+                Some(source) => source,
+                None => template.builtin(DEFAULT_LOCALE).unwrap_or_default().to_string(),
+            },
+        },
+    };
+
+    if extract_title(&source).is_none() {
+        return Err(format!("Template {} ({}) tidak memiliki <title>", template.file_name(), locale));
+    }
+
+    Ok(source)
+}
+
+fn extract_title(html: &str) -> Option<String> {
+    let start = html.find("<title>")? + "<title>".len();
+    let end = start + html[start..].find("</title>")?;
+    let title = html[start..end].trim();
+
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+// Banner di awal <body> agar email staging/development tidak tertukar dengan production
+fn insert_environment_banner(html: &str, environment: &str) -> String {
+    let banner = format!(
+        r#"<div style="background: #F59E0B; color: #111; padding: 8px; text-align: center; font-weight: bold;">Email dari environment {} - bukan email production</div>"#,
+        escape_html(&environment.to_uppercase())
+    );
+
+    match html.find("<body").and_then(|start| html[start..].find('>').map(|end| start + end + 1)) {
+        Some(index) => format!("{}{}{}", &html[..index], banner, &html[index..]),
+        None => format!("{}{}", banner, html),
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fills_placeholders_and_escapes() {
+        let templates = EmailTemplates::load(None, "id").unwrap();
+        let email = templates
+            .render(EmailTemplate::Otp, &[("name", "<b>Budi</b>"), ("otp", "123456")], "production")
+            .unwrap();
+
+        assert_eq!(email.subject, "Kode OTP Login Anda - Big Auto");
+        assert!(email.html.contains("123456"));
+        assert!(email.html.contains("&lt;b&gt;Budi&lt;/b&gt;"));
+        assert!(!email.html.contains("{{"));
+        assert!(!email.html.contains("bukan email production"));
+    }
+
+    #[test]
+    fn test_non_production_gets_banner_and_subject_prefix() {
+        let templates = EmailTemplates::load(None, "en").unwrap();
+        let email = templates
+            .render(EmailTemplate::Verification, &[("name", "Budi"), ("link", "https://x/verify")], "staging")
+            .unwrap();
+
+        assert_eq!(email.subject, "[STAGING] Verify Your Email - Big Auto");
+        let body = email.html.find("<body>").unwrap();
+        assert!(email.html[body..].contains("Email dari environment STAGING"));
+    }
+
+    #[test]
+    fn test_locale_falls_back_and_directory_overrides() {
+        let dir = std::env::temp_dir().join(format!("bigauto-email-templates-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("fr")).unwrap();
+        std::fs::write(dir.join("fr").join("otp.html"), "<title>Votre code</title><body>{{otp}}</body>").unwrap();
+
+        let templates = EmailTemplates::load(Some(&dir), "fr").unwrap();
+        let otp = templates.render(EmailTemplate::Otp, &[("otp", "999")], "production").unwrap();
+        assert_eq!(otp.subject, "Votre code");
+
+        // Verification tidak ada untuk fr, pakai bawaan locale default
+        let verification = templates.render(EmailTemplate::Verification, &[], "production").unwrap();
+        assert_eq!(verification.subject, "Verifikasi Email Anda - Big Auto");
+
+        assert!(EmailTemplates::load(None, "../etc").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,18 +1,37 @@
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use shared::auth::{decode_token, issue_token, AuthError, JwtConfig};
 use sqlx::PgPool;
 
-/// Structure untuk JWT claims
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct TokenClaims {
-    pub sub: i32,
-    pub email: String,
-    pub role: String,
-    pub exp: i64,
-    pub iat: i64,
-    pub token_type: String,
-    pub jti: String,
+/// Claims dan validasi token memakai shared::auth, sama dengan service lain
+pub use shared::auth::{TokenClaims, TokenType};
+
+/// Umur maksimal user session (expires_at), refresh token tidak boleh melebihi ini
+pub const SESSION_MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Validasi kombinasi expiry access/refresh token saat startup
+pub fn validate_expiry_config(jwt_access_expiry: i64, jwt_refresh_expiry: i64) -> Result<(), String> {
+    if jwt_access_expiry <= 0 {
+        return Err(format!(
+            "JWT_ACCESS_TOKEN_EXPIRY harus lebih dari 0 detik (sekarang {})",
+            jwt_access_expiry
+        ));
+    }
+
+    if jwt_access_expiry >= jwt_refresh_expiry {
+        return Err(format!(
+            "JWT_ACCESS_TOKEN_EXPIRY ({}) harus lebih kecil dari JWT_REFRESH_TOKEN_EXPIRY ({})",
+            jwt_access_expiry, jwt_refresh_expiry
+        ));
+    }
+
+    // Refresh token yang hidup lebih lama dari session akan ditolak karena session sudah expired
+    if jwt_refresh_expiry > SESSION_MAX_AGE_SECS {
+        return Err(format!(
+            "JWT_REFRESH_TOKEN_EXPIRY ({}) tidak boleh melebihi umur session {} detik",
+            jwt_refresh_expiry, SESSION_MAX_AGE_SECS
+        ));
+    }
+
+    Ok(())
 }
 
 /// Generate access token dengan expiry 15 menit 
@@ -20,27 +39,10 @@ pub fn generate_access_token(
     user_id: i32,
     email: &str,
     role: &str,
-    jwt_secret: &str,
+    jwt: &JwtConfig,
     jwt_access_expiry: i64,
 ) -> Result<String, jsonwebtoken::errors::Error> {
-    let now = Utc::now();
-    let exp = now + Duration::seconds(jwt_access_expiry);
-
-    let claims = TokenClaims {
-        sub: user_id,
-        email: email.to_string(),
-        role: role.to_string(),
-        exp: exp.timestamp(),
-        iat: now.timestamp(),
-        token_type: "access".to_string(),
-        jti: uuid::Uuid::new_v4().to_string(),
-    };
-
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_secret.as_bytes())
-    )
+    issue_token(jwt, user_id, email, role, TokenType::Access, jwt_access_expiry)
 }
 
 /// Generate refresh token dengan expiry 7 hari 
@@ -48,112 +50,50 @@ pub fn generate_refresh_token(
     user_id: i32,
     email: &str,
     role: &str,
-    jwt_secret: &str,
+    jwt: &JwtConfig,
     jwt_refresh_expiry: i64,
 ) -> Result<String, jsonwebtoken::errors::Error> {
-    let now = Utc::now();
-    let exp = now + Duration::seconds(jwt_refresh_expiry);
-
-    let claims = TokenClaims {
-        sub: user_id,
-        email: email.to_string(),
-        role: role.to_string(),
-        exp: exp.timestamp(),
-        iat: now.timestamp(),
-        token_type: "refresh".to_string(),
-        jti: uuid::Uuid::new_v4().to_string(),
-    };
-
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_secret.as_bytes())
-    )
+    issue_token(jwt, user_id, email, role, TokenType::Refresh, jwt_refresh_expiry)
 }
 
-/// Validasi JWT token signature dan extract claims
+/// Validasi JWT token signature dan extract claims (tanpa blacklist check)
 pub fn validate_token_signature(
     token: &str,
-    jwt_secret: &str,
-) -> Result<TokenClaims, jsonwebtoken::errors::Error> {
-    let mut validation = Validation::default();
-    validation.leeway = 60; 
-    let token_data = decode::<TokenClaims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &validation,
-    )?;
-
-    Ok(token_data.claims)
+    jwt: &JwtConfig,
+    expected: TokenType,
+) -> Result<TokenClaims, AuthError> {
+    decode_token(token, jwt, expected)
 }
 
-/// Validasi token lengkap dengan signature dan blacklist check
+/// Validasi token lengkap dengan signature, token type, dan blacklist check
 pub async fn validate_token(
     token: &str,
-    jwt_secret: &str,
+    jwt: &JwtConfig,
     db: &PgPool,
+    expected: TokenType,
 ) -> Result<TokenClaims, String> {
-    // Step 1: Validasi signature JWT
-    let claims = validate_token_signature(token, jwt_secret)
-        .map_err(|e| format!("Token signature invalid: {}", e))?;
-
-    // Step 2: Validasi expiry time
-    let now = Utc::now().timestamp();
-    if claims.exp < now {
-        return Err("Token has expired".to_string());
-    }
-
-    // Step 3: Validasi token type (hanya access/refresh)
-    if !matches!(claims.token_type.as_str(), "access" | "refresh") {
-        return Err("Invalid token type".to_string());
-    }
-
-    // Step 4: Validasi blacklist
-    validate_blacklist_status(&claims, db).await?;
-
-    Ok(claims)
-}
-
-/// Check token status di blacklist
-pub async fn validate_blacklist_status(
-    claims: &TokenClaims,
-    db: &PgPool,
-) -> Result<(), String> {
-    let is_blacklisted: Option<bool> = sqlx::query_scalar::<_, bool>(
-        "SELECT is_token_blacklisted_v2($1, $2)"
-    )
-    .bind(&claims.jti)
-    .bind(&claims.token_type)
-    .fetch_optional(db)
-    .await
-    .map_err(|e| format!("Blacklist validation failed: {}", e))?;
-
-    if is_blacklisted.unwrap_or(false) {
-        tracing::warn!("Token has been blacklisted - user_id: {}, jti: {}...",
-            claims.sub,
-            &claims.jti[..8.min(claims.jti.len())]
-        );
-        return Err("Token has been revoked".to_string());
-    }
-
-    Ok(())
+    shared::auth::validate_token(token, jwt, db, expected)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
+
     #[test]
     fn test_generate_and_validate_access_token() {
         let user_id = 123;
         let email = "test@example.com";
         let role = "customer";
-        let jwt_secret = "test-secret-key";
+        let jwt = JwtConfig::new("test-secret-key");
         let jwt_access_expiry = 900;
 
-        let token = generate_access_token(user_id, email, role, jwt_secret, jwt_access_expiry)
+        let token = generate_access_token(user_id, email, role, &jwt, jwt_access_expiry)
             .expect("Gagal generate access token");
 
-        let claims = validate_token_signature(&token, jwt_secret).expect("Gagal validate token");
+        let claims = validate_token_signature(&token, &jwt, TokenType::Access).expect("Gagal validate token");
 
         assert_eq!(claims.sub, user_id);
         assert_eq!(claims.email, email);
@@ -166,13 +106,13 @@ mod tests {
         let user_id = 456;
         let email = "seller@example.com";
         let role = "seller";
-        let jwt_secret = "test-secret-key";
+        let jwt = JwtConfig::new("test-secret-key");
         let jwt_refresh_expiry = 604800;
 
-        let token = generate_refresh_token(user_id, email, role, jwt_secret, jwt_refresh_expiry)
+        let token = generate_refresh_token(user_id, email, role, &jwt, jwt_refresh_expiry)
             .expect("Gagal generate refresh token");
 
-        let claims = validate_token_signature(&token, jwt_secret).expect("Gagal validate token");
+        let claims = validate_token_signature(&token, &jwt, TokenType::Refresh).expect("Gagal validate token");
 
         assert_eq!(claims.sub, user_id);
         assert_eq!(claims.email, email);
@@ -183,21 +123,39 @@ mod tests {
     #[test]
     fn test_invalid_token() {
         let invalid_token = "invalid.jwt.token";
-        let result = validate_token_signature(invalid_token, "test-secret");
+        let result = validate_token_signature(invalid_token, &JwtConfig::new("test-secret"), TokenType::Access);
 
         assert!(result.is_err(), "Token invalid seharusnya error");
     }
 
     #[test]
     fn test_token_expiry_timestamp() {
-        let token = generate_access_token(1, "test@test.com", "customer", "test-secret", 900)
+        let jwt = JwtConfig::new("test-secret");
+        let token = generate_access_token(1, "test@test.com", "customer", &jwt, 900)
             .expect("Gagal generate token");
 
-        let claims = validate_token_signature(&token, "test-secret").expect("Gagal validate");
+        let claims = validate_token_signature(&token, &jwt, TokenType::Access).expect("Gagal validate");
         let now = Utc::now().timestamp();
 
         assert!(claims.exp > now, "Token expiry harus di masa depan");
         assert!(claims.iat <= now, "Token issued time tidak boleh masa depan");
     }
 
+    #[test]
+    fn test_validate_expiry_config() {
+        assert!(validate_expiry_config(900, 604800).is_ok());
+        assert!(validate_expiry_config(900, SESSION_MAX_AGE_SECS).is_ok());
+
+        // Access token nol atau negatif
+        assert!(validate_expiry_config(0, 604800).is_err());
+        assert!(validate_expiry_config(-60, 604800).is_err());
+
+        // Access token sama atau lebih lama dari refresh token
+        assert!(validate_expiry_config(3600, 3600).is_err());
+        assert!(validate_expiry_config(7200, 3600).is_err());
+
+        // Refresh token melebihi umur session
+        assert!(validate_expiry_config(900, SESSION_MAX_AGE_SECS + 1).is_err());
+    }
+
   }
\ No newline at end of file
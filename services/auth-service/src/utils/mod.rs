@@ -1,6 +1,8 @@
 pub mod hash;
 pub mod jwt;
 pub mod otp;
+pub mod otp_channel;
 pub mod email;
+pub mod email_template;
 pub mod validation;
 
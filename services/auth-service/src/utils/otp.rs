@@ -1,4 +1,9 @@
 use rand::Rng;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Status pengiriman OTP disimpan lebih lama dari masa berlaku OTP (5 menit)
+pub const OTP_DELIVERY_TTL_SECS: u64 = 600;
 
 // Generate a 6-digit OTP code (cryptographically secure)
 pub fn generate_otp() -> String {
@@ -7,6 +12,54 @@ pub fn generate_otp() -> String {
     otp.to_string()
 }
 
+// Status pengiriman email OTP yang bisa dicek client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpDeliveryStatus {
+    Queued,
+    Sent,
+    Failed,
+    Unknown,
+}
+
+impl OtpDeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtpDeliveryStatus::Queued => "queued",
+            OtpDeliveryStatus::Sent => "sent",
+            OtpDeliveryStatus::Failed => "failed",
+            OtpDeliveryStatus::Unknown => "unknown",
+        }
+    }
+
+    // Nilai Redis yang hilang/tidak dikenal dianggap unknown
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("queued") => OtpDeliveryStatus::Queued,
+            Some("sent") => OtpDeliveryStatus::Sent,
+            Some("failed") => OtpDeliveryStatus::Failed,
+            _ => OtpDeliveryStatus::Unknown,
+        }
+    }
+}
+
+// Panjang request id pengiriman OTP (UUID v4 tanpa tanda hubung)
+const OTP_REQUEST_ID_LEN: usize = 32;
+
+// Id opaque per pengiriman OTP, dipakai client untuk cek status tanpa membuka user id
+pub fn generate_request_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+pub fn is_valid_request_id(request_id: &str) -> bool {
+    request_id.len() == OTP_REQUEST_ID_LEN && request_id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// Key Redis status pengiriman OTP per request id
+pub fn delivery_key(request_id: &str) -> String {
+    format!("otp_delivery:{}", request_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,6 +99,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_delivery_status_roundtrip() {
+        for status in [OtpDeliveryStatus::Queued, OtpDeliveryStatus::Sent, OtpDeliveryStatus::Failed] {
+            assert_eq!(OtpDeliveryStatus::parse(Some(status.as_str())), status);
+        }
+        assert_eq!(OtpDeliveryStatus::parse(None), OtpDeliveryStatus::Unknown);
+        assert_eq!(OtpDeliveryStatus::parse(Some("garbage")), OtpDeliveryStatus::Unknown);
+    }
+
+    #[test]
+    fn test_request_id_is_opaque_and_validated() {
+        let first = generate_request_id();
+        let second = generate_request_id();
+        assert_ne!(first, second);
+        assert!(is_valid_request_id(&first));
+        assert_eq!(delivery_key(&first), format!("otp_delivery:{}", first));
+
+        // User id berurutan dan input aneh tidak pernah cocok dengan key status
+        assert!(!is_valid_request_id("7"));
+        assert!(!is_valid_request_id(&"z".repeat(OTP_REQUEST_ID_LEN)));
+        assert!(!is_valid_request_id("*"));
+    }
+
     #[test]
     fn test_otp_randomness() {
         let mut otps = HashSet::new();
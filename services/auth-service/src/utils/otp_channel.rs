@@ -0,0 +1,261 @@
+// Channel pengiriman OTP login dan urutan fallback-nya
+//
+// OTP_CHANNELS berisi urutan channel yang dicoba (mis. "sms,email"): channel berikutnya
+// hanya dipakai jika channel sebelumnya gagal, dan login baru error jika semua channel gagal.
+
+use serde::Serialize;
+use serde_json::json;
+use std::env;
+use std::future::Future;
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+use crate::utils::{email::reject_dry_run_in_production, validation};
+
+// Default: hanya email, sama seperti sebelum ada SMS
+pub const DEFAULT_OTP_CHANNELS: &str = "email";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpChannel {
+    Email,
+    Sms,
+}
+
+impl OtpChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtpChannel::Email => "email",
+            OtpChannel::Sms => "sms",
+        }
+    }
+
+    // Label untuk pesan ke user ("dikirim via SMS")
+    pub fn label(&self) -> &'static str {
+        match self {
+            OtpChannel::Email => "email",
+            OtpChannel::Sms => "SMS",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "email" => Some(OtpChannel::Email),
+            "sms" => Some(OtpChannel::Sms),
+            _ => None,
+        }
+    }
+}
+
+// Parse OTP_CHANNELS: minimal satu channel, tidak boleh ada channel asing atau duplikat
+pub fn parse_channels(raw: &str) -> Result<Vec<OtpChannel>, String> {
+    let mut channels = Vec::new();
+
+    for value in raw.split(',').map(str::trim).filter(|value| !value.is_empty()) {
+        let channel = OtpChannel::parse(value)
+            .ok_or_else(|| format!("OTP_CHANNELS berisi channel tidak dikenal: {}", value))?;
+
+        if channels.contains(&channel) {
+            return Err(format!("OTP_CHANNELS berisi channel duplikat: {}", value));
+        }
+        channels.push(channel);
+    }
+
+    if channels.is_empty() {
+        return Err("OTP_CHANNELS minimal berisi satu channel (email, sms)".to_string());
+    }
+
+    Ok(channels)
+}
+
+// Coba channel sesuai urutan dan kembalikan channel pertama yang berhasil.
+// Jika semua gagal, error tiap channel dikembalikan untuk di-log
+pub async fn deliver_with_fallback<F, Fut>(
+    channels: &[OtpChannel],
+    mut send: F,
+) -> Result<OtpChannel, Vec<(OtpChannel, String)>>
+where
+    F: FnMut(OtpChannel) -> Fut,
+    Fut: Future<Output = Result<(), AppError>>,
+{
+    let mut failures = Vec::new();
+
+    for &channel in channels {
+        match send(channel).await {
+            Ok(()) => return Ok(channel),
+            Err(e) => failures.push((channel, e.to_string())),
+        }
+    }
+
+    Err(failures)
+}
+
+// Konfigurasi SMS gateway HTTP generik (POST JSON dengan Bearer API key)
+#[derive(Debug, Clone)]
+pub struct SmsConfig {
+    pub gateway_url: String,
+    pub api_key: String,
+    pub sender_id: String,
+    // SMS hanya di-log (tidak dikirim), untuk test dan development
+    pub dry_run: bool,
+}
+
+impl SmsConfig {
+    // Hanya di-load jika "sms" ada di OTP_CHANNELS
+    pub fn from_env() -> Result<Self, String> {
+        let dry_run = env::var("SMS_DRY_RUN")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        // Gateway dan API key tidak dibutuhkan jika SMS tidak benar-benar dikirim
+        let required = |key: &str| match env::var(key) {
+            Ok(value) if !value.trim().is_empty() => Ok(value),
+            _ if dry_run => Ok(String::new()),
+            _ => Err(format!("{} harus diset jika OTP_CHANNELS berisi sms", key)),
+        };
+
+        let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
+        reject_dry_run_in_production("SMS_DRY_RUN", dry_run, &environment)?;
+
+        Ok(SmsConfig {
+            gateway_url: required("SMS_GATEWAY_URL")?,
+            api_key: required("SMS_API_KEY")?,
+            sender_id: env::var("SMS_SENDER_ID").unwrap_or_else(|_| "BigAuto".to_string()),
+            dry_run,
+        })
+    }
+}
+
+// Isi SMS OTP, dibuat pendek agar muat dalam satu segmen SMS
+pub fn otp_sms_message(otp: &str) -> String {
+    format!("Kode OTP Big Auto: {}. Berlaku 5 menit. Jangan berikan kode ini ke siapa pun.", otp)
+}
+
+// Isi SMS untuk log dry-run, kode OTP disamarkan
+fn redacted_otp_sms_message(otp: &str) -> String {
+    otp_sms_message(&"*".repeat(otp.chars().count()))
+}
+
+// Kirim OTP login via SMS gateway; nomor dinormalisasi ke +628xx dulu
+pub async fn send_otp_sms(
+    http_client: &reqwest::Client,
+    config: Option<&SmsConfig>,
+    phone: &str,
+    otp: &str,
+) -> Result<(), AppError> {
+    let config = config.ok_or_else(|| AppError::internal("SMS gateway belum dikonfigurasi"))?;
+
+    let to = validation::normalize_phone(phone)
+        .map_err(|e| AppError::validation(format!("Nomor telepon tidak bisa menerima SMS: {}", e.message)))?;
+
+    if config.dry_run {
+        tracing::info!(
+            "📭 [SMS_DRY_RUN] from: {} | to: {}\n{}",
+            config.sender_id,
+            to,
+            redacted_otp_sms_message(otp)
+        );
+        return Ok(());
+    }
+
+    let message = otp_sms_message(otp);
+
+    let response = http_client
+        .post(&config.gateway_url)
+        .header("Authorization", format!("Bearer {}", config.api_key))
+        .json(&json!({
+            "from": config.sender_id,
+            "to": to,
+            "message": message,
+        }))
+        .send()
+        .await
+        .map_err(|e| AppError::internal(format!("Gagal menghubungi SMS gateway: {}", e)))?;
+
+    if response.status().is_success() {
+        tracing::info!("✅ OTP SMS sent successfully to {}", to);
+        Ok(())
+    } else {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        Err(AppError::internal(format!("SMS gateway menolak pengiriman ({}): {}", status, error_text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channels() {
+        assert_eq!(parse_channels(DEFAULT_OTP_CHANNELS).unwrap(), vec![OtpChannel::Email]);
+        assert_eq!(
+            parse_channels(" SMS , email ").unwrap(),
+            vec![OtpChannel::Sms, OtpChannel::Email]
+        );
+        assert!(parse_channels("").is_err());
+        assert!(parse_channels("sms,whatsapp").is_err());
+        assert!(parse_channels("email,email").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sms_fails_falls_back_to_email() {
+        let mut attempted = Vec::new();
+
+        let delivered = deliver_with_fallback(&[OtpChannel::Sms, OtpChannel::Email], |channel| {
+            attempted.push(channel);
+            async move {
+                match channel {
+                    OtpChannel::Sms => Err(AppError::internal("SMS gateway timeout")),
+                    OtpChannel::Email => Ok(()),
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(delivered, Ok(OtpChannel::Email));
+        assert_eq!(attempted, vec![OtpChannel::Sms, OtpChannel::Email]);
+    }
+
+    #[tokio::test]
+    async fn test_first_success_stops_chain_and_all_failures_reported() {
+        let mut attempted = Vec::new();
+        let delivered = deliver_with_fallback(&[OtpChannel::Sms, OtpChannel::Email], |channel| {
+            attempted.push(channel);
+            async { Ok(()) }
+        })
+        .await;
+        assert_eq!(delivered, Ok(OtpChannel::Sms));
+        assert_eq!(attempted, vec![OtpChannel::Sms]);
+
+        let failed = deliver_with_fallback(&[OtpChannel::Email, OtpChannel::Sms], |_| async {
+            Err(AppError::email("Resend down"))
+        })
+        .await
+        .unwrap_err();
+        let failed_channels: Vec<OtpChannel> = failed.iter().map(|(channel, _)| *channel).collect();
+        assert_eq!(failed_channels, vec![OtpChannel::Email, OtpChannel::Sms]);
+    }
+
+    #[test]
+    fn test_dry_run_sms_log_hides_otp() {
+        let redacted = redacted_otp_sms_message("482913");
+        assert!(!redacted.contains("482913"));
+        assert!(redacted.contains("******"));
+    }
+
+    #[tokio::test]
+    async fn test_sms_without_deliverable_phone_fails() {
+        let config = SmsConfig {
+            gateway_url: String::new(),
+            api_key: String::new(),
+            sender_id: "BigAuto".to_string(),
+            dry_run: true,
+        };
+        let client = reqwest::Client::new();
+
+        assert!(send_otp_sms(&client, Some(&config), "081234567890", "123456").await.is_ok());
+        assert!(send_otp_sms(&client, Some(&config), "", "123456").await.is_err());
+        assert!(send_otp_sms(&client, None, "081234567890", "123456").await.is_err());
+    }
+}
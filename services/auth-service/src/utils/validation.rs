@@ -1,5 +1,6 @@
 use regex::Regex;
 use once_cell::sync::Lazy;
+use shared::utils::validation::FieldError;
 
 // Regex untuk email validation (RFC 5322 compliant)
 static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
@@ -7,39 +8,53 @@ static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
         .unwrap()
 });
 
-// Regex untuk nomor telepon Indonesia (08xx, 628xx, atau +628xx format)
-static PHONE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^(\+62|62|0)8[1-9][0-9]{7,11}$")
-        .unwrap()
-});
+// Prefix operator seluler Indonesia (3 digit setelah kode negara 62)
+const MOBILE_OPERATOR_PREFIXES: &[&str] = &[
+    // Telkomsel / by.U
+    "811", "812", "813", "821", "822", "823", "851", "852", "853",
+    // Indosat Ooredoo
+    "814", "815", "816", "855", "856", "857", "858",
+    // XL
+    "817", "818", "819", "859", "877", "878",
+    // Axis
+    "831", "832", "833", "838",
+    // Tri
+    "895", "896", "897", "898", "899",
+    // Smartfren
+    "881", "882", "883", "884", "885", "886", "887", "888", "889",
+];
+
+// Panjang nomor setelah 62 (national significant number) untuk nomor seluler
+const MIN_MOBILE_NSN_LEN: usize = 9;
+const MAX_MOBILE_NSN_LEN: usize = 12;
 
 // Validasi format email sesuai RFC 5322 standard
-pub fn validate_email(email: &str) -> Result<(), String> {
+pub fn validate_email(email: &str) -> Result<(), FieldError> {
     let trimmed = email.trim();
 
     if trimmed.is_empty() {
-        return Err("Email tidak boleh kosong".to_string());
+        return Err(FieldError::new("email", "Email tidak boleh kosong"));
     }
 
     if trimmed.len() > 254 {
-        return Err("Email terlalu panjang (maksimal 254 karakter)".to_string());
+        return Err(FieldError::new("email", "Email terlalu panjang (maksimal 254 karakter)"));
     }
 
     if !EMAIL_REGEX.is_match(trimmed) {
-        return Err("Format email tidak valid".to_string());
+        return Err(FieldError::new("email", "Format email tidak valid"));
     }
 
     Ok(())
 }
 
 // Validasi password dengan aturan keamanan enterprise-grade
-pub fn validate_password(password: &str) -> Result<(), String> {
+pub fn validate_password(password: &str) -> Result<(), FieldError> {
     if password.len() < 8 {
-        return Err("Password minimal 8 karakter".to_string());
+        return Err(FieldError::new("password", "Password minimal 8 karakter"));
     }
 
     if password.len() > 128 {
-        return Err("Password maksimal 128 karakter".to_string());
+        return Err(FieldError::new("password", "Password maksimal 128 karakter"));
     }
 
     let has_uppercase = password.chars().any(|c| c.is_uppercase());
@@ -47,48 +62,69 @@ pub fn validate_password(password: &str) -> Result<(), String> {
     let has_digit = password.chars().any(|c| c.is_numeric());
 
     if !has_uppercase {
-        return Err("Password harus mengandung minimal 1 huruf besar".to_string());
+        return Err(FieldError::new("password", "Password harus mengandung minimal 1 huruf besar"));
     }
 
     if !has_lowercase {
-        return Err("Password harus mengandung minimal 1 huruf kecil".to_string());
+        return Err(FieldError::new("password", "Password harus mengandung minimal 1 huruf kecil"));
     }
 
     if !has_digit {
-        return Err("Password harus mengandung minimal 1 angka".to_string());
+        return Err(FieldError::new("password", "Password harus mengandung minimal 1 angka"));
     }
 
     Ok(())
 }
 
 // Validasi nomor telepon Indonesia dengan berbagai format
-pub fn validate_phone(phone: &str) -> Result<(), String> {
-    let trimmed = phone.trim().replace(&[' ', '-'][..], "");
+pub fn validate_phone(phone: &str) -> Result<(), FieldError> {
+    normalize_phone(phone).map(|_| ())
+}
 
+// Normalisasi nomor seluler Indonesia ke E.164 (+628xx), tolak nomor yang jelas tidak valid.
+// Format yang diterima: 08xx, 628xx, +628xx, 00628xx, +62 08xx; spasi, titik, strip, dan kurung diabaikan
+pub fn normalize_phone(phone: &str) -> Result<String, FieldError> {
+    let trimmed = phone.trim();
     if trimmed.is_empty() {
-        return Err("Nomor telepon tidak boleh kosong".to_string());
+        return Err(FieldError::new("phone", "Nomor telepon tidak boleh kosong"));
     }
 
-    if !PHONE_REGEX.is_match(&trimmed) {
-        return Err("Format nomor telepon tidak valid (gunakan format 08xx, 628xx, atau +628xx)".to_string());
+    let has_plus = trimmed.starts_with('+');
+    let digits: String = trimmed
+        .trim_start_matches('+')
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(FieldError::new("phone", "Nomor telepon hanya boleh berisi angka"));
     }
 
-    Ok(())
-}
+    // Ambil nomor setelah kode negara/trunk prefix
+    let national = if let Some(rest) = digits.strip_prefix("0062").or_else(|| digits.strip_prefix("62")) {
+        // "+62 0812..." sering diketik user: buang trunk 0 setelah kode negara
+        rest.strip_prefix('0').unwrap_or(rest)
+    } else if has_plus {
+        return Err(FieldError::new("phone", "Hanya nomor Indonesia (+62) yang didukung"));
+    } else if let Some(rest) = digits.strip_prefix('0') {
+        rest
+    } else {
+        return Err(FieldError::new("phone", "Format nomor telepon tidak valid (gunakan format 08xx, 628xx, atau +628xx)"));
+    };
 
-// Normalisasi nomor telepon ke format +628xx untuk konsistensi database
-pub fn normalize_phone(phone: &str) -> String {
-    let cleaned = phone.trim().replace(&[' ', '-'][..], "");
+    if !national.starts_with('8') {
+        return Err(FieldError::new("phone", "Nomor telepon harus nomor seluler (diawali 08 atau +628)"));
+    }
 
-    if cleaned.starts_with("+62") {
-        cleaned
-    } else if cleaned.starts_with("62") {
-        format!("+{}", cleaned)
-    } else if cleaned.starts_with('0') {
-        format!("+62{}", &cleaned[1..])
-    } else {
-        cleaned
+    if !(MIN_MOBILE_NSN_LEN..=MAX_MOBILE_NSN_LEN).contains(&national.len()) {
+        return Err(FieldError::new("phone", "Panjang nomor telepon tidak valid (10-13 digit dengan awalan 0)"));
     }
+
+    if !MOBILE_OPERATOR_PREFIXES.contains(&&national[..3]) {
+        return Err(FieldError::new("phone", "Prefix operator nomor telepon tidak dikenal"));
+    }
+
+    Ok(format!("+62{}", national))
 }
 
 #[cfg(test)]
@@ -127,6 +163,13 @@ mod tests {
         assert!(validate_password("NoDigitsHere").is_err()); 
     }
 
+    #[test]
+    fn test_errors_tagged_with_field() {
+        assert_eq!(validate_email("invalid").unwrap_err().field, "email");
+        assert_eq!(validate_password("short").unwrap_err().field, "password");
+        assert_eq!(normalize_phone("123").unwrap_err().field, "phone");
+    }
+
     #[test]
     fn test_valid_phones() {
         assert!(validate_phone("08123456789").is_ok());
@@ -145,9 +188,58 @@ mod tests {
 
     #[test]
     fn test_normalize_phone() {
-        assert_eq!(normalize_phone("08123456789"), "+628123456789");
-        assert_eq!(normalize_phone("628123456789"), "+628123456789");
-        assert_eq!(normalize_phone("+628123456789"), "+628123456789");
-        assert_eq!(normalize_phone("0812-3456-7890"), "+6281234567890");
+        let cases = [
+            // Format lokal
+            ("08123456789", "+628123456789"),
+            ("0812-3456-7890", "+6281234567890"),
+            ("0812 3456 7890", "+6281234567890"),
+            ("(0812) 3456.7890", "+6281234567890"),
+            // Dengan kode negara
+            ("628123456789", "+628123456789"),
+            ("+628123456789", "+628123456789"),
+            ("+62 812-3456-789", "+628123456789"),
+            ("00628123456789", "+628123456789"),
+            ("+62 0812 3456 789", "+628123456789"),
+            // Berbagai operator
+            ("0856-1234-5678", "+6285612345678"),
+            ("0878 1234 5678", "+6287812345678"),
+            ("0838-1234-5678", "+6283812345678"),
+            ("0896-1234-5678", "+6289612345678"),
+            ("0881-1234-5678", "+6288112345678"),
+            // Batas panjang (9 dan 12 digit setelah 62)
+            ("0812345678", "+62812345678"),
+            ("0812345678901", "+62812345678901"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(normalize_phone(input).as_deref(), Ok(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_normalize_phone_rejects_invalid() {
+        let invalid = [
+            "",
+            "   ",
+            "abc",
+            "0812-abcd-789",
+            "123456",
+            // Bukan nomor seluler
+            "0211234567",
+            "07123456789",
+            // Prefix operator tidak dikenal
+            "08001234567",
+            "0810-1234-5678",
+            // Terlalu pendek / panjang
+            "081234567",
+            "08123456789012",
+            // Kode negara lain
+            "+6591234567",
+            "+1 555 123 4567",
+        ];
+
+        for input in invalid {
+            assert!(normalize_phone(input).is_err(), "input harus ditolak: {:?}", input);
+        }
     }
 }
@@ -2,6 +2,29 @@ use sqlx::{postgres::PgPoolOptions, postgres::PgConnectOptions, PgPool};
 use std::env;
 use std::time::Duration;
 use crate::middleware::rate_limit::RateLimiter;
+use shared::utils::storage::StorageBackend;
+use shared::auth::JwtConfig;
+use shared::utils::bind_addr;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+use crate::utils::business_hours::{self, BusinessHours};
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("rental_bookings", &["id", "vehicle_id", "customer_id", "seller_id", "status", "deposit_status"]),
+    ("rental_handovers", &["id", "rental_booking_id", "kind", "odometer_km", "late_fee"]),
+    ("sale_orders", &["id", "vehicle_id", "buyer_id", "seller_id", "status", "tracking_reference", "document_checklist", "version"]),
+    ("sale_payment_events", &["id", "sale_order_id", "processed_at"]),
+    ("seller_document_checklists", &["seller_id", "items"]),
+    ("testdrive_bookings", &["id", "vehicle_id", "customer_id", "seller_id", "status", "version"]),
+    ("seller_availability", &["id", "seller_id", "weekday", "date", "start_time", "capacity", "is_available"]),
+    ("vehicles", &["id", "seller_id", "status"]),
+    ("seller_buyer_blocks", &["seller_id", "buyer_id"]),
+    // Milik chat-service, dibaca langsung untuk cek buyer yang pernah menghubungi seller
+    ("conversations", &["customer_id", "seller_id"]),
+    ("outbound_webhooks", &["id", "seller_id", "url", "secret"]),
+    ("webhook_deliveries", &["id", "webhook_id", "status", "next_attempt_at"]),
+];
 
 // Konfigurasi aplikasi dari environment variables
 #[derive(Debug, Clone)]
@@ -11,9 +34,19 @@ pub struct AppConfig {
     pub server_port: u16,
     pub environment: String,
     pub jwt_secret: String,
+    pub jwt: JwtConfig,
     pub vehicle_service_url: String,
     pub auth_service_url: String,
     pub user_service_url: String,
+    pub invoice_tax_percentage: f64,
+    pub seller_sla_minutes: i64,
+    pub max_counter_rounds: i32,
+    pub testdrive_reminder_hours: i64,
+    pub testdrive_hours: BusinessHours,
+    pub auto_create_sale_conversation: bool,
+    pub chat_service_url: Option<String>,
+    pub file_url_secret: String,
+    pub file_url_ttl_secs: i64,
 }
 
 impl AppConfig {
@@ -29,13 +62,13 @@ impl AppConfig {
             return Err("JWT_SECRET masih default! Ganti untuk production".to_string());
         }
 
-        let server_host = env::var("BOOKING_SERVICE_HOST")
-            .expect("BOOKING_SERVICE_HOST harus diset di environment");
+        // Issuer/audience opsional dari JWT_ISSUER dan JWT_AUDIENCE
+        let jwt = JwtConfig::from_env(jwt_secret.clone());
 
-        let server_port = env::var("BOOKING_SERVICE_PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .expect("BOOKING_SERVICE_PORT harus diset di environment");
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("BOOKING_SERVICE_HOST", None)?;
+        let server_port = bind_addr::port_from_env("BOOKING_SERVICE_PORT", None)?;
+        bind_addr::resolve("BOOKING_SERVICE_HOST", &server_host, server_port)?;
 
         let environment = env::var("RUST_ENV")
             .expect("RUST_ENV harus diset di environment");
@@ -49,15 +82,82 @@ impl AppConfig {
         let user_service_url = env::var("USER_SERVICE_URL")
             .expect("USER_SERVICE_URL harus diset di environment");
 
+        // PPN yang sudah termasuk di harga jual (default 11%)
+        let invoice_tax_percentage = env::var("INVOICE_TAX_PERCENTAGE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(11.0);
+
+        // Batas waktu seller merespon order baru (menit)
+        let seller_sla_minutes = env::var("SELLER_SLA_MINUTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        // Maksimal counter offer seller per order
+        let max_counter_rounds = env::var("MAX_COUNTER_OFFER_ROUNDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3);
+
+        // Reminder test drive dikirim N jam sebelum jadwal
+        let testdrive_reminder_hours = env::var("TESTDRIVE_REMINDER_HOURS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(24);
+
+        // Jam operasional test drive default, dipakai seller yang belum mengatur sendiri
+        let testdrive_hours = BusinessHours::parse(
+            &env::var("TESTDRIVE_OPEN_TIME").unwrap_or_else(|_| business_hours::DEFAULT_OPEN_TIME.to_string()),
+            &env::var("TESTDRIVE_CLOSE_TIME").unwrap_or_else(|_| business_hours::DEFAULT_CLOSE_TIME.to_string()),
+            &env::var("TESTDRIVE_TIMEZONE").unwrap_or_else(|_| business_hours::DEFAULT_TIMEZONE.to_string()),
+        )
+        .map_err(|errors| {
+            let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+            format!("Jam operasional test drive default tidak valid: {}", messages.join(", "))
+        })?;
+
+        // Auto buat conversation buyer-seller saat sale order dibuat
+        let auto_create_sale_conversation = env::var("AUTO_CREATE_SALE_CONVERSATION")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false);
+
+        let chat_service_url = env::var("CHAT_SERVICE_URL").ok();
+
+        if auto_create_sale_conversation && chat_service_url.is_none() {
+            return Err("CHAT_SERVICE_URL harus diset jika AUTO_CREATE_SALE_CONVERSATION=true".to_string());
+        }
+
+        // Secret HMAC untuk signed URL dokumen privat (KTP/SIM)
+        let file_url_secret = env::var("FILE_URL_SIGNING_SECRET")
+            .map_err(|_| "FILE_URL_SIGNING_SECRET harus diset")?;
+
+        // Masa berlaku signed URL dokumen privat (detik)
+        let file_url_ttl_secs = env::var("FILE_URL_TTL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(900);
+
         Ok(AppConfig {
             database_url,
             server_host,
             server_port,
             environment,
             jwt_secret,
+            jwt,
             vehicle_service_url,
             auth_service_url,
             user_service_url,
+            invoice_tax_percentage,
+            seller_sla_minutes,
+            max_counter_rounds,
+            testdrive_reminder_hours,
+            testdrive_hours,
+            auto_create_sale_conversation,
+            chat_service_url,
+            file_url_secret,
+            file_url_ttl_secs,
         })
     }
 
@@ -100,11 +200,12 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 }
 
 // Health check database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
-        .fetch_optional(pool)
+        .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 // Application state yang di-share ke semua handlers
@@ -114,6 +215,7 @@ pub struct AppState {
     pub config: AppConfig,
     pub http_client: reqwest::Client,
     pub rate_limiter: RateLimiter,
+    pub storage: StorageBackend,
 }
 
 impl axum::extract::FromRef<AppState> for PgPool {
@@ -142,6 +244,9 @@ impl AppState {
             .await
             .map_err(|e| format!("Failed to init database: {}", e))?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "booking-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         let http_client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
@@ -162,11 +267,16 @@ impl AppState {
             });
         tracing::info!("✅ Redis rate limiter initialized successfully (MANDATORY)");
 
+        // Storage untuk stream dokumen privat lewat signed URL
+        let storage = StorageBackend::from_env()
+            .map_err(|e| format!("Failed to init storage: {}", e))?;
+
         Ok(AppState {
             db,
             config,
             http_client,
             rate_limiter,
+            storage,
         })
     }
 
@@ -178,26 +288,71 @@ impl AppState {
 
     // Test database connection
     pub async fn test_database_connection(&self) -> Result<(), String> {
-        check_db_health(&self.db)
-            .await
-            .then_some(())
-            .ok_or_else(|| "Database connection failed".to_string())
+        check_db_health(&self.db).await
     }
 
     // Health check semua dependencies
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
+        HealthStatus::check(&self.db).await
+    }
+}
+
+// Response untuk health check endpoint
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct HealthStatus {
+    pub database: DependencyHealth,
+    pub overall: HealthLevel,
+}
+
+impl HealthStatus {
+    // Probe database dengan latency; detail error hanya masuk log
+    pub async fn check(pool: &PgPool) -> Self {
+        let database = health::probe("database", check_db_health(pool)).await;
 
         HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
+            overall: health::worst_status(&[&database]),
+            database,
         }
     }
 }
+#[cfg(test)]
+impl AppState {
+    // State untuk test handler dengan database sqlx::test; Redis dan storage tidak pernah dihubungi
+    pub fn for_test(db: PgPool) -> Self {
+        let jwt_secret = "test-secret".to_string();
 
-// Response untuk health check endpoint
-#[derive(Debug, serde::Serialize)]
-pub struct HealthStatus {
-    pub database: String,
-    pub overall: String,
-}
\ No newline at end of file
+        AppState {
+            db,
+            config: AppConfig {
+                database_url: String::new(),
+                server_host: "127.0.0.1".to_string(),
+                server_port: 3002,
+                environment: "development".to_string(),
+                jwt: JwtConfig::new(jwt_secret.clone()),
+                jwt_secret,
+                vehicle_service_url: "http://127.0.0.1:3003".to_string(),
+                auth_service_url: "http://127.0.0.1:3001".to_string(),
+                user_service_url: "http://127.0.0.1:3004".to_string(),
+                invoice_tax_percentage: 11.0,
+                seller_sla_minutes: 60,
+                max_counter_rounds: 3,
+                testdrive_reminder_hours: 24,
+                testdrive_hours: BusinessHours::parse(
+                    business_hours::DEFAULT_OPEN_TIME,
+                    business_hours::DEFAULT_CLOSE_TIME,
+                    business_hours::DEFAULT_TIMEZONE,
+                )
+                .unwrap(),
+                auto_create_sale_conversation: false,
+                chat_service_url: None,
+                file_url_secret: "test-file-secret".to_string(),
+                file_url_ttl_secs: 900,
+            },
+            http_client: reqwest::Client::new(),
+            rate_limiter: RateLimiter::new("redis://127.0.0.1:6379").unwrap(),
+            storage: StorageBackend::S3(shared::utils::storage::S3Storage::new(
+                "http://127.0.0.1:9000", "test", "us-east-1", "test-key", "test-secret", None,
+            )),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Batas panjang alasan blokir (catatan internal seller)
+const MAX_REASON_LEN: usize = 500;
+
+// Buyer yang diblokir seller untuk transaksi (order & test drive).
+// Terpisah dari blokir chat: ini hanya mengatur transaksi, bukan pesan.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct BlockedBuyer {
+    pub buyer_id: i32,
+    #[schema(example = "Budi Santoso")]
+    pub buyer_name: String,
+    // Catatan internal seller, tidak pernah ditampilkan ke buyer
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Request seller memblokir buyer
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BlockBuyerRequest {
+    pub buyer_id: i32,
+    #[schema(example = "Tiga kali tidak datang test drive")]
+    pub reason: Option<String>,
+}
+
+// Validasi request blokir, return alasan yang sudah di-trim (kosong jadi None)
+pub fn validate_block_request(seller_id: i32, req: &BlockBuyerRequest) -> Result<Option<String>, String> {
+    if req.buyer_id == seller_id {
+        return Err("Tidak bisa memblokir akun sendiri".to_string());
+    }
+
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > MAX_REASON_LEN) {
+        return Err(format!("Alasan blokir maksimal {} karakter", MAX_REASON_LEN));
+    }
+
+    Ok(reason.map(str::to_string))
+}
+
+// Buyer boleh bertransaksi jika vehicle tersedia dan buyer tidak diblokir seller.
+// Sengaja satu kondisi: buyer yang diblokir mendapat error yang sama dengan vehicle
+// tidak tersedia, sehingga blokir tidak terlihat dari sisi buyer.
+pub fn can_transact(is_available: bool, buyer_blocked: bool) -> bool {
+    is_available && !buyer_blocked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(buyer_id: i32, reason: Option<&str>) -> BlockBuyerRequest {
+        BlockBuyerRequest {
+            buyer_id,
+            reason: reason.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_validate_block_request() {
+        assert_eq!(validate_block_request(7, &request(12, Some("  tidak datang  "))), Ok(Some("tidak datang".to_string())));
+        assert_eq!(validate_block_request(7, &request(12, Some("   "))), Ok(None));
+        assert_eq!(validate_block_request(7, &request(12, None)), Ok(None));
+
+        assert!(validate_block_request(7, &request(7, None)).is_err());
+        assert!(validate_block_request(7, &request(12, Some(&"x".repeat(501)))).is_err());
+    }
+}
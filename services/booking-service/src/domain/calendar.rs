@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Query parameters untuk calendar booking customer
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct CalendarQueryParams {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+// Satu event di calendar (test drive atau rental)
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CalendarEvent {
+    #[schema(example = "test_drive")]
+    pub event_type: String,
+    pub booking_id: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub requested_time: Option<String>,
+    pub status: String,
+    pub vehicle_id: i32,
+    pub vehicle_title: String,
+    pub seller_id: i32,
+    pub seller_name: String,
+    /// Tempat pertemuan test drive (null untuk rental)
+    pub location: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+}
+
+// Response calendar dengan pagination
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub events: Vec<CalendarEvent>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
@@ -0,0 +1,28 @@
+// Optimistic concurrency untuk transisi status sale order dan test drive
+//
+// Handler membaca row (status + version), lalu repo menjalankan
+// UPDATE ... WHERE id = $ AND status = $expected AND version = $v.
+// Jika request lain sudah mengubah row lebih dulu, UPDATE tidak mengenai row apa pun
+// dan request ini mendapat 409 Conflict, bukan menimpa state yang sudah berubah.
+
+use crate::error::AppError;
+
+// Hasil UPDATE bersyarat: None berarti row sudah diubah request lain
+pub fn ensure_applied<T>(row: Option<T>, entity: &str) -> Result<T, AppError> {
+    row.ok_or_else(|| AppError::conflict(format!(
+        "{} sudah diubah oleh request lain, muat ulang lalu coba lagi",
+        entity
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Race accept vs reject terhadap query UPDATE asli ada di repositories::sale_repo::tests
+    #[test]
+    fn test_ensure_applied_maps_missing_row_to_conflict() {
+        assert_eq!(ensure_applied(Some(1), "Sale order").unwrap(), 1);
+        assert!(matches!(ensure_applied::<i32>(None, "Sale order"), Err(AppError::Conflict(_))));
+    }
+}
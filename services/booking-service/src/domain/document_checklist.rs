@@ -0,0 +1,357 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use utoipa::ToSchema;
+
+// Checklist dokumen serah terima sale order.
+// Seller bisa mengatur checklist sendiri (seller_document_checklists), tanpa itu dipakai
+// checklist default BPKB/STNK/faktur/pajak. Checklist di-snapshot ke sale_orders.document_checklist
+// saat order dibuat, jadi perubahan checklist seller tidak mengubah order yang sedang berjalan.
+
+pub const MAX_CHECKLIST_ITEMS: usize = 20;
+const MAX_KEY_LEN: usize = 40;
+const MAX_LABEL_LEN: usize = 100;
+
+const DEFAULT_ITEMS: [(&str, &str); 4] = [
+    ("bpkb", "BPKB (Buku Pemilik Kendaraan Bermotor)"),
+    ("stnk", "STNK (Surat Tanda Nomor Kendaraan)"),
+    ("faktur", "Faktur kendaraan"),
+    ("pajak", "Bukti pajak kendaraan"),
+];
+
+fn default_required() -> bool {
+    true
+}
+
+// Satu dokumen di checklist seller
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChecklistItem {
+    /// Kunci dokumen (huruf kecil, angka, underscore), dipakai saat update status dokumen
+    #[schema(example = "kunci_cadangan")]
+    pub key: String,
+    #[schema(example = "Kunci cadangan")]
+    pub label: String,
+    /// Wajib diserahkan sebelum buyer bisa konfirmasi dokumen diterima
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+// Dokumen di checklist order beserta status serah terimanya
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DocumentItem {
+    #[schema(example = "bpkb")]
+    pub key: String,
+    #[schema(example = "BPKB (Buku Pemilik Kendaraan Bermotor)")]
+    pub label: String,
+    pub required: bool,
+    #[serde(default)]
+    pub transferred: bool,
+}
+
+// Ringkasan progress serah terima dokumen order
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct DocumentProgress {
+    pub transferred: i32,
+    pub total: i32,
+    /// Dokumen wajib yang belum diserahkan
+    pub required_remaining: i32,
+    /// Persentase dokumen yang sudah diserahkan (0-100)
+    pub percentage: i32,
+    /// Semua dokumen wajib sudah diserahkan, buyer bisa konfirmasi
+    pub is_complete: bool,
+}
+
+// Request seller mengganti checklist dokumen
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetDocumentChecklistRequest {
+    pub items: Vec<ChecklistItem>,
+}
+
+// Checklist dokumen seller yang dipakai untuk order baru
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentChecklistResponse {
+    pub items: Vec<ChecklistItem>,
+    /// Seller belum mengatur checklist, dipakai checklist default
+    pub is_default: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+pub fn default_checklist() -> Vec<ChecklistItem> {
+    DEFAULT_ITEMS
+        .iter()
+        .map(|&(key, label)| ChecklistItem {
+            key: key.to_string(),
+            label: label.to_string(),
+            required: true,
+        })
+        .collect()
+}
+
+// Validasi checklist dari seller, return item yang sudah di-trim (key huruf kecil)
+pub fn validate_checklist(items: Vec<ChecklistItem>) -> Result<Vec<ChecklistItem>, String> {
+    if items.is_empty() {
+        return Err("Checklist dokumen minimal berisi satu item".to_string());
+    }
+    if items.len() > MAX_CHECKLIST_ITEMS {
+        return Err(format!("Checklist dokumen maksimal {} item", MAX_CHECKLIST_ITEMS));
+    }
+
+    let mut keys = HashSet::new();
+    let items: Vec<ChecklistItem> = items
+        .into_iter()
+        .map(|item| {
+            let key = item.key.trim().to_lowercase();
+            let label = item.label.trim().to_string();
+
+            let valid_key = !key.is_empty()
+                && key.len() <= MAX_KEY_LEN
+                && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid_key {
+                return Err(format!(
+                    "Key dokumen '{}' tidak valid (huruf kecil, angka, underscore, maksimal {} karakter)",
+                    item.key, MAX_KEY_LEN
+                ));
+            }
+            if label.is_empty() || label.chars().count() > MAX_LABEL_LEN {
+                return Err(format!("Label dokumen '{}' wajib diisi, maksimal {} karakter", key, MAX_LABEL_LEN));
+            }
+            if !keys.insert(key.clone()) {
+                return Err(format!("Key dokumen '{}' duplikat", key));
+            }
+
+            Ok(ChecklistItem { key, label, required: item.required })
+        })
+        .collect::<Result<_, _>>()?;
+
+    if !items.iter().any(|item| item.required) {
+        return Err("Checklist dokumen minimal berisi satu dokumen wajib".to_string());
+    }
+
+    Ok(items)
+}
+
+// Checklist order baru, semua dokumen belum diserahkan
+pub fn order_items(checklist: &[ChecklistItem]) -> Vec<DocumentItem> {
+    checklist
+        .iter()
+        .map(|item| DocumentItem {
+            key: item.key.clone(),
+            label: item.label.clone(),
+            required: item.required,
+            transferred: false,
+        })
+        .collect()
+}
+
+// Checklist order dari kolom JSONB sale_orders.document_checklist. Data rusak tidak boleh
+// dianggap checklist kosong, karena checklist kosong berarti tidak ada dokumen wajib
+pub fn parse_order_items(value: &JsonValue) -> Result<Vec<DocumentItem>, String> {
+    let items: Vec<DocumentItem> = serde_json::from_value(value.clone())
+        .map_err(|e| format!("Checklist dokumen order rusak: {}", e))?;
+
+    if items.is_empty() {
+        return Err("Checklist dokumen order kosong".to_string());
+    }
+
+    Ok(items)
+}
+
+// Key dokumen default yang dulu berupa kolom boolean <key>_transferred di sale_orders
+pub const LEGACY_DOCUMENT_KEYS: [&str; 4] = ["bpkb", "stnk", "faktur", "pajak"];
+
+// Status dokumen default untuk field lama bpkb_transferred/stnk_transferred/... di response.
+// Key yang tidak ada di checklist order dianggap belum diserahkan
+pub fn legacy_transferred(items: &[DocumentItem], key: &str) -> bool {
+    items.iter().any(|item| item.key == key && item.transferred)
+}
+
+// Terapkan update status dokumen dari seller; key yang tidak ada di checklist order ditolak
+pub fn apply_updates(items: &[DocumentItem], updates: &BTreeMap<String, bool>) -> Result<Vec<DocumentItem>, String> {
+    if updates.is_empty() {
+        return Err("Minimal satu status dokumen harus diupdate".to_string());
+    }
+
+    let unknown: Vec<&str> = updates
+        .keys()
+        .map(String::as_str)
+        .filter(|key| !items.iter().any(|item| item.key == *key))
+        .collect();
+    if !unknown.is_empty() {
+        let valid: Vec<&str> = items.iter().map(|item| item.key.as_str()).collect();
+        return Err(format!(
+            "Dokumen tidak ada di checklist order: {} (checklist: {})",
+            unknown.join(", "),
+            valid.join(", ")
+        ));
+    }
+
+    Ok(items
+        .iter()
+        .map(|item| DocumentItem {
+            transferred: updates.get(&item.key).copied().unwrap_or(item.transferred),
+            ..item.clone()
+        })
+        .collect())
+}
+
+// Label dokumen wajib yang belum diserahkan
+pub fn missing_required(items: &[DocumentItem]) -> Vec<&str> {
+    items
+        .iter()
+        .filter(|item| item.required && !item.transferred)
+        .map(|item| item.label.as_str())
+        .collect()
+}
+
+pub fn document_progress(items: &[DocumentItem]) -> DocumentProgress {
+    let total = items.len() as i32;
+    let transferred = items.iter().filter(|item| item.transferred).count() as i32;
+    let required_remaining = missing_required(items).len() as i32;
+    let percentage = if total == 0 { 0 } else { transferred * 100 / total };
+
+    DocumentProgress {
+        transferred,
+        total,
+        required_remaining,
+        percentage,
+        is_complete: total > 0 && required_remaining == 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(key: &str, label: &str, required: bool) -> ChecklistItem {
+        ChecklistItem {
+            key: key.to_string(),
+            label: label.to_string(),
+            required,
+        }
+    }
+
+    fn custom_checklist() -> Vec<ChecklistItem> {
+        validate_checklist(vec![
+            item(" BPKB ", "BPKB", true),
+            item("kunci_cadangan", "Kunci cadangan", true),
+            item("buku_servis", " Buku servis ", false),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_custom_checklist() {
+        let checklist = custom_checklist();
+        assert_eq!(checklist[0], item("bpkb", "BPKB", true));
+        assert_eq!(checklist[2], item("buku_servis", "Buku servis", false));
+
+        assert!(validate_checklist(vec![]).is_err());
+        assert!(validate_checklist(vec![item("bpkb", "BPKB", true), item("BPKB", "BPKB asli", true)]).is_err());
+        assert!(validate_checklist(vec![item("kunci cadangan", "Kunci", true)]).is_err());
+        assert!(validate_checklist(vec![item("kunci", " ", true)]).is_err());
+        // Minimal satu dokumen wajib supaya konfirmasi buyer tetap bermakna
+        assert!(validate_checklist(vec![item("buku_servis", "Buku servis", false)]).is_err());
+
+        let too_many = (0..=MAX_CHECKLIST_ITEMS).map(|i| item(&format!("doc_{}", i), "Dokumen", true)).collect();
+        assert!(validate_checklist(too_many).is_err());
+    }
+
+    #[test]
+    fn test_updates_validated_against_order_checklist() {
+        let items = order_items(&custom_checklist());
+
+        // Key bawaan default yang tidak ada di checklist seller ditolak
+        let updates = BTreeMap::from([("stnk".to_string(), true)]);
+        let err = apply_updates(&items, &updates).unwrap_err();
+        assert!(err.contains("stnk") && err.contains("kunci_cadangan"), "{}", err);
+        assert!(apply_updates(&items, &BTreeMap::new()).is_err());
+
+        let updates = BTreeMap::from([("bpkb".to_string(), true), ("buku_servis".to_string(), true)]);
+        let items = apply_updates(&items, &updates).unwrap();
+        assert_eq!(missing_required(&items), vec!["Kunci cadangan"]);
+
+        // Update berikutnya hanya mengubah key yang dikirim
+        let items = apply_updates(&items, &BTreeMap::from([("kunci_cadangan".to_string(), true)])).unwrap();
+        assert!(items.iter().all(|item| item.transferred));
+        assert!(missing_required(&items).is_empty());
+    }
+
+    #[test]
+    fn test_progress_from_dynamic_checklist() {
+        let mut items = order_items(&custom_checklist());
+        assert_eq!(
+            document_progress(&items),
+            DocumentProgress { transferred: 0, total: 3, required_remaining: 2, percentage: 0, is_complete: false }
+        );
+
+        // Dokumen opsional tidak menghalangi konfirmasi buyer
+        items[0].transferred = true;
+        items[1].transferred = true;
+        let progress = document_progress(&items);
+        assert_eq!((progress.transferred, progress.percentage), (2, 66));
+        assert!(progress.is_complete);
+
+        assert!(!document_progress(&[]).is_complete);
+    }
+
+    #[test]
+    fn test_malformed_order_checklist_is_an_error() {
+        assert!(parse_order_items(&serde_json::json!([{ "key": "bpkb" }])).is_err());
+        assert!(parse_order_items(&serde_json::json!(["bpkb"])).is_err());
+        // Array kosong lolos CHECK jsonb_typeof tapi tidak boleh membuat order bisa dikonfirmasi
+        assert!(parse_order_items(&serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn test_legacy_transferred_fields() {
+        let mut items = order_items(&default_checklist());
+        items[0].transferred = true;
+
+        assert!(legacy_transferred(&items, "bpkb"));
+        assert!(!legacy_transferred(&items, "stnk"));
+        assert!(!legacy_transferred(&order_items(&custom_checklist()), "pajak"));
+    }
+
+    #[test]
+    fn test_legacy_update_request_fields_still_accepted() {
+        use crate::domain::sale::UpdateDocumentStatusRequest;
+
+        // Client lama mengirim 4 field boolean tanpa map documents
+        let legacy: UpdateDocumentStatusRequest =
+            serde_json::from_value(serde_json::json!({ "bpkb_transferred": true, "pajak_transferred": false })).unwrap();
+        assert_eq!(
+            legacy.into_updates(),
+            BTreeMap::from([("bpkb".to_string(), true), ("pajak".to_string(), false)])
+        );
+
+        // Jika keduanya dikirim, map documents yang dipakai
+        let mixed: UpdateDocumentStatusRequest = serde_json::from_value(serde_json::json!({
+            "documents": { "bpkb": false, "kunci_cadangan": true },
+            "bpkb_transferred": true
+        }))
+        .unwrap();
+        assert_eq!(
+            mixed.into_updates(),
+            BTreeMap::from([("bpkb".to_string(), false), ("kunci_cadangan".to_string(), true)])
+        );
+    }
+
+    #[test]
+    fn test_legacy_order_checklist_parses_as_default() {
+        // Bentuk hasil migrasi order lama (4 kolom boolean)
+        let value = serde_json::json!([
+            { "key": "bpkb", "label": "BPKB (Buku Pemilik Kendaraan Bermotor)", "required": true, "transferred": true },
+            { "key": "stnk", "label": "STNK (Surat Tanda Nomor Kendaraan)", "required": true, "transferred": false },
+            { "key": "faktur", "label": "Faktur kendaraan", "required": true, "transferred": false },
+            { "key": "pajak", "label": "Bukti pajak kendaraan", "required": true, "transferred": false }
+        ]);
+
+        let items = parse_order_items(&value).unwrap();
+        let keys: Vec<&str> = items.iter().map(|item| item.key.as_str()).collect();
+        let default_keys: Vec<String> = default_checklist().into_iter().map(|item| item.key).collect();
+        assert_eq!(keys, default_keys);
+        assert_eq!(document_progress(&items).required_remaining, 3);
+    }
+}
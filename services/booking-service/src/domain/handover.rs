@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use utoipa::ToSchema;
+
+// Catatan serah terima kendaraan rental: check-in (penyerahan ke customer) dan check-out (pengembalian)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RentalHandover {
+    pub id: i32,
+    pub rental_booking_id: i32,
+    pub kind: String,
+    pub odometer_km: i32,
+    pub fuel_level: i32,
+    pub photos: JsonValue,
+    pub notes: Option<String>,
+    pub recorded_by: i32,
+    pub recorded_at: DateTime<Utc>,
+    pub late_fee: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandoverKind {
+    CheckIn,
+    CheckOut,
+}
+
+impl HandoverKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HandoverKind::CheckIn => "checkin",
+            HandoverKind::CheckOut => "checkout",
+        }
+    }
+}
+
+// Request check-in / check-out (seller)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct HandoverRequest {
+    #[schema(example = 45210)]
+    pub odometer_km: i32,
+    /// Persentase bahan bakar (0-100)
+    #[schema(example = 75)]
+    pub fuel_level: i32,
+    /// Foto kondisi kendaraan dari storage kita (minimal 1)
+    pub photos: Vec<String>,
+    #[schema(example = "Mobil bersih, ban serep lengkap")]
+    pub notes: Option<String>,
+}
+
+// Response check-in / check-out beserta status rental terbaru
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HandoverResponse {
+    pub id: i32,
+    pub rental_booking_id: i32,
+    #[schema(example = "checkin")]
+    pub kind: String,
+    pub odometer_km: i32,
+    pub fuel_level: i32,
+    pub photos: Vec<String>,
+    pub notes: Option<String>,
+    pub recorded_by: i32,
+    pub recorded_at: DateTime<Utc>,
+    /// Denda keterlambatan (rupiah), hanya untuk check-out; ditagihkan lewat laporan pengembalian
+    pub late_fee: i64,
+    #[schema(example = "berjalan")]
+    pub rental_status: String,
+}
+
+impl HandoverResponse {
+    pub fn new(handover: RentalHandover, rental_status: String) -> Self {
+        Self {
+            id: handover.id,
+            rental_booking_id: handover.rental_booking_id,
+            kind: handover.kind,
+            odometer_km: handover.odometer_km,
+            fuel_level: handover.fuel_level,
+            photos: serde_json::from_value(handover.photos).unwrap_or_default(),
+            notes: handover.notes,
+            recorded_by: handover.recorded_by,
+            recorded_at: handover.recorded_at,
+            late_fee: handover.late_fee,
+            rental_status,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// Invoice (faktur) sale order dari sale_invoices table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SaleInvoice {
+    pub id: i32,
+    pub sale_order_id: i32,
+    pub invoice_number: String,
+    pub subtotal: f64,
+    pub tax_percentage: f64,
+    pub tax_amount: f64,
+    pub total_amount: f64,
+    #[serde(skip_serializing)]
+    pub pdf_data: Vec<u8>,
+    pub issued_at: DateTime<Utc>,
+}
+
+// Data kendaraan & seller yang dicetak di invoice
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct InvoiceDetails {
+    pub vehicle_title: String,
+    pub vehicle_brand: String,
+    pub vehicle_model: String,
+    pub vehicle_year: i32,
+    pub seller_name: String,
+    pub seller_business_name: Option<String>,
+    pub seller_email: String,
+    pub seller_phone: String,
+    pub seller_address: Option<String>,
+}
+
+// Invoice baru yang sudah dirender, siap disimpan
+#[derive(Debug, Clone)]
+pub struct NewSaleInvoice {
+    pub sale_order_id: i32,
+    pub invoice_number: String,
+    pub subtotal: f64,
+    pub tax_percentage: f64,
+    pub tax_amount: f64,
+    pub total_amount: f64,
+    pub pdf_data: Vec<u8>,
+}
@@ -1,4 +1,11 @@
 pub mod rental;
 pub mod testdrive;
 pub mod sale;
-
+pub mod invoice;
+pub mod calendar;
+pub mod return_report;
+pub mod webhook;
+pub mod concurrency;
+pub mod buyer_block;
+pub mod document_checklist;
+pub mod handover;
@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use utoipa::ToSchema;
 
+use crate::config::AppConfig;
+use crate::utils::private_file::{self, PrivateFile};
+
 // Model utama RentalBooking dari database
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct RentalBooking {
@@ -26,6 +29,10 @@ pub struct RentalBooking {
     pub status: String,
     pub cancel_reason: Option<String>,
     pub cancelled_at: Option<DateTime<Utc>>,
+    pub deposit_amount: f64,
+    pub deposit_status: String,
+    pub deposit_refunded_amount: Option<f64>,
+    pub deposit_settled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -66,6 +73,15 @@ impl RentalStatus {
     }
 }
 
+// Status awal deposit jaminan (status lengkap dikelola payment-service)
+pub fn initial_deposit_status(deposit_amount: f64) -> &'static str {
+    if deposit_amount > 0.0 {
+        "pending"
+    } else {
+        "none"
+    }
+}
+
 // Request untuk create rental booking baru
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateRentalRequest {
@@ -95,7 +111,7 @@ pub struct UpdateRentalStatusRequest {
 // Request untuk validate pickup (seller)
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct ValidatePickupRequest {
-    #[schema(example = "https://cloudinary.com/ktp.jpg")]
+    #[schema(example = "https://res.cloudinary.com/bigauto/image/upload/v1/documents/ktp.jpg")]
     pub ktp_photo: String,
 }
 
@@ -128,6 +144,7 @@ pub struct RentalBookingResponse {
     pub customer_name: String,
     pub customer_phone: String,
     pub customer_email: String,
+    /// Signed URL `/api/files/{token}` dengan masa berlaku terbatas
     pub ktp_photo: Option<String>,
     pub total_days: i32,
     pub price_per_day: f64,
@@ -136,12 +153,21 @@ pub struct RentalBookingResponse {
     pub status: String,
     pub cancel_reason: Option<String>,
     pub cancelled_at: Option<DateTime<Utc>>,
+    pub deposit_amount: f64,
+    pub deposit_status: String,
+    pub deposit_refunded_amount: Option<f64>,
+    pub deposit_settled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-impl From<RentalBooking> for RentalBookingResponse {
-    fn from(booking: RentalBooking) -> Self {
+impl RentalBookingResponse {
+    // Build response, foto KTP/SIM diganti signed URL
+    pub fn new(booking: RentalBooking, config: &AppConfig) -> Self {
+        let ktp_photo = booking.ktp_photo
+            .as_deref()
+            .map(|url| private_file::signed_path(config, PrivateFile::RentalKtp, booking.id, url));
+
         Self {
             id: booking.id,
             vehicle_id: booking.vehicle_id,
@@ -155,7 +181,7 @@ impl From<RentalBooking> for RentalBookingResponse {
             customer_name: booking.customer_name,
             customer_phone: booking.customer_phone,
             customer_email: booking.customer_email,
-            ktp_photo: booking.ktp_photo,
+            ktp_photo,
             total_days: booking.total_days,
             price_per_day: booking.price_per_day,
             total_price: booking.total_price,
@@ -163,6 +189,10 @@ impl From<RentalBooking> for RentalBookingResponse {
             status: booking.status,
             cancel_reason: booking.cancel_reason,
             cancelled_at: booking.cancelled_at,
+            deposit_amount: booking.deposit_amount,
+            deposit_status: booking.deposit_status,
+            deposit_refunded_amount: booking.deposit_refunded_amount,
+            deposit_settled_at: booking.deposit_settled_at,
             created_at: booking.created_at,
             updated_at: booking.updated_at,
         }
@@ -192,10 +222,10 @@ impl RentalBooking {
         Ok(result.rows_affected())
     }
 
-    /// Update booking status from "akan datang" to "berjalan" when pickup date arrives
+    /// Update booking status from "akan_datang" to "berjalan" when pickup date arrives
     pub async fn update_pickup_ready_bookings(pool: &PgPool) -> Result<u64, sqlx::Error> {
         let result = sqlx::query(
-            "UPDATE rental_bookings SET status = 'berjalan', updated_at = NOW() WHERE status = 'akan datang' AND pickup_date <= NOW()"
+            "UPDATE rental_bookings SET status = 'berjalan', updated_at = NOW() WHERE status = 'akan_datang' AND pickup_date <= NOW()"
         )
         .execute(pool)
         .await?;
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use utoipa::ToSchema;
+
+// Laporan kondisi kendaraan saat rental dikembalikan
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RentalReturnReport {
+    pub id: i32,
+    pub rental_booking_id: i32,
+    pub seller_id: i32,
+    pub customer_id: i32,
+    pub condition_notes: String,
+    pub odometer_km: i32,
+    pub fuel_level: i32,
+    pub damage_items: JsonValue,
+    pub damage_total: i64,
+    pub charge_status: String,
+    pub charge_paid_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Status tagihan kerusakan yang dibayar lewat payment-service
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DamageChargeStatus {
+    None,
+    Pending,
+    Paid,
+}
+
+impl DamageChargeStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DamageChargeStatus::None => "none",
+            DamageChargeStatus::Pending => "pending",
+            DamageChargeStatus::Paid => "paid",
+        }
+    }
+}
+
+// Satu item kerusakan beserta biayanya (rupiah)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DamageItem {
+    #[schema(example = "Baret bumper depan")]
+    pub description: String,
+    #[schema(example = 350000)]
+    pub amount: i64,
+}
+
+// Request untuk seller membuat laporan pengembalian
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateReturnReportRequest {
+    #[schema(example = "Interior bersih, ada baret di bumper depan")]
+    pub condition_notes: String,
+    #[schema(example = 45210)]
+    pub odometer_km: i32,
+    /// Persentase bahan bakar (0-100)
+    #[schema(example = 75)]
+    pub fuel_level: i32,
+    #[serde(default)]
+    pub damage_items: Vec<DamageItem>,
+}
+
+// Response laporan pengembalian, bisa dilihat customer & seller
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReturnReportResponse {
+    pub id: i32,
+    pub rental_booking_id: i32,
+    pub seller_id: i32,
+    pub customer_id: i32,
+    pub condition_notes: String,
+    pub odometer_km: i32,
+    pub fuel_level: i32,
+    pub damage_items: Vec<DamageItem>,
+    /// Total biaya kerusakan yang ditagihkan ke customer
+    pub damage_total: i64,
+    #[schema(example = "pending")]
+    pub charge_status: String,
+    pub charge_paid_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<RentalReturnReport> for ReturnReportResponse {
+    fn from(report: RentalReturnReport) -> Self {
+        Self {
+            id: report.id,
+            rental_booking_id: report.rental_booking_id,
+            seller_id: report.seller_id,
+            customer_id: report.customer_id,
+            condition_notes: report.condition_notes,
+            odometer_km: report.odometer_km,
+            fuel_level: report.fuel_level,
+            damage_items: serde_json::from_value(report.damage_items).unwrap_or_default(),
+            damage_total: report.damage_total,
+            charge_status: report.charge_status,
+            charge_paid_at: report.charge_paid_at,
+            created_at: report.created_at,
+            updated_at: report.updated_at,
+        }
+    }
+}
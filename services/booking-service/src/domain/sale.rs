@@ -1,7 +1,16 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::types::JsonValue;
 use utoipa::ToSchema;
 
+use crate::config::AppConfig;
+use crate::domain::document_checklist::{self, DocumentItem, DocumentProgress};
+use crate::utils::negotiation;
+use crate::utils::private_file::{self, PrivateFile};
+
 // Model utama SaleOrder dari database
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct SaleOrder {
@@ -11,9 +20,11 @@ pub struct SaleOrder {
     pub seller_id: i32,
     pub testdrive_booking_id: Option<i32>,
     pub order_id: String,
+    pub tracking_reference: String,
     pub asking_price: f64,
     pub offer_price: Option<f64>,
     pub counter_offer_price: Option<f64>,
+    pub counter_round: i32,
     pub final_price: f64,
     pub buyer_name: String,
     pub buyer_phone: String,
@@ -21,10 +32,8 @@ pub struct SaleOrder {
     pub buyer_address: Option<String>,
     pub buyer_ktp_photo: Option<String>,
     pub status: String,
-    pub bpkb_transferred: bool,
-    pub stnk_transferred: bool,
-    pub faktur_transferred: bool,
-    pub pajak_transferred: bool,
+    // Snapshot checklist dokumen seller saat order dibuat (array DocumentItem)
+    pub document_checklist: JsonValue,
     pub created_at: DateTime<Utc>,
     pub confirmed_at: Option<DateTime<Utc>>,
     pub paid_at: Option<DateTime<Utc>>,
@@ -37,6 +46,34 @@ pub struct SaleOrder {
     pub rejected_at: Option<DateTime<Utc>>,
     pub buyer_notes: Option<String>,
     pub seller_notes: Option<String>,
+    pub version: i32,
+}
+
+impl SaleOrder {
+    /// Flag order pending_confirmation yang belum direspon seller melewati SLA dan notifikasi seller
+    pub async fn flag_sla_breaches(pool: &PgPool, sla_minutes: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "WITH breached AS (
+                UPDATE sale_orders
+                SET sla_breached_at = NOW()
+                WHERE status = 'pending_confirmation'
+                  AND first_response_at IS NULL
+                  AND sla_breached_at IS NULL
+                  AND created_at < NOW() - $1::BIGINT * INTERVAL '1 minute'
+                RETURNING id, seller_id, order_id
+            )
+            INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+            SELECT seller_id, 'sla_breach', 'Pesanan menunggu respon',
+                   'Order ' || order_id || ' belum direspon lebih dari ' || $1 || ' menit',
+                   id, 'sale_order'
+            FROM breached"
+        )
+        .bind(sla_minutes)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
 
 // Enum untuk status sale order
@@ -144,7 +181,7 @@ pub struct CancelRequest {
 // Request untuk upload KTP (buyer)
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UploadKtpRequest {
-    #[schema(example = "https://cloudinary.com/ktp.jpg")]
+    #[schema(example = "https://res.cloudinary.com/bigauto/image/upload/v1/documents/ktp.jpg")]
     pub ktp_photo: String,
 }
 
@@ -158,23 +195,81 @@ pub struct StartDocumentTransferRequest {
 // Request untuk update document transfer status (seller)
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateDocumentStatusRequest {
-    #[schema(example = true)]
+    /// Status serah terima per key dokumen di checklist order, key yang tidak dikirim tidak berubah
+    #[serde(default)]
+    #[schema(example = json!({"bpkb": true, "kunci_cadangan": true}))]
+    pub documents: BTreeMap<String, bool>,
+    /// Deprecated: pakai `documents.bpkb`
+    #[schema(deprecated)]
     pub bpkb_transferred: Option<bool>,
-    #[schema(example = true)]
+    /// Deprecated: pakai `documents.stnk`
+    #[schema(deprecated)]
     pub stnk_transferred: Option<bool>,
-    #[schema(example = true)]
+    /// Deprecated: pakai `documents.faktur`
+    #[schema(deprecated)]
     pub faktur_transferred: Option<bool>,
-    #[schema(example = true)]
+    /// Deprecated: pakai `documents.pajak`
+    #[schema(deprecated)]
     pub pajak_transferred: Option<bool>,
 }
 
+impl UpdateDocumentStatusRequest {
+    // Gabungkan field lama <key>_transferred ke map documents; key di documents menang
+    pub fn into_updates(self) -> BTreeMap<String, bool> {
+        let legacy = [
+            ("bpkb", self.bpkb_transferred),
+            ("stnk", self.stnk_transferred),
+            ("faktur", self.faktur_transferred),
+            ("pajak", self.pajak_transferred),
+        ];
+
+        let mut updates = self.documents;
+        for (key, value) in legacy {
+            if let Some(value) = value {
+                updates.entry(key.to_string()).or_insert(value);
+            }
+        }
+        updates
+    }
+}
+
 
 // Query parameters untuk sale order filtering
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct SaleOrderQueryParams {
     pub status: Option<String>,
-    pub page: Option<i32>,
-    pub limit: Option<i32>,
+    // Filter tambahan untuk list order seller
+    pub q: Option<String>,
+    pub buyer_name: Option<String>,
+    pub vehicle_id: Option<i32>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    #[schema(example = "newest")]
+    pub sort: Option<String>,
+}
+
+// Urutan list order yang diizinkan (whitelist untuk ORDER BY)
+pub fn sale_order_sort_clause(sort: Option<&str>) -> Option<&'static str> {
+    match sort.unwrap_or("newest") {
+        "newest" => Some("created_at DESC, id DESC"),
+        "oldest" => Some("created_at ASC, id ASC"),
+        "price_asc" => Some("final_price ASC, id DESC"),
+        "price_desc" => Some("final_price DESC, id DESC"),
+        "buyer_name" => Some("buyer_name ASC, id DESC"),
+        "status" => Some("status ASC, created_at DESC"),
+        _ => None,
+    }
+}
+
+// Response list order dengan pagination
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SaleOrderListResponse {
+    pub orders: Vec<SaleOrderResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
 }
 
 // Response untuk sale order
@@ -186,19 +281,37 @@ pub struct SaleOrderResponse {
     pub seller_id: i32,
     pub testdrive_booking_id: Option<i32>,
     pub order_id: String,
+    /// Kode untuk `GET /api/sales/track/{reference}`, bisa dibagikan buyer ke keluarga
+    pub tracking_reference: String,
     pub asking_price: f64,
     pub offer_price: Option<f64>,
     pub counter_offer_price: Option<f64>,
+    /// Jumlah counter offer yang sudah diberikan seller
+    pub counter_round: i32,
+    /// Sisa counter offer sebelum order harus diterima, dibatalkan, atau expire
+    pub counter_rounds_remaining: i32,
     pub final_price: f64,
     pub buyer_name: String,
     pub buyer_phone: String,
     pub buyer_email: String,
     pub buyer_address: Option<String>,
+    /// Signed URL `/api/files/{token}` dengan masa berlaku terbatas
     pub buyer_ktp_photo: Option<String>,
     pub status: String,
+    /// Checklist dokumen serah terima order (diatur seller, default BPKB/STNK/faktur/pajak)
+    pub document_checklist: Vec<DocumentItem>,
+    pub document_progress: DocumentProgress,
+    /// Deprecated: status dokumen `bpkb` di document_checklist
+    #[schema(deprecated)]
     pub bpkb_transferred: bool,
+    /// Deprecated: status dokumen `stnk` di document_checklist
+    #[schema(deprecated)]
     pub stnk_transferred: bool,
+    /// Deprecated: status dokumen `faktur` di document_checklist
+    #[schema(deprecated)]
     pub faktur_transferred: bool,
+    /// Deprecated: status dokumen `pajak` di document_checklist
+    #[schema(deprecated)]
     pub pajak_transferred: bool,
     pub created_at: DateTime<Utc>,
     pub confirmed_at: Option<DateTime<Utc>>,
@@ -212,10 +325,29 @@ pub struct SaleOrderResponse {
     pub rejected_at: Option<DateTime<Utc>>,
     pub buyer_notes: Option<String>,
     pub seller_notes: Option<String>,
+    /// Conversation buyer-seller yang dibuat otomatis saat order dibuat
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<i32>,
 }
 
-impl From<SaleOrder> for SaleOrderResponse {
-    fn from(order: SaleOrder) -> Self {
+impl SaleOrderResponse {
+    // Build response beserta sisa ronde counter offer, KTP diganti signed URL
+    pub fn new(order: SaleOrder, config: &AppConfig) -> Self {
+        let buyer_ktp_photo = order.buyer_ktp_photo
+            .as_deref()
+            .map(|url| private_file::signed_path(config, PrivateFile::SaleKtp, order.id, url));
+        // Checklist rusak ditampilkan kosong (progress tidak complete); aksi seller/buyer
+        // atas dokumen tetap ditolak dengan error di handler
+        let document_checklist = document_checklist::parse_order_items(&order.document_checklist)
+            .unwrap_or_else(|e| {
+                tracing::error!("Sale order {}: {}", order.id, e);
+                Vec::new()
+            });
+        let document_progress = document_checklist::document_progress(&document_checklist);
+        let [bpkb_transferred, stnk_transferred, faktur_transferred, pajak_transferred] =
+            document_checklist::LEGACY_DOCUMENT_KEYS
+                .map(|key| document_checklist::legacy_transferred(&document_checklist, key));
+
         Self {
             id: order.id,
             vehicle_id: order.vehicle_id,
@@ -223,20 +355,25 @@ impl From<SaleOrder> for SaleOrderResponse {
             seller_id: order.seller_id,
             testdrive_booking_id: order.testdrive_booking_id,
             order_id: order.order_id,
+            tracking_reference: order.tracking_reference,
             asking_price: order.asking_price,
             offer_price: order.offer_price,
             counter_offer_price: order.counter_offer_price,
+            counter_round: order.counter_round,
+            counter_rounds_remaining: negotiation::counter_rounds_remaining(order.counter_round, config.max_counter_rounds),
             final_price: order.final_price,
             buyer_name: order.buyer_name,
             buyer_phone: order.buyer_phone,
             buyer_email: order.buyer_email,
             buyer_address: order.buyer_address,
-            buyer_ktp_photo: order.buyer_ktp_photo,
+            buyer_ktp_photo,
             status: order.status,
-            bpkb_transferred: order.bpkb_transferred,
-            stnk_transferred: order.stnk_transferred,
-            faktur_transferred: order.faktur_transferred,
-            pajak_transferred: order.pajak_transferred,
+            document_checklist,
+            document_progress,
+            bpkb_transferred,
+            stnk_transferred,
+            faktur_transferred,
+            pajak_transferred,
             created_at: order.created_at,
             confirmed_at: order.confirmed_at,
             paid_at: order.paid_at,
@@ -249,6 +386,7 @@ impl From<SaleOrder> for SaleOrderResponse {
             rejected_at: order.rejected_at,
             buyer_notes: order.buyer_notes,
             seller_notes: order.seller_notes,
+            conversation_id: None,
         }
     }
 }
@@ -1,6 +1,7 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
+use sqlx::PgPool;
 use utoipa::ToSchema;
 
 // Model utama TestDriveBooking dari database
@@ -23,6 +24,68 @@ pub struct TestDriveBooking {
     pub cancelled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub location: String,
+    pub address: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub proposed_location: Option<JsonValue>,
+    pub reminder_sent_at: Option<DateTime<Utc>>,
+    pub version: i32,
+}
+
+impl TestDriveBooking {
+    /// Kirim notifikasi reminder (beserta lokasi) ke customer dan seller untuk test drive yang akan datang
+    pub async fn send_upcoming_reminders(pool: &PgPool, hours_ahead: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "WITH due AS (
+                UPDATE testdrive_bookings t
+                SET reminder_sent_at = NOW()
+                FROM vehicles v
+                WHERE v.id = t.vehicle_id
+                  AND t.status = 'diterima'
+                  AND t.reminder_sent_at IS NULL
+                  AND t.requested_date > NOW()
+                  AND t.requested_date <= NOW() + $1::BIGINT * INTERVAL '1 hour'
+                RETURNING t.id, t.customer_id, t.seller_id, t.requested_time, v.title,
+                          CASE WHEN t.location = 'customer_address' THEN t.address
+                               ELSE COALESCE(t.address, v.address) END AS meeting_place
+            )
+            INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+            SELECT recipient, 'testdrive_reminder', 'Pengingat test drive',
+                   'Test drive ' || title || ' jam ' || requested_time || ' di ' || meeting_place,
+                   id, 'testdrive_booking'
+            FROM due, LATERAL (VALUES (customer_id), (seller_id)) AS r(recipient)"
+        )
+        .bind(hours_ahead)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// Lokasi pertemuan test drive
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TestDriveLocation {
+    Showroom,
+    CustomerAddress,
+}
+
+impl TestDriveLocation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TestDriveLocation::Showroom => "showroom",
+            TestDriveLocation::CustomerAddress => "customer_address",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "showroom" => Some(TestDriveLocation::Showroom),
+            "customer_address" => Some(TestDriveLocation::CustomerAddress),
+            _ => None,
+        }
+    }
 }
 
 // Enum untuk status test drive booking
@@ -68,8 +131,13 @@ pub struct CreateTestDriveRequest {
     pub vehicle_id: i32,
     #[schema(example = "2025-12-01T10:00:00Z")]
     pub requested_date: DateTime<Utc>,
+    /// Jam lokal seller, diturunkan dari slot jika slot_id diisi
+    #[serde(default)]
     #[schema(example = "10:00")]
     pub requested_time: String,
+    /// Slot dari GET /api/testdrives/sellers/{id}/slots, wajib jika seller memakai jadwal slot
+    #[schema(example = 4)]
+    pub slot_id: Option<i32>,
     #[schema(example = "John Doe")]
     pub customer_name: String,
     #[schema(example = "081234567890")]
@@ -78,16 +146,51 @@ pub struct CreateTestDriveRequest {
     pub customer_email: String,
     #[schema(example = "Ingin test drive sebelum membeli")]
     pub notes: Option<String>,
+    /// showroom (default) atau customer_address
+    #[schema(example = "customer_address")]
+    pub location: Option<String>,
+    /// Wajib diisi jika location = customer_address
+    #[schema(example = "Jl. Sudirman No. 123, Jakarta Selatan")]
+    pub address: Option<String>,
+    #[schema(example = -6.208763)]
+    pub lat: Option<f64>,
+    #[schema(example = 106.845599)]
+    pub lng: Option<f64>,
+}
+
+// Lokasi alternatif yang diusulkan seller saat reschedule
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TestDriveLocationProposal {
+    #[schema(example = "showroom")]
+    pub location: String,
+    #[schema(example = "Showroom Auto Jaya, Jl. Gatot Subroto No. 8")]
+    pub address: Option<String>,
+    #[schema(example = -6.225014)]
+    pub lat: Option<f64>,
+    #[schema(example = 106.830120)]
+    pub lng: Option<f64>,
+}
+
+// Satu slot alternatif reschedule, disimpan sebagai JSON array di reschedule_slots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RescheduleSlot {
+    #[schema(example = "2025-12-02T10:00:00Z")]
+    pub date: DateTime<Utc>,
+    #[schema(example = "10:00")]
+    pub time: String,
 }
 
 // Request untuk seller reschedule test drive
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct RescheduleTestDriveRequest {
+    /// Maksimal 3 slot, di masa depan, jam 08:00-17:00, tidak bentrok di hari yang sama
     #[schema(example = json!([
         {"date": "2025-12-02T10:00:00Z", "time": "10:00"},
         {"date": "2025-12-03T14:00:00Z", "time": "14:00"}
     ]))]
-    pub reschedule_slots: serde_json::Value,
+    pub reschedule_slots: Vec<RescheduleSlot>,
+    /// Lokasi alternatif, berlaku saat customer memilih slot
+    pub location: Option<TestDriveLocationProposal>,
 }
 
 // Request untuk customer pilih slot reschedule
@@ -97,6 +200,127 @@ pub struct ChooseRescheduleSlotRequest {
     pub slot_index: usize,
 }
 
+// Request seller untuk mengatur jam operasional test drive (jam lokal di `timezone`)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetBusinessHoursRequest {
+    #[schema(example = "09:00")]
+    pub open_time: String,
+    #[schema(example = "17:00")]
+    pub close_time: String,
+    #[schema(example = "Asia/Jakarta")]
+    pub timezone: String,
+}
+
+// Jam operasional test drive seller
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BusinessHoursResponse {
+    #[schema(example = "09:00")]
+    pub open_time: String,
+    #[schema(example = "17:00")]
+    pub close_time: String,
+    // Jam mulai test drive paling lambat (close_time dikurangi durasi satu sesi)
+    #[schema(example = "16:00")]
+    pub last_start_time: String,
+    #[schema(example = "Asia/Jakarta")]
+    pub timezone: String,
+    // true jika seller belum mengatur dan memakai jam default
+    pub is_default: bool,
+}
+
+// Satu aturan availability seller dari tabel seller_availability
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct SellerAvailability {
+    pub id: i32,
+    pub weekday: Option<i16>,
+    pub date: Option<NaiveDate>,
+    pub start_time: Option<NaiveTime>,
+    pub capacity: i32,
+    pub is_available: bool,
+}
+
+// Slot mingguan berulang yang dipublikasikan seller
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WeeklySlotInput {
+    /// 0 = Senin ... 6 = Minggu
+    #[schema(example = 0)]
+    pub weekday: i16,
+    #[schema(example = "10:00")]
+    pub time: String,
+    /// Jumlah test drive paralel di slot ini (default 1)
+    #[schema(example = 1)]
+    pub capacity: Option<i32>,
+}
+
+// Pengecualian jadwal di tanggal tertentu
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AvailabilityExceptionInput {
+    #[schema(example = "2026-03-17")]
+    pub date: NaiveDate,
+    /// Kosong = seluruh hari (hanya untuk available = false)
+    #[schema(example = "10:00")]
+    pub time: Option<String>,
+    /// false menutup slot mingguan, true menambah slot ekstra di tanggal ini
+    pub available: bool,
+    #[schema(example = 1)]
+    pub capacity: Option<i32>,
+}
+
+// Request seller untuk mengganti seluruh jadwal slot test drive
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetAvailabilityRequest {
+    pub weekly: Vec<WeeklySlotInput>,
+    #[serde(default)]
+    pub exceptions: Vec<AvailabilityExceptionInput>,
+}
+
+// Satu aturan availability di response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AvailabilityEntry {
+    pub id: i32,
+    pub weekday: Option<i16>,
+    pub date: Option<NaiveDate>,
+    #[schema(example = "10:00")]
+    pub time: Option<String>,
+    pub capacity: i32,
+    pub available: bool,
+}
+
+// Jadwal slot test drive seller (jam lokal di `timezone`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AvailabilityResponse {
+    #[schema(example = "Asia/Jakarta")]
+    pub timezone: String,
+    pub entries: Vec<AvailabilityEntry>,
+}
+
+// Query slot test drive seller untuk satu tanggal (tanggal lokal seller)
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct SlotQuery {
+    #[schema(example = "2026-03-16")]
+    pub date: NaiveDate,
+}
+
+// Slot yang masih bisa dibooking
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct OpenSlot {
+    pub slot_id: i32,
+    #[schema(example = "10:00")]
+    pub time: String,
+    pub starts_at: DateTime<Utc>,
+    /// Sisa kapasitas setelah dikurangi booking aktif
+    pub remaining: i64,
+}
+
+// Slot terbuka seller di satu tanggal
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SellerSlotsResponse {
+    pub seller_id: i32,
+    pub date: NaiveDate,
+    #[schema(example = "Asia/Jakarta")]
+    pub timezone: String,
+    pub slots: Vec<OpenSlot>,
+}
+
 // Request untuk confirm test drive (seller)
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct ConfirmTestDriveRequest {
@@ -118,6 +342,63 @@ pub struct CancelTestDriveRequest {
     pub cancel_reason: String,
 }
 
+// Request seller accept banyak test drive sekaligus
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkAcceptTestDriveRequest {
+    #[schema(example = json!([12, 13, 15]))]
+    pub booking_ids: Vec<i32>,
+}
+
+// Request seller tolak banyak test drive sekaligus
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkRejectTestDriveRequest {
+    #[schema(example = json!([12, 13, 15]))]
+    pub booking_ids: Vec<i32>,
+    #[schema(example = "Unit sedang tidak tersedia untuk test drive")]
+    pub reason: Option<String>,
+}
+
+// Hasil per booking di bulk accept/reject
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkTestDriveResult {
+    pub id: i32,
+    pub success: bool,
+    /// Status booking setelah diproses (jika berhasil)
+    #[schema(example = "diterima")]
+    pub status: Option<String>,
+    /// Alasan booking dilewati (jika gagal)
+    pub error: Option<String>,
+}
+
+impl BulkTestDriveResult {
+    pub fn ok(id: i32, status: &str) -> Self {
+        Self { id, success: true, status: Some(status.to_string()), error: None }
+    }
+
+    pub fn skipped(id: i32, error: impl Into<String>) -> Self {
+        Self { id, success: false, status: None, error: Some(error.into()) }
+    }
+}
+
+// Response bulk accept/reject
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkTestDriveResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkTestDriveResult>,
+}
+
+impl From<Vec<BulkTestDriveResult>> for BulkTestDriveResponse {
+    fn from(results: Vec<BulkTestDriveResult>) -> Self {
+        let succeeded = results.iter().filter(|r| r.success).count();
+        Self {
+            succeeded,
+            failed: results.len() - succeeded,
+            results,
+        }
+    }
+}
+
 
 // Response untuk test drive booking
 #[derive(Debug, Serialize, ToSchema)]
@@ -139,6 +420,13 @@ pub struct TestDriveBookingResponse {
     pub cancelled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[schema(example = "customer_address")]
+    pub location: String,
+    pub address: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    /// Lokasi alternatif dari seller yang menunggu dipilih customer
+    pub proposed_location: Option<JsonValue>,
 }
 
 impl From<TestDriveBooking> for TestDriveBookingResponse {
@@ -161,6 +449,11 @@ impl From<TestDriveBooking> for TestDriveBookingResponse {
             cancelled_at: booking.cancelled_at,
             created_at: booking.created_at,
             updated_at: booking.updated_at,
+            location: booking.location,
+            address: booking.address,
+            lat: booking.lat,
+            lng: booking.lng,
+            proposed_location: booking.proposed_location,
         }
     }
 }
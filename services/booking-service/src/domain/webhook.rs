@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use utoipa::ToSchema;
+
+// Endpoint outbound webhook milik seller
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OutboundWebhook {
+    pub id: i32,
+    pub seller_id: i32,
+    pub url: String,
+    pub secret: String,
+    pub is_active: bool,
+    pub secret_rotated_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Satu delivery webhook (antrian + log)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i32,
+    #[schema(example = "sale_order.status_changed")]
+    pub event_type: String,
+    #[schema(value_type = Object)]
+    pub payload: JsonValue,
+    #[schema(example = "delivered")]
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Status delivery webhook
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+// Request seller mendaftarkan endpoint webhook
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    #[schema(example = "https://dms.dealer.co.id/bigauto/webhook")]
+    pub url: String,
+}
+
+// Response endpoint webhook, secret hanya dikirim saat register/rotate
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: i32,
+    pub url: String,
+    pub is_active: bool,
+    /// Shared secret untuk verifikasi signature, simpan karena tidak ditampilkan lagi
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "whsec_3f9a...")]
+    pub secret: Option<String>,
+    pub secret_rotated_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookResponse {
+    pub fn new(webhook: OutboundWebhook, reveal_secret: bool) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            is_active: webhook.is_active,
+            secret: reveal_secret.then_some(webhook.secret),
+            secret_rotated_at: webhook.secret_rotated_at,
+            disabled_at: webhook.disabled_at,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+// Query list delivery terbaru
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct DeliveryQueryParams {
+    /// Filter status: pending, delivered, failed
+    pub status: Option<String>,
+}
@@ -4,6 +4,8 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use shared::utils::request_id;
+use shared::utils::validation::{errors_by_field, FieldError, FromFieldErrors};
 
 // Type alias untuk Result dengan AppError
 pub type AppResult<T = ()> = Result<T, AppError>;
@@ -16,7 +18,7 @@ pub enum AppError {
     Unauthorized(String),
     Forbidden(String),
     BadRequest(String),
-    ValidationError(String),
+    ValidationError { message: String, errors: Vec<FieldError> },
     Conflict(String),
     InternalServer(String),
     InternalError(String),
@@ -48,7 +50,10 @@ impl AppError {
     }
 
     pub fn validation(msg: impl Into<String>) -> Self {
-        Self::ValidationError(msg.into())
+        Self::ValidationError {
+            message: msg.into(),
+            errors: Vec::new(),
+        }
     }
 
     pub fn conflict(msg: impl Into<String>) -> Self {
@@ -64,6 +69,31 @@ impl AppError {
     }
 }
 
+// Konversi dari shared AuthError, status mengikuti shared agar sama di semua service
+impl From<shared::auth::AuthError> for AppError {
+    fn from(err: shared::auth::AuthError) -> Self {
+        match err.status_code() {
+            StatusCode::FORBIDDEN => AppError::forbidden(err.to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR => AppError::internal(err.to_string()),
+            _ => AppError::unauthorized(err.to_string()),
+        }
+    }
+}
+
+// Error validasi per field (AppError::fields dari shared)
+impl FromFieldErrors for AppError {
+    fn from_field_errors(message: String, errors: Vec<FieldError>) -> Self {
+        AppError::ValidationError { message, errors }
+    }
+}
+
+// Konversi dari error validasi satu field
+impl From<FieldError> for AppError {
+    fn from(err: FieldError) -> Self {
+        AppError::fields(vec![err])
+    }
+}
+
 // Konversi dari sqlx::Error ke AppError
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
@@ -94,9 +124,9 @@ impl IntoResponse for AppError {
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "autentikasi_diperlukan", msg.clone()),
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "akses_dilarang", msg.clone()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "request_tidak_valid", msg.clone()),
-            AppError::ValidationError(msg) => {
-                tracing::warn!("Validation error: {}", msg);
-                (StatusCode::UNPROCESSABLE_ENTITY, "validasi_gagal", msg.clone())
+            AppError::ValidationError { message, .. } => {
+                tracing::warn!("Validation error: {}", message);
+                (StatusCode::UNPROCESSABLE_ENTITY, "validasi_gagal", message.clone())
             },
             AppError::Conflict(msg) => {
                 tracing::warn!("Conflict error: {}", msg);
@@ -128,11 +158,41 @@ impl IntoResponse for AppError {
             },
         };
 
-        let body = Json(json!({
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
+        let mut body = json!({
             "error": error_type,
             "pesan": message,
-        }));
+            "request_id": request_id,
+        });
+
+        // Pesan per field agar frontend bisa menandai input yang salah
+        if let AppError::ValidationError { errors, .. } = &self {
+            if !errors.is_empty() {
+                body["errors"] = json!(errors_by_field(errors));
+            }
+        }
+        let mut response = (status, Json(body)).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("Booking tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
 
-        (status, body).into_response()
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
     }
 }
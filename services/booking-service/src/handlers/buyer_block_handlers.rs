@@ -0,0 +1,179 @@
+// API Handlers untuk blocklist buyer milik seller (menolak order & test drive dari buyer bermasalah)
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use shared::utils::creation::Creation;
+
+use crate::{
+    domain::buyer_block::{self, BlockBuyerRequest, BlockedBuyer},
+    middleware::auth::AuthSeller,
+    repositories::buyer_block_repo,
+    error::AppError,
+    AppState,
+};
+
+// List buyer yang diblokir seller
+#[utoipa::path(
+    get,
+    path = "/api/blocked-buyers",
+    tag = "buyer-blocks",
+    summary = "Buyer yang saya blokir",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "List buyer yang diblokir", body = Vec<BlockedBuyer>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_blocked_buyers(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+) -> Result<Json<Vec<BlockedBuyer>>, AppError> {
+    let blocked = buyer_block_repo::find_blocked_buyers(&state.db, auth.user_id).await?;
+
+    Ok(Json(blocked))
+}
+
+// Blokir buyer dari transaksi dengan seller
+#[utoipa::path(
+    post,
+    path = "/api/blocked-buyers",
+    tag = "buyer-blocks",
+    summary = "Blokir buyer",
+    description = "Buyer yang diblokir tidak bisa membuat order pembelian atau booking test drive untuk mobil seller ini. Buyer hanya melihat error vehicle tidak tersedia. Tidak mempengaruhi chat. Hanya buyer yang pernah chat, order, test drive, atau rental dengan seller yang bisa diblokir",
+    security(("bearer_auth" = [])),
+    request_body = BlockBuyerRequest,
+    responses(
+        (status = 201, description = "Buyer diblokir", body = BlockedBuyer,
+            headers(("Location" = String, description = "URL buyer yang diblokir"))),
+        (status = 200, description = "Buyer sudah diblokir, alasan diperbarui", body = BlockedBuyer),
+        (status = 400, description = "Request tidak valid"),
+        (status = 404, description = "Buyer tidak ditemukan atau belum pernah menghubungi seller"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn block_buyer(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Json(payload): Json<BlockBuyerRequest>,
+) -> Result<Creation<BlockedBuyer>, AppError> {
+    let reason = buyer_block::validate_block_request(auth.user_id, &payload)
+        .map_err(AppError::validation)?;
+
+    let (blocked, inserted) = buyer_block_repo::block_buyer(&state.db, auth.user_id, payload.buyer_id, reason.as_deref())
+        .await?
+        .ok_or_else(|| AppError::not_found("Buyer tidak ditemukan"))?;
+
+    tracing::info!("Seller {} memblokir buyer {}", auth.user_id, payload.buyer_id);
+
+    // Blokir ulang buyer yang sudah diblokir hanya memperbarui alasan
+    if !inserted {
+        return Ok(Creation::existing(blocked));
+    }
+
+    Ok(Creation::created(format!("/api/blocked-buyers/{}", blocked.buyer_id), blocked))
+}
+
+// Detail buyer yang diblokir (target Location saat buyer diblokir)
+#[utoipa::path(
+    get,
+    path = "/api/blocked-buyers/{buyer_id}",
+    tag = "buyer-blocks",
+    summary = "Detail buyer yang diblokir",
+    security(("bearer_auth" = [])),
+    params(("buyer_id" = i32, Path, description = "Buyer ID")),
+    responses(
+        (status = 200, description = "Buyer yang diblokir", body = BlockedBuyer),
+        (status = 404, description = "Buyer tidak ada di blocklist"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_blocked_buyer(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Path(buyer_id): Path<i32>,
+) -> Result<Json<BlockedBuyer>, AppError> {
+    let blocked = buyer_block_repo::find_blocked_buyer(&state.db, auth.user_id, buyer_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Buyer tidak ada di blocklist"))?;
+
+    Ok(Json(blocked))
+}
+
+// Buka blokir buyer
+#[utoipa::path(
+    delete,
+    path = "/api/blocked-buyers/{buyer_id}",
+    tag = "buyer-blocks",
+    summary = "Buka blokir buyer",
+    security(("bearer_auth" = [])),
+    params(("buyer_id" = i32, Path, description = "Buyer ID")),
+    responses(
+        (status = 204, description = "Blokir dibuka"),
+        (status = 404, description = "Buyer tidak ada di blocklist"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn unblock_buyer(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Path(buyer_id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    if !buyer_block_repo::unblock_buyer(&state.db, auth.user_id, buyer_id).await? {
+        return Err(AppError::not_found("Buyer tidak ada di blocklist"));
+    }
+
+    tracing::info!("Seller {} membuka blokir buyer {}", auth.user_id, buyer_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::header, response::IntoResponse};
+
+    fn seller() -> AuthSeller {
+        AuthSeller { user_id: 2, email: "seller@test.local".to_string() }
+    }
+
+    fn request(reason: &str) -> Json<BlockBuyerRequest> {
+        Json(BlockBuyerRequest { buyer_id: 1, reason: Some(reason.to_string()) })
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_block_returns_201_with_location_then_200(pool: sqlx::PgPool) {
+        // Blokir hanya untuk buyer yang pernah menghubungi seller
+        sqlx::query(
+            "INSERT INTO testdrive_bookings (vehicle_id, customer_id, seller_id, requested_date, requested_time,
+                 customer_name, customer_phone, customer_email)
+             VALUES (2, 1, 2, NOW() + INTERVAL '1 day', '10:00', 'Customer Test', '081234567890', 'customer@test.local')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let state = AppState::for_test(pool);
+
+        let created = block_buyer(State(state.clone()), seller(), request("Tidak datang test drive"))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        assert_eq!(created.headers()[header::LOCATION], "/api/blocked-buyers/1");
+        let Json(fetched) = get_blocked_buyer(State(state.clone()), seller(), Path(1)).await.unwrap();
+        assert_eq!(fetched.reason.as_deref(), Some("Tidak datang test drive"));
+
+        // Blokir ulang hanya memperbarui alasan
+        let existing = block_buyer(State(state), seller(), request("Alasan baru")).await.unwrap().into_response();
+        assert_eq!(existing.status(), StatusCode::OK);
+        assert!(existing.headers().get(header::LOCATION).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+// API Handlers untuk calendar booking customer (test drive + rental)
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{Duration, Utc};
+use shared::utils::pagination::{Pagination, PaginationParams};
+
+use crate::{
+    domain::calendar::{CalendarQueryParams, CalendarResponse},
+    middleware::auth::AuthCustomer,
+    repositories::calendar_repo,
+    utils::ics,
+    error::AppError,
+    AppState,
+};
+
+// Window default dan maksimal calendar
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+const MAX_WINDOW_DAYS: i64 = 366;
+
+// Calendar gabungan test drive & rental milik customer
+#[utoipa::path(
+    get,
+    path = "/api/bookings/calendar",
+    tag = "calendar",
+    summary = "Calendar booking saya",
+    description = "Gabungan test drive dan rental customer dalam satu list event kronologis. Kirim `Accept: text/calendar` untuk format .ics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(CalendarQueryParams, PaginationParams),
+    responses(
+        (status = 200, description = "Event calendar", body = CalendarResponse),
+        (status = 200, description = "Calendar iCalendar", content_type = "text/calendar"),
+        (status = 400, description = "Window tanggal atau parameter paginasi tidak valid"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_booking_calendar(
+    State(state): State<AppState>,
+    auth: AuthCustomer,
+    headers: HeaderMap,
+    Query(params): Query<CalendarQueryParams>,
+    Pagination { page, limit, offset }: Pagination<50, 200>,
+) -> Result<Response, AppError> {
+    let from = params.from.unwrap_or_else(Utc::now);
+    let to = params.to.unwrap_or(from + Duration::days(DEFAULT_WINDOW_DAYS));
+
+    if to <= from {
+        return Err(AppError::bad_request("Parameter 'to' harus setelah 'from'"));
+    }
+
+    if to - from > Duration::days(MAX_WINDOW_DAYS) {
+        return Err(AppError::bad_request(format!(
+            "Rentang calendar maksimal {} hari",
+            MAX_WINDOW_DAYS
+        )));
+    }
+
+    let events = calendar_repo::find_calendar_events(
+        &state.db,
+        auth.user_id,
+        from,
+        to,
+        limit,
+        offset,
+    ).await?;
+
+    // Format .ics jika client minta text/calendar
+    let wants_ics = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/calendar"));
+
+    if wants_ics {
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "inline; filename=\"bigauto-bookings.ics\""),
+            ],
+            ics::render_calendar(&events, Utc::now()),
+        ).into_response());
+    }
+
+    let total = calendar_repo::count_calendar_events(&state.db, auth.user_id, from, to).await?;
+
+    Ok(Json(CalendarResponse {
+        from,
+        to,
+        events,
+        total,
+        page,
+        limit,
+    }).into_response())
+}
@@ -0,0 +1,76 @@
+// API Handlers untuk checklist dokumen serah terima milik seller (dipakai untuk order pembelian baru)
+use axum::{extract::State, Json};
+
+use crate::{
+    domain::document_checklist::{self, DocumentChecklistResponse, SetDocumentChecklistRequest},
+    middleware::auth::AuthSeller,
+    repositories::document_checklist_repo,
+    error::AppError,
+    AppState,
+};
+
+// Checklist dokumen seller (default jika belum diatur)
+#[utoipa::path(
+    get,
+    path = "/api/sales/document-checklist",
+    tag = "sale-orders",
+    summary = "Checklist dokumen serah terima saya",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Checklist dokumen seller", body = DocumentChecklistResponse),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_document_checklist(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+) -> Result<Json<DocumentChecklistResponse>, AppError> {
+    let response = match document_checklist_repo::find_checklist(&state.db, auth.user_id).await? {
+        Some((items, updated_at)) => DocumentChecklistResponse {
+            items,
+            is_default: false,
+            updated_at: Some(updated_at),
+        },
+        None => DocumentChecklistResponse {
+            items: document_checklist::default_checklist(),
+            is_default: true,
+            updated_at: None,
+        },
+    };
+
+    Ok(Json(response))
+}
+
+// Ganti checklist dokumen seller
+#[utoipa::path(
+    put,
+    path = "/api/sales/document-checklist",
+    tag = "sale-orders",
+    summary = "Atur checklist dokumen serah terima",
+    description = "Checklist dipakai untuk order pembelian yang dibuat setelah perubahan. Order yang sudah ada tetap memakai checklist saat order dibuat",
+    security(("bearer_auth" = [])),
+    request_body = SetDocumentChecklistRequest,
+    responses(
+        (status = 200, description = "Checklist dokumen disimpan", body = DocumentChecklistResponse),
+        (status = 400, description = "Checklist tidak valid"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn set_document_checklist(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Json(payload): Json<SetDocumentChecklistRequest>,
+) -> Result<Json<DocumentChecklistResponse>, AppError> {
+    let items = document_checklist::validate_checklist(payload.items)
+        .map_err(AppError::validation)?;
+
+    let updated_at = document_checklist_repo::upsert_checklist(&state.db, auth.user_id, &items).await?;
+
+    tracing::info!("Seller {} mengatur checklist dokumen ({} item)", auth.user_id, items.len());
+
+    Ok(Json(DocumentChecklistResponse {
+        items,
+        is_default: false,
+        updated_at: Some(updated_at),
+    }))
+}
@@ -0,0 +1,74 @@
+// API Handlers untuk dokumen privat lewat signed URL
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use shared::utils::storage::{verify_file_token, Storage, StorageError};
+
+use crate::{
+    middleware::auth::AuthUser,
+    repositories::{rental_repo, sale_repo},
+    utils::private_file::{self, PrivateFile},
+    error::AppError,
+    AppState,
+};
+
+// Stream dokumen privat (KTP/SIM) setelah validasi signature, expiry, dan akses user
+#[utoipa::path(
+    get,
+    path = "/api/files/{token}",
+    tag = "files",
+    summary = "Download dokumen privat",
+    description = "Validasi signed URL (HMAC + expiry) lalu stream file. Hanya pihak yang terlibat di booking yang boleh mengakses",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("token" = String, Path, description = "Token signed URL dari response booking")
+    ),
+    responses(
+        (status = 200, description = "Isi file"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Signed URL tidak valid, kedaluwarsa, atau bukan milik user"),
+        (status = 404, description = "Dokumen tidak ditemukan")
+    )
+)]
+pub async fn get_private_file(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(token): Path<String>,
+) -> Result<Response, AppError> {
+    let claims = verify_file_token(state.config.file_url_secret.as_bytes(), &token, Utc::now().timestamp())
+        .map_err(|e| AppError::forbidden(e.to_string()))?;
+
+    let kind = PrivateFile::from_str(&claims.resource)
+        .ok_or_else(|| AppError::forbidden("Signed URL tidak valid"))?;
+
+    // URL harus masih sama dengan dokumen di booking, dokumen yang diganti tidak bisa diakses lagi
+    let current_url = match kind {
+        PrivateFile::SaleKtp => sale_repo::find_ktp_for_party(&state.db, claims.resource_id, auth.user_id).await?,
+        PrivateFile::RentalKtp => rental_repo::find_ktp_for_party(&state.db, claims.resource_id, auth.user_id).await?,
+    };
+
+    if current_url.as_deref() != Some(claims.url.as_str()) {
+        return Err(AppError::forbidden("Anda tidak memiliki akses ke dokumen ini"));
+    }
+
+    let bytes = state.storage.get(&claims.url).await.map_err(|e| match e {
+        StorageError::NotFound(_) => AppError::not_found("Dokumen tidak ditemukan"),
+        other => {
+            tracing::error!("Gagal ambil dokumen privat {} {}: {}", claims.resource, claims.resource_id, other);
+            AppError::internal("Gagal mengambil dokumen")
+        }
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, private_file::content_type_for(&claims.url)),
+            (header::CONTENT_DISPOSITION, "inline"),
+        ],
+        bytes,
+    ).into_response())
+}
@@ -3,3 +3,8 @@ pub mod rental_handlers;
 pub mod testdrive_handlers;
 pub mod sale_handlers;
 
+pub mod calendar_handlers;
+pub mod file_handlers;
+pub mod webhook_handlers;
+pub mod buyer_block_handlers;
+pub mod document_checklist_handlers;
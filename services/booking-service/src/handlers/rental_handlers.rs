@@ -1,4 +1,5 @@
 use axum::{extract::{Path, Query, State}, Json};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -8,13 +9,16 @@ use crate::{
         ValidatePickupRequest, ValidateReturnRequest, CancelRentalRequest,
         UpdateRentalStatusRequest, RentalStatus,
     },
+    domain::handover::{HandoverKind, HandoverRequest, HandoverResponse},
+    domain::return_report::{CreateReturnReportRequest, ReturnReportResponse},
     error::AppError,
-    repositories::rental_repo,
+    repositories::{handover_repo, rental_repo, return_report_repo},
+    utils::{handover, return_report},
     AppState,
 };
 
 use crate::middleware::auth::{AuthUser, AuthCustomer, AuthSeller};
-use shared::utils::validation;
+use shared::utils::{creation::Creation, validation::{self, FieldError, FromFieldErrors}};
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct MessageResponse {
@@ -34,7 +38,8 @@ pub struct RentalFilter {
     security(("bearer_auth" = [])),
     request_body = CreateRentalRequest,
     responses(
-        (status = 201, description = "Rental booking created", body = RentalBookingResponse),
+        (status = 201, description = "Rental booking created", body = RentalBookingResponse,
+            headers(("Location" = String, description = "URL rental booking"))),
         (status = 400, description = "Input tidak valid"),
         (status = 409, description = "Vehicle tidak available"),
     )
@@ -43,12 +48,11 @@ pub async fn create_rental_booking(
     auth: AuthCustomer,
     State(state): State<AppState>,
     Json(payload): Json<CreateRentalRequest>,
-) -> Result<Json<RentalBookingResponse>, AppError> {
+) -> Result<Creation<RentalBookingResponse>, AppError> {
     tracing::info!(
-        "Customer {} ({}) creating rental for vehicle {}",
-        auth.user_id,
-        auth.email,
-        payload.vehicle_id
+        customer_id = auth.user_id,
+        vehicle_id = payload.vehicle_id,
+        "Customer creating rental"
     );
 
     // Validasi input
@@ -65,6 +69,9 @@ pub async fn create_rental_booking(
         id: i32,
         seller_id: i32,
         price_per_day: f64,
+        // Deposit jaminan yang ditetapkan seller (0 jika tidak ada). Wajib ada di response:
+        // vehicle-service lama tanpa field ini membuat booking gagal, bukan deposit 0 diam-diam
+        deposit_amount: f64,
         is_available: bool,
     }
 
@@ -82,13 +89,16 @@ pub async fn create_rental_booking(
     let vehicle_info: VehicleRentalInfo = response
         .json()
         .await
-        .map_err(|e| AppError::database_error(format!("Gagal parse response: {}", e)))?;
+        .map_err(|e| AppError::database_error(format!("Gagal parse rental-info vehicle-service: {}", e)))?;
 
     if !vehicle_info.is_available {
         return Err(AppError::Conflict("Vehicle tidak tersedia untuk rental".to_string()));
     }
 
     let (vehicle_id, seller_id, price_per_day) = (vehicle_info.id, vehicle_info.seller_id, vehicle_info.price_per_day);
+    if vehicle_info.deposit_amount < 0.0 {
+        return Err(AppError::validation("Deposit rental tidak valid"));
+    }
 
     // Check availability
     let is_available = rental_repo::check_vehicle_availability(
@@ -108,12 +118,25 @@ pub async fn create_rental_booking(
         auth.user_id,
         seller_id,
         price_per_day,
+        vehicle_info.deposit_amount,
         &payload,
     ).await?;
 
-    tracing::info!("Rental booking {} created", rental.order_id);
+    tracing::info!(
+        event = "booking_created",
+        booking_type = "rental",
+        booking_id = rental.id,
+        order_id = %rental.order_id,
+        customer_id = auth.user_id,
+        seller_id = rental.seller_id,
+        vehicle_id = rental.vehicle_id,
+        "Rental booking created"
+    );
 
-    Ok(Json(RentalBookingResponse::from(rental)))
+    Ok(Creation::created(
+        format!("/api/rentals/bookings/{}", rental.id),
+        RentalBookingResponse::new(rental, &state.config),
+    ))
 }
 
 // Get rental booking by ID
@@ -142,7 +165,7 @@ pub async fn get_rental_booking(
         return Err(AppError::forbidden("Anda tidak memiliki akses ke booking ini"));
     }
 
-    Ok(Json(RentalBookingResponse::from(rental)))
+    Ok(Json(RentalBookingResponse::new(rental, &state.config)))
 }
 
 // List my rental bookings (customer)
@@ -169,7 +192,7 @@ pub async fn get_customer_rental_bookings(
 
     let response: Vec<RentalBookingResponse> = rentals
         .into_iter()
-        .map(RentalBookingResponse::from)
+        .map(|rental| RentalBookingResponse::new(rental, &state.config))
         .collect();
 
     Ok(Json(response))
@@ -199,7 +222,7 @@ pub async fn get_seller_rental_bookings(
 
     let response: Vec<RentalBookingResponse> = rentals
         .into_iter()
-        .map(RentalBookingResponse::from)
+        .map(|rental| RentalBookingResponse::new(rental, &state.config))
         .collect();
 
     Ok(Json(response))
@@ -237,11 +260,22 @@ pub async fn validate_pickup(
         return Err(AppError::bad_request("Status rental tidak valid untuk pickup"));
     }
 
+    // Foto KTP/SIM penyewa harus dari storage kita
+    validation::validate_uploaded_image_url(&payload.ktp_photo)
+        .await
+        .map_err(AppError::bad_request)?;
+
     let updated = rental_repo::validate_pickup(&state.db, id, &payload.ktp_photo).await?;
 
-    tracing::info!("Rental {} pickup validated by seller {}", id, auth.user_id);
+    tracing::info!(
+        event = "rental_ktp_validated",
+        booking_type = "rental",
+        booking_id = id,
+        seller_id = auth.user_id,
+        "Rental KTP validated"
+    );
 
-    Ok(Json(RentalBookingResponse::from(updated)))
+    Ok(Json(RentalBookingResponse::new(updated, &state.config)))
 }
 
 // Validate return (seller)
@@ -284,7 +318,239 @@ pub async fn validate_return(
 
     tracing::info!("Rental {} return validated by seller {}", id, auth.user_id);
 
-    Ok(Json(RentalBookingResponse::from(updated)))
+    Ok(Json(RentalBookingResponse::new(updated, &state.config)))
+}
+
+// Seller catat check-in: kendaraan diserahkan ke customer dan rental mulai berjalan
+#[utoipa::path(
+    post,
+    path = "/api/rentals/bookings/{id}/checkin",
+    tag = "Rental Bookings",
+    summary = "Check-in rental",
+    description = "Seller mencatat odometer, bahan bakar, dan foto kondisi kendaraan saat diserahkan. KTP/SIM penyewa harus sudah divalidasi. Status rental paid/akan_datang -> berjalan (rental yang sudah berjalan lewat validate-pickup tetap berjalan)",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Rental booking ID")),
+    request_body = HandoverRequest,
+    responses(
+        (status = 201, description = "Check-in dicatat", body = HandoverResponse,
+            headers(("Location" = String, description = "URL rental booking"))),
+        (status = 400, description = "Input atau status rental tidak valid"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "Check-in sudah dicatat"),
+    )
+)]
+pub async fn check_in_rental(
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<HandoverRequest>,
+) -> Result<Creation<HandoverResponse>, AppError> {
+    record_rental_handover(&state, auth.user_id, id, HandoverKind::CheckIn, payload).await
+}
+
+// Seller catat check-out: kendaraan dikembalikan dan rental selesai
+#[utoipa::path(
+    post,
+    path = "/api/rentals/bookings/{id}/checkout",
+    tag = "Rental Bookings",
+    summary = "Check-out rental",
+    description = "Seller mencatat odometer (tidak boleh lebih kecil dari check-in), bahan bakar, dan foto kondisi kendaraan saat dikembalikan. Status rental berjalan -> selesai (rental yang sudah selesai lewat validate-return tetap selesai). Pengembalian lewat toleransi dikenakan denda tarif harian per hari terlambat, ditagihkan lewat laporan pengembalian",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Rental booking ID")),
+    request_body = HandoverRequest,
+    responses(
+        (status = 201, description = "Check-out dicatat", body = HandoverResponse,
+            headers(("Location" = String, description = "URL rental booking"))),
+        (status = 400, description = "Input atau status rental tidak valid"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "Check-out sudah dicatat"),
+    )
+)]
+pub async fn check_out_rental(
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<HandoverRequest>,
+) -> Result<Creation<HandoverResponse>, AppError> {
+    record_rental_handover(&state, auth.user_id, id, HandoverKind::CheckOut, payload).await
+}
+
+// Validasi dan simpan check-in / check-out
+async fn record_rental_handover(
+    state: &AppState,
+    seller_id: i32,
+    id: i32,
+    kind: HandoverKind,
+    payload: HandoverRequest,
+) -> Result<Creation<HandoverResponse>, AppError> {
+    let rental = rental_repo::find_rental_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Rental booking tidak ditemukan"))?;
+
+    if rental.seller_id != seller_id {
+        return Err(AppError::forbidden("Anda bukan seller dari vehicle ini"));
+    }
+
+    handover::next_status(kind, &rental.status)?;
+
+    let checkin_odometer = match kind {
+        HandoverKind::CheckIn => {
+            if rental.ktp_photo.is_none() {
+                return Err(AppError::bad_request("Validasi KTP/SIM penyewa (pickup) sebelum check-in"));
+            }
+            None
+        }
+        // Rental yang berjalan sebelum ada check-in tidak punya odometer awal
+        HandoverKind::CheckOut => handover_repo::find_handover(&state.db, id, HandoverKind::CheckIn)
+            .await?
+            .map(|checkin| checkin.odometer_km),
+    };
+
+    handover::validate_handover(&payload, checkin_odometer)?;
+
+    // Foto kondisi kendaraan harus dari storage kita
+    for photo in &payload.photos {
+        validation::validate_uploaded_image_url(photo)
+            .await
+            .map_err(AppError::bad_request)?;
+    }
+
+    let recorded_at = Utc::now();
+    let late_fee = match kind {
+        HandoverKind::CheckIn => 0,
+        HandoverKind::CheckOut => handover::late_fee(rental.price_per_day, rental.return_date, recorded_at),
+    };
+
+    let (record, updated) = handover_repo::record_handover(
+        &state.db,
+        &rental,
+        kind,
+        &payload,
+        seller_id,
+        recorded_at,
+        late_fee,
+    ).await?;
+
+    tracing::info!(
+        event = "rental_handover_recorded",
+        booking_type = "rental",
+        booking_id = id,
+        handover = kind.as_str(),
+        seller_id,
+        odometer_km = record.odometer_km,
+        late_fee,
+        "Rental handover dicatat seller"
+    );
+
+    // Handover tidak punya GET sendiri, Location menunjuk ke rental booking
+    Ok(Creation::created(
+        format!("/api/rentals/bookings/{}", id),
+        HandoverResponse::new(record, updated.status),
+    ))
+}
+
+// Seller buat laporan kondisi kendaraan saat pengembalian
+#[utoipa::path(
+    post,
+    path = "/api/rentals/bookings/{id}/return-report",
+    tag = "Rental Bookings",
+    summary = "Laporan pengembalian rental",
+    description = "Seller mencatat kondisi kendaraan, odometer, bahan bakar, dan item kerusakan. Total kerusakan menjadi tagihan tambahan customer (payment_for_type = rental_damage di payment-service)",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Rental booking ID")),
+    request_body = CreateReturnReportRequest,
+    responses(
+        (status = 201, description = "Laporan pengembalian dibuat", body = ReturnReportResponse,
+            headers(("Location" = String, description = "URL laporan pengembalian"))),
+        (status = 400, description = "Input atau status rental tidak valid"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "Laporan sudah dibuat"),
+    )
+)]
+pub async fn create_return_report(
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(mut payload): Json<CreateReturnReportRequest>,
+) -> Result<Creation<ReturnReportResponse>, AppError> {
+    let rental = rental_repo::find_rental_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Rental booking tidak ditemukan"))?;
+
+    if rental.seller_id != auth.user_id {
+        return Err(AppError::forbidden("Anda bukan seller dari vehicle ini"));
+    }
+
+    // Laporan hanya untuk rental yang sudah dikembalikan
+    if RentalStatus::from_str(&rental.status) != Some(RentalStatus::Selesai) {
+        return Err(AppError::bad_request("Laporan pengembalian hanya untuk rental yang sudah selesai"));
+    }
+
+    let damage_total = return_report::validate_return_report(&payload)?;
+
+    // Odometer laporan tidak boleh mundur dari check-in, denda keterlambatan check-out ikut ditagihkan
+    if let Some(checkin) = handover_repo::find_handover(&state.db, id, HandoverKind::CheckIn).await? {
+        handover::check_odometer(checkin.odometer_km, payload.odometer_km)?;
+    }
+
+    let late_fee = handover_repo::find_handover(&state.db, id, HandoverKind::CheckOut)
+        .await?
+        .map_or(0, |checkout| checkout.late_fee);
+    let damage_total = return_report::add_late_fee(&mut payload, damage_total, late_fee)?;
+
+    let report = return_report_repo::create_report(&state.db, &rental, &payload, damage_total).await?;
+
+    tracing::info!(
+        event = "rental_return_reported",
+        booking_type = "rental",
+        booking_id = id,
+        seller_id = auth.user_id,
+        damage_total,
+        "Return report rental dibuat seller"
+    );
+
+    Ok(Creation::created(
+        format!("/api/rentals/bookings/{}/return-report", id),
+        ReturnReportResponse::from(report),
+    ))
+}
+
+// Lihat laporan pengembalian (customer & seller)
+#[utoipa::path(
+    get,
+    path = "/api/rentals/bookings/{id}/return-report",
+    tag = "Rental Bookings",
+    summary = "Detail laporan pengembalian",
+    description = "Customer dan seller bisa melihat laporan kondisi dan tagihan kerusakan untuk transparansi dan dispute",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Rental booking ID")),
+    responses(
+        (status = 200, description = "Laporan pengembalian", body = ReturnReportResponse),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Laporan belum dibuat"),
+    )
+)]
+pub async fn get_return_report(
+    auth: AuthUser,
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<Json<ReturnReportResponse>, AppError> {
+    let rental = rental_repo::find_rental_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Rental booking tidak ditemukan"))?;
+
+    if rental.customer_id != auth.user_id && rental.seller_id != auth.user_id {
+        return Err(AppError::forbidden("Anda tidak memiliki akses ke booking ini"));
+    }
+
+    let report = return_report_repo::find_report_by_rental(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Laporan pengembalian belum dibuat"))?;
+
+    Ok(Json(ReturnReportResponse::from(report)))
 }
 
 // Cancel rental booking
@@ -321,7 +587,14 @@ pub async fn cancel_rental_booking(
 
     rental_repo::cancel_rental(&state.db, id, &payload.cancel_reason).await?;
 
-    tracing::info!("Rental {} cancelled by customer {}", id, auth.user_id);
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "rental",
+        booking_id = id,
+        status = RentalStatus::Cancelled.as_str(),
+        customer_id = auth.user_id,
+        "Rental cancelled by customer"
+    );
 
     Ok(Json(MessageResponse {
         message: "Rental booking berhasil dibatalkan".to_string(),
@@ -367,8 +640,13 @@ pub async fn update_rental_booking_status(
     }
 
     // Validasi status baru menggunakan RentalStatus enum
-    if RentalStatus::from_str(&payload.status).is_none() {
+    let Some(status) = RentalStatus::from_str(&payload.status) else {
         return Err(AppError::BadRequest("Status tidak valid".to_string()));
+    };
+
+    // Berjalan dan selesai hanya lewat check-in / check-out agar serah terima selalu tercatat
+    if matches!(status, RentalStatus::Berjalan | RentalStatus::Selesai) {
+        return Err(AppError::bad_request("Status berjalan/selesai diatur lewat check-in dan check-out"));
     }
 
     // Update status
@@ -378,31 +656,118 @@ pub async fn update_rental_booking_status(
         &payload.status,
     ).await?;
 
-    Ok(Json(RentalBookingResponse::from(updated)))
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "rental",
+        booking_id = updated.id,
+        previous_status = %rental.status,
+        status = status.as_str(),
+        user_id = auth.user_id,
+        "Rental status updated"
+    );
+
+    Ok(Json(RentalBookingResponse::new(updated, &state.config)))
 }
 
-// Validasi create rental request
+// Validasi create rental request, semua field yang salah dikembalikan sekaligus
 fn validate_create_rental(payload: &CreateRentalRequest) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+
     if payload.customer_name.trim().is_empty() {
-        return Err(AppError::validation("Nama customer harus diisi"));
+        errors.push(FieldError::new("customer_name", "Nama customer harus diisi"));
     }
 
     if !validation::is_valid_phone(&payload.customer_phone) {
-        return Err(AppError::validation("Format nomor telepon tidak valid"));
+        errors.push(FieldError::new("customer_phone", "Format nomor telepon tidak valid"));
     }
 
     if !validation::is_valid_email(&payload.customer_email) {
-        return Err(AppError::validation("Format email tidak valid"));
+        errors.push(FieldError::new("customer_email", "Format email tidak valid"));
     }
 
     if payload.pickup_date >= payload.return_date {
-        return Err(AppError::validation("Tanggal return harus setelah tanggal pickup"));
+        errors.push(FieldError::new("return_date", "Tanggal return harus setelah tanggal pickup"));
     }
 
     let now = chrono::Utc::now();
     if payload.pickup_date < now {
-        return Err(AppError::validation("Tanggal pickup tidak boleh di masa lalu"));
+        errors.push(FieldError::new("pickup_date", "Tanggal pickup tidak boleh di masa lalu"));
     }
 
-    Ok(())
-}
\ No newline at end of file
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::fields(errors))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+
+    // vehicle-service palsu: semua mobil milik seller 2, tersedia untuk rental tanpa deposit
+    async fn vehicle_service_stub() -> String {
+        let app = Router::new().route(
+            "/vehicles/{id}/rental-info",
+            get(|Path(id): Path<i32>| async move {
+                Json(serde_json::json!({
+                    "id": id, "seller_id": 2, "price_per_day": 350000.0, "deposit_amount": 0.0, "is_available": true
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    fn rental_request() -> Json<CreateRentalRequest> {
+        let pickup_date = Utc::now() + chrono::Duration::days(3);
+
+        Json(CreateRentalRequest {
+            vehicle_id: 1,
+            pickup_date,
+            return_date: pickup_date + chrono::Duration::days(2),
+            customer_name: "Customer Test".to_string(),
+            customer_phone: "081234567890".to_string(),
+            customer_email: "customer@test.local".to_string(),
+            notes: None,
+        })
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_create_rental_returns_201_with_location(pool: sqlx::PgPool) {
+        let mut state = AppState::for_test(pool);
+        state.config.vehicle_service_url = vehicle_service_stub().await;
+        let customer = || AuthCustomer { user_id: 1, email: "customer@test.local".to_string() };
+
+        let created = create_rental_booking(customer(), State(state.clone()), rental_request())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(created.status(), StatusCode::CREATED);
+
+        let location = created.headers()[header::LOCATION].to_str().unwrap().to_string();
+        let id: i32 = location.strip_prefix("/api/rentals/bookings/").unwrap().parse().unwrap();
+        let viewer = AuthUser { user_id: 1, email: "customer@test.local".to_string(), role: "customer".to_string() };
+        let Json(fetched) = get_rental_booking(viewer, Path(id), State(state.clone())).await.unwrap();
+        assert_eq!(fetched.id, id);
+
+        // Tanggal yang sama sudah dibooking
+        let conflict = create_rental_booking(customer(), State(state), rental_request()).await;
+        assert!(matches!(conflict, Err(AppError::Conflict(_))));
+    }
+}
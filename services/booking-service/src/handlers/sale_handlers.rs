@@ -2,8 +2,8 @@
 // Minimal working implementation for MVP
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json},
 };
 
 use crate::{
@@ -11,13 +11,19 @@ use crate::{
         CreateSaleOrderRequest, SaleOrderResponse, UpdateDocumentStatusRequest,
         SaleOrderQueryParams, UploadKtpRequest, AcceptSaleOrderRequest,
         AcceptCounterOfferRequest, CounterOfferRequest, CancelRequest,
-        RejectSaleOrderRequest, StartDocumentTransferRequest, SaleStatus
+        RejectSaleOrderRequest, StartDocumentTransferRequest, SaleStatus,
+        SaleOrderListResponse
     },
     middleware::auth::{AuthUser, AuthSeller, AuthCustomer},
-    repositories::sale_repo,
+    domain::{buyer_block, document_checklist, invoice::SaleInvoice, sale::SaleOrder},
+    repositories::{buyer_block_repo, document_checklist_repo, invoice_repo, sale_repo},
+    utils::{invoice_pdf, negotiation, order_tracking::{self, OrderTrackingResponse}},
     error::AppError,
     AppState,
 };
+use shared::utils::creation::Creation;
+use shared::utils::pagination::{Pagination, PaginationParams};
+use shared::utils::validation;
 
 
 // Create sale order baru (customer)
@@ -32,7 +38,8 @@ use crate::{
     ),
     request_body = CreateSaleOrderRequest,
     responses(
-        (status = 201, description = "Order berhasil dibuat", body = SaleOrderResponse),
+        (status = 201, description = "Order berhasil dibuat", body = SaleOrderResponse,
+            headers(("Location" = String, description = "URL sale order"))),
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
         (status = 409, description = "Conflict - Vehicle not available or already ordered")
@@ -41,8 +48,9 @@ use crate::{
 pub async fn create_sale_order(
     State(state): State<AppState>,
     auth: AuthCustomer,
+    headers: HeaderMap,
     Json(request): Json<CreateSaleOrderRequest>,
-) -> Result<(StatusCode, Json<SaleOrderResponse>), AppError> {
+) -> Result<Creation<SaleOrderResponse>, AppError> {
     // Validasi vehicle dan dapatkan seller_id + asking_price dari vehicle-service API
     let url = format!("{}/vehicles/{}/sale-info",
         state.config.vehicle_service_url,
@@ -72,10 +80,18 @@ pub async fn create_sale_order(
         .await
         .map_err(|e| AppError::database_error(format!("Gagal parse response: {}", e)))?;
 
-    if !vehicle_info.is_available {
+    // Buyer yang diblokir seller mendapat error yang sama dengan vehicle tidak tersedia
+    let buyer_blocked = buyer_block_repo::is_buyer_blocked(&state.db, vehicle_info.seller_id, auth.user_id).await?;
+    if !buyer_block::can_transact(vehicle_info.is_available, buyer_blocked) {
         return Err(AppError::Conflict("Vehicle tidak tersedia untuk dijual".to_string()));
     }
 
+    // Snapshot checklist dokumen seller (default jika belum diatur) untuk serah terima order ini
+    let checklist = document_checklist_repo::find_checklist(&state.db, vehicle_info.seller_id)
+        .await?
+        .map(|(items, _)| items)
+        .unwrap_or_else(document_checklist::default_checklist);
+
     // Buat sale order baru dengan data real dari vehicle-service
     let sale_order = sale_repo::create_sale_order(
         &state.db,
@@ -83,12 +99,64 @@ pub async fn create_sale_order(
         vehicle_info.seller_id,
         vehicle_info.asking_price,
         &request,
+        &document_checklist::order_items(&checklist),
     )
     .await?;
 
-    let response = SaleOrderResponse::from(sale_order);
+    let vehicle_id = sale_order.vehicle_id;
+    let mut response = SaleOrderResponse::new(sale_order, &state.config);
+
+    // Auto buat/reuse conversation, kegagalan tidak membatalkan order
+    if state.config.auto_create_sale_conversation {
+        match ensure_sale_conversation(&state, &headers, vehicle_info.seller_id, vehicle_id).await {
+            Ok(conversation_id) => response.conversation_id = Some(conversation_id),
+            Err(e) => tracing::warn!("Gagal auto-create conversation untuk order {}: {:?}", response.order_id, e),
+        }
+    }
 
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok(Creation::created(format!("/api/sales/orders/{}", response.id), response))
+}
+
+// Buat atau reuse conversation buyer-seller lewat chat-service (lookup existing ada di chat-service)
+async fn ensure_sale_conversation(
+    state: &AppState,
+    headers: &HeaderMap,
+    seller_id: i32,
+    vehicle_id: i32,
+) -> Result<i32, AppError> {
+    let chat_service_url = state.config.chat_service_url.as_deref()
+        .ok_or_else(|| AppError::internal("CHAT_SERVICE_URL tidak diset"))?;
+
+    let authorization = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::unauthorized("Authorization header missing"))?;
+
+    #[derive(serde::Deserialize)]
+    struct ConversationInfo {
+        id: i32,
+    }
+
+    let response = state.http_client
+        .post(format!("{}/api/conversations", chat_service_url))
+        .header(header::AUTHORIZATION, authorization)
+        .json(&serde_json::json!({
+            "seller_id": seller_id,
+            "vehicle_id": vehicle_id,
+        }))
+        .send()
+        .await
+        .map_err(|e| AppError::internal(format!("Gagal menghubungi chat-service: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(AppError::internal(format!("chat-service response: {}", response.status())));
+    }
+
+    let conversation: ConversationInfo = response
+        .json()
+        .await
+        .map_err(|e| AppError::internal(format!("Gagal parse response chat-service: {}", e)))?;
+
+    Ok(conversation.id)
 }
 
 // Mendapatkan detail order pembelian (customer/seller yang terkait)
@@ -125,10 +193,41 @@ pub async fn get_sale_order(
         return Err(AppError::Forbidden("Akses ditolak".to_string()));
     }
 
-    let response = SaleOrderResponse::from(sale_order);
+    let response = SaleOrderResponse::new(sale_order, &state.config);
     Ok(Json(response))
 }
 
+// Tracking order publik lewat kode referensi (tanpa login, tanpa data pribadi)
+#[utoipa::path(
+    get,
+    path = "/api/sales/track/{reference}",
+    tag = "sale-orders",
+    summary = "Tracking order pembelian",
+    description = "Status, tahap, dan estimasi waktu order berdasarkan kode tracking. Tidak butuh login dan tidak menampilkan data pribadi; dibatasi per IP untuk mencegah enumerasi kode",
+    params(
+        ("reference" = String, Path, description = "Kode tracking order, contoh 7KQ2-M9XD-4HTB-0RWE")
+    ),
+    responses(
+        (status = 200, description = "Status order", body = OrderTrackingResponse),
+        (status = 404, description = "Kode tracking tidak ditemukan"),
+        (status = 429, description = "Terlalu banyak request")
+    )
+)]
+pub async fn track_sale_order(
+    State(state): State<AppState>,
+    Path(reference): Path<String>,
+) -> Result<Json<OrderTrackingResponse>, AppError> {
+    // Format salah dijawab sama dengan kode yang tidak ada
+    let not_found = || AppError::NotFound("Kode tracking tidak ditemukan".to_string());
+
+    let reference = order_tracking::normalize_reference(&reference).ok_or_else(not_found)?;
+    let sale_order = sale_repo::find_sale_order_by_tracking_reference(&state.db, &reference)
+        .await?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(order_tracking::tracking_view(&sale_order, state.config.seller_sla_minutes)))
+}
+
 // Mendapatkan list order customer (customer perspective)
 #[utoipa::path(
     get,
@@ -139,8 +238,10 @@ pub async fn get_sale_order(
     security(
         ("bearer_auth" = [])
     ),
+    params(PaginationParams),
     responses(
         (status = 200, description = "List order customer"),
+        (status = 400, description = "Parameter paginasi tidak valid"),
         (status = 401, description = "Unauthorized")
     )
 )]
@@ -148,18 +249,19 @@ pub async fn get_customer_sale_orders(
     State(state): State<AppState>,
     auth: AuthCustomer,
     Query(params): Query<SaleOrderQueryParams>,
+    pagination: Pagination<10, 100>,
 ) -> Result<Json<Vec<SaleOrderResponse>>, AppError> {
     let orders = sale_repo::find_sale_orders_by_buyer(
         &state.db,
         auth.user_id,
         params.status,
-        params.page,
-        params.limit,
+        pagination.limit,
+        pagination.offset,
     ).await?;
 
     let response: Vec<SaleOrderResponse> = orders
         .into_iter()
-        .map(SaleOrderResponse::from)
+        .map(|order| SaleOrderResponse::new(order, &state.config))
         .collect();
 
     Ok(Json(response))
@@ -171,12 +273,14 @@ pub async fn get_customer_sale_orders(
     path = "/api/sales/orders/seller",
     tag = "sale-orders",
     summary = "List order penjualan saya",
-    description = "Seller melihat semua order pembelian yang masuk ke mobil mereka",
+    description = "Seller melihat order pembelian yang masuk ke mobil mereka. Mendukung filter status, q (nama buyer / order id), buyer_name, vehicle_id, date_from, date_to, min_price, max_price, dan sort (newest, oldest, price_asc, price_desc, buyer_name, status)",
     security(
         ("bearer_auth" = [])
     ),
+    params(PaginationParams),
     responses(
-        (status = 200, description = "List order seller"),
+        (status = 200, description = "List order seller", body = SaleOrderListResponse),
+        (status = 400, description = "Sort atau parameter paginasi tidak valid"),
         (status = 401, description = "Unauthorized")
     )
 )]
@@ -184,21 +288,31 @@ pub async fn get_seller_sale_orders(
     State(state): State<AppState>,
     auth: AuthSeller,
     Query(params): Query<SaleOrderQueryParams>,
-) -> Result<Json<Vec<SaleOrderResponse>>, AppError> {
-    let orders = sale_repo::find_sale_orders_by_seller(
+    pagination: Pagination<10, 100>,
+) -> Result<Json<SaleOrderListResponse>, AppError> {
+    if let (Some(min_price), Some(max_price)) = (params.min_price, params.max_price) {
+        if min_price > max_price {
+            return Err(AppError::bad_request("min_price tidak boleh lebih besar dari max_price"));
+        }
+    }
+
+    let (orders, total) = sale_repo::find_sale_orders_by_seller(
         &state.db,
         auth.user_id,
-        params.status,
-        params.page,
-        params.limit,
+        &params,
+        pagination.limit,
+        pagination.offset,
     ).await?;
 
-    let response: Vec<SaleOrderResponse> = orders
-        .into_iter()
-        .map(SaleOrderResponse::from)
-        .collect();
-
-    Ok(Json(response))
+    Ok(Json(SaleOrderListResponse {
+        orders: orders
+            .into_iter()
+            .map(|order| SaleOrderResponse::new(order, &state.config))
+            .collect(),
+        total,
+        page: pagination.page,
+        limit: pagination.limit,
+    }))
 }
 
 // Confirm order (customer)
@@ -219,7 +333,8 @@ pub async fn get_seller_sale_orders(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn confirm_sale_order(
@@ -248,12 +363,13 @@ pub async fn confirm_sale_order(
         // Customer menerima harga
         let updated_order = sale_repo::confirm_sale_order(
             &state.db,
-            order_id as i32,
+            &sale_order,
             None, // counter_price - tidak ada
             payload.notes.clone(),
+            state.config.max_counter_rounds,
         ).await?;
 
-        Ok(Json(SaleOrderResponse::from(updated_order)))
+        Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
     } else {
         // Customer menolak harga (implementasi di endpoint reject)
         Err(AppError::BadRequest("Silakan gunakan endpoint /cancel untuk menolak pesanan".to_string()))
@@ -266,7 +382,7 @@ pub async fn confirm_sale_order(
     path = "/api/sales/orders/{id}/counter",
     tag = "sale-orders",
     summary = "Counter offer dari seller",
-    description = "Seller memberikan counter offer harga",
+    description = "Seller memberikan counter offer harga. Jumlah counter dibatasi MAX_COUNTER_OFFER_ROUNDS per order",
     security(
         ("bearer_auth" = [])
     ),
@@ -276,10 +392,11 @@ pub async fn confirm_sale_order(
     request_body = CounterOfferRequest,
     responses(
         (status = 200, description = "Counter offer berhasil dibuat", body = SaleOrderResponse),
-        (status = 400, description = "Status tidak valid"),
+        (status = 400, description = "Status tidak valid atau batas counter offer tercapai"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn seller_counter_offer(
@@ -308,15 +425,19 @@ pub async fn seller_counter_offer(
         return Err(AppError::BadRequest("Harga counter offer harus positif".to_string()));
     }
 
+    // Batasi jumlah ronde negosiasi
+    negotiation::ensure_counter_allowed(sale_order.counter_round, state.config.max_counter_rounds)?;
+
     // Lakukan counter offer
     let updated_order = sale_repo::confirm_sale_order(
         &state.db,
-        order_id as i32,
+        &sale_order,
         Some(counter_price),
         payload.reason.clone(),
+        state.config.max_counter_rounds,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Reject sale order (seller)
@@ -338,7 +459,8 @@ pub async fn seller_counter_offer(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn reject_sale_order(
@@ -371,11 +493,11 @@ pub async fn reject_sale_order(
     // Tolak order
     let updated_order = sale_repo::reject_sale_order(
         &state.db,
-        order_id as i32,
+        &sale_order,
         &reject_reason,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Accept counter offer (customer)
@@ -396,7 +518,8 @@ pub async fn reject_sale_order(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn accept_counter_offer(
@@ -419,13 +542,21 @@ pub async fn accept_counter_offer(
         return Err(AppError::Forbidden("Akses ditolak".to_string()));
     }
 
+    if SaleStatus::from_str(&sale_order.status) != Some(SaleStatus::PendingConfirmation) {
+        return Err(AppError::BadRequest("Hanya bisa menerima counter offer untuk order yang menunggu konfirmasi".to_string()));
+    }
+
+    if sale_order.counter_offer_price.is_none() {
+        return Err(AppError::BadRequest("Tidak ada counter offer".to_string()));
+    }
+
     // Terima counter offer
     let updated_order = sale_repo::accept_counter_offer(
         &state.db,
-        order_id as i32,
+        &sale_order,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Cancel order (customer/seller)
@@ -446,7 +577,8 @@ pub async fn accept_counter_offer(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn cancel_sale_order(
@@ -475,11 +607,11 @@ pub async fn cancel_sale_order(
     // Batalkan order
     let updated_order = sale_repo::cancel_sale_order(
         &state.db,
-        order_id as i32,
+        &sale_order,
         &cancel_reason,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Upload KTP (customer)
@@ -501,7 +633,8 @@ pub async fn cancel_sale_order(
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn upload_buyer_ktp(
@@ -519,19 +652,19 @@ pub async fn upload_buyer_ktp(
         return Err(AppError::Forbidden("Akses ditolak".to_string()));
     }
 
-    // Validasi URL KTP
-    if payload.ktp_photo.is_empty() {
-        return Err(AppError::BadRequest("URL foto KTP harus diisi".to_string()));
-    }
+    // Validasi URL KTP harus dari storage kita
+    validation::validate_uploaded_image_url(&payload.ktp_photo)
+        .await
+        .map_err(AppError::BadRequest)?;
 
     // Upload KTP
     let updated_order = sale_repo::upload_ktp(
         &state.db,
-        order_id as i32,
+        &sale_order,
         &payload.ktp_photo,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Start document transfer (seller)
@@ -553,7 +686,8 @@ pub async fn upload_buyer_ktp(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn start_document_transfer(
@@ -584,10 +718,10 @@ pub async fn start_document_transfer(
     // Mulai proses transfer dokumen
     let updated_order = sale_repo::start_document_transfer(
         &state.db,
-        order_id as i32,
+        &sale_order,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Update document status (seller)
@@ -596,7 +730,7 @@ pub async fn start_document_transfer(
     path = "/api/sales/orders/{id}/update-documents",
     tag = "sale-orders",
     summary = "Update status dokumen",
-    description = "Seller mengupdate status transfer dokumen",
+    description = "Seller mengupdate status serah terima dokumen sesuai checklist order. Key dokumen yang tidak ada di checklist ditolak",
     security(
         ("bearer_auth" = [])
     ),
@@ -609,7 +743,8 @@ pub async fn start_document_transfer(
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn update_document_status(
@@ -632,17 +767,20 @@ pub async fn update_document_status(
         return Err(AppError::BadRequest("Hanya bisa update dokumen untuk order yang sedang dalam proses dokumen".to_string()));
     }
 
+    // Validasi key dokumen terhadap checklist order
+    let current_items = document_checklist::parse_order_items(&sale_order.document_checklist)
+        .map_err(|e| AppError::internal(format!("Sale order {}: {}", sale_order.id, e)))?;
+    let items = document_checklist::apply_updates(&current_items, &payload.into_updates())
+        .map_err(AppError::validation)?;
+
     // Update status dokumen
     let updated_order = sale_repo::update_document_status(
         &state.db,
-        order_id as i32,
-        payload.bpkb_transferred,
-        payload.stnk_transferred,
-        payload.faktur_transferred,
-        payload.pajak_transferred,
+        &sale_order,
+        &items,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Mark sale order as paid (payment callback from payment-service)
@@ -663,7 +801,8 @@ pub async fn update_document_status(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn mark_sale_order_as_paid(
@@ -687,10 +826,10 @@ pub async fn mark_sale_order_as_paid(
     // Mark as paid
     let updated_order = sale_repo::mark_as_paid(
         &state.db,
-        order_id as i32,
+        &sale_order,
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
 }
 
 // Confirm documents received (customer)
@@ -711,7 +850,8 @@ pub async fn mark_sale_order_as_paid(
         (status = 400, description = "Status tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
-        (status = 404, description = "Pesanan tidak ditemukan")
+        (status = 404, description = "Pesanan tidak ditemukan"),
+        (status = 409, description = "Order sudah diubah oleh request lain")
     )
 )]
 pub async fn confirm_documents_received(
@@ -733,10 +873,14 @@ pub async fn confirm_documents_received(
         return Err(AppError::BadRequest("Hanya bisa konfirmasi dokumen untuk order yang sedang dalam proses dokumen".to_string()));
     }
 
-    // Validasi bahwa semua dokumen sudah ditransfer
-    if !sale_order.bpkb_transferred || !sale_order.stnk_transferred ||
-       !sale_order.faktur_transferred || !sale_order.pajak_transferred {
-        return Err(AppError::BadRequest("Semua dokumen harus ditransfer sebelum konfirmasi".to_string()));
+    // Validasi bahwa semua dokumen wajib di checklist order sudah ditransfer
+    let items = document_checklist::parse_order_items(&sale_order.document_checklist)
+        .map_err(|e| AppError::internal(format!("Sale order {}: {}", sale_order.id, e)))?;
+    if !document_checklist::document_progress(&items).is_complete {
+        return Err(AppError::BadRequest(format!(
+            "Semua dokumen wajib harus ditransfer sebelum konfirmasi (belum: {})",
+            document_checklist::missing_required(&items).join(", ")
+        )));
     }
 
     // TODO: Tambahkan notifikasi ke seller dan update tracking
@@ -748,9 +892,176 @@ pub async fn confirm_documents_received(
     // Selesaikan order
     let updated_order = sale_repo::complete_sale_order(
         &state.db,
-        order_id as i32,
+        &sale_order,
         Some("Dokumen dikonfirmasi diterima oleh pembeli".to_string()),
     ).await?;
 
-    Ok(Json(SaleOrderResponse::from(updated_order)))
-}
\ No newline at end of file
+    // Terbitkan invoice, kegagalan tidak membatalkan order (bisa digenerate ulang saat download)
+    if let Err(e) = ensure_sale_invoice(&state, &updated_order).await {
+        tracing::error!("Gagal generate invoice untuk sale order {}: {:?}", updated_order.id, e);
+    }
+
+    Ok(Json(SaleOrderResponse::new(updated_order, &state.config)))
+}
+
+// Ambil invoice sale order, generate jika belum ada
+async fn ensure_sale_invoice(state: &AppState, order: &SaleOrder) -> Result<SaleInvoice, AppError> {
+    if let Some(invoice) = invoice_repo::find_invoice_by_sale_order(&state.db, order.id).await? {
+        return Ok(invoice);
+    }
+
+    let details = invoice_repo::find_invoice_details(&state.db, order.id).await?;
+    let sequence = invoice_repo::next_invoice_sequence(&state.db).await?;
+
+    let new_invoice = invoice_pdf::build_sale_invoice(
+        order,
+        &details,
+        sequence,
+        state.config.invoice_tax_percentage,
+        chrono::Utc::now(),
+    );
+
+    match invoice_repo::insert_invoice(&state.db, &new_invoice).await? {
+        Some(invoice) => {
+            tracing::info!(
+                event = "invoice_issued",
+                invoice_number = %invoice.invoice_number,
+                sale_order_id = order.id,
+                "Invoice diterbitkan untuk sale order"
+            );
+            Ok(invoice)
+        }
+        // Request paralel sudah menerbitkan invoice lebih dulu
+        None => invoice_repo::find_invoice_by_sale_order(&state.db, order.id)
+            .await?
+            .ok_or_else(|| AppError::internal("Invoice gagal disimpan")),
+    }
+}
+
+// Download invoice PDF sale order (buyer/seller)
+#[utoipa::path(
+    get,
+    path = "/api/sales/orders/{id}/invoice.pdf",
+    tag = "sale-orders",
+    summary = "Download invoice order",
+    description = "Buyer atau seller mengunduh invoice (faktur) PDF untuk order yang sudah completed",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = i64, Path, description = "ID order pembelian")
+    ),
+    responses(
+        (status = 200, description = "Invoice PDF", content_type = "application/pdf"),
+        (status = 400, description = "Order belum completed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Pesanan tidak ditemukan")
+    )
+)]
+pub async fn download_sale_invoice(
+    State(state): State<AppState>,
+    Path(order_id): Path<i64>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, AppError> {
+    let sale_order = sale_repo::find_sale_order_by_id(&state.db, order_id as i32)
+        .await?
+        .ok_or(AppError::NotFound("Pesanan tidak ditemukan".to_string()))?;
+
+    // Validasi akses: hanya customer atau seller terkait yang bisa download
+    if auth.user_id != sale_order.buyer_id && auth.user_id != sale_order.seller_id {
+        return Err(AppError::Forbidden("Akses ditolak".to_string()));
+    }
+
+    if sale_order.status != SaleStatus::Completed.as_str() {
+        return Err(AppError::BadRequest("Invoice hanya tersedia untuk order yang sudah completed".to_string()));
+    }
+
+    let invoice = ensure_sale_invoice(&state, &sale_order).await?;
+    let filename = format!("{}.pdf", invoice.invoice_number.replace('/', "-"));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        invoice.pdf_data,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::buyer_block::BlockBuyerRequest, handlers::buyer_block_handlers};
+    use axum::{http::StatusCode, routing::get, Router};
+
+    // vehicle-service palsu: semua mobil milik seller 2, vehicle 99 sudah tidak tersedia
+    async fn vehicle_service_stub() -> String {
+        let app = Router::new().route(
+            "/vehicles/{id}/sale-info",
+            get(|Path(id): Path<i32>| async move {
+                Json(serde_json::json!({ "seller_id": 2, "asking_price": 150000000.0, "is_available": id != 99 }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    fn order(vehicle_id: i32) -> Json<CreateSaleOrderRequest> {
+        Json(CreateSaleOrderRequest {
+            vehicle_id,
+            testdrive_booking_id: None,
+            offer_price: None,
+            buyer_name: "Customer Test".to_string(),
+            buyer_phone: "081234567890".to_string(),
+            buyer_email: "customer@test.local".to_string(),
+            buyer_address: None,
+            buyer_notes: None,
+        })
+    }
+
+    async fn place_order(state: &AppState, vehicle_id: i32) -> Result<StatusCode, AppError> {
+        let buyer = AuthCustomer { user_id: 1, email: "customer@test.local".to_string() };
+        let created = create_sale_order(State(state.clone()), buyer, HeaderMap::new(), order(vehicle_id)).await?;
+
+        Ok(created.into_response().status())
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_blocked_buyer_gets_unavailable_error(pool: sqlx::PgPool) {
+        let mut state = AppState::for_test(pool);
+        state.config.vehicle_service_url = vehicle_service_stub().await;
+        let seller = AuthSeller { user_id: 2, email: "seller@test.local".to_string() };
+
+        // Buyer yang tidak diblokir bisa order
+        assert_eq!(place_order(&state, 2).await.unwrap(), StatusCode::CREATED);
+
+        let block = Json(BlockBuyerRequest { buyer_id: 1, reason: Some("Tidak datang".to_string()) });
+        buyer_block_handlers::block_buyer(State(state.clone()), seller, block).await.unwrap();
+
+        // Error blokir sama persis dengan vehicle yang memang tidak tersedia
+        let Err(AppError::Conflict(blocked)) = place_order(&state, 2).await else {
+            panic!("buyer yang diblokir harus ditolak");
+        };
+        let Err(AppError::Conflict(unavailable)) = place_order(&state, 99).await else {
+            panic!("vehicle tidak tersedia harus ditolak");
+        };
+        assert_eq!(blocked, unavailable);
+
+        let orders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sale_orders WHERE buyer_id = 1")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(orders, 1);
+    }
+}
@@ -3,19 +3,25 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::{
+    domain::buyer_block,
     domain::testdrive::{
         TestDriveBookingResponse, CreateTestDriveRequest,
         RescheduleTestDriveRequest, ChooseRescheduleSlotRequest,
         CancelTestDriveRequest, ConfirmTestDriveRequest,
-        CompleteTestDriveRequest, TestDriveStatus,
+        CompleteTestDriveRequest, TestDriveStatus, TestDriveLocation,
+        BulkAcceptTestDriveRequest, BulkRejectTestDriveRequest, BulkTestDriveResponse,
+        SetBusinessHoursRequest, BusinessHoursResponse,
+        SellerAvailability, SetAvailabilityRequest, AvailabilityEntry, AvailabilityResponse,
+        SlotQuery, SellerSlotsResponse,
     },
     error::AppError,
-    repositories::testdrive_repo,
+    repositories::{buyer_block_repo, testdrive_repo::{self, BulkTestDriveAction}},
+    utils::{business_hours::{self, BusinessHours}, reschedule_slots, testdrive_bulk, testdrive_location, testdrive_slots},
     AppState,
 };
 
 use crate::middleware::auth::{AuthUser, AuthCustomer, AuthSeller};
-use shared::utils::validation;
+use shared::utils::{creation::Creation, validation::{self, FieldError, FromFieldErrors}};
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct MessageResponse {
@@ -35,25 +41,23 @@ pub struct TestDriveFilter {
     security(("bearer_auth" = [])),
     request_body = CreateTestDriveRequest,
     responses(
-        (status = 201, description = "Test drive booking created", body = TestDriveBookingResponse),
+        (status = 201, description = "Test drive booking created", body = TestDriveBookingResponse,
+            headers(("Location" = String, description = "URL test drive booking"))),
         (status = 400, description = "Input tidak valid"),
+        (status = 409, description = "Slot sudah penuh"),
     )
 )]
 pub async fn create_testdrive_booking(
     auth: AuthCustomer,
     State(state): State<AppState>,
-    Json(payload): Json<CreateTestDriveRequest>,
-) -> Result<Json<TestDriveBookingResponse>, AppError> {
+    Json(mut payload): Json<CreateTestDriveRequest>,
+) -> Result<Creation<TestDriveBookingResponse>, AppError> {
     tracing::info!(
-        "Customer {} ({}) creating testdrive for vehicle {}",
-        auth.user_id,
-        auth.email,
-        payload.vehicle_id
+        customer_id = auth.user_id,
+        vehicle_id = payload.vehicle_id,
+        "Customer creating testdrive"
     );
 
-    // Validasi input
-    validate_create_testdrive(&payload)?;
-
     // Check vehicle exists dan ambil seller_id dari vehicle-service (harus jual-beli)
     let url = format!("{}/vehicles/{}/testdrive-info",
         state.config.vehicle_service_url,
@@ -83,23 +87,58 @@ pub async fn create_testdrive_booking(
         .await
         .map_err(|e| AppError::database_error(format!("Gagal parse response: {}", e)))?;
 
-    if !vehicle_info.is_available {
+    let (_vehicle_id, seller_id) = (vehicle_info.id, vehicle_info.seller_id);
+
+    // Buyer yang diblokir seller mendapat error yang sama dengan vehicle tidak tersedia
+    let buyer_blocked = buyer_block_repo::is_buyer_blocked(&state.db, seller_id, auth.user_id).await?;
+    if !buyer_block::can_transact(vehicle_info.is_available, buyer_blocked) {
         return Err(AppError::bad_request("Vehicle tidak tersedia untuk test drive"));
     }
 
-    let (_vehicle_id, seller_id) = (vehicle_info.id, vehicle_info.seller_id);
-
-    // Create test drive booking
-    let testdrive = testdrive_repo::create_testdrive(
-        &state.db,
-        auth.user_id,
-        seller_id,
-        &payload,
-    ).await?;
+    // Seller dengan jadwal slot hanya menerima slot_id, jam diturunkan dari slot
+    let rules = testdrive_repo::find_availability(&mut *state.db.acquire().await?, seller_id).await?;
+    apply_slot_time(&mut payload, &rules)?;
+
+    // Validasi input, jadwal harus dalam jam operasional seller
+    let hours = seller_business_hours(&state, seller_id).await?;
+    let location = validate_create_testdrive(&payload, &hours)?;
+
+    // Create test drive booking, kapasitas slot dicek ulang di dalam lock kalender seller
+    let testdrive = match payload.slot_id {
+        Some(slot_id) => testdrive_repo::create_testdrive_in_slot(
+            &state.db,
+            auth.user_id,
+            seller_id,
+            &payload,
+            location,
+            slot_id,
+            &hours,
+        ).await?,
+        None => testdrive_repo::create_testdrive(
+            &state.db,
+            auth.user_id,
+            seller_id,
+            &payload,
+            location,
+            &hours,
+        ).await?,
+    };
 
-    tracing::info!("Test drive booking {} created", testdrive.id);
+    tracing::info!(
+        event = "booking_created",
+        booking_type = "testdrive",
+        booking_id = testdrive.id,
+        customer_id = auth.user_id,
+        seller_id = testdrive.seller_id,
+        vehicle_id = testdrive.vehicle_id,
+        slot_id = ?payload.slot_id,
+        "Test drive booking created"
+    );
 
-    Ok(Json(TestDriveBookingResponse::from(testdrive)))
+    Ok(Creation::created(
+        format!("/api/testdrives/bookings/{}", testdrive.id),
+        TestDriveBookingResponse::from(testdrive),
+    ))
 }
 
 // Get test drive booking by ID
@@ -202,6 +241,7 @@ pub async fn get_seller_testdrive_bookings(
         (status = 200, description = "Test drive accepted", body = TestDriveBookingResponse),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Not found"),
+        (status = 409, description = "Test drive sudah diubah oleh request lain atau slot bentrok"),
     )
 )]
 pub async fn accept_testdrive_booking(
@@ -222,13 +262,105 @@ pub async fn accept_testdrive_booking(
     }
 
     // Accept test drive booking
-    let updated = testdrive_repo::confirm_testdrive(&state.db, id).await?;
+    let updated = testdrive_repo::confirm_testdrive(&state.db, &testdrive).await?;
 
-    tracing::info!("Test drive {} accepted by seller {}", id, auth.user_id);
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "testdrive",
+        booking_id = id,
+        status = %updated.status,
+        seller_id = auth.user_id,
+        "Test drive accepted by seller"
+    );
 
     Ok(Json(TestDriveBookingResponse::from(updated)))
 }
 
+// Seller accept banyak test drive sekaligus
+#[utoipa::path(
+    post,
+    path = "/api/testdrives/bookings/bulk-accept",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    request_body = BulkAcceptTestDriveRequest,
+    responses(
+        (status = 200, description = "Hasil per booking, booking yang slotnya bentrok dilewati", body = BulkTestDriveResponse),
+        (status = 400, description = "booking_ids kosong atau melebihi batas"),
+    )
+)]
+pub async fn bulk_accept_testdrive_bookings(
+    auth: AuthSeller,
+    State(state): State<AppState>,
+    Json(payload): Json<BulkAcceptTestDriveRequest>,
+) -> Result<Json<BulkTestDriveResponse>, AppError> {
+    let ids = testdrive_bulk::normalize_booking_ids(&payload.booking_ids)
+        .map_err(AppError::bad_request)?;
+
+    let results = testdrive_repo::bulk_transition_testdrives(
+        &state.db,
+        auth.user_id,
+        &ids,
+        BulkTestDriveAction::Accept,
+    ).await?;
+
+    let response = BulkTestDriveResponse::from(results);
+
+    tracing::info!(
+        event = "booking_bulk_status_changed",
+        booking_type = "testdrive",
+        status = TestDriveStatus::Diterima.as_str(),
+        seller_id = auth.user_id,
+        succeeded = response.succeeded,
+        failed = response.failed,
+        "Seller bulk accept test drive"
+    );
+
+    Ok(Json(response))
+}
+
+// Seller tolak banyak test drive sekaligus
+#[utoipa::path(
+    post,
+    path = "/api/testdrives/bookings/bulk-reject",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    request_body = BulkRejectTestDriveRequest,
+    responses(
+        (status = 200, description = "Hasil per booking", body = BulkTestDriveResponse),
+        (status = 400, description = "booking_ids kosong atau melebihi batas"),
+    )
+)]
+pub async fn bulk_reject_testdrive_bookings(
+    auth: AuthSeller,
+    State(state): State<AppState>,
+    Json(payload): Json<BulkRejectTestDriveRequest>,
+) -> Result<Json<BulkTestDriveResponse>, AppError> {
+    let ids = testdrive_bulk::normalize_booking_ids(&payload.booking_ids)
+        .map_err(AppError::bad_request)?;
+    let reason = testdrive_bulk::reject_reason(payload.reason.as_deref());
+
+    let results = testdrive_repo::bulk_transition_testdrives(
+        &state.db,
+        auth.user_id,
+        &ids,
+        BulkTestDriveAction::Reject { reason: &reason },
+    ).await?;
+
+    let response = BulkTestDriveResponse::from(results);
+
+    tracing::info!(
+        event = "booking_bulk_status_changed",
+        booking_type = "testdrive",
+        status = TestDriveStatus::Cancelled.as_str(),
+        seller_id = auth.user_id,
+        succeeded = response.succeeded,
+        failed = response.failed,
+        "Seller bulk reject test drive"
+    );
+
+    Ok(Json(response))
+}
+
 // Seller reschedule test drive dengan alternative slots
 #[utoipa::path(
     put,
@@ -239,8 +371,10 @@ pub async fn accept_testdrive_booking(
     request_body = RescheduleTestDriveRequest,
     responses(
         (status = 200, description = "Test drive rescheduled", body = TestDriveBookingResponse),
+        (status = 400, description = "Slot reschedule tidak valid"),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Not found"),
+        (status = 409, description = "Test drive sudah diubah oleh request lain"),
     )
 )]
 pub async fn reschedule_testdrive_booking(
@@ -261,12 +395,39 @@ pub async fn reschedule_testdrive_booking(
         return Err(AppError::bad_request("Test drive tidak dalam status menunggu konfirmasi"));
     }
 
-    let reschedule_slots: sqlx::types::JsonValue = serde_json::to_value(&payload.reschedule_slots)
+    // Slot duplikat dibuang, sisanya wajib valid sebelum disimpan
+    let hours = seller_business_hours(&state, auth.user_id).await?;
+    let slots = reschedule_slots::validate_reschedule_slots(payload.reschedule_slots, &hours, chrono::Utc::now())
+        .map_err(AppError::validation)?;
+    let reschedule_slots: sqlx::types::JsonValue = serde_json::to_value(&slots)
         .map_err(|_| AppError::internal("Invalid reschedule slots format"))?;
 
-    let updated = testdrive_repo::reschedule_testdrive(&state.db, id, reschedule_slots).await?;
+    // Validasi lokasi alternatif jika seller mengusulkan tempat lain
+    let proposed_location = match payload.location {
+        Some(mut proposal) => {
+            let location = testdrive_location::validate_location(
+                Some(&proposal.location),
+                proposal.address.as_deref(),
+                proposal.lat,
+                proposal.lng,
+            )?;
+            proposal.location = location.as_str().to_string();
+            Some(serde_json::to_value(&proposal)
+                .map_err(|_| AppError::internal("Invalid proposed location format"))?)
+        }
+        None => None,
+    };
+
+    let updated = testdrive_repo::reschedule_testdrive(&state.db, &testdrive, reschedule_slots, proposed_location).await?;
 
-    tracing::info!("Test drive {} rescheduled by seller {}", id, auth.user_id);
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "testdrive",
+        booking_id = id,
+        status = %updated.status,
+        seller_id = auth.user_id,
+        "Test drive rescheduled by seller"
+    );
 
     Ok(Json(TestDriveBookingResponse::from(updated)))
 }
@@ -283,6 +444,7 @@ pub async fn reschedule_testdrive_booking(
         (status = 200, description = "Slot chosen", body = TestDriveBookingResponse),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Not found"),
+        (status = 409, description = "Test drive sudah diubah oleh request lain atau jam bentrok dengan test drive yang sudah diterima"),
     )
 )]
 pub async fn choose_reschedule_slot(
@@ -303,9 +465,17 @@ pub async fn choose_reschedule_slot(
         return Err(AppError::bad_request("Test drive tidak dalam status reschedule"));
     }
 
-    let updated = testdrive_repo::choose_reschedule_slot(&state.db, id, payload.slot_index).await?;
+    let hours = seller_business_hours(&state, testdrive.seller_id).await?;
+    let updated = testdrive_repo::choose_reschedule_slot(&state.db, &testdrive, payload.slot_index, &hours).await?;
 
-    tracing::info!("Customer {} chose slot {} for testdrive {}", auth.user_id, payload.slot_index, id);
+    tracing::info!(
+        event = "testdrive_slot_chosen",
+        booking_type = "testdrive",
+        booking_id = id,
+        customer_id = auth.user_id,
+        slot_index = payload.slot_index,
+        "Customer chose reschedule slot"
+    );
 
     Ok(Json(TestDriveBookingResponse::from(updated)))
 }
@@ -322,6 +492,7 @@ pub async fn choose_reschedule_slot(
         (status = 200, description = "Test drive confirmed", body = TestDriveBookingResponse),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Not found"),
+        (status = 409, description = "Test drive sudah diubah oleh request lain atau slot bentrok"),
     )
 )]
 pub async fn confirm_testdrive_booking(
@@ -347,9 +518,16 @@ pub async fn confirm_testdrive_booking(
         return Err(AppError::bad_request("Status tidak valid. Hanya 'diterima' yang diperbolehkan"));
     }
 
-    let updated = testdrive_repo::confirm_testdrive(&state.db, id).await?;
+    let updated = testdrive_repo::confirm_testdrive(&state.db, &testdrive).await?;
 
-    tracing::info!("Test drive {} confirmed by seller {}", id, auth.user_id);
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "testdrive",
+        booking_id = id,
+        status = %updated.status,
+        seller_id = auth.user_id,
+        "Test drive confirmed by seller"
+    );
 
     Ok(Json(TestDriveBookingResponse::from(updated)))
 }
@@ -366,6 +544,7 @@ pub async fn confirm_testdrive_booking(
         (status = 200, description = "Test drive completed", body = TestDriveBookingResponse),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Not found"),
+        (status = 409, description = "Test drive sudah diubah oleh request lain"),
     )
 )]
 pub async fn complete_testdrive_booking(
@@ -391,9 +570,16 @@ pub async fn complete_testdrive_booking(
         tracing::info!("Completion notes untuk test drive {}: {}", id, notes);
     }
 
-    let updated = testdrive_repo::complete_testdrive(&state.db, id).await?;
+    let updated = testdrive_repo::complete_testdrive(&state.db, &testdrive).await?;
 
-    tracing::info!("Test drive {} completed by seller {}", id, auth.user_id);
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "testdrive",
+        booking_id = id,
+        status = %updated.status,
+        seller_id = auth.user_id,
+        "Test drive completed by seller"
+    );
 
     Ok(Json(TestDriveBookingResponse::from(updated)))
 }
@@ -410,6 +596,7 @@ pub async fn complete_testdrive_booking(
         (status = 200, description = "Test drive cancelled", body = MessageResponse),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Not found"),
+        (status = 409, description = "Test drive sudah diubah oleh request lain"),
     )
 )]
 pub async fn cancel_testdrive_booking(
@@ -430,15 +617,172 @@ pub async fn cancel_testdrive_booking(
         return Err(AppError::bad_request("Test drive sudah selesai atau dibatalkan"));
     }
 
-    testdrive_repo::cancel_testdrive(&state.db, id, &payload.cancel_reason).await?;
+    testdrive_repo::cancel_testdrive(&state.db, &testdrive, &payload.cancel_reason).await?;
 
-    tracing::info!("Test drive {} cancelled by customer {}", id, auth.user_id);
+    tracing::info!(
+        event = "booking_status_changed",
+        booking_type = "testdrive",
+        booking_id = id,
+        status = TestDriveStatus::Cancelled.as_str(),
+        customer_id = auth.user_id,
+        "Test drive cancelled by customer"
+    );
 
     Ok(Json(MessageResponse {
         message: "Test drive booking berhasil dibatalkan".to_string(),
     }))
 }
 
+// Lihat jam operasional test drive seller
+#[utoipa::path(
+    get,
+    path = "/api/testdrives/business-hours",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Jam operasional test drive", body = BusinessHoursResponse),
+        (status = 403, description = "Forbidden"),
+    )
+)]
+pub async fn get_business_hours(
+    auth: AuthSeller,
+    State(state): State<AppState>,
+) -> Result<Json<BusinessHoursResponse>, AppError> {
+    let response = match testdrive_repo::find_business_hours(&state.db, auth.user_id).await? {
+        Some(hours) => business_hours_response(&hours, false),
+        None => business_hours_response(&state.config.testdrive_hours, true),
+    };
+
+    Ok(Json(response))
+}
+
+// Seller atur jam operasional test drive
+#[utoipa::path(
+    put,
+    path = "/api/testdrives/business-hours",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    request_body = SetBusinessHoursRequest,
+    responses(
+        (status = 200, description = "Jam operasional tersimpan", body = BusinessHoursResponse),
+        (status = 400, description = "Jam operasional tidak valid"),
+        (status = 403, description = "Forbidden"),
+    )
+)]
+pub async fn set_business_hours(
+    auth: AuthSeller,
+    State(state): State<AppState>,
+    Json(payload): Json<SetBusinessHoursRequest>,
+) -> Result<Json<BusinessHoursResponse>, AppError> {
+    let hours = BusinessHours::parse(&payload.open_time, &payload.close_time, &payload.timezone)
+        .map_err(AppError::fields)?;
+
+    testdrive_repo::upsert_business_hours(&state.db, auth.user_id, &hours).await?;
+
+    tracing::info!(
+        event = "testdrive_hours_updated",
+        seller_id = auth.user_id,
+        open = %business_hours::format_clock(hours.open),
+        close = %business_hours::format_clock(hours.close),
+        timezone = hours.timezone.name(),
+        "Seller set test drive hours"
+    );
+
+    Ok(Json(business_hours_response(&hours, false)))
+}
+
+// Lihat jadwal slot test drive seller
+#[utoipa::path(
+    get,
+    path = "/api/testdrives/availability",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Jadwal slot test drive", body = AvailabilityResponse),
+        (status = 403, description = "Forbidden"),
+    )
+)]
+pub async fn get_availability(
+    auth: AuthSeller,
+    State(state): State<AppState>,
+) -> Result<Json<AvailabilityResponse>, AppError> {
+    let hours = seller_business_hours(&state, auth.user_id).await?;
+    let rules = testdrive_repo::find_availability(&mut *state.db.acquire().await?, auth.user_id).await?;
+
+    Ok(Json(availability_response(&hours, rules)))
+}
+
+// Seller publikasikan jadwal slot test drive (menggantikan jadwal lama)
+#[utoipa::path(
+    put,
+    path = "/api/testdrives/availability",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    request_body = SetAvailabilityRequest,
+    responses(
+        (status = 200, description = "Jadwal slot tersimpan", body = AvailabilityResponse),
+        (status = 400, description = "Jadwal slot tidak valid"),
+        (status = 403, description = "Forbidden"),
+    )
+)]
+pub async fn set_availability(
+    auth: AuthSeller,
+    State(state): State<AppState>,
+    Json(payload): Json<SetAvailabilityRequest>,
+) -> Result<Json<AvailabilityResponse>, AppError> {
+    // Jam slot dievaluasi di timezone jam operasional seller
+    let hours = seller_business_hours(&state, auth.user_id).await?;
+    let today = hours.local_date(chrono::Utc::now());
+
+    let entries = testdrive_slots::validate_availability(&payload, &hours, today)
+        .map_err(AppError::fields)?;
+    let rules = testdrive_repo::replace_availability(&state.db, auth.user_id, &entries).await?;
+
+    tracing::info!(
+        event = "testdrive_availability_updated",
+        seller_id = auth.user_id,
+        entries = rules.len(),
+        "Seller published test drive availability"
+    );
+
+    Ok(Json(availability_response(&hours, rules)))
+}
+
+// Slot test drive seller yang masih bisa dibooking di satu tanggal
+#[utoipa::path(
+    get,
+    path = "/api/testdrives/sellers/{id}/slots",
+    tag = "Test Drive Bookings",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = i32, Path, description = "Seller ID"),
+        SlotQuery
+    ),
+    responses(
+        (status = 200, description = "Slot terbuka", body = SellerSlotsResponse),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+pub async fn get_seller_slots(
+    _auth: AuthUser,
+    State(state): State<AppState>,
+    Path(seller_id): Path<i32>,
+    Query(query): Query<SlotQuery>,
+) -> Result<Json<SellerSlotsResponse>, AppError> {
+    let hours = seller_business_hours(&state, seller_id).await?;
+
+    let mut conn = state.db.acquire().await?;
+    let rules = testdrive_repo::find_availability(&mut conn, seller_id).await?;
+    let booked = testdrive_repo::count_booked_slots(&mut conn, seller_id, query.date, &hours).await?;
+
+    Ok(Json(SellerSlotsResponse {
+        seller_id,
+        date: query.date,
+        timezone: hours.timezone.name().to_string(),
+        slots: testdrive_slots::open_slots(&rules, query.date, &hours, &booked, chrono::Utc::now()),
+    }))
+}
+
 // Auto-timeout expired test drives (scheduler endpoint)
 #[utoipa::path(
     post,
@@ -460,7 +804,14 @@ pub async fn timeout_expired_testdrives(
 ) -> Result<Json<serde_json::Value>, AppError> {
     let timeout_count = testdrive_repo::timeout_expired_testdrives(&state.db).await?;
 
-    tracing::info!("Timed out {} expired test drives by user {}", timeout_count, auth.user_id);
+    tracing::info!(
+        event = "booking_bulk_status_changed",
+        booking_type = "testdrive",
+        status = TestDriveStatus::Timeout.as_str(),
+        user_id = auth.user_id,
+        succeeded = timeout_count,
+        "Expired test drives timed out"
+    );
 
     Ok(Json(serde_json::json!({
         "timeout_count": timeout_count,
@@ -468,24 +819,187 @@ pub async fn timeout_expired_testdrives(
     })))
 }
 
-// Validasi create testdrive request
-fn validate_create_testdrive(payload: &CreateTestDriveRequest) -> Result<(), AppError> {
+// Jam operasional seller, fallback ke default global
+async fn seller_business_hours(state: &AppState, seller_id: i32) -> Result<BusinessHours, AppError> {
+    Ok(testdrive_repo::find_business_hours(&state.db, seller_id)
+        .await?
+        .unwrap_or(state.config.testdrive_hours))
+}
+
+fn business_hours_response(hours: &BusinessHours, is_default: bool) -> BusinessHoursResponse {
+    BusinessHoursResponse {
+        open_time: business_hours::format_clock(hours.open),
+        close_time: business_hours::format_clock(hours.close),
+        last_start_time: business_hours::format_clock(hours.last_start()),
+        timezone: hours.timezone.name().to_string(),
+        is_default,
+    }
+}
+
+// Isi requested_time dari slot yang dipilih; tanpa slot_id hanya boleh jika seller belum memakai jadwal slot
+fn apply_slot_time(payload: &mut CreateTestDriveRequest, rules: &[SellerAvailability]) -> Result<(), AppError> {
+    let Some(slot_id) = payload.slot_id else {
+        if testdrive_slots::requires_slot(rules) {
+            return Err(AppError::fields(vec![FieldError::new(
+                "slot_id",
+                "Seller memakai jadwal slot, pilih slot_id dari daftar slot yang tersedia",
+            )]));
+        }
+        return Ok(());
+    };
+
+    let time = testdrive_slots::slot_time(rules, slot_id)
+        .ok_or_else(|| AppError::fields(vec![FieldError::new("slot_id", "Slot tidak ditemukan")]))?;
+
+    let requested = payload.requested_time.trim();
+    if !requested.is_empty() && requested != time {
+        return Err(AppError::fields(vec![FieldError::new(
+            "requested_time",
+            format!("requested_time tidak sesuai dengan slot ({})", time),
+        )]));
+    }
+
+    payload.requested_time = time;
+    Ok(())
+}
+
+fn availability_response(hours: &BusinessHours, rules: Vec<SellerAvailability>) -> AvailabilityResponse {
+    AvailabilityResponse {
+        timezone: hours.timezone.name().to_string(),
+        entries: rules.into_iter().map(|rule| AvailabilityEntry {
+            id: rule.id,
+            weekday: rule.weekday,
+            date: rule.date,
+            time: rule.start_time.map(business_hours::format_clock),
+            capacity: rule.capacity,
+            available: rule.is_available,
+        }).collect(),
+    }
+}
+
+// Validasi create testdrive request, return lokasi pertemuan.
+// Semua field yang salah dikembalikan sekaligus
+fn validate_create_testdrive(
+    payload: &CreateTestDriveRequest,
+    hours: &BusinessHours,
+) -> Result<TestDriveLocation, AppError> {
+    let mut errors = Vec::new();
+
     if payload.customer_name.trim().is_empty() {
-        return Err(AppError::validation("Nama customer harus diisi"));
+        errors.push(FieldError::new("customer_name", "Nama customer harus diisi"));
     }
 
     if !validation::is_valid_phone(&payload.customer_phone) {
-        return Err(AppError::validation("Format nomor telepon tidak valid"));
+        errors.push(FieldError::new("customer_phone", "Format nomor telepon tidak valid"));
     }
 
     if !validation::is_valid_email(&payload.customer_email) {
-        return Err(AppError::validation("Format email tidak valid"));
+        errors.push(FieldError::new("customer_email", "Format email tidak valid"));
     }
 
-    let now = chrono::Utc::now();
-    if payload.requested_date < now {
-        return Err(AppError::validation("Tanggal test drive tidak boleh di masa lalu"));
+    // Jam test drive adalah jam lokal seller pada tanggal requested_date
+    let starts_at = match hours.slot_start(payload.requested_date, &payload.requested_time) {
+        Ok(start) => start,
+        Err(e) => {
+            errors.push(FieldError::new("requested_time", e));
+            payload.requested_date
+        }
+    };
+
+    if starts_at < chrono::Utc::now() {
+        errors.push(FieldError::new("requested_date", "Tanggal test drive tidak boleh di masa lalu"));
     }
 
-    Ok(())
+    let location = testdrive_location::validate_location(
+        payload.location.as_deref(),
+        payload.address.as_deref(),
+        payload.lat,
+        payload.lng,
+    );
+
+    match location {
+        Ok(location) if errors.is_empty() => Ok(location),
+        Ok(_) => Err(AppError::fields(errors)),
+        Err(e) => {
+            errors.push(e);
+            Err(AppError::fields(errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::buyer_block::BlockBuyerRequest, handlers::buyer_block_handlers};
+    use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+
+    // vehicle-service palsu: semua mobil milik seller 2 dan tersedia untuk test drive
+    async fn vehicle_service_stub() -> String {
+        let app = Router::new().route(
+            "/vehicles/{id}/testdrive-info",
+            get(|Path(id): Path<i32>| async move {
+                Json(serde_json::json!({ "id": id, "seller_id": 2, "is_available": true }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    fn booking(requested_time: &str) -> Json<CreateTestDriveRequest> {
+        Json(CreateTestDriveRequest {
+            vehicle_id: 2,
+            requested_date: chrono::Utc::now() + chrono::Duration::days(3),
+            requested_time: requested_time.to_string(),
+            slot_id: None,
+            customer_name: "Customer Test".to_string(),
+            customer_phone: "081234567890".to_string(),
+            customer_email: "customer@test.local".to_string(),
+            notes: None,
+            location: None,
+            address: None,
+            lat: None,
+            lng: None,
+        })
+    }
+
+    async fn book(state: &AppState, requested_time: &str) -> Result<StatusCode, AppError> {
+        let customer = AuthCustomer { user_id: 1, email: "customer@test.local".to_string() };
+        let created = create_testdrive_booking(customer, State(state.clone()), booking(requested_time)).await?;
+
+        Ok(created.into_response().status())
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_blocked_buyer_cannot_book_testdrive(pool: sqlx::PgPool) {
+        let mut state = AppState::for_test(pool);
+        state.config.vehicle_service_url = vehicle_service_stub().await;
+        let seller = AuthSeller { user_id: 2, email: "seller@test.local".to_string() };
+
+        // Buyer yang belum diblokir bisa booking, booking itu juga membuatnya bisa diblokir
+        assert_eq!(book(&state, "10:00").await.unwrap(), StatusCode::CREATED);
+
+        let block = Json(BlockBuyerRequest { buyer_id: 1, reason: Some("Tidak datang".to_string()) });
+        buyer_block_handlers::block_buyer(State(state.clone()), seller, block).await.unwrap();
+
+        let Err(AppError::BadRequest(message)) = book(&state, "13:00").await else {
+            panic!("buyer yang diblokir harus ditolak");
+        };
+        assert_eq!(message, "Vehicle tidak tersedia untuk test drive");
+
+        let bookings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM testdrive_bookings WHERE customer_id = 1")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(bookings, 1);
+    }
 }
@@ -0,0 +1,252 @@
+// API Handlers untuk outbound webhook seller (integrasi back-office dealer)
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use shared::utils::{
+    creation::Creation,
+    pagination::{Pagination, PaginationParams},
+};
+
+use crate::{
+    domain::webhook::{
+        CreateWebhookRequest, DeliveryQueryParams, DeliveryStatus, WebhookDelivery, WebhookResponse,
+    },
+    middleware::auth::AuthSeller,
+    repositories::webhook_repo,
+    utils::outbound_webhook::{self, MAX_WEBHOOKS_PER_SELLER},
+    error::AppError,
+    AppState,
+};
+
+// Daftarkan endpoint webhook baru
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    summary = "Daftarkan endpoint webhook",
+    description = "Event order, payment, dan test drive milik seller dikirim ke URL ini dengan signature HMAC-SHA256 (`X-BigAuto-Signature: v1=...` atas `{timestamp}.{body}`, timestamp di `X-BigAuto-Timestamp`). Secret hanya ditampilkan sekali",
+    security(("bearer_auth" = [])),
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Endpoint terdaftar", body = WebhookResponse,
+            headers(("Location" = String, description = "URL endpoint webhook"))),
+        (status = 400, description = "URL tidak valid"),
+        (status = 409, description = "Batas endpoint aktif tercapai"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<Creation<WebhookResponse>, AppError> {
+    let url = outbound_webhook::validate_endpoint_url(&payload.url, state.config.is_production())
+        .map_err(AppError::validation)?;
+
+    if webhook_repo::count_active_webhooks(&state.db, auth.user_id).await? >= MAX_WEBHOOKS_PER_SELLER {
+        return Err(AppError::conflict(format!(
+            "Maksimal {} endpoint webhook aktif per seller",
+            MAX_WEBHOOKS_PER_SELLER
+        )));
+    }
+
+    let secret = outbound_webhook::generate_secret();
+    let webhook = webhook_repo::create_webhook(&state.db, auth.user_id, url.as_str(), &secret).await?;
+
+    tracing::info!("Seller {} mendaftarkan webhook {}", auth.user_id, webhook.id);
+
+    Ok(Creation::created(format!("/api/webhooks/{}", webhook.id), WebhookResponse::new(webhook, true)))
+}
+
+// List endpoint webhook milik seller
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    summary = "Endpoint webhook saya",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "List endpoint webhook", body = Vec<WebhookResponse>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+) -> Result<Json<Vec<WebhookResponse>>, AppError> {
+    let webhooks = webhook_repo::find_webhooks_by_seller(&state.db, auth.user_id).await?;
+
+    Ok(Json(
+        webhooks
+            .into_iter()
+            .map(|webhook| WebhookResponse::new(webhook, false))
+            .collect(),
+    ))
+}
+
+// Detail endpoint webhook (target Location saat endpoint didaftarkan)
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    summary = "Detail endpoint webhook",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Endpoint webhook", body = WebhookResponse),
+        (status = 404, description = "Webhook tidak ditemukan"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+) -> Result<Json<WebhookResponse>, AppError> {
+    let webhook = webhook_repo::find_webhook(&state.db, id, auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Webhook tidak ditemukan"))?;
+
+    Ok(Json(WebhookResponse::new(webhook, false)))
+}
+
+// Rotate secret endpoint webhook
+#[utoipa::path(
+    put,
+    path = "/api/webhooks/{id}/rotate-secret",
+    tag = "webhooks",
+    summary = "Rotate secret webhook",
+    description = "Secret lama langsung tidak berlaku, termasuk untuk retry delivery yang masih pending",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Secret baru", body = WebhookResponse),
+        (status = 404, description = "Webhook tidak ditemukan"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn rotate_webhook_secret(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+) -> Result<Json<WebhookResponse>, AppError> {
+    let secret = outbound_webhook::generate_secret();
+    let webhook = webhook_repo::rotate_secret(&state.db, id, auth.user_id, &secret)
+        .await?
+        .ok_or_else(|| AppError::not_found("Webhook tidak ditemukan"))?;
+
+    tracing::info!("Seller {} rotate secret webhook {}", auth.user_id, id);
+
+    Ok(Json(WebhookResponse::new(webhook, true)))
+}
+
+// Nonaktifkan endpoint webhook
+#[utoipa::path(
+    put,
+    path = "/api/webhooks/{id}/disable",
+    tag = "webhooks",
+    summary = "Nonaktifkan webhook",
+    description = "Event baru tidak lagi dikirim dan delivery yang masih pending dibatalkan",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Webhook dinonaktifkan", body = WebhookResponse),
+        (status = 404, description = "Webhook tidak ditemukan"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn disable_webhook(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+) -> Result<Json<WebhookResponse>, AppError> {
+    let webhook = webhook_repo::disable_webhook(&state.db, id, auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Webhook tidak ditemukan"))?;
+
+    tracing::info!("Seller {} menonaktifkan webhook {}", auth.user_id, id);
+
+    Ok(Json(WebhookResponse::new(webhook, false)))
+}
+
+// Delivery log terbaru endpoint webhook
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    summary = "Riwayat delivery webhook",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = i32, Path, description = "Webhook ID"),
+        DeliveryQueryParams,
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Delivery terbaru", body = Vec<WebhookDelivery>),
+        (status = 400, description = "Filter status atau parameter paginasi tidak valid"),
+        (status = 404, description = "Webhook tidak ditemukan"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+    Query(params): Query<DeliveryQueryParams>,
+    Pagination { limit, offset, .. }: Pagination,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let status = params.status.as_deref();
+    let valid_statuses = [DeliveryStatus::Pending, DeliveryStatus::Delivered, DeliveryStatus::Failed];
+    if let Some(status) = status {
+        if !valid_statuses.iter().any(|valid| valid.as_str() == status) {
+            return Err(AppError::bad_request("Status harus pending, delivered, atau failed"));
+        }
+    }
+
+    let deliveries = webhook_repo::find_recent_deliveries(&state.db, id, auth.user_id, status, limit, offset)
+        .await?
+        .ok_or_else(|| AppError::not_found("Webhook tidak ditemukan"))?;
+
+    Ok(Json(deliveries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+    };
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_create_webhook_returns_201_with_location(pool: sqlx::PgPool) {
+        let state = AppState::for_test(pool);
+        let seller = AuthSeller { user_id: 2, email: "seller@test.local".to_string() };
+        let payload = Json(CreateWebhookRequest { url: "https://dms.dealer.co.id/bigauto/webhook".to_string() });
+
+        let response = create_webhook(State(state.clone()), seller, payload).await.unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let webhooks = webhook_repo::find_webhooks_by_seller(&state.db, 2).await.unwrap();
+        assert_eq!(webhooks.len(), 1);
+        assert_eq!(response.headers()[header::LOCATION], format!("/api/webhooks/{}", webhooks[0].id));
+
+        // Location bisa di-GET oleh pemilik, seller lain mendapat 404
+        let owner = AuthSeller { user_id: 2, email: "seller@test.local".to_string() };
+        let Json(fetched) = get_webhook(State(state.clone()), owner, Path(webhooks[0].id)).await.unwrap();
+        assert_eq!(fetched.id, webhooks[0].id);
+        let other = AuthSeller { user_id: 3, email: "seller2@test.local".to_string() };
+        assert!(matches!(
+            get_webhook(State(state), other, Path(webhooks[0].id)).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
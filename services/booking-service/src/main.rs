@@ -1,13 +1,12 @@
 // Main entry point untuk booking-service
-use axum::{Router, http::StatusCode};
+use axum::Router;
 use tower::ServiceBuilder;
+use shared::utils::{bind_addr, cors::CorsPolicy, logging, request_id, request_timeout, startup_gate::StartupGate};
 use tower_http::{
     compression::CompressionLayer,
     cors::CorsLayer,
-    trace::TraceLayer,
 };
 use dotenvy::dotenv;
-use std::net::SocketAddr;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
@@ -39,7 +38,7 @@ async fn main() -> AppResult<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "booking_service=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(logging::fmt_layer())
         .init();
 
     tracing::info!("🚀 Memulai BIG AUTO - Booking Service");
@@ -58,6 +57,32 @@ async fn main() -> AppResult<()> {
         tracing::warn!("⚠️  WARNING: Masih menggunakan default JWT_SECRET di production!");
     }
 
+    // Bind server ke BOOKING_SERVICE_HOST/BOOKING_SERVICE_PORT (sudah divalidasi di config)
+    let addr = bind_addr::resolve("BOOKING_SERVICE_HOST", config.host(), config.port())
+        .map_err(AppError::internal)?;
+
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .map_err(|e| AppError::internal(format!("Gagal bind server: {}", e)))?;
+
+    // Listener dibind sebelum koneksi database/Redis, request dijawab 503 sampai state siap
+    let gate = StartupGate::new(&["/health"]);
+    let server = tokio::spawn({
+        let router = gate.router();
+        async move {
+            // ConnectInfo dibutuhkan rate limiter untuk request tanpa X-Forwarded-For
+            axum::serve(listener, router.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+        }
+    });
+
+    // Start server dengan graceful shutdown
+    tracing::info!("🌐 Server listening on http://{}", addr);
+    tracing::info!("📚 API Documentation:");
+    tracing::info!("   - Swagger UI: http://{}/swagger-ui", addr);
+    tracing::info!("   - ReDoc: http://{}/redoc", addr);
+    tracing::info!("   - OpenAPI JSON: http://{}/api-docs/openapi.json", addr);
+
     // Buat application state dengan database connection
     let app_state = AppState::new(config).await
         .map_err(|e| AppError::internal(format!("Gagal inisialisasi app state: {}", e)))?;
@@ -70,25 +95,12 @@ async fn main() -> AppResult<()> {
     scheduler::BookingScheduler::new(app_state.clone()).start();
     tracing::info!("✅ Background cleanup scheduler started");
 
-    // Build router dengan middleware
+    // Build router dengan middleware, mulai layani request
     let app = create_app(app_state.clone()).await;
+    gate.ready(app);
 
-    // Bind server ke address yang ditentukan
-    let addr = SocketAddr::from(([0, 0, 0, 0], app_state.config.port()));
-
-    // Start server dengan graceful shutdown
-    tracing::info!("🌐 Server listening on http://{}", addr);
-    tracing::info!("📚 API Documentation:");
-    tracing::info!("   - Swagger UI: http://{}:{}/swagger-ui", app_state.config.host(), app_state.config.port());
-    tracing::info!("   - ReDoc: http://{}:{}/redoc", app_state.config.host(), app_state.config.port());
-    tracing::info!("   - OpenAPI JSON: http://{}:{}/api-docs/openapi.json", app_state.config.host(), app_state.config.port());
-
-    let listener = tokio::net::TcpListener::bind(addr).await
-        .map_err(|e| AppError::internal(format!("Gagal bind server: {}", e)))?;
-
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
+    server.await
+        .map_err(|e| AppError::internal(format!("Server task gagal: {}", e)))?
         .map_err(|e| AppError::internal(format!("Server error: {}", e)))?;
 
     tracing::info!("✅ Server shutdown gracefully");
@@ -101,32 +113,28 @@ async fn create_app(state: AppState) -> Router {
     let arc_state = Arc::new(state);
 
     // Build router dengan semua middleware
-    Router::new()
+    let router = Router::new()
         .merge(create_router(arc_state.as_ref().clone()))
         .fallback(not_found_handler)
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
+                // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+                .layer(request_timeout::layer(request_timeout::default_timeout()))
                 .layer(CompressionLayer::new())
                 .layer(create_cors_layer())
-        )
+        );
+
+    request_id::apply(router)
 }
 
 // JWT-Only CORS configuration 
 fn create_cors_layer() -> CorsLayer {
-    use axum::http::{HeaderValue, Method};
+    use axum::http::Method;
     use std::env;
     use std::time::Duration;
 
-    // Allowed origins dari environment variable
-    let allowed_origins = env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable HARUS diisi di .env file");
-
-    // Parse origins yang diperbolehkan 
-    let origins: Vec<HeaderValue> = allowed_origins
-        .split(',')
-        .filter_map(|origin| origin.trim().parse::<HeaderValue>().ok())
-        .collect();
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     // CORS max age dari environment
     let max_age_seconds = env::var("CORS_MAX_AGE_SECONDS")
@@ -134,30 +142,23 @@ fn create_cors_layer() -> CorsLayer {
         .parse::<u64>()
         .unwrap_or(86400);
 
-    tracing::info!("🌐 CORS enabled for origins: {}", allowed_origins);
+    tracing::info!("🌐 CORS enabled for origins: {}", env::var("FRONTEND_URL").unwrap_or_default());
 
     // JWT-Only CORS Configuration 
-    let mut cors = CorsLayer::new()
+    policy
+        .apply(CorsLayer::new())
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             axum::http::header::AUTHORIZATION,
             axum::http::header::ACCEPT,
             axum::http::header::CONTENT_TYPE,
         ])
-        .allow_credentials(false) 
-        .max_age(Duration::from_secs(max_age_seconds));
-
-    // Tambahkan semua allowed origins
-    for origin in origins {
-        cors = cors.allow_origin(origin);
-    }
-
-    cors
+        .max_age(Duration::from_secs(max_age_seconds))
 }
 
-// Handler untuk 404 errors
-async fn not_found_handler() -> (StatusCode, &'static str) {
-    (StatusCode::NOT_FOUND, "API endpoint tidak ditemukan")
+// Handler untuk 404 errors, format JSON sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
 }
 
 // Signal handler untuk graceful shutdown
@@ -1,108 +1,17 @@
 // JWT-Only Authentication untuk Booking Service
 use axum::{
     extract::{Request, State},
-    http::HeaderMap,
     response::Response,
     middleware::Next,
 };
-use crate::{config::AppState, error::AppError, utils::jwt};
+use shared::auth::{authenticate, AuthState};
+use crate::{config::AppState, error::AppError};
 
-// User authentication context 
-#[derive(Debug, Clone)]
-pub struct AuthUser {
-    pub user_id: i32,
-    pub email: String,
-    pub role: String,
-}
-
-// Customer authentication context - all users
-#[derive(Debug, Clone)]
-pub struct AuthCustomer {
-    pub user_id: i32,
-    pub email: String,
-}
-
-// Seller authentication context - role-restricted access
-#[derive(Debug, Clone)]
-pub struct AuthSeller {
-    pub user_id: i32,
-}
-
-// Implementasi Axum extractor untuk AuthUser 
-impl<S> axum::extract::FromRequestParts<S> for AuthUser
-where
-    S: Send + Sync,
-{
-    type Rejection = AppError;
-
-    async fn from_request_parts<'life0, 'life1>(
-        parts: &'life0 mut axum::http::request::Parts,
-        _state: &'life1 S,
-    ) -> Result<Self, Self::Rejection> {
-        parts
-            .extensions
-            .get::<AuthUser>()
-            .cloned()
-            .ok_or_else(|| AppError::unauthorized("Authentication required"))
-    }
-}
+// Extractor role dari shared::auth: AuthCustomer = semua user, AuthSeller = role seller
+pub use shared::auth::{AuthCustomer, AuthSeller, AuthUser};
 
-// Implementasi Axum extractor untuk AuthCustomer (all users)
-impl<S> axum::extract::FromRequestParts<S> for AuthCustomer
-where
-    S: Send + Sync,
-{
+impl AuthState for AppState {
     type Rejection = AppError;
-
-    async fn from_request_parts<'life0, 'life1>(
-        parts: &'life0 mut axum::http::request::Parts,
-        state: &'life1 S,
-    ) -> Result<Self, Self::Rejection> {
-        let auth_user = AuthUser::from_request_parts(parts, state).await?;
-
-        Ok(AuthCustomer {
-            user_id: auth_user.user_id,
-            email: auth_user.email,
-        })
-    }
-}
-
-// Implementasi Axum extractor untuk AuthSeller
-impl<S> axum::extract::FromRequestParts<S> for AuthSeller
-where
-    S: Send + Sync,
-{
-    type Rejection = AppError;
-
-    async fn from_request_parts<'life0, 'life1>(
-        parts: &'life0 mut axum::http::request::Parts,
-        state: &'life1 S,
-    ) -> Result<Self, Self::Rejection> {
-        let auth_user = AuthUser::from_request_parts(parts, state).await?;
-
-        if auth_user.role != "seller" {
-            return Err(AppError::forbidden("Seller authentication required"));
-        }
-
-        Ok(AuthSeller {
-            user_id: auth_user.user_id,
-        })
-    }
-}
-
-// Extract Bearer token dari Authorization header
-fn extract_jwt_token(headers: &HeaderMap) -> Result<String, AppError> {
-    let auth_header = headers
-        .get("authorization")
-        .ok_or_else(|| AppError::unauthorized("Authorization header dengan Bearer token diperlukan"))?
-        .to_str()
-        .map_err(|_| AppError::unauthorized("Invalid Authorization header format"))?;
-
-    if !auth_header.starts_with("Bearer ") {
-        return Err(AppError::unauthorized("Bearer token format diperlukan"));
-    }
-
-    Ok(auth_header[7..].to_string())
 }
 
 // JWT Authentication middleware dengan database blacklist validation
@@ -117,21 +26,8 @@ pub async fn jwt_auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    // Extract JWT token dari Authorization header
-    let headers = request.headers();
-    let token = extract_jwt_token(headers)?;
-
     // Validasi JWT dengan database trust boundary
-    let claims = jwt::validate_token(&token, &state.db)
-        .await
-        .map_err(|_| AppError::unauthorized("Token tidak valid, expired, atau sudah di-blacklist"))?;
-
-    // Prepare user data untuk injection
-    let auth_user = AuthUser {
-        user_id: claims.sub,
-        email: claims.email.clone(),
-        role: claims.role.clone(),
-    };
+    let auth_user = authenticate(request.headers(), &state.config.jwt, &state.db).await?;
 
     // Inject user data ke request extensions untuk extractor handlers
     request.extensions_mut().insert(auth_user.clone());
@@ -148,4 +44,23 @@ pub async fn jwt_auth_middleware(
     Ok(next.run(request).await)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::{header, HeaderMap, StatusCode}, response::IntoResponse};
+    use shared::auth::{decode_token, issue_token, JwtConfig, TokenType};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_blacklisted_token_rejected_with_401() {
+        let config = JwtConfig::new("test-secret-key-for-testing-only");
+        let token = issue_token(&config, 123, "test@example.com", "seller", TokenType::Access, 900).unwrap();
+        let jti = decode_token(&token, &config, TokenType::Access).unwrap().jti;
 
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+
+        let err: AppError = authenticate(&headers, &config, &HashSet::from([jti])).await.unwrap_err().into();
+        assert_eq!(err.into_response().status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,14 +1,20 @@
 // Redis-based Rate Limiting untuk Booking Service 
 use axum::{
-    extract::{Request, State},
-    http::StatusCode,
-    response::{Response, IntoResponse},
+    extract::{ConnectInfo, Request, State},
+    response::Response,
     middleware::Next,
 };
-use redis::{Client, AsyncCommands};
+use redis::Client;
 use std::env;
+use std::net::SocketAddr;
+use shared::utils::client_ip::{self, IpAllowlist};
+use shared::utils::rate_limit::{too_many_requests, sliding_window_hit};
 use thiserror::Error;
 
+// Tracking order publik, semua kode dihitung dalam satu window per IP
+const TRACKING_PATH_PREFIX: &str = "/api/sales/track/";
+const TRACKING_ENDPOINT: &str = "/api/sales/track";
+
 // Configuration dari environment variables
 #[derive(Debug, Clone)]
 pub struct RateLimitConfig {
@@ -16,7 +22,10 @@ pub struct RateLimitConfig {
     pub customer_requests_per_hour: u32,
     pub seller_requests_per_hour: u32,
     pub sensitive_requests_per_hour: u32,
+    pub tracking_requests_per_hour: u32,
     pub window_seconds: u64,
+    // Proxy yang boleh mengisi X-Forwarded-For untuk key guest (TRUSTED_PROXY_CIDRS)
+    pub trusted_proxies: Option<IpAllowlist>,
 }
 
 impl RateLimitConfig {
@@ -42,8 +51,13 @@ impl RateLimitConfig {
             .parse()
             .map_err(|_| RateLimitError::Configuration)?;
 
+        let tracking_requests = env::var("RATE_LIMIT_TRACKING_REQUESTS")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .map_err(|_| RateLimitError::Configuration)?;
+
         // Validasi configuration untuk security
-        if guest_requests == 0 || customer_requests == 0 || seller_requests == 0 || sensitive_requests == 0 {
+        if guest_requests == 0 || customer_requests == 0 || seller_requests == 0 || sensitive_requests == 0 || tracking_requests == 0 {
             return Err(RateLimitError::Configuration);
         }
 
@@ -51,12 +65,19 @@ impl RateLimitConfig {
             tracing::warn!("⚠️ Sensitive endpoint rate limit sangat tinggi: {}", sensitive_requests);
         }
 
+        let trusted_proxies = client_ip::trusted_proxies_from_env().map_err(|e| {
+            tracing::error!("❌ {}", e);
+            RateLimitError::Configuration
+        })?;
+
         Ok(Self {
             guest_requests_per_hour: guest_requests,
             customer_requests_per_hour: customer_requests,
             seller_requests_per_hour: seller_requests,
             sensitive_requests_per_hour: sensitive_requests,
+            tracking_requests_per_hour: tracking_requests,
             window_seconds: 3600,
+            trusted_proxies,
         })
     }
 }
@@ -94,53 +115,27 @@ impl RateLimiter {
             .map_err(RateLimitError::RedisConnection)?;
 
         let window_key = format!("rate_limit:{}:{}:{}", identifier, role, endpoint);
-        let current_time = chrono::Utc::now().timestamp() as u64;
-        let window_start = current_time - self.config.window_seconds + 1;
-
-        // Cleanup old entries dalam sliding window
-        let _: () = conn
-            .zrembyscore(&window_key, "-inf", window_start - 1)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        // Get current request count
-        let current_count: usize = conn
-            .zcard(&window_key)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
         let max_requests = self.get_max_requests(role, endpoint);
-
-        // Add current request ke sorted set
-        let _: () = conn
-            .zadd(&window_key, current_time, current_time)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        // Set expiration untuk automatic cleanup
-        let _: () = conn
-            .expire(&window_key, self.config.window_seconds as i64)
+        let hit = sliding_window_hit(&mut conn, &window_key, max_requests, self.config.window_seconds)
             .await
             .map_err(RateLimitError::RedisOperation)?;
 
-        let is_allowed = current_count < max_requests as usize;
-        let remaining = if is_allowed {
-            max_requests.saturating_sub(current_count as u32 + 1)
-        } else {
-            0
-        };
-
         Ok(RateLimitResult {
-            allowed: is_allowed,
-            current_count: current_count as u32 + 1,
+            allowed: hit.allowed,
+            current_count: hit.current_count,
             max_requests,
-            remaining,
-            reset_time: window_start + self.config.window_seconds,
+            remaining: hit.remaining,
+            reset_time: hit.reset_time,
         })
     }
 
     // Tentu max requests berdasarkan role dan endpoint sensitivity
     fn get_max_requests(&self, role: &str, endpoint: &str) -> u32 {
+        // Endpoint publik tanpa login, limit ketat untuk mencegah enumerasi kode tracking
+        if endpoint == TRACKING_ENDPOINT {
+            return self.config.tracking_requests_per_hour;
+        }
+
         // Booking service sensitive endpoints (write operations)
         let is_sensitive = endpoint.contains("/rentals/bookings") &&
                          (endpoint.contains("POST") || endpoint.contains("PUT") || endpoint.contains("DELETE")) ||
@@ -196,7 +191,7 @@ pub async fn rate_limit_middleware(
     }
 
     // Extract client identifier (user ID atau IP address)
-    let identifier = extract_identifier(&request);
+    let identifier = extract_identifier(&request, rate_limiter.config.trusted_proxies.as_ref());
 
     // Extract user role dari JWT claims 
     let role = request.extensions()
@@ -205,7 +200,7 @@ pub async fn rate_limit_middleware(
         .unwrap_or("guest");
 
     // Extract endpoint path untuk rate limiting key
-    let endpoint = request.uri().path();
+    let endpoint = rate_limit_endpoint(request.uri().path());
 
     // Check rate limit
     match rate_limiter.check_rate_limit(&identifier, role, endpoint).await {
@@ -225,20 +220,17 @@ pub async fn rate_limit_middleware(
 
             Ok(response)
         }
-        Ok(_) => {
+        Ok(result) => {
             // Rate limit exceeded
             tracing::warn!(
                 "🚨 Rate limit exceeded - identifier: {}, endpoint: {}, role: {}",
                 identifier, endpoint, role
             );
-
-            let error_response = axum::Json(serde_json::json!({
+            // Retry-After dihitung dari reset window limiter
+            Ok(too_many_requests(result.reset_time, serde_json::json!({
                 "error": "rate_limit_exceeded",
-                "message": "Too many requests. Please try again later.",
-                "retry_after": 3600
-            }));
-
-            Ok((StatusCode::TOO_MANY_REQUESTS, error_response).into_response())
+                "message": "Too many requests. Please try again later."
+            })))
         }
         Err(e) => {
             tracing::error!("💥 Rate limiting error: {}", e);
@@ -248,19 +240,71 @@ pub async fn rate_limit_middleware(
     }
 }
 
-// Extract client identifier untuk rate limiting 
-fn extract_identifier(request: &Request) -> String {
-    // Try to get user ID dari authenticated user 
-    if let Some(auth_user) = request.extensions().get::<crate::middleware::auth::AuthUser>() {
-        format!("user:{}", auth_user.user_id)
+// Path tracking digabung jadi satu key, supaya mencoba kode berbeda tidak membuka window baru
+fn rate_limit_endpoint(path: &str) -> &str {
+    if path.starts_with(TRACKING_PATH_PREFIX) {
+        TRACKING_ENDPOINT
     } else {
-        // Fallback ke IP address dengan proxy support
-        request.headers()
-            .get("x-forwarded-for")
-            .or_else(|| request.headers().get("x-real-ip"))
-            .or_else(|| request.headers().get("cf-connecting-ip")) 
-            .and_then(|h| h.to_str().ok())
-            .map(|s| s.split(',').next().unwrap_or("").trim().to_string())
-            .unwrap_or_else(|| "unknown".to_string())
+        path
+    }
+}
+
+// Extract client identifier untuk rate limiting
+// Client bebas mengisi X-Forwarded-For, jadi header hanya dipakai jika koneksi TCP datang dari
+// proxy tepercaya (lihat shared::utils::client_ip). Selain itu key = IP koneksi.
+fn extract_identifier(request: &Request, trusted_proxies: Option<&IpAllowlist>) -> String {
+    // Try to get user ID dari authenticated user
+    if let Some(auth_user) = request.extensions().get::<crate::middleware::auth::AuthUser>() {
+        return format!("user:{}", auth_user.user_id);
     }
-}
\ No newline at end of file
+
+    request.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| client_ip::source_ip(addr.ip(), request.headers(), trusted_proxies).to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(forwarded_for: Option<&str>, peer: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri("/api/sales/track/7KQ2-M9XD-4HTB-0RWE");
+        if let Some(value) = forwarded_for {
+            builder = builder.header("x-forwarded-for", value);
+        }
+        let mut request = builder.body(axum::body::Body::empty()).unwrap();
+        if let Some(addr) = peer {
+            request.extensions_mut().insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+        }
+        request
+    }
+
+    #[test]
+    fn test_identifier_ignores_client_supplied_forwarded_entries() {
+        let trusted = IpAllowlist::parse(client_ip::DEFAULT_TRUSTED_PROXY_CIDRS).unwrap();
+        let identifier = |forwarded_for, peer| extract_identifier(&request(forwarded_for, peer), Some(&trusted));
+
+        // Lewat gateway: entry kiri diisi client, entry kanan ditambahkan proxy
+        let spoofed = identifier(Some("1.1.1.1, 203.0.113.7"), Some("10.0.0.2:40000"));
+        assert_eq!(spoofed, "203.0.113.7");
+        assert_eq!(identifier(Some("9.9.9.9, 203.0.113.7"), Some("10.0.0.2:40000")), spoofed);
+
+        // Client langsung (bukan proxy tepercaya) yang memutar header tetap satu bucket
+        assert_eq!(identifier(Some("1.1.1.1"), Some("198.51.100.4:5000")), "198.51.100.4");
+        assert_eq!(identifier(Some("2.2.2.2"), Some("198.51.100.4:5000")), "198.51.100.4");
+        assert_eq!(extract_identifier(&request(Some("1.1.1.1"), Some("10.0.0.2:40000")), None), "10.0.0.2");
+
+        // Tanpa proxy: IP koneksi
+        assert_eq!(identifier(None, Some("198.51.100.4:5000")), "198.51.100.4");
+        assert_eq!(identifier(Some("bukan-ip"), Some("198.51.100.4:5000")), "198.51.100.4");
+        assert_eq!(identifier(Some("1.1.1.1"), None), "unknown");
+    }
+
+    #[test]
+    fn test_tracking_paths_share_one_window() {
+        assert_eq!(rate_limit_endpoint("/api/sales/track/7KQ2-M9XD-4HTB-0RWE"), TRACKING_ENDPOINT);
+        assert_eq!(rate_limit_endpoint("/api/sales/track/AAAA-AAAA-AAAA-AAAA"), TRACKING_ENDPOINT);
+        assert_eq!(rate_limit_endpoint("/api/sales/orders/12"), "/api/sales/orders/12");
+    }
+}
@@ -0,0 +1,162 @@
+use sqlx::{FromRow, PgPool, Row};
+
+use crate::{domain::buyer_block::BlockedBuyer, error::AppError};
+
+// Cek apakah buyer diblokir seller untuk transaksi
+pub async fn is_buyer_blocked(pool: &PgPool, seller_id: i32, buyer_id: i32) -> Result<bool, AppError> {
+    let blocked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM seller_buyer_blocks WHERE seller_id = $1 AND buyer_id = $2)"
+    )
+    .bind(seller_id)
+    .bind(buyer_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(blocked)
+}
+
+// List buyer yang diblokir seller, terbaru dulu
+pub async fn find_blocked_buyers(pool: &PgPool, seller_id: i32) -> Result<Vec<BlockedBuyer>, AppError> {
+    let blocked = sqlx::query_as(
+        "SELECT b.buyer_id, u.name AS buyer_name, b.reason, b.created_at
+         FROM seller_buyer_blocks b
+         JOIN users u ON u.id = b.buyer_id
+         WHERE b.seller_id = $1
+         ORDER BY b.created_at DESC"
+    )
+    .bind(seller_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(blocked)
+}
+
+// Satu buyer di blocklist seller
+pub async fn find_blocked_buyer(
+    pool: &PgPool,
+    seller_id: i32,
+    buyer_id: i32,
+) -> Result<Option<BlockedBuyer>, AppError> {
+    let blocked = sqlx::query_as(
+        "SELECT b.buyer_id, u.name AS buyer_name, b.reason, b.created_at
+         FROM seller_buyer_blocks b
+         JOIN users u ON u.id = b.buyer_id
+         WHERE b.seller_id = $1 AND b.buyer_id = $2"
+    )
+    .bind(seller_id)
+    .bind(buyer_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(blocked)
+}
+
+// Blokir buyer (idempotent, alasan diperbarui jika sudah diblokir)
+// Hanya buyer yang pernah menghubungi seller (chat, order, test drive, atau rental): nama user
+// lain tidak boleh bisa diintip lewat user id sembarang. None jika tidak ada kontak tersebut,
+// flag bool = true jika blokir baru dibuat. Tabel conversations milik chat-service dibaca langsung
+// di database bersama (kolomnya ikut dicek REQUIRED_SCHEMA)
+pub async fn block_buyer(
+    pool: &PgPool,
+    seller_id: i32,
+    buyer_id: i32,
+    reason: Option<&str>,
+) -> Result<Option<(BlockedBuyer, bool)>, AppError> {
+    let row = sqlx::query(
+        "WITH upserted AS (
+             INSERT INTO seller_buyer_blocks (seller_id, buyer_id, reason)
+             SELECT $1, id, $3 FROM users
+             WHERE id = $2
+               AND (
+                   EXISTS(SELECT 1 FROM conversations WHERE seller_id = $1 AND customer_id = $2)
+                   OR EXISTS(SELECT 1 FROM sale_orders WHERE seller_id = $1 AND buyer_id = $2)
+                   OR EXISTS(SELECT 1 FROM testdrive_bookings WHERE seller_id = $1 AND customer_id = $2)
+                   OR EXISTS(SELECT 1 FROM rental_bookings WHERE seller_id = $1 AND customer_id = $2)
+               )
+             ON CONFLICT (seller_id, buyer_id) DO UPDATE SET reason = EXCLUDED.reason
+             RETURNING buyer_id, reason, created_at, (xmax = 0) AS inserted
+         )
+         SELECT b.buyer_id, u.name AS buyer_name, b.reason, b.created_at, b.inserted
+         FROM upserted b
+         JOIN users u ON u.id = b.buyer_id"
+    )
+    .bind(seller_id)
+    .bind(buyer_id)
+    .bind(reason)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some((BlockedBuyer::from_row(&row)?, row.try_get("inserted")?)))
+}
+
+// Buka blokir buyer, false jika buyer tidak ada di blocklist
+pub async fn unblock_buyer(pool: &PgPool, seller_id: i32, buyer_id: i32) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM seller_buyer_blocks WHERE seller_id = $1 AND buyer_id = $2")
+        .bind(seller_id)
+        .bind(buyer_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_only_buyers_who_contacted_seller_can_be_blocked(pool: PgPool) {
+        // User yang ada tapi belum pernah menghubungi seller tidak bisa dipakai mengintip nama
+        assert!(block_buyer(&pool, 2, 1, None).await.unwrap().is_none());
+        assert!(block_buyer(&pool, 2, 999, None).await.unwrap().is_none());
+        assert!(!is_buyer_blocked(&pool, 2, 1).await.unwrap());
+
+        sqlx::query(
+            "INSERT INTO testdrive_bookings (vehicle_id, customer_id, seller_id, requested_date, requested_time,
+                 customer_name, customer_phone, customer_email)
+             VALUES (2, 1, 2, NOW() + INTERVAL '1 day', '10:00', 'Customer Test', '081234567890', 'customer@test.local')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (blocked, inserted) = block_buyer(&pool, 2, 1, Some("Tidak datang")).await.unwrap().unwrap();
+        assert!(inserted);
+        assert_eq!(blocked.buyer_id, 1);
+        assert!(is_buyer_blocked(&pool, 2, 1).await.unwrap());
+
+        // Transaksi dengan seller 2 tidak membuka blokir di seller lain
+        assert!(block_buyer(&pool, 3, 1, None).await.unwrap().is_none());
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_buyer_who_only_chatted_can_be_blocked(pool: PgPool) {
+        sqlx::query("INSERT INTO conversations (customer_id, seller_id, vehicle_id) VALUES (1, 3, NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (blocked, inserted) = block_buyer(&pool, 3, 1, Some("Spam chat")).await.unwrap().unwrap();
+        assert!(inserted);
+        assert_eq!(blocked.reason.as_deref(), Some("Spam chat"));
+        assert!(is_buyer_blocked(&pool, 3, 1).await.unwrap());
+    }
+}
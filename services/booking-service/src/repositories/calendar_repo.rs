@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{domain::calendar::CalendarEvent, error::AppError};
+
+// Gabungan test drive (tanggal request) dan rental (overlap pickup-return) milik customer
+const CALENDAR_EVENTS_CTE: &str = "WITH events AS (
+        SELECT 'test_drive' AS event_type, t.id AS booking_id,
+               t.requested_date AS starts_at, NULL::TIMESTAMPTZ AS ends_at,
+               t.requested_time, t.status, t.vehicle_id, v.title AS vehicle_title,
+               t.seller_id, COALESCE(u.business_name, u.name) AS seller_name,
+               CASE WHEN t.location = 'customer_address' THEN t.address
+                    ELSE COALESCE(t.address, v.address) END AS location,
+               CASE WHEN t.lat IS NOT NULL THEN t.lat
+                    WHEN t.location = 'showroom' THEN v.latitude::DOUBLE PRECISION END AS lat,
+               CASE WHEN t.lng IS NOT NULL THEN t.lng
+                    WHEN t.location = 'showroom' THEN v.longitude::DOUBLE PRECISION END AS lng
+        FROM testdrive_bookings t
+        JOIN vehicles v ON v.id = t.vehicle_id
+        JOIN users u ON u.id = t.seller_id
+        WHERE t.customer_id = $1 AND t.requested_date >= $2 AND t.requested_date < $3
+
+        UNION ALL
+
+        SELECT 'rental' AS event_type, r.id AS booking_id,
+               r.pickup_date AS starts_at, r.return_date AS ends_at,
+               NULL::VARCHAR AS requested_time, r.status, r.vehicle_id, v.title AS vehicle_title,
+               r.seller_id, COALESCE(u.business_name, u.name) AS seller_name,
+               NULL::TEXT AS location, NULL::DOUBLE PRECISION AS lat, NULL::DOUBLE PRECISION AS lng
+        FROM rental_bookings r
+        JOIN vehicles v ON v.id = r.vehicle_id
+        JOIN users u ON u.id = r.seller_id
+        WHERE r.customer_id = $1 AND r.pickup_date < $3 AND r.return_date >= $2
+    )";
+
+// Ambil event calendar customer dalam window tanggal, urut kronologis
+pub async fn find_calendar_events(
+    pool: &PgPool,
+    customer_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<CalendarEvent>, AppError> {
+    let events = sqlx::query_as(&format!(
+        "{} SELECT * FROM events
+         ORDER BY starts_at ASC, event_type ASC, booking_id ASC
+         LIMIT $4 OFFSET $5",
+        CALENDAR_EVENTS_CTE
+    ))
+    .bind(customer_id)
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+// Hitung total event calendar customer dalam window tanggal
+pub async fn count_calendar_events(
+    pool: &PgPool,
+    customer_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<i64, AppError> {
+    let (total,): (i64,) = sqlx::query_as(&format!(
+        "{} SELECT COUNT(*) FROM events",
+        CALENDAR_EVENTS_CTE
+    ))
+    .bind(customer_id)
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total)
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use sqlx::{types::JsonValue, PgPool};
+
+use crate::{domain::document_checklist::ChecklistItem, error::AppError};
+
+// Ambil checklist dokumen seller, None jika seller belum mengatur checklist
+pub async fn find_checklist(
+    pool: &PgPool,
+    seller_id: i32,
+) -> Result<Option<(Vec<ChecklistItem>, DateTime<Utc>)>, AppError> {
+    let row: Option<(JsonValue, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT items, updated_at FROM seller_document_checklists WHERE seller_id = $1"
+    )
+    .bind(seller_id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|(items, updated_at)| {
+        serde_json::from_value(items)
+            .map(|items| (items, updated_at))
+            .map_err(|e| AppError::internal(format!("Checklist dokumen seller {} rusak: {}", seller_id, e)))
+    })
+    .transpose()
+}
+
+// Simpan (ganti) checklist dokumen seller
+pub async fn upsert_checklist(
+    pool: &PgPool,
+    seller_id: i32,
+    items: &[ChecklistItem],
+) -> Result<DateTime<Utc>, AppError> {
+    let items: JsonValue = serde_json::to_value(items)
+        .map_err(|e| AppError::internal(format!("Gagal serialize checklist dokumen: {}", e)))?;
+
+    let updated_at = sqlx::query_scalar(
+        "INSERT INTO seller_document_checklists (seller_id, items, updated_at)
+         VALUES ($1, $2, NOW())
+         ON CONFLICT (seller_id) DO UPDATE SET items = EXCLUDED.items, updated_at = NOW()
+         RETURNING updated_at"
+    )
+    .bind(seller_id)
+    .bind(items)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(updated_at)
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use sqlx::types::JsonValue;
+
+use crate::{
+    domain::{
+        handover::{HandoverKind, HandoverRequest, RentalHandover},
+        rental::RentalBooking,
+    },
+    error::AppError,
+    repositories::rental_repo::RENTAL_BOOKING_COLUMNS,
+};
+
+const HANDOVER_COLUMNS: &str = "id, rental_booking_id, kind, odometer_km, fuel_level, photos, notes,
+    recorded_by, recorded_at, late_fee::BIGINT as late_fee, created_at";
+
+// Ambil catatan check-in / check-out rental
+pub async fn find_handover(
+    pool: &PgPool,
+    rental_booking_id: i32,
+    kind: HandoverKind,
+) -> Result<Option<RentalHandover>, AppError> {
+    let handover = sqlx::query_as(&format!(
+        "SELECT {} FROM rental_handovers WHERE rental_booking_id = $1 AND kind = $2",
+        HANDOVER_COLUMNS
+    ))
+    .bind(rental_booking_id)
+    .bind(kind.as_str())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(handover)
+}
+
+// Simpan serah terima dan pindahkan status rental dalam satu transaksi
+pub async fn record_handover(
+    pool: &PgPool,
+    rental: &RentalBooking,
+    kind: HandoverKind,
+    payload: &HandoverRequest,
+    recorded_by: i32,
+    recorded_at: DateTime<Utc>,
+    late_fee: i64,
+) -> Result<(RentalHandover, RentalBooking), AppError> {
+    let photos: JsonValue = serde_json::to_value(&payload.photos)
+        .map_err(|_| AppError::internal("Invalid photos format"))?;
+
+    // Status dicek ulang di WHERE, waktu pickup/return dari validate-pickup/return dipertahankan;
+    // catatan ganda dari request bersamaan ditolak ON CONFLICT di bawah
+    let update_status = match kind {
+        HandoverKind::CheckIn => format!(
+            "UPDATE rental_bookings
+             SET status = 'berjalan', actual_pickup_at = COALESCE(actual_pickup_at, $2), updated_at = NOW()
+             WHERE id = $1 AND status IN ('paid', 'akan_datang', 'berjalan')
+             RETURNING {}",
+            RENTAL_BOOKING_COLUMNS
+        ),
+        HandoverKind::CheckOut => format!(
+            "UPDATE rental_bookings
+             SET status = 'selesai', actual_return_at = COALESCE(actual_return_at, $2), updated_at = NOW()
+             WHERE id = $1 AND status IN ('berjalan', 'selesai')
+             RETURNING {}",
+            RENTAL_BOOKING_COLUMNS
+        ),
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let updated: RentalBooking = sqlx::query_as(&update_status)
+        .bind(rental.id)
+        .bind(recorded_at)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::conflict("Status rental sudah berubah, muat ulang booking"))?;
+
+    // ON CONFLICT: satu rental hanya punya satu check-in dan satu check-out
+    let handover: Option<RentalHandover> = sqlx::query_as(&format!(
+        "INSERT INTO rental_handovers (
+            rental_booking_id, kind, odometer_km, fuel_level, photos, notes,
+            recorded_by, recorded_at, late_fee
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (rental_booking_id, kind) DO NOTHING
+        RETURNING {}",
+        HANDOVER_COLUMNS
+    ))
+    .bind(rental.id)
+    .bind(kind.as_str())
+    .bind(payload.odometer_km)
+    .bind(payload.fuel_level)
+    .bind(photos)
+    .bind(payload.notes.as_deref().map(str::trim).filter(|notes| !notes.is_empty()))
+    .bind(recorded_by)
+    .bind(recorded_at)
+    .bind(late_fee)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let handover = handover
+        .ok_or_else(|| AppError::conflict(format!("{} rental ini sudah dicatat", kind.as_str())))?;
+
+    tx.commit().await?;
+
+    Ok((handover, updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::rental_repo;
+    use crate::utils::handover;
+
+    fn handover(odometer_km: i32) -> HandoverRequest {
+        HandoverRequest {
+            odometer_km,
+            fuel_level: 80,
+            photos: vec!["https://res.cloudinary.com/bigauto/image/upload/v1/handover/front.jpg".to_string()],
+            notes: Some("  ".to_string()),
+        }
+    }
+
+    // Rental paid milik seller 2, kembali 3 jam yang lalu supaya check-out kena denda
+    async fn paid_rental(pool: &PgPool) -> RentalBooking {
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO rental_bookings (
+                vehicle_id, customer_id, seller_id, order_id, pickup_date, return_date,
+                customer_name, customer_phone, customer_email, total_days, price_per_day, total_price, status
+            ) VALUES (
+                1, 1, 2, 'RENT-HANDOVER-1', NOW() - INTERVAL '2 days', NOW() - INTERVAL '3 hours',
+                'Customer Test', '081200000001', 'customer@test.local', 2, 500000, 1000000, 'paid'
+            ) RETURNING id"
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        rental_repo::find_rental_by_id(pool, id).await.unwrap().unwrap()
+    }
+
+    // Alur lengkap: validate-pickup -> check-in -> check-out, serah terima hanya tercatat sekali
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_full_handover_lifecycle(pool: PgPool) {
+        let rental = paid_rental(&pool).await;
+
+        // Validate-pickup menyimpan KTP dan langsung menjalankan rental
+        let picked_up = rental_repo::validate_pickup(&pool, rental.id, "https://res.cloudinary.com/bigauto/image/upload/v1/ktp.jpg")
+            .await
+            .unwrap();
+        assert_eq!(picked_up.status, "berjalan");
+        let pickup_at = picked_up.actual_pickup_at.unwrap();
+
+        // Check-in tetap dicatat untuk rental yang sudah berjalan, waktu pickup tidak ditimpa
+        let (checkin, updated) = record_handover(&pool, &picked_up, HandoverKind::CheckIn, &handover(45_000), 2, Utc::now(), 0)
+            .await
+            .unwrap();
+        assert_eq!(updated.status, "berjalan");
+        assert_eq!(updated.actual_pickup_at, Some(pickup_at));
+        assert_eq!(checkin.odometer_km, 45_000);
+        assert_eq!(checkin.notes, None);
+
+        let duplicate = record_handover(&pool, &updated, HandoverKind::CheckIn, &handover(45_010), 2, Utc::now(), 0).await;
+        assert!(matches!(duplicate, Err(AppError::Conflict(_))));
+
+        // Check-out: odometer tidak boleh mundur dari check-in, denda terlambat ikut tersimpan
+        let stored = find_handover(&pool, rental.id, HandoverKind::CheckIn).await.unwrap().unwrap();
+        assert!(handover::validate_handover(&handover(44_999), Some(stored.odometer_km)).is_err());
+
+        let returned_at = Utc::now();
+        let late_fee = handover::late_fee(updated.price_per_day, updated.return_date, returned_at);
+        assert_eq!(late_fee, 500_000);
+
+        let (checkout, completed) = record_handover(&pool, &updated, HandoverKind::CheckOut, &handover(45_320), 2, returned_at, late_fee)
+            .await
+            .unwrap();
+        assert_eq!(completed.status, "selesai");
+        assert!(completed.actual_return_at.is_some());
+        assert_eq!(checkout.late_fee, 500_000);
+
+        let duplicate = record_handover(&pool, &completed, HandoverKind::CheckOut, &handover(45_400), 2, Utc::now(), 0).await;
+        assert!(matches!(duplicate, Err(AppError::Conflict(_))));
+    }
+
+    // Rental yang diselesaikan lewat validate-return masih bisa dicatat check-out-nya, yang dibatalkan tidak
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_checkout_after_validate_return(pool: PgPool) {
+        let rental = paid_rental(&pool).await;
+        rental_repo::validate_pickup(&pool, rental.id, "https://res.cloudinary.com/bigauto/image/upload/v1/ktp.jpg")
+            .await
+            .unwrap();
+        let returned = rental_repo::validate_return(&pool, rental.id).await.unwrap();
+        assert_eq!(returned.status, "selesai");
+
+        let (_, completed) = record_handover(&pool, &returned, HandoverKind::CheckOut, &handover(45_320), 2, Utc::now(), 0)
+            .await
+            .unwrap();
+        assert_eq!(completed.status, "selesai");
+        assert_eq!(completed.actual_return_at, returned.actual_return_at);
+
+        sqlx::query("UPDATE rental_bookings SET status = 'cancelled' WHERE id = $1")
+            .bind(rental.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let cancelled = record_handover(&pool, &returned, HandoverKind::CheckIn, &handover(45_000), 2, Utc::now(), 0).await;
+        assert!(matches!(cancelled, Err(AppError::Conflict(_))));
+    }
+}
@@ -0,0 +1,86 @@
+use sqlx::PgPool;
+
+use crate::{
+    domain::invoice::{InvoiceDetails, NewSaleInvoice, SaleInvoice},
+    error::AppError,
+};
+
+const INVOICE_COLUMNS: &str = "id, sale_order_id, invoice_number,
+    subtotal::FLOAT8 as subtotal, tax_percentage::FLOAT8 as tax_percentage,
+    tax_amount::FLOAT8 as tax_amount, total_amount::FLOAT8 as total_amount,
+    pdf_data, issued_at";
+
+// Ambil invoice by sale order ID
+pub async fn find_invoice_by_sale_order(
+    pool: &PgPool,
+    sale_order_id: i32,
+) -> Result<Option<SaleInvoice>, AppError> {
+    let invoice = sqlx::query_as(&format!(
+        "SELECT {} FROM sale_invoices WHERE sale_order_id = $1",
+        INVOICE_COLUMNS
+    ))
+    .bind(sale_order_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(invoice)
+}
+
+// Ambil nomor urut invoice berikutnya dari sequence
+pub async fn next_invoice_sequence(pool: &PgPool) -> Result<i64, AppError> {
+    let (sequence,): (i64,) = sqlx::query_as("SELECT nextval('sale_invoice_number_seq')")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(sequence)
+}
+
+// Ambil data kendaraan & seller untuk dicetak di invoice
+pub async fn find_invoice_details(
+    pool: &PgPool,
+    sale_order_id: i32,
+) -> Result<InvoiceDetails, AppError> {
+    let details = sqlx::query_as(
+        "SELECT
+            v.title as vehicle_title, v.brand as vehicle_brand,
+            v.model as vehicle_model, v.year as vehicle_year,
+            u.name as seller_name, u.business_name as seller_business_name,
+            u.email as seller_email, u.phone as seller_phone, u.address as seller_address
+         FROM sale_orders so
+         JOIN vehicles v ON v.id = so.vehicle_id
+         JOIN users u ON u.id = so.seller_id
+         WHERE so.id = $1"
+    )
+    .bind(sale_order_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(details)
+}
+
+// Simpan invoice, return None jika sale order sudah punya invoice (request paralel)
+pub async fn insert_invoice(
+    pool: &PgPool,
+    invoice: &NewSaleInvoice,
+) -> Result<Option<SaleInvoice>, AppError> {
+    let saved = sqlx::query_as(&format!(
+        "INSERT INTO sale_invoices (
+            sale_order_id, invoice_number, subtotal, tax_percentage,
+            tax_amount, total_amount, pdf_data
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (sale_order_id) DO NOTHING
+        RETURNING {}",
+        INVOICE_COLUMNS
+    ))
+    .bind(invoice.sale_order_id)
+    .bind(&invoice.invoice_number)
+    .bind(invoice.subtotal)
+    .bind(invoice.tax_percentage)
+    .bind(invoice.tax_amount)
+    .bind(invoice.total_amount)
+    .bind(&invoice.pdf_data)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(saved)
+}
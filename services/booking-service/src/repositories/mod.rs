@@ -1,3 +1,10 @@
 pub mod rental_repo;
 pub mod testdrive_repo;
 pub mod sale_repo;
+pub mod invoice_repo;
+pub mod calendar_repo;
+pub mod return_report_repo;
+pub mod webhook_repo;
+pub mod buyer_block_repo;
+pub mod document_checklist_repo;
+pub mod handover_repo;
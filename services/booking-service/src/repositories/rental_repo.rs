@@ -2,10 +2,17 @@ use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
 use crate::{
-    domain::rental::{RentalBooking, CreateRentalRequest, RentalStatus},
+    domain::rental::{RentalBooking, CreateRentalRequest, RentalStatus, initial_deposit_status},
     error::AppError,
 };
 
+// Kolom NUMERIC di-cast ke FLOAT8 agar cocok dengan field f64 di RentalBooking
+pub(crate) const RENTAL_BOOKING_COLUMNS: &str = "id, vehicle_id, customer_id, seller_id, order_id, pickup_date,
+    return_date, actual_pickup_at, actual_return_at, customer_name, customer_phone, customer_email, ktp_photo,
+    total_days, price_per_day::FLOAT8 as price_per_day, total_price::FLOAT8 as total_price, notes, status,
+    cancel_reason, cancelled_at, deposit_amount::FLOAT8 as deposit_amount, deposit_status,
+    deposit_refunded_amount::FLOAT8 as deposit_refunded_amount, deposit_settled_at, created_at, updated_at";
+
 // Generate unique order ID untuk rental
 async fn generate_rental_order_id(pool: &PgPool) -> Result<String, AppError> {
     loop {
@@ -34,6 +41,7 @@ pub async fn create_rental(
     customer_id: i32,
     seller_id: i32,
     price_per_day: f64,
+    deposit_amount: f64,
     payload: &CreateRentalRequest,
 ) -> Result<RentalBooking, AppError> {
     let order_id = generate_rental_order_id(pool).await?;
@@ -45,16 +53,18 @@ pub async fn create_rental(
 
     let total_price = price_per_day * total_days as f64;
 
-    let rental = sqlx::query_as(
+    let rental = sqlx::query_as(&format!(
         "INSERT INTO rental_bookings (
             vehicle_id, customer_id, seller_id, order_id,
             pickup_date, return_date,
             customer_name, customer_phone, customer_email,
-            total_days, price_per_day, total_price, notes, status
+            total_days, price_per_day, total_price, notes, status,
+            deposit_amount, deposit_status
         ) VALUES (
-            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14
-        ) RETURNING *"
-    )
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16
+        ) RETURNING {}",
+        RENTAL_BOOKING_COLUMNS
+    ))
     .bind(payload.vehicle_id)
     .bind(customer_id)
     .bind(seller_id)
@@ -69,6 +79,8 @@ pub async fn create_rental(
     .bind(total_price)
     .bind(&payload.notes)
     .bind(RentalStatus::PendingPayment.as_str())
+    .bind(deposit_amount)
+    .bind(initial_deposit_status(deposit_amount))
     .fetch_one(pool)
     .await?;
 
@@ -80,9 +92,10 @@ pub async fn find_rental_by_id(
     pool: &PgPool,
     id: i32,
 ) -> Result<Option<RentalBooking>, AppError> {
-    let result = sqlx::query_as(
-        "SELECT * FROM rental_bookings WHERE id = $1"
-    )
+    let result = sqlx::query_as(&format!(
+        "SELECT {} FROM rental_bookings WHERE id = $1",
+        RENTAL_BOOKING_COLUMNS
+    ))
     .bind(id)
     .fetch_optional(pool)
     .await?;
@@ -97,21 +110,23 @@ pub async fn find_rentals_by_customer(
     status: Option<String>,
 ) -> Result<Vec<RentalBooking>, AppError> {
     let rentals = if let Some(status_filter) = status {
-        sqlx::query_as(
-            "SELECT * FROM rental_bookings
+        sqlx::query_as(&format!(
+            "SELECT {} FROM rental_bookings
              WHERE customer_id = $1 AND status = $2
-             ORDER BY created_at DESC"
-        )
+             ORDER BY created_at DESC",
+            RENTAL_BOOKING_COLUMNS
+        ))
         .bind(customer_id)
         .bind(status_filter)
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as(
-            "SELECT * FROM rental_bookings
+        sqlx::query_as(&format!(
+            "SELECT {} FROM rental_bookings
              WHERE customer_id = $1
-             ORDER BY created_at DESC"
-        )
+             ORDER BY created_at DESC",
+            RENTAL_BOOKING_COLUMNS
+        ))
         .bind(customer_id)
         .fetch_all(pool)
         .await?
@@ -127,21 +142,23 @@ pub async fn find_rentals_by_seller(
     status: Option<String>,
 ) -> Result<Vec<RentalBooking>, AppError> {
     let rentals = if let Some(status_filter) = status {
-        sqlx::query_as(
-            "SELECT * FROM rental_bookings
+        sqlx::query_as(&format!(
+            "SELECT {} FROM rental_bookings
              WHERE seller_id = $1 AND status = $2
-             ORDER BY created_at DESC"
-        )
+             ORDER BY created_at DESC",
+            RENTAL_BOOKING_COLUMNS
+        ))
         .bind(seller_id)
         .bind(status_filter)
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as(
-            "SELECT * FROM rental_bookings
+        sqlx::query_as(&format!(
+            "SELECT {} FROM rental_bookings
              WHERE seller_id = $1
-             ORDER BY created_at DESC"
-        )
+             ORDER BY created_at DESC",
+            RENTAL_BOOKING_COLUMNS
+        ))
         .bind(seller_id)
         .fetch_all(pool)
         .await?
@@ -156,12 +173,13 @@ pub async fn update_rental_status(
     id: i32,
     status: &str,
 ) -> Result<RentalBooking, AppError> {
-    let rental = sqlx::query_as(
+    let rental = sqlx::query_as(&format!(
         "UPDATE rental_bookings
          SET status = $1, updated_at = NOW()
          WHERE id = $2
-         RETURNING *"
-    )
+         RETURNING {}",
+        RENTAL_BOOKING_COLUMNS
+    ))
     .bind(status)
     .bind(id)
     .fetch_one(pool)
@@ -170,21 +188,40 @@ pub async fn update_rental_status(
     Ok(rental)
 }
 
+// URL KTP/SIM penyewa, hanya jika user adalah customer atau seller rental
+pub async fn find_ktp_for_party(
+    pool: &PgPool,
+    id: i32,
+    user_id: i32,
+) -> Result<Option<String>, AppError> {
+    let ktp_photo: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT ktp_photo FROM rental_bookings
+         WHERE id = $1 AND (customer_id = $2 OR seller_id = $2)"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(ktp_photo.and_then(|(url,)| url))
+}
+
 // Validate pickup (seller confirms pickup dengan KTP)
 pub async fn validate_pickup(
     pool: &PgPool,
     id: i32,
     ktp_photo: &str,
 ) -> Result<RentalBooking, AppError> {
-    let rental = sqlx::query_as(
+    let rental = sqlx::query_as(&format!(
         "UPDATE rental_bookings
          SET actual_pickup_at = NOW(),
              ktp_photo = $1,
              status = 'berjalan',
              updated_at = NOW()
          WHERE id = $2
-         RETURNING *"
-    )
+         RETURNING {}",
+        RENTAL_BOOKING_COLUMNS
+    ))
     .bind(ktp_photo)
     .bind(id)
     .fetch_one(pool)
@@ -198,14 +235,15 @@ pub async fn validate_return(
     pool: &PgPool,
     id: i32,
 ) -> Result<RentalBooking, AppError> {
-    let rental = sqlx::query_as(
+    let rental = sqlx::query_as(&format!(
         "UPDATE rental_bookings
          SET actual_return_at = NOW(),
              status = 'selesai',
              updated_at = NOW()
-         WHERE id = $2
-         RETURNING *"
-    )
+         WHERE id = $1
+         RETURNING {}",
+        RENTAL_BOOKING_COLUMNS
+    ))
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -219,15 +257,16 @@ pub async fn cancel_rental(
     id: i32,
     cancel_reason: &str,
 ) -> Result<RentalBooking, AppError> {
-    let rental = sqlx::query_as(
+    let rental = sqlx::query_as(&format!(
         "UPDATE rental_bookings
          SET status = 'cancelled',
              cancel_reason = $1,
              cancelled_at = NOW(),
              updated_at = NOW()
          WHERE id = $2
-         RETURNING *"
-    )
+         RETURNING {}",
+        RENTAL_BOOKING_COLUMNS
+    ))
     .bind(cancel_reason)
     .bind(id)
     .fetch_one(pool)
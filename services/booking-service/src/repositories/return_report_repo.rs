@@ -0,0 +1,95 @@
+use sqlx::PgPool;
+use sqlx::types::JsonValue;
+
+use crate::{
+    domain::{
+        rental::RentalBooking,
+        return_report::{CreateReturnReportRequest, DamageChargeStatus, RentalReturnReport},
+    },
+    error::AppError,
+};
+
+const REPORT_COLUMNS: &str = "id, rental_booking_id, seller_id, customer_id,
+    condition_notes, odometer_km, fuel_level, damage_items,
+    damage_total::BIGINT as damage_total, charge_status, charge_paid_at,
+    created_at, updated_at";
+
+// Ambil laporan pengembalian by rental booking
+pub async fn find_report_by_rental(
+    pool: &PgPool,
+    rental_booking_id: i32,
+) -> Result<Option<RentalReturnReport>, AppError> {
+    let report = sqlx::query_as(&format!(
+        "SELECT {} FROM rental_return_reports WHERE rental_booking_id = $1",
+        REPORT_COLUMNS
+    ))
+    .bind(rental_booking_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(report)
+}
+
+// Simpan laporan pengembalian dan notifikasi customer jika ada tagihan kerusakan
+pub async fn create_report(
+    pool: &PgPool,
+    rental: &RentalBooking,
+    payload: &CreateReturnReportRequest,
+    damage_total: i64,
+) -> Result<RentalReturnReport, AppError> {
+    let damage_items: JsonValue = serde_json::to_value(&payload.damage_items)
+        .map_err(|_| AppError::internal("Invalid damage items format"))?;
+
+    let charge_status = if damage_total > 0 {
+        DamageChargeStatus::Pending
+    } else {
+        DamageChargeStatus::None
+    };
+
+    let mut tx = pool.begin().await?;
+
+    // ON CONFLICT: satu rental hanya punya satu laporan
+    let report: Option<RentalReturnReport> = sqlx::query_as(&format!(
+        "INSERT INTO rental_return_reports (
+            rental_booking_id, seller_id, customer_id,
+            condition_notes, odometer_km, fuel_level,
+            damage_items, damage_total, charge_status
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (rental_booking_id) DO NOTHING
+        RETURNING {}",
+        REPORT_COLUMNS
+    ))
+    .bind(rental.id)
+    .bind(rental.seller_id)
+    .bind(rental.customer_id)
+    .bind(payload.condition_notes.trim())
+    .bind(payload.odometer_km)
+    .bind(payload.fuel_level)
+    .bind(damage_items)
+    .bind(damage_total)
+    .bind(charge_status.as_str())
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let report = report
+        .ok_or_else(|| AppError::conflict("Laporan pengembalian untuk rental ini sudah dibuat"))?;
+
+    if charge_status == DamageChargeStatus::Pending {
+        sqlx::query(
+            "INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+             VALUES ($1, 'rental_damage_charge', 'Tagihan kerusakan rental',
+                     'Seller mencatat kerusakan untuk rental ' || $2 || ' sebesar Rp ' || $3 || '. Lihat laporan pengembalian untuk detail',
+                     $4, 'rental_booking')"
+        )
+        .bind(rental.customer_id)
+        .bind(&rental.order_id)
+        .bind(damage_total.to_string())
+        .bind(rental.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(report)
+}
@@ -1,10 +1,23 @@
-use sqlx::PgPool;
+use sqlx::{types::JsonValue, PgPool, Postgres, QueryBuilder};
 
 use crate::{
-    domain::sale::{SaleOrder, CreateSaleOrderRequest, SaleStatus},
+    domain::concurrency::ensure_applied,
+    domain::document_checklist::DocumentItem,
+    domain::sale::{
+        SaleOrder, CreateSaleOrderRequest, SaleStatus, SaleOrderQueryParams, sale_order_sort_clause,
+    },
     error::AppError,
+    utils::order_tracking,
 };
 
+// Kolom NUMERIC di-cast ke FLOAT8 agar cocok dengan field f64 di SaleOrder
+const SALE_ORDER_COLUMNS: &str = "id, vehicle_id, buyer_id, seller_id, testdrive_booking_id, order_id,
+    tracking_reference, asking_price::FLOAT8 as asking_price, offer_price::FLOAT8 as offer_price,
+    counter_offer_price::FLOAT8 as counter_offer_price, counter_round, final_price::FLOAT8 as final_price,
+    buyer_name, buyer_phone, buyer_email, buyer_address, buyer_ktp_photo, status, document_checklist,
+    created_at, confirmed_at, paid_at, document_transfer_started_at, completed_at, cancelled_at,
+    updated_at, cancel_reason, reject_reason, rejected_at, buyer_notes, seller_notes, version";
+
 // Generate unique order ID untuk sale
 async fn generate_sale_order_id(pool: &PgPool) -> Result<String, AppError> {
     loop {
@@ -34,21 +47,24 @@ pub async fn create_sale_order(
     seller_id: i32,
     asking_price: f64,
     payload: &CreateSaleOrderRequest,
+    document_checklist: &[DocumentItem],
 ) -> Result<SaleOrder, AppError> {
     let order_id = generate_sale_order_id(pool).await?;
+    let document_checklist = checklist_value(document_checklist)?;
 
     let final_price = payload.offer_price.unwrap_or(asking_price);
 
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "INSERT INTO sale_orders (
             vehicle_id, buyer_id, seller_id, testdrive_booking_id,
             order_id, asking_price, offer_price, final_price,
             buyer_name, buyer_phone, buyer_email, buyer_address, buyer_notes,
-            status
+            status, tracking_reference, document_checklist
         ) VALUES (
-            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14
-        ) RETURNING *"
-    )
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16
+        ) RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
     .bind(payload.vehicle_id)
     .bind(buyer_id)
     .bind(seller_id)
@@ -63,6 +79,8 @@ pub async fn create_sale_order(
     .bind(&payload.buyer_address)
     .bind(&payload.buyer_notes)
     .bind(SaleStatus::PendingConfirmation.as_str())
+    .bind(order_tracking::generate_reference())
+    .bind(document_checklist)
     .fetch_one(pool)
     .await?;
 
@@ -74,9 +92,10 @@ pub async fn find_sale_order_by_id(
     pool: &PgPool,
     id: i32,
 ) -> Result<Option<SaleOrder>, AppError> {
-    let result = sqlx::query_as(
-        "SELECT * FROM sale_orders WHERE id = $1"
-    )
+    let result = sqlx::query_as(&format!(
+        "SELECT {} FROM sale_orders WHERE id = $1",
+        SALE_ORDER_COLUMNS
+    ))
     .bind(id)
     .fetch_optional(pool)
     .await?;
@@ -84,25 +103,38 @@ pub async fn find_sale_order_by_id(
     Ok(result)
 }
 
+// Ambil sale order by kode tracking publik (sudah dinormalisasi)
+pub async fn find_sale_order_by_tracking_reference(
+    pool: &PgPool,
+    reference: &str,
+) -> Result<Option<SaleOrder>, AppError> {
+    let result = sqlx::query_as(&format!(
+        "SELECT {} FROM sale_orders WHERE tracking_reference = $1",
+        SALE_ORDER_COLUMNS
+    ))
+    .bind(reference)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
 // Ambil sale orders by buyer dengan pagination
 pub async fn find_sale_orders_by_buyer(
     pool: &PgPool,
     buyer_id: i32,
     status: Option<String>,
-    page: Option<i32>,
-    limit: Option<i32>,
+    limit: i64,
+    offset: i64,
 ) -> Result<Vec<SaleOrder>, AppError> {
-    let page = page.unwrap_or(1).max(1);
-    let limit = limit.unwrap_or(10).min(100);
-    let offset = (page - 1) * limit;
-
     let orders = if let Some(status_filter) = status {
-        sqlx::query_as(
-            "SELECT * FROM sale_orders
+        sqlx::query_as(&format!(
+            "SELECT {} FROM sale_orders
              WHERE buyer_id = $1 AND status = $2
              ORDER BY created_at DESC
-             LIMIT $3 OFFSET $4"
-        )
+             LIMIT $3 OFFSET $4",
+            SALE_ORDER_COLUMNS
+        ))
         .bind(buyer_id)
         .bind(status_filter)
         .bind(limit)
@@ -110,12 +142,13 @@ pub async fn find_sale_orders_by_buyer(
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as(
-            "SELECT * FROM sale_orders
+        sqlx::query_as(&format!(
+            "SELECT {} FROM sale_orders
              WHERE buyer_id = $1
              ORDER BY created_at DESC
-             LIMIT $2 OFFSET $3"
-        )
+             LIMIT $2 OFFSET $3",
+            SALE_ORDER_COLUMNS
+        ))
         .bind(buyer_id)
         .bind(limit)
         .bind(offset)
@@ -126,279 +159,602 @@ pub async fn find_sale_orders_by_buyer(
     Ok(orders)
 }
 
-// Ambil sale orders by seller dengan pagination
+// Ambil sale orders by seller dengan filter, sort whitelist, dan pagination
 pub async fn find_sale_orders_by_seller(
     pool: &PgPool,
     seller_id: i32,
-    status: Option<String>,
-    page: Option<i32>,
-    limit: Option<i32>,
-) -> Result<Vec<SaleOrder>, AppError> {
-    let page = page.unwrap_or(1).max(1);
-    let limit = limit.unwrap_or(10).min(100);
-    let offset = (page - 1) * limit;
+    params: &SaleOrderQueryParams,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<SaleOrder>, i64), AppError> {
+    let sort_clause = sale_order_sort_clause(params.sort.as_deref()).ok_or_else(|| {
+        AppError::bad_request("Sort tidak valid. Gunakan: newest, oldest, price_asc, price_desc, buyer_name, status")
+    })?;
+
+    let mut count_query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM sale_orders");
+    push_seller_filters(&mut count_query, seller_id, params);
+    let (total,): (i64,) = count_query.build_query_as().fetch_one(pool).await?;
+
+    let mut query = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM sale_orders", SALE_ORDER_COLUMNS));
+    push_seller_filters(&mut query, seller_id, params);
+    query.push(" ORDER BY ").push(sort_clause);
+    query.push(" LIMIT ").push_bind(limit);
+    query.push(" OFFSET ").push_bind(offset);
+
+    let orders = query.build_query_as().fetch_all(pool).await?;
+
+    Ok((orders, total))
+}
 
-    let orders = if let Some(status_filter) = status {
-        sqlx::query_as(
-            "SELECT * FROM sale_orders
-             WHERE seller_id = $1 AND status = $2
-             ORDER BY created_at DESC
-             LIMIT $3 OFFSET $4"
-        )
-        .bind(seller_id)
-        .bind(status_filter)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
-    } else {
-        sqlx::query_as(
-            "SELECT * FROM sale_orders
-             WHERE seller_id = $1
-             ORDER BY created_at DESC
-             LIMIT $2 OFFSET $3"
-        )
-        .bind(seller_id)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
-    };
+// Tambahkan WHERE clause filter seller (semua value sebagai bind parameter)
+fn push_seller_filters(query: &mut QueryBuilder<'_, Postgres>, seller_id: i32, params: &SaleOrderQueryParams) {
+    query.push(" WHERE seller_id = ").push_bind(seller_id);
 
-    Ok(orders)
+    if let Some(status) = &params.status {
+        query.push(" AND status = ").push_bind(status.clone());
+    }
+
+    if let Some(vehicle_id) = params.vehicle_id {
+        query.push(" AND vehicle_id = ").push_bind(vehicle_id);
+    }
+
+    if let Some(buyer_name) = params.buyer_name.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        query.push(" AND buyer_name ILIKE ").push_bind(like_pattern(buyer_name));
+    }
+
+    if let Some(date_from) = params.date_from {
+        query.push(" AND created_at >= ").push_bind(date_from);
+    }
+
+    if let Some(date_to) = params.date_to {
+        query.push(" AND created_at < ").push_bind(date_to);
+    }
+
+    if let Some(min_price) = params.min_price {
+        query.push(" AND final_price >= ").push_bind(min_price);
+    }
+
+    if let Some(max_price) = params.max_price {
+        query.push(" AND final_price <= ").push_bind(max_price);
+    }
+
+    // Free-text: nama buyer, order_id, atau ID numerik order
+    if let Some(q) = params.q.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        let pattern = like_pattern(q);
+        query.push(" AND (buyer_name ILIKE ").push_bind(pattern.clone());
+        query.push(" OR order_id ILIKE ").push_bind(pattern);
+        if let Ok(id) = q.parse::<i32>() {
+            query.push(" OR id = ").push_bind(id);
+        }
+        query.push(")");
+    }
+}
+
+// Pattern ILIKE contains dengan escape wildcard dari input user
+fn like_pattern(input: &str) -> String {
+    let escaped = input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
 }
 
 // Seller confirm sale order (accept atau counter offer)
 pub async fn confirm_sale_order(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
     counter_offer_price: Option<f64>,
     seller_notes: Option<String>,
+    max_counter_rounds: i32,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = if let Some(counter_price) = counter_offer_price {
-        // Seller melakukan counter offer
-        sqlx::query_as(
+    let sale_order: Option<SaleOrder> = if let Some(counter_price) = counter_offer_price {
+        // Seller melakukan counter offer, guard batas ronde tetap di query
+        sqlx::query_as(&format!(
             "UPDATE sale_orders
              SET status = $4,
                  counter_offer_price = $1,
+                 counter_round = counter_round + 1,
                  seller_notes = $2,
                  confirmed_at = NOW(),
-                 updated_at = NOW()
-             WHERE id = $3
-             RETURNING *"
-        )
+                 updated_at = NOW(),
+                 version = version + 1
+             WHERE id = $3 AND status = $6 AND version = $7 AND counter_round < $5
+             RETURNING {}",
+            SALE_ORDER_COLUMNS
+        ))
         .bind(counter_price)
         .bind(seller_notes)
-        .bind(id)
+        .bind(current.id)
         .bind(SaleStatus::PendingConfirmation.as_str())
-        .fetch_one(pool)
+        .bind(max_counter_rounds)
+        .bind(&current.status)
+        .bind(current.version)
+        .fetch_optional(pool)
         .await?
     } else {
         // Seller langsung accept
-        sqlx::query_as(
+        sqlx::query_as(&format!(
             "UPDATE sale_orders
              SET status = $3,
                  seller_notes = $1,
                  confirmed_at = NOW(),
-                 updated_at = NOW()
-             WHERE id = $2
-             RETURNING *"
-        )
+                 updated_at = NOW(),
+                 version = version + 1
+             WHERE id = $2 AND status = $4 AND version = $5
+             RETURNING {}",
+            SALE_ORDER_COLUMNS
+        ))
         .bind(seller_notes)
-        .bind(id)
+        .bind(current.id)
         .bind(SaleStatus::PendingPayment.as_str())
-        .fetch_one(pool)
+        .bind(&current.status)
+        .bind(current.version)
+        .fetch_optional(pool)
         .await?
     };
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
 }
 
 // Buyer accept counter offer
 pub async fn accept_counter_offer(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order: SaleOrder = sqlx::query_as("SELECT * FROM sale_orders WHERE id = $1")
-        .bind(id)
-        .fetch_one(pool)
-        .await?;
-
-    if sale_order.counter_offer_price.is_none() {
-        return Err(AppError::bad_request("Tidak ada counter offer"));
-    }
-
-    let updated = sqlx::query_as(
+    let updated = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET status = 'pending_payment',
+         SET status = $2,
              final_price = counter_offer_price,
-             updated_at = NOW()
-         WHERE id = $1
-         RETURNING *"
-    )
-    .bind(id)
-    .fetch_one(pool)
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $1 AND status = $3 AND version = $4 AND counter_offer_price IS NOT NULL
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
+    .bind(current.id)
+    .bind(SaleStatus::PendingPayment.as_str())
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(updated)
+    ensure_applied(updated, "Sale order")
 }
 
 // Reject sale order (seller)
 pub async fn reject_sale_order(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
     reject_reason: &str,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET status = 'rejected',
+         SET status = $3,
              reject_reason = $1,
              rejected_at = NOW(),
-             updated_at = NOW()
-         WHERE id = $2
-         RETURNING *"
-    )
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $2 AND status = $4 AND version = $5
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
     .bind(reject_reason)
-    .bind(id)
-    .fetch_one(pool)
+    .bind(current.id)
+    .bind(SaleStatus::Rejected.as_str())
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
 }
 
 // Cancel sale order (buyer)
 pub async fn cancel_sale_order(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
     cancel_reason: &str,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET status = 'cancelled',
+         SET status = $3,
              cancel_reason = $1,
              cancelled_at = NOW(),
-             updated_at = NOW()
-         WHERE id = $2
-         RETURNING *"
-    )
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $2 AND status = $4 AND version = $5
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
     .bind(cancel_reason)
-    .bind(id)
-    .fetch_one(pool)
+    .bind(current.id)
+    .bind(SaleStatus::Cancelled.as_str())
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
 }
 
 // Upload KTP (buyer)
 pub async fn upload_ktp(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
     ktp_photo: &str,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
          SET buyer_ktp_photo = $1,
-             updated_at = NOW()
-         WHERE id = $2
-         RETURNING *"
-    )
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $2 AND version = $3
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
     .bind(ktp_photo)
+    .bind(current.id)
+    .bind(current.version)
+    .fetch_optional(pool)
+    .await?;
+
+    ensure_applied(sale_order, "Sale order")
+}
+
+// URL KTP pembeli, hanya jika user adalah buyer atau seller order
+pub async fn find_ktp_for_party(
+    pool: &PgPool,
+    id: i32,
+    user_id: i32,
+) -> Result<Option<String>, AppError> {
+    let ktp_photo: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT buyer_ktp_photo FROM sale_orders
+         WHERE id = $1 AND (buyer_id = $2 OR seller_id = $2)"
+    )
     .bind(id)
-    .fetch_one(pool)
+    .bind(user_id)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    Ok(ktp_photo.and_then(|(url,)| url))
 }
 
 // Update sale order status to paid
 pub async fn mark_as_paid(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET status = 'paid',
+         SET status = $2,
              paid_at = NOW(),
-             updated_at = NOW()
-         WHERE id = $1
-         RETURNING *"
-    )
-    .bind(id)
-    .fetch_one(pool)
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $1 AND status = $3 AND version = $4
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
+    .bind(current.id)
+    .bind(SaleStatus::Paid.as_str())
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
 }
 
 // Start document transfer (seller)
 pub async fn start_document_transfer(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET status = 'document_processing',
+         SET status = $2,
              document_transfer_started_at = NOW(),
-             updated_at = NOW()
-         WHERE id = $1
-         RETURNING *"
-    )
-    .bind(id)
-    .fetch_one(pool)
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $1 AND status = $3 AND version = $4
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
+    .bind(current.id)
+    .bind(SaleStatus::DocumentProcessing.as_str())
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
+}
+
+fn checklist_value(items: &[DocumentItem]) -> Result<JsonValue, AppError> {
+    serde_json::to_value(items)
+        .map_err(|e| AppError::internal(format!("Gagal serialize checklist dokumen: {}", e)))
 }
 
-// Update document transfer status (seller)
+// Update document transfer status (seller), checklist sudah divalidasi di handler
 pub async fn update_document_status(
     pool: &PgPool,
-    id: i32,
-    bpkb_transferred: Option<bool>,
-    stnk_transferred: Option<bool>,
-    faktur_transferred: Option<bool>,
-    pajak_transferred: Option<bool>,
+    current: &SaleOrder,
+    document_checklist: &[DocumentItem],
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET bpkb_transferred = COALESCE($1, bpkb_transferred),
-             stnk_transferred = COALESCE($2, stnk_transferred),
-             faktur_transferred = COALESCE($3, faktur_transferred),
-             pajak_transferred = COALESCE($4, pajak_transferred),
-             updated_at = NOW()
-         WHERE id = $5
-         RETURNING *"
-    )
-    .bind(bpkb_transferred)
-    .bind(stnk_transferred)
-    .bind(faktur_transferred)
-    .bind(pajak_transferred)
-    .bind(id)
-    .fetch_one(pool)
+         SET document_checklist = $1,
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $2 AND status = $3 AND version = $4
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
+    .bind(checklist_value(document_checklist)?)
+    .bind(current.id)
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
 }
 
 // Complete sale order (seller)
 pub async fn complete_sale_order(
     pool: &PgPool,
-    id: i32,
+    current: &SaleOrder,
     seller_notes: Option<String>,
 ) -> Result<SaleOrder, AppError> {
-    let sale_order = sqlx::query_as(
+    let sale_order = sqlx::query_as(&format!(
         "UPDATE sale_orders
-         SET status = 'completed',
+         SET status = $3,
              seller_notes = COALESCE($1, seller_notes),
              completed_at = NOW(),
-             updated_at = NOW()
-         WHERE id = $2
-         RETURNING *"
-    )
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $2 AND status = $4 AND version = $5
+         RETURNING {}",
+        SALE_ORDER_COLUMNS
+    ))
     .bind(seller_notes)
-    .bind(id)
-    .fetch_one(pool)
+    .bind(current.id)
+    .bind(SaleStatus::Completed.as_str())
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sale_order)
+    ensure_applied(sale_order, "Sale order")
+}
+
+// Proses event sale order lunas dari payment-service (sale_payment_events): pending_payment -> paid.
+// Order yang sudah tidak pending_payment hanya menandai event selesai. Return jumlah order yang ditandai paid
+pub async fn apply_payment_events(pool: &PgPool, limit: i64) -> Result<u64, AppError> {
+    let events: Vec<(i64, i32)> = sqlx::query_as(
+        "SELECT id, sale_order_id FROM sale_payment_events
+         WHERE processed_at IS NULL
+         ORDER BY id
+         LIMIT $1"
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    let mut paid = 0;
+    for (event_id, sale_order_id) in events {
+        if let Some(sale_order) = find_sale_order_by_id(pool, sale_order_id).await? {
+            if sale_order.status == SaleStatus::PendingPayment.as_str() {
+                match mark_as_paid(pool, &sale_order).await {
+                    Ok(_) => paid += 1,
+                    // Order berubah sejak dibaca, event dicek ulang di tick berikutnya
+                    Err(AppError::Conflict(_)) => continue,
+                    Err(e) => return Err(e),
+                }
+            } else {
+                tracing::warn!(
+                    "Payment sale order {} diterima saat status {}, status tidak diubah",
+                    sale_order_id, sale_order.status
+                );
+            }
+        }
+
+        sqlx::query("UPDATE sale_payment_events SET processed_at = NOW() WHERE id = $1")
+            .bind(event_id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(paid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_pending_order(pool: &PgPool, id: i32) -> SaleOrder {
+        sqlx::query(
+            "INSERT INTO sale_orders (id, vehicle_id, buyer_id, seller_id, order_id, tracking_reference,
+                                      asking_price, final_price, buyer_name, buyer_phone, buyer_email)
+             VALUES ($1, 2, 1, 2, 'SALE-TEST-' || $1, 'TRK-TEST-' || $1, 180000000, 180000000,
+                     'Customer Test', '081200000001', 'customer@test.local')"
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        find_sale_order_by_id(pool, id).await.unwrap().unwrap()
+    }
+
+    // Seller accept dan reject bersamaan dari snapshot yang sama: UPDATE bersyarat hanya meloloskan satu
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_accept_and_reject_race_exactly_one_wins(pool: PgPool) {
+        for id in 1..=20 {
+            let snapshot = insert_pending_order(&pool, id).await;
+
+            let (accepted, rejected) = tokio::join!(
+                confirm_sale_order(&pool, &snapshot, None, None, 3),
+                reject_sale_order(&pool, &snapshot, "Unit sudah terjual"),
+            );
+
+            let winner = match (&accepted, &rejected) {
+                (Ok(order), Err(AppError::Conflict(_))) | (Err(AppError::Conflict(_)), Ok(order)) => order,
+                other => panic!("tepat satu transisi harus menang: {:?}", other),
+            };
+
+            let stored = find_sale_order_by_id(&pool, id).await.unwrap().unwrap();
+            assert_eq!(stored.version, snapshot.version + 1);
+            assert_eq!(stored.status, winner.status);
+        }
+    }
+
+    async fn sale_status(pool: &PgPool, id: i32) -> String {
+        sqlx::query_scalar("SELECT status FROM sale_orders WHERE id = $1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // Event dari payment-service menandai order pending_payment paid, order status lain tidak diubah
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_payment_events_mark_pending_payment_orders_paid(pool: PgPool) {
+        insert_pending_order(&pool, 1).await;
+        insert_pending_order(&pool, 2).await;
+        sqlx::query("UPDATE sale_orders SET status = 'pending_payment' WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE sale_orders SET status = 'cancelled' WHERE id = 2")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO sale_payment_events (sale_order_id, payment_order_id)
+             VALUES (1, 'SALE-PAY-1'), (2, 'SALE-PAY-2')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(apply_payment_events(&pool, 100).await.unwrap(), 1);
+
+        let paid = find_sale_order_by_id(&pool, 1).await.unwrap().unwrap();
+        assert_eq!(paid.status, "paid");
+        assert!(paid.paid_at.is_some());
+        assert_eq!(sale_status(&pool, 2).await, "cancelled");
+
+        // Semua event selesai, tick berikutnya tidak memproses ulang
+        let unprocessed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sale_payment_events WHERE processed_at IS NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(unprocessed, 0);
+        assert_eq!(apply_payment_events(&pool, 100).await.unwrap(), 0);
+    }
+
+    fn seller_query() -> SaleOrderQueryParams {
+        SaleOrderQueryParams {
+            status: None,
+            q: None,
+            buyer_name: None,
+            vehicle_id: None,
+            date_from: None,
+            date_to: None,
+            min_price: None,
+            max_price: None,
+            sort: None,
+        }
+    }
+
+    async fn seller_order_ids(pool: &PgPool, params: &SaleOrderQueryParams, limit: i64, offset: i64) -> (Vec<i32>, i64) {
+        let (orders, total) = find_sale_orders_by_seller(pool, 2, params, limit, offset).await.unwrap();
+        (orders.iter().map(|order| order.id).collect(), total)
+    }
+
+    // Filter, pencarian, sort dan total list order seller, input wildcard diperlakukan literal
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_seller_order_search_filters_and_sort(pool: PgPool) {
+        for id in 1..=4 {
+            insert_pending_order(&pool, id).await;
+        }
+        sqlx::query(
+            "UPDATE sale_orders SET
+                 buyer_name = (ARRAY['Budi Santoso', 'Siti Aminah', 'Budi_Hartono', 'Andi'])[id],
+                 final_price = (ARRAY[150000000, 175000000, 200000000, 90000000])[id],
+                 vehicle_id = CASE WHEN id = 4 THEN 1 ELSE 2 END,
+                 status = CASE WHEN id = 2 THEN 'cancelled' ELSE status END,
+                 created_at = TIMESTAMPTZ '2026-10-01 00:00:00+00' + (id || ' days')::INTERVAL"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Order seller lain tidak pernah ikut
+        insert_pending_order(&pool, 5).await;
+        sqlx::query("UPDATE sale_orders SET seller_id = 3, buyer_name = 'Budi Lain' WHERE id = 5")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(seller_order_ids(&pool, &seller_query(), 20, 0).await, (vec![4, 3, 2, 1], 4));
+        // Pagination memotong halaman, total tetap seluruh hasil filter
+        assert_eq!(seller_order_ids(&pool, &seller_query(), 2, 2).await, (vec![2, 1], 4));
+
+        let mut params = seller_query();
+        params.buyer_name = Some("budi".to_string());
+        assert_eq!(seller_order_ids(&pool, &params, 20, 0).await, (vec![3, 1], 2));
+
+        // "_" bukan wildcard: hanya nama yang benar-benar mengandung underscore
+        params.buyer_name = Some("Budi_".to_string());
+        assert_eq!(seller_order_ids(&pool, &params, 20, 0).await, (vec![3], 1));
+
+        let mut params = seller_query();
+        params.q = Some("sale-test-2".to_string());
+        assert_eq!(seller_order_ids(&pool, &params, 20, 0).await, (vec![2], 1));
+        params.q = Some("4".to_string());
+        assert_eq!(seller_order_ids(&pool, &params, 20, 0).await, (vec![4], 1));
+
+        let mut params = seller_query();
+        params.status = Some("pending_confirmation".to_string());
+        params.vehicle_id = Some(2);
+        params.min_price = Some(150_000_000.0);
+        params.max_price = Some(200_000_000.0);
+        params.sort = Some("price_desc".to_string());
+        assert_eq!(seller_order_ids(&pool, &params, 20, 0).await, (vec![3, 1], 2));
+
+        // date_from inklusif, date_to eksklusif
+        let mut params = seller_query();
+        params.date_from = Some("2026-10-03T00:00:00Z".parse().unwrap());
+        params.date_to = Some("2026-10-05T00:00:00Z".parse().unwrap());
+        params.sort = Some("oldest".to_string());
+        assert_eq!(seller_order_ids(&pool, &params, 20, 0).await, (vec![2, 3], 2));
+
+        let mut params = seller_query();
+        params.sort = Some("created_at; DROP TABLE sale_orders".to_string());
+        assert!(matches!(
+            find_sale_orders_by_seller(&pool, 2, &params, 20, 0).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
 }
@@ -1,18 +1,95 @@
-use chrono::{DateTime, Duration, Utc};
-use sqlx::PgPool;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use sqlx::{PgConnection, PgPool};
 use sqlx::types::JsonValue;
 
 use crate::{
-    domain::testdrive::{TestDriveBooking, CreateTestDriveRequest, TestDriveStatus},
+    domain::concurrency::ensure_applied,
+    domain::testdrive::{
+        TestDriveBooking, CreateTestDriveRequest, TestDriveStatus,
+        TestDriveLocation, TestDriveLocationProposal, BulkTestDriveResult, RescheduleSlot,
+        SellerAvailability,
+    },
     error::AppError,
+    utils::{
+        business_hours::{BusinessHours, SLOT_DURATION_MINUTES},
+        reschedule_slots,
+        testdrive_slots::{self, NewAvailability},
+    },
 };
 
-// Create test drive booking baru
+// Namespace advisory lock slot test drive (key kedua = vehicle_id)
+const SLOT_LOCK_NAMESPACE: i32 = 7401;
+
+// Namespace advisory lock kalender slot seller (key kedua = seller_id)
+const SELLER_SLOT_LOCK_NAMESPACE: i32 = 7402;
+
+// Aksi bulk seller untuk test drive yang menunggu konfirmasi
+#[derive(Debug, Clone, Copy)]
+pub enum BulkTestDriveAction<'a> {
+    Accept,
+    Reject { reason: &'a str },
+}
+
+// Create test drive booking baru, requested_date disimpan sebagai awal sesi (tanggal + jam lokal seller)
 pub async fn create_testdrive(
     pool: &PgPool,
     customer_id: i32,
     seller_id: i32,
     payload: &CreateTestDriveRequest,
+    location: TestDriveLocation,
+    hours: &BusinessHours,
+) -> Result<TestDriveBooking, AppError> {
+    let starts_at = hours.slot_start(payload.requested_date, &payload.requested_time)
+        .map_err(AppError::bad_request)?;
+
+    let mut conn = pool.acquire().await?;
+    let schedule = (starts_at, payload.requested_time.as_str());
+
+    insert_testdrive(&mut conn, customer_id, seller_id, payload, location, schedule).await
+}
+
+// Create test drive booking di slot seller. Slot dicek ulang di dalam lock kalender seller
+// sehingga dua customer tidak bisa mengambil kapasitas terakhir slot yang sama
+pub async fn create_testdrive_in_slot(
+    pool: &PgPool,
+    customer_id: i32,
+    seller_id: i32,
+    payload: &CreateTestDriveRequest,
+    location: TestDriveLocation,
+    slot_id: i32,
+    hours: &BusinessHours,
+) -> Result<TestDriveBooking, AppError> {
+    let mut tx = pool.begin().await?;
+    lock_seller_calendar(&mut tx, seller_id).await?;
+
+    let day = hours.local_date(payload.requested_date);
+    let rules = find_availability(&mut tx, seller_id).await?;
+    let booked = count_booked_slots(&mut tx, seller_id, day, hours).await?;
+
+    let slot = testdrive_slots::pick_slot(&rules, slot_id, day, hours, &booked, Utc::now())
+        .map_err(AppError::conflict)?;
+
+    let testdrive = insert_testdrive(
+        &mut tx,
+        customer_id,
+        seller_id,
+        payload,
+        location,
+        (slot.starts_at, slot.time.as_str()),
+    ).await?;
+
+    tx.commit().await?;
+
+    Ok(testdrive)
+}
+
+async fn insert_testdrive(
+    conn: &mut PgConnection,
+    customer_id: i32,
+    seller_id: i32,
+    payload: &CreateTestDriveRequest,
+    location: TestDriveLocation,
+    (requested_date, requested_time): (DateTime<Utc>, &str),
 ) -> Result<TestDriveBooking, AppError> {
     let timeout_at = Utc::now() + Duration::hours(2);
 
@@ -21,23 +98,28 @@ pub async fn create_testdrive(
             vehicle_id, customer_id, seller_id,
             requested_date, requested_time,
             customer_name, customer_phone, customer_email,
-            notes, status, timeout_at
+            notes, status, timeout_at,
+            location, address, lat, lng
         ) VALUES (
-            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15
         ) RETURNING *"
     )
     .bind(payload.vehicle_id)
     .bind(customer_id)
     .bind(seller_id)
-    .bind(payload.requested_date)
-    .bind(&payload.requested_time)
+    .bind(requested_date)
+    .bind(requested_time)
     .bind(&payload.customer_name)
     .bind(&payload.customer_phone)
     .bind(&payload.customer_email)
     .bind(&payload.notes)
     .bind(TestDriveStatus::MenungguKonfirmasi.as_str())
     .bind(timeout_at)
-    .fetch_one(pool)
+    .bind(location.as_str())
+    .bind(payload.address.as_deref().map(str::trim).filter(|a| !a.is_empty()))
+    .bind(payload.lat)
+    .bind(payload.lng)
+    .fetch_one(&mut *conn)
     .await?;
 
     Ok(testdrive)
@@ -121,8 +203,9 @@ pub async fn find_testdrives_by_seller(
 // Seller reschedule test drive dengan alternative slots
 pub async fn reschedule_testdrive(
     pool: &PgPool,
-    id: i32,
+    current: &TestDriveBooking,
     reschedule_slots: JsonValue,
+    proposed_location: Option<JsonValue>,
 ) -> Result<TestDriveBooking, AppError> {
     let timeout_at = Utc::now() + Duration::hours(2);
 
@@ -130,57 +213,67 @@ pub async fn reschedule_testdrive(
         "UPDATE testdrive_bookings
          SET status = $4,
              reschedule_slots = $1,
+             proposed_location = $5,
              timeout_at = $2,
-             updated_at = NOW()
-         WHERE id = $3
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $3 AND status = $6 AND version = $7
          RETURNING *"
     )
     .bind(reschedule_slots)
     .bind(timeout_at)
-    .bind(id)
+    .bind(current.id)
     .bind(TestDriveStatus::SellerReschedule.as_str())
-    .fetch_one(pool)
+    .bind(proposed_location)
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(testdrive)
+    ensure_applied(testdrive, "Test drive")
 }
 
 // Customer pilih slot reschedule
 pub async fn choose_reschedule_slot(
     pool: &PgPool,
-    id: i32,
+    current: &TestDriveBooking,
     slot_index: usize,
+    hours: &BusinessHours,
 ) -> Result<TestDriveBooking, AppError> {
-    // Ambil testdrive dulu untuk get reschedule_slots
-    let testdrive: TestDriveBooking = sqlx::query_as(
-        "SELECT * FROM testdrive_bookings WHERE id = $1"
-    )
-    .bind(id)
-    .fetch_one(pool)
-    .await?;
+    // Slot dan lokasi dari snapshot yang sama dengan version yang dicek saat UPDATE
+    let slots: Vec<RescheduleSlot> = current.reschedule_slots.clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|_| AppError::internal("Invalid reschedule_slots format"))?
+        .ok_or_else(|| AppError::bad_request("Tidak ada reschedule slots"))?;
 
-    if testdrive.reschedule_slots.is_none() {
-        return Err(AppError::bad_request("Tidak ada reschedule slots"));
-    }
+    let (selected_slot, starts_at) = reschedule_slots::select_slot(&slots, slot_index, hours, Utc::now())
+        .map_err(AppError::bad_request)?;
+    let new_time = selected_slot.time.clone();
 
-    let slots = testdrive.reschedule_slots.unwrap();
-    let slots_array = slots.as_array()
-        .ok_or_else(|| AppError::internal("Invalid reschedule_slots format"))?;
+    // Lokasi alternatif dari seller ikut berlaku saat slot dipilih
+    let proposal: Option<TestDriveLocationProposal> = current.proposed_location.clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|_| AppError::internal("Invalid proposed_location format"))?;
 
-    if slot_index >= slots_array.len() {
-        return Err(AppError::bad_request("Slot index tidak valid"));
-    }
+    let (location, address, lat, lng) = match proposal {
+        Some(p) => (p.location, p.address, p.lat, p.lng),
+        None => (current.location.clone(), current.address.clone(), current.lat, current.lng),
+    };
 
-    let selected_slot = &slots_array[slot_index];
-    let new_date = selected_slot.get("date")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::internal("Invalid slot date"))?;
-    let new_time = selected_slot.get("time")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::internal("Invalid slot time"))?;
+    // Jam baru dicek terhadap test drive yang sudah diterima di dalam lock yang sama dengan confirm,
+    // termasuk jam lain di hari yang sama yang sesinya tumpang tindih
+    let mut tx = pool.begin().await?;
+    lock_seller_calendar(&mut tx, current.seller_id).await?;
+    lock_vehicle_slots(&mut tx, current.vehicle_id).await?;
 
-    let new_date_parsed = new_date.parse::<DateTime<Utc>>()
-        .map_err(|_| AppError::internal("Invalid date format"))?;
+    if let Some(conflict_id) = find_slot_conflict(&mut tx, current.vehicle_id, starts_at, current.id).await? {
+        return Err(AppError::conflict(format!(
+            "Slot bentrok dengan test drive #{} yang sudah diterima",
+            conflict_id
+        )));
+    }
 
     let updated = sqlx::query_as(
         "UPDATE testdrive_bookings
@@ -188,67 +281,246 @@ pub async fn choose_reschedule_slot(
              requested_time = $2,
              status = $5,
              reschedule_slots = NULL,
+             proposed_location = NULL,
+             location = $6,
+             address = $7,
+             lat = $8,
+             lng = $9,
              timeout_at = $3,
-             updated_at = NOW()
-         WHERE id = $4
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $4 AND status = $10 AND version = $11
          RETURNING *"
     )
-    .bind(new_date_parsed)
+    .bind(starts_at)
     .bind(new_time)
     .bind(Utc::now() + Duration::hours(2))
-    .bind(id)
+    .bind(current.id)
     .bind(TestDriveStatus::MenungguKonfirmasi.as_str())
-    .fetch_one(pool)
+    .bind(location)
+    .bind(address)
+    .bind(lat)
+    .bind(lng)
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(&mut *tx)
     .await?;
 
+    let updated = ensure_applied(updated, "Test drive")?;
+    tx.commit().await?;
+
     Ok(updated)
 }
 
 // Seller confirm test drive
+// Slot dicek ulang di dalam lock kalender seller dan lock slot vehicle (sama seperti create dan bulk accept)
+// sehingga dua booking di slot yang sama tidak bisa diterima bersamaan
 pub async fn confirm_testdrive(
     pool: &PgPool,
-    id: i32,
+    current: &TestDriveBooking,
 ) -> Result<TestDriveBooking, AppError> {
+    let mut tx = pool.begin().await?;
+    lock_seller_calendar(&mut tx, current.seller_id).await?;
+    lock_vehicle_slots(&mut tx, current.vehicle_id).await?;
+
+    if let Some(conflict_id) = find_slot_conflict(&mut tx, current.vehicle_id, current.requested_date, current.id).await? {
+        return Err(AppError::conflict(format!(
+            "Slot bentrok dengan test drive #{} yang sudah diterima",
+            conflict_id
+        )));
+    }
+
     let testdrive = sqlx::query_as(
         "UPDATE testdrive_bookings
          SET status = $2,
              timeout_at = NULL,
-             updated_at = NOW()
-         WHERE id = $1
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $1 AND status = $3 AND version = $4
          RETURNING *"
     )
-    .bind(id)
+    .bind(current.id)
     .bind(TestDriveStatus::Diterima.as_str())
-    .fetch_one(pool)
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(&mut *tx)
     .await?;
 
+    let testdrive = ensure_applied(testdrive, "Test drive")?;
+    tx.commit().await?;
+
     Ok(testdrive)
 }
 
+// Lock kalender slot seller sampai transaksi selesai (create, confirm, dan pilih slot reschedule)
+async fn lock_seller_calendar(
+    conn: &mut PgConnection,
+    seller_id: i32,
+) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1, $2)")
+        .bind(SELLER_SLOT_LOCK_NAMESPACE)
+        .bind(seller_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+// Lock slot test drive per vehicle sampai transaksi selesai (accept paralel tidak bisa lolos bersamaan)
+pub async fn lock_vehicle_slots(
+    conn: &mut PgConnection,
+    vehicle_id: i32,
+) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1, $2)")
+        .bind(SLOT_LOCK_NAMESPACE)
+        .bind(vehicle_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+// Cari test drive lain yang sudah diterima untuk vehicle yang sama dengan sesi tumpang tindih.
+// Dibandingkan per timestamp awal sesi, jadi 10:00 dan 10:30 di hari yang sama ikut bentrok
+pub async fn find_slot_conflict(
+    conn: &mut PgConnection,
+    vehicle_id: i32,
+    starts_at: DateTime<Utc>,
+    exclude_id: i32,
+) -> Result<Option<i32>, AppError> {
+    let conflict = sqlx::query_scalar(
+        "SELECT id FROM testdrive_bookings
+         WHERE vehicle_id = $1
+           AND requested_date > $2 - $3
+           AND requested_date < $2 + $3
+           AND status = $4
+           AND id <> $5
+         LIMIT 1"
+    )
+    .bind(vehicle_id)
+    .bind(starts_at)
+    .bind(Duration::minutes(SLOT_DURATION_MINUTES))
+    .bind(TestDriveStatus::Diterima.as_str())
+    .bind(exclude_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(conflict)
+}
+
+// Accept/reject banyak test drive seller dalam satu transaksi, hasil per ID sesuai urutan request
+pub async fn bulk_transition_testdrives(
+    pool: &PgPool,
+    seller_id: i32,
+    ids: &[i32],
+    action: BulkTestDriveAction<'_>,
+) -> Result<Vec<BulkTestDriveResult>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let bookings: Vec<TestDriveBooking> = sqlx::query_as(
+        "SELECT * FROM testdrive_bookings WHERE id = ANY($1) AND seller_id = $2 FOR UPDATE"
+    )
+    .bind(ids)
+    .bind(seller_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Lock slot vehicle dengan urutan tetap agar tidak deadlock dengan bulk lain
+    if matches!(action, BulkTestDriveAction::Accept) {
+        let mut vehicle_ids: Vec<i32> = bookings.iter().map(|b| b.vehicle_id).collect();
+        vehicle_ids.sort_unstable();
+        vehicle_ids.dedup();
+        for vehicle_id in vehicle_ids {
+            lock_vehicle_slots(&mut tx, vehicle_id).await?;
+        }
+    }
+
+    let mut results = Vec::with_capacity(ids.len());
+
+    for &id in ids {
+        let Some(testdrive) = bookings.iter().find(|b| b.id == id) else {
+            results.push(BulkTestDriveResult::skipped(id, "Test drive tidak ditemukan atau bukan milik Anda"));
+            continue;
+        };
+
+        if testdrive.status != TestDriveStatus::MenungguKonfirmasi.as_str() {
+            results.push(BulkTestDriveResult::skipped(id, "Test drive tidak dalam status menunggu konfirmasi"));
+            continue;
+        }
+
+        let updated: TestDriveBooking = match action {
+            BulkTestDriveAction::Accept => {
+                // Booking yang diterima lebih dulu di batch ini ikut terdeteksi karena satu transaksi
+                if let Some(conflict_id) = find_slot_conflict(&mut tx, testdrive.vehicle_id, testdrive.requested_date, id).await? {
+                    results.push(BulkTestDriveResult::skipped(
+                        id,
+                        format!("Slot bentrok dengan test drive #{} yang sudah diterima", conflict_id),
+                    ));
+                    continue;
+                }
+
+                sqlx::query_as(
+                    "UPDATE testdrive_bookings
+                     SET status = $2, timeout_at = NULL, updated_at = NOW(), version = version + 1
+                     WHERE id = $1
+                     RETURNING *"
+                )
+                .bind(id)
+                .bind(TestDriveStatus::Diterima.as_str())
+                .fetch_one(&mut *tx)
+                .await?
+            }
+            BulkTestDriveAction::Reject { reason } => {
+                sqlx::query_as(
+                    "UPDATE testdrive_bookings
+                     SET status = $2, cancel_reason = $3, cancelled_at = NOW(),
+                         timeout_at = NULL, updated_at = NOW(), version = version + 1
+                     WHERE id = $1
+                     RETURNING *"
+                )
+                .bind(id)
+                .bind(TestDriveStatus::Cancelled.as_str())
+                .bind(reason)
+                .fetch_one(&mut *tx)
+                .await?
+            }
+        };
+
+        results.push(BulkTestDriveResult::ok(id, &updated.status));
+    }
+
+    tx.commit().await?;
+
+    Ok(results)
+}
+
 // Seller complete test drive
 pub async fn complete_testdrive(
     pool: &PgPool,
-    id: i32,
+    current: &TestDriveBooking,
 ) -> Result<TestDriveBooking, AppError> {
     let testdrive = sqlx::query_as(
         "UPDATE testdrive_bookings
          SET status = $2,
-             updated_at = NOW()
-         WHERE id = $1
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $1 AND status = $3 AND version = $4
          RETURNING *"
     )
-    .bind(id)
+    .bind(current.id)
     .bind(TestDriveStatus::Selesai.as_str())
-    .fetch_one(pool)
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(testdrive)
+    ensure_applied(testdrive, "Test drive")
 }
 
 // Cancel test drive booking
 pub async fn cancel_testdrive(
     pool: &PgPool,
-    id: i32,
+    current: &TestDriveBooking,
     cancel_reason: &str,
 ) -> Result<TestDriveBooking, AppError> {
     let testdrive = sqlx::query_as(
@@ -256,24 +528,27 @@ pub async fn cancel_testdrive(
          SET status = $3,
              cancel_reason = $1,
              cancelled_at = NOW(),
-             updated_at = NOW()
-         WHERE id = $2
+             updated_at = NOW(),
+             version = version + 1
+         WHERE id = $2 AND status = $4 AND version = $5
          RETURNING *"
     )
     .bind(cancel_reason)
-    .bind(id)
+    .bind(current.id)
     .bind(TestDriveStatus::Cancelled.as_str())
-    .fetch_one(pool)
+    .bind(&current.status)
+    .bind(current.version)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(testdrive)
+    ensure_applied(testdrive, "Test drive")
 }
 
 // Auto-timeout test drive bookings yang sudah lewat 2 jam
 pub async fn timeout_expired_testdrives(pool: &PgPool) -> Result<i64, AppError> {
     let result = sqlx::query(
         "UPDATE testdrive_bookings
-         SET status = $3, updated_at = NOW()
+         SET status = $3, updated_at = NOW(), version = version + 1
          WHERE status IN ($1, $2)
            AND timeout_at < NOW()"
     )
@@ -285,3 +560,298 @@ pub async fn timeout_expired_testdrives(pool: &PgPool) -> Result<i64, AppError>
 
     Ok(result.rows_affected() as i64)
 }
+
+// Jam operasional test drive seller, None jika belum diatur (pakai default global)
+pub async fn find_business_hours(
+    pool: &PgPool,
+    seller_id: i32,
+) -> Result<Option<BusinessHours>, AppError> {
+    let row: Option<(NaiveTime, NaiveTime, String)> = sqlx::query_as(
+        "SELECT open_time, close_time, timezone
+         FROM seller_testdrive_hours
+         WHERE seller_id = $1"
+    )
+    .bind(seller_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.and_then(|(open, close, timezone)| {
+        let hours = BusinessHours::from_stored(open, close, &timezone);
+        if hours.is_none() {
+            tracing::warn!("Invalid test drive hours for seller {}, using default", seller_id);
+        }
+        hours
+    }))
+}
+
+// Simpan jam operasional test drive seller (menggantikan yang lama)
+pub async fn upsert_business_hours(
+    pool: &PgPool,
+    seller_id: i32,
+    hours: &BusinessHours,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO seller_testdrive_hours (seller_id, open_time, close_time, timezone)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (seller_id) DO UPDATE SET
+            open_time = EXCLUDED.open_time,
+            close_time = EXCLUDED.close_time,
+            timezone = EXCLUDED.timezone"
+    )
+    .bind(seller_id)
+    .bind(hours.open)
+    .bind(hours.close)
+    .bind(hours.timezone.name())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Jadwal slot seller (slot mingguan dulu, lalu pengecualian per tanggal)
+pub async fn find_availability(
+    conn: &mut PgConnection,
+    seller_id: i32,
+) -> Result<Vec<SellerAvailability>, AppError> {
+    let rules = sqlx::query_as(
+        "SELECT id, weekday, date, start_time, capacity, is_available
+         FROM seller_availability
+         WHERE seller_id = $1
+         ORDER BY weekday NULLS LAST, date, start_time NULLS FIRST, id"
+    )
+    .bind(seller_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rules)
+}
+
+// Ganti seluruh jadwal slot seller dalam satu transaksi. Slot yang masih ada di-upsert sehingga
+// id-nya (slot_id yang sudah dipegang customer) tidak berubah, slot yang dihapus seller dibuang
+pub async fn replace_availability(
+    pool: &PgPool,
+    seller_id: i32,
+    entries: &[NewAvailability],
+) -> Result<Vec<SellerAvailability>, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut kept_ids = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let (id,): (i32,) = sqlx::query_as(
+            "INSERT INTO seller_availability (seller_id, weekday, date, start_time, capacity, is_available)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (seller_id, weekday, date, start_time)
+             DO UPDATE SET capacity = EXCLUDED.capacity, is_available = EXCLUDED.is_available
+             RETURNING id"
+        )
+        .bind(seller_id)
+        .bind(entry.weekday)
+        .bind(entry.date)
+        .bind(entry.start_time)
+        .bind(entry.capacity)
+        .bind(entry.is_available)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        kept_ids.push(id);
+    }
+
+    sqlx::query("DELETE FROM seller_availability WHERE seller_id = $1 AND id <> ALL($2)")
+        .bind(seller_id)
+        .bind(&kept_ids)
+        .execute(&mut *tx)
+        .await?;
+
+    let rules = find_availability(&mut tx, seller_id).await?;
+    tx.commit().await?;
+
+    Ok(rules)
+}
+
+// Jumlah booking aktif seller per jam "HH:MM" di tanggal lokal `day`
+pub async fn count_booked_slots(
+    conn: &mut PgConnection,
+    seller_id: i32,
+    day: NaiveDate,
+    hours: &BusinessHours,
+) -> Result<Vec<(String, i64)>, AppError> {
+    let booked = sqlx::query_as(
+        "SELECT requested_time, COUNT(*)
+         FROM testdrive_bookings
+         WHERE seller_id = $1
+           AND (requested_date AT TIME ZONE $2)::date = $3
+           AND status = ANY($4)
+         GROUP BY requested_time"
+    )
+    .bind(seller_id)
+    .bind(hours.timezone.name())
+    .bind(day)
+    .bind(vec![TestDriveStatus::MenungguKonfirmasi.as_str(), TestDriveStatus::Diterima.as_str()])
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(booked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::utils::clock::parse_clock;
+
+    fn hours() -> BusinessHours {
+        BusinessHours::parse("08:00", "18:00", "Asia/Jakarta").unwrap()
+    }
+
+    fn slot(date: NaiveDate, time: &str, capacity: i32) -> NewAvailability {
+        NewAvailability {
+            weekday: None,
+            date: Some(date),
+            start_time: parse_clock(time),
+            capacity,
+            is_available: true,
+        }
+    }
+
+    fn request(slot_id: i32, requested_date: DateTime<Utc>) -> CreateTestDriveRequest {
+        CreateTestDriveRequest {
+            vehicle_id: 2,
+            requested_date,
+            requested_time: String::new(),
+            slot_id: Some(slot_id),
+            customer_name: "Customer Test".to_string(),
+            customer_phone: "081200000001".to_string(),
+            customer_email: "customer@test.local".to_string(),
+            notes: None,
+            location: None,
+            address: None,
+            lat: None,
+            lng: None,
+        }
+    }
+
+    // Tiga customer berebut kapasitas terakhir slot yang sama: lock kalender seller hanya meloloskan satu
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_double_booking_prevented(pool: PgPool) {
+        let hours = hours();
+        let day = hours.local_date(Utc::now()) + Duration::days(7);
+        let rules = replace_availability(&pool, 2, &[slot(day, "09:00", 1)]).await.unwrap();
+        // 09:00 WIB = 02:00 UTC
+        let requested_date = day.and_hms_opt(2, 0, 0).unwrap().and_utc();
+        let payload = request(rules[0].id, requested_date);
+        let book = || create_testdrive_in_slot(&pool, 1, 2, &payload, TestDriveLocation::Showroom, rules[0].id, &hours);
+
+        let (first, second, third) = tokio::join!(book(), book(), book());
+        let outcomes = [first, second, third];
+
+        assert_eq!(outcomes.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(outcomes.iter().all(|result| matches!(result, Ok(_) | Err(AppError::Conflict(_)))));
+
+        let booked: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM testdrive_bookings WHERE seller_id = 2")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(booked, 1);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_replace_availability_keeps_slot_ids(pool: PgPool) {
+        let day = NaiveDate::from_ymd_opt(2030, 3, 18).unwrap();
+        let before = replace_availability(&pool, 2, &[slot(day, "09:00", 1), slot(day, "10:00", 1)]).await.unwrap();
+
+        // Kapasitas 09:00 diubah, 10:00 dihapus, 11:00 ditambah
+        let after = replace_availability(&pool, 2, &[slot(day, "09:00", 3), slot(day, "11:00", 1)]).await.unwrap();
+
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[0].capacity, 3);
+        assert_eq!(after[1].start_time, parse_clock("11:00"));
+        assert!(after.iter().all(|rule| rule.id != before[1].id));
+    }
+
+    // Dua customer request slot yang sama tanpa kalender seller: hanya satu yang bisa dikonfirmasi
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_confirm_rejects_slot_conflict(pool: PgPool) {
+        let mut payload = request(0, Utc::now() + Duration::days(7));
+        payload.slot_id = None;
+        payload.requested_time = "10:00".to_string();
+
+        let first = create_testdrive(&pool, 1, 2, &payload, TestDriveLocation::Showroom, &hours()).await.unwrap();
+        let second = create_testdrive(&pool, 1, 2, &payload, TestDriveLocation::Showroom, &hours()).await.unwrap();
+
+        let (a, b) = tokio::join!(confirm_testdrive(&pool, &first), confirm_testdrive(&pool, &second));
+        let outcomes = [a, b];
+
+        assert_eq!(outcomes.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(outcomes.iter().all(|result| matches!(result, Ok(_) | Err(AppError::Conflict(_)))));
+
+        let accepted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM testdrive_bookings WHERE status = 'diterima'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(accepted, 1);
+    }
+
+    // Pindah jadwal ke jam lain di hari yang sama yang sesinya tumpang tindih dengan test drive diterima
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_reschedule_rejects_overlapping_time_same_day(pool: PgPool) {
+        let hours = hours();
+        let day = Utc::now() + Duration::days(7);
+        let mut payload = request(0, day);
+        payload.slot_id = None;
+        payload.requested_time = "10:00".to_string();
+
+        let accepted = create_testdrive(&pool, 1, 2, &payload, TestDriveLocation::Showroom, &hours).await.unwrap();
+        confirm_testdrive(&pool, &accepted).await.unwrap();
+
+        payload.requested_time = "14:00".to_string();
+        let pending = create_testdrive(&pool, 1, 2, &payload, TestDriveLocation::Showroom, &hours).await.unwrap();
+
+        let slots = reschedule_slots::validate_reschedule_slots(
+            vec![
+                RescheduleSlot { date: day, time: "10:30".to_string() },
+                RescheduleSlot { date: day, time: "12:00".to_string() },
+            ],
+            &hours,
+            Utc::now(),
+        ).unwrap();
+        let rescheduled = reschedule_testdrive(&pool, &pending, serde_json::to_value(&slots).unwrap(), None)
+            .await
+            .unwrap();
+
+        let err = choose_reschedule_slot(&pool, &rescheduled, 0, &hours).await.unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+
+        // Jam yang tidak tumpang tindih tetap bisa dipilih, disimpan sebagai awal sesi penuh
+        let moved = choose_reschedule_slot(&pool, &rescheduled, 1, &hours).await.unwrap();
+        assert_eq!(moved.requested_date, slots[1].date);
+        assert_eq!(moved.requested_time, "12:00");
+    }
+}
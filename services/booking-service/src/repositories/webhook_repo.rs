@@ -0,0 +1,274 @@
+use sqlx::PgPool;
+
+use crate::{
+    domain::webhook::{DeliveryStatus, OutboundWebhook, WebhookDelivery},
+    error::AppError,
+};
+
+const WEBHOOK_COLUMNS: &str = "id, seller_id, url, secret, is_active,
+    secret_rotated_at, disabled_at, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event_type, payload, status, attempts,
+    response_status, last_error, next_attempt_at, delivered_at, created_at";
+
+// Delivery yang diklaim worker beserta endpoint tujuannya
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PendingDelivery {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: sqlx::types::JsonValue,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
+
+// Jumlah endpoint aktif milik seller
+pub async fn count_active_webhooks(pool: &PgPool, seller_id: i32) -> Result<i64, AppError> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM outbound_webhooks WHERE seller_id = $1 AND is_active = true"
+    )
+    .bind(seller_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+// Daftarkan endpoint webhook baru
+pub async fn create_webhook(
+    pool: &PgPool,
+    seller_id: i32,
+    url: &str,
+    secret: &str,
+) -> Result<OutboundWebhook, AppError> {
+    let webhook = sqlx::query_as(&format!(
+        "INSERT INTO outbound_webhooks (seller_id, url, secret)
+         VALUES ($1, $2, $3)
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(seller_id)
+    .bind(url)
+    .bind(secret)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(webhook)
+}
+
+// List endpoint webhook milik seller
+pub async fn find_webhooks_by_seller(
+    pool: &PgPool,
+    seller_id: i32,
+) -> Result<Vec<OutboundWebhook>, AppError> {
+    let webhooks = sqlx::query_as(&format!(
+        "SELECT {} FROM outbound_webhooks WHERE seller_id = $1 ORDER BY created_at DESC",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(seller_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(webhooks)
+}
+
+// Satu endpoint webhook milik seller
+pub async fn find_webhook(
+    pool: &PgPool,
+    id: i32,
+    seller_id: i32,
+) -> Result<Option<OutboundWebhook>, AppError> {
+    let webhook = sqlx::query_as(&format!(
+        "SELECT {} FROM outbound_webhooks WHERE id = $1 AND seller_id = $2",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(id)
+    .bind(seller_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(webhook)
+}
+
+// Ganti secret endpoint, delivery berikutnya langsung pakai secret baru
+pub async fn rotate_secret(
+    pool: &PgPool,
+    id: i32,
+    seller_id: i32,
+    secret: &str,
+) -> Result<Option<OutboundWebhook>, AppError> {
+    let webhook = sqlx::query_as(&format!(
+        "UPDATE outbound_webhooks
+         SET secret = $3, secret_rotated_at = NOW()
+         WHERE id = $1 AND seller_id = $2
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(id)
+    .bind(seller_id)
+    .bind(secret)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(webhook)
+}
+
+// Nonaktifkan endpoint dan batalkan delivery yang masih pending
+pub async fn disable_webhook(
+    pool: &PgPool,
+    id: i32,
+    seller_id: i32,
+) -> Result<Option<OutboundWebhook>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let webhook: Option<OutboundWebhook> = sqlx::query_as(&format!(
+        "UPDATE outbound_webhooks
+         SET is_active = false, disabled_at = COALESCE(disabled_at, NOW())
+         WHERE id = $1 AND seller_id = $2
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(id)
+    .bind(seller_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if webhook.is_some() {
+        sqlx::query(
+            "UPDATE webhook_deliveries
+             SET status = $2, last_error = 'Endpoint dinonaktifkan seller'
+             WHERE webhook_id = $1 AND status = $3"
+        )
+        .bind(id)
+        .bind(DeliveryStatus::Failed.as_str())
+        .bind(DeliveryStatus::Pending.as_str())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(webhook)
+}
+
+// Delivery terbaru untuk endpoint milik seller
+pub async fn find_recent_deliveries(
+    pool: &PgPool,
+    webhook_id: i32,
+    seller_id: i32,
+    status: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Option<Vec<WebhookDelivery>>, AppError> {
+    let owned: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM outbound_webhooks WHERE id = $1 AND seller_id = $2"
+    )
+    .bind(webhook_id)
+    .bind(seller_id)
+    .fetch_optional(pool)
+    .await?;
+
+    if owned.is_none() {
+        return Ok(None);
+    }
+
+    let deliveries = sqlx::query_as(&format!(
+        "SELECT {} FROM webhook_deliveries
+         WHERE webhook_id = $1 AND ($2::TEXT IS NULL OR status = $2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3 OFFSET $4",
+        DELIVERY_COLUMNS
+    ))
+    .bind(webhook_id)
+    .bind(status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(deliveries))
+}
+
+// Klaim delivery yang jatuh tempo dengan lease agar tidak dikirim ganda oleh instance lain
+pub async fn claim_due_deliveries(
+    pool: &PgPool,
+    limit: i64,
+    lease_secs: i64,
+) -> Result<Vec<PendingDelivery>, AppError> {
+    let deliveries = sqlx::query_as(
+        "WITH claimed AS (
+            UPDATE webhook_deliveries
+            SET next_attempt_at = NOW() + $2::BIGINT * INTERVAL '1 second'
+            WHERE id IN (
+                SELECT d.id FROM webhook_deliveries d
+                JOIN outbound_webhooks w ON w.id = d.webhook_id
+                WHERE d.status = 'pending' AND d.next_attempt_at <= NOW() AND w.is_active = true
+                ORDER BY d.next_attempt_at, d.id
+                LIMIT $1
+                FOR UPDATE OF d SKIP LOCKED
+            )
+            RETURNING id, webhook_id, event_type, payload, attempts
+        )
+        SELECT c.id, c.event_type, c.payload, c.attempts, w.url, w.secret
+        FROM claimed c
+        JOIN outbound_webhooks w ON w.id = c.webhook_id
+        ORDER BY c.id"
+    )
+    .bind(limit)
+    .bind(lease_secs)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deliveries)
+}
+
+// Catat delivery berhasil
+pub async fn mark_delivered(
+    pool: &PgPool,
+    id: i64,
+    response_status: i32,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE webhook_deliveries
+         SET status = $2, attempts = attempts + 1, response_status = $3,
+             last_error = NULL, delivered_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(id)
+    .bind(DeliveryStatus::Delivered.as_str())
+    .bind(response_status)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Catat percobaan gagal, jadwalkan retry atau tandai failed jika sudah habis
+pub async fn mark_attempt_failed(
+    pool: &PgPool,
+    id: i64,
+    response_status: Option<i32>,
+    error: &str,
+    retry_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), AppError> {
+    let status = match retry_at {
+        Some(_) => DeliveryStatus::Pending,
+        None => DeliveryStatus::Failed,
+    };
+
+    sqlx::query(
+        "UPDATE webhook_deliveries
+         SET status = $2, attempts = attempts + 1, response_status = $3,
+             last_error = $4, next_attempt_at = COALESCE($5, next_attempt_at)
+         WHERE id = $1"
+    )
+    .bind(id)
+    .bind(status.as_str())
+    .bind(response_status)
+    .bind(error)
+    .bind(retry_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -5,22 +5,22 @@ use axum::{
     http::{header, HeaderValue, Method},
 };
 use tower_http::cors::CorsLayer;
+use shared::utils::cors::CorsPolicy;
 use utoipa::{OpenApi, Modify};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
-use std::env;
 
 use crate::{
     handlers::{
-        rental_handlers, testdrive_handlers, sale_handlers,
+        rental_handlers, testdrive_handlers, sale_handlers, calendar_handlers, file_handlers,
+        webhook_handlers, buyer_block_handlers, document_checklist_handlers,
     },
-    config::{AppState, HealthStatus, check_db_health},
+    config::{AppState, HealthStatus},
     domain::sale::{
         CreateSaleOrderRequest, SaleOrderResponse, UpdateDocumentStatusRequest,
         SaleOrderQueryParams, UploadKtpRequest, AcceptSaleOrderRequest,
         CounterOfferRequest, CancelRequest
     },
-    domain::rental::ValidateReturnRequest,
     middleware::{auth::jwt_auth_middleware, rate_limit::rate_limit_middleware},
 };
 
@@ -55,6 +55,10 @@ impl Modify for SecurityAddon {
         rental_handlers::update_rental_booking_status,
         rental_handlers::validate_pickup,
         rental_handlers::validate_return,
+        rental_handlers::check_in_rental,
+        rental_handlers::check_out_rental,
+        rental_handlers::create_return_report,
+        rental_handlers::get_return_report,
         rental_handlers::cancel_rental_booking,
 
         // Test Drive Bookings
@@ -63,16 +67,24 @@ impl Modify for SecurityAddon {
         testdrive_handlers::get_customer_testdrive_bookings,
         testdrive_handlers::get_seller_testdrive_bookings,
         testdrive_handlers::accept_testdrive_booking,
+        testdrive_handlers::bulk_accept_testdrive_bookings,
+        testdrive_handlers::bulk_reject_testdrive_bookings,
         testdrive_handlers::reschedule_testdrive_booking,
         testdrive_handlers::choose_reschedule_slot,
         testdrive_handlers::confirm_testdrive_booking,
         testdrive_handlers::complete_testdrive_booking,
         testdrive_handlers::cancel_testdrive_booking,
         testdrive_handlers::timeout_expired_testdrives,
+        testdrive_handlers::get_business_hours,
+        testdrive_handlers::set_business_hours,
+        testdrive_handlers::get_availability,
+        testdrive_handlers::set_availability,
+        testdrive_handlers::get_seller_slots,
 
         // Sale Orders
         sale_handlers::create_sale_order,
         sale_handlers::get_sale_order,
+        sale_handlers::track_sale_order,
         sale_handlers::get_customer_sale_orders,
         sale_handlers::get_seller_sale_orders,
         sale_handlers::confirm_sale_order,
@@ -84,7 +96,30 @@ impl Modify for SecurityAddon {
         sale_handlers::upload_buyer_ktp,
         sale_handlers::start_document_transfer,
         sale_handlers::update_document_status,
-        sale_handlers::confirm_documents_received
+        sale_handlers::confirm_documents_received,
+        sale_handlers::download_sale_invoice,
+        document_checklist_handlers::get_document_checklist,
+        document_checklist_handlers::set_document_checklist,
+
+        // Calendar
+        calendar_handlers::get_booking_calendar,
+
+        // Private Files
+        file_handlers::get_private_file,
+
+        // Outbound Webhooks
+        webhook_handlers::create_webhook,
+        webhook_handlers::list_webhooks,
+        webhook_handlers::get_webhook,
+        webhook_handlers::rotate_webhook_secret,
+        webhook_handlers::disable_webhook,
+        webhook_handlers::list_webhook_deliveries,
+
+        // Buyer Blocklist
+        buyer_block_handlers::list_blocked_buyers,
+        buyer_block_handlers::block_buyer,
+        buyer_block_handlers::get_blocked_buyer,
+        buyer_block_handlers::unblock_buyer
     ),
     modifiers(&SecurityAddon),
     components(
@@ -94,15 +129,35 @@ impl Modify for SecurityAddon {
             crate::domain::rental::RentalBookingResponse,
             crate::domain::rental::ValidatePickupRequest,
             crate::domain::rental::UpdateRentalStatusRequest,
-            ValidateReturnRequest,
+            crate::domain::rental::ValidateReturnRequest,
+            crate::domain::handover::HandoverRequest,
+            crate::domain::handover::HandoverResponse,
+            crate::domain::return_report::CreateReturnReportRequest,
+            crate::domain::return_report::ReturnReportResponse,
+            crate::domain::return_report::DamageItem,
 
             // Test Drive
             crate::domain::testdrive::CreateTestDriveRequest,
             crate::domain::testdrive::TestDriveBookingResponse,
             crate::domain::testdrive::RescheduleTestDriveRequest,
+            crate::domain::testdrive::RescheduleSlot,
+            crate::domain::testdrive::TestDriveLocationProposal,
             crate::domain::testdrive::ChooseRescheduleSlotRequest,
             crate::domain::testdrive::ConfirmTestDriveRequest,
             crate::domain::testdrive::CompleteTestDriveRequest,
+            crate::domain::testdrive::BulkAcceptTestDriveRequest,
+            crate::domain::testdrive::BulkRejectTestDriveRequest,
+            crate::domain::testdrive::BulkTestDriveResult,
+            crate::domain::testdrive::BulkTestDriveResponse,
+            crate::domain::testdrive::SetBusinessHoursRequest,
+            crate::domain::testdrive::BusinessHoursResponse,
+            crate::domain::testdrive::WeeklySlotInput,
+            crate::domain::testdrive::AvailabilityExceptionInput,
+            crate::domain::testdrive::SetAvailabilityRequest,
+            crate::domain::testdrive::AvailabilityEntry,
+            crate::domain::testdrive::AvailabilityResponse,
+            crate::domain::testdrive::OpenSlot,
+            crate::domain::testdrive::SellerSlotsResponse,
 
             // Sale Orders
             CreateSaleOrderRequest,
@@ -114,13 +169,38 @@ impl Modify for SecurityAddon {
             crate::domain::sale::StartDocumentTransferRequest,
             UpdateDocumentStatusRequest,
             UploadKtpRequest,
-            SaleOrderQueryParams
+            SaleOrderQueryParams,
+            crate::domain::sale::SaleOrderListResponse,
+            crate::utils::order_tracking::OrderTrackingResponse,
+            crate::utils::order_tracking::TrackingStep,
+            crate::domain::document_checklist::ChecklistItem,
+            crate::domain::document_checklist::DocumentItem,
+            crate::domain::document_checklist::DocumentProgress,
+            crate::domain::document_checklist::SetDocumentChecklistRequest,
+            crate::domain::document_checklist::DocumentChecklistResponse,
+
+            // Calendar
+            crate::domain::calendar::CalendarEvent,
+            crate::domain::calendar::CalendarResponse,
+
+            // Outbound Webhooks
+            crate::domain::webhook::CreateWebhookRequest,
+            crate::domain::webhook::WebhookResponse,
+            crate::domain::webhook::WebhookDelivery,
+
+            // Buyer Blocklist
+            crate::domain::buyer_block::BlockBuyerRequest,
+            crate::domain::buyer_block::BlockedBuyer
         )
     ),
     tags(
         (name = "rental-bookings", description = "Manajemen booking rental mobil"),
         (name = "testdrive-bookings", description = "Manajemen booking test drive"),
-        (name = "sale-orders", description = "Manajemen order pembelian mobil")
+        (name = "sale-orders", description = "Manajemen order pembelian mobil"),
+        (name = "calendar", description = "Calendar gabungan booking customer"),
+        (name = "files", description = "Dokumen privat (KTP/SIM) lewat signed URL"),
+        (name = "webhooks", description = "Outbound webhook event order/payment/test drive untuk seller"),
+        (name = "buyer-blocks", description = "Blocklist buyer per seller untuk order dan test drive")
     ),
     info(
         title = "BIG AUTO - Booking Service API",
@@ -147,18 +227,13 @@ pub struct ApiDoc;
 
 // Health check endpoint
 async fn health_check(State(pool): State<sqlx::PgPool>) -> Json<HealthStatus> {
-    let db_healthy = check_db_health(&pool).await;
-
-    Json(HealthStatus {
-        database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-        overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-    })
+    Json(HealthStatus::check(&pool).await)
 }
 
 // JWT-Only CORS configuration
 fn configure_cors() -> CorsLayer {
-    let frontend_url = env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable harus diset");
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     let allowed_methods = vec![
         Method::GET,
@@ -174,11 +249,10 @@ fn configure_cors() -> CorsLayer {
         header::CONTENT_TYPE,
     ];
 
-    CorsLayer::new()
-        .allow_origin(frontend_url.parse::<HeaderValue>().expect("Invalid FRONTEND_URL"))
+    policy
+        .apply(CorsLayer::new())
         .allow_methods(allowed_methods)
         .allow_headers(allowed_headers)
-        .allow_credentials(false)
         .max_age(std::time::Duration::from_secs(86400))
 }
 
@@ -219,6 +293,12 @@ fn build_api_routes_with_auth(state: AppState) -> Router {
         .route("/rentals/bookings/{id}/status", put(rental_handlers::update_rental_booking_status))
         .route("/rentals/bookings/{id}/validate-pickup", put(rental_handlers::validate_pickup))
         .route("/rentals/bookings/{id}/validate-return", put(rental_handlers::validate_return))
+        .route("/rentals/bookings/{id}/checkin", post(rental_handlers::check_in_rental))
+        .route("/rentals/bookings/{id}/checkout", post(rental_handlers::check_out_rental))
+        .route(
+            "/rentals/bookings/{id}/return-report",
+            post(rental_handlers::create_return_report).get(rental_handlers::get_return_report),
+        )
 
         // Test Drive Bookings - All endpoints
         .route("/testdrives/bookings", get(testdrive_handlers::get_customer_testdrive_bookings))
@@ -226,6 +306,8 @@ fn build_api_routes_with_auth(state: AppState) -> Router {
         .route("/testdrives/bookings/seller", get(testdrive_handlers::get_seller_testdrive_bookings))
         .route("/testdrives/bookings/{id}", get(testdrive_handlers::get_testdrive_booking))
         .route("/testdrives/bookings", post(testdrive_handlers::create_testdrive_booking))
+        .route("/testdrives/bookings/bulk-accept", post(testdrive_handlers::bulk_accept_testdrive_bookings))
+        .route("/testdrives/bookings/bulk-reject", post(testdrive_handlers::bulk_reject_testdrive_bookings))
         .route("/testdrives/bookings/{id}/accept", put(testdrive_handlers::accept_testdrive_booking))
         .route("/testdrives/bookings/{id}/reschedule", put(testdrive_handlers::reschedule_testdrive_booking))
         .route("/testdrives/bookings/{id}/choose-slot", put(testdrive_handlers::choose_reschedule_slot))
@@ -233,6 +315,15 @@ fn build_api_routes_with_auth(state: AppState) -> Router {
         .route("/testdrives/bookings/{id}/complete", put(testdrive_handlers::complete_testdrive_booking))
         .route("/testdrives/bookings/{id}/cancel", put(testdrive_handlers::cancel_testdrive_booking))
         .route("/testdrives/timeout-expired", post(testdrive_handlers::timeout_expired_testdrives))
+        .route(
+            "/testdrives/business-hours",
+            get(testdrive_handlers::get_business_hours).put(testdrive_handlers::set_business_hours),
+        )
+        .route(
+            "/testdrives/availability",
+            get(testdrive_handlers::get_availability).put(testdrive_handlers::set_availability),
+        )
+        .route("/testdrives/sellers/{id}/slots", get(testdrive_handlers::get_seller_slots))
 
         // Sale Orders - All endpoints
         .route("/sales/orders/my", get(sale_handlers::get_customer_sale_orders))
@@ -249,7 +340,39 @@ fn build_api_routes_with_auth(state: AppState) -> Router {
         .route("/sales/orders/{id}/start-documents", put(sale_handlers::start_document_transfer))
         .route("/sales/orders/{id}/update-documents", put(sale_handlers::update_document_status))
         .route("/sales/orders/{id}/confirm-documents", put(sale_handlers::confirm_documents_received))
+        .route("/sales/orders/{id}/invoice.pdf", get(sale_handlers::download_sale_invoice))
+        .route(
+            "/sales/document-checklist",
+            get(document_checklist_handlers::get_document_checklist)
+                .put(document_checklist_handlers::set_document_checklist),
+        )
+
+        // Calendar - test drive & rental customer
+        .route("/bookings/calendar", get(calendar_handlers::get_booking_calendar))
+
+        // Private Files - signed URL dokumen KTP/SIM
+        .route("/files/{token}", get(file_handlers::get_private_file))
+
+        // Outbound Webhooks - endpoint back-office dealer milik seller
+        .route("/webhooks", get(webhook_handlers::list_webhooks).post(webhook_handlers::create_webhook))
+        .route("/webhooks/{id}", get(webhook_handlers::get_webhook))
+        .route("/webhooks/{id}/rotate-secret", put(webhook_handlers::rotate_webhook_secret))
+        .route("/webhooks/{id}/disable", put(webhook_handlers::disable_webhook))
+        .route("/webhooks/{id}/deliveries", get(webhook_handlers::list_webhook_deliveries))
+
+        // Buyer Blocklist - seller menolak transaksi dari buyer bermasalah
+        .route(
+            "/blocked-buyers",
+            get(buyer_block_handlers::list_blocked_buyers).post(buyer_block_handlers::block_buyer),
+        )
+        .route(
+            "/blocked-buyers/{buyer_id}",
+            get(buyer_block_handlers::get_blocked_buyer).delete(buyer_block_handlers::unblock_buyer),
+        )
         .layer(axum::middleware::from_fn_with_state(state.clone(), jwt_auth_middleware))
+
+        // Public order tracking - didaftarkan setelah JWT layer, dibatasi per IP di rate limiter
+        .route("/sales/track/{reference}", get(sale_handlers::track_sale_order))
         .with_state(state);
 
     api_routes
@@ -1,8 +1,29 @@
 use crate::config::AppState;
 use crate::domain::rental::RentalBooking;
+use crate::domain::sale::SaleOrder;
+use crate::domain::testdrive::TestDriveBooking;
+use crate::repositories::sale_repo;
+use crate::repositories::webhook_repo::{self, PendingDelivery};
+use crate::utils::outbound_webhook::{self, MAX_DELIVERY_ATTEMPTS};
+use shared::utils::scheduler::{run_job, spawn_job, JobSchedule, Ticker};
+use shared::utils::webhook_signature::{self, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use std::convert::Infallible;
 use std::time::Duration;
 
-/// Background scheduler for booking service cleanup and maintenance
+// Jumlah delivery webhook per batch worker
+const WEBHOOK_BATCH_SIZE: i64 = 50;
+
+// Timeout request ke endpoint seller
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Lease klaim delivery agar tidak dikirim ganda oleh instance lain; batch dikirim berurutan,
+// jadi lease harus menutup kasus terburuk semua delivery timeout (+ margin untuk update DB)
+const WEBHOOK_LEASE_SECS: i64 = WEBHOOK_BATCH_SIZE * WEBHOOK_REQUEST_TIMEOUT.as_secs() as i64 + 60;
+
+// Jumlah event pembayaran sale order per tick
+const SALE_PAYMENT_BATCH_SIZE: i64 = 100;
+
+/// Background scheduler for booking service cleanup, maintenance, and outbound webhooks
 pub struct BookingScheduler {
     state: AppState,
 }
@@ -22,105 +43,182 @@ impl BookingScheduler {
 
         tracing::info!("🚗 Starting Booking Service Background Scheduler...");
 
-        // Spawn task for booking maintenance tasks
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(2400)); // Every 40 minutes
-
-            loop {
-                interval.tick().await;
-
-                tracing::info!("🧹 Running booking service cleanup tasks...");
-
-                // Cancel expired pending payment bookings (older than 1 hour)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match RentalBooking::cleanup_expired_pending_payments(&db).await {
-                            Ok(cancelled) => {
-                                if cancelled > 0 {
-                                    tracing::info!("✅ Cancelled {} expired pending payment bookings", cancelled);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup expired pending payments after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
-
-                // Auto-complete overdue rentals (where return date has passed)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match RentalBooking::auto_complete_overdue_rentals(&db).await {
-                            Ok(completed) => {
-                                if completed > 0 {
-                                    tracing::info!("✅ Auto-completed {} overdue rentals", completed);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to auto-complete overdue rentals after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
-
-                // Update booking status from "akan datang" to "berjalan" when pickup date arrives
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match RentalBooking::update_pickup_ready_bookings(&db).await {
-                            Ok(updated) => {
-                                if updated > 0 {
-                                    tracing::info!("✅ Updated {} bookings to 'berjalan' status", updated);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to update pickup ready bookings after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
-
-                // Cleanup very old completed/cancelled bookings (older than 180 days)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match RentalBooking::cleanup_old_bookings(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} old bookings", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup old bookings after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
-
-                tracing::info!("✅ Booking service cleanup tasks completed");
+        // Cek SLA respon seller (lebih sering dari cleanup), default every 5 minutes
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("BOOKING_SLA_INTERVAL_SECS", 300)),
+            self.state.clone(),
+            |state| async move { check_seller_sla(&state).await },
+        );
+
+        // Reminder test drive yang akan datang (beserta lokasi), default every 10 minutes
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("BOOKING_REMINDER_INTERVAL_SECS", 600)),
+            self.state.clone(),
+            |state| async move { send_testdrive_reminders(&state).await },
+        );
+
+        // Sale order lunas dari event payment-service, default every 15 seconds
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("BOOKING_SALE_PAYMENT_INTERVAL_SECS", 15)),
+            self.state.clone(),
+            |state| async move { apply_sale_payments(&state).await },
+        );
+
+        // Kirim outbound webhook seller (retry dengan backoff), default every 15 seconds
+        match outbound_webhook::delivery_client(WEBHOOK_REQUEST_TIMEOUT) {
+            Ok(client) => {
+                spawn_job(
+                    Ticker::interval(JobSchedule::from_env("BOOKING_WEBHOOK_INTERVAL_SECS", 15)),
+                    (self.state.clone(), client),
+                    |(state, client)| async move { deliver_webhooks(&state, &client).await },
+                );
             }
-        });
+            Err(e) => tracing::error!("❌ Outbound webhook delivery disabled: {}", e),
+        }
+
+        // Booking maintenance tasks, default every 40 minutes
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("BOOKING_CLEANUP_INTERVAL_SECS", 2400)),
+            self.state,
+            |state| async move { run_cleanup(&state).await },
+        );
     }
-}
\ No newline at end of file
+}
+
+// Satu tick cek SLA respon seller
+async fn check_seller_sla(state: &AppState) {
+    let _ = run_job("Flag sale orders breaching seller response SLA", 1, || {
+        SaleOrder::flag_sla_breaches(&state.db, state.config.seller_sla_minutes)
+    })
+    .await;
+}
+
+// Satu tick reminder test drive
+async fn send_testdrive_reminders(state: &AppState) {
+    let _ = run_job("Send test drive reminder notifications", 1, || {
+        TestDriveBooking::send_upcoming_reminders(&state.db, state.config.testdrive_reminder_hours)
+    })
+    .await;
+}
+
+// Satu tick event pembayaran sale order
+async fn apply_sale_payments(state: &AppState) {
+    let _ = run_job("Mark sale orders paid from payment events", 1, || async {
+        sale_repo::apply_payment_events(&state.db, SALE_PAYMENT_BATCH_SIZE)
+            .await
+            .map_err(|e| format!("{:?}", e))
+    })
+    .await;
+}
+
+// Satu tick pengiriman outbound webhook
+async fn deliver_webhooks(state: &AppState, client: &reqwest::Client) {
+    let _ = run_job("Deliver outbound webhooks", 1, || async {
+        let (delivered, failed) = deliver_due_webhooks(state, client).await;
+        if delivered > 0 || failed > 0 {
+            tracing::info!("📤 Webhook deliveries: {} delivered, {} failed attempts", delivered, failed);
+        }
+        Ok::<u64, Infallible>((delivered + failed) as u64)
+    })
+    .await;
+}
+
+// Satu tick cleanup booking
+async fn run_cleanup(state: &AppState) {
+    tracing::info!("🧹 Running booking service cleanup tasks...");
+
+    // Cancel expired pending payment bookings (older than 1 hour)
+    let _ = run_job("Cancel expired pending payment bookings", 3, || {
+        RentalBooking::cleanup_expired_pending_payments(&state.db)
+    })
+    .await;
+
+    // Auto-complete overdue rentals (where return date has passed)
+    let _ = run_job("Auto-complete overdue rentals", 3, || {
+        RentalBooking::auto_complete_overdue_rentals(&state.db)
+    })
+    .await;
+
+    // Update booking status from "akan_datang" to "berjalan" when pickup date arrives
+    let _ = run_job("Update pickup ready bookings to berjalan", 3, || {
+        RentalBooking::update_pickup_ready_bookings(&state.db)
+    })
+    .await;
+
+    // Cleanup very old completed/cancelled bookings (older than 180 days)
+    let _ = run_job("Cleanup old bookings", 3, || RentalBooking::cleanup_old_bookings(&state.db)).await;
+
+    tracing::info!("✅ Booking service cleanup tasks completed");
+}
+
+// Kirim delivery webhook yang jatuh tempo, return (berhasil, gagal)
+async fn deliver_due_webhooks(state: &AppState, client: &reqwest::Client) -> (usize, usize) {
+    let deliveries = match webhook_repo::claim_due_deliveries(&state.db, WEBHOOK_BATCH_SIZE, WEBHOOK_LEASE_SECS).await {
+        Ok(deliveries) => deliveries,
+        Err(e) => {
+            tracing::error!("❌ Failed to claim webhook deliveries: {:?}", e);
+            return (0, 0);
+        }
+    };
+
+    let mut delivered = 0;
+    let mut failed = 0;
+
+    for delivery in deliveries {
+        let result = match send_webhook(client, &delivery).await {
+            Ok(status) => {
+                delivered += 1;
+                webhook_repo::mark_delivered(&state.db, delivery.id, status).await
+            }
+            Err((status, error)) => {
+                failed += 1;
+                let attempts = delivery.attempts + 1;
+                let retry_at = (attempts < MAX_DELIVERY_ATTEMPTS)
+                    .then(|| chrono::Utc::now() + outbound_webhook::retry_backoff(attempts));
+
+                if retry_at.is_none() {
+                    tracing::warn!("⚠️ Webhook delivery {} gave up after {} attempts: {}", delivery.id, attempts, error);
+                }
+
+                webhook_repo::mark_attempt_failed(&state.db, delivery.id, status, &error, retry_at).await
+            }
+        };
+
+        if let Err(e) = result {
+            tracing::error!("❌ Failed to record webhook delivery {}: {:?}", delivery.id, e);
+        }
+    }
+
+    (delivered, failed)
+}
+
+// POST payload bertanda tangan, Err berisi status HTTP (jika ada) dan alasan gagal
+async fn send_webhook(
+    http_client: &reqwest::Client,
+    delivery: &PendingDelivery,
+) -> Result<i32, (Option<i32>, String)> {
+    let url = outbound_webhook::check_delivery_url(&delivery.url).map_err(|e| (None, e))?;
+
+    let body = delivery.payload.to_string();
+    let timestamp = chrono::Utc::now().timestamp();
+    let signature = webhook_signature::sign_webhook(delivery.secret.as_bytes(), timestamp, body.as_bytes());
+
+    let response = http_client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = response.status().as_u16();
+    if outbound_webhook::is_delivered(status) {
+        Ok(i32::from(status))
+    } else {
+        Err((Some(i32::from(status)), format!("Endpoint merespon HTTP {}", status)))
+    }
+}
@@ -0,0 +1,157 @@
+// Jam operasional test drive per seller
+//
+// Jam buka/tutup disimpan sebagai jam lokal seller dan dievaluasi di timezone IANA seller.
+// Seller yang belum mengatur jam operasional memakai default global dari config.
+// Jam slot ("HH:MM") adalah jam lokal seller, harinya diambil dari tanggal slot di timezone seller.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use shared::utils::clock::{parse_clock, resolve_local};
+use shared::utils::validation::FieldError;
+
+// Durasi satu sesi test drive, slot yang lebih rapat dianggap bentrok
+pub const SLOT_DURATION_MINUTES: i64 = 60;
+
+// Default global jika env tidak diset
+pub const DEFAULT_OPEN_TIME: &str = "08:00";
+pub const DEFAULT_CLOSE_TIME: &str = "18:00";
+pub const DEFAULT_TIMEZONE: &str = "Asia/Jakarta";
+
+// Jam operasional test drive: sesi mulai paling awal `open` dan selesai paling lambat `close`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusinessHours {
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+    pub timezone: Tz,
+}
+
+impl BusinessHours {
+    // Validasi input jam operasional, semua field yang salah dikembalikan sekaligus
+    pub fn parse(open: &str, close: &str, timezone: &str) -> Result<Self, Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        let open = parse_clock(open);
+        if open.is_none() {
+            errors.push(FieldError::new("open_time", "open_time harus berformat HH:MM"));
+        }
+
+        let close = parse_clock(close);
+        if close.is_none() {
+            errors.push(FieldError::new("close_time", "close_time harus berformat HH:MM"));
+        }
+
+        let timezone = timezone.trim().parse::<Tz>().ok();
+        if timezone.is_none() {
+            errors.push(FieldError::new("timezone", "Timezone tidak dikenal, gunakan nama IANA seperti Asia/Jakarta"));
+        }
+
+        match (open, close, timezone) {
+            (Some(open), Some(close), Some(timezone)) if errors.is_empty() => {
+                Self::from_stored(open, close, timezone.name()).ok_or_else(|| {
+                    vec![FieldError::new(
+                        "close_time",
+                        format!("close_time minimal {} menit setelah open_time", SLOT_DURATION_MINUTES),
+                    )]
+                })
+            }
+            _ => Err(errors),
+        }
+    }
+
+    // Dari kolom seller_testdrive_hours, None jika data tidak valid
+    pub fn from_stored(open: NaiveTime, close: NaiveTime, timezone: &str) -> Option<Self> {
+        let timezone = timezone.parse::<Tz>().ok()?;
+        (close - open >= Duration::minutes(SLOT_DURATION_MINUTES)).then_some(Self { open, close, timezone })
+    }
+
+    // Jam mulai paling lambat agar sesi selesai sebelum tutup
+    pub fn last_start(&self) -> NaiveTime {
+        self.close - Duration::minutes(SLOT_DURATION_MINUTES)
+    }
+
+    // Awal sesi dalam UTC untuk slot di hari `date` (timezone seller) jam lokal `time`
+    pub fn slot_start(&self, date: DateTime<Utc>, time: &str) -> Result<DateTime<Utc>, String> {
+        let start = parse_clock(time).ok_or("format jam harus HH:MM")?;
+        self.start_on(self.local_date(date), start)
+    }
+
+    // Awal sesi dalam UTC untuk tanggal lokal seller `day` jam lokal `start`
+    pub fn start_on(&self, day: NaiveDate, start: NaiveTime) -> Result<DateTime<Utc>, String> {
+        if !self.contains(start) {
+            return Err(format!(
+                "jam test drive harus antara {} dan {} ({})",
+                format_clock(self.open),
+                format_clock(self.last_start()),
+                self.timezone.name()
+            ));
+        }
+
+        Ok(resolve_local(&self.timezone, day, start))
+    }
+
+    // true jika sesi yang mulai jam `start` muat dalam jam operasional
+    pub fn contains(&self, start: NaiveTime) -> bool {
+        start >= self.open && start <= self.last_start()
+    }
+
+    // Tanggal di timezone seller
+    pub fn local_date(&self, date: DateTime<Utc>) -> NaiveDate {
+        date.with_timezone(&self.timezone).date_naive()
+    }
+}
+
+pub fn format_clock(time: NaiveTime) -> String {
+    time.format("%H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_slot_boundaries() {
+        // 08:00-18:00 WIB (UTC+7), sesi terakhir mulai 17:00
+        let hours = BusinessHours::parse("08:00", "18:00", "Asia/Jakarta").unwrap();
+        let day = at("2026-03-10T03:00:00Z");
+
+        assert_eq!(hours.slot_start(day, "08:00"), Ok(at("2026-03-10T01:00:00Z")));
+        assert_eq!(hours.slot_start(day, "17:00"), Ok(at("2026-03-10T10:00:00Z")));
+
+        let err = hours.slot_start(day, "07:59").unwrap_err();
+        assert!(err.contains("08:00 dan 17:00"));
+        assert!(hours.slot_start(day, "17:01").is_err());
+        assert!(hours.slot_start(day, "18:00").is_err());
+        assert_eq!(hours.slot_start(day, "8:00"), hours.slot_start(day, "08:00"));
+        assert!(hours.slot_start(day, "8").is_err());
+    }
+
+    #[test]
+    fn test_outside_seller_hours_in_seller_timezone() {
+        // Seller di Jayapura (UTC+9), buka 09:00-15:00
+        let hours = BusinessHours::parse("09:00", "15:00", "Asia/Jayapura").unwrap();
+
+        // 20:00 UTC tanggal 10 sudah tanggal 11 di Jayapura
+        assert_eq!(hours.slot_start(at("2026-03-10T20:00:00Z"), "09:00"), Ok(at("2026-03-11T00:00:00Z")));
+        assert_eq!(hours.slot_start(at("2026-03-10T20:00:00Z"), "14:00"), Ok(at("2026-03-11T05:00:00Z")));
+
+        // Masih dalam jam default global, tapi di luar jam seller
+        assert!(hours.slot_start(at("2026-03-10T20:00:00Z"), "08:00").is_err());
+        assert!(hours.slot_start(at("2026-03-10T20:00:00Z"), "14:30").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_hours() {
+        let errors = BusinessHours::parse("8", "25:00", "Mars/Olympus").unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["open_time", "close_time", "timezone"]);
+
+        // Tutup sebelum buka, atau kurang dari satu sesi
+        assert!(BusinessHours::parse("17:00", "09:00", "Asia/Jakarta").is_err());
+        assert!(BusinessHours::parse("09:00", "09:30", "Asia/Jakarta").is_err());
+        assert!(BusinessHours::parse("09:00", "10:00", "Asia/Jakarta").is_ok());
+    }
+}
@@ -0,0 +1,153 @@
+// Validasi check-in / check-out rental, transisi status, dan denda keterlambatan
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    domain::{
+        handover::{HandoverKind, HandoverRequest},
+        rental::RentalStatus,
+    },
+    error::AppError,
+};
+
+// Batas foto kondisi kendaraan per serah terima
+const MAX_HANDOVER_PHOTOS: usize = 10;
+
+// Toleransi keterlambatan pengembalian sebelum denda dihitung
+pub const LATE_RETURN_GRACE_MINUTES: i64 = 60;
+
+// Status rental setelah serah terima: check-in dari paid/akan_datang ke berjalan,
+// check-out dari berjalan ke selesai. Rental yang sudah berjalan lewat validate-pickup
+// (atau selesai lewat validate-return/scheduler) tetap bisa dicatat serah terimanya
+pub fn next_status(kind: HandoverKind, current: &str) -> Result<RentalStatus, AppError> {
+    match (kind, RentalStatus::from_str(current)) {
+        (HandoverKind::CheckIn, Some(RentalStatus::Paid | RentalStatus::AkanDatang | RentalStatus::Berjalan)) => {
+            Ok(RentalStatus::Berjalan)
+        }
+        (HandoverKind::CheckOut, Some(RentalStatus::Berjalan | RentalStatus::Selesai)) => Ok(RentalStatus::Selesai),
+        (HandoverKind::CheckIn, _) => Err(AppError::bad_request(
+            "Check-in hanya untuk rental yang sudah dibayar",
+        )),
+        (HandoverKind::CheckOut, _) => Err(AppError::bad_request(
+            "Check-out hanya untuk rental yang sudah berjalan",
+        )),
+    }
+}
+
+// Validasi data serah terima, checkin_odometer diisi saat check-out
+pub fn validate_handover(payload: &HandoverRequest, checkin_odometer: Option<i32>) -> Result<(), AppError> {
+    if payload.odometer_km < 0 {
+        return Err(AppError::validation("Odometer tidak boleh negatif"));
+    }
+
+    if !(0..=100).contains(&payload.fuel_level) {
+        return Err(AppError::validation("Fuel level harus antara 0-100"));
+    }
+
+    if payload.photos.is_empty() {
+        return Err(AppError::validation("Minimal satu foto kondisi kendaraan"));
+    }
+
+    if payload.photos.len() > MAX_HANDOVER_PHOTOS {
+        return Err(AppError::validation(format!(
+            "Maksimal {} foto per serah terima",
+            MAX_HANDOVER_PHOTOS
+        )));
+    }
+
+    match checkin_odometer {
+        Some(checkin_km) => check_odometer(checkin_km, payload.odometer_km),
+        None => Ok(()),
+    }
+}
+
+// Odometer tidak boleh mundur dari angka saat check-in
+pub fn check_odometer(checkin_km: i32, current_km: i32) -> Result<(), AppError> {
+    if current_km < checkin_km {
+        return Err(AppError::validation(format!(
+            "Odometer tidak boleh lebih kecil dari saat check-in ({} km)",
+            checkin_km
+        )));
+    }
+
+    Ok(())
+}
+
+// Denda keterlambatan: tarif harian per hari terlambat (dibulatkan ke atas),
+// tidak ada denda jika kembali dalam toleransi
+pub fn late_fee(price_per_day: f64, return_date: DateTime<Utc>, returned_at: DateTime<Utc>) -> i64 {
+    let late = returned_at - return_date;
+    if late <= Duration::minutes(LATE_RETURN_GRACE_MINUTES) {
+        return 0;
+    }
+
+    let late_days = (late.num_seconds() + 86_399) / 86_400;
+    (price_per_day * late_days as f64).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handover(odometer_km: i32) -> HandoverRequest {
+        HandoverRequest {
+            odometer_km,
+            fuel_level: 80,
+            photos: vec!["https://res.cloudinary.com/bigauto/image/upload/v1/handover/front.jpg".to_string()],
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_full_handover_lifecycle() {
+        // Belum dibayar: tidak bisa check-in, dan tidak bisa check-out sebelum berjalan
+        assert!(next_status(HandoverKind::CheckIn, "pending_payment").is_err());
+        assert!(next_status(HandoverKind::CheckOut, "paid").is_err());
+
+        // Check-in: paid -> berjalan
+        let checkin = handover(45_000);
+        assert!(validate_handover(&checkin, None).is_ok());
+        let status = next_status(HandoverKind::CheckIn, "paid").unwrap();
+        assert_eq!(status, RentalStatus::Berjalan);
+        // Sudah berjalan lewat validate-pickup: check-in tetap dicatat tanpa ubah status
+        assert_eq!(next_status(HandoverKind::CheckIn, status.as_str()).unwrap(), RentalStatus::Berjalan);
+
+        // Check-out: odometer harus naik atau sama, berjalan -> selesai
+        assert!(validate_handover(&handover(44_999), Some(checkin.odometer_km)).is_err());
+        assert!(validate_handover(&handover(45_320), Some(checkin.odometer_km)).is_ok());
+        let status = next_status(HandoverKind::CheckOut, status.as_str()).unwrap();
+        assert_eq!(status, RentalStatus::Selesai);
+
+        // Selesai lewat validate-return: check-out masih bisa dicatat, check-in tidak
+        assert_eq!(next_status(HandoverKind::CheckOut, status.as_str()).unwrap(), RentalStatus::Selesai);
+        assert!(next_status(HandoverKind::CheckIn, status.as_str()).is_err());
+        assert!(next_status(HandoverKind::CheckIn, "cancelled").is_err());
+        assert!(next_status(HandoverKind::CheckOut, "cancelled").is_err());
+    }
+
+    #[test]
+    fn test_invalid_handover_rejected() {
+        let mut invalid = handover(45_000);
+        invalid.fuel_level = 101;
+        assert!(validate_handover(&invalid, None).is_err());
+
+        let mut invalid = handover(-1);
+        invalid.fuel_level = 50;
+        assert!(validate_handover(&invalid, None).is_err());
+
+        let mut invalid = handover(45_000);
+        invalid.photos.clear();
+        assert!(validate_handover(&invalid, None).is_err());
+    }
+
+    #[test]
+    fn test_late_fee_after_grace_period() {
+        let return_date = Utc::now();
+        let price_per_day = 500_000.0;
+
+        assert_eq!(late_fee(price_per_day, return_date, return_date - Duration::hours(2)), 0);
+        assert_eq!(late_fee(price_per_day, return_date, return_date + Duration::minutes(LATE_RETURN_GRACE_MINUTES)), 0);
+        assert_eq!(late_fee(price_per_day, return_date, return_date + Duration::hours(3)), 500_000);
+        assert_eq!(late_fee(price_per_day, return_date, return_date + Duration::hours(25)), 1_000_000);
+    }
+}
@@ -0,0 +1,171 @@
+// Render calendar booking ke format iCalendar (RFC 5545)
+use chrono::{DateTime, Duration, Utc};
+use std::fmt::Write as _;
+
+use crate::domain::calendar::CalendarEvent;
+
+// Durasi default event test drive (tidak punya jam selesai)
+const TEST_DRIVE_DURATION_MINUTES: i64 = 60;
+
+// Panjang baris maksimal sebelum di-fold
+const MAX_LINE_OCTETS: usize = 75;
+
+// Render semua event menjadi dokumen VCALENDAR
+pub fn render_calendar(events: &[CalendarEvent], generated_at: DateTime<Utc>) -> String {
+    let mut output = String::new();
+    push_line(&mut output, "BEGIN:VCALENDAR");
+    push_line(&mut output, "VERSION:2.0");
+    push_line(&mut output, "PRODID:-//Big Auto//Booking Calendar//ID");
+    push_line(&mut output, "CALSCALE:GREGORIAN");
+
+    for event in events {
+        let (label, ends_at) = match event.event_type.as_str() {
+            "rental" => ("Rental", event.ends_at.unwrap_or(event.starts_at)),
+            _ => ("Test Drive", event.starts_at + Duration::minutes(TEST_DRIVE_DURATION_MINUTES)),
+        };
+
+        push_line(&mut output, "BEGIN:VEVENT");
+        push_line(&mut output, &format!("UID:{}-{}@bigauto", event.event_type, event.booking_id));
+        push_line(&mut output, &format!("DTSTAMP:{}", format_datetime(generated_at)));
+        push_line(&mut output, &format!("DTSTART:{}", format_datetime(event.starts_at)));
+        push_line(&mut output, &format!("DTEND:{}", format_datetime(ends_at)));
+        push_line(&mut output, &format!("SUMMARY:{}", escape_text(&format!("{}: {}", label, event.vehicle_title))));
+        push_line(&mut output, &format!(
+            "DESCRIPTION:{}",
+            escape_text(&format!("Seller: {}\nStatus: {}", event.seller_name, event.status))
+        ));
+        if let Some(location) = &event.location {
+            push_line(&mut output, &format!("LOCATION:{}", escape_text(location)));
+        }
+        if let (Some(lat), Some(lng)) = (event.lat, event.lng) {
+            push_line(&mut output, &format!("GEO:{:.6};{:.6}", lat, lng));
+        }
+        push_line(&mut output, &format!("STATUS:{}", event_status(&event.status)));
+        push_line(&mut output, "END:VEVENT");
+    }
+
+    push_line(&mut output, "END:VCALENDAR");
+    output
+}
+
+// Format UTC: 20260315T100000Z
+fn format_datetime(value: DateTime<Utc>) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Mapping status booking ke STATUS iCalendar
+fn event_status(status: &str) -> &'static str {
+    match status {
+        "cancelled" | "timeout" => "CANCELLED",
+        "menunggu_konfirmasi" | "seller_reschedule" | "pending_payment" => "TENTATIVE",
+        _ => "CONFIRMED",
+    }
+}
+
+// Escape karakter khusus TEXT value iCalendar
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Tulis satu content line dengan CRLF, fold jika lebih dari 75 octet
+fn push_line(output: &mut String, line: &str) {
+    let mut current_len = 0;
+    for c in line.chars() {
+        if current_len + c.len_utf8() > MAX_LINE_OCTETS {
+            output.push_str("\r\n ");
+            current_len = 1;
+        }
+        output.push(c);
+        current_len += c.len_utf8();
+    }
+    let _ = write!(output, "\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample_event(event_type: &str, status: &str) -> CalendarEvent {
+        CalendarEvent {
+            event_type: event_type.to_string(),
+            booking_id: 12,
+            starts_at: Utc.with_ymd_and_hms(2026, 3, 15, 10, 0, 0).unwrap(),
+            ends_at: Some(Utc.with_ymd_and_hms(2026, 3, 18, 10, 0, 0).unwrap()),
+            requested_time: None,
+            status: status.to_string(),
+            vehicle_id: 3,
+            vehicle_title: "Toyota Avanza, 2022".to_string(),
+            seller_id: 5,
+            seller_name: "Auto Jaya".to_string(),
+            location: None,
+            lat: None,
+            lng: None,
+        }
+    }
+
+    #[test]
+    fn test_render_rental_event() {
+        let generated_at = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let ics = render_calendar(&[sample_event("rental", "paid")], generated_at);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:rental-12@bigauto\r\n"));
+        assert!(ics.contains("DTSTART:20260315T100000Z\r\n"));
+        assert!(ics.contains("DTEND:20260318T100000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Rental: Toyota Avanza\\, 2022\r\n"));
+        assert!(ics.contains("STATUS:CONFIRMED\r\n"));
+    }
+
+    #[test]
+    fn test_test_drive_gets_default_duration() {
+        let generated_at = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let ics = render_calendar(&[sample_event("test_drive", "menunggu_konfirmasi")], generated_at);
+
+        assert!(ics.contains("DTEND:20260315T110000Z\r\n"));
+        assert!(ics.contains("STATUS:TENTATIVE\r\n"));
+        assert!(!ics.contains("LOCATION:"));
+    }
+
+    #[test]
+    fn test_test_drive_location_is_exported() {
+        let generated_at = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let mut event = sample_event("test_drive", "diterima");
+        event.location = Some("Jl. Sudirman No. 1, Jakarta".to_string());
+        event.lat = Some(-6.208763);
+        event.lng = Some(106.845599);
+
+        let ics = render_calendar(&[event], generated_at);
+        assert!(ics.contains("LOCATION:Jl. Sudirman No. 1\\, Jakarta\r\n"));
+        assert!(ics.contains("GEO:-6.208763;106.845599\r\n"));
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a;b,c\\d\ne"), "a\\;b\\,c\\\\d\\ne");
+    }
+
+    #[test]
+    fn test_long_lines_are_folded() {
+        let mut output = String::new();
+        push_line(&mut output, &format!("SUMMARY:{}", "x".repeat(100)));
+
+        for line in output.split("\r\n").filter(|l| !l.is_empty()) {
+            assert!(line.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(output.replace("\r\n ", ""), format!("SUMMARY:{}\r\n", "x".repeat(100)));
+    }
+}
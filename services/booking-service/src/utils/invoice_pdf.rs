@@ -0,0 +1,128 @@
+// Render invoice (faktur) PDF untuk sale order yang completed
+use chrono::{DateTime, Utc};
+use shared::utils::pdf::PdfDocument;
+
+use crate::domain::{
+    invoice::{InvoiceDetails, NewSaleInvoice},
+    sale::SaleOrder,
+};
+
+// Format nomor invoice: INV/2026/10/000123
+pub fn format_invoice_number(sequence: i64, issued_at: DateTime<Utc>) -> String {
+    format!("INV/{}/{:06}", issued_at.format("%Y/%m"), sequence)
+}
+
+// Pisahkan harga (sudah termasuk pajak) menjadi subtotal dan pajak
+pub fn split_tax_inclusive(total: f64, tax_percentage: f64) -> (f64, f64) {
+    let subtotal = (total * 100.0 / (100.0 + tax_percentage) * 100.0).round() / 100.0;
+    let tax = ((total - subtotal) * 100.0).round() / 100.0;
+    (subtotal, tax)
+}
+
+// Format nominal ke Rupiah: Rp 250.000.000
+pub fn format_rupiah(amount: f64) -> String {
+    let rounded = amount.round() as i64;
+    let digits = rounded.abs().to_string();
+
+    let mut grouped = String::new();
+    for (index, c) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(c);
+    }
+
+    if rounded < 0 {
+        format!("-Rp {}", grouped)
+    } else {
+        format!("Rp {}", grouped)
+    }
+}
+
+// Hitung nominal dan render PDF invoice
+pub fn build_sale_invoice(
+    order: &SaleOrder,
+    details: &InvoiceDetails,
+    sequence: i64,
+    tax_percentage: f64,
+    issued_at: DateTime<Utc>,
+) -> NewSaleInvoice {
+    let invoice_number = format_invoice_number(sequence, issued_at);
+    let (subtotal, tax_amount) = split_tax_inclusive(order.final_price, tax_percentage);
+
+    let seller_display = details.seller_business_name
+        .clone()
+        .unwrap_or_else(|| details.seller_name.clone());
+
+    let pdf_data = PdfDocument::new(format!("Invoice {}", invoice_number))
+        .heading("BIG AUTO - INVOICE")
+        .line(format!("No. Invoice   : {}", invoice_number))
+        .line(format!("Tanggal       : {}", issued_at.format("%d-%m-%Y")))
+        .line(format!("No. Order     : {}", order.order_id))
+        .blank()
+        .section("Penjual")
+        .line(seller_display)
+        .line(format!("Email: {} | Telp: {}", details.seller_email, details.seller_phone))
+        .line(details.seller_address.clone().unwrap_or_default())
+        .blank()
+        .section("Pembeli")
+        .line(order.buyer_name.clone())
+        .line(format!("Email: {} | Telp: {}", order.buyer_email, order.buyer_phone))
+        .line(order.buyer_address.clone().unwrap_or_default())
+        .blank()
+        .section("Kendaraan")
+        .line(details.vehicle_title.clone())
+        .line(format!(
+            "{} {} ({})",
+            details.vehicle_brand, details.vehicle_model, details.vehicle_year
+        ))
+        .blank()
+        .section("Rincian Pembayaran")
+        .line(format!("Harga Jual (DPP)     : {}", format_rupiah(subtotal)))
+        .line(format!("PPN {:.0}%              : {}", tax_percentage, format_rupiah(tax_amount)))
+        .line(format!("Total                : {}", format_rupiah(order.final_price)))
+        .blank()
+        .line("Dokumen ini diterbitkan secara elektronik dan sah tanpa tanda tangan.")
+        .to_bytes();
+
+    NewSaleInvoice {
+        sale_order_id: order.id,
+        invoice_number,
+        subtotal,
+        tax_percentage,
+        tax_amount,
+        total_amount: order.final_price,
+        pdf_data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_invoice_number() {
+        let issued_at = Utc.with_ymd_and_hms(2026, 3, 5, 10, 0, 0).unwrap();
+        assert_eq!(format_invoice_number(42, issued_at), "INV/2026/03/000042");
+    }
+
+    #[test]
+    fn test_split_tax_inclusive() {
+        let (subtotal, tax) = split_tax_inclusive(111_000_000.0, 11.0);
+        assert_eq!(subtotal, 100_000_000.0);
+        assert_eq!(tax, 11_000_000.0);
+
+        let (subtotal, tax) = split_tax_inclusive(250_000_000.0, 0.0);
+        assert_eq!(subtotal, 250_000_000.0);
+        assert_eq!(tax, 0.0);
+    }
+
+    #[test]
+    fn test_format_rupiah() {
+        assert_eq!(format_rupiah(250_000_000.0), "Rp 250.000.000");
+        assert_eq!(format_rupiah(999.0), "Rp 999");
+        assert_eq!(format_rupiah(1_000.4), "Rp 1.000");
+        assert_eq!(format_rupiah(0.0), "Rp 0");
+    }
+}
@@ -1 +1,13 @@
-pub mod jwt;
+pub mod invoice_pdf;
+pub mod ics;
+pub mod negotiation;
+pub mod testdrive_location;
+pub mod return_report;
+pub mod private_file;
+pub mod outbound_webhook;
+pub mod testdrive_bulk;
+pub mod reschedule_slots;
+pub mod business_hours;
+pub mod handover;
+pub mod testdrive_slots;
+pub mod order_tracking;
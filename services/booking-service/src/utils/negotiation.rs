@@ -0,0 +1,52 @@
+// Aturan negosiasi harga sale order (batas ronde counter offer)
+
+use crate::error::AppError;
+
+// Sisa ronde counter offer, tidak pernah negatif
+pub fn counter_rounds_remaining(counter_round: i32, max_counter_rounds: i32) -> i32 {
+    (max_counter_rounds - counter_round).max(0)
+}
+
+// Tolak counter offer baru jika batas ronde sudah tercapai
+pub fn ensure_counter_allowed(counter_round: i32, max_counter_rounds: i32) -> Result<(), AppError> {
+    if counter_rounds_remaining(counter_round, max_counter_rounds) == 0 {
+        return Err(AppError::bad_request(format!(
+            "Batas {} kali counter offer sudah tercapai. Order hanya bisa diterima, dibatalkan, atau dibiarkan expire",
+            max_counter_rounds
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_rounds_remaining() {
+        assert_eq!(counter_rounds_remaining(0, 3), 3);
+        assert_eq!(counter_rounds_remaining(2, 3), 1);
+        assert_eq!(counter_rounds_remaining(3, 3), 0);
+        // Batas diturunkan setelah order sudah lewat batas baru
+        assert_eq!(counter_rounds_remaining(5, 3), 0);
+    }
+
+    #[test]
+    fn test_counter_rejected_when_cap_reached() {
+        let max_rounds = 3;
+        let mut round = 0;
+
+        while ensure_counter_allowed(round, max_rounds).is_ok() {
+            round += 1;
+        }
+
+        assert_eq!(round, max_rounds);
+        assert!(ensure_counter_allowed(round, max_rounds).is_err());
+    }
+
+    #[test]
+    fn test_zero_cap_disables_counter_offer() {
+        assert!(ensure_counter_allowed(0, 0).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+// Tracking order jual-beli publik lewat kode referensi
+//
+// Kode dibuat acak saat order dibuat (80 bit, alfabet Crockford base32), jadi tidak bisa
+// ditebak dari order_id maupun kode order lain. Response hanya berisi status, tahap, dan
+// estimasi waktu: tanpa data buyer/seller, harga, atau alasan pembatalan.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::sale::{SaleOrder, SaleStatus};
+
+// Crockford base32: tanpa I, L, O, U supaya kode mudah dibaca dan diketik ulang
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const REFERENCE_CHARS: usize = 16;
+const GROUP_SIZE: usize = 4;
+
+// Estimasi lama serah terima dokumen (BPKB, STNK, faktur, pajak) setelah pembayaran
+pub const ESTIMATED_DOCUMENT_DAYS: i64 = 14;
+
+// Kode baru format XXXX-XXXX-XXXX-XXXX, 5 bit acak per karakter
+pub fn generate_reference() -> String {
+    let bytes: [u8; REFERENCE_CHARS] = rand::random();
+    let chars: Vec<char> = bytes
+        .iter()
+        .map(|b| ALPHABET[(b & 0x1f) as usize] as char)
+        .collect();
+
+    group(&chars)
+}
+
+// Bentuk kanonik kode dari input user (huruf kecil, tanpa strip, O/I/L dibaca 0/1/1),
+// None jika bukan kode yang mungkin dibuat generate_reference
+pub fn normalize_reference(raw: &str) -> Option<String> {
+    let chars: Vec<char> = raw
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            other => other,
+        })
+        .collect();
+
+    if chars.len() != REFERENCE_CHARS || !chars.iter().all(|c| c.is_ascii() && ALPHABET.contains(&(*c as u8))) {
+        return None;
+    }
+
+    Some(group(&chars))
+}
+
+fn group(chars: &[char]) -> String {
+    chars
+        .chunks(GROUP_SIZE)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Satu tahap di timeline tracking
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackingStep {
+    #[schema(example = "paid")]
+    pub stage: &'static str,
+    #[schema(example = "Pembayaran diterima")]
+    pub label: &'static str,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Perkiraan selesai untuk tahap yang belum terjadi (hanya jika bisa diperkirakan)
+    pub estimated_at: Option<DateTime<Utc>>,
+}
+
+// Tampilan status order untuk publik, tanpa PII
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrderTrackingResponse {
+    #[schema(example = "7KQ2-M9XD-4HTB-0RWE")]
+    pub reference: String,
+    #[schema(example = "document_processing")]
+    pub status: String,
+    /// Tahap saat ini (1..=total_stages), 0 jika order dibatalkan atau ditolak
+    pub stage: i32,
+    pub total_stages: i32,
+    #[schema(example = "Proses dokumen kendaraan")]
+    pub stage_label: &'static str,
+    pub is_closed: bool,
+    pub timeline: Vec<TrackingStep>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const STAGES: [(&str, &str); 5] = [
+    ("order_placed", "Pesanan dibuat"),
+    ("confirmed", "Dikonfirmasi seller"),
+    ("paid", "Pembayaran diterima"),
+    ("document_processing", "Proses dokumen kendaraan"),
+    ("completed", "Selesai"),
+];
+
+// Status order -> (tahap saat ini, label); tahap 0 untuk order yang sudah ditutup
+fn current_stage(status: Option<&SaleStatus>) -> (i32, &'static str) {
+    match status {
+        Some(SaleStatus::PendingConfirmation) => (1, "Menunggu konfirmasi seller"),
+        Some(SaleStatus::PendingPayment) => (2, "Menunggu pembayaran"),
+        Some(SaleStatus::Paid) => (3, "Pembayaran diterima"),
+        Some(SaleStatus::DocumentProcessing) => (4, "Proses dokumen kendaraan"),
+        Some(SaleStatus::Completed) => (5, "Selesai"),
+        Some(SaleStatus::Cancelled) => (0, "Dibatalkan"),
+        Some(SaleStatus::Rejected) => (0, "Ditolak seller"),
+        None => (0, "Status tidak diketahui"),
+    }
+}
+
+// Build tampilan tracking; estimasi konfirmasi dari SLA seller, estimasi selesai dari
+// mulai proses dokumen (atau tanggal bayar) + ESTIMATED_DOCUMENT_DAYS
+pub fn tracking_view(order: &SaleOrder, sla_minutes: i64) -> OrderTrackingResponse {
+    let status = SaleStatus::from_str(&order.status);
+    let (stage, stage_label) = current_stage(status.as_ref());
+    let is_closed = stage == 0 || stage == STAGES.len() as i32;
+
+    let completed = [
+        Some(order.created_at),
+        order.confirmed_at,
+        order.paid_at,
+        order.document_transfer_started_at,
+        order.completed_at,
+    ];
+
+    let estimated = |index: usize| -> Option<DateTime<Utc>> {
+        if is_closed || completed[index].is_some() {
+            return None;
+        }
+        match STAGES[index].0 {
+            "confirmed" => Some(order.created_at + Duration::minutes(sla_minutes)),
+            "completed" => order
+                .document_transfer_started_at
+                .or(order.paid_at)
+                .map(|start| start + Duration::days(ESTIMATED_DOCUMENT_DAYS)),
+            _ => None,
+        }
+    };
+
+    let timeline = STAGES
+        .iter()
+        .enumerate()
+        .map(|(index, &(stage, label))| TrackingStep {
+            stage,
+            label,
+            completed_at: completed[index],
+            estimated_at: estimated(index),
+        })
+        .collect();
+
+    OrderTrackingResponse {
+        reference: order.tracking_reference.clone(),
+        status: order.status.clone(),
+        stage,
+        total_stages: STAGES.len() as i32,
+        stage_label,
+        is_closed,
+        timeline,
+        updated_at: order.updated_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn order(status: SaleStatus) -> SaleOrder {
+        let created_at = Utc::now() - Duration::days(3);
+        SaleOrder {
+            id: 42,
+            vehicle_id: 7,
+            buyer_id: 11,
+            seller_id: 12,
+            testdrive_booking_id: None,
+            order_id: "SALE-20260101120000-0042".to_string(),
+            tracking_reference: generate_reference(),
+            asking_price: 250_000_000.0,
+            offer_price: Some(245_000_000.0),
+            counter_offer_price: None,
+            counter_round: 0,
+            final_price: 245_000_000.0,
+            buyer_name: "Budi Santoso".to_string(),
+            buyer_phone: "081234567890".to_string(),
+            buyer_email: "budi@example.com".to_string(),
+            buyer_address: Some("Jl. Sudirman No. 123".to_string()),
+            buyer_ktp_photo: None,
+            status: status.as_str().to_string(),
+            document_checklist: serde_json::json!([]),
+            created_at,
+            confirmed_at: Some(created_at + Duration::hours(1)),
+            paid_at: Some(created_at + Duration::days(1)),
+            document_transfer_started_at: Some(created_at + Duration::days(2)),
+            completed_at: None,
+            cancelled_at: None,
+            updated_at: created_at + Duration::days(2),
+            cancel_reason: None,
+            reject_reason: None,
+            rejected_at: None,
+            buyer_notes: Some("Tolong hubungi istri saya".to_string()),
+            seller_notes: None,
+            version: 3,
+        }
+    }
+
+    #[test]
+    fn test_reference_is_random_not_sequential() {
+        let references: Vec<String> = (0..2000).map(|_| generate_reference()).collect();
+
+        let unique: HashSet<&String> = references.iter().collect();
+        assert_eq!(unique.len(), references.len());
+
+        // Kode berurutan berbeda di hampir semua posisi, tidak ada pola increment
+        let mut total_distance = 0;
+        for pair in references.windows(2) {
+            let distance = pair[0].chars().zip(pair[1].chars()).filter(|(a, b)| a != b).count();
+            total_distance += distance;
+        }
+        let average = total_distance as f64 / (references.len() - 1) as f64;
+        assert!(average > 13.0, "rata-rata jarak {} terlalu kecil", average);
+
+        // Semua karakter alfabet terpakai kira-kira merata (ekspektasi 1000 per karakter)
+        let mut counts = [0usize; 32];
+        for reference in &references {
+            for c in reference.chars().filter(|c| *c != '-') {
+                let index = ALPHABET.iter().position(|a| *a as char == c).expect("karakter di luar alfabet");
+                counts[index] += 1;
+            }
+        }
+        assert!(counts.iter().all(|count| (700..1300).contains(count)), "distribusi tidak merata: {:?}", counts);
+    }
+
+    #[test]
+    fn test_normalize_reference() {
+        let reference = generate_reference();
+        assert_eq!(reference.len(), 19);
+        assert_eq!(normalize_reference(&reference).as_deref(), Some(reference.as_str()));
+
+        // Input user: huruf kecil, tanpa strip, O/I/L yang mirip angka
+        assert_eq!(normalize_reference(" 7kq2m9xd4htb0rwe ").as_deref(), Some("7KQ2-M9XD-4HTB-0RWE"));
+        assert_eq!(normalize_reference("7KQ2-M9XD-4HTB-ORWE").as_deref(), Some("7KQ2-M9XD-4HTB-0RWE"));
+        assert_eq!(normalize_reference("ilIL-0000-0000-0000").as_deref(), Some("1111-0000-0000-0000"));
+
+        // Order ID, panjang salah, dan karakter di luar alfabet ditolak tanpa query database
+        assert_eq!(normalize_reference("SALE-20260101120000-0042"), None);
+        assert_eq!(normalize_reference("7KQ2-M9XD-4HTB"), None);
+        assert_eq!(normalize_reference("7KQ2-M9XD-4HTB-0RWU"), None);
+        assert_eq!(normalize_reference("7KQ2-M9XD-4HTB-0RW!"), None);
+        assert_eq!(normalize_reference("7KQ2-M9XD-4HTB-0RWÉ"), None);
+    }
+
+    #[test]
+    fn test_tracking_view_has_no_pii() {
+        let order = order(SaleStatus::DocumentProcessing);
+        let view = tracking_view(&order, 60);
+
+        assert_eq!(view.stage, 4);
+        assert!(!view.is_closed);
+        assert_eq!(view.timeline[3].completed_at, order.document_transfer_started_at);
+        assert_eq!(
+            view.timeline[4].estimated_at,
+            order.document_transfer_started_at.map(|start| start + Duration::days(ESTIMATED_DOCUMENT_DAYS))
+        );
+
+        let json = serde_json::to_string(&view).unwrap();
+        for secret in ["Budi", "081234567890", "budi@example.com", "Sudirman", "SALE-", "245000000", "istri"] {
+            assert!(!json.contains(secret), "response membocorkan {}", secret);
+        }
+        assert!(!json.contains("buyer") && !json.contains("seller_id") && !json.contains("price"));
+    }
+
+    #[test]
+    fn test_tracking_view_closed_and_pending_orders() {
+        let mut pending = order(SaleStatus::PendingConfirmation);
+        pending.confirmed_at = None;
+        pending.paid_at = None;
+        pending.document_transfer_started_at = None;
+        let view = tracking_view(&pending, 60);
+        assert_eq!(view.stage, 1);
+        assert_eq!(view.timeline[1].estimated_at, Some(pending.created_at + Duration::minutes(60)));
+        assert_eq!(view.timeline[4].estimated_at, None);
+
+        // Order dibatalkan: tahap 0, tanpa estimasi
+        let mut cancelled = pending;
+        cancelled.status = SaleStatus::Cancelled.as_str().to_string();
+        cancelled.cancel_reason = Some("Pindah ke Surabaya".to_string());
+        let view = tracking_view(&cancelled, 60);
+        assert_eq!((view.stage, view.is_closed), (0, true));
+        assert!(view.timeline.iter().all(|step| step.estimated_at.is_none()));
+        assert!(!serde_json::to_string(&view).unwrap().contains("Surabaya"));
+    }
+}
@@ -0,0 +1,78 @@
+// Signed URL untuk dokumen privat booking (KTP/SIM), file publik tetap URL biasa
+
+use chrono::Utc;
+use shared::utils::storage::{sign_file_token, FileTokenClaims};
+
+use crate::config::AppConfig;
+
+// Jenis dokumen privat yang bisa diakses lewat /api/files/{token}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrivateFile {
+    // KTP pembeli di sale order
+    SaleKtp,
+    // KTP/SIM penyewa saat pickup rental
+    RentalKtp,
+}
+
+impl PrivateFile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrivateFile::SaleKtp => "sale_ktp",
+            PrivateFile::RentalKtp => "rental_ktp",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "sale_ktp" => Some(PrivateFile::SaleKtp),
+            "rental_ktp" => Some(PrivateFile::RentalKtp),
+            _ => None,
+        }
+    }
+}
+
+// Path signed yang menggantikan URL storage mentah di response API
+pub fn signed_path(config: &AppConfig, kind: PrivateFile, resource_id: i32, url: &str) -> String {
+    let claims = FileTokenClaims {
+        url: url.to_string(),
+        resource: kind.as_str().to_string(),
+        resource_id,
+        exp: Utc::now().timestamp() + config.file_url_ttl_secs,
+    };
+
+    format!("/api/files/{}", sign_file_token(config.file_url_secret.as_bytes(), &claims))
+}
+
+// Content-Type file dari ekstensi URL
+pub fn content_type_for(url: &str) -> &'static str {
+    let path = url.split('?').next().unwrap_or(url).to_lowercase();
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("heic") => "image/heic",
+        Some("heif") => "image/heif",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_file_kind_roundtrip() {
+        for kind in [PrivateFile::SaleKtp, PrivateFile::RentalKtp] {
+            assert_eq!(PrivateFile::from_str(kind.as_str()), Some(kind));
+        }
+        assert_eq!(PrivateFile::from_str("vehicle_photo"), None);
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        assert_eq!(content_type_for("https://res.cloudinary.com/bigauto/image/upload/v1/documents/ktp.JPG"), "image/jpeg");
+        assert_eq!(content_type_for("http://localhost:9000/bigauto/docs/sim.pdf?x=1"), "application/pdf");
+        assert_eq!(content_type_for("https://res.cloudinary.com/bigauto/raw/upload/v1/file"), "application/octet-stream");
+    }
+}
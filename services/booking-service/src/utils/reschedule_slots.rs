@@ -0,0 +1,158 @@
+// Validasi slot alternatif reschedule test drive dari seller
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::testdrive::RescheduleSlot;
+use crate::utils::business_hours::{BusinessHours, SLOT_DURATION_MINUTES};
+
+// Maksimal slot alternatif per reschedule
+pub const MAX_RESCHEDULE_SLOTS: usize = 3;
+
+// Validasi slot reschedule: harus di masa depan, dalam jam operasional seller, tidak bentrok.
+// Tanggal disimpan sebagai awal sesi penuh (tanggal + jam lokal seller), sehingga slot yang sama
+// dengan timestamp berbeda dibuang sebagai duplikat dan bentrok dicek per jam, bukan per tanggal
+pub fn validate_reschedule_slots(
+    slots: Vec<RescheduleSlot>,
+    hours: &BusinessHours,
+    now: DateTime<Utc>,
+) -> Result<Vec<RescheduleSlot>, String> {
+    let mut unique: Vec<RescheduleSlot> = Vec::with_capacity(slots.len());
+    for (index, slot) in slots.into_iter().enumerate() {
+        let time = slot.time.trim().to_string();
+        let start = hours.slot_start(slot.date, &time)
+            .map_err(|e| format!("Slot {}: {}", index + 1, e))?;
+
+        if start <= now {
+            return Err(format!("Slot {}: tanggal reschedule tidak boleh di masa lalu", index + 1));
+        }
+
+        if !unique.iter().any(|existing| existing.date == start) {
+            unique.push(RescheduleSlot { date: start, time });
+        }
+    }
+
+    if unique.is_empty() {
+        return Err("Minimal 1 slot reschedule harus diisi".to_string());
+    }
+
+    if unique.len() > MAX_RESCHEDULE_SLOTS {
+        return Err(format!("Maksimal {} slot reschedule", MAX_RESCHEDULE_SLOTS));
+    }
+
+    for (i, slot_a) in unique.iter().enumerate() {
+        for slot_b in unique.iter().skip(i + 1) {
+            if (slot_a.date - slot_b.date).abs() < Duration::minutes(SLOT_DURATION_MINUTES) {
+                return Err(format!(
+                    "Slot reschedule bentrok, beri jarak minimal {} menit di hari yang sama",
+                    SLOT_DURATION_MINUTES
+                ));
+            }
+        }
+    }
+
+    Ok(unique)
+}
+
+// Ambil slot pilihan customer beserta awal sesinya, index harus dalam range, slot belum lewat
+// dan masih dalam jam operasional seller (bisa berubah sejak slot diusulkan)
+pub fn select_slot<'a>(
+    slots: &'a [RescheduleSlot],
+    slot_index: usize,
+    hours: &BusinessHours,
+    now: DateTime<Utc>,
+) -> Result<(&'a RescheduleSlot, DateTime<Utc>), String> {
+    let slot = slots.get(slot_index).ok_or_else(|| {
+        format!("Slot index tidak valid, pilih 0 sampai {}", slots.len().saturating_sub(1))
+    })?;
+
+    let start = hours.slot_start(slot.date, &slot.time)?;
+    if start <= now {
+        return Err("Slot yang dipilih sudah lewat".to_string());
+    }
+
+    Ok((slot, start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn hours() -> BusinessHours {
+        BusinessHours::parse("08:00", "18:00", "Asia/Jakarta").unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 12, 1, 9, 0, 0).unwrap()
+    }
+
+    fn slot(day: u32, hour: u32, time: &str) -> RescheduleSlot {
+        RescheduleSlot {
+            date: Utc.with_ymd_and_hms(2025, 12, day, hour, 0, 0).unwrap(),
+            time: time.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_valid_slots_deduplicated() {
+        // Jam sama di hari yang sama dengan timestamp berbeda tetap dianggap duplikat
+        let slots = vec![slot(2, 3, "10:00"), slot(2, 10, " 10:00"), slot(3, 14, "14:00")];
+        let valid = validate_reschedule_slots(slots, &hours(), now()).unwrap();
+
+        // Disimpan sebagai awal sesi: 10:00 dan 14:00 WIB
+        assert_eq!(valid, vec![slot(2, 3, "10:00"), slot(3, 7, "14:00")]);
+    }
+
+    #[test]
+    fn test_past_dated_slot_rejected() {
+        let past = RescheduleSlot { date: now() - Duration::hours(1), time: "08:00".to_string() };
+        let err = validate_reschedule_slots(vec![slot(2, 10, "10:00"), past], &hours(), now()).unwrap_err();
+        assert!(err.contains("Slot 2"));
+        assert!(err.contains("masa lalu"));
+    }
+
+    #[test]
+    fn test_invalid_slots_rejected() {
+        assert!(validate_reschedule_slots(vec![], &hours(), now()).is_err());
+
+        // Melebihi batas jumlah slot
+        let too_many = vec![slot(2, 10, "10:00"), slot(3, 10, "10:00"), slot(4, 10, "10:00"), slot(5, 10, "10:00")];
+        assert!(validate_reschedule_slots(too_many, &hours(), now()).is_err());
+
+        // Di luar jam operasional / format salah
+        assert!(validate_reschedule_slots(vec![slot(2, 7, "07:30")], &hours(), now()).is_err());
+        assert!(validate_reschedule_slots(vec![slot(2, 17, "17:30")], &hours(), now()).is_err());
+        assert!(validate_reschedule_slots(vec![slot(2, 10, "10.00")], &hours(), now()).is_err());
+
+        // Bentrok di hari yang sama, walau timestamp tanggal yang dikirim berbeda
+        let overlapping = vec![slot(2, 10, "10:00"), slot(2, 10, "10:30")];
+        assert!(validate_reschedule_slots(overlapping, &hours(), now()).is_err());
+        let overlapping = vec![slot(2, 1, "10:00"), slot(2, 9, "10:59")];
+        assert!(validate_reschedule_slots(overlapping, &hours(), now()).is_err());
+        assert!(validate_reschedule_slots(vec![slot(2, 10, "10:00"), slot(2, 11, "11:00")], &hours(), now()).is_ok());
+    }
+
+    #[test]
+    fn test_select_slot_out_of_range() {
+        let slots = vec![slot(2, 10, "10:00"), slot(3, 14, "14:00")];
+        // Awal sesi dari jam slot, bukan jam pada timestamp tanggal
+        assert_eq!(select_slot(&slots, 1, &hours(), now()), Ok((&slots[1], slot(3, 7, "14:00").date)));
+
+        let err = select_slot(&slots, 2, &hours(), now()).unwrap_err();
+        assert!(err.contains("0 sampai 1"));
+        assert!(select_slot(&[], 0, &hours(), now()).is_err());
+
+        // Slot yang sudah lewat saat dipilih
+        assert!(select_slot(&slots, 0, &hours(), now() + Duration::days(2)).is_err());
+    }
+
+    #[test]
+    fn test_select_slot_outside_updated_hours() {
+        // Seller mempersingkat jam operasional setelah slot diusulkan
+        let slots = vec![slot(2, 10, "10:00"), slot(3, 14, "14:00")];
+        let shorter = BusinessHours::parse("09:00", "12:00", "Asia/Jakarta").unwrap();
+
+        assert!(select_slot(&slots, 0, &shorter, now()).is_ok());
+        assert!(select_slot(&slots, 1, &shorter, now()).is_err());
+    }
+}
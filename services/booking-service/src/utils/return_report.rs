@@ -0,0 +1,121 @@
+// Validasi laporan pengembalian rental dan hitung total biaya kerusakan
+
+use crate::{
+    domain::return_report::{CreateReturnReportRequest, DamageItem},
+    error::AppError,
+};
+
+// Batas jumlah item kerusakan per laporan
+const MAX_DAMAGE_ITEMS: usize = 50;
+
+const LATE_FEE_DESCRIPTION: &str = "Denda keterlambatan pengembalian";
+
+// Validasi request, return total biaya kerusakan (rupiah)
+pub fn validate_return_report(payload: &CreateReturnReportRequest) -> Result<i64, AppError> {
+    if payload.condition_notes.trim().is_empty() {
+        return Err(AppError::validation("Catatan kondisi kendaraan harus diisi"));
+    }
+
+    if payload.odometer_km < 0 {
+        return Err(AppError::validation("Odometer tidak boleh negatif"));
+    }
+
+    if !(0..=100).contains(&payload.fuel_level) {
+        return Err(AppError::validation("Fuel level harus antara 0-100"));
+    }
+
+    if payload.damage_items.len() > MAX_DAMAGE_ITEMS {
+        return Err(AppError::validation(format!(
+            "Maksimal {} item kerusakan per laporan",
+            MAX_DAMAGE_ITEMS
+        )));
+    }
+
+    let mut total: i64 = 0;
+    for item in &payload.damage_items {
+        if item.description.trim().is_empty() {
+            return Err(AppError::validation("Deskripsi kerusakan harus diisi"));
+        }
+
+        if item.amount <= 0 {
+            return Err(AppError::validation("Biaya kerusakan harus lebih dari 0"));
+        }
+
+        total = total
+            .checked_add(item.amount)
+            .ok_or_else(|| AppError::validation("Total biaya kerusakan terlalu besar"))?;
+    }
+
+    Ok(total)
+}
+
+// Denda keterlambatan dari check-out ditagihkan sebagai item laporan pengembalian,
+// ikut dibayar lewat tagihan kerusakan dan dipotong dari deposit
+pub fn add_late_fee(
+    payload: &mut CreateReturnReportRequest,
+    damage_total: i64,
+    late_fee: i64,
+) -> Result<i64, AppError> {
+    if late_fee <= 0 {
+        return Ok(damage_total);
+    }
+
+    payload.damage_items.push(DamageItem {
+        description: LATE_FEE_DESCRIPTION.to_string(),
+        amount: late_fee,
+    });
+
+    damage_total
+        .checked_add(late_fee)
+        .ok_or_else(|| AppError::validation("Total biaya kerusakan terlalu besar"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(damage_items: Vec<DamageItem>) -> CreateReturnReportRequest {
+        CreateReturnReportRequest {
+            condition_notes: "Baret di bumper depan".to_string(),
+            odometer_km: 45210,
+            fuel_level: 75,
+            damage_items,
+        }
+    }
+
+    fn item(amount: i64) -> DamageItem {
+        DamageItem { description: "Baret bumper".to_string(), amount }
+    }
+
+    #[test]
+    fn test_damage_total_is_summed() {
+        assert_eq!(validate_return_report(&report(vec![])).unwrap(), 0);
+        assert_eq!(validate_return_report(&report(vec![item(350_000), item(150_000)])).unwrap(), 500_000);
+    }
+
+    #[test]
+    fn test_invalid_report_rejected() {
+        let mut invalid = report(vec![]);
+        invalid.fuel_level = 120;
+        assert!(validate_return_report(&invalid).is_err());
+
+        let mut invalid = report(vec![]);
+        invalid.condition_notes = "  ".to_string();
+        assert!(validate_return_report(&invalid).is_err());
+
+        assert!(validate_return_report(&report(vec![item(0)])).is_err());
+        assert!(validate_return_report(&report(vec![item(i64::MAX), item(1)])).is_err());
+    }
+
+    #[test]
+    fn test_late_fee_added_as_damage_item() {
+        let mut payload = report(vec![item(350_000)]);
+        let total = validate_return_report(&payload).unwrap();
+
+        assert_eq!(add_late_fee(&mut payload, total, 0).unwrap(), 350_000);
+        assert_eq!(payload.damage_items.len(), 1);
+
+        assert_eq!(add_late_fee(&mut payload, total, 500_000).unwrap(), 850_000);
+        assert_eq!(payload.damage_items.last().unwrap().amount, 500_000);
+    }
+}
@@ -0,0 +1,62 @@
+// Validasi batch bulk accept/reject test drive seller
+
+// Maksimal booking per request bulk
+pub const MAX_BULK_TESTDRIVES: usize = 50;
+
+// Alasan default saat seller menolak tanpa keterangan
+pub const DEFAULT_REJECT_REASON: &str = "Ditolak oleh seller";
+
+// Buang ID duplikat (urutan dipertahankan) lalu cek batas batch
+pub fn normalize_booking_ids(ids: &[i32]) -> Result<Vec<i32>, String> {
+    let mut unique = Vec::with_capacity(ids.len());
+    for &id in ids {
+        if !unique.contains(&id) {
+            unique.push(id);
+        }
+    }
+
+    if unique.is_empty() {
+        return Err("booking_ids tidak boleh kosong".to_string());
+    }
+
+    if unique.len() > MAX_BULK_TESTDRIVES {
+        return Err(format!("Maksimal {} test drive per request", MAX_BULK_TESTDRIVES));
+    }
+
+    Ok(unique)
+}
+
+// Alasan penolakan yang disimpan ke cancel_reason
+pub fn reject_reason(reason: Option<&str>) -> String {
+    reason
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .unwrap_or(DEFAULT_REJECT_REASON)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_booking_ids() {
+        assert_eq!(normalize_booking_ids(&[3, 1, 3, 2, 1]), Ok(vec![3, 1, 2]));
+        assert!(normalize_booking_ids(&[]).is_err());
+
+        let too_many: Vec<i32> = (1..=MAX_BULK_TESTDRIVES as i32 + 1).collect();
+        assert!(normalize_booking_ids(&too_many).is_err());
+
+        // Duplikat tidak dihitung ke batas batch
+        let mut at_cap: Vec<i32> = (1..=MAX_BULK_TESTDRIVES as i32).collect();
+        at_cap.push(1);
+        assert_eq!(normalize_booking_ids(&at_cap).map(|ids| ids.len()), Ok(MAX_BULK_TESTDRIVES));
+    }
+
+    #[test]
+    fn test_reject_reason() {
+        assert_eq!(reject_reason(Some("  Unit di bengkel ")), "Unit di bengkel");
+        assert_eq!(reject_reason(Some("   ")), DEFAULT_REJECT_REASON);
+        assert_eq!(reject_reason(None), DEFAULT_REJECT_REASON);
+    }
+}
@@ -0,0 +1,76 @@
+// Validasi lokasi pertemuan test drive (showroom seller atau alamat customer)
+
+use crate::domain::testdrive::TestDriveLocation;
+use shared::utils::validation::FieldError;
+
+// Panjang alamat maksimal
+const MAX_ADDRESS_LENGTH: usize = 500;
+
+// Validasi kombinasi location, address, dan koordinat. Location kosong = showroom
+pub fn validate_location(
+    location: Option<&str>,
+    address: Option<&str>,
+    lat: Option<f64>,
+    lng: Option<f64>,
+) -> Result<TestDriveLocation, FieldError> {
+    let location = match location.map(str::trim).filter(|l| !l.is_empty()) {
+        Some(value) => TestDriveLocation::from_str(value).ok_or_else(|| {
+            FieldError::new("location", "Location harus 'showroom' atau 'customer_address'")
+        })?,
+        None => TestDriveLocation::Showroom,
+    };
+
+    let address = address.map(str::trim).filter(|a| !a.is_empty());
+
+    if location == TestDriveLocation::CustomerAddress && address.is_none() {
+        return Err(FieldError::new("address", "Alamat harus diisi jika test drive di lokasi customer"));
+    }
+
+    if address.is_some_and(|a| a.chars().count() > MAX_ADDRESS_LENGTH) {
+        return Err(FieldError::new(
+            "address",
+            format!("Alamat maksimal {} karakter", MAX_ADDRESS_LENGTH),
+        ));
+    }
+
+    match (lat, lng) {
+        (Some(lat), Some(lng)) => {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+                return Err(FieldError::new("lat", "Koordinat lokasi tidak valid"));
+            }
+        }
+        (None, None) => {}
+        _ => return Err(FieldError::new("lat", "lat dan lng harus diisi bersamaan")),
+    }
+
+    Ok(location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_location_is_showroom() {
+        assert_eq!(validate_location(None, None, None, None).unwrap(), TestDriveLocation::Showroom);
+        assert_eq!(validate_location(Some(""), None, None, None).unwrap(), TestDriveLocation::Showroom);
+    }
+
+    #[test]
+    fn test_customer_address_requires_address() {
+        assert_eq!(validate_location(Some("customer_address"), None, None, None).unwrap_err().field, "address");
+        assert!(validate_location(Some("customer_address"), Some("   "), None, None).is_err());
+        assert_eq!(
+            validate_location(Some("customer_address"), Some("Jl. Sudirman No. 1"), Some(-6.2), Some(106.8)).unwrap(),
+            TestDriveLocation::CustomerAddress
+        );
+    }
+
+    #[test]
+    fn test_invalid_location_and_coordinates() {
+        assert!(validate_location(Some("cafe"), Some("Jl. Sudirman"), None, None).is_err());
+        assert!(validate_location(None, None, Some(-6.2), None).is_err());
+        assert!(validate_location(None, None, Some(91.0), Some(106.8)).is_err());
+        assert!(validate_location(None, None, Some(-6.2), Some(181.0)).is_err());
+    }
+}
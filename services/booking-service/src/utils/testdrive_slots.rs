@@ -0,0 +1,344 @@
+// Jadwal slot test drive yang dipublikasikan seller
+//
+// Slot mingguan berulang (weekday 0 = Senin) ditambah pengecualian per tanggal: pengecualian
+// available=false menutup slot mingguan (tanpa jam = seharian), available=true menambah slot ekstra.
+// Slot terbuka = slot di tanggal itu yang belum lewat, masih dalam jam operasional seller, dan
+// kapasitasnya belum habis oleh booking aktif di jam yang sama.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use shared::utils::clock::parse_clock;
+use shared::utils::validation::FieldError;
+
+use crate::domain::testdrive::{OpenSlot, SellerAvailability, SetAvailabilityRequest};
+use crate::utils::business_hours::{format_clock, BusinessHours};
+
+// Batas aturan availability per seller dan kapasitas per slot
+pub const MAX_AVAILABILITY_ENTRIES: usize = 100;
+pub const MAX_SLOT_CAPACITY: i32 = 20;
+
+// Aturan availability yang sudah divalidasi, siap disimpan
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAvailability {
+    pub weekday: Option<i16>,
+    pub date: Option<NaiveDate>,
+    pub start_time: Option<NaiveTime>,
+    pub capacity: i32,
+    pub is_available: bool,
+}
+
+// Validasi jadwal dari seller, semua field yang salah dikembalikan sekaligus
+pub fn validate_availability(
+    request: &SetAvailabilityRequest,
+    hours: &BusinessHours,
+    today: NaiveDate,
+) -> Result<Vec<NewAvailability>, Vec<FieldError>> {
+    if request.weekly.len() + request.exceptions.len() > MAX_AVAILABILITY_ENTRIES {
+        return Err(vec![FieldError::new(
+            "weekly",
+            format!("Maksimal {} slot dan pengecualian", MAX_AVAILABILITY_ENTRIES),
+        )]);
+    }
+
+    let mut errors = Vec::new();
+    let mut entries = Vec::with_capacity(request.weekly.len() + request.exceptions.len());
+
+    for (index, slot) in request.weekly.iter().enumerate() {
+        let field = format!("weekly[{}]", index);
+
+        if !(0..=6).contains(&slot.weekday) {
+            errors.push(FieldError::new(&field, "weekday harus 0 (Senin) sampai 6 (Minggu)"));
+        }
+
+        entries.push(NewAvailability {
+            weekday: Some(slot.weekday),
+            date: None,
+            start_time: check_time(&field, &slot.time, hours, &mut errors),
+            capacity: check_capacity(&field, slot.capacity, &mut errors),
+            is_available: true,
+        });
+    }
+
+    for (index, exception) in request.exceptions.iter().enumerate() {
+        let field = format!("exceptions[{}]", index);
+
+        if exception.date < today {
+            errors.push(FieldError::new(&field, "Tanggal pengecualian tidak boleh di masa lalu"));
+        }
+
+        let start_time = match exception.time.as_deref() {
+            Some(time) => check_time(&field, time, hours, &mut errors),
+            None if exception.available => {
+                errors.push(FieldError::new(&field, "Slot ekstra wajib mengisi time"));
+                None
+            }
+            None => None,
+        };
+
+        entries.push(NewAvailability {
+            weekday: None,
+            date: Some(exception.date),
+            start_time,
+            capacity: check_capacity(&field, exception.capacity, &mut errors),
+            is_available: exception.available,
+        });
+    }
+
+    for (index, entry) in entries.iter().enumerate() {
+        let duplicate = entries[..index].iter().any(|other| {
+            other.weekday == entry.weekday && other.date == entry.date && other.start_time == entry.start_time
+        });
+        if duplicate {
+            let field = if entry.weekday.is_some() { "weekly" } else { "exceptions" };
+            errors.push(FieldError::new(field, "Slot atau pengecualian yang sama tidak boleh diulang"));
+            break;
+        }
+    }
+
+    if errors.is_empty() { Ok(entries) } else { Err(errors) }
+}
+
+fn check_time(field: &str, time: &str, hours: &BusinessHours, errors: &mut Vec<FieldError>) -> Option<NaiveTime> {
+    match parse_clock(time) {
+        Some(start) if hours.contains(start) => Some(start),
+        Some(_) => {
+            errors.push(FieldError::new(field, format!(
+                "Jam slot harus antara {} dan {} ({})",
+                format_clock(hours.open),
+                format_clock(hours.last_start()),
+                hours.timezone.name()
+            )));
+            None
+        }
+        None => {
+            errors.push(FieldError::new(field, "Jam slot harus berformat HH:MM"));
+            None
+        }
+    }
+}
+
+fn check_capacity(field: &str, capacity: Option<i32>, errors: &mut Vec<FieldError>) -> i32 {
+    let capacity = capacity.unwrap_or(1);
+    if !(1..=MAX_SLOT_CAPACITY).contains(&capacity) {
+        errors.push(FieldError::new(field, format!("capacity harus 1 sampai {}", MAX_SLOT_CAPACITY)));
+    }
+    capacity
+}
+
+// Seller yang sudah mempublikasikan slot mingguan hanya menerima booking lewat slot_id
+pub fn requires_slot(rules: &[SellerAvailability]) -> bool {
+    rules.iter().any(|rule| rule.weekday.is_some())
+}
+
+// Jam lokal slot untuk mengisi requested_time, None jika slot tidak ada
+pub fn slot_time(rules: &[SellerAvailability], slot_id: i32) -> Option<String> {
+    rules.iter()
+        .find(|rule| rule.id == slot_id && rule.is_available)
+        .and_then(|rule| rule.start_time)
+        .map(format_clock)
+}
+
+// Slot terbuka di tanggal lokal `day`, `booked` = jumlah booking aktif per jam "HH:MM"
+pub fn open_slots(
+    rules: &[SellerAvailability],
+    day: NaiveDate,
+    hours: &BusinessHours,
+    booked: &[(String, i64)],
+    now: DateTime<Utc>,
+) -> Vec<OpenSlot> {
+    let weekday = day.weekday().num_days_from_monday() as i16;
+    let closed = |time: NaiveTime| {
+        rules.iter().any(|rule| {
+            rule.date == Some(day) && !rule.is_available && rule.start_time.is_none_or(|t| t == time)
+        })
+    };
+
+    let mut seen: Vec<NaiveTime> = Vec::new();
+    let mut slots = Vec::new();
+
+    for rule in rules {
+        let Some(time) = rule.start_time else { continue };
+
+        let applies = match (rule.weekday, rule.date) {
+            (Some(rule_weekday), None) => rule_weekday == weekday && !closed(time),
+            (None, Some(date)) => date == day && rule.is_available,
+            _ => false,
+        };
+
+        // Satu slot per jam, aturan pertama yang berlaku menentukan slot_id dan kapasitas
+        if !applies || seen.contains(&time) {
+            continue;
+        }
+        seen.push(time);
+
+        let Ok(starts_at) = hours.start_on(day, time) else { continue };
+        if starts_at <= now {
+            continue;
+        }
+
+        let clock = format_clock(time);
+        let taken: i64 = booked.iter().filter(|(t, _)| *t == clock).map(|(_, count)| count).sum();
+        let remaining = i64::from(rule.capacity) - taken;
+
+        if remaining > 0 {
+            slots.push(OpenSlot { slot_id: rule.id, time: clock, starts_at, remaining });
+        }
+    }
+
+    slots.sort_by_key(|slot| slot.starts_at);
+    slots
+}
+
+// Slot pilihan customer harus masih terbuka di tanggal itu (dipanggil lagi di dalam lock saat insert)
+pub fn pick_slot(
+    rules: &[SellerAvailability],
+    slot_id: i32,
+    day: NaiveDate,
+    hours: &BusinessHours,
+    booked: &[(String, i64)],
+    now: DateTime<Utc>,
+) -> Result<OpenSlot, String> {
+    if !rules.iter().any(|rule| rule.id == slot_id) {
+        return Err("Slot tidak ditemukan".to_string());
+    }
+
+    open_slots(rules, day, hours, booked, now)
+        .into_iter()
+        .find(|slot| slot.slot_id == slot_id)
+        .ok_or_else(|| "Slot sudah penuh atau tidak tersedia di tanggal ini".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::testdrive::{AvailabilityExceptionInput, WeeklySlotInput};
+
+    fn hours() -> BusinessHours {
+        BusinessHours::parse("08:00", "18:00", "Asia/Jakarta").unwrap()
+    }
+
+    fn weekly(id: i32, weekday: i16, time: &str, capacity: i32) -> SellerAvailability {
+        SellerAvailability {
+            id,
+            weekday: Some(weekday),
+            date: None,
+            start_time: parse_clock(time),
+            capacity,
+            is_available: true,
+        }
+    }
+
+    fn exception(id: i32, date: NaiveDate, time: Option<&str>, is_available: bool) -> SellerAvailability {
+        SellerAvailability {
+            id,
+            weekday: None,
+            date: Some(date),
+            start_time: time.and_then(parse_clock),
+            capacity: 1,
+            is_available,
+        }
+    }
+
+    // Senin 16 Maret 2026, "sekarang" seminggu sebelumnya
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 16).unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-09T00:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    fn times(slots: &[OpenSlot]) -> Vec<&str> {
+        slots.iter().map(|slot| slot.time.as_str()).collect()
+    }
+
+    #[test]
+    fn test_weekly_slots_with_exceptions() {
+        let rules = vec![
+            weekly(1, 0, "09:00", 1),
+            weekly(2, 0, "10:00", 1),
+            weekly(3, 1, "09:00", 1),
+            exception(4, monday(), Some("10:00"), false),
+            exception(5, monday(), Some("14:00"), true),
+        ];
+
+        let slots = open_slots(&rules, monday(), &hours(), &[], now());
+        assert_eq!(times(&slots), vec!["09:00", "14:00"]);
+        // 09:00 WIB = 02:00 UTC
+        assert_eq!(slots[0].starts_at.to_rfc3339(), "2026-03-16T02:00:00+00:00");
+
+        // Tutup seharian menyisakan slot ekstra saja
+        let mut closed_day = rules.clone();
+        closed_day.push(exception(6, monday(), None, false));
+        assert_eq!(times(&open_slots(&closed_day, monday(), &hours(), &[], now())), vec!["14:00"]);
+
+        // Selasa hanya punya slot mingguan Selasa
+        let tuesday = monday().succ_opt().unwrap();
+        assert_eq!(times(&open_slots(&rules, tuesday, &hours(), &[], now())), vec!["09:00"]);
+
+        // Slot yang sudah lewat tidak ditawarkan
+        let late = DateTime::parse_from_rfc3339("2026-03-16T03:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(times(&open_slots(&rules, monday(), &hours(), &[], late)), vec!["14:00"]);
+    }
+
+    #[test]
+    fn test_slot_exhaustion() {
+        let rules = vec![weekly(1, 0, "09:00", 2), weekly(2, 0, "11:00", 1)];
+
+        let slots = open_slots(&rules, monday(), &hours(), &[("09:00".to_string(), 1)], now());
+        assert_eq!(slots[0].remaining, 1);
+
+        let booked = vec![("09:00".to_string(), 2), ("11:00".to_string(), 1)];
+        assert!(open_slots(&rules, monday(), &hours(), &booked, now()).is_empty());
+        assert_eq!(
+            pick_slot(&rules, 1, monday(), &hours(), &booked, now()),
+            Err("Slot sudah penuh atau tidak tersedia di tanggal ini".to_string())
+        );
+        assert_eq!(pick_slot(&rules, 99, monday(), &hours(), &[], now()), Err("Slot tidak ditemukan".to_string()));
+
+        // Slot mingguan yang sama di minggu berikutnya masih terbuka
+        let next_week = monday() + chrono::Duration::days(7);
+        assert!(pick_slot(&rules, 2, next_week, &hours(), &[], now()).is_ok());
+    }
+
+    // Double booking di bawah lock kalender seller ada di repositories::testdrive_repo::tests
+
+    #[test]
+    fn test_validate_availability() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let request = SetAvailabilityRequest {
+            weekly: vec![
+                WeeklySlotInput { weekday: 0, time: "09:00".to_string(), capacity: None },
+                WeeklySlotInput { weekday: 7, time: "17:30".to_string(), capacity: Some(0) },
+            ],
+            exceptions: vec![
+                AvailabilityExceptionInput { date: today, time: None, available: false, capacity: None },
+                AvailabilityExceptionInput { date: today.succ_opt().unwrap(), time: None, available: true, capacity: None },
+                AvailabilityExceptionInput {
+                    date: today.pred_opt().unwrap(), time: Some("10:00".to_string()), available: false, capacity: None,
+                },
+            ],
+        };
+
+        let errors = validate_availability(&request, &hours(), today).unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["weekly[1]", "weekly[1]", "weekly[1]", "exceptions[1]", "exceptions[2]"]);
+
+        let valid = SetAvailabilityRequest {
+            weekly: vec![WeeklySlotInput { weekday: 0, time: "09:00".to_string(), capacity: Some(2) }],
+            exceptions: vec![AvailabilityExceptionInput { date: today, time: None, available: false, capacity: None }],
+        };
+        let entries = validate_availability(&valid, &hours(), today).unwrap();
+        assert_eq!(entries[0].capacity, 2);
+        assert_eq!(entries[1].start_time, None);
+
+        // Slot yang sama dua kali ditolak
+        let duplicate = SetAvailabilityRequest {
+            weekly: vec![
+                WeeklySlotInput { weekday: 0, time: "09:00".to_string(), capacity: None },
+                WeeklySlotInput { weekday: 0, time: "09:00".to_string(), capacity: None },
+            ],
+            exceptions: vec![],
+        };
+        assert!(validate_availability(&duplicate, &hours(), today).is_err());
+    }
+}
@@ -2,45 +2,118 @@
 use serde::Serialize;
 use sqlx::{PgPool, postgres::PgConnectOptions, postgres::PgPoolOptions};
 use std::env;
+use std::net::SocketAddr;
 use std::time::Duration;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use tracing;
 
 use crate::middleware::rate_limit::RateLimiter;
-
-// Health check response structure
+use crate::utils::file_scanner::{FileScanner, ScanBackend};
+use shared::auth::JwtConfig;
+use shared::utils::bind_addr;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+use shared::utils::storage::StorageBackend;
+use crate::utils::nats_monitor::NatsMonitor;
+use crate::utils::auto_reply::DEFAULT_AUTO_REPLY_COOLDOWN_MINUTES;
+use crate::utils::realtime;
+use crate::utils::message_validation::DEFAULT_MAX_ATTACHMENTS_PER_MESSAGE;
+use crate::utils::upload_policy::UploadCategoryPolicy;
+use crate::utils::search_snippet::SnippetOptions;
+use crate::utils::vehicle_owner::{VehicleOwnerLookup, DEFAULT_VEHICLE_OWNER_CACHE_SECS};
+use crate::domain::message::DEFAULT_LAST_MESSAGE_PREVIEW_LEN;
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("conversations", &["id", "customer_id", "seller_id", "vehicle_id", "customer_unread_count", "seller_unread_count", "retention_days", "auto_replied_at", "assigned_to", "assigned_at", "is_general", "last_message_sender_id"]),
+    ("messages", &["id", "conversation_id", "sender_id", "is_deleted", "reply_to_message_id", "thread_root_id", "is_auto_reply"]),
+    ("seller_auto_replies", &["seller_id", "enabled", "message", "active_start", "active_end", "timezone"]),
+    ("seller_staff", &["seller_id", "staff_user_id"]),
+    ("chat_outbox", &["id", "subject", "payload", "next_attempt_at", "sent_at"]),
+    ("users", &["id", "name", "email"]),
+    ("inbound_email_deliveries", &["provider", "delivery_id", "message_id"]),
+    ("audit_logs", &["id", "user_id", "action", "entity_type"]),
+    // Tabel milik booking-service, dibaca langsung (database yang sama) oleh has_booking_relationship
+    ("sale_orders", &["seller_id", "buyer_id"]),
+    ("testdrive_bookings", &["seller_id", "customer_id"]),
+    ("rental_bookings", &["seller_id", "customer_id"]),
+];
+
+// Health check response structure, `status` adalah status terburuk dari semua dependency
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct HealthCheckResponse {
     pub service: String,
-    pub status: String,
+    pub status: HealthLevel,
     pub version: String,
-    pub database: String,
+    pub database: DependencyHealth,
     pub nats: String,
-    pub redis: String,
+    pub redis: DependencyHealth,
     pub uptime: String,
 }
 
+// Readiness response, termasuk status koneksi NATS
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub database: String,
+    pub nats: String,
+    pub nats_reconnect_count: u64,
+    pub nats_dead_letter_count: u64,
+}
+
 // Application configuration yang di-load dari environment variables
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub database_url: String,
     pub server_host: String,
     pub server_port: u16,
+    // Listener /metrics terpisah (CHAT_METRICS_HOST/CHAT_METRICS_PORT), None = di listener API
+    pub metrics_addr: Option<SocketAddr>,
     pub environment: String,
     pub jwt_secret: String,
+    pub jwt: JwtConfig,
     pub jwt_access_expiry: i64,
     pub jwt_refresh_expiry: i64,
     pub nats_url: String,
     pub redis_url: String,
-    pub cloudinary_cloud_name: String,
-    pub cloudinary_api_key: String,
-    pub cloudinary_api_secret: String,
     pub auth_service_url: String,
     pub user_service_url: String,
     pub vehicle_service_url: String,
+    pub vehicle_owner_cache_secs: u64,
     pub booking_service_url: String,
+    pub file_scan_backend: String,
+    pub clamav_address: String,
+    pub av_gateway_url: Option<String>,
+    pub file_scan_timeout_secs: u64,
+    pub file_scan_fail_open: bool,
+    pub seller_sla_minutes: i64,
+    pub auto_reply_cooldown_minutes: i64,
+    pub ws_compression_threshold_bytes: usize,
+    pub ws_compression_debug: bool,
+    pub outbox_relay_interval_secs: u64,
+    pub nats_dead_letter_subject: String,
+    pub max_message_length: usize,
+    pub max_attachments_per_message: usize,
+    pub last_message_preview_len: usize,
+    // Delimiter highlight & batas panjang snippet hasil search message
+    pub search_snippet: SnippetOptions,
+    pub upload_image_policy: UploadCategoryPolicy,
+    pub upload_document_policy: UploadCategoryPolicy,
+    pub upload_audio_policy: UploadCategoryPolicy,
+    // Limit upload file per user: maksimal upload_rate_limit_max per upload_rate_limit_window_secs
+    pub upload_rate_limit_window_secs: i32,
+    pub upload_rate_limit_max: i32,
+    pub retain_deleted_content: bool,
+    pub strict_validation: bool,
+    pub reply_token_secret: Option<String>,
+    pub reply_domain: Option<String>,
+    pub reply_token_ttl_days: i64,
+    pub resend_api_key: Option<String>,
+    pub resend_from_email: Option<String>,
+    pub resend_inbound_secret: Option<String>,
+    pub sendgrid_inbound_basic_auth: Option<String>,
 }
 
 impl AppConfig {
@@ -57,13 +130,14 @@ impl AppConfig {
             return Err("JWT_SECRET masih menggunakan default value! Ganti dengan value yang aman untuk production".to_string());
         }
 
-        let server_host = env::var("CHAT_SERVICE_HOST")
-            .unwrap_or_else(|_| "0.0.0.0".to_string());
+        // Issuer/audience opsional dari JWT_ISSUER dan JWT_AUDIENCE
+        let jwt = JwtConfig::from_env(jwt_secret.clone());
 
-        let server_port = env::var("CHAT_SERVICE_PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .expect("CHAT_SERVICE_PORT harus diset di environment");
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("CHAT_SERVICE_HOST", Some(bind_addr::DEFAULT_HOST))?;
+        let server_port = bind_addr::port_from_env("CHAT_SERVICE_PORT", None)?;
+        let server_addr = bind_addr::resolve("CHAT_SERVICE_HOST", &server_host, server_port)?;
+        let metrics_addr = bind_addr::metrics_addr_from_env("CHAT_METRICS_HOST", "CHAT_METRICS_PORT", server_addr)?;
 
         let environment = env::var("RUST_ENV")
             .expect("RUST_ENV harus diset di environment");
@@ -84,15 +158,6 @@ impl AppConfig {
         let redis_url = env::var("REDIS_URL")
             .expect("REDIS_URL harus diset di environment");
 
-        let cloudinary_cloud_name = env::var("CLOUDINARY_CLOUD_NAME")
-            .expect("CLOUDINARY_CLOUD_NAME harus diset di environment");
-
-        let cloudinary_api_key = env::var("CLOUDINARY_API_KEY")
-            .expect("CLOUDINARY_API_KEY harus diset di environment");
-
-        let cloudinary_api_secret = env::var("CLOUDINARY_API_SECRET")
-            .expect("CLOUDINARY_API_SECRET harus diset di environment");
-
         let auth_service_url = env::var("AUTH_SERVICE_URL")
             .expect("AUTH_SERVICE_URL harus diset di environment");
 
@@ -102,26 +167,179 @@ impl AppConfig {
         let vehicle_service_url = env::var("VEHICLE_SERVICE_URL")
             .expect("VEHICLE_SERVICE_URL harus diset di environment");
 
+        // Lama cache pemilik vehicle untuk validasi create conversation (detik)
+        let vehicle_owner_cache_secs = env::var("VEHICLE_OWNER_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_VEHICLE_OWNER_CACHE_SECS);
+
         let booking_service_url = env::var("BOOKING_SERVICE_URL")
             .expect("BOOKING_SERVICE_URL harus diset di environment");
 
+        // Malware scanning untuk attachment: disabled | clamav | http
+        let file_scan_backend = env::var("FILE_SCAN_BACKEND")
+            .unwrap_or_else(|_| "disabled".to_string());
+
+        let clamav_address = env::var("CLAMAV_ADDRESS")
+            .unwrap_or_else(|_| "127.0.0.1:3310".to_string());
+
+        let av_gateway_url = env::var("AV_GATEWAY_URL").ok();
+
+        let file_scan_timeout_secs = env::var("FILE_SCAN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10);
+
+        // Fail-closed secara default: file ditolak jika scanner error/timeout
+        let file_scan_fail_open = env::var("FILE_SCAN_FAIL_OPEN")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false);
+
+        // Batas waktu seller merespon conversation baru (menit)
+        let seller_sla_minutes = env::var("SELLER_SLA_MINUTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        // Jeda minimum antar auto-reply seller di conversation yang sama (menit)
+        let auto_reply_cooldown_minutes = env::var("AUTO_REPLY_COOLDOWN_MINUTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|minutes: &i64| *minutes > 0)
+            .unwrap_or(DEFAULT_AUTO_REPLY_COOLDOWN_MINUTES);
+
+        // Payload WebSocket di atas ukuran ini dikompres (client opt-in)
+        let ws_compression_threshold_bytes = env::var("WS_COMPRESSION_THRESHOLD_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1024);
+
+        // Log penghematan bandwidth per koneksi WebSocket
+        let ws_compression_debug = env::var("WS_COMPRESSION_DEBUG")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false);
+
+        // Interval polling outbox relay (relay juga dibangunkan setiap ada message baru)
+        let outbox_relay_interval_secs = env::var("OUTBOX_RELAY_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+
+        // Subject untuk message NATS yang tidak bisa dideliver ke WebSocket
+        let nats_dead_letter_subject = env::var("NATS_DEAD_LETTER_SUBJECT")
+            .unwrap_or_else(|_| "chat.dead_letter".to_string());
+
+        // Panjang maksimal isi pesan chat (karakter)
+        let max_message_length = env::var("MAX_MESSAGE_LENGTH")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(2000);
+
+        // Jumlah maksimal file yang dilampirkan dalam satu message
+        let max_attachments_per_message = env::var("MAX_ATTACHMENTS_PER_MESSAGE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|max| *max > 0)
+            .unwrap_or(DEFAULT_MAX_ATTACHMENTS_PER_MESSAGE);
+
+        // Panjang preview last_message di inbox (karakter)
+        let last_message_preview_len = env::var("LAST_MESSAGE_PREVIEW_LEN")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|len| *len > 0)
+            .unwrap_or(DEFAULT_LAST_MESSAGE_PREVIEW_LEN);
+
+        let search_snippet = SnippetOptions::from_env();
+
+        // Folder, format, dan transformasi CDN per kategori upload chat
+        let upload_image_policy = UploadCategoryPolicy::from_env("IMAGE", "chat/images");
+        let upload_document_policy = UploadCategoryPolicy::from_env("DOCUMENT", "chat/documents");
+        let upload_audio_policy = UploadCategoryPolicy::from_env("AUDIO", "chat/audio");
+
+        let upload_rate_limit_window_secs = env::var("UPLOAD_RATE_LIMIT_WINDOW_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(60);
+        let upload_rate_limit_max = env::var("UPLOAD_RATE_LIMIT_MAX")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|max| *max > 0)
+            .unwrap_or(5);
+
+        // Simpan isi asli message yang dihapus agar admin tetap bisa moderasi
+        let retain_deleted_content = env::var("CHAT_RETAIN_DELETED_CONTENT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(true);
+
+        // Validasi ketat hanya di production; detail yang di-relax ada di utils::message_validation
+        let strict_validation = environment == "production";
+
+        // Reply-by-email: secret token alamat reply+ dan kredensial webhook inbound provider.
+        // Endpoint inbound menolak request selama secret yang dibutuhkan belum diset.
+        let reply_token_secret = env::var("CHAT_REPLY_TOKEN_SECRET").ok().filter(|s| !s.is_empty());
+        let reply_domain = env::var("CHAT_REPLY_DOMAIN").ok().filter(|s| !s.is_empty());
+        // Masa berlaku alamat reply di email notifikasi (hari)
+        let reply_token_ttl_days = env::var("CHAT_REPLY_TOKEN_TTL_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|days| *days > 0)
+            .unwrap_or(30);
+        // Email notifikasi message baru (dengan Reply-To) dikirim lewat Resend jika diset
+        let resend_api_key = env::var("RESEND_API_KEY").ok().filter(|s| !s.is_empty());
+        let resend_from_email = env::var("RESEND_FROM_EMAIL").ok().filter(|s| !s.is_empty());
+        let resend_inbound_secret = env::var("RESEND_INBOUND_WEBHOOK_SECRET").ok().filter(|s| !s.is_empty());
+        let sendgrid_inbound_basic_auth = env::var("SENDGRID_INBOUND_BASIC_AUTH").ok().filter(|s| !s.is_empty());
+
         Ok(AppConfig {
             database_url,
             server_host,
             server_port,
+            metrics_addr,
             environment,
             jwt_secret,
+            jwt,
             jwt_access_expiry,
             jwt_refresh_expiry,
             nats_url,
             redis_url,
-            cloudinary_cloud_name,
-            cloudinary_api_key,
-            cloudinary_api_secret,
             auth_service_url,
             user_service_url,
             vehicle_service_url,
+            vehicle_owner_cache_secs,
             booking_service_url,
+            file_scan_backend,
+            clamav_address,
+            av_gateway_url,
+            file_scan_timeout_secs,
+            file_scan_fail_open,
+            seller_sla_minutes,
+            auto_reply_cooldown_minutes,
+            ws_compression_threshold_bytes,
+            ws_compression_debug,
+            outbox_relay_interval_secs,
+            nats_dead_letter_subject,
+            max_message_length,
+            max_attachments_per_message,
+            last_message_preview_len,
+            search_snippet,
+            upload_image_policy,
+            upload_document_policy,
+            upload_audio_policy,
+            upload_rate_limit_window_secs,
+            upload_rate_limit_max,
+            retain_deleted_content,
+            strict_validation,
+            reply_token_secret,
+            reply_domain,
+            reply_token_ttl_days,
+            resend_api_key,
+            resend_from_email,
+            resend_inbound_secret,
+            sendgrid_inbound_basic_auth,
         })
     }
 
@@ -130,13 +348,12 @@ impl AppConfig {
         self.environment == "production"
     }
 
-    // Field aliases untuk kompatibilitas dengan services lainnya
-    pub fn host(&self) -> &str {
-        &self.server_host
-    }
-
-    pub fn port(&self) -> u16 {
-        self.server_port
+    // Email notifikasi message baru aktif jika reply-by-email dan Resend sudah dikonfigurasi
+    pub fn message_email_enabled(&self) -> bool {
+        self.reply_token_secret.is_some()
+            && self.reply_domain.is_some()
+            && self.resend_api_key.is_some()
+            && self.resend_from_email.is_some()
     }
 
     /// Kemudahan untuk pattern yang konsisten dengan services lainnya
@@ -168,11 +385,12 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 }
 
 // Health check database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
-        .fetch_optional(pool)
+        .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 // WebSocket Connection Limiter untuk mencegah abuse
@@ -226,8 +444,16 @@ pub struct AppState {
     pub nats_client: Option<async_nats::Client>,
     pub message_repo: crate::repositories::MessageRepository,
     pub conversation_repo: crate::repositories::ConversationRepository,
+    pub auto_reply_repo: crate::repositories::AutoReplyRepository,
+    pub seller_staff_repo: crate::repositories::SellerStaffRepository,
     pub ws_limiter: WebSocketConnectionLimiter,
     pub rate_limiter: Arc<RateLimiter>,
+    pub file_scanner: FileScanner,
+    pub vehicle_owners: VehicleOwnerLookup,
+    pub storage: StorageBackend,
+    pub outbox_repo: crate::repositories::OutboxRepository,
+    pub outbox_notify: Arc<Notify>,
+    pub nats_monitor: Arc<NatsMonitor>,
 }
 
 impl axum::extract::FromRef<AppState> for PgPool {
@@ -249,15 +475,28 @@ impl AppState {
             .await
             .map_err(|e| format!("Failed to init database: {}", e))?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "chat-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         let http_client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
-        // Initialize NATS client
-        let nats_client = match async_nats::connect(&config.nats_url).await {
+        // Initialize NATS client, tetap reconnect di background jika NATS belum up
+        let nats_monitor = Arc::new(NatsMonitor::new(config.nats_dead_letter_subject.clone()));
+        let event_monitor = nats_monitor.clone();
+        let nats_client = match async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .event_callback(move |event| {
+                let monitor = event_monitor.clone();
+                async move { monitor.handle_event(event) }
+            })
+            .connect(&config.nats_url)
+            .await
+        {
             Ok(client) => {
-                tracing::info!("✅ Terhubung ke NATS server");
+                tracing::info!("✅ NATS client siap (status: {:?})", client.connection_state());
                 Some(client)
             }
             Err(e) => {
@@ -266,9 +505,35 @@ impl AppState {
             }
         };
 
+        // Initialize attachment scanner
+        let scan_backend = ScanBackend::from_config(&config)?;
+        let file_scanner = FileScanner::new(
+            scan_backend,
+            http_client.clone(),
+            config.file_scan_timeout_secs,
+            config.file_scan_fail_open,
+        );
+        tracing::info!("🛡️ File scanner backend: {} (fail_open: {})",
+                       file_scanner.backend().name(), file_scanner.is_fail_open());
+
+        // Lookup pemilik vehicle ke vehicle-service dengan cache singkat
+        let vehicle_owners = VehicleOwnerLookup::new(
+            http_client.clone(),
+            config.vehicle_service_url.clone(),
+            config.vehicle_owner_cache_secs,
+        );
+
+        // Storage upload (Cloudinary atau S3/MinIO via STORAGE_BACKEND)
+        let storage = StorageBackend::from_env()
+            .map_err(|e| format!("Failed to init storage: {}", e))?;
+        tracing::info!("🗄️ Storage backend: {}", storage.name());
+
         // Initialize repositories
         let message_repo = crate::repositories::MessageRepository::new(db.clone());
         let conversation_repo = crate::repositories::ConversationRepository::new(db.clone());
+        let auto_reply_repo = crate::repositories::AutoReplyRepository::new(db.clone());
+        let seller_staff_repo = crate::repositories::SellerStaffRepository::new(db.clone());
+        let outbox_repo = crate::repositories::OutboxRepository::new(db.clone());
 
         // Initialize WebSocket connection limiter
         let ws_limiter = WebSocketConnectionLimiter::new();
@@ -289,8 +554,16 @@ impl AppState {
             nats_client,
             message_repo,
             conversation_repo,
+            auto_reply_repo,
+            seller_staff_repo,
             ws_limiter,
             rate_limiter: Arc::new(rate_limiter),
+            file_scanner,
+            vehicle_owners,
+            storage,
+            outbox_repo,
+            outbox_notify: Arc::new(Notify::new()),
+            nats_monitor,
         })
     }
 
@@ -301,45 +574,133 @@ impl AppState {
     }
 
     // Health check semua dependencies
+    // Probe dijalankan paralel dan masing-masing diukur latency-nya
     pub async fn health_check(&self) -> HealthCheckResponse {
-        let db_healthy = check_db_health(&self.db).await;
-
-        let nats_status = match &self.nats_client {
-            Some(client) => match client.connection_state() {
-                async_nats::connection::State::Connected => "connected".to_string(),
-                _ => "disconnected".to_string(),
-            },
-            None => "not_initialized".to_string(),
-        };
-
-        // Check Redis connection
-        let redis_status = match self.rate_limiter.check_rate_limit("health_check", "guest", "/health").await {
-            Ok(_) => "connected".to_string(),
-            Err(_) => "disconnected".to_string(),
-        };
-
-        let overall_status = if db_healthy && (nats_status == "connected" || nats_status == "not_initialized") && redis_status == "connected" {
-            "healthy"
-        } else {
-            "degraded"
-        };
+        let (database, redis) = tokio::join!(
+            health::probe("database", check_db_health(&self.db)),
+            health::probe("redis", async {
+                self.rate_limiter
+                    .check_rate_limit("health_check", "guest", "/health")
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }),
+        );
+
+        let nats_status = realtime::nats_status(self.nats_client.as_ref());
+
+        // Tanpa NATS service tetap melayani REST, tapi dilaporkan degraded
+        let status = health::worst_status(&[&database, &redis]).max(realtime::nats_health(nats_status));
 
         HealthCheckResponse {
             service: "chat-service".to_string(),
-            status: overall_status.to_string(),
+            status,
             version: env!("CARGO_PKG_VERSION").to_string(),
-            database: if db_healthy { "connected".to_string() } else { "disconnected".to_string() },
-            nats: nats_status,
-            redis: redis_status,
+            database,
+            nats: nats_status.to_string(),
+            redis,
             uptime: chrono::Utc::now().to_rfc3339(),
         }
     }
 
+    // Readiness: DB wajib terhubung, NATS opsional (lihat utils::realtime::is_ready)
+    pub async fn readiness_check(&self) -> ReadinessResponse {
+        let db_healthy = check_db_health(&self.db).await.is_ok();
+
+        let nats_status = realtime::nats_status(self.nats_client.as_ref());
+
+        ReadinessResponse {
+            ready: realtime::is_ready(db_healthy, nats_status),
+            database: if db_healthy { "connected".to_string() } else { "disconnected".to_string() },
+            nats: nats_status.to_string(),
+            nats_reconnect_count: self.nats_monitor.reconnect_count(),
+            nats_dead_letter_count: self.nats_monitor.dead_letter_count(),
+        }
+    }
+
     // Test database connection
     pub async fn test_database_connection(&self) -> Result<(), String> {
-        check_db_health(&self.db)
-            .await
-            .then_some(())
-            .ok_or_else(|| "Database connection failed".to_string())
+        check_db_health(&self.db).await
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+impl AppState {
+    // State untuk test handler dengan database sqlx::test; NATS, Redis, storage, dan
+    // vehicle-service tidak pernah dihubungi
+    pub fn for_test(db: PgPool) -> Self {
+        let jwt_secret = "test-secret".to_string();
+        let config = AppConfig {
+            database_url: String::new(),
+            server_host: "127.0.0.1".to_string(),
+            server_port: 3005,
+            metrics_addr: None,
+            environment: "development".to_string(),
+            jwt: JwtConfig::new(jwt_secret.clone()),
+            jwt_secret,
+            jwt_access_expiry: 900,
+            jwt_refresh_expiry: 604800,
+            nats_url: "nats://127.0.0.1:4222".to_string(),
+            redis_url: "redis://127.0.0.1:6379".to_string(),
+            auth_service_url: "http://127.0.0.1:3001".to_string(),
+            user_service_url: "http://127.0.0.1:3004".to_string(),
+            vehicle_service_url: "http://127.0.0.1:3003".to_string(),
+            vehicle_owner_cache_secs: DEFAULT_VEHICLE_OWNER_CACHE_SECS,
+            booking_service_url: "http://127.0.0.1:3002".to_string(),
+            file_scan_backend: "disabled".to_string(),
+            clamav_address: "127.0.0.1:3310".to_string(),
+            av_gateway_url: None,
+            file_scan_timeout_secs: 10,
+            file_scan_fail_open: false,
+            seller_sla_minutes: 60,
+            auto_reply_cooldown_minutes: DEFAULT_AUTO_REPLY_COOLDOWN_MINUTES,
+            ws_compression_threshold_bytes: 1024,
+            ws_compression_debug: false,
+            outbox_relay_interval_secs: 5,
+            nats_dead_letter_subject: "chat.dead_letter".to_string(),
+            max_message_length: 2000,
+            max_attachments_per_message: DEFAULT_MAX_ATTACHMENTS_PER_MESSAGE,
+            last_message_preview_len: DEFAULT_LAST_MESSAGE_PREVIEW_LEN,
+            search_snippet: SnippetOptions::default(),
+            upload_image_policy: UploadCategoryPolicy::from_env("IMAGE", "chat/images"),
+            upload_document_policy: UploadCategoryPolicy::from_env("DOCUMENT", "chat/documents"),
+            upload_audio_policy: UploadCategoryPolicy::from_env("AUDIO", "chat/audio"),
+            upload_rate_limit_window_secs: 60,
+            upload_rate_limit_max: 5,
+            retain_deleted_content: true,
+            strict_validation: false,
+            reply_token_secret: None,
+            reply_domain: None,
+            reply_token_ttl_days: 30,
+            resend_api_key: None,
+            resend_from_email: None,
+            resend_inbound_secret: None,
+            sendgrid_inbound_basic_auth: None,
+        };
+        let http_client = reqwest::Client::new();
+
+        AppState {
+            message_repo: crate::repositories::MessageRepository::new(db.clone()),
+            conversation_repo: crate::repositories::ConversationRepository::new(db.clone()),
+            auto_reply_repo: crate::repositories::AutoReplyRepository::new(db.clone()),
+            seller_staff_repo: crate::repositories::SellerStaffRepository::new(db.clone()),
+            outbox_repo: crate::repositories::OutboxRepository::new(db.clone()),
+            db,
+            nats_client: None,
+            ws_limiter: WebSocketConnectionLimiter::new(),
+            rate_limiter: Arc::new(RateLimiter::new(&config.redis_url).unwrap()),
+            file_scanner: FileScanner::new(ScanBackend::Disabled, http_client.clone(), config.file_scan_timeout_secs, false),
+            vehicle_owners: VehicleOwnerLookup::new(
+                http_client.clone(),
+                config.vehicle_service_url.clone(),
+                config.vehicle_owner_cache_secs,
+            ),
+            storage: StorageBackend::S3(shared::utils::storage::S3Storage::new(
+                "http://127.0.0.1:9000", "chat", "us-east-1", "test-key", "test-secret", None,
+            )),
+            outbox_notify: Arc::new(Notify::new()),
+            nats_monitor: Arc::new(NatsMonitor::new(config.nats_dead_letter_subject.clone())),
+            http_client,
+            config,
+        }
+    }
+}
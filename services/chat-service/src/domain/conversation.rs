@@ -11,6 +11,7 @@ pub struct Conversation {
     pub seller_id: i32,
     pub vehicle_id: Option<i32>,
     pub last_message: Option<String>,
+    pub last_message_sender_id: Option<i32>,
     pub last_message_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -18,7 +19,10 @@ pub struct Conversation {
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateConversationRequest {
-    pub seller_id: i32,
+    /// Wajib untuk customer, seller boleh kosong (otomatis dirinya sendiri)
+    pub seller_id: Option<i32>,
+    /// Wajib untuk seller yang follow-up customer yang pernah order/test drive
+    pub customer_id: Option<i32>,
     pub vehicle_id: Option<i32>,
 }
 
@@ -28,9 +32,14 @@ pub struct ConversationResponse {
     pub customer_id: i32,
     pub seller_id: i32,
     pub seller_name: String,
+    /// Staff dealer yang menangani conversation, null = seller langsung
+    pub assigned_to: Option<i32>,
     pub vehicle_id: Option<i32>,
     pub vehicle_title: Option<String>,
     pub last_message: Option<String>,
+    pub last_message_sender_id: Option<i32>,
+    /// Last message dikirim dari sisi user yang request (staff dealer dihitung sisi seller)
+    pub last_message_is_mine: bool,
     pub last_message_at: Option<DateTime<Utc>>,
     pub unread_count: i64,
     pub created_at: DateTime<Utc>,
@@ -108,6 +117,7 @@ impl ConversationWithDetails {
             "vehicle_id": self.vehicle_id(),
             "vehicle_title": self.vehicle_title,
             "last_message": self.last_message(),
+            "last_message_sender_id": self.conversation.last_message_sender_id,
             "last_message_at": self.last_message_at(),
             "unread_count": self.unread_messages,
             "created_at": self.created_at(),
@@ -126,6 +136,7 @@ impl Conversation {
             seller_id,
             vehicle_id,
             last_message: None,
+            last_message_sender_id: None,
             last_message_at: None,
             created_at: now,
             updated_at: now,
@@ -133,8 +144,9 @@ impl Conversation {
     }
 
     // Update last message info
-    pub fn update_last_message(&mut self, message: &str) {
+    pub fn update_last_message(&mut self, message: &str, sender_id: i32) {
         self.last_message = Some(message.to_string());
+        self.last_message_sender_id = Some(sender_id);
         self.last_message_at = Some(Utc::now());
         self.updated_at = Utc::now();
     }
@@ -154,4 +166,24 @@ impl Conversation {
             None
         }
     }
-}
\ No newline at end of file
+}
+
+// Filter inbox berdasarkan peran user di conversation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversationRoleFilter {
+    Customer,
+    Seller,
+    #[default]
+    All,
+}
+
+impl ConversationRoleFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConversationRoleFilter::Customer => "customer",
+            ConversationRoleFilter::Seller => "seller",
+            ConversationRoleFilter::All => "all",
+        }
+    }
+}
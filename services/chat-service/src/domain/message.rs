@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
 
+use crate::utils::media_proxy::{proxied_url, MediaVariant};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Message {
     pub id: i32,
@@ -15,7 +17,89 @@ pub struct Message {
     pub thumbnail_url: Option<String>,
     pub is_read: bool,
     pub read_at: Option<DateTime<Utc>>,
+    pub is_deleted: bool,
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub reply_to_message_id: Option<i32>,
+    // Root thread jika message ini balasan di thread, tetap tampil inline di timeline
+    pub thread_root_id: Option<i32>,
+    // Balasan otomatis seller (away message), tidak dihitung sebagai respon untuk SLA
+    pub is_auto_reply: bool,
+    // Preview message yang dibalas, diisi repository (bukan kolom)
+    #[sqlx(skip)]
+    pub reply_to: Option<QuotedMessage>,
+}
+
+// Isi tombstone untuk message yang sudah dihapus sender
+pub const DELETED_MESSAGE_TEXT: &str = "Pesan ini telah dihapus";
+
+// Default panjang preview last_message di inbox (override via LAST_MESSAGE_PREVIEW_LEN)
+pub const DEFAULT_LAST_MESSAGE_PREVIEW_LEN: usize = 50;
+
+// Panjang preview message yang dibalas (quote)
+pub const QUOTE_PREVIEW_LEN: usize = 100;
+
+// Potong teks maksimal max_chars karakter (bukan byte) agar tetap UTF-8 valid
+pub fn truncate_preview(content: &str, max_chars: usize) -> String {
+    match content.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &content[..end]),
+        None => content.to_string(),
+    }
+}
+
+// Preview ringkas message yang dibalas, tombstone jika message tersebut sudah dihapus
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuotedMessage {
+    pub id: i32,
+    pub sender_id: i32,
+    pub content: String,
+    pub message_type: String,
+    pub is_deleted: bool,
+}
+
+impl QuotedMessage {
+    pub fn from_message(message: &Message) -> Self {
+        let content = if message.is_deleted {
+            DELETED_MESSAGE_TEXT.to_string()
+        } else {
+            message.preview_text(QUOTE_PREVIEW_LEN)
+        };
+
+        Self {
+            id: message.id,
+            sender_id: message.sender_id,
+            content,
+            message_type: message.message_type.as_str().to_string(),
+            is_deleted: message.is_deleted,
+        }
+    }
+}
+
+// Thread selalu datar: balasan ke message yang sudah ada di thread masuk ke root thread yang sama
+pub fn resolve_thread_root(conversation_id: i32, target: Option<&Message>) -> Result<i32, &'static str> {
+    match target {
+        Some(target) if target.conversation_id == conversation_id => Ok(target.thread_root_id.unwrap_or(target.id)),
+        Some(_) => Err("Thread berasal dari conversation lain"),
+        None => Err("Thread tidak ditemukan"),
+    }
+}
+
+// Root thread beserta jumlah balasan untuk daftar thread conversation
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ThreadSummary {
+    #[sqlx(flatten)]
+    pub root: Message,
+    pub reply_count: i64,
+    pub last_reply_at: Option<DateTime<Utc>>,
+}
+
+// Message yang dibalas harus ada dan berada di conversation yang sama
+pub fn validate_reply_target(conversation_id: i32, target: Option<&Message>) -> Result<QuotedMessage, &'static str> {
+    match target {
+        Some(target) if target.conversation_id == conversation_id => Ok(QuotedMessage::from_message(target)),
+        Some(_) => Err("Pesan yang dibalas berasal dari conversation lain"),
+        None => Err("Pesan yang dibalas tidak ditemukan"),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
@@ -23,6 +107,8 @@ pub struct Message {
 pub enum MessageType {
     Text,
     Image,
+    Document,
+    Audio,
 }
 
 impl MessageType {
@@ -30,6 +116,8 @@ impl MessageType {
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "image" => MessageType::Image,
+            "document" => MessageType::Document,
+            "audio" => MessageType::Audio,
             _ => MessageType::Text,
         }
     }
@@ -38,6 +126,8 @@ impl MessageType {
     pub fn from_str_option(s: &Option<String>) -> Self {
         match s.as_ref().map(|s| s.to_lowercase()).unwrap_or_else(|| "text".to_string()).as_str() {
             "image" => MessageType::Image,
+            "document" => MessageType::Document,
+            "audio" => MessageType::Audio,
             _ => MessageType::Text,
         }
     }
@@ -47,6 +137,8 @@ impl MessageType {
         match self {
             MessageType::Text => "text",
             MessageType::Image => "image",
+            MessageType::Document => "document",
+            MessageType::Audio => "audio",
         }
     }
 }
@@ -54,14 +146,35 @@ impl MessageType {
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateMessageRequest {
     pub conversation_id: i32,
+    // Boleh kosong jika media_url diisi
+    #[serde(default)]
     pub content: String,
     pub message_type: Option<String>,
     pub media_url: Option<String>,
     pub thumbnail_url: Option<String>,
+    // Balas (quote) message tertentu di conversation yang sama
+    pub reply_to_message_id: Option<i32>,
+    // Kirim sebagai balasan thread (ID root atau message mana pun di thread tersebut)
+    pub thread_root_id: Option<i32>,
+    // Hanya diisi server untuk auto-reply seller, tidak bisa dikirim client
+    #[serde(skip)]
+    pub is_auto_reply: bool,
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Isi asli message yang dihapus sender (CHAT_RETAIN_DELETED_CONTENT), khusus moderasi admin
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeletedMessageOriginal {
+    pub message_id: i32,
+    pub conversation_id: i32,
+    pub sender_id: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub original_content: Option<String>,
+    /// Attachment di storage diganti path `/admin/messages/{id}/original/media`
+    pub original_media_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MessageResponse {
     pub id: i32,
     pub conversation_id: i32,
@@ -73,7 +186,12 @@ pub struct MessageResponse {
     pub thumbnail_url: Option<String>,
     pub is_read: bool,
     pub read_at: Option<DateTime<Utc>>,
+    pub is_deleted: bool,
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub reply_to: Option<QuotedMessage>,
+    pub thread_root_id: Option<i32>,
+    pub is_auto_reply: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -160,7 +278,13 @@ impl Message {
             thumbnail_url: None,
             is_read: false,
             read_at: None,
+            is_deleted: false,
+            deleted_at: None,
             created_at: Utc::now(),
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+            reply_to: None,
         }
     }
 
@@ -204,12 +328,37 @@ impl Message {
                 "media_url": self.media_url,
                 "thumbnail_url": self.thumbnail_url,
                 "is_read": self.is_read,
-                "created_at": self.created_at
+                "is_deleted": self.is_deleted,
+                "created_at": self.created_at,
+                "reply_to": self.reply_to,
+                "thread_root_id": self.thread_root_id,
+                "is_auto_reply": self.is_auto_reply
             }),
             timestamp: self.created_at,
         }
     }
 
+    // Payload event new_message untuk broadcast real-time via NATS
+    pub fn to_broadcast_payload(&self, sender_email: &str) -> serde_json::Value {
+        serde_json::json!({
+            "type": "new_message",
+            "conversation_id": self.conversation_id,
+            "message": {
+                "id": self.id,
+                "sender_id": self.sender_id,
+                "content": self.content,
+                "message_type": self.message_type,
+                "media_url": self.media_url,
+                "thumbnail_url": self.thumbnail_url,
+                "created_at": self.created_at,
+                "sender_email": sender_email,
+                "reply_to": self.reply_to,
+                "thread_root_id": self.thread_root_id,
+                "is_auto_reply": self.is_auto_reply
+            }
+        })
+    }
+
     // Convert ke MessageResponse untuk API consistency
     pub fn to_response(&self, sender_name: String) -> MessageResponse {
         MessageResponse {
@@ -223,12 +372,162 @@ impl Message {
             thumbnail_url: self.thumbnail_url.clone(),
             is_read: self.is_read,
             read_at: self.read_at,
+            is_deleted: self.is_deleted,
+            deleted_at: self.deleted_at,
             created_at: self.created_at,
+            reply_to: self.reply_to.clone(),
+            thread_root_id: self.thread_root_id,
+            is_auto_reply: self.is_auto_reply,
         }
     }
 
+    // Versi message untuk response/broadcast: media di storage kita diganti path proxy /messages/{id}/media
+    pub fn with_media_proxy(mut self, is_private: impl Fn(&str) -> bool) -> Self {
+        self.media_url = proxied_url(self.media_url, self.id, MediaVariant::Original, &is_private);
+        self.thumbnail_url = proxied_url(self.thumbnail_url, self.id, MediaVariant::Thumbnail, &is_private);
+        self
+    }
+
     // Validasi message content
-    pub fn is_valid(&self) -> bool {
-        !self.content.trim().is_empty() && self.content.len() <= 2000
+    pub fn is_valid(&self, max_length: usize, strict: bool) -> bool {
+        crate::utils::message_validation::validate_message_content(
+            &self.content,
+            self.media_url.is_some(),
+            max_length,
+            strict,
+        ).is_ok()
+    }
+
+    // Preview untuk last_message conversation (maksimal max_chars karakter)
+    pub fn preview_text(&self, max_chars: usize) -> String {
+        if self.content.trim().is_empty() {
+            return match self.message_type {
+                MessageType::Image => "📷 Gambar".to_string(),
+                MessageType::Document => "📄 Dokumen".to_string(),
+                MessageType::Audio => "🎤 Audio".to_string(),
+                MessageType::Text => "📎 File".to_string(),
+            };
+        }
+
+        truncate_preview(&self.content, max_chars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_message(content: &str) -> Message {
+        Message {
+            id: 1,
+            conversation_id: 1,
+            sender_id: 1,
+            content: content.to_string(),
+            message_type: MessageType::Text,
+            media_url: None,
+            thumbnail_url: None,
+            is_read: false,
+            read_at: None,
+            is_deleted: false,
+            deleted_at: None,
+            created_at: Utc::now(),
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+            reply_to: None,
+        }
+    }
+
+    #[test]
+    fn test_preview_respects_configured_length() {
+        assert_eq!(text_message("Halo kak").preview_text(20), "Halo kak");
+        assert_eq!(text_message("Mobilnya masih ada?").preview_text(6), "Mobiln...");
+        assert_eq!(text_message("abc").preview_text(3), "abc");
+    }
+
+    #[test]
+    fn test_preview_truncates_on_char_boundary() {
+        let preview = text_message("éééé🚗🚗").preview_text(5);
+        assert_eq!(preview, "éééé🚗...");
+
+        let preview = text_message(&"🚗".repeat(60)).preview_text(DEFAULT_LAST_MESSAGE_PREVIEW_LEN);
+        assert_eq!(preview.trim_end_matches("...").chars().count(), DEFAULT_LAST_MESSAGE_PREVIEW_LEN);
+    }
+
+    #[test]
+    fn test_preview_for_media_without_caption() {
+        let mut message = text_message("  ");
+        message.message_type = MessageType::Image;
+        assert_eq!(message.preview_text(10), "📷 Gambar");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_media_proxy_hides_storage_urls() {
+        let mut message = text_message("");
+        message.id = 42;
+        message.message_type = MessageType::Image;
+        message.media_url = Some("https://res.cloudinary.com/bigauto/chat/a.jpg".to_string());
+        message.thumbnail_url = Some("https://res.cloudinary.com/bigauto/chat/a.jpg?w=200&h=200&c_thumb".to_string());
+
+        let proxied = message.with_media_proxy(|url| url.starts_with("https://res.cloudinary.com/"));
+        assert_eq!(proxied.media_url.as_deref(), Some("/messages/42/media"));
+        assert_eq!(proxied.thumbnail_url.as_deref(), Some("/messages/42/media?variant=thumbnail"));
+
+        let payload = proxied.to_broadcast_payload("a@example.com").to_string();
+        assert!(!payload.contains("cloudinary"));
+    }
+
+    #[test]
+    fn test_reply_rejected_across_conversations() {
+        let mut target = text_message("Masih ada kak?");
+        target.conversation_id = 2;
+
+        assert!(validate_reply_target(1, Some(&target)).is_err());
+        assert!(validate_reply_target(1, None).is_err());
+
+        let quote = validate_reply_target(2, Some(&target)).unwrap();
+        assert_eq!(quote.id, target.id);
+        assert_eq!(quote.content, "Masih ada kak?");
+    }
+
+    #[test]
+    fn test_deleted_quote_shows_tombstone() {
+        let mut target = text_message("Nomor rekening saya ...");
+        target.is_deleted = true;
+
+        let quote = QuotedMessage::from_message(&target);
+        assert!(quote.is_deleted);
+        assert_eq!(quote.content, DELETED_MESSAGE_TEXT);
+
+        let mut message = text_message("Yang ini maksudnya?");
+        message.reply_to_message_id = Some(target.id);
+        message.reply_to = Some(quote);
+        let payload = message.to_broadcast_payload("a@example.com");
+        assert_eq!(payload["message"]["reply_to"]["content"], DELETED_MESSAGE_TEXT);
+    }
+
+    #[test]
+    fn test_thread_replies_group_under_root() {
+        let root = text_message("Unit Avanza ini bisa nego?");
+
+        let mut first_reply = text_message("Bisa kak");
+        first_reply.id = 2;
+        first_reply.thread_root_id = Some(resolve_thread_root(1, Some(&root)).unwrap());
+        assert_eq!(first_reply.thread_root_id, Some(root.id));
+
+        // Balasan ke balasan tetap di thread root yang sama
+        assert_eq!(resolve_thread_root(1, Some(&first_reply)), Ok(root.id));
+
+        let payload = first_reply.to_broadcast_payload("a@example.com");
+        assert_eq!(payload["message"]["thread_root_id"], root.id);
+    }
+
+    #[test]
+    fn test_thread_rejected_across_conversations() {
+        let mut root = text_message("Topik lain");
+        root.conversation_id = 9;
+
+        assert!(resolve_thread_root(1, Some(&root)).is_err());
+        assert!(resolve_thread_root(1, None).is_err());
+    }
+}
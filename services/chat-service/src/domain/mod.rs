@@ -1,7 +1,9 @@
 // Modul domain untuk Chat Service
 pub mod conversation;
 pub mod message;
+pub mod seller_staff;
 
 // Export publik untuk semua services
 pub use conversation::*;
-pub use message::*;
\ No newline at end of file
+pub use message::*;
+pub use seller_staff::*;
\ No newline at end of file
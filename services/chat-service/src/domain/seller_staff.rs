@@ -0,0 +1,32 @@
+// Domain model staff dealer (organisasi seller) dan assignment conversation
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Anggota staff di organisasi seller
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SellerStaffMember {
+    pub staff_user_id: i32,
+    pub name: String,
+    pub email: String,
+    pub added_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddSellerStaffRequest {
+    /// User yang dijadikan staff, tidak boleh akun seller dan belum jadi staff dealer lain
+    pub staff_user_id: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AssignConversationRequest {
+    /// Staff yang menangani conversation; null atau seller_id = kembali ke seller
+    pub assigned_to: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConversationAssignmentResponse {
+    pub conversation_id: i32,
+    pub assigned_to: Option<i32>,
+    pub assigned_at: Option<DateTime<Utc>>,
+}
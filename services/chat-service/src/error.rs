@@ -1,9 +1,11 @@
 use axum::{
-    http::StatusCode,
+    extract::ws::{close_code, CloseCode},
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
+use shared::utils::request_id;
 use std::fmt;
 
 // Custom error type untuk chat service dengan response standardized
@@ -14,8 +16,9 @@ pub enum AppError {
     Unauthorized(String),
     Forbidden(String),
     BadRequest(String),
+    Conflict(String),
     ValidationError(String),
-    RateLimit(String),
+    RateLimit { message: String, retry_after_secs: u64 },
     WebSocket(String),
     NATS(String),
     InternalServer(String),
@@ -38,12 +41,20 @@ impl AppError {
         Self::BadRequest(msg.into())
     }
 
+    pub fn conflict(msg: impl Into<String>) -> Self {
+        Self::Conflict(msg.into())
+    }
+
     pub fn validation(msg: impl Into<String>) -> Self {
         Self::ValidationError(msg.into())
     }
 
-    pub fn rate_limit(msg: impl Into<String>) -> Self {
-        Self::RateLimit(msg.into())
+    // Retry-After dalam detik, minimal 1
+    pub fn rate_limit(msg: impl Into<String>, retry_after_secs: u64) -> Self {
+        Self::RateLimit {
+            message: msg.into(),
+            retry_after_secs: retry_after_secs.max(1),
+        }
     }
 
     pub fn websocket(msg: impl Into<String>) -> Self {
@@ -58,9 +69,26 @@ impl AppError {
         Self::InternalServer(msg.into())
     }
 
-    pub fn cloudinary(msg: impl Into<String>) -> Self {
+    pub fn storage(msg: impl Into<String>) -> Self {
         Self::BadRequest(format!("File upload error: {}", msg.into()))
     }
+
+    // Close code untuk error yang harus memutus koneksi WebSocket, None = cukup kirim pesan error
+    pub fn ws_close_code(&self) -> Option<CloseCode> {
+        match self {
+            AppError::WebSocket(_) => Some(close_code::UNSUPPORTED),
+            AppError::Unauthorized(_) => Some(close_code::POLICY),
+            AppError::NATS(_) | AppError::InternalServer(_) | AppError::DatabaseError(_) => {
+                Some(close_code::ERROR)
+            }
+            AppError::NotFound(_)
+            | AppError::Forbidden(_)
+            | AppError::BadRequest(_)
+            | AppError::Conflict(_)
+            | AppError::ValidationError(_)
+            | AppError::RateLimit { .. } => None,
+        }
+    }
 }
 
 // Konversi dari sqlx::Error ke AppError
@@ -80,15 +108,26 @@ impl From<sqlx::Error> for AppError {
 impl From<async_nats::Error> for AppError {
     fn from(err: async_nats::Error) -> Self {
         tracing::error!("NATS error: {:?}", err);
-        AppError::NATS(format!("NATS connection error: {}", err))
+        AppError::nats(format!("NATS connection error: {}", err))
     }
 }
 
-// Konversi dari axum::Error ke AppError untuk WebSocket
+// Konversi dari axum::Error (transport WebSocket) ke AppError
 impl From<axum::Error> for AppError {
     fn from(err: axum::Error) -> Self {
-        tracing::error!("WebSocket error: {:?}", err);
-        AppError::WebSocket(format!("WebSocket error: {}", err))
+        tracing::error!("WebSocket transport error: {:?}", err);
+        AppError::InternalServer(format!("WebSocket transport error: {}", err))
+    }
+}
+
+// Konversi dari shared AuthError, status mengikuti shared agar sama di semua service
+impl From<shared::auth::AuthError> for AppError {
+    fn from(err: shared::auth::AuthError) -> Self {
+        match err.status_code() {
+            StatusCode::FORBIDDEN => AppError::forbidden(err.to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR => AppError::internal(err.to_string()),
+            _ => AppError::unauthorized(err.to_string()),
+        }
     }
 }
 
@@ -113,21 +152,19 @@ impl IntoResponse for AppError {
             ),
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "forbidden", msg.clone()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg.clone()),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg.clone()),
             AppError::ValidationError(msg) => {
                 tracing::warn!("Validation error: {}", msg);
                 (StatusCode::UNPROCESSABLE_ENTITY, "validation_error", msg.clone())
             },
-            AppError::RateLimit(msg) => {
-                tracing::warn!("Rate limit exceeded: {}", msg);
-                (StatusCode::TOO_MANY_REQUESTS, "rate_limit", msg.clone())
+            AppError::RateLimit { message, .. } => {
+                tracing::warn!("Rate limit exceeded: {}", message);
+                (StatusCode::TOO_MANY_REQUESTS, "rate_limit", message.clone())
             },
+            // Protocol error dari client (format message salah)
             AppError::WebSocket(msg) => {
-                tracing::error!("WebSocket error: {}", msg);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "websocket_error",
-                    msg.clone(),
-                )
+                tracing::warn!("WebSocket protocol error: {}", msg);
+                (StatusCode::BAD_REQUEST, "websocket_error", msg.clone())
             },
             AppError::NATS(msg) => {
                 tracing::error!("NATS error: {}", msg);
@@ -147,12 +184,34 @@ impl IntoResponse for AppError {
             },
         };
 
-        let body = Json(json!({
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
+        let mut body = json!({
             "error": error_type,
             "message": message,
-        }));
+            "request_id": request_id,
+        });
+
+        // Beri tahu client kapan boleh mencoba lagi (body dan header Retry-After)
+        let retry_after = match &self {
+            AppError::RateLimit { retry_after_secs, .. } => Some(*retry_after_secs),
+            _ => None,
+        };
 
-        (status, body).into_response()
+        if let Some(secs) = retry_after {
+            body["retry_after"] = json!(secs);
+        }
+
+        let mut response = (status, Json(body)).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+
+        response
     }
 }
 
@@ -165,11 +224,28 @@ impl fmt::Display for AppError {
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
             AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
             AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
-            AppError::RateLimit(msg) => write!(f, "Rate limit exceeded: {}", msg),
+            AppError::RateLimit { message, .. } => write!(f, "Rate limit exceeded: {}", message),
             AppError::WebSocket(msg) => write!(f, "WebSocket error: {}", msg),
             AppError::NATS(msg) => write!(f, "NATS error: {}", msg),
             AppError::InternalServer(msg) => write!(f, "Internal server error: {}", msg),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("Conversation tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
+    }
+}
@@ -0,0 +1,126 @@
+// Auto-reply Handlers: pengaturan away message seller
+use axum::{extract::State, response::Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    config::AppState,
+    error::AppError,
+    middleware::ChatParticipant,
+    utils::auto_reply::{parse_active_hours, parse_timezone, AutoReplySettings, DEFAULT_TIMEZONE},
+    utils::message_validation::validate_message_content,
+};
+
+// Request update auto-reply seller
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateAutoReplyRequest {
+    pub enabled: bool,
+    /// Isi balasan otomatis, wajib jika enabled
+    #[serde(default)]
+    pub message: String,
+    /// Jam aktif lokal "HH:MM", kosongkan start & end agar auto-reply hanya untuk message pertama
+    pub active_start: Option<String>,
+    pub active_end: Option<String>,
+    /// Timezone IANA jam aktif, default Asia/Jakarta
+    pub timezone: Option<String>,
+}
+
+// Response pengaturan auto-reply seller
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AutoReplyResponse {
+    pub enabled: bool,
+    pub message: String,
+    pub active_start: Option<String>,
+    pub active_end: Option<String>,
+    pub timezone: String,
+}
+
+impl From<AutoReplySettings> for AutoReplyResponse {
+    fn from(settings: AutoReplySettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            message: settings.message,
+            active_start: settings.active_hours.map(|hours| hours.start.format("%H:%M").to_string()),
+            active_end: settings.active_hours.map(|hours| hours.end.format("%H:%M").to_string()),
+            timezone: settings.timezone.name().to_string(),
+        }
+    }
+}
+
+// Ambil pengaturan auto-reply seller yang login
+#[utoipa::path(
+    get,
+    path = "/auto-reply",
+    tag = "auto-reply",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Pengaturan auto-reply", body = AutoReplyResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_auto_reply(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+) -> Result<Json<AutoReplyResponse>, AppError> {
+    if !participant.is_seller() {
+        return Err(AppError::forbidden("Auto-reply hanya untuk seller"));
+    }
+
+    let settings = state.auto_reply_repo
+        .get_settings(participant.user_id)
+        .await?
+        .unwrap_or_else(|| AutoReplySettings::from_stored(false, String::new(), None, None, DEFAULT_TIMEZONE));
+
+    Ok(Json(settings.into()))
+}
+
+// Atur auto-reply seller yang login
+#[utoipa::path(
+    put,
+    path = "/auto-reply",
+    tag = "auto-reply",
+    security(("bearer_auth" = [])),
+    request_body = UpdateAutoReplyRequest,
+    responses(
+        (status = 200, description = "Auto-reply diupdate", body = AutoReplyResponse),
+        (status = 400, description = "Message, jam aktif, atau timezone tidak valid"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_auto_reply(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Json(request): Json<UpdateAutoReplyRequest>,
+) -> Result<Json<AutoReplyResponse>, AppError> {
+    if !participant.is_seller() {
+        return Err(AppError::forbidden("Auto-reply hanya untuk seller"));
+    }
+
+    let message = request.message.trim();
+    if request.enabled || !message.is_empty() {
+        validate_message_content(message, false, state.config.max_message_length, state.config.strict_validation)?;
+    }
+
+    let active_hours = parse_active_hours(request.active_start.as_deref(), request.active_end.as_deref())
+        .map_err(AppError::validation)?;
+
+    let timezone = parse_timezone(request.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE))
+        .ok_or_else(|| AppError::validation("Timezone tidak dikenal, gunakan nama IANA seperti Asia/Jakarta"))?;
+
+    let settings = state.auto_reply_repo
+        .upsert_settings(participant.user_id, request.enabled, message, active_hours, timezone.name())
+        .await?;
+
+    tracing::info!(
+        event = "auto_reply_updated",
+        seller_id = participant.user_id,
+        enabled = settings.enabled,
+        "Seller mengatur auto-reply"
+    );
+
+    Ok(Json(settings.into()))
+}
@@ -5,20 +5,29 @@ use axum::{
     response::Json,
 };
 use serde::{Deserialize, Serialize};
+use shared::utils::{
+    creation::Creation,
+    pagination::{Pagination, PaginationParams},
+};
 use utoipa::ToSchema;
 
 use crate::{
     config::AppState,
-    domain::conversation::{CreateConversationRequest, ConversationResponse},
-    middleware::{ChatParticipant, AuthUser},
+    domain::conversation::{ConversationRoleFilter, CreateConversationRequest, ConversationResponse},
+    middleware::{ChatParticipant, AuthUser, ConversationAccess, is_chat_role},
     error::AppError,
+    handlers::websocket::broadcast_conversation_updated,
+    utils::conversation_initiation::{self, FindOrCreate, InitiationError},
+    utils::{assignment, realtime, retention, unread},
 };
 
-// Query parameters untuk pagination
+// Query list conversation: filter inbox (pagination via PaginationParams)
 #[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
-pub struct PaginationQuery {
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
+pub struct ConversationListQuery {
+    /// customer = "Pembelian saya", seller = "Penjualan saya", all (default)
+    pub role: Option<ConversationRoleFilter>,
+    /// Hanya conversation yang punya pesan belum dibaca
+    pub unread_only: Option<bool>,
 }
 
 // Response untuk conversation list
@@ -40,22 +49,27 @@ pub struct ConversationWithDetailsResponse {
     pub vehicle_id: Option<i32>,
     pub vehicle_title: Option<String>,
     pub last_message: Option<String>,
+    pub last_message_sender_id: Option<i32>,
+    /// Diisi relatif ke user yang request
+    #[serde(default)]
+    pub last_message_is_mine: bool,
     pub last_message_at: Option<chrono::DateTime<chrono::Utc>>,
     pub unread_count: i64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-impl Default for PaginationQuery {
-    fn default() -> Self {
-        Self {
-            limit: Some(20),
-            offset: Some(0),
+impl From<InitiationError> for AppError {
+    fn from(err: InitiationError) -> Self {
+        match err {
+            InitiationError::Forbidden(msg) => AppError::forbidden(msg),
+            InitiationError::BadRequest(msg) => AppError::bad_request(msg),
+            InitiationError::NotFound(msg) => AppError::not_found(msg),
         }
     }
 }
 
-// Buat conversation baru
+// Buat conversation baru (customer, atau seller follow-up customer yang pernah order/test drive)
 #[utoipa::path(
     post,
     path = "/conversations",
@@ -63,9 +77,13 @@ impl Default for PaginationQuery {
     security(("bearer_auth" = [])),
     request_body = CreateConversationRequest,
     responses(
-        (status = 201, description = "Conversation berhasil dibuat", body = ConversationResponse),
-        (status = 400, description = "Request tidak valid"),
+        (status = 201, description = "Conversation berhasil dibuat", body = ConversationResponse,
+            headers(("Location" = String, description = "URL conversation"))),
+        (status = 200, description = "Conversation yang sudah ada dikembalikan", body = ConversationResponse),
+        (status = 400, description = "Request tidak valid atau seller bukan pemilik vehicle"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Seller tidak punya relasi order/test drive dengan customer"),
+        (status = 404, description = "Vehicle tidak ditemukan"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -73,115 +91,100 @@ pub async fn create_conversation(
     State(state): State<AppState>,
     user: AuthUser,
     Json(request): Json<CreateConversationRequest>,
-) -> Result<(StatusCode, Json<ConversationResponse>), AppError> {
-    // Validasi role user - hanya customer yang bisa membuat conversation
-    if !user.is_customer() {
-        return Err(AppError::forbidden("Hanya customer yang bisa membuat conversation baru"));
+) -> Result<Creation<ConversationResponse>, AppError> {
+    // Tentukan customer/seller dari role pembuat (termasuk guard conversation dengan diri sendiri)
+    let parties = conversation_initiation::resolve_parties(
+        user.user_id,
+        &user.role,
+        request.seller_id,
+        request.customer_id,
+    )?;
+
+    // Seller tidak boleh cold outreach, hanya follow-up customer dengan riwayat booking
+    if parties.initiated_by_seller {
+        let related = state.conversation_repo
+            .has_booking_relationship(parties.seller_id, parties.customer_id)
+            .await?;
+        conversation_initiation::check_seller_outreach(&parties, related)?;
     }
 
-    // Validasi request
-    if request.seller_id == user.user_id {
-        return Err(AppError::bad_request("Tidak bisa membuat conversation dengan diri sendiri"));
+    // seller_id harus pemilik vehicle, mencegah conversation palsu atas nama seller lain
+    if let Some(vehicle_id) = request.vehicle_id {
+        let owner = state.vehicle_owners.owner_of(vehicle_id).await?;
+        conversation_initiation::check_vehicle_owner(&parties, owner)?;
     }
 
-    // Cek apakah conversation sudah ada antara user ini dan seller dengan vehicle yang sama
-    let existing_conversation = sqlx::query!(
-        r#"
-        SELECT c.id, c.customer_id, c.seller_id, c.vehicle_id,
-               c.last_message, c.last_message_at, c.created_at, c.updated_at,
-               u.name as seller_name, v.title as vehicle_title
-        FROM conversations c
-        JOIN users u ON c.seller_id = u.id
-        LEFT JOIN vehicles v ON c.vehicle_id = v.id
-        WHERE (c.customer_id = $1 AND c.seller_id = $2 AND c.vehicle_id = $3)
-           OR (c.customer_id = $3 AND c.seller_id = $2 AND c.vehicle_id = $1)
-        ORDER BY c.created_at DESC
-        LIMIT 1
-        "#,
-        user.user_id, request.seller_id, request.vehicle_id
-    )
-    .fetch_optional(&state.db)
-    .await?;
-
-    if let Some(conv) = existing_conversation {
-        // Hitung unread count yang REAL dari database
-        let unread_count = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM messages
-             WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-            conv.id,
-            user.user_id
-        )
-        .fetch_one(&state.db)
+    // Satu conversation per customer-seller-vehicle, dijaga unique index di DB
+    let outcome = state.conversation_repo
+        .find_or_create_conversation(parties.customer_id, parties.seller_id, request.vehicle_id)
         .await?
-        .unwrap_or(0);
+        .ok_or_else(|| AppError::InternalServer("Conversation gagal dibuat, silakan coba lagi".to_string()))?;
 
-        let response = ConversationResponse {
-            id: conv.id,
-            customer_id: conv.customer_id,
-            seller_id: conv.seller_id,
-            seller_name: conv.seller_name,
-            vehicle_id: conv.vehicle_id,
-            vehicle_title: Some(conv.vehicle_title),
-            last_message: conv.last_message,
-            last_message_at: conv.last_message_at,
-            unread_count,
-            created_at: conv.created_at.unwrap_or_else(|| chrono::Utc::now()),
-            updated_at: conv.updated_at.unwrap_or_else(|| chrono::Utc::now()),
-        };
-
-        tracing::info!("Existing conversation {} found for user {} with {} unread messages",
-                      conv.id, user.user_id, unread_count);
-
-        return Ok((StatusCode::OK, Json(response)));
-    }
+    let (conversation_id, created) = match outcome {
+        FindOrCreate::Existing(id) => (id, false),
+        FindOrCreate::Created(id) => (id, true),
+    };
 
-    // Buat conversation baru
-    let conversation_id = sqlx::query_scalar!(
+    // Nama seller, judul vehicle, dan unread count (counter denormalized) dalam satu query
+    let conv = sqlx::query!(
         r#"
-        INSERT INTO conversations (customer_id, seller_id, vehicle_id, created_at, updated_at)
-        VALUES ($1, $2, $3, NOW(), NOW())
-        RETURNING id
-        "#,
-        user.user_id, request.seller_id, request.vehicle_id
-    )
-    .fetch_one(&state.db)
-    .await?;
-
-    // Ambil conversation yang baru dibuat dengan details
-    let conversation = sqlx::query!(
-        r#"
-        SELECT c.id, c.customer_id, c.seller_id, c.vehicle_id,
-               c.last_message, c.last_message_at, c.created_at, c.updated_at,
-               u.name as seller_name, v.title as vehicle_title
+        SELECT c.id, c.customer_id, c.seller_id, c.assigned_to, c.vehicle_id,
+               c.last_message, c.last_message_sender_id, c.last_message_at, c.created_at, c.updated_at,
+               u.name as seller_name, v.title as "vehicle_title?",
+               (CASE WHEN c.customer_id = $2 THEN c.customer_unread_count ELSE c.seller_unread_count END)::BIGINT as "unread_count!"
         FROM conversations c
         JOIN users u ON c.seller_id = u.id
         LEFT JOIN vehicles v ON c.vehicle_id = v.id
         WHERE c.id = $1
         "#,
-        conversation_id
+        conversation_id, user.user_id
     )
     .fetch_one(&state.db)
     .await?;
 
+    let unread_count = conv.unread_count;
+
     let response = ConversationResponse {
-        id: conversation.id,
-        customer_id: conversation.customer_id,
-        seller_id: conversation.seller_id,
-        seller_name: conversation.seller_name,
-        vehicle_id: conversation.vehicle_id,
-        vehicle_title: Some(conversation.vehicle_title),
-        last_message: conversation.last_message,
-        last_message_at: conversation.last_message_at,
-        unread_count: 0, 
-        created_at: conversation.created_at.unwrap_or_else(|| chrono::Utc::now()),
-        updated_at: conversation.updated_at.unwrap_or_else(|| chrono::Utc::now()),
+        id: conv.id,
+        customer_id: conv.customer_id,
+        seller_id: conv.seller_id,
+        seller_name: conv.seller_name,
+        assigned_to: conv.assigned_to,
+        vehicle_id: conv.vehicle_id,
+        vehicle_title: conv.vehicle_title,
+        last_message: conv.last_message,
+        last_message_sender_id: conv.last_message_sender_id,
+        last_message_is_mine: unread::last_message_is_mine(conv.last_message_sender_id, user.user_id, conv.customer_id),
+        last_message_at: conv.last_message_at,
+        unread_count,
+        created_at: conv.created_at.unwrap_or_else(|| chrono::Utc::now()),
+        updated_at: conv.updated_at.unwrap_or_else(|| chrono::Utc::now()),
     };
 
-    tracing::info!("Conversation {} created by user {} with seller {} for vehicle {}",
-                  conversation_id, user.user_id, request.seller_id,
-                  request.vehicle_id.unwrap_or(0));
+    if !created {
+        tracing::info!(
+            event = "conversation_reused",
+            conversation_id,
+            user_id = user.user_id,
+            unread_count,
+            "Existing conversation found"
+        );
+
+        return Ok(Creation::existing(response));
+    }
 
-    Ok((StatusCode::CREATED, Json(response)))
+    tracing::info!(
+        event = "conversation_created",
+        conversation_id,
+        user_id = user.user_id,
+        customer_id = parties.customer_id,
+        seller_id = parties.seller_id,
+        vehicle_id = ?request.vehicle_id,
+        initiated_by_seller = parties.initiated_by_seller,
+        "Conversation created"
+    );
+
+    Ok(Creation::created(format!("/api/conversations/{}", conversation_id), response))
 }
 
 // Ambil conversations user (customer atau seller)
@@ -192,10 +195,12 @@ pub async fn create_conversation(
     security(("bearer_auth" = [])),
     params(
         ("user_id" = i32, Path, description = "User ID"),
-        PaginationQuery
+        ConversationListQuery,
+        PaginationParams
     ),
     responses(
         (status = 200, description = "Daftar conversations berhasil diambil", body = ConversationListResponse),
+        (status = 400, description = "Parameter paginasi tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -203,53 +208,52 @@ pub async fn create_conversation(
 pub async fn get_user_conversations(
     State(state): State<AppState>,
     participant: ChatParticipant,
-    Query(query): Query<PaginationQuery>,
+    Query(query): Query<ConversationListQuery>,
+    Pagination { limit, offset, .. }: Pagination,
 ) -> Result<Json<ConversationListResponse>, AppError> {
-    let limit = query.limit.unwrap_or(20).min(100);
-    let offset = query.offset.unwrap_or(0);
+    let role = query.role.unwrap_or_default();
+    let unread_only = query.unread_only.unwrap_or(false);
 
-    // Query conversations dengan join ke users dan vehicles untuk response lengkap
+    // Query conversations dengan join ke users dan vehicles, filter role/unread di SQL.
+    // Staff dealer hanya melihat conversation yang di-assign ke dirinya (sisi seller)
     let conversations_raw = sqlx::query!(
         r#"
-        SELECT c.id, c.customer_id, c.seller_id, c.vehicle_id,
-               c.last_message, c.last_message_at, c.created_at, c.updated_at,
+        SELECT c.id, c.customer_id, c.seller_id, c.assigned_to, c.vehicle_id,
+               c.last_message, c.last_message_sender_id, c.last_message_at, c.created_at, c.updated_at,
                cu.name as customer_name,
                su.name as seller_name,
-               v.title as vehicle_title
+               v.title as "vehicle_title?",
+               (CASE WHEN c.customer_id = $1 THEN c.customer_unread_count ELSE c.seller_unread_count END)::BIGINT as "unread_count!"
         FROM conversations c
         JOIN users cu ON c.customer_id = cu.id
         JOIN users su ON c.seller_id = su.id
         LEFT JOIN vehicles v ON c.vehicle_id = v.id
-        WHERE c.customer_id = $1 OR c.seller_id = $1
+        WHERE ((c.customer_id = $1 AND $4 IN ('customer', 'all'))
+            OR ((c.seller_id = $1 OR c.assigned_to = $1) AND $4 IN ('seller', 'all')))
+          AND (NOT $5 OR (CASE WHEN c.customer_id = $1 THEN c.customer_unread_count ELSE c.seller_unread_count END) > 0)
         ORDER BY c.updated_at DESC
         LIMIT $2 OFFSET $3
         "#,
-        participant.user_id, limit, offset
+        participant.user_id, limit, offset, role.as_str(), unread_only
     )
     .fetch_all(&state.db)
     .await?;
 
-    // Build response dengan unread count untuk setiap conversation
     let mut conversations = Vec::new();
     for conv in conversations_raw {
-        let unread_count = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM messages
-             WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-            conv.id,
-            participant.user_id
-        )
-        .fetch_one(&state.db)
-        .await?
-        .unwrap_or(0);
+        let unread_count = conv.unread_count;
 
         let response = ConversationResponse {
             id: conv.id,
             customer_id: conv.customer_id,
             seller_id: conv.seller_id,
             seller_name: conv.seller_name,
+            assigned_to: conv.assigned_to,
             vehicle_id: conv.vehicle_id,
-            vehicle_title: Some(conv.vehicle_title),
+            vehicle_title: conv.vehicle_title,
             last_message: conv.last_message,
+            last_message_sender_id: conv.last_message_sender_id,
+            last_message_is_mine: unread::last_message_is_mine(conv.last_message_sender_id, participant.user_id, conv.customer_id),
             last_message_at: conv.last_message_at,
             unread_count,
             created_at: conv.created_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -258,18 +262,20 @@ pub async fn get_user_conversations(
         conversations.push(response);
     }
 
-    // Hitung total conversations untuk user ini
+    // Hitung total dengan filter yang sama untuk pagination
     let total = sqlx::query_scalar!(
         "SELECT COUNT(*) FROM conversations
-         WHERE customer_id = $1 OR seller_id = $1",
-        participant.user_id
+         WHERE ((customer_id = $1 AND $2 IN ('customer', 'all'))
+             OR ((seller_id = $1 OR assigned_to = $1) AND $2 IN ('seller', 'all')))
+           AND (NOT $3 OR (CASE WHEN customer_id = $1 THEN customer_unread_count ELSE seller_unread_count END) > 0)",
+        participant.user_id, role.as_str(), unread_only
     )
     .fetch_one(&state.db)
     .await?
     .unwrap_or(0);
 
-    tracing::info!("User {} retrieved {} conversations ({} total)",
-                  participant.user_id, conversations.len(), total);
+    tracing::info!("User {} retrieved {} conversations ({} total, role {}, unread_only {})",
+                  participant.user_id, conversations.len(), total, role.as_str(), unread_only);
 
     Ok(Json(ConversationListResponse {
         conversations,
@@ -304,44 +310,39 @@ pub async fn get_conversation_by_id(
     // Cek apakah conversation ada
     let conversation = sqlx::query!(
         r#"
-        SELECT c.id, c.customer_id, c.seller_id, c.vehicle_id,
-               c.last_message, c.last_message_at, c.created_at, c.updated_at,
-               u.name as seller_name, v.title as vehicle_title
+        SELECT c.id, c.customer_id, c.seller_id, c.assigned_to, c.vehicle_id,
+               c.last_message, c.last_message_sender_id, c.last_message_at, c.created_at, c.updated_at,
+               u.name as seller_name, v.title as "vehicle_title?",
+               (CASE WHEN c.customer_id = $2 THEN c.customer_unread_count ELSE c.seller_unread_count END)::BIGINT as "unread_count!"
         FROM conversations c
         JOIN users u ON c.seller_id = u.id
         LEFT JOIN vehicles v ON c.vehicle_id = v.id
         WHERE c.id = $1
         "#,
-        conversation_id
+        conversation_id, user.user_id
     )
     .fetch_optional(&state.db)
     .await?
     .ok_or_else(|| AppError::not_found("Conversation tidak ditemukan"))?;
 
-    // Gunakan AuthUser method untuk validasi akses
-    if !user.can_access_conversation(conversation.customer_id, conversation.seller_id) {
+    // Gunakan AuthUser method untuk validasi akses (termasuk staff yang di-assign)
+    if !user.can_access_conversation(conversation.customer_id, conversation.seller_id, conversation.assigned_to) {
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    // Hitung unread count
-    let unread_count = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM messages
-         WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-        conversation_id,
-        user.user_id
-    )
-    .fetch_one(&state.db)
-    .await?
-    .unwrap_or(0);
+    let unread_count = conversation.unread_count;
 
     let response = ConversationResponse {
         id: conversation.id,
         customer_id: conversation.customer_id,
         seller_id: conversation.seller_id,
         seller_name: conversation.seller_name,
+        assigned_to: conversation.assigned_to,
         vehicle_id: conversation.vehicle_id,
-        vehicle_title: Some(conversation.vehicle_title),
+        vehicle_title: conversation.vehicle_title,
         last_message: conversation.last_message,
+        last_message_sender_id: conversation.last_message_sender_id,
+        last_message_is_mine: unread::last_message_is_mine(conversation.last_message_sender_id, user.user_id, conversation.customer_id),
         last_message_at: conversation.last_message_at,
         unread_count,
         created_at: conversation.created_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -379,7 +380,7 @@ pub async fn get_conversation_with_details(
     Path(conversation_id): Path<i32>,
 ) -> Result<Json<ConversationWithDetailsResponse>, AppError> {
     // Cek apakah participant memiliki role yang valid
-    if !participant.role.contains("customer") && !participant.role.contains("seller") {
+    if !is_chat_role(&participant.role) {
         return Err(AppError::forbidden("Invalid role for chat access"));
     }
 
@@ -391,38 +392,30 @@ pub async fn get_conversation_with_details(
     // Query dengan join ke customer dan seller untuk details lengkap
     let conversation = sqlx::query!(
         r#"
-        SELECT c.id, c.customer_id, c.seller_id, c.vehicle_id,
-               c.last_message, c.last_message_at, c.created_at, c.updated_at,
+        SELECT c.id, c.customer_id, c.seller_id, c.assigned_to, c.vehicle_id,
+               c.last_message, c.last_message_sender_id, c.last_message_at, c.created_at, c.updated_at,
                cu.name as customer_name,
                su.name as seller_name,
-               v.title as vehicle_title
+               v.title as "vehicle_title?",
+               (CASE WHEN c.customer_id = $2 THEN c.customer_unread_count ELSE c.seller_unread_count END)::BIGINT as "unread_count!"
         FROM conversations c
         JOIN users cu ON c.customer_id = cu.id
         JOIN users su ON c.seller_id = su.id
         LEFT JOIN vehicles v ON c.vehicle_id = v.id
         WHERE c.id = $1
         "#,
-        conversation_id
+        conversation_id, participant.user_id
     )
     .fetch_optional(&state.db)
     .await?
     .ok_or_else(|| AppError::not_found("Conversation tidak ditemukan"))?;
 
-    // Cek apakah user adalah participant
-    if conversation.customer_id != participant.user_id && conversation.seller_id != participant.user_id {
+    // Cek apakah user adalah participant (termasuk staff yang di-assign)
+    if !assignment::is_visible_to(participant.user_id, conversation.customer_id, conversation.seller_id, conversation.assigned_to) {
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    // Hitung unread count untuk user ini
-    let unread_count = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM messages
-         WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-        conversation_id,
-        participant.user_id
-    )
-    .fetch_one(&state.db)
-    .await?
-    .unwrap_or(0);
+    let unread_count = conversation.unread_count;
 
     // Buat Conversation object dari query result
     let conversation_obj = crate::domain::Conversation {
@@ -431,6 +424,7 @@ pub async fn get_conversation_with_details(
         seller_id: conversation.seller_id,
         vehicle_id: conversation.vehicle_id,
         last_message: conversation.last_message,
+        last_message_sender_id: conversation.last_message_sender_id,
         last_message_at: conversation.last_message_at,
         created_at: conversation.created_at.unwrap_or_else(|| chrono::Utc::now()),
         updated_at: conversation.updated_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -441,17 +435,22 @@ pub async fn get_conversation_with_details(
         conversation_obj,
         conversation.customer_name,
         conversation.seller_name,
-        Some(conversation.vehicle_title),
+        conversation.vehicle_title,
         unread_count,
     );
 
     // Convert ke response format
     let response_data = conversation_with_details.to_response_map();
-    let response: ConversationWithDetailsResponse = serde_json::from_value(response_data)
+    let mut response: ConversationWithDetailsResponse = serde_json::from_value(response_data)
         .map_err(|e| {
             tracing::error!("Failed to convert ConversationWithDetails to response: {}", e);
             AppError::internal("Failed to process conversation data")
         })?;
+    response.last_message_is_mine = unread::last_message_is_mine(
+        response.last_message_sender_id,
+        participant.user_id,
+        response.customer_id,
+    );
 
     tracing::info!("User {} accessed detailed conversation {} with {} unread messages",
                   participant.user_id, conversation_id, unread_count);
@@ -483,37 +482,168 @@ pub async fn mark_conversation_read(
 ) -> Result<StatusCode, AppError> {
     // Cek apakah conversation ada dan user adalah participant
     let conversation = sqlx::query!(
-        "SELECT customer_id, seller_id FROM conversations WHERE id = $1",
+        "SELECT customer_id, seller_id, assigned_to FROM conversations WHERE id = $1",
         conversation_id
     )
     .fetch_optional(&state.db)
     .await?
     .ok_or_else(|| AppError::not_found("Conversation tidak ditemukan"))?;
 
-    // Cek apakah user adalah participant
-    if conversation.customer_id != participant.user_id && conversation.seller_id != participant.user_id {
+    // Cek apakah user adalah participant (termasuk staff yang di-assign)
+    if !assignment::is_visible_to(participant.user_id, conversation.customer_id, conversation.seller_id, conversation.assigned_to) {
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    // Update semua messages yang belum dibaca dari user lain
-    let updated_rows = sqlx::query!(
-        r#"
-        UPDATE messages
-        SET is_read = true, read_at = NOW()
-        WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false
-        "#,
-        conversation_id, participant.user_id
-    )
-    .execute(&state.db)
-    .await?
-    .rows_affected();
+    // Update semua messages yang belum dibaca dari user lain + counter unread secara atomic
+    let updated_rows = state.conversation_repo
+        .mark_messages_as_read(conversation_id, participant.user_id)
+        .await?;
+
+    broadcast_conversation_updated(&state, conversation_id).await;
 
-    tracing::info!("User {} marked {} messages as read in conversation {}",
-                  participant.user_id, updated_rows, conversation_id);
+    tracing::info!(
+        event = "messages_read",
+        conversation_id,
+        user_id = participant.user_id,
+        count = updated_rows,
+        "Messages marked as read"
+    );
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+// Response mark read semua conversation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadAllResponse {
+    /// Jumlah message yang ditandai dibaca
+    pub marked_read: u64,
+    /// Jumlah conversation yang terdampak
+    pub conversations: usize,
+    /// true jika masih ada conversation unread di luar batas satu request (panggil lagi)
+    pub has_more: bool,
+}
+
+// Tandai semua message untuk user sebagai dibaca di seluruh inbox
+#[utoipa::path(
+    post,
+    path = "/conversations/read-all",
+    tag = "conversations",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Semua conversation ditandai sebagai sudah dibaca", body = ReadAllResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn mark_all_conversations_read(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+) -> Result<Json<ReadAllResponse>, AppError> {
+    let (reads, has_more) = state.conversation_repo
+        .mark_all_read(participant.user_id, unread::READ_ALL_MAX_CONVERSATIONS)
+        .await?;
+
+    // Broadcast read status per conversation agar participant lain melihat tanda dibaca
+    let read_at = chrono::Utc::now();
+    for (conversation_id, read_count) in &reads {
+        let read_payload = serde_json::json!({
+            "type": "message_read",
+            "conversation_id": conversation_id,
+            "read_by": participant.user_id,
+            "read_count": read_count,
+            "read_at": read_at
+        });
+        realtime::publish_best_effort(
+            state.nats_client.as_ref(),
+            format!("chat.{}", conversation_id),
+            read_payload.to_string(),
+            "read status",
+        ).await;
+        broadcast_conversation_updated(&state, *conversation_id).await;
+    }
+
+    let marked_read: u64 = reads.iter().map(|(_, count)| count).sum();
+
+    tracing::info!(
+        event = "messages_read",
+        user_id = participant.user_id,
+        count = marked_read,
+        conversations = reads.len(),
+        has_more,
+        "Messages marked as read in all conversations"
+    );
+
+    Ok(Json(ReadAllResponse {
+        marked_read,
+        conversations: reads.len(),
+        has_more,
+    }))
+}
+
+// Request ubah retensi message conversation
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRetentionRequest {
+    /// Hari sebelum message dihapus otomatis (1-365), null = simpan selamanya
+    #[schema(example = 30)]
+    pub retention_days: Option<i32>,
+}
+
+// Response retensi message conversation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RetentionResponse {
+    pub conversation_id: i32,
+    pub retention_days: Option<i32>,
+}
+
+// Atur auto-purge message conversation (oleh salah satu participant)
+#[utoipa::path(
+    put,
+    path = "/conversations/{conversation_id}/retention",
+    tag = "conversations",
+    security(("bearer_auth" = [])),
+    params(
+        ("conversation_id" = i32, Path, description = "Conversation ID")
+    ),
+    request_body = UpdateRetentionRequest,
+    responses(
+        (status = 200, description = "Retensi message diupdate", body = RetentionResponse),
+        (status = 400, description = "retention_days di luar batas"),
+        (status = 404, description = "Conversation tidak ditemukan"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_conversation_retention(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(conversation_id): Path<i32>,
+    Json(request): Json<UpdateRetentionRequest>,
+) -> Result<Json<RetentionResponse>, AppError> {
+    let retention_days = retention::validate_retention_days(request.retention_days)
+        .map_err(AppError::validation)?;
+
+    let updated = state.conversation_repo
+        .set_retention_days(conversation_id, participant.user_id, retention_days)
+        .await?;
+
+    if !updated {
+        return Err(AppError::not_found("Conversation tidak ditemukan"));
+    }
+
+    tracing::info!(
+        event = "conversation_retention_updated",
+        conversation_id,
+        user_id = participant.user_id,
+        retention_days = ?retention_days,
+        "Retensi conversation diubah"
+    );
+
+    Ok(Json(RetentionResponse {
+        conversation_id,
+        retention_days,
+    }))
+}
+
 // Ambil jumlah unread messages untuk conversation
 #[utoipa::path(
     get,
@@ -531,17 +661,9 @@ pub async fn get_unread_count(
     participant: ChatParticipant,
 ) -> Result<Json<serde_json::Value>, AppError> {
     // Hitung total unread messages untuk user ini
-    let unread_count = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM messages m
-         JOIN conversations c ON m.conversation_id = c.id
-         WHERE (c.customer_id = $1 OR c.seller_id = $1)
-           AND m.sender_id != $1
-           AND m.is_read = false",
-        participant.user_id
-    )
-    .fetch_one(&state.db)
-    .await?
-    .unwrap_or(0);
+    let unread_count = state.conversation_repo
+        .get_unread_count(participant.user_id)
+        .await?;
 
     tracing::info!("User {} has {} unread messages", participant.user_id, unread_count);
 
@@ -565,4 +687,172 @@ pub async fn health_check(
     State(state): State<AppState>,
 ) -> Json<crate::config::HealthCheckResponse> {
     Json(state.health_check().await)
-}
\ No newline at end of file
+}
+// Readiness check: 503 jika database atau NATS belum terhubung
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Service siap menerima traffic", body = crate::config::ReadinessResponse),
+        (status = 503, description = "Database atau NATS belum terhubung", body = crate::config::ReadinessResponse)
+    )
+)]
+pub async fn readiness_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<crate::config::ReadinessResponse>) {
+    let readiness = state.readiness_check().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(readiness))
+}
+
+// Metrics koneksi NATS (format Prometheus)
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Metrics format Prometheus text", content_type = "text/plain")
+    )
+)]
+pub async fn metrics(
+    State(state): State<AppState>,
+) -> ([(axum::http::HeaderName, &'static str); 1], String) {
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.nats_monitor.render_metrics(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    use axum::{http::{header, StatusCode}, response::IntoResponse};
+    const INBOX_CONVERSATIONS: i32 = 100;
+
+    // Inbox sebelum refactor: list conversation lalu COUNT(*) unread dari messages per baris (N+1)
+    async fn inbox_per_row(pool: &PgPool, user_id: i32) -> Vec<(i32, i64)> {
+        let rows: Vec<(i32, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT c.id, cu.name, su.name, v.title
+             FROM conversations c
+             JOIN users cu ON c.customer_id = cu.id
+             JOIN users su ON c.seller_id = su.id
+             LEFT JOIN vehicles v ON c.vehicle_id = v.id
+             WHERE c.customer_id = $1 OR c.seller_id = $1
+             ORDER BY c.updated_at DESC
+             LIMIT 100"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        let mut inbox = Vec::new();
+        for (id, ..) in rows {
+            let unread: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM messages WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false"
+            )
+            .bind(id)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            inbox.push((id, unread));
+        }
+        inbox
+    }
+
+    async fn inbox_single_query(state: &AppState, participant: &ChatParticipant) -> Vec<(i32, i64)> {
+        let Json(response) = get_user_conversations(
+            State(state.clone()),
+            participant.clone(),
+            Query(ConversationListQuery { role: None, unread_only: None }),
+            Pagination { page: 1, limit: 100, offset: 0 },
+        )
+        .await
+        .unwrap();
+
+        response.conversations.iter().map(|c| (c.id, c.unread_count)).collect()
+    }
+
+    // Inbox customer dengan 100 conversation: satu query grouped harus memberi unread
+    // count yang sama dengan cara lama (COUNT(*) per conversation)
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_inbox_single_query_matches_per_row_counts(pool: PgPool) {
+        sqlx::query("INSERT INTO conversations (customer_id, seller_id) SELECT 1, 2 FROM generate_series(1, $1)")
+            .bind(INBOX_CONVERSATIONS)
+            .execute(&pool)
+            .await
+            .unwrap();
+        // 5 message seller per conversation, 0-2 terakhir belum dibaca; counter denormalized mengikuti
+        sqlx::query(
+            "INSERT INTO messages (conversation_id, sender_id, content, is_read)
+             SELECT c.id, 2, 'Pesan ' || n, n <= 5 - c.id % 3
+             FROM conversations c, generate_series(1, 5) n"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE conversations SET customer_unread_count = id % 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let state = AppState::for_test(pool.clone());
+        let customer = ChatParticipant {
+            user_id: 1,
+            email: "customer@test.local".to_string(),
+            role: "customer".to_string(),
+            is_active: true,
+        };
+
+        let mut before = inbox_per_row(&pool, 1).await;
+        let mut after = inbox_single_query(&state, &customer).await;
+        before.sort_unstable();
+        after.sort_unstable();
+        assert_eq!(before.len(), INBOX_CONVERSATIONS as usize);
+        assert_eq!(before, after);
+        assert!(after.iter().any(|(_, unread)| *unread > 0));
+    }
+
+    // Conversation baru: 201 + Location yang bisa di-GET; dibuat ulang: 200 dengan conversation yang sama
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_create_conversation_status_codes(pool: PgPool) {
+        let state = AppState::for_test(pool);
+        let customer = || AuthUser { user_id: 1, email: "customer@test.local".to_string(), role: "customer".to_string() };
+        let request = || Json(CreateConversationRequest { seller_id: Some(2), customer_id: None, vehicle_id: None });
+
+        let created = create_conversation(State(state.clone()), customer(), request()).await.unwrap().into_response();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let location = created.headers()[header::LOCATION].to_str().unwrap().to_string();
+        let id: i32 = location.strip_prefix("/api/conversations/").unwrap().parse().unwrap();
+
+        let Json(fetched) = get_conversation_by_id(State(state.clone()), customer(), Path(id)).await.unwrap();
+        assert_eq!(fetched.seller_id, 2);
+
+        let existing = create_conversation(State(state), customer(), request()).await.unwrap().into_response();
+        assert_eq!(existing.status(), StatusCode::OK);
+        assert!(existing.headers().get(header::LOCATION).is_none());
+    }
+}
@@ -0,0 +1,331 @@
+// Inbound Email Handlers - balasan email notifikasi chat diposting ke conversation
+use axum::{
+    extract::State,
+    http::HeaderMap,
+};
+use axum_extra::extract::Multipart;
+use serde::Deserialize;
+use shared::utils::creation::Creation;
+use shared::utils::storage::Storage;
+use shared::utils::inbound_email::{
+    reply_token_from_address, verify_basic_auth, verify_reply_token, verify_svix_signature,
+};
+
+use crate::{
+    config::AppState,
+    domain::{CreateMessageRequest, MessageResponse},
+    error::AppError,
+    handlers::messages::{complete_sent_message, created_message, stored_message_response},
+    middleware::ChatParticipant,
+    utils::email_reply::{extract_address, raw_header_value, sender_is_token_user, strip_quoted_reply},
+    utils::message_validation::validate_message_content,
+};
+
+// Toleransi selisih svix-timestamp untuk mencegah replay (detik)
+const SVIX_TOLERANCE_SECS: i64 = 300;
+
+// Payload webhook Resend (event email.received)
+#[derive(Debug, Deserialize)]
+struct ResendInboundEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    data: ResendInboundEmail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResendInboundEmail {
+    from: String,
+    #[serde(default)]
+    to: Vec<String>,
+    text: Option<String>,
+}
+
+// Email masuk yang sudah dinormalisasi dari provider mana pun
+struct InboundEmail {
+    // ID delivery dari provider untuk dedupe retry (svix-id Resend, Message-ID SendGrid)
+    delivery_id: Option<String>,
+    from: String,
+    to: Vec<String>,
+    text: String,
+}
+
+// Webhook inbound Resend (ditandatangani Svix)
+#[utoipa::path(
+    post,
+    path = "/webhooks/email/resend",
+    tag = "inbound-email",
+    security(()),
+    responses(
+        (status = 201, description = "Balasan email diposting ke conversation", body = MessageResponse,
+            headers(("Location" = String, description = "URL message"))),
+        (status = 200, description = "Delivery ulang, message yang sudah diposting dikembalikan", body = MessageResponse),
+        (status = 400, description = "Payload tidak valid atau alamat reply tidak dikenali"),
+        (status = 401, description = "Signature webhook tidak valid"),
+        (status = 403, description = "Pengirim bukan participant conversation"),
+        (status = 409, description = "Delivery yang sama sedang diproses"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn resend_inbound(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Creation<MessageResponse>, AppError> {
+    let secret = state.config.resend_inbound_secret.as_deref()
+        .ok_or_else(|| AppError::forbidden("Inbound email Resend belum dikonfigurasi"))?;
+
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let timestamp = header("svix-timestamp").parse::<i64>()
+        .map_err(|_| AppError::unauthorized("svix-timestamp tidak valid"))?;
+
+    if !verify_svix_signature(
+        secret,
+        header("svix-id"),
+        timestamp,
+        body.as_bytes(),
+        header("svix-signature"),
+        chrono::Utc::now().timestamp(),
+        SVIX_TOLERANCE_SECS,
+    ) {
+        return Err(AppError::unauthorized("Signature webhook tidak valid"));
+    }
+
+    let event: ResendInboundEvent = serde_json::from_str(&body)
+        .map_err(|e| AppError::bad_request(format!("Payload webhook tidak valid: {}", e)))?;
+
+    if event.event_type != "email.received" {
+        return Err(AppError::bad_request(format!("Event {} tidak didukung", event.event_type)));
+    }
+
+    let email = InboundEmail {
+        delivery_id: Some(header("svix-id").to_string()).filter(|id| !id.is_empty()),
+        from: event.data.from,
+        to: event.data.to,
+        text: event.data.text
+            .ok_or_else(|| AppError::bad_request("Email tidak memiliki isi teks"))?,
+    };
+
+    process_delivery(&state, "resend", email).await
+}
+
+// Webhook SendGrid Inbound Parse (multipart, dilindungi Basic auth di URL webhook)
+#[utoipa::path(
+    post,
+    path = "/webhooks/email/sendgrid",
+    tag = "inbound-email",
+    security(()),
+    responses(
+        (status = 201, description = "Balasan email diposting ke conversation", body = MessageResponse,
+            headers(("Location" = String, description = "URL message"))),
+        (status = 200, description = "Delivery ulang, message yang sudah diposting dikembalikan", body = MessageResponse),
+        (status = 400, description = "Payload tidak valid atau alamat reply tidak dikenali"),
+        (status = 401, description = "Kredensial webhook tidak valid"),
+        (status = 403, description = "Pengirim bukan participant conversation"),
+        (status = 409, description = "Delivery yang sama sedang diproses"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn sendgrid_inbound(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Creation<MessageResponse>, AppError> {
+    let credentials = state.config.sendgrid_inbound_basic_auth.as_deref()
+        .ok_or_else(|| AppError::forbidden("Inbound email SendGrid belum dikonfigurasi"))?;
+
+    let authorization = headers.get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if !verify_basic_auth(authorization, credentials) {
+        return Err(AppError::unauthorized("Kredensial webhook tidak valid"));
+    }
+
+    let (mut from, mut to, mut text, mut raw_headers) = (None, None, None, None);
+    while let Some(field) = multipart.next_field().await
+        .map_err(|e| AppError::bad_request(format!("Multipart error: {}", e)))? {
+        let name = field.name().unwrap_or_default().to_string();
+        if !matches!(name.as_str(), "from" | "to" | "text" | "headers") {
+            continue;
+        }
+
+        let value = field.text().await
+            .map_err(|e| AppError::bad_request(format!("Multipart error: {}", e)))?;
+
+        match name.as_str() {
+            "from" => from = Some(value),
+            "to" => to = Some(value),
+            "headers" => raw_headers = Some(value),
+            _ => text = Some(value),
+        }
+    }
+
+    let email = InboundEmail {
+        delivery_id: raw_headers.and_then(|headers| raw_header_value(&headers, "Message-ID")),
+        from: from.ok_or_else(|| AppError::bad_request("Field from wajib ada"))?,
+        to: to.unwrap_or_default().split(',').map(|s| s.trim().to_string()).collect(),
+        text: text.ok_or_else(|| AppError::bad_request("Email tidak memiliki isi teks"))?,
+    };
+
+    process_delivery(&state, "sendgrid", email).await
+}
+
+// Provider me-retry delivery yang timeout/gagal; delivery yang sama hanya diposting sekali.
+// Delivery diklaim dulu, klaim dilepas jika pemrosesan gagal agar retry berikutnya bisa diproses.
+async fn process_delivery(
+    state: &AppState,
+    provider: &str,
+    email: InboundEmail,
+) -> Result<Creation<MessageResponse>, AppError> {
+    let Some(delivery_id) = email.delivery_id.clone() else {
+        return Ok(created_message(process_inbound_email(state, email).await?));
+    };
+
+    let claimed = sqlx::query(
+        "INSERT INTO inbound_email_deliveries (provider, delivery_id) VALUES ($1, $2)
+         ON CONFLICT (provider, delivery_id) DO NOTHING"
+    )
+    .bind(provider)
+    .bind(&delivery_id)
+    .execute(&state.db)
+    .await?
+    .rows_affected() == 1;
+
+    if !claimed {
+        let existing: Option<(Option<i32>, Option<i32>)> = sqlx::query_as(
+            "SELECT d.message_id, c.customer_id
+             FROM inbound_email_deliveries d
+             LEFT JOIN messages m ON m.id = d.message_id
+             LEFT JOIN conversations c ON c.id = m.conversation_id
+             WHERE d.provider = $1 AND d.delivery_id = $2"
+        )
+        .bind(provider)
+        .bind(&delivery_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+        return match existing {
+            Some((Some(message_id), Some(viewer_id))) => {
+                tracing::info!(event = "email_reply_duplicate", provider, message_id, "Delivery inbound email ulang diabaikan");
+                Ok(Creation::existing(stored_message_response(state, message_id, viewer_id).await?))
+            }
+            _ => Err(AppError::conflict("Delivery email yang sama sedang diproses")),
+        };
+    }
+
+    match process_inbound_email(state, email).await {
+        Ok(message) => {
+            sqlx::query(
+                "UPDATE inbound_email_deliveries SET message_id = $3 WHERE provider = $1 AND delivery_id = $2"
+            )
+            .bind(provider)
+            .bind(&delivery_id)
+            .bind(message.id)
+            .execute(&state.db)
+            .await?;
+
+            Ok(created_message(message))
+        }
+        Err(e) => {
+            if let Err(release_err) = sqlx::query(
+                "DELETE FROM inbound_email_deliveries WHERE provider = $1 AND delivery_id = $2 AND message_id IS NULL"
+            )
+            .bind(provider)
+            .bind(&delivery_id)
+            .execute(&state.db)
+            .await
+            {
+                tracing::error!("Gagal melepas klaim delivery {} {}: {}", provider, delivery_id, release_err);
+            }
+
+            Err(e)
+        }
+    }
+}
+
+// Cocokkan token reply ke conversation + user, validasi pengirim, lalu posting sebagai message
+async fn process_inbound_email(
+    state: &AppState,
+    email: InboundEmail,
+) -> Result<MessageResponse, AppError> {
+    let secret = state.config.reply_token_secret.as_deref()
+        .ok_or_else(|| AppError::forbidden("Reply-by-email belum dikonfigurasi"))?;
+
+    // Hanya alamat reply+ di domain reply kita (jika diset) yang diproses
+    let (conversation_id, user_id) = email.to.iter()
+        .filter(|address| match state.config.reply_domain.as_deref() {
+            Some(domain) => extract_address(address)
+                .is_some_and(|a| a.ends_with(&format!("@{}", domain.to_lowercase()))),
+            None => true,
+        })
+        .filter_map(|address| reply_token_from_address(address))
+        .find_map(|token| verify_reply_token(secret.as_bytes(), token, chrono::Utc::now().timestamp()))
+        .ok_or_else(|| AppError::bad_request("Alamat reply tidak valid atau sudah kedaluwarsa"))?;
+
+    let user = sqlx::query!(
+        "SELECT email, is_active FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::forbidden("User tidak ditemukan"))?;
+
+    // Token reply terikat ke user; email pengirim harus milik user tersebut
+    if !sender_is_token_user(&email.from, &user.email) {
+        tracing::warn!("Inbound email conversation {} ditolak: pengirim {} bukan pemilik token user {}",
+                       conversation_id, email.from, user_id);
+        return Err(AppError::forbidden("Pengirim email tidak sesuai dengan participant"));
+    }
+
+    if !user.is_active.unwrap_or(false) {
+        return Err(AppError::forbidden("User tidak aktif atau dibanned"));
+    }
+
+    let is_participant = state.conversation_repo
+        .is_participant(conversation_id, user_id)
+        .await?;
+
+    if !is_participant {
+        return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
+    }
+
+    // Role mengikuti posisi user di conversation ini (user bisa customer sekaligus seller)
+    let seller_id = sqlx::query_scalar!(
+        "SELECT seller_id FROM conversations WHERE id = $1",
+        conversation_id
+    )
+    .fetch_one(&state.db)
+    .await?;
+
+    let participant = ChatParticipant {
+        user_id,
+        email: user.email,
+        role: if seller_id == user_id { "seller" } else { "customer" }.to_string(),
+        is_active: true,
+    };
+
+    let content = strip_quoted_reply(&email.text);
+    validate_message_content(&content, false, state.config.max_message_length, state.config.strict_validation)?;
+
+    let message = state.message_repo
+        .create_message(conversation_id, participant.user_id, &participant.email, CreateMessageRequest {
+            conversation_id,
+            content,
+            message_type: Some("text".to_string()),
+            media_url: None,
+            thumbnail_url: None,
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+        }, None, |url| state.storage.owns_url(url))
+        .await?;
+
+    tracing::info!(
+        event = "email_reply_posted",
+        conversation_id,
+        user_id = participant.user_id,
+        "Balasan email diposting ke conversation"
+    );
+
+    complete_sent_message(state, &participant, message).await
+}
@@ -1,26 +1,37 @@
 // Message Handlers untuk Chat Service
+use std::net::SocketAddr;
+
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use shared::utils::creation::Creation;
+use shared::utils::pagination::{Pagination, PaginationParams};
+use shared::utils::rate_limit::{retry_after_secs, unix_now};
+use shared::utils::storage::{Storage, StorageError};
 
 use crate::{
     config::AppState,
-    domain::{Message, MessageType, CreateMessageRequest, MessageResponse},
-    middleware::ChatParticipant,
+    domain::{DeletedMessageOriginal, Message, MessageType, CreateMessageRequest, MessageResponse, ThreadSummary, resolve_thread_root, validate_reply_target},
+    middleware::{AuthAdmin, ChatParticipant},
     error::AppError,
-    handlers::upload::{validate_chat_files, generate_preview_text, FileCategory, UploadResponse, UploadedFile, extract_file_info_for_message},
+    utils::media_proxy::{authorize_media, content_type_for, original_media_path, MediaVariant},
+    utils::message_validation::validate_message_content,
+    utils::auto_reply::should_auto_reply,
+    utils::message_email::{build_message_email, send_via_resend, should_email},
+    utils::unread::Participant,
+    utils::realtime,
+    handlers::websocket::broadcast_conversation_updated,
+    handlers::upload::{validate_chat_files, scan_chat_files, generate_preview_text, category_from_url, category_delivery_url, upload_policy, FileCategory, UploadResponse, UploadedFile, extract_file_info_for_message},
 };
 
-// Query parameters untuk pagination dan search
+// Query parameters untuk search (pagination via PaginationParams)
 #[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
 pub struct MessageQuery {
     pub conversation_id: Option<i32>,
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
     pub search: Option<String>,
 }
 
@@ -31,64 +42,64 @@ pub struct MessageListResponse {
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    // Masih ada message setelah halaman ini (untuk infinite scroll)
+    pub has_more: bool,
     pub conversation_id: i32,
 }
 
-// Response untuk message count
+// Satu hasil search: message + snippet ber-highlight di sekitar kata yang cocok
 #[derive(Debug, Serialize, ToSchema)]
-pub struct MessageCountResponse {
-    pub total_messages: i64,
-    pub unread_messages: i64,
+pub struct MessageSearchHit {
+    #[serde(flatten)]
+    pub message: Message,
+    // Teks sudah di-escape HTML, hanya delimiter highlight yang berupa markup
+    #[schema(example = "…apakah <mark>Avanza</mark> ini masih tersedia…")]
+    pub snippet: String,
+}
+
+// Response search messages
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageSearchResponse {
+    pub messages: Vec<MessageSearchHit>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
     pub conversation_id: i32,
 }
 
-// Broadcast message ke NATS untuk real-time delivery
-async fn broadcast_message_via_nats(
-    nats_client: &async_nats::Client,
-    conversation_id: i32,
-    message: &Message,
-    participant_email: &str,
-) -> Result<(), AppError> {
-    // Buat payload untuk WebSocket broadcast
-    let broadcast_payload = serde_json::json!({
-        "type": "new_message",
-        "conversation_id": conversation_id,
-        "message": {
-            "id": message.id,
-            "sender_id": message.sender_id,
-            "content": message.content,
-            "message_type": message.message_type,
-            "media_url": message.media_url,
-            "thumbnail_url": message.thumbnail_url,
-            "created_at": message.created_at,
-            "sender_email": participant_email
-        }
-    });
+// Repository diminta limit + 1 baris; baris ekstra hanya penanda ada halaman berikutnya
+fn into_page<T>(mut items: Vec<T>, limit: i64) -> (Vec<T>, bool) {
+    let limit = limit.max(0) as usize;
+    let has_more = items.len() > limit;
+    items.truncate(limit);
+    (items, has_more)
+}
 
-    // Publish ke NATS subject untuk conversation spesifik
-    let subject = format!("chat.{}", conversation_id);
-    let payload_str = broadcast_payload.to_string();
+// Response daftar thread dalam conversation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadListResponse {
+    pub threads: Vec<ThreadSummary>,
+    pub limit: i64,
+    pub offset: i64,
+    pub conversation_id: i32,
+}
 
-    nats_client
-        .publish(subject, payload_str.clone().into())
-        .await
-        .map_err(|e| {
-            tracing::error!("Gagal broadcast message ke NATS: {}", e);
-            AppError::nats("Gagal mengirim pesan real-time")
-        })?;
-
-    // Juga publish ke user-specific subjects untuk targeted delivery
-    let user_subject = format!("chat.user.{}", message.sender_id);
-    nats_client
-        .publish(user_subject, payload_str.into())
-        .await
-        .map_err(|e| {
-            tracing::error!("Gagal broadcast message ke user subject: {}", e);
-            AppError::nats("Gagal mengirim pesan real-time")
-        })?;
+// Response isi satu thread: root dan balasannya
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThreadResponse {
+    pub root: Message,
+    pub replies: Vec<Message>,
+    pub limit: i64,
+    pub offset: i64,
+}
 
-    tracing::info!("Message {} di broadcast ke conversation {} via NATS", message.id, conversation_id);
-    Ok(())
+// Response untuk message count
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageCountResponse {
+    pub total_messages: i64,
+    pub unread_messages: i64,
+    pub conversation_id: i32,
 }
 
 // Kirim message baru ke conversation
@@ -99,20 +110,23 @@ async fn broadcast_message_via_nats(
     security(("bearer_auth" = [])),
     request_body = CreateMessageRequest,
     responses(
-        (status = 201, description = "Message berhasil dikirim", body = Message),
+        (status = 201, description = "Message berhasil dikirim", body = MessageResponse,
+            headers(("Location" = String, description = "URL message"))),
         (status = 400, description = "Request tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Tidak memiliki akses"),
         (status = 404, description = "Conversation tidak ditemukan"),
+        (status = 429, description = "Terlalu banyak message dikirim"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn send_message(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     participant: ChatParticipant,
     Path(conversation_id): Path<i32>,
-    Json(request): Json<CreateMessageRequest>,
-) -> Result<(StatusCode, Json<MessageResponse>), AppError> {
+    Json(mut request): Json<CreateMessageRequest>,
+) -> Result<Creation<MessageResponse>, AppError> {
     // Validasi role participant - customer dan seller bisa kirim message
     if !participant.is_customer() && !participant.is_seller() {
         return Err(AppError::forbidden("Role tidak valid untuk mengirim pesan"));
@@ -127,53 +141,301 @@ pub async fn send_message(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
+    enforce_send_limit(&state, participant.user_id).await?;
+
+    // Content boleh kosong jika ada media (gambar tanpa caption)
+    validate_message_content(&request.content, request.media_url.is_some(), state.config.max_message_length, state.config.strict_validation)?;
+
+    // Scan media attachment sebelum message disimpan
+    if let Some(ref media_url) = request.media_url {
+        let files = vec![media_url.clone()];
+        validate_chat_files(&state.storage, &files, None, state.config.max_attachments_per_message)?;
+        scan_chat_files(&state, participant.user_id, peer.ip(), "/messages", &files).await?;
+    }
+
+    // Message yang dibalas harus berada di conversation yang sama
+    let reply_to = match request.reply_to_message_id {
+        Some(message_id) => {
+            let target = state.message_repo
+                .get_message_by_id(message_id, participant.user_id)
+                .await?;
+            Some(validate_reply_target(conversation_id, target.as_ref()).map_err(AppError::validation)?)
+        }
+        None => None,
+    };
+
+    // Balasan thread selalu disimpan dengan ID root, meski yang dibalas message di dalam thread
+    if let Some(message_id) = request.thread_root_id {
+        let target = state.message_repo
+            .get_message_by_id(message_id, participant.user_id)
+            .await?;
+        request.thread_root_id = Some(resolve_thread_root(conversation_id, target.as_ref()).map_err(AppError::validation)?);
+    }
+
     // Buat message baru
     let message = state.message_repo
-        .create_message(conversation_id, participant.user_id, request)
+        .create_message(conversation_id, participant.user_id, &participant.email, request, reply_to, |url| state.storage.owns_url(url))
         .await?;
 
-    // Get sender name for MessageResponse
+    let message_response = complete_sent_message(&state, &participant, message).await?;
+
+    Ok(created_message(message_response))
+}
+
+// Response message yang sudah tersimpan (delivery ulang webhook inbound email)
+pub(crate) async fn stored_message_response(
+    state: &AppState,
+    message_id: i32,
+    viewer_id: i32,
+) -> Result<MessageResponse, AppError> {
+    let message = state.message_repo
+        .get_message_by_id(message_id, viewer_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Message tidak ditemukan"))?;
+
+    // Sender yang sudah dihapus tampil sebagai "Unknown", error database tetap dikembalikan
+    let sender_name = sqlx::query_scalar!(
+        "SELECT name FROM users WHERE id = $1",
+        message.sender_id
+    )
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(build_message_response(&proxy_media(state, message), sender_name))
+}
+
+// Message baru: 201 + Location ke GET /api/messages/{id}
+pub(crate) fn created_message(response: MessageResponse) -> Creation<MessageResponse> {
+    Creation::created(format!("/api/messages/{}", response.id), response)
+}
+
+// Langkah bersama setelah message tersimpan (dengan atau tanpa files):
+// ambil nama sender, update last message, bangunkan outbox relay untuk broadcast
+pub(crate) async fn complete_sent_message(
+    state: &AppState,
+    participant: &ChatParticipant,
+    message: Message,
+) -> Result<MessageResponse, AppError> {
+    // Message sudah tersimpan, tapi error database tidak boleh disamarkan jadi sender "Unknown"
     let sender_name = sqlx::query_scalar!(
         "SELECT name FROM users WHERE id = $1",
         participant.user_id
     )
-    .fetch_one(&state.db)
-    .await
-    .unwrap_or_else(|_| "Unknown".to_string());
+    .fetch_optional(&state.db)
+    .await?;
 
     // Update last message info di conversation
-    let content_preview = if message.content.len() > 50 {
-        format!("{}...", &message.content[..50])
-    } else {
-        message.content.clone()
-    };
+    let content_preview = message.preview_text(state.config.last_message_preview_len);
 
     state.conversation_repo
-        .update_last_message(conversation_id, &content_preview)
+        .update_last_message(message.conversation_id, &content_preview, message.sender_id)
         .await?;
 
-    // Broadcast message via NATS ke WebSocket connections
-    if let Some(nats_client) = &state.nats_client {
-        if let Err(e) = broadcast_message_via_nats(
-            nats_client,
-            conversation_id,
-            &message,
-            &participant.email,
-        ).await {
-            // Log error tapi tidak gagalkan request, karena message sudah tersimpan di database
-            tracing::warn!("Broadcast message gagal: {}, tapi message tersimpan di database", e);
+    // Auto-reply seller gagal tidak boleh menggagalkan message customer
+    if let Err(e) = send_auto_reply(state, &message).await {
+        tracing::warn!("Auto-reply untuk message {} gagal: {}", message.id, e);
+    }
+
+    // Email notifikasi gagal tidak boleh menggagalkan pengiriman message
+    if let Err(e) = send_message_email(state, &message, sender_name.as_deref()).await {
+        tracing::warn!("Email notifikasi message {} gagal: {}", message.id, e);
+    }
+
+    // Bangunkan outbox relay agar event real-time langsung dipublish ke NATS
+    state.outbox_notify.notify_one();
+
+    // Inbox kedua participant ikut terupdate (last message + unread)
+    broadcast_conversation_updated(state, message.conversation_id).await;
+
+    tracing::info!(
+        event = "message_sent",
+        conversation_id = message.conversation_id,
+        message_id = message.id,
+        message_type = message.message_type.as_str(),
+        user_id = participant.user_id,
+        "Message dikirim"
+    );
+
+    Ok(build_message_response(&proxy_media(state, message), sender_name))
+}
+
+// Email message baru ke penerima yang belum membaca conversation, Reply-To alamat reply+ miliknya
+async fn send_message_email(state: &AppState, message: &Message, sender_name: Option<&str>) -> Result<(), AppError> {
+    let config = &state.config;
+    if message.is_auto_reply || !config.message_email_enabled() {
+        return Ok(());
+    }
+
+    let conversation = sqlx::query!(
+        "SELECT customer_id, seller_id, assigned_to, customer_unread_count, seller_unread_count
+         FROM conversations WHERE id = $1",
+        message.conversation_id
+    )
+    .fetch_one(&state.db)
+    .await?;
+
+    let Some(sender) = Participant::of(message.sender_id, conversation.customer_id, conversation.seller_id, conversation.assigned_to) else {
+        return Ok(());
+    };
+
+    // Message dari staff/seller masuk ke customer; message customer ke staff yang di-assign atau seller
+    let (recipient_id, recipient_unread) = match sender.other() {
+        Participant::Customer => (conversation.customer_id, conversation.customer_unread_count),
+        Participant::Seller => (conversation.assigned_to.unwrap_or(conversation.seller_id), conversation.seller_unread_count),
+    };
+
+    if !should_email(recipient_unread) {
+        return Ok(());
+    }
+
+    let recipient_email = sqlx::query_scalar!(
+        "SELECT email FROM users WHERE id = $1 AND is_active = true",
+        recipient_id
+    )
+    .fetch_optional(&state.db)
+    .await?;
+
+    let Some(recipient_email) = recipient_email else {
+        return Ok(());
+    };
+
+    let (Some(secret), Some(domain), Some(api_key), Some(from)) = (
+        config.reply_token_secret.clone(),
+        config.reply_domain.clone(),
+        config.resend_api_key.clone(),
+        config.resend_from_email.clone(),
+    ) else {
+        return Ok(());
+    };
+
+    let expires_at = chrono::Utc::now().timestamp() + config.reply_token_ttl_days * 86_400;
+    let email = build_message_email(
+        secret.as_bytes(),
+        &domain,
+        expires_at,
+        message.conversation_id,
+        recipient_id,
+        &recipient_email,
+        sender_name.unwrap_or("Pengguna Big Auto"),
+        // Preview dipotong di build_message_email, message file-only memakai label lampiran
+        &message.preview_text(usize::MAX),
+    );
+
+    // Kirim di background agar latency Resend tidak menahan response
+    let client = state.http_client.clone();
+    let (conversation_id, message_id) = (message.conversation_id, message.id);
+    tokio::spawn(async move {
+        match send_via_resend(&client, &api_key, &from, &email).await {
+            Ok(()) => tracing::info!(
+                event = "message_email_sent",
+                conversation_id,
+                message_id,
+                recipient_id,
+                "Email notifikasi message dikirim"
+            ),
+            Err(e) => tracing::warn!("Email notifikasi message {} ke user {} gagal: {}", message_id, recipient_id, e),
+        }
+    });
+
+    Ok(())
+}
+
+// Limit kirim message per user; Redis error tidak memblokir pengiriman (sama dengan middleware)
+async fn enforce_send_limit(state: &AppState, user_id: i32) -> Result<(), AppError> {
+    match state.rate_limiter.check_message_send(user_id).await {
+        Ok(result) if result.allowed => Ok(()),
+        Ok(result) => {
+            tracing::warn!("User {} melewati limit kirim message", user_id);
+            Err(AppError::rate_limit(
+                "Terlalu banyak pesan dikirim. Silakan tunggu sebentar.",
+                retry_after_secs(result.reset_time, unix_now()),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Rate limit kirim message gagal dicek: {}. Message tetap dikirim.", e);
+            Ok(())
         }
-    } else {
-        tracing::warn!("NATS client tidak tersedia, message tidak di-broadcast secara real-time");
     }
+}
+
+// Balas otomatis atas nama seller jika customer mengirim message pertama atau di luar jam aktif seller
+async fn send_auto_reply(state: &AppState, message: &Message) -> Result<(), AppError> {
+    let conversation = state.conversation_repo
+        .get_conversation_by_id(message.conversation_id, message.sender_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Conversation tidak ditemukan"))?;
+
+    // Hanya message dari customer yang memicu auto-reply
+    if message.sender_id != conversation.customer_id || message.is_auto_reply {
+        return Ok(());
+    }
+
+    let Some(settings) = state.auto_reply_repo.get_settings(conversation.seller_id).await? else {
+        return Ok(());
+    };
+
+    let is_first_message = state.message_repo
+        .get_conversation_message_count(conversation.id)
+        .await? == 1;
+    let last_auto_reply_at = state.auto_reply_repo.last_auto_reply_at(conversation.id).await?;
+    let cooldown = chrono::Duration::minutes(state.config.auto_reply_cooldown_minutes);
+
+    if !should_auto_reply(&settings, is_first_message, last_auto_reply_at, chrono::Utc::now(), cooldown) {
+        return Ok(());
+    }
+
+    let seller_email = sqlx::query_scalar!(
+        "SELECT email FROM users WHERE id = $1",
+        conversation.seller_id
+    )
+    .fetch_one(&state.db)
+    .await?;
+
+    let request = CreateMessageRequest {
+        conversation_id: conversation.id,
+        content: settings.message,
+        message_type: None,
+        media_url: None,
+        thumbnail_url: None,
+        reply_to_message_id: None,
+        thread_root_id: None,
+        is_auto_reply: true,
+    };
+
+    // Request bersamaan: hanya satu yang berhasil klaim slot auto-reply
+    let Some(reply) = state.message_repo
+        .create_auto_reply(conversation.id, conversation.seller_id, &seller_email, request, last_auto_reply_at, |url| state.storage.owns_url(url))
+        .await?
+    else {
+        return Ok(());
+    };
 
-    tracing::info!("User {} mengirim message {} ke conversation {}",
-                   participant.user_id, message.id, conversation_id);
+    state.conversation_repo
+        .update_last_message(conversation.id, &reply.preview_text(state.config.last_message_preview_len), conversation.seller_id)
+        .await?;
 
-    
-    let message_response = message.to_response(sender_name);
+    tracing::info!(
+        event = "auto_reply_sent",
+        conversation_id = conversation.id,
+        seller_id = conversation.seller_id,
+        "Auto-reply seller dikirim"
+    );
+
+    Ok(())
+}
+
+// Bentuk response yang sama untuk semua endpoint kirim message
+fn build_message_response(message: &Message, sender_name: Option<String>) -> MessageResponse {
+    message.to_response(sender_name.unwrap_or_else(|| "Unknown".to_string()))
+}
 
-    Ok((StatusCode::CREATED, Json(message_response)))
+// URL storage attachment diganti path proxy sebelum message keluar di response
+fn proxy_media(state: &AppState, message: Message) -> Message {
+    message.with_media_proxy(|url| state.storage.owns_url(url))
+}
+
+pub(crate) fn proxy_media_list(state: &AppState, messages: Vec<Message>) -> Vec<Message> {
+    messages.into_iter().map(|message| proxy_media(state, message)).collect()
 }
 
 // Ambil messages dalam conversation dengan pagination
@@ -184,10 +446,11 @@ pub async fn send_message(
     security(("bearer_auth" = [])),
     params(
         ("conversation_id" = i32, Path, description = "Conversation ID"),
-        MessageQuery
+        PaginationParams
     ),
     responses(
         (status = 200, description = "Messages berhasil diambil", body = MessageListResponse),
+        (status = 400, description = "Parameter paginasi tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Tidak memiliki akses ke conversation"),
         (status = 404, description = "Conversation tidak ditemukan"),
@@ -198,7 +461,7 @@ pub async fn get_conversation_messages(
     State(state): State<AppState>,
     participant: ChatParticipant,
     Path(conversation_id): Path<i32>,
-    Query(query): Query<MessageQuery>,
+    Pagination { limit, offset, .. }: Pagination<50, 100>,
 ) -> Result<Json<MessageListResponse>, AppError> {
     // Cek apakah user adalah participant
     let is_participant = state.conversation_repo
@@ -209,12 +472,10 @@ pub async fn get_conversation_messages(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    let limit = query.limit.unwrap_or(50).min(100);
-    let offset = query.offset.unwrap_or(0);
-
     let messages = state.message_repo
-        .get_conversation_messages(conversation_id, participant.user_id, limit, offset)
+        .get_conversation_messages(conversation_id, participant.user_id, limit + 1, offset)
         .await?;
+    let (messages, has_more) = into_page(messages, limit);
 
     // Ambil total message count
     let total = state.message_repo
@@ -225,10 +486,11 @@ pub async fn get_conversation_messages(
                    participant.user_id, messages.len(), conversation_id);
 
     Ok(Json(MessageListResponse {
-        messages,
+        messages: proxy_media_list(&state, messages),
         total,
         limit,
         offset,
+        has_more,
         conversation_id,
     }))
 }
@@ -262,12 +524,176 @@ pub async fn get_message_by_id(
     match message {
         Some(msg) => {
             tracing::info!("User {} mengakses message {}", participant.user_id, message_id);
-            Ok(Json(msg))
+            Ok(Json(proxy_media(&state, msg)))
         }
         None => Err(AppError::not_found("Message tidak ditemukan")),
     }
 }
 
+// Ambil daftar thread dalam conversation beserta jumlah balasan
+#[utoipa::path(
+    get,
+    path = "/conversations/{conversation_id}/threads",
+    tag = "messages",
+    security(("bearer_auth" = [])),
+    params(
+        ("conversation_id" = i32, Path, description = "Conversation ID"),
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Thread berhasil diambil", body = ThreadListResponse),
+        (status = 400, description = "Parameter paginasi tidak valid"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Tidak memiliki akses ke conversation"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_conversation_threads(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(conversation_id): Path<i32>,
+    Pagination { limit, offset, .. }: Pagination<20, 50>,
+) -> Result<Json<ThreadListResponse>, AppError> {
+    let is_participant = state.conversation_repo
+        .is_participant(conversation_id, participant.user_id)
+        .await?;
+
+    if !is_participant {
+        return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
+    }
+
+    let threads = state.message_repo
+        .get_thread_roots(conversation_id, limit, offset)
+        .await?
+        .into_iter()
+        .map(|thread| ThreadSummary {
+            root: proxy_media(&state, thread.root),
+            ..thread
+        })
+        .collect();
+
+    Ok(Json(ThreadListResponse {
+        threads,
+        limit,
+        offset,
+        conversation_id,
+    }))
+}
+
+// Ambil isi thread dari message mana pun di dalamnya (root atau balasan)
+#[utoipa::path(
+    get,
+    path = "/messages/{message_id}/thread",
+    tag = "messages",
+    security(("bearer_auth" = [])),
+    params(
+        ("message_id" = i32, Path, description = "Message ID (root atau balasan thread)"),
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Thread berhasil diambil", body = ThreadResponse),
+        (status = 400, description = "Parameter paginasi tidak valid"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Message tidak ditemukan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_message_thread(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(message_id): Path<i32>,
+    Pagination { limit, offset, .. }: Pagination<50, 100>,
+) -> Result<Json<ThreadResponse>, AppError> {
+    let message = state.message_repo
+        .get_message_by_id(message_id, participant.user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Message tidak ditemukan"))?;
+
+    let root = match message.thread_root_id {
+        Some(root_id) => state.message_repo
+            .get_message_by_id(root_id, participant.user_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Root thread tidak ditemukan"))?,
+        None => message,
+    };
+
+    let replies = state.message_repo
+        .get_thread_replies(root.id, limit, offset)
+        .await?;
+
+    Ok(Json(ThreadResponse {
+        root: proxy_media(&state, root),
+        replies: proxy_media_list(&state, replies),
+        limit,
+        offset,
+    }))
+}
+
+// Query parameter untuk media proxy
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct MediaQuery {
+    // "original" (default) atau "thumbnail"
+    #[serde(default)]
+    pub variant: MediaVariant,
+}
+
+// Stream attachment message setelah memastikan requester participant conversation
+#[utoipa::path(
+    get,
+    path = "/messages/{message_id}/media",
+    tag = "messages",
+    security(("bearer_auth" = [])),
+    params(
+        ("message_id" = i32, Path, description = "Message ID"),
+        MediaQuery
+    ),
+    responses(
+        (status = 200, description = "Isi file attachment"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Bukan participant conversation"),
+        (status = 404, description = "Message atau media tidak ditemukan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_message_media(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(message_id): Path<i32>,
+    Query(query): Query<MediaQuery>,
+) -> Result<Response, AppError> {
+    let media = state.message_repo
+        .find_message_media(message_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Message tidak ditemukan"))?;
+
+    let url = authorize_media(&media, participant.user_id, query.variant)?;
+
+    // File asli dikirim lewat URL delivery kategori (transformasi CDN) jika dikonfigurasi
+    let source = match query.variant {
+        MediaVariant::Original => category_from_url(url)
+            .and_then(|category| category_delivery_url(&state.storage, upload_policy(&state, category), url)),
+        MediaVariant::Thumbnail => None,
+    };
+
+    // URL publik di luar storage tidak pernah diganti path proxy, jadi tidak dilayani di sini
+    let bytes = state.storage.get(source.as_deref().unwrap_or(url)).await.map_err(|e| match e {
+        StorageError::NotFound(_) | StorageError::ForeignUrl(_) => AppError::not_found("Media tidak ditemukan"),
+        other => {
+            tracing::error!("Gagal ambil media message {}: {}", message_id, other);
+            AppError::internal("Gagal mengambil media")
+        }
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type_for(url)),
+            (header::CONTENT_DISPOSITION, "inline"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        bytes,
+    ).into_response())
+}
+
 // Tandai message sebagai sudah dibaca dengan broadcast update
 #[utoipa::path(
     post,
@@ -306,31 +732,35 @@ pub async fn mark_message_read(
         .mark_message_as_read(message_id, participant.user_id)
         .await?;
 
-    // Broadcast read status update via NATS
-    if let Some(nats_client) = &state.nats_client {
-        let read_payload = serde_json::json!({
-            "type": "message_read",
-            "conversation_id": message.conversation_id,
-            "message_id": message_id,
-            "read_by": participant.user_id,
-            "read_at": chrono::Utc::now()
-        });
-
-        let subject = format!("chat.{}", message.conversation_id);
-        if let Err(e) = nats_client
-            .publish(subject, read_payload.to_string().into())
-            .await
-        {
-            tracing::warn!("Gagal broadcast read status: {}", e);
-        }
-    }
-
-    tracing::info!("User {} menandai message {} sebagai sudah dibaca", participant.user_id, message_id);
+    // Broadcast read status update via NATS (dilewati di degraded mode)
+    let read_payload = serde_json::json!({
+        "type": "message_read",
+        "conversation_id": message.conversation_id,
+        "message_id": message_id,
+        "read_by": participant.user_id,
+        "read_at": chrono::Utc::now()
+    });
+    realtime::publish_best_effort(
+        state.nats_client.as_ref(),
+        format!("chat.{}", message.conversation_id),
+        read_payload.to_string(),
+        "read status",
+    ).await;
+    broadcast_conversation_updated(&state, message.conversation_id).await;
+
+    tracing::info!(
+        event = "messages_read",
+        conversation_id = message.conversation_id,
+        message_id,
+        user_id = participant.user_id,
+        count = 1,
+        "Message ditandai sudah dibaca"
+    );
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-// Hapus message (hanya oleh sender) dengan broadcast notification
+// Hapus message (soft delete, hanya oleh sender) dengan broadcast notification
 #[utoipa::path(
     delete,
     path = "/messages/{message_id}",
@@ -367,40 +797,150 @@ pub async fn delete_message(
         return Err(AppError::forbidden("Hanya sender yang bisa menghapus message"));
     }
 
+    // Sudah jadi tombstone, delete ulang tidak mengubah apa pun
+    if message.is_deleted {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
     let conversation_id = message.conversation_id;
+    let retain_original = state.config.retain_deleted_content;
 
-    // Hapus message
+    // Soft delete: row tetap ada sebagai tombstone "Pesan ini telah dihapus"
     let deleted = state.message_repo
-        .delete_message(message_id, participant.user_id)
+        .delete_message(message_id, participant.user_id, retain_original)
         .await?;
 
     if deleted {
-        // Broadcast message deletion via NATS
-        if let Some(nats_client) = &state.nats_client {
-            let delete_payload = serde_json::json!({
-                "type": "message_deleted",
-                "conversation_id": conversation_id,
-                "message_id": message_id,
-                "deleted_by": participant.user_id,
-                "deleted_at": chrono::Utc::now()
-            });
-
-            let subject = format!("chat.{}", conversation_id);
-            if let Err(e) = nats_client
-                .publish(subject, delete_payload.to_string().into())
-                .await
-            {
-                tracing::warn!("Gagal broadcast message deletion: {}", e);
+        // Media hanya dihapus dari storage jika isi asli tidak disimpan untuk moderasi
+        if !retain_original {
+            if let Some(url) = message.media_url.as_deref().filter(|url| state.storage.owns_url(url)) {
+                if let Err(e) = state.storage.delete(url).await {
+                    tracing::warn!("Gagal menghapus media message {}: {}", message_id, e);
+                }
             }
         }
 
-        tracing::info!("User {} menghapus message {}", participant.user_id, message_id);
+        // Broadcast message deletion via NATS (dilewati di degraded mode)
+        let delete_payload = serde_json::json!({
+            "type": "message_deleted",
+            "conversation_id": conversation_id,
+            "message_id": message_id,
+            "deleted_by": participant.user_id,
+            "deleted_at": chrono::Utc::now()
+        });
+        realtime::publish_best_effort(
+            state.nats_client.as_ref(),
+            format!("chat.{}", conversation_id),
+            delete_payload.to_string(),
+            "message deletion",
+        ).await;
+
+        tracing::info!(
+            event = "message_deleted",
+            message_id,
+            user_id = participant.user_id,
+            "Message dihapus"
+        );
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err(AppError::internal("Gagal menghapus message"))
     }
 }
 
+// Isi asli message yang sudah dihapus untuk moderasi admin
+#[utoipa::path(
+    get,
+    path = "/admin/messages/{message_id}/original",
+    tag = "messages",
+    security(("bearer_auth" = [])),
+    params(
+        ("message_id" = i32, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Isi asli message yang dihapus", body = DeletedMessageOriginal),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Message belum dihapus atau isi asli tidak disimpan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_deleted_message_original(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(message_id): Path<i32>,
+) -> Result<Json<DeletedMessageOriginal>, AppError> {
+    let mut original = state.message_repo
+        .find_deleted_original(message_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Isi asli message tidak tersedia"))?;
+
+    // URL storage mentah tidak keluar di response, sama seperti media message biasa
+    original.original_media_url = original.original_media_url
+        .map(|url| if state.storage.owns_url(&url) { original_media_path(message_id) } else { url });
+
+    tracing::info!(
+        event = "deleted_message_viewed",
+        message_id,
+        admin_id = admin.user_id,
+        "Admin membuka isi asli message yang dihapus"
+    );
+
+    Ok(Json(original))
+}
+
+// Stream attachment asli message yang sudah dihapus untuk moderasi admin
+#[utoipa::path(
+    get,
+    path = "/admin/messages/{message_id}/original/media",
+    tag = "messages",
+    security(("bearer_auth" = [])),
+    params(
+        ("message_id" = i32, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Isi file attachment asli"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Attachment asli tidak tersedia"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_deleted_message_original_media(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(message_id): Path<i32>,
+) -> Result<Response, AppError> {
+    let url = state.message_repo
+        .find_deleted_original(message_id)
+        .await?
+        .and_then(|original| original.original_media_url)
+        .ok_or_else(|| AppError::not_found("Attachment asli tidak tersedia"))?;
+
+    let bytes = state.storage.get(&url).await.map_err(|e| match e {
+        StorageError::NotFound(_) | StorageError::ForeignUrl(_) => AppError::not_found("Attachment asli tidak tersedia"),
+        other => {
+            tracing::error!("Gagal ambil attachment asli message {}: {}", message_id, other);
+            AppError::internal("Gagal mengambil media")
+        }
+    })?;
+
+    tracing::info!(
+        event = "deleted_message_media_viewed",
+        message_id,
+        admin_id = admin.user_id,
+        "Admin membuka attachment asli message yang dihapus"
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type_for(&url)),
+            (header::CONTENT_DISPOSITION, "inline"),
+            (header::CACHE_CONTROL, "private, no-store"),
+        ],
+        bytes,
+    ).into_response())
+}
+
 // Ambil latest message dalam conversation
 #[utoipa::path(
     get,
@@ -439,7 +979,7 @@ pub async fn get_latest_message(
     tracing::info!("User {} mengambil latest message dari conversation {}",
                    participant.user_id, conversation_id);
 
-    Ok(Json(message))
+    Ok(Json(message.map(|message| proxy_media(&state, message))))
 }
 
 // Ambil jumlah messages dalam conversation
@@ -479,7 +1019,7 @@ pub async fn get_message_count(
         .await?;
 
     // Ambil unread messages untuk user
-    let unread_messages = state.message_repo
+    let unread_messages = state.conversation_repo
         .get_conversation_unread_count(conversation_id, participant.user_id)
         .await?;
 
@@ -524,7 +1064,7 @@ pub async fn get_unread_count(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    let unread_count = state.message_repo
+    let unread_count = state.conversation_repo
         .get_conversation_unread_count(conversation_id, participant.user_id)
         .await?;
 
@@ -547,11 +1087,10 @@ pub async fn get_unread_count(
     params(
         ("conversation_id" = Option<i32>, Query, description = "Filter by conversation ID"),
         ("search" = String, Query, description = "Search query string"),
-        ("limit" = Option<i64>, Query, description = "Limit results (max 50)"),
-        ("offset" = Option<i64>, Query, description = "Offset for pagination")
+        PaginationParams
     ),
     responses(
-        (status = 200, description = "Search results berhasil diambil", body = MessageListResponse),
+        (status = 200, description = "Search results berhasil diambil", body = MessageSearchResponse),
         (status = 400, description = "Query search diperlukan atau tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Tidak memiliki akses ke conversation"),
@@ -563,7 +1102,8 @@ pub async fn search_messages(
     State(state): State<AppState>,
     participant: ChatParticipant,
     Query(query): Query<MessageQuery>,
-) -> Result<Json<MessageListResponse>, AppError> {
+    Pagination { limit, offset, .. }: Pagination<20, 50>,
+) -> Result<Json<MessageSearchResponse>, AppError> {
     // Get conversation_id from query params
     let conversation_id = query.conversation_id.ok_or_else(|| {
         AppError::bad_request("Conversation ID diperlukan")
@@ -586,23 +1126,38 @@ pub async fn search_messages(
         return Err(AppError::bad_request("Query search tidak boleh kosong"));
     }
 
-    let limit = query.limit.unwrap_or(20).min(50);
-    let offset = query.offset.unwrap_or(0);
-
-    let messages = state.message_repo
-        .search_conversation_messages(conversation_id, participant.user_id, &search_query, limit, offset)
+    let snippet_options = &state.config.search_snippet;
+    let results = state.message_repo
+        .search_conversation_messages(
+            conversation_id,
+            participant.user_id,
+            &search_query,
+            &snippet_options.headline_options(),
+            limit + 1,
+            offset,
+        )
         .await?;
+    let (results, has_more) = into_page(results, limit);
 
-    let total = messages.len() as i64;
+    let total = results.len() as i64;
 
     tracing::info!("User {} search '{}' dalam conversation {} menemukan {} results",
                    participant.user_id, search_query, conversation_id, total);
 
-    Ok(Json(MessageListResponse {
+    let messages = results
+        .into_iter()
+        .map(|(message, headline)| {
+            let snippet = snippet_options.render(headline.as_deref(), &message.content, &search_query);
+            MessageSearchHit { message: proxy_media(&state, message), snippet }
+        })
+        .collect();
+
+    Ok(Json(MessageSearchResponse {
         messages,
         total,
         limit,
         offset,
+        has_more,
         conversation_id,
     }))
 }
@@ -615,10 +1170,11 @@ pub async fn search_messages(
     security(("bearer_auth" = [])),
     params(
         ("conversation_id" = i32, Path, description = "Conversation ID"),
-        MessageQuery
+        PaginationParams
     ),
     responses(
         (status = 200, description = "Media messages berhasil diambil", body = MessageListResponse),
+        (status = 400, description = "Parameter paginasi tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Tidak memiliki akses"),
         (status = 404, description = "Conversation tidak ditemukan"),
@@ -629,7 +1185,7 @@ pub async fn get_media_messages(
     State(state): State<AppState>,
     participant: ChatParticipant,
     Path(conversation_id): Path<i32>,
-    Query(query): Query<MessageQuery>,
+    Pagination { limit, offset, .. }: Pagination<20, 50>,
 ) -> Result<Json<MessageListResponse>, AppError> {
     // Cek apakah user adalah participant
     let is_participant = state.conversation_repo
@@ -640,12 +1196,10 @@ pub async fn get_media_messages(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    let limit = query.limit.unwrap_or(20).min(50);
-    let offset = query.offset.unwrap_or(0);
-
     let messages = state.message_repo
-        .get_media_messages(conversation_id, participant.user_id, limit, offset)
+        .get_media_messages(conversation_id, participant.user_id, limit + 1, offset)
         .await?;
+    let (messages, has_more) = into_page(messages, limit);
 
     let total = messages.len() as i64;
 
@@ -653,10 +1207,11 @@ pub async fn get_media_messages(
                    participant.user_id, total, conversation_id);
 
     Ok(Json(MessageListResponse {
-        messages,
+        messages: proxy_media_list(&state, messages),
         total,
         limit,
         offset,
+        has_more,
         conversation_id,
     }))
 }
@@ -670,10 +1225,11 @@ pub async fn get_media_messages(
     params(
         ("conversation_id" = i32, Path, description = "Conversation ID"),
         ("sender_id" = i32, Path, description = "Sender ID"),
-        MessageQuery
+        PaginationParams
     ),
     responses(
         (status = 200, description = "Messages dari sender berhasil diambil", body = MessageListResponse),
+        (status = 400, description = "Parameter paginasi tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Tidak memiliki akses"),
         (status = 404, description = "Conversation tidak ditemukan"),
@@ -684,7 +1240,7 @@ pub async fn get_messages_by_sender(
     State(state): State<AppState>,
     participant: ChatParticipant,
     Path((conversation_id, sender_id)): Path<(i32, i32)>,
-    Query(query): Query<MessageQuery>,
+    Pagination { limit, offset, .. }: Pagination<20, 50>,
 ) -> Result<Json<MessageListResponse>, AppError> {
     // Cek apakah user adalah participant
     let is_participant = state.conversation_repo
@@ -695,12 +1251,10 @@ pub async fn get_messages_by_sender(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    let limit = query.limit.unwrap_or(20).min(50);
-    let offset = query.offset.unwrap_or(0);
-
     let messages = state.message_repo
-        .get_messages_by_sender(conversation_id, sender_id, limit, offset)
+        .get_messages_by_sender(conversation_id, sender_id, limit + 1, offset)
         .await?;
+    let (messages, has_more) = into_page(messages, limit);
 
     let total = messages.len() as i64;
 
@@ -708,10 +1262,11 @@ pub async fn get_messages_by_sender(
                    participant.user_id, total, sender_id, conversation_id);
 
     Ok(Json(MessageListResponse {
-        messages,
+        messages: proxy_media_list(&state, messages),
         total,
         limit,
         offset,
+        has_more,
         conversation_id,
     }))
 }
@@ -719,12 +1274,54 @@ pub async fn get_messages_by_sender(
 // Request struct untuk message dengan files
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateMessageWithFilesRequest {
+    #[serde(default)]
     pub content: String,
-    pub message_type: MessageType,
+    // Opsional: jika kosong diturunkan dari files yang dilampirkan
+    pub message_type: Option<MessageType>,
     pub files: Option<Vec<String>>,            
     pub thumbnails: Option<Vec<String>>,       
 }
 
+// Pastikan message_type sesuai dengan media yang dilampirkan, atau turunkan dari files
+fn resolve_file_message_type(
+    declared: Option<MessageType>,
+    categories: &[FileCategory],
+) -> Result<MessageType, AppError> {
+    // Satu message hanya boleh berisi satu kategori lampiran
+    let mut kinds = categories.to_vec();
+    kinds.sort_by_key(|category| *category as u8);
+    kinds.dedup();
+
+    let derived = match kinds.as_slice() {
+        [] => MessageType::Text,
+        [FileCategory::Image] => MessageType::Image,
+        [FileCategory::Document] => MessageType::Document,
+        [FileCategory::Audio] => MessageType::Audio,
+        _ => {
+            return Err(AppError::validation(
+                "Gambar, dokumen, dan audio tidak boleh dicampur dalam satu message"
+            ));
+        }
+    };
+
+    match declared {
+        None => Ok(derived),
+        Some(declared) if declared.as_str() == derived.as_str() => Ok(declared),
+        Some(MessageType::Text) => Err(AppError::validation(
+            "Message bertipe text tidak boleh memiliki lampiran"
+        )),
+        Some(MessageType::Image) => Err(AppError::validation(
+            "Message bertipe image harus melampirkan file gambar"
+        )),
+        Some(MessageType::Document) => Err(AppError::validation(
+            "Message bertipe document harus melampirkan file dokumen"
+        )),
+        Some(MessageType::Audio) => Err(AppError::validation(
+            "Message bertipe audio harus melampirkan file audio"
+        )),
+    }
+}
+
 // Kirim message dengan files (terintegrasi dengan upload handler)
 #[utoipa::path(
     post,
@@ -733,20 +1330,23 @@ pub struct CreateMessageWithFilesRequest {
     security(("bearer_auth" = [])),
     request_body = CreateMessageWithFilesRequest,
     responses(
-        (status = 201, description = "Message dengan files berhasil dikirim", body = Message),
+        (status = 201, description = "Message dengan files berhasil dikirim", body = MessageResponse,
+            headers(("Location" = String, description = "URL message"))),
         (status = 400, description = "Request tidak valid"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Tidak memiliki akses ke conversation"),
         (status = 404, description = "Conversation tidak ditemukan"),
+        (status = 429, description = "Terlalu banyak message dikirim"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn send_message_with_files(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     participant: ChatParticipant,
     Path(conversation_id): Path<i32>,
     Json(request): Json<CreateMessageWithFilesRequest>,
-) -> Result<Json<Message>, AppError> {
+) -> Result<Creation<MessageResponse>, AppError> {
     // Cek apakah user adalah participant dalam conversation
     let is_participant = state.conversation_repo
         .is_participant(conversation_id, participant.user_id)
@@ -756,27 +1356,47 @@ pub async fn send_message_with_files(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    // Validate files jika ada
-    if let Some(ref files) = request.files {
-        validate_chat_files(files)?;
-    }
+    enforce_send_limit(&state, participant.user_id).await?;
+
+    let files = request.files.unwrap_or_default();
+
+    // Jumlah files/thumbnails dan URL dicek duluan agar array besar ditolak sebelum diproses
+    validate_chat_files(
+        &state.storage,
+        &files,
+        request.thumbnails.as_deref(),
+        state.config.max_attachments_per_message,
+    )?;
+
+    // Content boleh kosong jika ada file yang dilampirkan
+    validate_message_content(&request.content, !files.is_empty(), state.config.max_message_length, state.config.strict_validation)?;
+
+    // Kategori setiap file menentukan message_type yang sah, dicek sebelum scan yang mahal
+    let categories = files.iter()
+        .map(|file_url| category_from_url(file_url).ok_or_else(|| {
+            AppError::validation(format!("Tipe file tidak dikenali: {}", file_url))
+        }))
+        .collect::<Result<Vec<_>, _>>()?;
+    let message_type = resolve_file_message_type(request.message_type, &categories)?;
+
+    // Scan files jika ada
+    scan_chat_files(&state, participant.user_id, peer.ip(), "/messages/with-files", &files).await?;
 
     // Extract file info untuk message creation menggunakan utility function
     let upload_response = UploadResponse {
         success: true,
-        files: request.files.as_ref().map_or(vec![], |files| {
-            files.iter().enumerate().map(|(i, file_url)| UploadedFile {
-                filename: format!("file-{}", i),
-                original_name: Some(format!("file-{}", i)),
-                file_type: "unknown".to_string(),
-                file_size: 0,
-                url: file_url.clone(),
-                thumbnail_url: request.thumbnails.as_ref()
-                    .and_then(|thumbs| thumbs.get(i))
-                    .cloned(),
-                category: FileCategory::Image, 
-            }).collect()
-        }),
+        files: files.iter().zip(categories).enumerate().map(|(i, (file_url, category))| UploadedFile {
+            filename: format!("file-{}", i),
+            original_name: Some(format!("file-{}", i)),
+            file_type: "unknown".to_string(),
+            file_size: 0,
+            url: file_url.clone(),
+            thumbnail_url: request.thumbnails.as_ref()
+                .and_then(|thumbs| thumbs.get(i))
+                .cloned(),
+            delivery_url: category_delivery_url(&state.storage, upload_policy(&state, category), file_url),
+            category,
+        }).collect(),
         message: "Files processed".to_string(),
     };
 
@@ -791,46 +1411,22 @@ pub async fn send_message_with_files(
     let create_request = CreateMessageRequest {
         conversation_id,
         content: request.content,
-        message_type: Some(request.message_type.as_str().to_string()),
+        message_type: Some(message_type.as_str().to_string()),
         media_url,
         thumbnail_url,
+        reply_to_message_id: None,
+        thread_root_id: None,
+        is_auto_reply: false,
     };
 
     // Buat message baru
     let message = state.message_repo
-        .create_message(conversation_id, participant.user_id, create_request)
+        .create_message(conversation_id, participant.user_id, &participant.email, create_request, None, |url| state.storage.owns_url(url))
         .await?;
 
-    // Update last message info di conversation
-    let content_preview = if message.content.len() > 50 {
-        format!("{}...", &message.content[..50])
-    } else {
-        message.content.clone()
-    };
+    let message_response = complete_sent_message(&state, &participant, message).await?;
 
-    state.conversation_repo
-        .update_last_message(conversation_id, &content_preview)
-        .await?;
-
-    // Broadcast message via NATS ke WebSocket connections
-    if let Some(nats_client) = &state.nats_client {
-        if let Err(e) = broadcast_message_via_nats(
-            nats_client,
-            conversation_id,
-            &message,
-            &participant.email,
-        ).await {
-            // Log error tapi tidak gagalkan request, karena message sudah tersimpan di database
-            tracing::warn!("Broadcast message gagal: {}, tapi message tersimpan di database", e);
-        }
-    } else {
-        tracing::warn!("NATS client tidak tersedia, message tidak di-broadcast secara real-time");
-    }
-
-    tracing::info!("User {} mengirim message {} dengan files ke conversation {}",
-                   participant.user_id, message.id, conversation_id);
-
-    Ok(Json(message))
+    Ok(created_message(message_response))
 }
 
 // Typing indicator request
@@ -880,31 +1476,27 @@ pub async fn send_typing_indicator(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
-    // Broadcast typing indicator via NATS
-    if let Some(nats_client) = &state.nats_client {
-        let typing_payload = serde_json::json!({
-            "type": "typing_indicator",
-            "conversation_id": conversation_id,
-            "user_id": participant.user_id,
-            "user_email": participant.email,
-            "is_typing": request.is_typing,
-            "timestamp": chrono::Utc::now()
-        });
-
-        let subject = format!("chat.{}", conversation_id);
-        if let Err(e) = nats_client
-            .publish(subject, typing_payload.to_string().into())
-            .await
-        {
-            tracing::warn!("Gagal broadcast typing indicator: {}", e);
-        } else {
-            tracing::info!("User {} {} di conversation {}",
-                          participant.user_id,
-                          if request.is_typing { "sedang mengetik" } else { "berhenti mengetik" },
-                          conversation_id);
-        }
-    } else {
-        tracing::warn!("NATS client tidak tersedia, typing indicator tidak di-broadcast");
+    // Broadcast typing indicator via NATS (dilewati di degraded mode)
+    let typing_payload = serde_json::json!({
+        "type": "typing_indicator",
+        "conversation_id": conversation_id,
+        "user_id": participant.user_id,
+        "user_email": participant.email,
+        "is_typing": request.is_typing,
+        "timestamp": chrono::Utc::now()
+    });
+    let broadcasted = realtime::publish_best_effort(
+        state.nats_client.as_ref(),
+        format!("chat.{}", conversation_id),
+        typing_payload.to_string(),
+        "typing indicator",
+    ).await;
+
+    if broadcasted {
+        tracing::info!("User {} {} di conversation {}",
+                      participant.user_id,
+                      if request.is_typing { "sedang mengetik" } else { "berhenti mengetik" },
+                      conversation_id);
     }
 
     Ok(StatusCode::OK)
@@ -917,6 +1509,7 @@ pub struct MessagePreviewResponse {
     pub file_count: usize,
     pub has_images: bool,
     pub has_documents: bool,
+    pub has_audio: bool,
 }
 
 // Generate preview text untuk message dengan files
@@ -948,11 +1541,254 @@ pub async fn generate_message_preview(
     let doc_count = request.files.iter()
         .filter(|f| matches!(f.category, FileCategory::Document))
         .count();
+    let audio_count = request.files.iter()
+        .filter(|f| matches!(f.category, FileCategory::Audio))
+        .count();
 
     Ok(Json(MessagePreviewResponse {
         preview_text,
         file_count: request.files.len(),
         has_images: image_count > 0,
         has_documents: doc_count > 0,
+        has_audio: audio_count > 0,
     }))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(message_type: MessageType, content: &str, media_url: Option<&str>) -> Message {
+        Message {
+            id: 1,
+            conversation_id: 3,
+            sender_id: 7,
+            content: content.to_string(),
+            message_type,
+            media_url: media_url.map(str::to_string),
+            thumbnail_url: None,
+            is_read: false,
+            read_at: None,
+            is_deleted: false,
+            deleted_at: None,
+            created_at: chrono::Utc::now(),
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+            reply_to: None,
+        }
+    }
+
+    async fn response_body(response: axum::response::Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn body_keys(body: &serde_json::Value) -> Vec<String> {
+        let mut keys: Vec<String> = body.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_text_and_file_messages_share_response_shape(pool: sqlx::PgPool) {
+        use axum::{http::StatusCode, response::IntoResponse};
+        use crate::utils::conversation_initiation::FindOrCreate;
+
+        let state = AppState::for_test(pool);
+        let conversation_id = match state.conversation_repo.find_or_create_conversation(1, 2, None).await.unwrap() {
+            Some(FindOrCreate::Created(id) | FindOrCreate::Existing(id)) => id,
+            None => panic!("conversation tidak dibuat"),
+        };
+        let customer = ChatParticipant {
+            user_id: 1,
+            email: "customer@test.local".to_string(),
+            role: "customer".to_string(),
+            is_active: true,
+        };
+
+        let text = send_message(
+            State(state.clone()),
+            ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))),
+            customer.clone(),
+            Path(conversation_id),
+            Json(CreateMessageRequest {
+                conversation_id,
+                content: "Halo, mobilnya masih ada?".to_string(),
+                message_type: None,
+                media_url: None,
+                thumbnail_url: None,
+                reply_to_message_id: None,
+                thread_root_id: None,
+                is_auto_reply: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+
+        let with_files = send_message_with_files(
+            State(state.clone()),
+            ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))),
+            customer,
+            Path(conversation_id),
+            Json(CreateMessageWithFilesRequest {
+                content: String::new(),
+                message_type: None,
+                files: Some(vec!["http://127.0.0.1:9000/chat/chat/images/mobil.jpg".to_string()]),
+                thumbnails: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+
+        assert_eq!(text.status(), StatusCode::CREATED);
+        assert_eq!(with_files.status(), StatusCode::CREATED);
+
+        let text = response_body(text).await;
+        let with_files = response_body(with_files).await;
+        assert_eq!(body_keys(&text), body_keys(&with_files));
+
+        // Kedua endpoint mengisi nama sender dari users, bukan hanya field kosong yang sama
+        assert_eq!(text["sender_name"], "Customer Test");
+        assert_eq!(with_files["sender_name"], "Customer Test");
+        assert_eq!(with_files["message_type"], "image");
+        // URL storage tidak pernah dikirim langsung, selalu lewat proxy media
+        assert_eq!(with_files["media_url"], format!("/messages/{}/media", with_files["id"]));
+    }
+
+    // Degraded mode (nats_client None): kirim message tetap berhasil, event tertahan di outbox,
+    // dan instance tetap ready
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_send_without_nats_keeps_events_in_outbox(pool: sqlx::PgPool) {
+        use axum::{http::StatusCode, response::IntoResponse};
+        use crate::utils::conversation_initiation::FindOrCreate;
+
+        let state = AppState::for_test(pool.clone());
+        assert!(state.nats_client.is_none());
+        let conversation_id = match state.conversation_repo.find_or_create_conversation(1, 2, None).await.unwrap() {
+            Some(FindOrCreate::Created(id) | FindOrCreate::Existing(id)) => id,
+            None => panic!("conversation tidak dibuat"),
+        };
+        let customer = ChatParticipant {
+            user_id: 1,
+            email: "customer@test.local".to_string(),
+            role: "customer".to_string(),
+            is_active: true,
+        };
+
+        let response = send_message(
+            State(state.clone()),
+            ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))),
+            customer,
+            Path(conversation_id),
+            Json(CreateMessageRequest {
+                conversation_id,
+                content: "Halo, masih tersedia?".to_string(),
+                message_type: None,
+                media_url: None,
+                thumbnail_url: None,
+                reply_to_message_id: None,
+                thread_root_id: None,
+                is_auto_reply: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chat_outbox WHERE sent_at IS NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(pending, 2);
+
+        let readiness = state.readiness_check().await;
+        assert!(readiness.ready);
+        assert_eq!(readiness.nats, "not_initialized");
+    }
+
+    #[test]
+    fn test_file_message_type_derived_from_files() {
+        assert!(matches!(resolve_file_message_type(None, &[]), Ok(MessageType::Text)));
+        assert!(matches!(resolve_file_message_type(None, &[FileCategory::Image, FileCategory::Image]), Ok(MessageType::Image)));
+        assert!(matches!(resolve_file_message_type(None, &[FileCategory::Document]), Ok(MessageType::Document)));
+    }
+
+    #[test]
+    fn test_image_type_without_images_rejected() {
+        assert!(matches!(
+            resolve_file_message_type(Some(MessageType::Image), &[]),
+            Err(AppError::ValidationError(msg)) if msg.contains("image")
+        ));
+        assert!(resolve_file_message_type(Some(MessageType::Image), &[FileCategory::Document]).is_err());
+    }
+
+    #[test]
+    fn test_document_type_without_documents_rejected() {
+        assert!(resolve_file_message_type(Some(MessageType::Document), &[]).is_err());
+        assert!(resolve_file_message_type(Some(MessageType::Document), &[FileCategory::Image]).is_err());
+    }
+
+    #[test]
+    fn test_text_type_with_media_rejected() {
+        assert!(matches!(
+            resolve_file_message_type(Some(MessageType::Text), &[FileCategory::Image]),
+            Err(AppError::ValidationError(msg)) if msg.contains("lampiran")
+        ));
+    }
+
+    #[test]
+    fn test_mixed_attachments_rejected() {
+        assert!(resolve_file_message_type(None, &[FileCategory::Image, FileCategory::Document]).is_err());
+        assert!(resolve_file_message_type(None, &[FileCategory::Audio, FileCategory::Image]).is_err());
+    }
+
+    #[test]
+    fn test_audio_message_type_derived_from_files() {
+        assert!(matches!(resolve_file_message_type(None, &[FileCategory::Audio, FileCategory::Audio]), Ok(MessageType::Audio)));
+        assert!(matches!(resolve_file_message_type(Some(MessageType::Audio), &[FileCategory::Audio]), Ok(MessageType::Audio)));
+        assert!(resolve_file_message_type(Some(MessageType::Audio), &[FileCategory::Document]).is_err());
+    }
+
+    #[test]
+    fn test_category_from_url_ignores_query() {
+        assert_eq!(category_from_url("https://cdn.example.com/chat/a.JPG?v=1"), Some(FileCategory::Image));
+        assert_eq!(category_from_url("https://cdn.example.com/chat/kontrak.pdf#page=2"), Some(FileCategory::Document));
+        assert_eq!(category_from_url("https://cdn.example.com/chat/voice.M4A"), Some(FileCategory::Audio));
+        assert_eq!(category_from_url("https://cdn.example.com/chat/noext"), None);
+    }
+
+    #[test]
+    fn test_missing_sender_name_falls_back() {
+        let response = build_message_response(&message(MessageType::Text, "Halo", None), None);
+        assert_eq!(response.sender_name, "Unknown");
+    }
+
+    #[test]
+    fn test_has_more_at_exact_page_boundaries() {
+        // Tepat satu halaman penuh: tidak ada halaman berikutnya
+        assert_eq!(into_page(vec![1, 2, 3], 3), (vec![1, 2, 3], false));
+        // Satu baris ekstra: ada halaman berikutnya, baris ekstra dibuang
+        assert_eq!(into_page(vec![1, 2, 3, 4], 3), (vec![1, 2, 3], true));
+        assert_eq!(into_page(vec![1, 2], 3), (vec![1, 2], false));
+        assert_eq!(into_page(Vec::<i32>::new(), 3), (vec![], false));
+        assert_eq!(into_page(vec![1, 2], 1), (vec![1], true));
+    }
+}
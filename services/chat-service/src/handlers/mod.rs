@@ -2,4 +2,7 @@
 pub mod conversations;
 pub mod messages;
 pub mod websocket;
-pub mod upload;
\ No newline at end of file
+pub mod upload;
+pub mod inbound_email;
+pub mod auto_reply;
+pub mod seller_staff;
@@ -0,0 +1,287 @@
+// Seller Staff Handlers: anggota dealer dan assignment conversation ke staff
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use shared::utils::creation::Creation;
+
+use crate::{
+    config::AppState,
+    domain::{AddSellerStaffRequest, AssignConversationRequest, ConversationAssignmentResponse, SellerStaffMember},
+    error::AppError,
+    handlers::websocket::{broadcast_conversation_updated, WsMessage},
+    middleware::ChatParticipant,
+    utils::{assignment, realtime},
+};
+
+// Daftar staff dealer milik seller yang login
+#[utoipa::path(
+    get,
+    path = "/seller/staff",
+    tag = "seller-staff",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Daftar staff dealer", body = Vec<SellerStaffMember>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_staff(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+) -> Result<Json<Vec<SellerStaffMember>>, AppError> {
+    if !participant.is_seller() {
+        return Err(AppError::forbidden("Staff dealer hanya bisa dikelola seller"));
+    }
+
+    let staff = state.seller_staff_repo.list(participant.user_id).await?;
+
+    Ok(Json(staff))
+}
+
+// Tambah user sebagai staff dealer seller yang login
+#[utoipa::path(
+    post,
+    path = "/seller/staff",
+    tag = "seller-staff",
+    security(("bearer_auth" = [])),
+    request_body = AddSellerStaffRequest,
+    responses(
+        (status = 201, description = "Staff ditambahkan", body = Vec<SellerStaffMember>,
+            headers(("Location" = String, description = "URL staff dealer"))),
+        (status = 400, description = "User adalah seller, tidak aktif, atau sudah jadi staff dealer lain"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller"),
+        (status = 404, description = "User tidak ditemukan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn add_staff(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Json(request): Json<AddSellerStaffRequest>,
+) -> Result<Creation<Vec<SellerStaffMember>>, AppError> {
+    if !participant.is_seller() {
+        return Err(AppError::forbidden("Staff dealer hanya bisa dikelola seller"));
+    }
+
+    let candidate = state.seller_staff_repo
+        .find_candidate(request.staff_user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User tidak ditemukan"))?;
+
+    if request.staff_user_id == participant.user_id || candidate.is_seller {
+        return Err(AppError::bad_request("Akun seller tidak bisa dijadikan staff"));
+    }
+
+    if !candidate.is_active {
+        return Err(AppError::bad_request("Akun user tidak aktif"));
+    }
+
+    if !state.seller_staff_repo.add(participant.user_id, request.staff_user_id).await? {
+        return Err(AppError::bad_request("User sudah terdaftar sebagai staff dealer"));
+    }
+
+    tracing::info!(
+        event = "seller_staff_added",
+        seller_id = participant.user_id,
+        staff_user_id = request.staff_user_id,
+        "Seller menambahkan staff"
+    );
+
+    let staff = state.seller_staff_repo.list(participant.user_id).await?;
+
+    Ok(Creation::created(format!("/api/seller/staff/{}", request.staff_user_id), staff))
+}
+
+// Detail satu staff dealer (target Location saat staff ditambahkan)
+#[utoipa::path(
+    get,
+    path = "/seller/staff/{staff_user_id}",
+    tag = "seller-staff",
+    security(("bearer_auth" = [])),
+    params(
+        ("staff_user_id" = i32, Path, description = "User ID staff")
+    ),
+    responses(
+        (status = 200, description = "Staff dealer", body = SellerStaffMember),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller"),
+        (status = 404, description = "Staff tidak ditemukan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_staff(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(staff_user_id): Path<i32>,
+) -> Result<Json<SellerStaffMember>, AppError> {
+    if !participant.is_seller() {
+        return Err(AppError::forbidden("Staff dealer hanya bisa dikelola seller"));
+    }
+
+    let staff = state.seller_staff_repo
+        .find(participant.user_id, staff_user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Staff tidak ditemukan"))?;
+
+    Ok(Json(staff))
+}
+
+// Hapus staff dealer, conversation yang di-assign ke staff kembali ke seller
+#[utoipa::path(
+    delete,
+    path = "/seller/staff/{staff_user_id}",
+    tag = "seller-staff",
+    security(("bearer_auth" = [])),
+    params(
+        ("staff_user_id" = i32, Path, description = "User ID staff")
+    ),
+    responses(
+        (status = 204, description = "Staff dihapus"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller"),
+        (status = 404, description = "Staff tidak ditemukan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_staff(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(staff_user_id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    if !participant.is_seller() {
+        return Err(AppError::forbidden("Staff dealer hanya bisa dikelola seller"));
+    }
+
+    if !state.seller_staff_repo.remove(participant.user_id, staff_user_id).await? {
+        return Err(AppError::not_found("Staff tidak ditemukan"));
+    }
+
+    tracing::info!(
+        event = "seller_staff_removed",
+        seller_id = participant.user_id,
+        staff_user_id,
+        "Seller menghapus staff"
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Assign / reassign conversation ke staff dealer (seller atau staff dealer yang sama)
+#[utoipa::path(
+    put,
+    path = "/conversations/{conversation_id}/assignment",
+    tag = "seller-staff",
+    security(("bearer_auth" = [])),
+    params(
+        ("conversation_id" = i32, Path, description = "Conversation ID")
+    ),
+    request_body = AssignConversationRequest,
+    responses(
+        (status = 200, description = "Assignment diupdate", body = ConversationAssignmentResponse),
+        (status = 400, description = "Assignee bukan staff dealer pemilik conversation"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Hanya seller dan staff dealer pemilik conversation"),
+        (status = 404, description = "Conversation tidak ditemukan"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn assign_conversation(
+    State(state): State<AppState>,
+    participant: ChatParticipant,
+    Path(conversation_id): Path<i32>,
+    Json(request): Json<AssignConversationRequest>,
+) -> Result<Json<ConversationAssignmentResponse>, AppError> {
+    let seller_id = state.conversation_repo
+        .get_seller_id(conversation_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Conversation tidak ditemukan"))?;
+
+    let actor_staff_of = state.seller_staff_repo.org_of(participant.user_id).await?;
+    if !assignment::can_manage_assignment(participant.user_id, seller_id, actor_staff_of) {
+        return Err(AppError::forbidden("Hanya anggota dealer pemilik conversation yang bisa mengatur assignment"));
+    }
+
+    let assignee_staff_of = match request.assigned_to {
+        Some(assignee) => state.seller_staff_repo.org_of(assignee).await?,
+        None => None,
+    };
+    let assigned_to = assignment::resolve_assignee(seller_id, request.assigned_to, assignee_staff_of)
+        .map_err(AppError::bad_request)?;
+
+    let assigned_at = state.conversation_repo
+        .assign(conversation_id, assigned_to, participant.user_id)
+        .await?;
+
+    // Notifikasi real-time ke assignee baru, lalu update item inbox semua participant
+    if let Some(assignee) = assigned_to.filter(|assignee| *assignee != participant.user_id) {
+        let event = WsMessage::ConversationAssigned { conversation_id, assigned_by: participant.user_id };
+        if let Ok(payload) = serde_json::to_string(&event) {
+            realtime::publish_best_effort(
+                state.nats_client.as_ref(),
+                realtime::user_subject(assignee),
+                payload,
+                "conversation assigned",
+            ).await;
+        }
+    }
+    broadcast_conversation_updated(&state, conversation_id).await;
+
+    tracing::info!(
+        event = "conversation_assigned",
+        conversation_id,
+        user_id = participant.user_id,
+        assigned_to = ?assigned_to,
+        "Conversation di-assign"
+    );
+
+    Ok(Json(ConversationAssignmentResponse {
+        conversation_id,
+        assigned_to,
+        assigned_at,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::header, response::IntoResponse};
+    use sqlx::PgPool;
+
+    fn seller(user_id: i32) -> ChatParticipant {
+        ChatParticipant {
+            user_id,
+            email: format!("seller{}@test.local", user_id),
+            role: "seller".to_string(),
+            is_active: true,
+        }
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_add_staff_location_resolves_to_member(pool: PgPool) {
+        let state = AppState::for_test(pool);
+
+        let created = add_staff(State(state.clone()), seller(2), Json(AddSellerStaffRequest { staff_user_id: 1 }))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        assert_eq!(created.headers()[header::LOCATION], "/api/seller/staff/1");
+
+        let Json(member) = get_staff(State(state.clone()), seller(2), Path(1)).await.unwrap();
+        assert_eq!(member.staff_user_id, 1);
+
+        // Staff seller lain tidak terlihat
+        assert!(matches!(get_staff(State(state), seller(3), Path(1)).await, Err(AppError::NotFound(_))));
+    }
+}
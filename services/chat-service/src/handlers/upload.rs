@@ -1,18 +1,23 @@
 // Upload Handler untuk Chat Service - Media Files
+use std::net::{IpAddr, SocketAddr};
+
 use axum::{
-    extract::{State},
+    extract::{ConnectInfo, State},
     response::Json,
 };
 use axum_extra::extract::Multipart;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use utoipa::ToSchema;
-use shared::utils::cloudinary::CloudinaryClient;
+use shared::utils::storage::{Storage, StorageBackend};
 
 use crate::{
     config::AppState,
     middleware::ChatParticipant,
     error::AppError,
+    utils::file_scanner::ScanResult,
+    utils::message_validation::{validate_attachment_count, validate_attachment_url, validate_upload_extension},
+    utils::upload_policy::UploadCategoryPolicy,
 };
 
 // Constants untuk file upload validation
@@ -26,6 +31,9 @@ const ALLOWED_DOCUMENT_TYPES: &[&str] = &[
     "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
     "text/plain", "text/csv"
 ];
+const ALLOWED_AUDIO_TYPES: &[&str] = &[
+    "audio/mpeg", "audio/mp4", "audio/aac", "audio/ogg", "audio/wav"
+];
 
 // Response untuk upload success
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -44,15 +52,19 @@ pub struct UploadedFile {
     pub file_size: usize,
     pub url: String,
     pub thumbnail_url: Option<String>,
+    // URL dengan transformasi CDN kategori (jika dikonfigurasi)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_url: Option<String>,
     pub category: FileCategory,
 }
 
 // Kategori file yang diupload
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum FileCategory {
     Image,
     Document,
+    Audio,
 }
 
 // Validasi tipe file
@@ -61,14 +73,46 @@ fn validate_file_type(content_type: &str) -> Result<FileCategory, AppError> {
         Ok(FileCategory::Image)
     } else if ALLOWED_DOCUMENT_TYPES.contains(&content_type) {
         Ok(FileCategory::Document)
+    } else if ALLOWED_AUDIO_TYPES.contains(&content_type) {
+        Ok(FileCategory::Audio)
     } else {
         Err(AppError::validation(format!(
-            "Tipe file tidak diizinkan: {}. Allowed: images (jpeg, png, gif, webp) dan documents (pdf, doc, docx, txt, csv)",
+            "Tipe file tidak diizinkan: {}. Allowed: images (jpeg, png, gif, webp), documents (pdf, doc, docx, txt, csv) dan audio (mp3, m4a, aac, ogg, wav)",
             content_type
         )))
     }
 }
 
+// Kategori attachment dari ekstensi URL (query string dan fragment diabaikan)
+pub fn category_from_url(url: &str) -> Option<FileCategory> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name.rsplit_once('.')?.1.to_lowercase();
+
+    match extension.as_str() {
+        "jpg" | "jpeg" | "png" | "gif" | "webp" => Some(FileCategory::Image),
+        "pdf" | "doc" | "docx" | "txt" | "csv" => Some(FileCategory::Document),
+        "mp3" | "m4a" | "aac" | "ogg" | "wav" => Some(FileCategory::Audio),
+        _ => None,
+    }
+}
+
+// Kebijakan upload (folder/format/transformasi) untuk kategori file
+pub fn upload_policy(state: &AppState, category: FileCategory) -> &UploadCategoryPolicy {
+    match category {
+        FileCategory::Image => &state.config.upload_image_policy,
+        FileCategory::Document => &state.config.upload_document_policy,
+        FileCategory::Audio => &state.config.upload_audio_policy,
+    }
+}
+
+// URL delivery dengan transformasi CDN kategori file, None jika transformasi tidak dikonfigurasi
+// atau backend storage tidak mendukung transformasi
+pub fn category_delivery_url(storage: &StorageBackend, policy: &UploadCategoryPolicy, url: &str) -> Option<String> {
+    policy.transformation.as_deref()
+        .and_then(|transformation| storage.delivery_url(url, transformation))
+}
+
 // Generate filename yang unik untuk chat
 fn generate_chat_filename(user_id: i32, original_name: &str, index: usize) -> String {
     let timestamp = chrono::Utc::now().timestamp();
@@ -96,6 +140,7 @@ fn generate_chat_filename(user_id: i32, original_name: &str, index: usize) -> St
 )]
 pub async fn upload_file(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     participant: ChatParticipant,
     mut multipart: Multipart,
 ) -> Result<Json<UploadResponse>, AppError> {
@@ -103,9 +148,12 @@ pub async fn upload_file(
 
     // Validate user rate limit sebelum upload
     let rate_limit_key = format!("upload:{}", participant.user_id);
+    let upload_window_secs = state.config.upload_rate_limit_window_secs;
     let rate_check = sqlx::query_scalar!(
-        "SELECT (check_rate_limit($1, 'user', 'file_upload', '/upload', 'chat-service', 60, 5)->>'allowed')::boolean",
-        rate_limit_key
+        "SELECT (check_rate_limit($1, 'user', 'file_upload', '/upload', 'chat-service', $2, $3)->>'allowed')::boolean",
+        rate_limit_key,
+        upload_window_secs,
+        state.config.upload_rate_limit_max
     )
     .fetch_one(&state.db)
     .await
@@ -115,13 +163,46 @@ pub async fn upload_file(
     })?;
 
     if !rate_check.unwrap_or(false) {
-        return Err(AppError::rate_limit("Too many upload attempts. Please wait before trying again."));
+        return Err(AppError::rate_limit("Too many upload attempts. Please wait before trying again.", upload_window_secs as u64));
     }
 
-    let cloudinary = CloudinaryClient::new()
-        .map_err(|e| AppError::cloudinary(format!("Cloudinary init error: {}", e)))?;
-
+    // Jika satu file gagal di tengah request, file yang sudah terupload dihapus lagi
+    // agar tidak jadi asset yatim di storage
     let mut uploaded_files: Vec<UploadedFile> = Vec::new();
+    if let Err(e) = receive_files(&state, &participant, peer.ip(), &mut multipart, &mut uploaded_files).await {
+        discard_uploaded_files(&state.storage, &uploaded_files).await;
+        return Err(e);
+    }
+
+    // Validasi minimal ada file yang diupload
+    if uploaded_files.is_empty() {
+        return Err(AppError::validation("Tidak ada file yang diupload"));
+    }
+
+    tracing::info!(
+        event = "files_uploaded",
+        user_id = participant.user_id,
+        count = uploaded_files.len(),
+        "Upload file chat berhasil"
+    );
+
+    let message = format!("{} files berhasil diupload", uploaded_files.len());
+    Ok(Json(UploadResponse {
+        success: true,
+        files: uploaded_files,
+        message,
+    }))
+}
+
+// Baca dan upload setiap file di multipart; file yang sukses ditambahkan ke `uploaded_files`
+// walaupun file berikutnya gagal, supaya caller bisa membersihkannya
+async fn receive_files(
+    state: &AppState,
+    participant: &ChatParticipant,
+    client_ip: IpAddr,
+    multipart: &mut Multipart,
+    uploaded_files: &mut Vec<UploadedFile>,
+) -> Result<(), AppError> {
     let mut file_count = 0;
 
     // Process semua fields di multipart
@@ -162,24 +243,25 @@ pub async fn upload_file(
 
         // Validasi file type
         let file_category = validate_file_type(&content_type)?;
+        validate_upload_extension(&file_name, &content_type, state.config.strict_validation)?;
 
         // Generate filename yang unik
         let safe_filename = generate_chat_filename(participant.user_id, &file_name, file_count);
 
-        // Upload ke Cloudinary dengan folder yang sesuai
-        let folder_name = match file_category {
-            FileCategory::Image => "chat/images",
-            FileCategory::Document => "chat/documents",
-        };
+        // Upload ke storage backend dengan folder sesuai kebijakan kategori
+        let policy = upload_policy(state, file_category);
+        policy.check_format(&file_name)?;
 
-        let upload_result = cloudinary
-            .upload_image(data.to_vec(), folder_name, Some(safe_filename.clone()))
+        let url = state.storage
+            .put(&policy.storage_key(&safe_filename), data.to_vec(), &content_type)
             .await
-            .map_err(|e| AppError::cloudinary(format!("Upload gagal: {}", e)))?;
+            .map_err(|e| AppError::storage(format!("Upload gagal: {}", e)))?;
+
+        let delivery_url = category_delivery_url(&state.storage, policy, &url);
 
         // Generate thumbnail untuk images
         let thumbnail_url = if matches!(file_category, FileCategory::Image) {
-            Some(format!("{}?w=200&h=200&c_thumb", upload_result.secure_url))
+            state.storage.thumbnail_url(&url)
         } else {
             None
         };
@@ -190,8 +272,9 @@ pub async fn upload_file(
             original_name: Some(file_name),
             file_type: content_type,
             file_size: data.len(),
-            url: upload_result.secure_url,
+            url,
             thumbnail_url,
+            delivery_url,
             category: file_category,
         });
 
@@ -203,9 +286,10 @@ pub async fn upload_file(
         if let Err(e) = sqlx::query!(
             r#"
             INSERT INTO audit_logs (user_id, ip_address, action, entity_type, entity_id, new_values, service_name, endpoint, http_method, created_at)
-            VALUES ($1, '127.0.0.1'::INET, 'FILE_UPLOAD', 'uploaded_file', $2, $3, 'chat-service', '/upload', 'POST', NOW())
+            VALUES ($1, $2::TEXT::INET, 'FILE_UPLOAD', 'uploaded_file', $3, $4, 'chat-service', '/upload', 'POST', NOW())
             "#,
             participant.user_id,
+            client_ip.to_string(),
             uploaded_files.last().unwrap().file_size as i32,
             json!({
                 "filename": uploaded_files.last().unwrap().filename,
@@ -219,19 +303,16 @@ pub async fn upload_file(
         }
     }
 
-    // Validasi minimal ada file yang diupload
-    if uploaded_files.is_empty() {
-        return Err(AppError::validation("Tidak ada file yang diupload"));
-    }
-
-    tracing::info!("User {} berhasil mengupload {} files",
-                   participant.user_id, uploaded_files.len());
+    Ok(())
+}
 
-    Ok(Json(UploadResponse {
-        success: true,
-        files: uploaded_files,
-        message: format!("{} files berhasil diupload", file_count),
-    }))
+// Hapus file yang sudah terupload saat request upload gagal (best effort)
+async fn discard_uploaded_files<S: Storage>(storage: &S, files: &[UploadedFile]) {
+    for file in files {
+        if let Err(e) = storage.delete(&file.url).await {
+            tracing::warn!("Gagal menghapus file upload yang dibatalkan {}: {}", file.url, e);
+        }
+    }
 }
 
 // Utility function untuk extract file info dari upload response
@@ -242,20 +323,73 @@ pub fn extract_file_info_for_message(upload_response: &UploadResponse) -> Vec<(S
         .collect()
 }
 
-// Validasi multiple files untuk chat message
-pub fn validate_chat_files(files: &[String]) -> Result<(), AppError> {
-    if files.is_empty() {
-        return Ok(());
+// Validasi multiple files (dan thumbnails jika ada) untuk chat message
+pub fn validate_chat_files(
+    storage: &StorageBackend,
+    files: &[String],
+    thumbnails: Option<&[String]>,
+    max_attachments: usize,
+) -> Result<(), AppError> {
+    validate_attachment_count(files.len(), thumbnails.map(<[String]>::len), max_attachments)?;
+
+    // Hanya URL milik storage backend yang diterima, di semua environment, karena
+    // scan_chat_files mengunduh setiap attachment dari server (cegah SSRF)
+    for file_url in files {
+        validate_attachment_url(file_url, storage.owns_url(file_url))?;
     }
 
-    if files.len() > 5 {
-        return Err(AppError::validation("Maksimal 5 files per message"));
-    }
+    Ok(())
+}
 
-    // Validasi setiap URL format
+// Scan malware semua attachment sebelum message disimpan
+pub async fn scan_chat_files(
+    state: &AppState,
+    user_id: i32,
+    client_ip: IpAddr,
+    endpoint: &str,
+    files: &[String],
+) -> Result<(), AppError> {
     for file_url in files {
-        if !file_url.starts_with("https://res.cloudinary.com") {
-            return Err(AppError::validation("URL file tidak valid"));
+        match state.file_scanner.scan_file(file_url).await {
+            Ok(ScanResult::Clean) | Ok(ScanResult::Skipped) => {}
+            Ok(ScanResult::Infected { signature }) => {
+                tracing::warn!("🚨 Malware terdeteksi pada file {} dari user {}: {}",
+                               file_url, user_id, signature);
+
+                // Log deteksi malware ke audit trail
+                if let Err(e) = sqlx::query!(
+                    r#"
+                    INSERT INTO audit_logs (user_id, ip_address, action, entity_type, new_values, service_name, endpoint, http_method, created_at)
+                    VALUES ($1, $2::TEXT::INET, 'MALWARE_DETECTED', 'uploaded_file', $3, 'chat-service', $4, 'POST', NOW())
+                    "#,
+                    user_id,
+                    client_ip.to_string(),
+                    json!({
+                        "url": file_url,
+                        "signature": signature,
+                        "backend": state.file_scanner.backend().name()
+                    }),
+                    endpoint
+                )
+                .execute(&state.db)
+                .await {
+                    tracing::warn!("Failed to log malware detection to audit trail: {}", e);
+                }
+
+                return Err(AppError::validation(
+                    "File terdeteksi mengandung malware dan tidak dapat dikirim"
+                ));
+            }
+            Err(e) => {
+                if state.file_scanner.is_fail_open() {
+                    tracing::warn!("File scan gagal untuk {} (fail-open, file diizinkan): {}", file_url, e);
+                } else {
+                    tracing::error!("File scan gagal untuk {} (fail-closed, file ditolak): {}", file_url, e);
+                    return Err(AppError::bad_request(
+                        "File tidak dapat diverifikasi keamanannya. Silakan coba lagi nanti"
+                    ));
+                }
+            }
         }
     }
 
@@ -270,13 +404,172 @@ pub fn generate_preview_text(files: &[UploadedFile]) -> String {
 
     let image_count = files.iter().filter(|f| matches!(f.category, FileCategory::Image)).count();
     let doc_count = files.iter().filter(|f| matches!(f.category, FileCategory::Document)).count();
+    let audio_count = files.iter().filter(|f| matches!(f.category, FileCategory::Audio)).count();
+
+    match (image_count, doc_count, audio_count) {
+        (0, 0, 0) => "File terlampir".to_string(),
+        (1, 0, 0) => "📷 Gambar".to_string(),
+        (img, 0, 0) => format!("📷 {} gambar", img),
+        (0, 1, 0) => "📄 Dokumen".to_string(),
+        (0, doc, 0) => format!("📄 {} dokumen", doc),
+        (0, 0, 1) => "🎤 Audio".to_string(),
+        (0, 0, audio) => format!("🎤 {} audio", audio),
+        (img, doc, audio) => [(img, "📷", "gambar"), (doc, "📄", "dokumen"), (audio, "🎤", "audio")]
+            .iter()
+            .filter(|(count, _, _)| *count > 0)
+            .map(|(count, icon, label)| format!("{} {} {}", icon, count, label))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::utils::cloudinary::CloudinaryClient;
+    use shared::utils::storage::{CloudinaryStorage, S3Storage, StorageError};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    // Storage palsu yang mencatat URL yang dihapus; delete ke `failing_url` selalu gagal
+    #[derive(Default)]
+    struct RecordingStorage {
+        deleted: Mutex<Vec<String>>,
+        failing_url: Option<String>,
+    }
+
+    impl Storage for RecordingStorage {
+        async fn put(&self, key: &str, _bytes: Vec<u8>, _content_type: &str) -> Result<String, StorageError> {
+            Ok(format!("memory://bucket/{}", key))
+        }
+
+        async fn get(&self, url: &str) -> Result<Vec<u8>, StorageError> {
+            Err(StorageError::NotFound(url.to_string()))
+        }
+
+        async fn delete(&self, url: &str) -> Result<(), StorageError> {
+            self.deleted.lock().unwrap().push(url.to_string());
+            if self.failing_url.as_deref() == Some(url) {
+                return Err(StorageError::NotFound(url.to_string()));
+            }
+            Ok(())
+        }
+
+        fn signed_url(&self, url: &str, _expires_in: Duration) -> Result<String, StorageError> {
+            Ok(url.to_string())
+        }
+
+        fn owns_url(&self, url: &str) -> bool {
+            url.starts_with("memory://bucket")
+        }
+    }
 
-    match (image_count, doc_count) {
-        (0, 0) => "File terlampir".to_string(),
-        (img, 0) if img == 1 => "📷 Gambar".to_string(),
-        (img, 0) => format!("📷 {} gambar", img),
-        (0, doc) if doc == 1 => "📄 Dokumen".to_string(),
-        (0, doc) => format!("📄 {} dokumen", doc),
-        (img, doc) => format!("📷 {} gambar, 📄 {} dokumen", img, doc),
+    fn uploaded_at(url: &str) -> UploadedFile {
+        UploadedFile {
+            filename: "chat-1-a.jpg".to_string(),
+            original_name: Some("a.jpg".to_string()),
+            file_type: "image/jpeg".to_string(),
+            file_size: 3,
+            url: url.to_string(),
+            thumbnail_url: None,
+            delivery_url: None,
+            category: FileCategory::Image,
+        }
+    }
+
+    fn policy(transformation: Option<&str>) -> UploadCategoryPolicy {
+        UploadCategoryPolicy {
+            folder: "chat/images".to_string(),
+            allowed_formats: None,
+            transformation: transformation.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_category_delivery_url() {
+        let cloudinary = StorageBackend::Cloudinary(CloudinaryStorage::new(CloudinaryClient::with_credentials(
+            "bigauto".to_string(),
+            "key".to_string(),
+            "secret".to_string(),
+        )));
+        let url = "https://res.cloudinary.com/bigauto/image/upload/v1/chat/images/a.jpg";
+
+        assert_eq!(
+            category_delivery_url(&cloudinary, &policy(Some("q_auto,f_auto")), url).as_deref(),
+            Some("https://res.cloudinary.com/bigauto/image/upload/q_auto,f_auto/v1/chat/images/a.jpg")
+        );
+        // Tanpa transformasi file asli yang dikirim
+        assert_eq!(category_delivery_url(&cloudinary, &policy(None), url), None);
+
+        // S3 tidak punya transformasi on-the-fly
+        let s3 = StorageBackend::S3(S3Storage::new("http://127.0.0.1:9000", "chat", "us-east-1", "key", "secret", None));
+        assert_eq!(
+            category_delivery_url(&s3, &policy(Some("q_auto")), "http://127.0.0.1:9000/chat/chat/images/a.jpg"),
+            None
+        );
+    }
+
+    fn uploaded(category: FileCategory) -> UploadedFile {
+        UploadedFile {
+            filename: "chat-1-a".to_string(),
+            original_name: None,
+            file_type: String::new(),
+            file_size: 1,
+            url: String::new(),
+            thumbnail_url: None,
+            delivery_url: None,
+            category,
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_audio_upload_category_and_preview() {
+        assert!(matches!(validate_file_type("audio/mpeg"), Ok(FileCategory::Audio)));
+        assert!(validate_file_type("audio/x-unknown").is_err());
+
+        assert_eq!(generate_preview_text(&[uploaded(FileCategory::Audio)]), "🎤 Audio");
+        assert_eq!(
+            generate_preview_text(&[uploaded(FileCategory::Audio), uploaded(FileCategory::Audio)]),
+            "🎤 2 audio"
+        );
+        assert_eq!(
+            generate_preview_text(&[uploaded(FileCategory::Image), uploaded(FileCategory::Audio)]),
+            "📷 1 gambar, 🎤 1 audio"
+        );
+        assert_eq!(
+            generate_preview_text(&[uploaded(FileCategory::Image), uploaded(FileCategory::Document)]),
+            "📷 1 gambar, 📄 1 dokumen"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discard_uploaded_files_deletes_every_uploaded_asset() {
+        let storage = RecordingStorage {
+            failing_url: Some("memory://bucket/chat/images/a.jpg".to_string()),
+            ..RecordingStorage::default()
+        };
+        let files = vec![
+            uploaded_at("memory://bucket/chat/images/a.jpg"),
+            uploaded_at("memory://bucket/chat/images/b.jpg"),
+        ];
+
+        // Delete yang gagal tidak menghentikan pembersihan file lainnya
+        discard_uploaded_files(&storage, &files).await;
+
+        assert_eq!(
+            *storage.deleted.lock().unwrap(),
+            vec![
+                "memory://bucket/chat/images/a.jpg".to_string(),
+                "memory://bucket/chat/images/b.jpg".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discard_uploaded_files_without_uploads_is_noop() {
+        let storage = RecordingStorage::default();
+
+        discard_uploaded_files(&storage, &[]).await;
+
+        assert!(storage.deleted.lock().unwrap().is_empty());
+    }
+}
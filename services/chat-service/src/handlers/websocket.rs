@@ -4,21 +4,31 @@ use axum::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         State, Path,
     },
-    response::Response,
+    response::{Json, Response},
 };
+use axum::extract::ws::close_code;
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::{RwLock, Mutex};
+use tokio::sync::{Mutex, Notify, RwLock};
+use tokio::task::JoinSet;
 use uuid::Uuid;
 use async_nats::Client;
+use shared::auth::TokenType;
 
 use crate::{
     config::AppState,
-    middleware::WebSocketParticipant,
+    middleware::{is_chat_role, AuthAdmin, WebSocketParticipant},
     error::AppError,
     domain::message::TypingIndicator,
+    handlers::messages::proxy_media_list,
+    repositories::conversation_repo::InboxSnapshot,
+    utils::nats_monitor::NatsMonitor,
+    utils::realtime,
+    utils::unread,
+    utils::ws_close,
+    utils::ws_compression::{WsEncoder, WsEncoding},
 };
 
 // WebSocket message types
@@ -31,6 +41,14 @@ pub enum WsMessage {
     Unsubscribe { conversation_id: i32 },
     TypingStart { conversation_id: i32 },
     TypingStop { conversation_id: i32 },
+    // Backfill dari DB, dipakai sebagai polling saat real-time tidak tersedia
+    FetchHistory {
+        conversation_id: i32,
+        #[serde(default)]
+        after_message_id: Option<i32>,
+        #[serde(default)]
+        limit: Option<i64>,
+    },
 
     // Server messages
     Pong,
@@ -56,12 +74,92 @@ pub enum WsMessage {
         user_email: String,
         is_typing: bool,
     },
+    History {
+        conversation_id: i32,
+        messages: serde_json::Value,
+        // false = degraded mode, client perlu fetch_history berkala
+        live: bool,
+    },
+    // Update item inbox (last message & unread milik penerima), dikirim lewat subject user
+    ConversationUpdated {
+        conversation_id: i32,
+        last_message: Option<String>,
+        last_message_sender_id: Option<i32>,
+        last_message_is_mine: bool,
+        last_message_at: Option<chrono::DateTime<chrono::Utc>>,
+        unread_count: i64,
+    },
+    // Conversation di-assign ke staff penerima event, dikirim lewat subject user
+    ConversationAssigned {
+        conversation_id: i32,
+        assigned_by: i32,
+    },
     Error {
         code: String,
         message: String,
     },
 }
 
+// Satu update inbox per participant, unread_count sesuai milik masing-masing.
+// Staff yang di-assign menerima unread sisi seller
+pub fn conversation_updates(conversation_id: i32, snapshot: &InboxSnapshot) -> Vec<(i32, WsMessage)> {
+    let update = |user_id: i32, unread_count: i32| {
+        (
+            user_id,
+            WsMessage::ConversationUpdated {
+                conversation_id,
+                last_message: snapshot.last_message.clone(),
+                last_message_sender_id: snapshot.last_message_sender_id,
+                last_message_is_mine: unread::last_message_is_mine(
+                    snapshot.last_message_sender_id,
+                    user_id,
+                    snapshot.customer_id,
+                ),
+                last_message_at: snapshot.last_message_at,
+                unread_count: i64::from(unread_count),
+            },
+        )
+    };
+
+    let mut updates = vec![
+        update(snapshot.customer_id, snapshot.unread.customer),
+        update(snapshot.seller_id, snapshot.unread.seller),
+    ];
+    if let Some(staff_id) = snapshot.assigned_to {
+        updates.push(update(staff_id, snapshot.unread.seller));
+    }
+    updates
+}
+
+// Broadcast conversation_updated ke subject user setiap participant setelah last message/unread berubah.
+// Best-effort: tanpa NATS (atau jika query gagal) dilewati, client tetap bisa refetch inbox
+pub async fn broadcast_conversation_updated(state: &AppState, conversation_id: i32) {
+    if state.nats_client.is_none() {
+        return;
+    }
+
+    let snapshot = match state.conversation_repo.get_inbox_snapshot(conversation_id).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!("Gagal mengambil snapshot inbox conversation {}: {}", conversation_id, e);
+            return;
+        }
+    };
+
+    for (user_id, update) in conversation_updates(conversation_id, &snapshot) {
+        let Ok(payload) = serde_json::to_string(&update) else {
+            continue;
+        };
+        realtime::publish_best_effort(
+            state.nats_client.as_ref(),
+            realtime::user_subject(user_id),
+            payload,
+            "conversation update",
+        ).await;
+    }
+}
+
 // WebSocket connection info
 #[derive(Debug, Clone)]
 pub struct WsConnection {
@@ -70,11 +168,34 @@ pub struct WsConnection {
     pub user_role: String,
     pub conversation_subscriptions: Arc<RwLock<HashMap<i32, bool>>>,
     pub is_alive: Arc<RwLock<bool>>,
+    pub resubscribe: Arc<Notify>,
+    pub evict: Arc<Notify>,
+}
+
+// Subject NATS untuk event ban user, di-subscribe semua instance chat-service
+pub const USER_BANNED_SUBJECT: &str = "chat.control.user_banned";
+
+// Payload event ban user yang dibroadcast ke semua instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserBannedEvent {
+    pub user_id: i32,
+}
+
+// Response endpoint disconnect user
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DisconnectUserResponse {
+    pub user_id: i32,
+    /// Jumlah koneksi yang ditutup di instance yang menerima request
+    pub local_connections_closed: usize,
+    /// Event ban berhasil dibroadcast ke instance lain via NATS
+    pub broadcasted: bool,
 }
 
 // Active connections manager - Manajer koneksi WebSocket aktif
 pub struct ConnectionManager {
     connections: Arc<RwLock<HashMap<Uuid, Arc<WsConnection>>>>,
+    // Index connection per user untuk teardown terarah (ban)
+    user_connections: Arc<RwLock<HashMap<i32, HashSet<Uuid>>>>,
 }
 
 // Global connection manager instance untuk tracking semua koneksi aktif
@@ -87,27 +208,79 @@ impl ConnectionManager {
     pub fn new() -> Self {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
+            user_connections: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    // Daftarkan koneksi + index per user, return total koneksi
+    async fn insert(&self, connection_id: Uuid, connection: Arc<WsConnection>) -> usize {
+        let user_id = connection.user_id;
+        let mut connections = self.connections.write().await;
+        connections.insert(connection_id, connection);
+        self.user_connections
+            .write()
+            .await
+            .entry(user_id)
+            .or_default()
+            .insert(connection_id);
+        connections.len()
+    }
+
+    // Lepas koneksi + index per user, return total koneksi
+    async fn remove(&self, connection_id: &Uuid) -> usize {
+        let mut connections = self.connections.write().await;
+        if let Some(connection) = connections.remove(connection_id) {
+            let mut user_connections = self.user_connections.write().await;
+            if let Some(ids) = user_connections.get_mut(&connection.user_id) {
+                ids.remove(connection_id);
+                if ids.is_empty() {
+                    user_connections.remove(&connection.user_id);
+                }
+            }
         }
+        connections.len()
+    }
+
+    // Sinyal semua koneksi milik user untuk ditutup, return jumlah koneksi
+    async fn evict_user(&self, user_id: i32) -> usize {
+        let connections = self.connections.read().await;
+        let user_connections = self.user_connections.read().await;
+        let Some(ids) = user_connections.get(&user_id) else {
+            return 0;
+        };
+
+        let mut evicted = 0;
+        for connection in ids.iter().filter_map(|id| connections.get(id)) {
+            // notify_one menyimpan permit, jadi aman walau task belum sampai select
+            connection.evict.notify_one();
+            evicted += 1;
+        }
+        evicted
     }
 
     // Tambahkan koneksi baru ke manager
     pub async fn tambah_koneksi(connection_id: Uuid, connection: Arc<WsConnection>) {
-        let mut manager = CONNECTION_MANAGER.connections.write().await;
-        manager.insert(connection_id, connection.clone());
+        let total = CONNECTION_MANAGER.insert(connection_id, connection).await;
         tracing::info!("Koneksi {} ditambahkan ke manager. Total koneksi: {}",
-                      connection_id, manager.len());
+                      connection_id, total);
     }
 
     // Hapus koneksi dari manager
     pub async fn hapus_koneksi(connection_id: &Uuid) {
-        let mut manager = CONNECTION_MANAGER.connections.write().await;
-        manager.remove(connection_id);
+        let total = CONNECTION_MANAGER.remove(connection_id).await;
         tracing::info!("Koneksi {} dihapus dari manager. Total koneksi: {}",
-                      connection_id, manager.len());
+                      connection_id, total);
+    }
+
+    // Tutup semua koneksi WebSocket user di instance ini (user dibanned)
+    pub async fn putus_koneksi_user(user_id: i32) -> usize {
+        let evicted = CONNECTION_MANAGER.evict_user(user_id).await;
+        if evicted > 0 {
+            tracing::info!("{} koneksi WebSocket user {} diputus karena user dibanned", evicted, user_id);
+        }
+        evicted
     }
 
-  
-  
     // Ambil total jumlah koneksi aktif
     pub async fn total_koneksi() -> usize {
         let manager = CONNECTION_MANAGER.connections.read().await;
@@ -115,17 +288,68 @@ impl ConnectionManager {
     }
 }
 
-// Process NATS messages dan forward ke WebSocket
+// Process NATS messages dan forward ke WebSocket, subscribe ulang setiap NATS reconnect
 async fn process_nats_messages(
-    nats_client: &Client,
+    nats_client: Client,
+    nats_monitor: Arc<NatsMonitor>,
     connection_id: Uuid,
     connection: Arc<WsConnection>,
     tx: Arc<Mutex<futures::stream::SplitSink<WebSocket, Message>>>,
+    encoder: Arc<WsEncoder>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut reconnects = nats_monitor.subscribe_reconnects();
+
+    loop {
+        // JoinSet di-drop setiap putaran sehingga forwarder lama otomatis di-abort
+        let mut forwarders = JoinSet::new();
+        spawn_nats_forwarders(
+            &mut forwarders,
+            &nats_client,
+            &nats_monitor,
+            connection_id,
+            &connection,
+            &tx,
+            &encoder,
+        ).await?;
+
+        tokio::select! {
+            changed = reconnects.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                tracing::info!("Connection {} subscribe ulang conversation aktif setelah NATS reconnect", connection_id);
+            }
+            _ = connection.resubscribe.notified() => {
+                tracing::debug!("Connection {} subscribe ulang karena subscription berubah", connection_id);
+            }
+            _ = async { while forwarders.join_next().await.is_some() {} } => {
+                // Semua forwarder berhenti (WebSocket tertutup)
+                return Ok(());
+            }
+        }
+    }
+}
+
+// Pengirim event conversation: top-level sender_id, atau message.sender_id untuk new_message
+fn event_sender_id(event: &serde_json::Value) -> Option<i64> {
+    event.get("sender_id")
+        .or_else(|| event.get("message").and_then(|message| message.get("sender_id")))
+        .and_then(|id| id.as_i64())
+}
+
+// Subscribe user topic + semua conversation aktif, forward setiap message ke WebSocket
+async fn spawn_nats_forwarders(
+    forwarders: &mut JoinSet<()>,
+    nats_client: &Client,
+    nats_monitor: &Arc<NatsMonitor>,
+    connection_id: Uuid,
+    connection: &Arc<WsConnection>,
+    tx: &Arc<Mutex<futures::stream::SplitSink<WebSocket, Message>>>,
+    encoder: &Arc<WsEncoder>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Subscribe ke user-specific messages
-    let user_sub = nats_client
-        .subscribe(format!("chat.{}", connection.user_id))
-        .await?;
+    let user_subject = realtime::user_subject(connection.user_id);
+    let user_sub = nats_client.subscribe(user_subject.clone()).await?;
 
     tracing::info!("Connection {} subscribed ke NATS user topic", connection_id);
 
@@ -144,19 +368,26 @@ async fn process_nats_messages(
     // Process NATS messages
     let mut user_messages = user_sub;
     let tx_clone = tx.clone();
-
+    let user_encoder = encoder.clone();
+    let user_client = nats_client.clone();
+    let user_monitor = nats_monitor.clone();
     // User-specific messages handler
-    tokio::spawn(async move {
+    forwarders.spawn(async move {
         while let Some(nats_msg) = user_messages.next().await {
-            if let Ok(text) = String::from_utf8(nats_msg.payload.into()) {
-                if let Ok(ws_message) = serde_json::from_str::<serde_json::Value>(&text) {
-                    if let Ok(ws_text) = serde_json::to_string(&ws_message) {
-                        let mut tx_lock = tx_clone.lock().await;
-                        if tx_lock.send(Message::Text(ws_text.into())).await.is_err() {
-                            break;
-                        }
-                    }
-                }
+            let Ok(ws_message) = serde_json::from_slice::<serde_json::Value>(&nats_msg.payload) else {
+                user_monitor.dead_letter(&user_client, &user_subject, "invalid_payload", &nats_msg.payload).await;
+                continue;
+            };
+
+            // Tidak ada filter pengirim: message milik user sendiri (outbox ke subject pengirim) adalah
+            // echo untuk tab/device lain user tersebut; client dedup dengan message.id dari response REST
+            let ws_text = ws_message.to_string();
+
+            let mut tx_lock = tx_clone.lock().await;
+            if tx_lock.send(user_encoder.encode(ws_text)).await.is_err() {
+                drop(tx_lock);
+                user_monitor.dead_letter(&user_client, &user_subject, "websocket_send_failed", &nats_msg.payload).await;
+                break;
             }
         }
     });
@@ -165,49 +396,52 @@ async fn process_nats_messages(
     for (conv_id, sub) in conversation_subs {
         let tx_conv = tx.clone();
         let connection = connection.clone();
+        let encoder = encoder.clone();
+        let nats_client = nats_client.clone();
+        let nats_monitor = nats_monitor.clone();
+        let subject = format!("chat.{}", conv_id);
 
         // Log subscription untuk debugging
         tracing::debug!("Connection {} subscribed ke conversation {} via NATS", connection_id, conv_id);
 
-        tokio::spawn(async move {
+        forwarders.spawn(async move {
             let mut conv_messages = sub;
             while let Some(nats_msg) = conv_messages.next().await {
-                if let Ok(text) = String::from_utf8(nats_msg.payload.into()) {
-                    // Check jika ini adalah TypingIndicator
-                    if let Ok(typing_indicator) = serde_json::from_str::<crate::domain::message::TypingIndicator>(&text) {
-                        // Filter typing indicator yang tidak dari user ini sendiri
-                        if typing_indicator.user_id == connection.user_id {
-                            continue;
-                        }
-
-                        // Convert ke WebSocket message dan kirim
-                        let ws_message = typing_indicator.to_websocket_message();
-                        if let Ok(ws_text) = serde_json::to_string(&ws_message) {
-                            let mut tx_lock = tx_conv.lock().await;
-                            if tx_lock.send(Message::Text(ws_text.into())).await.is_err() {
-                                break;
-                            }
-
-                            tracing::debug!("Typing indicator sent to connection {} for conversation {}",
-                                           connection_id, conv_id);
-                        }
-                    } else if let Ok(ws_message) = serde_json::from_str::<serde_json::Value>(&text) {
-                        // Filter messages yang tidak dari user ini sendiri
-                        if let Some(sender_id) = ws_message.get("sender_id") {
-                            if let Some(sender_num) = sender_id.as_i64() {
-                                if sender_num == connection.user_id as i64 {
-                                    continue;
-                                }
-                            }
-                        }
+                let Ok(text) = std::str::from_utf8(&nats_msg.payload) else {
+                    nats_monitor.dead_letter(&nats_client, &subject, "invalid_payload", &nats_msg.payload).await;
+                    continue;
+                };
+
+                // Check jika ini adalah TypingIndicator
+                let ws_text = if let Ok(typing_indicator) = serde_json::from_str::<crate::domain::message::TypingIndicator>(text) {
+                    // Filter typing indicator yang tidak dari user ini sendiri
+                    if typing_indicator.user_id == connection.user_id {
+                        continue;
+                    }
 
-                        if let Ok(ws_text) = serde_json::to_string(&ws_message) {
-                            let mut tx_lock = tx_conv.lock().await;
-                            if tx_lock.send(Message::Text(ws_text.into())).await.is_err() {
-                                break;
-                            }
-                        }
+                    // Convert ke WebSocket message
+                    let ws_message = typing_indicator.to_websocket_message();
+                    match serde_json::to_string(&ws_message) {
+                        Ok(ws_text) => ws_text,
+                        Err(_) => continue,
+                    }
+                } else if let Ok(ws_message) = serde_json::from_str::<serde_json::Value>(text) {
+                    // Event milik user ini sendiri dilewati: echo ke tab/device lain sudah lewat subject user
+                    if event_sender_id(&ws_message) == Some(i64::from(connection.user_id)) {
+                        continue;
                     }
+
+                    ws_message.to_string()
+                } else {
+                    nats_monitor.dead_letter(&nats_client, &subject, "invalid_payload", &nats_msg.payload).await;
+                    continue;
+                };
+
+                let mut tx_lock = tx_conv.lock().await;
+                if tx_lock.send(encoder.encode(ws_text)).await.is_err() {
+                    drop(tx_lock);
+                    nats_monitor.dead_letter(&nats_client, &subject, "websocket_send_failed", &nats_msg.payload).await;
+                    break;
                 }
             }
         });
@@ -235,12 +469,10 @@ async fn validate_websocket_token(
     token: &str,
     state: &AppState,
 ) -> Result<WebSocketParticipant, AppError> {
-    let claims = crate::utils::jwt::validate_token_with_blacklist(token, &state.db)
-        .await
-        .map_err(|_| AppError::unauthorized("Token tidak valid, expired, atau di-blacklist"))?;
+    let claims = shared::auth::validate_token(token, &state.config.jwt, &state.db, TokenType::Access).await?;
 
     // Validasi role untuk chat service 
-    if !claims.is_customer() && !claims.is_seller() {
+    if !is_chat_role(&claims.role) {
         return Err(AppError::forbidden("Hanya customer dan seller yang bisa akses chat"));
     }
 
@@ -287,7 +519,7 @@ pub async fn websocket_handler(
     let participant = validate_websocket_token(&token, &state).await?;
 
     // Validate participant role
-    if !participant.role.contains("customer") && !participant.role.contains("seller") {
+    if !is_chat_role(&participant.role) {
         return Err(AppError::forbidden("Invalid role for chat access"));
     }
 
@@ -305,6 +537,9 @@ pub async fn websocket_handler(
         return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
     }
 
+    // Client opt-in kompresi payload lewat ?compression=deflate
+    let encoding = WsEncoding::from_query(uri.query());
+
     // Cek connection limiter
     state.ws_limiter.add_connection(participant.user_id).await;
     tracing::info!("WebSocket connection dimulai untuk user {} ({}) ke conversation {}",
@@ -316,17 +551,75 @@ pub async fn websocket_handler(
         participant,
         state,
         conversation_id,
+        encoding,
     )))
 }
 
+// Putus semua koneksi WebSocket user yang dibanned di seluruh instance chat-service
+#[utoipa::path(
+    post,
+    path = "/ws/disconnect-user/{user_id}",
+    tag = "websocket",
+    security(("bearer_auth" = [])),
+    params(
+        ("user_id" = i32, Path, description = "ID user yang dibanned")
+    ),
+    responses(
+        (status = 200, description = "Koneksi user diputus", body = DisconnectUserResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn disconnect_user(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(user_id): Path<i32>,
+) -> Result<Json<DisconnectUserResponse>, AppError> {
+    // Tutup langsung di instance ini, tidak menunggu round-trip NATS
+    let local_connections_closed = ConnectionManager::putus_koneksi_user(user_id).await;
+
+    // Broadcast ke instance lain; instance ini akan menerima event yang sama tanpa efek tambahan
+    let broadcasted = match &state.nats_client {
+        Some(nats_client) => {
+            let payload = serde_json::to_vec(&UserBannedEvent { user_id })
+                .map_err(|e| AppError::internal(format!("Gagal serialize event ban: {}", e)))?;
+            nats_client
+                .publish(USER_BANNED_SUBJECT, payload.into())
+                .await
+                .map_err(|e| AppError::nats(format!("Gagal broadcast event ban: {}", e)))?;
+            true
+        }
+        None => {
+            tracing::warn!("NATS tidak tersedia, ban user {} hanya diterapkan di instance ini", user_id);
+            false
+        }
+    };
+
+    tracing::info!("Admin {} memutus koneksi WebSocket user {} ({} koneksi lokal)",
+                   admin.user_id, user_id, local_connections_closed);
+
+    Ok(Json(DisconnectUserResponse {
+        user_id,
+        local_connections_closed,
+        broadcasted,
+    }))
+}
+
 // Handle WebSocket communication
 async fn handle_websocket_socket(
     socket: WebSocket,
     participant: WebSocketParticipant,
     state: AppState,
     conversation_id: i32,
+    encoding: WsEncoding,
 ) {
     let connection_id = Uuid::new_v4();
+    let encoder = Arc::new(WsEncoder::new(
+        encoding,
+        state.config.ws_compression_threshold_bytes,
+        state.config.ws_compression_debug,
+    ));
 
     // Buat connection info dengan data lengkap
     let participant_role = participant.role.clone();
@@ -336,6 +629,8 @@ async fn handle_websocket_socket(
         user_role: participant_role,
         conversation_subscriptions: Arc::new(RwLock::new(HashMap::new())),
         is_alive: Arc::new(RwLock::new(true)),
+        resubscribe: Arc::new(Notify::new()),
+        evict: Arc::new(Notify::new()),
     });
 
     // Subscribe ke conversation ini secara otomatis
@@ -347,8 +642,9 @@ async fn handle_websocket_socket(
     // Tambahkan koneksi ke ConnectionManager untuk tracking real-time
     ConnectionManager::tambah_koneksi(connection_id, connection.clone()).await;
 
-    tracing::info!("WebSocket koneksi {} dibuat untuk user {} ({}) dengan role {}",
-                  connection_id, participant.user_id, participant.email, connection.user_role);
+    tracing::info!("WebSocket koneksi {} dibuat untuk user {} ({}) dengan role {}, encoding {:?}",
+                  connection_id, participant.user_id, participant.email, connection.user_role,
+                  encoder.encoding());
 
     // Split WebSocket ke sender dan receiver
     let (sender, mut receiver) = socket.split();
@@ -359,13 +655,28 @@ async fn handle_websocket_socket(
     let tx_incoming = tx.clone();
     let conn_clone = connection.clone();
     let nats_client = state.nats_client.clone();
+    let nats_monitor = state.nats_monitor.clone();
     let state_clone = state.clone();
     let participant_clone = participant.clone();
 
+    // Degraded mode: tanpa push NATS, client langsung menerima backfill dari DB
+    let initial_history = if realtime::is_live(realtime::nats_status(nats_client.as_ref())) {
+        None
+    } else {
+        match load_history(&state, conversation_id, None, None).await {
+            Ok(history) => Some(history),
+            Err(e) => {
+                tracing::warn!("Gagal memuat backfill untuk connection {}: {}", connection_id, e);
+                None
+            }
+        }
+    };
+
     // Handle outgoing messages (server ke client)
     let outgoing_task = {
         let connection_id = connection_id;
         let connection = conn_clone.clone();
+        let encoder = encoder.clone();
 
         tokio::spawn(async move {
             // Send initial subscription confirmation
@@ -376,20 +687,41 @@ async fn handle_websocket_socket(
                 let _ = tx_lock.send(Message::Text(subscribed_msg.into())).await;
             }
 
-            // Setup NATS subscription jika available
-            if let Some(nats_client) = &nats_client {
-                if let Err(e) = process_nats_messages(
-                    nats_client,
-                    connection_id,
-                    connection.clone(),
-                    tx_outgoing.clone(),
-                ).await {
-                    tracing::error!("NATS subscription setup failed: {}", e);
+            if let Some(history) = initial_history {
+                if let Ok(history_msg) = serde_json::to_string(&history) {
+                    let mut tx_lock = tx_outgoing.lock().await;
+                    let _ = tx_lock.send(encoder.encode(history_msg)).await;
                 }
-            } else {
-                tracing::warn!("NATS client tidak tersedia, real-time features terbatas");
             }
 
+            // Setup NATS subscription jika available
+            let nats_task = if let Some(nats_client) = nats_client {
+                let nats_monitor = nats_monitor.clone();
+                let connection = connection.clone();
+                let tx_nats = tx_outgoing.clone();
+                Some(tokio::spawn(async move {
+                    if let Err(e) = process_nats_messages(
+                        nats_client,
+                        nats_monitor,
+                        connection_id,
+                        connection,
+                        tx_nats.clone(),
+                        encoder,
+                    ).await {
+                        tracing::error!("NATS subscription setup failed: {}", e);
+
+                        // Tutup dengan 1011 agar client reconnect, bukan diam tanpa real-time
+                        let mut tx_lock = tx_nats.lock().await;
+                        let _ = tx_lock
+                            .send(ws_close::close_message(close_code::ERROR, "Real-time service unavailable"))
+                            .await;
+                    }
+                }))
+            } else {
+                tracing::warn!("NATS client tidak tersedia, connection {} berjalan di degraded mode (backfill via fetch_history)", connection_id);
+                None
+            };
+
             // Keep connection alive dengan ping/pong
             let mut ping_interval = tokio::time::interval(std::time::Duration::from_secs(30));
 
@@ -402,7 +734,7 @@ async fn handle_websocket_socket(
                             break;
                         }
 
-                        // Send ping
+                        // Ping keepalive selalu Text tanpa kompresi agar client bisa langsung balas
                         let mut tx_lock = tx_outgoing.lock().await;
                         if let Ok(ping_msg) = serde_json::to_string(&WsMessage::Ping) {
                             if tx_lock.send(Message::Text(ping_msg.into())).await.is_err() {
@@ -410,8 +742,21 @@ async fn handle_websocket_socket(
                             }
                         }
                     }
+                    _ = connection.evict.notified() => {
+                        // User dibanned: tutup dengan 1008 agar client tidak auto-reconnect
+                        let mut tx_lock = tx_outgoing.lock().await;
+                        let _ = tx_lock
+                            .send(ws_close::close_message(close_code::POLICY, "Akun dinonaktifkan"))
+                            .await;
+                        break;
+                    }
                 }
             }
+
+            // Hentikan forwarder NATS setelah koneksi selesai
+            if let Some(nats_task) = nats_task {
+                nats_task.abort();
+            }
         })
     };
 
@@ -420,27 +765,44 @@ async fn handle_websocket_socket(
         let state = state_clone;
         let connection = conn_clone;
         let participant = participant_clone;
+        let encoder = encoder.clone();
 
         tokio::spawn(async move {
             while let Some(msg_result) = receiver.next().await {
                 match msg_result {
                     Ok(Message::Text(text)) => {
-                        if let Err(e) = handle_text_message(
+                        match handle_text_message(
                             &text,
                             &connection,
                             &participant,
                             &state,
                             connection_id,
                         ).await {
-                            tracing::error!("Error handling message from connection {}: {}", connection_id, e);
-
-                            // Send error response
-                            if let Ok(error_msg) = serde_json::to_string(&WsMessage::Error {
-                                code: "MESSAGE_ERROR".to_string(),
-                                message: e.to_string(),
-                            }) {
-                                let mut tx_lock = tx_incoming.lock().await;
-                                let _ = tx_lock.send(Message::Text(error_msg.into())).await;
+                            Ok(Some(reply)) => {
+                                if let Ok(reply_msg) = serde_json::to_string(&reply) {
+                                    let mut tx_lock = tx_incoming.lock().await;
+                                    let _ = tx_lock.send(encoder.encode(reply_msg)).await;
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(e) => {
+                                tracing::error!("Error handling message from connection {}: {}", connection_id, e);
+
+                                // Error fatal (protocol/internal): kirim close frame lalu putus koneksi
+                                if let Some(code) = e.ws_close_code() {
+                                    let mut tx_lock = tx_incoming.lock().await;
+                                    let _ = tx_lock.send(ws_close::close_message(code, &e.to_string())).await;
+                                    break;
+                                }
+
+                                // Send error response
+                                if let Ok(error_msg) = serde_json::to_string(&WsMessage::Error {
+                                    code: "MESSAGE_ERROR".to_string(),
+                                    message: e.to_string(),
+                                }) {
+                                    let mut tx_lock = tx_incoming.lock().await;
+                                    let _ = tx_lock.send(Message::Text(error_msg.into())).await;
+                                }
                             }
                         }
                     }
@@ -459,6 +821,10 @@ async fn handle_websocket_socket(
                     }
                     Err(e) => {
                         tracing::error!("WebSocket error untuk connection {}: {}", connection_id, e);
+
+                        // Best effort: socket mungkin sudah putus
+                        let mut tx_lock = tx_incoming.lock().await;
+                        let _ = tx_lock.send(ws_close::close_message(close_code::PROTOCOL, "WebSocket protocol error")).await;
                         break;
                     }
                     _ => {} // Ignore other message types
@@ -468,11 +834,14 @@ async fn handle_websocket_socket(
     };
 
     // Tunggu salah satu task selesai
+    let mut incoming_task = incoming_task;
     tokio::select! {
         _ = outgoing_task => {
             tracing::info!("Outgoing task selesai untuk connection {}", connection_id);
+            // Koneksi yang diputus server tidak boleh terus memproses pesan client
+            incoming_task.abort();
         }
-        _ = incoming_task => {
+        _ = &mut incoming_task => {
             tracing::info!("Incoming task selesai untuk connection {}", connection_id);
         }
     }
@@ -483,6 +852,9 @@ async fn handle_websocket_socket(
     // Hapus koneksi dari ConnectionManager untuk tracking real-time
     ConnectionManager::hapus_koneksi(&connection_id).await;
 
+    // Log penghematan bandwidth (hanya jika WS_COMPRESSION_DEBUG aktif)
+    encoder.log_savings(connection_id);
+
     tracing::info!("WebSocket koneksi {} ditutup untuk user {} ({}), total koneksi aktif: {}",
                   connection_id, participant.user_id, participant.email,
                   ConnectionManager::total_koneksi().await);
@@ -495,7 +867,7 @@ async fn handle_text_message(
     participant: &WebSocketParticipant,
     state: &AppState,
     connection_id: Uuid,
-) -> Result<(), AppError> {
+) -> Result<Option<WsMessage>, AppError> {
     // Log pesan masuk dengan connection ID untuk debugging
     tracing::debug!("Received WebSocket message from connection {}: {}", connection_id, text);
 
@@ -523,13 +895,8 @@ async fn handle_text_message(
                 subscriptions.insert(conversation_id, true);
             }
 
-            // Subscribe ke NATS untuk conversation baru
-            if let Some(nats_client) = &state.nats_client {
-                let subject = format!("chat.{}", conversation_id);
-                if let Err(e) = nats_client.subscribe(subject).await {
-                    tracing::warn!("Gagal subscribe ke conversation {}: {}", conversation_id, e);
-                }
-            }
+            // Forwarder NATS subscribe ulang dengan conversation baru
+            connection.resubscribe.notify_one();
 
             tracing::info!("Connection {} - User {} ({}) subscribe ke conversation {}",
                            connection_id, participant.user_id, participant.email, conversation_id);
@@ -541,6 +908,7 @@ async fn handle_text_message(
                 let mut subscriptions = connection.conversation_subscriptions.write().await;
                 subscriptions.remove(&conversation_id);
             }
+            connection.resubscribe.notify_one();
 
             tracing::info!("Connection {} - User {} ({}) unsubscribe dari conversation {}",
                            connection_id, participant.user_id, participant.email, conversation_id);
@@ -614,6 +982,20 @@ async fn handle_text_message(
             }
         }
 
+        WsMessage::FetchHistory { conversation_id, after_message_id, limit } => {
+            // Validate participant access
+            let is_participant = state.conversation_repo
+                .is_participant(conversation_id, participant.user_id)
+                .await?;
+
+            if !is_participant {
+                return Err(AppError::forbidden("Tidak memiliki akses ke conversation ini"));
+            }
+
+            let history = load_history(state, conversation_id, after_message_id, limit).await?;
+            return Ok(Some(history));
+        }
+
         WsMessage::Ping => {
             // Handle ping dengan pong
             // Pong handling sudah ada di main loop
@@ -624,5 +1006,159 @@ async fn handle_text_message(
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(None)
+}
+
+// Ambil backfill message dari DB, sumber data yang sama dengan REST
+async fn load_history(
+    state: &AppState,
+    conversation_id: i32,
+    after_message_id: Option<i32>,
+    limit: Option<i64>,
+) -> Result<WsMessage, AppError> {
+    let messages = state.message_repo
+        .get_messages_since(conversation_id, after_message_id, realtime::backfill_limit(limit))
+        .await?;
+
+    let messages = serde_json::to_value(proxy_media_list(state, messages))
+        .map_err(|e| AppError::internal(format!("Gagal serialize backfill: {}", e)))?;
+
+    Ok(WsMessage::History {
+        conversation_id,
+        messages,
+        live: realtime::is_live(realtime::nats_status(state.nats_client.as_ref())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn test_connection(user_id: i32) -> Arc<WsConnection> {
+        Arc::new(WsConnection {
+            user_id,
+            user_email: format!("user{}@example.com", user_id),
+            user_role: "customer".to_string(),
+            conversation_subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            is_alive: Arc::new(RwLock::new(true)),
+            resubscribe: Arc::new(Notify::new()),
+            evict: Arc::new(Notify::new()),
+        })
+    }
+
+    async fn is_evicted(connection: &WsConnection) -> bool {
+        tokio::time::timeout(Duration::from_millis(50), connection.evict.notified())
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_evict_user_only_signals_that_users_connections() {
+        let manager = ConnectionManager::new();
+        let banned_a = test_connection(7);
+        let banned_b = test_connection(7);
+        let other = test_connection(8);
+
+        manager.insert(Uuid::new_v4(), banned_a.clone()).await;
+        manager.insert(Uuid::new_v4(), banned_b.clone()).await;
+        manager.insert(Uuid::new_v4(), other.clone()).await;
+
+        assert_eq!(manager.evict_user(7).await, 2);
+        assert!(is_evicted(&banned_a).await);
+        assert!(is_evicted(&banned_b).await);
+        assert!(!is_evicted(&other).await);
+        assert_eq!(manager.evict_user(99).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_drops_user_index() {
+        let manager = ConnectionManager::new();
+        let connection_id = Uuid::new_v4();
+        manager.insert(connection_id, test_connection(7)).await;
+
+        assert_eq!(manager.remove(&connection_id).await, 0);
+        assert!(manager.user_connections.read().await.is_empty());
+        assert_eq!(manager.evict_user(7).await, 0);
+    }
+
+    #[test]
+    fn test_user_banned_event_roundtrip() {
+        let payload = serde_json::to_vec(&UserBannedEvent { user_id: 42 }).unwrap();
+        let event: UserBannedEvent = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(event.user_id, 42);
+    }
+
+    #[test]
+    fn test_fetch_history_optional_fields() {
+        let msg: WsMessage = serde_json::from_str(r#"{"type":"fetch_history","conversation_id":5}"#).unwrap();
+        assert!(matches!(msg, WsMessage::FetchHistory { conversation_id: 5, after_message_id: None, limit: None }));
+
+        let msg: WsMessage = serde_json::from_str(
+            r#"{"type":"fetch_history","conversation_id":5,"after_message_id":10,"limit":20}"#,
+        ).unwrap();
+        assert!(matches!(msg, WsMessage::FetchHistory { after_message_id: Some(10), limit: Some(20), .. }));
+    }
+
+    #[test]
+    fn test_history_reports_degraded_mode() {
+        let history = WsMessage::History {
+            conversation_id: 5,
+            messages: serde_json::json!([]),
+            live: realtime::is_live(realtime::nats_status(None)),
+        };
+        let json: serde_json::Value = serde_json::to_value(&history).unwrap();
+        assert_eq!(json["type"], "history");
+        assert_eq!(json["live"], false);
+    }
+
+    #[test]
+    fn test_conversation_updates_carry_own_unread_count() {
+        let snapshot = InboxSnapshot {
+            customer_id: 7,
+            seller_id: 8,
+            assigned_to: Some(9),
+            last_message: Some("Masih tersedia?".to_string()),
+            last_message_sender_id: Some(7),
+            last_message_at: Some(chrono::Utc::now()),
+            unread: crate::utils::unread::UnreadCounters { customer: 0, seller: 3 },
+        };
+
+        let updates = conversation_updates(5, &snapshot);
+        let json: Vec<(i32, serde_json::Value)> = updates
+            .iter()
+            .map(|(user_id, update)| (*user_id, serde_json::to_value(update).unwrap()))
+            .collect();
+
+        assert_eq!(json[0].0, 7);
+        assert_eq!(json[0].1["type"], "conversation_updated");
+        assert_eq!(json[0].1["unread_count"], 0);
+        assert_eq!(json[1].0, 8);
+        assert_eq!(json[1].1["unread_count"], 3);
+        assert_eq!(json[1].1["last_message"], "Masih tersedia?");
+        assert_eq!(json[2].0, 9);
+        assert_eq!(json[2].1["unread_count"], 3);
+
+        // Message terakhir dari customer: milik customer, bukan milik seller maupun staff
+        assert_eq!(json[0].1["last_message_sender_id"], 7);
+        assert_eq!(json[0].1["last_message_is_mine"], true);
+        assert_eq!(json[1].1["last_message_sender_id"], 7);
+        assert_eq!(json[1].1["last_message_is_mine"], false);
+        assert_eq!(json[2].1["last_message_is_mine"], false);
+    }
+
+    #[test]
+    fn test_event_sender_id_reads_nested_new_message() {
+        let new_message = serde_json::json!({
+            "type": "new_message",
+            "conversation_id": 5,
+            "message": { "id": 10, "sender_id": 7 }
+        });
+        let read_event = serde_json::json!({ "type": "messages_read", "sender_id": 8 });
+        let update = serde_json::json!({ "type": "conversation_updated", "last_message_sender_id": 7 });
+
+        assert_eq!(event_sender_id(&new_message), Some(7));
+        assert_eq!(event_sender_id(&read_event), Some(8));
+        assert_eq!(event_sender_id(&update), None);
+    }
+}
@@ -1,4 +1,5 @@
 // Main Entry Point untuk Chat Service
+use shared::utils::{bind_addr, logging, request_id, startup_gate::StartupGate};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
@@ -8,6 +9,7 @@ mod handlers;
 mod middleware;
 mod repositories;
 mod routes;
+mod scheduler;
 mod utils;
 
 #[tokio::main]
@@ -21,7 +23,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "chat_service=debug,tower_http=debug,async_nats=info".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(logging::fmt_layer())
         .init();
 
     tracing::info!("💬 Starting Big Auto - Chat Service");
@@ -30,6 +32,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("🔌 Initializing application state...");
     let config = config::AppConfig::from_env()
         .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    // Setup server address (CHAT_SERVICE_HOST/CHAT_SERVICE_PORT, sudah divalidasi di config)
+    let addr = bind_addr::resolve("CHAT_SERVICE_HOST", &config.server_host, config.server_port)?;
+
+    // Graceful shutdown setup
+    let shutdown_signal = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Expect ctrl-c signal");
+        tracing::info!("🛑 Received shutdown signal");
+    };
+
+    // Listener dibind sebelum koneksi database/Redis/NATS, request dijawab 503 sampai state siap
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("🌐 Server bound to {}", addr);
+
+    let gate = StartupGate::new(&["/health", "/health/ready"]);
+    let server = tokio::spawn({
+        let router = gate.router();
+        async move {
+            // ConnectInfo dibutuhkan audit log upload/malware untuk mencatat IP client
+            axum::serve(listener, router.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .with_graceful_shutdown(shutdown_signal)
+                .await
+        }
+    });
+
     let state = config::AppState::new(config).await
         .map_err(|e| format!("Failed to initialize app state: {}", e))?;
     tracing::info!("✅ Application state initialized");
@@ -72,14 +101,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if state.config.is_production() {
         tracing::warn!("🚨 Running in PRODUCTION mode - all security features enabled");
     } else {
-        tracing::info!("🧪 Running in DEVELOPMENT mode - relaxed validation");
+        tracing::info!("🧪 Running in DEVELOPMENT mode - relaxed validation (control chars, foreign attachment URLs, upload extensions)");
     }
 
-    // Build application dengan semua layers
-    let app = routes::create_router(state.clone());
+    // Start background scheduler (SLA respon seller)
+    scheduler::ChatScheduler::new(state.clone()).start();
+
+    // Start outbox relay untuk broadcast real-time via NATS
+    scheduler::OutboxRelay::new(state.clone()).start();
 
-    // Setup server address
-    let addr = format!("0.0.0.0:{}", state.config.port());
+    // Start listener ban user agar semua instance memutus WebSocket user yang dibanned
+    scheduler::UserBanListener::new(state.clone()).start();
+
+    // Build application dengan semua layers
+    let app = request_id::apply(routes::create_router(state.clone()));
 
     tracing::info!("🎯 Chat Service listening on {}", addr);
     tracing::info!("📚 API Documentation:");
@@ -90,28 +125,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("🚀 Chat Service Features:");
     tracing::info!("   ✅ Real-time messaging (WebSocket)");
-    tracing::info!("   ✅ File & media upload (Cloudinary)");
+    tracing::info!("   ✅ File & media upload (Cloudinary / S3)");
     tracing::info!("   ✅ Message search & filtering");
     tracing::info!("   ✅ Typing indicators & read receipts");
     tracing::info!("   ✅ JWT-Only authentication");
     tracing::info!("   ✅ Redis-based rate limiting");
     tracing::info!("   ✅ Security headers");
 
-    // Graceful shutdown setup
-    let shutdown_signal = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Expect ctrl-c signal");
-        tracing::info!("🛑 Received shutdown signal");
-    };
+    // Listener /metrics terpisah, mis. hanya interface internal untuk Prometheus
+    if let Some(metrics_addr) = state.config.metrics_addr {
+        let metrics_listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+        let metrics_app = routes::create_metrics_router(state.clone());
+        tracing::info!("📈 Metrics bound to {}", metrics_addr);
 
-    // Start server dengan graceful shutdown
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    tracing::info!("🌐 Server bound to {}", addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(metrics_listener, metrics_app).await {
+                tracing::error!("Metrics listener berhenti: {}", e);
+            }
+        });
+    }
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal)
-        .await?;
+    // Router asli mulai melayani request
+    gate.ready(app);
+    server.await??;
 
     tracing::info!("👋 Chat Service shutdown complete");
 
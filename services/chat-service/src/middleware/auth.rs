@@ -2,65 +2,20 @@
 
 use axum::{
     extract::{Request, State, FromRequestParts},
-    http::HeaderMap,
     response::Response,
     middleware::Next,
     http::request::Parts,
-    RequestPartsExt,
-};
-use axum_extra::{
-    headers::{authorization::Bearer, Authorization},
-    TypedHeader,
 };
+use shared::auth::{authenticate, AdminState, AuthState, Role};
 use sqlx::PgPool;
 
-use crate::{config::AppState, error::AppError, utils::jwt};
+use crate::{config::AppState, error::AppError};
 
-// Authenticated user structure
-#[derive(Debug, Clone)]
-pub struct AuthUser {
-    pub user_id: i32,
-    pub email: String,
-    pub role: String,
-}
+// AuthUser diinject jwt_auth_middleware, validasi token ada di shared::auth
+pub use shared::auth::AuthUser;
 
-// Axum extractor implementation untuk AuthUser
-impl<S> FromRequestParts<S> for AuthUser
-where
-    S: Send + Sync,
-{
+impl AuthState for AppState {
     type Rejection = AppError;
-
-    async fn from_request_parts(
-        parts: &mut Parts,
-        _state: &S,
-    ) -> Result<Self, Self::Rejection> {
-        let TypedHeader(Authorization(bearer)) = parts
-            .extract::<TypedHeader<Authorization<Bearer>>>()
-            .await
-            .map_err(|_| AppError::unauthorized("Authorization header dengan Bearer token diperlukan"))?;
-
-        let claims = jwt::validate_token(bearer.token())
-            .map_err(|_| AppError::unauthorized("Token tidak valid atau sudah expired"))?;
-
-        // Validasi role untuk chat service (customer/seller)
-        if !claims.is_customer() && !claims.is_seller() {
-            return Err(AppError::forbidden("Hanya customer dan seller yang bisa akses chat"));
-        }
-
-        tracing::debug!(
-            "User authenticated - ID: {}, Email: {}, Role: {}",
-            claims.sub,
-            claims.email,
-            claims.role
-        );
-
-        Ok(AuthUser {
-            user_id: claims.sub,
-            email: claims.email,
-            role: claims.role,
-        })
-    }
 }
 
 // Chat participant dengan status check 
@@ -72,15 +27,12 @@ pub struct ChatParticipant {
     pub is_active: bool,
 }
 
-impl<S> FromRequestParts<S> for ChatParticipant
-where
-    S: Send + Sync,
-{
+impl FromRequestParts<AppState> for ChatParticipant {
     type Rejection = AppError;
 
     async fn from_request_parts(
         parts: &mut Parts,
-        _state: &S,
+        _state: &AppState,
     ) -> Result<Self, Self::Rejection> {
         let user = AuthUser::from_request_parts(parts, _state).await?;
 
@@ -127,15 +79,12 @@ pub struct WebSocketParticipant {
     pub is_active: bool,
 }
 
-impl<S> FromRequestParts<S> for WebSocketParticipant
-where
-    S: Send + Sync,
-{
+impl FromRequestParts<AppState> for WebSocketParticipant {
     type Rejection = AppError;
 
     async fn from_request_parts(
         parts: &mut Parts,
-        _state: &S,
+        _state: &AppState,
     ) -> Result<Self, Self::Rejection> {
         let user = AuthUser::from_request_parts(parts, _state).await?;
 
@@ -173,19 +122,15 @@ where
     }
 }
 
-// Extract Bearer token dari Authorization header
-fn extract_jwt_token(headers: &HeaderMap) -> Result<String, AppError> {
-    let auth_header = headers
-        .get("authorization")
-        .ok_or_else(|| AppError::unauthorized("Authorization header dengan Bearer token diperlukan"))?
-        .to_str()
-        .map_err(|_| AppError::unauthorized("Invalid Authorization header format"))?;
+// Admin platform terautentikasi (moderasi koneksi chat), flag is_admin dicek oleh shared::auth
+pub use shared::auth::AuthAdmin;
 
-    if !auth_header.starts_with("Bearer ") {
-        return Err(AppError::unauthorized("Bearer token format diperlukan"));
-    }
+impl AdminState for AppState {
+    type Rejection = AppError;
 
-    Ok(auth_header[7..].to_string())
+    fn admin_db(&self) -> &PgPool {
+        &self.db
+    }
 }
 
 // JWT authentication middleware dengan blacklist validation (HTTP endpoints)
@@ -200,26 +145,14 @@ pub async fn jwt_auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    // Extract JWT token dari Authorization header
-    let token = extract_jwt_token(request.headers())?;
-
     // Validasi JWT dengan database trust boundary (termasuk blacklist check)
-    let claims = jwt::validate_token_with_blacklist(&token, &state.db)
-        .await
-        .map_err(|_| AppError::unauthorized("Token tidak valid, expired, atau sudah di-blacklist"))?;
+    let auth_user = authenticate(request.headers(), &state.config.jwt, &state.db).await?;
 
     // Validasi role untuk chat service
-    if !claims.can_access_chat() {
-        return Err(AppError::forbidden("Role tidak valid untuk chat service"));
+    if !can_access_chat(&auth_user) {
+        return Err(AppError::forbidden("Hanya customer dan seller yang bisa akses chat"));
     }
 
-    // Prepare user context untuk handlers
-    let auth_user = AuthUser {
-        user_id: claims.sub,
-        email: claims.email.clone(),
-        role: claims.role.clone(),
-    };
-
     // Inject ke request extensions agar bisa di-extract oleh handlers
     request.extensions_mut().insert(auth_user.clone());
 
@@ -235,17 +168,28 @@ pub async fn jwt_auth_middleware(
     Ok(next.run(request).await)
 }
 
-// Helper functions untuk role validation
-impl AuthUser {
-    pub fn is_customer(&self) -> bool {
-        self.role == "customer"
-    }
+// Chat hanya untuk customer dan seller (role claim dicocokkan exact)
+pub fn is_chat_role(role: &str) -> bool {
+    matches!(role.parse::<Role>(), Ok(Role::Customer | Role::Seller))
+}
+
+pub fn can_access_chat(user: &AuthUser) -> bool {
+    is_chat_role(&user.role)
+}
 
-    pub fn can_access_conversation(&self, conversation_customer_id: i32, conversation_seller_id: i32) -> bool {
-        self.user_id == conversation_customer_id || self.user_id == conversation_seller_id
+// Helper akses conversation untuk AuthUser (tipe dari shared::auth)
+pub trait ConversationAccess {
+    fn can_access_conversation(&self, conversation_customer_id: i32, conversation_seller_id: i32, assigned_to: Option<i32>) -> bool;
+    fn get_conversation_role(&self, conversation_customer_id: i32) -> &'static str;
+}
+
+impl ConversationAccess for AuthUser {
+    // Customer, seller, atau staff dealer yang di-assign
+    fn can_access_conversation(&self, conversation_customer_id: i32, conversation_seller_id: i32, assigned_to: Option<i32>) -> bool {
+        crate::utils::assignment::is_visible_to(self.user_id, conversation_customer_id, conversation_seller_id, assigned_to)
     }
 
-    pub fn get_conversation_role(&self, conversation_customer_id: i32) -> &'static str {
+    fn get_conversation_role(&self, conversation_customer_id: i32) -> &'static str {
         if self.user_id == conversation_customer_id {
             "customer"
         } else {
@@ -257,10 +201,53 @@ impl AuthUser {
 // Helper functions untuk participant validation
 impl ChatParticipant {
     pub fn is_customer(&self) -> bool {
-        self.role == "customer"
+        Role::Customer.matches(&self.role)
     }
 
     pub fn is_seller(&self) -> bool {
-        self.role == "seller"
+        Role::Seller.matches(&self.role)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::{header, HeaderMap, StatusCode}, response::IntoResponse};
+    use shared::auth::{decode_token, issue_token, JwtConfig, TokenType};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_blacklisted_token_rejected_with_401() {
+        let config = JwtConfig::new("test-secret-key-for-testing");
+        let token = issue_token(&config, 1, "customer@test.com", "customer", TokenType::Access, 900).unwrap();
+        let jti = decode_token(&token, &config, TokenType::Access).unwrap().jti;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+
+        let err: AppError = authenticate(&headers, &config, &HashSet::from([jti])).await.unwrap_err().into();
+        assert_eq!(err.into_response().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_chat_roles() {
+        let user = |role: &str| AuthUser { user_id: 1, email: "u@test.com".to_string(), role: role.to_string() };
+        assert!(can_access_chat(&user("customer")));
+        assert!(can_access_chat(&user("seller")));
+        assert!(!can_access_chat(&user("admin")));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deceptive_roles_cannot_access_chat() {
+        for role in ["customer_support", "seller_admin", "not_a_customer", "Customer", "seller "] {
+            assert!(!is_chat_role(role), "role {:?} tidak boleh akses chat", role);
+
+            let participant = ChatParticipant {
+                user_id: 1,
+                email: "u@test.com".to_string(),
+                role: role.to_string(),
+                is_active: true,
+            };
+            assert!(!participant.is_customer() && !participant.is_seller());
+        }
+    }
+}
@@ -2,13 +2,13 @@
 
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    response::Response,
     middleware::Next,
 };
-use redis::{Client, AsyncCommands};
+use redis::Client;
 use std::env;
 use std::sync::Arc;
+use shared::utils::rate_limit::{too_many_requests, sliding_window_hit};
 use thiserror::Error;
 
 // Rate limit configuration
@@ -18,6 +18,8 @@ pub struct RateLimitConfig {
     pub customer_per_minute: u32,
     pub seller_per_minute: u32,
     pub chat_ops_per_minute: u32,
+    // Kirim message per user per window, terpisah dari limit umum per endpoint
+    pub message_send_per_minute: u32,
     pub window_seconds: u64,
 }
 
@@ -45,6 +47,10 @@ impl RateLimitConfig {
                 .unwrap_or_else(|_| "60".to_string())
                 .parse()
                 .unwrap_or(60),
+            message_send_per_minute: env::var("RATE_LIMIT_MESSAGE_SEND")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
             window_seconds: window_minutes * 60,
         }
     }
@@ -86,44 +92,38 @@ impl RateLimiter {
         // Determine max requests berdasarkan role dan endpoint
         let max_requests = self.get_max_requests(role, endpoint);
         let window_key = format!("chat_rate_limit:{}:{}:{}", identifier, role, endpoint);
-
-        // Gunakan Redis sorted set untuk sliding window
-        let current_time = chrono::Utc::now().timestamp() as u64;
-        let window_start = current_time.saturating_sub(self.config.window_seconds) + 1;
-
-        // Clean old entries
-        let _: () = conn
-            .zrembyscore(&window_key, "-inf", &(window_start - 1))
+        let hit = sliding_window_hit(&mut conn, &window_key, max_requests, self.config.window_seconds)
             .await
             .map_err(RateLimitError::RedisOperation)?;
 
-        // Get current count
-        let current_count: usize = conn
-            .zcard(&window_key)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
+        Ok(RateLimitResult {
+            allowed: hit.allowed,
+            current_count: hit.current_count,
+            max_requests,
+            remaining: hit.remaining,
+            reset_time: hit.reset_time,
+        })
+    }
 
-        // Add current request
-        let _: () = conn
-            .zadd(&window_key, current_time, current_time)
+    // Limit kirim message per user, dipakai handler kirim message (dengan atau tanpa files)
+    pub async fn check_message_send(&self, user_id: i32) -> Result<RateLimitResult, RateLimitError> {
+        let mut conn = self.redis_client
+            .get_multiplexed_async_connection()
             .await
-            .map_err(RateLimitError::RedisOperation)?;
+            .map_err(RateLimitError::RedisConnection)?;
 
-        // Set expiration untuk cleanup
-        let _: () = conn
-            .expire(&window_key, self.config.window_seconds as i64)
+        let max_requests = self.config.message_send_per_minute;
+        let window_key = format!("chat_rate_limit:message_send:user:{}", user_id);
+        let hit = sliding_window_hit(&mut conn, &window_key, max_requests, self.config.window_seconds)
             .await
             .map_err(RateLimitError::RedisOperation)?;
 
-        let allowed = current_count < max_requests as usize;
-        let remaining = max_requests.saturating_sub(current_count as u32 + 1);
-
         Ok(RateLimitResult {
-            allowed,
-            current_count: current_count as u32 + 1,
+            allowed: hit.allowed,
+            current_count: hit.current_count,
             max_requests,
-            remaining,
-            reset_time: window_start + self.config.window_seconds,
+            remaining: hit.remaining,
+            reset_time: hit.reset_time,
         })
     }
 
@@ -218,17 +218,14 @@ pub async fn rate_limit_middleware(
 
             Ok(response)
         }
-        Ok(_) => {
+        Ok(result) => {
             // Rate limit exceeded
             tracing::warn!("Rate limit exceeded for {} on {}", identifier, endpoint);
-
-            let error_response = axum::Json(serde_json::json!({
+            // Retry-After dihitung dari reset window limiter
+            Ok(too_many_requests(result.reset_time, serde_json::json!({
                 "error": "rate_limit_exceeded",
-                "message": "Terlalu banyak permintaan. Silakan coba lagi dalam beberapa saat.",
-                "retry_after": 60
-            }));
-
-            Ok((StatusCode::TOO_MANY_REQUESTS, error_response).into_response())
+                "message": "Terlalu banyak permintaan. Silakan coba lagi dalam beberapa saat."
+            })))
         }
         Err(e) => {
             // Log error tapi allow request
@@ -0,0 +1,107 @@
+// Repository untuk auto-reply seller
+use crate::utils::auto_reply::{ActiveHours, AutoReplySettings};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+
+#[derive(Clone)]
+pub struct AutoReplyRepository {
+    pool: PgPool,
+}
+
+impl AutoReplyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Pengaturan auto-reply seller, None jika seller belum pernah mengatur
+    pub async fn get_settings(&self, seller_id: i32) -> Result<Option<AutoReplySettings>, sqlx::Error> {
+        let row = sqlx::query!(
+            "SELECT enabled, message, active_start, active_end, timezone
+             FROM seller_auto_replies WHERE seller_id = $1",
+            seller_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|record| {
+            AutoReplySettings::from_stored(
+                record.enabled,
+                record.message,
+                record.active_start,
+                record.active_end,
+                &record.timezone,
+            )
+        }))
+    }
+
+    // Simpan pengaturan auto-reply (sudah divalidasi handler)
+    pub async fn upsert_settings(
+        &self,
+        seller_id: i32,
+        enabled: bool,
+        message: &str,
+        active_hours: Option<ActiveHours>,
+        timezone: &str,
+    ) -> Result<AutoReplySettings, sqlx::Error> {
+        let record = sqlx::query!(
+            "INSERT INTO seller_auto_replies (seller_id, enabled, message, active_start, active_end, timezone)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (seller_id) DO UPDATE
+             SET enabled = EXCLUDED.enabled,
+                 message = EXCLUDED.message,
+                 active_start = EXCLUDED.active_start,
+                 active_end = EXCLUDED.active_end,
+                 timezone = EXCLUDED.timezone,
+                 updated_at = NOW()
+             RETURNING enabled, message, active_start, active_end, timezone",
+            seller_id,
+            enabled,
+            message,
+            active_hours.map(|hours| hours.start),
+            active_hours.map(|hours| hours.end),
+            timezone
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(AutoReplySettings::from_stored(
+            record.enabled,
+            record.message,
+            record.active_start,
+            record.active_end,
+            &record.timezone,
+        ))
+    }
+
+    // Waktu auto-reply terakhir di conversation, dipakai untuk cek cooldown
+    pub async fn last_auto_reply_at(&self, conversation_id: i32) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let last = sqlx::query_scalar!(
+            "SELECT auto_replied_at FROM conversations WHERE id = $1",
+            conversation_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(last.flatten())
+    }
+
+    // Klaim slot auto-reply secara atomic: gagal jika request lain sudah mengirim auto-reply
+    // sejak last_auto_reply_at dibaca, sehingga dua message bersamaan tidak memicu dua auto-reply.
+    // Dijalankan di transaksi yang sama dengan insert auto-reply (lihat MessageRepository::create_auto_reply)
+    pub async fn claim(
+        conn: &mut PgConnection,
+        conversation_id: i32,
+        last_auto_reply_at: Option<DateTime<Utc>>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE conversations SET auto_replied_at = NOW()
+             WHERE id = $1 AND auto_replied_at IS NOT DISTINCT FROM $2",
+            conversation_id,
+            last_auto_reply_at
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,7 +1,29 @@
 // Repository untuk Conversation operations
-use crate::domain::{Conversation, CreateConversationRequest};
+use crate::domain::Conversation;
+use crate::utils::conversation_initiation::{find_or_create, FindOrCreate};
+use crate::utils::unread::{count_reads_by_conversation, Participant, UnreadCounters};
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+
+// Row conversation yang di-lock untuk update counter unread
+pub struct LockedConversation {
+    pub customer_id: i32,
+    pub seller_id: i32,
+    pub assigned_to: Option<i32>,
+    pub unread: UnreadCounters,
+}
+
+// Ringkasan conversation untuk update inbox real-time
+pub struct InboxSnapshot {
+    pub customer_id: i32,
+    pub seller_id: i32,
+    pub assigned_to: Option<i32>,
+    pub last_message: Option<String>,
+    pub last_message_sender_id: Option<i32>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub unread: UnreadCounters,
+}
 
 // Repository untuk conversation database operations
 #[derive(Clone)]
@@ -15,42 +37,45 @@ impl ConversationRepository {
         Self { pool }
     }
 
-    // membuat conversation baru
-    pub async fn create_conversation(
+    // Cari conversation customer-seller-vehicle atau buat baru (conversation tanpa vehicle lewat
+    // uniq_conversations_general). Request bersamaan yang kalah race kena ON CONFLICT dan
+    // membaca ulang conversation milik pemenang
+    pub async fn find_or_create_conversation(
         &self,
         customer_id: i32,
-        request: CreateConversationRequest,
-    ) -> Result<i32, sqlx::Error> {
-        let result = sqlx::query!(
-            r#"
-            INSERT INTO conversations (customer_id, seller_id, vehicle_id)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (customer_id, seller_id, vehicle_id)
-            DO NOTHING
-            RETURNING id
-            "#,
-            customer_id,
-            request.seller_id,
-            request.vehicle_id
-        )
-        .fetch_one(&self.pool)
-        .await;
+        seller_id: i32,
+        vehicle_id: Option<i32>,
+    ) -> Result<Option<FindOrCreate<i32, i32>>, sqlx::Error> {
+        let find_existing = || {
+            sqlx::query_scalar!(
+                r#"
+                SELECT id FROM conversations
+                WHERE customer_id = $1 AND seller_id = $2
+                  AND (vehicle_id = $3 OR ($3::INT IS NULL AND is_general))
+                "#,
+                customer_id,
+                seller_id,
+                vehicle_id
+            )
+            .fetch_optional(&self.pool)
+        };
 
-        match result {
-            Ok(record) => Ok(record.id),
-            Err(sqlx::Error::RowNotFound) => {
-                let existing = sqlx::query_scalar!(
-                    "SELECT id FROM conversations WHERE customer_id = $1 AND seller_id = $2 AND vehicle_id = $3",
-                    customer_id,
-                    request.seller_id,
-                    request.vehicle_id
-                )
-                .fetch_one(&self.pool)
-                .await?;
-                Ok(existing)
-            }
-            Err(e) => Err(e),
-        }
+        let insert_new = || {
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO conversations (customer_id, seller_id, vehicle_id, is_general, created_at, updated_at)
+                VALUES ($1, $2, $3, $3::INT IS NULL, NOW(), NOW())
+                ON CONFLICT DO NOTHING
+                RETURNING id
+                "#,
+                customer_id,
+                seller_id,
+                vehicle_id
+            )
+            .fetch_optional(&self.pool)
+        };
+
+        find_or_create(find_existing, insert_new).await
     }
 
     // Get conversation by ID
@@ -60,8 +85,8 @@ impl ConversationRepository {
         user_id: i32,
     ) -> Result<Option<Conversation>, sqlx::Error> {
         let row = sqlx::query!(
-            "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_at, created_at, updated_at
-             FROM conversations WHERE id = $1 AND (customer_id = $2 OR seller_id = $2)",
+            "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_sender_id, last_message_at, created_at, updated_at
+             FROM conversations WHERE id = $1 AND (customer_id = $2 OR seller_id = $2 OR assigned_to = $2)",
             conversation_id,
             user_id
         )
@@ -75,6 +100,7 @@ impl ConversationRepository {
                 seller_id: record.seller_id,
                 vehicle_id: record.vehicle_id,
                 last_message: record.last_message,
+                last_message_sender_id: record.last_message_sender_id,
                 last_message_at: record.last_message_at,
                 created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
                 updated_at: record.updated_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -91,8 +117,8 @@ impl ConversationRepository {
         offset: i64,
     ) -> Result<Vec<Conversation>, sqlx::Error> {
         let rows = sqlx::query!(
-            "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_at, created_at, updated_at
-             FROM conversations WHERE customer_id = $1 OR seller_id = $1
+            "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_sender_id, last_message_at, created_at, updated_at
+             FROM conversations WHERE customer_id = $1 OR seller_id = $1 OR assigned_to = $1
              ORDER BY updated_at DESC LIMIT $2 OFFSET $3",
             user_id,
             limit,
@@ -107,6 +133,7 @@ impl ConversationRepository {
             seller_id: record.seller_id,
             vehicle_id: record.vehicle_id,
             last_message: record.last_message,
+            last_message_sender_id: record.last_message_sender_id,
             last_message_at: record.last_message_at,
             created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
             updated_at: record.updated_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -120,11 +147,15 @@ impl ConversationRepository {
         &self,
         conversation_id: i32,
         last_message: &str,
+        sender_id: i32,
     ) -> Result<(), sqlx::Error> {
         sqlx::query!(
-            "UPDATE conversations SET last_message = $1, last_message_at = NOW(), updated_at = NOW() WHERE id = $2",
+            "UPDATE conversations
+             SET last_message = $1, last_message_sender_id = $3, last_message_at = NOW(), updated_at = NOW()
+             WHERE id = $2",
             last_message,
-            conversation_id
+            conversation_id,
+            sender_id
         )
         .execute(&self.pool)
         .await?;
@@ -132,6 +163,34 @@ impl ConversationRepository {
         Ok(())
     }
 
+    // Ambil last message + counter unread terbaru untuk broadcast inbox
+    pub async fn get_inbox_snapshot(
+        &self,
+        conversation_id: i32,
+    ) -> Result<Option<InboxSnapshot>, sqlx::Error> {
+        let row = sqlx::query!(
+            "SELECT customer_id, seller_id, assigned_to, last_message, last_message_sender_id, last_message_at,
+                    customer_unread_count, seller_unread_count
+             FROM conversations WHERE id = $1",
+            conversation_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| InboxSnapshot {
+            customer_id: row.customer_id,
+            seller_id: row.seller_id,
+            assigned_to: row.assigned_to,
+            last_message: row.last_message,
+            last_message_sender_id: row.last_message_sender_id,
+            last_message_at: row.last_message_at,
+            unread: UnreadCounters {
+                customer: row.customer_unread_count,
+                seller: row.seller_unread_count,
+            },
+        }))
+    }
+
     // Mengambil percakapan beserta detail (informasi) para pesertanya
     pub async fn get_conversation_with_details(
         &self,
@@ -142,17 +201,16 @@ impl ConversationRepository {
             r#"
             SELECT
                 c.id, c.customer_id, c.seller_id, c.vehicle_id,
-                c.last_message, c.last_message_at, c.created_at, c.updated_at,
+                c.last_message, c.last_message_sender_id, c.last_message_at, c.created_at, c.updated_at,
                 cu.name as customer_name,
                 su.name as seller_name,
-                v.title as vehicle_title,
-                (SELECT COUNT(*) FROM messages m
-                 WHERE m.conversation_id = c.id AND m.sender_id != $2 AND m.is_read = false) as unread_messages
+                v.title as "vehicle_title?",
+                (CASE WHEN c.customer_id = $2 THEN c.customer_unread_count ELSE c.seller_unread_count END)::BIGINT as "unread_messages!"
             FROM conversations c
             JOIN users cu ON c.customer_id = cu.id
             JOIN users su ON c.seller_id = su.id
             LEFT JOIN vehicles v ON c.vehicle_id = v.id
-            WHERE c.id = $1 AND (c.customer_id = $2 OR c.seller_id = $2)
+            WHERE c.id = $1 AND (c.customer_id = $2 OR c.seller_id = $2 OR c.assigned_to = $2)
             "#,
             conversation_id,
             current_user_id
@@ -168,6 +226,7 @@ impl ConversationRepository {
                     seller_id: record.seller_id,
                     vehicle_id: record.vehicle_id,
                     last_message: record.last_message,
+                    last_message_sender_id: record.last_message_sender_id,
                     last_message_at: record.last_message_at,
                     created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
                     updated_at: record.updated_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -177,8 +236,8 @@ impl ConversationRepository {
                     conversation,
                     customer_name: record.customer_name,
                     seller_name: record.seller_name,
-                    vehicle_title: Some(record.vehicle_title),
-                    unread_messages: record.unread_messages.unwrap_or(0),
+                    vehicle_title: record.vehicle_title,
+                    unread_messages: record.unread_messages,
                 };
 
                 Ok(Some(details))
@@ -187,6 +246,30 @@ impl ConversationRepository {
         }
     }
 
+    // Seller punya relasi booking (order/test drive/rental) dengan customer.
+    // Query langsung ke tabel milik booking-service di database bersama, bukan lewat API booking-service;
+    // kolom yang dipakai ikut dicek saat startup (REQUIRED_SCHEMA di config.rs)
+    pub async fn has_booking_relationship(
+        &self,
+        seller_id: i32,
+        customer_id: i32,
+    ) -> Result<bool, sqlx::Error> {
+        let related = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM sale_orders WHERE seller_id = $1 AND buyer_id = $2)
+                OR EXISTS (SELECT 1 FROM testdrive_bookings WHERE seller_id = $1 AND customer_id = $2)
+                OR EXISTS (SELECT 1 FROM rental_bookings WHERE seller_id = $1 AND customer_id = $2)
+                as "related!"
+            "#,
+            seller_id,
+            customer_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(related)
+    }
+
     // Check if user is participant in conversation
     pub async fn is_participant(
         &self,
@@ -194,7 +277,7 @@ impl ConversationRepository {
         user_id: i32,
     ) -> Result<bool, sqlx::Error> {
         let exists = sqlx::query_scalar!(
-            "SELECT EXISTS(SELECT 1 FROM conversations WHERE id = $1 AND (customer_id = $2 OR seller_id = $2))",
+            "SELECT EXISTS(SELECT 1 FROM conversations WHERE id = $1 AND (customer_id = $2 OR seller_id = $2 OR assigned_to = $2))",
             conversation_id,
             user_id
         )
@@ -214,7 +297,7 @@ impl ConversationRepository {
         // Query berdasarkan vehicle_id yang diberikan
         if let Some(vid) = vehicle_id {
             let row = sqlx::query!(
-                "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_at, created_at, updated_at
+                "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_sender_id, last_message_at, created_at, updated_at
                  FROM conversations WHERE customer_id = $1 AND seller_id = $2 AND vehicle_id = $3",
                 customer_id,
                 seller_id,
@@ -230,6 +313,7 @@ impl ConversationRepository {
                     seller_id: record.seller_id,
                     vehicle_id: record.vehicle_id,
                     last_message: record.last_message,
+                    last_message_sender_id: record.last_message_sender_id,
                     last_message_at: record.last_message_at,
                     created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
                     updated_at: record.updated_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -238,7 +322,7 @@ impl ConversationRepository {
             }
         } else {
             let row = sqlx::query!(
-                "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_at, created_at, updated_at
+                "SELECT id, customer_id, seller_id, vehicle_id, last_message, last_message_sender_id, last_message_at, created_at, updated_at
                  FROM conversations WHERE customer_id = $1 AND seller_id = $2 AND vehicle_id IS NULL",
                 customer_id,
                 seller_id
@@ -253,6 +337,7 @@ impl ConversationRepository {
                     seller_id: record.seller_id,
                     vehicle_id: record.vehicle_id,
                     last_message: record.last_message,
+                    last_message_sender_id: record.last_message_sender_id,
                     last_message_at: record.last_message_at,
                     created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
                     updated_at: record.updated_at.unwrap_or_else(|| chrono::Utc::now()),
@@ -262,33 +347,28 @@ impl ConversationRepository {
         }
     }
 
-    // Mark messages as read for user
+    // Mark messages as read for user, counter unread di-reset dalam transaksi yang sama
     pub async fn mark_messages_as_read(
         &self,
         conversation_id: i32,
         user_id: i32,
     ) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query!(
-            "UPDATE messages SET is_read = true, read_at = NOW()
-             WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-            conversation_id,
-            user_id
-        )
-        .execute(&self.pool)
-        .await?;
+        let mut tx = self.pool.begin().await?;
+        let read = Self::mark_read_locked(&mut tx, conversation_id, user_id).await?;
+        tx.commit().await?;
 
-        Ok(result.rows_affected())
+        Ok(read)
     }
 
-    // Get unread message count for user
+    // Get unread message count for user (semua conversation) dari counter denormalized
     pub async fn get_unread_count(
         &self,
         user_id: i32,
     ) -> Result<i64, sqlx::Error> {
         let count = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM messages m
-             JOIN conversations c ON m.conversation_id = c.id
-             WHERE (c.customer_id = $1 OR c.seller_id = $1) AND m.sender_id != $1 AND m.is_read = false",
+            "SELECT COALESCE(SUM(CASE WHEN customer_id = $1 THEN customer_unread_count ELSE seller_unread_count END), 0)::BIGINT
+             FROM conversations
+             WHERE customer_id = $1 OR seller_id = $1 OR assigned_to = $1",
             user_id
         )
         .fetch_one(&self.pool)
@@ -296,6 +376,318 @@ impl ConversationRepository {
 
         Ok(count.unwrap_or(0))
     }
+
+    // Unread satu conversation untuk participant
+    pub async fn get_conversation_unread_count(
+        &self,
+        conversation_id: i32,
+        user_id: i32,
+    ) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar!(
+            "SELECT (CASE WHEN customer_id = $2 THEN customer_unread_count ELSE seller_unread_count END)::BIGINT
+             FROM conversations
+             WHERE id = $1 AND (customer_id = $2 OR seller_id = $2 OR assigned_to = $2)",
+            conversation_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(count.unwrap_or(0))
+    }
+
+    // Lock row conversation sebelum mengubah messages agar counter unread tidak race
+    pub async fn lock_for_unread(
+        conn: &mut PgConnection,
+        conversation_id: i32,
+    ) -> Result<Option<LockedConversation>, sqlx::Error> {
+        let row = sqlx::query!(
+            "SELECT customer_id, seller_id, assigned_to, customer_unread_count, seller_unread_count
+             FROM conversations WHERE id = $1 FOR UPDATE",
+            conversation_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(row.map(|row| LockedConversation {
+            customer_id: row.customer_id,
+            seller_id: row.seller_id,
+            assigned_to: row.assigned_to,
+            unread: UnreadCounters {
+                customer: row.customer_unread_count,
+                seller: row.seller_unread_count,
+            },
+        }))
+    }
+
+    // Simpan counter unread (row harus sudah di-lock lewat lock_for_unread)
+    pub async fn store_unread(
+        conn: &mut PgConnection,
+        conversation_id: i32,
+        unread: UnreadCounters,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE conversations SET customer_unread_count = $2, seller_unread_count = $3 WHERE id = $1",
+            conversation_id,
+            unread.customer,
+            unread.seller
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    // Tandai semua message dari participant lain sebagai dibaca dan update counter reader
+    pub async fn mark_read_locked(
+        conn: &mut PgConnection,
+        conversation_id: i32,
+        user_id: i32,
+    ) -> Result<u64, sqlx::Error> {
+        let Some(locked) = Self::lock_for_unread(conn, conversation_id).await? else {
+            return Ok(0);
+        };
+
+        let Some(reader) = Participant::of(user_id, locked.customer_id, locked.seller_id, locked.assigned_to) else {
+            return Ok(0);
+        };
+
+        // Baca message dari sisi lain: sisi seller (seller/staff) membaca message customer,
+        // customer membaca message seller/staff
+        let read = sqlx::query!(
+            "UPDATE messages SET is_read = true, read_at = NOW()
+             WHERE conversation_id = $1 AND (sender_id = $2) = $3 AND is_read = false AND is_deleted = false",
+            conversation_id,
+            locked.customer_id,
+            reader == Participant::Seller
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        Self::store_unread(conn, conversation_id, locked.unread.after_read(reader, read)).await?;
+
+        Ok(read)
+    }
+
+    // Tandai semua message untuk user sebagai dibaca di maksimal `max_conversations` conversation,
+    // return jumlah dibaca per conversation + apakah masih ada conversation unread tersisa
+    pub async fn mark_all_read(
+        &self,
+        user_id: i32,
+        max_conversations: i64,
+    ) -> Result<(Vec<(i32, u64)>, bool), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock conversation unread milik user dengan urutan tetap (sama seperti lock per conversation)
+        let mut conversation_ids = sqlx::query_scalar!(
+            "SELECT id FROM conversations
+             WHERE (customer_id = $1 AND customer_unread_count > 0)
+                OR ((seller_id = $1 OR assigned_to = $1) AND seller_unread_count > 0)
+             ORDER BY id
+             LIMIT $2
+             FOR UPDATE",
+            user_id,
+            max_conversations + 1
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let has_more = conversation_ids.len() as i64 > max_conversations;
+        conversation_ids.truncate(max_conversations.max(0) as usize);
+
+        if conversation_ids.is_empty() {
+            return Ok((Vec::new(), has_more));
+        }
+
+        let read_in = sqlx::query_scalar!(
+            "UPDATE messages m SET is_read = true, read_at = NOW()
+             FROM conversations c
+             WHERE m.conversation_id = c.id
+               AND c.id = ANY($2)
+               AND (CASE WHEN c.customer_id = $1 THEN m.sender_id != $1 ELSE m.sender_id = c.customer_id END)
+               AND m.is_read = false
+               AND m.is_deleted = false
+             RETURNING m.conversation_id",
+            user_id,
+            &conversation_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Semua message dari participant lain sudah dibaca di bawah lock, counter reader menjadi 0
+        sqlx::query!(
+            "UPDATE conversations
+             SET customer_unread_count = CASE WHEN customer_id = $1 THEN 0 ELSE customer_unread_count END,
+                 seller_unread_count = CASE WHEN seller_id = $1 OR assigned_to = $1 THEN 0 ELSE seller_unread_count END
+             WHERE id = ANY($2)",
+            user_id,
+            &conversation_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok((count_reads_by_conversation(&read_in), has_more))
+    }
+
+    // Conversation yang counter unread-nya tidak sama dengan hitungan message asli
+    pub async fn find_unread_drift(&self) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "SELECT c.id FROM conversations c
+             WHERE c.customer_unread_count != (SELECT COUNT(*) FROM messages m
+                    WHERE m.conversation_id = c.id AND m.sender_id != c.customer_id AND m.is_read = false AND m.is_deleted = false)
+                OR c.seller_unread_count != (SELECT COUNT(*) FROM messages m
+                    WHERE m.conversation_id = c.id AND m.sender_id = c.customer_id AND m.is_read = false AND m.is_deleted = false)"
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Hitung ulang counter unread satu conversation di bawah lock
+    pub async fn reconcile_unread(&self, conversation_id: i32) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(locked) = Self::lock_for_unread(&mut tx, conversation_id).await? else {
+            return Ok(false);
+        };
+
+        let actual = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE sender_id != $2) as "customer!",
+                COUNT(*) FILTER (WHERE sender_id = $2) as "seller!"
+            FROM messages
+            WHERE conversation_id = $1 AND is_read = false AND is_deleted = false
+            "#,
+            conversation_id,
+            locked.customer_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let actual = UnreadCounters {
+            customer: actual.customer as i32,
+            seller: actual.seller as i32,
+        };
+
+        let drifted = actual != locked.unread;
+        if drifted {
+            Self::store_unread(&mut tx, conversation_id, actual).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(drifted)
+    }
+
+    // Set retensi message, hanya participant yang bisa mengubah
+    pub async fn set_retention_days(
+        &self,
+        conversation_id: i32,
+        user_id: i32,
+        retention_days: Option<i32>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE conversations SET retention_days = $3, updated_at = NOW()
+             WHERE id = $1 AND (customer_id = $2 OR seller_id = $2 OR assigned_to = $2)",
+            conversation_id,
+            user_id,
+            retention_days
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Conversation dengan retensi aktif: (conversation_id, retention_days)
+    pub async fn find_retention_policies(&self) -> Result<Vec<(i32, i32)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT id, retention_days as "retention_days!" FROM conversations WHERE retention_days IS NOT NULL"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.retention_days)).collect())
+    }
+
+    // Seller pemilik conversation, untuk cek hak assignment
+    pub async fn get_seller_id(&self, conversation_id: i32) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "SELECT seller_id FROM conversations WHERE id = $1",
+            conversation_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Set staff yang menangani conversation (NULL = kembali ke seller), assignee baru
+    // mendapat notifikasi di transaksi yang sama. Return waktu assignment
+    pub async fn assign(
+        &self,
+        conversation_id: i32,
+        assigned_to: Option<i32>,
+        assigned_by: i32,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let assigned_at = sqlx::query_scalar!(
+            "UPDATE conversations
+             SET assigned_to = $2,
+                 assigned_at = CASE WHEN $2::INTEGER IS NULL THEN NULL ELSE NOW() END,
+                 updated_at = NOW()
+             WHERE id = $1
+             RETURNING assigned_at",
+            conversation_id,
+            assigned_to
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(assignee) = assigned_to.filter(|assignee| *assignee != assigned_by) {
+            sqlx::query!(
+                "INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+                 VALUES ($1, 'conversation_assigned', 'Chat baru untuk Anda',
+                         'Sebuah percakapan pembeli telah di-assign ke Anda', $2, 'conversation')",
+                assignee,
+                conversation_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(assigned_at)
+    }
+
+    // Flag conversation yang belum direspon seller melewati SLA dan notifikasi seller
+    pub async fn flag_sla_breaches(&self, sla_minutes: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "WITH breached AS (
+                UPDATE conversations
+                SET sla_breached_at = NOW()
+                WHERE first_response_at IS NULL
+                  AND sla_breached_at IS NULL
+                  AND last_message_at IS NOT NULL
+                  AND created_at < NOW() - $1::BIGINT * INTERVAL '1 minute'
+                RETURNING id, seller_id
+            )
+            INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+            SELECT seller_id, 'sla_breach', 'Chat menunggu respon',
+                   'Ada pembeli yang belum dibalas lebih dari ' || $1 || ' menit',
+                   id, 'conversation'
+            FROM breached",
+            sla_minutes
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
 
 // Additional struct for conversation with details
@@ -306,4 +698,172 @@ pub struct ConversationWithDetails {
     pub seller_name: String,
     pub vehicle_title: Option<String>,
     pub unread_messages: i64,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn conversation_count(pool: &PgPool) -> i64 {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM conversations")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // Dua request create bersamaan (dengan vehicle dan tanpa vehicle) hanya menghasilkan satu conversation
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_concurrent_creates_yield_one_conversation(pool: PgPool) {
+        let repo = ConversationRepository::new(pool.clone());
+
+        for vehicle_id in [Some(1), None] {
+            for _ in 0..20 {
+                sqlx::query("DELETE FROM conversations").execute(&pool).await.unwrap();
+
+                let (first, second) = tokio::join!(
+                    repo.find_or_create_conversation(1, 2, vehicle_id),
+                    repo.find_or_create_conversation(1, 2, vehicle_id),
+                );
+
+                let mut outcomes = [first.unwrap().unwrap(), second.unwrap().unwrap()];
+                outcomes.sort_by_key(|outcome| matches!(outcome, FindOrCreate::Existing(_)));
+
+                // Satu request membuat, yang lain mendapat conversation yang sama
+                let id = match outcomes[0] {
+                    FindOrCreate::Created(id) => id,
+                    FindOrCreate::Existing(_) => panic!("tidak ada request yang membuat conversation"),
+                };
+                assert_eq!(outcomes[1], FindOrCreate::Existing(id));
+                assert_eq!(conversation_count(&pool).await, 1);
+            }
+        }
+    }
+
+    // Conversation yang vehicle-nya dihapus (is_general = false) tidak dianggap conversation umum
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_orphaned_vehicle_conversation_not_reused_as_general(pool: PgPool) {
+        let repo = ConversationRepository::new(pool.clone());
+
+        sqlx::query("INSERT INTO conversations (customer_id, seller_id, vehicle_id, is_general) VALUES (1, 2, NULL, false)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let id = match repo.find_or_create_conversation(1, 2, None).await.unwrap().unwrap() {
+            FindOrCreate::Created(id) => id,
+            FindOrCreate::Existing(_) => panic!("conversation yatim dipakai sebagai conversation umum"),
+        };
+        assert_eq!(repo.find_or_create_conversation(1, 2, None).await.unwrap().unwrap(), FindOrCreate::Existing(id));
+    }
+
+    // Relasi dibaca dari tabel booking-service di database bersama
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_booking_relationship_from_booking_tables(pool: PgPool) {
+        let repo = ConversationRepository::new(pool.clone());
+        assert!(!repo.has_booking_relationship(2, 1).await.unwrap());
+
+        sqlx::query(
+            "INSERT INTO testdrive_bookings (vehicle_id, customer_id, seller_id, requested_date, requested_time,
+                 customer_name, customer_phone, customer_email)
+             VALUES (2, 1, 2, NOW() + INTERVAL '1 day', '10:00', 'Customer Test', '081234567890', 'customer@test.local')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(repo.has_booking_relationship(2, 1).await.unwrap());
+        assert!(!repo.has_booking_relationship(3, 1).await.unwrap());
+    }
+
+    // Staff yang di-assign punya akses yang sama dengan seller: detail, media, search, retensi,
+    // dan balasannya mengisi first_response_at
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_assigned_staff_has_seller_access(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO users (id, email, password_hash, name, phone, is_seller)
+             VALUES (4, 'staff@test.local', 'x', 'Staff Dealer', '081200000004', false)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO seller_staff (seller_id, staff_user_id) VALUES (2, 4)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let conversation_id: i32 = sqlx::query_scalar(
+            "INSERT INTO conversations (customer_id, seller_id, is_general) VALUES (1, 2, true) RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let repo = ConversationRepository::new(pool.clone());
+        let messages = crate::repositories::MessageRepository::new(pool.clone());
+        assert!(repo.get_conversation_with_details(conversation_id, 4).await.unwrap().is_none());
+
+        repo.assign(conversation_id, Some(4), 2).await.unwrap();
+
+        let photo = crate::domain::CreateMessageRequest {
+            conversation_id,
+            content: "Foto STNK".to_string(),
+            message_type: Some("image".to_string()),
+            media_url: Some("https://example.com/stnk.jpg".to_string()),
+            thumbnail_url: None,
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+        };
+        messages.create_message(conversation_id, 1, "customer@test.local", photo, None, |_| false).await.unwrap();
+
+        assert!(repo.get_conversation_with_details(conversation_id, 4).await.unwrap().is_some());
+        assert_eq!(messages.get_media_messages(conversation_id, 4, 10, 0).await.unwrap().len(), 1);
+        assert_eq!(messages.search_conversation_messages(conversation_id, 4, "STNK", "", 10, 0).await.unwrap().len(), 1);
+        assert!(repo.set_retention_days(conversation_id, 4, Some(30)).await.unwrap());
+
+        let reply = crate::domain::CreateMessageRequest {
+            conversation_id,
+            content: "Sudah kami terima".to_string(),
+            message_type: None,
+            media_url: None,
+            thumbnail_url: None,
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+        };
+        messages.create_message(conversation_id, 4, "staff@test.local", reply, None, |_| false).await.unwrap();
+
+        let responded: bool = sqlx::query_scalar("SELECT first_response_at IS NOT NULL FROM conversations WHERE id = $1")
+            .bind(conversation_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(responded);
+    }
+}
@@ -1,7 +1,21 @@
 // Repository untuk Message operations
-use crate::domain::{Message, MessageType, CreateMessageRequest};
+use crate::domain::{DeletedMessageOriginal, Message, MessageType, CreateMessageRequest, QuotedMessage, ThreadSummary};
+use crate::domain::message::DELETED_MESSAGE_TEXT;
+use crate::repositories::{AutoReplyRepository, ConversationRepository, OutboxRepository};
+use crate::utils::media_proxy::MessageMedia;
+use crate::utils::realtime;
+use crate::utils::unread::Participant;
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+
+// Kolom messages untuk query_as::<_, Message>; kolom nullable dengan default diberi COALESCE
+// agar cocok dengan field non-Option di Message
+pub(crate) const MESSAGE_COLUMNS: &str = "id, conversation_id, sender_id, content, \
+    COALESCE(message_type, 'text') AS message_type, media_url, thumbnail_url, \
+    COALESCE(is_read, false) AS is_read, read_at, is_deleted, deleted_at, \
+    COALESCE(created_at, NOW()) AS created_at, reply_to_message_id, thread_root_id, is_auto_reply";
 
 // Repository untuk message database operations
 #[derive(Clone)]
@@ -15,41 +29,85 @@ impl MessageRepository {
         Self { pool }
     }
 
-    // Create new message
+    // Create new message, event real-time ditulis ke outbox dalam transaksi yang sama.
+    // reply_to adalah preview message yang dibalas, sudah divalidasi handler
     pub async fn create_message(
         &self,
         conversation_id: i32,
         sender_id: i32,
+        sender_email: &str,
+        request: CreateMessageRequest,
+        reply_to: Option<QuotedMessage>,
+        media_is_private: impl Fn(&str) -> bool,
+    ) -> Result<Message, sqlx::Error> {
+        // Validasi content (kosong/panjang) dilakukan di handler sesuai MAX_MESSAGE_LENGTH
+        let mut tx = self.pool.begin().await?;
+        let message = Self::insert_message(&mut tx, conversation_id, sender_id, sender_email, request, reply_to, media_is_private).await?;
+        tx.commit().await?;
+
+        Ok(message)
+    }
+
+    // Kirim auto-reply seller: klaim slot cooldown dan insert message dalam satu transaksi, sehingga
+    // klaim ikut rollback jika insert gagal. None jika request lain sudah mengklaim slot lebih dulu
+    pub async fn create_auto_reply(
+        &self,
+        conversation_id: i32,
+        seller_id: i32,
+        seller_email: &str,
+        request: CreateMessageRequest,
+        last_auto_reply_at: Option<DateTime<Utc>>,
+        media_is_private: impl Fn(&str) -> bool,
+    ) -> Result<Option<Message>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if !AutoReplyRepository::claim(&mut tx, conversation_id, last_auto_reply_at).await? {
+            return Ok(None);
+        }
+
+        let message = Self::insert_message(&mut tx, conversation_id, seller_id, seller_email, request, None, media_is_private).await?;
+        tx.commit().await?;
+
+        Ok(Some(message))
+    }
+
+    // Insert message, counter unread, dan outbox di transaksi milik pemanggil
+    async fn insert_message(
+        conn: &mut PgConnection,
+        conversation_id: i32,
+        sender_id: i32,
+        sender_email: &str,
         request: CreateMessageRequest,
+        reply_to: Option<QuotedMessage>,
+        media_is_private: impl Fn(&str) -> bool,
     ) -> Result<Message, sqlx::Error> {
         // Convert message type dari string ke enum
         let message_type = request.message_type
             .map(|t| MessageType::from_str_option(&Some(t)))
             .unwrap_or(MessageType::Text);
 
-        // Validate message content
-        if request.content.trim().is_empty() {
-            return Err(sqlx::Error::Protocol("Message content cannot be empty".to_string()));
-        }
-
-        if request.content.len() > 2000 {
-            return Err(sqlx::Error::Protocol("Message content too long".to_string()));
-        }
+        // Lock conversation dulu agar insert + counter unread penerima atomic
+        let locked = ConversationRepository::lock_for_unread(conn, conversation_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
 
         let row = sqlx::query!(
             r#"
-            INSERT INTO messages (conversation_id, sender_id, content, message_type, media_url, thumbnail_url)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, created_at
+            INSERT INTO messages (conversation_id, sender_id, content, message_type, media_url, thumbnail_url, reply_to_message_id, thread_root_id, is_auto_reply)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, is_deleted, deleted_at, created_at, reply_to_message_id, thread_root_id, is_auto_reply
             "#,
             conversation_id,
             sender_id,
             request.content,
             message_type.as_str() as &str,
             request.media_url,
-            request.thumbnail_url
+            request.thumbnail_url,
+            reply_to.as_ref().map(|quote| quote.id),
+            request.thread_root_id,
+            request.is_auto_reply
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *conn)
         .await?;
 
         let message = Message {
@@ -62,9 +120,24 @@ impl MessageRepository {
             thumbnail_url: row.thumbnail_url,
             is_read: row.is_read.unwrap_or(false),
             read_at: row.read_at,
+            is_deleted: row.is_deleted,
+            deleted_at: row.deleted_at,
             created_at: row.created_at.unwrap_or_else(|| chrono::Utc::now()),
+            reply_to_message_id: row.reply_to_message_id,
+            thread_root_id: row.thread_root_id,
+            is_auto_reply: row.is_auto_reply,
+            reply_to,
         };
 
+        if let Some(sender) = Participant::of(sender_id, locked.customer_id, locked.seller_id, locked.assigned_to) {
+            ConversationRepository::store_unread(conn, conversation_id, locked.unread.after_message(sender)).await?;
+        }
+
+        // Broadcast ke conversation dan ke subject user pengirim, media lewat path proxy
+        let payload = message.clone().with_media_proxy(media_is_private).to_broadcast_payload(sender_email);
+        OutboxRepository::enqueue(conn, &format!("chat.{}", conversation_id), &payload).await?;
+        OutboxRepository::enqueue(conn, &realtime::user_subject(sender_id), &payload).await?;
+
         Ok(message)
     }
 
@@ -79,7 +152,7 @@ impl MessageRepository {
         // Validasi bahwa user adalah participant dalam conversation
         let is_participant = sqlx::query_scalar!(
             "SELECT COUNT(*) FROM conversations
-             WHERE id = $1 AND (customer_id = $2 OR seller_id = $2)",
+             WHERE id = $1 AND (customer_id = $2 OR seller_id = $2 OR assigned_to = $2)",
             conversation_id,
             user_id
         )
@@ -91,7 +164,7 @@ impl MessageRepository {
         }
 
         let rows = sqlx::query!(
-            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, created_at
+            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, is_deleted, deleted_at, created_at, reply_to_message_id, thread_root_id, is_auto_reply
              FROM messages WHERE conversation_id = $1 ORDER BY created_at ASC LIMIT $2 OFFSET $3",
             conversation_id,
             limit,
@@ -110,9 +183,142 @@ impl MessageRepository {
             thumbnail_url: record.thumbnail_url,
             is_read: record.is_read.unwrap_or(false),
             read_at: record.read_at,
+            is_deleted: record.is_deleted,
+            deleted_at: record.deleted_at,
             created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
+            reply_to_message_id: record.reply_to_message_id,
+            thread_root_id: record.thread_root_id,
+            is_auto_reply: record.is_auto_reply,
+            reply_to: None,
         }).collect();
 
+        self.attach_quotes(messages).await
+    }
+
+    // Backfill messages untuk WebSocket: setelah message tertentu, atau N message terakhir
+    pub async fn get_messages_since(
+        &self,
+        conversation_id: i32,
+        after_message_id: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        let messages = match after_message_id {
+            Some(after_id) => sqlx::query_as::<_, Message>(&format!(
+                "SELECT {} FROM messages WHERE conversation_id = $1 AND id > $2 ORDER BY id ASC LIMIT $3",
+                MESSAGE_COLUMNS
+            ))
+            .bind(conversation_id)
+            .bind(after_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?,
+            None => {
+                // Ambil yang terbaru lalu balik ke urutan kronologis
+                let mut messages = sqlx::query_as::<_, Message>(&format!(
+                    "SELECT {} FROM messages WHERE conversation_id = $1 ORDER BY id DESC LIMIT $2",
+                    MESSAGE_COLUMNS
+                ))
+                .bind(conversation_id)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?;
+                messages.reverse();
+                messages
+            }
+        };
+
+        self.attach_quotes(messages).await
+    }
+
+    // Root thread di conversation beserta jumlah balasan, thread yang terakhir aktif di atas
+    pub async fn get_thread_roots(
+        &self,
+        conversation_id: i32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ThreadSummary>, sqlx::Error> {
+        let threads = sqlx::query_as::<_, ThreadSummary>(&format!(
+            "SELECT {}, t.reply_count, t.last_reply_at
+             FROM (
+                 SELECT thread_root_id AS root_id, COUNT(*) AS reply_count, MAX(created_at) AS last_reply_at
+                 FROM messages
+                 WHERE conversation_id = $1 AND thread_root_id IS NOT NULL AND is_deleted = false
+                 GROUP BY thread_root_id
+             ) t
+             JOIN messages ON messages.id = t.root_id
+             ORDER BY t.last_reply_at DESC
+             LIMIT $2 OFFSET $3",
+            MESSAGE_COLUMNS
+        ))
+        .bind(conversation_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(threads)
+    }
+
+    // Balasan dalam satu thread, urut kronologis; tombstone tidak ikut (sama dengan reply_count)
+    pub async fn get_thread_replies(
+        &self,
+        root_id: i32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        let messages = sqlx::query_as::<_, Message>(&format!(
+            "SELECT {} FROM messages WHERE thread_root_id = $1 AND is_deleted = false ORDER BY created_at ASC LIMIT $2 OFFSET $3",
+            MESSAGE_COLUMNS
+        ))
+        .bind(root_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.attach_quotes(messages).await
+    }
+
+    // Isi preview quote untuk message yang membalas message lain (satu query untuk semua)
+    async fn attach_quotes(&self, mut messages: Vec<Message>) -> Result<Vec<Message>, sqlx::Error> {
+        let quoted_ids: Vec<i32> = messages.iter().filter_map(|message| message.reply_to_message_id).collect();
+        if quoted_ids.is_empty() {
+            return Ok(messages);
+        }
+
+        let quotes: HashMap<i32, QuotedMessage> = sqlx::query!(
+            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, is_deleted, deleted_at, created_at, reply_to_message_id, thread_root_id, is_auto_reply
+             FROM messages WHERE id = ANY($1)",
+            &quoted_ids
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|record| Message {
+            id: record.id,
+            conversation_id: record.conversation_id,
+            sender_id: record.sender_id,
+            content: record.content,
+            message_type: MessageType::from_str_option(&record.message_type),
+            media_url: record.media_url,
+            thumbnail_url: record.thumbnail_url,
+            is_read: record.is_read.unwrap_or(false),
+            read_at: record.read_at,
+            is_deleted: record.is_deleted,
+            deleted_at: record.deleted_at,
+            created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
+            reply_to_message_id: record.reply_to_message_id,
+            thread_root_id: record.thread_root_id,
+            is_auto_reply: record.is_auto_reply,
+            reply_to: None,
+        })
+        .map(|quoted| (quoted.id, QuotedMessage::from_message(&quoted)))
+        .collect();
+
+        for message in &mut messages {
+            message.reply_to = message.reply_to_message_id.and_then(|id| quotes.get(&id).cloned());
+        }
+
         Ok(messages)
     }
 
@@ -125,10 +331,10 @@ impl MessageRepository {
         let row = sqlx::query!(
             r#"
             SELECT m.id, m.conversation_id, m.sender_id, m.content, m.message_type,
-                   m.media_url, m.thumbnail_url, m.is_read, m.read_at, m.created_at
+                   m.media_url, m.thumbnail_url, m.is_read, m.read_at, m.is_deleted, m.deleted_at, m.created_at, m.reply_to_message_id, m.thread_root_id, m.is_auto_reply
             FROM messages m
             JOIN conversations c ON m.conversation_id = c.id
-            WHERE m.id = $1 AND (c.customer_id = $2 OR c.seller_id = $2)
+            WHERE m.id = $1 AND (c.customer_id = $2 OR c.seller_id = $2 OR c.assigned_to = $2)
             "#,
             message_id,
             user_id
@@ -147,26 +353,98 @@ impl MessageRepository {
                 thumbnail_url: record.thumbnail_url,
                 is_read: record.is_read.unwrap_or(false),
                 read_at: record.read_at,
+                is_deleted: record.is_deleted,
+                deleted_at: record.deleted_at,
                 created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
+                reply_to_message_id: record.reply_to_message_id,
+                thread_root_id: record.thread_root_id,
+                is_auto_reply: record.is_auto_reply,
+                reply_to: None,
             })),
             None => Ok(None),
         }
     }
 
-    // Mark message as read
+    // Media message beserta participant conversation untuk proxy /messages/{id}/media
+    pub async fn find_message_media(&self, message_id: i32) -> Result<Option<MessageMedia>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT c.customer_id, c.seller_id, c.assigned_to, m.media_url, m.thumbnail_url
+            FROM messages m
+            JOIN conversations c ON m.conversation_id = c.id
+            WHERE m.id = $1
+            "#,
+            message_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|record| MessageMedia {
+            customer_id: record.customer_id,
+            seller_id: record.seller_id,
+            assigned_to: record.assigned_to,
+            media_url: record.media_url,
+            thumbnail_url: record.thumbnail_url,
+        }))
+    }
+
+    // Isi asli message yang sudah dihapus, None jika message belum dihapus atau isi asli tidak disimpan
+    pub async fn find_deleted_original(&self, message_id: i32) -> Result<Option<DeletedMessageOriginal>, sqlx::Error> {
+        sqlx::query_as!(
+            DeletedMessageOriginal,
+            r#"
+            SELECT id as message_id, conversation_id, sender_id, deleted_at, original_content, original_media_url
+            FROM messages
+            WHERE id = $1 AND is_deleted = true
+              AND (original_content IS NOT NULL OR original_media_url IS NOT NULL)
+            "#,
+            message_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Mark message as read, counter unread reader turun dalam transaksi yang sama
     pub async fn mark_message_as_read(
         &self,
         message_id: i32,
         user_id: i32,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+        let mut tx = self.pool.begin().await?;
+
+        let Some(conversation_id) = sqlx::query_scalar!(
+            "SELECT conversation_id FROM messages WHERE id = $1",
+            message_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(());
+        };
+
+        let Some(locked) = ConversationRepository::lock_for_unread(&mut tx, conversation_id).await? else {
+            return Ok(());
+        };
+
+        let Some(reader) = Participant::of(user_id, locked.customer_id, locked.seller_id, locked.assigned_to) else {
+            return Ok(());
+        };
+
+        // Hanya message dari sisi lain reader (lihat mark_read_locked)
+        let read = sqlx::query!(
             "UPDATE messages SET is_read = true, read_at = NOW()
-             WHERE id = $1 AND sender_id != $2 AND is_read = false",
+             WHERE id = $1 AND (sender_id = $2) = $3 AND is_read = false AND is_deleted = false",
             message_id,
-            user_id
+            locked.customer_id,
+            reader == Participant::Seller
         )
-        .execute(&self.pool)
-        .await?;
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        ConversationRepository::store_unread(&mut tx, conversation_id, locked.unread.after_read(reader, read)).await?;
+
+        tx.commit().await?;
 
         Ok(())
     }
@@ -177,51 +455,102 @@ impl MessageRepository {
         conversation_id: i32,
         user_id: i32,
     ) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query!(
-            "UPDATE messages SET is_read = true, read_at = NOW()
-             WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-            conversation_id,
-            user_id
-        )
-        .execute(&self.pool)
-        .await?;
+        let mut tx = self.pool.begin().await?;
+        let read = ConversationRepository::mark_read_locked(&mut tx, conversation_id, user_id).await?;
+        tx.commit().await?;
 
-        Ok(result.rows_affected())
+        Ok(read)
     }
 
-    // Get unread message count untuk conversation
-    pub async fn get_conversation_unread_count(
+    // Soft delete message (hanya oleh sender): row jadi tombstone, unread penerima dikoreksi jika belum dibaca
+    pub async fn delete_message(
         &self,
-        conversation_id: i32,
+        message_id: i32,
         user_id: i32,
-    ) -> Result<i64, sqlx::Error> {
-        let count = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM messages
-             WHERE conversation_id = $1 AND sender_id != $2 AND is_read = false",
-            conversation_id,
+        retain_original: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(conversation_id) = sqlx::query_scalar!(
+            "SELECT conversation_id FROM messages WHERE id = $1 AND sender_id = $2",
+            message_id,
             user_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(false);
+        };
+
+        let Some(locked) = ConversationRepository::lock_for_unread(&mut tx, conversation_id).await? else {
+            return Ok(false);
+        };
+
+        // Isi asli hanya disimpan untuk moderasi jika diizinkan config
+        let deleted = sqlx::query!(
+            r#"
+            UPDATE messages
+            SET is_deleted = true, deleted_at = NOW(),
+                original_content = CASE WHEN $3 THEN content END,
+                original_media_url = CASE WHEN $3 THEN media_url END,
+                content = $4, media_url = NULL, thumbnail_url = NULL
+            WHERE id = $1 AND sender_id = $2 AND is_deleted = false
+            RETURNING is_read
+            "#,
+            message_id,
+            user_id,
+            retain_original,
+            DELETED_MESSAGE_TEXT
+        )
+        .fetch_optional(&mut *tx)
         .await?;
 
-        Ok(count.unwrap_or(0))
+        let Some(deleted) = deleted else {
+            return Ok(false);
+        };
+
+        // Preview conversation ikut jadi tombstone jika yang dihapus message terakhir
+        sqlx::query!(
+            "UPDATE conversations SET last_message = $3
+             WHERE id = $1 AND $2 = (
+                 SELECT id FROM messages WHERE conversation_id = $1
+                 ORDER BY created_at DESC, id DESC LIMIT 1
+             )",
+            conversation_id,
+            message_id,
+            DELETED_MESSAGE_TEXT
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Message yang belum dibaca tidak lagi dihitung sebagai unread penerima
+        if !deleted.is_read.unwrap_or(false) {
+            if let Some(sender) = Participant::of(user_id, locked.customer_id, locked.seller_id, locked.assigned_to) {
+                ConversationRepository::store_unread(&mut tx, conversation_id, locked.unread.after_read(sender.other(), 1)).await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
     }
 
-    // Delete message (hanya oleh sender)
-    pub async fn delete_message(
+    // Purge diam-diam message yang lebih lama dari cutoff retensi, return media_url (termasuk milik tombstone) untuk dihapus dari storage
+    pub async fn purge_messages_before(
         &self,
-        message_id: i32,
-        user_id: i32,
-    ) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query!(
-            "DELETE FROM messages WHERE id = $1 AND sender_id = $2 RETURNING id",
-            message_id,
-            user_id
+        conversation_id: i32,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Option<String>>, sqlx::Error> {
+        let media_urls = sqlx::query_scalar!(
+            "DELETE FROM messages WHERE conversation_id = $1 AND created_at < $2
+             RETURNING COALESCE(media_url, original_media_url) as media_url",
+            conversation_id,
+            cutoff
         )
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(result.is_some())
+        Ok(media_urls)
     }
 
     // Get latest message untuk conversation
@@ -230,7 +559,7 @@ impl MessageRepository {
         conversation_id: i32,
     ) -> Result<Option<Message>, sqlx::Error> {
         let row = sqlx::query!(
-            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, created_at
+            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, is_deleted, deleted_at, created_at, reply_to_message_id, thread_root_id, is_auto_reply
              FROM messages WHERE conversation_id = $1 ORDER BY created_at DESC LIMIT 1",
             conversation_id
         )
@@ -248,7 +577,13 @@ impl MessageRepository {
                 thumbnail_url: record.thumbnail_url,
                 is_read: record.is_read.unwrap_or(false),
                 read_at: record.read_at,
+                is_deleted: record.is_deleted,
+                deleted_at: record.deleted_at,
                 created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
+                reply_to_message_id: record.reply_to_message_id,
+                thread_root_id: record.thread_root_id,
+                is_auto_reply: record.is_auto_reply,
+                reply_to: None,
             })),
             None => Ok(None),
         }
@@ -263,7 +598,7 @@ impl MessageRepository {
         offset: i64,
     ) -> Result<Vec<Message>, sqlx::Error> {
         let rows = sqlx::query!(
-            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, created_at
+            "SELECT id, conversation_id, sender_id, content, message_type, media_url, thumbnail_url, is_read, read_at, is_deleted, deleted_at, created_at, reply_to_message_id, thread_root_id, is_auto_reply
              FROM messages WHERE conversation_id = $1 AND sender_id = $2 ORDER BY created_at DESC LIMIT $3 OFFSET $4",
             conversation_id,
             sender_id,
@@ -283,10 +618,16 @@ impl MessageRepository {
             thumbnail_url: record.thumbnail_url,
             is_read: record.is_read.unwrap_or(false),
             read_at: record.read_at,
+            is_deleted: record.is_deleted,
+            deleted_at: record.deleted_at,
             created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
+            reply_to_message_id: record.reply_to_message_id,
+            thread_root_id: record.thread_root_id,
+            is_auto_reply: record.is_auto_reply,
+            reply_to: None,
         }).collect();
 
-        Ok(messages)
+        self.attach_quotes(messages).await
     }
 
     // Count total messages dalam conversation
@@ -315,12 +656,13 @@ impl MessageRepository {
         let rows = sqlx::query!(
             r#"
             SELECT m.id, m.conversation_id, m.sender_id, m.content, m.message_type,
-                   m.media_url, m.thumbnail_url, m.is_read, m.read_at, m.created_at
+                   m.media_url, m.thumbnail_url, m.is_read, m.read_at, m.is_deleted, m.deleted_at, m.created_at, m.reply_to_message_id, m.thread_root_id, m.is_auto_reply
             FROM messages m
             JOIN conversations c ON m.conversation_id = c.id
             WHERE m.conversation_id = $1
-            AND (c.customer_id = $2 OR c.seller_id = $2)
+            AND (c.customer_id = $2 OR c.seller_id = $2 OR c.assigned_to = $2)
             AND m.message_type != 'text'
+            AND m.is_deleted = false
             ORDER BY m.created_at DESC LIMIT $3 OFFSET $4
             "#,
             conversation_id,
@@ -341,44 +683,55 @@ impl MessageRepository {
             thumbnail_url: record.thumbnail_url,
             is_read: record.is_read.unwrap_or(false),
             read_at: record.read_at,
+            is_deleted: record.is_deleted,
+            deleted_at: record.deleted_at,
             created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
+            reply_to_message_id: record.reply_to_message_id,
+            thread_root_id: record.thread_root_id,
+            is_auto_reply: record.is_auto_reply,
+            reply_to: None,
         }).collect();
 
-        Ok(messages)
+        self.attach_quotes(messages).await
     }
 
-    // Search messages dalam conversation
+    // Search messages dalam conversation, beserta headline ts_headline (opsi dari SnippetOptions)
     pub async fn search_conversation_messages(
         &self,
         conversation_id: i32,
         user_id: i32,
         search_query: &str,
+        headline_options: &str,
         limit: i64,
         offset: i64,
-    ) -> Result<Vec<Message>, sqlx::Error> {
+    ) -> Result<Vec<(Message, Option<String>)>, sqlx::Error> {
         let search_pattern = format!("%{}%", search_query);
 
         let rows = sqlx::query!(
             r#"
             SELECT m.id, m.conversation_id, m.sender_id, m.content, m.message_type,
-                   m.media_url, m.thumbnail_url, m.is_read, m.read_at, m.created_at
+                   m.media_url, m.thumbnail_url, m.is_read, m.read_at, m.is_deleted, m.deleted_at, m.created_at, m.reply_to_message_id, m.thread_root_id, m.is_auto_reply,
+                   ts_headline('simple', m.content, plainto_tsquery('simple', $6), $7) AS headline
             FROM messages m
             JOIN conversations c ON m.conversation_id = c.id
             WHERE m.conversation_id = $1
-            AND (c.customer_id = $2 OR c.seller_id = $2)
+            AND (c.customer_id = $2 OR c.seller_id = $2 OR c.assigned_to = $2)
             AND m.content ILIKE $3
+            AND m.is_deleted = false
             ORDER BY m.created_at DESC LIMIT $4 OFFSET $5
             "#,
             conversation_id,
             user_id,
             search_pattern,
             limit,
-            offset
+            offset,
+            search_query,
+            headline_options
         )
         .fetch_all(&self.pool)
         .await?;
 
-        let messages = rows.into_iter().map(|record| Message {
+        let (messages, headlines): (Vec<Message>, Vec<Option<String>>) = rows.into_iter().map(|record| (Message {
             id: record.id,
             conversation_id: record.conversation_id,
             sender_id: record.sender_id,
@@ -388,9 +741,216 @@ impl MessageRepository {
             thumbnail_url: record.thumbnail_url,
             is_read: record.is_read.unwrap_or(false),
             read_at: record.read_at,
+            is_deleted: record.is_deleted,
+            deleted_at: record.deleted_at,
             created_at: record.created_at.unwrap_or_else(|| chrono::Utc::now()),
-        }).collect();
+            reply_to_message_id: record.reply_to_message_id,
+            thread_root_id: record.thread_root_id,
+            is_auto_reply: record.is_auto_reply,
+            reply_to: None,
+        }, record.headline)).unzip();
 
-        Ok(messages)
+        // Quote diisi terpisah, lalu dipasangkan lagi dengan headline-nya
+        let messages = self.attach_quotes(messages).await?;
+
+        Ok(messages.into_iter().zip(headlines).collect())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(conversation_id: i32, content: String) -> CreateMessageRequest {
+        CreateMessageRequest {
+            conversation_id,
+            content,
+            message_type: None,
+            media_url: None,
+            thumbnail_url: None,
+            reply_to_message_id: None,
+            thread_root_id: None,
+            is_auto_reply: false,
+        }
+    }
+
+    // Kirim message dan mark-read dari kedua participant bersamaan: counter unread harus tetap
+    // sama dengan hitungan asli (query yang dipakai job rekonsiliasi)
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_concurrent_sends_and_reads_keep_counters_consistent(pool: PgPool) {
+        let conversation_id: i32 = sqlx::query_scalar(
+            "INSERT INTO conversations (customer_id, seller_id, is_general) VALUES (1, 2, true) RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let messages = MessageRepository::new(pool.clone());
+        let mut tasks = Vec::new();
+
+        for i in 0..90 {
+            let messages = messages.clone();
+            tasks.push(tokio::spawn(async move {
+                let (user_id, email) = if i % 2 == 0 { (1, "customer@test.local") } else { (2, "seller@test.local") };
+                if i % 3 == 0 {
+                    messages.mark_conversation_read(conversation_id, user_id).await.map(|_| ())
+                } else {
+                    let request = text(conversation_id, format!("Pesan {}", i));
+                    messages.create_message(conversation_id, user_id, email, request, None, |_| false).await.map(|_| ())
+                }
+            }));
+        }
+
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let conversations = ConversationRepository::new(pool.clone());
+        assert!(conversations.find_unread_drift().await.unwrap().is_empty());
+        assert!(!conversations.reconcile_unread(conversation_id).await.unwrap());
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_thread_roots_and_replies(pool: PgPool) {
+        let conversation_id: i32 = sqlx::query_scalar(
+            "INSERT INTO conversations (customer_id, seller_id, is_general) VALUES (1, 2, true) RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let messages = MessageRepository::new(pool.clone());
+
+        let root = messages
+            .create_message(conversation_id, 1, "customer@test.local", text(conversation_id, "Root".to_string()), None, |_| false)
+            .await
+            .unwrap();
+        for (user_id, email, content) in [
+            (2, "seller@test.local", "Balasan 1"),
+            (1, "customer@test.local", "Balasan 2"),
+            (2, "seller@test.local", "Balasan dihapus"),
+        ] {
+            let mut request = text(conversation_id, content.to_string());
+            request.thread_root_id = Some(root.id);
+            let reply = messages.create_message(conversation_id, user_id, email, request, None, |_| false).await.unwrap();
+            if content == "Balasan dihapus" {
+                assert!(messages.delete_message(reply.id, user_id, false).await.unwrap());
+            }
+        }
+
+        let threads = messages.get_thread_roots(conversation_id, 10, 0).await.unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].root.id, root.id);
+        assert_eq!(threads[0].root.content, "Root");
+        assert_eq!(threads[0].reply_count, 2);
+        assert!(threads[0].last_reply_at.is_some());
+
+        let replies = messages.get_thread_replies(root.id, 10, 0).await.unwrap();
+        let contents: Vec<_> = replies.iter().map(|reply| reply.content.as_str()).collect();
+        assert_eq!(contents, vec!["Balasan 1", "Balasan 2"]);
+        assert!(replies.iter().all(|reply| reply.thread_root_id == Some(root.id)));
+    }
+
+    // Quote harus ikut terisi di hasil search, media, dan filter sender, bukan hanya di history
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_quotes_attached_in_search_media_and_sender_results(pool: PgPool) {
+        let conversation_id: i32 = sqlx::query_scalar(
+            "INSERT INTO conversations (customer_id, seller_id, is_general) VALUES (1, 2, true) RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let messages = MessageRepository::new(pool.clone());
+
+        let quoted = messages
+            .create_message(conversation_id, 1, "customer@test.local", text(conversation_id, "Masih tersedia?".to_string()), None, |_| false)
+            .await
+            .unwrap();
+
+        let mut reply = text(conversation_id, "Masih, foto terlampir".to_string());
+        reply.reply_to_message_id = Some(quoted.id);
+        reply.message_type = Some("image".to_string());
+        reply.media_url = Some("https://example.com/foto.jpg".to_string());
+        let quote = Some(QuotedMessage::from_message(&quoted));
+        messages.create_message(conversation_id, 2, "seller@test.local", reply, quote, |_| false).await.unwrap();
+
+        let by_sender = messages.get_messages_by_sender(conversation_id, 2, 10, 0).await.unwrap();
+        let media = messages.get_media_messages(conversation_id, 1, 10, 0).await.unwrap();
+        let search = messages.search_conversation_messages(conversation_id, 1, "terlampir", "", 10, 0).await.unwrap();
+
+        assert_eq!(search.len(), 1);
+        assert!(search[0].1.is_some());
+        for message in [&by_sender[0], &media[0], &search[0].0] {
+            let quote = message.reply_to.as_ref().expect("quote harus terisi");
+            assert_eq!(quote.id, quoted.id);
+        }
+    }
+
+    // Dua auto-reply bersamaan dengan cooldown yang sama: hanya satu yang terkirim dan klaimnya tercatat
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_concurrent_auto_replies_send_once(pool: PgPool) {
+        let conversation_id: i32 = sqlx::query_scalar(
+            "INSERT INTO conversations (customer_id, seller_id, is_general) VALUES (1, 2, true) RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let messages = MessageRepository::new(pool.clone());
+
+        let mut tasks = Vec::new();
+        for _ in 0..5 {
+            let messages = messages.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut request = text(conversation_id, "Terima kasih, kami balas segera".to_string());
+                request.is_auto_reply = true;
+                messages.create_auto_reply(conversation_id, 2, "seller@test.local", request, None, |_| false).await
+            }));
+        }
+
+        let mut sent = 0;
+        for task in tasks {
+            if task.await.unwrap().unwrap().is_some() {
+                sent += 1;
+            }
+        }
+        assert_eq!(sent, 1);
+
+        let (auto_replies, claimed): (i64, bool) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM messages WHERE conversation_id = $1 AND is_auto_reply),
+                    (SELECT auto_replied_at IS NOT NULL FROM conversations WHERE id = $1)"
+        )
+        .bind(conversation_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(auto_replies, 1);
+        assert!(claimed);
+    }
+}
@@ -1,7 +1,13 @@
 // Repository modules untuk Chat Service
+pub mod auto_reply_repo;
 pub mod conversation_repo;
 pub mod message_repo;
+pub mod outbox_repo;
+pub mod seller_staff_repo;
 
 // Export publik
+pub use auto_reply_repo::*;
 pub use conversation_repo::*;
-pub use message_repo::*;
\ No newline at end of file
+pub use message_repo::*;
+pub use outbox_repo::*;
+pub use seller_staff_repo::*;
\ No newline at end of file
@@ -0,0 +1,127 @@
+// Repository untuk transactional outbox event chat
+use sqlx::{PgConnection, PgPool};
+use std::time::Duration;
+
+use crate::utils::outbox::OutboxEvent;
+
+// Repository untuk tabel chat_outbox
+#[derive(Clone)]
+pub struct OutboxRepository {
+    pool: PgPool,
+}
+
+impl OutboxRepository {
+    // Create new outbox repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Simpan event di transaksi yang sama dengan perubahan datanya
+    pub async fn enqueue(
+        conn: &mut PgConnection,
+        subject: &str,
+        payload: &serde_json::Value,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO chat_outbox (subject, payload) VALUES ($1, $2)",
+            subject,
+            payload
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    // Klaim event pending dengan lease agar tidak diproses relay lain secara paralel
+    pub async fn claim_pending(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> Result<Vec<OutboxEvent>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            UPDATE chat_outbox
+            SET next_attempt_at = NOW() + $2::BIGINT * INTERVAL '1 second'
+            WHERE id IN (
+                SELECT id FROM chat_outbox
+                WHERE sent_at IS NULL AND next_attempt_at <= NOW()
+                ORDER BY id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, subject, payload, attempts
+            "#,
+            limit,
+            lease_secs
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut events: Vec<OutboxEvent> = rows.into_iter().map(|row| OutboxEvent {
+            id: row.id,
+            subject: row.subject,
+            payload: row.payload,
+            attempts: row.attempts,
+        }).collect();
+
+        // RETURNING tidak menjamin urutan, publish sesuai urutan insert
+        events.sort_by_key(|e| e.id);
+
+        Ok(events)
+    }
+
+    // Tandai event sudah terkirim ke NATS
+    pub async fn mark_sent(&self, ids: &[i64]) -> Result<(), sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            "UPDATE chat_outbox SET sent_at = NOW(), last_error = NULL WHERE id = ANY($1)",
+            ids
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Catat kegagalan publish dan jadwalkan retry
+    pub async fn mark_failed(
+        &self,
+        id: i64,
+        error: &str,
+        retry_after: Duration,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE chat_outbox
+            SET attempts = attempts + 1,
+                last_error = $2,
+                next_attempt_at = NOW() + $3::BIGINT * INTERVAL '1 second'
+            WHERE id = $1
+            "#,
+            id,
+            error,
+            retry_after.as_secs() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Hapus event yang sudah terkirim lebih lama dari retention
+    pub async fn purge_sent(&self, retention_days: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM chat_outbox
+             WHERE sent_at IS NOT NULL AND sent_at < NOW() - $1::BIGINT * INTERVAL '1 day'",
+            retention_days
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
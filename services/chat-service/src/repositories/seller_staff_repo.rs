@@ -0,0 +1,130 @@
+// Repository untuk staff dealer (organisasi seller)
+use crate::domain::SellerStaffMember;
+use sqlx::PgPool;
+
+// Status akun calon staff
+pub struct StaffCandidate {
+    pub is_active: bool,
+    pub is_seller: bool,
+}
+
+#[derive(Clone)]
+pub struct SellerStaffRepository {
+    pool: PgPool,
+}
+
+impl SellerStaffRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Seller pemilik org tempat user menjadi staff, None jika bukan staff
+    pub async fn org_of(&self, user_id: i32) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "SELECT seller_id FROM seller_staff WHERE staff_user_id = $1",
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Daftar staff milik seller
+    pub async fn list(&self, seller_id: i32) -> Result<Vec<SellerStaffMember>, sqlx::Error> {
+        let rows = sqlx::query!(
+            "SELECT s.staff_user_id, u.name, u.email, s.created_at
+             FROM seller_staff s
+             JOIN users u ON u.id = s.staff_user_id
+             WHERE s.seller_id = $1
+             ORDER BY s.created_at, s.staff_user_id",
+            seller_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| SellerStaffMember {
+            staff_user_id: row.staff_user_id,
+            name: row.name,
+            email: row.email,
+            added_at: row.created_at,
+        }).collect())
+    }
+
+    // Satu staff milik seller, None jika user bukan staff seller ini
+    pub async fn find(&self, seller_id: i32, staff_user_id: i32) -> Result<Option<SellerStaffMember>, sqlx::Error> {
+        let row = sqlx::query!(
+            "SELECT s.staff_user_id, u.name, u.email, s.created_at
+             FROM seller_staff s
+             JOIN users u ON u.id = s.staff_user_id
+             WHERE s.seller_id = $1 AND s.staff_user_id = $2",
+            seller_id,
+            staff_user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| SellerStaffMember {
+            staff_user_id: row.staff_user_id,
+            name: row.name,
+            email: row.email,
+            added_at: row.created_at,
+        }))
+    }
+
+    // Akun user yang akan dijadikan staff, None jika user tidak ada
+    pub async fn find_candidate(&self, user_id: i32) -> Result<Option<StaffCandidate>, sqlx::Error> {
+        let row = sqlx::query!(
+            "SELECT is_active, is_seller FROM users WHERE id = $1",
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| StaffCandidate {
+            is_active: row.is_active.unwrap_or(true),
+            is_seller: row.is_seller.unwrap_or(false),
+        }))
+    }
+
+    // Tambah staff, false jika user sudah terdaftar sebagai staff (di org mana pun)
+    pub async fn add(&self, seller_id: i32, staff_user_id: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "INSERT INTO seller_staff (seller_id, staff_user_id) VALUES ($1, $2)
+             ON CONFLICT (staff_user_id) DO NOTHING",
+            seller_id,
+            staff_user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Hapus staff dan kembalikan conversation yang di-assign ke dia ke seller
+    pub async fn remove(&self, seller_id: i32, staff_user_id: i32) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let removed = sqlx::query!(
+            "DELETE FROM seller_staff WHERE seller_id = $1 AND staff_user_id = $2",
+            seller_id,
+            staff_user_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() > 0;
+
+        if removed {
+            sqlx::query!(
+                "UPDATE conversations SET assigned_to = NULL, assigned_at = NULL, updated_at = NOW()
+                 WHERE seller_id = $1 AND assigned_to = $2",
+                seller_id,
+                staff_user_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+}
@@ -1,21 +1,20 @@
 // API Routes untuk Chat Service dengan JWT-Only architecture
 
 use crate::config::AppState;
-use crate::handlers::{conversations, messages, upload, websocket};
+use crate::error::AppError;
+use crate::handlers::{auto_reply, conversations, inbound_email, messages, seller_staff, upload, websocket};
 use crate::middleware::{auth::jwt_auth_middleware, rate_limit::rate_limit_middleware};
 use axum::{
     extract::Request,
     middleware::Next,
     response::Response,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use std::time::Duration;
-use tower::ServiceBuilder;
-use tower_http::{
-    cors::CorsLayer,
-    trace::TraceLayer,
-};
+use shared::utils::cors::CorsPolicy;
+use shared::utils::request_timeout;
+use tower_http::cors::CorsLayer;
 use utoipa::{OpenApi, Modify};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_redoc::{Redoc, Servable};
@@ -35,8 +34,19 @@ use utoipa_swagger_ui::SwaggerUi;
         conversations::get_conversation_by_id,
         conversations::get_conversation_with_details,
         conversations::mark_conversation_read,
+        conversations::mark_all_conversations_read,
+        conversations::update_conversation_retention,
+        auto_reply::get_auto_reply,
+        auto_reply::update_auto_reply,
+        seller_staff::list_staff,
+        seller_staff::add_staff,
+        seller_staff::get_staff,
+        seller_staff::remove_staff,
+        seller_staff::assign_conversation,
         conversations::get_unread_count,
         conversations::health_check,
+        conversations::readiness_check,
+        conversations::metrics,
         messages::send_message,
         messages::send_typing_indicator,
         messages::get_conversation_messages,
@@ -44,33 +54,65 @@ use utoipa_swagger_ui::SwaggerUi;
         messages::get_message_count,
         messages::search_messages,
         messages::get_message_by_id,
+        messages::get_conversation_threads,
+        messages::get_message_thread,
+        messages::get_message_media,
         messages::mark_message_read,
         messages::delete_message,
+        messages::get_deleted_message_original,
+        messages::get_deleted_message_original_media,
         messages::get_unread_count,
         messages::get_media_messages,
         messages::get_messages_by_sender,
         messages::send_message_with_files,
         messages::generate_message_preview,
         upload::upload_file,
+        websocket::disconnect_user,
+        inbound_email::resend_inbound,
+        inbound_email::sendgrid_inbound,
     ),
     components(
         schemas(
             crate::domain::Conversation,
             crate::domain::Message,
+            crate::domain::MessageResponse,
+            crate::domain::QuotedMessage,
+            crate::domain::ThreadSummary,
             crate::domain::CreateConversationRequest,
             crate::domain::CreateMessageRequest,
             crate::domain::MessageType,
             conversations::ConversationListResponse,
+            conversations::ConversationListQuery,
+            crate::domain::conversation::ConversationRoleFilter,
+            conversations::UpdateRetentionRequest,
+            conversations::RetentionResponse,
+            auto_reply::UpdateAutoReplyRequest,
+            auto_reply::AutoReplyResponse,
+            crate::domain::SellerStaffMember,
+            crate::domain::AddSellerStaffRequest,
+            crate::domain::AssignConversationRequest,
+            crate::domain::ConversationAssignmentResponse,
+            conversations::ReadAllResponse,
             conversations::ConversationWithDetailsResponse,
             crate::config::HealthCheckResponse,
+            shared::utils::health::HealthLevel,
+            shared::utils::health::DependencyHealth,
+            crate::config::ReadinessResponse,
+            crate::domain::DeletedMessageOriginal,
             messages::MessageListResponse,
+            messages::MessageSearchHit,
+            messages::MessageSearchResponse,
             messages::MessageCountResponse,
+            messages::ThreadListResponse,
+            messages::ThreadResponse,
+            crate::utils::media_proxy::MediaVariant,
             upload::UploadResponse,
             upload::UploadedFile,
             upload::FileCategory,
             messages::CreateMessageWithFilesRequest,
             messages::MessagePreviewResponse,
             messages::TypingIndicatorRequest,
+            websocket::DisconnectUserResponse,
         )
     ),
     tags(
@@ -126,6 +168,19 @@ async fn security_headers_middleware(
     response
 }
 
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
+}
+
+// Router listener metrics terpisah (CHAT_METRICS_PORT), tanpa CORS/rate limit karena tidak publik
+pub fn create_metrics_router(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(conversations::metrics))
+        .fallback(not_found_handler)
+        .with_state(state)
+}
+
 // Buat router dengan JWT-only security dan Redis rate limiting
 pub fn create_router(state: AppState) -> Router {
     if state.config.is_production() {
@@ -134,15 +189,11 @@ pub fn create_router(state: AppState) -> Router {
         tracing::info!("Chat Service running in DEVELOPMENT mode");
     }
 
-    // CORS configuration dari environment
-    let frontend_url = std::env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable HARUS diisi di .env file");
+    // CORS: origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let cors_policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
-    let allowed_origin = frontend_url.parse::<axum::http::HeaderValue>()
-        .expect("FRONTEND_URL harus valid URL format");
-
-    let cors = CorsLayer::new()
-        .allow_origin(allowed_origin)
+    let cors = cors_policy
+        .apply(CorsLayer::new())
         .allow_methods([
             axum::http::Method::GET,
             axum::http::Method::POST,
@@ -155,7 +206,6 @@ pub fn create_router(state: AppState) -> Router {
             axum::http::header::ACCEPT,
             axum::http::header::CONTENT_TYPE,
         ])
-        .allow_credentials(false) 
         .max_age(Duration::from_secs(86400));
 
     // Setup OpenAPI documentation
@@ -163,8 +213,20 @@ pub fn create_router(state: AppState) -> Router {
     SecurityAddon.modify(&mut openapi);
 
     // Public routes - tanpa JWT authentication
-    let public_routes = Router::new()
+    // Webhook inbound email diautentikasi lewat signature/kredensial provider
+    let mut public_routes = Router::new()
         .route("/health", get(conversations::health_check))
+        .route("/health/ready", get(conversations::readiness_check));
+
+    // /metrics hanya di listener API jika tidak ada listener metrics terpisah
+    if state.config.metrics_addr.is_none() {
+        public_routes = public_routes.route("/metrics", get(conversations::metrics));
+    }
+
+    let public_routes = public_routes
+        .route("/webhooks/email/resend", post(inbound_email::resend_inbound))
+        .route("/webhooks/email/sendgrid", post(inbound_email::sendgrid_inbound))
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
         .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi.clone()))
         .merge(Redoc::with_url("/redoc", openapi))
         .with_state(state.clone());
@@ -179,11 +241,8 @@ pub fn create_router(state: AppState) -> Router {
     // Combine semua routes dengan shared middleware
     public_routes
         .nest("/api", protected_routes)
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(cors)
-        )
+        .fallback(not_found_handler)
+        .layer(cors)
         .layer(axum::middleware::from_fn(security_headers_middleware))
         .layer(axum::middleware::from_fn_with_state(
             state.rate_limiter.clone(),
@@ -193,9 +252,12 @@ pub fn create_router(state: AppState) -> Router {
 
 // Build API routes dengan JWT authentication
 fn build_api_routes(state: AppState) -> Router {
+    // Upload multipart boleh lebih lama dari timeout default
+    let upload_timeout = request_timeout::route_timeout("UPLOAD_REQUEST_TIMEOUT_SECS", 120);
+
     Router::new()
-        // ===== WebSocket Endpoint =====
-        .route("/ws/chat/{conversation_id}", get(websocket::websocket_handler))
+        // ===== WebSocket Management =====
+        .route("/ws/disconnect-user/{user_id}", post(websocket::disconnect_user))
 
         // ===== Conversation Operations =====
         .route("/conversations", post(conversations::create_conversation))
@@ -203,7 +265,13 @@ fn build_api_routes(state: AppState) -> Router {
         .route("/conversations/{conversation_id}", get(conversations::get_conversation_by_id))
         .route("/conversations/{conversation_id}/details", get(conversations::get_conversation_with_details))
         .route("/conversations/{conversation_id}/read", post(conversations::mark_conversation_read))
+        .route("/conversations/{conversation_id}/retention", put(conversations::update_conversation_retention))
+        .route("/auto-reply", get(auto_reply::get_auto_reply).put(auto_reply::update_auto_reply))
+        .route("/conversations/{conversation_id}/assignment", put(seller_staff::assign_conversation))
+        .route("/seller/staff", get(seller_staff::list_staff).post(seller_staff::add_staff))
+        .route("/seller/staff/{staff_user_id}", get(seller_staff::get_staff).delete(seller_staff::remove_staff))
         .route("/conversations/unread", get(conversations::get_unread_count))
+        .route("/conversations/read-all", post(conversations::mark_all_conversations_read))
 
         // ===== Message Operations =====
         .route("/messages", post(messages::send_message))
@@ -214,15 +282,26 @@ fn build_api_routes(state: AppState) -> Router {
         .route("/messages/search", get(messages::search_messages))
         .route("/messages/{message_id}", get(messages::get_message_by_id))
         .route("/messages/{message_id}/read", post(messages::mark_message_read))
+        .route("/messages/{message_id}/media", get(messages::get_message_media))
+        .route("/messages/{message_id}/thread", get(messages::get_message_thread))
+        .route("/conversations/{conversation_id}/threads", get(messages::get_conversation_threads))
         .route("/messages/{message_id}", delete(messages::delete_message))
+        .route("/admin/messages/{message_id}/original", get(messages::get_deleted_message_original))
+        .route("/admin/messages/{message_id}/original/media", get(messages::get_deleted_message_original_media))
         .route("/messages/unread/{conversation_id}", get(messages::get_unread_count))
         .route("/messages/media/{conversation_id}", get(messages::get_media_messages))
         .route("/conversations/{conversation_id}/messages/sender/{sender_id}", get(messages::get_messages_by_sender))
         .route("/messages/with-files", post(messages::send_message_with_files))
         .route("/messages/preview", post(messages::generate_message_preview))
 
+        // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
+
         // ===== File Upload Operations =====
-        .route("/upload", post(upload::upload_file))
+        .route("/upload", post(upload::upload_file).layer(request_timeout::layer(upload_timeout)))
+
+        // ===== WebSocket Endpoint (long-lived, tanpa timeout) =====
+        .route("/ws/chat/{conversation_id}", get(websocket::websocket_handler))
 
         .with_state(state)
 }
\ No newline at end of file
@@ -0,0 +1,375 @@
+use crate::config::AppState;
+use crate::handlers::websocket::{ConnectionManager, UserBannedEvent, USER_BANNED_SUBJECT};
+use crate::utils::outbox::{relay_batch, retry_backoff};
+use crate::utils::retention::{self, Clock, SystemClock};
+use shared::utils::storage::Storage;
+use futures::StreamExt;
+use std::time::Duration;
+
+// Jumlah event outbox per batch relay
+const OUTBOX_BATCH_SIZE: i64 = 100;
+
+// Lease klaim event agar tidak dipublish ganda oleh instance lain
+const OUTBOX_LEASE_SECS: i64 = 30;
+
+// Retensi event outbox yang sudah terkirim
+const OUTBOX_RETENTION_DAYS: i64 = 7;
+
+/// Background scheduler untuk chat service (SLA respon seller, cleanup outbox, retensi message, rekonsiliasi unread)
+pub struct ChatScheduler {
+    state: AppState,
+}
+
+impl ChatScheduler {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Start background tasks untuk chat service
+    pub fn start(self) {
+        // Check if scheduler is disabled
+        if std::env::var("DISABLE_SCHEDULER").unwrap_or_else(|_| "false".to_string()) == "true" {
+            tracing::info!("💬 Chat scheduler disabled via DISABLE_SCHEDULER environment variable");
+            return;
+        }
+
+        tracing::info!("💬 Starting Chat Service Background Scheduler...");
+
+        // Bersihkan outbox yang sudah terkirim
+        let outbox_state = self.state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(3600)); // Every hour
+
+            loop {
+                interval.tick().await;
+
+                match outbox_state.outbox_repo.purge_sent(OUTBOX_RETENTION_DAYS).await {
+                    Ok(purged) => {
+                        if purged > 0 {
+                            tracing::info!("🧹 Purged {} sent outbox events", purged);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("❌ Failed to purge outbox events: {}", e);
+                    }
+                }
+            }
+        });
+
+        // Purge message yang melewati retensi conversation (silent, tanpa broadcast)
+        let retention_state = self.state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(3600)); // Every hour
+
+            loop {
+                interval.tick().await;
+
+                let (messages, media) = purge_expired_messages(&retention_state, &SystemClock).await;
+                if messages > 0 {
+                    tracing::info!("🗑️ Purged {} expired messages ({} media files)", messages, media);
+                }
+            }
+        });
+
+        // Perbaiki counter unread yang drift dari jumlah message asli
+        let unread_state = self.state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(900)); // Every 15 minutes
+
+            loop {
+                interval.tick().await;
+
+                let reconciled = reconcile_unread_counts(&unread_state).await;
+                if reconciled > 0 {
+                    tracing::warn!("🔢 Reconciled unread counters for {} conversations", reconciled);
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(300)); // Every 5 minutes
+
+            loop {
+                interval.tick().await;
+
+                match self.state.conversation_repo.flag_sla_breaches(self.state.config.seller_sla_minutes).await {
+                    Ok(flagged) => {
+                        if flagged > 0 {
+                            tracing::info!("⏰ Flagged {} conversations breaching seller response SLA", flagged);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("❌ Failed to check seller response SLA: {}", e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+// Hapus message lewat retensi per conversation beserta media-nya, last_message conversation tidak diubah
+async fn purge_expired_messages(state: &AppState, clock: &impl Clock) -> (usize, usize) {
+    let policies = match state.conversation_repo.find_retention_policies().await {
+        Ok(policies) => policies,
+        Err(e) => {
+            tracing::error!("❌ Failed to load message retention policies: {}", e);
+            return (0, 0);
+        }
+    };
+
+    let now = clock.now();
+    let mut purged_messages = 0;
+    let mut purged_media = 0;
+
+    for (conversation_id, retention_days) in policies {
+        let cutoff = retention::retention_cutoff(now, retention_days);
+
+        let media_urls = match state.message_repo.purge_messages_before(conversation_id, cutoff).await {
+            Ok(media_urls) => media_urls,
+            Err(e) => {
+                tracing::error!("❌ Failed to purge messages for conversation {}: {}", conversation_id, e);
+                continue;
+            }
+        };
+        purged_messages += media_urls.len();
+
+        // Message unread yang ikut terhapus harus keluar dari counter
+        if !media_urls.is_empty() {
+            if let Err(e) = state.conversation_repo.reconcile_unread(conversation_id).await {
+                tracing::error!("❌ Failed to reconcile unread after purge for conversation {}: {}", conversation_id, e);
+            }
+        }
+
+        // Media ikut dihapus dari storage, kegagalan tidak membatalkan purge message
+        for url in media_urls.into_iter().flatten() {
+            if !state.storage.owns_url(&url) {
+                continue;
+            }
+
+            match state.storage.delete(&url).await {
+                Ok(()) => purged_media += 1,
+                Err(e) => tracing::warn!("⚠️ Failed to delete purged media {}: {}", url, e),
+            }
+        }
+    }
+
+    (purged_messages, purged_media)
+}
+
+// Hitung ulang counter unread conversation yang drift, return jumlah yang dikoreksi
+async fn reconcile_unread_counts(state: &AppState) -> usize {
+    let drifted = match state.conversation_repo.find_unread_drift().await {
+        Ok(drifted) => drifted,
+        Err(e) => {
+            tracing::error!("❌ Failed to detect unread counter drift: {}", e);
+            return 0;
+        }
+    };
+
+    let mut reconciled = 0;
+    for conversation_id in drifted {
+        match state.conversation_repo.reconcile_unread(conversation_id).await {
+            Ok(true) => reconciled += 1,
+            Ok(false) => {}
+            Err(e) => tracing::error!("❌ Failed to reconcile unread for conversation {}: {}", conversation_id, e),
+        }
+    }
+
+    reconciled
+}
+
+/// Relay transactional outbox ke NATS, selalu jalan terlepas dari DISABLE_SCHEDULER
+pub struct OutboxRelay {
+    state: AppState,
+}
+
+impl OutboxRelay {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Start loop relay: dibangunkan setiap ada event baru, dengan polling sebagai fallback
+    pub fn start(self) {
+        let Some(nats_client) = self.state.nats_client.clone() else {
+            tracing::warn!("📭 NATS client tidak tersedia, event outbox akan tertahan sampai service restart");
+            return;
+        };
+
+        tracing::info!("📮 Starting chat outbox relay...");
+
+        tokio::spawn(async move {
+            let poll_interval = Duration::from_secs(self.state.config.outbox_relay_interval_secs);
+
+            loop {
+                tokio::select! {
+                    _ = self.state.outbox_notify.notified() => {}
+                    _ = tokio::time::sleep(poll_interval) => {}
+                }
+
+                // Proses sampai outbox kosong atau NATS gagal
+                loop {
+                    let events = match self.state.outbox_repo.claim_pending(OUTBOX_BATCH_SIZE, OUTBOX_LEASE_SECS).await {
+                        Ok(events) => events,
+                        Err(e) => {
+                            tracing::error!("❌ Failed to claim outbox events: {}", e);
+                            break;
+                        }
+                    };
+
+                    if events.is_empty() {
+                        break;
+                    }
+
+                    let outcome = relay_batch(&nats_client, &events).await;
+
+                    if let Err(e) = self.state.outbox_repo.mark_sent(&outcome.sent).await {
+                        tracing::error!("❌ Failed to mark outbox events as sent: {}", e);
+                    }
+
+                    for (id, error) in &outcome.failed {
+                        let attempts = events.iter().find(|e| e.id == *id).map_or(0, |e| e.attempts);
+                        if let Err(e) = self.state.outbox_repo.mark_failed(*id, error, retry_backoff(attempts)).await {
+                            tracing::error!("❌ Failed to reschedule outbox event {}: {}", id, e);
+                        }
+                    }
+
+                    if !outcome.failed.is_empty() {
+                        tracing::warn!("📮 {} outbox events gagal dipublish, akan di-retry: {}",
+                                       outcome.failed.len(), outcome.failed[0].1);
+                        break;
+                    }
+
+                    tracing::debug!("📮 Published {} outbox events ke NATS", outcome.sent.len());
+                }
+            }
+        });
+    }
+}
+
+/// Listener event ban user via NATS, memutus koneksi WebSocket user di instance ini
+pub struct UserBanListener {
+    state: AppState,
+}
+
+impl UserBanListener {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Start subscribe event ban; async-nats otomatis subscribe ulang setelah reconnect
+    pub fn start(self) {
+        let Some(nats_client) = self.state.nats_client.clone() else {
+            tracing::warn!("🚫 NATS client tidak tersedia, ban user hanya memutus koneksi di instance yang menerima request");
+            return;
+        };
+
+        tracing::info!("🚫 Starting user ban listener...");
+
+        tokio::spawn(async move {
+            let mut subscriber = match nats_client.subscribe(USER_BANNED_SUBJECT).await {
+                Ok(subscriber) => subscriber,
+                Err(e) => {
+                    tracing::error!("❌ Failed to subscribe {}: {}", USER_BANNED_SUBJECT, e);
+                    return;
+                }
+            };
+
+            while let Some(message) = subscriber.next().await {
+                match serde_json::from_slice::<UserBannedEvent>(&message.payload) {
+                    Ok(event) => {
+                        ConnectionManager::putus_koneksi_user(event.user_id).await;
+                    }
+                    Err(e) => {
+                        tracing::warn!("⚠️ Invalid user ban event payload: {}", e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::conversation_initiation::FindOrCreate;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::Mutex;
+
+    // Clock palsu yang bisa dimajukan manual
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl FakeClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    async fn conversation(state: &AppState, customer_id: i32, vehicle_id: Option<i32>) -> i32 {
+        match state.conversation_repo.find_or_create_conversation(customer_id, 2, vehicle_id).await.unwrap() {
+            Some(FindOrCreate::Created(id) | FindOrCreate::Existing(id)) => id,
+            None => panic!("conversation tidak dibuat"),
+        }
+    }
+
+    async fn insert_message(state: &AppState, conversation_id: i32, content: &str, created_at: DateTime<Utc>) {
+        sqlx::query(
+            "INSERT INTO messages (conversation_id, sender_id, content, message_type, created_at)
+             VALUES ($1, 1, $2, 'text', $3)"
+        )
+        .bind(conversation_id)
+        .bind(content)
+        .bind(created_at)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+
+    async fn contents(state: &AppState) -> Vec<String> {
+        sqlx::query_scalar("SELECT content FROM messages ORDER BY id")
+            .fetch_all(&state.db)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_purge_respects_retention_window(pool: sqlx::PgPool) {
+        let state = AppState::for_test(pool);
+        let start = DateTime::parse_from_rfc3339("2026-03-01T08:00:00Z").unwrap().with_timezone(&Utc);
+        let clock = FakeClock(Mutex::new(start));
+
+        let short_retention = conversation(&state, 1, Some(1)).await;
+        let forever = conversation(&state, 1, None).await;
+        state.conversation_repo.set_retention_days(short_retention, 1, Some(1)).await.unwrap();
+
+        insert_message(&state, short_retention, "lama", start).await;
+        insert_message(&state, short_retention, "baru", start + Duration::hours(20)).await;
+        insert_message(&state, forever, "tanpa retensi", start).await;
+
+        // Belum lewat 1 hari: tidak ada yang dihapus
+        clock.advance(Duration::hours(23));
+        assert_eq!(purge_expired_messages(&state, &clock).await, (0, 0));
+
+        // Lewat 1 hari: hanya message lama di conversation dengan retensi yang dihapus
+        clock.advance(Duration::hours(2));
+        assert_eq!(purge_expired_messages(&state, &clock).await, (1, 0));
+        assert_eq!(contents(&state).await, vec!["baru".to_string(), "tanpa retensi".to_string()]);
+
+        // Default retensi tak terbatas tidak pernah purge
+        clock.advance(Duration::days(3650));
+        assert_eq!(purge_expired_messages(&state, &clock).await, (1, 0));
+        assert_eq!(contents(&state).await, vec!["tanpa retensi".to_string()]);
+    }
+}
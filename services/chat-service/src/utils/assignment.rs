@@ -0,0 +1,91 @@
+// Assignment conversation ke staff dealer (organisasi seller)
+//
+// Organisasi seller minimal: seller adalah pemilik, staff terdaftar di seller_staff.
+// Conversation yang di-assign ke staff terlihat di inbox staff tersebut dan staff
+// berada di sisi seller (unread, read receipt, SLA respon pertama).
+
+use crate::utils::unread::Participant;
+
+// Pemilik org (seller) atau staff org seller tersebut yang boleh mengatur assignment
+pub fn can_manage_assignment(actor_id: i32, seller_id: i32, actor_staff_of: Option<i32>) -> bool {
+    actor_id == seller_id || actor_staff_of == Some(seller_id)
+}
+
+// Assignee harus seller sendiri atau staff org seller. Assign ke seller = kembali ke seller
+// langsung, disimpan sebagai NULL
+pub fn resolve_assignee(
+    seller_id: i32,
+    assignee: Option<i32>,
+    assignee_staff_of: Option<i32>,
+) -> Result<Option<i32>, &'static str> {
+    match assignee {
+        None => Ok(None),
+        Some(user_id) if user_id == seller_id => Ok(None),
+        Some(user_id) if assignee_staff_of == Some(seller_id) => Ok(Some(user_id)),
+        Some(_) => Err("Assignee harus staff dari dealer pemilik conversation"),
+    }
+}
+
+// Conversation terlihat oleh customer, seller, dan staff yang sedang di-assign
+pub fn is_visible_to(user_id: i32, customer_id: i32, seller_id: i32, assigned_to: Option<i32>) -> bool {
+    Participant::of(user_id, customer_id, seller_id, assigned_to).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTOMER: i32 = 1;
+    const SELLER: i32 = 10;
+    const STAFF_A: i32 = 11;
+    const STAFF_B: i32 = 12;
+    const OTHER_DEALER_STAFF: i32 = 21;
+
+    // seller_staff: STAFF_A & STAFF_B milik SELLER, OTHER_DEALER_STAFF milik dealer 20
+    fn staff_of(user_id: i32) -> Option<i32> {
+        match user_id {
+            STAFF_A | STAFF_B => Some(SELLER),
+            OTHER_DEALER_STAFF => Some(20),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_assignment_visibility() {
+        // Belum di-assign: hanya customer dan seller
+        let mut assigned_to = None;
+        assert!(is_visible_to(CUSTOMER, CUSTOMER, SELLER, assigned_to));
+        assert!(is_visible_to(SELLER, CUSTOMER, SELLER, assigned_to));
+        assert!(!is_visible_to(STAFF_A, CUSTOMER, SELLER, assigned_to));
+
+        // Assign ke staff A: staff A melihat, staff B belum
+        assigned_to = resolve_assignee(SELLER, Some(STAFF_A), staff_of(STAFF_A)).unwrap();
+        assert!(is_visible_to(STAFF_A, CUSTOMER, SELLER, assigned_to));
+        assert!(!is_visible_to(STAFF_B, CUSTOMER, SELLER, assigned_to));
+        assert_eq!(Participant::of(STAFF_A, CUSTOMER, SELLER, assigned_to), Some(Participant::Seller));
+
+        // Reassign ke staff B: staff A kehilangan akses, seller tetap melihat
+        assert!(can_manage_assignment(STAFF_A, SELLER, staff_of(STAFF_A)));
+        assigned_to = resolve_assignee(SELLER, Some(STAFF_B), staff_of(STAFF_B)).unwrap();
+        assert!(!is_visible_to(STAFF_A, CUSTOMER, SELLER, assigned_to));
+        assert!(is_visible_to(STAFF_B, CUSTOMER, SELLER, assigned_to));
+        assert!(is_visible_to(SELLER, CUSTOMER, SELLER, assigned_to));
+
+        // Kembali ke seller: disimpan sebagai NULL
+        assigned_to = resolve_assignee(SELLER, Some(SELLER), staff_of(SELLER)).unwrap();
+        assert_eq!(assigned_to, None);
+        assert!(!is_visible_to(STAFF_B, CUSTOMER, SELLER, assigned_to));
+    }
+
+    #[test]
+    fn test_assignment_restricted_to_org_members() {
+        assert!(can_manage_assignment(SELLER, SELLER, staff_of(SELLER)));
+        assert!(can_manage_assignment(STAFF_B, SELLER, staff_of(STAFF_B)));
+        assert!(!can_manage_assignment(CUSTOMER, SELLER, staff_of(CUSTOMER)));
+        assert!(!can_manage_assignment(OTHER_DEALER_STAFF, SELLER, staff_of(OTHER_DEALER_STAFF)));
+
+        assert!(resolve_assignee(SELLER, Some(OTHER_DEALER_STAFF), staff_of(OTHER_DEALER_STAFF)).is_err());
+        assert!(resolve_assignee(SELLER, Some(CUSTOMER), staff_of(CUSTOMER)).is_err());
+        assert_eq!(resolve_assignee(SELLER, None, None), Ok(None));
+    }
+}
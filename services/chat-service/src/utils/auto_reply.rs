@@ -0,0 +1,182 @@
+// Auto-reply (away message) seller
+//
+// Seller bisa mengaktifkan balasan otomatis yang dikirim atas nama seller ketika customer
+// mengirim message pertama di conversation, atau mengirim message di luar jam aktif seller.
+// Dalam satu conversation auto-reply hanya dikirim sekali per cooldown, dan tidak dihitung
+// sebagai respon pertama seller untuk SLA (kolom messages.is_auto_reply).
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use chrono_tz::Tz;
+use shared::utils::clock::parse_clock;
+
+// Timezone default jam aktif seller
+pub const DEFAULT_TIMEZONE: &str = "Asia/Jakarta";
+
+// Jeda minimum antar auto-reply di conversation yang sama (override via AUTO_REPLY_COOLDOWN_MINUTES)
+pub const DEFAULT_AUTO_REPLY_COOLDOWN_MINUTES: i64 = 720;
+
+// Jam aktif seller dalam jam lokal, boleh melewati tengah malam (20:00-02:00)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl ActiveHours {
+    // Jendela kosong (start == end) tidak valid
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Option<Self> {
+        (start != end).then_some(Self { start, end })
+    }
+
+    // Start inklusif, end eksklusif
+    pub fn contains(&self, local: NaiveTime) -> bool {
+        if self.start < self.end {
+            local >= self.start && local < self.end
+        } else {
+            local >= self.start || local < self.end
+        }
+    }
+}
+
+// Pengaturan auto-reply seller yang sudah diparse
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoReplySettings {
+    pub enabled: bool,
+    pub message: String,
+    // None = tanpa jam aktif, auto-reply hanya untuk message pertama
+    pub active_hours: Option<ActiveHours>,
+    pub timezone: Tz,
+}
+
+impl AutoReplySettings {
+    // Dari kolom seller_auto_replies, timezone tidak dikenal jatuh ke default
+    pub fn from_stored(
+        enabled: bool,
+        message: String,
+        start: Option<NaiveTime>,
+        end: Option<NaiveTime>,
+        timezone: &str,
+    ) -> Self {
+        Self {
+            enabled,
+            message,
+            active_hours: start.zip(end).and_then(|(start, end)| ActiveHours::new(start, end)),
+            timezone: parse_timezone(timezone).unwrap_or(chrono_tz::Asia::Jakarta),
+        }
+    }
+
+    pub fn is_outside_active_hours(&self, now: DateTime<Utc>) -> bool {
+        self.active_hours
+            .is_some_and(|hours| !hours.contains(now.with_timezone(&self.timezone).time()))
+    }
+}
+
+pub fn parse_timezone(timezone: &str) -> Option<Tz> {
+    timezone.parse::<Tz>().ok()
+}
+
+// Start/end harus diisi berpasangan, keduanya kosong berarti tanpa jam aktif
+pub fn parse_active_hours(start: Option<&str>, end: Option<&str>) -> Result<Option<ActiveHours>, &'static str> {
+    match (start, end) {
+        (None, None) => Ok(None),
+        (Some(start), Some(end)) => {
+            let start = parse_clock(start).ok_or("active_start harus berformat HH:MM")?;
+            let end = parse_clock(end).ok_or("active_end harus berformat HH:MM")?;
+            ActiveHours::new(start, end)
+                .map(Some)
+                .ok_or("active_start dan active_end tidak boleh sama")
+        }
+        _ => Err("active_start dan active_end harus diisi berpasangan"),
+    }
+}
+
+// Kirim auto-reply jika aktif, dipicu message pertama customer atau message di luar jam aktif,
+// dan auto-reply terakhir di conversation ini sudah lewat cooldown
+pub fn should_auto_reply(
+    settings: &AutoReplySettings,
+    is_first_message: bool,
+    last_auto_reply_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    cooldown: Duration,
+) -> bool {
+    if !settings.enabled || settings.message.trim().is_empty() {
+        return false;
+    }
+
+    if last_auto_reply_at.is_some_and(|last| now - last < cooldown) {
+        return false;
+    }
+
+    is_first_message || settings.is_outside_active_hours(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    // 09:00-17:00 WIB
+    fn settings() -> AutoReplySettings {
+        AutoReplySettings::from_stored(
+            true,
+            "Terima kasih, kami balas di jam kerja".to_string(),
+            Some(time(9)),
+            Some(time(17)),
+            DEFAULT_TIMEZONE,
+        )
+    }
+
+    // Jam lokal WIB (UTC+7)
+    fn wib(hour: u32) -> DateTime<Utc> {
+        chrono_tz::Asia::Jakarta
+            .with_ymd_and_hms(2025, 3, 10, hour, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_first_message_triggers_auto_reply() {
+        let cooldown = Duration::minutes(DEFAULT_AUTO_REPLY_COOLDOWN_MINUTES);
+
+        // Jam kerja: hanya message pertama yang dibalas otomatis
+        assert!(should_auto_reply(&settings(), true, None, wib(10), cooldown));
+        assert!(!should_auto_reply(&settings(), false, None, wib(10), cooldown));
+
+        // Di luar jam aktif: message berikutnya juga dibalas
+        assert!(should_auto_reply(&settings(), false, None, wib(20), cooldown));
+
+        let disabled = AutoReplySettings { enabled: false, ..settings() };
+        assert!(!should_auto_reply(&disabled, true, None, wib(20), cooldown));
+
+        // Tanpa jam aktif hanya message pertama
+        let always_active = AutoReplySettings { active_hours: None, ..settings() };
+        assert!(!should_auto_reply(&always_active, false, None, wib(3), cooldown));
+    }
+
+    #[test]
+    fn test_cooldown_limits_auto_reply_per_conversation() {
+        let cooldown = Duration::minutes(60);
+        let now = wib(22);
+
+        assert!(!should_auto_reply(&settings(), false, Some(now - Duration::minutes(59)), now, cooldown));
+        assert!(should_auto_reply(&settings(), false, Some(now - Duration::minutes(60)), now, cooldown));
+    }
+
+    #[test]
+    fn test_active_hours_across_midnight() {
+        let night_shift = ActiveHours::new(time(20), time(2)).unwrap();
+        assert!(night_shift.contains(time(23)));
+        assert!(night_shift.contains(time(1)));
+        assert!(!night_shift.contains(time(2)));
+        assert!(!night_shift.contains(time(12)));
+        assert!(ActiveHours::new(time(9), time(9)).is_none());
+
+        assert_eq!(parse_active_hours(Some("20:00"), Some("02:00")), Ok(Some(night_shift)));
+        assert!(parse_active_hours(Some("20:00"), None).is_err());
+        assert!(parse_active_hours(Some("25:00"), Some("02:00")).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+// Aturan siapa yang boleh memulai conversation baru
+
+use std::future::Future;
+
+// Pihak conversation yang akan dibuat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConversationParties {
+    pub customer_id: i32,
+    pub seller_id: i32,
+    pub initiated_by_seller: bool,
+}
+
+// Alasan request memulai conversation ditolak
+#[derive(Debug, PartialEq)]
+pub enum InitiationError {
+    Forbidden(&'static str),
+    BadRequest(&'static str),
+    NotFound(&'static str),
+}
+
+// Tentukan customer/seller dari role pembuat dan isi request
+pub fn resolve_parties(
+    user_id: i32,
+    role: &str,
+    seller_id: Option<i32>,
+    customer_id: Option<i32>,
+) -> Result<ConversationParties, InitiationError> {
+    let parties = match role {
+        "customer" => ConversationParties {
+            customer_id: user_id,
+            seller_id: seller_id.ok_or(InitiationError::BadRequest("seller_id wajib diisi"))?,
+            initiated_by_seller: false,
+        },
+        "seller" => {
+            if seller_id.is_some_and(|id| id != user_id) {
+                return Err(InitiationError::Forbidden("Seller hanya bisa membuat conversation atas namanya sendiri"));
+            }
+
+            ConversationParties {
+                customer_id: customer_id.ok_or(InitiationError::BadRequest("customer_id wajib diisi untuk follow-up seller"))?,
+                seller_id: user_id,
+                initiated_by_seller: true,
+            }
+        }
+        _ => return Err(InitiationError::Forbidden("Hanya customer dan seller yang bisa membuat conversation")),
+    };
+
+    if parties.customer_id == parties.seller_id {
+        return Err(InitiationError::BadRequest("Tidak bisa membuat conversation dengan diri sendiri"));
+    }
+
+    Ok(parties)
+}
+
+// Seller hanya boleh follow-up customer yang pernah order/test drive, cold outreach ditolak
+pub fn check_seller_outreach(
+    parties: &ConversationParties,
+    has_booking_relationship: bool,
+) -> Result<(), InitiationError> {
+    if parties.initiated_by_seller && !has_booking_relationship {
+        return Err(InitiationError::Forbidden(
+            "Seller hanya bisa memulai conversation dengan customer yang pernah order atau test drive",
+        ));
+    }
+
+    Ok(())
+}
+
+// Conversation tentang vehicle hanya boleh dengan seller pemilik vehicle itu
+pub fn check_vehicle_owner(
+    parties: &ConversationParties,
+    vehicle_owner: Option<i32>,
+) -> Result<(), InitiationError> {
+    match vehicle_owner {
+        None => Err(InitiationError::NotFound("Vehicle tidak ditemukan")),
+        Some(owner) if owner != parties.seller_id => {
+            Err(InitiationError::BadRequest("Seller bukan pemilik vehicle ini"))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+// Hasil find-or-create conversation
+#[derive(Debug, PartialEq)]
+pub enum FindOrCreate<F, C> {
+    Existing(F),
+    Created(C),
+}
+
+// Cari conversation lalu insert jika belum ada. Insert memakai ON CONFLICT DO NOTHING, jadi
+// request yang kalah race (insert -> None) membaca ulang conversation milik pemenang.
+// None hanya jika conversation pemenang sudah hilang lagi saat dibaca ulang
+pub async fn find_or_create<F, C, E, Find, FindFut, Insert, InsertFut>(
+    mut find: Find,
+    insert: Insert,
+) -> Result<Option<FindOrCreate<F, C>>, E>
+where
+    Find: FnMut() -> FindFut,
+    FindFut: Future<Output = Result<Option<F>, E>>,
+    Insert: FnOnce() -> InsertFut,
+    InsertFut: Future<Output = Result<Option<C>, E>>,
+{
+    if let Some(existing) = find().await? {
+        return Ok(Some(FindOrCreate::Existing(existing)));
+    }
+
+    if let Some(created) = insert().await? {
+        return Ok(Some(FindOrCreate::Created(created)));
+    }
+
+    Ok(find().await?.map(FindOrCreate::Existing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seller_follow_up_allowed_with_booking_relationship() {
+        let parties = resolve_parties(7, "seller", None, Some(3)).unwrap();
+        assert_eq!(parties, ConversationParties { customer_id: 3, seller_id: 7, initiated_by_seller: true });
+        assert!(check_seller_outreach(&parties, true).is_ok());
+
+        // seller_id boleh dikirim selama milik seller itu sendiri
+        assert!(resolve_parties(7, "seller", Some(7), Some(3)).is_ok());
+    }
+
+    #[test]
+    fn test_seller_cold_outreach_and_invalid_requests_rejected() {
+        let parties = resolve_parties(7, "seller", None, Some(3)).unwrap();
+        assert!(matches!(check_seller_outreach(&parties, false), Err(InitiationError::Forbidden(_))));
+
+        assert!(matches!(resolve_parties(7, "seller", None, None), Err(InitiationError::BadRequest(_))));
+        assert!(matches!(resolve_parties(7, "seller", Some(8), Some(3)), Err(InitiationError::Forbidden(_))));
+        assert!(matches!(resolve_parties(7, "seller", None, Some(7)), Err(InitiationError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_customer_initiation_unchanged() {
+        let parties = resolve_parties(3, "customer", Some(7), None).unwrap();
+        assert!(!parties.initiated_by_seller);
+        assert!(check_seller_outreach(&parties, false).is_ok());
+
+        assert!(matches!(resolve_parties(3, "customer", Some(3), None), Err(InitiationError::BadRequest(_))));
+        assert!(matches!(resolve_parties(3, "customer", None, None), Err(InitiationError::BadRequest(_))));
+        assert!(matches!(resolve_parties(3, "admin", Some(7), None), Err(InitiationError::Forbidden(_))));
+    }
+
+    #[test]
+    fn test_claimed_seller_must_own_vehicle() {
+        let parties = resolve_parties(3, "customer", Some(7), None).unwrap();
+        assert!(check_vehicle_owner(&parties, Some(7)).is_ok());
+
+        // Customer mengklaim seller 7 untuk vehicle milik seller 9
+        assert_eq!(
+            check_vehicle_owner(&parties, Some(9)),
+            Err(InitiationError::BadRequest("Seller bukan pemilik vehicle ini"))
+        );
+        assert!(matches!(check_vehicle_owner(&parties, None), Err(InitiationError::NotFound(_))));
+
+        // Follow-up seller juga hanya untuk vehicle miliknya
+        let parties = resolve_parties(7, "seller", None, Some(3)).unwrap();
+        assert!(check_vehicle_owner(&parties, Some(7)).is_ok());
+        assert!(check_vehicle_owner(&parties, Some(9)).is_err());
+    }
+
+    // Create bersamaan terhadap unique index asli ada di repositories::conversation_repo::tests
+}
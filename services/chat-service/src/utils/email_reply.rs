@@ -0,0 +1,166 @@
+// Bersihkan balasan email sebelum diposting sebagai message chat
+//
+// Email client menyertakan riwayat kutipan dan signature di bawah balasan;
+// yang diposting ke chat hanya teks baru yang ditulis user.
+
+// Ambil alamat email dari header From ("Nama <user@mail.com>" atau "user@mail.com")
+pub fn extract_address(header: &str) -> Option<String> {
+    let header = header.trim();
+    let address = match (header.rfind('<'), header.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &header[start + 1..end],
+        _ => header,
+    };
+
+    let address = address.trim();
+    address.contains('@').then(|| address.to_lowercase())
+}
+
+// Token reply terikat ke user; hanya email dari alamat user tersebut yang boleh memakai token
+pub fn sender_is_token_user(from_header: &str, user_email: &str) -> bool {
+    extract_address(from_header).is_some_and(|sender| sender == user_email.trim().to_lowercase())
+}
+
+// Nilai header dari blok header mentah email (field "headers" SendGrid), termasuk header
+// yang dilipat ke baris berikutnya
+pub fn raw_header_value(raw_headers: &str, name: &str) -> Option<String> {
+    let mut lines = raw_headers.lines().map(|line| line.trim_end_matches('\r')).peekable();
+
+    while let Some(line) = lines.next() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !key.trim().eq_ignore_ascii_case(name) {
+            continue;
+        }
+
+        let mut value = value.trim().to_string();
+        while let Some(next) = lines.next_if(|next| next.starts_with([' ', '\t'])) {
+            value.push(' ');
+            value.push_str(next.trim());
+        }
+
+        let value = value.trim();
+        return (!value.is_empty()).then(|| value.to_string());
+    }
+
+    None
+}
+
+// Baris penanda awal riwayat kutipan (Gmail/Outlook, EN/ID)
+fn is_quote_header(line: &str) -> bool {
+    let line = line.trim();
+    let lower = line.to_lowercase();
+
+    (lower.starts_with("on ") && lower.ends_with("wrote:"))
+        || (lower.starts_with("pada ") && lower.ends_with("menulis:"))
+        || lower.starts_with("-----original message-----")
+        || lower.starts_with("-----pesan asli-----")
+        || lower.starts_with("________________________________")
+        || (lower.starts_with("from:") && lower.contains('@'))
+}
+
+// Signature otomatis dari aplikasi mobile
+fn is_mobile_signature(line: &str) -> bool {
+    let lower = line.trim().to_lowercase();
+    lower.starts_with("sent from my ") || lower.starts_with("dikirim dari ")
+}
+
+// Potong kutipan dan signature, return teks balasan saja
+pub fn strip_quoted_reply(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+
+        // Delimiter signature standar "-- " dan semua setelah header kutipan dibuang
+        if line == "-- " || line == "--" || is_quote_header(line) {
+            break;
+        }
+
+        if line.trim_start().starts_with('>') || is_mobile_signature(line) {
+            continue;
+        }
+
+        lines.push(line);
+    }
+
+    // Gmail kadang memecah "On ... wrote:" menjadi dua baris
+    if let Some(last) = lines.iter().rposition(|line| !line.trim().is_empty()) {
+        let tail = lines[last].trim().to_lowercase();
+        if tail.ends_with("wrote:") || tail.ends_with("menulis:") {
+            let start = lines[..last]
+                .iter()
+                .rposition(|line| line.trim().is_empty())
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let header = lines[start..=last].join(" ").to_lowercase();
+            if header.trim_start().starts_with("on ") || header.trim_start().starts_with("pada ") {
+                lines.truncate(start);
+            }
+        }
+    }
+
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_address() {
+        assert_eq!(extract_address("Budi <Budi@Mail.com>").as_deref(), Some("budi@mail.com"));
+        assert_eq!(extract_address(" budi@mail.com ").as_deref(), Some("budi@mail.com"));
+        assert_eq!(extract_address("Budi"), None);
+    }
+
+    #[test]
+    fn test_sender_must_be_token_user() {
+        assert!(sender_is_token_user("Budi <Budi@Mail.com>", "budi@mail.com"));
+        assert!(sender_is_token_user("budi@mail.com", " BUDI@mail.com"));
+        // Email lain yang mendapat alamat reply (forward/CC) tidak boleh posting atas nama user
+        assert!(!sender_is_token_user("Andi <andi@mail.com>", "budi@mail.com"));
+        assert!(!sender_is_token_user("Budi", "budi@mail.com"));
+    }
+
+    #[test]
+    fn test_raw_header_value() {
+        let headers = "Received: from mx.mail.com\r\nMessage-ID:\r\n <abc@mail.com>\r\nSubject: Re: Pesan baru\r\n";
+        assert_eq!(raw_header_value(headers, "message-id").as_deref(), Some("<abc@mail.com>"));
+        assert_eq!(raw_header_value(headers, "Subject").as_deref(), Some("Re: Pesan baru"));
+        assert_eq!(raw_header_value(headers, "In-Reply-To"), None);
+    }
+
+    #[test]
+    fn test_strip_gmail_quote() {
+        let text = "Masih tersedia?\n\nOn Mon, 3 Jun 2024 at 10:00, Big Auto <reply+1.2.ab@bigauto.com> wrote:\n> Halo, ada pesan baru\n> dari seller";
+        assert_eq!(strip_quoted_reply(text), "Masih tersedia?");
+    }
+
+    #[test]
+    fn test_strip_wrapped_quote_header() {
+        let text = "Oke, besok saya cek.\n\nOn Mon, 3 Jun 2024 at 10:00, Big Auto <\nreply+1.2.ab@bigauto.com> wrote:\n> Halo";
+        assert_eq!(strip_quoted_reply(text), "Oke, besok saya cek.");
+    }
+
+    #[test]
+    fn test_strip_indonesian_quote_and_signature() {
+        let text = "Bisa nego?\r\n\r\nDikirim dari iPhone saya\r\nPada Sen, 3 Jun 2024 pukul 10.00 Big Auto menulis:\r\n> Halo";
+        assert_eq!(strip_quoted_reply(text), "Bisa nego?");
+    }
+
+    #[test]
+    fn test_strip_signature_and_outlook_quote() {
+        assert_eq!(strip_quoted_reply("Siap\n-- \nBudi Santoso\n0812"), "Siap");
+        assert_eq!(
+            strip_quoted_reply("Deal.\n\n-----Original Message-----\nFrom: Big Auto"),
+            "Deal."
+        );
+    }
+
+    #[test]
+    fn test_plain_reply_kept() {
+        assert_eq!(strip_quoted_reply("Baris 1\nBaris 2\n"), "Baris 1\nBaris 2");
+        assert_eq!(strip_quoted_reply("> hanya kutipan"), "");
+    }
+}
@@ -0,0 +1,246 @@
+// Pemindaian malware untuk attachment chat sebelum message disimpan
+
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::config::AppConfig;
+
+// Ukuran chunk untuk protokol INSTREAM ClamAV
+const CLAMAV_CHUNK_SIZE: usize = 64 * 1024;
+
+// Backend scanner yang dipilih lewat FILE_SCAN_BACKEND
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanBackend {
+    Disabled,
+    ClamAv { address: String },
+    HttpGateway { url: String },
+}
+
+impl ScanBackend {
+    // Parse backend dari konfigurasi environment
+    pub fn from_config(config: &AppConfig) -> Result<Self, String> {
+        match config.file_scan_backend.to_lowercase().as_str() {
+            "disabled" | "none" | "" => Ok(ScanBackend::Disabled),
+            "clamav" => Ok(ScanBackend::ClamAv {
+                address: config.clamav_address.clone(),
+            }),
+            "http" => {
+                let url = config.av_gateway_url.clone()
+                    .ok_or("AV_GATEWAY_URL harus diset jika FILE_SCAN_BACKEND=http")?;
+                Ok(ScanBackend::HttpGateway { url })
+            }
+            other => Err(format!("FILE_SCAN_BACKEND tidak dikenal: {}", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScanBackend::Disabled => "disabled",
+            ScanBackend::ClamAv { .. } => "clamav",
+            ScanBackend::HttpGateway { .. } => "http",
+        }
+    }
+}
+
+// Hasil pemindaian satu file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanResult {
+    Clean,
+    Infected { signature: String },
+    Skipped,
+}
+
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("Gagal download file untuk scanning: {0}")]
+    Download(String),
+
+    #[error("Scanner tidak bisa dihubungi: {0}")]
+    Connection(String),
+
+    #[error("Response scanner tidak valid: {0}")]
+    InvalidResponse(String),
+
+    #[error("Scanning melebihi batas waktu {0} detik")]
+    Timeout(u64),
+}
+
+// Response dari HTTP AV gateway
+#[derive(Debug, serde::Deserialize)]
+struct GatewayScanResponse {
+    infected: bool,
+    signature: Option<String>,
+}
+
+// Scanner attachment yang di-share lewat AppState
+#[derive(Debug, Clone)]
+pub struct FileScanner {
+    backend: ScanBackend,
+    http_client: reqwest::Client,
+    timeout: Duration,
+    fail_open: bool,
+}
+
+impl FileScanner {
+    pub fn new(backend: ScanBackend, http_client: reqwest::Client, timeout_secs: u64, fail_open: bool) -> Self {
+        Self {
+            backend,
+            http_client,
+            timeout: Duration::from_secs(timeout_secs),
+            fail_open,
+        }
+    }
+
+    pub fn backend(&self) -> &ScanBackend {
+        &self.backend
+    }
+
+    pub fn is_fail_open(&self) -> bool {
+        self.fail_open
+    }
+
+    // Scan file berdasarkan URL dengan timeout
+    pub async fn scan_file(&self, url: &str) -> Result<ScanResult, ScanError> {
+        let scan = async {
+            match &self.backend {
+                ScanBackend::Disabled => Ok(ScanResult::Skipped),
+                ScanBackend::ClamAv { address } => {
+                    let bytes = self.download(url).await?;
+                    scan_with_clamav(address, &bytes).await
+                }
+                ScanBackend::HttpGateway { url: gateway_url } => {
+                    self.scan_with_gateway(gateway_url, url).await
+                }
+            }
+        };
+
+        tokio::time::timeout(self.timeout, scan)
+            .await
+            .map_err(|_| ScanError::Timeout(self.timeout.as_secs()))?
+    }
+
+    // Download isi file dari storage
+    async fn download(&self, url: &str) -> Result<Vec<u8>, ScanError> {
+        let response = self.http_client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| ScanError::Download(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ScanError::Download(format!("HTTP {}", response.status())));
+        }
+
+        response
+            .bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| ScanError::Download(e.to_string()))
+    }
+
+    // Kirim URL file ke HTTP AV gateway
+    async fn scan_with_gateway(&self, gateway_url: &str, file_url: &str) -> Result<ScanResult, ScanError> {
+        let response = self.http_client
+            .post(gateway_url)
+            .json(&serde_json::json!({ "url": file_url }))
+            .send()
+            .await
+            .map_err(|e| ScanError::Connection(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ScanError::InvalidResponse(format!("HTTP {}", response.status())));
+        }
+
+        let result: GatewayScanResponse = response
+            .json()
+            .await
+            .map_err(|e| ScanError::InvalidResponse(e.to_string()))?;
+
+        if result.infected {
+            Ok(ScanResult::Infected {
+                signature: result.signature.unwrap_or_else(|| "unknown".to_string()),
+            })
+        } else {
+            Ok(ScanResult::Clean)
+        }
+    }
+}
+
+// Scan bytes via protokol INSTREAM ClamAV (clamd TCP)
+async fn scan_with_clamav(address: &str, bytes: &[u8]) -> Result<ScanResult, ScanError> {
+    let mut stream = TcpStream::connect(address)
+        .await
+        .map_err(|e| ScanError::Connection(e.to_string()))?;
+
+    stream.write_all(b"zINSTREAM\0")
+        .await
+        .map_err(|e| ScanError::Connection(e.to_string()))?;
+
+    for chunk in bytes.chunks(CLAMAV_CHUNK_SIZE) {
+        stream.write_all(&(chunk.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| ScanError::Connection(e.to_string()))?;
+        stream.write_all(chunk)
+            .await
+            .map_err(|e| ScanError::Connection(e.to_string()))?;
+    }
+
+    // Chunk dengan panjang 0 menandakan akhir stream
+    stream.write_all(&0u32.to_be_bytes())
+        .await
+        .map_err(|e| ScanError::Connection(e.to_string()))?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)
+        .await
+        .map_err(|e| ScanError::Connection(e.to_string()))?;
+
+    parse_clamav_reply(&String::from_utf8_lossy(&reply))
+}
+
+// Parse reply clamd, contoh: "stream: OK" atau "stream: Eicar-Signature FOUND"
+fn parse_clamav_reply(reply: &str) -> Result<ScanResult, ScanError> {
+    let reply = reply.trim_end_matches('\0').trim();
+    let status = reply.strip_prefix("stream:").map(str::trim).unwrap_or(reply);
+
+    if status == "OK" {
+        Ok(ScanResult::Clean)
+    } else if let Some(signature) = status.strip_suffix("FOUND") {
+        Ok(ScanResult::Infected {
+            signature: signature.trim().to_string(),
+        })
+    } else {
+        Err(ScanError::InvalidResponse(reply.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_clamav_clean_reply() {
+        assert_eq!(parse_clamav_reply("stream: OK\0").unwrap(), ScanResult::Clean);
+    }
+
+    #[test]
+    fn test_parse_clamav_infected_reply() {
+        let result = parse_clamav_reply("stream: Eicar-Test-Signature FOUND\0").unwrap();
+        assert_eq!(result, ScanResult::Infected { signature: "Eicar-Test-Signature".to_string() });
+    }
+
+    #[test]
+    fn test_parse_clamav_error_reply() {
+        let result = parse_clamav_reply("INSTREAM size limit exceeded. ERROR\0");
+        assert!(matches!(result, Err(ScanError::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_backend_skips_scan() {
+        let scanner = FileScanner::new(ScanBackend::Disabled, reqwest::Client::new(), 5, false);
+        let result = scanner.scan_file("https://res.cloudinary.com/demo/file.pdf").await.unwrap();
+        assert_eq!(result, ScanResult::Skipped);
+    }
+}
@@ -0,0 +1,173 @@
+// Proxy attachment chat agar URL storage mentah tidak keluar di response
+//
+// Attachment yang tersimpan di storage kita diganti path /messages/{id}/media.
+// Endpoint itu mengecek requester adalah participant conversation sebelum file di-stream.
+// URL di luar storage (link publik eksternal) tidak bisa diproxy dan tetap apa adanya.
+
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+use crate::utils::assignment::is_visible_to;
+
+// Varian media yang diminta lewat ?variant=
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaVariant {
+    #[default]
+    Original,
+    Thumbnail,
+}
+
+// Media sebuah message beserta participant conversation-nya
+#[derive(Debug, Clone)]
+pub struct MessageMedia {
+    pub customer_id: i32,
+    pub seller_id: i32,
+    // Staff dealer yang di-assign ke conversation
+    pub assigned_to: Option<i32>,
+    pub media_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+}
+
+// Path proxy yang menggantikan URL storage di response API
+pub fn media_path(message_id: i32, variant: MediaVariant) -> String {
+    match variant {
+        MediaVariant::Original => format!("/messages/{}/media", message_id),
+        MediaVariant::Thumbnail => format!("/messages/{}/media?variant=thumbnail", message_id),
+    }
+}
+
+// Path proxy attachment asli message yang sudah dihapus (khusus admin moderasi)
+pub fn original_media_path(message_id: i32) -> String {
+    format!("/admin/messages/{}/original/media", message_id)
+}
+
+// URL yang boleh tampil di response: object storage privat jadi path proxy, URL publik tetap
+pub fn proxied_url(
+    url: Option<String>,
+    message_id: i32,
+    variant: MediaVariant,
+    is_private: impl Fn(&str) -> bool,
+) -> Option<String> {
+    url.map(|url| if is_private(&url) { media_path(message_id, variant) } else { url })
+}
+
+// URL storage yang boleh di-stream: bukan participant 403, media sudah tidak ada 404
+pub fn authorize_media(media: &MessageMedia, user_id: i32, variant: MediaVariant) -> Result<&str, AppError> {
+    if !is_visible_to(user_id, media.customer_id, media.seller_id, media.assigned_to) {
+        return Err(AppError::forbidden("Tidak memiliki akses ke media ini"));
+    }
+
+    let url = match variant {
+        MediaVariant::Original => media.media_url.as_deref(),
+        MediaVariant::Thumbnail => media.thumbnail_url.as_deref(),
+    };
+
+    url.ok_or_else(|| AppError::not_found("Media tidak ditemukan"))
+}
+
+// Content-Type attachment dari ekstensi URL (sesuai tipe yang boleh diupload)
+pub fn content_type_for(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        Some("doc") => "application/msword",
+        Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some("txt") => "text/plain",
+        Some("csv") => "text/csv",
+        Some("mp3") => "audio/mpeg",
+        Some("m4a") => "audio/mp4",
+        Some("aac") => "audio/aac",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    fn media() -> MessageMedia {
+        MessageMedia {
+            customer_id: 10,
+            seller_id: 20,
+            assigned_to: Some(30),
+            media_url: Some("https://res.cloudinary.com/bigauto/image/upload/chat/a.jpg".to_string()),
+            thumbnail_url: None,
+        }
+    }
+
+    #[test]
+    fn test_non_participant_forbidden() {
+        let err = authorize_media(&media(), 99, MediaVariant::Original).unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_participants_get_storage_url() {
+        let media = media();
+        for user_id in [10, 20, 30] {
+            assert_eq!(authorize_media(&media, user_id, MediaVariant::Original).unwrap(), media.media_url.as_deref().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_unassigned_staff_forbidden() {
+        // Staff lain (atau staff yang assignment-nya sudah dicabut) tidak boleh membuka media
+        let mut media = media();
+        assert!(matches!(authorize_media(&media, 31, MediaVariant::Original), Err(AppError::Forbidden(_))));
+
+        media.assigned_to = None;
+        assert!(matches!(authorize_media(&media, 30, MediaVariant::Original), Err(AppError::Forbidden(_))));
+    }
+
+    #[test]
+    fn test_original_media_path_is_admin_only_route() {
+        assert_eq!(original_media_path(7), "/admin/messages/7/original/media");
+        assert_ne!(original_media_path(7), media_path(7, MediaVariant::Original));
+    }
+
+    #[test]
+    fn test_missing_variant_not_found() {
+        let mut media = media();
+        assert!(matches!(authorize_media(&media, 10, MediaVariant::Thumbnail), Err(AppError::NotFound(_))));
+
+        // Media message yang sudah dihapus dikosongkan di DB
+        media.media_url = None;
+        assert!(matches!(authorize_media(&media, 10, MediaVariant::Original), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_only_private_urls_proxied() {
+        let owned = |url: &str| url.starts_with("https://res.cloudinary.com/");
+
+        assert_eq!(
+            proxied_url(Some("https://res.cloudinary.com/bigauto/chat/a.jpg".to_string()), 5, MediaVariant::Original, owned),
+            Some("/messages/5/media".to_string())
+        );
+        assert_eq!(
+            proxied_url(Some("https://res.cloudinary.com/bigauto/chat/a.jpg?w=200".to_string()), 5, MediaVariant::Thumbnail, owned),
+            Some("/messages/5/media?variant=thumbnail".to_string())
+        );
+        assert_eq!(
+            proxied_url(Some("https://example.com/brosur.pdf".to_string()), 5, MediaVariant::Original, owned),
+            Some("https://example.com/brosur.pdf".to_string())
+        );
+        assert_eq!(proxied_url(None, 5, MediaVariant::Original, owned), None);
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        assert_eq!(content_type_for("https://res.cloudinary.com/bigauto/chat/a.JPG?w=200&h=200"), "image/jpeg");
+        assert_eq!(content_type_for("http://localhost:9000/bigauto/chat/kontrak.pdf"), "application/pdf");
+        assert_eq!(content_type_for("https://res.cloudinary.com/bigauto/raw/upload/file"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,120 @@
+// Email notifikasi message chat baru dengan Reply-To alamat reply+ (reply-by-email)
+//
+// Penerima yang belum membaca conversation mendapat satu email per "putaran" unread:
+// hanya message pertama sejak terakhir dibaca yang memicu email, message berikutnya
+// sudah terwakili email tersebut. Balasan ke alamat Reply-To masuk lewat webhook inbound
+// (handlers::inbound_email) dan diposting sebagai message atas nama penerima.
+
+use serde_json::json;
+use shared::utils::inbound_email::reply_address;
+
+// Panjang preview isi message di badan email (karakter)
+const EMAIL_PREVIEW_LEN: usize = 280;
+
+// Kirim email hanya saat unread penerima baru saja naik dari 0 ke 1
+pub fn should_email(recipient_unread: i32) -> bool {
+    recipient_unread == 1
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageEmail {
+    pub to: String,
+    pub reply_to: String,
+    pub subject: String,
+    pub text: String,
+}
+
+// Email message baru untuk penerima, alamat reply terikat ke conversation + penerima
+#[allow(clippy::too_many_arguments)]
+pub fn build_message_email(
+    secret: &[u8],
+    reply_domain: &str,
+    expires_at: i64,
+    conversation_id: i32,
+    recipient_id: i32,
+    recipient_email: &str,
+    sender_name: &str,
+    content: &str,
+) -> MessageEmail {
+    let mut preview: String = content.chars().take(EMAIL_PREVIEW_LEN).collect();
+    if content.chars().count() > EMAIL_PREVIEW_LEN {
+        preview.push('…');
+    }
+
+    MessageEmail {
+        to: recipient_email.to_string(),
+        reply_to: reply_address(secret, reply_domain, conversation_id, recipient_id, expires_at),
+        subject: format!("Pesan baru dari {}", sender_name),
+        text: format!(
+            "{} mengirim pesan:\n\n{}\n\nBalas email ini untuk menjawab langsung di chat Big Auto.",
+            sender_name, preview
+        ),
+    }
+}
+
+// Kirim lewat Resend API
+pub async fn send_via_resend(
+    client: &reqwest::Client,
+    api_key: &str,
+    from: &str,
+    email: &MessageEmail,
+) -> Result<(), String> {
+    let response = client
+        .post("https://api.resend.com/emails")
+        .bearer_auth(api_key)
+        .json(&json!({
+            "from": from,
+            "to": [email.to],
+            "reply_to": email.reply_to,
+            "subject": email.subject,
+            "text": email.text,
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Gagal menghubungi Resend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Resend menolak email: HTTP {}", response.status()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::utils::inbound_email::{reply_token_from_address, verify_reply_token};
+
+    const SECRET: &[u8] = b"reply-secret";
+
+    #[test]
+    fn test_only_first_unread_message_is_emailed() {
+        assert!(should_email(1));
+        assert!(!should_email(0));
+        assert!(!should_email(2));
+    }
+
+    #[test]
+    fn test_email_reply_to_resolves_to_recipient() {
+        let expires_at = 1_700_000_000 + 86_400;
+        let email = build_message_email(
+            SECRET, "reply.bigauto.com", expires_at, 42, 7, "buyer@mail.com", "Dealer Maju", "Unitnya masih ada?",
+        );
+
+        assert_eq!(email.to, "buyer@mail.com");
+        assert_eq!(email.subject, "Pesan baru dari Dealer Maju");
+        assert!(email.text.contains("Unitnya masih ada?"));
+
+        // Balasan ke Reply-To diposting sebagai penerima email, bukan pengirim message
+        let token = reply_token_from_address(&email.reply_to).unwrap();
+        assert_eq!(verify_reply_token(SECRET, token, 1_700_000_000), Some((42, 7)));
+        assert!(email.reply_to.ends_with("@reply.bigauto.com"));
+    }
+
+    #[test]
+    fn test_long_content_preview_truncated() {
+        let content = "a".repeat(EMAIL_PREVIEW_LEN + 10);
+        let email = build_message_email(SECRET, "reply.bigauto.com", 0, 1, 2, "x@mail.com", "Seller", &content);
+        assert!(email.text.contains(&format!("{}…", "a".repeat(EMAIL_PREVIEW_LEN))));
+    }
+}
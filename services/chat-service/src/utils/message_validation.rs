@@ -0,0 +1,208 @@
+// Validasi isi pesan dan attachment chat sebelum disimpan
+//
+// Mode strict (AppConfig::strict_validation, aktif di production) vs relaxed (development):
+// - Karakter kontrol di isi pesan: strict ditolak, relaxed diterima (data seed/fixture)
+// - Ekstensi file upload: strict wajib cocok dengan Content-Type, relaxed percaya Content-Type
+// Selalu ditegakkan di kedua mode: pesan kosong tanpa media, panjang maksimal pesan,
+// jumlah/ukuran file (termasuk jumlah attachment per message), whitelist Content-Type upload,
+// dan URL attachment wajib milik storage backend (attachment diunduh server untuk scan malware).
+use crate::error::AppError;
+
+// Default jumlah attachment per message (override via MAX_ATTACHMENTS_PER_MESSAGE)
+pub const DEFAULT_MAX_ATTACHMENTS_PER_MESSAGE: usize = 5;
+
+// Content boleh kosong hanya jika ada media yang dilampirkan
+pub fn validate_message_content(
+    content: &str,
+    has_media: bool,
+    max_length: usize,
+    strict: bool,
+) -> Result<(), AppError> {
+    if content.trim().is_empty() && !has_media {
+        return Err(AppError::validation(
+            "Isi pesan tidak boleh kosong kecuali ada file yang dilampirkan",
+        ));
+    }
+
+    let length = content.chars().count();
+    if length > max_length {
+        return Err(AppError::validation(format!(
+            "Isi pesan terlalu panjang ({} karakter). Maksimal {} karakter",
+            length, max_length
+        )));
+    }
+
+    // Newline dan tab tetap boleh untuk pesan multi-baris
+    if strict && content.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) {
+        return Err(AppError::validation("Isi pesan mengandung karakter yang tidak diizinkan"));
+    }
+
+    Ok(())
+}
+
+// Jumlah attachment per message dibatasi (MAX_ATTACHMENTS_PER_MESSAGE), thumbnails jika
+// dikirim harus satu per file agar tidak salah pasang
+pub fn validate_attachment_count(
+    files: usize,
+    thumbnails: Option<usize>,
+    max_attachments: usize,
+) -> Result<(), AppError> {
+    if files > max_attachments {
+        return Err(AppError::validation(format!(
+            "Terlalu banyak file ({}). Maksimal {} file per message",
+            files, max_attachments
+        )));
+    }
+
+    if let Some(thumbnails) = thumbnails.filter(|count| *count != files) {
+        return Err(AppError::validation(format!(
+            "Jumlah thumbnails ({}) harus sama dengan jumlah files ({})",
+            thumbnails, files
+        )));
+    }
+
+    Ok(())
+}
+
+// URL attachment hanya boleh file hasil upload ke storage kita, URL lain akan diunduh
+// file scanner dari jaringan internal
+pub fn validate_attachment_url(url: &str, owned_by_storage: bool) -> Result<(), AppError> {
+    if owned_by_storage && (url.starts_with("https://") || url.starts_with("http://")) {
+        return Ok(());
+    }
+
+    Err(AppError::validation("URL file tidak valid"))
+}
+
+// Ekstensi yang sah untuk setiap Content-Type upload
+fn allowed_extensions(content_type: &str) -> &'static [&'static str] {
+    match content_type {
+        "image/jpeg" | "image/jpg" => &["jpg", "jpeg"],
+        "image/png" => &["png"],
+        "image/gif" => &["gif"],
+        "image/webp" => &["webp"],
+        "application/pdf" => &["pdf"],
+        "application/msword" => &["doc"],
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => &["docx"],
+        "text/plain" => &["txt"],
+        "text/csv" => &["csv"],
+        "audio/mpeg" => &["mp3"],
+        "audio/mp4" => &["m4a"],
+        "audio/aac" => &["aac"],
+        "audio/ogg" => &["ogg"],
+        "audio/wav" => &["wav"],
+        _ => &[],
+    }
+}
+
+// Nama file harus berekstensi sesuai Content-Type yang diklaim client
+pub fn validate_upload_extension(file_name: &str, content_type: &str, strict: bool) -> Result<(), AppError> {
+    if !strict {
+        return Ok(());
+    }
+
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+
+    if allowed_extensions(content_type).contains(&extension.as_str()) {
+        Ok(())
+    } else {
+        Err(AppError::validation(format!(
+            "Ekstensi file {} tidak sesuai dengan tipe {}",
+            file_name, content_type
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_content_with_media_is_allowed() {
+        assert!(validate_message_content("", true, 2000, true).is_ok());
+        assert!(validate_message_content("   ", true, 2000, true).is_ok());
+    }
+
+    #[test]
+    fn test_empty_content_without_media_is_rejected() {
+        let result = validate_message_content("  ", false, 2000, true);
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_over_length_content_is_rejected() {
+        let content = "a".repeat(101);
+        let result = validate_message_content(&content, false, 100, true);
+        assert!(matches!(result, Err(AppError::ValidationError(msg)) if msg.contains("Maksimal 100")));
+
+        // Media tidak membebaskan batas panjang caption
+        assert!(validate_message_content(&content, true, 100, true).is_err());
+    }
+
+    #[test]
+    fn test_length_counts_characters_not_bytes() {
+        let content = "🚗".repeat(100);
+        assert!(validate_message_content(&content, false, 100, true).is_ok());
+    }
+
+    #[test]
+    fn test_control_characters_rejected_only_in_production() {
+        let content = "halo\u{0007}seller";
+        assert!(validate_message_content(content, false, 2000, false).is_ok());
+        assert!(matches!(
+            validate_message_content(content, false, 2000, true),
+            Err(AppError::ValidationError(_))
+        ));
+
+        // Pesan multi-baris tetap valid di production
+        assert!(validate_message_content("baris 1\nbaris 2\tok", false, 2000, true).is_ok());
+    }
+
+    #[test]
+    fn test_length_limit_not_relaxed_in_development() {
+        let content = "a".repeat(101);
+        assert!(validate_message_content(&content, false, 100, false).is_err());
+        assert!(validate_message_content("  ", false, 100, false).is_err());
+    }
+
+    #[test]
+    fn test_attachment_count_at_and_beyond_limit() {
+        assert!(validate_attachment_count(5, None, 5).is_ok());
+        assert!(validate_attachment_count(0, None, 5).is_ok());
+
+        let result = validate_attachment_count(6, None, 5);
+        assert!(matches!(result, Err(AppError::ValidationError(msg)) if msg.contains("Maksimal 5")));
+        assert!(validate_attachment_count(300, Some(300), 5).is_err());
+    }
+
+    #[test]
+    fn test_thumbnails_must_match_files() {
+        assert!(validate_attachment_count(3, Some(3), 5).is_ok());
+        assert!(validate_attachment_count(3, Some(2), 5).is_err());
+        assert!(validate_attachment_count(0, Some(1), 5).is_err());
+    }
+
+    #[test]
+    fn test_foreign_attachment_url_always_rejected() {
+        // URL luar (termasuk host internal) ditolak di semua environment
+        assert!(validate_attachment_url("https://placehold.co/600x400.png", false).is_err());
+        assert!(validate_attachment_url("http://169.254.169.254/latest/meta-data", false).is_err());
+        assert!(validate_attachment_url("javascript:alert(1)", false).is_err());
+
+        assert!(validate_attachment_url("https://res.cloudinary.com/bigauto/image/upload/a.png", true).is_ok());
+    }
+
+    #[test]
+    fn test_upload_extension_mismatch_rejected_only_in_production() {
+        assert!(validate_upload_extension("fixture.exe", "image/png", false).is_ok());
+        assert!(validate_upload_extension("fixture.exe", "image/png", true).is_err());
+        assert!(validate_upload_extension("no_extension", "application/pdf", true).is_err());
+
+        assert!(validate_upload_extension("foto.JPG", "image/jpeg", true).is_ok());
+        assert!(validate_upload_extension("kontrak.docx",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document", true).is_ok());
+    }
+}
@@ -1,2 +1,19 @@
 // Utils modules untuk Chat Service
-pub mod jwt;
\ No newline at end of file
+pub mod file_scanner;
+pub mod message_validation;
+pub mod nats_monitor;
+pub mod outbox;
+pub mod ws_compression;
+pub mod retention;
+pub mod unread;
+pub mod ws_close;
+pub mod conversation_initiation;
+pub mod realtime;
+pub mod upload_policy;
+pub mod email_reply;
+pub mod message_email;
+pub mod media_proxy;
+pub mod vehicle_owner;
+pub mod auto_reply;
+pub mod assignment;
+pub mod search_snippet;
@@ -0,0 +1,175 @@
+// Monitoring koneksi NATS: status, reconnect, dan dead-letter
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use async_nats::Event;
+use tokio::sync::watch;
+
+// Status koneksi NATS + counter yang di-share lewat AppState
+#[derive(Debug)]
+pub struct NatsMonitor {
+    dead_letter_subject: String,
+    connected: AtomicBool,
+    disconnected_since_connect: AtomicBool,
+    reconnect_count: AtomicU64,
+    dead_letter_count: AtomicU64,
+    reconnect_tx: watch::Sender<u64>,
+}
+
+impl NatsMonitor {
+    pub fn new(dead_letter_subject: impl Into<String>) -> Self {
+        let (reconnect_tx, _) = watch::channel(0);
+        Self {
+            dead_letter_subject: dead_letter_subject.into(),
+            connected: AtomicBool::new(false),
+            disconnected_since_connect: AtomicBool::new(false),
+            reconnect_count: AtomicU64::new(0),
+            dead_letter_count: AtomicU64::new(0),
+            reconnect_tx,
+        }
+    }
+
+    // Dipanggil dari event callback async-nats
+    pub fn handle_event(&self, event: Event) {
+        match event {
+            Event::Connected => {
+                self.connected.store(true, Ordering::SeqCst);
+
+                // Koneksi pertama tidak dihitung sebagai reconnect
+                if self.disconnected_since_connect.swap(false, Ordering::SeqCst) {
+                    let count = self.reconnect_count.fetch_add(1, Ordering::SeqCst) + 1;
+                    self.reconnect_tx.send_replace(count);
+                    tracing::info!("🔄 NATS reconnected (reconnect ke-{})", count);
+                } else {
+                    tracing::info!("✅ Terhubung ke NATS server");
+                }
+            }
+            Event::Disconnected => {
+                self.connected.store(false, Ordering::SeqCst);
+                self.disconnected_since_connect.store(true, Ordering::SeqCst);
+                tracing::warn!("⚠️ NATS terputus, mencoba reconnect...");
+            }
+            Event::Closed => {
+                self.connected.store(false, Ordering::SeqCst);
+                tracing::error!("❌ Koneksi NATS ditutup permanen");
+            }
+            other => {
+                tracing::warn!("NATS event: {}", other);
+            }
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    pub fn reconnect_count(&self) -> u64 {
+        self.reconnect_count.load(Ordering::SeqCst)
+    }
+
+    pub fn dead_letter_count(&self) -> u64 {
+        self.dead_letter_count.load(Ordering::SeqCst)
+    }
+
+    // Receiver yang berubah setiap kali NATS reconnect
+    pub fn subscribe_reconnects(&self) -> watch::Receiver<u64> {
+        self.reconnect_tx.subscribe()
+    }
+
+    // Kirim message yang tidak bisa dideliver ke dead-letter subject
+    pub async fn dead_letter(
+        &self,
+        client: &async_nats::Client,
+        original_subject: &str,
+        reason: &str,
+        payload: &[u8],
+    ) {
+        self.dead_letter_count.fetch_add(1, Ordering::SeqCst);
+
+        let envelope = dead_letter_envelope(original_subject, reason, payload);
+        if let Err(e) = client
+            .publish(self.dead_letter_subject.clone(), envelope.to_string().into())
+            .await
+        {
+            tracing::error!("Gagal kirim message dari {} ke dead-letter: {}", original_subject, e);
+        }
+    }
+
+    // Render metrics format Prometheus text
+    pub fn render_metrics(&self) -> String {
+        let mut output = String::new();
+        let _ = writeln!(output, "# HELP chat_nats_connected Status koneksi NATS (1 = connected)");
+        let _ = writeln!(output, "# TYPE chat_nats_connected gauge");
+        let _ = writeln!(output, "chat_nats_connected {}", u8::from(self.is_connected()));
+        let _ = writeln!(output, "# HELP chat_nats_reconnects_total Jumlah reconnect NATS sejak service start");
+        let _ = writeln!(output, "# TYPE chat_nats_reconnects_total counter");
+        let _ = writeln!(output, "chat_nats_reconnects_total {}", self.reconnect_count());
+        let _ = writeln!(output, "# HELP chat_nats_dead_letters_total Jumlah message yang dikirim ke dead-letter");
+        let _ = writeln!(output, "# TYPE chat_nats_dead_letters_total counter");
+        let _ = writeln!(output, "chat_nats_dead_letters_total {}", self.dead_letter_count());
+        output
+    }
+}
+
+// Envelope dead-letter dengan konteks untuk inspeksi manual
+fn dead_letter_envelope(original_subject: &str, reason: &str, payload: &[u8]) -> serde_json::Value {
+    serde_json::json!({
+        "original_subject": original_subject,
+        "reason": reason,
+        "payload": String::from_utf8_lossy(payload),
+        "failed_at": chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_connect_is_not_reconnect() {
+        let monitor = NatsMonitor::new("chat.dead_letter");
+        monitor.handle_event(Event::Connected);
+
+        assert!(monitor.is_connected());
+        assert_eq!(monitor.reconnect_count(), 0);
+    }
+
+    #[test]
+    fn test_reconnect_is_counted_and_notified() {
+        let monitor = NatsMonitor::new("chat.dead_letter");
+        let mut reconnects = monitor.subscribe_reconnects();
+
+        monitor.handle_event(Event::Connected);
+        monitor.handle_event(Event::Disconnected);
+        assert!(!monitor.is_connected());
+
+        monitor.handle_event(Event::Connected);
+        assert!(monitor.is_connected());
+        assert_eq!(monitor.reconnect_count(), 1);
+        assert!(reconnects.has_changed().unwrap());
+        assert_eq!(*reconnects.borrow_and_update(), 1);
+    }
+
+    #[test]
+    fn test_dead_letter_envelope() {
+        let envelope = dead_letter_envelope("chat.7", "websocket_send_failed", br#"{"type":"new_message"}"#);
+
+        assert_eq!(envelope["original_subject"], "chat.7");
+        assert_eq!(envelope["reason"], "websocket_send_failed");
+        assert_eq!(envelope["payload"], r#"{"type":"new_message"}"#);
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let monitor = NatsMonitor::new("chat.dead_letter");
+        monitor.handle_event(Event::Connected);
+        monitor.handle_event(Event::Disconnected);
+        monitor.handle_event(Event::Connected);
+
+        let metrics = monitor.render_metrics();
+        assert!(metrics.contains("chat_nats_connected 1"));
+        assert!(metrics.contains("chat_nats_reconnects_total 1"));
+        assert!(metrics.contains("chat_nats_dead_letters_total 0"));
+    }
+}
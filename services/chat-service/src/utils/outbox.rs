@@ -0,0 +1,178 @@
+// Relay transactional outbox chat ke NATS (at-least-once delivery)
+
+use std::future::Future;
+use std::time::Duration;
+
+// Batas tunggu flush NATS sebelum batch dianggap gagal
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Backoff retry maksimal 5 menit
+const MAX_BACKOFF_SECS: u64 = 300;
+
+// Event outbox yang menunggu dipublish
+#[derive(Debug, Clone)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub subject: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+}
+
+// Hasil relay satu batch
+#[derive(Debug, Default)]
+pub struct RelayOutcome {
+    pub sent: Vec<i64>,
+    pub failed: Vec<(i64, String)>,
+}
+
+// Tujuan publish event outbox (NATS di production)
+pub trait EventPublisher {
+    fn publish(&self, subject: String, payload: Vec<u8>) -> impl Future<Output = Result<(), String>> + Send;
+
+    // Pastikan event yang di-buffer benar-benar sampai ke server
+    fn flush(&self) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+impl EventPublisher for async_nats::Client {
+    async fn publish(&self, subject: String, payload: Vec<u8>) -> Result<(), String> {
+        // Client async-nats tetap mem-buffer saat disconnect, jadi gagal cepat di sini
+        if !matches!(self.connection_state(), async_nats::connection::State::Connected) {
+            return Err("NATS tidak terhubung".to_string());
+        }
+
+        async_nats::Client::publish(self, subject, payload.into())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn flush(&self) -> Result<(), String> {
+        tokio::time::timeout(FLUSH_TIMEOUT, async_nats::Client::flush(self))
+            .await
+            .map_err(|_| "Flush NATS timeout".to_string())?
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Publish satu batch event, event hanya dianggap terkirim setelah flush berhasil
+pub async fn relay_batch<P: EventPublisher>(publisher: &P, events: &[OutboxEvent]) -> RelayOutcome {
+    let mut outcome = RelayOutcome::default();
+
+    for event in events {
+        match publisher.publish(event.subject.clone(), event.payload.to_string().into_bytes()).await {
+            Ok(()) => outcome.sent.push(event.id),
+            Err(e) => outcome.failed.push((event.id, e)),
+        }
+    }
+
+    if !outcome.sent.is_empty() {
+        if let Err(e) = publisher.flush().await {
+            let unconfirmed: Vec<_> = outcome.sent.drain(..).map(|id| (id, e.clone())).collect();
+            outcome.failed.extend(unconfirmed);
+        }
+    }
+
+    outcome
+}
+
+// Backoff eksponensial berdasarkan jumlah percobaan sebelumnya: 2s, 4s, 8s, ...
+pub fn retry_backoff(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(0, 16) as u32;
+    Duration::from_secs((2u64 << exponent).min(MAX_BACKOFF_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    // Publisher palsu yang bisa disimulasikan down / up
+    struct FakePublisher {
+        online: AtomicBool,
+        flush_ok: AtomicBool,
+        delivered: Mutex<Vec<(String, String)>>,
+    }
+
+    impl FakePublisher {
+        fn new(online: bool) -> Self {
+            Self {
+                online: AtomicBool::new(online),
+                flush_ok: AtomicBool::new(true),
+                delivered: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl EventPublisher for FakePublisher {
+        async fn publish(&self, subject: String, payload: Vec<u8>) -> Result<(), String> {
+            if !self.online.load(Ordering::SeqCst) {
+                return Err("NATS tidak terhubung".to_string());
+            }
+            self.delivered.lock().unwrap().push((subject, String::from_utf8(payload).unwrap()));
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<(), String> {
+            if self.flush_ok.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("Flush NATS timeout".to_string())
+            }
+        }
+    }
+
+    fn new_message_event(id: i64) -> OutboxEvent {
+        OutboxEvent {
+            id,
+            subject: "chat.7".to_string(),
+            payload: serde_json::json!({ "type": "new_message", "conversation_id": 7 }),
+            attempts: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_queued_while_nats_down_is_delivered_after_recovery() {
+        let publisher = FakePublisher::new(false);
+        let mut outbox = vec![new_message_event(1)];
+
+        // NATS down: event tetap di outbox dan dijadwalkan ulang
+        let outcome = relay_batch(&publisher, &outbox).await;
+        assert!(outcome.sent.is_empty());
+        assert_eq!(outcome.failed.len(), 1);
+        for (id, _) in &outcome.failed {
+            let event = outbox.iter_mut().find(|e| e.id == *id).unwrap();
+            event.attempts += 1;
+        }
+        assert!(publisher.delivered.lock().unwrap().is_empty());
+
+        // NATS pulih: relay berikutnya mengirim event yang tertunda
+        publisher.online.store(true, Ordering::SeqCst);
+        let outcome = relay_batch(&publisher, &outbox).await;
+        assert_eq!(outcome.sent, vec![1]);
+        assert!(outcome.failed.is_empty());
+        outbox.retain(|e| !outcome.sent.contains(&e.id));
+
+        assert!(outbox.is_empty());
+        let delivered = publisher.delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].0, "chat.7");
+        assert!(delivered[0].1.contains("new_message"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_keeps_events_pending() {
+        let publisher = FakePublisher::new(true);
+        publisher.flush_ok.store(false, Ordering::SeqCst);
+
+        let outcome = relay_batch(&publisher, &[new_message_event(1), new_message_event(2)]).await;
+        assert!(outcome.sent.is_empty());
+        assert_eq!(outcome.failed.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(retry_backoff(0), Duration::from_secs(2));
+        assert_eq!(retry_backoff(3), Duration::from_secs(16));
+        assert_eq!(retry_backoff(20), Duration::from_secs(MAX_BACKOFF_SECS));
+    }
+}
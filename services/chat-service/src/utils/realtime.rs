@@ -0,0 +1,130 @@
+// Mode real-time chat: live (NATS terhubung) atau degraded (tanpa NATS)
+//
+// Degraded mode aktif jika NATS_URL tidak di-set, koneksi awal gagal, atau NATS terputus.
+// Jaminan di degraded mode:
+// - REST tetap penuh: message tersimpan di DB dan bisa diambil via GET /conversations/{id}/messages
+// - Event outbox tetap ditulis dan tertahan di DB sampai NATS kembali (OutboxRelay)
+// - WebSocket tetap menerima koneksi; server mengirim `history` dari DB saat connect
+//   dan setiap kali client mengirim `fetch_history` (polling pengganti push)
+// - Broadcast read/delete/typing/conversation_updated dilewati tanpa error ke client
+// - /health melaporkan status "degraded"
+// - /ready tetap true tanpa NATS (service memang dirancang jalan dengan nats_client None);
+//   hanya koneksi NATS yang sudah ada lalu terputus yang membuat instance tidak ready
+
+use async_nats::Client;
+use async_nats::connection::State;
+use shared::utils::health::HealthLevel;
+
+// Default dan batas jumlah message per backfill WebSocket
+pub const DEFAULT_BACKFILL_LIMIT: i64 = 50;
+pub const MAX_BACKFILL_LIMIT: i64 = 100;
+
+// Status NATS untuk health/readiness response
+pub fn nats_status(client: Option<&Client>) -> &'static str {
+    match client.map(|c| c.connection_state()) {
+        Some(State::Connected) => "connected",
+        Some(State::Pending) => "reconnecting",
+        Some(State::Disconnected) => "disconnected",
+        None => "not_initialized",
+    }
+}
+
+// Push real-time hanya tersedia jika NATS terhubung
+pub fn is_live(nats_status: &str) -> bool {
+    nats_status == "connected"
+}
+
+// Readiness: DB wajib, NATS opsional. Tanpa NATS (not_initialized) service tetap melayani
+// REST dan WebSocket polling, jadi tetap ready; koneksi NATS yang putus/reconnect tidak ready
+pub fn is_ready(db_healthy: bool, nats_status: &str) -> bool {
+    db_healthy && matches!(nats_status, "connected" | "not_initialized")
+}
+
+// Kontribusi NATS ke status /health: tanpa koneksi live service tetap melayani REST, jadi degraded
+pub fn nats_health(nats_status: &str) -> HealthLevel {
+    if is_live(nats_status) {
+        HealthLevel::Healthy
+    } else {
+        HealthLevel::Degraded
+    }
+}
+
+// Batasi limit backfill dari client
+pub fn backfill_limit(requested: Option<i64>) -> i64 {
+    requested.unwrap_or(DEFAULT_BACKFILL_LIMIT).clamp(1, MAX_BACKFILL_LIMIT)
+}
+
+// Subject NATS per user (inbox & event lintas conversation)
+pub fn user_subject(user_id: i32) -> String {
+    format!("chat.user.{}", user_id)
+}
+
+// Publish best-effort; tanpa NATS event dilewati dan return false
+pub async fn publish_best_effort(
+    nats_client: Option<&Client>,
+    subject: String,
+    payload: String,
+    event: &str,
+) -> bool {
+    let Some(client) = nats_client else {
+        tracing::debug!("Degraded mode: broadcast {} ke {} dilewati", event, subject);
+        return false;
+    };
+
+    match client.publish(subject, payload.into()).await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::warn!("Gagal broadcast {}: {}", event, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nats_absent_reports_not_initialized() {
+        assert_eq!(nats_status(None), "not_initialized");
+        assert!(!is_live(nats_status(None)));
+    }
+
+    #[test]
+    fn test_ready_without_nats() {
+        assert!(is_ready(true, nats_status(None)));
+        assert!(is_ready(true, "connected"));
+
+        assert!(!is_ready(false, nats_status(None)));
+        assert!(!is_ready(true, "disconnected"));
+        assert!(!is_ready(true, "reconnecting"));
+    }
+
+    #[test]
+    fn test_nats_health_degraded_without_nats() {
+        assert_eq!(nats_health("connected"), HealthLevel::Healthy);
+        assert_eq!(nats_health(nats_status(None)), HealthLevel::Degraded);
+        assert_eq!(nats_health("reconnecting"), HealthLevel::Degraded);
+        assert_eq!(nats_health("disconnected"), HealthLevel::Degraded);
+    }
+
+    #[test]
+    fn test_backfill_limit_clamped() {
+        assert_eq!(backfill_limit(None), DEFAULT_BACKFILL_LIMIT);
+        assert_eq!(backfill_limit(Some(0)), 1);
+        assert_eq!(backfill_limit(Some(20)), 20);
+        assert_eq!(backfill_limit(Some(10_000)), MAX_BACKFILL_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn test_publish_without_nats_is_skipped() {
+        let published = publish_best_effort(
+            None,
+            "chat.1".to_string(),
+            serde_json::json!({ "type": "message_read" }).to_string(),
+            "read status",
+        )
+        .await;
+        assert!(!published);
+    }
+}
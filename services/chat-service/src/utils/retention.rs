@@ -0,0 +1,49 @@
+// Retensi message per conversation (auto-purge), NULL = simpan selamanya
+
+use chrono::{DateTime, Duration, Utc};
+
+// Batas retensi yang boleh diset participant
+pub const MIN_RETENTION_DAYS: i32 = 1;
+pub const MAX_RETENTION_DAYS: i32 = 365;
+
+// Sumber waktu purge, bisa diganti fake clock di test
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+// Validasi retention_days dari request, None berarti retensi tak terbatas
+pub fn validate_retention_days(retention_days: Option<i32>) -> Result<Option<i32>, String> {
+    match retention_days {
+        Some(days) if !(MIN_RETENTION_DAYS..=MAX_RETENTION_DAYS).contains(&days) => Err(format!(
+            "retention_days harus antara {} dan {} hari, atau null untuk simpan selamanya",
+            MIN_RETENTION_DAYS, MAX_RETENTION_DAYS
+        )),
+        other => Ok(other),
+    }
+}
+
+// Message yang dibuat sebelum cutoff ini dihapus
+pub fn retention_cutoff(now: DateTime<Utc>, retention_days: i32) -> DateTime<Utc> {
+    now - Duration::days(i64::from(retention_days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_retention_days() {
+        assert_eq!(validate_retention_days(None), Ok(None));
+        assert_eq!(validate_retention_days(Some(7)), Ok(Some(7)));
+        assert!(validate_retention_days(Some(0)).is_err());
+        assert!(validate_retention_days(Some(MAX_RETENTION_DAYS + 1)).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+// Snippet hasil search message dengan highlight
+//
+// Snippet dibuat Postgres `ts_headline` dengan delimiter sentinel (karakter kontrol yang tidak lolos
+// validasi isi message), lalu di sini: teks message di-escape HTML, sentinel diganti delimiter
+// highlight (default <mark>...</mark>), dan panjang teks dipotong di sekitar match pertama.
+// Match substring (ILIKE) yang bukan kata utuh tidak ditandai ts_headline, jadi ditandai ulang di sini.
+//
+// Env (opsional):
+// - CHAT_SEARCH_HIGHLIGHT_START / CHAT_SEARCH_HIGHLIGHT_STOP  delimiter highlight
+// - CHAT_SEARCH_SNIPPET_MAX_CHARS  panjang maksimal teks snippet (tanpa delimiter), default 160
+
+use std::env;
+
+const MATCH_START: char = '\u{2}';
+const MATCH_STOP: char = '\u{3}';
+const ELLIPSIS: char = '…';
+
+pub const DEFAULT_HIGHLIGHT_START: &str = "<mark>";
+pub const DEFAULT_HIGHLIGHT_STOP: &str = "</mark>";
+pub const DEFAULT_SNIPPET_MAX_CHARS: usize = 160;
+const MIN_SNIPPET_MAX_CHARS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetOptions {
+    pub highlight_start: String,
+    pub highlight_stop: String,
+    pub max_chars: usize,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self {
+            highlight_start: DEFAULT_HIGHLIGHT_START.to_string(),
+            highlight_stop: DEFAULT_HIGHLIGHT_STOP.to_string(),
+            max_chars: DEFAULT_SNIPPET_MAX_CHARS,
+        }
+    }
+}
+
+impl SnippetOptions {
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.trim().is_empty());
+        let defaults = Self::default();
+
+        Self {
+            highlight_start: var("CHAT_SEARCH_HIGHLIGHT_START").unwrap_or(defaults.highlight_start),
+            highlight_stop: var("CHAT_SEARCH_HIGHLIGHT_STOP").unwrap_or(defaults.highlight_stop),
+            max_chars: var("CHAT_SEARCH_SNIPPET_MAX_CHARS")
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .map(|max| max.max(MIN_SNIPPET_MAX_CHARS))
+                .unwrap_or(defaults.max_chars),
+        }
+    }
+
+    // Opsi ts_headline: delimiter sentinel, jumlah kata kira-kira mengikuti batas karakter
+    pub fn headline_options(&self) -> String {
+        let max_words = (self.max_chars / 6).clamp(4, 60);
+        let min_words = (max_words / 3).max(2);
+
+        format!(
+            "StartSel={}, StopSel={}, MaxWords={}, MinWords={}, ShortWord=2",
+            MATCH_START, MATCH_STOP, max_words, min_words
+        )
+    }
+
+    // Snippet final dari output ts_headline (atau isi message jika headline kosong)
+    pub fn render(&self, headline: Option<&str>, content: &str, query: &str) -> String {
+        let mut segments = parse_segments(headline.unwrap_or(content));
+        if !segments.iter().any(|(_, highlighted)| *highlighted) {
+            segments = highlight_substring(content, query);
+        }
+
+        let segments = bound_segments(segments, self.max_chars);
+
+        segments
+            .iter()
+            .map(|(text, highlighted)| {
+                let escaped = escape_html(text);
+                if *highlighted {
+                    format!("{}{}{}", self.highlight_start, escaped, self.highlight_stop)
+                } else {
+                    escaped
+                }
+            })
+            .collect()
+    }
+}
+
+// Pecah output ts_headline jadi (teks, ditandai); sentinel yang tidak berpasangan diabaikan
+fn parse_segments(headline: &str) -> Vec<(String, bool)> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut highlighted = false;
+
+    for c in headline.chars() {
+        let toggle = match c {
+            MATCH_START => !highlighted,
+            MATCH_STOP => highlighted,
+            _ => {
+                current.push(c);
+                false
+            }
+        };
+        if toggle {
+            push_segment(&mut segments, std::mem::take(&mut current), highlighted);
+            highlighted = !highlighted;
+        }
+    }
+    push_segment(&mut segments, current, highlighted);
+
+    segments
+}
+
+fn push_segment(segments: &mut Vec<(String, bool)>, text: String, highlighted: bool) {
+    if !text.is_empty() {
+        segments.push((text, highlighted));
+    }
+}
+
+// Tandai semua kemunculan query (case-insensitive) di isi message
+fn highlight_substring(content: &str, query: &str) -> Vec<(String, bool)> {
+    let chars: Vec<char> = content.chars().collect();
+    let needle: Vec<char> = query.trim().chars().collect();
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    let mut segments = Vec::new();
+    let mut plain_start = 0;
+    let mut i = 0;
+    while !needle.is_empty() && i + needle.len() <= chars.len() {
+        if chars[i..i + needle.len()].iter().zip(&needle).all(|(a, b)| same(*a, *b)) {
+            push_segment(&mut segments, chars[plain_start..i].iter().collect(), false);
+            push_segment(&mut segments, chars[i..i + needle.len()].iter().collect(), true);
+            i += needle.len();
+            plain_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    push_segment(&mut segments, chars[plain_start..].iter().collect(), false);
+
+    segments
+}
+
+// Potong ke max_chars karakter teks (termasuk elipsis), jendela dimulai sedikit sebelum match pertama
+fn bound_segments(segments: Vec<(String, bool)>, max_chars: usize) -> Vec<(String, bool)> {
+    let total: usize = segments.iter().map(|(text, _)| text.chars().count()).sum();
+    if total <= max_chars {
+        return segments;
+    }
+
+    let first_match = segments
+        .iter()
+        .take_while(|(_, highlighted)| !highlighted)
+        .map(|(text, _)| text.chars().count())
+        .sum::<usize>();
+    let first_match = if first_match == total { 0 } else { first_match };
+
+    // Sisakan tempat untuk elipsis di kedua sisi
+    let budget = max_chars.saturating_sub(2).max(1);
+    let start = first_match.saturating_sub(budget / 4).min(total - budget);
+    let end = start + budget;
+
+    let mut bounded = Vec::new();
+    if start > 0 {
+        bounded.push((ELLIPSIS.to_string(), false));
+    }
+
+    let mut offset = 0;
+    for (text, highlighted) in segments {
+        let len = text.chars().count();
+        let (from, to) = (start.max(offset), end.min(offset + len));
+        if from < to {
+            let part: String = text.chars().skip(from - offset).take(to - from).collect();
+            push_segment(&mut bounded, part, highlighted);
+        }
+        offset += len;
+    }
+
+    if end < total {
+        bounded.push((ELLIPSIS.to_string(), false));
+    }
+
+    bounded
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible_len(snippet: &str, options: &SnippetOptions) -> usize {
+        snippet
+            .replace(&options.highlight_start, "")
+            .replace(&options.highlight_stop, "")
+            .chars()
+            .count()
+    }
+
+    #[test]
+    fn test_snippet_highlights_term_within_length_bound() {
+        let options = SnippetOptions { max_chars: 40, ..SnippetOptions::default() };
+        let content = format!(
+            "{} apakah Avanza ini masih tersedia untuk disewa minggu depan? {}",
+            "Selamat siang pak, saya lihat iklannya kemarin.".repeat(3),
+            "Terima kasih banyak.".repeat(3)
+        );
+        // Bentuk output ts_headline: kata yang cocok diapit sentinel
+        let headline = content.replace("Avanza", "\u{2}Avanza\u{3}");
+
+        let snippet = options.render(Some(&headline), &content, "avanza");
+        assert!(snippet.contains("<mark>Avanza</mark>"), "{}", snippet);
+        assert!(visible_len(&snippet, &options) <= 40, "{}", snippet);
+        assert!(snippet.starts_with(ELLIPSIS) && snippet.ends_with(ELLIPSIS));
+
+        // Match bagian kata (ILIKE) tetap ditandai walau ts_headline tidak menandai
+        let snippet = options.render(Some(&content), &content, "vanz");
+        assert!(snippet.contains("<mark>vanz</mark>"), "{}", snippet);
+        assert!(visible_len(&snippet, &options) <= 40);
+    }
+
+    #[test]
+    fn test_snippet_escapes_message_and_uses_configured_delimiters() {
+        let options = SnippetOptions {
+            highlight_start: "[[".to_string(),
+            highlight_stop: "]]".to_string(),
+            max_chars: DEFAULT_SNIPPET_MAX_CHARS,
+        };
+        let content = "<script>alert(1)</script> harga nego?";
+        let headline = "<script>alert(1)</script> harga \u{2}nego\u{3}?";
+
+        let snippet = options.render(Some(headline), content, "nego");
+        assert_eq!(snippet, "&lt;script&gt;alert(1)&lt;/script&gt; harga [[nego]]?");
+
+        // Pesan pendek tidak dipotong, headline kosong memakai isi message
+        assert_eq!(options.render(None, "Halo NEGO", "nego"), "Halo [[NEGO]]");
+    }
+
+    #[test]
+    fn test_headline_options_follow_length_bound() {
+        let options = SnippetOptions::default();
+        let headline = options.headline_options();
+        assert!(headline.contains("MaxWords=26") && headline.contains("MinWords=8"), "{}", headline);
+        assert!(headline.contains('\u{2}') && headline.contains('\u{3}'));
+    }
+}
@@ -0,0 +1,132 @@
+// Counter unread denormalized per participant conversation
+
+// Participant dalam conversation 1-on-1, staff yang di-assign berada di sisi seller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Participant {
+    Customer,
+    Seller,
+}
+
+impl Participant {
+    // Posisi user di conversation, None jika bukan participant
+    pub fn of(user_id: i32, customer_id: i32, seller_id: i32, assigned_to: Option<i32>) -> Option<Self> {
+        if user_id == customer_id {
+            Some(Participant::Customer)
+        } else if user_id == seller_id || assigned_to == Some(user_id) {
+            Some(Participant::Seller)
+        } else {
+            None
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Participant::Customer => Participant::Seller,
+            Participant::Seller => Participant::Customer,
+        }
+    }
+}
+
+// Last message dikirim dari sisi user yang melihat inbox. Sisi ditentukan dari customer_id,
+// jadi balasan staff dealer (termasuk yang sudah tidak di-assign) dihitung milik sisi seller
+pub fn last_message_is_mine(sender_id: Option<i32>, viewer_id: i32, customer_id: i32) -> bool {
+    sender_id.is_some_and(|sender_id| (sender_id == customer_id) == (viewer_id == customer_id))
+}
+
+// Unread tiap participant, selalu diubah saat row conversation di-lock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnreadCounters {
+    pub customer: i32,
+    pub seller: i32,
+}
+
+impl UnreadCounters {
+    pub fn get(&self, participant: Participant) -> i32 {
+        match participant {
+            Participant::Customer => self.customer,
+            Participant::Seller => self.seller,
+        }
+    }
+
+    fn get_mut(&mut self, participant: Participant) -> &mut i32 {
+        match participant {
+            Participant::Customer => &mut self.customer,
+            Participant::Seller => &mut self.seller,
+        }
+    }
+
+    // Message baru menambah unread penerima
+    pub fn after_message(mut self, sender: Participant) -> Self {
+        *self.get_mut(sender.other()) += 1;
+        self
+    }
+
+    // Reader membaca `read` message dari participant lain
+    pub fn after_read(mut self, reader: Participant, read: u64) -> Self {
+        let counter = self.get_mut(reader);
+        *counter = (i64::from(*counter) - read as i64).max(0) as i32;
+        self
+    }
+}
+
+// Batas conversation per request mark read-all agar satu transaksi tetap pendek
+pub const READ_ALL_MAX_CONVERSATIONS: i64 = 200;
+
+// Kelompokkan conversation_id dari message yang baru dibaca menjadi (conversation_id, jumlah), urut id
+pub fn count_reads_by_conversation(conversation_ids: &[i32]) -> Vec<(i32, u64)> {
+    let mut counts = std::collections::BTreeMap::new();
+    for &id in conversation_ids {
+        *counts.entry(id).or_insert(0u64) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_follow_messages_and_reads() {
+        let counters = UnreadCounters::default()
+            .after_message(Participant::Customer)
+            .after_message(Participant::Customer)
+            .after_message(Participant::Seller);
+        assert_eq!(counters, UnreadCounters { customer: 1, seller: 2 });
+
+        let counters = counters.after_read(Participant::Seller, 2).after_read(Participant::Customer, 5);
+        assert_eq!(counters, UnreadCounters::default());
+        assert_eq!(Participant::of(7, 7, 9, None), Some(Participant::Customer));
+        assert_eq!(Participant::of(8, 7, 9, None), None);
+        assert_eq!(Participant::of(8, 7, 9, Some(8)), Some(Participant::Seller));
+    }
+
+    #[test]
+    fn test_last_message_is_mine_for_both_participants() {
+        let (customer, seller, staff) = (7, 9, 11);
+
+        // Customer mengirim pesan terakhir
+        assert!(last_message_is_mine(Some(customer), customer, customer));
+        assert!(!last_message_is_mine(Some(customer), seller, customer));
+        assert!(!last_message_is_mine(Some(customer), staff, customer));
+
+        // Seller membalas, staff dealer melihat dari sisi yang sama
+        assert!(!last_message_is_mine(Some(seller), customer, customer));
+        assert!(last_message_is_mine(Some(seller), seller, customer));
+        assert!(last_message_is_mine(Some(staff), seller, customer));
+
+        // Belum ada pesan
+        assert!(!last_message_is_mine(None, customer, customer));
+        assert!(!last_message_is_mine(None, seller, customer));
+    }
+
+    #[test]
+    fn test_count_reads_by_conversation() {
+        assert_eq!(count_reads_by_conversation(&[]), Vec::<(i32, u64)>::new());
+        assert_eq!(
+            count_reads_by_conversation(&[9, 3, 9, 9, 3, 12]),
+            vec![(3, 2), (9, 3), (12, 1)]
+        );
+    }
+
+    // Send dan mark-read bersamaan terhadap row conversation asli ada di repositories::message_repo::tests
+}
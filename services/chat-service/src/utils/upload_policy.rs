@@ -0,0 +1,118 @@
+// Kebijakan upload per kategori file: folder tujuan, format yang diizinkan, transformasi CDN
+//
+// Env per kategori (IMAGE / DOCUMENT / AUDIO), semuanya opsional:
+// - CHAT_UPLOAD_{KATEGORI}_FOLDER          folder storage (default chat/images, chat/documents, chat/audio)
+// - CHAT_UPLOAD_{KATEGORI}_FORMATS         ekstensi dipisah koma, mis. "jpg,png,webp"
+// - CHAT_UPLOAD_{KATEGORI}_TRANSFORMATION  transformasi Cloudinary, mis. "q_auto,f_auto"
+// Tanpa env, perilaku sama seperti sebelumnya: folder default, semua format yang lolos validasi tipe,
+// dan tanpa delivery URL tambahan.
+
+use std::env;
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadCategoryPolicy {
+    pub folder: String,
+    // None = semua ekstensi yang lolos validasi Content-Type
+    pub allowed_formats: Option<Vec<String>>,
+    pub transformation: Option<String>,
+}
+
+impl UploadCategoryPolicy {
+    // Load dari env dengan prefix kategori, mis. "IMAGE" -> CHAT_UPLOAD_IMAGE_*
+    pub fn from_env(category: &str, default_folder: &str) -> Self {
+        let var = |name: &str| {
+            env::var(format!("CHAT_UPLOAD_{}_{}", category, name))
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Self::from_values(
+            default_folder,
+            var("FOLDER"),
+            var("FORMATS"),
+            var("TRANSFORMATION"),
+        )
+    }
+
+    fn from_values(
+        default_folder: &str,
+        folder: Option<String>,
+        formats: Option<String>,
+        transformation: Option<String>,
+    ) -> Self {
+        let allowed_formats = formats.map(|formats| {
+            formats
+                .split(',')
+                .map(|format| format.trim().trim_start_matches('.').to_lowercase())
+                .filter(|format| !format.is_empty())
+                .collect::<Vec<_>>()
+        });
+
+        Self {
+            folder: folder
+                .map(|folder| folder.trim_matches('/').to_string())
+                .unwrap_or_else(|| default_folder.to_string()),
+            allowed_formats,
+            transformation,
+        }
+    }
+
+    // Tolak file yang ekstensinya tidak ada di daftar format kategori
+    pub fn check_format(&self, file_name: &str) -> Result<(), AppError> {
+        let Some(allowed) = &self.allowed_formats else {
+            return Ok(());
+        };
+
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .unwrap_or_default();
+
+        if allowed.contains(&extension) {
+            Ok(())
+        } else {
+            Err(AppError::validation(format!(
+                "Format file {} tidak diizinkan. Allowed: {}",
+                file_name,
+                allowed.join(", ")
+            )))
+        }
+    }
+
+    // Key storage lengkap untuk file di kategori ini
+    pub fn storage_key(&self, file_name: &str) -> String {
+        format!("{}/{}", self.folder, file_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unset_config_keeps_defaults() {
+        let policy = UploadCategoryPolicy::from_values("chat/images", None, None, None);
+        assert_eq!(policy.storage_key("a.jpg"), "chat/images/a.jpg");
+        assert!(policy.check_format("anything.heic").is_ok());
+        assert_eq!(policy.transformation, None);
+    }
+
+    #[test]
+    fn test_configured_folder_and_formats() {
+        let policy = UploadCategoryPolicy::from_values(
+            "chat/images",
+            Some("/cdn/chat-img/".to_string()),
+            Some("JPG, .png,webp,".to_string()),
+            Some("q_auto,f_auto".to_string()),
+        );
+
+        assert_eq!(policy.storage_key("a.jpg"), "cdn/chat-img/a.jpg");
+        assert_eq!(policy.allowed_formats, Some(vec!["jpg".to_string(), "png".to_string(), "webp".to_string()]));
+        assert!(policy.check_format("foto.PNG").is_ok());
+        assert!(matches!(policy.check_format("anim.gif"), Err(AppError::ValidationError(_))));
+        assert!(policy.check_format("tanpa-ekstensi").is_err());
+    }
+}
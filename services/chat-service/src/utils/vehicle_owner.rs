@@ -0,0 +1,125 @@
+// Lookup pemilik vehicle ke vehicle-service untuk validasi conversation baru
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::error::AppError;
+
+// Default lama cache pemilik vehicle (override via VEHICLE_OWNER_CACHE_SECS)
+pub const DEFAULT_VEHICLE_OWNER_CACHE_SECS: u64 = 60;
+
+// Timeout request ke vehicle-service, create conversation tidak boleh menggantung lama
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Cache in-memory vehicle_id -> seller_id dengan TTL pendek
+#[derive(Debug, Default)]
+pub struct OwnerCache {
+    entries: HashMap<i32, (i32, Instant)>,
+}
+
+impl OwnerCache {
+    pub fn get(&self, vehicle_id: i32, now: Instant, ttl: Duration) -> Option<i32> {
+        self.entries
+            .get(&vehicle_id)
+            .filter(|(_, cached_at)| now.duration_since(*cached_at) < ttl)
+            .map(|(seller_id, _)| *seller_id)
+    }
+
+    // Entry kedaluwarsa dibuang saat insert agar cache tidak tumbuh terus
+    pub fn insert(&mut self, vehicle_id: i32, seller_id: i32, now: Instant, ttl: Duration) {
+        self.entries.retain(|_, (_, cached_at)| now.duration_since(*cached_at) < ttl);
+        self.entries.insert(vehicle_id, (seller_id, now));
+    }
+}
+
+// Field yang dibutuhkan dari GET /api/vehicles/{id}
+#[derive(Debug, Deserialize)]
+struct VehicleOwner {
+    seller_id: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct VehicleOwnerLookup {
+    http_client: reqwest::Client,
+    vehicle_service_url: String,
+    ttl: Duration,
+    cache: Arc<RwLock<OwnerCache>>,
+}
+
+impl VehicleOwnerLookup {
+    pub fn new(http_client: reqwest::Client, vehicle_service_url: String, cache_secs: u64) -> Self {
+        Self {
+            http_client,
+            vehicle_service_url,
+            ttl: Duration::from_secs(cache_secs),
+            cache: Arc::new(RwLock::new(OwnerCache::default())),
+        }
+    }
+
+    // seller_id pemilik vehicle, None jika vehicle tidak ada
+    pub async fn owner_of(&self, vehicle_id: i32) -> Result<Option<i32>, AppError> {
+        if let Some(seller_id) = self.cache.read().await.get(vehicle_id, Instant::now(), self.ttl) {
+            return Ok(Some(seller_id));
+        }
+
+        // Fail closed: tanpa konfirmasi vehicle-service conversation tidak dibuat
+        let response = self.http_client
+            .get(format!("{}/api/vehicles/{}", self.vehicle_service_url, vehicle_id))
+            .timeout(LOOKUP_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Gagal menghubungi vehicle-service untuk vehicle {}: {}", vehicle_id, e);
+                AppError::internal("Gagal memverifikasi vehicle, coba lagi nanti")
+            })?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            tracing::error!("vehicle-service membalas {} untuk vehicle {}", response.status(), vehicle_id);
+            return Err(AppError::internal("Gagal memverifikasi vehicle, coba lagi nanti"));
+        }
+
+        let vehicle: VehicleOwner = response.json().await.map_err(|e| {
+            tracing::error!("Response vehicle-service untuk vehicle {} tidak valid: {}", vehicle_id, e);
+            AppError::internal("Gagal memverifikasi vehicle, coba lagi nanti")
+        })?;
+
+        self.cache.write().await.insert(vehicle_id, vehicle.seller_id, Instant::now(), self.ttl);
+
+        Ok(Some(vehicle.seller_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_within_ttl() {
+        let ttl = Duration::from_secs(60);
+        let now = Instant::now();
+        let mut cache = OwnerCache::default();
+
+        cache.insert(5, 7, now, ttl);
+        assert_eq!(cache.get(5, now + Duration::from_secs(30), ttl), Some(7));
+        assert_eq!(cache.get(6, now, ttl), None);
+    }
+
+    #[test]
+    fn test_cache_expires_and_prunes() {
+        let ttl = Duration::from_secs(60);
+        let now = Instant::now();
+        let mut cache = OwnerCache::default();
+
+        cache.insert(5, 7, now, ttl);
+        assert_eq!(cache.get(5, now + ttl, ttl), None);
+
+        cache.insert(6, 8, now + ttl, ttl);
+        assert_eq!(cache.entries.len(), 1);
+    }
+}
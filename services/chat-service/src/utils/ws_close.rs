@@ -0,0 +1,51 @@
+// Close frame WebSocket dengan code dan alasan yang valid
+
+use axum::extract::ws::{CloseCode, CloseFrame, Message};
+
+// Batas payload alasan close frame (125 byte control frame - 2 byte code)
+const MAX_REASON_BYTES: usize = 123;
+
+// Potong alasan di batas char agar tetap UTF-8 valid
+pub fn close_reason(reason: &str) -> String {
+    if reason.len() <= MAX_REASON_BYTES {
+        return reason.to_string();
+    }
+
+    let mut end = MAX_REASON_BYTES;
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    reason[..end].to_string()
+}
+
+// Message Close siap kirim ke client
+pub fn close_message(code: CloseCode, reason: &str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: close_reason(reason).into(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::close_code;
+
+    #[test]
+    fn test_close_reason_truncated_on_char_boundary() {
+        assert_eq!(close_reason("Invalid message format"), "Invalid message format");
+
+        let long = "é".repeat(100);
+        let reason = close_reason(&long);
+        assert!(reason.len() <= MAX_REASON_BYTES);
+        assert_eq!(reason, "é".repeat(61));
+
+        match close_message(close_code::UNSUPPORTED, "x") {
+            Message::Close(Some(frame)) => {
+                assert_eq!(frame.code, close_code::UNSUPPORTED);
+                assert_eq!(frame.reason.as_str(), "x");
+            }
+            other => panic!("bukan close frame: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// Kompresi payload WebSocket chat (deflate level aplikasi)
+//
+// axum 0.8 / tungstenite belum mendukung extension permessage-deflate (RFC 7692),
+// jadi kompresi dilakukan per frame: client opt-in lewat `?compression=deflate`
+// dan payload JSON di atas threshold dikirim sebagai Binary frame berisi raw deflate
+// (bisa di-decode di browser dengan `DecompressionStream("deflate-raw")`).
+
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::extract::ws::Message;
+use flate2::{write::DeflateEncoder, Compression};
+
+// Encoding payload yang dinegosiasikan saat handshake
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WsEncoding {
+    Json,
+    Deflate,
+}
+
+impl WsEncoding {
+    // Parse dari query string handshake, default JSON biasa
+    pub fn from_query(query: Option<&str>) -> Self {
+        let Some(query) = query else {
+            return WsEncoding::Json;
+        };
+
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            if key == "compression" && value.eq_ignore_ascii_case("deflate") {
+                return WsEncoding::Deflate;
+            }
+        }
+
+        WsEncoding::Json
+    }
+}
+
+// Encoder frame per koneksi + statistik bandwidth untuk debug
+#[derive(Debug)]
+pub struct WsEncoder {
+    encoding: WsEncoding,
+    threshold_bytes: usize,
+    debug: bool,
+    raw_bytes: AtomicU64,
+    sent_bytes: AtomicU64,
+}
+
+impl WsEncoder {
+    pub fn new(encoding: WsEncoding, threshold_bytes: usize, debug: bool) -> Self {
+        Self {
+            encoding,
+            threshold_bytes,
+            debug,
+            raw_bytes: AtomicU64::new(0),
+            sent_bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn encoding(&self) -> WsEncoding {
+        self.encoding
+    }
+
+    // Ubah payload JSON menjadi frame, dikompres jika besar dan client opt-in
+    pub fn encode(&self, json: String) -> Message {
+        let raw_len = json.len();
+
+        let message = if self.encoding == WsEncoding::Deflate && raw_len >= self.threshold_bytes {
+            match deflate(json.as_bytes()) {
+                // Kirim compressed hanya jika memang lebih kecil
+                Ok(compressed) if compressed.len() < raw_len => Message::Binary(compressed.into()),
+                _ => Message::Text(json.into()),
+            }
+        } else {
+            Message::Text(json.into())
+        };
+
+        if self.debug {
+            let sent_len = match &message {
+                Message::Binary(data) => data.len(),
+                Message::Text(text) => text.len(),
+                _ => raw_len,
+            };
+            self.raw_bytes.fetch_add(raw_len as u64, Ordering::Relaxed);
+            self.sent_bytes.fetch_add(sent_len as u64, Ordering::Relaxed);
+
+            if sent_len < raw_len {
+                tracing::debug!("WS frame dikompres: {} -> {} bytes", raw_len, sent_len);
+            }
+        }
+
+        message
+    }
+
+    // Total (raw, terkirim) bytes sejak koneksi dibuka
+    pub fn bandwidth(&self) -> (u64, u64) {
+        (
+            self.raw_bytes.load(Ordering::Relaxed),
+            self.sent_bytes.load(Ordering::Relaxed),
+        )
+    }
+
+    // Log ringkasan penghematan bandwidth saat koneksi ditutup
+    pub fn log_savings(&self, connection_id: uuid::Uuid) {
+        if !self.debug {
+            return;
+        }
+
+        let (raw, sent) = self.bandwidth();
+        tracing::info!(
+            "WS bandwidth connection {} ({:?}): raw {} bytes, terkirim {} bytes, hemat {:.1}%",
+            connection_id,
+            self.encoding,
+            raw,
+            sent,
+            savings_percent(raw, sent)
+        );
+    }
+}
+
+// Kompres bytes dengan raw deflate
+fn deflate(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::with_capacity(data.len() / 2), Compression::fast());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+// Persentase bandwidth yang dihemat
+fn savings_percent(raw: u64, sent: u64) -> f64 {
+    if raw == 0 {
+        return 0.0;
+    }
+    (raw.saturating_sub(sent) as f64 / raw as f64) * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    fn large_batch() -> String {
+        let messages: Vec<_> = (0..50)
+            .map(|i| serde_json::json!({ "id": i, "content": "Halo, mobilnya masih tersedia?" }))
+            .collect();
+        serde_json::json!({ "type": "new_message", "messages": messages }).to_string()
+    }
+
+    #[test]
+    fn test_encoding_from_query() {
+        assert_eq!(WsEncoding::from_query(None), WsEncoding::Json);
+        assert_eq!(WsEncoding::from_query(Some("token=abc")), WsEncoding::Json);
+        assert_eq!(WsEncoding::from_query(Some("token=abc&compression=deflate")), WsEncoding::Deflate);
+    }
+
+    #[test]
+    fn test_large_payload_roundtrip() {
+        let encoder = WsEncoder::new(WsEncoding::Deflate, 512, true);
+        let payload = large_batch();
+
+        let Message::Binary(compressed) = encoder.encode(payload.clone()) else {
+            panic!("payload besar harus dikirim sebagai binary frame");
+        };
+
+        let mut decoded = String::new();
+        DeflateDecoder::new(&compressed[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, payload);
+
+        let (raw, sent) = encoder.bandwidth();
+        assert_eq!(raw, payload.len() as u64);
+        assert!(sent < raw);
+    }
+
+    #[test]
+    fn test_small_or_plain_payload_stays_text() {
+        let compressed = WsEncoder::new(WsEncoding::Deflate, 512, false);
+        assert!(matches!(compressed.encode(r#"{"type":"ping"}"#.to_string()), Message::Text(_)));
+
+        let plain = WsEncoder::new(WsEncoding::Json, 512, false);
+        assert!(matches!(plain.encode(large_batch()), Message::Text(_)));
+    }
+
+    #[test]
+    fn test_savings_percent() {
+        assert_eq!(savings_percent(0, 0), 0.0);
+        assert_eq!(savings_percent(1000, 250), 75.0);
+    }
+}
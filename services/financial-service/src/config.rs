@@ -3,6 +3,20 @@ use serde::Deserialize;
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use std::time::Duration;
 use crate::middleware::rate_limit::RateLimiter;
+use crate::utils::payout::{DEFAULT_MIN_PAYOUT_AMOUNT, DEFAULT_PAYOUT_CLEARING_DAYS, DEFAULT_PAYOUT_INTERVAL_HOURS};
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+use shared::utils::bind_addr;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("ledger_entries", &["id", "seller_id", "entry_type", "source_type", "source_id", "amount"]),
+    ("payouts", &["id", "seller_id", "amount", "status", "cleared_until"]),
+    ("payout_items", &["id", "payout_id", "ledger_entry_id"]),
+    ("withdrawals", &["id", "seller_id", "amount", "status"]),
+    ("seller_balance", &["seller_id", "available_balance", "pending_balance"]),
+    ("transaction_logs", &["id", "user_id", "amount", "status"]),
+];
 
 // Konfigurasi aplikasi dari environment variables
 #[derive(Debug, Clone, Deserialize)]
@@ -12,13 +26,29 @@ pub struct AppConfig {
     pub server_host: String,
     pub server_port: u16,
     pub environment: String,
+    // Jadwal payout seller
+    pub payout_interval_hours: u64,
+    pub payout_clearing_days: i64,
+    pub min_payout_amount: f64,
 }
 
 // Status untuk health check
-#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
 pub struct HealthStatus {
-    pub database: String,
-    pub overall: String,
+    pub database: DependencyHealth,
+    pub overall: HealthLevel,
+}
+
+impl HealthStatus {
+    // Probe database dengan latency; detail error hanya masuk log
+    pub async fn check(pool: &PgPool) -> Self {
+        let database = health::probe("database", check_db_health(pool)).await;
+
+        HealthStatus {
+            overall: health::worst_status(&[&database]),
+            database,
+        }
+    }
 }
 
 // Application state dengan dependency injection
@@ -40,23 +70,41 @@ impl AppConfig {
         let jwt_secret = std::env::var("JWT_SECRET")
             .map_err(|_| "JWT_SECRET environment variable harus diset".to_string())?;
 
-        let server_host = std::env::var("FINANCIAL_SERVICE_HOST")
-            .unwrap_or_else(|_| "0.0.0.0".to_string());
-
-        let server_port = std::env::var("FINANCIAL_SERVICE_PORT")
-            .unwrap_or_else(|_| "3008".to_string())
-            .parse::<u16>()
-            .map_err(|_| "FINANCIAL_SERVICE_PORT harus berupa angka".to_string())?;
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("FINANCIAL_SERVICE_HOST", Some(bind_addr::DEFAULT_HOST))?;
+        let server_port = bind_addr::port_from_env("FINANCIAL_SERVICE_PORT", Some(3008))?;
+        bind_addr::resolve("FINANCIAL_SERVICE_HOST", &server_host, server_port)?;
 
         let environment = std::env::var("RUST_ENV")
             .unwrap_or_else(|_| "development".to_string());
 
+        // Jadwal payout: interval batch, masa clearing, dan nominal minimum
+        let payout_interval_hours = std::env::var("PAYOUT_INTERVAL_HOURS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|hours| *hours > 0)
+            .unwrap_or(DEFAULT_PAYOUT_INTERVAL_HOURS);
+
+        let payout_clearing_days = std::env::var("PAYOUT_CLEARING_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .filter(|days| *days >= 0)
+            .unwrap_or(DEFAULT_PAYOUT_CLEARING_DAYS);
+
+        let min_payout_amount = std::env::var("MIN_PAYOUT_AMOUNT")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(DEFAULT_MIN_PAYOUT_AMOUNT);
+
         Ok(Self {
             database_url,
             jwt_secret,
             server_host,
             server_port,
             environment,
+            payout_interval_hours,
+            payout_clearing_days,
+            min_payout_amount,
         })
     }
 
@@ -82,8 +130,7 @@ impl axum::extract::FromRef<AppState> for crate::middleware::rate_limit::RateLim
 
 impl AppState {
     // Buat AppState baru dengan database connection pool dan rate limiter
-    pub async fn new() -> Result<Self, String> {
-        let config = AppConfig::from_env()?;
+    pub async fn new(config: AppConfig) -> Result<Self, String> {
 
         // Security check untuk production
         if config.is_production() && config.jwt_secret.contains("change-this") {
@@ -92,6 +139,9 @@ impl AppState {
 
         let db = init_db_pool(&config.database_url).await?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "financial-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         // Inisialisasi rate limiter dengan Redis dari environment
         let redis_url = std::env::var("REDIS_URL")
             .map_err(|_| "REDIS_URL environment variable harus diset".to_string())?;
@@ -103,12 +153,7 @@ impl AppState {
 
     // Health check untuk database
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
-
-        HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-        }
+        HealthStatus::check(&self.db).await
     }
 }
 
@@ -125,9 +170,10 @@ async fn init_db_pool(database_url: &str) -> Result<PgPool, String> {
 }
 
 // Cek kesehatan database dengan simple query
-pub async fn check_db_health(pool: &PgPool) -> bool {
-    sqlx::query_scalar::<_, i32>("SELECT 1")
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
+    sqlx::query("SELECT 1")
         .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
\ No newline at end of file
@@ -294,6 +294,12 @@ impl<'de> serde::Deserialize<'de> for Withdrawal {
     }
 }
 
+// Request DTO untuk POST /api/admin/withdrawals/{id}/reject
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RejectWithdrawalRequest {
+    pub reason: String,
+}
+
 // Response DTO untuk GET /api/seller/withdrawals 
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct WithdrawalsListResponse {
@@ -309,4 +315,93 @@ pub struct WithdrawalsListQuery {
     pub status: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
-}
\ No newline at end of file
+}
+// Payout status enum
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub enum PayoutStatus {
+    Processing,
+    Paid,
+    Failed,
+}
+
+// Status dari database; nilai yang tidak dikenal jadi error, bukan panic
+impl TryFrom<&str> for PayoutStatus {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "processing" => Ok(PayoutStatus::Processing),
+            "paid" => Ok(PayoutStatus::Paid),
+            "failed" => Ok(PayoutStatus::Failed),
+            _ => Err(format!("Status payout tidak dikenal: {}", s)),
+        }
+    }
+}
+
+impl PayoutStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayoutStatus::Processing => "processing",
+            PayoutStatus::Paid => "paid",
+            PayoutStatus::Failed => "failed",
+        }
+    }
+}
+
+// Implement Display untuk logging
+impl std::fmt::Display for PayoutStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Payout batch dari payouts table
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Payout {
+    pub id: i32,
+    pub seller_id: i32,
+    pub amount: f64,
+    pub item_count: i32,
+    pub status: PayoutStatus,
+    pub cleared_until: DateTime<Utc>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+}
+
+// Ringkasan dana seller untuk rekonsiliasi saldo vs payout
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PayoutSummary {
+    // Saldo ledger saat ini (sama dengan available_balance)
+    pub ledger_balance: f64,
+    // Credit yang sudah lewat masa clearing dan belum masuk payout
+    pub eligible_amount: f64,
+    // Credit yang masih dalam masa clearing
+    pub clearing_amount: f64,
+    pub clearing_days: i64,
+}
+
+// Response DTO untuk GET /api/seller/payouts
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PayoutsListResponse {
+    pub payouts: Vec<Payout>,
+    pub summary: PayoutSummary,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+// Query parameters untuk payout list
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PayoutsListQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Request DTO untuk POST /api/admin/payouts/{id}/fail
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FailPayoutRequest {
+    pub reason: String,
+}
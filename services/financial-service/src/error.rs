@@ -3,6 +3,7 @@ use axum::{
     response::{IntoResponse, Json, Response},
 };
 use serde_json::json;
+use shared::utils::request_id;
 use thiserror::Error;
 
 // Error type untuk aplikasi dengan HTTP mapping
@@ -64,13 +65,21 @@ impl IntoResponse for AppError {
 
         tracing::error!("{}: {}", error_type, message);
 
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
         let body = json!({
             "success": false,
             "error": error_type,
-            "message": message
+            "message": message,
+            "request_id": request_id,
         });
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        response
     }
 }
 
@@ -87,6 +96,17 @@ impl From<jsonwebtoken::errors::Error> for AppError {
     }
 }
 
+// Konversi dari error autentikasi bersama (extractor AuthAdmin)
+impl From<shared::auth::AuthError> for AppError {
+    fn from(err: shared::auth::AuthError) -> Self {
+        match err.status_code() {
+            StatusCode::FORBIDDEN => AppError::AuthorizationError(err.to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR => AppError::DatabaseError(err.to_string()),
+            _ => AppError::AuthenticationError(err.to_string()),
+        }
+    }
+}
+
 impl From<validator::ValidationErrors> for AppError {
     fn from(err: validator::ValidationErrors) -> Self {
         let messages: Vec<String> = err
@@ -100,4 +120,20 @@ impl From<validator::ValidationErrors> for AppError {
             .collect();
         AppError::validation(&messages.join(", "))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("Payout tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
+    }
+}
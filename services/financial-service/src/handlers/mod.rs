@@ -1,4 +1,5 @@
 // Handlers exports
 pub mod balance;
+pub mod payouts;
 pub mod transactions;
 pub mod withdrawals;
\ No newline at end of file
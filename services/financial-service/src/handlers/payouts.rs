@@ -0,0 +1,561 @@
+use crate::config::{AppConfig, AppState};
+use crate::domain::models::{FailPayoutRequest, Payout, PayoutStatus, PayoutSummary, PayoutsListQuery, PayoutsListResponse};
+use crate::error::AppError;
+use crate::middleware::{AuthAdmin, AuthSeller};
+use crate::utils::payout;
+use axum::{extract::{Path, Query, State}, Json};
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, PgPool, Row};
+
+const PAYOUT_COLUMNS: &str = "id, seller_id, amount::FLOAT8 AS amount, item_count, status,
+    cleared_until, failure_reason, created_at, paid_at, failed_at";
+
+// Credit pendapatan (penjualan & sewa) yang belum masuk payout aktif, dengan sisa setelah
+// refund. Refund sebagian hanya mengurangi amount credit; credit yang sudah direfund penuh
+// tidak ikut. Credit pembalik (withdrawal_rejected, payout_failed) tidak dihitung: payout
+// gagal melepas payout_items-nya sehingga credit asalnya kembali terbuka.
+// Dipakai sebagai sumber baris `le` (id, seller_id, amount, created_at).
+const OPEN_CREDITS: &str = "(
+    SELECT c.id, c.seller_id, c.created_at, c.amount - refunded.amount AS amount
+    FROM ledger_entries c
+    CROSS JOIN LATERAL (
+        SELECT COALESCE(SUM(r.amount), 0) AS amount FROM ledger_entries r
+        WHERE r.entry_type = 'debit' AND r.source_id = c.source_id
+            AND (r.source_type, c.source_type) IN (('sale_refund', 'sale_completed'), ('rental_refund', 'rental_completed'))
+    ) refunded
+    WHERE c.entry_type = 'credit' AND c.source_type IN ('sale_completed', 'rental_completed')
+        AND NOT EXISTS (SELECT 1 FROM payout_items pi WHERE pi.ledger_entry_id = c.id)
+        AND c.amount > refunded.amount
+) le";
+
+fn payout_from_row(row: &PgRow) -> Result<Payout, AppError> {
+    let status_str: String = row.get("status");
+    Ok(Payout {
+        id: row.get("id"),
+        seller_id: row.get("seller_id"),
+        amount: row.get("amount"),
+        item_count: row.get("item_count"),
+        status: PayoutStatus::try_from(status_str.as_str()).map_err(|e| AppError::database(&e))?,
+        cleared_until: row.get("cleared_until"),
+        failure_reason: row.get("failure_reason"),
+        created_at: row.get::<Option<DateTime<Utc>>, _>("created_at").unwrap_or_else(Utc::now),
+        paid_at: row.get("paid_at"),
+        failed_at: row.get("failed_at"),
+    })
+}
+
+// Seller yang punya credit sudah clear dan belum masuk payout
+pub async fn sellers_with_cleared_funds(db: &PgPool, cutoff: DateTime<Utc>) -> Result<Vec<i32>, AppError> {
+    let sellers = sqlx::query_scalar::<_, i32>(&format!(
+        "SELECT DISTINCT le.seller_id FROM {} WHERE le.created_at <= $1",
+        OPEN_CREDITS
+    ))
+    .bind(cutoff)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to find sellers with cleared funds: {}", e);
+        AppError::DatabaseError(format!("Gagal mengambil seller untuk payout: {}", e))
+    })?;
+
+    Ok(sellers)
+}
+
+// Gabungkan credit seller yang sudah clear menjadi satu payout batch dan debit ledger.
+// Return None jika tidak ada dana yang memenuhi syarat.
+pub async fn run_payout_batch(
+    db: &PgPool,
+    config: &AppConfig,
+    seller_id: i32,
+) -> Result<Option<Payout>, AppError> {
+    let cutoff = payout::clearing_cutoff(Utc::now(), config.payout_clearing_days);
+
+    let mut tx = db.begin().await
+        .map_err(|e| {
+            tracing::error!("Failed to start transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal memulai transaksi: {}", e))
+        })?;
+
+    // Lock saldo seller agar payout dan withdrawal tidak berjalan bersamaan
+    let locked = sqlx::query_scalar::<_, i32>(
+        "SELECT seller_id FROM seller_balance WHERE seller_id = $1 FOR UPDATE"
+    )
+    .bind(seller_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if locked.is_none() {
+        return Ok(None);
+    }
+
+    let credits: Vec<(i32, f64)> = sqlx::query_as(&format!(
+        "SELECT le.id, le.amount::FLOAT8 FROM {}
+         WHERE le.seller_id = $1 AND le.created_at <= $2
+         ORDER BY le.id",
+        OPEN_CREDITS
+    ))
+    .bind(seller_id)
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if credits.is_empty() {
+        return Ok(None);
+    }
+
+    let cleared_total: f64 = credits.iter().map(|(_, amount)| amount).sum();
+
+    let ledger_balance = sqlx::query_scalar::<_, f64>(
+        "SELECT COALESCE((SELECT balance FROM seller_ledger_balance WHERE seller_id = $1), 0)::FLOAT8"
+    )
+    .bind(seller_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let Some(amount) = payout::payout_amount(cleared_total, ledger_balance, config.min_payout_amount) else {
+        return Ok(None);
+    };
+
+    // 1. Insert payout batch
+    let row = sqlx::query(&format!(
+        "INSERT INTO payouts (seller_id, amount, item_count, status, cleared_until)
+         VALUES ($1, $2, $3, 'processing', $4)
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(seller_id)
+    .bind(amount)
+    .bind(credits.len() as i32)
+    .bind(cutoff)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create payout for seller_id {}: {}", seller_id, e);
+        AppError::DatabaseError(format!("Gagal membuat payout: {}", e))
+    })?;
+
+    let payout = payout_from_row(&row)?;
+
+    // 2. Tandai credit ledger yang masuk batch ini
+    let (entry_ids, amounts): (Vec<i32>, Vec<f64>) = credits.into_iter().unzip();
+    sqlx::query(
+        "INSERT INTO payout_items (payout_id, ledger_entry_id, amount)
+         SELECT $1, entry_id, amount FROM UNNEST($2::INT[], $3::FLOAT8[]) AS t(entry_id, amount)"
+    )
+    .bind(payout.id)
+    .bind(&entry_ids)
+    .bind(&amounts)
+    .execute(&mut *tx)
+    .await?;
+
+    // 3. Debit ledger (trigger mengurangi available_balance di seller_balance)
+    sqlx::query(
+        r#"
+        INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+        VALUES ($1, 'debit', 'payout', $2, $3, $4)
+        "#
+    )
+    .bind(seller_id)
+    .bind(payout.id)
+    .bind(amount)
+    .bind(format!("Payout #{}", payout.id))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to debit ledger for payout {}: {}", payout.id, e);
+        AppError::DatabaseError(format!("Gagal update saldo: {}", e))
+    })?;
+
+    tx.commit().await
+        .map_err(|e| {
+            tracing::error!("Failed to commit transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal commit transaksi: {}", e))
+        })?;
+
+    tracing::info!(
+        "Payout {} created for seller {}: Rp {:.2} ({} credits)",
+        payout.id,
+        seller_id,
+        payout.amount,
+        payout.item_count
+    );
+
+    Ok(Some(payout))
+}
+
+// Ringkasan saldo ledger vs dana yang sudah/belum clear
+async fn payout_summary(db: &PgPool, config: &AppConfig, seller_id: i32) -> Result<PayoutSummary, AppError> {
+    let cutoff = payout::clearing_cutoff(Utc::now(), config.payout_clearing_days);
+
+    let row = sqlx::query(&format!(
+        "SELECT
+            COALESCE((SELECT balance FROM seller_ledger_balance WHERE seller_id = $1), 0)::FLOAT8 AS ledger_balance,
+            COALESCE(SUM(le.amount) FILTER (WHERE le.created_at <= $2), 0)::FLOAT8 AS cleared,
+            COALESCE(SUM(le.amount) FILTER (WHERE le.created_at > $2), 0)::FLOAT8 AS clearing
+         FROM {}
+         WHERE le.seller_id = $1",
+        OPEN_CREDITS
+    ))
+    .bind(seller_id)
+    .bind(cutoff)
+    .fetch_one(db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch payout summary for seller_id {}: {}", seller_id, e);
+        AppError::DatabaseError(format!("Gagal mengambil ringkasan payout: {}", e))
+    })?;
+
+    let ledger_balance: f64 = row.get("ledger_balance");
+    let cleared: f64 = row.get("cleared");
+
+    Ok(PayoutSummary {
+        ledger_balance,
+        eligible_amount: cleared.min(ledger_balance).max(0.0),
+        clearing_amount: row.get("clearing"),
+        clearing_days: config.payout_clearing_days,
+    })
+}
+
+// Get list payout untuk seller
+#[utoipa::path(
+    get,
+    path = "/api/seller/payouts",
+    responses(
+        (status = 200, description = "List payout berhasil diambil", body = PayoutsListResponse),
+        (status = 400, description = "Filter status tidak valid"),
+        (status = 401, description = "Unauthorized - JWT token invalid or missing"),
+        (status = 403, description = "Forbidden - User is not a seller")
+    ),
+    params(
+        ("status" = Option<String>, Query, description = "Filter by status (processing, paid, failed)"),
+        ("limit" = Option<i64>, Query, description = "Jumlah item per halaman (default: 50, max: 100)"),
+        ("offset" = Option<i64>, Query, description = "Offset untuk pagination (default: 0)")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Seller Payouts"
+)]
+pub async fn list_payouts(
+    State(state): State<AppState>,
+    auth: AuthSeller,
+    Query(params): Query<PayoutsListQuery>,
+) -> Result<Json<PayoutsListResponse>, AppError> {
+    let seller_id = auth.user_id;
+
+    if let Some(status) = params.status.as_deref() {
+        if !matches!(status, "processing" | "paid" | "failed") {
+            return Err(AppError::validation("Status payout tidak valid"));
+        }
+    }
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM payouts WHERE seller_id = $1 AND ($2::TEXT IS NULL OR status = $2)"
+    )
+    .bind(seller_id)
+    .bind(&params.status)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count payouts for seller_id {}: {}", seller_id, e);
+        AppError::DatabaseError(format!("Gagal menghitung payout: {}", e))
+    })?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM payouts
+         WHERE seller_id = $1 AND ($2::TEXT IS NULL OR status = $2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3 OFFSET $4",
+        PAYOUT_COLUMNS
+    ))
+    .bind(seller_id)
+    .bind(&params.status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch payouts for seller_id {}: {}", seller_id, e);
+        AppError::DatabaseError(format!("Gagal mengambil payout: {}", e))
+    })?;
+
+    let payouts = rows.iter().map(payout_from_row).collect::<Result<_, _>>()?;
+    let summary = payout_summary(&state.db, &state.config, seller_id).await?;
+
+    Ok(Json(PayoutsListResponse {
+        payouts,
+        summary,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+// Lock payout yang masih processing untuk diproses admin
+async fn lock_processing_payout(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: i32,
+) -> Result<(i32, f64), AppError> {
+    let row = sqlx::query(
+        "SELECT seller_id, amount::FLOAT8 AS amount, status FROM payouts WHERE id = $1 FOR UPDATE"
+    )
+    .bind(id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Payout tidak ditemukan"))?;
+
+    let status: String = row.get("status");
+    let current = PayoutStatus::try_from(status.as_str()).map_err(|e| AppError::database(&e))?;
+    if !matches!(current, PayoutStatus::Processing) {
+        return Err(AppError::validation(format!(
+            "Payout sudah diproses dengan status {}",
+            status
+        ).as_str()));
+    }
+
+    Ok((row.get("seller_id"), row.get("amount")))
+}
+
+// Tandai payout sudah ditransfer ke seller
+#[utoipa::path(
+    post,
+    path = "/api/admin/payouts/{id}/complete",
+    responses(
+        (status = 200, description = "Payout ditandai paid", body = Payout),
+        (status = 400, description = "Payout sudah diproses sebelumnya"),
+        (status = 401, description = "Unauthorized - JWT token invalid or missing"),
+        (status = 403, description = "Forbidden - User is not an admin"),
+        (status = 404, description = "Payout tidak ditemukan")
+    ),
+    params(
+        ("id" = i32, Path, description = "Payout ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin Payouts"
+)]
+pub async fn complete_payout(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(id): Path<i32>,
+) -> Result<Json<Payout>, AppError> {
+    let mut tx = state.db.begin().await?;
+
+    lock_processing_payout(&mut tx, id).await?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE payouts SET status = 'paid', paid_at = NOW(), processed_by = $2
+         WHERE id = $1
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(id)
+    .bind(admin.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("Payout {} marked paid by admin {}", id, admin.user_id);
+
+    Ok(Json(payout_from_row(&row)?))
+}
+
+// Payout gagal ditransfer: saldo dikembalikan dan credit ikut batch berikutnya
+#[utoipa::path(
+    post,
+    path = "/api/admin/payouts/{id}/fail",
+    responses(
+        (status = 200, description = "Payout ditandai failed dan saldo dikembalikan", body = Payout),
+        (status = 400, description = "Alasan kosong atau payout sudah diproses sebelumnya"),
+        (status = 401, description = "Unauthorized - JWT token invalid or missing"),
+        (status = 403, description = "Forbidden - User is not an admin"),
+        (status = 404, description = "Payout tidak ditemukan")
+    ),
+    params(
+        ("id" = i32, Path, description = "Payout ID")
+    ),
+    request_body = FailPayoutRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin Payouts"
+)]
+pub async fn fail_payout(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(id): Path<i32>,
+    Json(payload): Json<FailPayoutRequest>,
+) -> Result<Json<Payout>, AppError> {
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::validation("Alasan gagal payout wajib diisi"));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let (seller_id, amount) = lock_processing_payout(&mut tx, id).await?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE payouts SET status = 'failed', failed_at = NOW(), processed_by = $2, failure_reason = $3
+         WHERE id = $1
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(id)
+    .bind(admin.user_id)
+    .bind(reason)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Credit balik ke ledger (trigger menambah available_balance)
+    sqlx::query(
+        r#"
+        INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+        VALUES ($1, 'credit', 'payout_failed', $2, $3, $4)
+        "#
+    )
+    .bind(seller_id)
+    .bind(id)
+    .bind(amount)
+    .bind(format!("Payout #{} gagal: {}", id, reason))
+    .execute(&mut *tx)
+    .await?;
+
+    // Lepas credit dari batch ini agar masuk payout berikutnya
+    sqlx::query("DELETE FROM payout_items WHERE payout_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Payout {} failed by admin {}, Rp {:.2} dikembalikan ke seller {}",
+        id,
+        admin.user_id,
+        amount,
+        seller_id
+    );
+
+    Ok(Json(payout_from_row(&row)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AppConfig {
+        AppConfig {
+            database_url: String::new(),
+            jwt_secret: String::new(),
+            server_host: "127.0.0.1".to_string(),
+            server_port: 0,
+            environment: "test".to_string(),
+            payout_interval_hours: 24,
+            payout_clearing_days: 0,
+            min_payout_amount: 0.0,
+        }
+    }
+
+    // Rental selesai milik seller 2 dari test_seed, komisi rental default 5%
+    async fn complete_rental(db: &PgPool, id: i32, total_price: f64) {
+        sqlx::query(
+            "INSERT INTO rental_bookings (id, vehicle_id, customer_id, seller_id, order_id, pickup_date, return_date,
+                                          customer_name, customer_phone, customer_email, total_days, price_per_day,
+                                          total_price, status)
+             VALUES ($1, 1, 1, 2, 'RNT-' || $1, NOW() - INTERVAL '3 days', NOW() - INTERVAL '1 day',
+                     'Customer Test', '081200000001', 'customer@test.local', 2, $2 / 2, $2, 'berjalan')"
+        )
+        .bind(id)
+        .bind(total_price)
+        .execute(db)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE rental_bookings SET status = 'selesai' WHERE id = $1")
+            .bind(id)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_rental_credits_are_paid_out(db: PgPool) {
+        complete_rental(&db, 1, 2_000_000.0).await;
+        complete_rental(&db, 2, 1_000_000.0).await;
+
+        // Biaya sewa rental 2 direfund: credit-nya tidak ikut payout
+        sqlx::query(
+            "INSERT INTO payments (rental_booking_id, order_id, gross_amount, status, payment_for_type)
+             VALUES (2, 'PAY-RNT-2', 1000000, 'success', 'rental')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE payments SET status = 'refunded', refund_amount = 950000 WHERE rental_booking_id = 2")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let summary = payout_summary(&db, &config(), 2).await.unwrap();
+        assert_eq!(summary.ledger_balance, 1_900_000.0);
+        assert_eq!(summary.eligible_amount, 1_900_000.0);
+
+        let payout = run_payout_batch(&db, &config(), 2).await.unwrap().unwrap();
+        assert_eq!(payout.amount, 1_900_000.0);
+        assert_eq!(payout.item_count, 1);
+
+        let summary = payout_summary(&db, &config(), 2).await.unwrap();
+        assert_eq!(summary.ledger_balance, 0.0);
+        assert_eq!(summary.eligible_amount, 0.0);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_partially_refunded_credit_pays_out_remainder(db: PgPool) {
+        complete_rental(&db, 1, 1_000_000.0).await;
+
+        // Refund sebagian biaya sewa: sisa credit (950.000 - 400.000) tetap bisa dicairkan
+        sqlx::query(
+            "INSERT INTO payments (rental_booking_id, order_id, gross_amount, status, payment_for_type)
+             VALUES (1, 'PAY-RNT-1', 1000000, 'success', 'rental')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE payments SET status = 'refunded', refund_amount = 400000 WHERE rental_booking_id = 1")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let summary = payout_summary(&db, &config(), 2).await.unwrap();
+        assert_eq!(summary.eligible_amount, 550_000.0);
+
+        let payout = run_payout_batch(&db, &config(), 2).await.unwrap().unwrap();
+        assert_eq!((payout.amount, payout.item_count), (550_000.0, 1));
+        assert!(run_payout_batch(&db, &config(), 2).await.unwrap().is_none());
+
+        // Status payout yang tidak dikenal jadi error, bukan panic
+        sqlx::query("ALTER TABLE payouts DROP CONSTRAINT payouts_status_check").execute(&db).await.unwrap();
+        sqlx::query("UPDATE payouts SET status = 'on_hold'").execute(&db).await.unwrap();
+        let row = sqlx::query(&format!("SELECT {} FROM payouts", PAYOUT_COLUMNS)).fetch_one(&db).await.unwrap();
+        assert!(matches!(payout_from_row(&row), Err(AppError::DatabaseError(_))));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::config::AppState;
-use crate::domain::models::{CreateWithdrawalRequest, WithdrawalResponse, Withdrawal, WithdrawalsListResponse, WithdrawalsListQuery};
+use crate::domain::models::{CreateWithdrawalRequest, RejectWithdrawalRequest, WithdrawalResponse, Withdrawal, WithdrawalStatus, WithdrawalsListResponse, WithdrawalsListQuery};
 use crate::error::AppError;
-use crate::middleware::AuthSeller;
+use crate::middleware::{AuthAdmin, AuthSeller};
 use axum::{extract::{Path, Query, State}, Json};
 use sqlx::Row;
 
@@ -48,34 +48,45 @@ pub async fn create_withdrawal(
         ).as_str()));
     }
 
-    // Check saldo tersedia di seller_balance
-    let balance_row = sqlx::query!(
+    // Start transaction untuk lock saldo + buat withdrawal record + ledger debit + transaction log
+    let mut tx = state.db.begin().await
+        .map_err(|e| {
+            tracing::error!("Failed to start transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal memulai transaksi: {}", e))
+        })?;
+
+    // Lock row seller_balance agar withdrawal paralel diproses berurutan
+    let locked = sqlx::query_scalar!(
+        "SELECT seller_id FROM seller_balance WHERE seller_id = $1 FOR UPDATE",
+        seller_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to lock balance for seller_id {}: {}", seller_id, e);
+        AppError::DatabaseError(format!("Gagal mengambil saldo: {}", e))
+    })?;
+
+    if locked.is_none() {
+        return Err(AppError::not_found("Saldo seller tidak ditemukan"));
+    }
+
+    // Saldo tersedia dihitung dari ledger (sumber kebenaran saldo)
+    let available_balance = sqlx::query_scalar!(
         r#"
-        SELECT
-            seller_id as "seller_id!: i32",
-            available_balance::FLOAT8 as "available_balance!: f64",
-            pending_balance::FLOAT8 as "pending_balance!: f64",
-            total_earned::FLOAT8 as "total_earned!: f64",
-            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>"
-        FROM seller_balance
-        WHERE seller_id = $1
+        SELECT COALESCE(
+            (SELECT balance FROM seller_ledger_balance WHERE seller_id = $1), 0
+        )::FLOAT8 as "balance!: f64"
         "#,
         seller_id
     )
-    .fetch_optional(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
-        tracing::error!("Failed to fetch balance for seller_id {}: {}", seller_id, e);
+        tracing::error!("Failed to fetch ledger balance for seller_id {}: {}", seller_id, e);
         AppError::DatabaseError(format!("Gagal mengambil saldo: {}", e))
     })?;
 
-    let available_balance = match balance_row {
-        Some(row) => row.available_balance,
-        None => {
-            return Err(AppError::not_found("Saldo seller tidak ditemukan"));
-        }
-    };
-
     // Validate amount tidak melebihi saldo tersedia
     if payload.amount > available_balance {
         return Err(AppError::validation(format!(
@@ -85,13 +96,6 @@ pub async fn create_withdrawal(
         ).as_str()));
     }
 
-    // Start transaction untuk buat withdrawal record + update balance + create transaction log
-    let mut tx = state.db.begin().await
-        .map_err(|e| {
-            tracing::error!("Failed to start transaction: {}", e);
-            AppError::DatabaseError(format!("Gagal memulai transaksi: {}", e))
-        })?;
-
     // 1. Insert ke withdrawals table
     let withdrawal_row = sqlx::query(
         r#"
@@ -122,22 +126,21 @@ pub async fn create_withdrawal(
 
     let withdrawal_id: i32 = withdrawal_row.get("id");
 
-    // 2. Reduce available_balance di seller_balance
+    // 2. Debit ledger (trigger mengurangi available_balance di seller_balance)
     sqlx::query(
         r#"
-        UPDATE seller_balance
-        SET
-            available_balance = available_balance - $2,
-            updated_at = NOW()
-        WHERE seller_id = $1
+        INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+        VALUES ($1, 'debit', 'withdrawal', $2, $3, $4)
         "#
     )
     .bind(seller_id)
+    .bind(withdrawal_id)
     .bind(payload.amount)
+    .bind(format!("Withdrawal request #{}", withdrawal_id))
     .execute(&mut *tx)
     .await
     .map_err(|e| {
-        tracing::error!("Failed to update balance for seller_id {}: {}", seller_id, e);
+        tracing::error!("Failed to debit ledger for seller_id {}: {}", seller_id, e);
         AppError::DatabaseError(format!("Gagal update saldo: {}", e))
     })?;
 
@@ -442,4 +445,404 @@ pub async fn get_withdrawal_by_id(
     );
 
     Ok(Json(withdrawal))
-}
\ No newline at end of file
+}
+
+// Lock withdrawal yang masih pending untuk diproses admin
+async fn lock_pending_withdrawal(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: i32,
+) -> Result<(i32, f64), AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            seller_id,
+            amount::FLOAT8 as "amount!: f64",
+            status as "status!: String"
+        FROM withdrawals
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to lock withdrawal {}: {}", id, e);
+        AppError::DatabaseError(format!("Gagal mengambil withdrawal: {}", e))
+    })?
+    .ok_or_else(|| AppError::not_found("Withdrawal tidak ditemukan"))?;
+
+    if !matches!(WithdrawalStatus::from(row.status.as_str()), WithdrawalStatus::Pending) {
+        return Err(AppError::validation(format!(
+            "Withdrawal sudah diproses dengan status {}",
+            row.status
+        ).as_str()));
+    }
+
+    Ok((row.seller_id, row.amount))
+}
+
+// Approve withdrawal seller oleh admin (payout selesai)
+#[utoipa::path(
+    post,
+    path = "/api/admin/withdrawals/{id}/approve",
+    responses(
+        (status = 200, description = "Withdrawal berhasil di-approve", body = Withdrawal),
+        (status = 400, description = "Withdrawal sudah diproses sebelumnya"),
+        (status = 401, description = "Unauthorized - JWT token invalid or missing"),
+        (status = 403, description = "Forbidden - User is not an admin"),
+        (status = 404, description = "Withdrawal tidak ditemukan")
+    ),
+    params(
+        ("id" = i32, Path, description = "Withdrawal ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin Withdrawals"
+)]
+pub async fn approve_withdrawal(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(id): Path<i32>,
+) -> Result<Json<Withdrawal>, AppError> {
+    tracing::info!("Admin {} approving withdrawal {}", admin.user_id, id);
+
+    let mut tx = state.db.begin().await
+        .map_err(|e| {
+            tracing::error!("Failed to start transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal memulai transaksi: {}", e))
+        })?;
+
+    lock_pending_withdrawal(&mut tx, id).await?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE withdrawals
+        SET status = 'completed', processed_at = NOW(), completed_at = NOW(), processed_by = $2
+        WHERE id = $1
+        RETURNING
+            id, seller_id,
+            amount::FLOAT8 as "amount!: f64",
+            bank_name, account_number, account_holder_name,
+            status as "status!: String",
+            requested_at as "requested_at!: chrono::DateTime<chrono::Utc>",
+            processed_at, completed_at
+        "#,
+        id,
+        admin.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to approve withdrawal {}: {}", id, e);
+        AppError::DatabaseError(format!("Gagal approve withdrawal: {}", e))
+    })?;
+
+    // Tandai transaction_log withdrawal sebagai completed
+    sqlx::query!(
+        "UPDATE transaction_logs SET status = 'completed' WHERE withdrawal_id = $1 AND transaction_type = 'seller_withdrawal'",
+        id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update transaction_log for withdrawal {}: {}", id, e);
+        AppError::DatabaseError(format!("Gagal update transaction log: {}", e))
+    })?;
+
+    tx.commit().await
+        .map_err(|e| {
+            tracing::error!("Failed to commit transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal commit transaksi: {}", e))
+        })?;
+
+    tracing::info!("Withdrawal {} approved by admin {}", id, admin.user_id);
+
+    Ok(Json(Withdrawal {
+        id: row.id,
+        seller_id: row.seller_id,
+        amount: row.amount,
+        bank_name: row.bank_name,
+        account_number: row.account_number,
+        account_holder_name: row.account_holder_name,
+        status: WithdrawalStatus::from(row.status.as_str()),
+        requested_at: row.requested_at,
+        processed_at: row.processed_at,
+        completed_at: row.completed_at,
+    }))
+}
+
+// Reject withdrawal seller oleh admin, saldo dikembalikan lewat ledger
+#[utoipa::path(
+    post,
+    path = "/api/admin/withdrawals/{id}/reject",
+    responses(
+        (status = 200, description = "Withdrawal berhasil di-reject dan saldo dikembalikan", body = Withdrawal),
+        (status = 400, description = "Alasan kosong atau withdrawal sudah diproses sebelumnya"),
+        (status = 401, description = "Unauthorized - JWT token invalid or missing"),
+        (status = 403, description = "Forbidden - User is not an admin"),
+        (status = 404, description = "Withdrawal tidak ditemukan")
+    ),
+    params(
+        ("id" = i32, Path, description = "Withdrawal ID")
+    ),
+    request_body = RejectWithdrawalRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin Withdrawals"
+)]
+pub async fn reject_withdrawal(
+    State(state): State<AppState>,
+    admin: AuthAdmin,
+    Path(id): Path<i32>,
+    Json(payload): Json<RejectWithdrawalRequest>,
+) -> Result<Json<Withdrawal>, AppError> {
+    tracing::info!("Admin {} rejecting withdrawal {}", admin.user_id, id);
+
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::validation("Alasan reject wajib diisi"));
+    }
+
+    let mut tx = state.db.begin().await
+        .map_err(|e| {
+            tracing::error!("Failed to start transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal memulai transaksi: {}", e))
+        })?;
+
+    let (seller_id, amount) = lock_pending_withdrawal(&mut tx, id).await?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE withdrawals
+        SET status = 'failed', processed_at = NOW(), processed_by = $2, rejection_reason = $3
+        WHERE id = $1
+        RETURNING
+            id, seller_id,
+            amount::FLOAT8 as "amount!: f64",
+            bank_name, account_number, account_holder_name,
+            status as "status!: String",
+            requested_at as "requested_at!: chrono::DateTime<chrono::Utc>",
+            processed_at, completed_at
+        "#,
+        id,
+        admin.user_id,
+        reason
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to reject withdrawal {}: {}", id, e);
+        AppError::DatabaseError(format!("Gagal reject withdrawal: {}", e))
+    })?;
+
+    // Credit balik ke ledger (trigger menambah available_balance)
+    sqlx::query(
+        r#"
+        INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount, description)
+        VALUES ($1, 'credit', 'withdrawal_rejected', $2, $3, $4)
+        "#
+    )
+    .bind(seller_id)
+    .bind(id)
+    .bind(amount)
+    .bind(format!("Withdrawal #{} ditolak: {}", id, reason))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to credit ledger for withdrawal {}: {}", id, e);
+        AppError::DatabaseError(format!("Gagal mengembalikan saldo: {}", e))
+    })?;
+
+    // Tandai transaction_log withdrawal sebagai reversed
+    sqlx::query!(
+        "UPDATE transaction_logs SET status = 'reversed', notes = $2 WHERE withdrawal_id = $1 AND transaction_type = 'seller_withdrawal'",
+        id,
+        format!("Withdrawal #{} ditolak: {}", id, reason)
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update transaction_log for withdrawal {}: {}", id, e);
+        AppError::DatabaseError(format!("Gagal update transaction log: {}", e))
+    })?;
+
+    tx.commit().await
+        .map_err(|e| {
+            tracing::error!("Failed to commit transaction: {}", e);
+            AppError::DatabaseError(format!("Gagal commit transaksi: {}", e))
+        })?;
+
+    tracing::info!(
+        "Withdrawal {} rejected by admin {}, Rp {:.2} dikembalikan ke seller {}",
+        id,
+        admin.user_id,
+        amount,
+        seller_id
+    );
+
+    Ok(Json(Withdrawal {
+        id: row.id,
+        seller_id: row.seller_id,
+        amount: row.amount,
+        bank_name: row.bank_name,
+        account_number: row.account_number,
+        account_holder_name: row.account_holder_name,
+        status: WithdrawalStatus::from(row.status.as_str()),
+        requested_at: row.requested_at,
+        processed_at: row.processed_at,
+        completed_at: row.completed_at,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    // Seller 2 dari test_seed, komisi sale default 5%
+    async fn complete_sale(db: &PgPool, id: i32, final_price: f64) {
+        sqlx::query(
+            "INSERT INTO sale_orders (id, vehicle_id, buyer_id, seller_id, order_id, tracking_reference,
+                                      asking_price, final_price, buyer_name, buyer_phone, buyer_email, status)
+             VALUES ($1, 2, 1, 2, 'SO-' || $1, 'TRK-' || $1, $2, $2, 'Customer Test', '081200000001',
+                     'customer@test.local', 'document_processing')"
+        )
+        .bind(id)
+        .bind(final_price)
+        .execute(db)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE sale_orders SET status = 'completed' WHERE id = $1")
+            .bind(id)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    async fn refund_sale(db: &PgPool, sale_order_id: i32, amount: f64) {
+        sqlx::query(
+            "INSERT INTO payments (sale_order_id, order_id, gross_amount, status, payment_for_type)
+             VALUES ($1, 'PAY-SO-' || $1, $2, 'success', 'sale')"
+        )
+        .bind(sale_order_id)
+        .bind(amount)
+        .execute(db)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE payments SET status = 'refunded', refund_amount = $2 WHERE sale_order_id = $1")
+            .bind(sale_order_id)
+            .bind(amount)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    async fn debit(db: &PgPool, source_type: &str, source_id: i32, amount: f64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO ledger_entries (seller_id, entry_type, source_type, source_id, amount)
+             VALUES (2, 'debit', $1, $2, $3)"
+        )
+        .bind(source_type)
+        .bind(source_id)
+        .bind(amount)
+        .execute(db)
+        .await
+        .map(|_| ())
+    }
+
+    // (saldo ledger, available_balance di seller_balance)
+    async fn balances(db: &PgPool) -> (f64, f64) {
+        sqlx::query_as(
+            "SELECT COALESCE((SELECT balance FROM seller_ledger_balance WHERE seller_id = 2), 0)::FLOAT8,
+                    COALESCE((SELECT available_balance FROM seller_balance WHERE seller_id = 2), 0)::FLOAT8"
+        )
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_completed_sale_credits_net_of_commission(db: PgPool) {
+        complete_sale(&db, 1, 100_000_000.0).await;
+        assert_eq!(balances(&db).await, (95_000_000.0, 95_000_000.0));
+
+        // Update status berikutnya tidak meng-credit dua kali
+        sqlx::query("UPDATE sale_orders SET status = 'completed', updated_at = NOW() WHERE id = 1")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert_eq!(balances(&db).await, (95_000_000.0, 95_000_000.0));
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_refund_debits_credited_sale(db: PgPool) {
+        complete_sale(&db, 1, 100_000_000.0).await;
+        complete_sale(&db, 2, 20_000_000.0).await;
+
+        // Debit refund maksimal sebesar credit penjualan tersebut
+        refund_sale(&db, 2, 20_000_000.0).await;
+        assert_eq!(balances(&db).await, (95_000_000.0, 95_000_000.0));
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_refund_after_withdrawal_never_goes_negative(db: PgPool) {
+        complete_sale(&db, 1, 100_000_000.0).await;
+        debit(&db, "withdrawal", 1, 90_000_000.0).await.unwrap();
+
+        // Refund penuh dipotong ke sisa saldo, selisihnya tercatat untuk ditagih manual
+        refund_sale(&db, 1, 100_000_000.0).await;
+        assert_eq!(balances(&db).await, (0.0, 0.0));
+
+        let (amount, description): (f64, String) = sqlx::query_as(
+            "SELECT amount::FLOAT8, description FROM ledger_entries WHERE source_type = 'sale_refund'"
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(amount, 5_000_000.0);
+        assert!(description.contains("saldo kurang Rp 90000000"), "{}", description);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_debit_beyond_balance_rejected(db: PgPool) {
+        complete_sale(&db, 1, 10_000_000.0).await;
+
+        let err = debit(&db, "withdrawal", 1, 9_500_000.01).await.unwrap_err();
+        let code = err.as_database_error().and_then(|e| e.code()).map(|c| c.into_owned());
+        assert_eq!(code.as_deref(), Some("23514"));
+        assert_eq!(balances(&db).await, (9_500_000.0, 9_500_000.0));
+
+        debit(&db, "withdrawal", 1, 9_500_000.0).await.unwrap();
+        assert_eq!(balances(&db).await, (0.0, 0.0));
+    }
+}
@@ -1,5 +1,5 @@
 // Financial Service Entry Point
-use tower_http::trace::TraceLayer;
+use shared::utils::{bind_addr, health::HealthLevel, logging, request_id, request_timeout, startup_gate::StartupGate};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
@@ -8,6 +8,7 @@ mod error;
 mod handlers;
 mod middleware;
 mod routes;
+mod scheduler;
 mod utils;
 
 #[tokio::main]
@@ -21,14 +22,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "financial_service=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(logging::fmt_layer())
         .init();
 
     tracing::info!("🚀 Starting Big Auto - Financial Service");
 
     // Initialize AppState dengan database connection
     tracing::info!("🔌 Initializing application state...");
-    let state = config::AppState::new().await
+    let config = config::AppConfig::from_env()
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    // Listener dibind sebelum koneksi database, request dijawab 503 sampai state siap
+    let addr = bind_addr::resolve("FINANCIAL_SERVICE_HOST", &config.server_host, config.server_port)?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let gate = StartupGate::new(&["/health"]);
+    let server = tokio::spawn({
+        let router = gate.router();
+        async move { axum::serve(listener, router).await }
+    });
+
+    let state = config::AppState::new(config).await
         .map_err(|e| format!("Failed to initialize app state: {}", e))?;
     tracing::info!("✅ Application state initialized");
 
@@ -41,18 +54,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Test database connectivity
     let health = state.health_check().await;
-    if health.overall == "healthy" {
+    if health.overall == HealthLevel::Healthy {
         tracing::info!("✅ Database health check passed");
     } else {
-        tracing::warn!("⚠️ Health check: Database {}", health.database);
+        tracing::warn!("⚠️ Health check: Database {:?}", health.database.status);
     }
 
+    // Start payout scheduler
+    scheduler::PayoutScheduler::new(state.clone()).start();
+
     // Create router dengan CORS
-    let app = routes::create_router(state.clone())
-        .layer(TraceLayer::new_for_http());
+    let app = request_id::apply(
+        routes::create_router(state.clone())
+            // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+            .layer(request_timeout::layer(request_timeout::default_timeout())),
+    );
 
-    // Server address
-    let addr = format!("{}:{}", state.config.server_host, state.config.server_port);
     tracing::info!("🎯 Financial Service listening on {}", addr);
     tracing::info!("📚 API Documentation:");
     tracing::info!("   - Swagger UI: http://{}/swagger-ui", addr);
@@ -60,9 +77,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("   - Health: http://{}/health", addr);
     tracing::info!("🌍 Environment: {}", state.config.environment);
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    // Router asli mulai melayani request
+    gate.ready(app);
+    server.await??;
 
     Ok(())
 }
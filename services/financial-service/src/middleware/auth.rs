@@ -6,6 +6,7 @@ use axum::{
     middleware::Next,
 };
 use crate::{config::AppState, error::AppError};
+use shared::auth::{AdminState, Role};
 
 // Import JWT validation dari utils
 use crate::utils::jwt;
@@ -58,7 +59,7 @@ where
             .get::<AuthUser>()
             .ok_or_else(|| AppError::AuthenticationError("Authentication required".to_string()))?;
 
-        if auth_user.role != "seller" {
+        if !Role::Seller.matches(&auth_user.role) {
             return Err(AppError::AuthorizationError("Seller access required".to_string()));
         }
 
@@ -68,6 +69,22 @@ where
     }
 }
 
+// Admin platform terautentikasi (approval withdrawal), flag is_admin dicek oleh shared::auth
+pub use shared::auth::AuthAdmin;
+
+impl AdminState for AppState {
+    type Rejection = AppError;
+
+    fn admin_db(&self) -> &sqlx::PgPool {
+        &self.db
+    }
+
+    // JWT middleware financial-service menginject AuthUser miliknya sendiri
+    fn authenticated_user_id(parts: &axum::http::request::Parts) -> Option<i32> {
+        parts.extensions.get::<AuthUser>().map(|user| user.user_id)
+    }
+}
+
 // Extract JWT token dari Authorization header
 fn extract_jwt_token(headers: &HeaderMap) -> Result<String, AppError> {
     let auth_header = headers
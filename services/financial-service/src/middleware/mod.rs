@@ -2,5 +2,5 @@
 pub mod auth;
 pub mod rate_limit;
 
-pub use auth::{AuthSeller, auth_middleware};
+pub use auth::{AuthAdmin, AuthSeller, auth_middleware};
 pub use rate_limit::rate_limit_middleware;
\ No newline at end of file
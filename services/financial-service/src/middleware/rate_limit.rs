@@ -2,13 +2,13 @@
 
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    response::Response,
     middleware::Next,
 };
-use redis::{Client, AsyncCommands};
+use redis::Client;
 use serde::Serialize;
 use std::env;
+use shared::utils::rate_limit::{too_many_requests, sliding_window_hit};
 use thiserror::Error;
 
 // Konfigurasi rate limit dari environment variables
@@ -66,48 +66,17 @@ impl RateLimiter {
             .map_err(RateLimitError::RedisConnection)?;
 
         let window_key = format!("rate_limit:financial:{}:{}", identifier, endpoint);
-        let current_time = chrono::Utc::now().timestamp() as u64;
-        let window_start = current_time - self.config.window_seconds + 1;
-
-        // Clean old entries di luar time window
-        let _: () = conn
-            .zrembyscore(&window_key, "-inf", &(window_start - 1))
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        // Hitung request dalam window
-        let current_count: usize = conn
-            .zcard(&window_key)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
         let max_requests = self.get_max_requests(role, endpoint);
-
-        // Tambah current request ke sorted set
-        let _: () = conn
-            .zadd(&window_key, current_time, current_time)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        // Set expiration untuk automatic cleanup
-        let _: () = conn
-            .expire(&window_key, self.config.window_seconds as i64)
+        let hit = sliding_window_hit(&mut conn, &window_key, max_requests, self.config.window_seconds)
             .await
             .map_err(RateLimitError::RedisOperation)?;
 
-        let is_allowed = current_count < max_requests as usize;
-        let remaining = if is_allowed {
-            max_requests.saturating_sub(current_count as u32 + 1)
-        } else {
-            0
-        };
-
         Ok(RateLimitResult {
-            allowed: is_allowed,
-            current_count: current_count as u32 + 1,
+            allowed: hit.allowed,
+            current_count: hit.current_count,
             max_requests,
-            remaining,
-            reset_time: (window_start + self.config.window_seconds) as u64,
+            remaining: hit.remaining,
+            reset_time: hit.reset_time,
         })
     }
 
@@ -183,15 +152,14 @@ pub async fn rate_limit_middleware(
 
             Ok(response)
         }
-        Ok(_) => {
+        Ok(result) => {
             // Rate limit exceeded
-            let error_response = axum::Json(serde_json::json!({
+            // Retry-After dihitung dari reset window limiter
+            Ok(too_many_requests(result.reset_time, serde_json::json!({
                 "success": false,
                 "error": "rate_limit_exceeded",
                 "message": "Terlalu banyak permintaan. Silakan coba lagi nanti."
-            }));
-
-            Ok((StatusCode::TOO_MANY_REQUESTS, error_response).into_response())
+            })))
         }
         Err(e) => {
             // Redis error - fail open untuk tidak block user
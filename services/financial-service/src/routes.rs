@@ -8,16 +8,19 @@ use axum::{
 };
 use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
+use shared::utils::cors::CorsPolicy;
 use utoipa::{OpenApi, Modify};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_redoc::{Redoc, Servable};
 use crate::{
-    config::{AppState, HealthStatus, check_db_health},
+    config::{AppState, HealthStatus},
+    error::AppError,
     handlers::{
         balance::{get_balance, __path_get_balance},
+        payouts::{list_payouts, __path_list_payouts, complete_payout, __path_complete_payout, fail_payout, __path_fail_payout},
         transactions::{get_transactions, __path_get_transactions},
-        withdrawals::{create_withdrawal, __path_create_withdrawal, list_withdrawals, __path_list_withdrawals, get_withdrawal_by_id, __path_get_withdrawal_by_id},
+        withdrawals::{create_withdrawal, __path_create_withdrawal, list_withdrawals, __path_list_withdrawals, get_withdrawal_by_id, __path_get_withdrawal_by_id, approve_withdrawal, __path_approve_withdrawal, reject_withdrawal, __path_reject_withdrawal},
     },
     middleware::{auth_middleware, rate_limit_middleware},
 };
@@ -47,7 +50,7 @@ impl Modify for SecurityAddon {
     info(
         title = "Big Auto - Financial Service API",
         version = "0.1.0",
-        description = "Financial Management Service\n\n## Features\n\n- 💰 Seller Balance Management\n- 💸 Withdrawal Requests\n- 📊 Transaction History\n- 💳 Commission Processing\n\n## Authentication\n\nAll endpoints require JWT token from auth-service.\nInclude token in `Authorization: Bearer {token}` header.\n",
+        description = "Financial Management Service\n\n## Features\n\n- 💰 Seller Balance Management\n- 💸 Withdrawal Requests\n- 🗓️ Scheduled Seller Payouts\n- 📊 Transaction History\n- 💳 Commission Processing\n\n## Authentication\n\nAll endpoints require JWT token from auth-service.\nInclude token in `Authorization: Bearer {token}` header.\n",
     ),
     paths(
         health_check,
@@ -56,13 +59,21 @@ impl Modify for SecurityAddon {
         create_withdrawal,
         list_withdrawals,
         get_withdrawal_by_id,
+        approve_withdrawal,
+        reject_withdrawal,
+        list_payouts,
+        complete_payout,
+        fail_payout,
     ),
     modifiers(&SecurityAddon),
     tags(
         (name = "Health", description = "Health check endpoints"),
         (name = "Seller Balance", description = "Seller balance management"),
         (name = "Seller Transactions", description = "Transaction history and logs"),
-        (name = "Seller Withdrawals", description = "Withdrawal request management")
+        (name = "Seller Withdrawals", description = "Withdrawal request management"),
+        (name = "Admin Withdrawals", description = "Withdrawal approval oleh admin"),
+        (name = "Seller Payouts", description = "Payout terjadwal dari saldo yang sudah clear"),
+        (name = "Admin Payouts", description = "Status transfer payout oleh admin")
     )
 )]
 struct ApiDoc;
@@ -77,18 +88,13 @@ struct ApiDoc;
     tag = "Health"
 )]
 async fn health_check(State(pool): State<PgPool>) -> Json<HealthStatus> {
-    let db_healthy = check_db_health(&pool).await;
-
-    Json(HealthStatus {
-        database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-        overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-    })
+    Json(HealthStatus::check(&pool).await)
 }
 
 // Build JWT-Only CORS configuration
 fn configure_cors() -> CorsLayer {
-    let frontend_url = std::env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable harus diset");
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     let allowed_methods = vec![
         Method::GET,
@@ -104,11 +110,10 @@ fn configure_cors() -> CorsLayer {
         header::CONTENT_TYPE,
     ];
 
-    CorsLayer::new()
-        .allow_origin(frontend_url.parse::<HeaderValue>().expect("Invalid FRONTEND_URL"))
+    policy
+        .apply(CorsLayer::new())
         .allow_methods(allowed_methods)
         .allow_headers(allowed_headers)
-        .allow_credentials(false)
         .max_age(std::time::Duration::from_secs(86400))
 }
 
@@ -141,6 +146,11 @@ async fn security_headers_middleware(
     response
 }
 
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
+}
+
 // Buat router dengan JWT-Only security
 pub fn create_router(state: AppState) -> Router {
     // OpenAPI documentation
@@ -151,6 +161,7 @@ pub fn create_router(state: AppState) -> Router {
         .route("/health", get(health_check).with_state(state.db.clone()))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi.clone()))
         .merge(Redoc::with_url("/redoc", openapi))
+        .fallback(not_found_handler)
         // Security layers (JWT-Only)
         .layer(axum::middleware::from_fn(security_headers_middleware))
         .layer(configure_cors())
@@ -165,11 +176,16 @@ fn create_jwt_protected_routes(state: AppState) -> Router {
         .route("/seller/balance", get(get_balance))
         .route("/seller/transactions", get(get_transactions))
         .route("/seller/withdrawals", get(list_withdrawals))
-        .route("/seller/withdrawals/{id}", get(get_withdrawal_by_id));
+        .route("/seller/withdrawals/{id}", get(get_withdrawal_by_id))
+        .route("/seller/payouts", get(list_payouts));
 
     let write_routes = Router::new()
         // WRITE endpoints 
         .route("/seller/withdrawals", post(create_withdrawal))
+        .route("/admin/withdrawals/{id}/approve", post(approve_withdrawal))
+        .route("/admin/withdrawals/{id}/reject", post(reject_withdrawal))
+        .route("/admin/payouts/{id}/complete", post(complete_payout))
+        .route("/admin/payouts/{id}/fail", post(fail_payout))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             rate_limit_middleware
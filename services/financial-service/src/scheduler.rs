@@ -0,0 +1,67 @@
+use crate::config::AppState;
+use crate::handlers::payouts;
+use crate::utils::payout;
+use chrono::Utc;
+use std::time::Duration;
+
+/// Background scheduler untuk payout seller terjadwal
+pub struct PayoutScheduler {
+    state: AppState,
+}
+
+impl PayoutScheduler {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Jalankan batch payout setiap PAYOUT_INTERVAL_HOURS
+    pub fn start(self) {
+        if std::env::var("DISABLE_SCHEDULER").unwrap_or_else(|_| "false".to_string()) == "true" {
+            tracing::info!("💸 Payout scheduler disabled via DISABLE_SCHEDULER environment variable");
+            return;
+        }
+
+        let interval_hours = self.state.config.payout_interval_hours;
+        tracing::info!(
+            "💸 Starting payout scheduler (every {} hours, clearing {} days)",
+            interval_hours,
+            self.state.config.payout_clearing_days
+        );
+
+        let state = self.state;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_hours * 3600));
+
+            loop {
+                interval.tick().await;
+                run_payout_cycle(&state).await;
+            }
+        });
+    }
+}
+
+// Satu siklus payout: proses setiap seller yang punya dana sudah clear
+async fn run_payout_cycle(state: &AppState) {
+    let cutoff = payout::clearing_cutoff(Utc::now(), state.config.payout_clearing_days);
+
+    let sellers = match payouts::sellers_with_cleared_funds(&state.db, cutoff).await {
+        Ok(sellers) => sellers,
+        Err(e) => {
+            tracing::error!("❌ Failed to load sellers for payout: {}", e);
+            return;
+        }
+    };
+
+    let mut created = 0;
+    for seller_id in sellers {
+        match payouts::run_payout_batch(&state.db, &state.config, seller_id).await {
+            Ok(Some(_)) => created += 1,
+            Ok(None) => {}
+            Err(e) => tracing::error!("❌ Payout batch failed for seller {}: {}", seller_id, e),
+        }
+    }
+
+    if created > 0 {
+        tracing::info!("💸 Created {} payout batches", created);
+    }
+}
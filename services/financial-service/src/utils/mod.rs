@@ -1,2 +1,3 @@
 // Utils exports
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod payout;
\ No newline at end of file
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Duration, Utc};
+
+// Default jadwal payout: seminggu sekali, clearing 7 hari, minimal Rp 50.000
+pub const DEFAULT_PAYOUT_INTERVAL_HOURS: u64 = 168;
+pub const DEFAULT_PAYOUT_CLEARING_DAYS: i64 = 7;
+pub const DEFAULT_MIN_PAYOUT_AMOUNT: f64 = 50000.0;
+
+// Credit ledger yang dibuat sebelum cutoff sudah lewat masa clearing
+pub fn clearing_cutoff(now: DateTime<Utc>, clearing_days: i64) -> DateTime<Utc> {
+    now - Duration::days(clearing_days.max(0))
+}
+
+// Nominal payout: total credit yang sudah clear, dibatasi saldo ledger
+// (sebagian mungkin sudah ditarik lewat withdrawal manual). None jika di bawah minimum.
+pub fn payout_amount(cleared_total: f64, ledger_balance: f64, min_amount: f64) -> Option<f64> {
+    let amount = (cleared_total.min(ledger_balance) * 100.0).round() / 100.0;
+
+    if amount <= 0.0 || amount < min_amount {
+        None
+    } else {
+        Some(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clearing_cutoff() {
+        let now = Utc::now();
+        assert_eq!(clearing_cutoff(now, 7), now - Duration::days(7));
+        assert_eq!(clearing_cutoff(now, 0), now);
+        assert_eq!(clearing_cutoff(now, -3), now);
+    }
+
+    #[test]
+    fn test_payout_amount_uses_cleared_total() {
+        assert_eq!(payout_amount(750000.0, 1000000.0, 50000.0), Some(750000.0));
+    }
+
+    #[test]
+    fn test_payout_amount_capped_by_ledger_balance() {
+        // Seller sudah withdraw sebagian, payout tidak boleh membuat saldo minus
+        assert_eq!(payout_amount(750000.0, 200000.0, 50000.0), Some(200000.0));
+    }
+
+    #[test]
+    fn test_payout_amount_below_minimum_skipped() {
+        assert_eq!(payout_amount(40000.0, 1000000.0, 50000.0), None);
+        assert_eq!(payout_amount(750000.0, 0.0, 50000.0), None);
+        assert_eq!(payout_amount(750000.0, -10000.0, 0.0), None);
+    }
+}
@@ -5,6 +5,19 @@ use sqlx::{
 use std::env;
 use std::time::Duration;
 use crate::middleware::rate_limit::RateLimiter;
+use crate::utils::digest::{DigestTemplate, DEFAULT_DIGEST_INTERVAL_SECS};
+use crate::utils::dispatch::DEFAULT_DISPATCH_INTERVAL_SECS;
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+use shared::utils::bind_addr;
+use shared::utils::internal_notifications;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+
+/// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("notifications", &["id", "user_id", "type", "title", "message", "is_read", "dispatched_at", "push_status", "push_attempts", "next_push_at"]),
+    ("notification_preferences", &["user_id", "timezone", "quiet_hours_start", "quiet_hours_end", "digest_types"]),
+    ("notification_digest_events", &["id", "user_id", "type", "created_at"]),
+];
 
 /// Konfigurasi utama aplikasi yang di-load dari environment variables
 #[derive(Debug, Clone)]
@@ -17,6 +30,14 @@ pub struct AppConfig {
     pub resend_api_key: String,
     pub resend_from_email: String,
     pub frontend_url: String,
+    pub digest_interval_secs: u64,
+    // Template notifikasi ringkasan (bawaan atau file NOTIFICATION_DIGEST_TEMPLATE)
+    pub digest_template: DigestTemplate,
+    pub dispatch_interval_secs: u64,
+    // Endpoint push gateway (POST JSON per notifikasi), None = push tidak dikirim
+    pub push_gateway_url: Option<String>,
+    // Secret HMAC untuk POST /internal/notifications dari service lain, None = endpoint ditolak
+    pub internal_service_secret: Option<String>,
 }
 
 impl AppConfig {
@@ -33,13 +54,10 @@ impl AppConfig {
             return Err("JWT_SECRET masih menggunakan default value! Ganti dengan value yang aman untuk production".to_string());
         }
 
-        let server_host = env::var("NOTIFICATION_SERVICE_HOST")
-            .unwrap_or_else(|_| "0.0.0.0".to_string());
-
-        let server_port = env::var("NOTIFICATION_SERVICE_PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3007);
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("NOTIFICATION_SERVICE_HOST", Some(bind_addr::DEFAULT_HOST))?;
+        let server_port = bind_addr::port_from_env("NOTIFICATION_SERVICE_PORT", Some(3007))?;
+        bind_addr::resolve("NOTIFICATION_SERVICE_HOST", &server_host, server_port)?;
 
         let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
 
@@ -52,6 +70,26 @@ impl AppConfig {
         let frontend_url = env::var("FRONTEND_URL")
             .map_err(|_| "FRONTEND_URL environment variable harus diset")?;
 
+        // Cadence scheduler ringkasan notifikasi mode digest
+        let digest_interval_secs = env::var("NOTIFICATION_DIGEST_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_DIGEST_INTERVAL_SECS);
+
+        // Template digest divalidasi saat startup, file yang salah langsung gagal
+        let digest_template_path = env::var("NOTIFICATION_DIGEST_TEMPLATE").ok().filter(|s| !s.is_empty());
+        let digest_template = DigestTemplate::load(digest_template_path.as_deref().map(std::path::Path::new))?;
+
+        // Cadence dispatch push notifikasi baru (quiet hours dievaluasi saat dispatch)
+        let dispatch_interval_secs = env::var("NOTIFICATION_DISPATCH_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_DISPATCH_INTERVAL_SECS);
+
+        let push_gateway_url = env::var("PUSH_GATEWAY_URL").ok().filter(|s| !s.is_empty());
+
+        let internal_service_secret = internal_notifications::secret_from_env();
+
         Ok(AppConfig {
             database_url,
             jwt_secret,
@@ -61,6 +99,11 @@ impl AppConfig {
             resend_api_key,
             resend_from_email,
             frontend_url,
+            digest_interval_secs,
+            digest_template,
+            dispatch_interval_secs,
+            push_gateway_url,
+            internal_service_secret,
         })
     }
 
@@ -89,11 +132,12 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 }
 
 /// Health check untuk database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
         .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 /// State aplikasi yang akan di-share ke semua handlers
@@ -128,12 +172,14 @@ impl axum::extract::FromRef<AppState> for RateLimiter {
 
 impl AppState {
     /// Buat AppState baru dengan semua dependensi
-    pub async fn new() -> Result<Self, String> {
-        let config = AppConfig::from_env()?;
+    pub async fn new(config: AppConfig) -> Result<Self, String> {
         let db = init_db_pool(&config.database_url)
             .await
             .map_err(|e| format!("Gagal menginisialisasi database: {}", e))?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "notification-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         // Initialize Redis rate limiter
         let redis_url = env::var("REDIS_URL")
             .map_err(|_| "REDIS_URL environment variable harus diset untuk rate limiting")?;
@@ -159,23 +205,25 @@ impl AppState {
 
     /// Health check untuk dependencies
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
-
-        HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy {
-                "healthy"
-            } else {
-                "degraded"
-            }
-            .to_string(),
-        }
+        HealthStatus::check(&self.db).await
     }
 }
 
 /// Struktur untuk response health check endpoint
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
 pub struct HealthStatus {
-    pub database: String,
-    pub overall: String,
+    pub database: DependencyHealth,
+    pub overall: HealthLevel,
+}
+
+impl HealthStatus {
+    // Probe database dengan latency; detail error hanya masuk log
+    pub async fn check(pool: &PgPool) -> Self {
+        let database = health::probe("database", check_db_health(pool)).await;
+
+        HealthStatus {
+            overall: health::worst_status(&[&database]),
+            database,
+        }
+    }
 }
\ No newline at end of file
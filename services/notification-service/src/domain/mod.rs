@@ -1,2 +1,2 @@
 pub mod notification;
-
+pub mod preferences;
@@ -66,3 +66,37 @@ pub struct ReadAllResponse {
 pub struct UnreadCountResponse {
     pub unread_count: i64,
 }
+
+// Jumlah notifikasi yang dibuat lewat endpoint internal
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CreatedNotificationsResponse {
+    pub created: u64,
+}
+
+// Simpan notifikasi dari service lain dalam satu statement (dispatcher mengirim push-nya)
+pub async fn insert_notifications(
+    pool: &sqlx::PgPool,
+    notifications: &[shared::utils::internal_notifications::NewNotification],
+) -> Result<u64, sqlx::Error> {
+    let user_ids: Vec<i32> = notifications.iter().map(|n| n.user_id).collect();
+    let types: Vec<&str> = notifications.iter().map(|n| n.notification_type.as_str()).collect();
+    let titles: Vec<&str> = notifications.iter().map(|n| n.title.as_str()).collect();
+    let messages: Vec<&str> = notifications.iter().map(|n| n.message.as_str()).collect();
+    let related_ids: Vec<Option<i32>> = notifications.iter().map(|n| n.related_id).collect();
+    let related_types: Vec<Option<&str>> = notifications.iter().map(|n| n.related_type.as_deref()).collect();
+
+    let result = sqlx::query(
+        "INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+         SELECT * FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::INT[], $6::TEXT[])"
+    )
+    .bind(user_ids)
+    .bind(types)
+    .bind(titles)
+    .bind(messages)
+    .bind(related_ids)
+    .bind(related_types)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
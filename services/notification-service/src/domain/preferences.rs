@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::utils::quiet_hours::DeliveryPlan;
+
+// Request update preferensi notifikasi
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdatePreferencesRequest {
+    /// Timezone IANA user, mis. "Asia/Jakarta"
+    pub timezone: String,
+    /// Jam lokal "HH:MM", kosongkan start & end untuk mematikan quiet hours
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    /// Kirim ringkasan notifikasi yang ditahan setelah quiet hours (default true)
+    #[serde(default = "default_digest")]
+    pub quiet_hours_digest: bool,
+    /// Tipe notifikasi yang dirangkum berkala, tipe lain dikirim langsung (default kosong)
+    #[serde(default)]
+    pub digest_types: Vec<String>,
+}
+
+fn default_digest() -> bool {
+    true
+}
+
+// Response preferensi notifikasi
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PreferencesResponse {
+    pub timezone: String,
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub quiet_hours_digest: bool,
+    pub digest_types: Vec<String>,
+    /// Channel yang aktif untuk notifikasi baru saat ini
+    pub delivery_now: DeliveryPlan,
+}
@@ -4,6 +4,7 @@ use axum::{
     Json,
 };
 use serde::Serialize;
+use shared::utils::request_id;
 use std::fmt;
 
 /// Struktur response error yang konsisten untuk semua endpoint
@@ -13,6 +14,7 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    pub request_id: String,
 }
 
 /// Enum untuk semua jenis error yang mungkin terjadi di aplikasi
@@ -22,6 +24,7 @@ pub enum AppError {
     RedisError(redis::RedisError),
     AuthenticationError(String),
     NotFoundError(String),
+    ValidationError(String),
     InternalError(String),
     TokenError(String),
 }
@@ -33,6 +36,7 @@ impl fmt::Display for AppError {
             AppError::RedisError(e) => write!(f, "Redis error: {}", e),
             AppError::AuthenticationError(msg) => write!(f, "Authentication error: {}", msg),
             AppError::NotFoundError(msg) => write!(f, "Not found: {}", msg),
+            AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
             AppError::TokenError(msg) => write!(f, "Token error: {}", msg),
         }
@@ -101,6 +105,9 @@ impl IntoResponse for AppError {
             AppError::NotFoundError(msg) => {
                 (StatusCode::NOT_FOUND, "not_found", msg.as_str(), None)
             }
+            AppError::ValidationError(msg) => {
+                (StatusCode::BAD_REQUEST, "validation_error", msg.as_str(), None)
+            }
             AppError::InternalError(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (
@@ -126,13 +133,21 @@ impl IntoResponse for AppError {
             ),
         };
 
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
         let error_response = ErrorResponse {
             error: error_type.to_string(),
             message: message.to_string(),
             details,
+            request_id: request_id.clone(),
         };
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        response
     }
 }
 
@@ -152,6 +167,11 @@ impl AppError {
     pub fn not_found(msg: impl Into<String>) -> Self {
         AppError::NotFoundError(msg.into())
     }
+
+    /// Buat error validasi input (400) dengan pesan custom
+    pub fn validation(msg: impl Into<String>) -> Self {
+        AppError::ValidationError(msg.into())
+    }
 }
 
 /// From implementations untuk error conversion
@@ -168,4 +188,20 @@ impl From<&str> for AppError {
 }
 
 /// Type alias untuk Result dengan AppError sebagai error type
-pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("Notifikasi tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
+    }
+}
@@ -0,0 +1,144 @@
+// Internal Handlers - notifikasi dari service lain (vehicle, booking, dll)
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use sqlx::PgPool;
+use shared::utils::internal_notifications::{
+    verify_request, CreateNotificationsRequest, MAX_NOTIFICATIONS_PER_REQUEST,
+};
+use crate::{
+    config::AppConfig,
+    domain::notification::{insert_notifications, CreatedNotificationsResponse},
+    error::{AppError, AppResult},
+};
+
+/// Buat notifikasi in-app dari service lain (signature HMAC INTERNAL_SERVICE_SECRET)
+#[utoipa::path(
+    post,
+    path = "/internal/notifications",
+    tag = "Internal",
+    request_body = CreateNotificationsRequest,
+    responses(
+        (status = 201, description = "Notifikasi dibuat", body = CreatedNotificationsResponse),
+        (status = 400, description = "Payload tidak valid"),
+        (status = 401, description = "Signature tidak valid")
+    )
+)]
+pub async fn create_notifications(
+    State(pool): State<PgPool>,
+    State(config): State<AppConfig>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<(StatusCode, Json<CreatedNotificationsResponse>)> {
+    let created = accept_notifications(
+        &pool,
+        config.internal_service_secret.as_deref(),
+        &headers,
+        &body,
+        chrono::Utc::now().timestamp(),
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(CreatedNotificationsResponse { created })))
+}
+
+async fn accept_notifications(
+    pool: &PgPool,
+    secret: Option<&str>,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: i64,
+) -> AppResult<u64> {
+    // Tanpa secret endpoint tidak bisa diverifikasi, semua request ditolak
+    let Some(secret) = secret else {
+        return Err(AppError::unauthorized("Endpoint internal belum dikonfigurasi"));
+    };
+    if !verify_request(secret, headers, body, now) {
+        return Err(AppError::unauthorized("Signature request internal tidak valid"));
+    }
+
+    let request: CreateNotificationsRequest = serde_json::from_slice(body)
+        .map_err(|e| AppError::validation(format!("Payload notifikasi tidak valid: {}", e)))?;
+
+    if request.notifications.is_empty() || request.notifications.len() > MAX_NOTIFICATIONS_PER_REQUEST {
+        return Err(AppError::validation(format!(
+            "Jumlah notifikasi harus 1-{} per request",
+            MAX_NOTIFICATIONS_PER_REQUEST
+        )));
+    }
+
+    let invalid = request.notifications.iter().any(|n| {
+        n.notification_type.trim().is_empty()
+            || n.notification_type.chars().count() > 50
+            || n.title.trim().is_empty()
+            || n.title.chars().count() > 255
+            || n.message.trim().is_empty()
+            || n.related_type.as_ref().is_some_and(|t| t.chars().count() > 50)
+    });
+    if invalid {
+        return Err(AppError::validation("Type, title, dan message notifikasi wajib diisi sesuai batas panjang"));
+    }
+
+    Ok(insert_notifications(pool, &request.notifications).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::utils::internal_notifications::NewNotification;
+    use shared::utils::webhook_signature::{sign_webhook, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+    const SECRET: &str = "internal-test-secret";
+    const NOW: i64 = 1_760_000_000;
+
+    fn signed_headers(secret: &str, body: &[u8]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SIGNATURE_HEADER, sign_webhook(secret.as_bytes(), NOW, body).parse().unwrap());
+        headers.insert(TIMESTAMP_HEADER, NOW.to_string().parse().unwrap());
+        headers
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_only_signed_requests_create_notifications(db: PgPool) {
+        let body = serde_json::to_vec(&CreateNotificationsRequest {
+            notifications: vec![NewNotification {
+                user_id: 1,
+                notification_type: "price_drop".to_string(),
+                title: "Harga turun".to_string(),
+                message: "Honda Jazz turun harga".to_string(),
+                related_id: Some(2),
+                related_type: Some("vehicle".to_string()),
+            }],
+        })
+        .unwrap();
+
+        let unsigned = accept_notifications(&db, Some(SECRET), &HeaderMap::new(), &body, NOW).await;
+        assert!(matches!(unsigned, Err(AppError::AuthenticationError(_))));
+        let wrong_secret = accept_notifications(&db, Some(SECRET), &signed_headers("lain", &body), &body, NOW).await;
+        assert!(matches!(wrong_secret, Err(AppError::AuthenticationError(_))));
+        let unconfigured = accept_notifications(&db, None, &signed_headers(SECRET, &body), &body, NOW).await;
+        assert!(matches!(unconfigured, Err(AppError::AuthenticationError(_))));
+
+        let created = accept_notifications(&db, Some(SECRET), &signed_headers(SECRET, &body), &body, NOW).await.unwrap();
+        assert_eq!(created, 1);
+
+        let stored: Vec<(i32, String, Option<i32>, Option<String>)> = sqlx::query_as(
+            "SELECT user_id, type, related_id, related_type FROM notifications WHERE type = 'price_drop'"
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(stored, vec![(1, "price_drop".to_string(), Some(2), Some("vehicle".to_string()))]);
+    }
+}
@@ -1 +1,3 @@
-pub mod notification;
\ No newline at end of file
+pub mod internal;
+pub mod notification;
+pub mod preferences;
@@ -0,0 +1,141 @@
+// Notification Preferences Handlers - quiet hours & mode digest per user
+
+use axum::{extract::State, Json};
+use chrono::{NaiveTime, Utc};
+use crate::{
+    config::AppState,
+    domain::preferences::{PreferencesResponse, UpdatePreferencesRequest},
+    error::{AppError, AppResult},
+    middleware::auth::AuthUser,
+    utils::digest::normalize_digest_types,
+    utils::quiet_hours::{parse_quiet_hours, parse_timezone, NotificationSchedule, DEFAULT_TIMEZONE},
+};
+
+/// Get preferensi notifikasi (quiet hours) user
+#[utoipa::path(
+    get,
+    path = "/api/notifications/preferences",
+    tag = "Notifications",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Preferences retrieved", body = PreferencesResponse),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    AuthUser { user_id, .. }: AuthUser,
+) -> AppResult<Json<PreferencesResponse>> {
+    let preferences = sqlx::query!(
+        r#"
+        SELECT timezone, quiet_hours_start, quiet_hours_end, quiet_hours_digest, digest_types
+        FROM notification_preferences
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch notification preferences for user {}: {}", user_id, e);
+        AppError::internal("Gagal mengambil preferensi notifikasi")
+    })?;
+
+    // User yang belum pernah menyimpan preferensi: tanpa quiet hours, semua tipe dikirim langsung
+    let response = match preferences {
+        Some(p) => build_preferences_response(
+            p.timezone,
+            p.quiet_hours_start,
+            p.quiet_hours_end,
+            p.quiet_hours_digest,
+            p.digest_types,
+        ),
+        None => build_preferences_response(DEFAULT_TIMEZONE.to_string(), None, None, true, Vec::new()),
+    };
+
+    Ok(Json(response))
+}
+
+/// Simpan preferensi notifikasi (timezone, quiet hours, tipe mode digest)
+#[utoipa::path(
+    put,
+    path = "/api/notifications/preferences",
+    tag = "Notifications",
+    security(("bearer_auth" = [])),
+    request_body = UpdatePreferencesRequest,
+    responses(
+        (status = 200, description = "Preferences updated", body = PreferencesResponse),
+        (status = 400, description = "Timezone, jam quiet hours, atau digest_types tidak valid"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    AuthUser { user_id, .. }: AuthUser,
+    Json(request): Json<UpdatePreferencesRequest>,
+) -> AppResult<Json<PreferencesResponse>> {
+    let timezone = parse_timezone(&request.timezone)
+        .ok_or_else(|| AppError::validation("Timezone tidak dikenal, gunakan nama IANA seperti Asia/Jakarta"))?;
+
+    let quiet_hours = parse_quiet_hours(
+        request.quiet_hours_start.as_deref(),
+        request.quiet_hours_end.as_deref(),
+    )
+    .map_err(AppError::validation)?;
+
+    let digest_types = normalize_digest_types(request.digest_types).map_err(AppError::validation)?;
+
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO notification_preferences (user_id, timezone, quiet_hours_start, quiet_hours_end, quiet_hours_digest, digest_types)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) DO UPDATE
+        SET timezone = EXCLUDED.timezone,
+            quiet_hours_start = EXCLUDED.quiet_hours_start,
+            quiet_hours_end = EXCLUDED.quiet_hours_end,
+            quiet_hours_digest = EXCLUDED.quiet_hours_digest,
+            digest_types = EXCLUDED.digest_types
+        RETURNING timezone, quiet_hours_start, quiet_hours_end, quiet_hours_digest, digest_types
+        "#,
+        user_id,
+        timezone.name(),
+        quiet_hours.map(|q| q.start),
+        quiet_hours.map(|q| q.end),
+        request.quiet_hours_digest,
+        &digest_types[..]
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save notification preferences for user {}: {}", user_id, e);
+        AppError::internal("Gagal menyimpan preferensi notifikasi")
+    })?;
+
+    Ok(Json(build_preferences_response(
+        saved.timezone,
+        saved.quiet_hours_start,
+        saved.quiet_hours_end,
+        saved.quiet_hours_digest,
+        saved.digest_types,
+    )))
+}
+
+// Response preferensi beserta channel yang aktif saat ini
+fn build_preferences_response(
+    timezone: String,
+    start: Option<NaiveTime>,
+    end: Option<NaiveTime>,
+    digest: bool,
+    digest_types: Vec<String>,
+) -> PreferencesResponse {
+    let schedule = NotificationSchedule::from_stored(&timezone, start, end, digest);
+
+    PreferencesResponse {
+        timezone,
+        quiet_hours_start: start.map(|t| t.format("%H:%M").to_string()),
+        quiet_hours_end: end.map(|t| t.format("%H:%M").to_string()),
+        quiet_hours_digest: digest,
+        digest_types,
+        delivery_now: schedule.delivery_plan(Utc::now()),
+    }
+}
@@ -1,17 +1,19 @@
 use axum::{
     http::{header, HeaderValue, Method},
-    routing::{get, put},
+    routing::{get, post, put},
     Router, Json, extract::State,
 };
 use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
+use shared::utils::cors::CorsPolicy;
 use utoipa::{OpenApi, Modify};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_redoc::{Redoc, Servable};
 use crate::{
-    handlers::notification,
-    config::{AppState, HealthStatus, check_db_health},
+    handlers::{internal, notification, preferences},
+    config::{AppState, HealthStatus},
+    error::AppError,
     middleware::{auth::auth_middleware, rate_limit::rate_limit_middleware},
 };
 
@@ -40,13 +42,16 @@ impl Modify for SecurityAddon {
     info(
         title = "Big Auto - Notification Service API",
         version = "1.0.0",
-        description = "Notification Service\n\n## Features\n\n- 📨 Get user notifications\n- ✅ Mark notification as read\n- 📬 Mark all notifications as read\n- 🔔 Get unread count\n\n## Authentication\n\nAll endpoints require JWT token from auth-service.\nInclude token in `Authorization: Bearer {token}` header.\n",
+        description = "Notification Service\n\n## Features\n\n- 📨 Get user notifications\n- ✅ Mark notification as read\n- 📬 Mark all notifications as read\n- 🔔 Get unread count\n- 🌙 Quiet hours per user (push/SMS ditahan, in-app tetap)\n- 📰 Mode digest per tipe notifikasi (dirangkum berkala)\n\n## Authentication\n\nAll endpoints require JWT token from auth-service.\nInclude token in `Authorization: Bearer {token}` header.\n",
     ),
     paths(
         notification::get_notifications,
         notification::mark_as_read,
         notification::mark_all_as_read,
         notification::get_unread_count,
+        preferences::get_preferences,
+        preferences::update_preferences,
+        internal::create_notifications,
     ),
     modifiers(&SecurityAddon),
     components(
@@ -57,28 +62,30 @@ impl Modify for SecurityAddon {
             crate::domain::notification::UnreadCountResponse,
             notification::NotificationQuery,
             notification::NotificationListResponse,
+            crate::domain::preferences::UpdatePreferencesRequest,
+            crate::domain::preferences::PreferencesResponse,
+            crate::utils::quiet_hours::DeliveryPlan,
+            crate::domain::notification::CreatedNotificationsResponse,
+            shared::utils::internal_notifications::CreateNotificationsRequest,
+            shared::utils::internal_notifications::NewNotification,
         )
     ),
     tags(
-        (name = "Notifications", description = "Notification management endpoints")
+        (name = "Notifications", description = "Notification management endpoints"),
+        (name = "Internal", description = "Endpoint antar service (signature HMAC, bukan JWT)")
     )
 )]
 struct ApiDoc;
 
 // Health check handler
 async fn health_check(State(pool): State<PgPool>) -> Json<HealthStatus> {
-    let db_healthy = check_db_health(&pool).await;
-
-    Json(HealthStatus {
-        database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-        overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-    })
+    Json(HealthStatus::check(&pool).await)
 }
 
 /// Build JWT-Only CORS configuration
 fn configure_cors() -> CorsLayer {
-    let frontend_url = std::env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable harus diset");
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     let allowed_methods = vec![
         Method::GET,
@@ -94,11 +101,10 @@ fn configure_cors() -> CorsLayer {
         header::CONTENT_TYPE,
     ];
 
-    CorsLayer::new()
-        .allow_origin(frontend_url.parse::<HeaderValue>().expect("Invalid FRONTEND_URL"))
+    policy
+        .apply(CorsLayer::new())
         .allow_methods(allowed_methods)
         .allow_headers(allowed_headers)
-        .allow_credentials(false)
         .max_age(std::time::Duration::from_secs(86400))
 }
 
@@ -119,6 +125,11 @@ async fn security_headers_middleware(
     response
 }
 
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
+}
+
 /// Buat router dengan JWT-Only security
 pub fn create_router(state: AppState) -> Router {
     // OpenAPI documentation
@@ -133,6 +144,9 @@ pub fn create_router(state: AppState) -> Router {
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi.clone()))
         .merge(Redoc::with_url("/redoc", openapi))
         .nest("/api", api_routes)
+        // Antar service: diverifikasi signature HMAC di handler, bukan JWT
+        .route("/internal/notifications", post(internal::create_notifications).with_state(state.clone()))
+        .fallback(not_found_handler)
         .layer(axum::middleware::from_fn(security_headers_middleware))
         .layer(configure_cors())
         .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit_middleware))
@@ -146,6 +160,7 @@ fn build_api_routes_with_auth(state: AppState) -> Router {
         .route("/notifications", get(notification::get_notifications))
         .route("/notifications/unread-count", get(notification::get_unread_count))
         .route("/notifications/read-all", put(notification::mark_all_as_read))
+        .route("/notifications/preferences", get(preferences::get_preferences).put(preferences::update_preferences))
         .route("/notifications/{id}/read", put(notification::mark_as_read))
         .layer(axum::middleware::from_fn_with_state(state.clone(), auth_middleware))
         .with_state(state);
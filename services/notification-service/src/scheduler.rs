@@ -0,0 +1,569 @@
+use crate::config::AppState;
+use crate::utils::digest::{plan_flush, DigestEvent, DigestTemplate, FlushDecision, DIGEST_NOTIFICATION_TYPE};
+use crate::utils::dispatch::{
+    retry_backoff, send_push, DispatchAction, PushMessage, PushStatus, DISPATCH_BATCH_SIZE, DISPATCH_LEASE_SECS,
+    MAX_PUSH_ATTEMPTS,
+};
+use crate::utils::quiet_hours::{NotificationSchedule, DEFAULT_TIMEZONE};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Background scheduler untuk notification service (dispatch push + flush antrean digest)
+pub struct NotificationScheduler {
+    state: AppState,
+}
+
+impl NotificationScheduler {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Start background tasks untuk notification service
+    pub fn start(self) {
+        // Check if scheduler is disabled
+        if std::env::var("DISABLE_SCHEDULER").unwrap_or_else(|_| "false".to_string()) == "true" {
+            tracing::info!("🔔 Notification scheduler disabled via DISABLE_SCHEDULER environment variable");
+            return;
+        }
+
+        tracing::info!("🔔 Starting Notification Service Background Scheduler...");
+
+        // Dispatch push notifikasi baru, ditahan selama quiet hours user
+        let dispatch_state = self.state.clone();
+        tokio::spawn(async move {
+            let state = dispatch_state;
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                state.config.dispatch_interval_secs.max(1),
+            ));
+
+            loop {
+                interval.tick().await;
+
+                match dispatch_notifications(&state.db, &state.http_client, state.config.push_gateway_url.as_deref(), Utc::now()).await {
+                    Ok(summary) if summary != DispatchSummary::default() => {
+                        tracing::info!(
+                            "📲 Notification dispatch: {} pushed, {} suppressed, {} held for digest, {} retrying",
+                            summary.pushed, summary.suppressed, summary.held, summary.retrying
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("❌ Failed to dispatch notifications: {}", e),
+                }
+            }
+        });
+
+        // Rangkum antrean digest tiap user sesuai cadence
+        let state = self.state;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                state.config.digest_interval_secs.max(60),
+            ));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = flush_digests(&state).await {
+                    tracing::error!("❌ Failed to flush notification digests: {}", e);
+                }
+            }
+        });
+    }
+}
+
+// Satu putaran flush untuk semua user yang punya event di antrean
+async fn flush_digests(state: &AppState) -> Result<(), sqlx::Error> {
+    let users = sqlx::query!(
+        r#"
+        SELECT DISTINCT e.user_id,
+               p.timezone AS "timezone?",
+               p.quiet_hours_start,
+               p.quiet_hours_end
+        FROM notification_digest_events e
+        LEFT JOIN notification_preferences p ON p.user_id = e.user_id
+        "#
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    let (mut sent, mut deferred) = (0, 0);
+
+    for user in users {
+        // Flag quiet_hours_digest tidak relevan di sini: event mode digest selalu dirangkum
+        let schedule = NotificationSchedule::from_stored(
+            user.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE),
+            user.quiet_hours_start,
+            user.quiet_hours_end,
+            true,
+        );
+
+        match flush_user_digest(&state.db, &state.config.digest_template, user.user_id, &schedule, Utc::now()).await {
+            Ok(FlushDecision::Send(_)) => sent += 1,
+            Ok(FlushDecision::Defer(until)) => {
+                deferred += 1;
+                tracing::debug!("🌙 Digest user {} ditahan sampai {} (quiet hours)", user.user_id, until);
+            }
+            Ok(FlushDecision::Empty) => {}
+            Err(e) => tracing::warn!("⚠️ Failed to flush digest for user {}: {}", user.user_id, e),
+        }
+    }
+
+    if sent > 0 || deferred > 0 {
+        tracing::info!("📬 Notification digest: {} sent, {} deferred", sent, deferred);
+    }
+
+    Ok(())
+}
+
+// Ambil & hapus antrean user dalam satu transaksi, rollback jika digest ditunda
+async fn flush_user_digest(
+    db: &PgPool,
+    template: &DigestTemplate,
+    user_id: i32,
+    schedule: &NotificationSchedule,
+    now: DateTime<Utc>,
+) -> Result<FlushDecision, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let rows = sqlx::query!(
+        r#"
+        DELETE FROM notification_digest_events
+        WHERE user_id = $1
+        RETURNING type, title, created_at AS "created_at!"
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let events: Vec<DigestEvent> = rows
+        .into_iter()
+        .map(|row| DigestEvent {
+            notification_type: row.r#type,
+            title: row.title,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    let decision = plan_flush(template, &events, schedule, now);
+
+    if let FlushDecision::Send(digest) = &decision {
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (user_id, type, title, message)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            user_id,
+            DIGEST_NOTIFICATION_TYPE,
+            digest.title,
+            digest.message
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+
+    Ok(decision)
+}
+
+// Ringkasan satu putaran dispatch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct DispatchSummary {
+    pushed: usize,
+    suppressed: usize,
+    held: usize,
+    // Push gagal yang dijadwalkan ulang
+    retrying: usize,
+}
+
+// Hasil dispatch satu notifikasi
+enum DispatchOutcome {
+    Done(PushStatus),
+    // Push gagal: dicoba lagi setelah waktu ini
+    Retry { attempts: i32, next_push_at: DateTime<Utc> },
+}
+
+// Satu putaran dispatch. Notifikasi yang belum di-dispatch di-claim dengan lease (SKIP LOCKED
+// agar aman dijalankan beberapa replika) dan langsung di-commit, jadi tidak ada lock yang ditahan
+// selama push dikirim. Hasil dicatat per notifikasi; push gagal dijadwalkan ulang dengan backoff.
+async fn dispatch_notifications(
+    db: &PgPool,
+    http_client: &reqwest::Client,
+    push_gateway_url: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<DispatchSummary, sqlx::Error> {
+    let lease_until = now + chrono::Duration::seconds(DISPATCH_LEASE_SECS);
+
+    let pending = sqlx::query!(
+        r#"
+        WITH claimed AS (
+            UPDATE notifications SET next_push_at = $2
+            WHERE id IN (
+                SELECT id FROM notifications
+                WHERE dispatched_at IS NULL AND (next_push_at IS NULL OR next_push_at <= $3)
+                ORDER BY id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, user_id, type, title, message, related_id, related_type, push_attempts, created_at
+        )
+        SELECT c.id AS "id!", c.user_id AS "user_id!", c.type AS "type!", c.title AS "title!",
+               c.message AS "message!", c.related_id, c.related_type,
+               c.push_attempts AS "push_attempts!",
+               COALESCE(c.created_at, $3) AS "created_at!",
+               p.timezone AS "timezone?",
+               p.quiet_hours_start AS "quiet_hours_start?",
+               p.quiet_hours_end AS "quiet_hours_end?",
+               p.quiet_hours_digest AS "quiet_hours_digest?"
+        FROM claimed c
+        LEFT JOIN notification_preferences p ON p.user_id = c.user_id
+        ORDER BY c.id
+        "#,
+        DISPATCH_BATCH_SIZE,
+        lease_until,
+        now
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut summary = DispatchSummary::default();
+
+    for notification in pending {
+        let schedule = NotificationSchedule::from_stored(
+            notification.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE),
+            notification.quiet_hours_start,
+            notification.quiet_hours_end,
+            notification.quiet_hours_digest.unwrap_or(true),
+        );
+
+        let outcome = match DispatchAction::from_plan(&schedule.delivery_plan(now)) {
+            DispatchAction::Push => match push_gateway_url {
+                Some(url) => {
+                    let push = PushMessage {
+                        notification_id: notification.id,
+                        user_id: notification.user_id,
+                        notification_type: &notification.r#type,
+                        title: &notification.title,
+                        message: &notification.message,
+                    };
+                    match send_push(http_client, url, &push).await {
+                        Ok(()) => {
+                            summary.pushed += 1;
+                            DispatchOutcome::Done(PushStatus::Sent)
+                        }
+                        Err(e) => {
+                            let attempts = notification.push_attempts + 1;
+                            if attempts >= MAX_PUSH_ATTEMPTS {
+                                tracing::warn!("⚠️ Push notifikasi {} gagal {} kali, berhenti: {}", notification.id, attempts, e);
+                                DispatchOutcome::Done(PushStatus::Failed)
+                            } else {
+                                tracing::warn!("⚠️ Push notifikasi {} gagal (percobaan {}), dicoba lagi: {}", notification.id, attempts, e);
+                                summary.retrying += 1;
+                                DispatchOutcome::Retry { attempts, next_push_at: now + retry_backoff(attempts) }
+                            }
+                        }
+                    }
+                }
+                None => DispatchOutcome::Done(PushStatus::Skipped),
+            },
+            DispatchAction::Suppress => {
+                summary.suppressed += 1;
+                DispatchOutcome::Done(PushStatus::Suppressed)
+            }
+            DispatchAction::HoldForDigest(until) => {
+                summary.held += 1;
+                tracing::debug!("🌙 Push notifikasi {} ditahan sampai {} (quiet hours)", notification.id, until);
+
+                // Digest hasil flush juga notifikasi; yang ditahan lagi cukup di-suppress
+                if notification.r#type == DIGEST_NOTIFICATION_TYPE {
+                    DispatchOutcome::Done(PushStatus::Suppressed)
+                } else {
+                    // Masuk antrean digest dan ditandai selesai dalam satu transaksi
+                    let mut tx = db.begin().await?;
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO notification_digest_events (user_id, type, title, message, related_id, related_type, created_at)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7)
+                        "#,
+                        notification.user_id,
+                        notification.r#type,
+                        notification.title,
+                        notification.message,
+                        notification.related_id,
+                        notification.related_type,
+                        notification.created_at
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    mark_dispatched(&mut *tx, notification.id, PushStatus::Digest, now).await?;
+                    tx.commit().await?;
+                    continue;
+                }
+            }
+        };
+
+        match outcome {
+            DispatchOutcome::Done(status) => mark_dispatched(db, notification.id, status, now).await?,
+            DispatchOutcome::Retry { attempts, next_push_at } => {
+                sqlx::query!(
+                    "UPDATE notifications SET push_attempts = $2, next_push_at = $3 WHERE id = $1",
+                    notification.id,
+                    attempts,
+                    next_push_at
+                )
+                .execute(db)
+                .await?;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+// Catat hasil akhir dispatch satu notifikasi (lease dilepas)
+async fn mark_dispatched<'e, E>(executor: E, id: i32, status: PushStatus, now: DateTime<Utc>) -> Result<(), sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query!(
+        "UPDATE notifications SET dispatched_at = $2, push_status = $3, next_push_at = NULL WHERE id = $1",
+        id,
+        now,
+        status.as_str()
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::quiet_hours::parse_timezone;
+    use axum::{routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    // Push gateway palsu yang mencatat user_id setiap push
+    async fn spawn_push_gateway() -> (String, Arc<Mutex<Vec<i64>>>) {
+        let pushes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = pushes.clone();
+        let router = Router::new().route(
+            "/push",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let recorded = recorded.clone();
+                async move {
+                    recorded.lock().unwrap().push(body["user_id"].as_i64().unwrap_or_default());
+                    "ok"
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{}/push", addr), pushes)
+    }
+
+    async fn push_status(db: &PgPool, user_id: i32) -> Vec<String> {
+        sqlx::query_scalar("SELECT push_status FROM notifications WHERE user_id = $1 ORDER BY id")
+            .bind(user_id)
+            .fetch_all(db)
+            .await
+            .unwrap()
+    }
+
+    // User 1 quiet hours 22:00-07:00 WIB: push ditahan, in-app tetap ada, digest dikirim setelah 07:00
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_notification_in_quiet_hours_is_deferred(db: PgPool) {
+        sqlx::query(
+            "INSERT INTO notification_preferences (user_id, timezone, quiet_hours_start, quiet_hours_end, quiet_hours_digest)
+             VALUES (1, 'Asia/Jakarta', '22:00', '07:00', true)"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO notifications (user_id, type, title, message)
+             VALUES (1, 'rental_booking', 'Booking dikonfirmasi', 'Seller mengonfirmasi booking'),
+                    (3, 'sale_order', 'Pesanan baru', 'Ada pesanan baru')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let (gateway, pushes) = spawn_push_gateway().await;
+        let client = reqwest::Client::new();
+
+        // 23:00 WIB
+        let night = at("2026-03-10T16:00:00Z");
+        let summary = dispatch_notifications(&db, &client, Some(&gateway), night).await.unwrap();
+        assert_eq!(summary, DispatchSummary { pushed: 1, suppressed: 0, held: 1, retrying: 0 });
+        assert_eq!(*pushes.lock().unwrap(), vec![3]);
+        assert_eq!(push_status(&db, 1).await, vec!["digest"]);
+        assert_eq!(push_status(&db, 3).await, vec!["sent"]);
+
+        // Putaran berikutnya tidak mengirim ulang
+        let summary = dispatch_notifications(&db, &client, Some(&gateway), night).await.unwrap();
+        assert_eq!(summary, DispatchSummary::default());
+
+        let schedule = NotificationSchedule {
+            timezone: parse_timezone("Asia/Jakarta").unwrap(),
+            quiet_hours: crate::utils::quiet_hours::parse_quiet_hours(Some("22:00"), Some("07:00")).unwrap(),
+            digest: true,
+        };
+        assert_eq!(
+            flush_user_digest(&db, &DigestTemplate::builtin(), 1, &schedule, night).await.unwrap(),
+            FlushDecision::Defer(at("2026-03-11T00:00:00Z"))
+        );
+
+        // 07:30 WIB: ringkasan ditulis dan di-push
+        let morning = at("2026-03-11T00:30:00Z");
+        assert!(matches!(flush_user_digest(&db, &DigestTemplate::builtin(), 1, &schedule, morning).await.unwrap(), FlushDecision::Send(_)));
+        let summary = dispatch_notifications(&db, &client, Some(&gateway), morning).await.unwrap();
+        assert_eq!(summary.pushed, 1);
+        assert_eq!(*pushes.lock().unwrap(), vec![3, 1]);
+        assert_eq!(push_status(&db, 1).await, vec!["digest", "sent"]);
+    }
+
+    // User 3 memilih mode digest untuk sale_order: notifikasi in-app tetap dibuat, push-nya
+    // ditunda ke satu ringkasan, tipe lain tetap di-push langsung
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_digest_types_accumulate_and_flush_once(db: PgPool) {
+        sqlx::query("INSERT INTO notification_preferences (user_id, digest_types) VALUES (3, '{sale_order}')")
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO notifications (user_id, type, title, message)
+             VALUES (3, 'sale_order', 'Pesanan baru ORD-1', 'Ada pesanan baru'),
+                    (3, 'sale_order', 'Pesanan baru ORD-2', 'Ada pesanan baru'),
+                    (3, 'testdrive_booking', 'Test drive baru', 'Ada booking test drive')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let in_app: Vec<String> = sqlx::query_scalar("SELECT title FROM notifications WHERE user_id = 3 ORDER BY id")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(in_app, vec!["Pesanan baru ORD-1", "Pesanan baru ORD-2", "Test drive baru"]);
+
+        let (gateway, pushes) = spawn_push_gateway().await;
+        let client = reqwest::Client::new();
+        let now = at("2026-03-10T05:00:00Z");
+
+        let summary = dispatch_notifications(&db, &client, Some(&gateway), now).await.unwrap();
+        assert_eq!(summary.pushed, 1);
+        assert_eq!(push_status(&db, 3).await, vec!["digest", "digest", "sent"]);
+
+        let schedule = NotificationSchedule::from_stored(DEFAULT_TIMEZONE, None, None, true);
+        let template = DigestTemplate::builtin();
+        match flush_user_digest(&db, &template, 3, &schedule, now).await.unwrap() {
+            FlushDecision::Send(digest) => {
+                assert_eq!(digest.title, "2 notifikasi baru");
+                assert!(digest.message.starts_with("2 pesanan\n"));
+            }
+            other => panic!("digest tidak dikirim: {:?}", other),
+        }
+        let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notification_digest_events")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(queued, 0);
+
+        // Antrean kosong: putaran berikutnya tidak menulis digest lagi
+        assert_eq!(flush_user_digest(&db, &template, 3, &schedule, now).await.unwrap(), FlushDecision::Empty);
+
+        // Ringkasan sendiri di-push seperti notifikasi biasa
+        let summary = dispatch_notifications(&db, &client, Some(&gateway), now).await.unwrap();
+        assert_eq!(summary.pushed, 1);
+        assert_eq!(*pushes.lock().unwrap(), vec![3, 3]);
+        assert_eq!(push_status(&db, 3).await, vec!["digest", "digest", "sent", "sent"]);
+    }
+
+    // Push gateway mati: push dicoba ulang dengan backoff lalu ditandai failed, tidak ada lock
+    // yang ditahan, dan notifikasi yang sedang di-claim replika lain tidak diambil dua kali
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_failed_push_is_retried_with_backoff(db: PgPool) {
+        sqlx::query(
+            "INSERT INTO notifications (user_id, type, title, message)
+             VALUES (3, 'sale_order', 'Pesanan baru', 'Ada pesanan baru')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let client = reqwest::Client::new();
+        let down = "http://127.0.0.1:1/push";
+        let mut now = at("2026-03-10T05:00:00Z");
+
+        let summary = dispatch_notifications(&db, &client, Some(down), now).await.unwrap();
+        assert_eq!(summary, DispatchSummary { retrying: 1, ..DispatchSummary::default() });
+        let (attempts, next_push_at): (i32, Option<DateTime<Utc>>) =
+            sqlx::query_as("SELECT push_attempts, next_push_at FROM notifications WHERE user_id = 3")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!((attempts, next_push_at), (1, Some(now + retry_backoff(1))));
+
+        // Belum waktunya retry
+        assert_eq!(dispatch_notifications(&db, &client, Some(down), now).await.unwrap(), DispatchSummary::default());
+
+        // Gagal terus sampai batas percobaan, lalu failed dan tidak diambil lagi
+        for attempt in 2..=MAX_PUSH_ATTEMPTS {
+            now += retry_backoff(attempt - 1);
+            dispatch_notifications(&db, &client, Some(down), now).await.unwrap();
+        }
+        assert_eq!(push_status(&db, 3).await, vec!["failed"]);
+        now += chrono::Duration::hours(2);
+        assert_eq!(dispatch_notifications(&db, &client, Some(down), now).await.unwrap(), DispatchSummary::default());
+
+        // Notifikasi yang lease-nya masih dipegang replika lain dilewati, diambil lagi setelah lease habis
+        sqlx::query(
+            "INSERT INTO notifications (user_id, type, title, message, next_push_at)
+             VALUES (3, 'sale_order', 'Pesanan kedua', 'Ada pesanan baru', $1)"
+        )
+        .bind(now + chrono::Duration::seconds(DISPATCH_LEASE_SECS))
+        .execute(&db)
+        .await
+        .unwrap();
+        let (gateway, pushes) = spawn_push_gateway().await;
+        assert_eq!(dispatch_notifications(&db, &client, Some(&gateway), now).await.unwrap(), DispatchSummary::default());
+
+        now += chrono::Duration::seconds(DISPATCH_LEASE_SECS);
+        let summary = dispatch_notifications(&db, &client, Some(&gateway), now).await.unwrap();
+        assert_eq!(summary.pushed, 1);
+        assert_eq!(*pushes.lock().unwrap(), vec![3]);
+        assert_eq!(push_status(&db, 3).await, vec!["failed", "sent"]);
+    }
+}
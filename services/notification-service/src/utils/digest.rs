@@ -0,0 +1,295 @@
+// Digest notifikasi per user
+//
+// Notifikasi bertipe mode digest tetap masuk tabel notifications (in-app), tapi push-nya
+// tidak dikirim: trigger DB menyalinnya ke notification_digest_events. Scheduler mengosongkan
+// antrean itu tiap NOTIFICATION_DIGEST_INTERVAL_SECS dan menulis satu notifikasi bertipe
+// "digest" yang merangkum semua event user, dirender dari template templates/digest.txt.
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::utils::quiet_hours::NotificationSchedule;
+
+// Tipe notifikasi hasil rangkuman, tidak pernah dialihkan ke digest lagi
+pub const DIGEST_NOTIFICATION_TYPE: &str = "digest";
+
+// Default cadence digest (override via NOTIFICATION_DIGEST_INTERVAL_SECS)
+pub const DEFAULT_DIGEST_INTERVAL_SECS: u64 = 3600;
+
+// Batas jumlah tipe mode digest per user dan panjang nama tipe (kolom type VARCHAR(50))
+const MAX_DIGEST_TYPES: usize = 20;
+const MAX_TYPE_LEN: usize = 50;
+
+// Judul event terbaru yang ikut ditampilkan di isi digest
+const MAX_LISTED_TITLES: usize = 3;
+
+// Template bawaan; baris pertama judul, sisanya isi. NOTIFICATION_DIGEST_TEMPLATE (path file)
+// meng-override template bawaan
+const BUILTIN_TEMPLATE: &str = include_str!("../../templates/digest.txt");
+
+// Satu notifikasi yang menunggu di antrean digest
+#[derive(Debug, Clone)]
+pub struct DigestEvent {
+    pub notification_type: String,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// Notifikasi rangkuman yang akan ditulis ke tabel notifications
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestNotification {
+    pub title: String,
+    pub message: String,
+}
+
+// Keputusan scheduler untuk antrean digest satu user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlushDecision {
+    // Tidak ada event, tidak ada yang dikirim
+    Empty,
+    // Sedang quiet hours, event tetap di antrean sampai waktu ini
+    Defer(DateTime<Utc>),
+    Send(DigestNotification),
+}
+
+// Label tipe notifikasi untuk ringkasan, tipe yang belum dikenal tampil apa adanya
+fn type_label(notification_type: &str) -> &str {
+    match notification_type {
+        "sale_order" => "pesanan",
+        "testdrive_booking" => "test drive",
+        "testdrive_reminder" => "pengingat test drive",
+        "rental_booking" => "rental",
+        "rental_damage_charge" => "biaya kerusakan rental",
+        "sla_breach" => "menunggu respon",
+        other => other,
+    }
+}
+
+// Template notifikasi digest dengan placeholder {{count}}, {{summary}} dan {{latest}}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestTemplate {
+    title: String,
+    message: String,
+}
+
+impl DigestTemplate {
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_TEMPLATE).expect("template digest bawaan valid")
+    }
+
+    // Template dari file jika path diset, selain itu template bawaan
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let Some(path) = path else {
+            return Ok(Self::builtin());
+        };
+
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Gagal membaca template digest {}: {}", path.display(), e))?;
+        Self::parse(&source).map_err(|e| format!("Template digest {}: {}", path.display(), e))
+    }
+
+    fn parse(source: &str) -> Result<Self, String> {
+        let (title, message) = source.trim().split_once('\n').unwrap_or((source.trim(), ""));
+        let (title, message) = (title.trim(), message.trim());
+
+        if title.is_empty() || message.is_empty() {
+            return Err("baris pertama (judul) dan isi template tidak boleh kosong".to_string());
+        }
+
+        Ok(Self { title: title.to_string(), message: message.to_string() })
+    }
+
+    // Rangkum event jadi satu notifikasi: jumlah per tipe + beberapa judul terbaru
+    pub fn render(&self, events: &[DigestEvent]) -> Option<DigestNotification> {
+        if events.is_empty() {
+            return None;
+        }
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for event in events {
+            *counts.entry(event.notification_type.as_str()).or_default() += 1;
+        }
+
+        let summary = counts
+            .iter()
+            .map(|(notification_type, count)| format!("{} {}", count, type_label(notification_type)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut latest: Vec<&DigestEvent> = events.iter().collect();
+        latest.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+
+        let mut lines: Vec<String> = latest
+            .iter()
+            .take(MAX_LISTED_TITLES)
+            .map(|event| format!("• {}", event.title))
+            .collect();
+
+        let remaining = events.len().saturating_sub(MAX_LISTED_TITLES);
+        if remaining > 0 {
+            lines.push(format!("+{} lainnya", remaining));
+        }
+
+        let count = events.len().to_string();
+        let latest = lines.join("\n");
+        let vars = [("count", count.as_str()), ("summary", summary.as_str()), ("latest", latest.as_str())];
+        let fill = |template: &str| {
+            vars.iter()
+                .fold(template.to_string(), |text, (key, value)| text.replace(&format!("{{{{{}}}}}", key), value))
+        };
+
+        Some(DigestNotification {
+            title: fill(&self.title),
+            message: fill(&self.message),
+        })
+    }
+}
+
+// Digest tidak dikirim selama quiet hours, antrean dipertahankan sampai jendela selesai
+pub fn plan_flush(
+    template: &DigestTemplate,
+    events: &[DigestEvent],
+    schedule: &NotificationSchedule,
+    now: DateTime<Utc>,
+) -> FlushDecision {
+    if events.is_empty() {
+        return FlushDecision::Empty;
+    }
+
+    if let Some(until) = schedule.quiet_until(now) {
+        return FlushDecision::Defer(until);
+    }
+
+    template.render(events).map_or(FlushDecision::Empty, FlushDecision::Send)
+}
+
+// Normalisasi tipe mode digest dari request preferensi (trim, unik, urut)
+pub fn normalize_digest_types(types: Vec<String>) -> Result<Vec<String>, &'static str> {
+    let mut normalized: Vec<String> = types.into_iter().map(|t| t.trim().to_string()).collect();
+
+    if normalized.iter().any(|t| t.is_empty() || t.len() > MAX_TYPE_LEN) {
+        return Err("digest_types berisi tipe notifikasi yang tidak valid");
+    }
+    if normalized.iter().any(|t| t == DIGEST_NOTIFICATION_TYPE) {
+        return Err("Notifikasi digest tidak bisa dijadikan mode digest");
+    }
+
+    normalized.sort();
+    normalized.dedup();
+
+    if normalized.len() > MAX_DIGEST_TYPES {
+        return Err("digest_types terlalu banyak");
+    }
+
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::quiet_hours::parse_quiet_hours;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    fn event(notification_type: &str, title: &str, created_at: &str) -> DigestEvent {
+        DigestEvent {
+            notification_type: notification_type.to_string(),
+            title: title.to_string(),
+            created_at: at(created_at),
+        }
+    }
+
+    fn jakarta(quiet: Option<(&str, &str)>) -> NotificationSchedule {
+        NotificationSchedule {
+            timezone: chrono_tz::Asia::Jakarta,
+            quiet_hours: quiet.and_then(|(start, end)| parse_quiet_hours(Some(start), Some(end)).unwrap()),
+            digest: true,
+        }
+    }
+
+    #[test]
+    fn test_accumulated_events_rendered_as_single_digest() {
+        let events = vec![
+            event("sale_order", "Pesanan baru ORD-1", "2026-03-10T01:00:00Z"),
+            event("testdrive_booking", "Test drive Avanza", "2026-03-10T01:10:00Z"),
+            event("sale_order", "Pesanan baru ORD-2", "2026-03-10T01:20:00Z"),
+            event("sale_order", "Pesanan baru ORD-3", "2026-03-10T01:30:00Z"),
+            event("custom_type", "Info lain", "2026-03-10T00:50:00Z"),
+        ];
+
+        let digest = DigestTemplate::builtin().render(&events).unwrap();
+        assert_eq!(digest.title, "5 notifikasi baru");
+        assert_eq!(
+            digest.message,
+            "1 custom_type, 3 pesanan, 1 test drive\n• Pesanan baru ORD-3\n• Pesanan baru ORD-2\n• Test drive Avanza\n+2 lainnya"
+        );
+    }
+
+    #[test]
+    fn test_flush_sends_once_and_empty_queue_sends_nothing() {
+        let template = DigestTemplate::builtin();
+        let schedule = jakarta(None);
+        let now = at("2026-03-10T03:00:00Z");
+
+        // Antrean in-memory dengan semantik DELETE ... RETURNING milik scheduler
+        let mut queue = vec![
+            event("sale_order", "Pesanan baru ORD-1", "2026-03-10T01:00:00Z"),
+            event("sale_order", "Pesanan baru ORD-2", "2026-03-10T02:00:00Z"),
+        ];
+
+        let drained: Vec<DigestEvent> = std::mem::take(&mut queue);
+        assert!(matches!(plan_flush(&template, &drained, &schedule, now), FlushDecision::Send(d) if d.title == "2 notifikasi baru"));
+
+        // Putaran berikutnya tanpa event baru tidak mengirim apa pun
+        let drained: Vec<DigestEvent> = std::mem::take(&mut queue);
+        assert_eq!(plan_flush(&template, &drained, &schedule, now), FlushDecision::Empty);
+    }
+
+    #[test]
+    fn test_flush_deferred_during_quiet_hours() {
+        // 22:00-07:00 WIB, 23:00 WIB = 16:00 UTC
+        let template = DigestTemplate::builtin();
+        let schedule = jakarta(Some(("22:00", "07:00")));
+        let events = vec![event("sale_order", "Pesanan baru ORD-1", "2026-03-10T15:30:00Z")];
+
+        assert_eq!(
+            plan_flush(&template, &events, &schedule, at("2026-03-10T16:00:00Z")),
+            FlushDecision::Defer(at("2026-03-11T00:00:00Z"))
+        );
+        assert!(matches!(plan_flush(&template, &events, &schedule, at("2026-03-11T00:00:00Z")), FlushDecision::Send(_)));
+    }
+
+    #[test]
+    fn test_template_file_overrides_builtin() {
+        let path = std::env::temp_dir().join(format!("bigauto-digest-template-{}.txt", std::process::id()));
+        std::fs::write(&path, "Ringkasan: {{count}} update\n\n{{summary}}\n---\n{{latest}}\n").unwrap();
+
+        let template = DigestTemplate::load(Some(&path)).unwrap();
+        let digest = template
+            .render(&[event("sale_order", "Pesanan baru ORD-1", "2026-03-10T01:00:00Z")])
+            .unwrap();
+        assert_eq!(digest.title, "Ringkasan: 1 update");
+        assert_eq!(digest.message, "1 pesanan\n---\n• Pesanan baru ORD-1");
+
+        std::fs::write(&path, "Hanya judul\n").unwrap();
+        assert!(DigestTemplate::load(Some(&path)).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(DigestTemplate::load(Some(Path::new("/tidak/ada/digest.txt"))).is_err());
+        assert_eq!(DigestTemplate::load(None).unwrap(), DigestTemplate::builtin());
+    }
+
+    #[test]
+    fn test_normalize_digest_types() {
+        assert_eq!(
+            normalize_digest_types(vec![" sale_order".to_string(), "sla_breach".to_string(), "sale_order".to_string()]),
+            Ok(vec!["sale_order".to_string(), "sla_breach".to_string()])
+        );
+        assert!(normalize_digest_types(vec!["digest".to_string()]).is_err());
+        assert!(normalize_digest_types(vec!["  ".to_string()]).is_err());
+        assert_eq!(normalize_digest_types(Vec::new()), Ok(Vec::new()));
+    }
+}
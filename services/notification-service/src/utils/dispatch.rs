@@ -0,0 +1,129 @@
+// Dispatch push notifikasi baru
+//
+// Notifikasi in-app ada di tabel notifications. Scheduler meng-claim notifikasi yang belum
+// di-dispatch dengan lease (commit sebelum push dikirim, jadi tidak ada lock selama request
+// HTTP), mengevaluasi quiet hours user lewat NotificationSchedule::delivery_plan, lalu
+// mengirim push ke PUSH_GATEWAY_URL atau menahannya. Push yang gagal dicoba ulang dengan
+// backoff sampai MAX_PUSH_ATTEMPTS.
+// Push yang ditahan dengan quiet_hours_digest aktif masuk antrean digest dan dikirim sebagai
+// ringkasan setelah quiet hours selesai.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::utils::quiet_hours::DeliveryPlan;
+
+// Default cadence dispatch push (override via NOTIFICATION_DISPATCH_INTERVAL_SECS)
+pub const DEFAULT_DISPATCH_INTERVAL_SECS: u64 = 30;
+
+// Batas notifikasi per putaran dispatch
+pub const DISPATCH_BATCH_SIZE: i64 = 200;
+
+// Lama claim satu putaran dispatch; jika replika mati sebelum mencatat hasil, notifikasi
+// diambil lagi setelah lease habis
+pub const DISPATCH_LEASE_SECS: i64 = 300;
+
+// Push yang gagal sebanyak ini ditandai failed dan tidak dicoba lagi
+pub const MAX_PUSH_ATTEMPTS: i32 = 5;
+
+// Jeda retry setelah percobaan push ke-`attempts` gagal: 30 detik, lalu dua kali lipat (maks 1 jam)
+pub fn retry_backoff(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 8) as u32 - 1;
+    chrono::Duration::seconds((30 * 2_i64.pow(exponent)).min(3600))
+}
+
+// Tindakan untuk satu notifikasi baru
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchAction {
+    Push,
+    // Quiet hours tanpa digest: push dibuang, notifikasi in-app tetap ada
+    Suppress,
+    // Quiet hours dengan digest: masuk antrean digest yang dikirim setelah waktu ini
+    HoldForDigest(DateTime<Utc>),
+}
+
+impl DispatchAction {
+    pub fn from_plan(plan: &DeliveryPlan) -> Self {
+        match (plan.push, plan.digest_at) {
+            (true, _) => DispatchAction::Push,
+            (false, Some(at)) => DispatchAction::HoldForDigest(at),
+            (false, None) => DispatchAction::Suppress,
+        }
+    }
+}
+
+// Hasil dispatch yang disimpan di notifications.push_status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushStatus {
+    Sent,
+    Failed,
+    // Push gateway belum dikonfigurasi
+    Skipped,
+    Suppressed,
+    Digest,
+}
+
+impl PushStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushStatus::Sent => "sent",
+            PushStatus::Failed => "failed",
+            PushStatus::Skipped => "skipped",
+            PushStatus::Suppressed => "suppressed",
+            PushStatus::Digest => "digest",
+        }
+    }
+}
+
+// Payload ke push gateway
+#[derive(Debug, Clone, Serialize)]
+pub struct PushMessage<'a> {
+    pub notification_id: i32,
+    pub user_id: i32,
+    #[serde(rename = "type")]
+    pub notification_type: &'a str,
+    pub title: &'a str,
+    pub message: &'a str,
+}
+
+pub async fn send_push(client: &reqwest::Client, gateway_url: &str, push: &PushMessage<'_>) -> Result<(), String> {
+    let response = client
+        .post(gateway_url)
+        .json(push)
+        .send()
+        .await
+        .map_err(|e| format!("Gagal menghubungi push gateway: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Push gateway menolak notifikasi: HTTP {}", response.status()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_from_plan() {
+        let at = Utc::now();
+        let open = DeliveryPlan { in_app: true, push: true, sms: true, digest_at: None };
+        let quiet = DeliveryPlan { in_app: true, push: false, sms: false, digest_at: None };
+
+        assert_eq!(DispatchAction::from_plan(&open), DispatchAction::Push);
+        assert_eq!(DispatchAction::from_plan(&quiet), DispatchAction::Suppress);
+        assert_eq!(
+            DispatchAction::from_plan(&DeliveryPlan { digest_at: Some(at), ..quiet }),
+            DispatchAction::HoldForDigest(at)
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles_until_cap() {
+        assert_eq!(retry_backoff(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_backoff(2), chrono::Duration::seconds(60));
+        assert_eq!(retry_backoff(4), chrono::Duration::seconds(240));
+        assert_eq!(retry_backoff(MAX_PUSH_ATTEMPTS + 10), chrono::Duration::seconds(3600));
+    }
+}
@@ -1 +1,4 @@
-pub mod jwt;
\ No newline at end of file
+pub mod digest;
+pub mod dispatch;
+pub mod jwt;
+pub mod quiet_hours;
@@ -0,0 +1,222 @@
+// Quiet hours (do-not-disturb) notifikasi per user
+//
+// Jam mulai/selesai disimpan sebagai jam lokal user dan dievaluasi di timezone IANA user,
+// sehingga pergantian DST tidak menggeser jendela quiet hours.
+// Selama quiet hours push/SMS ditahan, notifikasi in-app tetap dibuat.
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use shared::utils::clock::{parse_clock, resolve_local};
+use utoipa::ToSchema;
+
+// Timezone default user baru (database juga berjalan di Asia/Jakarta)
+pub const DEFAULT_TIMEZONE: &str = "Asia/Jakarta";
+
+// Jendela quiet hours dalam jam lokal, boleh melewati tengah malam (22:00-07:00)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    // Jendela kosong (start == end) tidak valid
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Option<Self> {
+        (start != end).then_some(Self { start, end })
+    }
+
+    // Start inklusif, end eksklusif
+    pub fn contains(&self, local: NaiveTime) -> bool {
+        if self.start < self.end {
+            local >= self.start && local < self.end
+        } else {
+            local >= self.start || local < self.end
+        }
+    }
+}
+
+// Channel yang boleh dikirim untuk notifikasi baru pada saat tertentu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct DeliveryPlan {
+    pub in_app: bool,
+    pub push: bool,
+    pub sms: bool,
+    // Waktu kirim digest notifikasi yang ditahan (akhir quiet hours), jika digest aktif
+    pub digest_at: Option<DateTime<Utc>>,
+}
+
+// Preferensi quiet hours user yang sudah diparse
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationSchedule {
+    pub timezone: Tz,
+    pub quiet_hours: Option<QuietHours>,
+    pub digest: bool,
+}
+
+impl NotificationSchedule {
+    // Dari kolom notification_preferences, timezone tidak dikenal jatuh ke default
+    pub fn from_stored(timezone: &str, start: Option<NaiveTime>, end: Option<NaiveTime>, digest: bool) -> Self {
+        Self {
+            timezone: parse_timezone(timezone).unwrap_or(chrono_tz::Asia::Jakarta),
+            quiet_hours: start.zip(end).and_then(|(start, end)| QuietHours::new(start, end)),
+            digest,
+        }
+    }
+
+    // Akhir quiet hours yang sedang berjalan dalam UTC, None jika sekarang bukan quiet hours
+    pub fn quiet_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let quiet = self.quiet_hours?;
+        let local = now.with_timezone(&self.timezone);
+        if !quiet.contains(local.time()) {
+            return None;
+        }
+
+        // Sebelum jam end berarti end hari ini, selain itu jendela berakhir besok pagi
+        let end_date = if local.time() < quiet.end {
+            local.date_naive()
+        } else {
+            local.date_naive() + Duration::days(1)
+        };
+
+        Some(resolve_local(&self.timezone, end_date, quiet.end))
+    }
+
+    pub fn delivery_plan(&self, now: DateTime<Utc>) -> DeliveryPlan {
+        match self.quiet_until(now) {
+            Some(until) => DeliveryPlan {
+                in_app: true,
+                push: false,
+                sms: false,
+                digest_at: self.digest.then_some(until),
+            },
+            None => DeliveryPlan { in_app: true, push: true, sms: true, digest_at: None },
+        }
+    }
+}
+
+// Timezone IANA, mis. "Asia/Jakarta" atau "Europe/London"
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+// Start/end harus diisi berpasangan, keduanya kosong berarti quiet hours mati
+pub fn parse_quiet_hours(start: Option<&str>, end: Option<&str>) -> Result<Option<QuietHours>, &'static str> {
+    match (start, end) {
+        (None, None) => Ok(None),
+        (Some(start), Some(end)) => {
+            let start = parse_clock(start).ok_or("quiet_hours_start harus berformat HH:MM")?;
+            let end = parse_clock(end).ok_or("quiet_hours_end harus berformat HH:MM")?;
+            QuietHours::new(start, end)
+                .map(Some)
+                .ok_or("quiet_hours_start dan quiet_hours_end tidak boleh sama")
+        }
+        _ => Err("quiet_hours_start dan quiet_hours_end harus diisi berpasangan"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fake clock: instant UTC dari string RFC 3339
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    fn quiet_schedule(timezone: &str, start: &str, end: &str) -> NotificationSchedule {
+        NotificationSchedule {
+            timezone: parse_timezone(timezone).unwrap(),
+            quiet_hours: parse_quiet_hours(Some(start), Some(end)).unwrap(),
+            digest: true,
+        }
+    }
+
+    #[test]
+    fn test_window_spanning_midnight() {
+        // 22:00-07:00 WIB (UTC+7)
+        let schedule = quiet_schedule("Asia/Jakarta", "22:00", "07:00");
+
+        // 21:59 WIB masih boleh push
+        assert_eq!(schedule.quiet_until(at("2026-03-10T14:59:00Z")), None);
+        // 22:00 WIB mulai quiet, berakhir 07:00 WIB besok
+        assert_eq!(schedule.quiet_until(at("2026-03-10T15:00:00Z")), Some(at("2026-03-11T00:00:00Z")));
+        // 02:30 WIB setelah tengah malam, berakhir 07:00 WIB hari yang sama
+        assert_eq!(schedule.quiet_until(at("2026-03-10T19:30:00Z")), Some(at("2026-03-11T00:00:00Z")));
+        // 07:00 WIB tepat, quiet hours sudah selesai
+        assert_eq!(schedule.quiet_until(at("2026-03-11T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_delivery_plan_across_boundary() {
+        let schedule = quiet_schedule("Asia/Jakarta", "22:00", "07:00");
+
+        let before = schedule.delivery_plan(at("2026-03-10T14:59:59Z"));
+        assert_eq!(before, DeliveryPlan { in_app: true, push: true, sms: true, digest_at: None });
+
+        let during = schedule.delivery_plan(at("2026-03-10T15:00:00Z"));
+        assert!(during.in_app);
+        assert!(!during.push && !during.sms);
+        assert_eq!(during.digest_at, Some(at("2026-03-11T00:00:00Z")));
+
+        let without_digest = NotificationSchedule { digest: false, ..schedule };
+        assert_eq!(without_digest.delivery_plan(at("2026-03-10T15:00:00Z")).digest_at, None);
+    }
+
+    #[test]
+    fn test_window_within_same_day() {
+        let schedule = quiet_schedule("Asia/Jakarta", "13:00", "15:00");
+        assert_eq!(schedule.quiet_until(at("2026-03-10T06:30:00Z")), Some(at("2026-03-10T08:00:00Z")));
+        assert_eq!(schedule.quiet_until(at("2026-03-10T08:00:00Z")), None);
+        assert_eq!(schedule.quiet_until(at("2026-03-10T15:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_dst_spring_forward() {
+        // London maju 01:00 GMT -> 02:00 BST pada 29 Maret 2026
+        let schedule = quiet_schedule("Europe/London", "22:00", "07:00");
+
+        // 23:00 GMT malam sebelumnya, end 07:00 BST = 06:00 UTC (malam itu hanya 8 jam)
+        assert_eq!(schedule.quiet_until(at("2026-03-28T23:00:00Z")), Some(at("2026-03-29T06:00:00Z")));
+        // 06:30 UTC sudah 07:30 BST, bukan quiet hours lagi
+        assert_eq!(schedule.quiet_until(at("2026-03-29T06:30:00Z")), None);
+
+        // End di jam yang hilang (01:30 tidak ada) digeser ke 02:00 BST = 01:00 UTC
+        let gap = quiet_schedule("Europe/London", "23:00", "01:30");
+        assert_eq!(gap.quiet_until(at("2026-03-28T23:30:00Z")), Some(at("2026-03-29T01:00:00Z")));
+    }
+
+    #[test]
+    fn test_dst_fall_back() {
+        // London mundur 02:00 BST -> 01:00 GMT pada 25 Oktober 2026
+        let schedule = quiet_schedule("Europe/London", "22:00", "07:00");
+
+        // 22:00 BST = 21:00 UTC, end 07:00 GMT = 07:00 UTC (malam itu 10 jam)
+        assert_eq!(schedule.quiet_until(at("2026-10-24T21:00:00Z")), Some(at("2026-10-25T07:00:00Z")));
+
+        // End di jam ambigu 01:30 memakai kemunculan pertama (01:30 BST = 00:30 UTC)
+        let ambiguous = quiet_schedule("Europe/London", "23:00", "01:30");
+        assert_eq!(ambiguous.quiet_until(at("2026-10-24T22:30:00Z")), Some(at("2026-10-25T00:30:00Z")));
+    }
+
+    #[test]
+    fn test_no_quiet_hours_always_delivers() {
+        let schedule = NotificationSchedule {
+            timezone: parse_timezone(DEFAULT_TIMEZONE).unwrap(),
+            quiet_hours: None,
+            digest: true,
+        };
+        assert!(schedule.delivery_plan(at("2026-03-10T18:00:00Z")).push);
+    }
+
+    #[test]
+    fn test_parse_quiet_hours() {
+        assert_eq!(parse_quiet_hours(None, None), Ok(None));
+        assert!(parse_quiet_hours(Some("22:00"), Some("07:00:00")).unwrap().is_some());
+        assert!(parse_quiet_hours(Some("22:00"), None).is_err());
+        assert!(parse_quiet_hours(Some("22:00"), Some("22:00")).is_err());
+        assert!(parse_quiet_hours(Some("25:00"), Some("07:00")).is_err());
+        assert!(parse_timezone("Asia/Jakarta").is_some());
+        assert!(parse_timezone("Mars/Olympus").is_none());
+    }
+}
@@ -4,7 +4,35 @@ use std::env;
 use std::time::Duration;
 use std::str::FromStr;
 use crate::repositories::payment_repo::PaymentRepository;
+use crate::repositories::audit_log_repo::AuditLogRepository;
 use crate::middleware::rate_limit::RateLimiter;
+use shared::auth::JwtConfig;
+use shared::utils::bind_addr;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+use crate::utils::midtrans_retry::{DEFAULT_CHARGE_MAX_RETRIES, DEFAULT_CHARGE_TIMEOUT_SECS};
+use crate::domain::payment::DEFAULT_REFUND_WINDOW_DAYS;
+use crate::utils::payment_events::{PaymentEvents, DEFAULT_PAYMENT_EVENTS_POLL_SECS};
+use crate::utils::payment_reconcile::{
+    DEFAULT_RECONCILE_BATCH_SIZE, DEFAULT_RECONCILE_CALLS_PER_MINUTE,
+    DEFAULT_RECONCILE_INTERVAL_SECS, DEFAULT_RECONCILE_MIN_AGE_MINS,
+};
+use crate::utils::resend_throttle::{
+    DEFAULT_RESEND_PAYMENT_COOLDOWN_SECS, DEFAULT_RESEND_USER_LIMIT,
+    DEFAULT_RESEND_USER_WINDOW_SECS, DEFAULT_STATUS_RECHECK_SECS,
+};
+use crate::utils::webhook_allowlist::{IpAllowlist, DEFAULT_MIDTRANS_WEBHOOK_IPS};
+use shared::utils::client_ip;
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("payments", &["id", "rental_booking_id", "sale_order_id", "order_id", "transaction_id", "status", "refund_status", "refund_reference"]),
+    ("rental_bookings", &["id", "customer_id", "seller_id", "deposit_status"]),
+    ("sale_orders", &["id", "buyer_id", "seller_id", "status"]),
+    ("sale_payment_events", &["sale_order_id", "payment_order_id"]),
+    ("rental_return_reports", &["id", "rental_booking_id", "charge_status"]),
+    ("audit_logs", &["id", "user_id", "action", "entity_type", "new_values"]),
+];
 
 // Konfigurasi aplikasi dari environment variables
 #[derive(Debug, Clone)]
@@ -14,14 +42,32 @@ pub struct AppConfig {
     pub server_port: u16,
     pub environment: String,
     pub jwt_secret: String,
+    pub jwt: JwtConfig,
     pub jwt_access_expiry: i64,
     pub jwt_refresh_expiry: i64,
     pub midtrans_server_key: String,
     pub midtrans_client_key: String,
     pub midtrans_is_production: bool,
     pub midtrans_api_url: String,
+    pub midtrans_charge_timeout_secs: u64,
+    pub midtrans_charge_max_retries: u32,
+    pub payment_reconcile_interval_secs: u64,
+    pub payment_reconcile_min_age_mins: i64,
+    pub payment_reconcile_batch_size: i64,
+    pub midtrans_status_calls_per_minute: u32,
+    pub resend_payment_cooldown_secs: u64,
+    pub resend_user_limit: u64,
+    pub resend_user_window_secs: u64,
+    pub midtrans_status_recheck_secs: u64,
+    pub payment_events_poll_secs: u64,
+    pub refund_window_days: i64,
+    pub midtrans_webhook_allowlist: Option<IpAllowlist>,
+    // Proxy yang boleh mengisi X-Forwarded-For/X-Real-IP, None = header proxy diabaikan
+    pub trusted_proxies: Option<IpAllowlist>,
     pub booking_service_url: String,
     pub user_service_url: String,
+    pub file_url_secret: String,
+    pub file_url_ttl_secs: i64,
     pub app_version: String,
 }
 
@@ -38,13 +84,13 @@ impl AppConfig {
             return Err("JWT_SECRET masih default! Ganti untuk production".to_string());
         }
 
-        let server_host = env::var("PAYMENT_SERVICE_HOST")
-            .expect("PAYMENT_SERVICE_HOST harus diset di environment");
+        // Issuer/audience opsional dari JWT_ISSUER dan JWT_AUDIENCE
+        let jwt = JwtConfig::from_env(jwt_secret.clone());
 
-        let server_port = env::var("PAYMENT_SERVICE_PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .expect("PAYMENT_SERVICE_PORT harus diset di environment");
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("PAYMENT_SERVICE_HOST", None)?;
+        let server_port = bind_addr::port_from_env("PAYMENT_SERVICE_PORT", None)?;
+        bind_addr::resolve("PAYMENT_SERVICE_HOST", &server_host, server_port)?;
 
         let environment = env::var("RUST_ENV")
             .expect("RUST_ENV harus diset di environment");
@@ -73,12 +119,127 @@ impl AppConfig {
         let midtrans_api_url = env::var("MIDTRANS_API_URL")
             .expect("MIDTRANS_API_URL harus diset di environment");
 
+        // Cegah key production dipakai di luar production (dan sebaliknya)
+        let allow_midtrans_mismatch = env::var("MIDTRANS_ALLOW_ENV_MISMATCH")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false);
+
+        crate::utils::midtrans_guard::validate_midtrans_mode(
+            &environment,
+            midtrans_is_production,
+            &midtrans_server_key,
+            &midtrans_api_url,
+            allow_midtrans_mismatch,
+        )?;
+
+        // Timeout per panggilan charge Midtrans (detik)
+        let midtrans_charge_timeout_secs = env::var("MIDTRANS_CHARGE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_CHARGE_TIMEOUT_SECS);
+
+        // Jumlah retry cek status order_id setelah charge yang gagal transient (timeout, koneksi, 5xx)
+        let midtrans_charge_max_retries = env::var("MIDTRANS_CHARGE_MAX_RETRIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_CHARGE_MAX_RETRIES);
+
+        // Rekonsiliasi payment pending yang webhook-nya hilang
+        let payment_reconcile_interval_secs = env::var("PAYMENT_RECONCILE_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RECONCILE_INTERVAL_SECS);
+
+        let payment_reconcile_min_age_mins = env::var("PAYMENT_RECONCILE_MIN_AGE_MINS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RECONCILE_MIN_AGE_MINS);
+
+        let payment_reconcile_batch_size = env::var("PAYMENT_RECONCILE_BATCH_SIZE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RECONCILE_BATCH_SIZE);
+
+        // Batas panggilan status Midtrans dari scheduler per menit
+        let midtrans_status_calls_per_minute = env::var("MIDTRANS_STATUS_CALLS_PER_MINUTE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RECONCILE_CALLS_PER_MINUTE);
+
+        // Throttle resend webhook: cooldown per payment dan batas per user
+        let resend_payment_cooldown_secs = env::var("RESEND_WEBHOOK_COOLDOWN_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RESEND_PAYMENT_COOLDOWN_SECS);
+
+        let resend_user_limit = env::var("RESEND_WEBHOOK_USER_LIMIT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RESEND_USER_LIMIT);
+
+        let resend_user_window_secs = env::var("RESEND_WEBHOOK_USER_WINDOW_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RESEND_USER_WINDOW_SECS);
+
+        // Payment yang baru dicek tidak dicek ulang ke Midtrans dalam jeda ini
+        let midtrans_status_recheck_secs = env::var("MIDTRANS_STATUS_RECHECK_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_STATUS_RECHECK_SECS);
+
+        // Stream SSE status payment membaca ulang database tiap sekian detik
+        let payment_events_poll_secs = env::var("PAYMENT_EVENTS_POLL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_PAYMENT_EVENTS_POLL_SECS);
+
+        // Biaya rental hanya bisa direfund sekian hari setelah dibayar
+        let refund_window_days = env::var("REFUND_WINDOW_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|days| *days > 0)
+            .unwrap_or(DEFAULT_REFUND_WINDOW_DAYS);
+
+        // Allowlist IP webhook Midtrans, bisa dimatikan untuk testing lokal
+        let webhook_ip_check = env::var("MIDTRANS_WEBHOOK_IP_CHECK")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(true);
+
+        let midtrans_webhook_allowlist = if webhook_ip_check {
+            let spec = env::var("MIDTRANS_WEBHOOK_IP_ALLOWLIST")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_MIDTRANS_WEBHOOK_IPS.to_string());
+            Some(IpAllowlist::parse(&spec).map_err(|e| format!("MIDTRANS_WEBHOOK_IP_ALLOWLIST: {}", e))?)
+        } else {
+            tracing::warn!("⚠️ MIDTRANS_WEBHOOK_IP_CHECK=false, webhook Midtrans diterima dari semua IP");
+            None
+        };
+
+        // IP client dari X-Forwarded-For/X-Real-IP hanya jika koneksi datang dari proxy tepercaya
+        // (default network privat tempat gateway nginx berjalan), "none" untuk mengabaikan header
+        let trusted_proxies = client_ip::trusted_proxies_from_env()?;
+
         let booking_service_url = env::var("BOOKING_SERVICE_URL")
             .expect("BOOKING_SERVICE_URL harus diset di environment");
 
         let user_service_url = env::var("USER_SERVICE_URL")
             .expect("USER_SERVICE_URL harus diset di environment");
 
+        // Secret HMAC untuk signed URL receipt (sama dengan dokumen privat booking-service)
+        let file_url_secret = env::var("FILE_URL_SIGNING_SECRET")
+            .map_err(|_| "FILE_URL_SIGNING_SECRET harus diset")?;
+
+        // Masa berlaku signed URL receipt (detik)
+        let file_url_ttl_secs = env::var("FILE_URL_TTL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(900);
+
         let app_version = env::var("APP_VERSION")
             .unwrap_or_else(|_| "1.0.0".to_string());
 
@@ -88,14 +249,31 @@ impl AppConfig {
             server_port,
             environment,
             jwt_secret,
+            jwt,
             jwt_access_expiry,
             jwt_refresh_expiry,
             midtrans_server_key,
             midtrans_client_key,
             midtrans_is_production,
             midtrans_api_url,
+            midtrans_charge_timeout_secs,
+            midtrans_charge_max_retries,
+            payment_reconcile_interval_secs,
+            payment_reconcile_min_age_mins,
+            payment_reconcile_batch_size,
+            midtrans_status_calls_per_minute,
+            resend_payment_cooldown_secs,
+            resend_user_limit,
+            resend_user_window_secs,
+            midtrans_status_recheck_secs,
+            payment_events_poll_secs,
+            refund_window_days,
+            midtrans_webhook_allowlist,
+            trusted_proxies,
             booking_service_url,
             user_service_url,
+            file_url_secret,
+            file_url_ttl_secs,
             app_version,
         })
     }
@@ -143,11 +321,12 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 }
 
 // Health check database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
-        .fetch_optional(pool)
+        .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 // Application state yang di-share ke semua handlers
@@ -157,6 +336,8 @@ pub struct AppState {
     pub config: AppConfig,
     pub http_client: reqwest::Client,
     pub payment_repository: PaymentRepository,
+    pub audit_log_repository: AuditLogRepository,
+    pub payment_events: PaymentEvents,
     pub rate_limiter: RateLimiter,
 }
 
@@ -185,12 +366,16 @@ impl AppState {
             .await
             .map_err(|e| format!("Failed to init database: {}", e))?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "payment-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         let http_client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
         let payment_repository = PaymentRepository::new(db.clone());
+        let audit_log_repository = AuditLogRepository::new(db.clone());
 
         // Redis MANDATORY untuk rate limiting 
         let redis_url = env::var("REDIS_URL")
@@ -212,39 +397,90 @@ impl AppState {
             config,
             http_client,
             payment_repository,
+            audit_log_repository,
+            payment_events: PaymentEvents::new(),
             rate_limiter,
         })
     }
 
-    // Inisialisasi application state dari environment
-    pub async fn from_env() -> Result<Self, String> {
-        let config = AppConfig::from_env()?;
-        Self::new(config).await
-    }
-
     // Test database connection
     pub async fn test_database_connection(&self) -> Result<(), String> {
-        check_db_health(&self.db)
-            .await
-            .then_some(())
-            .ok_or_else(|| "Database connection failed".to_string())
+        check_db_health(&self.db).await
     }
 
     // Health check semua dependencies
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
-
-        HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-        }
+        HealthStatus::check(&self.db).await
     }
 }
 
 // Response untuk health check endpoint
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
 pub struct HealthStatus {
-    pub database: String,
-    pub overall: String,
+    pub database: DependencyHealth,
+    pub overall: HealthLevel,
 }
 
+impl HealthStatus {
+    // Probe database dengan latency; detail error hanya masuk log
+    pub async fn check(pool: &PgPool) -> Self {
+        let database = health::probe("database", check_db_health(pool)).await;
+
+        HealthStatus {
+            overall: health::worst_status(&[&database]),
+            database,
+        }
+    }
+}
+
+
+#[cfg(test)]
+impl AppState {
+    // State untuk test handler dengan database sqlx::test; Redis tidak pernah dihubungi,
+    // Midtrans diarahkan ke midtrans_api_url (mock di test)
+    pub fn for_test(db: PgPool, midtrans_api_url: String) -> Self {
+        let jwt_secret = "test-secret".to_string();
+
+        AppState {
+            config: AppConfig {
+                database_url: String::new(),
+                server_host: "127.0.0.1".to_string(),
+                server_port: 3005,
+                environment: "development".to_string(),
+                jwt: JwtConfig::new(jwt_secret.clone()),
+                jwt_secret,
+                jwt_access_expiry: 900,
+                jwt_refresh_expiry: 604800,
+                midtrans_server_key: "server-key".to_string(),
+                midtrans_client_key: String::new(),
+                midtrans_is_production: false,
+                midtrans_api_url,
+                midtrans_charge_timeout_secs: 5,
+                midtrans_charge_max_retries: 0,
+                payment_reconcile_interval_secs: DEFAULT_RECONCILE_INTERVAL_SECS,
+                payment_reconcile_min_age_mins: DEFAULT_RECONCILE_MIN_AGE_MINS,
+                payment_reconcile_batch_size: DEFAULT_RECONCILE_BATCH_SIZE,
+                midtrans_status_calls_per_minute: DEFAULT_RECONCILE_CALLS_PER_MINUTE,
+                resend_payment_cooldown_secs: DEFAULT_RESEND_PAYMENT_COOLDOWN_SECS,
+                resend_user_limit: DEFAULT_RESEND_USER_LIMIT,
+                resend_user_window_secs: DEFAULT_RESEND_USER_WINDOW_SECS,
+                midtrans_status_recheck_secs: DEFAULT_STATUS_RECHECK_SECS,
+                payment_events_poll_secs: DEFAULT_PAYMENT_EVENTS_POLL_SECS,
+                refund_window_days: DEFAULT_REFUND_WINDOW_DAYS,
+                midtrans_webhook_allowlist: None,
+                trusted_proxies: None,
+                booking_service_url: "http://127.0.0.1:3002".to_string(),
+                user_service_url: "http://127.0.0.1:3004".to_string(),
+                file_url_secret: "file-url-secret".to_string(),
+                file_url_ttl_secs: 900,
+                app_version: "test".to_string(),
+            },
+            http_client: reqwest::Client::new(),
+            payment_repository: PaymentRepository::new(db.clone()),
+            audit_log_repository: AuditLogRepository::new(db.clone()),
+            payment_events: PaymentEvents::new(),
+            rate_limiter: RateLimiter::new("redis://127.0.0.1:6379").unwrap(),
+            db,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Filter query audit log untuk investigasi security & rekonsiliasi finance
+#[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+pub struct AuditLogQueryParams {
+    pub user_id: Option<i32>,
+    #[param(example = "MANUAL_WEBHOOK_RESEND")]
+    pub action: Option<String>,
+    #[param(example = "payment")]
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+}
+
+// Satu entry audit log beserta nilai lama/baru dan metadata request
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub user_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub action: String,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    pub old_values: Option<serde_json::Value>,
+    pub new_values: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    pub service_name: Option<String>,
+    pub endpoint: Option<String>,
+    pub http_method: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// Response list audit log dengan pagination
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct AuditLogListResponse {
+    pub logs: Vec<AuditLogEntry>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+// Status deposit jaminan di rental_bookings, terpisah dari status biaya sewa
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    None,
+    Pending,
+    Held,
+    Refunded,
+    PartiallyRefunded,
+    Forfeited,
+}
+
+impl DepositStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DepositStatus::None => "none",
+            DepositStatus::Pending => "pending",
+            DepositStatus::Held => "held",
+            DepositStatus::Refunded => "refunded",
+            DepositStatus::PartiallyRefunded => "partially_refunded",
+            DepositStatus::Forfeited => "forfeited",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(DepositStatus::None),
+            "pending" => Some(DepositStatus::Pending),
+            "held" => Some(DepositStatus::Held),
+            "refunded" => Some(DepositStatus::Refunded),
+            "partially_refunded" => Some(DepositStatus::PartiallyRefunded),
+            "forfeited" => Some(DepositStatus::Forfeited),
+            _ => None,
+        }
+    }
+}
+
+// Hasil perhitungan refund deposit setelah dipotong tagihan kerusakan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DepositSettlement {
+    pub deposit_amount: i64,
+    // Bagian tagihan kerusakan yang dilunasi dari deposit
+    pub damage_deducted: i64,
+    // Sisa tagihan kerusakan yang tetap harus dibayar customer (rental_damage)
+    pub damage_remaining: i64,
+    pub refund_amount: i64,
+    pub status: DepositStatus,
+}
+
+// Hitung refund deposit: deposit dikurangi tagihan kerusakan yang belum dibayar
+pub fn settle_deposit(deposit_amount: i64, outstanding_damage: i64) -> DepositSettlement {
+    let outstanding_damage = outstanding_damage.max(0);
+    let damage_deducted = outstanding_damage.min(deposit_amount);
+    let refund_amount = deposit_amount - damage_deducted;
+
+    let status = if refund_amount == deposit_amount {
+        DepositStatus::Refunded
+    } else if refund_amount == 0 {
+        DepositStatus::Forfeited
+    } else {
+        DepositStatus::PartiallyRefunded
+    };
+
+    DepositSettlement {
+        deposit_amount,
+        damage_deducted,
+        damage_remaining: outstanding_damage - damage_deducted,
+        refund_amount,
+        status,
+    }
+}
+
+// Nominal refund dari client opsional (0), jika diisi harus sama dengan hasil perhitungan
+pub fn check_requested_amount(settlement: &DepositSettlement, requested: i64) -> Result<(), AppError> {
+    if requested < 0 {
+        return Err(AppError::validation("Refund amount cannot be negative"));
+    }
+
+    if requested != 0 && requested != settlement.refund_amount {
+        return Err(AppError::validation(format!(
+            "Deposit refund must be {} after deducting damage charges of {}",
+            settlement.refund_amount, settlement.damage_deducted
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_deposit_refund_without_damage() {
+        let settlement = settle_deposit(1_000_000, 0);
+        assert_eq!(settlement.refund_amount, 1_000_000);
+        assert_eq!(settlement.damage_deducted, 0);
+        assert_eq!(settlement.damage_remaining, 0);
+        assert_eq!(settlement.status, DepositStatus::Refunded);
+    }
+
+    #[test]
+    fn test_partial_deposit_refund_nets_damage() {
+        let settlement = settle_deposit(1_000_000, 350_000);
+        assert_eq!(settlement.refund_amount, 650_000);
+        assert_eq!(settlement.damage_deducted, 350_000);
+        assert_eq!(settlement.damage_remaining, 0);
+        assert_eq!(settlement.status, DepositStatus::PartiallyRefunded);
+    }
+
+    #[test]
+    fn test_damage_exceeding_deposit_forfeits_and_keeps_remainder() {
+        let settlement = settle_deposit(500_000, 800_000);
+        assert_eq!(settlement.refund_amount, 0);
+        assert_eq!(settlement.damage_deducted, 500_000);
+        assert_eq!(settlement.damage_remaining, 300_000);
+        assert_eq!(settlement.status, DepositStatus::Forfeited);
+    }
+
+    #[test]
+    fn test_requested_amount_must_match_settlement() {
+        let settlement = settle_deposit(1_000_000, 350_000);
+        assert!(check_requested_amount(&settlement, 0).is_ok());
+        assert!(check_requested_amount(&settlement, 650_000).is_ok());
+        assert!(check_requested_amount(&settlement, 1_000_000).is_err());
+        assert!(check_requested_amount(&settlement, -1).is_err());
+    }
+
+    #[test]
+    fn test_status_roundtrip() {
+        for status in [
+            DepositStatus::None,
+            DepositStatus::Pending,
+            DepositStatus::Held,
+            DepositStatus::Refunded,
+            DepositStatus::PartiallyRefunded,
+            DepositStatus::Forfeited,
+        ] {
+            assert_eq!(DepositStatus::parse(status.as_str()), Some(status));
+        }
+    }
+}
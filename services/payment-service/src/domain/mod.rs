@@ -1 +1,3 @@
-pub mod payment;
\ No newline at end of file
+pub mod payment;
+pub mod audit_log;
+pub mod deposit;
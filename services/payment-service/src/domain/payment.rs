@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::error::AppError;
+
 // Model data payment transaction
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
 pub struct Payment {
@@ -75,6 +77,14 @@ pub enum PaymentType {
     Rental,
     #[serde(rename = "sale")]
     Sale,
+    // Tagihan tambahan kerusakan dari laporan pengembalian rental
+    #[serde(rename = "rental_damage")]
+    #[sqlx(rename = "rental_damage")]
+    RentalDamage,
+    // Deposit jaminan rental, dibayar terpisah dari biaya sewa dan direfund saat pengembalian
+    #[serde(rename = "rental_deposit")]
+    #[sqlx(rename = "rental_deposit")]
+    RentalDeposit,
 }
 
 impl std::fmt::Display for PaymentType {
@@ -82,6 +92,8 @@ impl std::fmt::Display for PaymentType {
         match self {
             PaymentType::Rental => write!(f, "rental"),
             PaymentType::Sale => write!(f, "sale"),
+            PaymentType::RentalDamage => write!(f, "rental_damage"),
+            PaymentType::RentalDeposit => write!(f, "rental_deposit"),
         }
     }
 }
@@ -177,26 +189,88 @@ pub struct MidtransWebhookPayload {
     pub va_numbers: Option<Vec<VaNumber>>,
 }
 
+// Bagian rental yang direfund: biaya sewa atau deposit jaminan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundTarget {
+    #[default]
+    RentalFee,
+    Deposit,
+}
+
 // Request refund payment
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct RefundRequest {
     pub order_id: String,
+    // Untuk target deposit boleh 0: nominal dihitung dari deposit dikurangi tagihan kerusakan
+    #[serde(default)]
     pub refund_amount: i64,
     pub reason: String,
+    #[serde(default)]
+    pub target: RefundTarget,
+}
+
+// Default batas waktu refund biaya rental setelah pembayaran (hari)
+pub const DEFAULT_REFUND_WINDOW_DAYS: i64 = 7;
+
+// Batas akhir refund: biaya rental dan tagihan kerusakan sampai paid_at + window.
+// Deposit punya alur sendiri saat pengembalian dan sale tidak bisa direfund, jadi tanpa batas di sini
+pub fn refund_deadline(
+    payment_for_type: &PaymentType,
+    paid_at: Option<DateTime<Utc>>,
+    refund_window_days: i64,
+) -> Option<DateTime<Utc>> {
+    match payment_for_type {
+        PaymentType::Rental | PaymentType::RentalDamage => {
+            paid_at.map(|paid| paid + chrono::Duration::days(refund_window_days))
+        }
+        PaymentType::Sale | PaymentType::RentalDeposit => None,
+    }
+}
+
+// Masih dalam window sampai tepat di deadline
+pub fn within_refund_window(deadline: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    deadline.is_none_or(|deadline| now <= deadline)
+}
+
+// Sisa window refund dalam detik, 0 jika sudah lewat
+pub fn refund_window_remaining_secs(deadline: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<i64> {
+    deadline.map(|deadline| (deadline - now).num_seconds().max(0))
+}
+
+// Komponen acak untuk ID (48 bit dari UUID v4) agar request bersamaan di detik yang sama tidak bentrok
+fn unique_suffix() -> String {
+    let uuid = uuid::Uuid::new_v4().simple().to_string();
+    uuid[..12].to_uppercase()
 }
 
 // Business logic methods
 impl Payment {
     /// Cek apakah payment sudah expired
     pub fn is_expired(&self) -> bool {
-        self.expired_at
-            .map(|expired| Utc::now() > expired)
-            .unwrap_or(false)
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Cek expired terhadap waktu tertentu (satu `now` untuk banyak payment sekaligus)
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expired_at.is_some_and(|expired| now > expired)
+    }
+
+    /// Batas akhir refund payment ini (lihat `refund_deadline`)
+    pub fn refund_deadline(&self, refund_window_days: i64) -> Option<DateTime<Utc>> {
+        refund_deadline(&self.payment_for_type, self.paid_at, refund_window_days)
     }
 
-    /// Cek apakah payment bisa direfund
-    pub fn can_be_refunded(&self) -> bool {
-        matches!(self.status, PaymentStatus::Success) && !self.is_expired()
+    /// Cek apakah payment bisa direfund, termasuk batas waktu refund
+    pub fn can_be_refunded(&self, refund_window_days: i64) -> bool {
+        self.can_be_refunded_at(refund_window_days, Utc::now())
+    }
+
+    /// Sama dengan `can_be_refunded` terhadap waktu tertentu
+    pub fn can_be_refunded_at(&self, refund_window_days: i64, now: DateTime<Utc>) -> bool {
+        matches!(self.status, PaymentStatus::Success)
+            && !self.is_expired_at(now)
+            && within_refund_window(self.refund_deadline(refund_window_days), now)
     }
 
     /// Generate order ID unik
@@ -204,22 +278,46 @@ impl Payment {
         let prefix = match payment_type {
             PaymentType::Rental => "RNT",
             PaymentType::Sale => "SAL",
+            PaymentType::RentalDamage => "DMG",
+            PaymentType::RentalDeposit => "DEP",
         };
 
         let date = Utc::now().format("%Y%m%d");
-        let random: u32 = rand::random();
-        format!("{}-{}-{:05}", prefix, date, random % 100000)
+        format!("{}-{}-{}", prefix, date, unique_suffix())
     }
 
-    /// Generate expiry time (24 jam untuk rental, 48 jam untuk sale)
+    /// Refund key deterministik per payment (satu payment hanya bisa direfund sekali),
+    /// retry dan request bersamaan mengirim key yang sama sehingga Midtrans tidak me-refund dua kali
+    pub fn refund_key(payment_id: i32) -> String {
+        format!("REF-PAY-{}", payment_id)
+    }
+
+    /// Generate expiry time (24 jam untuk rental & deposit, 48 jam untuk sale & tagihan kerusakan)
     pub fn generate_expiry_time(payment_type: PaymentType) -> DateTime<Utc> {
         let hours = match payment_type {
-            PaymentType::Rental => 24,
-            PaymentType::Sale => 48,
+            PaymentType::Rental | PaymentType::RentalDeposit => 24,
+            PaymentType::Sale | PaymentType::RentalDamage => 48,
         };
         Utc::now() + chrono::Duration::hours(hours)
     }
 }
+// gross_amount dari client harus sama persis dengan nominal dari booking/order di database,
+// agar nominal yang di-charge ke Midtrans tidak bisa dimanipulasi
+pub fn check_gross_amount(payment_type: &PaymentType, expected: i64, requested: i64) -> Result<(), AppError> {
+    if requested == expected {
+        return Ok(());
+    }
+
+    let source = match payment_type {
+        PaymentType::Rental => "rental total price",
+        PaymentType::Sale => "agreed sale price",
+        PaymentType::RentalDamage => "damage charge in the return report",
+        PaymentType::RentalDeposit => "rental deposit",
+    };
+
+    Err(AppError::validation(format!("Gross amount must match the {} ({})", source, expected)))
+}
+
 // Webhook response untuk Midtrans callback
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct WebhookResponse {
@@ -260,4 +358,85 @@ impl PaymentReceipt {
             receipt_url: format!("/receipts/{}", payment.order_id),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_generated_ids_unique_under_parallel_load() {
+        let ids: Vec<String> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..2_000)
+                            .flat_map(|_| {
+                                let order_id = Payment::generate_order_id(PaymentType::Rental);
+                                let damage_order_id = Payment::generate_order_id(PaymentType::RentalDamage);
+                                [order_id, damage_order_id]
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+
+        let unique: HashSet<&String> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len());
+    }
+
+    #[test]
+    fn test_refund_key_deterministic_per_payment() {
+        assert_eq!(Payment::refund_key(42), Payment::refund_key(42));
+        assert_ne!(Payment::refund_key(42), Payment::refund_key(43));
+    }
+
+    #[test]
+    fn test_tampered_gross_amount_rejected() {
+        // Harga mobil 250 juta, client mengirim 1 rupiah
+        assert!(check_gross_amount(&PaymentType::Sale, 250_000_000, 1).is_err());
+        assert!(check_gross_amount(&PaymentType::Sale, 250_000_000, 250_000_001).is_err());
+        assert!(check_gross_amount(&PaymentType::Sale, 250_000_000, 250_000_000).is_ok());
+
+        // Rental 3 hari x 500 ribu, deposit dibayar terpisah
+        assert!(check_gross_amount(&PaymentType::Rental, 1_500_000, 500_000).is_err());
+        assert!(check_gross_amount(&PaymentType::Rental, 1_500_000, 1_500_000).is_ok());
+
+        assert!(check_gross_amount(&PaymentType::RentalDeposit, 1_000_000, 999_999).is_err());
+        assert!(check_gross_amount(&PaymentType::RentalDamage, 350_000, 350_000).is_ok());
+    }
+
+    #[test]
+    fn test_refund_window_boundary() {
+        let paid_at = Utc::now() - chrono::Duration::days(30);
+        let deadline = refund_deadline(&PaymentType::Rental, Some(paid_at), 7);
+        assert_eq!(deadline, Some(paid_at + chrono::Duration::days(7)));
+        let deadline_at = deadline.unwrap();
+
+        // Tepat di batas masih boleh, satu detik setelahnya ditolak
+        assert!(within_refund_window(deadline, deadline_at));
+        assert!(!within_refund_window(deadline, deadline_at + chrono::Duration::seconds(1)));
+        assert!(within_refund_window(deadline, paid_at));
+
+        assert_eq!(refund_window_remaining_secs(deadline, deadline_at - chrono::Duration::hours(1)), Some(3600));
+        assert_eq!(refund_window_remaining_secs(deadline, deadline_at + chrono::Duration::days(1)), Some(0));
+
+        // Tagihan kerusakan ikut window, deposit dan sale tidak
+        assert!(refund_deadline(&PaymentType::RentalDamage, Some(paid_at), 7).is_some());
+        assert_eq!(refund_deadline(&PaymentType::RentalDeposit, Some(paid_at), 7), None);
+        assert_eq!(refund_deadline(&PaymentType::Sale, Some(paid_at), 7), None);
+        assert!(within_refund_window(None, Utc::now()));
+        assert_eq!(refund_window_remaining_secs(None, Utc::now()), None);
+    }
+
+    #[test]
+    fn test_order_id_fits_column() {
+        let order_id = Payment::generate_order_id(PaymentType::RentalDeposit);
+        assert!(order_id.starts_with("DEP-"));
+        // payments.order_id VARCHAR(50)
+        assert!(order_id.len() <= 50);
+    }
+}
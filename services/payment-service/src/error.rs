@@ -1,9 +1,12 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
+use shared::utils::request_id;
+use shared::utils::validation::{errors_by_field, FieldError, FromFieldErrors};
+use std::collections::BTreeMap;
 use std::fmt;
 
 // Struktur response error yang konsisten untuk semua endpoint
@@ -13,13 +16,20 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    // Pesan error per field untuk validasi form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<BTreeMap<String, String>>,
+    // Detik sampai client boleh mencoba lagi (sama dengan header Retry-After)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    pub request_id: String,
 }
 
 // Enum untuk semua jenis error yang mungkin terjadi di payment service
 #[derive(Debug)]
 pub enum AppError {
     DatabaseError(sqlx::Error),
-    ValidationError(String),
+    ValidationError { message: String, errors: Vec<FieldError> },
     UnauthorizedError(String),
     ForbiddenError(String),
     NotFoundError(String),
@@ -29,13 +39,14 @@ pub enum AppError {
     InternalError(String),
     TokenError(String),
     HttpClientError(reqwest::Error),
+    TooManyRequestsError { message: String, retry_after_secs: u64 },
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AppError::DatabaseError(e) => write!(f, "Database error: {}", e),
-            AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+            AppError::ValidationError { message, .. } => write!(f, "Validation error: {}", message),
             AppError::UnauthorizedError(msg) => write!(f, "Unauthorized error: {}", msg),
             AppError::ForbiddenError(msg) => write!(f, "Forbidden error: {}", msg),
             AppError::NotFoundError(msg) => write!(f, "Not found: {}", msg),
@@ -45,12 +56,31 @@ impl fmt::Display for AppError {
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
             AppError::TokenError(msg) => write!(f, "Token error: {}", msg),
             AppError::HttpClientError(e) => write!(f, "HTTP client error: {}", e),
+            AppError::TooManyRequestsError { message, .. } => write!(f, "Too many requests: {}", message),
         }
     }
 }
 
 impl std::error::Error for AppError {}
 
+// Konversi dari shared AuthError, status mengikuti shared agar sama di semua service
+impl From<shared::auth::AuthError> for AppError {
+    fn from(err: shared::auth::AuthError) -> Self {
+        match err.status_code() {
+            StatusCode::FORBIDDEN => AppError::forbidden(err.to_string()),
+            StatusCode::INTERNAL_SERVER_ERROR => AppError::internal(err.to_string()),
+            _ => AppError::unauthorized(err.to_string()),
+        }
+    }
+}
+
+// Error validasi per field (AppError::fields dari shared)
+impl FromFieldErrors for AppError {
+    fn from_field_errors(message: String, errors: Vec<FieldError>) -> Self {
+        AppError::ValidationError { message, errors }
+    }
+}
+
 // Konversi dari sqlx::Error ke AppError
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
@@ -89,10 +119,10 @@ impl IntoResponse for AppError {
                     },
                 )
             }
-            AppError::ValidationError(msg) => (
+            AppError::ValidationError { message, .. } => (
                 StatusCode::BAD_REQUEST,
                 "validation_error",
-                msg.as_str(),
+                message.as_str(),
                 None,
             ),
             AppError::UnauthorizedError(msg) => (
@@ -174,6 +204,12 @@ impl IntoResponse for AppError {
                     None
                 },
             ),
+            AppError::TooManyRequestsError { message, .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limit_exceeded",
+                message.as_str(),
+                None,
+            ),
             AppError::HttpClientError(e) => {
                 tracing::error!("HTTP client error: {:?}", e);
                 (
@@ -189,13 +225,36 @@ impl IntoResponse for AppError {
             }
         };
 
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
         let error_response = ErrorResponse {
             error: error_type.to_string(),
             message: message.to_string(),
             details,
+            errors: match &self {
+                AppError::ValidationError { errors, .. } if !errors.is_empty() => {
+                    Some(errors_by_field(errors))
+                }
+                _ => None,
+            },
+            retry_after: match &self {
+                AppError::TooManyRequestsError { retry_after_secs, .. } => Some(*retry_after_secs),
+                _ => None,
+            },
+            request_id: request_id.clone(),
         };
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        // Beri tahu client kapan boleh mencoba lagi
+        if let AppError::TooManyRequestsError { retry_after_secs, .. } = &self {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(*retry_after_secs));
+        }
+
+        response
     }
 }
 
@@ -203,7 +262,10 @@ impl IntoResponse for AppError {
 impl AppError {
     // Buat error validasi dengan pesan custom
     pub fn validation(msg: impl Into<String>) -> Self {
-        AppError::ValidationError(msg.into())
+        AppError::ValidationError {
+            message: msg.into(),
+            errors: Vec::new(),
+        }
     }
 
     // Buat error not found dengan pesan custom
@@ -233,7 +295,7 @@ impl AppError {
 
     // Buat error bad request dengan pesan custom
     pub fn bad_request(msg: impl Into<String>) -> Self {
-        AppError::ValidationError(msg.into())
+        AppError::validation(msg)
     }
 
     // Buat error database dengan pesan custom
@@ -255,7 +317,28 @@ impl AppError {
     pub fn internal_error(msg: impl Into<String>) -> Self {
         AppError::InternalError(msg.into())
     }
+
+    // Buat error 429 dengan Retry-After (detik)
+    pub fn too_many_requests(msg: impl Into<String>, retry_after_secs: u64) -> Self {
+        AppError::TooManyRequestsError { message: msg.into(), retry_after_secs }
+    }
 }
 
 // Type alias untuk Result dengan AppError sebagai error type
-pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("Payment tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
+    }
+}
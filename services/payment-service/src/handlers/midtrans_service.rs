@@ -4,6 +4,8 @@ use crate::domain::payment::{
     MidtransWebhookPayload, PaymentStatus
 };
 use crate::error::AppError;
+use crate::utils::midtrans_refund::MidtransRefundResponse;
+use crate::utils::midtrans_retry::{self, ChargeRetryPolicy};
 use reqwest::Client;
 use hmac::{Hmac, Mac};
 use sha2::Sha512;
@@ -16,10 +18,27 @@ pub struct MidtransService {
     server_key: String,
     is_production: bool,
     api_url: String,
+    charge_policy: ChargeRetryPolicy,
 }
 
 type HmacSha512 = Hmac<Sha512>;
 
+// Charge Midtrans yang gagal: ditolak pasti (4xx, transaksi belum dibuat) atau tidak pasti
+// (timeout, 5xx, response tidak terbaca) sehingga transaksi mungkin sudah dibuat Midtrans
+#[derive(Debug)]
+pub enum ChargeError {
+    Rejected(AppError),
+    Ambiguous(AppError),
+}
+
+impl From<ChargeError> for AppError {
+    fn from(error: ChargeError) -> Self {
+        match error {
+            ChargeError::Rejected(e) | ChargeError::Ambiguous(e) => e,
+        }
+    }
+}
+
 impl MidtransService {
     // Buat Midtrans Service baru
     pub fn new(
@@ -39,9 +58,16 @@ impl MidtransService {
             server_key,
             is_production,
             api_url,
+            charge_policy: ChargeRetryPolicy::default(),
         }
     }
 
+    // Set timeout + retry charge dari konfigurasi
+    pub fn with_charge_policy(mut self, policy: ChargeRetryPolicy) -> Self {
+        self.charge_policy = policy;
+        self
+    }
+
     /// Generate VA number unik
     fn generate_va_number(&self, order_id: &str, bank: &str) -> String {
         format!("{}-{}", bank.to_lowercase(), order_id)
@@ -75,31 +101,70 @@ impl MidtransService {
         &self,
         request: &CreatePaymentRequest,
         order_id: String,
-    ) -> Result<MidtransChargeResponse, AppError> {
+    ) -> Result<MidtransChargeResponse, ChargeError> {
         let midtrans_request = self.convert_to_midtrans_request(request, order_id);
 
         let auth_header = format!("Basic {}", self.encode_auth());
+        let url = format!("{}/charge", self.api_url);
 
-        let response = self.client
-            .post(format!("{}/charge", self.api_url))
-            .header("Authorization", auth_header)
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json")
-            .json(&midtrans_request)
-            .send()
-            .await?;
+        // Charge dikirim sekali: Midtrans menolak order_id yang sama sebagai duplikat sehingga retry
+        // charge tidak pernah berhasil. Hasil yang tidak pasti dicek lewat status order_id (di-retry)
+        let single_attempt = ChargeRetryPolicy { max_retries: 0, ..self.charge_policy };
+        let response = match midtrans_retry::send_with_retry(&single_attempt, || {
+            self.client
+                .post(&url)
+                .header("Authorization", &auth_header)
+                .header("Content-Type", "application/json")
+                .header("Accept", "application/json")
+                .timeout(self.charge_policy.timeout)
+                .json(&midtrans_request)
+                .send()
+        })
+        .await
+        {
+            Ok(response) => response,
+            Err(failure) => {
+                return self
+                    .recover_charge(&midtrans_request.transaction_details.order_id, failure.detail)
+                    .await;
+            }
+        };
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::midtrans(format!("Midtrans API error: {}", error_text)));
+            return Err(ChargeError::Rejected(AppError::midtrans(format!("Midtrans API error: {}", error_text))));
         }
 
-        let midtrans_response: MidtransChargeResponse = response.json().await
-            .map_err(|e| AppError::midtrans(format!("Failed to parse Midtrans response: {}", e)))?;
+        let midtrans_response: MidtransChargeResponse = match response.json().await {
+            Ok(midtrans_response) => midtrans_response,
+            Err(e) => {
+                let detail = format!("Failed to parse Midtrans response: {}", e);
+                return self.recover_charge(&midtrans_request.transaction_details.order_id, detail).await;
+            }
+        };
 
         Ok(midtrans_response)
     }
 
+    // Charge yang hasilnya tidak pasti: ambil transaksinya lewat order_id, tetap Ambiguous jika
+    // belum ditemukan (request charge bisa masih diproses Midtrans, scheduler mengecek lagi nanti)
+    async fn recover_charge(&self, order_id: &str, detail: String) -> Result<MidtransChargeResponse, ChargeError> {
+        match self.find_transaction_by_order_id(order_id).await {
+            Ok(Some(charge)) => {
+                tracing::info!("✅ Midtrans charge {} found after unclear response: {}", order_id, detail);
+                Ok(charge)
+            }
+            Ok(None) => Err(ChargeError::Ambiguous(AppError::payment(format!(
+                "Midtrans charge gagal, transaksi belum ditemukan: {}",
+                detail
+            )))),
+            Err(e) => Err(ChargeError::Ambiguous(AppError::payment(format!(
+                "Midtrans charge gagal ({}), cek status juga gagal: {}",
+                detail, e
+            )))),
+        }
+    }
+
     /// Verify webhook signature dari Midtrans
     pub fn verify_webhook_signature(
         &self,
@@ -212,7 +277,7 @@ impl MidtransService {
 
   /// Check transaction status dari Midtrans API
   pub async fn check_transaction_status(&self, transaction_id: &str) -> Result<serde_json::Value, AppError> {
-      let url = format!("{}/v2/{}/status", self.api_url, transaction_id);
+      let url = format!("{}/{}/status", self.api_url, transaction_id);
 
       let response = self.client
           .get(&url)
@@ -238,4 +303,184 @@ impl MidtransService {
       }
   }
 
-  }
\ No newline at end of file
+  /// Cari transaksi berdasarkan order_id, None jika Midtrans belum pernah menerima charge-nya
+  pub async fn find_transaction_by_order_id(&self, order_id: &str) -> Result<Option<MidtransChargeResponse>, AppError> {
+      let url = format!("{}/{}/status", self.api_url, order_id);
+
+      // Cek status idempoten, jadi aman di-retry untuk timeout/5xx
+      let response = midtrans_retry::send_with_retry(&self.charge_policy, || {
+          self.client
+              .get(&url)
+              .header("Accept", "application/json")
+              .basic_auth(&self.server_key, Some(""))
+              .timeout(self.charge_policy.timeout)
+              .send()
+      })
+      .await
+      .map_err(|failure| AppError::midtrans(format!(
+          "Midtrans status gagal setelah {} percobaan: {}",
+          failure.attempts, failure.detail
+      )))?;
+
+      if response.status() == reqwest::StatusCode::NOT_FOUND {
+          return Ok(None);
+      }
+      if !response.status().is_success() {
+          let error_text = response.text().await.unwrap_or_default();
+          return Err(AppError::midtrans(format!("Midtrans API error: {}", error_text)));
+      }
+
+      // Midtrans juga bisa menjawab HTTP 200 dengan status_code "404" di body
+      let body: serde_json::Value = response.json().await
+          .map_err(|e| AppError::midtrans(format!("Failed to parse Midtrans response: {}", e)))?;
+      if body.get("status_code").and_then(|v| v.as_str()) == Some("404") {
+          return Ok(None);
+      }
+
+      serde_json::from_value(body)
+          .map(Some)
+          .map_err(|e| AppError::midtrans(format!("Unexpected Midtrans status response: {}", e)))
+  }
+
+  /// Refund ke channel pembayaran asal. Midtrans mendedupe refund berdasarkan refund_key,
+  /// jadi caller wajib memakai key yang sama untuk retry refund yang sama (lihat Payment::refund_key)
+  pub async fn refund(
+      &self,
+      transaction_id: &str,
+      refund_key: &str,
+      amount: i64,
+      reason: &str,
+  ) -> Result<MidtransRefundResponse, AppError> {
+      let url = format!("{}/{}/refund", self.api_url, transaction_id);
+
+      let response = self.client
+          .post(&url)
+          .header("Accept", "application/json")
+          .header("Content-Type", "application/json")
+          .basic_auth(&self.server_key, Some(""))
+          .json(&serde_json::json!({
+              "refund_key": refund_key,
+              "amount": amount,
+              "reason": reason
+          }))
+          .send()
+          .await
+          .map_err(|e| AppError::midtrans(format!("Failed to call Midtrans refund API: {}", e)))?;
+
+      // Midtrans menaruh hasil sebenarnya di status_code body, termasuk saat refund ditolak
+      let body = response.text().await
+          .map_err(|e| AppError::midtrans(format!("Failed to read Midtrans refund response: {}", e)))?;
+
+      serde_json::from_str(&body)
+          .map_err(|_| AppError::midtrans(format!("Unexpected Midtrans refund response: {}", body)))
+  }
+
+  }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::midtrans_refund::{outcome_from_response, RefundStatus};
+    use axum::{extract::Path, routing::post, Json, Router};
+
+    // Mock endpoint refund Midtrans di port acak, api_url sama formatnya dengan MIDTRANS_API_URL (.../v2)
+    async fn spawn_refund_mock(response: serde_json::Value) -> String {
+        let router = Router::new().route(
+            "/v2/{transaction_id}/refund",
+            post(move |Path(transaction_id): Path<String>, Json(body): Json<serde_json::Value>| {
+                let response = response.clone();
+                async move {
+                    assert_eq!(transaction_id, "tx-123");
+                    assert_eq!(body["refund_key"], "REF-ORDER-1");
+                    assert_eq!(body["amount"], 150000);
+                    Json(response)
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}/v2", addr)
+    }
+
+    #[tokio::test]
+    async fn test_refund_with_mocked_midtrans_response() {
+        let api_url = spawn_refund_mock(serde_json::json!({
+            "status_code": "200",
+            "status_message": "Success, refund request is approved",
+            "refund_chargeback_id": 987,
+            "refund_key": "REF-ORDER-1"
+        }))
+        .await;
+        let service = MidtransService::new("server-key".to_string(), "client-key".to_string(), api_url);
+
+        let response = service.refund("tx-123", "REF-ORDER-1", 150000, "Booking dibatalkan").await.unwrap();
+        let outcome = outcome_from_response(&response).unwrap();
+
+        assert_eq!(outcome.status, RefundStatus::Completed);
+        assert_eq!(outcome.reference.as_deref(), Some("987"));
+    }
+
+    // Charge 5xx tidak diulang; transaksi yang ternyata sudah dibuat diambil lewat status order_id
+    #[tokio::test]
+    async fn test_unclear_charge_recovered_by_status_lookup() {
+        use axum::{http::StatusCode, routing::get};
+        use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+
+        let charges = Arc::new(AtomicU32::new(0));
+        let counter = charges.clone();
+        let router = Router::new()
+            .route(
+                "/v2/charge",
+                post(move || {
+                    let counter = counter.clone();
+                    async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        (StatusCode::BAD_GATEWAY, "upstream error")
+                    }
+                }),
+            )
+            .route(
+                "/v2/{order_id}/status",
+                get(|Path(order_id): Path<String>| async move {
+                    Json(serde_json::json!({
+                        "status_code": "201",
+                        "status_message": "Success, transaction is found",
+                        "transaction_id": "trx-recovered-1",
+                        "order_id": order_id,
+                        "gross_amount": "700000.00",
+                        "payment_type": "bank_transfer",
+                        "transaction_status": "pending",
+                        "transaction_time": "2026-10-16 10:00:00",
+                        "va_numbers": [{ "bank": "bca", "va_number": "1234567890" }]
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let service = MidtransService::new("server-key".to_string(), String::new(), format!("http://{}/v2", addr))
+            .with_charge_policy(ChargeRetryPolicy::new(2, 2));
+        let request = CreatePaymentRequest {
+            payment_for_type: crate::domain::payment::PaymentType::Rental,
+            rental_booking_id: Some(1),
+            sale_order_id: None,
+            gross_amount: 700_000,
+            payment_method: "bca".to_string(),
+            customer_details: crate::domain::payment::CustomerDetails {
+                first_name: "Customer".to_string(),
+                last_name: None,
+                email: "customer@test.local".to_string(),
+                phone: "081200000001".to_string(),
+            },
+            item_details: vec![],
+        };
+
+        let charge = service.charge_payment(&request, "RNT-PAY-9".to_string()).await.unwrap();
+        assert_eq!(charge.transaction_id, "trx-recovered-1");
+        assert_eq!(charge.order_id, "RNT-PAY-9");
+        assert_eq!(charges.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod payment_handler;
-pub mod midtrans_service;
\ No newline at end of file
+pub mod midtrans_service;
+pub mod audit_log_handler;
\ No newline at end of file
@@ -1,18 +1,37 @@
+use crate::domain::deposit::{self, DepositStatus};
 use crate::domain::payment::{
-    CreatePaymentRequest, Payment, PaymentStatus, PaymentType,
-    RefundRequest, WebhookResponse, PaymentReceipt
+    check_gross_amount, refund_window_remaining_secs, CreatePaymentRequest, MidtransChargeResponse, Payment, PaymentStatus, PaymentType,
+    RefundRequest, RefundTarget, WebhookResponse, PaymentReceipt
 };
-use crate::handlers::midtrans_service::MidtransService;
+use crate::handlers::midtrans_service::{ChargeError, MidtransService};
+use crate::repositories::payment_repo::PaymentRepository;
+use crate::utils::payment_events::{PaymentEvent, PaymentStatusSnapshot};
+use crate::utils::payment_status_batch::{
+    build_batch_items, normalize_order_ids, PaymentStatusBatchItem, PaymentStatusBatchRequest,
+};
+use crate::utils::midtrans_refund::{self, RefundFailure, RefundOutcome, RefundStatus};
+use crate::utils::midtrans_retry::ChargeRetryPolicy;
+use crate::utils::resend_throttle::{check_resend_allowed, claim_status_check, ResendLimits};
+use crate::utils::webhook_allowlist;
+use crate::utils::private_file;
 use crate::error::AppError;
 use axum::{
-    extract::{Path, State},
-    response::Json,
-    http::HeaderMap,
+    extract::{ConnectInfo, Path, State},
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Json, Response},
+    http::{header, HeaderMap},
 };
+use futures_util::Stream;
 use serde_json::{json, Value};
+use shared::utils::creation::Creation;
+use shared::utils::storage::verify_file_token;
+use shared::utils::validation::{FieldError, FromFieldErrors};
 use chrono::Utc;
 use crate::middleware::auth::AuthUser;
 use sqlx::PgPool;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
 use utoipa;
 
 
@@ -25,7 +44,9 @@ use utoipa;
     description = "Create a new payment for rental booking or sale order with Midtrans integration",
     request_body = CreatePaymentRequest,
     responses(
-        (status = 200, description = "Payment created successfully", body = serde_json::Value),
+        (status = 201, description = "Payment created successfully", body = serde_json::Value,
+            headers(("Location" = String, description = "URL payment by order ID"))),
+        (status = 200, description = "Payment already exists for this booking/order", body = serde_json::Value),
         (status = 400, description = "Bad request"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
@@ -39,40 +60,37 @@ pub async fn create_payment(
     auth: AuthUser,
     State(app_state): State<crate::config::AppState>,
     Json(request): Json<CreatePaymentRequest>,
-) -> Result<Json<Value>, AppError> {
+) -> Result<Creation<Value>, AppError> {
     // security validasi
     validate_booking_order_ownership(&auth, &request, &app_state.db).await?;
 
     // Validasi request awal
     validate_payment_request(&request)?;
 
-    // Cek duplikasi payment menggunakan repository
-    let payment_exists = match request.payment_for_type {
-        PaymentType::Rental => {
-            if let Some(booking_id) = request.rental_booking_id {
-                app_state.payment_repository.exists_for_rental_booking(booking_id).await?
-            } else {
-                false
-            }
-        }
-        PaymentType::Sale => {
-            if let Some(sale_order_id) = request.sale_order_id {
-                app_state.payment_repository.exists_for_sale_order(sale_order_id).await?
-            } else {
-                false
-            }
-        }
+    // Cek duplikasi payment menggunakan repository; payment yang sudah ada dikembalikan apa adanya
+    let repo = &app_state.payment_repository;
+    let existing_order_id = match (request.payment_for_type.clone(), request.rental_booking_id, request.sale_order_id) {
+        (PaymentType::Rental, Some(booking_id), _) => repo.existing_order_id_for_rental_booking(booking_id).await?,
+        (PaymentType::Sale, _, Some(sale_order_id)) => repo.existing_order_id_for_sale_order(sale_order_id).await?,
+        (PaymentType::RentalDamage, Some(booking_id), _) => repo.existing_order_id_for_rental_damage(booking_id).await?,
+        (PaymentType::RentalDeposit, Some(booking_id), _) => repo.existing_order_id_for_rental_deposit(booking_id).await?,
+        _ => None,
     };
 
-    if payment_exists {
-        return Ok(Json(json!({
+    if let Some(existing_order_id) = existing_order_id {
+        let payment = repo.find_by_order_id(&existing_order_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Payment not found"))?;
+
+        return Ok(Creation::existing(json!({
             "success": true,
-            "message": "Payment already exists for this booking/order"
+            "message": "Payment already exists for this booking/order",
+            "data": format_payment_response(&payment, &app_state.config)
         })));
     }
 
     // Generate order ID unik berdasarkan tipe
-    let order_id = Payment::generate_order_id(request.payment_for_type.clone());
+    let mut order_id = Payment::generate_order_id(request.payment_for_type.clone());
     let expiry_time = Payment::generate_expiry_time(request.payment_for_type.clone());
 
     // Create Midtrans service
@@ -80,19 +98,25 @@ pub async fn create_payment(
         app_state.config.midtrans_server_key.clone(),
         app_state.config.midtrans_client_key.clone(),
         app_state.config.midtrans_api_url.clone(),
-    );
-
-    // Proses charge ke Midtrans
-    let midtrans_response = midtrans_service
-        .charge_payment(&request, order_id.clone())
-        .await
-        .map_err(|e| {
-            tracing::error!("Midtrans charge failed: {} - {}", order_id, e);
-            e
-        })?;
+    )
+    .with_charge_policy(ChargeRetryPolicy::new(
+        app_state.config.midtrans_charge_timeout_secs,
+        app_state.config.midtrans_charge_max_retries,
+    ));
+
+    // Reservasi order_id di database sebelum charge; jika order_id ternyata sudah dipakai,
+    // generate ulang sekali. Midtrans belum dipanggil, jadi tidak ada charge yatim
+    let payment = match app_state.payment_repository.reserve_payment(&request, &order_id, expiry_time).await {
+        Err(e) if is_order_id_conflict(&e) => {
+            tracing::warn!("Order ID {} already exists, regenerating", order_id);
+            order_id = Payment::generate_order_id(request.payment_for_type.clone());
+            app_state.payment_repository.reserve_payment(&request, &order_id, expiry_time).await?
+        }
+        result => result?,
+    };
 
-    // Simpan payment ke database
-    let payment = app_state.payment_repository.create_payment(&request, &order_id, &midtrans_response, expiry_time).await?;
+    let (midtrans_response, payment) =
+        charge_reserved_payment(&app_state.payment_repository, &midtrans_service, &request, payment).await?;
 
     // Generate instruksi pembayaran
     let instructions = if let Some(vas) = &midtrans_response.va_numbers {
@@ -108,7 +132,7 @@ pub async fn create_payment(
     // Log untuk audit
     log_payment_created(&order_id, &request);
 
-    Ok(Json(json!({
+    Ok(Creation::created(format!("/api/payments/{}", order_id), json!({
         "success": true,
         "message": "Payment created successfully",
         "data": {
@@ -163,7 +187,7 @@ pub async fn get_payment_by_order_id(
 
     Ok(Json(json!({
         "success": true,
-        "data": format_payment_response(&payment)
+        "data": format_payment_response(&payment, &app_state.config)
     })))
 }
 
@@ -243,7 +267,7 @@ pub async fn get_payment_details(
 
     Ok(Json(json!({
         "success": true,
-        "data": format_payment_response(&payment)
+        "data": format_payment_response(&payment, &app_state.config)
     })))
 }
 
@@ -258,14 +282,25 @@ pub async fn get_payment_details(
         (status = 200, description = "Webhook processed successfully", body = WebhookResponse),
         (status = 400, description = "Bad request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Source IP tidak ada di allowlist Midtrans"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn midtrans_webhook(
     State(app_state): State<crate::config::AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: axum::http::HeaderMap,
     body: String,
 ) -> Result<Json<WebhookResponse>, AppError> {
+    // Allowlist IP sebelum payload diproses, signature tetap dicek setelahnya
+    if let Some(allowlist) = &app_state.config.midtrans_webhook_allowlist {
+        let source_ip = webhook_allowlist::source_ip(peer.ip(), &headers, app_state.config.trusted_proxies.as_ref());
+        if !allowlist.contains(source_ip) {
+            tracing::warn!("Midtrans webhook rejected from non-allowlisted IP {}", source_ip);
+            return Err(AppError::forbidden("Source IP tidak diizinkan"));
+        }
+    }
+
     // Extract dan validasi signature
     let signature = extract_signature(&headers)?;
 
@@ -303,12 +338,18 @@ pub async fn midtrans_webhook(
         &webhook_payload,
     ).await?;
 
+    apply_payment_success_effects(&app_state.payment_repository, &payment, new_status).await?;
+    app_state.payment_events.publish(&payment.order_id, new_status);
+
     // Log webhook processing
     tracing::info!(
-        "Webhook processed: {} - {} -> {}",
-        webhook_payload.order_id,
-        webhook_payload.transaction_status,
-        new_status
+        event = "payment_status_changed",
+        order_id = %webhook_payload.order_id,
+        payment_id = payment.id,
+        transaction_status = %webhook_payload.transaction_status,
+        status = %new_status,
+        source = "webhook",
+        "Webhook processed"
     );
 
     Ok(Json(WebhookResponse {
@@ -320,13 +361,55 @@ pub async fn midtrans_webhook(
     }))
 }
 
+// Efek ke booking setelah payment lunas (webhook, resend, dan scheduler rekonsiliasi)
+pub(crate) async fn apply_payment_success_effects(
+    repository: &PaymentRepository,
+    payment: &Payment,
+    new_status: PaymentStatus,
+) -> Result<(), AppError> {
+    if new_status != PaymentStatus::Success {
+        return Ok(());
+    }
+
+    match payment.payment_for_type {
+        // Tagihan kerusakan lunas: update laporan pengembalian rental
+        PaymentType::RentalDamage => {
+            if let Some(booking_id) = payment.rental_booking_id {
+                repository.mark_damage_charge_paid(booking_id).await?;
+            }
+        }
+        // Deposit lunas: tahan sampai rental dikembalikan
+        PaymentType::RentalDeposit => {
+            if let Some(booking_id) = payment.rental_booking_id {
+                repository.mark_deposit_held(booking_id).await?;
+            }
+        }
+        // Sale order lunas: booking-service memindahkan order pending_payment -> paid
+        PaymentType::Sale => {
+            if let Some(sale_order_id) = payment.sale_order_id {
+                if repository.record_sale_order_paid(sale_order_id, &payment.order_id).await? {
+                    tracing::info!(
+                        event = "sale_order_paid",
+                        sale_order_id,
+                        order_id = %payment.order_id,
+                        "Event sale order lunas dicatat untuk booking-service"
+                    );
+                }
+            }
+        }
+        PaymentType::Rental => {}
+    }
+
+    Ok(())
+}
+
 /// Process refund request
 #[utoipa::path(
     post,
     path = "/api/refunds",
     tag = "Payment Service",
     summary = "Process refund",
-    description = "Process refund for successful rental payments",
+    description = "Process refund for successful rental payments. Set `target` to `deposit` to refund the rental deposit; the refund is the deposit minus any unpaid damage charges from the return report",
     request_body = RefundRequest,
     responses(
         (status = 200, description = "Refund processed successfully", body = serde_json::Value),
@@ -351,6 +434,15 @@ pub async fn process_refund(
 
     validate_payment_ownership(&auth, &payment, &app_state.db).await?;
 
+    // Refund deposit punya alur sendiri (netting tagihan kerusakan)
+    match (request.target, &payment.payment_for_type) {
+        (RefundTarget::Deposit, _) => return process_deposit_refund(auth, app_state, payment, request).await,
+        (RefundTarget::RentalFee, PaymentType::RentalDeposit) => {
+            return Err(AppError::validation("Use target \"deposit\" to refund a rental deposit"));
+        }
+        _ => {}
+    }
+
     // Validasi status
     if payment.status != PaymentStatus::Success {
         return Err(AppError::refund("Refund only available for successful payments"));
@@ -364,34 +456,247 @@ pub async fn process_refund(
         return Err(AppError::payment("Sale payments are final and cannot be refunded"));
     }
 
+    let refund_window_days = app_state.config.refund_window_days;
+    if let Some(deadline) = payment.refund_deadline(refund_window_days) {
+        if Utc::now() > deadline {
+            return Err(AppError::refund(format!(
+                "Refund window of {} days has passed (ended at {})",
+                refund_window_days,
+                deadline.to_rfc3339()
+            )));
+        }
+    }
+
     // Validasi business rules
     check_refund_eligibility(&payment, &request)?;
 
-    // Generate refund ID
-    let refund_id = generate_refund_id(&payment.order_id);
+    let midtrans_service = MidtransService::new(
+        app_state.config.midtrans_server_key.clone(),
+        app_state.config.midtrans_client_key.clone(),
+        app_state.config.midtrans_api_url.clone(),
+    );
 
-    // Proses refund menggunakan repository
-    app_state.payment_repository.process_refund(
-        payment.id,
-        &refund_id,
+    let (refund_id, refunded_amount, outcome) = refund_to_channel(
+        &app_state.payment_repository,
+        &midtrans_service,
+        &payment,
         request.refund_amount,
         &request.reason,
+        auth.user_id,
     ).await?;
 
     // Log refund
     tracing::info!(
-        "Refund processed: {} - {} ({} IDR)",
-        payment.order_id, refund_id, request.refund_amount
+        event = "refund_processed",
+        order_id = %payment.order_id,
+        payment_id = payment.id,
+        refund_id = %refund_id,
+        amount = refunded_amount,
+        status = outcome.status.as_str(),
+        user_id = auth.user_id,
+        "Refund processed"
+    );
+
+    let message = match outcome.status {
+        RefundStatus::Completed => "Refund sent to the original payment channel",
+        RefundStatus::ManualRequired => "Refund recorded and flagged for manual processing",
+    };
+
+    Ok(Json(json!({
+        "success": true,
+        "message": message,
+        "data": {
+            "refund_id": refund_id,
+            "order_id": payment.order_id,
+            "refund_amount": refunded_amount,
+            "status": outcome.status.as_str(),
+            "refund_reference": outcome.reference,
+            "detail": outcome.detail
+        }
+    })))
+}
+
+// Kembalikan dana ke channel asal; channel tanpa refund otomatis ditandai untuk proses manual.
+// Payment dikunci dan refund dicatat pending sebelum Midtrans dipanggil, dengan refund key
+// deterministik per payment: retry atau request bersamaan mengirim key + nominal yang sama
+// sehingga Midtrans hanya me-refund sekali.
+async fn refund_to_channel(
+    repository: &PaymentRepository,
+    midtrans_service: &MidtransService,
+    payment: &Payment,
+    refund_amount: i64,
+    reason: &str,
+    actor_id: i32,
+) -> Result<(String, i64, RefundOutcome), AppError> {
+    let refund_key = Payment::refund_key(payment.id);
+    let reserved = repository.reserve_refund(payment.id, refund_amount, reason).await?;
+
+    if reserved.resumed {
+        tracing::info!(payment_id = payment.id, refund_key = %refund_key, "Melanjutkan refund pending");
+    }
+
+    let outcome = submit_pending_refund(
+        repository,
+        midtrans_service,
+        payment,
+        reserved.amount,
+        &reserved.reason,
+        Some(actor_id),
+    ).await?;
+
+    Ok((refund_key, reserved.amount, outcome))
+}
+
+// Kirim refund pending ke Midtrans lalu catat hasilnya. Hanya penolakan pasti (4xx) yang melepas
+// refund pending; timeout/5xx tetap pending karena Midtrans mungkin sudah memprosesnya, dan
+// diselesaikan rekonsiliasi scheduler.
+async fn submit_pending_refund(
+    repository: &PaymentRepository,
+    midtrans_service: &MidtransService,
+    payment: &Payment,
+    amount: i64,
+    reason: &str,
+    actor_id: Option<i32>,
+) -> Result<RefundOutcome, AppError> {
+    let refund_key = Payment::refund_key(payment.id);
+
+    let outcome = match send_refund(midtrans_service, payment, amount, reason).await {
+        Ok(outcome) => outcome,
+        Err(RefundFailure::Rejected(message)) => {
+            // Refund belum terjadi; refund ulang nanti tetap memakai key yang sama
+            if let Err(release_err) = repository.release_refund(payment.id).await {
+                tracing::error!("Gagal melepas refund pending payment {}: {}", payment.id, release_err);
+            }
+            return Err(AppError::midtrans(message));
+        }
+        Err(RefundFailure::Ambiguous(message)) => {
+            tracing::warn!(
+                payment_id = payment.id,
+                refund_key = %refund_key,
+                "Hasil refund Midtrans tidak pasti, refund tetap pending: {}",
+                message
+            );
+            return Err(AppError::midtrans(format!(
+                "Refund result is not confirmed yet and stays pending until reconciled with Midtrans ({})",
+                message
+            )));
+        }
+    };
+
+    repository.process_refund(payment.id, &refund_key, amount, reason, actor_id, &outcome).await?;
+
+    Ok(outcome)
+}
+
+// Kirim refund ke channel asal (key deterministik per payment), tanpa mengubah database.
+// Timeout, 5xx dan response yang tidak terbaca dianggap tidak pasti.
+pub async fn send_refund(
+    midtrans_service: &MidtransService,
+    payment: &Payment,
+    amount: i64,
+    reason: &str,
+) -> Result<RefundOutcome, RefundFailure> {
+    if !midtrans_refund::supports_automated_refund(payment.payment_type.as_deref()) {
+        return Ok(RefundOutcome::manual(format!(
+            "Channel {} tidak mendukung refund otomatis",
+            payment.payment_type.as_deref().unwrap_or("unknown")
+        )));
+    }
+
+    let transaction_id = payment.transaction_id.as_deref().unwrap_or(&payment.order_id);
+    let refund_key = Payment::refund_key(payment.id);
+
+    match midtrans_service.refund(transaction_id, &refund_key, amount, reason).await {
+        Ok(response) => midtrans_refund::outcome_from_response(&response),
+        Err(e) => Err(RefundFailure::Ambiguous(e.to_string())),
+    }
+}
+
+// Refund deposit rental setelah pengembalian (atau booking dibatalkan)
+async fn process_deposit_refund(
+    auth: AuthUser,
+    app_state: crate::config::AppState,
+    payment: Payment,
+    request: RefundRequest,
+) -> Result<Json<Value>, AppError> {
+    let booking_id = payment.rental_booking_id
+        .ok_or_else(|| AppError::validation("Deposit refunds are only available for rental payments"))?;
+
+    // Order ID biaya sewa boleh dipakai, diarahkan ke payment deposit booking yang sama
+    let payment = if matches!(payment.payment_for_type, PaymentType::RentalDeposit) {
+        payment
+    } else {
+        let order_id = app_state.payment_repository.find_paid_deposit_order_id(booking_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("No paid deposit for this rental"))?;
+        app_state.payment_repository.find_by_order_id(&order_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Payment not found"))?
+    };
+
+    if payment.status != PaymentStatus::Success {
+        return Err(AppError::refund("Refund only available for successful payments"));
+    }
+
+    if request.reason.trim().is_empty() {
+        return Err(AppError::fields(vec![FieldError::new("reason", "Refund reason is required")]));
+    }
+
+    let context = app_state.payment_repository.get_deposit_context(booking_id).await?;
+
+    if context.deposit_status != DepositStatus::Held {
+        return Err(AppError::refund(format!(
+            "Deposit cannot be refunded (status: {})",
+            context.deposit_status.as_str()
+        )));
+    }
+
+    if !context.returned && !context.cancelled {
+        return Err(AppError::refund("Deposit can only be refunded after the return report is filed"));
+    }
+
+    if context.damage_payment_pending {
+        return Err(AppError::refund("A damage charge payment is in progress for this rental"));
+    }
+
+    let settlement = deposit::settle_deposit(payment.gross_amount, context.outstanding_damage);
+    deposit::check_requested_amount(&settlement, request.refund_amount)?;
+
+    let refund_id = Payment::refund_key(payment.id);
+
+    app_state.payment_repository.settle_deposit_refund(
+        payment.id,
+        booking_id,
+        &refund_id,
+        &settlement,
+        &request.reason,
+        auth.user_id,
+    ).await?;
+
+    tracing::info!(
+        event = "deposit_refund_processed",
+        order_id = %payment.order_id,
+        payment_id = payment.id,
+        refund_id = %refund_id,
+        amount = settlement.refund_amount,
+        damage_deducted = settlement.damage_deducted,
+        user_id = auth.user_id,
+        "Deposit refund processed"
     );
 
     Ok(Json(json!({
         "success": true,
-        "message": "Refund processed successfully",
+        "message": "Deposit refund processed successfully",
         "data": {
             "refund_id": refund_id,
             "order_id": payment.order_id,
-            "refund_amount": request.refund_amount,
-            "status": "processing"
+            "target": RefundTarget::Deposit,
+            "deposit_amount": settlement.deposit_amount,
+            "damage_deducted": settlement.damage_deducted,
+            "damage_remaining": settlement.damage_remaining,
+            "refund_amount": settlement.refund_amount,
+            "deposit_status": settlement.status,
+            "status": if settlement.refund_amount > 0 { "processing" } else { "settled" }
         }
     })))
 }
@@ -434,12 +739,15 @@ pub async fn get_payment_receipt(
         return Err(AppError::bad_request("Payment receipt only available for successful payments"));
     }
 
-    let receipt = PaymentReceipt::from_payment(&payment);
+    let mut receipt = PaymentReceipt::from_payment(&payment);
 
     // Update receipt path di database untuk tracking
     let receipt_path = format!("/receipts/{}.pdf", receipt.receipt_id);
     app_state.payment_repository.update_receipt_path(payment.id, receipt_path.clone()).await?;
 
+    // Client hanya menerima signed URL dengan masa berlaku terbatas
+    receipt.receipt_url = private_file::signed_receipt_path(&app_state.config, payment.id, &receipt_path);
+
     tracing::info!("Receipt generated: {} - {} (path: {})", order_id, receipt.receipt_id, receipt_path);
 
     Ok(Json(json!({
@@ -448,6 +756,58 @@ pub async fn get_payment_receipt(
     })))
 }
 
+/// Download receipt lewat signed URL
+#[utoipa::path(
+    get,
+    path = "/api/payments/files/{token}",
+    tag = "Payment Service",
+    summary = "Download payment receipt",
+    description = "Validasi signed URL (HMAC + expiry) lalu kirim receipt. Hanya pihak yang terlibat di payment yang boleh mengakses",
+    params(
+        ("token" = String, Path, description = "Token signed URL dari response payment/receipt")
+    ),
+    responses(
+        (status = 200, description = "Isi receipt", body = PaymentReceipt),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Signed URL tidak valid, kedaluwarsa, atau bukan milik user"),
+        (status = 404, description = "Receipt tidak ditemukan")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_receipt_file(
+    auth: AuthUser,
+    State(app_state): State<crate::config::AppState>,
+    Path(token): Path<String>,
+) -> Result<Response, AppError> {
+    let claims = verify_file_token(app_state.config.file_url_secret.as_bytes(), &token, Utc::now().timestamp())
+        .map_err(|e| AppError::forbidden(e.to_string()))?;
+
+    if claims.resource != private_file::PAYMENT_RECEIPT {
+        return Err(AppError::forbidden("Signed URL tidak valid"));
+    }
+
+    let payment = app_state.payment_repository.find_by_id(claims.resource_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt tidak ditemukan"))?;
+
+    validate_payment_ownership(&auth, &payment, &app_state.db).await?;
+
+    // Path harus masih sama dengan receipt di payment, receipt yang diganti tidak bisa diakses lagi
+    if payment.receipt_pdf_path.as_deref() != Some(claims.url.as_str()) {
+        return Err(AppError::forbidden("Anda tidak memiliki akses ke receipt ini"));
+    }
+
+    let mut receipt = PaymentReceipt::from_payment(&payment);
+    receipt.receipt_url = format!("/api/payments/files/{}", token);
+
+    Ok((
+        [(header::CONTENT_DISPOSITION, format!("inline; filename=\"{}.json\"", receipt.receipt_id))],
+        Json(receipt),
+    ).into_response())
+}
+
 /// Check payment status
 #[utoipa::path(
     get,
@@ -482,6 +842,9 @@ pub async fn check_payment_status(
 
     tracing::info!("Payment status checked: {} - {} by user: {}", order_id, payment.status, auth.user_id);
 
+    let refund_window_days = app_state.config.refund_window_days;
+    let refund_deadline = payment.refund_deadline(refund_window_days);
+
     Ok(Json(json!({
         "success": true,
         "data": {
@@ -490,12 +853,163 @@ pub async fn check_payment_status(
             "transaction_id": payment.transaction_id,
             "is_expired": payment.is_expired(),
             "expired_at": payment.expired_at,
-            "can_be_refunded": payment.can_be_refunded(),
+            "can_be_refunded": payment.can_be_refunded(refund_window_days),
+            "refund_deadline": refund_deadline,
+            "refund_window_remaining_secs": refund_window_remaining_secs(refund_deadline, Utc::now()),
             "payment_type": payment.payment_type
         }
     })))
 }
 
+/// Check status of multiple payments in one call
+#[utoipa::path(
+    post,
+    path = "/api/payments/status/batch",
+    tag = "Payment Service",
+    summary = "Batch check payment status",
+    description = "Status, expiry, dan refundability banyak payment sekaligus (maksimal 50 order_id). Order ID yang tidak ditemukan atau bukan milik user dikembalikan dengan result `not_found` / `forbidden` tanpa detail",
+    request_body = PaymentStatusBatchRequest,
+    responses(
+        (status = 200, description = "Status per order_id sesuai urutan request", body = Vec<PaymentStatusBatchItem>),
+        (status = 400, description = "order_ids kosong atau melebihi batas"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn check_payment_status_batch(
+    auth: AuthUser,
+    State(app_state): State<crate::config::AppState>,
+    Json(request): Json<PaymentStatusBatchRequest>,
+) -> Result<Json<Value>, AppError> {
+    let order_ids = normalize_order_ids(&request.order_ids).map_err(AppError::validation)?;
+
+    let rows = app_state.payment_repository.find_statuses_for_user(&order_ids, auth.user_id).await?;
+    let items = build_batch_items(&order_ids, &rows, app_state.config.refund_window_days, Utc::now());
+
+    tracing::info!("Payment status batch checked: {} order(s) by user: {}", items.len(), auth.user_id);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": items
+    })))
+}
+
+/// Stream payment status changes (SSE)
+#[utoipa::path(
+    get,
+    path = "/api/payments/{order_id}/events",
+    tag = "Payment Service",
+    summary = "Stream payment status",
+    description = "Server-Sent Events untuk layar checkout. Event `status` dikirim saat stream dibuka, saat status berubah (webhook, resend, rekonsiliasi), dan tiap interval poll sebagai sinkronisasi countdown expiry VA. Stream ditutup setelah status terminal (success, failed, expired, refunded)",
+    params(
+        ("order_id" = String, Path, description = "Unique order identifier")
+    ),
+    responses(
+        (status = 200, description = "Event stream, data tiap event `status` berupa PaymentStatusSnapshot", content_type = "text/event-stream", body = PaymentStatusSnapshot),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Payment not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn stream_payment_events(
+    auth: AuthUser,
+    State(app_state): State<crate::config::AppState>,
+    Path(order_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    // Subscribe sebelum membaca status agar perubahan di antaranya tidak terlewat
+    let receiver = app_state.payment_events.subscribe();
+
+    let payment = app_state.payment_repository.find_by_order_id(&order_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Payment not found"))?;
+
+    validate_payment_ownership(&auth, &payment, &app_state.db).await?;
+
+    tracing::info!("Payment event stream opened: {} by user: {}", order_id, auth.user_id);
+
+    let stream = PaymentEventStream {
+        poll_interval: Duration::from_secs(app_state.config.payment_events_poll_secs),
+        app_state,
+        receiver,
+        snapshot: PaymentStatusSnapshot::at(&payment.order_id, payment.status, payment.expired_at, Utc::now()),
+        sent_initial: false,
+    };
+
+    Ok(Sse::new(futures_util::stream::unfold(stream, next_payment_event)).keep_alive(KeepAlive::default()))
+}
+
+// State stream SSE satu payment
+struct PaymentEventStream {
+    app_state: crate::config::AppState,
+    receiver: broadcast::Receiver<PaymentEvent>,
+    poll_interval: Duration,
+    // Snapshot terakhir yang dikirim ke client
+    snapshot: PaymentStatusSnapshot,
+    sent_initial: bool,
+}
+
+// Event berikutnya: status awal, lalu perubahan status atau hasil poll; selesai setelah status terminal
+async fn next_payment_event(
+    mut stream: PaymentEventStream,
+) -> Option<(Result<Event, Infallible>, PaymentEventStream)> {
+    if !stream.sent_initial {
+        stream.sent_initial = true;
+        return Some((Ok(status_event(&stream.snapshot)), stream));
+    }
+
+    if stream.snapshot.is_terminal {
+        return None;
+    }
+
+    // Deadline tetap agar event payment lain tidak menunda poll
+    let deadline = tokio::time::Instant::now() + stream.snapshot.next_check_in(stream.poll_interval);
+
+    let snapshot = loop {
+        let received = tokio::select! {
+            received = stream.receiver.recv() => Some(received),
+            _ = tokio::time::sleep_until(deadline) => None,
+        };
+
+        match received {
+            Some(Ok(event)) if event.order_id != stream.snapshot.order_id => continue,
+            Some(Err(RecvError::Closed)) => return None,
+            // Event payment ini, waktu poll, atau event terlewat (lagged): status (dan keputusan
+            // menutup stream) selalu dibaca ulang dari database
+            Some(Ok(_)) | Some(Err(RecvError::Lagged(_))) | None => break reload_payment_snapshot(&stream).await?,
+        }
+    };
+
+    stream.snapshot = snapshot;
+    Some((Ok(status_event(&stream.snapshot)), stream))
+}
+
+// Status terbaru dari database, None menutup stream (client EventSource akan reconnect)
+async fn reload_payment_snapshot(stream: &PaymentEventStream) -> Option<PaymentStatusSnapshot> {
+    match stream.app_state.payment_repository.find_by_order_id(&stream.snapshot.order_id).await {
+        Ok(payment) => payment.map(|payment| {
+            PaymentStatusSnapshot::at(&payment.order_id, payment.status, payment.expired_at, Utc::now())
+        }),
+        Err(e) => {
+            tracing::warn!("⚠️ Failed to reload payment {} for event stream: {}", stream.snapshot.order_id, e);
+            None
+        }
+    }
+}
+
+fn status_event(snapshot: &PaymentStatusSnapshot) -> Event {
+    Event::default()
+        .event("status")
+        .json_data(snapshot)
+        .unwrap_or_default()
+}
+
 /// Cancel pending payment
 #[utoipa::path(
     post,
@@ -538,9 +1052,17 @@ pub async fn cancel_payment(
         payment.id,
         PaymentStatus::Failed,
         None,
+        Some(auth.user_id),
     ).await?;
+    app_state.payment_events.publish(&payment.order_id, PaymentStatus::Failed);
 
-    tracing::info!("Payment cancelled: {}", order_id);
+    tracing::info!(
+        event = "payment_cancelled",
+        order_id = %order_id,
+        payment_id = payment.id,
+        user_id = auth.user_id,
+        "Payment cancelled"
+    );
 
     Ok(Json(json!({
         "success": true,
@@ -555,17 +1077,22 @@ pub async fn cancel_payment(
     path = "/health",
     tag = "Payment Service",
     summary = "Health check",
-    description = "Check if payment service is running",
+    description = "Check if payment service is running, with database probe latency",
     responses(
-        (status = 200, description = "Service is healthy", body = serde_json::Value)
+        (status = 200, description = "Service health, status adalah status terburuk dari semua dependency", body = serde_json::Value)
     )
 )]
-pub async fn health_check() -> Result<Json<Value>, AppError> {
+pub async fn health_check(
+    State(app_state): State<crate::config::AppState>,
+) -> Result<Json<Value>, AppError> {
+    let health = app_state.health_check().await;
+
     Ok(Json(json!({
-        "status": "healthy",
+        "status": health.overall,
         "service": "payment-service",
         "timestamp": Utc::now(),
         "version": "1.0.0",
+        "database": health.database,
     })))
 }
 
@@ -614,27 +1141,89 @@ pub async fn get_service_info(
 
 // Helper Functions untuk Payment Handlers
 
-// Validasi payment request
+// Validasi payment request, semua field yang salah dikembalikan sekaligus
 fn validate_payment_request(request: &CreatePaymentRequest) -> crate::error::AppResult<()> {
+    let mut errors = Vec::new();
+
     if request.gross_amount <= 0 {
-        return Err(AppError::validation("Gross amount must be greater than 0"));
+        errors.push(FieldError::new("gross_amount", "Gross amount must be greater than 0"));
     }
 
     // Validasi payment type
     match request.payment_for_type {
         PaymentType::Rental => {
             if request.rental_booking_id.is_none() {
-                return Err(AppError::validation("Rental booking ID is required for rental payments"));
+                errors.push(FieldError::new("rental_booking_id", "Rental booking ID is required for rental payments"));
             }
         },
         PaymentType::Sale => {
             if request.sale_order_id.is_none() {
-                return Err(AppError::validation("Sale order ID is required for sale payments"));
+                errors.push(FieldError::new("sale_order_id", "Sale order ID is required for sale payments"));
+            }
+        }
+        PaymentType::RentalDamage => {
+            if request.rental_booking_id.is_none() {
+                errors.push(FieldError::new("rental_booking_id", "Rental booking ID is required for damage charge payments"));
+            }
+        }
+        PaymentType::RentalDeposit => {
+            if request.rental_booking_id.is_none() {
+                errors.push(FieldError::new("rental_booking_id", "Rental booking ID is required for deposit payments"));
             }
         }
     }
 
-    Ok(())
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::fields(errors))
+    }
+}
+
+// Charge payment yang sudah direservasi ke Midtrans lalu simpan data charge-nya.
+// Charge yang ditolak pasti menghapus reservasi agar booking/order bisa dibayar ulang; kegagalan
+// yang tidak pasti mempertahankan reservasi (order_id tetap tercatat) untuk dicek scheduler
+async fn charge_reserved_payment(
+    repository: &PaymentRepository,
+    midtrans_service: &MidtransService,
+    request: &CreatePaymentRequest,
+    payment: Payment,
+) -> crate::error::AppResult<(MidtransChargeResponse, Payment)> {
+    let midtrans_response = match midtrans_service.charge_payment(request, payment.order_id.clone()).await {
+        Ok(response) => response,
+        Err(ChargeError::Rejected(e)) => {
+            tracing::error!("Midtrans charge rejected: {} - {}", payment.order_id, e);
+            if let Err(discard_err) = repository.discard_reserved_payment(payment.id).await {
+                tracing::error!("Gagal menghapus reservasi payment {}: {}", payment.order_id, discard_err);
+            }
+            return Err(e);
+        }
+        Err(ChargeError::Ambiguous(e)) => {
+            tracing::warn!(
+                event = "payment_charge_ambiguous",
+                order_id = %payment.order_id,
+                payment_id = payment.id,
+                "⚠️ Midtrans charge result unknown, reservation kept for reconciliation: {}",
+                e
+            );
+            return Err(e);
+        }
+    };
+
+    let payment = repository
+        .update_midtrans_response(payment.id, &midtrans_response)
+        .await?;
+
+    Ok((midtrans_response, payment))
+}
+
+// Cek apakah error insert disebabkan order_id yang sudah ada (unique violation)
+fn is_order_id_conflict(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::DatabaseError(sqlx::Error::Database(db_err))
+            if db_err.is_unique_violation() && db_err.constraint() == Some("payments_order_id_key")
+    )
 }
 
 // Extract webhook signature dari headers
@@ -649,17 +1238,23 @@ fn extract_signature(headers: &HeaderMap) -> crate::error::AppResult<String> {
 // Log payment creation untuk audit
 fn log_payment_created(order_id: &str, request: &CreatePaymentRequest) {
     tracing::info!(
-        "Payment created: order_id={}, type={}, amount={}, booking_id={:?}, sale_id={:?}",
+        event = "payment_created",
         order_id,
-        request.payment_for_type,
-        request.gross_amount,
-        request.rental_booking_id,
-        request.sale_order_id
+        payment_type = %request.payment_for_type,
+        amount = request.gross_amount,
+        booking_id = ?request.rental_booking_id,
+        sale_order_id = ?request.sale_order_id,
+        "Payment created"
     );
 }
 
-// Format payment response untuk API
-fn format_payment_response(payment: &Payment) -> Value {
+// Format payment response untuk API, receipt diganti signed URL
+fn format_payment_response(payment: &Payment, config: &crate::config::AppConfig) -> Value {
+    let refund_window_days = config.refund_window_days;
+    let receipt_pdf_path = payment.receipt_pdf_path
+        .as_deref()
+        .map(|path| private_file::signed_receipt_path(config, payment.id, path));
+
     json!({
         "id": payment.id,
         "order_id": payment.order_id,
@@ -675,11 +1270,12 @@ fn format_payment_response(payment: &Payment) -> Value {
         "paid_at": payment.paid_at,
         "expired_at": payment.expired_at,
         "refunded_at": payment.refunded_at,
-        "receipt_pdf_path": payment.receipt_pdf_path,
+        "receipt_pdf_path": receipt_pdf_path,
         "created_at": payment.created_at,
         "updated_at": payment.updated_at,
         "is_expired": payment.is_expired(),
-        "can_be_refunded": payment.can_be_refunded()
+        "can_be_refunded": payment.can_be_refunded(refund_window_days),
+        "refund_deadline": payment.refund_deadline(refund_window_days)
     })
 }
 
@@ -699,29 +1295,28 @@ fn format_payment_summary(payment: &Payment) -> Value {
 
 // Check refund eligibility
 fn check_refund_eligibility(payment: &Payment, request: &RefundRequest) -> crate::error::AppResult<()> {
+    let mut errors = Vec::new();
+
     // Validasi amount
     if request.refund_amount <= 0 {
-        return Err(AppError::validation("Refund amount must be greater than 0"));
-    }
-
-    if request.refund_amount > payment.gross_amount {
-        return Err(AppError::validation("Refund amount cannot exceed gross amount"));
+        errors.push(FieldError::new("refund_amount", "Refund amount must be greater than 0"));
+    } else if request.refund_amount > payment.gross_amount {
+        errors.push(FieldError::new("refund_amount", "Refund amount cannot exceed gross amount"));
     }
 
     // Validasi reason
     if request.reason.trim().is_empty() {
-        return Err(AppError::validation("Refund reason is required"));
+        errors.push(FieldError::new("reason", "Refund reason is required"));
     }
 
-    Ok(())
-}
-
-// Generate unique refund ID
-fn generate_refund_id(order_id: &str) -> String {
-    format!("REF-{}-{}", order_id, chrono::Utc::now().timestamp())
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::fields(errors))
+    }
 }
 
-// Security: Validate user can create payment for booking/order
+// Security: Validate user can create payment for booking/order, dan gross_amount sesuai nominal di database
 async fn validate_booking_order_ownership(
     auth: &AuthUser,
     request: &CreatePaymentRequest,
@@ -729,11 +1324,11 @@ async fn validate_booking_order_ownership(
 ) -> Result<(), AppError> {
     let user_id = auth.user_id;
 
-    match request.payment_for_type {
+    let expected_amount = match request.payment_for_type {
         PaymentType::Rental => {
             if let Some(booking_id) = request.rental_booking_id {
                 let result = sqlx::query!(
-                    "SELECT customer_id FROM rental_bookings WHERE id = $1",
+                    r#"SELECT customer_id, total_price::BIGINT as "total_price!" FROM rental_bookings WHERE id = $1"#,
                     booking_id
                 )
                 .fetch_optional(pool)
@@ -744,6 +1339,9 @@ async fn validate_booking_order_ownership(
                 if result.customer_id != user_id {
                     return Err(AppError::forbidden("Access denied: Only customers can create rental payments"));
                 }
+
+                // Biaya sewa saja, deposit dibayar terpisah (payment_for_type = rental_deposit)
+                result.total_price
             } else {
                 return Err(AppError::validation("Rental booking ID is required for rental payments"));
             }
@@ -751,7 +1349,7 @@ async fn validate_booking_order_ownership(
         PaymentType::Sale => {
             if let Some(sale_order_id) = request.sale_order_id {
                 let result = sqlx::query!(
-                    "SELECT buyer_id FROM sale_orders WHERE id = $1",
+                    r#"SELECT buyer_id, final_price::BIGINT as "final_price!" FROM sale_orders WHERE id = $1"#,
                     sale_order_id
                 )
                 .fetch_optional(pool)
@@ -762,13 +1360,73 @@ async fn validate_booking_order_ownership(
                 if result.buyer_id != user_id {
                     return Err(AppError::forbidden("Access denied: Only customers can create sale payments"));
                 }
+
+                // Harga yang disepakati (asking price, offer, atau counter offer yang diterima)
+                result.final_price
             } else {
                 return Err(AppError::validation("Sale order ID is required for sale payments"));
             }
         }
-    }
+        PaymentType::RentalDamage => {
+            if let Some(booking_id) = request.rental_booking_id {
+                let result = sqlx::query!(
+                    "SELECT customer_id FROM rental_bookings WHERE id = $1",
+                    booking_id
+                )
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| AppError::not_found("Rental booking not found"))?;
 
-    Ok(())
+                if result.customer_id != user_id {
+                    return Err(AppError::forbidden("Access denied: Only customers can pay damage charges"));
+                }
+
+                // Nominal harus sama dengan sisa kerusakan di laporan pengembalian (setelah potong deposit)
+                sqlx::query_scalar!(
+                    r#"SELECT (damage_total - deposit_deducted)::BIGINT as "damage_total!" FROM rental_return_reports
+                       WHERE rental_booking_id = $1 AND charge_status = 'pending'"#,
+                    booking_id
+                )
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| AppError::not_found("No pending damage charge for this rental"))?
+            } else {
+                return Err(AppError::validation("Rental booking ID is required for damage charge payments"));
+            }
+        }
+        PaymentType::RentalDeposit => {
+            if let Some(booking_id) = request.rental_booking_id {
+                let result = sqlx::query!(
+                    r#"SELECT customer_id, deposit_amount::BIGINT as "deposit_amount!", deposit_status
+                       FROM rental_bookings WHERE id = $1"#,
+                    booking_id
+                )
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| AppError::not_found("Rental booking not found"))?;
+
+                if result.customer_id != user_id {
+                    return Err(AppError::forbidden("Access denied: Only customers can pay rental deposits"));
+                }
+
+                if result.deposit_status != DepositStatus::Pending.as_str() {
+                    return Err(AppError::validation("This rental has no pending deposit"));
+                }
+
+                // Nominal harus sama dengan deposit di rental booking
+                result.deposit_amount
+            } else {
+                return Err(AppError::validation("Rental booking ID is required for deposit payments"));
+            }
+        }
+    };
+
+    check_gross_amount(&request.payment_for_type, expected_amount, request.gross_amount).inspect_err(|_| {
+        tracing::warn!(
+            "Gross amount mismatch for {} payment by user {}: requested {}, expected {}",
+            request.payment_for_type, user_id, request.gross_amount, expected_amount
+        );
+    })
 }
 
 // Security: Validate user access to payment
@@ -780,7 +1438,7 @@ async fn validate_payment_ownership(
     let user_id = auth.user_id;
 
     match payment.payment_for_type {
-        PaymentType::Rental => {
+        PaymentType::Rental | PaymentType::RentalDamage | PaymentType::RentalDeposit => {
             if let Some(booking_id) = payment.rental_booking_id {
                 let booking = sqlx::query!(
                     "SELECT customer_id, seller_id FROM rental_bookings WHERE id = $1",
@@ -915,7 +1573,7 @@ pub async fn get_payment_methods() -> impl axum::response::IntoResponse {
     path = "/api/webhooks/resend/{payment_id}",
     tag = "Payment Service",
     summary = "Resend webhook",
-    description = "Manually trigger webhook resend for missed payment notifications",
+    description = "Manually trigger webhook resend for missed payment notifications. Dibatasi per payment dan per user; payment yang baru saja dicek tidak dicek ulang ke Midtrans",
     params(
         ("payment_id" = i32, Path, description = "Payment database ID")
     ),
@@ -924,6 +1582,7 @@ pub async fn get_payment_methods() -> impl axum::response::IntoResponse {
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "Payment not found"),
+        (status = 429, description = "Terlalu sering resend, lihat header Retry-After"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -960,6 +1619,20 @@ pub async fn resend_webhook(
         })));
     }
 
+    // Throttle per user dan per payment sebelum menyentuh Midtrans
+    let limits = ResendLimits {
+        payment_cooldown_secs: app_state.config.resend_payment_cooldown_secs,
+        user_limit: app_state.config.resend_user_limit,
+        user_window_secs: app_state.config.resend_user_window_secs,
+    };
+    if let Err(retry_after) = check_resend_allowed(&app_state.rate_limiter, auth.user_id, payment_id, &limits).await {
+        tracing::warn!("⏳ Webhook resend throttled for payment {} by user {}", payment_id, auth.user_id);
+        return Err(AppError::too_many_requests(
+            format!("Terlalu sering meminta resend webhook. Coba lagi dalam {} detik.", retry_after),
+            retry_after,
+        ));
+    }
+
     // Log manual webhook request untuk security auditing
     sqlx::query!(
         "INSERT INTO audit_logs (user_id, action, entity_type, entity_id, old_values, new_values, request_id, service_name, endpoint, http_method)
@@ -978,6 +1651,17 @@ pub async fn resend_webhook(
     .execute(&app_state.db)
     .await?;
 
+    // Status baru saja dicek (resend lain atau scheduler rekonsiliasi), pakai status di database
+    if !claim_status_check(&app_state.rate_limiter, payment_id, app_state.config.midtrans_status_recheck_secs).await {
+        return Ok(Json(json!({
+            "success": true,
+            "message": "Payment status baru saja dicek",
+            "payment_id": payment_id,
+            "status": payment.status.to_string(),
+            "timestamp": Utc::now().to_rfc3339()
+        })));
+    }
+
     // Trigger Midtrans status check dengan proper constructor
     let midtrans_service = MidtransService::new(
         app_state.config.midtrans_server_key.clone(),
@@ -1005,10 +1689,19 @@ pub async fn resend_webhook(
 
             // Update status di database jika berubah
             if new_status != payment.status {
-                app_state.payment_repository.update_status(payment_id, new_status, None).await?;
-
-                tracing::info!("✅ Payment status updated via webhook resend: {} -> {}",
-                    payment.status.to_string(), new_status.to_string());
+                app_state.payment_repository.update_status(payment_id, new_status, None, Some(auth.user_id)).await?;
+                apply_payment_success_effects(&app_state.payment_repository, &payment, new_status).await?;
+                app_state.payment_events.publish(&payment.order_id, new_status);
+
+                tracing::info!(
+                    event = "payment_status_changed",
+                    order_id = %payment.order_id,
+                    payment_id,
+                    previous_status = %payment.status,
+                    status = %new_status,
+                    source = "webhook_resend",
+                    "✅ Payment status updated via webhook resend"
+                );
             }
 
             Ok(Json(json!({
@@ -1030,4 +1723,686 @@ pub async fn resend_webhook(
             })))
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::payment::MidtransWebhookPayload;
+    use crate::utils::payment_status_batch::BatchLookupResult;
+    use std::sync::{Arc, Mutex};
+
+    fn webhook(order_id: &str) -> MidtransWebhookPayload {
+        MidtransWebhookPayload {
+            transaction_status: "settlement".to_string(),
+            transaction_id: "trx-deposit-1".to_string(),
+            status_code: "200".to_string(),
+            order_id: order_id.to_string(),
+            gross_amount: "1000000.00".to_string(),
+            payment_type: "bank_transfer".to_string(),
+            transaction_time: "2026-10-16 10:00:00".to_string(),
+            fraud_status: None,
+            va_numbers: None,
+        }
+    }
+
+    async fn deposit_status(pool: &PgPool, booking_id: i32) -> String {
+        sqlx::query_scalar("SELECT deposit_status FROM rental_bookings WHERE id = $1")
+            .bind(booking_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // Alur deposit lengkap di database: pending -> dibayar (webhook) -> held -> refund dipotong kerusakan
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_deposit_hold_and_refund_path(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO rental_bookings (
+                id, vehicle_id, customer_id, seller_id, order_id, pickup_date, return_date,
+                customer_name, customer_phone, customer_email, total_days, price_per_day, total_price,
+                status, deposit_amount, deposit_status
+            ) VALUES (
+                1, 1, 1, 2, 'RNT-TEST-1', NOW(), NOW() + INTERVAL '2 days',
+                'Customer Test', '081200000001', 'customer@test.local', 2, 350000, 700000,
+                'selesai', (SELECT deposit_amount FROM vehicles WHERE id = 1), 'pending'
+            )"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO payments (rental_booking_id, order_id, gross_amount, status, payment_for_type)
+             VALUES (1, 'DEP-TEST-1', 1000000, 'pending', 'rental_deposit')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let repository = PaymentRepository::new(pool.clone());
+        let payment = repository.find_by_order_id("DEP-TEST-1").await.unwrap().unwrap();
+        assert!(matches!(payment.payment_for_type, PaymentType::RentalDeposit));
+
+        // Webhook settlement: status payment + efek ke booking seperti midtrans_webhook
+        repository
+            .update_status_with_transaction_log(payment.id, &PaymentStatus::Success, Some("trx-deposit-1"), &webhook("DEP-TEST-1"))
+            .await
+            .unwrap();
+        apply_payment_success_effects(&repository, &payment, PaymentStatus::Success).await.unwrap();
+        assert_eq!(deposit_status(&pool, 1).await, "held");
+
+        // Webhook duplikat tidak mengubah deposit yang sudah held
+        apply_payment_success_effects(&repository, &payment, PaymentStatus::Success).await.unwrap();
+        assert_eq!(deposit_status(&pool, 1).await, "held");
+        assert_eq!(repository.find_paid_deposit_order_id(1).await.unwrap().as_deref(), Some("DEP-TEST-1"));
+
+        sqlx::query(
+            "INSERT INTO rental_return_reports (
+                rental_booking_id, seller_id, customer_id, condition_notes, odometer_km, fuel_level,
+                damage_total, charge_status
+            ) VALUES (1, 2, 1, 'Baret pintu kiri', 15200, 80, 300000, 'pending')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let context = repository.get_deposit_context(1).await.unwrap();
+        assert_eq!(context.deposit_status, DepositStatus::Held);
+        assert_eq!((context.deposit_amount, context.outstanding_damage), (1_000_000, 300_000));
+        assert!(context.returned);
+
+        let settlement = deposit::settle_deposit(context.deposit_amount, context.outstanding_damage);
+        repository
+            .settle_deposit_refund(payment.id, 1, "ref-deposit-1", &settlement, "Pengembalian selesai", 1)
+            .await
+            .unwrap();
+
+        let (status, refunded, charge_status): (String, i64, String) = sqlx::query_as(
+            "SELECT rb.deposit_status, rb.deposit_refunded_amount::BIGINT, rr.charge_status
+             FROM rental_bookings rb JOIN rental_return_reports rr ON rr.rental_booking_id = rb.id
+             WHERE rb.id = 1"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((status.as_str(), refunded, charge_status.as_str()), ("partially_refunded", 700_000, "paid"));
+
+        // Deposit hanya bisa di-settle sekali
+        assert!(repository
+            .settle_deposit_refund(payment.id, 1, "ref-deposit-2", &settlement, "Duplikat", 1)
+            .await
+            .is_err());
+    }
+
+    // Payment sale lunas hanya dicatat sebagai event, sale_orders diubah booking-service
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_sale_payment_records_one_event(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO sale_orders (id, vehicle_id, buyer_id, seller_id, order_id, tracking_reference,
+                                      asking_price, final_price, buyer_name, buyer_phone, buyer_email, status)
+             VALUES (1, 2, 1, 2, 'SALE-TEST-1', 'TRK-TEST-1', 180000000, 180000000,
+                     'Customer Test', '081200000001', 'customer@test.local', 'pending_payment')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO payments (sale_order_id, order_id, gross_amount, status, payment_for_type)
+             VALUES (1, 'SALE-PAY-1', 180000000, 'pending', 'sale')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let repository = PaymentRepository::new(pool.clone());
+        let payment = repository.find_by_order_id("SALE-PAY-1").await.unwrap().unwrap();
+
+        // Status selain success tidak mencatat event
+        apply_payment_success_effects(&repository, &payment, PaymentStatus::Pending).await.unwrap();
+
+        // Webhook lalu rekonsiliasi untuk payment yang sama: satu event
+        apply_payment_success_effects(&repository, &payment, PaymentStatus::Success).await.unwrap();
+        apply_payment_success_effects(&repository, &payment, PaymentStatus::Success).await.unwrap();
+
+        let events: Vec<(i32, String)> = sqlx::query_as(
+            "SELECT sale_order_id, payment_order_id FROM sale_payment_events WHERE processed_at IS NULL"
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(events, vec![(1, "SALE-PAY-1".to_string())]);
+
+        let status: String = sqlx::query_scalar("SELECT status FROM sale_orders WHERE id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "pending_payment");
+    }
+
+    // Mock refund Midtrans yang mencatat (refund_key, amount) setiap request
+    async fn spawn_refund_mock(status_code: &'static str) -> (String, Arc<Mutex<Vec<(String, i64)>>>) {
+        use axum::{routing::post, Router};
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let router = Router::new().route(
+            "/v2/{transaction_id}/refund",
+            post(move |Json(body): Json<Value>| {
+                let recorded = recorded.clone();
+                async move {
+                    let key = body["refund_key"].as_str().unwrap_or_default().to_string();
+                    recorded.lock().unwrap().push((key.clone(), body["amount"].as_i64().unwrap_or_default()));
+                    // Jeda agar request bersamaan benar-benar tumpang tindih
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Json(json!({ "status_code": status_code, "status_message": "mock", "refund_key": key }))
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{}/v2", addr), calls)
+    }
+
+    async fn paid_rental_payment(pool: &PgPool) -> Payment {
+        sqlx::query(
+            "INSERT INTO rental_bookings (
+                id, vehicle_id, customer_id, seller_id, order_id, pickup_date, return_date,
+                customer_name, customer_phone, customer_email, total_days, price_per_day, total_price, status
+            ) VALUES (
+                1, 1, 1, 2, 'RNT-TEST-1', NOW() + INTERVAL '3 days', NOW() + INTERVAL '5 days',
+                'Customer Test', '081200000001', 'customer@test.local', 2, 350000, 700000, 'paid'
+            )"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO payments (rental_booking_id, order_id, transaction_id, payment_type, gross_amount, status, payment_for_type, paid_at)
+             VALUES (1, 'RNT-PAY-1', 'trx-rental-1', 'credit_card', 700000, 'success', 'rental', NOW())"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        PaymentRepository::new(pool.clone()).find_by_order_id("RNT-PAY-1").await.unwrap().unwrap()
+    }
+
+    // Batch status memakai aturan refund yang sama dengan Payment::can_be_refunded
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_batch_status_matches_single_payment(pool: PgPool) {
+        let payment = paid_rental_payment(&pool).await;
+        let repository = PaymentRepository::new(pool.clone());
+        let order_ids = vec![payment.order_id.clone(), "RNT-MISSING".to_string()];
+
+        let rows = repository.find_statuses_for_user(&order_ids, 1).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].has_access);
+        assert_eq!(rows[0].payment.id, payment.id);
+        assert_eq!(rows[0].payment.gross_amount, 700_000);
+
+        let items = build_batch_items(&order_ids, &rows, 7, Utc::now());
+        assert_eq!(items[0].status, Some(PaymentStatus::Success));
+        assert_eq!(items[0].can_be_refunded, Some(payment.can_be_refunded(7)));
+        assert_eq!(items[0].can_be_refunded, Some(true));
+        assert_eq!(items[1].result, BatchLookupResult::NotFound);
+
+        // Seller lain tidak punya akses ke payment ini
+        let rows = repository.find_statuses_for_user(&order_ids, 3).await.unwrap();
+        assert!(!rows[0].has_access);
+    }
+
+    // Dua request refund bersamaan: Midtrans hanya menerima satu refund (key + nominal sama)
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_concurrent_refunds_send_one_refund_key(pool: PgPool) {
+        let payment = paid_rental_payment(&pool).await;
+        let repository = PaymentRepository::new(pool.clone());
+        let (api_url, calls) = spawn_refund_mock("200").await;
+        let midtrans = MidtransService::new("server-key".to_string(), String::new(), api_url);
+
+        let (first, second) = tokio::join!(
+            refund_to_channel(&repository, &midtrans, &payment, 700_000, "Batal sewa", 1),
+            refund_to_channel(&repository, &midtrans, &payment, 500_000, "Batal sewa (retry)", 1),
+        );
+        assert!(first.is_ok() || second.is_ok());
+
+        let recorded = calls.lock().unwrap().clone();
+        assert!(!recorded.is_empty());
+        assert!(recorded.iter().all(|call| *call == recorded[0]), "{:?}", recorded);
+        assert_eq!(recorded[0].0, Payment::refund_key(payment.id));
+
+        let (status, refund_status, refund_amount): (String, String, i64) = sqlx::query_as(
+            "SELECT status, refund_status, refund_amount::BIGINT FROM payments WHERE id = $1"
+        )
+        .bind(payment.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((status.as_str(), refund_status.as_str(), refund_amount), ("refunded", "completed", recorded[0].1));
+
+        // Payment yang sudah direfund tidak memanggil Midtrans lagi
+        assert!(refund_to_channel(&repository, &midtrans, &payment, 700_000, "Lagi", 1).await.is_err());
+        assert_eq!(calls.lock().unwrap().len(), recorded.len());
+    }
+
+    // Mock charge Midtrans: mencatat apakah order_id sudah tersimpan di database saat charge diterima
+    async fn spawn_charge_mock(pool: PgPool, accept: bool) -> (String, Arc<Mutex<Vec<(String, bool)>>>) {
+        use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let router = Router::new().route(
+            "/v2/charge",
+            post(move |Json(body): Json<Value>| {
+                let (pool, recorded) = (pool.clone(), recorded.clone());
+                async move {
+                    let order_id = body["transaction_details"]["order_id"].as_str().unwrap_or_default().to_string();
+                    let reserved: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM payments WHERE order_id = $1)")
+                        .bind(&order_id)
+                        .fetch_one(&pool)
+                        .await
+                        .unwrap();
+                    recorded.lock().unwrap().push((order_id.clone(), reserved));
+
+                    if !accept {
+                        return (StatusCode::BAD_REQUEST, Json(json!({ "status_message": "rejected" }))).into_response();
+                    }
+                    Json(json!({
+                        "status_code": "201",
+                        "status_message": "Success, Bank Transfer transaction is created",
+                        "transaction_id": "trx-charge-1",
+                        "order_id": order_id,
+                        "gross_amount": "700000.00",
+                        "payment_type": "bank_transfer",
+                        "transaction_status": "pending",
+                        "transaction_time": "2026-10-16 10:00:00",
+                        "va_numbers": [{ "bank": "bca", "va_number": "1234567890" }]
+                    }))
+                    .into_response()
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{}/v2", addr), calls)
+    }
+
+    fn rental_payment_request() -> CreatePaymentRequest {
+        CreatePaymentRequest {
+            payment_for_type: PaymentType::Rental,
+            rental_booking_id: Some(1),
+            sale_order_id: None,
+            gross_amount: 700_000,
+            payment_method: "bca".to_string(),
+            customer_details: crate::domain::payment::CustomerDetails {
+                first_name: "Customer".to_string(),
+                last_name: None,
+                email: "customer@test.local".to_string(),
+                phone: "081200000001".to_string(),
+            },
+            item_details: vec![],
+        }
+    }
+
+    // order_id direservasi sebelum charge; bentrok order_id tidak pernah sampai ke Midtrans
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_order_id_reserved_before_charge(pool: PgPool) {
+        paid_rental_payment(&pool).await;
+        let repository = PaymentRepository::new(pool.clone());
+        let request = rental_payment_request();
+        let expiry = Payment::generate_expiry_time(PaymentType::Rental);
+
+        // order_id yang sudah dipakai ditolak di database, bukan setelah charge
+        let conflict = repository.reserve_payment(&request, "RNT-PAY-1", expiry).await.unwrap_err();
+        assert!(is_order_id_conflict(&conflict));
+
+        let (api_url, calls) = spawn_charge_mock(pool.clone(), true).await;
+        let midtrans = MidtransService::new("server-key".to_string(), String::new(), api_url);
+        let reserved = repository.reserve_payment(&request, "RNT-PAY-2", expiry).await.unwrap();
+        assert!(reserved.transaction_id.is_none());
+
+        let (_, payment) = charge_reserved_payment(&repository, &midtrans, &request, reserved).await.unwrap();
+        assert_eq!(calls.lock().unwrap().clone(), vec![("RNT-PAY-2".to_string(), true)]);
+        assert_eq!(payment.transaction_id.as_deref(), Some("trx-charge-1"));
+        assert_eq!(payment.va_number.as_deref(), Some("1234567890"));
+    }
+
+    // Payment baru: 201 + Location yang bisa di-GET; request ulang: 200 dengan payment yang sudah ada
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_create_payment_status_codes(pool: PgPool) {
+        use axum::{http::{header, StatusCode}, response::IntoResponse};
+
+        sqlx::query(
+            "INSERT INTO rental_bookings (
+                id, vehicle_id, customer_id, seller_id, order_id, pickup_date, return_date,
+                customer_name, customer_phone, customer_email, total_days, price_per_day, total_price, status
+            ) VALUES (
+                1, 1, 1, 2, 'RNT-TEST-1', NOW() + INTERVAL '3 days', NOW() + INTERVAL '5 days',
+                'Customer Test', '081200000001', 'customer@test.local', 2, 350000, 700000, 'pending_payment'
+            )"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let (api_url, _) = spawn_charge_mock(pool.clone(), true).await;
+        let state = crate::config::AppState::for_test(pool, api_url);
+        let customer = || AuthUser { user_id: 1, email: "customer@test.local".to_string(), role: "customer".to_string() };
+
+        let created = create_payment(customer(), State(state.clone()), Json(rental_payment_request()))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let location = created.headers()[header::LOCATION].to_str().unwrap().to_string();
+        let order_id = location.strip_prefix("/api/payments/").unwrap().to_string();
+
+        let Json(fetched) = get_payment_by_order_id(customer(), State(state.clone()), Path(order_id.clone()))
+            .await
+            .unwrap();
+        assert_eq!(fetched["data"]["order_id"], order_id.as_str());
+
+        let existing = create_payment(customer(), State(state), Json(rental_payment_request()))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(existing.status(), StatusCode::OK);
+        assert!(existing.headers().get(header::LOCATION).is_none());
+        let body = axum::body::to_bytes(existing.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"]["order_id"], order_id.as_str());
+        assert_eq!(body["data"]["transaction_id"], "trx-charge-1");
+    }
+
+    // Charge ditolak Midtrans: reservasi dihapus agar booking bisa dibayar ulang
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_failed_charge_discards_reservation(pool: PgPool) {
+        paid_rental_payment(&pool).await;
+        let repository = PaymentRepository::new(pool.clone());
+        let request = rental_payment_request();
+        let (api_url, calls) = spawn_charge_mock(pool.clone(), false).await;
+        let midtrans = MidtransService::new("server-key".to_string(), String::new(), api_url);
+
+        let reserved = repository
+            .reserve_payment(&request, "RNT-PAY-3", Payment::generate_expiry_time(PaymentType::Rental))
+            .await
+            .unwrap();
+        assert!(charge_reserved_payment(&repository, &midtrans, &request, reserved).await.is_err());
+        assert_eq!(calls.lock().unwrap().clone(), vec![("RNT-PAY-3".to_string(), true)]);
+        assert!(repository.find_by_order_id("RNT-PAY-3").await.unwrap().is_none());
+    }
+
+    // VA lewat expired_at tidak menutup stream; stream selesai setelah database mencatat settlement
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_event_stream_closes_only_on_stored_terminal_status(pool: PgPool) {
+        paid_rental_payment(&pool).await;
+        sqlx::query(
+            "INSERT INTO payments (rental_booking_id, order_id, transaction_id, payment_type, gross_amount, status, payment_for_type, expired_at)
+             VALUES (1, 'RNT-LATE-1', 'trx-late-1', 'bank_transfer', 150000, 'pending', 'rental_deposit', NOW() - INTERVAL '1 minute')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let state = crate::config::AppState::for_test(pool.clone(), String::new());
+        let payment = state.payment_repository.find_by_order_id("RNT-LATE-1").await.unwrap().unwrap();
+        let stream = PaymentEventStream {
+            poll_interval: Duration::from_millis(50),
+            receiver: state.payment_events.subscribe(),
+            snapshot: PaymentStatusSnapshot::at(&payment.order_id, payment.status, payment.expired_at, Utc::now()),
+            app_state: state.clone(),
+            sent_initial: false,
+        };
+
+        let (_, stream) = next_payment_event(stream).await.unwrap();
+        assert_eq!(stream.snapshot.status, PaymentStatus::Pending);
+        let (_, stream) = next_payment_event(stream).await.unwrap();
+        assert_eq!(stream.snapshot.status, PaymentStatus::Pending);
+        assert!(!stream.snapshot.is_terminal);
+
+        sqlx::query("UPDATE payments SET status = 'success', paid_at = NOW() WHERE order_id = 'RNT-LATE-1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        state.payment_events.publish("RNT-LATE-1", PaymentStatus::Success);
+
+        let (_, stream) = next_payment_event(stream).await.unwrap();
+        assert_eq!(stream.snapshot.status, PaymentStatus::Success);
+        assert!(next_payment_event(stream).await.is_none());
+    }
+
+    // Receipt hanya bisa diambil lewat signed URL oleh pihak payment, token yang diubah ditolak
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_receipt_served_through_signed_url(pool: PgPool) {
+        use axum::http::StatusCode;
+
+        paid_rental_payment(&pool).await;
+        let state = crate::config::AppState::for_test(pool, String::new());
+        let user = |user_id| AuthUser { user_id, email: format!("user{}@test.local", user_id), role: "customer".to_string() };
+
+        let Json(receipt) = get_payment_receipt(user(1), State(state.clone()), Path("RNT-PAY-1".to_string()))
+            .await
+            .unwrap();
+        let receipt_url = receipt["data"]["receipt_url"].as_str().unwrap().to_string();
+        let token = receipt_url.strip_prefix("/api/payments/files/").unwrap().to_string();
+        assert!(!receipt_url.contains("/receipts/"));
+
+        let Json(payment) = get_payment_by_order_id(user(1), State(state.clone()), Path("RNT-PAY-1".to_string()))
+            .await
+            .unwrap();
+        assert!(payment["data"]["receipt_pdf_path"].as_str().unwrap().starts_with("/api/payments/files/"));
+
+        let file = get_receipt_file(user(1), State(state.clone()), Path(token.clone())).await.unwrap();
+        assert_eq!(file.status(), StatusCode::OK);
+        let seller = get_receipt_file(user(2), State(state.clone()), Path(token.clone())).await.unwrap();
+        assert_eq!(seller.status(), StatusCode::OK);
+
+        let outsider = get_receipt_file(user(3), State(state.clone()), Path(token.clone())).await;
+        assert!(matches!(outsider, Err(AppError::ForbiddenError(_))));
+        let tampered = get_receipt_file(user(1), State(state), Path(format!("x{}", token))).await;
+        assert!(matches!(tampered, Err(AppError::ForbiddenError(_))));
+    }
+
+    // Charge timeout/5xx: hasilnya tidak pasti, reservasi (order_id) dipertahankan untuk rekonsiliasi
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_ambiguous_charge_keeps_reservation(pool: PgPool) {
+        use axum::{http::StatusCode, routing::post, Router};
+        use crate::utils::midtrans_retry::ChargeRetryPolicy;
+
+        paid_rental_payment(&pool).await;
+        let router = Router::new().route("/v2/charge", post(|| async { (StatusCode::BAD_GATEWAY, "upstream error") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let repository = PaymentRepository::new(pool.clone());
+        let request = rental_payment_request();
+        let midtrans = MidtransService::new("server-key".to_string(), String::new(), format!("http://{}/v2", addr))
+            .with_charge_policy(ChargeRetryPolicy::new(2, 0));
+
+        let reserved = repository
+            .reserve_payment(&request, "RNT-PAY-4", Payment::generate_expiry_time(PaymentType::Rental))
+            .await
+            .unwrap();
+        assert!(charge_reserved_payment(&repository, &midtrans, &request, reserved).await.is_err());
+
+        let kept = repository.find_by_order_id("RNT-PAY-4").await.unwrap().unwrap();
+        assert_eq!(kept.status, PaymentStatus::Pending);
+        assert!(kept.transaction_id.is_none());
+    }
+
+    // Midtrans menolak refund (4xx): refund pending dilepas agar bisa dicoba lagi
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_rejected_refund_releases_pending(pool: PgPool) {
+        let payment = paid_rental_payment(&pool).await;
+        let repository = PaymentRepository::new(pool.clone());
+        let (api_url, calls) = spawn_refund_mock("406").await;
+        let midtrans = MidtransService::new("server-key".to_string(), String::new(), api_url);
+
+        assert!(refund_to_channel(&repository, &midtrans, &payment, 700_000, "Batal sewa", 1).await.is_err());
+        assert_eq!(calls.lock().unwrap().len(), 1);
+
+        let (status, refund_status): (String, Option<String>) = sqlx::query_as(
+            "SELECT status, refund_status FROM payments WHERE id = $1"
+        )
+        .bind(payment.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((status.as_str(), refund_status), ("success", None));
+
+        let retry = repository.reserve_refund(payment.id, 700_000, "Batal sewa").await.unwrap();
+        assert!(!retry.resumed);
+    }
+
+    // Hasil refund tidak pasti (5xx): refund tetap pending dengan nominal awal, lalu rekonsiliasi
+    // menemukan refund_key di status Midtrans dan menyelesaikannya tanpa refund kedua
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_ambiguous_refund_stays_pending_until_reconciled(pool: PgPool) {
+        use axum::{routing::{get, post}, Router};
+
+        let payment = paid_rental_payment(&pool).await;
+        let repository = PaymentRepository::new(pool.clone());
+        let refund_key = Payment::refund_key(payment.id);
+
+        let refund_calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = refund_calls.clone();
+        let status_key = refund_key.clone();
+        let router = Router::new()
+            .route(
+                "/v2/{transaction_id}/refund",
+                post(move |Json(body): Json<Value>| {
+                    let recorded = recorded.clone();
+                    async move {
+                        recorded.lock().unwrap().push(body["amount"].as_i64().unwrap_or_default());
+                        Json(json!({ "status_code": "500", "status_message": "Internal error" }))
+                    }
+                }),
+            )
+            .route(
+                "/v2/{transaction_id}/status",
+                get(move || {
+                    let key = status_key.clone();
+                    async move {
+                        Json(json!({
+                            "transaction_status": "refund",
+                            "refunds": [{ "refund_chargeback_id": 55, "refund_key": key, "refund_amount": "700000.00" }]
+                        }))
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let midtrans = MidtransService::new("server-key".to_string(), String::new(), format!("http://{}/v2", addr));
+
+        assert!(refund_to_channel(&repository, &midtrans, &payment, 700_000, "Batal sewa", 1).await.is_err());
+        // Request ulang dengan nominal lain melanjutkan refund pending yang sama
+        assert!(refund_to_channel(&repository, &midtrans, &payment, 500_000, "Lagi", 1).await.is_err());
+        assert_eq!(refund_calls.lock().unwrap().clone(), vec![700_000, 700_000]);
+
+        let refund_status: Option<String> = sqlx::query_scalar("SELECT refund_status FROM payments WHERE id = $1")
+            .bind(payment.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(refund_status.as_deref(), Some("pending"));
+
+        assert!(crate::scheduler::reconcile_refund(&repository, &midtrans, payment.id).await.unwrap());
+        assert_eq!(refund_calls.lock().unwrap().len(), 2);
+
+        let (status, refund_status, reference, amount): (String, String, String, i64) = sqlx::query_as(
+            "SELECT status, refund_status, refund_reference, refund_amount::BIGINT FROM payments WHERE id = $1"
+        )
+        .bind(payment.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((status.as_str(), refund_status.as_str(), reference.as_str(), amount), ("refunded", "completed", "55", 700_000));
+    }
+}
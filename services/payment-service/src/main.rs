@@ -6,11 +6,14 @@ mod routes;
 mod middleware;
 mod utils;
 mod error;
+mod scheduler;
 
-use config::AppState;
+use config::{AppConfig, AppState};
 use routes::create_routes;
-use tokio::net::TcpListener;
-use tower_http::trace::TraceLayer;
+use scheduler::PaymentScheduler;
+use std::net::SocketAddr;
+use tokio::{net::TcpListener, task::JoinHandle};
+use shared::utils::{bind_addr, logging, request_id, startup_gate::StartupGate};
 use tracing::{info};
 use tracing_subscriber::{
     layer::SubscriberExt,
@@ -26,17 +29,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Setup logging dengan environment
     setup_logging();
 
+    let config = AppConfig::from_env()?;
+    info!("🚀 Payment Service starting on {}:{}", config.server_host, config.server_port);
+
+    // Bind listener ke PAYMENT_SERVICE_HOST/PAYMENT_SERVICE_PORT (sudah divalidasi di config)
+    let addr = bind_addr::resolve("PAYMENT_SERVICE_HOST", &config.server_host, config.server_port)?;
+    let listener = TcpListener::bind(addr)
+        .await?;
+
+    // Listener dibind sebelum koneksi database/Redis, request dijawab 503 sampai state siap
+    let gate = StartupGate::new(&["/health"]);
+    let server = start_server(listener, &gate);
+
+    info!("🌐 Server running on http://{}", addr);
+    info!("📚 API Docs: http://{}/docs", addr);
+    info!("🏥 Health Check: http://{}/health", addr);
+
     // Create application state (includes database connection)
-    let app_state = AppState::from_env().await?;
+    let app_state = AppState::new(config).await?;
 
-    info!("🚀 Payment Service starting on {}:{}", app_state.config.server_host, app_state.config.server_port);
     info!("💳 Mode: {} | Midtrans API: {}",
         if app_state.config.midtrans_is_production { "Production" } else { "Sandbox" },
         app_state.config.midtrans_api_url
     );
 
-    // Build dan start server dengan graceful shutdown
-    start_server(app_state).await
+    // Start background scheduler (rekonsiliasi payment pending)
+    PaymentScheduler::new(app_state.clone()).start();
+
+    // Build application dengan middleware stack, mulai layani request
+    let app = request_id::apply(create_routes(app_state.clone()).await);
+    gate.ready(app);
+
+    server.await??;
+
+    info!("✅ Payment Service shutdown successfully");
+    Ok(())
 }
 
 /// Inisialisasi structured logging berdasarkan environment
@@ -48,25 +75,14 @@ fn setup_logging() {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("payment_service=debug,tower_http=debug"))
         )
-        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(logging::fmt_layer())
         .init();
 }
 
 
-/// Start server dengan graceful shutdown dan proper middleware
-async fn start_server(app_state: AppState) -> Result<(), Box<dyn std::error::Error>> {
-    // Build application dengan middleware stack
-    let app = create_routes(app_state.clone())
-        .await
-        .layer(TraceLayer::new_for_http());
-
-    // Bind listener ke configured address
-    let listener = TcpListener::bind(format!("{}:{}", app_state.config.server_host, app_state.config.server_port))
-        .await?;
-
-    info!("🌐 Server running on http://{}:{}", app_state.config.server_host, app_state.config.server_port);
-    info!("📚 API Docs: http://{}:{}/docs", app_state.config.server_host, app_state.config.server_port);
-    info!("🏥 Health Check: http://{}:{}/health", app_state.config.server_host, app_state.config.server_port);
+/// Start server lewat startup gate dengan graceful shutdown
+fn start_server(listener: TcpListener, gate: &StartupGate) -> JoinHandle<std::io::Result<()>> {
+    let router = gate.router();
 
     // Setup graceful shutdown signal handler
     let shutdown_signal = async move {
@@ -77,10 +93,10 @@ async fn start_server(app_state: AppState) -> Result<(), Box<dyn std::error::Err
     };
 
     // Run server dengan graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal)
-        .await?;
-
-    info!("✅ Payment Service shutdown successfully");
-    Ok(())
+    // ConnectInfo dibutuhkan allowlist IP webhook Midtrans, diteruskan gate ke router asli
+    tokio::spawn(async move {
+        axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown_signal)
+            .await
+    })
 }
\ No newline at end of file
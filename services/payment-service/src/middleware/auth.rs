@@ -2,52 +2,28 @@
 
 use axum::{
     extract::{Request, State},
-    http::HeaderMap,
     response::Response,
     middleware::Next,
 };
-use crate::{config::AppState, error::AppError, utils::jwt};
+use shared::auth::{authenticate, AdminState, AuthState};
+use crate::{config::AppState, error::AppError};
 
-// Authentication context untuk user yang sudah terautentikasi
-#[derive(Debug, Clone)]
-pub struct AuthUser {
-    pub user_id: i32,
-    pub email: String,
-    pub role: String,
-}
+// AuthUser diinject jwt_auth_middleware, validasi token ada di shared::auth
+pub use shared::auth::AuthUser;
 
-// Axum extractor implementation untuk AuthUser
-impl<S> axum::extract::FromRequestParts<S> for AuthUser
-where
-    S: Send + Sync,
-{
+impl AuthState for AppState {
     type Rejection = AppError;
-
-    async fn from_request_parts<'life0, 'life1>(
-        parts: &'life0 mut axum::http::request::Parts,
-        _state: &'life1 S,
-    ) -> Result<Self, Self::Rejection> {
-        parts
-            .extensions
-            .get::<AuthUser>()
-            .cloned()
-            .ok_or_else(|| AppError::unauthorized("Authentication required"))
-    }
 }
 
-// Extract Bearer token dari Authorization header
-fn extract_jwt_token(headers: &HeaderMap) -> Result<String, AppError> {
-    let auth_header = headers
-        .get("authorization")
-        .ok_or_else(|| AppError::unauthorized("Authorization header dengan Bearer token diperlukan"))?
-        .to_str()
-        .map_err(|_| AppError::unauthorized("Invalid Authorization header format"))?;
+// Admin platform terautentikasi (investigasi audit log), flag is_admin dicek oleh shared::auth
+pub use shared::auth::AuthAdmin;
 
-    if !auth_header.starts_with("Bearer ") {
-        return Err(AppError::unauthorized("Bearer token format diperlukan"));
-    }
+impl AdminState for AppState {
+    type Rejection = AppError;
 
-    Ok(auth_header[7..].to_string())
+    fn admin_db(&self) -> &sqlx::PgPool {
+        &self.db
+    }
 }
 
 // JWT authentication middleware dengan blacklist validation
@@ -62,20 +38,8 @@ pub async fn jwt_auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    // Extract JWT token dari Authorization header
-    let token = extract_jwt_token(request.headers())?;
-
     // Validasi JWT dengan database trust boundary
-    let claims = jwt::validate_token(&token, &state.db)
-        .await
-        .map_err(|_| AppError::unauthorized("Token tidak valid, expired, atau sudah di-blacklist"))?;
-
-    // Prepare user context untuk handlers
-    let auth_user = AuthUser {
-        user_id: claims.sub,
-        email: claims.email.clone(),
-        role: claims.role.clone(),
-    };
+    let auth_user = authenticate(request.headers(), &state.config.jwt, &state.db).await?;
 
     // Inject ke request extensions agar bisa di-extract oleh handlers
     request.extensions_mut().insert(auth_user.clone());
@@ -90,4 +54,25 @@ pub async fn jwt_auth_middleware(
     );
 
     Ok(next.run(request).await)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::{header, HeaderMap, StatusCode}, response::IntoResponse};
+    use shared::auth::{decode_token, issue_token, JwtConfig, TokenType};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_blacklisted_token_rejected_with_401() {
+        let config = JwtConfig::new("test-secret-key-for-testing-only");
+        let token = issue_token(&config, 123, "test@example.com", "customer", TokenType::Access, 900).unwrap();
+        let jti = decode_token(&token, &config, TokenType::Access).unwrap().jti;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+
+        let err: AppError = authenticate(&headers, &config, &HashSet::from([jti])).await.unwrap_err().into();
+        assert_eq!(err.into_response().status(), StatusCode::UNAUTHORIZED);
+    }
+}
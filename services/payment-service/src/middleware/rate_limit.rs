@@ -2,15 +2,17 @@
 
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    response::Response,
     middleware::Next,
 };
 use redis::{Client, AsyncCommands};
 use std::env;
 use std::sync::Arc;
+use shared::utils::rate_limit::{too_many_requests, sliding_window_hit};
 use thiserror::Error;
 
+use crate::utils::resend_throttle::CooldownStore;
+
 // Rate limit configuration dari environment variables
 #[derive(Debug, Clone)]
 pub struct RateLimitConfig {
@@ -84,44 +86,16 @@ impl RateLimiter {
         // Determine max requests berdasarkan role dan endpoint
         let max_requests = self.get_max_requests(role, endpoint);
         let window_key = format!("payment_rate_limit:{}:{}:{}", identifier, role, endpoint);
-
-        // Gunakan Redis sorted set untuk sliding window
-        let current_time = chrono::Utc::now().timestamp() as u64;
-        let window_start = current_time.saturating_sub(self.config.window_seconds) + 1;
-
-        // Clean old entries
-        let _: () = conn
-            .zrembyscore(&window_key, "-inf", &(window_start - 1))
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        // Get current count
-        let current_count: usize = conn
-            .zcard(&window_key)
+        let hit = sliding_window_hit(&mut conn, &window_key, max_requests, self.config.window_seconds)
             .await
             .map_err(RateLimitError::RedisOperation)?;
 
-        // Add current request
-        let _: () = conn
-            .zadd(&window_key, current_time, current_time)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        // Set expiration untuk cleanup
-        let _: () = conn
-            .expire(&window_key, self.config.window_seconds as i64)
-            .await
-            .map_err(RateLimitError::RedisOperation)?;
-
-        let allowed = current_count < max_requests as usize;
-        let remaining = max_requests.saturating_sub(current_count as u32 + 1);
-
         Ok(RateLimitResult {
-            allowed,
-            current_count: current_count as u32 + 1,
+            allowed: hit.allowed,
+            current_count: hit.current_count,
             max_requests,
-            remaining,
-            reset_time: window_start + self.config.window_seconds,
+            remaining: hit.remaining,
+            reset_time: hit.reset_time,
         })
     }
 
@@ -149,6 +123,54 @@ impl RateLimiter {
     }
 }
 
+// Cooldown & counter berbasis TTL untuk throttle resend webhook
+impl CooldownStore for RateLimiter {
+    async fn acquire(&self, key: &str, ttl_secs: u64) -> Result<Option<u64>, RateLimitError> {
+        let mut conn = self.redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(RateLimitError::RedisConnection)?;
+
+        // SET NX EX: hanya berhasil jika key belum ada
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async(&mut conn)
+            .await
+            .map_err(RateLimitError::RedisOperation)?;
+
+        if acquired.is_some() {
+            return Ok(None);
+        }
+
+        let remaining: i64 = conn.ttl(key).await.map_err(RateLimitError::RedisOperation)?;
+        Ok(Some(remaining.max(1) as u64))
+    }
+
+    async fn hit(&self, key: &str, window_secs: u64) -> Result<(u64, u64), RateLimitError> {
+        let mut conn = self.redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(RateLimitError::RedisConnection)?;
+
+        // Window dimulai dari hit pertama: SET NX EX membuat counter beserta TTL-nya, lalu INCR,
+        // dalam satu MULTI agar tidak pernah ada counter tanpa expiry
+        let (count, remaining): (u64, i64) = redis::pipe()
+            .atomic()
+            .cmd("SET").arg(key).arg(0).arg("NX").arg("EX").arg(window_secs).ignore()
+            .incr(key, 1)
+            .ttl(key)
+            .query_async(&mut conn)
+            .await
+            .map_err(RateLimitError::RedisOperation)?;
+
+        Ok((count, remaining.max(1) as u64))
+    }
+}
+
 // Rate limit check result
 #[derive(Debug, Clone)]
 pub struct RateLimitResult {
@@ -204,17 +226,14 @@ pub async fn rate_limit_middleware(
 
             Ok(response)
         }
-        Ok(_) => {
+        Ok(result) => {
             // Rate limit exceeded
             tracing::warn!("Rate limit exceeded for {} on {}", identifier, endpoint);
-
-            let error_response = axum::Json(serde_json::json!({
+            // Retry-After dihitung dari reset window limiter
+            Ok(too_many_requests(result.reset_time, serde_json::json!({
                 "error": "rate_limit_exceeded",
-                "message": "Terlalu banyak permintaan. Silakan coba lagi dalam beberapa saat.",
-                "retry_after": 60
-            }));
-
-            Ok((StatusCode::TOO_MANY_REQUESTS, error_response).into_response())
+                "message": "Terlalu banyak permintaan. Silakan coba lagi dalam beberapa saat."
+            })))
         }
         Err(e) => {
             // Log error tapi allow request
@@ -0,0 +1,145 @@
+use crate::domain::audit_log::{AuditLogEntry, AuditLogQueryParams};
+use crate::error::AppError;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+const AUDIT_LOG_COLUMNS: &str = "id, user_id, host(ip_address) as ip_address, user_agent,
+    action, entity_type, entity_id, old_values, new_values,
+    request_id, service_name, endpoint, http_method, created_at";
+
+// Repository read-only untuk audit_logs
+#[derive(Clone)]
+pub struct AuditLogRepository {
+    pool: PgPool,
+}
+
+impl AuditLogRepository {
+    // Buat audit log repository baru
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Cari audit log dengan filter, urut terbaru. Filter equality memakai
+    // index (entity_type, entity_id) dan (user_id, created_at)
+    pub async fn search(
+        &self,
+        params: &AuditLogQueryParams,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<AuditLogEntry>, i64), AppError> {
+        let mut count_query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM audit_logs");
+        push_filters(&mut count_query, params);
+        let total: i64 = count_query.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM audit_logs", AUDIT_LOG_COLUMNS));
+        push_filters(&mut query, params);
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        query.push_bind(limit);
+        query.push(" OFFSET ");
+        query.push_bind(offset);
+
+        let logs = query.build_query_as::<AuditLogEntry>().fetch_all(&self.pool).await?;
+
+        Ok((logs, total))
+    }
+}
+
+// Tambah WHERE clause sesuai filter yang diisi
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, params: &AuditLogQueryParams) {
+    query.push(" WHERE TRUE");
+
+    if let Some(user_id) = params.user_id {
+        query.push(" AND user_id = ").push_bind(user_id);
+    }
+
+    if let Some(action) = params.action.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        query.push(" AND action = ").push_bind(action.to_string());
+    }
+
+    if let Some(entity_type) = params.entity_type.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+        query.push(" AND entity_type = ").push_bind(entity_type.to_string());
+    }
+
+    if let Some(entity_id) = params.entity_id {
+        query.push(" AND entity_id = ").push_bind(entity_id);
+    }
+
+    if let Some(date_from) = params.date_from {
+        query.push(" AND created_at >= ").push_bind(date_from);
+    }
+
+    if let Some(date_to) = params.date_to {
+        query.push(" AND created_at < ").push_bind(date_to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn params() -> AuditLogQueryParams {
+        AuditLogQueryParams {
+            user_id: None,
+            action: None,
+            entity_type: None,
+            entity_id: None,
+            date_from: None,
+            date_to: None,
+        }
+    }
+
+    // Filter user/action/entity/tanggal digabung AND, hasil urut terbaru dengan total sebelum pagination
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_search_filters_and_paginates(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO audit_logs (user_id, ip_address, action, entity_type, entity_id, new_values, created_at) VALUES
+                (1, '10.0.0.1', 'PAYMENT_REFUND', 'payment', 10, '{\"status\":\"refunded\"}', NOW() - INTERVAL '3 days'),
+                (1, NULL, 'MANUAL_WEBHOOK_RESEND', 'payment', 10, NULL, NOW() - INTERVAL '2 days'),
+                (1, NULL, 'MANUAL_WEBHOOK_RESEND', 'payment', 11, NULL, NOW() - INTERVAL '1 day'),
+                (2, NULL, 'MANUAL_WEBHOOK_RESEND', 'payment', 12, NULL, NOW())"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let repository = AuditLogRepository::new(pool);
+
+        let (logs, total) = repository
+            .search(&AuditLogQueryParams { user_id: Some(1), ..params() }, 2, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(logs.iter().map(|l| l.entity_id).collect::<Vec<_>>(), vec![Some(11), Some(10)]);
+
+        let (logs, total) = repository
+            .search(&AuditLogQueryParams { user_id: Some(1), ..params() }, 2, 2)
+            .await
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(logs[0].action, "PAYMENT_REFUND");
+        assert_eq!(logs[0].ip_address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(logs[0].new_values, Some(serde_json::json!({ "status": "refunded" })));
+
+        let filtered = AuditLogQueryParams {
+            action: Some(" MANUAL_WEBHOOK_RESEND ".to_string()),
+            entity_type: Some("payment".to_string()),
+            date_from: Some(Utc::now() - Duration::hours(36)),
+            ..params()
+        };
+        let (logs, total) = repository.search(&filtered, 20, 0).await.unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(logs.iter().map(|l| l.user_id).collect::<Vec<_>>(), vec![Some(2), Some(1)]);
+
+        let (_, total) = repository
+            .search(&AuditLogQueryParams { entity_id: Some(10), date_to: Some(Utc::now() - Duration::days(2) - Duration::hours(12)), ..params() }, 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+    }
+}
@@ -1 +1,2 @@
-pub mod payment_repo;
\ No newline at end of file
+pub mod payment_repo;
+pub mod audit_log_repo;
\ No newline at end of file
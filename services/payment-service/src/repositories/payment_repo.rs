@@ -1,12 +1,38 @@
+use crate::domain::deposit::{DepositSettlement, DepositStatus};
 use crate::domain::payment::{
     Payment, PaymentStatus, PaymentType, CreatePaymentRequest,
     MidtransWebhookPayload, MidtransChargeResponse
 };
 use crate::error::AppError;
-use sqlx::PgPool;
+use crate::utils::midtrans_refund::RefundOutcome;
+use crate::utils::payment_status_batch::PaymentStatusRow;
+use sqlx::{PgConnection, PgPool};
+use serde_json::json;
 use chrono::Utc;
 use bigdecimal::ToPrimitive;
 
+// Snapshot deposit rental untuk proses refund deposit
+#[derive(Debug, Clone)]
+pub struct DepositContext {
+    pub deposit_amount: i64,
+    pub deposit_status: DepositStatus,
+    // Sudah ada laporan pengembalian dari seller
+    pub returned: bool,
+    pub cancelled: bool,
+    pub outstanding_damage: i64,
+    // Customer sedang membayar tagihan kerusakan lewat Midtrans
+    pub damage_payment_pending: bool,
+}
+
+// Refund yang sudah dicatat pending sebelum Midtrans dipanggil
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReservedRefund {
+    pub amount: i64,
+    pub reason: String,
+    // Refund pending dari request sebelumnya dilanjutkan dengan nominal yang sama
+    pub resumed: bool,
+}
+
 // Repository untuk operasi database payment
 #[derive(Clone)]
 pub struct PaymentRepository {
@@ -19,47 +45,33 @@ impl PaymentRepository {
         Self { pool }
     }
 
-    // Create payment baru with Midtrans response 
-    pub async fn create_payment(
+    // Reservasi payment pending dengan order_id sebelum charge ke Midtrans.
+    // Data Midtrans (transaction_id, VA) diisi lewat update_midtrans_response setelah charge berhasil
+    pub async fn reserve_payment(
         &self,
         request: &CreatePaymentRequest,
         order_id: &str,
-        midtrans_response: &MidtransChargeResponse,
         expiry_time: chrono::DateTime<Utc>,
     ) -> Result<Payment, AppError> {
-        // Extract VA numbers from Midtrans response
-        let (bank, va_number) = if let Some(vas) = &midtrans_response.va_numbers {
-            if let Some(first_va) = vas.first() {
-                (Some(first_va.bank.clone()), Some(first_va.va_number.clone()))
-            } else {
-                (None, None)
-            }
-        } else {
-            (None, None)
-        };
-
         let payment_type_str = match request.payment_for_type {
             PaymentType::Rental => "rental",
             PaymentType::Sale => "sale",
+            PaymentType::RentalDamage => "rental_damage",
+            PaymentType::RentalDeposit => "rental_deposit",
         };
 
         let row = sqlx::query!(
             r#"
             INSERT INTO payments (
                 rental_booking_id, sale_order_id, order_id,
-                transaction_id, va_number, bank, payment_type,
                 gross_amount, status, payment_for_type,
                 expired_at, created_at, updated_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             RETURNING *
             "#,
             request.rental_booking_id,
             request.sale_order_id,
             order_id,
-            midtrans_response.transaction_id,
-            va_number,
-            bank,
-            midtrans_response.payment_type,
             bigdecimal::BigDecimal::from(request.gross_amount),
             "pending",
             payment_type_str,
@@ -84,6 +96,8 @@ impl PaymentRepository {
             payment_for_type: match row.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                 "rental" => PaymentType::Rental,
                 "sale" => PaymentType::Sale,
+                "rental_damage" => PaymentType::RentalDamage,
+                "rental_deposit" => PaymentType::RentalDeposit,
                 _ => PaymentType::Rental,
             },
             refund_amount: row.refund_amount.and_then(|v| v.to_i64()),
@@ -132,6 +146,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -183,6 +199,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -200,6 +218,43 @@ impl PaymentRepository {
         Ok(payment)
     }
 
+    /// Status banyak payment dalam satu query, beserta akses user (customer/buyer atau seller)
+    pub async fn find_statuses_for_user(
+        &self,
+        order_ids: &[String],
+        user_id: i32,
+    ) -> Result<Vec<PaymentStatusRow>, AppError> {
+        // Kolom dicast agar cocok dengan field Payment (NUMERIC -> BIGINT, default NULL diisi)
+        let rows = sqlx::query_as::<_, PaymentStatusRow>(
+            r#"
+            SELECT p.id, p.rental_booking_id, p.sale_order_id, p.order_id, p.transaction_id, p.va_number,
+                   p.bank, p.payment_type, p.gross_amount::BIGINT AS gross_amount,
+                   COALESCE(p.status, 'pending') AS status,
+                   COALESCE(p.payment_for_type, 'rental') AS payment_for_type,
+                   p.refund_amount::BIGINT AS refund_amount, p.refund_reason, p.paid_at, p.expired_at,
+                   p.refunded_at, p.receipt_pdf_path,
+                   COALESCE(p.created_at, NOW()) AS created_at, COALESCE(p.updated_at, NOW()) AS updated_at,
+                   COALESCE(
+                       rb.customer_id = $2 OR rb.seller_id = $2,
+                       so.buyer_id = $2 OR so.seller_id = $2,
+                       false
+                   ) AS has_access
+            FROM payments p
+            LEFT JOIN rental_bookings rb
+                   ON rb.id = p.rental_booking_id AND COALESCE(p.payment_for_type, 'rental') <> 'sale'
+            LEFT JOIN sale_orders so
+                   ON so.id = p.sale_order_id AND p.payment_for_type = 'sale'
+            WHERE p.order_id = ANY($1)
+            "#
+        )
+        .bind(order_ids)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     /// Cari payment berdasarkan rental booking ID
     pub async fn find_by_rental_booking_id(
         &self, 
@@ -234,6 +289,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -285,6 +342,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -308,8 +367,11 @@ impl PaymentRepository {
         payment_id: i32,
         status: PaymentStatus,
         transaction_id: Option<String>,
+        actor_id: Option<i32>,
     ) -> Result<Payment, AppError> {
         let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let old_values = Self::lock_audit_snapshot(&mut tx, payment_id).await?;
 
         let status_str = match status {
             PaymentStatus::Pending => "pending",
@@ -334,9 +396,24 @@ impl PaymentRepository {
             now,
             payment_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        Self::insert_audit_log(
+            &mut tx,
+            actor_id,
+            "PAYMENT_STATUS_UPDATE",
+            payment_id,
+            old_values,
+            json!({
+                "status": status_str,
+                "transaction_id": payment.transaction_id,
+                "gross_amount": payment.gross_amount.to_i64(),
+            }),
+        ).await?;
+
+        tx.commit().await?;
+
         // Manual conversion
         Ok(Payment {
             id: payment.id,
@@ -359,6 +436,8 @@ impl PaymentRepository {
             payment_for_type: match payment.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                 "rental" => PaymentType::Rental,
                 "sale" => PaymentType::Sale,
+                "rental_damage" => PaymentType::RentalDamage,
+                "rental_deposit" => PaymentType::RentalDeposit,
                 _ => PaymentType::Rental,
             },
             refund_amount: payment.refund_amount.and_then(|v| v.to_i64()),
@@ -378,8 +457,11 @@ impl PaymentRepository {
         payment_id: i32,
         refund_amount: i64,
         refund_reason: String,
+        actor_id: Option<i32>,
     ) -> Result<Payment, AppError> {
         let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let old_values = Self::lock_audit_snapshot(&mut tx, payment_id).await?;
 
         let row = sqlx::query!(
             r#"
@@ -397,9 +479,25 @@ impl PaymentRepository {
             now,
             payment_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        Self::insert_audit_log(
+            &mut tx,
+            actor_id,
+            "PAYMENT_REFUND_UPDATE",
+            payment_id,
+            old_values,
+            json!({
+                "status": "refunded",
+                "gross_amount": row.gross_amount.to_i64(),
+                "refund_amount": refund_amount,
+                "refund_reason": row.refund_reason,
+            }),
+        ).await?;
+
+        tx.commit().await?;
+
         let payment = Payment {
             id: row.id,
             rental_booking_id: row.rental_booking_id,
@@ -414,6 +512,8 @@ impl PaymentRepository {
             payment_for_type: match row.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                 "rental" => PaymentType::Rental,
                 "sale" => PaymentType::Sale,
+                "rental_damage" => PaymentType::RentalDamage,
+                "rental_deposit" => PaymentType::RentalDeposit,
                 _ => PaymentType::Rental,
             },
             refund_amount: row.refund_amount.and_then(|v| v.to_i64()),
@@ -443,28 +543,312 @@ impl PaymentRepository {
         Ok(())
     }
 
-    /// Check apakah payment ada untuk booking
-    pub async fn exists_for_rental_booking(&self, booking_id: i32) -> Result<bool, AppError> {
-        let count = sqlx::query_scalar!(
-            "SELECT COUNT(*) as count FROM payments WHERE rental_booking_id = $1",
+    /// Order ID payment rental yang sudah ada untuk booking
+    pub async fn existing_order_id_for_rental_booking(&self, booking_id: i32) -> Result<Option<String>, AppError> {
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM payments
+             WHERE rental_booking_id = $1 AND COALESCE(payment_for_type, 'rental') = 'rental'
+             ORDER BY id DESC LIMIT 1",
             booking_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(order_id)
+    }
+
+    /// Order ID payment aktif (pending/success) tagihan kerusakan rental
+    pub async fn existing_order_id_for_rental_damage(&self, booking_id: i32) -> Result<Option<String>, AppError> {
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM payments
+             WHERE rental_booking_id = $1 AND payment_for_type = 'rental_damage'
+               AND status IN ('pending', 'success')
+             ORDER BY id DESC LIMIT 1",
+            booking_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(order_id)
+    }
+
+    /// Tandai tagihan kerusakan rental sudah dibayar
+    pub async fn mark_damage_charge_paid(&self, booking_id: i32) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE rental_return_reports
+             SET charge_status = 'paid', charge_paid_at = NOW(), updated_at = NOW()
+             WHERE rental_booking_id = $1 AND charge_status = 'pending'",
+            booking_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Order ID payment aktif (pending/success) deposit rental
+    pub async fn existing_order_id_for_rental_deposit(&self, booking_id: i32) -> Result<Option<String>, AppError> {
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM payments
+             WHERE rental_booking_id = $1 AND payment_for_type = 'rental_deposit'
+               AND status IN ('pending', 'success')
+             ORDER BY id DESC LIMIT 1",
+            booking_id
+        )
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(count.unwrap_or(0) > 0)
+        Ok(order_id)
     }
 
-    /// Check apakah payment ada untuk sale order
-    pub async fn exists_for_sale_order(&self, sale_order_id: i32) -> Result<bool, AppError> {
-        let count = sqlx::query_scalar!(
-            "SELECT COUNT(*) as count FROM payments WHERE sale_order_id = $1",
+    /// Tandai deposit rental sudah dibayar dan ditahan sampai pengembalian
+    pub async fn mark_deposit_held(&self, booking_id: i32) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE rental_bookings
+             SET deposit_status = 'held', updated_at = NOW()
+             WHERE id = $1 AND deposit_status = 'pending'",
+            booking_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Catat event sale order lunas, status order diubah booking-service (sale_payment_events).
+    /// Return false jika payment ini sudah pernah dicatat
+    pub async fn record_sale_order_paid(&self, sale_order_id: i32, payment_order_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            "INSERT INTO sale_payment_events (sale_order_id, payment_order_id)
+             VALUES ($1, $2)
+             ON CONFLICT (payment_order_id) DO NOTHING",
+            sale_order_id,
+            payment_order_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Cari order ID payment deposit yang sudah dibayar untuk rental booking
+    pub async fn find_paid_deposit_order_id(&self, booking_id: i32) -> Result<Option<String>, AppError> {
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM payments
+             WHERE rental_booking_id = $1 AND payment_for_type = 'rental_deposit'
+               AND status = 'success'
+             ORDER BY id DESC
+             LIMIT 1",
+            booking_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(order_id)
+    }
+
+    /// Data deposit rental + tagihan kerusakan yang belum dibayar untuk perhitungan refund
+    pub async fn get_deposit_context(&self, booking_id: i32) -> Result<DepositContext, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT rb.deposit_amount::BIGINT as "deposit_amount!",
+                   rb.deposit_status,
+                   rb.status,
+                   rr.id as "report_id?",
+                   CASE WHEN rr.charge_status = 'pending'
+                        THEN (rr.damage_total - rr.deposit_deducted)::BIGINT
+                        ELSE 0 END as "outstanding_damage?",
+                   EXISTS(
+                       SELECT 1 FROM payments p
+                       WHERE p.rental_booking_id = rb.id AND p.payment_for_type = 'rental_damage'
+                         AND p.status = 'pending'
+                   ) as "damage_payment_pending!"
+            FROM rental_bookings rb
+            LEFT JOIN rental_return_reports rr ON rr.rental_booking_id = rb.id
+            WHERE rb.id = $1
+            "#,
+            booking_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Associated booking not found"))?;
+
+        Ok(DepositContext {
+            deposit_amount: row.deposit_amount,
+            deposit_status: DepositStatus::parse(&row.deposit_status).unwrap_or(DepositStatus::None),
+            returned: row.report_id.is_some(),
+            cancelled: row.status.as_deref() == Some("cancelled"),
+            outstanding_damage: row.outstanding_damage.unwrap_or(0),
+            damage_payment_pending: row.damage_payment_pending,
+        })
+    }
+
+    /// Refund deposit rental: potong tagihan kerusakan, update status deposit dan laporan pengembalian
+    pub async fn settle_deposit_refund(
+        &self,
+        payment_id: i32,
+        booking_id: i32,
+        refund_id: &str,
+        settlement: &DepositSettlement,
+        refund_reason: &str,
+        actor_id: i32,
+    ) -> Result<(), AppError> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let old_values = Self::lock_audit_snapshot(&mut tx, payment_id).await?;
+
+        // Deposit hanya bisa di-settle sekali
+        let updated = sqlx::query!(
+            "UPDATE rental_bookings
+             SET deposit_status = $1, deposit_refunded_amount = $2,
+                 deposit_settled_at = $3, updated_at = $3
+             WHERE id = $4 AND deposit_status = 'held'",
+            settlement.status.as_str(),
+            bigdecimal::BigDecimal::from(settlement.refund_amount),
+            now,
+            booking_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Err(AppError::refund("Deposit is not held or already settled"));
+        }
+
+        if settlement.refund_amount > 0 {
+            sqlx::query!(
+                "UPDATE payments
+                 SET status = 'refunded', refund_amount = $1, refund_reason = $2,
+                     refunded_at = $3, updated_at = $3
+                 WHERE id = $4",
+                bigdecimal::BigDecimal::from(settlement.refund_amount),
+                refund_reason,
+                now,
+                payment_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Tagihan kerusakan yang tertutup deposit dianggap lunas, sisanya tetap pending
+        if settlement.damage_deducted > 0 {
+            sqlx::query!(
+                "UPDATE rental_return_reports
+                 SET deposit_deducted = deposit_deducted + $1,
+                     charge_status = CASE WHEN $2 THEN 'paid' ELSE charge_status END,
+                     charge_paid_at = CASE WHEN $2 THEN $3 ELSE charge_paid_at END,
+                     updated_at = $3
+                 WHERE rental_booking_id = $4 AND charge_status = 'pending'",
+                bigdecimal::BigDecimal::from(settlement.damage_deducted),
+                settlement.damage_remaining == 0,
+                now,
+                booking_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        Self::insert_audit_log(
+            &mut tx,
+            Some(actor_id),
+            "DEPOSIT_REFUND",
+            payment_id,
+            old_values,
+            json!({
+                "refund_id": refund_id,
+                "deposit_status": settlement.status.as_str(),
+                "deposit_amount": settlement.deposit_amount,
+                "damage_deducted": settlement.damage_deducted,
+                "damage_remaining": settlement.damage_remaining,
+                "refund_amount": settlement.refund_amount,
+                "refund_reason": refund_reason,
+            }),
+        ).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Order ID payment yang sudah ada untuk sale order
+    pub async fn existing_order_id_for_sale_order(&self, sale_order_id: i32) -> Result<Option<String>, AppError> {
+        let order_id = sqlx::query_scalar!(
+            "SELECT order_id FROM payments
+             WHERE sale_order_id = $1
+             ORDER BY id DESC LIMIT 1",
             sale_order_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(order_id)
+    }
+
+    /// ID payment pending lebih tua dari `min_age_mins` menit yang belum expired. Reservasi tanpa
+    /// transaction_id (hasil charge tidak pasti) tetap diambil meski sudah lewat expired_at
+    pub async fn find_stale_pending_ids(&self, min_age_mins: i64, limit: i64) -> Result<Vec<i32>, AppError> {
+        let ids = sqlx::query_scalar!(
+            "SELECT id FROM payments
+             WHERE status = 'pending'
+               AND created_at < NOW() - $1::BIGINT * INTERVAL '1 minute'
+               AND (transaction_id IS NULL OR expired_at IS NULL OR expired_at > NOW())
+             ORDER BY created_at
+             LIMIT $2",
+            min_age_mins,
+            limit
+        )
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(count.unwrap_or(0) > 0)
+        Ok(ids)
+    }
+
+    /// Update status payment yang masih pending dari hasil cek status Midtrans.
+    /// Return false jika status sudah diubah webhook lebih dulu.
+    pub async fn reconcile_pending_status(
+        &self,
+        payment_id: i32,
+        status: PaymentStatus,
+        transaction_status: &str,
+    ) -> Result<bool, AppError> {
+        let status_str = status.to_string();
+
+        let mut tx = self.pool.begin().await?;
+        let old_values = Self::lock_audit_snapshot(&mut tx, payment_id).await?;
+
+        let updated = sqlx::query!(
+            "UPDATE payments
+             SET status = $1::varchar,
+                 paid_at = CASE WHEN $1::varchar = 'success' THEN NOW() ELSE paid_at END,
+                 updated_at = NOW()
+             WHERE id = $2 AND status = 'pending'",
+            status_str,
+            payment_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            return Ok(false);
+        }
+
+        // Rekonsiliasi scheduler tidak punya actor user
+        Self::insert_audit_log(
+            &mut tx,
+            None,
+            "PAYMENT_STATUS_RECONCILED",
+            payment_id,
+            old_values,
+            json!({
+                "status": status_str,
+                "transaction_status": transaction_status,
+            }),
+        ).await?;
+
+        tx.commit().await?;
+
+        Ok(true)
     }
 
     /// Get list payments by status
@@ -506,6 +890,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -531,6 +917,8 @@ impl PaymentRepository {
         let payment_type_str = match payment_type {
             PaymentType::Rental => "rental",
             PaymentType::Sale => "sale",
+            PaymentType::RentalDamage => "rental_damage",
+            PaymentType::RentalDeposit => "rental_deposit",
         };
 
         let status_str = match status {
@@ -571,6 +959,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -624,6 +1014,8 @@ impl PaymentRepository {
                 payment_for_type: match p.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                     "rental" => PaymentType::Rental,
                     "sale" => PaymentType::Sale,
+                    "rental_damage" => PaymentType::RentalDamage,
+                    "rental_deposit" => PaymentType::RentalDeposit,
                     _ => PaymentType::Rental,
                 },
                 refund_amount: p.refund_amount.and_then(|v| v.to_i64()),
@@ -652,11 +1044,12 @@ impl PaymentRepository {
 
         // Log webhook payload for audit trail
         tracing::info!(
-            "Webhook payment update: payment_id={}, order_id={}, transaction_status={}, fraud_status={:?}",
+            event = "payment_webhook_update",
             payment_id,
-            webhook_payload.order_id,
-            webhook_payload.transaction_status,
-            webhook_payload.fraud_status
+            order_id = %webhook_payload.order_id,
+            transaction_status = %webhook_payload.transaction_status,
+            fraud_status = ?webhook_payload.fraud_status,
+            "Webhook payment update"
         );
 
         let status_str = match status {
@@ -667,6 +1060,9 @@ impl PaymentRepository {
             PaymentStatus::Refunded => "refunded",
         };
 
+        let mut tx = self.pool.begin().await?;
+        let old_values = Self::lock_audit_snapshot(&mut tx, payment_id).await?;
+
         let row = sqlx::query!(
             r#"
             UPDATE payments
@@ -686,9 +1082,26 @@ impl PaymentRepository {
             now,
             payment_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        // Webhook Midtrans tidak punya actor user
+        Self::insert_audit_log(
+            &mut tx,
+            None,
+            "PAYMENT_STATUS_WEBHOOK",
+            payment_id,
+            old_values,
+            json!({
+                "status": status_str,
+                "transaction_id": transaction_id,
+                "transaction_status": webhook_payload.transaction_status,
+                "gross_amount": row.gross_amount.to_i64(),
+            }),
+        ).await?;
+
+        tx.commit().await?;
+
         Ok(Payment {
             id: row.id,
             rental_booking_id: row.rental_booking_id,
@@ -710,6 +1123,8 @@ impl PaymentRepository {
             payment_for_type: match row.payment_for_type.as_str() {
                 "rental" => PaymentType::Rental,
                 "sale" => PaymentType::Sale,
+                "rental_damage" => PaymentType::RentalDamage,
+                "rental_deposit" => PaymentType::RentalDeposit,
                 _ => PaymentType::Rental,
             },
             refund_amount: row.refund_amount.and_then(|v| v.to_i64()),
@@ -723,13 +1138,101 @@ impl PaymentRepository {
         })
     }
 
-    /// Process refund for a payment
+    /// Kunci payment dan catat refund pending sebelum Midtrans dipanggil.
+    /// Refund pending yang belum selesai (retry/request bersamaan) dilanjutkan dengan nominal tersimpan
+    pub async fn reserve_refund(
+        &self,
+        payment_id: i32,
+        refund_amount: i64,
+        refund_reason: &str,
+    ) -> Result<ReservedRefund, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT status as "status?", refund_status, refund_amount::BIGINT as refund_amount, refund_reason
+            FROM payments
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            payment_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("Payment not found"))?;
+
+        if row.refund_status.as_deref() == Some("pending") {
+            return Ok(ReservedRefund {
+                amount: row.refund_amount.ok_or_else(|| AppError::internal("Pending refund without amount"))?,
+                reason: row.refund_reason.unwrap_or_default(),
+                resumed: true,
+            });
+        }
+
+        if row.status.as_deref() != Some("success") || row.refund_status.is_some() {
+            return Err(AppError::refund("Payment has already been refunded"));
+        }
+
+        sqlx::query(
+            "UPDATE payments
+             SET refund_status = 'pending', refund_amount = $2, refund_reason = $3, updated_at = NOW()
+             WHERE id = $1"
+        )
+        .bind(payment_id)
+        .bind(bigdecimal::BigDecimal::from(refund_amount))
+        .bind(refund_reason)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(ReservedRefund {
+            amount: refund_amount,
+            reason: refund_reason.to_string(),
+            resumed: false,
+        })
+    }
+
+    /// Refund pending yang hasil Midtrans-nya belum pasti (tidak berubah sejak `min_age_mins` menit)
+    pub async fn find_stale_pending_refund_ids(&self, min_age_mins: i64, limit: i64) -> Result<Vec<i32>, AppError> {
+        let ids = sqlx::query_scalar!(
+            "SELECT id FROM payments
+             WHERE refund_status = 'pending'
+               AND updated_at < NOW() - $1::BIGINT * INTERVAL '1 minute'
+             ORDER BY updated_at
+             LIMIT $2",
+            min_age_mins,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Lepas refund pending saat Midtrans menolak refund, payment bisa direfund ulang dengan key yang sama
+    pub async fn release_refund(&self, payment_id: i32) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE payments
+             SET refund_status = NULL, refund_amount = NULL, refund_reason = NULL, updated_at = NOW()
+             WHERE id = $1 AND refund_status = 'pending'"
+        )
+        .bind(payment_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Selesaikan refund pending: status refunded + hasil refund dari Midtrans
     pub async fn process_refund(
         &self,
         payment_id: i32,
         refund_id: &str,
         refund_amount: i64,
         refund_reason: &str,
+        actor_id: Option<i32>,
+        outcome: &RefundOutcome,
     ) -> Result<Payment, AppError> {
         let now = Utc::now();
 
@@ -739,10 +1242,17 @@ impl PaymentRepository {
         }
 
         tracing::info!(
-            "Processing refund: payment_id={}, refund_id={}, amount={}, reason={}",
-            payment_id, refund_id, refund_amount, refund_reason
+            event = "refund_started",
+            payment_id,
+            refund_id,
+            amount = refund_amount,
+            reason = refund_reason,
+            "Processing refund"
         );
 
+        let mut tx = self.pool.begin().await?;
+        let old_values = Self::lock_audit_snapshot(&mut tx, payment_id).await?;
+
         let row = sqlx::query!(
             r#"
             UPDATE payments
@@ -750,17 +1260,42 @@ impl PaymentRepository {
                 refund_amount = $1,
                 refund_reason = $2,
                 refunded_at = $3,
-                updated_at = $3
-            WHERE id = $4
+                updated_at = $3,
+                refund_status = $5,
+                refund_reference = $6
+            WHERE id = $4 AND refund_status = 'pending'
             RETURNING *
             "#,
             bigdecimal::BigDecimal::from(refund_amount),
             refund_reason,
             now,
-            payment_id
+            payment_id,
+            outcome.status.as_str(),
+            outcome.reference
         )
-        .fetch_one(&self.pool)
-        .await?;
+        .fetch_optional(&mut *tx)
+        .await?
+        // Request bersamaan dengan key yang sama sudah menyelesaikan refund ini
+        .ok_or_else(|| AppError::refund("Refund has already been processed"))?;
+
+        Self::insert_audit_log(
+            &mut tx,
+            actor_id,
+            "PAYMENT_REFUND",
+            payment_id,
+            old_values,
+            json!({
+                "status": "refunded",
+                "refund_id": refund_id,
+                "gross_amount": row.gross_amount.to_i64(),
+                "refund_amount": refund_amount,
+                "refund_reason": refund_reason,
+                "refund_status": outcome.status.as_str(),
+                "refund_reference": outcome.reference,
+            }),
+        ).await?;
+
+        tx.commit().await?;
 
         let payment = Payment {
             id: row.id,
@@ -776,6 +1311,8 @@ impl PaymentRepository {
             payment_for_type: match row.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                 "rental" => PaymentType::Rental,
                 "sale" => PaymentType::Sale,
+                "rental_damage" => PaymentType::RentalDamage,
+                "rental_deposit" => PaymentType::RentalDeposit,
                 _ => PaymentType::Rental,
             },
             refund_amount: row.refund_amount.and_then(|v| v.to_i64()),
@@ -791,11 +1328,23 @@ impl PaymentRepository {
         Ok(payment)
     }
 
+    /// Hapus reservasi payment yang charge Midtrans-nya gagal (belum punya transaction_id)
+    pub async fn discard_reserved_payment(&self, payment_id: i32) -> Result<(), AppError> {
+        sqlx::query(
+            "DELETE FROM payments WHERE id = $1 AND status = 'pending' AND transaction_id IS NULL"
+        )
+        .bind(payment_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Update Midtrans response data
     pub async fn update_midtrans_response(
         &self,
         payment_id: i32,
-        midtrans_response: &crate::domain::payment::MidtransChargeResponse,
+        midtrans_response: &MidtransChargeResponse,
     ) -> Result<Payment, AppError> {
         let va_number = midtrans_response.va_numbers
             .as_ref()
@@ -851,6 +1400,8 @@ impl PaymentRepository {
             payment_for_type: match row.payment_for_type.as_ref().map_or("rental", |s| s.as_str()) {
                 "rental" => PaymentType::Rental,
                 "sale" => PaymentType::Sale,
+                "rental_damage" => PaymentType::RentalDamage,
+                "rental_deposit" => PaymentType::RentalDeposit,
                 _ => PaymentType::Rental,
             },
             refund_amount: row.refund_amount.and_then(|v| v.to_i64()),
@@ -865,4 +1416,55 @@ impl PaymentRepository {
 
         Ok(payment)
     }
+
+    // Lock row payment dan ambil nilai sebelum perubahan untuk audit trail
+    async fn lock_audit_snapshot(
+        conn: &mut PgConnection,
+        payment_id: i32,
+    ) -> Result<serde_json::Value, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT status, transaction_id, gross_amount, refund_amount, refund_reason
+            FROM payments
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            payment_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Payment not found"))?;
+
+        Ok(json!({
+            "status": row.status,
+            "transaction_id": row.transaction_id,
+            "gross_amount": row.gross_amount.to_i64(),
+            "refund_amount": row.refund_amount.and_then(|v| v.to_i64()),
+            "refund_reason": row.refund_reason,
+        }))
+    }
+
+    // Catat mutasi payment sensitif ke audit_logs di transaksi yang sama
+    async fn insert_audit_log(
+        conn: &mut PgConnection,
+        actor_id: Option<i32>,
+        action: &str,
+        payment_id: i32,
+        old_values: serde_json::Value,
+        new_values: serde_json::Value,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            "INSERT INTO audit_logs (user_id, action, entity_type, entity_id, old_values, new_values, service_name)
+             VALUES ($1, $2, 'payment', $3, $4, $5, 'payment-service')",
+            actor_id,
+            action,
+            payment_id,
+            old_values,
+            new_values
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
 }
\ No newline at end of file
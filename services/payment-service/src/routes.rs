@@ -1,7 +1,8 @@
 // API Routes untuk Payment Service dengan JWT-Only architecture
 
 use crate::config::AppState;
-use crate::handlers::payment_handler;
+use crate::error::AppError;
+use crate::handlers::{audit_log_handler, payment_handler};
 use crate::middleware::{auth::jwt_auth_middleware, rate_limit::rate_limit_middleware};
 use axum::{
     routing::{get, post},
@@ -9,19 +10,16 @@ use axum::{
     extract::Request,
     middleware::Next,
     response::Response,
-    http::{Method, StatusCode, header::HeaderValue},
+    http::Method,
 };
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa::Modify;
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use std::sync::Arc;
-use tower::ServiceBuilder;
-use tower_http::{
-    cors::CorsLayer,
-    trace::TraceLayer,
-    timeout::TimeoutLayer,
-};
+use shared::utils::cors::CorsPolicy;
+use shared::utils::request_timeout;
+use tower_http::cors::CorsLayer;
 use std::time::Duration;
 
 // OpenAPI Documentation untuk Payment Service
@@ -35,12 +33,16 @@ use std::time::Duration;
         payment_handler::midtrans_webhook,
         payment_handler::process_refund,
         payment_handler::get_payment_receipt,
+        payment_handler::get_receipt_file,
         payment_handler::check_payment_status,
+        payment_handler::check_payment_status_batch,
+        payment_handler::stream_payment_events,
         payment_handler::cancel_payment,
         payment_handler::get_payment_methods,
         payment_handler::resend_webhook,
         payment_handler::health_check,
         payment_handler::get_service_info,
+        audit_log_handler::list_audit_logs,
     ),
     components(
         schemas(
@@ -49,12 +51,20 @@ use std::time::Duration;
             crate::domain::payment::PaymentStatus,
             crate::domain::payment::PaymentType,
             crate::domain::payment::RefundRequest,
+            crate::domain::payment::RefundTarget,
+            crate::domain::deposit::DepositStatus,
             crate::domain::payment::WebhookResponse,
             crate::domain::payment::PaymentReceipt,
+            crate::utils::payment_events::PaymentStatusSnapshot,
+            crate::utils::payment_status_batch::PaymentStatusBatchRequest,
+            crate::utils::payment_status_batch::PaymentStatusBatchItem,
+            crate::utils::payment_status_batch::BatchLookupResult,
             crate::domain::payment::CustomerDetails,
             crate::domain::payment::ItemDetails,
             crate::domain::payment::MidtransChargeResponse,
-            crate::domain::payment::MidtransWebhookPayload
+            crate::domain::payment::MidtransWebhookPayload,
+            crate::domain::audit_log::AuditLogEntry,
+            crate::domain::audit_log::AuditLogListResponse
         )
     ),
     tags(
@@ -118,6 +128,11 @@ async fn security_headers_middleware(
     response
 }
 
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
+}
+
 // Buat router dengan JWT-only security dan Redis rate limiting
 pub async fn create_routes(state: AppState) -> Router {
     if state.config.is_production() {
@@ -126,15 +141,11 @@ pub async fn create_routes(state: AppState) -> Router {
         tracing::info!("Payment Service running in DEVELOPMENT mode");
     }
 
-    // CORS configuration
-    let frontend_url = std::env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable HARUS diisi di .env file");
-
-    let allowed_origin = frontend_url.parse::<HeaderValue>()
-        .expect("FRONTEND_URL harus valid URL format");
+    // CORS: origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let cors_policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
-    let cors = CorsLayer::new()
-        .allow_origin(allowed_origin)
+    let cors = cors_policy
+        .apply(CorsLayer::new())
         .allow_methods([
             Method::GET,
             Method::POST,
@@ -148,7 +159,6 @@ pub async fn create_routes(state: AppState) -> Router {
             axum::http::header::ACCEPT,
             axum::http::header::CONTENT_TYPE,
         ])
-        .allow_credentials(false)
         .max_age(Duration::from_secs(86400));
 
     // Setup OpenAPI documentation
@@ -159,6 +169,7 @@ pub async fn create_routes(state: AppState) -> Router {
     let public_routes = Router::new()
         .route("/health", get(payment_handler::health_check))
         .route("/info", get(payment_handler::get_service_info))
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
         .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi))
         .with_state(state.clone());
 
@@ -172,12 +183,8 @@ pub async fn create_routes(state: AppState) -> Router {
     // Combine semua routes dengan shared middleware
     public_routes
         .nest("/api", protected_routes)
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, Duration::from_secs(30)))
-                .layer(cors)
-        )
+        .fallback(not_found_handler)
+        .layer(cors)
         .layer(axum::middleware::from_fn(security_headers_middleware))
         .layer(axum::middleware::from_fn_with_state(
             Arc::new(state.rate_limiter.clone()),
@@ -193,8 +200,10 @@ fn build_api_routes(state: AppState) -> Router {
         .route("/payments/{order_id}", get(payment_handler::get_payment_by_order_id).post(payment_handler::cancel_payment))
         .route("/payments/details/{payment_id}", get(payment_handler::get_payment_details))
         .route("/payments/status/{order_id}", get(payment_handler::check_payment_status))
+        .route("/payments/status/batch", post(payment_handler::check_payment_status_batch))
         .route("/payments/user/{user_id}", get(payment_handler::get_user_payment_history))
         .route("/payments/receipt/{order_id}", get(payment_handler::get_payment_receipt))
+        .route("/payments/files/{token}", get(payment_handler::get_receipt_file))
 
         // ===== Refund Operations =====
         .route("/refunds", post(payment_handler::process_refund))
@@ -205,5 +214,14 @@ fn build_api_routes(state: AppState) -> Router {
         // ===== Webhook (External - Midtrans) =====
         .route("/webhooks/midtrans", post(payment_handler::midtrans_webhook))
         .route("/webhooks/resend", post(payment_handler::resend_webhook))
+
+        // ===== Admin =====
+        .route("/admin/audit-logs", get(audit_log_handler::list_audit_logs))
+
+        // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
+
+        // ===== Long-lived (tanpa timeout) =====
+        .route("/payments/{order_id}/events", get(payment_handler::stream_payment_events))
         .with_state(state)
 }
\ No newline at end of file
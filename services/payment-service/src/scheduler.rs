@@ -0,0 +1,367 @@
+use crate::config::AppState;
+use crate::handlers::midtrans_service::MidtransService;
+use crate::domain::payment::Payment;
+use crate::handlers::payment_handler::{apply_payment_success_effects, send_refund};
+use crate::repositories::payment_repo::PaymentRepository;
+use crate::utils::midtrans_refund::{self, RefundFailure};
+use crate::utils::payment_reconcile::{call_spacing, reconciled_status, ReconcileSummary};
+use crate::utils::resend_throttle::claim_status_check;
+use std::time::Duration;
+
+/// Background scheduler untuk payment service (rekonsiliasi payment pending dengan Midtrans)
+pub struct PaymentScheduler {
+    state: AppState,
+}
+
+impl PaymentScheduler {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Start background tasks untuk payment service
+    pub fn start(self) {
+        // Check if scheduler is disabled
+        if std::env::var("DISABLE_SCHEDULER").unwrap_or_else(|_| "false".to_string()) == "true" {
+            tracing::info!("💳 Payment scheduler disabled via DISABLE_SCHEDULER environment variable");
+            return;
+        }
+
+        tracing::info!("💳 Starting Payment Service Background Scheduler...");
+
+        // Cek status Midtrans untuk payment pending yang webhook-nya tidak pernah datang
+        let state = self.state;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                state.config.payment_reconcile_interval_secs.max(60),
+            ));
+
+            loop {
+                interval.tick().await;
+
+                let summary = reconcile_pending_payments(&state).await;
+                if summary.checked > 0 {
+                    tracing::info!("🔁 Payment reconciliation: {}", summary);
+                }
+
+                let summary = reconcile_pending_refunds(&state).await;
+                if summary.checked > 0 {
+                    tracing::info!("🔁 Refund reconciliation: {}", summary);
+                }
+            }
+        });
+    }
+}
+
+// Satu putaran rekonsiliasi, panggilan Midtrans diberi jeda sesuai batas per menit
+async fn reconcile_pending_payments(state: &AppState) -> ReconcileSummary {
+    let mut summary = ReconcileSummary::default();
+
+    let ids = match state.payment_repository
+        .find_stale_pending_ids(state.config.payment_reconcile_min_age_mins, state.config.payment_reconcile_batch_size)
+        .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::error!("❌ Failed to load pending payments for reconciliation: {}", e);
+            return summary;
+        }
+    };
+
+    let midtrans_service = MidtransService::new(
+        state.config.midtrans_server_key.clone(),
+        state.config.midtrans_client_key.clone(),
+        state.config.midtrans_api_url.clone(),
+    );
+    let spacing = call_spacing(state.config.midtrans_status_calls_per_minute);
+
+    for (index, payment_id) in ids.into_iter().enumerate() {
+        if index > 0 {
+            tokio::time::sleep(spacing).await;
+        }
+
+        summary.checked += 1;
+        match reconcile_payment(state, &midtrans_service, payment_id).await {
+            Ok(true) => summary.updated += 1,
+            Ok(false) => summary.still_pending += 1,
+            Err(e) => {
+                summary.failed += 1;
+                tracing::warn!("⚠️ Reconciliation failed for payment {}: {}", payment_id, e);
+            }
+        }
+    }
+
+    summary
+}
+
+// Cek satu payment ke Midtrans, return true jika status berubah
+async fn reconcile_payment(
+    state: &AppState,
+    midtrans_service: &MidtransService,
+    payment_id: i32,
+) -> Result<bool, crate::error::AppError> {
+    // Ambil ulang: webhook bisa sudah memproses payment sejak query batch
+    let Some(payment) = state.payment_repository.find_by_id(payment_id).await? else {
+        return Ok(false);
+    };
+
+    // Baru saja dicek lewat resend webhook, tunggu batch berikutnya
+    if !claim_status_check(&state.rate_limiter, payment_id, state.config.midtrans_status_recheck_secs).await {
+        return Ok(false);
+    }
+
+    let (transaction_status, fraud_status, attached) = match payment.transaction_id.as_deref() {
+        Some(transaction_id) => {
+            let response = midtrans_service.check_transaction_status(transaction_id).await?;
+            let field = |name: &str| response.get(name).and_then(|v| v.as_str()).map(str::to_string);
+            (field("transaction_status"), field("fraud_status"), false)
+        }
+        // Charge yang hasilnya tidak pasti saat dibuat: cari transaksinya lewat order_id reservasi
+        None => match midtrans_service.find_transaction_by_order_id(&payment.order_id).await? {
+            Some(charge) => {
+                state.payment_repository.update_midtrans_response(payment.id, &charge).await?;
+                tracing::info!(
+                    event = "payment_charge_recovered",
+                    order_id = %payment.order_id,
+                    payment_id = payment.id,
+                    transaction_id = %charge.transaction_id,
+                    "✅ Midtrans charge found for reserved payment"
+                );
+                (Some(charge.transaction_status), None, true)
+            }
+            None => {
+                // Midtrans tidak pernah menerima charge: reservasi dilepas agar bisa dibayar ulang
+                state.payment_repository.discard_reserved_payment(payment.id).await?;
+                tracing::info!(
+                    event = "payment_reservation_released",
+                    order_id = %payment.order_id,
+                    payment_id = payment.id,
+                    "Reserved payment has no Midtrans charge, reservation released"
+                );
+                return Ok(true);
+            }
+        },
+    };
+
+    let Some(new_status) = reconciled_status(transaction_status.as_deref(), fraud_status.as_deref()) else {
+        return Ok(attached);
+    };
+
+    let transaction_status = transaction_status.unwrap_or_default();
+    if !state.payment_repository
+        .reconcile_pending_status(payment.id, new_status, &transaction_status)
+        .await?
+    {
+        return Ok(false);
+    }
+
+    apply_payment_success_effects(&state.payment_repository, &payment, new_status).await?;
+    state.payment_events.publish(&payment.order_id, new_status);
+
+    tracing::info!(
+        event = "payment_status_changed",
+        order_id = %payment.order_id,
+        payment_id = payment.id,
+        previous_status = "pending",
+        status = %new_status,
+        transaction_status = %transaction_status,
+        source = "reconciliation",
+        "✅ Payment reconciled"
+    );
+
+    Ok(true)
+}
+
+// Refund pending yang hasil Midtrans-nya tidak pasti (timeout/5xx saat refund dikirim)
+async fn reconcile_pending_refunds(state: &AppState) -> ReconcileSummary {
+    let mut summary = ReconcileSummary::default();
+
+    let ids = match state.payment_repository
+        .find_stale_pending_refund_ids(state.config.payment_reconcile_min_age_mins, state.config.payment_reconcile_batch_size)
+        .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::error!("❌ Failed to load pending refunds for reconciliation: {}", e);
+            return summary;
+        }
+    };
+
+    let midtrans_service = MidtransService::new(
+        state.config.midtrans_server_key.clone(),
+        state.config.midtrans_client_key.clone(),
+        state.config.midtrans_api_url.clone(),
+    );
+    let spacing = call_spacing(state.config.midtrans_status_calls_per_minute);
+
+    for (index, payment_id) in ids.into_iter().enumerate() {
+        if index > 0 {
+            tokio::time::sleep(spacing).await;
+        }
+
+        summary.checked += 1;
+        match reconcile_refund(&state.payment_repository, &midtrans_service, payment_id).await {
+            Ok(true) => summary.updated += 1,
+            Ok(false) => summary.still_pending += 1,
+            Err(e) => {
+                summary.failed += 1;
+                tracing::warn!("⚠️ Refund reconciliation failed for payment {}: {}", payment_id, e);
+            }
+        }
+    }
+
+    summary
+}
+
+// Selesaikan satu refund pending, return true jika refund selesai atau dilepas.
+// Refund yang sudah tercatat di Midtrans (refund_key sama) langsung diselesaikan; jika belum ada,
+// refund dikirim ulang dengan key + nominal yang sama sehingga Midtrans tetap me-refund sekali.
+pub(crate) async fn reconcile_refund(
+    repository: &PaymentRepository,
+    midtrans_service: &MidtransService,
+    payment_id: i32,
+) -> Result<bool, crate::error::AppError> {
+    let Some(payment) = repository.find_by_id(payment_id).await? else {
+        return Ok(false);
+    };
+    let (Some(amount), Some(reason)) = (payment.refund_amount, payment.refund_reason.clone()) else {
+        return Ok(false);
+    };
+    let refund_key = Payment::refund_key(payment.id);
+
+    let lookup_id = payment.transaction_id.as_deref().unwrap_or(&payment.order_id);
+    let status = midtrans_service.check_transaction_status(lookup_id).await?;
+
+    if let Some(outcome) = midtrans_refund::refund_from_status(&status, &refund_key) {
+        repository.process_refund(payment.id, &refund_key, amount, &reason, None, &outcome).await?;
+        tracing::info!(
+            event = "refund_reconciled",
+            order_id = %payment.order_id,
+            payment_id = payment.id,
+            refund_key = %refund_key,
+            "✅ Pending refund found at Midtrans"
+        );
+        return Ok(true);
+    }
+
+    match send_refund(midtrans_service, &payment, amount, &reason).await {
+        Ok(outcome) => {
+            repository.process_refund(payment.id, &refund_key, amount, &reason, None, &outcome).await?;
+            Ok(true)
+        }
+        Err(RefundFailure::Rejected(message)) => {
+            // Midtrans menolak: refund belum terjadi, dilepas agar bisa diajukan ulang
+            repository.release_refund(payment.id).await?;
+            tracing::warn!("⚠️ Pending refund for payment {} rejected by Midtrans: {}", payment.id, message);
+            Ok(true)
+        }
+        Err(RefundFailure::Ambiguous(message)) => Err(crate::error::AppError::midtrans(message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::payment::{CreatePaymentRequest, CustomerDetails, PaymentStatus, PaymentType};
+    use crate::middleware::rate_limit::RateLimiter;
+    use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+    use sqlx::PgPool;
+
+    // Mock status Midtrans: hanya RNT-AMB-1 yang pernah di-charge (sudah settlement)
+    async fn spawn_status_mock() -> String {
+        let router = Router::new().route(
+            "/v2/{order_id}/status",
+            get(|Path(order_id): Path<String>| async move {
+                if order_id != "RNT-AMB-1" {
+                    return (
+                        StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({ "status_code": "404", "status_message": "Transaction doesn't exist." })),
+                    )
+                        .into_response();
+                }
+                Json(serde_json::json!({
+                    "status_code": "200",
+                    "status_message": "Success, transaction is found",
+                    "transaction_id": "trx-late-1",
+                    "order_id": order_id,
+                    "gross_amount": "700000.00",
+                    "payment_type": "bank_transfer",
+                    "transaction_status": "settlement",
+                    "transaction_time": "2026-10-16 10:00:00",
+                    "va_numbers": [{ "bank": "bca", "va_number": "1234567890" }]
+                }))
+                .into_response()
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}/v2", addr)
+    }
+
+    // Reservasi tanpa transaction_id (charge timeout/5xx) dicek lewat order_id:
+    // charge yang ternyata ada disimpan + statusnya diterapkan, yang tidak ada dilepas
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_reconcile_reservations_without_transaction_id(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO rental_bookings (
+                id, vehicle_id, customer_id, seller_id, order_id, pickup_date, return_date,
+                customer_name, customer_phone, customer_email, total_days, price_per_day, total_price, status
+            ) VALUES
+                (1, 1, 1, 2, 'RNT-TEST-1', NOW() + INTERVAL '3 days', NOW() + INTERVAL '5 days',
+                 'Customer Test', '081200000001', 'customer@test.local', 2, 350000, 700000, 'pending_payment'),
+                (2, 1, 1, 2, 'RNT-TEST-2', NOW() + INTERVAL '8 days', NOW() + INTERVAL '10 days',
+                 'Customer Test', '081200000001', 'customer@test.local', 2, 350000, 700000, 'pending_payment')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut state = AppState::for_test(pool.clone(), spawn_status_mock().await);
+        // Redis tidak tersedia: penanda status check fail-open, tidak tertinggal dari run sebelumnya
+        state.rate_limiter = RateLimiter::new("redis://127.0.0.1:1").unwrap();
+        state.config.midtrans_status_calls_per_minute = 60_000;
+
+        for (booking_id, order_id) in [(1, "RNT-AMB-1"), (2, "RNT-AMB-2")] {
+            let request = CreatePaymentRequest {
+                payment_for_type: PaymentType::Rental,
+                rental_booking_id: Some(booking_id),
+                sale_order_id: None,
+                gross_amount: 700_000,
+                payment_method: "bca".to_string(),
+                customer_details: CustomerDetails {
+                    first_name: "Customer".to_string(),
+                    last_name: None,
+                    email: "customer@test.local".to_string(),
+                    phone: "081200000001".to_string(),
+                },
+                item_details: vec![],
+            };
+            state.payment_repository
+                .reserve_payment(&request, order_id, Payment::generate_expiry_time(PaymentType::Rental))
+                .await
+                .unwrap();
+        }
+        // Reservasi sudah lewat ambang umur dan expired_at-nya
+        sqlx::query("UPDATE payments SET created_at = NOW() - INTERVAL '2 days', expired_at = NOW() - INTERVAL '1 day'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let summary = reconcile_pending_payments(&state).await;
+        assert_eq!(summary, ReconcileSummary { checked: 2, updated: 2, still_pending: 0, failed: 0 });
+
+        let recovered = state.payment_repository.find_by_order_id("RNT-AMB-1").await.unwrap().unwrap();
+        assert_eq!(recovered.transaction_id.as_deref(), Some("trx-late-1"));
+        assert_eq!(recovered.va_number.as_deref(), Some("1234567890"));
+        assert_eq!(recovered.status, PaymentStatus::Success);
+        assert!(state.payment_repository.find_by_order_id("RNT-AMB-2").await.unwrap().is_none());
+    }
+}
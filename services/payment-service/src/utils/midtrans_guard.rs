@@ -0,0 +1,86 @@
+// Guardrail mode Midtrans (sandbox/production) terhadap environment aplikasi
+
+// Prefix server key sandbox dari dashboard Midtrans
+const SANDBOX_KEY_PREFIX: &str = "SB-";
+
+// Cek apakah konfigurasi Midtrans memakai kredensial atau endpoint production
+fn uses_production_midtrans(midtrans_is_production: bool, server_key: &str, api_url: &str) -> bool {
+    midtrans_is_production
+        || !server_key.starts_with(SANDBOX_KEY_PREFIX)
+        || !api_url.contains("sandbox")
+}
+
+// Cari ketidakcocokan mode Midtrans dengan environment aplikasi
+pub fn find_mode_mismatch(
+    environment: &str,
+    midtrans_is_production: bool,
+    server_key: &str,
+    api_url: &str,
+) -> Option<String> {
+    let app_is_production = environment == "production";
+
+    if !app_is_production && uses_production_midtrans(midtrans_is_production, server_key, api_url) {
+        return Some(format!(
+            "RUST_ENV={} tapi Midtrans memakai key/URL production (charge uang asli)",
+            environment
+        ));
+    }
+
+    if app_is_production && !midtrans_is_production {
+        return Some("RUST_ENV=production tapi MIDTRANS_IS_PRODUCTION=false (sandbox)".to_string());
+    }
+
+    None
+}
+
+// Tolak start jika mode tidak cocok, kecuali override eksplisit
+pub fn validate_midtrans_mode(
+    environment: &str,
+    midtrans_is_production: bool,
+    server_key: &str,
+    api_url: &str,
+    allow_mismatch: bool,
+) -> Result<(), String> {
+    let Some(mismatch) = find_mode_mismatch(environment, midtrans_is_production, server_key, api_url) else {
+        return Ok(());
+    };
+
+    if allow_mismatch {
+        tracing::warn!("⚠️⚠️⚠️ MIDTRANS MODE MISMATCH DI-OVERRIDE: {} ⚠️⚠️⚠️", mismatch);
+        tracing::warn!("⚠️ MIDTRANS_ALLOW_ENV_MISMATCH=true aktif, pastikan ini disengaja");
+        return Ok(());
+    }
+
+    Err(format!(
+        "{}. Set MIDTRANS_ALLOW_ENV_MISMATCH=true jika memang disengaja",
+        mismatch
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SANDBOX_URL: &str = "https://api.sandbox.midtrans.com/v2";
+    const PRODUCTION_URL: &str = "https://api.midtrans.com/v2";
+
+    #[test]
+    fn test_matching_modes_pass() {
+        assert!(validate_midtrans_mode("development", false, "SB-Mid-server-abc", SANDBOX_URL, false).is_ok());
+        assert!(validate_midtrans_mode("production", true, "Mid-server-abc", PRODUCTION_URL, false).is_ok());
+    }
+
+    #[test]
+    fn test_mismatch_refuses_start_unless_overridden() {
+        // Production key/flag/URL di environment non-production
+        assert!(validate_midtrans_mode("development", true, "SB-Mid-server-abc", SANDBOX_URL, false).is_err());
+        assert!(validate_midtrans_mode("staging", false, "Mid-server-abc", SANDBOX_URL, false).is_err());
+        assert!(validate_midtrans_mode("development", false, "SB-Mid-server-abc", PRODUCTION_URL, false).is_err());
+
+        // Sandbox di production
+        let err = validate_midtrans_mode("production", false, "SB-Mid-server-abc", SANDBOX_URL, false).unwrap_err();
+        assert!(err.contains("MIDTRANS_ALLOW_ENV_MISMATCH"));
+
+        assert!(validate_midtrans_mode("development", true, "Mid-server-abc", PRODUCTION_URL, true).is_ok());
+    }
+}
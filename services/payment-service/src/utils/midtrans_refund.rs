@@ -0,0 +1,167 @@
+// Refund ke channel pembayaran asal via Midtrans
+//
+// Midtrans hanya mendukung refund otomatis untuk sebagian channel (kartu kredit, e-wallet, QRIS).
+// Virtual account (bank_transfer) tidak bisa direfund lewat API, refund-nya ditandai
+// manual_required agar tim finance mentransfer dana ke rekening buyer.
+
+use serde::Deserialize;
+
+// payment_type Midtrans yang bisa direfund lewat API
+const AUTOMATED_REFUND_CHANNELS: &[&str] = &["credit_card", "gopay", "shopeepay", "qris"];
+
+// Status refund di payments.refund_status (selain 'pending' saat refund sedang dikirim ke Midtrans)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefundStatus {
+    // Midtrans sudah menerima refund ke channel asal
+    Completed,
+    // Channel tidak mendukung refund otomatis, menunggu proses manual
+    ManualRequired,
+}
+
+impl RefundStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefundStatus::Completed => "completed",
+            RefundStatus::ManualRequired => "manual_required",
+        }
+    }
+}
+
+// Response POST /v2/{id}/refund
+#[derive(Debug, Clone, Deserialize)]
+pub struct MidtransRefundResponse {
+    pub status_code: String,
+    pub status_message: Option<String>,
+    pub refund_key: Option<String>,
+    pub refund_chargeback_id: Option<i64>,
+}
+
+// Hasil refund yang disimpan ke payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefundOutcome {
+    pub status: RefundStatus,
+    // Referensi refund dari Midtrans (refund_chargeback_id, fallback refund_key)
+    pub reference: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl RefundOutcome {
+    pub fn manual(detail: impl Into<String>) -> Self {
+        Self {
+            status: RefundStatus::ManualRequired,
+            reference: None,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+// Refund Midtrans yang gagal: ditolak pasti (4xx, refund belum terjadi) atau tidak pasti
+// (timeout, 5xx, response tidak terbaca) sehingga refund mungkin sudah diproses Midtrans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefundFailure {
+    Rejected(String),
+    Ambiguous(String),
+}
+
+pub fn supports_automated_refund(payment_type: Option<&str>) -> bool {
+    payment_type.is_some_and(|payment_type| AUTOMATED_REFUND_CHANNELS.contains(&payment_type))
+}
+
+// 200 = refund diterima, 412 = transaksi tidak bisa direfund otomatis (manual),
+// 4xx lain = ditolak, selain itu hasilnya tidak pasti
+pub fn outcome_from_response(response: &MidtransRefundResponse) -> Result<RefundOutcome, RefundFailure> {
+    let message = response.status_message.clone().unwrap_or_default();
+
+    match response.status_code.as_str() {
+        "200" => Ok(RefundOutcome {
+            status: RefundStatus::Completed,
+            reference: response
+                .refund_chargeback_id
+                .map(|id| id.to_string())
+                .or_else(|| response.refund_key.clone()),
+            detail: None,
+        }),
+        "412" => Ok(RefundOutcome::manual(format!("Midtrans menolak refund otomatis: {}", message))),
+        code => {
+            let detail = format!("Midtrans refund gagal ({}): {}", code, message);
+            match code.parse::<u16>() {
+                Ok(400..=499) => Err(RefundFailure::Rejected(detail)),
+                _ => Err(RefundFailure::Ambiguous(detail)),
+            }
+        }
+    }
+}
+
+// Cari refund dengan refund_key kita di response status transaksi Midtrans (array "refunds")
+pub fn refund_from_status(status_response: &serde_json::Value, refund_key: &str) -> Option<RefundOutcome> {
+    let refund = status_response
+        .get("refunds")?
+        .as_array()?
+        .iter()
+        .find(|refund| refund.get("refund_key").and_then(|key| key.as_str()) == Some(refund_key))?;
+
+    Some(RefundOutcome {
+        status: RefundStatus::Completed,
+        reference: refund
+            .get("refund_chargeback_id")
+            .and_then(|id| id.as_i64())
+            .map(|id| id.to_string())
+            .or_else(|| Some(refund_key.to_string())),
+        detail: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(json: &str) -> MidtransRefundResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_only_supported_channels_are_automated() {
+        assert!(supports_automated_refund(Some("gopay")));
+        assert!(supports_automated_refund(Some("credit_card")));
+        assert!(!supports_automated_refund(Some("bank_transfer")));
+        assert!(!supports_automated_refund(None));
+    }
+
+    #[test]
+    fn test_outcome_from_midtrans_response() {
+        let success = response(
+            r#"{"status_code":"200","status_message":"Success, refund request is approved","refund_chargeback_id":1234,"refund_key":"RF-1"}"#,
+        );
+        let outcome = outcome_from_response(&success).unwrap();
+        assert_eq!(outcome.status, RefundStatus::Completed);
+        assert_eq!(outcome.reference.as_deref(), Some("1234"));
+
+        let unsupported = response(r#"{"status_code":"412","status_message":"Merchant cannot modify the status of the transaction"}"#);
+        assert_eq!(outcome_from_response(&unsupported).unwrap().status, RefundStatus::ManualRequired);
+
+        let rejected = response(r#"{"status_code":"406","status_message":"Refund amount exceeds"}"#);
+        assert!(matches!(outcome_from_response(&rejected), Err(RefundFailure::Rejected(_))));
+
+        let failed = response(r#"{"status_code":"500","status_message":"Internal error"}"#);
+        let failure = outcome_from_response(&failed).unwrap_err();
+        assert!(matches!(failure, RefundFailure::Ambiguous(ref message) if message.contains("500")));
+    }
+
+    #[test]
+    fn test_refund_from_status() {
+        let status = serde_json::json!({
+            "transaction_status": "partial_refund",
+            "refunds": [
+                { "refund_chargeback_id": 11, "refund_key": "REF-LAIN", "refund_amount": "1000.00" },
+                { "refund_chargeback_id": 12, "refund_key": "REF-7", "refund_amount": "700000.00" }
+            ]
+        });
+
+        let outcome = refund_from_status(&status, "REF-7").unwrap();
+        assert_eq!(outcome.status, RefundStatus::Completed);
+        assert_eq!(outcome.reference.as_deref(), Some("12"));
+
+        assert!(refund_from_status(&status, "REF-8").is_none());
+        assert!(refund_from_status(&serde_json::json!({ "transaction_status": "settlement" }), "REF-7").is_none());
+    }
+}
@@ -0,0 +1,229 @@
+// Timeout dan retry panggilan Midtrans untuk kegagalan transient
+//
+// Charge sendiri hanya dikirim sekali: Midtrans menolak order_id yang sudah dipakai, jadi
+// mengulang charge dengan order_id yang sama tidak pernah berhasil. Yang di-retry adalah cek
+// status order_id setelah charge yang hasilnya tidak pasti (timeout, 5xx).
+
+use std::future::Future;
+use std::time::Duration;
+
+// Default timeout per panggilan charge
+pub const DEFAULT_CHARGE_TIMEOUT_SECS: u64 = 15;
+
+// Default retry cek status setelah percobaan pertama gagal
+pub const DEFAULT_CHARGE_MAX_RETRIES: u32 = 2;
+
+// Batas atas retry agar request customer tidak tertahan terlalu lama
+pub const MAX_CHARGE_RETRIES: u32 = 5;
+
+// Panjang maksimal body upstream yang disimpan di pesan error
+const MAX_DETAIL_LEN: usize = 300;
+
+// Kebijakan timeout + retry panggilan Midtrans
+#[derive(Debug, Clone, Copy)]
+pub struct ChargeRetryPolicy {
+    pub timeout: Duration,
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl ChargeRetryPolicy {
+    pub fn new(timeout_secs: u64, max_retries: u32) -> Self {
+        Self {
+            timeout: Duration::from_secs(timeout_secs.max(1)),
+            max_retries: max_retries.min(MAX_CHARGE_RETRIES),
+            base_delay: Duration::from_millis(500),
+        }
+    }
+
+    // Jeda sebelum retry ke-`attempt` (eksponensial: 0.5s, 1s, 2s, ...)
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        self.base_delay * 2_u32.pow(attempt.saturating_sub(1).min(6))
+    }
+}
+
+impl Default for ChargeRetryPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHARGE_TIMEOUT_SECS, DEFAULT_CHARGE_MAX_RETRIES)
+    }
+}
+
+// Semua percobaan gagal
+#[derive(Debug)]
+pub struct ChargeFailure {
+    pub attempts: u32,
+    pub detail: String,
+}
+
+// Status upstream yang layak di-retry
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+// Kirim request dengan retry; response non-transient (termasuk 4xx) langsung dikembalikan
+pub async fn send_with_retry<F, Fut>(
+    policy: &ChargeRetryPolicy,
+    mut send: F,
+) -> Result<reqwest::Response, ChargeFailure>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Response, reqwest::Error>>,
+{
+    let max_attempts = policy.max_retries + 1;
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let detail = match send().await {
+            Ok(response) if is_retryable_status(response.status().as_u16()) => {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                format!("HTTP {}: {}", status, truncate(&body))
+            }
+            Ok(response) => return Ok(response),
+            Err(e) if e.is_timeout() => {
+                format!("timeout setelah {} ms", policy.timeout.as_millis())
+            }
+            Err(e) if e.is_connect() => format!("koneksi gagal: {}", e),
+            Err(e) => {
+                return Err(ChargeFailure { attempts: attempt, detail: e.to_string() });
+            }
+        };
+
+        if attempt >= max_attempts {
+            return Err(ChargeFailure { attempts: attempt, detail });
+        }
+
+        tracing::warn!(
+            "Midtrans request percobaan {}/{} gagal ({}), retry",
+            attempt,
+            max_attempts,
+            detail
+        );
+        tokio::time::sleep(policy.retry_delay(attempt)).await;
+    }
+}
+
+fn truncate(body: &str) -> String {
+    body.trim().chars().take(MAX_DETAIL_LEN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    fn fast_policy(timeout: Duration, max_retries: u32) -> ChargeRetryPolicy {
+        ChargeRetryPolicy { timeout, max_retries, base_delay: Duration::from_millis(1) }
+    }
+
+    // Mock endpoint Midtrans /charge di port acak
+    async fn spawn_mock(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}/charge", addr)
+    }
+
+    // Mock yang gagal `failures` kali dengan status tertentu, lalu 200
+    async fn spawn_flaky(failures: u32, status: StatusCode) -> (String, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let router = Router::new().route(
+            "/charge",
+            post(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                        (status, "upstream error")
+                    } else {
+                        (StatusCode::OK, r#"{"status_code":"201"}"#)
+                    }
+                }
+            }),
+        );
+        (spawn_mock(router).await, calls)
+    }
+
+    #[tokio::test]
+    async fn test_transient_5xx_is_retried_until_success() {
+        let (url, calls) = spawn_flaky(2, StatusCode::SERVICE_UNAVAILABLE).await;
+        let client = reqwest::Client::new();
+        let policy = fast_policy(Duration::from_secs(2), 2);
+
+        let response = send_with_retry(&policy, || client.post(&url).timeout(policy.timeout).send())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Retry habis: detail upstream ikut dikembalikan
+        let (url, calls) = spawn_flaky(10, StatusCode::BAD_GATEWAY).await;
+        let failure = send_with_retry(&policy, || client.post(&url).timeout(policy.timeout).send())
+            .await
+            .unwrap_err();
+        assert_eq!(failure.attempts, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(failure.detail.contains("HTTP 502"));
+        assert!(failure.detail.contains("upstream error"));
+    }
+
+    #[tokio::test]
+    async fn test_client_error_is_not_retried() {
+        let (url, calls) = spawn_flaky(10, StatusCode::BAD_REQUEST).await;
+        let client = reqwest::Client::new();
+        let policy = fast_policy(Duration::from_secs(2), 2);
+
+        let response = send_with_retry(&policy, || client.post(&url).timeout(policy.timeout).send())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_endpoint_times_out_per_attempt() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let router = Router::new().route(
+            "/charge",
+            post(move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "terlambat"
+                }
+            }),
+        );
+        let url = spawn_mock(router).await;
+        let client = reqwest::Client::new();
+        let policy = fast_policy(Duration::from_millis(100), 1);
+
+        let started = std::time::Instant::now();
+        let failure = send_with_retry(&policy, || client.post(&url).timeout(policy.timeout).send())
+            .await
+            .unwrap_err();
+
+        assert_eq!(failure.attempts, 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(failure.detail.contains("timeout"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_policy_bounds() {
+        let policy = ChargeRetryPolicy::new(0, 99);
+        assert_eq!(policy.timeout, Duration::from_secs(1));
+        assert_eq!(policy.max_retries, MAX_CHARGE_RETRIES);
+        assert_eq!(policy.retry_delay(1), Duration::from_millis(500));
+        assert_eq!(policy.retry_delay(3), Duration::from_secs(2));
+        assert!(is_retryable_status(503) && is_retryable_status(429));
+        assert!(!is_retryable_status(400) && !is_retryable_status(200));
+    }
+}
@@ -1,2 +1,10 @@
 // Payment Service Utils
-pub mod jwt;
\ No newline at end of file
+pub mod midtrans_retry;
+pub mod midtrans_refund;
+pub mod midtrans_guard;
+pub mod payment_reconcile;
+pub mod resend_throttle;
+pub mod webhook_allowlist;
+pub mod payment_events;
+pub mod payment_status_batch;
+pub mod private_file;
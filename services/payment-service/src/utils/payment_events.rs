@@ -0,0 +1,139 @@
+// Event perubahan status payment untuk stream SSE checkout
+//
+// Webhook, resend, cancel, dan scheduler rekonsiliasi mem-publish status baru ke channel
+// broadcast in-process. Stream SSE juga membaca ulang status dari database secara berkala,
+// sehingga update dari instance lain tetap sampai walau tanpa event lokal.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+use crate::domain::payment::PaymentStatus;
+
+// Default jeda baca ulang status dari database selama stream terbuka
+pub const DEFAULT_PAYMENT_EVENTS_POLL_SECS: u64 = 15;
+
+// Kapasitas buffer broadcast, subscriber yang tertinggal akan membaca ulang dari database
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentEvent {
+    pub order_id: String,
+    pub status: PaymentStatus,
+}
+
+// Hub broadcast status payment, di-clone ke AppState
+#[derive(Clone)]
+pub struct PaymentEvents {
+    sender: broadcast::Sender<PaymentEvent>,
+}
+
+impl PaymentEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    // Tanpa subscriber event dibuang, bukan error
+    pub fn publish(&self, order_id: &str, status: PaymentStatus) {
+        let _ = self.sender.send(PaymentEvent { order_id: order_id.to_string(), status });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PaymentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for PaymentEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Status yang dikirim ke client checkout
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PaymentStatusSnapshot {
+    pub order_id: String,
+    pub status: PaymentStatus,
+    pub expired_at: Option<DateTime<Utc>>,
+    // Sisa detik sampai VA expired, hanya untuk payment pending
+    pub seconds_remaining: Option<i64>,
+    pub is_terminal: bool,
+}
+
+impl PaymentStatusSnapshot {
+    // Status apa adanya dari database: payment pending yang lewat expired_at tetap pending sampai
+    // webhook/rekonsiliasi mencatat expire, karena settlement Midtrans bisa datang terlambat
+    pub fn at(order_id: &str, status: PaymentStatus, expired_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Self {
+        let seconds_remaining = (status == PaymentStatus::Pending)
+            .then(|| expired_at.map(|expired| (expired - now).num_seconds().max(0)))
+            .flatten();
+
+        Self {
+            order_id: order_id.to_string(),
+            status,
+            expired_at,
+            seconds_remaining,
+            is_terminal: status != PaymentStatus::Pending,
+        }
+    }
+
+    // Tunggu sampai poll berikutnya, atau sampai VA expired jika lebih dulu
+    pub fn next_check_in(&self, poll_interval: Duration) -> Duration {
+        match self.seconds_remaining {
+            Some(remaining) if remaining > 0 => poll_interval.min(Duration::from_secs(remaining as u64)),
+            _ => poll_interval,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_countdown_and_expiry() {
+        let now = Utc::now();
+        let poll = Duration::from_secs(DEFAULT_PAYMENT_EVENTS_POLL_SECS);
+
+        let pending = PaymentStatusSnapshot::at("RNT-1", PaymentStatus::Pending, Some(now + chrono::Duration::seconds(90)), now);
+        assert_eq!(pending.status, PaymentStatus::Pending);
+        assert_eq!(pending.seconds_remaining, Some(90));
+        assert!(!pending.is_terminal);
+        assert_eq!(pending.next_check_in(poll), poll);
+
+        // Sisa 5 detik: cek berikutnya tepat saat expired
+        let almost = PaymentStatusSnapshot::at("RNT-1", PaymentStatus::Pending, Some(now + chrono::Duration::seconds(5)), now);
+        assert_eq!(almost.next_check_in(poll), Duration::from_secs(5));
+
+        // Lewat expired_at tapi database masih pending: stream tetap terbuka menunggu settlement terlambat
+        let overdue = PaymentStatusSnapshot::at("RNT-1", PaymentStatus::Pending, Some(now - chrono::Duration::seconds(1)), now);
+        assert_eq!(overdue.status, PaymentStatus::Pending);
+        assert_eq!(overdue.seconds_remaining, Some(0));
+        assert!(!overdue.is_terminal);
+        assert_eq!(overdue.next_check_in(poll), poll);
+
+        let expired = PaymentStatusSnapshot::at("RNT-1", PaymentStatus::Expired, Some(now - chrono::Duration::seconds(1)), now);
+        assert_eq!(expired.seconds_remaining, None);
+        assert!(expired.is_terminal);
+
+        // Lunas sebelum expired tetap success
+        let paid = PaymentStatusSnapshot::at("RNT-1", PaymentStatus::Success, Some(now - chrono::Duration::seconds(1)), now);
+        assert_eq!(paid.status, PaymentStatus::Success);
+        assert!(paid.is_terminal);
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let events = PaymentEvents::new();
+        events.publish("RNT-0", PaymentStatus::Success);
+
+        let mut receiver = events.subscribe();
+        events.publish("RNT-1", PaymentStatus::Success);
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event, PaymentEvent { order_id: "RNT-1".to_string(), status: PaymentStatus::Success });
+    }
+}
@@ -0,0 +1,90 @@
+// Rekonsiliasi payment pending yang webhook Midtrans-nya hilang
+//
+// Scheduler mengambil payment pending yang sudah lewat ambang umur (dan belum expired),
+// lalu mengecek status transaksi ke Midtrans satu per satu dengan jeda tetap.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::domain::payment::PaymentStatus;
+
+// Default interval scheduler rekonsiliasi
+pub const DEFAULT_RECONCILE_INTERVAL_SECS: u64 = 600;
+
+// Default umur minimal payment pending sebelum dicek (webhook normal biasanya datang dalam hitungan detik)
+pub const DEFAULT_RECONCILE_MIN_AGE_MINS: i64 = 15;
+
+// Default jumlah payment per putaran
+pub const DEFAULT_RECONCILE_BATCH_SIZE: i64 = 50;
+
+// Default batas panggilan status Midtrans per menit
+pub const DEFAULT_RECONCILE_CALLS_PER_MINUTE: u32 = 30;
+
+// Jeda antar panggilan status Midtrans agar tidak melewati batas per menit
+pub fn call_spacing(calls_per_minute: u32) -> Duration {
+    Duration::from_millis(60_000 / u64::from(calls_per_minute.max(1)))
+}
+
+// Status final dari response status Midtrans, None jika transaksi masih pending / belum pasti
+pub fn reconciled_status(transaction_status: Option<&str>, fraud_status: Option<&str>) -> Option<PaymentStatus> {
+    match transaction_status? {
+        "settlement" => Some(PaymentStatus::Success),
+        // Capture kartu yang di-challenge fraud detection menunggu review manual di dashboard
+        "capture" if fraud_status == Some("challenge") => None,
+        "capture" if fraud_status == Some("deny") => Some(PaymentStatus::Failed),
+        "capture" => Some(PaymentStatus::Success),
+        "deny" | "cancel" | "failure" => Some(PaymentStatus::Failed),
+        "expire" => Some(PaymentStatus::Expired),
+        "refund" | "partial_refund" => Some(PaymentStatus::Refunded),
+        _ => None,
+    }
+}
+
+// Ringkasan satu putaran rekonsiliasi untuk log
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReconcileSummary {
+    pub checked: u32,
+    pub updated: u32,
+    pub still_pending: u32,
+    pub failed: u32,
+}
+
+impl fmt::Display for ReconcileSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checked={} updated={} still_pending={} failed={}",
+            self.checked, self.updated, self.still_pending, self.failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_spacing() {
+        assert_eq!(call_spacing(30), Duration::from_secs(2));
+        assert_eq!(call_spacing(120), Duration::from_millis(500));
+        assert_eq!(call_spacing(0), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_reconciled_status() {
+        assert_eq!(reconciled_status(Some("settlement"), None), Some(PaymentStatus::Success));
+        assert_eq!(reconciled_status(Some("capture"), Some("accept")), Some(PaymentStatus::Success));
+        assert_eq!(reconciled_status(Some("capture"), Some("challenge")), None);
+        assert_eq!(reconciled_status(Some("expire"), None), Some(PaymentStatus::Expired));
+        assert_eq!(reconciled_status(Some("cancel"), None), Some(PaymentStatus::Failed));
+        assert_eq!(reconciled_status(Some("pending"), None), None);
+        assert_eq!(reconciled_status(Some("something_new"), None), None);
+        assert_eq!(reconciled_status(None, None), None);
+    }
+
+    #[test]
+    fn test_summary_display() {
+        let summary = ReconcileSummary { checked: 5, updated: 2, still_pending: 2, failed: 1 };
+        assert_eq!(summary.to_string(), "checked=5 updated=2 still_pending=2 failed=1");
+    }
+}
@@ -0,0 +1,209 @@
+// Status banyak payment sekaligus untuk layar checkout / riwayat order
+//
+// Satu query `order_id = ANY($1)` menggantikan N panggilan check_payment_status.
+// Order ID yang tidak ada atau bukan milik user tetap muncul di response dengan
+// result not_found / forbidden, tanpa detail payment.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::payment::{Payment, PaymentStatus};
+
+// Batas order ID per request batch
+pub const MAX_BATCH_ORDER_IDS: usize = 50;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct PaymentStatusBatchRequest {
+    #[schema(example = json!(["RNT-20260301-AB12CD", "SAL-20260302-EF34GH"]))]
+    pub order_ids: Vec<String>,
+}
+
+// Baris hasil query batch, has_access dihitung dari customer/buyer/seller booking atau order
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PaymentStatusRow {
+    #[sqlx(flatten)]
+    pub payment: Payment,
+    pub has_access: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchLookupResult {
+    Found,
+    NotFound,
+    Forbidden,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PaymentStatusBatchItem {
+    pub order_id: String,
+    pub result: BatchLookupResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<PaymentStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_expired: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expired_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_be_refunded: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_deadline: Option<DateTime<Utc>>,
+}
+
+impl PaymentStatusBatchItem {
+    fn without_details(order_id: &str, result: BatchLookupResult) -> Self {
+        Self {
+            order_id: order_id.to_string(),
+            result,
+            status: None,
+            transaction_id: None,
+            payment_type: None,
+            is_expired: None,
+            expired_at: None,
+            can_be_refunded: None,
+            refund_deadline: None,
+        }
+    }
+}
+
+// Trim, buang yang kosong dan duplikat (urutan dipertahankan), lalu cek batas
+pub fn normalize_order_ids(order_ids: &[String]) -> Result<Vec<String>, String> {
+    let mut unique: Vec<String> = Vec::with_capacity(order_ids.len());
+    for order_id in order_ids.iter().map(|id| id.trim()).filter(|id| !id.is_empty()) {
+        if !unique.iter().any(|existing| existing == order_id) {
+            unique.push(order_id.to_string());
+        }
+    }
+
+    if unique.is_empty() {
+        return Err("order_ids tidak boleh kosong".to_string());
+    }
+
+    if unique.len() > MAX_BATCH_ORDER_IDS {
+        return Err(format!("Maksimal {} order_id per request", MAX_BATCH_ORDER_IDS));
+    }
+
+    Ok(unique)
+}
+
+// Satu item per order ID sesuai urutan request, sama dengan check_payment_status per payment
+pub fn build_batch_items(
+    order_ids: &[String],
+    rows: &[PaymentStatusRow],
+    refund_window_days: i64,
+    now: DateTime<Utc>,
+) -> Vec<PaymentStatusBatchItem> {
+    order_ids
+        .iter()
+        .map(|order_id| match rows.iter().find(|row| &row.payment.order_id == order_id) {
+            None => PaymentStatusBatchItem::without_details(order_id, BatchLookupResult::NotFound),
+            Some(row) if !row.has_access => {
+                PaymentStatusBatchItem::without_details(order_id, BatchLookupResult::Forbidden)
+            }
+            Some(row) => {
+                let payment = &row.payment;
+                PaymentStatusBatchItem {
+                    order_id: payment.order_id.clone(),
+                    result: BatchLookupResult::Found,
+                    status: Some(payment.status),
+                    transaction_id: payment.transaction_id.clone(),
+                    payment_type: payment.payment_type.clone(),
+                    is_expired: Some(payment.is_expired_at(now)),
+                    expired_at: payment.expired_at,
+                    can_be_refunded: Some(payment.can_be_refunded_at(refund_window_days, now)),
+                    refund_deadline: payment.refund_deadline(refund_window_days),
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::payment::PaymentType;
+
+    fn row(order_id: &str, status: PaymentStatus, expired_at: Option<DateTime<Utc>>, has_access: bool) -> PaymentStatusRow {
+        PaymentStatusRow {
+            payment: Payment {
+                id: 1,
+                rental_booking_id: Some(1),
+                sale_order_id: None,
+                order_id: order_id.to_string(),
+                transaction_id: Some(format!("tx-{}", order_id)),
+                va_number: None,
+                bank: None,
+                payment_type: Some("bank_transfer".to_string()),
+                gross_amount: 1_000_000,
+                status,
+                payment_for_type: PaymentType::Rental,
+                refund_amount: None,
+                refund_reason: None,
+                paid_at: Some(Utc::now() - chrono::Duration::days(1)),
+                expired_at,
+                refunded_at: None,
+                receipt_pdf_path: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            has_access,
+        }
+    }
+
+    #[test]
+    fn test_normalize_order_ids() {
+        let ids = vec![" RNT-1 ".to_string(), "SAL-2".to_string(), "RNT-1".to_string(), "".to_string()];
+        assert_eq!(normalize_order_ids(&ids).unwrap(), vec!["RNT-1", "SAL-2"]);
+
+        assert!(normalize_order_ids(&[]).is_err());
+        assert!(normalize_order_ids(&["  ".to_string()]).is_err());
+
+        let too_many: Vec<String> = (0..=MAX_BATCH_ORDER_IDS).map(|i| format!("RNT-{}", i)).collect();
+        assert!(normalize_order_ids(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_batch_items_enforce_ownership_per_id() {
+        let now = Utc::now();
+        let ids: Vec<String> = ["RNT-1", "SAL-2", "DMG-3", "RNT-4"].iter().map(|s| s.to_string()).collect();
+        let rows = vec![
+            row("SAL-2", PaymentStatus::Pending, Some(now - chrono::Duration::minutes(5)), true),
+            row("RNT-1", PaymentStatus::Success, Some(now + chrono::Duration::hours(1)), true),
+            row("DMG-3", PaymentStatus::Success, None, false),
+        ];
+
+        let items = build_batch_items(&ids, &rows, 7, now);
+        let order: Vec<&str> = items.iter().map(|item| item.order_id.as_str()).collect();
+        assert_eq!(order, ids.iter().map(String::as_str).collect::<Vec<_>>());
+
+        assert_eq!(items[0].result, BatchLookupResult::Found);
+        assert_eq!(items[0].can_be_refunded, Some(true));
+        assert_eq!(items[0].refund_deadline, rows[1].payment.paid_at.map(|paid| paid + chrono::Duration::days(7)));
+        assert_eq!(items[1].is_expired, Some(true));
+        assert_eq!(items[1].can_be_refunded, Some(false));
+
+        // Payment milik user lain tidak membocorkan status
+        assert_eq!(items[2].result, BatchLookupResult::Forbidden);
+        assert_eq!(items[2].status, None);
+        assert_eq!(items[3].result, BatchLookupResult::NotFound);
+    }
+
+    #[test]
+    fn test_batch_refund_window() {
+        let now = Utc::now();
+        let ids = vec!["RNT-1".to_string()];
+        let mut paid_rental = row("RNT-1", PaymentStatus::Success, None, true);
+
+        paid_rental.payment.paid_at = Some(now - chrono::Duration::days(7));
+        let items = build_batch_items(&ids, std::slice::from_ref(&paid_rental), 7, now);
+        assert_eq!(items[0].can_be_refunded, Some(true));
+
+        paid_rental.payment.paid_at = Some(now - chrono::Duration::days(7) - chrono::Duration::seconds(1));
+        let items = build_batch_items(&ids, std::slice::from_ref(&paid_rental), 7, now);
+        assert_eq!(items[0].can_be_refunded, Some(false));
+    }
+}
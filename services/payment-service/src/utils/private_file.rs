@@ -0,0 +1,21 @@
+// Signed URL untuk receipt payment, path receipt mentah tidak dikirim ke client
+
+use chrono::Utc;
+use shared::utils::storage::{sign_file_token, FileTokenClaims};
+
+use crate::config::AppConfig;
+
+// Jenis resource di token, dibedakan dari dokumen booking-service (sale_ktp/rental_ktp)
+pub const PAYMENT_RECEIPT: &str = "payment_receipt";
+
+// Path signed `/api/payments/files/{token}` yang menggantikan receipt_pdf_path di response API
+pub fn signed_receipt_path(config: &AppConfig, payment_id: i32, receipt_path: &str) -> String {
+    let claims = FileTokenClaims {
+        url: receipt_path.to_string(),
+        resource: PAYMENT_RECEIPT.to_string(),
+        resource_id: payment_id,
+        exp: Utc::now().timestamp() + config.file_url_ttl_secs,
+    };
+
+    format!("/api/payments/files/{}", sign_file_token(config.file_url_secret.as_bytes(), &claims))
+}
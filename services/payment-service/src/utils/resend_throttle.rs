@@ -0,0 +1,171 @@
+// Throttle endpoint resend webhook
+//
+// Tiap resend memicu panggilan status ke Midtrans, jadi dibatasi per user (jumlah per window)
+// dan per payment (cooldown). Status payment yang baru saja dicek (oleh resend lain atau
+// scheduler rekonsiliasi) tidak dicek ulang ke Midtrans.
+
+use std::future::Future;
+
+use crate::middleware::rate_limit::RateLimitError;
+
+// Default cooldown resend per payment
+pub const DEFAULT_RESEND_PAYMENT_COOLDOWN_SECS: u64 = 60;
+
+// Default batas resend per user dalam satu window
+pub const DEFAULT_RESEND_USER_LIMIT: u64 = 5;
+pub const DEFAULT_RESEND_USER_WINDOW_SECS: u64 = 600;
+
+// Default jeda minimal antar cek status Midtrans untuk payment yang sama
+pub const DEFAULT_STATUS_RECHECK_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResendLimits {
+    pub payment_cooldown_secs: u64,
+    pub user_limit: u64,
+    pub user_window_secs: u64,
+}
+
+// Key counter/cooldown dengan TTL (Redis di production)
+pub trait CooldownStore {
+    // Set key jika belum ada: None jika berhasil, Some(sisa detik) jika key masih aktif
+    fn acquire(&self, key: &str, ttl_secs: u64) -> impl Future<Output = Result<Option<u64>, RateLimitError>> + Send;
+
+    // Tambah counter dalam window, return (jumlah termasuk hit ini, sisa detik window)
+    fn hit(&self, key: &str, window_secs: u64) -> impl Future<Output = Result<(u64, u64), RateLimitError>> + Send;
+}
+
+fn user_key(user_id: i32) -> String {
+    format!("payment_resend_user:{}", user_id)
+}
+
+fn cooldown_key(payment_id: i32) -> String {
+    format!("payment_resend_cooldown:{}", payment_id)
+}
+
+fn status_check_key(payment_id: i32) -> String {
+    format!("payment_status_checked:{}", payment_id)
+}
+
+// Ok jika resend boleh jalan, Err(retry_after detik) jika terkena limit
+// Redis error tidak memblokir resend (fail open, sama seperti rate_limit_middleware)
+pub async fn check_resend_allowed<S: CooldownStore>(
+    store: &S,
+    user_id: i32,
+    payment_id: i32,
+    limits: &ResendLimits,
+) -> Result<(), u64> {
+    match store.hit(&user_key(user_id), limits.user_window_secs).await {
+        Ok((count, remaining)) if count > limits.user_limit => return Err(remaining.max(1)),
+        Ok(_) => {}
+        Err(e) => tracing::error!("Resend throttle error (user {}): {}. Allowing request.", user_id, e),
+    }
+
+    match store.acquire(&cooldown_key(payment_id), limits.payment_cooldown_secs).await {
+        Ok(Some(remaining)) => Err(remaining.max(1)),
+        Ok(None) => Ok(()),
+        Err(e) => {
+            tracing::error!("Resend throttle error (payment {}): {}. Allowing request.", payment_id, e);
+            Ok(())
+        }
+    }
+}
+
+// Tandai payment akan dicek ke Midtrans, false jika sudah dicek dalam `recheck_secs` terakhir
+pub async fn claim_status_check<S: CooldownStore>(store: &S, payment_id: i32, recheck_secs: u64) -> bool {
+    match store.acquire(&status_check_key(payment_id), recheck_secs).await {
+        Ok(remaining) => remaining.is_none(),
+        Err(e) => {
+            tracing::error!("Status check marker error (payment {}): {}", payment_id, e);
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    // Store in-memory dengan jam palsu (detik), meniru semantik SET NX EX dan INCR + EXPIRE
+    #[derive(Default)]
+    struct FakeStore {
+        now: Mutex<u64>,
+        keys: Mutex<HashMap<String, (u64, u64)>>,
+    }
+
+    impl FakeStore {
+        fn advance(&self, secs: u64) {
+            *self.now.lock().unwrap() += secs;
+        }
+
+        fn live(&self, key: &str) -> Option<(u64, u64)> {
+            let now = *self.now.lock().unwrap();
+            self.keys.lock().unwrap().get(key).copied().filter(|(_, expires_at)| *expires_at > now)
+        }
+    }
+
+    impl CooldownStore for FakeStore {
+        async fn acquire(&self, key: &str, ttl_secs: u64) -> Result<Option<u64>, RateLimitError> {
+            let now = *self.now.lock().unwrap();
+            if let Some((_, expires_at)) = self.live(key) {
+                return Ok(Some(expires_at - now));
+            }
+            self.keys.lock().unwrap().insert(key.to_string(), (1, now + ttl_secs));
+            Ok(None)
+        }
+
+        async fn hit(&self, key: &str, window_secs: u64) -> Result<(u64, u64), RateLimitError> {
+            let now = *self.now.lock().unwrap();
+            let (count, expires_at) = self.live(key).map_or((1, now + window_secs), |(c, e)| (c + 1, e));
+            self.keys.lock().unwrap().insert(key.to_string(), (count, expires_at));
+            Ok((count, expires_at - now))
+        }
+    }
+
+    fn limits() -> ResendLimits {
+        ResendLimits {
+            payment_cooldown_secs: 60,
+            user_limit: 3,
+            user_window_secs: 600,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rapid_second_call_is_throttled() {
+        let store = FakeStore::default();
+
+        assert_eq!(check_resend_allowed(&store, 7, 42, &limits()).await, Ok(()));
+
+        store.advance(5);
+        assert_eq!(check_resend_allowed(&store, 7, 42, &limits()).await, Err(55));
+
+        // Setelah cooldown payment habis boleh lagi
+        store.advance(55);
+        assert_eq!(check_resend_allowed(&store, 7, 42, &limits()).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_user_limit_across_payments() {
+        let store = FakeStore::default();
+
+        for payment_id in 1..=3 {
+            assert_eq!(check_resend_allowed(&store, 7, payment_id, &limits()).await, Ok(()));
+        }
+        assert_eq!(check_resend_allowed(&store, 7, 4, &limits()).await, Err(600));
+
+        // User lain tidak terpengaruh
+        assert_eq!(check_resend_allowed(&store, 8, 4, &limits()).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_recent_status_check_short_circuits() {
+        let store = FakeStore::default();
+
+        assert!(claim_status_check(&store, 42, 30).await);
+        store.advance(10);
+        assert!(!claim_status_check(&store, 42, 30).await);
+        store.advance(20);
+        assert!(claim_status_check(&store, 42, 30).await);
+    }
+}
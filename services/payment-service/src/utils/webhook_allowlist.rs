@@ -0,0 +1,60 @@
+// Allowlist IP sumber webhook Midtrans
+//
+// Defense in depth: request dari luar allowlist ditolak sebelum payload diproses,
+// tapi signature tetap penentu utama keaslian notifikasi.
+
+// IP notifikasi HTTP(S) Midtrans sesuai dokumentasi Midtrans, override via MIDTRANS_WEBHOOK_IP_ALLOWLIST
+pub const DEFAULT_MIDTRANS_WEBHOOK_IPS: &str = "103.208.23.0/24,103.127.16.0/23,34.87.92.33/32,34.87.59.67/32,35.186.147.251/32,34.87.157.231/32,34.101.178.4/32";
+
+// IP sumber di belakang proxy tepercaya, dipakai bersama rate limiter service lain
+pub use shared::utils::client_ip::{source_ip, IpAllowlist};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderMap;
+    use shared::utils::client_ip::DEFAULT_TRUSTED_PROXY_CIDRS;
+    use std::net::IpAddr;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_default_allowlist_ranges() {
+        let allowlist = IpAllowlist::parse(DEFAULT_MIDTRANS_WEBHOOK_IPS).unwrap();
+
+        assert!(allowlist.contains(ip("103.208.23.6")));
+        assert!(allowlist.contains(ip("103.127.17.255")));
+        assert!(allowlist.contains(ip("34.87.92.33")));
+        // IPv4-mapped IPv6 dari listener dual-stack
+        assert!(allowlist.contains(ip("::ffff:103.208.23.6")));
+
+        assert!(!allowlist.contains(ip("103.208.24.1")));
+        assert!(!allowlist.contains(ip("34.87.92.34")));
+        assert!(!allowlist.contains(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn test_midtrans_ip_behind_trusted_proxy_is_allowed() {
+        let trusted = IpAllowlist::parse(DEFAULT_TRUSTED_PROXY_CIDRS).unwrap();
+        let midtrans = IpAllowlist::parse(DEFAULT_MIDTRANS_WEBHOOK_IPS).unwrap();
+        // Koneksi dari gateway nginx di network docker
+        let gateway = ip("172.18.0.5");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "103.208.23.6".parse().unwrap());
+        let source = source_ip(gateway, &headers, Some(&trusted));
+        assert_eq!(source, ip("103.208.23.6"));
+        assert!(midtrans.contains(source));
+
+        // Hop proxy internal di kanan dilewati
+        headers.insert("x-forwarded-for", "103.208.23.6, 10.0.0.5".parse().unwrap());
+        assert_eq!(source_ip(gateway, &headers, Some(&trusted)), ip("103.208.23.6"));
+
+        // Tanpa X-Forwarded-For, X-Real-IP dari proxy dipakai
+        let mut real_ip = HeaderMap::new();
+        real_ip.insert("x-real-ip", "34.87.92.33".parse().unwrap());
+        assert_eq!(source_ip(gateway, &real_ip, Some(&trusted)), ip("34.87.92.33"));
+    }
+}
@@ -5,6 +5,18 @@ use sqlx::{
 use std::env;
 use std::time::Duration;
 use crate::middleware::rate_limit::RateLimiter;
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+use shared::utils::bind_addr;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("users", &["id", "email", "name", "is_seller", "profile_photo"]),
+    ("reviews", &["id", "seller_id", "customer_id", "vehicle_id", "overall_rating", "is_visible"]),
+    ("favorites", &["id", "customer_id", "vehicle_id", "price_alerts_muted"]),
+    ("rental_bookings", &["id", "customer_id", "seller_id", "status"]),
+    ("sale_orders", &["id", "buyer_id", "seller_id", "status"]),
+];
 
 // Konfigurasi utama aplikasi yang di-load dari environment variables
 #[derive(Debug, Clone)]
@@ -29,13 +41,10 @@ impl AppConfig {
             return Err("JWT_SECRET masih menggunakan default value! Ganti dengan value yang aman untuk production".to_string());
         }
 
-        let server_host = env::var("USER_SERVICE_HOST")
-            .unwrap_or_else(|_| "0.0.0.0".to_string());
-
-        let server_port = env::var("USER_SERVICE_PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3002);
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("USER_SERVICE_HOST", Some(bind_addr::DEFAULT_HOST))?;
+        let server_port = bind_addr::port_from_env("USER_SERVICE_PORT", Some(3002))?;
+        bind_addr::resolve("USER_SERVICE_HOST", &server_host, server_port)?;
 
         let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
 
@@ -80,11 +89,12 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 }
 
 // Health check untuk database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
         .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 // State aplikasi yang akan di-share ke semua handlers
@@ -118,12 +128,14 @@ impl axum::extract::FromRef<AppState> for RateLimiter {
 
 impl AppState {
     // Buat AppState baru dengan semua dependensi
-    pub async fn new() -> Result<Self, String> {
-        let config = AppConfig::from_env()?;
+    pub async fn new(config: AppConfig) -> Result<Self, String> {
         let db = init_db_pool(&config.database_url)
             .await
             .map_err(|e| format!("Gagal menginisialisasi database: {}", e))?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "user-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         // Initialize Redis rate limiter
         let redis_url = env::var("REDIS_URL")
             .unwrap_or_else(|_| {
@@ -148,23 +160,25 @@ impl AppState {
 
     // Health check untuk dependencies
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
-
-        HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy {
-                "healthy"
-            } else {
-                "degraded"
-            }
-            .to_string(),
-        }
+        HealthStatus::check(&self.db).await
     }
 }
 
 // Struktur untuk response health check endpoint
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
 pub struct HealthStatus {
-    pub database: String,
-    pub overall: String,
+    pub database: DependencyHealth,
+    pub overall: HealthLevel,
+}
+
+impl HealthStatus {
+    // Probe database dengan latency; detail error hanya masuk log
+    pub async fn check(pool: &PgPool) -> Self {
+        let database = health::probe("database", check_db_health(pool)).await;
+
+        HealthStatus {
+            overall: health::worst_status(&[&database]),
+            database,
+        }
+    }
 }
\ No newline at end of file
@@ -9,6 +9,7 @@ pub struct Favorite {
     pub id: i32,
     pub customer_id: i32,
     pub vehicle_id: i32,
+    pub price_alerts_muted: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -23,6 +24,16 @@ pub struct AddFavoriteRequest {
     pub vehicle_id: i32,
 }
 
+// Request mute/unmute notifikasi penurunan harga satu favorite
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "muted": true
+}))]
+pub struct PriceAlertRequest {
+    /// true = tidak menerima notifikasi price drop untuk vehicle ini
+    pub muted: bool,
+}
+
 // Response favorite dengan info vehicle
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
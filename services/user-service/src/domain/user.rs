@@ -39,6 +39,34 @@ pub struct UserProfile {
     pub profile_photo: Option<String>,
     pub business_name: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Statistik waktu respon (hanya untuk seller)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_stats: Option<SellerResponseStats>,
+}
+
+// Batas badge "fast responder": rata-rata respon <= 60 menit dari minimal 3 respon
+const FAST_RESPONDER_MAX_MINUTES: f64 = 60.0;
+const FAST_RESPONDER_MIN_RESPONSES: i64 = 3;
+
+// Rata-rata waktu respon seller (rolling 90 hari) dari view seller_response_stats
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerResponseStats {
+    pub response_count: i64,
+    pub avg_response_minutes: Option<f64>,
+    pub is_fast_responder: bool,
+}
+
+impl SellerResponseStats {
+    pub fn new(response_count: i64, avg_response_minutes: Option<f64>) -> Self {
+        let is_fast_responder = response_count >= FAST_RESPONDER_MIN_RESPONSES
+            && avg_response_minutes.is_some_and(|avg| avg <= FAST_RESPONDER_MAX_MINUTES);
+
+        Self {
+            response_count,
+            avg_response_minutes,
+            is_fast_responder,
+        }
+    }
 }
 
 impl From<User> for UserProfile {
@@ -55,6 +83,7 @@ impl From<User> for UserProfile {
             profile_photo: user.profile_photo,
             business_name: user.business_name,
             created_at: user.created_at,
+            response_stats: None,
         }
     }
 }
@@ -2,6 +2,7 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde_json::json;
+use shared::utils::request_id;
 use thiserror::Error;
 
 // Custom error types untuk user-service
@@ -67,12 +68,20 @@ impl IntoResponse for AppError {
             }
         };
 
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
         let body = Json(json!({
             "error": error_type,
             "message": message,
+            "request_id": request_id,
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        response
     }
 }
 // Helper untuk membuat error response
@@ -105,3 +114,19 @@ impl AppError {
         Self::Cloudinary(msg.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("User tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
+    }
+}
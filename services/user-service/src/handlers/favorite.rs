@@ -10,7 +10,7 @@ use utoipa::ToSchema;
 
 use crate::{
     config::AppConfig,
-    domain::favorite::{Favorite, AddFavoriteRequest, FavoriteWithVehicle, CheckFavoriteResponse},
+    domain::favorite::{Favorite, AddFavoriteRequest, FavoriteWithVehicle, CheckFavoriteResponse, PriceAlertRequest},
     error::AppError,
     middleware::AuthUser,
 };
@@ -143,6 +143,32 @@ pub async fn remove_favorite(
 }
 
 
+// Mute/unmute notifikasi penurunan harga untuk satu favorite
+#[utoipa::path(
+    put,
+    path = "/api/users/me/favorites/{vehicle_id}/price-alerts",
+    tag = "Favorites",
+    security(("bearer_auth" = [])),
+    params(
+        ("vehicle_id" = i32, Path, description = "Vehicle ID")
+    ),
+    request_body = PriceAlertRequest,
+    responses(
+        (status = 200, description = "Pengaturan notifikasi harga diperbarui", body = Favorite),
+        (status = 404, description = "Favorite tidak ditemukan"),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+pub async fn set_price_alerts(
+    auth: AuthUser,
+    Path(vehicle_id): Path<i32>,
+    State(pool): State<PgPool>,
+    Json(payload): Json<PriceAlertRequest>,
+) -> Result<Json<Favorite>, AppError> {
+    let favorite = update_price_alerts(&pool, auth.user_id, vehicle_id, payload.muted).await?;
+    Ok(Json(favorite))
+}
+
 // Cek apakah vehicle sudah di-favorite
 #[utoipa::path(
     get,
@@ -239,6 +265,29 @@ async fn delete_favorite(pool: &PgPool, customer_id: i32, vehicle_id: i32) -> Re
     Ok(())
 }
 
+// Update flag mute notifikasi harga
+async fn update_price_alerts(
+    pool: &PgPool,
+    customer_id: i32,
+    vehicle_id: i32,
+    muted: bool,
+) -> Result<Favorite, AppError> {
+    let result = sqlx::query(
+        "UPDATE favorites SET price_alerts_muted = $3
+         WHERE customer_id = $1 AND vehicle_id = $2
+         RETURNING *"
+    )
+    .bind(customer_id)
+    .bind(vehicle_id)
+    .bind(muted)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Favorite tidak ditemukan"))?;
+
+    let favorite = Favorite::from_row(&result)?;
+    Ok(favorite)
+}
+
 // Cek apakah vehicle sudah di-favorite
 async fn is_vehicle_favorited(pool: &PgPool, customer_id: i32, vehicle_id: i32) -> Result<bool, AppError> {
     let result = sqlx::query(
@@ -9,7 +9,7 @@ use utoipa::ToSchema;
 
 use crate::{
     config::AppConfig,
-    domain::user::{User, UserProfile, UpdateProfileRequest, UpgradeToSellerRequest, UploadPhotoResponse},
+    domain::user::{User, UserProfile, SellerResponseStats, UpdateProfileRequest, UpgradeToSellerRequest, UploadPhotoResponse},
     error::AppError,
     middleware::AuthUser,
 };
@@ -43,7 +43,7 @@ pub async fn get_my_profile(
     );
 
     let user = find_user_by_id(&pool, auth.user_id).await?;
-    Ok(Json(UserProfile::from(user)))
+    Ok(Json(build_profile(&pool, user).await?))
 }
 
 // Ambil profile user berdasarkan ID (public)
@@ -66,7 +66,7 @@ pub async fn get_user_profile(
     State(pool): State<PgPool>,
 ) -> Result<Json<UserProfile>, AppError> {
     let user = find_user_by_id(&pool, user_id).await?;
-    Ok(Json(UserProfile::from(user)))
+    Ok(Json(build_profile(&pool, user).await?))
 }
 
 // Update profile user
@@ -234,6 +234,34 @@ async fn find_user_by_id(pool: &PgPool, user_id: i32) -> Result<User, AppError>
     }
 }
 
+// Build profile, seller mendapat statistik waktu respon
+async fn build_profile(pool: &PgPool, user: User) -> Result<UserProfile, AppError> {
+    let is_seller = user.is_seller;
+    let user_id = user.id;
+    let mut profile = UserProfile::from(user);
+
+    if is_seller {
+        profile.response_stats = Some(find_response_stats(pool, user_id).await?);
+    }
+
+    Ok(profile)
+}
+
+// Ambil rata-rata waktu respon seller
+async fn find_response_stats(pool: &PgPool, seller_id: i32) -> Result<SellerResponseStats, AppError> {
+    let row: Option<(i64, Option<f64>)> = sqlx::query_as(
+        "SELECT response_count, avg_response_minutes::FLOAT8 FROM seller_response_stats WHERE seller_id = $1"
+    )
+    .bind(seller_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match row {
+        Some((count, avg)) => SellerResponseStats::new(count, avg),
+        None => SellerResponseStats::new(0, None),
+    })
+}
+
 // Update profile user
 async fn update_user_profile(
     pool: &PgPool,
@@ -373,3 +401,56 @@ async fn extract_image_from_multipart(
 
     Err(AppError::bad_request("File tidak ditemukan dalam form"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_responder_threshold() {
+        assert!(SellerResponseStats::new(3, Some(60.0)).is_fast_responder);
+
+        // Rata-rata lebih dari 60 menit, respon terlalu sedikit, atau belum ada data
+        assert!(!SellerResponseStats::new(3, Some(60.5)).is_fast_responder);
+        assert!(!SellerResponseStats::new(2, Some(5.0)).is_fast_responder);
+        assert!(!SellerResponseStats::new(0, None).is_fast_responder);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_profile_includes_seller_response_stats(pool: PgPool) {
+        // Seller 2 membalas 3 conversation dalam 10, 20 dan 30 menit
+        sqlx::query(
+            "INSERT INTO conversations (customer_id, seller_id, vehicle_id, is_general, created_at, first_response_at)
+             VALUES (1, 2, 1, false, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '110 minutes'),
+                    (1, 2, 2, false, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '100 minutes'),
+                    (1, 2, NULL, true, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '90 minutes')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let seller = build_profile(&pool, find_user_by_id(&pool, 2).await.unwrap()).await.unwrap();
+        let stats = seller.response_stats.unwrap();
+        assert_eq!(stats.response_count, 3);
+        assert!((stats.avg_response_minutes.unwrap() - 20.0).abs() < 0.01);
+        assert!(stats.is_fast_responder);
+
+        // Seller tanpa respon tetap dapat statistik kosong, customer tidak dapat statistik
+        let quiet = build_profile(&pool, find_user_by_id(&pool, 3).await.unwrap()).await.unwrap();
+        let stats = quiet.response_stats.unwrap();
+        assert_eq!(stats.response_count, 0);
+        assert_eq!(stats.avg_response_minutes, None);
+        assert!(!stats.is_fast_responder);
+
+        let customer = build_profile(&pool, find_user_by_id(&pool, 1).await.unwrap()).await.unwrap();
+        assert!(customer.response_stats.is_none());
+        assert!(serde_json::to_value(&customer).unwrap().get("response_stats").is_none());
+    }
+}
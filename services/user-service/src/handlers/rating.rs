@@ -3,6 +3,7 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use shared::auth::Role;
 use shared::utils::validation;
 use sqlx::{PgPool, Row, FromRow};
 use utoipa::{IntoParams, ToSchema};
@@ -66,7 +67,7 @@ pub async fn submit_review(
     );
 
     // Hanya customer yang bisa review seller
-    if auth.role != "customer" {
+    if !Role::Customer.matches(&auth.role) {
         return Err(AppError::forbidden("Only customers can submit reviews"));
     }
 
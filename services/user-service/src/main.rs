@@ -1,4 +1,4 @@
-use tower_http::trace::TraceLayer;
+use shared::utils::{bind_addr, health::HealthLevel, logging, request_id, startup_gate::StartupGate};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
@@ -21,14 +21,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "user_service=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(logging::fmt_layer())
         .init();
 
     tracing::info!("🚀 Starting Big Auto - User Service");
 
     // Initialize AppState dengan database connection
     tracing::info!("🔌 Initializing application state...");
-    let state = config::AppState::new().await
+    let config = config::AppConfig::from_env()
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    // Listener dibind sebelum koneksi database, request dijawab 503 sampai state siap
+    let addr = bind_addr::resolve("USER_SERVICE_HOST", &config.server_host, config.server_port)?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let gate = StartupGate::new(&["/health"]);
+    let server = tokio::spawn({
+        let router = gate.router();
+        async move { axum::serve(listener, router).await }
+    });
+
+    let state = config::AppState::new(config).await
         .map_err(|e| format!("Failed to initialize app state: {}", e))?;
     tracing::info!("✅ Application state initialized");
 
@@ -41,10 +53,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Test database connectivity
     let health = state.health_check().await;
-    if health.overall == "healthy" {
+    if health.overall == HealthLevel::Healthy {
         tracing::info!("✅ Database health check passed");
     } else {
-        tracing::warn!("⚠️ Health check: Database {}", health.database);
+        tracing::warn!("⚠️ Health check: Database {:?}", health.database.status);
     }
 
     // Start background cleanup scheduler
@@ -53,20 +65,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("✅ Background cleanup scheduler started");
 
     // Create router dengan CORS
-    let app = routes::create_router(state.clone())
-        .layer(TraceLayer::new_for_http());
+    let app = request_id::apply(routes::create_router(state.clone()));
 
-    // Server address
-    let addr = format!("{}:{}", state.config.server_host, state.config.server_port);
     tracing::info!("🎯 User Service listening on {}", addr);
     tracing::info!("📚 API Documentation:");
     tracing::info!("   - Swagger UI: http://{}/swagger-ui", addr);
     tracing::info!("   - ReDoc: http://{}/redoc", addr);
     tracing::info!("🌍 Environment: {}", state.config.environment);
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    // Router asli mulai melayani request
+    gate.ready(app);
+    server.await??;
 
     Ok(())
 }
\ No newline at end of file
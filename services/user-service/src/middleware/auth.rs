@@ -7,6 +7,7 @@ use axum::{
     middleware::Next,
 };
 use crate::{config::AppState, error::AppError};
+use shared::auth::Role;
 
 // Import JWT validation from utils
 use crate::utils::jwt;
@@ -62,7 +63,7 @@ where
             .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
 
         // Validasi role seller
-        if auth_user.role != "seller" {
+        if !Role::Seller.matches(&auth_user.role) {
             return Err(AppError::forbidden("Seller authentication required"));
         }
 
@@ -5,13 +5,16 @@ use axum::{
 };
 use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
+use shared::utils::cors::CorsPolicy;
+use shared::utils::request_timeout;
 use utoipa::{OpenApi, Modify};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_redoc::{Redoc, Servable};
 use crate::{
     handlers::{profile, favorite, rating},
-    config::{AppState, HealthStatus, check_db_health},
+    config::{AppState, HealthStatus},
+    error::AppError,
     middleware::{auth::auth_middleware, rate_limit::rate_limit_middleware},
 };
 
@@ -54,6 +57,7 @@ impl Modify for SecurityAddon {
         favorite::add_favorite,
         favorite::remove_favorite,
         favorite::check_favorite,
+        favorite::set_price_alerts,
         // Rating endpoints
         rating::submit_review,
         rating::get_seller_ratings,
@@ -66,6 +70,7 @@ impl Modify for SecurityAddon {
         schemas(
             // Profile schemas
             crate::domain::user::UserProfile,
+            crate::domain::user::SellerResponseStats,
             crate::domain::user::UpdateProfileRequest,
             crate::domain::user::UpgradeToSellerRequest,
             crate::domain::user::UploadPhotoResponse,
@@ -75,6 +80,7 @@ impl Modify for SecurityAddon {
             crate::domain::favorite::AddFavoriteRequest,
             crate::domain::favorite::FavoriteWithVehicle,
             crate::domain::favorite::CheckFavoriteResponse,
+            crate::domain::favorite::PriceAlertRequest,
             favorite::MessageResponse,
             // Rating schemas
             crate::domain::review::CreateReviewRequest,
@@ -94,23 +100,13 @@ struct ApiDoc;
 
 // Health check handler
 async fn health_check(State(pool): State<PgPool>) -> Json<HealthStatus> {
-    let db_healthy = check_db_health(&pool).await;
-
-    Json(HealthStatus {
-        database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-        overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-    })
+    Json(HealthStatus::check(&pool).await)
 }
 
 /// Build JWT-Only CORS configuration untuk User Service
 fn configure_cors() -> CorsLayer {
-    let frontend_urls = std::env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable harus diset");
-
-    let allowed_origins: Vec<HeaderValue> = frontend_urls
-        .split(',')
-        .map(|s| s.trim().parse::<HeaderValue>().expect("Invalid FRONTEND_URL format"))
-        .collect();
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     let allowed_methods = vec![
         Method::GET,
@@ -132,11 +128,10 @@ fn configure_cors() -> CorsLayer {
         std::time::Duration::from_secs(3600)    
     };
 
-    CorsLayer::new()
-        .allow_origin(allowed_origins)
+    policy
+        .apply(CorsLayer::new())
         .allow_methods(allowed_methods)
         .allow_headers(allowed_headers)
-        .allow_credentials(false)
         .max_age(max_age)
 }
 
@@ -171,6 +166,11 @@ async fn security_headers_middleware(
 
 
 
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
+}
+
 // Buat router dengan JWT-Only security
 pub fn create_router(state: AppState) -> Router {
     // OpenAPI documentation
@@ -179,10 +179,13 @@ pub fn create_router(state: AppState) -> Router {
     // Complete router dengan security layers
     Router::new()
         .route("/health", get(health_check).with_state(state.db.clone()))
+        // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi.clone()))
         .merge(Redoc::with_url("/redoc", openapi))
         // Merge API 
         .merge(create_jwt_protected_routes(state))
+        .fallback(not_found_handler)
         // CORS layer 
         .layer(configure_cors())
         // Security headers 
@@ -191,6 +194,9 @@ pub fn create_router(state: AppState) -> Router {
 
 // All API routes require JWT authentication
 fn create_jwt_protected_routes(state: AppState) -> Router {
+    // Upload foto multipart boleh lebih lama dari timeout default
+    let upload_timeout = request_timeout::route_timeout("UPLOAD_REQUEST_TIMEOUT_SECS", 120);
+
     let read_routes = Router::new()
         // READ endpoints
         .route("/api/users/me", get(profile::get_my_profile))
@@ -199,16 +205,21 @@ fn create_jwt_protected_routes(state: AppState) -> Router {
         .route("/api/users/me/favorites/check/{vehicle_id}", get(favorite::check_favorite))
         .route("/api/sellers/{seller_id}/ratings", get(rating::get_seller_ratings))
         .route("/api/sellers/{seller_id}/rating-summary", get(rating::get_seller_rating_summary))
-        .route("/api/sellers/me/reviews", get(rating::get_my_seller_reviews));
+        .route("/api/sellers/me/reviews", get(rating::get_my_seller_reviews))
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()));
 
     let write_routes = Router::new()
         // WRITE endpoints
         .route("/api/users/me", put(profile::update_profile))
-        .route("/api/users/me/photo", post(profile::upload_profile_photo))
         .route("/api/users/me/upgrade-seller", post(profile::upgrade_to_seller))
         .route("/api/users/me/favorites", post(favorite::add_favorite))
         .route("/api/users/me/favorites/{vehicle_id}", delete(favorite::remove_favorite))
+        .route("/api/users/me/favorites/{vehicle_id}/price-alerts", put(favorite::set_price_alerts))
         .route("/api/sellers/{seller_id}/reviews", post(rating::submit_review))
+        // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
+        // Upload memakai UPLOAD_REQUEST_TIMEOUT_SECS sebagai pengganti timeout default
+        .route("/api/users/me/photo", post(profile::upload_profile_photo).layer(request_timeout::layer(upload_timeout)))
         // Apply strict rate limiting to write operations
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
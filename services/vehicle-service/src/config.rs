@@ -1,7 +1,24 @@
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::env;
 use std::time::Duration;
+use shared::utils::storage::StorageBackend;
 use crate::middleware::rate_limit::RateLimiter;
+use crate::utils::price_drop;
+use shared::utils::schema_check::{verify_schema, SchemaRequirements};
+use shared::utils::bind_addr;
+use shared::utils::internal_notifications;
+use shared::utils::health::{self, DependencyHealth, HealthLevel};
+
+// Tabel dan kolom yang wajib ada, dicek saat startup (lihat shared::utils::schema_check)
+const REQUIRED_SCHEMA: SchemaRequirements = &[
+    ("vehicles", &["id", "seller_id", "category", "price", "status", "condition_grade", "deposit_amount"]),
+    ("vehicle_images", &["id", "vehicle_id", "url", "position", "is_primary"]),
+    ("vehicle_price_drops", &["vehicle_id", "previous_price", "current_price", "notify_after"]),
+    ("vehicle_inspections", &["vehicle_id", "overall_score", "condition_grade"]),
+    ("favorites", &["customer_id", "vehicle_id"]),
+    ("vehicle_brands", &["id", "name", "logo_url", "is_active"]),
+    ("vehicle_models", &["id", "brand_id", "name", "vehicle_type", "is_active"]),
+];
 
 // Konfigurasi utama aplikasi yang di-load dari environment variables
 #[derive(Debug, Clone)]
@@ -10,6 +27,12 @@ pub struct AppConfig {
     pub server_host: String,
     pub server_port: u16,
     pub environment: String,
+    // Window debounce notifikasi penurunan harga (menit)
+    pub price_drop_debounce_minutes: i64,
+    // Notification-service untuk notifikasi price drop (POST /internal/notifications)
+    pub notification_service_url: String,
+    // Secret HMAC request internal ke notification-service, None = notifikasi price drop ditahan
+    pub internal_service_secret: Option<String>,
 }
 
 impl AppConfig {
@@ -27,21 +50,31 @@ impl AppConfig {
             return Err("JWT_SECRET masih menggunakan default value! Ganti dengan value yang aman untuk production".to_string());
         }
 
-        let server_host = env::var("VEHICLE_SERVICE_HOST")
-            .unwrap_or_else(|_| "0.0.0.0".to_string());
+        // Host/port divalidasi saat startup, salah konfigurasi langsung gagal
+        let server_host = bind_addr::host_from_env("VEHICLE_SERVICE_HOST", Some(bind_addr::DEFAULT_HOST))?;
+        let server_port = bind_addr::port_from_env("VEHICLE_SERVICE_PORT", Some(3003))?;
+        bind_addr::resolve("VEHICLE_SERVICE_HOST", &server_host, server_port)?;
 
-        let server_port = env::var("VEHICLE_SERVICE_PORT")
+        let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
+
+        let price_drop_debounce_minutes = env::var("PRICE_DROP_DEBOUNCE_MINUTES")
             .ok()
             .and_then(|s| s.parse().ok())
-            .unwrap_or(3003);
+            .filter(|minutes: &i64| *minutes > 0)
+            .unwrap_or(price_drop::DEFAULT_DEBOUNCE_MINUTES);
 
-        let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
+        let notification_service_url = env::var("NOTIFICATION_SERVICE_URL")
+            .unwrap_or_else(|_| "http://localhost:3007".to_string());
+        let internal_service_secret = internal_notifications::secret_from_env();
 
         Ok(AppConfig {
             database_url,
             server_host,
             server_port,
             environment,
+            price_drop_debounce_minutes,
+            notification_service_url,
+            internal_service_secret,
         })
     }
 
@@ -75,11 +108,12 @@ pub async fn init_db_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 }
 
 // Health check untuk database connection
-pub async fn check_db_health(pool: &PgPool) -> bool {
+pub async fn check_db_health(pool: &PgPool) -> Result<(), String> {
     sqlx::query("SELECT 1")
         .fetch_one(pool)
         .await
-        .is_ok()
+        .map(|_| ())
+        .map_err(|e| format!("Database error: {}", e))
 }
 
 // State aplikasi yang akan di-share ke semua handlers
@@ -88,6 +122,8 @@ pub struct AppState {
     pub db: PgPool,
     pub config: AppConfig,
     pub rate_limiter: RateLimiter,
+    pub storage: StorageBackend,
+    pub http_client: reqwest::Client,
 }
 
 // Implement FromRef untuk bisa extract PgPool dari AppState
@@ -111,14 +147,23 @@ impl axum::extract::FromRef<AppState> for RateLimiter {
     }
 }
 
+// Implement FromRef untuk bisa extract StorageBackend dari AppState
+impl axum::extract::FromRef<AppState> for StorageBackend {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
 impl AppState {
     // Buat AppState baru dengan semua dependensi
-    pub async fn new() -> Result<Self, String> {
-        let config = AppConfig::from_env()?;
+    pub async fn new(config: AppConfig) -> Result<Self, String> {
         let db = init_db_pool(&config.database_url)
             .await
             .map_err(|e| format!("Gagal menginisialisasi database: {}", e))?;
 
+        // Gagal start lebih awal jika migration belum lengkap, daripada 500 saat runtime
+        verify_schema(&db, "vehicle-service", REQUIRED_SCHEMA, config.is_production()).await?;
+
         // Initialize Redis rate limiter 
         let redis_url = env::var("REDIS_URL")
             .unwrap_or_else(|_| {
@@ -134,28 +179,40 @@ impl AppState {
             });
         tracing::info!("✅ Redis rate limiter initialized successfully (MANDATORY)");
 
-        Ok(AppState { db, config, rate_limiter })
+        // Storage backend untuk allowlist URL dokumen (laporan inspeksi)
+        let storage = StorageBackend::from_env()
+            .map_err(|e| format!("Gagal menginisialisasi storage: {}", e))?;
+        tracing::info!("🗄️ Storage backend: {}", storage.name());
+
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("Gagal membuat HTTP client: {}", e))?;
+
+        Ok(AppState { db, config, rate_limiter, storage, http_client })
     }
 
     // Health check untuk dependencies
     pub async fn health_check(&self) -> HealthStatus {
-        let db_healthy = check_db_health(&self.db).await;
-
-        HealthStatus {
-            database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-            overall: if db_healthy {
-                "healthy"
-            } else {
-                "degraded"
-            }
-            .to_string(),
-        }
+        HealthStatus::check(&self.db).await
     }
 }
 
 // Struktur untuk response health check endpoint
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
 pub struct HealthStatus {
-    pub database: String,
-    pub overall: String,
+    pub database: DependencyHealth,
+    pub overall: HealthLevel,
+}
+
+impl HealthStatus {
+    // Probe database dengan latency; detail error hanya masuk log
+    pub async fn check(pool: &PgPool) -> Self {
+        let database = health::probe("database", check_db_health(pool)).await;
+
+        HealthStatus {
+            overall: health::worst_status(&[&database]),
+            database,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Batas jumlah vehicle per request bulk availability
+pub const MAX_BULK_AVAILABILITY: usize = 100;
+
+// Target availability yang bisa diatur seller sendiri.
+// pending_sale dan sold dikelola alur order, tidak bisa diubah lewat bulk update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    Available,
+    // Offline sementara (mis. dipakai event), tidak muncul di pencarian
+    Unavailable,
+}
+
+impl Availability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Availability::Available => "available",
+            Availability::Unavailable => "unavailable",
+        }
+    }
+}
+
+// Request seller untuk mengubah availability banyak vehicle sekaligus
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkAvailabilityRequest {
+    #[schema(example = json!([12, 15, 18]))]
+    pub vehicle_ids: Vec<i32>,
+    pub status: Availability,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAvailabilityOutcome {
+    Updated,
+    // Status sudah sama dengan target
+    Unchanged,
+    NotFound,
+    // Vehicle milik seller lain
+    Forbidden,
+    // Vehicle sedang dalam proses jual / sudah terjual
+    InvalidStatus,
+}
+
+impl BulkAvailabilityOutcome {
+    pub fn message(&self) -> Option<&'static str> {
+        match self {
+            BulkAvailabilityOutcome::Updated | BulkAvailabilityOutcome::Unchanged => None,
+            BulkAvailabilityOutcome::NotFound => Some("Vehicle tidak ditemukan"),
+            BulkAvailabilityOutcome::Forbidden => Some("Anda tidak punya akses ke vehicle ini"),
+            BulkAvailabilityOutcome::InvalidStatus => Some("Vehicle sedang dalam proses penjualan atau sudah terjual"),
+        }
+    }
+}
+
+// Hasil per vehicle id
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkAvailabilityResult {
+    pub vehicle_id: i32,
+    pub result: BulkAvailabilityOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl BulkAvailabilityResult {
+    pub fn new(vehicle_id: i32, result: BulkAvailabilityOutcome) -> Self {
+        Self {
+            vehicle_id,
+            result,
+            message: result.message().map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkAvailabilityResponse {
+    pub status: Availability,
+    pub updated: usize,
+    // Notifikasi "tersedia lagi" ke user yang mem-favorite vehicle
+    pub notified: u64,
+    pub results: Vec<BulkAvailabilityResult>,
+}
+
+// Validasi daftar id: tidak kosong, tidak melebihi batas, duplikat dibuang (urutan dipertahankan)
+pub fn normalize_vehicle_ids(ids: &[i32]) -> Result<Vec<i32>, String> {
+    if ids.is_empty() {
+        return Err("vehicle_ids tidak boleh kosong".to_string());
+    }
+
+    let mut unique = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !unique.contains(id) {
+            unique.push(*id);
+        }
+    }
+
+    if unique.len() > MAX_BULK_AVAILABILITY {
+        return Err(format!("Maksimal {} vehicle per request", MAX_BULK_AVAILABILITY));
+    }
+
+    Ok(unique)
+}
+
+// Hasil untuk satu vehicle berdasarkan (seller_id, status) saat ini, None jika tidak ada
+pub fn plan_availability(
+    current: Option<(i32, &str)>,
+    seller_id: i32,
+    target: Availability,
+) -> BulkAvailabilityOutcome {
+    let Some((owner_id, status)) = current else {
+        return BulkAvailabilityOutcome::NotFound;
+    };
+
+    if owner_id != seller_id {
+        return BulkAvailabilityOutcome::Forbidden;
+    }
+
+    match status {
+        s if s == target.as_str() => BulkAvailabilityOutcome::Unchanged,
+        "available" | "unavailable" => BulkAvailabilityOutcome::Updated,
+        _ => BulkAvailabilityOutcome::InvalidStatus,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SELLER: i32 = 7;
+
+    #[test]
+    fn test_normalize_vehicle_ids() {
+        assert!(normalize_vehicle_ids(&[]).is_err());
+        assert_eq!(normalize_vehicle_ids(&[3, 1, 3, 2, 1]).unwrap(), vec![3, 1, 2]);
+
+        let too_many: Vec<i32> = (1..=MAX_BULK_AVAILABILITY as i32 + 1).collect();
+        assert!(normalize_vehicle_ids(&too_many).is_err());
+
+        // Duplikat tidak dihitung ke batas
+        let mut at_cap: Vec<i32> = (1..=MAX_BULK_AVAILABILITY as i32).collect();
+        at_cap.push(1);
+        assert_eq!(normalize_vehicle_ids(&at_cap).unwrap().len(), MAX_BULK_AVAILABILITY);
+    }
+
+    #[test]
+    fn test_plan_availability() {
+        use BulkAvailabilityOutcome::*;
+
+        assert_eq!(plan_availability(None, SELLER, Availability::Unavailable), NotFound);
+        assert_eq!(plan_availability(Some((99, "available")), SELLER, Availability::Unavailable), Forbidden);
+        assert_eq!(plan_availability(Some((SELLER, "available")), SELLER, Availability::Unavailable), Updated);
+        assert_eq!(plan_availability(Some((SELLER, "unavailable")), SELLER, Availability::Available), Updated);
+        assert_eq!(plan_availability(Some((SELLER, "available")), SELLER, Availability::Available), Unchanged);
+        assert_eq!(plan_availability(Some((SELLER, "pending_sale")), SELLER, Availability::Unavailable), InvalidStatus);
+        assert_eq!(plan_availability(Some((SELLER, "sold")), SELLER, Availability::Available), InvalidStatus);
+    }
+}
@@ -0,0 +1,250 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Referensi brand dari tabel vehicle_brands (id asli, bukan turunan data vehicles)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct VehicleBrand {
+    pub id: i32,
+    #[schema(example = "Toyota")]
+    pub name: String,
+    pub logo_url: Option<String>,
+}
+
+// Referensi model dari tabel vehicle_models
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct VehicleModel {
+    pub id: i32,
+    pub brand_id: i32,
+    #[schema(example = "Avanza")]
+    pub name: String,
+    #[schema(example = "MPV")]
+    pub vehicle_type: Option<String>,
+}
+
+// Alasan brand/model input seller ditolak
+#[derive(Debug, PartialEq, Eq)]
+pub enum BrandModelError {
+    EmptyBrand,
+    EmptyModel,
+    UnknownBrand(String),
+    UnknownModel { brand: String, model: String },
+}
+
+impl BrandModelError {
+    pub fn message(&self) -> String {
+        match self {
+            BrandModelError::EmptyBrand => "Brand tidak boleh kosong".to_string(),
+            BrandModelError::EmptyModel => "Model tidak boleh kosong".to_string(),
+            BrandModelError::UnknownBrand(brand) => {
+                format!("Brand '{}' tidak terdaftar, pilih dari GET /api/vehicles/brands", brand)
+            }
+            BrandModelError::UnknownModel { brand, model } => {
+                format!("Model '{}' tidak terdaftar untuk brand {}", model, brand)
+            }
+        }
+    }
+}
+
+// Kunci pencocokan nama: huruf kecil, hanya huruf/angka.
+// "TOYOTA", " toyota " dan "Toyota" sama; begitu juga "CR-V" / "crv" dan "Mercedes Benz" / "Mercedes-Benz"
+pub fn name_key(raw: &str) -> String {
+    raw.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub fn find_brand<'a>(brands: &'a [VehicleBrand], input: &str) -> Option<&'a VehicleBrand> {
+    let key = name_key(input);
+    brands.iter().find(|brand| name_key(&brand.name) == key)
+}
+
+pub fn find_model<'a>(models: &'a [VehicleModel], input: &str) -> Option<&'a VehicleModel> {
+    let key = name_key(input);
+    models.iter().find(|model| name_key(&model.name) == key)
+}
+
+// Cocokkan brand input ke referensi; models dimuat setelah brand diketahui
+pub fn resolve_brand<'a>(brands: &'a [VehicleBrand], input: &str) -> Result<&'a VehicleBrand, BrandModelError> {
+    if name_key(input).is_empty() {
+        return Err(BrandModelError::EmptyBrand);
+    }
+
+    find_brand(brands, input).ok_or_else(|| BrandModelError::UnknownBrand(input.trim().to_string()))
+}
+
+// Cocokkan model input ke model milik brand, hasilnya nama kanonik yang disimpan
+pub fn resolve_model<'a>(
+    brand: &VehicleBrand,
+    models: &'a [VehicleModel],
+    input: &str,
+) -> Result<&'a VehicleModel, BrandModelError> {
+    if name_key(input).is_empty() {
+        return Err(BrandModelError::EmptyModel);
+    }
+
+    find_model(models, input)
+        .filter(|model| model.brand_id == brand.id)
+        .ok_or_else(|| BrandModelError::UnknownModel {
+            brand: brand.name.clone(),
+            model: input.trim().to_string(),
+        })
+}
+
+// Hasil normalisasi brand/model untuk disimpan di vehicles
+#[derive(Debug, PartialEq, Eq)]
+pub struct CanonicalBrandModel {
+    pub brand: String,
+    pub model: String,
+    // Terisi jika brand/model belum ada di referensi (disimpan apa adanya)
+    pub unlisted: Option<BrandModelError>,
+}
+
+// Brand/model terdaftar dinormalisasi ke nama referensi. Yang belum terdaftar tidak ditolak:
+// referensi belum punya endpoint admin, jadi listing brand/model baru tetap bisa dibuat.
+// `models` adalah model milik brand yang cocok (kosong jika brand tidak dikenal)
+pub fn canonicalize_brand_model(
+    brands: &[VehicleBrand],
+    models: &[VehicleModel],
+    brand_input: &str,
+    model_input: &str,
+) -> Result<CanonicalBrandModel, BrandModelError> {
+    if name_key(model_input).is_empty() {
+        return Err(BrandModelError::EmptyModel);
+    }
+
+    let brand = match resolve_brand(brands, brand_input) {
+        Ok(brand) => brand,
+        Err(BrandModelError::UnknownBrand(name)) => {
+            return Ok(CanonicalBrandModel {
+                brand: name.clone(),
+                model: model_input.trim().to_string(),
+                unlisted: Some(BrandModelError::UnknownBrand(name)),
+            });
+        }
+        Err(e) => return Err(e),
+    };
+
+    match resolve_model(brand, models, model_input) {
+        Ok(model) => Ok(CanonicalBrandModel {
+            brand: brand.name.clone(),
+            model: model.name.clone(),
+            unlisted: None,
+        }),
+        Err(unlisted @ BrandModelError::UnknownModel { .. }) => Ok(CanonicalBrandModel {
+            brand: brand.name.clone(),
+            model: model_input.trim().to_string(),
+            unlisted: Some(unlisted),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brands() -> Vec<VehicleBrand> {
+        ["Toyota", "Honda", "Mercedes-Benz"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| VehicleBrand { id: i as i32 + 1, name: name.to_string(), logo_url: None })
+            .collect()
+    }
+
+    fn honda_models() -> Vec<VehicleModel> {
+        ["Brio", "CR-V", "HR-V"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| VehicleModel {
+                id: i as i32 + 10,
+                brand_id: 2,
+                name: name.to_string(),
+                vehicle_type: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_brand_and_model_are_normalized_to_reference_name() {
+        let brands = brands();
+        for input in ["Toyota", "toyota", "TOYOTA", "  toyota "] {
+            assert_eq!(resolve_brand(&brands, input).unwrap().name, "Toyota");
+        }
+        assert_eq!(resolve_brand(&brands, "mercedes benz").unwrap().name, "Mercedes-Benz");
+
+        let honda = resolve_brand(&brands, "honda").unwrap();
+        let models = honda_models();
+        for input in ["CR-V", "crv", "Cr V", "cr-v"] {
+            assert_eq!(resolve_model(honda, &models, input).unwrap().name, "CR-V");
+        }
+        // "hrv" tidak boleh tertukar dengan "crv"
+        assert_eq!(resolve_model(honda, &models, "hrv").unwrap().name, "HR-V");
+    }
+
+    #[test]
+    fn test_invalid_brand_or_model_rejected() {
+        let brands = brands();
+        assert_eq!(resolve_brand(&brands, "  ").unwrap_err(), BrandModelError::EmptyBrand);
+        assert_eq!(resolve_brand(&brands, "-").unwrap_err(), BrandModelError::EmptyBrand);
+        assert_eq!(
+            resolve_brand(&brands, " Toyoda ").unwrap_err(),
+            BrandModelError::UnknownBrand("Toyoda".to_string())
+        );
+
+        let honda = &brands[1];
+        let toyota = &brands[0];
+        let models = honda_models();
+        assert_eq!(resolve_model(honda, &models, "").unwrap_err(), BrandModelError::EmptyModel);
+        assert_eq!(
+            resolve_model(honda, &models, "Avanza").unwrap_err(),
+            BrandModelError::UnknownModel { brand: "Honda".to_string(), model: "Avanza".to_string() }
+        );
+
+        // Model brand lain tidak diterima walau namanya terdaftar
+        let err = resolve_model(toyota, &models, "Brio").unwrap_err();
+        assert_eq!(err.message(), "Model 'Brio' tidak terdaftar untuk brand Toyota");
+    }
+
+    #[test]
+    fn test_canonicalize_normalizes_listed_brand_and_model() {
+        let brands = brands();
+        let models = honda_models();
+
+        let resolved = canonicalize_brand_model(&brands, &models, " HONDA ", "crv").unwrap();
+        assert_eq!(
+            resolved,
+            CanonicalBrandModel { brand: "Honda".to_string(), model: "CR-V".to_string(), unlisted: None }
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_unlisted_falls_back_with_warning() {
+        let brands = brands();
+
+        // Brand baru belum ada di referensi: disimpan apa adanya (trim), bukan ditolak
+        let resolved = canonicalize_brand_model(&brands, &[], " Chery ", " Tiggo 7 ").unwrap();
+        assert_eq!(resolved.brand, "Chery");
+        assert_eq!(resolved.model, "Tiggo 7");
+        assert_eq!(resolved.unlisted, Some(BrandModelError::UnknownBrand("Chery".to_string())));
+
+        // Brand dikenal tetap dinormalisasi walau modelnya belum terdaftar
+        let resolved = canonicalize_brand_model(&brands, &honda_models(), "honda", "WR-V").unwrap();
+        assert_eq!(resolved.brand, "Honda");
+        assert_eq!(resolved.model, "WR-V");
+        assert!(matches!(resolved.unlisted, Some(BrandModelError::UnknownModel { .. })));
+    }
+
+    #[test]
+    fn test_canonicalize_rejects_empty_input() {
+        let brands = brands();
+        assert_eq!(
+            canonicalize_brand_model(&brands, &[], " ", "Avanza").unwrap_err(),
+            BrandModelError::EmptyBrand
+        );
+        assert_eq!(
+            canonicalize_brand_model(&brands, &honda_models(), "Honda", "-").unwrap_err(),
+            BrandModelError::EmptyModel
+        );
+    }
+}
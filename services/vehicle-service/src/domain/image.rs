@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Batas jumlah foto gallery per vehicle
+pub const MAX_PHOTOS: usize = 10;
+
+// Minimal foto gallery: listing sale butuh lebih banyak foto dari rental
+pub fn min_photos(category: &str) -> usize {
+    if category == "sale" { 5 } else { 3 }
+}
+
+// Satu foto di gallery vehicle (tabel vehicle_images)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow, ToSchema)]
+pub struct VehicleImage {
+    pub id: i32,
+    pub vehicle_id: i32,
+    #[schema(example = "https://res.cloudinary.com/bigauto/image/upload/vehicles/vehicle-1-0.jpg")]
+    pub url: String,
+    #[schema(example = 0)]
+    pub position: i32,
+    pub is_primary: bool,
+}
+
+// Request urutan baru gallery: semua image_id milik vehicle, urutan pertama = position 0
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReorderImagesRequest {
+    #[schema(example = json!([12, 10, 11]))]
+    pub image_ids: Vec<i32>,
+}
+
+// Urutan tampil gallery: primary dulu, sisanya sesuai position
+pub fn display_order(mut images: Vec<VehicleImage>) -> Vec<VehicleImage> {
+    images.sort_by_key(|image| (!image.is_primary, image.position));
+    images
+}
+
+// URL foto sesuai urutan tampil, disalin ke vehicles.photos
+pub fn photo_urls(images: &[VehicleImage]) -> Vec<String> {
+    display_order(images.to_vec())
+        .into_iter()
+        .map(|image| image.url)
+        .collect()
+}
+
+// Terapkan urutan baru, image_ids harus permutasi lengkap dari gallery saat ini
+pub fn reorder(images: Vec<VehicleImage>, image_ids: &[i32]) -> Result<Vec<VehicleImage>, &'static str> {
+    if image_ids.len() != images.len() {
+        return Err("image_ids harus berisi semua foto vehicle");
+    }
+
+    let mut reordered = Vec::with_capacity(images.len());
+    for (position, image_id) in image_ids.iter().enumerate() {
+        if reordered.iter().any(|image: &VehicleImage| image.id == *image_id) {
+            return Err("image_ids tidak boleh duplikat");
+        }
+
+        let mut image = images
+            .iter()
+            .find(|image| image.id == *image_id)
+            .cloned()
+            .ok_or("image_ids berisi foto yang bukan milik vehicle ini")?;
+        image.position = position as i32;
+        reordered.push(image);
+    }
+
+    Ok(reordered)
+}
+
+// Jadikan image_id cover photo, None jika foto bukan milik vehicle
+pub fn set_primary(images: Vec<VehicleImage>, image_id: i32) -> Option<Vec<VehicleImage>> {
+    if !images.iter().any(|image| image.id == image_id) {
+        return None;
+    }
+
+    Some(
+        images
+            .into_iter()
+            .map(|image| VehicleImage { is_primary: image.id == image_id, ..image })
+            .collect(),
+    )
+}
+
+// Rapikan position jadi 0..n dan pastikan tepat satu primary (setelah upload/hapus foto)
+pub fn normalize(mut images: Vec<VehicleImage>) -> Vec<VehicleImage> {
+    images.sort_by_key(|image| image.position);
+
+    let primary_id = images
+        .iter()
+        .find(|image| image.is_primary)
+        .or_else(|| images.first())
+        .map(|image| image.id);
+
+    images
+        .into_iter()
+        .enumerate()
+        .map(|(position, image)| VehicleImage {
+            position: position as i32,
+            is_primary: Some(image.id) == primary_id,
+            ..image
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gallery(ids: &[i32]) -> Vec<VehicleImage> {
+        ids.iter()
+            .enumerate()
+            .map(|(position, id)| VehicleImage {
+                id: *id,
+                vehicle_id: 1,
+                url: format!("https://res.cloudinary.com/bigauto/vehicle-1-{}.jpg", id),
+                position: position as i32,
+                is_primary: position == 0,
+            })
+            .collect()
+    }
+
+    fn ids(images: &[VehicleImage]) -> Vec<i32> {
+        images.iter().map(|image| image.id).collect()
+    }
+
+    #[test]
+    fn test_reorder_assigns_positions() {
+        let reordered = reorder(gallery(&[10, 11, 12]), &[12, 10, 11]).unwrap();
+
+        assert_eq!(ids(&reordered), vec![12, 10, 11]);
+        assert_eq!(reordered.iter().map(|i| i.position).collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        // Primary tidak ikut pindah, tetap tampil paling depan
+        assert_eq!(ids(&display_order(reordered)), vec![10, 12, 11]);
+    }
+
+    #[test]
+    fn test_reorder_rejects_incomplete_or_foreign_ids() {
+        assert!(reorder(gallery(&[10, 11, 12]), &[12, 10]).is_err());
+        assert!(reorder(gallery(&[10, 11, 12]), &[12, 10, 10]).is_err());
+        assert!(reorder(gallery(&[10, 11, 12]), &[12, 10, 99]).is_err());
+    }
+
+    #[test]
+    fn test_set_primary_switches_single_cover() {
+        let switched = set_primary(gallery(&[10, 11, 12]), 12).unwrap();
+
+        assert_eq!(switched.iter().filter(|image| image.is_primary).count(), 1);
+        assert_eq!(ids(&display_order(switched.clone())), vec![12, 10, 11]);
+        assert_eq!(photo_urls(&switched)[0], "https://res.cloudinary.com/bigauto/vehicle-1-12.jpg");
+
+        assert!(set_primary(gallery(&[10, 11, 12]), 99).is_none());
+    }
+
+    #[test]
+    fn test_normalize_after_primary_removed() {
+        // Foto primary (10) dihapus, foto berikutnya jadi cover dan position dirapikan
+        let remaining: Vec<VehicleImage> = gallery(&[10, 11, 12]).into_iter().skip(1).collect();
+        let normalized = normalize(remaining);
+
+        assert_eq!(ids(&normalized), vec![11, 12]);
+        assert_eq!(normalized.iter().map(|i| i.position).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(normalized[0].is_primary && !normalized[1].is_primary);
+    }
+}
@@ -0,0 +1,196 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::JsonValue;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+// Batas laporan inspeksi
+const MAX_COMPONENTS: usize = 30;
+const MAX_COMPONENT_NAME_LEN: usize = 50;
+const MAX_INSPECTOR_LEN: usize = 100;
+
+// Laporan inspeksi kondisi vehicle dari database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VehicleInspection {
+    pub overall_score: i32,
+    pub condition_grade: String,
+    pub component_ratings: JsonValue,
+    pub inspector_name: String,
+    pub inspected_at: NaiveDate,
+    pub report_url: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Request seller untuk melampirkan laporan inspeksi (menggantikan laporan sebelumnya)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AttachInspectionRequest {
+    // Skor keseluruhan 0-100
+    #[schema(example = 86)]
+    pub overall_score: i32,
+    // Rating per komponen 1-5
+    #[schema(example = json!({"mesin": 5, "transmisi": 4, "interior": 4, "eksterior": 3}))]
+    pub component_ratings: BTreeMap<String, i32>,
+    #[schema(example = "Bengkel Inspeksi Mobil Sejahtera")]
+    pub inspector_name: String,
+    #[schema(example = "2026-03-01")]
+    pub inspected_at: NaiveDate,
+    // PDF laporan, harus dari storage Big Auto
+    #[schema(example = "https://res.cloudinary.com/bigauto/raw/upload/documents/inspection-12.pdf")]
+    pub report_url: String,
+}
+
+// Laporan inspeksi read-only di detail vehicle
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InspectionResponse {
+    pub overall_score: i32,
+    #[schema(example = "A")]
+    pub condition_grade: String,
+    pub component_ratings: BTreeMap<String, i32>,
+    pub inspector_name: String,
+    pub inspected_at: NaiveDate,
+    pub report_url: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<VehicleInspection> for InspectionResponse {
+    fn from(inspection: VehicleInspection) -> Self {
+        Self {
+            overall_score: inspection.overall_score,
+            condition_grade: inspection.condition_grade,
+            component_ratings: serde_json::from_value(inspection.component_ratings).unwrap_or_default(),
+            inspector_name: inspection.inspector_name,
+            inspected_at: inspection.inspected_at,
+            report_url: inspection.report_url,
+            updated_at: inspection.updated_at,
+        }
+    }
+}
+
+// Grade kondisi dari skor: A >= 85, B >= 70, C >= 55, selain itu D
+// Urutan huruf dipakai filter min_condition_grade (A terbaik)
+pub fn condition_grade(overall_score: i32) -> &'static str {
+    match overall_score {
+        85.. => "A",
+        70..=84 => "B",
+        55..=69 => "C",
+        _ => "D",
+    }
+}
+
+// Grade valid untuk filter pencarian
+pub fn is_valid_grade(grade: &str) -> bool {
+    matches!(grade, "A" | "B" | "C" | "D")
+}
+
+// PDF dikenali dari ekstensi path (query string diabaikan)
+fn is_pdf_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.to_ascii_lowercase().ends_with(".pdf")
+}
+
+// Validasi laporan inspeksi, `storage_owns_url` dari allowlist storage
+pub fn validate_inspection(
+    req: &AttachInspectionRequest,
+    today: NaiveDate,
+    storage_owns_url: bool,
+) -> Result<(), String> {
+    if !(0..=100).contains(&req.overall_score) {
+        return Err("overall_score harus antara 0-100".to_string());
+    }
+
+    if req.component_ratings.is_empty() || req.component_ratings.len() > MAX_COMPONENTS {
+        return Err(format!("component_ratings harus berisi 1-{} komponen", MAX_COMPONENTS));
+    }
+
+    for (component, rating) in &req.component_ratings {
+        if component.trim().is_empty() || component.len() > MAX_COMPONENT_NAME_LEN {
+            return Err(format!("Nama komponen maksimal {} karakter dan tidak boleh kosong", MAX_COMPONENT_NAME_LEN));
+        }
+        if !(1..=5).contains(rating) {
+            return Err(format!("Rating komponen {} harus antara 1-5", component));
+        }
+    }
+
+    let inspector = req.inspector_name.trim();
+    if inspector.is_empty() || inspector.len() > MAX_INSPECTOR_LEN {
+        return Err(format!("inspector_name wajib diisi (maksimal {} karakter)", MAX_INSPECTOR_LEN));
+    }
+
+    if req.inspected_at > today {
+        return Err("inspected_at tidak boleh di masa depan".to_string());
+    }
+
+    if !storage_owns_url {
+        return Err("report_url harus berasal dari storage Big Auto".to_string());
+    }
+
+    if !is_pdf_url(&req.report_url) {
+        return Err("report_url harus berupa file PDF".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 10).unwrap()
+    }
+
+    fn request() -> AttachInspectionRequest {
+        AttachInspectionRequest {
+            overall_score: 86,
+            component_ratings: BTreeMap::from([("mesin".to_string(), 5), ("interior".to_string(), 4)]),
+            inspector_name: "Bengkel Sejahtera".to_string(),
+            inspected_at: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            report_url: "https://res.cloudinary.com/bigauto/raw/upload/documents/inspection-12.PDF?v=2".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_condition_grade_boundaries() {
+        assert_eq!(condition_grade(100), "A");
+        assert_eq!(condition_grade(85), "A");
+        assert_eq!(condition_grade(84), "B");
+        assert_eq!(condition_grade(70), "B");
+        assert_eq!(condition_grade(55), "C");
+        assert_eq!(condition_grade(54), "D");
+        assert!(is_valid_grade("B"));
+        assert!(!is_valid_grade("b"));
+    }
+
+    #[test]
+    fn test_valid_inspection() {
+        assert!(validate_inspection(&request(), today(), true).is_ok());
+    }
+
+    #[test]
+    fn test_report_url_must_be_owned_pdf() {
+        assert!(validate_inspection(&request(), today(), false).is_err());
+
+        let mut req = request();
+        req.report_url = "https://res.cloudinary.com/bigauto/image/upload/documents/inspection-12.jpg".to_string();
+        assert!(validate_inspection(&req, today(), true).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_scores_and_dates() {
+        let mut req = request();
+        req.overall_score = 101;
+        assert!(validate_inspection(&req, today(), true).is_err());
+
+        let mut req = request();
+        req.component_ratings.insert("rem".to_string(), 0);
+        assert!(validate_inspection(&req, today(), true).is_err());
+
+        let mut req = request();
+        req.component_ratings.clear();
+        assert!(validate_inspection(&req, today(), true).is_err());
+
+        let mut req = request();
+        req.inspected_at = NaiveDate::from_ymd_opt(2026, 3, 11).unwrap();
+        assert!(validate_inspection(&req, today(), true).is_err());
+    }
+}
@@ -1 +1,5 @@
 pub mod vehicle;
+pub mod image;
+pub mod inspection;
+pub mod availability;
+pub mod brand;
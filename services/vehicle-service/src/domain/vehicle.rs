@@ -4,6 +4,11 @@ use sqlx::PgPool;
 use sqlx::types::JsonValue;
 use utoipa::ToSchema;
 
+use shared::utils::internal_notifications::{send_notifications, NewNotification};
+
+use crate::domain::inspection::InspectionResponse;
+use crate::utils::price_drop;
+
 // Model utama Vehicle dari database
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Vehicle {
@@ -30,6 +35,7 @@ pub struct Vehicle {
     pub has_stnk: bool,
     pub description: Option<String>,
     pub rental_terms: Option<String>,
+    pub deposit_amount: f64,
     pub city: String,
     pub address: String,
     pub latitude: Option<f64>,
@@ -39,6 +45,7 @@ pub struct Vehicle {
     pub status: String,
     pub rating: Option<f64>,
     pub review_count: i32,
+    pub condition_grade: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -70,6 +77,7 @@ pub struct VehicleWithSeller {
     pub has_stnk: bool,
     pub description: Option<String>,
     pub rental_terms: Option<String>,
+    pub deposit_amount: f64,
     pub city: String,
     pub address: String,
     pub latitude: Option<f64>,
@@ -79,6 +87,7 @@ pub struct VehicleWithSeller {
     pub status: String,
     pub rating: Option<f64>,
     pub review_count: i32,
+    pub condition_grade: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     // Seller field
@@ -130,6 +139,9 @@ pub struct CreateVehicleRequest {
     pub description: Option<String>,
     #[schema(example = "- Wajib KTP\n- SIM A aktif\n- Booking minimal 1 hari")]
     pub rental_terms: Option<String>,
+    // Deposit jaminan rental, dibayar customer terpisah dari biaya sewa (default 0)
+    #[schema(example = 1000000.0)]
+    pub deposit_amount: Option<f64>,
     #[schema(example = "Jakarta")]
     pub city: String,
     #[schema(example = "Jl. Sudirman No. 123, Jakarta Pusat")]
@@ -166,12 +178,19 @@ pub struct UpdateVehicleRequest {
     pub description: Option<String>,
     #[schema(example = "- Updated terms")]
     pub rental_terms: Option<String>,
+    #[schema(example = 1500000.0)]
+    pub deposit_amount: Option<f64>,
     #[schema(example = "Jl. Sudirman No. 456")]
     pub address: Option<String>,
     #[schema(example = -6.208763)]
     pub latitude: Option<f64>,
     #[schema(example = 106.845599)]
     pub longitude: Option<f64>,
+    // Brand/model divalidasi ke referensi, yang tidak dikirim tetap memakai nilai lama
+    #[schema(example = "Toyota")]
+    pub brand: Option<String>,
+    #[schema(example = "Veloz")]
+    pub model: Option<String>,
 }
 
 // Query parameters untuk filtering vehicles
@@ -203,6 +222,9 @@ pub struct VehicleFilter {
     pub min_seats: Option<i32>,
     #[schema(example = false)]
     pub is_luxury: Option<bool>,
+    // Grade kondisi minimal hasil inspeksi (A terbaik), vehicle tanpa inspeksi tidak ikut
+    #[schema(example = "B")]
+    pub min_condition_grade: Option<String>,
     #[schema(example = "price_asc")]
     pub sort_by: Option<String>,
     #[schema(example = 1)]
@@ -238,6 +260,7 @@ pub struct VehicleResponse {
     pub has_stnk: bool,
     pub description: Option<String>,
     pub rental_terms: Option<String>,
+    pub deposit_amount: f64,
     pub city: String,
     pub address: String,
     pub latitude: Option<f64>,
@@ -247,8 +270,22 @@ pub struct VehicleResponse {
     pub status: String,
     pub rating: Option<f64>,
     pub review_count: i32,
+    pub condition_grade: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // Laporan inspeksi, hanya diisi di detail vehicle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inspection: Option<InspectionResponse>,
+}
+
+// Data rental untuk booking-service saat membuat rental booking
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+pub struct RentalInfoResponse {
+    pub id: i32,
+    pub seller_id: i32,
+    pub price_per_day: f64,
+    pub deposit_amount: f64,
+    pub is_available: bool,
 }
 
 // Response untuk list vehicles dengan pagination
@@ -268,18 +305,21 @@ pub struct City {
     pub name: String,
 }
 
-// Master data - Brand
-#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+// Master data - Brand (referensi vehicle_brands + brand listing yang belum terdaftar)
+#[derive(Debug, PartialEq, Serialize, sqlx::FromRow, ToSchema)]
 pub struct Brand {
-    pub id: i32,
+    /// null untuk brand listing yang belum ada di referensi
+    pub id: Option<i32>,
     pub name: String,
 }
 
-// Master data - Model
-#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+// Master data - Model (referensi vehicle_models + model listing yang belum terdaftar)
+#[derive(Debug, PartialEq, Serialize, sqlx::FromRow, ToSchema)]
 pub struct Model {
-    pub id: i32,
-    pub brand_id: i32,
+    /// null untuk model listing yang belum ada di referensi
+    pub id: Option<i32>,
+    /// null jika brand-nya juga belum ada di referensi
+    pub brand_id: Option<i32>,
     pub name: String,
 }
 
@@ -306,14 +346,129 @@ impl Vehicle {
         Ok(result.rows_affected())
     }
 
-    /// Cleanup inactive vehicles (not updated in 90 days)
+    /// Cleanup inactive vehicles (not updated in 90 days).
+    /// Listing yang sengaja di-offline-kan seller (unavailable) tidak ikut dihapus.
     pub async fn cleanup_inactive_vehicles(pool: &PgPool) -> Result<u64, sqlx::Error> {
         let result = sqlx::query(
-            "DELETE FROM vehicles WHERE updated_at < NOW() - INTERVAL '90 days' AND status NOT IN ('available', 'rented')"
+            "DELETE FROM vehicles WHERE updated_at < NOW() - INTERVAL '90 days' AND status NOT IN ('available', 'unavailable', 'rented')"
         )
         .execute(pool)
         .await?;
 
         Ok(result.rows_affected())
     }
+
+    /// Kirim notifikasi price drop yang window debounce-nya sudah selesai ke user yang mem-favorite,
+    /// lewat notification-service. Window di-claim dengan lease dulu (commit), lalu baru dihapus
+    /// setelah notification-service menerima notifikasinya; gagal kirim = dicoba lagi setelah lease.
+    /// Window yang harga akhirnya tidak lebih rendah dari harga awal dibuang tanpa notifikasi.
+    pub async fn flush_price_drop_notifications(
+        pool: &PgPool,
+        client: &reqwest::Client,
+        notification_url: &str,
+        secret: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let claimed: Vec<(i32, f64, f64)> = sqlx::query_as(
+            "UPDATE vehicle_price_drops
+             SET notify_after = NOW() + $1::BIGINT * INTERVAL '1 minute'
+             WHERE notify_after <= NOW()
+             RETURNING vehicle_id, previous_price::FLOAT8, current_price::FLOAT8"
+        )
+        .bind(price_drop::CLAIM_LEASE_MINUTES)
+        .fetch_all(pool)
+        .await?;
+
+        let mut sent = 0;
+        for (vehicle_id, previous_price, current_price) in claimed {
+            let recipients: Vec<(i32, String)> = if current_price < previous_price {
+                sqlx::query_as(
+                    "SELECT f.customer_id, v.title
+                     FROM favorites f
+                     JOIN vehicles v ON v.id = f.vehicle_id
+                     WHERE f.vehicle_id = $1
+                       AND v.status = 'available'
+                       AND NOT f.price_alerts_muted
+                       AND f.customer_id <> v.seller_id
+                     ORDER BY f.customer_id"
+                )
+                .bind(vehicle_id)
+                .fetch_all(pool)
+                .await?
+            } else {
+                Vec::new()
+            };
+
+            let notifications: Vec<NewNotification> = recipients
+                .into_iter()
+                .map(|(customer_id, title)| NewNotification {
+                    user_id: customer_id,
+                    notification_type: "price_drop".to_string(),
+                    title: "Harga turun".to_string(),
+                    message: price_drop::notification_message(&title, previous_price, current_price),
+                    related_id: Some(vehicle_id),
+                    related_type: Some("vehicle".to_string()),
+                })
+                .collect();
+
+            if !notifications.is_empty() {
+                if let Err(e) = send_notifications(client, notification_url, secret, &notifications).await {
+                    tracing::warn!("Notifikasi price drop vehicle {} gagal dikirim, dicoba lagi: {}", vehicle_id, e);
+                    continue;
+                }
+                sent += notifications.len() as u64;
+            }
+
+            // Harga yang berubah lagi selama pengiriman tetap diantrekan, dibandingkan dari harga yang sudah dikirim
+            sqlx::query(
+                "WITH done AS (
+                    DELETE FROM vehicle_price_drops
+                    WHERE vehicle_id = $1 AND current_price = $2::NUMERIC
+                    RETURNING vehicle_id
+                )
+                UPDATE vehicle_price_drops SET previous_price = $2::NUMERIC
+                WHERE vehicle_id = $1 AND NOT EXISTS (SELECT 1 FROM done)"
+            )
+            .bind(vehicle_id)
+            .bind(current_price)
+            .execute(pool)
+            .await?;
+        }
+
+        Ok(sent)
+    }
+}
+
+// Validasi deposit jaminan: hanya untuk vehicle rental, tidak negatif, maksimal sama dengan batas harga
+pub fn validate_deposit(category: &str, deposit_amount: f64) -> Result<(), String> {
+    if !deposit_amount.is_finite() || deposit_amount < 0.0 {
+        return Err("Deposit tidak boleh negatif".to_string());
+    }
+    if deposit_amount == 0.0 {
+        return Ok(());
+    }
+    if category != "rental" {
+        return Err("Deposit hanya untuk vehicle rental".to_string());
+    }
+    if !shared::utils::validation::is_valid_price(deposit_amount as i64) {
+        return Err("Deposit tidak valid (maksimal 10 miliar)".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_deposit() {
+        assert!(validate_deposit("rental", 0.0).is_ok());
+        assert!(validate_deposit("rental", 1_000_000.0).is_ok());
+        assert!(validate_deposit("sale", 0.0).is_ok());
+
+        assert!(validate_deposit("sale", 500_000.0).is_err());
+        assert!(validate_deposit("rental", -1.0).is_err());
+        assert!(validate_deposit("rental", f64::NAN).is_err());
+        assert!(validate_deposit("rental", 20_000_000_000.0).is_err());
+    }
 }
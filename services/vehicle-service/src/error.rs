@@ -4,6 +4,7 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use shared::utils::request_id;
 
 // Custom error type untuk vehicle service dengan response standardized
 #[derive(Debug)]
@@ -104,11 +105,35 @@ impl IntoResponse for AppError {
             },
         };
 
+        // request_id untuk korelasi laporan error client dengan log
+        let request_id = request_id::current();
+        tracing::debug!("Error response {} ({}): {}", request_id, status, error_type);
+
         let body = Json(json!({
             "error": error_type,
             "message": message,
+            "request_id": request_id,
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        request_id::set_header(&mut response, &request_id);
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_body_has_request_id() {
+        let response = AppError::not_found("Vehicle tidak ditemukan").into_response();
+        let request_id = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], request_id.as_str());
+        assert_eq!(request_id.len(), 36);
     }
 }
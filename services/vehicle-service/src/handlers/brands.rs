@@ -0,0 +1,80 @@
+use axum::{extract::{Path, State}, Json};
+use sqlx::PgPool;
+
+use crate::{
+    domain::brand::{canonicalize_brand_model, find_brand, VehicleBrand, VehicleModel},
+    error::AppError,
+    repositories::brand_repo,
+};
+
+// List brand referensi (typeahead form listing)
+#[utoipa::path(
+    get,
+    path = "/api/vehicles/brands",
+    tag = "Brands",
+    responses(
+        (status = 200, description = "List brand aktif", body = Vec<VehicleBrand>),
+        (status = 401, description = "Unauthorized"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_brands(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<VehicleBrand>>, AppError> {
+    let brands = brand_repo::find_active_brands(&pool).await?;
+    Ok(Json(brands))
+}
+
+// List model referensi milik satu brand
+#[utoipa::path(
+    get,
+    path = "/api/vehicles/brands/{id}/models",
+    tag = "Brands",
+    params(("id" = i32, Path, description = "Brand ID")),
+    responses(
+        (status = 200, description = "List model aktif", body = Vec<VehicleModel>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Brand tidak ditemukan"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_models(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<VehicleModel>>, AppError> {
+    brand_repo::find_active_brand(&pool, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Brand tidak ditemukan"))?;
+
+    let models = brand_repo::find_active_models(&pool, id).await?;
+    Ok(Json(models))
+}
+
+// Normalisasi brand/model input seller ke referensi, return nama yang disimpan.
+// Brand/model yang belum terdaftar tetap diterima apa adanya dengan warning di log
+pub async fn canonical_brand_model(
+    pool: &PgPool,
+    brand: &str,
+    model: &str,
+) -> Result<(String, String), AppError> {
+    let brands = brand_repo::find_active_brands(pool).await?;
+    let models = match find_brand(&brands, brand) {
+        Some(reference) => brand_repo::find_active_models(pool, reference.id).await?,
+        None => Vec::new(),
+    };
+
+    let resolved = canonicalize_brand_model(&brands, &models, brand, model)
+        .map_err(|e| AppError::validation(e.message()))?;
+
+    if let Some(unlisted) = &resolved.unlisted {
+        tracing::warn!(
+            event = "vehicle_brand_model_unlisted",
+            brand = %resolved.brand,
+            model = %resolved.model,
+            "{}",
+            unlisted.message()
+        );
+    }
+
+    Ok((resolved.brand, resolved.model))
+}
@@ -4,9 +4,9 @@ use sqlx::PgPool;
 use utoipa::ToSchema;
 
 use crate::{
-    domain::vehicle::{City, Brand, Model},
+    domain::{brand::find_brand, vehicle::{City, Brand, Model}},
     error::AppError,
-    repositories::filter_repo,
+    repositories::{brand_repo, filter_repo},
 };
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -32,7 +32,7 @@ pub async fn get_cities(
     Ok(Json(cities))
 }
 
-// Get list brands
+// Get list brands: referensi vehicle_brands ditambah brand listing yang belum terdaftar (id null)
 #[utoipa::path(
     get,
     path = "/api/filters/brands",
@@ -46,11 +46,12 @@ pub async fn get_cities(
 pub async fn get_brands(
     State(pool): State<PgPool>,
 ) -> Result<Json<Vec<Brand>>, AppError> {
-    let brands = filter_repo::find_all_brands(&pool).await?;
+    let brands = filter_repo::find_filter_brands(&pool).await?;
     Ok(Json(brands))
 }
 
-// Get list models by brand
+// Get list models by brand. Nama brand dicocokkan ke referensi ("toyota" = "Toyota"); brand di luar
+// referensi memakai nama apa adanya, hasilnya model dari listing available brand itu
 #[utoipa::path(
     get,
     path = "/api/filters/models",
@@ -73,6 +74,10 @@ pub async fn get_models(
         .brand
         .ok_or_else(|| AppError::validation("Brand parameter required"))?;
 
-    let models = filter_repo::find_models_by_brand(&pool, &brand).await?;
+    let brands = brand_repo::find_active_brands(&pool).await?;
+    let models = match find_brand(&brands, &brand) {
+        Some(reference) => filter_repo::find_filter_models(&pool, Some(reference.id), &reference.name).await?,
+        None => filter_repo::find_filter_models(&pool, None, brand.trim()).await?,
+    };
     Ok(Json(models))
 }
@@ -0,0 +1,60 @@
+use axum::{extract::{Path, State}, Json};
+use chrono::Utc;
+use shared::utils::storage::{Storage, StorageBackend};
+use sqlx::PgPool;
+
+use crate::{
+    domain::inspection::{condition_grade, validate_inspection, AttachInspectionRequest, InspectionResponse},
+    error::AppError,
+    middleware::auth::AuthSeller,
+    repositories::{inspection_repo, vehicle_repo},
+};
+
+// Lampirkan laporan inspeksi kondisi vehicle (seller pemilik)
+#[utoipa::path(
+    put,
+    path = "/api/vehicles/{id}/inspection",
+    tag = "Inspections",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Vehicle ID")),
+    request_body = AttachInspectionRequest,
+    responses(
+        (status = 200, description = "Laporan inspeksi tersimpan", body = InspectionResponse),
+        (status = 400, description = "Laporan inspeksi tidak valid"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Vehicle tidak ditemukan"),
+    )
+)]
+pub async fn attach_inspection(
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    State(storage): State<StorageBackend>,
+    Json(payload): Json<AttachInspectionRequest>,
+) -> Result<Json<InspectionResponse>, AppError> {
+    // Audit log: siapa yang melampirkan laporan inspeksi
+    tracing::info!(
+        "Seller {} ({}) attaching inspection to vehicle {}",
+        auth.user_id,
+        auth.email,
+        id
+    );
+
+    vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
+
+    validate_inspection(&payload, Utc::now().date_naive(), storage.owns_url(&payload.report_url))
+        .map_err(AppError::validation)?;
+
+    let grade = condition_grade(payload.overall_score);
+    let inspection = inspection_repo::upsert_inspection(&pool, id, &payload, grade).await?;
+
+    tracing::info!(
+        "Inspection for vehicle {} saved by seller {} (score {}, grade {})",
+        id,
+        auth.user_id,
+        inspection.overall_score,
+        inspection.condition_grade
+    );
+
+    Ok(Json(inspection.into()))
+}
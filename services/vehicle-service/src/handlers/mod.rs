@@ -1,3 +1,5 @@
 pub mod vehicles;
 pub mod photos;
 pub mod filters;
+pub mod inspections;
+pub mod brands;
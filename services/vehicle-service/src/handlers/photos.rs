@@ -3,15 +3,17 @@ use shared::utils::cloudinary::CloudinaryClient;
 use sqlx::PgPool;
 
 use crate::{
-    domain::vehicle::VehicleResponse,
+    domain::{
+        image::{reorder, set_primary, ReorderImagesRequest, VehicleImage, MAX_PHOTOS},
+        vehicle::VehicleResponse,
+    },
     error::AppError,
     middleware::auth::AuthSeller,
-    repositories::vehicle_repo,
+    repositories::{image_repo, vehicle_repo},
 };
 
 use super::vehicles::map_to_response;
 
-const MAX_PHOTOS: usize = 10;
 const MAX_FILE_SIZE: usize = 5 * 1024 * 1024;
 
 // Upload additional photos
@@ -43,23 +45,22 @@ pub async fn upload_photos(
 
     let vehicle = vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
 
-    let mut photos: Vec<String> = serde_json::from_value(vehicle.photos.clone())
-        .unwrap_or_default();
+    let existing = image_repo::find_images(&pool, vehicle.id).await?.len();
 
-    if photos.len() >= MAX_PHOTOS {
-        return Err(AppError::validation("Maksimal 10 photos per vehicle"));
+    if existing >= MAX_PHOTOS {
+        return Err(AppError::validation(format!("Maksimal {} photos per vehicle", MAX_PHOTOS)));
     }
 
     let cloudinary = CloudinaryClient::new()
         .map_err(|e| AppError::cloudinary(format!("Cloudinary init error: {}", e)))?;
 
-    let uploaded = process_photo_uploads(multipart, &cloudinary, id, &mut photos).await?;
+    let uploaded = process_photo_uploads(multipart, &cloudinary, id, existing).await?;
 
-    if uploaded == 0 {
+    if uploaded.is_empty() {
         return Err(AppError::validation("Tidak ada photo yang diupload"));
     }
 
-    let vehicle = vehicle_repo::update_photos(&pool, id, photos).await?;
+    let vehicle = image_repo::add_images(&pool, id, &uploaded).await?;
     let seller_name = vehicle_repo::find_seller_name(&pool, auth.user_id).await?;
 
     Ok(Json(map_to_response(vehicle, seller_name)))
@@ -73,7 +74,7 @@ pub async fn upload_photos(
     security(("bearer_auth" = [])),
     params(
         ("id" = i32, Path, description = "Vehicle ID"),
-        ("index" = usize, Path, description = "Photo index")
+        ("index" = usize, Path, description = "Photo index (urutan tampil, primary = 0)")
     ),
     responses(
         (status = 200, description = "Photo deleted", body = VehicleResponse),
@@ -97,37 +98,121 @@ pub async fn delete_photo(
 
     let vehicle = vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
 
-    let mut photos: Vec<String> = serde_json::from_value(vehicle.photos.clone())
-        .unwrap_or_default();
+    let vehicle = image_repo::remove_image(&pool, id, &vehicle.category, index).await?;
+    let seller_name = vehicle_repo::find_seller_name(&pool, auth.user_id).await?;
 
-    if index >= photos.len() {
-        return Err(AppError::validation("Index photo tidak valid"));
-    }
+    Ok(Json(map_to_response(vehicle, seller_name)))
+}
 
-    let min_photos = if vehicle.category == "sale" { 5 } else { 3 };
-    if photos.len() <= min_photos {
-        return Err(AppError::validation(format!(
-            "Minimal {} photos untuk category {}",
-            min_photos, vehicle.category
-        )));
+// List gallery vehicle dalam urutan tampil
+#[utoipa::path(
+    get,
+    path = "/api/vehicles/{id}/images",
+    tag = "Photos",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Vehicle ID")),
+    responses(
+        (status = 200, description = "Gallery vehicle (primary dulu, lalu position)", body = Vec<VehicleImage>),
+        (status = 404, description = "Vehicle tidak ditemukan"),
+    )
+)]
+pub async fn list_images(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<VehicleImage>>, AppError> {
+    if vehicle_repo::find_vehicle_by_id(&pool, id).await?.is_none() {
+        return Err(AppError::not_found("Vehicle tidak ditemukan"));
     }
 
-    photos.remove(index);
+    Ok(Json(image_repo::find_images(&pool, id).await?))
+}
+
+// Ubah urutan gallery
+#[utoipa::path(
+    put,
+    path = "/api/vehicles/{id}/images/order",
+    tag = "Photos",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Vehicle ID")),
+    request_body = ReorderImagesRequest,
+    responses(
+        (status = 200, description = "Gallery reordered", body = VehicleResponse),
+        (status = 400, description = "image_ids tidak sesuai gallery vehicle"),
+        (status = 403, description = "Forbidden"),
+    )
+)]
+pub async fn reorder_images(
+    auth: AuthSeller,
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Json(payload): Json<ReorderImagesRequest>,
+) -> Result<Json<VehicleResponse>, AppError> {
+    tracing::info!(
+        "Seller {} ({}) reordering images of vehicle {}",
+        auth.user_id,
+        auth.email,
+        id
+    );
+
+    vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
+
+    let vehicle = image_repo::update_gallery(&pool, id, |images| {
+        reorder(images, &payload.image_ids).map_err(AppError::validation)
+    })
+    .await?;
+    let seller_name = vehicle_repo::find_seller_name(&pool, auth.user_id).await?;
+
+    Ok(Json(map_to_response(vehicle, seller_name)))
+}
+
+// Jadikan satu foto sebagai cover (primary)
+#[utoipa::path(
+    put,
+    path = "/api/vehicles/{id}/images/{image_id}/primary",
+    tag = "Photos",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = i32, Path, description = "Vehicle ID"),
+        ("image_id" = i32, Path, description = "Image ID")
+    ),
+    responses(
+        (status = 200, description = "Primary image updated", body = VehicleResponse),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Foto tidak ditemukan"),
+    )
+)]
+pub async fn set_primary_image(
+    auth: AuthSeller,
+    Path((id, image_id)): Path<(i32, i32)>,
+    State(pool): State<PgPool>,
+) -> Result<Json<VehicleResponse>, AppError> {
+    tracing::info!(
+        "Seller {} ({}) setting image {} as primary for vehicle {}",
+        auth.user_id,
+        auth.email,
+        image_id,
+        id
+    );
+
+    vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
 
-    let vehicle = vehicle_repo::update_photos(&pool, id, photos).await?;
+    let vehicle = image_repo::update_gallery(&pool, id, |images| {
+        set_primary(images, image_id).ok_or_else(|| AppError::not_found("Foto tidak ditemukan di vehicle ini"))
+    })
+    .await?;
     let seller_name = vehicle_repo::find_seller_name(&pool, auth.user_id).await?;
 
     Ok(Json(map_to_response(vehicle, seller_name)))
 }
 
-// Process multipart photo uploads
+// Process multipart photo uploads, return URL foto baru
 async fn process_photo_uploads(
     mut multipart: Multipart,
     cloudinary: &CloudinaryClient,
     vehicle_id: i32,
-    photos: &mut Vec<String>,
-) -> Result<usize, AppError> {
-    let mut count = 0;
+    existing: usize,
+) -> Result<Vec<String>, AppError> {
+    let mut uploaded = Vec::new();
 
     while let Some(field) = multipart.next_field().await.map_err(|e| {
         AppError::bad_request(format!("Multipart error: {}", e))
@@ -136,7 +221,7 @@ async fn process_photo_uploads(
             continue;
         }
 
-        if photos.len() >= MAX_PHOTOS {
+        if existing + uploaded.len() >= MAX_PHOTOS {
             break;
         }
 
@@ -148,15 +233,14 @@ async fn process_photo_uploads(
             return Err(AppError::validation("File maksimal 5MB"));
         }
 
-        let filename = format!("vehicle-{}-{}", vehicle_id, photos.len());
+        let filename = format!("vehicle-{}-{}", vehicle_id, existing + uploaded.len());
         let result = cloudinary
             .upload_image(data.to_vec(), "vehicles", Some(filename))
             .await
             .map_err(|e| AppError::cloudinary(format!("Upload error: {}", e)))?;
 
-        photos.push(result.secure_url);
-        count += 1;
+        uploaded.push(result.secure_url);
     }
 
-    Ok(count)
+    Ok(uploaded)
 }
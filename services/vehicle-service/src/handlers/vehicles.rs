@@ -5,13 +5,20 @@ use utoipa::ToSchema;
 
 use crate::{
     config::AppConfig,
+    domain::availability::{
+        normalize_vehicle_ids, BulkAvailabilityOutcome, BulkAvailabilityRequest,
+        BulkAvailabilityResponse, BulkAvailabilityResult,
+    },
+    domain::brand::{find_brand, find_model},
     domain::vehicle::{
         VehicleResponse, VehicleListResponse, VehicleFilter,
-        CreateVehicleRequest, UpdateVehicleRequest,
+        CreateVehicleRequest, UpdateVehicleRequest, RentalInfoResponse, validate_deposit,
     },
     error::AppError,
+    domain::inspection::is_valid_grade,
     middleware::auth::AuthSeller,
-    repositories::vehicle_repo,
+    handlers::brands::canonical_brand_model,
+    repositories::{brand_repo, inspection_repo, vehicle_repo},
 };
 
 // Import shared validation utilities
@@ -30,17 +37,24 @@ pub struct MessageResponse {
     params(VehicleFilter),
     responses(
         (status = 200, description = "List vehicles", body = VehicleListResponse),
+        (status = 400, description = "Filter tidak valid"),
         (status = 401, description = "Unauthorized"),
     ),
     security(("bearer_auth" = []))
 )]
 pub async fn list_vehicles(
-    Query(filter): Query<VehicleFilter>,
+    Query(mut filter): Query<VehicleFilter>,
     State(pool): State<PgPool>,
 ) -> Result<Json<VehicleListResponse>, AppError> {
     let page = filter.page.unwrap_or(1);
     let limit = filter.limit.unwrap_or(20);
 
+    if filter.min_condition_grade.as_deref().is_some_and(|grade| !is_valid_grade(grade)) {
+        return Err(AppError::validation("min_condition_grade harus A, B, C, atau D"));
+    }
+
+    canonicalize_brand_filter(&pool, &mut filter).await?;
+
     let (vehicles, total) = vehicle_repo::find_vehicles(&pool, &filter).await?;
 
     let data: Vec<VehicleResponse> = vehicles
@@ -76,12 +90,37 @@ pub async fn get_vehicle(
     Path(id): Path<i32>,
     State(pool): State<PgPool>,
 ) -> Result<Json<VehicleResponse>, AppError> {
-    let result = vehicle_repo::find_vehicle_by_id(&pool, id).await?;
+    let vehicle = vehicle_repo::find_vehicle_by_id(&pool, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Vehicle tidak ditemukan"))?;
 
-    match result {
-        Some(vehicle) => Ok(Json(map_to_response_from_with_seller(vehicle))),
-        None => Err(AppError::not_found("Vehicle tidak ditemukan")),
-    }
+    // Detail menampilkan laporan inspeksi lengkap (read-only)
+    let mut response = map_to_response_from_with_seller(vehicle);
+    response.inspection = inspection_repo::find_inspection(&pool, id).await?.map(Into::into);
+
+    Ok(Json(response))
+}
+
+// Harga sewa dan deposit vehicle rental, dipanggil booking-service saat membuat rental booking
+#[utoipa::path(
+    get,
+    path = "/vehicles/{id}/rental-info",
+    tag = "Vehicles",
+    params(("id" = i32, Path, description = "Vehicle ID")),
+    responses(
+        (status = 200, description = "Harga per hari dan deposit rental", body = RentalInfoResponse),
+        (status = 404, description = "Vehicle rental tidak ditemukan"),
+    )
+)]
+pub async fn get_rental_info(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+) -> Result<Json<RentalInfoResponse>, AppError> {
+    let info = vehicle_repo::find_rental_info(&pool, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Vehicle rental tidak ditemukan"))?;
+
+    Ok(Json(info))
 }
 
 // Create vehicle baru
@@ -101,7 +140,7 @@ pub async fn create_vehicle(
     auth: AuthSeller,
     State(pool): State<PgPool>,
     State(config): State<AppConfig>,
-    Json(payload): Json<CreateVehicleRequest>,
+    Json(mut payload): Json<CreateVehicleRequest>,
 ) -> Result<Json<VehicleResponse>, AppError> {
     // Audit log: siapa yang create vehicle
     tracing::info!(
@@ -113,6 +152,11 @@ pub async fn create_vehicle(
 
     validate_create_request(&payload, &config)?;
 
+    // Brand/model disimpan dengan nama referensi supaya filter pencarian konsisten
+    let (brand, model) = canonical_brand_model(&pool, &payload.brand, &payload.model).await?;
+    payload.brand = brand;
+    payload.model = model;
+
     let vehicle = vehicle_repo::create_vehicle(&pool, auth.user_id, &payload).await?;
     let seller_name = vehicle_repo::find_seller_name(&pool, auth.user_id).await?;
 
@@ -144,7 +188,8 @@ pub async fn update_vehicle(
     auth: AuthSeller,
     Path(id): Path<i32>,
     State(pool): State<PgPool>,
-    Json(payload): Json<UpdateVehicleRequest>,
+    State(config): State<AppConfig>,
+    Json(mut payload): Json<UpdateVehicleRequest>,
 ) -> Result<Json<VehicleResponse>, AppError> {
     // Audit log: siapa yang update vehicle
     tracing::info!(
@@ -154,13 +199,38 @@ pub async fn update_vehicle(
         id
     );
 
-    vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
+    let existing = vehicle_repo::check_ownership(&pool, id, auth.user_id).await?;
 
     if !has_update_fields(&payload) {
         return Err(AppError::bad_request("Tidak ada field yang diupdate"));
     }
 
-    let vehicle = vehicle_repo::update_vehicle(&pool, id, &payload).await?;
+    if let Some(deposit_amount) = payload.deposit_amount {
+        validate_deposit(&existing.category, deposit_amount).map_err(AppError::validation)?;
+    }
+
+    // Ganti brand saja tetap harus cocok dengan model lama (dan sebaliknya)
+    if payload.brand.is_some() || payload.model.is_some() {
+        let brand = payload.brand.as_deref().unwrap_or(&existing.brand);
+        let model = payload.model.as_deref().unwrap_or(&existing.model);
+        let (brand, model) = canonical_brand_model(&pool, brand, model).await?;
+        payload.brand = Some(brand);
+        payload.model = Some(model);
+    }
+
+    let (vehicle, old_price) = vehicle_repo::update_vehicle(&pool, id, &payload).await?;
+
+    // Penurunan harga diantrekan untuk notifikasi favorite (debounce), gagal tidak membatalkan update
+    if let Err(e) = vehicle_repo::record_price_change(
+        &pool,
+        id,
+        old_price,
+        vehicle.price,
+        config.price_drop_debounce_minutes,
+    ).await {
+        tracing::error!("Gagal mencatat perubahan harga vehicle {}: {:?}", id, e);
+    }
+
     let seller_name = vehicle_repo::find_seller_name(&pool, auth.user_id).await?;
 
     tracing::info!(
@@ -214,6 +284,64 @@ pub async fn delete_vehicle(
     }))
 }
 
+// Ubah availability banyak vehicle sekaligus (seller pemilik)
+#[utoipa::path(
+    post,
+    path = "/api/vehicles/bulk-availability",
+    tag = "Vehicles",
+    security(("bearer_auth" = [])),
+    request_body = BulkAvailabilityRequest,
+    responses(
+        (status = 200, description = "Hasil per vehicle id", body = BulkAvailabilityResponse),
+        (status = 400, description = "vehicle_ids kosong atau melebihi batas"),
+        (status = 403, description = "Hanya seller"),
+    )
+)]
+pub async fn bulk_update_availability(
+    auth: AuthSeller,
+    State(pool): State<PgPool>,
+    Json(payload): Json<BulkAvailabilityRequest>,
+) -> Result<Json<BulkAvailabilityResponse>, AppError> {
+    let vehicle_ids = normalize_vehicle_ids(&payload.vehicle_ids).map_err(AppError::validation)?;
+
+    // Audit log: siapa yang mengubah availability
+    tracing::info!(
+        "Seller {} ({}) setting {} vehicles to {}",
+        auth.user_id,
+        auth.email,
+        vehicle_ids.len(),
+        payload.status.as_str()
+    );
+
+    let (outcomes, notified) =
+        vehicle_repo::bulk_update_availability(&pool, auth.user_id, &vehicle_ids, payload.status).await?;
+
+    let results: Vec<BulkAvailabilityResult> = outcomes
+        .into_iter()
+        .map(|(id, outcome)| BulkAvailabilityResult::new(id, outcome))
+        .collect();
+    let updated = results
+        .iter()
+        .filter(|r| r.result == BulkAvailabilityOutcome::Updated)
+        .count();
+
+    tracing::info!(
+        "Seller {} updated {} of {} vehicles to {} ({} favorite notifications)",
+        auth.user_id,
+        updated,
+        results.len(),
+        payload.status.as_str(),
+        notified
+    );
+
+    Ok(Json(BulkAvailabilityResponse {
+        status: payload.status,
+        updated,
+        notified,
+        results,
+    }))
+}
+
 // Map Vehicle ke Response (full data)
 pub fn map_to_response(v: crate::domain::vehicle::Vehicle, seller_name: String) -> VehicleResponse {
     let photos: Vec<String> = serde_json::from_value(v.photos.clone()).unwrap_or_default();
@@ -243,6 +371,7 @@ pub fn map_to_response(v: crate::domain::vehicle::Vehicle, seller_name: String)
         has_stnk: v.has_stnk,
         description: v.description,
         rental_terms: v.rental_terms,
+        deposit_amount: v.deposit_amount,
         city: v.city,
         address: v.address,
         latitude: v.latitude,
@@ -252,8 +381,10 @@ pub fn map_to_response(v: crate::domain::vehicle::Vehicle, seller_name: String)
         status: v.status,
         rating: v.rating,
         review_count: v.review_count,
+        condition_grade: v.condition_grade,
         created_at: v.created_at,
         updated_at: v.updated_at,
+        inspection: None,
     }
 }
 
@@ -286,6 +417,7 @@ fn map_to_response_from_with_seller(v: crate::domain::vehicle::VehicleWithSeller
         has_stnk: v.has_stnk,
         description: v.description,
         rental_terms: v.rental_terms,
+        deposit_amount: v.deposit_amount,
         city: v.city,
         address: v.address,
         latitude: v.latitude,
@@ -295,8 +427,10 @@ fn map_to_response_from_with_seller(v: crate::domain::vehicle::VehicleWithSeller
         status: v.status,
         rating: v.rating,
         review_count: v.review_count,
+        condition_grade: v.condition_grade,
         created_at: v.created_at,
         updated_at: v.updated_at,
+        inspection: None,
     }
 }
 
@@ -318,6 +452,8 @@ fn validate_create_request(req: &CreateVehicleRequest, config: &AppConfig) -> Re
         return Err(AppError::validation("Price tidak valid (harus 1 - 10 miliar)"));
     }
 
+    validate_deposit(&req.category, req.deposit_amount.unwrap_or(0.0)).map_err(AppError::validation)?;
+
     // Validate year using shared validation (business rule)
     if !validation::is_valid_year(req.year) {
         return Err(AppError::validation("Year tidak valid (1900 - tahun depan)"));
@@ -392,7 +528,33 @@ fn has_update_fields(req: &UpdateVehicleRequest) -> bool {
         || req.luggage_capacity.is_some()
         || req.description.is_some()
         || req.rental_terms.is_some()
+        || req.deposit_amount.is_some()
         || req.address.is_some()
         || req.latitude.is_some()
         || req.longitude.is_some()
+        || req.brand.is_some()
+        || req.model.is_some()
+}
+
+// Samakan filter brand/model dengan nama referensi ("toyota" -> "Toyota").
+// Input yang tidak dikenal dibiarkan apa adanya (hasil pencarian kosong)
+async fn canonicalize_brand_filter(pool: &PgPool, filter: &mut VehicleFilter) -> Result<(), AppError> {
+    let Some(input) = filter.brand.as_deref() else {
+        return Ok(());
+    };
+
+    let brands = brand_repo::find_active_brands(pool).await?;
+    let Some(brand) = find_brand(&brands, input) else {
+        return Ok(());
+    };
+
+    if let Some(model_input) = filter.model.as_deref() {
+        let models = brand_repo::find_active_models(pool, brand.id).await?;
+        if let Some(model) = find_model(&models, model_input) {
+            filter.model = Some(model.name.clone());
+        }
+    }
+    filter.brand = Some(brand.name.clone());
+
+    Ok(())
 }
@@ -1,6 +1,11 @@
 use std::time::Duration;
 use tower_http::cors::CorsLayer;
-use tower_http::trace::TraceLayer;
+use shared::utils::bind_addr;
+use shared::utils::logging;
+use shared::utils::request_id;
+use shared::utils::health::HealthLevel;
+use shared::utils::startup_gate::StartupGate;
+use shared::utils::cors::CorsPolicy;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
@@ -22,21 +27,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "vehicle_service=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(logging::fmt_layer())
         .init();
 
     tracing::info!("🚗 Starting Big Auto - Vehicle Service");
 
     tracing::info!("🔌 Initializing application state...");
-    let state = config::AppState::new().await
+    let config = config::AppConfig::from_env()
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    // Listener dibind sebelum koneksi database, request dijawab 503 sampai state siap
+    let addr = bind_addr::resolve("VEHICLE_SERVICE_HOST", &config.server_host, config.server_port)?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let gate = StartupGate::new(&["/health"]);
+    let server = tokio::spawn({
+        let router = gate.router();
+        async move { axum::serve(listener, router).await }
+    });
+
+    let state = config::AppState::new(config).await
         .map_err(|e| format!("Failed to initialize app state: {}", e))?;
     tracing::info!("✅ Application state initialized");
 
     let health = state.health_check().await;
-    if health.overall == "healthy" {
+    if health.overall == HealthLevel::Healthy {
         tracing::info!("✅ Database health check passed");
     } else {
-        tracing::warn!("⚠️ Health check: Database {}", health.database);
+        tracing::warn!("⚠️ Health check: Database {:?}", health.database.status);
     }
 
     // Start background cleanup scheduler
@@ -44,37 +61,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     scheduler::VehicleScheduler::new(state.clone()).start();
     tracing::info!("✅ Background cleanup scheduler started");
 
-    let app = routes::create_router(state.clone())
-        .layer(create_cors_layer())
-        .layer(TraceLayer::new_for_http());
+    let app = request_id::apply(routes::create_router(state.clone()).layer(create_cors_layer()));
 
-    let addr = format!("{}:{}", state.config.server_host, state.config.server_port);
     tracing::info!("🎯 Vehicle Service listening on {}", addr);
     tracing::info!("📚 API Documentation:");
     tracing::info!("   - Swagger UI: http://{}/swagger-ui", addr);
     tracing::info!("   - ReDoc: http://{}/redoc", addr);
     tracing::info!("🌍 Environment: {}", state.config.environment);
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    // Router asli mulai melayani request
+    gate.ready(app);
+    server.await??;
 
     Ok(())
 }
 
 // Membuat CORS layer yang aman berdasarkan environment variables
 fn create_cors_layer() -> CorsLayer {
-    use axum::http::{HeaderValue, Method};
+    use axum::http::Method;
     use std::env;
 
-    // Allowed origins dari environment variable
-    let allowed_origins = env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable HARUS diisi di .env file");
-
-    // Parse origins yang diperbolehkan
-    let origins: Vec<HeaderValue> = allowed_origins
-        .split(',')
-        .filter_map(|origin| origin.trim().parse::<HeaderValue>().ok())
-        .collect();
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     // CORS max age dari environment
     let max_age_seconds = env::var("CORS_MAX_AGE_SECONDS")
@@ -83,19 +91,12 @@ fn create_cors_layer() -> CorsLayer {
         .expect("CORS_MAX_AGE_SECONDS harus berupa angka (detik)");
 
     // Build CORS layer dengan origins yang dinamis
-    let mut cors = CorsLayer::new()
+    policy
+        .apply(CorsLayer::new())
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             axum::http::header::AUTHORIZATION,
             axum::http::header::CONTENT_TYPE,
         ])
-        .allow_credentials(false)  
-        .max_age(Duration::from_secs(max_age_seconds));
-
-    // Tambahkan semua allowed origins
-    for origin in origins {
-        cors = cors.allow_origin(origin);
-    }
-
-    cors
+        .max_age(Duration::from_secs(max_age_seconds))
 }
@@ -0,0 +1,49 @@
+use sqlx::PgPool;
+
+use crate::{
+    domain::brand::{VehicleBrand, VehicleModel},
+    error::AppError,
+};
+
+// Ambil semua brand aktif (urut nama)
+pub async fn find_active_brands(pool: &PgPool) -> Result<Vec<VehicleBrand>, AppError> {
+    let brands = sqlx::query_as(
+        "SELECT id, name, logo_url
+         FROM vehicle_brands
+         WHERE is_active
+         ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(brands)
+}
+
+// Ambil brand aktif by ID
+pub async fn find_active_brand(pool: &PgPool, brand_id: i32) -> Result<Option<VehicleBrand>, AppError> {
+    let brand = sqlx::query_as(
+        "SELECT id, name, logo_url
+         FROM vehicle_brands
+         WHERE id = $1 AND is_active"
+    )
+    .bind(brand_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(brand)
+}
+
+// Ambil model aktif milik satu brand (urut nama)
+pub async fn find_active_models(pool: &PgPool, brand_id: i32) -> Result<Vec<VehicleModel>, AppError> {
+    let models = sqlx::query_as(
+        "SELECT id, brand_id, name, vehicle_type
+         FROM vehicle_models
+         WHERE brand_id = $1 AND is_active
+         ORDER BY name"
+    )
+    .bind(brand_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(models)
+}
@@ -18,15 +18,19 @@ pub async fn find_all_cities(pool: &PgPool) -> Result<Vec<City>, AppError> {
     Ok(cities)
 }
 
-// Ambil list semua brands
-pub async fn find_all_brands(pool: &PgPool) -> Result<Vec<Brand>, AppError> {
+// Ambil list brand untuk filter: referensi aktif ditambah brand listing available yang belum
+// terdaftar (disimpan apa adanya saat create), supaya listing itu tetap bisa dicari
+pub async fn find_filter_brands(pool: &PgPool) -> Result<Vec<Brand>, AppError> {
     let brands = sqlx::query_as(
-        "SELECT DISTINCT
-            ROW_NUMBER() OVER (ORDER BY brand) as id,
-            brand as name
-         FROM vehicles
-         WHERE status = 'available'
-         ORDER BY brand"
+        "SELECT id, name
+         FROM vehicle_brands
+         WHERE is_active
+         UNION
+         SELECT DISTINCT NULL::INTEGER, v.brand
+         FROM vehicles v
+         WHERE v.status = 'available'
+           AND NOT EXISTS (SELECT 1 FROM vehicle_brands b WHERE b.name = v.brand AND b.is_active)
+         ORDER BY name"
     )
     .fetch_all(pool)
     .await?;
@@ -34,20 +38,76 @@ pub async fn find_all_brands(pool: &PgPool) -> Result<Vec<Brand>, AppError> {
     Ok(brands)
 }
 
-// Ambil list models berdasarkan brand
-pub async fn find_models_by_brand(pool: &PgPool, brand: &str) -> Result<Vec<Model>, AppError> {
+// Ambil list model untuk filter: model referensi brand (jika terdaftar) ditambah model listing
+// available yang belum terdaftar. `brand_id` None untuk brand di luar referensi
+pub async fn find_filter_models(
+    pool: &PgPool,
+    brand_id: Option<i32>,
+    brand: &str,
+) -> Result<Vec<Model>, AppError> {
     let models = sqlx::query_as(
-        "SELECT DISTINCT
-            ROW_NUMBER() OVER (ORDER BY model) as id,
-            1 as brand_id,
-            model as name
-         FROM vehicles
-         WHERE brand = $1 AND status = 'available'
-         ORDER BY model"
+        "SELECT id, brand_id, name
+         FROM vehicle_models
+         WHERE brand_id = $1 AND is_active
+         UNION
+         SELECT DISTINCT NULL::INTEGER, $1, v.model
+         FROM vehicles v
+         WHERE v.brand = $2
+           AND v.status = 'available'
+           AND NOT EXISTS (
+               SELECT 1 FROM vehicle_models m
+               WHERE m.brand_id = $1 AND m.name = v.model AND m.is_active
+           )
+         ORDER BY name"
     )
+    .bind(brand_id)
     .bind(brand)
     .fetch_all(pool)
     .await?;
 
     Ok(models)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brand(id: Option<i32>, name: &str) -> Brand {
+        Brand { id, name: name.to_string() }
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_filters_include_unlisted_listing_brands_and_models(pool: PgPool) {
+        // Listing brand/model di luar referensi (diterima dengan warning saat create)
+        sqlx::query(
+            "INSERT INTO vehicles (seller_id, title, category, price, brand, model, year, seats, vehicle_type, city, address, photos)
+             VALUES (2, 'Chery Tiggo 7 2024', 'sale', 400000000, 'Chery', 'Tiggo 7', 2024, 5, 'SUV', 'Jakarta', 'Jl. Sudirman No. 1', '[]'),
+                    (2, 'Honda WR-V 2023', 'sale', 280000000, 'Honda', 'WR-V', 2023, 5, 'SUV', 'Jakarta', 'Jl. Sudirman No. 1', '[]')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let brands = find_filter_brands(&pool).await.unwrap();
+        assert!(brands.contains(&brand(None, "Chery")));
+        let honda: Vec<_> = brands.iter().filter(|b| b.name == "Honda").collect();
+        assert_eq!(honda.len(), 1, "brand referensi tidak diduplikasi");
+        assert!(honda[0].id.is_some());
+
+        let honda_id = honda[0].id;
+        let models = find_filter_models(&pool, honda_id, "Honda").await.unwrap();
+        assert!(models.iter().any(|m| m.name == "Jazz" && m.id.is_some()));
+        assert!(models.contains(&Model { id: None, brand_id: honda_id, name: "WR-V".to_string() }));
+        assert_eq!(models.iter().filter(|m| m.name == "Jazz").count(), 1);
+
+        let models = find_filter_models(&pool, None, "Chery").await.unwrap();
+        assert_eq!(models, vec![Model { id: None, brand_id: None, name: "Tiggo 7".to_string() }]);
+    }
+}
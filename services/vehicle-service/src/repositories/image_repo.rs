@@ -0,0 +1,241 @@
+use sqlx::{PgConnection, PgPool};
+use serde_json::json;
+
+use crate::{
+    domain::{
+        image::{display_order, min_photos, normalize, photo_urls, VehicleImage, MAX_PHOTOS},
+        vehicle::Vehicle,
+    },
+    error::AppError,
+    repositories::vehicle_repo::VEHICLE_COLUMNS,
+};
+
+// Ambil gallery vehicle dalam urutan tampil (primary dulu, lalu position)
+pub async fn find_images(pool: &PgPool, vehicle_id: i32) -> Result<Vec<VehicleImage>, AppError> {
+    let mut conn = pool.acquire().await?;
+    load_images(&mut conn, vehicle_id).await
+}
+
+// Kunci vehicle sampai transaksi selesai: perubahan gallery (upload, hapus, reorder) dibaca dan
+// ditulis berurutan, jadi tidak ada yang menimpa gallery berdasarkan data yang sudah basi
+async fn lock_gallery(conn: &mut PgConnection, vehicle_id: i32) -> Result<(), AppError> {
+    sqlx::query("SELECT id FROM vehicles WHERE id = $1 FOR UPDATE")
+        .bind(vehicle_id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Vehicle tidak ditemukan"))?;
+
+    Ok(())
+}
+
+async fn load_images(conn: &mut PgConnection, vehicle_id: i32) -> Result<Vec<VehicleImage>, AppError> {
+    let images = sqlx::query_as(
+        "SELECT id, vehicle_id, url, position, is_primary
+         FROM vehicle_images
+         WHERE vehicle_id = $1
+         ORDER BY is_primary DESC, position ASC"
+    )
+    .bind(vehicle_id)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(images)
+}
+
+// Tambah foto di akhir gallery, foto pertama vehicle otomatis jadi primary
+pub async fn insert_images(
+    conn: &mut PgConnection,
+    vehicle_id: i32,
+    urls: &[String],
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO vehicle_images (vehicle_id, url, position, is_primary)
+         SELECT $1, u.url, base.next_position + (u.ord - 1)::INTEGER,
+                NOT base.has_primary AND u.ord = 1
+         FROM UNNEST($2::TEXT[]) WITH ORDINALITY AS u(url, ord)
+         CROSS JOIN (
+             SELECT COALESCE(MAX(position) + 1, 0) AS next_position,
+                    COALESCE(BOOL_OR(is_primary), false) AS has_primary
+             FROM vehicle_images
+             WHERE vehicle_id = $1
+         ) base"
+    )
+    .bind(vehicle_id)
+    .bind(urls)
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to insert images for vehicle {}: {:?}", vehicle_id, e);
+        AppError::internal(format!("Gagal menyimpan foto: {}", e))
+    })?;
+
+    Ok(())
+}
+
+// Tulis position & primary gallery, lalu salin urutan URL ke vehicles.photos
+async fn save_gallery(
+    conn: &mut PgConnection,
+    vehicle_id: i32,
+    images: &[VehicleImage],
+) -> Result<Vehicle, AppError> {
+    // Lepas primary lama dulu agar partial unique index tidak bentrok
+    sqlx::query("UPDATE vehicle_images SET is_primary = false WHERE vehicle_id = $1 AND is_primary = true")
+        .bind(vehicle_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for image in images {
+        sqlx::query("UPDATE vehicle_images SET position = $1, is_primary = $2 WHERE id = $3 AND vehicle_id = $4")
+            .bind(image.position)
+            .bind(image.is_primary)
+            .bind(image.id)
+            .bind(vehicle_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let vehicle = sqlx::query_as(&format!(
+        "UPDATE vehicles SET photos = $1, updated_at = NOW() WHERE id = $2 RETURNING {}",
+        VEHICLE_COLUMNS
+    ))
+    .bind(json!(photo_urls(images)))
+    .bind(vehicle_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to sync photos for vehicle {}: {:?}", vehicle_id, e);
+        AppError::internal(format!("Gagal update photos: {}", e))
+    })?;
+
+    Ok(vehicle)
+}
+
+// Ubah urutan/primary gallery (reorder atau set primary). `change` menerima gallery yang
+// dibaca di transaksi yang sama dengan penulisannya.
+pub async fn update_gallery<F>(
+    pool: &PgPool,
+    vehicle_id: i32,
+    change: F,
+) -> Result<Vehicle, AppError>
+where
+    F: FnOnce(Vec<VehicleImage>) -> Result<Vec<VehicleImage>, AppError>,
+{
+    let mut tx = pool.begin().await?;
+    lock_gallery(&mut tx, vehicle_id).await?;
+
+    let images = change(load_images(&mut tx, vehicle_id).await?)?;
+    let vehicle = save_gallery(&mut tx, vehicle_id, &images).await?;
+    tx.commit().await?;
+
+    Ok(vehicle)
+}
+
+// Upload foto tambahan ke akhir gallery, batas jumlah foto dicek ulang di dalam transaksi
+pub async fn add_images(
+    pool: &PgPool,
+    vehicle_id: i32,
+    urls: &[String],
+) -> Result<Vehicle, AppError> {
+    let mut tx = pool.begin().await?;
+    lock_gallery(&mut tx, vehicle_id).await?;
+
+    let existing = load_images(&mut tx, vehicle_id).await?.len();
+    if existing + urls.len() > MAX_PHOTOS {
+        return Err(AppError::validation(format!("Maksimal {} photos per vehicle", MAX_PHOTOS)));
+    }
+
+    insert_images(&mut tx, vehicle_id, urls).await?;
+    let images = normalize(load_images(&mut tx, vehicle_id).await?);
+    let vehicle = save_gallery(&mut tx, vehicle_id, &images).await?;
+
+    tx.commit().await?;
+
+    Ok(vehicle)
+}
+
+// Hapus foto ke-`index` (urutan tampil), position dirapikan dan primary dipindah ke foto
+// berikutnya jika perlu. Index dan minimal foto per category dicek di dalam transaksi.
+pub async fn remove_image(
+    pool: &PgPool,
+    vehicle_id: i32,
+    category: &str,
+    index: usize,
+) -> Result<Vehicle, AppError> {
+    let mut tx = pool.begin().await?;
+    lock_gallery(&mut tx, vehicle_id).await?;
+
+    let images = display_order(load_images(&mut tx, vehicle_id).await?);
+    let image_id = images
+        .get(index)
+        .map(|image| image.id)
+        .ok_or_else(|| AppError::validation("Index photo tidak valid"))?;
+
+    let min_photos = min_photos(category);
+    if images.len() <= min_photos {
+        return Err(AppError::validation(format!(
+            "Minimal {} photos untuk category {}",
+            min_photos, category
+        )));
+    }
+
+    sqlx::query("DELETE FROM vehicle_images WHERE id = $1 AND vehicle_id = $2")
+        .bind(image_id)
+        .bind(vehicle_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let images = normalize(load_images(&mut tx, vehicle_id).await?);
+    let vehicle = save_gallery(&mut tx, vehicle_id, &images).await?;
+
+    tx.commit().await?;
+
+    Ok(vehicle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::image::reorder;
+
+    async fn gallery_ids(pool: &PgPool, vehicle_id: i32) -> Vec<i32> {
+        find_images(pool, vehicle_id).await.unwrap().iter().map(|image| image.id).collect()
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_gallery_changes_read_inside_transaction(pool: PgPool) {
+        let urls: Vec<String> = (0..4).map(|i| format!("https://res.cloudinary.com/bigauto/vehicle-1-{}.jpg", i)).collect();
+        let vehicle = add_images(&pool, 1, &urls).await.unwrap();
+        assert_eq!(vehicle.photos, json!(urls));
+
+        let ids = gallery_ids(&pool, 1).await;
+        let reversed: Vec<i32> = ids.iter().rev().copied().collect();
+
+        // Urutan dari gallery yang dibaca di transaksi, primary tetap di depan
+        let vehicle = update_gallery(&pool, 1, |images| reorder(images, &reversed).map_err(AppError::validation))
+            .await
+            .unwrap();
+        assert_eq!(vehicle.photos[0], json!(urls[0]));
+        assert_eq!(vehicle.photos[1], json!(urls[3]));
+
+        // Error dari perubahan membatalkan transaksi tanpa mengubah gallery
+        let stale = update_gallery(&pool, 1, |images| reorder(images, &ids[..2]).map_err(AppError::validation)).await;
+        assert!(matches!(stale, Err(AppError::ValidationError(_))));
+        assert_eq!(gallery_ids(&pool, 1).await, vec![ids[0], ids[3], ids[2], ids[1]]);
+
+        // Hapus cover: foto berikutnya jadi primary; minimal 3 foto untuk rental
+        let vehicle = remove_image(&pool, 1, "rental", 0).await.unwrap();
+        assert_eq!(vehicle.photos, json!([urls[3], urls[2], urls[1]]));
+        assert!(matches!(remove_image(&pool, 1, "rental", 0).await, Err(AppError::ValidationError(_))));
+        assert!(matches!(remove_image(&pool, 99, "rental", 0).await, Err(AppError::NotFound(_))));
+
+        let too_many: Vec<String> = (0..8).map(|i| format!("https://res.cloudinary.com/bigauto/extra-{}.jpg", i)).collect();
+        assert!(matches!(add_images(&pool, 1, &too_many).await, Err(AppError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,75 @@
+use sqlx::PgPool;
+use serde_json::json;
+
+use crate::{
+    domain::inspection::{AttachInspectionRequest, VehicleInspection},
+    error::AppError,
+};
+
+// Ambil laporan inspeksi vehicle
+pub async fn find_inspection(
+    pool: &PgPool,
+    vehicle_id: i32,
+) -> Result<Option<VehicleInspection>, AppError> {
+    let inspection = sqlx::query_as(
+        "SELECT overall_score, condition_grade, component_ratings,
+                inspector_name, inspected_at, report_url, updated_at
+         FROM vehicle_inspections
+         WHERE vehicle_id = $1"
+    )
+    .bind(vehicle_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(inspection)
+}
+
+// Simpan laporan inspeksi (menggantikan yang lama) dan salin grade ke vehicles untuk filter
+pub async fn upsert_inspection(
+    pool: &PgPool,
+    vehicle_id: i32,
+    payload: &AttachInspectionRequest,
+    condition_grade: &str,
+) -> Result<VehicleInspection, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let inspection = sqlx::query_as(
+        "INSERT INTO vehicle_inspections (
+            vehicle_id, overall_score, condition_grade, component_ratings,
+            inspector_name, inspected_at, report_url
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (vehicle_id) DO UPDATE SET
+            overall_score = EXCLUDED.overall_score,
+            condition_grade = EXCLUDED.condition_grade,
+            component_ratings = EXCLUDED.component_ratings,
+            inspector_name = EXCLUDED.inspector_name,
+            inspected_at = EXCLUDED.inspected_at,
+            report_url = EXCLUDED.report_url,
+            updated_at = NOW()
+        RETURNING overall_score, condition_grade, component_ratings,
+                  inspector_name, inspected_at, report_url, updated_at"
+    )
+    .bind(vehicle_id)
+    .bind(payload.overall_score)
+    .bind(condition_grade)
+    .bind(json!(payload.component_ratings))
+    .bind(payload.inspector_name.trim())
+    .bind(payload.inspected_at)
+    .bind(&payload.report_url)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save inspection for vehicle {}: {:?}", vehicle_id, e);
+        AppError::internal(format!("Gagal menyimpan laporan inspeksi: {}", e))
+    })?;
+
+    sqlx::query("UPDATE vehicles SET condition_grade = $1, updated_at = NOW() WHERE id = $2")
+        .bind(condition_grade)
+        .bind(vehicle_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(inspection)
+}
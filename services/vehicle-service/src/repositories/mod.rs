@@ -1,2 +1,5 @@
 pub mod vehicle_repo;
 pub mod filter_repo;
+pub mod image_repo;
+pub mod inspection_repo;
+pub mod brand_repo;
@@ -2,10 +2,32 @@ use sqlx::{PgPool, Row, FromRow};
 use serde_json::json;
 
 use crate::{
-    domain::vehicle::{Vehicle, VehicleWithSeller, VehicleFilter, CreateVehicleRequest, UpdateVehicleRequest},
+    domain::availability::{plan_availability, Availability, BulkAvailabilityOutcome},
+    domain::vehicle::{
+        Vehicle, VehicleWithSeller, VehicleFilter, CreateVehicleRequest, UpdateVehicleRequest,
+        RentalInfoResponse,
+    },
     error::AppError,
+    repositories::image_repo,
+    utils::price_drop::{self, PriceChange},
 };
 
+// Kolom vehicles untuk struct Vehicle, kolom NUMERIC di-cast ke FLOAT8 (f64)
+pub(crate) const VEHICLE_COLUMNS: &str = "id, seller_id, title, category, price::FLOAT8 AS price, brand, model, year,
+    transmission, fuel_type, engine_capacity, mileage, seats, doors, luggage_capacity, vehicle_type,
+    is_luxury, is_flood_free, tax_active, has_bpkb, has_stnk, description, rental_terms,
+    deposit_amount::FLOAT8 AS deposit_amount, city, address, latitude::FLOAT8 AS latitude,
+    longitude::FLOAT8 AS longitude, area_coverage, photos, status, rating::FLOAT8 AS rating, review_count,
+    condition_grade, created_at, updated_at";
+
+// Hasil update vehicle beserta harga sebelum update (dibaca di statement yang sama)
+#[derive(FromRow)]
+struct UpdatedVehicle {
+    #[sqlx(flatten)]
+    vehicle: Vehicle,
+    old_price: f64,
+}
+
 // Ambil list vehicles dengan filtering dan pagination
 pub async fn find_vehicles(
     pool: &PgPool,
@@ -22,8 +44,8 @@ pub async fn find_vehicles(
         WHERE v.status = 'available'
           AND (v.category IS NULL OR v.category = $1)
           AND (v.city IS NULL OR v.city = $2)
-          AND (v.brand IS NULL OR v.brand = $3)
-          AND (v.model IS NULL OR v.model = $4)
+          AND (v.brand = $3 OR $3 IS NULL)
+          AND (v.model = $4 OR $4 IS NULL)
           AND (v.transmission IS NULL OR v.transmission = $5)
           AND (v.fuel_type IS NULL OR v.fuel_type = $6)
           AND (v.vehicle_type IS NULL OR v.vehicle_type = $7)
@@ -33,6 +55,7 @@ pub async fn find_vehicles(
           AND (v.year <= $11 OR $11 IS NULL)
           AND (v.seats >= $12 OR $12 IS NULL)
           AND (v.is_luxury = $13 OR $13 IS NULL)
+          AND (v.condition_grade <= $14 OR $14 IS NULL)
     "#;
 
     let total_result = sqlx::query(count_query)
@@ -49,6 +72,7 @@ pub async fn find_vehicles(
         .bind(filter.max_year)
         .bind(filter.min_seats)
         .bind(filter.is_luxury)
+        .bind(&filter.min_condition_grade)
         .fetch_one(pool)
         .await?;
 
@@ -63,14 +87,14 @@ pub async fn find_vehicles(
             v.brand, v.model, v.year, v.transmission, v.fuel_type, v.engine_capacity,
             v.mileage, v.seats, v.doors, v.luggage_capacity, v.vehicle_type,
             v.is_luxury, v.is_flood_free, v.tax_active, v.has_bpkb, v.has_stnk,
-            u.name as seller_name
+            v.condition_grade, v.deposit_amount, u.name as seller_name
         FROM vehicles v
         INNER JOIN users u ON v.seller_id = u.id
         WHERE v.status = 'available'
           AND (v.category IS NULL OR v.category = $1)
           AND (v.city IS NULL OR v.city = $2)
-          AND (v.brand IS NULL OR v.brand = $3)
-          AND (v.model IS NULL OR v.model = $4)
+          AND (v.brand = $3 OR $3 IS NULL)
+          AND (v.model = $4 OR $4 IS NULL)
           AND (v.transmission IS NULL OR v.transmission = $5)
           AND (v.fuel_type IS NULL OR v.fuel_type = $6)
           AND (v.vehicle_type IS NULL OR v.vehicle_type = $7)
@@ -80,6 +104,7 @@ pub async fn find_vehicles(
           AND (v.year <= $11 OR $11 IS NULL)
           AND (v.seats >= $12 OR $12 IS NULL)
           AND (v.is_luxury = $13 OR $13 IS NULL)
+          AND (v.condition_grade <= $14 OR $14 IS NULL)
     "#;
 
     let sort = match filter.sort_by.as_deref() {
@@ -127,6 +152,8 @@ pub async fn find_vehicles(
         tax_active: bool,
         has_bpkb: bool,
         has_stnk: bool,
+        condition_grade: Option<String>,
+        deposit_amount: f64,
         seller_name: String,
     }
 
@@ -144,6 +171,7 @@ pub async fn find_vehicles(
         .bind(filter.max_year)
         .bind(filter.min_seats)
         .bind(filter.is_luxury)
+        .bind(&filter.min_condition_grade)
         .fetch_all(pool)
         .await?;
 
@@ -174,6 +202,7 @@ pub async fn find_vehicles(
             has_stnk: vehicle_row.has_stnk,
             description: vehicle_row.description,
             rental_terms: vehicle_row.rental_terms,
+            deposit_amount: vehicle_row.deposit_amount,
             city: vehicle_row.city,
             address: vehicle_row.address,
             latitude: vehicle_row.latitude,
@@ -183,6 +212,7 @@ pub async fn find_vehicles(
             status: vehicle_row.status,
             rating: vehicle_row.rating,
             review_count: vehicle_row.review_count,
+            condition_grade: vehicle_row.condition_grade,
             created_at: vehicle_row.created_at,
             updated_at: vehicle_row.updated_at,
             seller_name: vehicle_row.seller_name,
@@ -210,6 +240,21 @@ pub async fn find_vehicle_by_id(
     Ok(result)
 }
 
+// Data rental (harga per hari + deposit) untuk booking-service, None jika bukan vehicle rental
+pub async fn find_rental_info(pool: &PgPool, id: i32) -> Result<Option<RentalInfoResponse>, AppError> {
+    let result = sqlx::query_as(
+        "SELECT id, seller_id, price::FLOAT8 AS price_per_day, deposit_amount::FLOAT8 AS deposit_amount,
+                status = 'available' AS is_available
+         FROM vehicles
+         WHERE id = $1 AND category = 'rental'"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
 // Create vehicle baru
 pub async fn create_vehicle(
     pool: &PgPool,
@@ -218,18 +263,21 @@ pub async fn create_vehicle(
 ) -> Result<Vehicle, AppError> {
     let photos_json = json!(payload.photos);
 
-    let vehicle = sqlx::query_as(
+    // Vehicle dan gallery-nya dibuat dalam satu transaksi
+    let mut tx = pool.begin().await?;
+
+    let vehicle: Vehicle = sqlx::query_as(
         "INSERT INTO vehicles (
             seller_id, title, category, price, brand, model, year,
             transmission, fuel_type, engine_capacity, mileage,
             seats, doors, luggage_capacity, vehicle_type, is_luxury,
             is_flood_free, tax_active, has_bpkb, has_stnk,
             description, rental_terms, city, address,
-            latitude, longitude, photos
+            latitude, longitude, photos, deposit_amount
         ) VALUES (
             $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
             $12, $13, $14, $15, $16, $17, $18, $19, $20,
-            $21, $22, $23, $24, $25, $26, $27
+            $21, $22, $23, $24, $25, $26, $27, $28
         ) RETURNING *"
     )
     .bind(seller_id)
@@ -259,20 +307,28 @@ pub async fn create_vehicle(
     .bind(payload.latitude)
     .bind(payload.longitude)
     .bind(photos_json)
-    .fetch_one(pool)
+    .bind(payload.deposit_amount.unwrap_or(0.0))
+    .fetch_one(&mut *tx)
     .await?;
 
+    image_repo::insert_images(&mut tx, vehicle.id, &payload.photos).await?;
+
+    tx.commit().await?;
+
     Ok(vehicle)
 }
 
-// Update vehicle dengan dynamic fields
+// Update vehicle dengan dynamic fields.
+// Harga lama dikunci dan dibaca di statement yang sama, jadi update harga yang bersamaan
+// tetap tercatat berurutan di antrean price drop.
 pub async fn update_vehicle(
     pool: &PgPool,
     id: i32,
     payload: &UpdateVehicleRequest,
-) -> Result<Vehicle, AppError> {
-    let vehicle = sqlx::query_as(
-        "UPDATE vehicles SET
+) -> Result<(Vehicle, f64), AppError> {
+    let updated: UpdatedVehicle = sqlx::query_as(&format!(
+        "WITH old AS (SELECT id AS old_id, price AS old_price FROM vehicles WHERE id = $18 FOR UPDATE)
+         UPDATE vehicles SET
             title = COALESCE($1, title),
             price = COALESCE($2, price),
             transmission = COALESCE($3, transmission),
@@ -287,10 +343,15 @@ pub async fn update_vehicle(
             address = COALESCE($12, address),
             latitude = COALESCE($13, latitude),
             longitude = COALESCE($14, longitude),
+            brand = COALESCE($15, brand),
+            model = COALESCE($16, model),
+            deposit_amount = COALESCE($17, deposit_amount),
             updated_at = NOW()
-         WHERE id = $15
-         RETURNING *"
-    )
+         FROM old
+         WHERE id = old.old_id
+         RETURNING {}, old.old_price::FLOAT8 AS old_price",
+        VEHICLE_COLUMNS
+    ))
     .bind(&payload.title)
     .bind(payload.price)
     .bind(&payload.transmission)
@@ -305,44 +366,125 @@ pub async fn update_vehicle(
     .bind(&payload.address)
     .bind(payload.latitude)
     .bind(payload.longitude)
+    .bind(&payload.brand)
+    .bind(&payload.model)
+    .bind(payload.deposit_amount)
     .bind(id)
     .fetch_one(pool)
     .await?;
 
-    Ok(vehicle)
+    Ok((updated.vehicle, updated.old_price))
 }
 
-// Soft delete vehicle (update status ke sold)
-pub async fn delete_vehicle(pool: &PgPool, id: i32) -> Result<(), AppError> {
-    sqlx::query("UPDATE vehicles SET status = 'sold', updated_at = NOW() WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
+// Catat perubahan harga ke antrean notifikasi price drop.
+// Window debounce dihitung dari penurunan pertama, edit berikutnya hanya memperbarui harga terbaru.
+pub async fn record_price_change(
+    pool: &PgPool,
+    vehicle_id: i32,
+    old_price: f64,
+    new_price: f64,
+    debounce_minutes: i64,
+) -> Result<(), AppError> {
+    match price_drop::classify(old_price, new_price) {
+        PriceChange::Dropped => {
+            sqlx::query(
+                "INSERT INTO vehicle_price_drops (vehicle_id, previous_price, current_price, notify_after)
+                 VALUES ($1, $2::NUMERIC, $3::NUMERIC, NOW() + $4::BIGINT * INTERVAL '1 minute')
+                 ON CONFLICT (vehicle_id) DO UPDATE SET current_price = EXCLUDED.current_price"
+            )
+            .bind(vehicle_id)
+            .bind(old_price)
+            .bind(new_price)
+            .bind(debounce_minutes)
+            .execute(pool)
+            .await?;
+        }
+        PriceChange::Raised => {
+            sqlx::query("UPDATE vehicle_price_drops SET current_price = $2::NUMERIC WHERE vehicle_id = $1")
+                .bind(vehicle_id)
+                .bind(new_price)
+                .execute(pool)
+                .await?;
+        }
+        PriceChange::Unchanged => {}
+    }
 
     Ok(())
 }
 
-// Update photos vehicle
-pub async fn update_photos(
+// Ubah availability banyak vehicle dalam satu transaksi.
+// Hanya vehicle milik seller dengan status available/unavailable yang diubah, sisanya dilaporkan per id.
+// Vehicle yang kembali available memicu notifikasi ke user yang mem-favorite.
+pub async fn bulk_update_availability(
     pool: &PgPool,
-    id: i32,
-    photos: Vec<String>,
-) -> Result<Vehicle, AppError> {
-    let photos_json = json!(photos);
+    seller_id: i32,
+    vehicle_ids: &[i32],
+    target: Availability,
+) -> Result<(Vec<(i32, BulkAvailabilityOutcome)>, u64), AppError> {
+    let mut tx = pool.begin().await?;
 
-    let vehicle = sqlx::query_as(
-        "UPDATE vehicles SET photos = $1, updated_at = NOW() WHERE id = $2 RETURNING *"
+    let current: Vec<(i32, i32, String)> = sqlx::query_as(
+        "SELECT id, seller_id, status FROM vehicles WHERE id = ANY($1) FOR UPDATE"
     )
-    .bind(photos_json)
-    .bind(id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update photos for vehicle {}: {:?}", id, e);
-        AppError::internal(format!("Gagal update photos: {}", e))
-    })?;
+    .bind(vehicle_ids)
+    .fetch_all(&mut *tx)
+    .await?;
 
-    Ok(vehicle)
+    let outcomes: Vec<(i32, BulkAvailabilityOutcome)> = vehicle_ids
+        .iter()
+        .map(|id| {
+            let vehicle = current
+                .iter()
+                .find(|(vehicle_id, _, _)| vehicle_id == id)
+                .map(|(_, owner_id, status)| (*owner_id, status.as_str()));
+            (*id, plan_availability(vehicle, seller_id, target))
+        })
+        .collect();
+
+    let to_update: Vec<i32> = outcomes
+        .iter()
+        .filter(|(_, outcome)| *outcome == BulkAvailabilityOutcome::Updated)
+        .map(|(id, _)| *id)
+        .collect();
+
+    let mut notified = 0;
+    if !to_update.is_empty() {
+        sqlx::query("UPDATE vehicles SET status = $1, updated_at = NOW() WHERE id = ANY($2)")
+            .bind(target.as_str())
+            .bind(&to_update)
+            .execute(&mut *tx)
+            .await?;
+
+        if target == Availability::Available {
+            notified = sqlx::query(
+                "INSERT INTO notifications (user_id, type, title, message, related_id, related_type)
+                 SELECT f.customer_id, 'vehicle_available', 'Tersedia lagi',
+                        v.title || ' kembali tersedia', v.id, 'vehicle'
+                 FROM vehicles v
+                 JOIN favorites f ON f.vehicle_id = v.id
+                 WHERE v.id = ANY($1)
+                   AND f.customer_id <> v.seller_id"
+            )
+            .bind(&to_update)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok((outcomes, notified))
+}
+
+// Soft delete vehicle (update status ke sold)
+pub async fn delete_vehicle(pool: &PgPool, id: i32) -> Result<(), AppError> {
+    sqlx::query("UPDATE vehicles SET status = 'sold', updated_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
 }
 
 // Ambil seller name by ID
@@ -385,3 +527,173 @@ pub async fn check_ownership(
     Ok(vehicle)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::{HeaderMap, StatusCode}, routing::post, Router};
+    use shared::utils::internal_notifications::{verify_request, CreateNotificationsRequest, NOTIFICATIONS_PATH};
+    use std::sync::{Arc, Mutex};
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_rental_info_includes_deposit(pool: PgPool) {
+        let info = find_rental_info(&pool, 1).await.unwrap().unwrap();
+        assert_eq!((info.seller_id, info.price_per_day, info.deposit_amount), (2, 350_000.0, 1_000_000.0));
+        assert!(info.is_available);
+
+        // Vehicle sale bukan vehicle rental
+        assert!(find_rental_info(&pool, 2).await.unwrap().is_none());
+
+        sqlx::query("UPDATE vehicles SET status = 'unavailable' WHERE id = 1").execute(&pool).await.unwrap();
+        assert!(!find_rental_info(&pool, 1).await.unwrap().unwrap().is_available);
+    }
+
+    const INTERNAL_SECRET: &str = "internal-test-secret";
+
+    // Stub notification-service: mencatat (user_id, message) notifikasi yang diterima,
+    // status respons bisa diubah untuk mensimulasikan notification-service down
+    async fn spawn_notification_service() -> (String, Arc<Mutex<Vec<(i32, String)>>>, Arc<Mutex<StatusCode>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let status = Arc::new(Mutex::new(StatusCode::CREATED));
+        let (recorded, response_status) = (received.clone(), status.clone());
+        let router = Router::new().route(
+            NOTIFICATIONS_PATH,
+            post(move |headers: HeaderMap, body: Bytes| {
+                let (recorded, response_status) = (recorded.clone(), response_status.clone());
+                async move {
+                    let now = chrono::Utc::now().timestamp();
+                    if !verify_request(INTERNAL_SECRET, &headers, &body, now) {
+                        return StatusCode::UNAUTHORIZED;
+                    }
+                    let status = *response_status.lock().unwrap();
+                    if status.is_success() {
+                        let request: CreateNotificationsRequest = serde_json::from_slice(&body).unwrap();
+                        recorded.lock().unwrap().extend(request.notifications.into_iter().map(|n| (n.user_id, n.message)));
+                    }
+                    status
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{}", addr), received, status)
+    }
+
+    async fn flush(pool: &PgPool, url: &str) -> u64 {
+        Vehicle::flush_price_drop_notifications(pool, &reqwest::Client::new(), url, INTERNAL_SECRET).await.unwrap()
+    }
+
+    // Update harga lewat update_vehicle, harga lama dari statement yang sama masuk antrean
+    async fn update_price(pool: &PgPool, vehicle_id: i32, price: f64) {
+        let payload: UpdateVehicleRequest = serde_json::from_value(json!({ "price": price })).unwrap();
+        let (vehicle, old_price) = update_vehicle(pool, vehicle_id, &payload).await.unwrap();
+        assert_eq!(vehicle.price, price);
+        record_price_change(pool, vehicle_id, old_price, vehicle.price, 30).await.unwrap();
+    }
+
+    // Window debounce dianggap sudah selesai tanpa menunggu scheduler (termasuk lease claim)
+    async fn close_window(pool: &PgPool) {
+        sqlx::query("UPDATE vehicle_price_drops SET notify_after = NOW()").execute(pool).await.unwrap();
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_increase_does_not_notify_and_decrease_does(pool: PgPool) {
+        sqlx::query("INSERT INTO favorites (customer_id, vehicle_id) VALUES (1, 2), (3, 2)").execute(&pool).await.unwrap();
+        sqlx::query("UPDATE favorites SET price_alerts_muted = true WHERE customer_id = 3").execute(&pool).await.unwrap();
+        let (url, received, status) = spawn_notification_service().await;
+
+        // Harga naik: tidak ada window, tidak ada notifikasi
+        update_price(&pool, 2, 185_000_000.0).await;
+        close_window(&pool).await;
+        assert_eq!(flush(&pool, &url).await, 0);
+        assert!(received.lock().unwrap().is_empty());
+
+        // Dua penurunan dalam satu window: satu notifikasi dengan harga awal dan harga akhir
+        update_price(&pool, 2, 175_000_000.0).await;
+        update_price(&pool, 2, 170_000_000.0).await;
+        assert_eq!(flush(&pool, &url).await, 0, "window belum selesai");
+
+        // Notification-service down: window tetap ada dan dikirim ulang setelah lease habis
+        *status.lock().unwrap() = StatusCode::SERVICE_UNAVAILABLE;
+        close_window(&pool).await;
+        assert_eq!(flush(&pool, &url).await, 0);
+        assert_eq!(flush(&pool, &url).await, 0, "window masih di-claim");
+
+        *status.lock().unwrap() = StatusCode::CREATED;
+        close_window(&pool).await;
+        assert_eq!(flush(&pool, &url).await, 1);
+
+        // Favorite yang di-mute tidak menerima notifikasi
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![(1, "Honda Jazz 2019 turun dari Rp 185.000.000 menjadi Rp 170.000.000".to_string())]
+        );
+
+        // Turun lalu naik lagi ke harga semula dalam window: window dibuang tanpa notifikasi
+        update_price(&pool, 2, 160_000_000.0).await;
+        update_price(&pool, 2, 170_000_000.0).await;
+        close_window(&pool).await;
+        assert_eq!(flush(&pool, &url).await, 0);
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM vehicle_price_drops").fetch_one(&pool).await.unwrap();
+        assert_eq!(pending, 0);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../../database/supabase/schema.sql",
+            "../../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_bulk_update_availability(pool: PgPool) {
+        sqlx::query("INSERT INTO favorites (customer_id, vehicle_id) VALUES (1, 1)").execute(&pool).await.unwrap();
+
+        // Offline: vehicle milik seller diubah, id yang tidak ada dilaporkan
+        let (outcomes, notified) = bulk_update_availability(&pool, 2, &[1, 2, 99], Availability::Unavailable).await.unwrap();
+        assert_eq!(outcomes, vec![
+            (1, BulkAvailabilityOutcome::Updated),
+            (2, BulkAvailabilityOutcome::Updated),
+            (99, BulkAvailabilityOutcome::NotFound),
+        ]);
+        assert_eq!(notified, 0);
+
+        // Seller lain tidak bisa mengubah, status tetap unavailable
+        let (outcomes, _) = bulk_update_availability(&pool, 3, &[1], Availability::Available).await.unwrap();
+        assert_eq!(outcomes, vec![(1, BulkAvailabilityOutcome::Forbidden)]);
+
+        // Vehicle dalam proses jual tidak ikut diubah, yang kembali available memicu notifikasi favorite
+        sqlx::query("UPDATE vehicles SET status = 'pending_sale' WHERE id = 2").execute(&pool).await.unwrap();
+        let (outcomes, notified) = bulk_update_availability(&pool, 2, &[1, 2], Availability::Available).await.unwrap();
+        assert_eq!(outcomes, vec![(1, BulkAvailabilityOutcome::Updated), (2, BulkAvailabilityOutcome::InvalidStatus)]);
+        assert_eq!(notified, 1);
+
+        let statuses: Vec<String> = sqlx::query_scalar("SELECT status FROM vehicles ORDER BY id").fetch_all(&pool).await.unwrap();
+        assert_eq!(statuses, vec!["available", "pending_sale"]);
+
+        // Listing yang di-offline-kan seller tidak dihapus cleanup walau lama tidak di-update
+        bulk_update_availability(&pool, 2, &[1], Availability::Unavailable).await.unwrap();
+        sqlx::query("UPDATE vehicles SET updated_at = NOW() - INTERVAL '120 days' WHERE id = 1").execute(&pool).await.unwrap();
+        Vehicle::cleanup_inactive_vehicles(&pool).await.unwrap();
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM vehicles WHERE id = 1").fetch_one(&pool).await.unwrap();
+        assert_eq!(remaining, 1);
+    }
+}
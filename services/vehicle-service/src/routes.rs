@@ -1,20 +1,23 @@
 use axum::{
     routing::{get, post, put, delete},
     Router, Json, extract::State, 
-    http::{header, HeaderValue, Method, StatusCode}, 
+    http::{header, Method, StatusCode}, 
     middleware::{self, Next}, response::Response,
 };
 use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
+use shared::utils::cors::CorsPolicy;
+use shared::utils::request_timeout;
 use utoipa::{OpenApi, Modify};
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_redoc::{Redoc, Servable};
 use std::env;
 
-use crate::handlers::{vehicles, photos, filters};
+use crate::handlers::{vehicles, photos, inspections, filters, brands};
 use crate::middleware::{auth::auth_middleware, rate_limit::rate_limit_middleware};
-use crate::config::{HealthStatus, check_db_health, AppState};
+use crate::config::{HealthStatus, AppState};
+use crate::error::AppError;
 
 struct SecurityAddon;
 
@@ -40,19 +43,27 @@ impl Modify for SecurityAddon {
     info(
         title = "Big Auto - Vehicle Service API",
         version = "1.0.0",
-        description = "Vehicle Management Service\n\n## Features\n\n- 🚗 Vehicle CRUD (Rental & Sale)\n- 📸 Photo Management\n- 🔍 Advanced Filtering\n- 📍 Master Data (Cities, Brands, Models)\n\n## Authentication\n\nSeller endpoints require JWT token.\nInclude in `Authorization: Bearer {token}` header.\n",
+        description = "Vehicle Management Service\n\n## Features\n\n- 🚗 Vehicle CRUD (Rental & Sale)\n- 📸 Photo Management (urutan gallery & cover photo)\n- 🧾 Inspection Report & Condition Grade\n- 🔍 Advanced Filtering\n- 📍 Master Data (Cities, Brands, Models)\n\n## Authentication\n\nSeller endpoints require JWT token.\nInclude in `Authorization: Bearer {token}` header.\n",
     ),
     paths(
         vehicles::list_vehicles,
         vehicles::get_vehicle,
+        vehicles::get_rental_info,
         vehicles::create_vehicle,
         vehicles::update_vehicle,
         vehicles::delete_vehicle,
+        vehicles::bulk_update_availability,
         photos::upload_photos,
         photos::delete_photo,
+        photos::list_images,
+        photos::reorder_images,
+        photos::set_primary_image,
+        inspections::attach_inspection,
         filters::get_cities,
         filters::get_brands,
         filters::get_models,
+        brands::list_brands,
+        brands::list_models,
     ),
     modifiers(&SecurityAddon),
     components(
@@ -62,9 +73,21 @@ impl Modify for SecurityAddon {
             crate::domain::vehicle::VehicleFilter,
             crate::domain::vehicle::CreateVehicleRequest,
             crate::domain::vehicle::UpdateVehicleRequest,
+            crate::domain::vehicle::RentalInfoResponse,
+            crate::domain::availability::Availability,
+            crate::domain::availability::BulkAvailabilityRequest,
+            crate::domain::availability::BulkAvailabilityOutcome,
+            crate::domain::availability::BulkAvailabilityResult,
+            crate::domain::availability::BulkAvailabilityResponse,
             crate::domain::vehicle::City,
             crate::domain::vehicle::Brand,
             crate::domain::vehicle::Model,
+            crate::domain::brand::VehicleBrand,
+            crate::domain::brand::VehicleModel,
+            crate::domain::image::VehicleImage,
+            crate::domain::image::ReorderImagesRequest,
+            crate::domain::inspection::AttachInspectionRequest,
+            crate::domain::inspection::InspectionResponse,
             vehicles::MessageResponse,
             filters::BrandQuery,
         )
@@ -72,30 +95,22 @@ impl Modify for SecurityAddon {
     tags(
         (name = "Vehicles", description = "Vehicle management endpoints"),
         (name = "Photos", description = "Photo management endpoints"),
-        (name = "Filters", description = "Master data filter endpoints")
+        (name = "Inspections", description = "Laporan inspeksi kondisi vehicle"),
+        (name = "Filters", description = "Master data filter endpoints"),
+        (name = "Brands", description = "Referensi brand & model vehicle")
     )
 )]
 struct ApiDoc;
 
 // Health check endpoint
 async fn health_check(State(pool): State<PgPool>) -> Json<HealthStatus> {
-    let db_healthy = check_db_health(&pool).await;
-
-    Json(HealthStatus {
-        database: if db_healthy { "healthy" } else { "unhealthy" }.to_string(),
-        overall: if db_healthy { "healthy" } else { "degraded" }.to_string(),
-    })
+    Json(HealthStatus::check(&pool).await)
 }
 
 // JWT-Only CORS
 fn configure_cors() -> CorsLayer {
-    let frontend_urls = env::var("FRONTEND_URL")
-        .expect("FRONTEND_URL environment variable harus diset");
-
-    let allowed_origins: Vec<HeaderValue> = frontend_urls
-        .split(',')
-        .map(|s| s.trim().parse::<HeaderValue>().expect("Invalid FRONTEND_URL format"))
-        .collect();
+    // Origin hanya di-echo jika ada di FRONTEND_URL, credentials via CORS_ALLOW_CREDENTIALS
+    let policy = CorsPolicy::from_env().expect("Konfigurasi CORS tidak valid");
 
     let allowed_methods = vec![
         Method::GET,
@@ -117,14 +132,18 @@ fn configure_cors() -> CorsLayer {
         std::time::Duration::from_secs(3600)
     };
 
-    CorsLayer::new()
-        .allow_origin(allowed_origins)
+    policy
+        .apply(CorsLayer::new())
         .allow_methods(allowed_methods)
         .allow_headers(allowed_headers)
-        .allow_credentials(false)
         .max_age(max_age)
 }
 
+// Fallback 404 dengan format JSON yang sama dengan AppError
+async fn not_found_handler() -> AppError {
+    AppError::not_found("API endpoint tidak ditemukan")
+}
+
 // Buat router dengan JWT-Only security
 pub fn create_router(state: AppState) -> Router {
     // Log environment information
@@ -138,10 +157,14 @@ pub fn create_router(state: AppState) -> Router {
 
     Router::new()
         .route("/health", get(health_check).with_state(state.db.clone()))
+        // Service-to-service (booking-service), tanpa JWT
+        .route("/vehicles/{id}/rental-info", get(vehicles::get_rental_info).with_state(state.db.clone()))
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi.clone()))
         .merge(Redoc::with_url("/redoc", openapi))
         // Merge API routes
         .merge(build_api_routes_with_auth(state.clone()))
+        .fallback(not_found_handler)
         // CORS layer 
         .layer(configure_cors())
         // Security headers 
@@ -182,6 +205,9 @@ async fn security_headers_middleware(request: axum::extract::Request, next: Next
 
 // Build API routes dengan JWT authentication
 fn build_api_routes_with_auth(state: AppState) -> Router {
+    // Upload foto multipart boleh lebih lama dari timeout default
+    let upload_timeout = request_timeout::route_timeout("UPLOAD_REQUEST_TIMEOUT_SECS", 120);
+
     // All API routes require JWT authentication
     let api_routes = Router::new()
         // Vehicles - All endpoints
@@ -190,15 +216,29 @@ fn build_api_routes_with_auth(state: AppState) -> Router {
         .route("/api/vehicles", post(vehicles::create_vehicle))
         .route("/api/vehicles/{id}", put(vehicles::update_vehicle))
         .route("/api/vehicles/{id}", delete(vehicles::delete_vehicle))
+        .route("/api/vehicles/bulk-availability", post(vehicles::bulk_update_availability))
+
+        // Referensi brand & model (typeahead form listing)
+        .route("/api/vehicles/brands", get(brands::list_brands))
+        .route("/api/vehicles/brands/{id}/models", get(brands::list_models))
 
-        // Photos - All endpoints
-        .route("/api/vehicles/{id}/photos", post(photos::upload_photos))
+        // Photos - All endpoints (upload di bawah, timeout sendiri)
         .route("/api/vehicles/{id}/photos/{index}", delete(photos::delete_photo))
+        .route("/api/vehicles/{id}/images", get(photos::list_images))
+        .route("/api/vehicles/{id}/images/order", put(photos::reorder_images))
+        .route("/api/vehicles/{id}/images/{image_id}/primary", put(photos::set_primary_image))
+
+        // Inspections
+        .route("/api/vehicles/{id}/inspection", put(inspections::attach_inspection))
 
         // Filters - All endpoints
         .route("/api/filters/cities", get(filters::get_cities))
         .route("/api/filters/brands", get(filters::get_brands))
         .route("/api/filters/models", get(filters::get_models))
+
+        // Request yang menggantung dijawab 504 setelah REQUEST_TIMEOUT_SECS
+        .route_layer(request_timeout::layer(request_timeout::default_timeout()))
+        .route("/api/vehicles/{id}/photos", post(photos::upload_photos).layer(request_timeout::layer(upload_timeout)))
         .layer(middleware::from_fn_with_state(state.clone(), auth_middleware))
         .with_state(state);
 
@@ -1,6 +1,6 @@
 use crate::config::AppState;
 use crate::domain::vehicle::Vehicle;
-use std::time::Duration;
+use shared::utils::scheduler::{run_job, spawn_job, JobSchedule, Ticker};
 
 /// Background scheduler for vehicle service cleanup and maintenance
 pub struct VehicleScheduler {
@@ -22,87 +22,53 @@ impl VehicleScheduler {
 
         tracing::info!("🚗 Starting Vehicle Service Background Scheduler...");
 
-        // Spawn task for vehicle maintenance tasks
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(1800)); // Every 30 minutes
+        // Price drop notifications (window debounce selesai), default every minute
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("VEHICLE_PRICE_DROP_INTERVAL_SECS", 60)),
+            self.state.clone(),
+            |state| async move { send_price_drop_notifications(&state).await },
+        );
 
-            loop {
-                interval.tick().await;
+        // Vehicle maintenance tasks, default every 30 minutes
+        spawn_job(
+            Ticker::interval(JobSchedule::from_env("VEHICLE_CLEANUP_INTERVAL_SECS", 1800)),
+            self.state,
+            |state| async move { run_cleanup(&state).await },
+        );
+    }
+}
 
-                tracing::info!("🧹 Running vehicle service cleanup tasks...");
+// Satu tick notifikasi price drop
+async fn send_price_drop_notifications(state: &AppState) {
+    // Tanpa secret notification-service menolak request, window tetap diantrekan
+    let Some(secret) = state.config.internal_service_secret.as_deref() else {
+        tracing::warn!("INTERNAL_SERVICE_SECRET belum diset, notifikasi price drop ditahan");
+        return;
+    };
 
-                // Cleanup expired vehicle listings
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match Vehicle::cleanup_expired_listings(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} expired vehicle listings", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup expired listings after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+    let _ = run_job("Send price drop notifications", 1, || {
+        Vehicle::flush_price_drop_notifications(
+            &state.db,
+            &state.http_client,
+            &state.config.notification_service_url,
+            secret,
+        )
+    })
+    .await;
+}
 
-                // Update vehicle status for expired listings
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match Vehicle::update_expired_status(&db).await {
-                            Ok(updated) => {
-                                if updated > 0 {
-                                    tracing::info!("✅ Updated status for {} expired vehicles", updated);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to update expired vehicle status after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+// Satu tick cleanup vehicle
+async fn run_cleanup(state: &AppState) {
+    tracing::info!("🧹 Running vehicle service cleanup tasks...");
 
-                // Cleanup inactive vehicles (not updated in 90 days)
-                let db = self.state.db.clone();
-                tokio::spawn(async move {
-                    for attempt in 1..=3 {
-                        match Vehicle::cleanup_inactive_vehicles(&db).await {
-                            Ok(deleted) => {
-                                if deleted > 0 {
-                                    tracing::info!("✅ Cleaned up {} inactive vehicles", deleted);
-                                }
-                                break;
-                            }
-                            Err(e) => {
-                                if attempt == 3 {
-                                    tracing::error!("❌ Failed to cleanup inactive vehicles after 3 attempts: {}", e);
-                                } else {
-                                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                                }
-                            }
-                        }
-                    }
-                });
+    // Cleanup expired vehicle listings
+    let _ = run_job("Cleanup expired vehicle listings", 3, || Vehicle::cleanup_expired_listings(&state.db)).await;
 
-  
+    // Update vehicle status for expired listings
+    let _ = run_job("Update status for expired vehicles", 3, || Vehicle::update_expired_status(&state.db)).await;
 
-                tracing::info!("✅ Vehicle service cleanup tasks completed");
-            }
-        });
-    }
+    // Cleanup inactive vehicles (not updated in 90 days)
+    let _ = run_job("Cleanup inactive vehicles", 3, || Vehicle::cleanup_inactive_vehicles(&state.db)).await;
 
-  
-}
\ No newline at end of file
+    tracing::info!("✅ Vehicle service cleanup tasks completed");
+}
@@ -1,2 +1,3 @@
 // Vehicle Service Utils
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod price_drop;
@@ -0,0 +1,72 @@
+// Deteksi penurunan harga vehicle untuk notifikasi user yang mem-favorite
+//
+// Penurunan harga membuka window debounce. Edit harga berikutnya dalam window hanya
+// memperbarui harga terbaru, lalu scheduler mengirim satu notifikasi saat window selesai
+// (hanya jika harga akhir masih lebih rendah dari harga sebelum window).
+
+// Default lama window debounce jika PRICE_DROP_DEBOUNCE_MINUTES tidak diset
+pub const DEFAULT_DEBOUNCE_MINUTES: i64 = 30;
+
+// Window yang sedang dikirim di-claim selama ini; jika pengiriman gagal atau instance mati,
+// window muncul lagi setelah lease habis dan dikirim ulang
+pub const CLAIM_LEASE_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceChange {
+    // Harga turun: buka window baru atau perbarui window yang sedang berjalan
+    Dropped,
+    // Harga naik: tidak membuka window, hanya memperbarui window yang sedang berjalan
+    Raised,
+    Unchanged,
+}
+
+pub fn classify(old_price: f64, new_price: f64) -> PriceChange {
+    if new_price < old_price {
+        PriceChange::Dropped
+    } else if new_price > old_price {
+        PriceChange::Raised
+    } else {
+        PriceChange::Unchanged
+    }
+}
+
+// Harga dalam format "Rp 185.000.000" (pecahan rupiah dibulatkan)
+pub fn format_rupiah(amount: f64) -> String {
+    let digits = (amount.round() as i64).unsigned_abs().to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    format!("Rp {}", grouped)
+}
+
+// Isi notifikasi price drop untuk user yang mem-favorite
+pub fn notification_message(title: &str, previous_price: f64, current_price: f64) -> String {
+    format!("{} turun dari {} menjadi {}", title, format_rupiah(previous_price), format_rupiah(current_price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_price_change() {
+        assert_eq!(classify(250_000_000.0, 260_000_000.0), PriceChange::Raised);
+        assert_eq!(classify(250_000_000.0, 240_000_000.0), PriceChange::Dropped);
+        assert_eq!(classify(250_000_000.0, 250_000_000.0), PriceChange::Unchanged);
+    }
+
+    #[test]
+    fn test_notification_message_formats_rupiah() {
+        assert_eq!(format_rupiah(950.0), "Rp 950");
+        assert_eq!(format_rupiah(350_000.0), "Rp 350.000");
+        assert_eq!(format_rupiah(185_000_000.4), "Rp 185.000.000");
+        assert_eq!(
+            notification_message("Honda Jazz", 185_000_000.0, 170_000_000.0),
+            "Honda Jazz turun dari Rp 185.000.000 menjadi Rp 170.000.000"
+        );
+    }
+}
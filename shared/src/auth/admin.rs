@@ -0,0 +1,138 @@
+// Extractor admin bersama: role JWT tidak punya admin, jadi flag is_admin dicek langsung ke database.
+// Setiap service cukup implement AdminState untuk AppState-nya.
+
+use axum::{extract::FromRequestParts, http::request::Parts, response::IntoResponse};
+use sqlx::PgPool;
+
+use super::extractor::AuthUser;
+use super::AuthError;
+
+// Admin platform terautentikasi
+#[derive(Debug, Clone)]
+pub struct AuthAdmin {
+    pub user_id: i32,
+}
+
+// State service yang memakai AuthAdmin, menentukan DB users dan format error response
+pub trait AdminState: Send + Sync {
+    type Rejection: From<AuthError> + IntoResponse;
+
+    fn admin_db(&self) -> &PgPool;
+
+    // User yang diinject JWT middleware service; default AuthUser dari shared::auth
+    fn authenticated_user_id(parts: &Parts) -> Option<i32> {
+        parts.extensions.get::<AuthUser>().map(|user| user.user_id)
+    }
+}
+
+// User nonaktif atau tidak ditemukan tidak pernah dianggap admin
+pub async fn is_admin(db: &PgPool, user_id: i32) -> Result<bool, sqlx::Error> {
+    let is_admin = sqlx::query_scalar::<_, Option<bool>>(
+        "SELECT is_admin FROM users WHERE id = $1 AND is_active = true"
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .flatten();
+
+    Ok(is_admin.unwrap_or(false))
+}
+
+impl<S: AdminState> FromRequestParts<S> for AuthAdmin {
+    type Rejection = S::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user_id = S::authenticated_user_id(parts).ok_or(AuthError::Unauthenticated)?;
+
+        let admin = is_admin(state.admin_db(), user_id).await.map_err(|e| {
+            tracing::error!("Gagal cek flag is_admin user {}: {}", user_id, e);
+            AuthError::AdminCheckUnavailable
+        })?;
+
+        if !admin {
+            return Err(AuthError::Forbidden("Admin access required").into());
+        }
+
+        Ok(AuthAdmin { user_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Request, StatusCode};
+    use axum::response::Response;
+
+    struct TestState {
+        db: PgPool,
+    }
+
+    struct Rejected(AuthError);
+
+    impl From<AuthError> for Rejected {
+        fn from(err: AuthError) -> Self {
+            Rejected(err)
+        }
+    }
+
+    impl IntoResponse for Rejected {
+        fn into_response(self) -> Response {
+            self.0.status_code().into_response()
+        }
+    }
+
+    impl AdminState for TestState {
+        type Rejection = Rejected;
+
+        fn admin_db(&self) -> &PgPool {
+            &self.db
+        }
+    }
+
+    async fn extract(state: &TestState, user_id: Option<i32>) -> Result<AuthAdmin, AuthError> {
+        let mut request = Request::builder().uri("/admin").body(()).unwrap();
+        if let Some(user_id) = user_id {
+            request.extensions_mut().insert(AuthUser {
+                user_id,
+                email: "user@test.local".to_string(),
+                role: "customer".to_string(),
+            });
+        }
+        let (mut parts, _) = request.into_parts();
+
+        AuthAdmin::from_request_parts(&mut parts, state).await.map_err(|rejected| rejected.0)
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(
+            "../../../database/supabase/fixtures/test_prelude.sql",
+            "../../../database/supabase/schema.sql",
+            "../../../database/supabase/fixtures/test_seed.sql"
+        )
+    )]
+    async fn test_only_active_admin_passes(db: PgPool) {
+        sqlx::query("UPDATE users SET is_admin = true WHERE id IN (1, 3)")
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE users SET is_active = false WHERE id = 3")
+            .execute(&db)
+            .await
+            .unwrap();
+        let state = TestState { db };
+
+        assert_eq!(extract(&state, Some(1)).await.unwrap().user_id, 1);
+
+        let err = extract(&state, Some(2)).await.unwrap_err();
+        assert_eq!(err, AuthError::Forbidden("Admin access required"));
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+
+        // Admin yang dinonaktifkan dan user yang tidak ada ditolak
+        assert!(matches!(extract(&state, Some(3)).await, Err(AuthError::Forbidden(_))));
+        assert!(matches!(extract(&state, Some(999)).await, Err(AuthError::Forbidden(_))));
+
+        // Tanpa user dari JWT middleware
+        assert_eq!(extract(&state, None).await.unwrap_err(), AuthError::Unauthenticated);
+    }
+}
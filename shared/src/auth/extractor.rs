@@ -0,0 +1,181 @@
+// Extractor role bersama: AuthUser diinject oleh JWT middleware tiap service,
+// AuthCustomer/AuthSeller menurunkan role dari AuthUser dengan aturan yang sama di semua service.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap},
+    response::IntoResponse,
+};
+
+use super::jwt::{validate_token, JwtConfig, TokenBlacklist, TokenType};
+use super::role::Role;
+use super::AuthError;
+
+// State service yang memakai extractor ini, menentukan format error response
+pub trait AuthState: Send + Sync {
+    type Rejection: From<AuthError> + IntoResponse;
+}
+
+// User yang sudah terautentikasi (access token valid dan tidak di-blacklist)
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: i32,
+    pub email: String,
+    pub role: String,
+}
+
+impl AuthUser {
+    pub fn is_customer(&self) -> bool {
+        Role::Customer.matches(&self.role)
+    }
+
+    pub fn is_seller(&self) -> bool {
+        Role::Seller.matches(&self.role)
+    }
+}
+
+// Customer: semua user terautentikasi (seller juga bisa booking sebagai customer)
+#[derive(Debug, Clone)]
+pub struct AuthCustomer {
+    pub user_id: i32,
+    pub email: String,
+}
+
+// Seller: hanya token dengan role seller
+#[derive(Debug, Clone)]
+pub struct AuthSeller {
+    pub user_id: i32,
+    pub email: String,
+}
+
+// Extract Bearer token dari Authorization header
+fn extract_bearer_token(headers: &HeaderMap) -> Result<&str, AuthError> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(AuthError::MissingToken)
+}
+
+// Validasi access token dari header untuk JWT middleware service
+pub async fn authenticate<B: TokenBlacklist>(
+    headers: &HeaderMap,
+    config: &JwtConfig,
+    blacklist: &B,
+) -> Result<AuthUser, AuthError> {
+    let token = extract_bearer_token(headers)?;
+    let claims = validate_token(token, config, blacklist, TokenType::Access).await?;
+
+    Ok(AuthUser {
+        user_id: claims.sub,
+        email: claims.email,
+        role: claims.role,
+    })
+}
+
+impl<S: AuthState> FromRequestParts<S> for AuthUser {
+    type Rejection = S::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or_else(|| AuthError::Unauthenticated.into())
+    }
+}
+
+impl<S: AuthState> FromRequestParts<S> for AuthCustomer {
+    type Rejection = S::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        Ok(AuthCustomer {
+            user_id: user.user_id,
+            email: user.email,
+        })
+    }
+}
+
+impl<S: AuthState> FromRequestParts<S> for AuthSeller {
+    type Rejection = S::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        if !user.is_seller() {
+            return Err(AuthError::Forbidden("Seller authentication required").into());
+        }
+
+        Ok(AuthSeller {
+            user_id: user.user_id,
+            email: user.email,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt::issue_token;
+    use std::collections::HashSet;
+
+    fn headers_with(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_valid_token() {
+        let config = JwtConfig::new("test-secret-key-for-testing-only");
+        let token = issue_token(&config, 7, "seller@test.com", "seller", TokenType::Access, 900).unwrap();
+
+        let user = authenticate(&headers_with(&token), &config, &HashSet::new()).await.unwrap();
+        assert_eq!(user.user_id, 7);
+        assert!(user.is_seller());
+        assert!(!user.is_customer());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_missing_header() {
+        let config = JwtConfig::new("test-secret-key-for-testing-only");
+        let result = authenticate(&HeaderMap::new(), &config, &HashSet::new()).await;
+        assert_eq!(result.unwrap_err(), AuthError::MissingToken);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        let result = authenticate(&headers, &config, &HashSet::new()).await;
+        assert_eq!(result.unwrap_err(), AuthError::MissingToken);
+    }
+
+    #[tokio::test]
+    async fn test_deceptive_role_is_not_seller() {
+        let config = JwtConfig::new("test-secret-key-for-testing-only");
+
+        for role in ["customer_support", "seller_admin", "Seller"] {
+            let token = issue_token(&config, 1, "user@test.com", role, TokenType::Access, 900).unwrap();
+            let user = authenticate(&headers_with(&token), &config, &HashSet::new()).await.unwrap();
+            assert!(!user.is_customer(), "{} bukan customer", role);
+            assert!(!user.is_seller(), "{} bukan seller", role);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blacklisted_token_rejected_for_every_role() {
+        let config = JwtConfig::new("test-secret-key-for-testing-only");
+
+        for role in ["customer", "seller", "admin"] {
+            let token = issue_token(&config, 1, "user@test.com", role, TokenType::Access, 900).unwrap();
+            let jti = crate::auth::decode_token(&token, &config, TokenType::Access).unwrap().jti;
+            let blacklist = HashSet::from([jti]);
+
+            let err = authenticate(&headers_with(&token), &config, &blacklist).await.unwrap_err();
+            assert_eq!(err, AuthError::TokenBlacklisted);
+            assert_eq!(err.status_code(), axum::http::StatusCode::UNAUTHORIZED);
+        }
+    }
+}
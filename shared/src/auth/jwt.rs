@@ -0,0 +1,281 @@
+// JWT issue + validasi bersama untuk semua service
+//
+// Satu implementasi untuk signature, token type, issuer/audience, dan blacklist check
+// supaya token yang ditolak di satu service juga ditolak di service lain.
+
+use std::collections::HashSet;
+use std::env;
+
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use super::AuthError;
+
+// Claims JWT yang diterbitkan auth-service
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenClaims {
+    pub sub: i32,
+    pub email: String,
+    pub role: String,
+    pub exp: i64,
+    pub iat: i64,
+    pub token_type: String,
+    pub jti: String,
+    // Opsional agar token lama (sebelum JWT_ISSUER/JWT_AUDIENCE diset) tetap bisa di-decode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+}
+
+// Jenis token: business service hanya menerima access token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    Access,
+    Refresh,
+}
+
+impl TokenType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenType::Access => "access",
+            TokenType::Refresh => "refresh",
+        }
+    }
+}
+
+// Konfigurasi JWT per service, dibangun dari AppConfig masing-masing service
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    pub secret: String,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+}
+
+impl JwtConfig {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: secret.into(),
+            issuer: None,
+            audience: None,
+        }
+    }
+
+    // Secret dari AppConfig service, issuer/audience dari JWT_ISSUER dan JWT_AUDIENCE (opsional)
+    pub fn from_env(secret: impl Into<String>) -> Self {
+        Self {
+            secret: secret.into(),
+            issuer: env::var("JWT_ISSUER").ok().filter(|s| !s.is_empty()),
+            audience: env::var("JWT_AUDIENCE").ok().filter(|s| !s.is_empty()),
+        }
+    }
+
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(Algorithm::HS256);
+        let mut required = vec!["exp"];
+
+        if let Some(ref issuer) = self.issuer {
+            validation.set_issuer(&[issuer]);
+            required.push("iss");
+        }
+
+        match self.audience {
+            Some(ref audience) => {
+                validation.set_audience(&[audience]);
+                required.push("aud");
+            }
+            None => validation.validate_aud = false,
+        }
+
+        validation.set_required_spec_claims(&required);
+        validation
+    }
+}
+
+// Sumber data blacklist token (logout / revoke)
+#[allow(async_fn_in_trait)]
+pub trait TokenBlacklist {
+    async fn is_blacklisted(&self, claims: &TokenClaims) -> Result<bool, AuthError>;
+}
+
+// Blacklist di database via secure function is_token_blacklisted_v2
+impl TokenBlacklist for PgPool {
+    async fn is_blacklisted(&self, claims: &TokenClaims) -> Result<bool, AuthError> {
+        let is_blacklisted = sqlx::query_scalar::<_, Option<bool>>("SELECT is_token_blacklisted_v2($1, $2)")
+            .bind(&claims.jti)
+            .bind(&claims.token_type)
+            .fetch_one(self)
+            .await
+            .map_err(|e| {
+                tracing::error!("JWT blacklist check failed: {}", e);
+                AuthError::BlacklistUnavailable
+            })?;
+
+        Ok(is_blacklisted.unwrap_or(false))
+    }
+}
+
+// Blacklist in-memory berisi jti, untuk test
+impl TokenBlacklist for HashSet<String> {
+    async fn is_blacklisted(&self, claims: &TokenClaims) -> Result<bool, AuthError> {
+        Ok(self.contains(&claims.jti))
+    }
+}
+
+// Terbitkan token baru (dipakai auth-service)
+pub fn issue_token(
+    config: &JwtConfig,
+    user_id: i32,
+    email: &str,
+    role: &str,
+    token_type: TokenType,
+    expiry_secs: i64,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+
+    let claims = TokenClaims {
+        sub: user_id,
+        email: email.to_string(),
+        role: role.to_string(),
+        exp: (now + Duration::seconds(expiry_secs)).timestamp(),
+        iat: now.timestamp(),
+        token_type: token_type.as_str().to_string(),
+        jti: uuid::Uuid::new_v4().to_string(),
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.secret.as_bytes()),
+    )
+}
+
+// Decode + validasi signature, expiry, issuer/audience, dan token type (tanpa blacklist)
+pub fn decode_token(token: &str, config: &JwtConfig, expected: TokenType) -> Result<TokenClaims, AuthError> {
+    // Production safety check - tidak boleh pakai default value
+    if !cfg!(debug_assertions) && config.secret.contains("change-this") {
+        return Err(AuthError::InsecureSecret);
+    }
+
+    let claims = decode::<TokenClaims>(
+        token,
+        &DecodingKey::from_secret(config.secret.as_bytes()),
+        &config.validation(),
+    )
+    .map_err(|_| AuthError::InvalidToken)?
+    .claims;
+
+    if claims.token_type != expected.as_str() {
+        return Err(AuthError::InvalidTokenType);
+    }
+
+    Ok(claims)
+}
+
+// Validasi lengkap: decode + blacklist check
+pub async fn validate_token<B: TokenBlacklist>(
+    token: &str,
+    config: &JwtConfig,
+    blacklist: &B,
+    expected: TokenType,
+) -> Result<TokenClaims, AuthError> {
+    let claims = decode_token(token, config, expected)?;
+
+    if blacklist.is_blacklisted(&claims).await? {
+        tracing::warn!("Token has been blacklisted - user_id: {}, jti: {}...",
+            claims.sub,
+            &claims.jti[..8.min(claims.jti.len())]
+        );
+        return Err(AuthError::TokenBlacklisted);
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret-key-for-testing-only";
+
+    fn config() -> JwtConfig {
+        JwtConfig::new(SECRET)
+    }
+
+    #[test]
+    fn test_issue_and_decode_access_token() {
+        let token = issue_token(&config(), 123, "test@example.com", "customer", TokenType::Access, 900).unwrap();
+        let claims = decode_token(&token, &config(), TokenType::Access).unwrap();
+
+        assert_eq!(claims.sub, 123);
+        assert_eq!(claims.email, "test@example.com");
+        assert_eq!(claims.role, "customer");
+        assert_eq!(claims.token_type, "access");
+    }
+
+    #[test]
+    fn test_reject_wrong_token_type() {
+        let token = issue_token(&config(), 123, "test@example.com", "customer", TokenType::Refresh, 900).unwrap();
+        assert_eq!(decode_token(&token, &config(), TokenType::Access), Err(AuthError::InvalidTokenType));
+        assert!(decode_token(&token, &config(), TokenType::Refresh).is_ok());
+    }
+
+    #[test]
+    fn test_reject_invalid_and_expired_token() {
+        assert_eq!(decode_token("invalid.token.here", &config(), TokenType::Access), Err(AuthError::InvalidToken));
+
+        let expired = issue_token(&config(), 1, "a@b.com", "customer", TokenType::Access, -3600).unwrap();
+        assert_eq!(decode_token(&expired, &config(), TokenType::Access), Err(AuthError::InvalidToken));
+
+        let token = issue_token(&config(), 1, "a@b.com", "customer", TokenType::Access, 900).unwrap();
+        assert_eq!(
+            decode_token(&token, &JwtConfig::new("other-secret"), TokenType::Access),
+            Err(AuthError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_issuer_and_audience_validation() {
+        let strict = config().with_issuer("bigauto-auth").with_audience("bigauto-api");
+
+        let token = issue_token(&strict, 1, "a@b.com", "customer", TokenType::Access, 900).unwrap();
+        assert!(decode_token(&token, &strict, TokenType::Access).is_ok());
+
+        // Token tanpa iss/aud ditolak service yang mewajibkannya
+        let legacy = issue_token(&config(), 1, "a@b.com", "customer", TokenType::Access, 900).unwrap();
+        assert_eq!(decode_token(&legacy, &strict, TokenType::Access), Err(AuthError::InvalidToken));
+
+        let other = config().with_issuer("other-issuer").with_audience("bigauto-api");
+        let token = issue_token(&other, 1, "a@b.com", "customer", TokenType::Access, 900).unwrap();
+        assert_eq!(decode_token(&token, &strict, TokenType::Access), Err(AuthError::InvalidToken));
+    }
+
+    #[tokio::test]
+    async fn test_blacklisted_token_rejected() {
+        let token = issue_token(&config(), 1, "a@b.com", "customer", TokenType::Access, 900).unwrap();
+        let claims = decode_token(&token, &config(), TokenType::Access).unwrap();
+
+        let empty = HashSet::new();
+        assert!(validate_token(&token, &config(), &empty, TokenType::Access).await.is_ok());
+
+        let blacklist = HashSet::from([claims.jti]);
+        assert_eq!(
+            validate_token(&token, &config(), &blacklist, TokenType::Access).await,
+            Err(AuthError::TokenBlacklisted)
+        );
+    }
+}
@@ -0,0 +1,57 @@
+// Autentikasi JWT bersama: validasi token + extractor role untuk semua service
+pub mod admin;
+pub mod extractor;
+pub mod jwt;
+pub mod role;
+
+use axum::http::StatusCode;
+use thiserror::Error;
+
+pub use admin::{AdminState, AuthAdmin};
+pub use extractor::{authenticate, AuthCustomer, AuthSeller, AuthState, AuthUser};
+pub use jwt::{decode_token, issue_token, validate_token, JwtConfig, TokenBlacklist, TokenClaims, TokenType};
+pub use role::Role;
+
+// Error autentikasi, setiap service memetakan ke AppError miliknya via From<AuthError>
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AuthError {
+    #[error("Authorization header dengan Bearer token diperlukan")]
+    MissingToken,
+
+    #[error("Token tidak valid atau sudah expired")]
+    InvalidToken,
+
+    #[error("Token type tidak valid untuk endpoint ini")]
+    InvalidTokenType,
+
+    #[error("Token sudah di-blacklist")]
+    TokenBlacklisted,
+
+    #[error("Authentication required")]
+    Unauthenticated,
+
+    #[error("{0}")]
+    Forbidden(&'static str),
+
+    #[error("JWT secret menggunakan default value")]
+    InsecureSecret,
+
+    #[error("Validasi blacklist token gagal")]
+    BlacklistUnavailable,
+
+    #[error("Validasi akses admin gagal")]
+    AdminCheckUnavailable,
+}
+
+impl AuthError {
+    // Status HTTP yang sama di semua service untuk error yang sama
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AuthError::InsecureSecret | AuthError::BlacklistUnavailable | AuthError::AdminCheckUnavailable => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// Role di claim JWT, dicocokkan exact agar role lain (mis. "customer_support") tidak ikut lolos
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::AuthError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Customer,
+    Seller,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Customer => "customer",
+            Role::Seller => "seller",
+            Role::Admin => "admin",
+        }
+    }
+
+    // Cek role claim sama persis dengan role ini
+    pub fn matches(self, role: &str) -> bool {
+        role.parse::<Role>() == Ok(self)
+    }
+}
+
+impl FromStr for Role {
+    type Err = AuthError;
+
+    // Exact match, tanpa trim / case folding / substring
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "customer" => Ok(Role::Customer),
+            "seller" => Ok(Role::Seller),
+            "admin" => Ok(Role::Admin),
+            _ => Err(AuthError::Forbidden("Role tidak dikenal")),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_roles() {
+        for role in [Role::Customer, Role::Seller, Role::Admin] {
+            assert_eq!(role.as_str().parse::<Role>(), Ok(role));
+            assert!(role.matches(&role.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_deceptive_roles_rejected() {
+        let deceptive = [
+            "customer_support", "seller_admin", "not_a_seller", "sellers", "Customer", "SELLER",
+            " seller", "seller ", "customer,seller", "admin|seller", "seller\0", "",
+        ];
+
+        for role in deceptive {
+            assert!(role.parse::<Role>().is_err(), "role {:?} harus ditolak", role);
+            assert!(!Role::Customer.matches(role));
+            assert!(!Role::Seller.matches(role));
+            assert!(!Role::Admin.matches(role));
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Alamat bind HTTP untuk semua service
+//
+// Setiap service membaca <SERVICE>_HOST / <SERVICE>_PORT lewat config-nya sendiri dan divalidasi
+// di sini saat startup: port harus 1-65535, host harus alamat IP (v4/v6) atau hostname yang bisa
+// di-resolve. Konfigurasi salah langsung gagal dengan pesan jelas, bukan diam-diam fallback ke
+// 0.0.0.0. Endpoint /metrics bisa dipisah ke listener sendiri (mis. hanya interface internal).
+
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+
+pub const DEFAULT_HOST: &str = "0.0.0.0";
+// Listener metrics default hanya bisa diakses dari host yang sama
+pub const DEFAULT_METRICS_HOST: &str = "127.0.0.1";
+
+// Host dari env `key`; `default` None berarti wajib diset
+pub fn host_from_env(key: &str, default: Option<&str>) -> Result<String, String> {
+    parse_host(key, std::env::var(key).ok().as_deref(), default)
+}
+
+// Port dari env `key` (1-65535); `default` None berarti wajib diset
+pub fn port_from_env(key: &str, default: Option<u16>) -> Result<u16, String> {
+    parse_port(key, std::env::var(key).ok().as_deref(), default)
+}
+
+// Listener /metrics terpisah dari `host_key`/`port_key`, None jika port tidak diset
+// (metrics tetap disajikan di listener utama)
+pub fn metrics_addr_from_env(host_key: &str, port_key: &str, server: SocketAddr) -> Result<Option<SocketAddr>, String> {
+    let Some(raw_port) = std::env::var(port_key).ok().filter(|v| !v.trim().is_empty()) else {
+        return Ok(None);
+    };
+
+    let host = host_from_env(host_key, Some(DEFAULT_METRICS_HOST))?;
+    let port = parse_port(port_key, Some(&raw_port), None)?;
+    let metrics = resolve(host_key, &host, port)?;
+
+    check_distinct(metrics, server, port_key)?;
+    Ok(Some(metrics))
+}
+
+fn parse_host(key: &str, value: Option<&str>, default: Option<&str>) -> Result<String, String> {
+    match value.map(str::trim).filter(|v| !v.is_empty()).or(default) {
+        Some(host) => Ok(host.to_string()),
+        None => Err(format!("{} harus diset di environment", key)),
+    }
+}
+
+fn parse_port(key: &str, value: Option<&str>, default: Option<u16>) -> Result<u16, String> {
+    let Some(raw) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return default.ok_or_else(|| format!("{} harus diset di environment", key));
+    };
+
+    match raw.parse::<u32>() {
+        Ok(port @ 1..=65535) => Ok(port as u16),
+        _ => Err(format!("{} tidak valid: '{}' (harus angka 1-65535)", key, raw)),
+    }
+}
+
+// Gabungkan host dan port jadi alamat bind. IP literal dipakai langsung (IPv6 boleh dengan
+// kurung siku), selain itu hostname di-resolve dan alamat pertama yang dipakai
+pub fn resolve(host_key: &str, host: &str, port: u16) -> Result<SocketAddr, String> {
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = literal.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+
+    let invalid = || format!("{} tidak valid: '{}' bukan alamat IP atau hostname yang bisa di-resolve", host_key, host);
+    if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == '/' || c == ':') {
+        return Err(invalid());
+    }
+
+    (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(invalid)
+}
+
+// Listener metrics tidak boleh bentrok dengan listener utama (port sama di interface yang overlap)
+fn check_distinct(metrics: SocketAddr, server: SocketAddr, port_key: &str) -> Result<(), String> {
+    let overlaps = metrics.ip() == server.ip()
+        || metrics.ip().is_unspecified()
+        || server.ip().is_unspecified();
+
+    if metrics.port() == server.port() && overlaps {
+        return Err(format!(
+            "{} ({}) bentrok dengan alamat API publik {}",
+            port_key, metrics, server
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_validation() {
+        assert_eq!(parse_port("X_PORT", Some("3006"), None), Ok(3006));
+        assert_eq!(parse_port("X_PORT", Some(" 65535 "), None), Ok(65535));
+        assert_eq!(parse_port("X_PORT", None, Some(3001)), Ok(3001));
+        assert_eq!(parse_port("X_PORT", Some(""), Some(3001)), Ok(3001));
+
+        for raw in ["0", "65536", "70000", "-1", "abc", "3006a"] {
+            let err = parse_port("X_PORT", Some(raw), Some(3001)).unwrap_err();
+            assert!(err.contains("X_PORT") && err.contains(raw), "{}", err);
+        }
+        assert_eq!(parse_port("X_PORT", None, None).unwrap_err(), "X_PORT harus diset di environment");
+    }
+
+    #[test]
+    fn test_host_resolution() {
+        assert_eq!(resolve("X_HOST", "0.0.0.0", 3001).unwrap(), "0.0.0.0:3001".parse().unwrap());
+        assert_eq!(resolve("X_HOST", "10.1.2.3", 80).unwrap(), "10.1.2.3:80".parse().unwrap());
+        assert_eq!(resolve("X_HOST", "::1", 9000).unwrap(), "[::1]:9000".parse().unwrap());
+        assert_eq!(resolve("X_HOST", "[::]", 9000).unwrap(), "[::]:9000".parse().unwrap());
+        assert!(resolve("X_HOST", "localhost", 3001).unwrap().ip().is_loopback());
+
+        for host in ["", "0.0.0.0:3001", "http://api", "bukan host", "256.1.1.1.invalid."] {
+            let err = resolve("X_HOST", host, 3001).unwrap_err();
+            assert!(err.starts_with("X_HOST tidak valid"), "{}", err);
+        }
+
+        assert_eq!(parse_host("X_HOST", Some(" "), Some(DEFAULT_HOST)).unwrap(), DEFAULT_HOST);
+        assert!(parse_host("X_HOST", None, None).is_err());
+    }
+
+    #[test]
+    fn test_metrics_listener_must_not_clash_with_api() {
+        let server: SocketAddr = "0.0.0.0:3006".parse().unwrap();
+
+        assert!(check_distinct("127.0.0.1:9106".parse().unwrap(), server, "M_PORT").is_ok());
+        assert!(check_distinct("127.0.0.1:3006".parse().unwrap(), server, "M_PORT").is_err());
+
+        // Interface berbeda boleh memakai port yang sama
+        let public: SocketAddr = "203.0.113.10:3006".parse().unwrap();
+        assert!(check_distinct("10.0.0.5:3006".parse().unwrap(), public, "M_PORT").is_ok());
+        assert!(check_distinct("203.0.113.10:3006".parse().unwrap(), public, "M_PORT").is_err());
+    }
+}
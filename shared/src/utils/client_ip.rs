@@ -0,0 +1,165 @@
+// IP client di belakang reverse proxy untuk semua service
+//
+// Header X-Forwarded-For/X-Real-IP bisa diisi sembarangan oleh client, jadi hanya dipercaya jika
+// koneksi TCP datang dari proxy tepercaya (TRUSTED_PROXY_CIDRS). Dipakai untuk allowlist webhook
+// dan key rate limit request tanpa login.
+
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+
+pub const TRUSTED_PROXIES_ENV: &str = "TRUSTED_PROXY_CIDRS";
+
+// Reverse proxy yang boleh mengisi X-Forwarded-For/X-Real-IP (gateway nginx di network docker
+// internal), override via TRUSTED_PROXY_CIDRS
+pub const DEFAULT_TRUSTED_PROXY_CIDRS: &str = "127.0.0.0/8,10.0.0.0/8,172.16.0.0/12,192.168.0.0/16,::1/128,fc00::/7";
+
+// Satu range CIDR, IP tanpa prefix dianggap /32 (IPv4) atau /128 (IPv6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    fn parse(value: &str) -> Option<Self> {
+        let (address, prefix) = match value.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+            None => (value, None),
+        };
+
+        let network = address.parse::<IpAddr>().ok()?.to_canonical();
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max_prefix);
+
+        (prefix <= max_prefix).then_some(Self { network, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpAllowlist {
+    ranges: Vec<IpRange>,
+}
+
+impl IpAllowlist {
+    // Daftar IP/CIDR dipisah koma, entry yang tidak valid membuat config gagal
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let ranges = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| IpRange::parse(entry).ok_or_else(|| format!("IP/CIDR tidak valid: {}", entry)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if ranges.is_empty() {
+            return Err("Allowlist IP tidak boleh kosong".to_string());
+        }
+
+        Ok(Self { ranges })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+}
+
+// Proxy tepercaya dari TRUSTED_PROXY_CIDRS (default network privat tempat gateway nginx
+// berjalan), "none" untuk mengabaikan header proxy sepenuhnya
+pub fn trusted_proxies_from_env() -> Result<Option<IpAllowlist>, String> {
+    parse_trusted_proxies(std::env::var(TRUSTED_PROXIES_ENV).ok().as_deref())
+}
+
+fn parse_trusted_proxies(value: Option<&str>) -> Result<Option<IpAllowlist>, String> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(spec) if spec.eq_ignore_ascii_case("none") => Ok(None),
+        spec => IpAllowlist::parse(spec.unwrap_or(DEFAULT_TRUSTED_PROXY_CIDRS))
+            .map(Some)
+            .map_err(|e| format!("{}: {}", TRUSTED_PROXIES_ENV, e)),
+    }
+}
+
+// IP sumber request. Header proxy hanya dipercaya jika koneksi datang dari proxy di
+// `trusted_proxies`, karena client bisa mengisi X-Forwarded-For sembarangan.
+// X-Forwarded-For dibaca dari kanan (entry yang ditambahkan proxy kita), melewati hop yang
+// juga proxy tepercaya; entry pertama di luar daftar itu adalah client sebenarnya.
+pub fn source_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: Option<&IpAllowlist>) -> IpAddr {
+    let Some(trusted) = trusted_proxies.filter(|trusted| trusted.contains(peer)) else {
+        return peer;
+    };
+
+    let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok());
+
+    let forwarded = header("x-forwarded-for").and_then(|value| {
+        value
+            .rsplit(',')
+            .map(|ip| ip.trim().parse::<IpAddr>())
+            .take_while(Result::is_ok)
+            .flatten()
+            .find(|ip| !trusted.contains(*ip))
+    });
+
+    forwarded
+        .or_else(|| header("x-real-ip").and_then(|ip| ip.trim().parse().ok()))
+        .unwrap_or(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_entries() {
+        assert!(IpAllowlist::parse("").is_err());
+        assert!(IpAllowlist::parse("10.0.0.0/33").is_err());
+        assert!(IpAllowlist::parse("10.0.0.0/8, bukan-ip").is_err());
+
+        let allowlist = IpAllowlist::parse("0.0.0.0/0, 2001:db8::/32").unwrap();
+        assert!(allowlist.contains(ip("8.8.8.8")));
+        assert!(allowlist.contains(ip("2001:db8:1::5")));
+    }
+
+    #[test]
+    fn test_trusted_proxies_env_values() {
+        let default = parse_trusted_proxies(None).unwrap().unwrap();
+        assert!(default.contains(ip("172.18.0.5")));
+        assert!(!default.contains(ip("198.51.100.7")));
+
+        assert_eq!(parse_trusted_proxies(Some(" ")).unwrap(), Some(default));
+        assert_eq!(parse_trusted_proxies(Some("NONE")).unwrap(), None);
+        assert!(parse_trusted_proxies(Some("10.0.0.0/8, bukan-ip")).is_err());
+    }
+
+    #[test]
+    fn test_spoofed_proxy_headers_ignored() {
+        let trusted = IpAllowlist::parse(DEFAULT_TRUSTED_PROXY_CIDRS).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "103.208.23.6".parse().unwrap());
+        headers.insert("x-real-ip", "103.208.23.6".parse().unwrap());
+
+        // Client langsung (bukan proxy tepercaya) tidak bisa memalsukan IP
+        let attacker = ip("198.51.100.7");
+        assert_eq!(source_ip(attacker, &headers, Some(&trusted)), attacker);
+        assert_eq!(source_ip(ip("172.18.0.5"), &headers, None), ip("172.18.0.5"));
+
+        // Entry kiri yang diisi client di depan IP yang ditambahkan gateway tidak dipakai
+        headers.insert("x-forwarded-for", "103.208.23.6, 198.51.100.7".parse().unwrap());
+        assert_eq!(source_ip(ip("172.18.0.5"), &headers, Some(&trusted)), attacker);
+    }
+}
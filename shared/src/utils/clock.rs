@@ -0,0 +1,70 @@
+// Helper jam lokal yang dipakai business hours (booking), quiet hours (notification)
+// dan jam aktif auto-reply (chat)
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// Jam "HH:MM" atau "HH:MM:SS" (kolom TIME), jam satu digit ("8:30") tetap diterima
+// seperti parser lama di notification dan chat
+pub fn parse_clock(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .ok()
+}
+
+// Jam lokal ke UTC: jam ambigu (DST mundur) pakai kemunculan pertama,
+// jam yang tidak ada (DST maju) digeser ke menit pertama setelah lompatan
+pub fn resolve_local(timezone: &Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    let mut naive = date.and_time(time);
+    loop {
+        match timezone.from_local_datetime(&naive) {
+            LocalResult::Single(dt) => return dt.with_timezone(&Utc),
+            LocalResult::Ambiguous(earliest, _) => return earliest.with_timezone(&Utc),
+            LocalResult::None => naive += Duration::minutes(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_clock() {
+        assert_eq!(parse_clock("08:30"), NaiveTime::from_hms_opt(8, 30, 0));
+        assert_eq!(parse_clock(" 22:00 "), NaiveTime::from_hms_opt(22, 0, 0));
+        assert_eq!(parse_clock("07:00:15"), NaiveTime::from_hms_opt(7, 0, 15));
+
+        assert_eq!(parse_clock("8:30"), NaiveTime::from_hms_opt(8, 30, 0));
+        assert_eq!(parse_clock("8:30:00"), NaiveTime::from_hms_opt(8, 30, 0));
+
+        assert_eq!(parse_clock("8"), None);
+        assert_eq!(parse_clock(""), None);
+        assert_eq!(parse_clock("08:60"), None);
+        assert_eq!(parse_clock("25:00"), None);
+        assert_eq!(parse_clock("+8:30"), None);
+        assert_eq!(parse_clock("08:30:00:00"), None);
+    }
+
+    #[test]
+    fn test_resolve_local_across_dst() {
+        let london = chrono_tz::Europe::London;
+        let jam = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+        // 29 Mar 2026 01:30 tidak ada (01:00 -> 02:00 BST), digeser ke 02:00 BST
+        let spring = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        assert_eq!(resolve_local(&london, spring, jam(1, 30)), at("2026-03-29T01:00:00Z"));
+
+        // 25 Okt 2026 01:30 muncul dua kali, pakai yang pertama (BST)
+        let fall = NaiveDate::from_ymd_opt(2026, 10, 25).unwrap();
+        assert_eq!(resolve_local(&london, fall, jam(1, 30)), at("2026-10-25T00:30:00Z"));
+
+        let jakarta = chrono_tz::Asia::Jakarta;
+        assert_eq!(resolve_local(&jakarta, fall, jam(8, 0)), at("2026-10-25T01:00:00Z"));
+    }
+}
@@ -51,11 +51,19 @@ impl CloudinaryClient {
         let api_key = env::var("CLOUDINARY_API_KEY")?;
         let api_secret = env::var("CLOUDINARY_API_SECRET")?;
 
-        Ok(Self {
+        Ok(Self::with_credentials(cloud_name, api_key, api_secret))
+    }
+
+    pub fn with_credentials(cloud_name: String, api_key: String, api_secret: String) -> Self {
+        Self {
             cloud_name,
             api_key,
             api_secret,
-        })
+        }
+    }
+
+    pub fn cloud_name(&self) -> &str {
+        &self.cloud_name
     }
 
     // Upload file ke Cloudinary
@@ -216,6 +224,58 @@ impl CloudinaryClient {
             .map(|idx| without_version[..idx].to_string())
     }
 
+    // Sisipkan transformasi delivery (mis. "q_auto,f_auto") setelah segmen /upload/
+    pub fn apply_transformation(url: &str, transformation: &str) -> Option<String> {
+        if !url.contains("cloudinary.com") || transformation.is_empty() {
+            return None;
+        }
+
+        let (base, rest) = url.split_once("/upload/")?;
+        Some(format!("{}/upload/{}/{}", base, transformation, rest))
+    }
+
+    // URL download privat via Admin API yang ditolak Cloudinary setelah expires_at (unix timestamp)
+    pub fn private_download_url(
+        &self,
+        public_id: &str,
+        format: &str,
+        resource_type: ResourceType,
+        timestamp: i64,
+        expires_at: i64,
+    ) -> String {
+        let mut params = vec![
+            ("expires_at", expires_at.to_string()),
+            ("format", format.to_string()),
+            ("public_id", public_id.to_string()),
+            ("timestamp", timestamp.to_string()),
+            ("type", "upload".to_string()),
+        ];
+        let signature = self.sign_params(&params);
+        params.push(("api_key", self.api_key.clone()));
+        params.push(("signature", signature));
+
+        let base = format!(
+            "https://api.cloudinary.com/v1_1/{}/{}/download",
+            self.cloud_name,
+            resource_type.as_str()
+        );
+
+        reqwest::Url::parse_with_params(&base, &params)
+            .map(String::from)
+            .unwrap_or(base)
+    }
+
+    // Extension file dari Cloudinary URL (format asset)
+    pub fn extract_format(url: &str) -> Option<String> {
+        let (_, after_upload) = url.split_once("/upload/")?;
+        let filename = after_upload.rsplit('/').next()?;
+
+        filename
+            .rsplit_once('.')
+            .map(|(_, format)| format.to_string())
+            .filter(|format| !format.is_empty())
+    }
+
     // Build upload URL berdasarkan resource type
     fn build_upload_url(&self, resource_type: ResourceType) -> String {
         format!(
@@ -236,7 +296,23 @@ impl CloudinaryClient {
 
     // Generate signature untuk authenticated requests
     fn generate_signature(&self, public_id: &str, timestamp: i64) -> String {
-        let data = format!("public_id={}&timestamp={}{}", public_id, timestamp, self.api_secret);
+        self.sign_params(&[
+            ("public_id", public_id.to_string()),
+            ("timestamp", timestamp.to_string()),
+        ])
+    }
+
+    // Signature Cloudinary: parameter urut abjad "k=v&k=v" lalu ditambah api_secret
+    fn sign_params(&self, params: &[(&str, String)]) -> String {
+        let mut sorted = params.to_vec();
+        sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+        let joined = sorted
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        let data = format!("{}{}", joined, self.api_secret);
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         format!("{:x}", hasher.finalize())
@@ -256,6 +332,48 @@ mod tests {
         assert_eq!(CloudinaryClient::extract_public_id(url2), Some("profiles/user".to_string()));
     }
 
+    #[test]
+    fn test_apply_transformation() {
+        let url = "https://res.cloudinary.com/test/image/upload/v123/chat/images/a.jpg";
+        assert_eq!(
+            CloudinaryClient::apply_transformation(url, "q_auto,f_auto"),
+            Some("https://res.cloudinary.com/test/image/upload/q_auto,f_auto/v123/chat/images/a.jpg".to_string())
+        );
+        assert_eq!(CloudinaryClient::apply_transformation(url, ""), None);
+        assert_eq!(CloudinaryClient::apply_transformation("https://cdn.example.com/a.jpg", "q_auto"), None);
+    }
+
+    #[test]
+    fn test_extract_format() {
+        let url = "https://res.cloudinary.com/test/image/upload/v123/chat/images/a.jpg";
+        assert_eq!(CloudinaryClient::extract_format(url), Some("jpg".to_string()));
+        assert_eq!(CloudinaryClient::extract_format("https://res.cloudinary.com/test/raw/upload/v1/docs/ktp"), None);
+    }
+
+    #[test]
+    fn test_private_download_url_is_signed() {
+        let client = CloudinaryClient::with_credentials(
+            "demo".to_string(),
+            "key123".to_string(),
+            "secret".to_string(),
+        );
+
+        let url = client.private_download_url("docs/ktp 1", "pdf", ResourceType::Raw, 1_700_000_000, 1_700_000_600);
+        let parsed = reqwest::Url::parse(&url).unwrap();
+        assert_eq!(parsed.path(), "/v1_1/demo/raw/download");
+
+        let query: std::collections::HashMap<_, _> = parsed.query_pairs().into_owned().collect();
+        assert_eq!(query["public_id"], "docs/ktp 1");
+        assert_eq!(query["expires_at"], "1700000600");
+        assert_eq!(query["api_key"], "key123");
+
+        // api_key dan signature tidak ikut ditandatangani
+        let expected = Sha256::digest(
+            "expires_at=1700000600&format=pdf&public_id=docs/ktp 1&timestamp=1700000000&type=uploadsecret".as_bytes()
+        );
+        assert_eq!(query["signature"], format!("{:x}", expected));
+    }
+
     #[test]
     fn test_resource_type() {
         assert_eq!(ResourceType::Image.as_str(), "image");
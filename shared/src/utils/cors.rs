@@ -0,0 +1,136 @@
+// Kebijakan CORS bersama untuk semua service
+//
+// Origin diambil dari FRONTEND_URL (dipisah koma) dan hanya origin yang ada di allowlist
+// yang di-echo balik di Access-Control-Allow-Origin. CORS_ALLOW_CREDENTIALS=true dibutuhkan
+// untuk flow berbasis cookie, dan tidak boleh digabung dengan origin wildcard.
+
+use axum::http::HeaderValue;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    // None = wildcard (*), hanya boleh tanpa credentials
+    origins: Option<Vec<HeaderValue>>,
+    allow_credentials: bool,
+}
+
+impl CorsPolicy {
+    pub fn new(origins_spec: &str, allow_credentials: bool) -> Result<Self, String> {
+        let entries: Vec<&str> = origins_spec
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .collect();
+
+        if entries.is_empty() {
+            return Err("FRONTEND_URL tidak boleh kosong".to_string());
+        }
+
+        if entries.contains(&"*") {
+            if allow_credentials {
+                return Err("CORS_ALLOW_CREDENTIALS=true tidak boleh digabung dengan origin wildcard (*)".to_string());
+            }
+            if entries.len() > 1 {
+                return Err("Origin wildcard (*) tidak boleh digabung dengan origin lain".to_string());
+            }
+            return Ok(Self { origins: None, allow_credentials });
+        }
+
+        let origins = entries
+            .into_iter()
+            .map(parse_origin)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { origins: Some(origins), allow_credentials })
+    }
+
+    // FRONTEND_URL wajib, CORS_ALLOW_CREDENTIALS default false
+    pub fn from_env() -> Result<Self, String> {
+        let origins = std::env::var("FRONTEND_URL")
+            .map_err(|_| "FRONTEND_URL environment variable harus diset".to_string())?;
+
+        let allow_credentials = match std::env::var("CORS_ALLOW_CREDENTIALS") {
+            Ok(value) => value
+                .trim()
+                .parse::<bool>()
+                .map_err(|_| format!("CORS_ALLOW_CREDENTIALS harus true/false, didapat: {}", value))?,
+            Err(_) => false,
+        };
+
+        Self::new(&origins, allow_credentials)
+    }
+
+    // Pasang origin dan credentials ke layer; method, header, dan max age tetap diatur per service
+    pub fn apply(&self, layer: CorsLayer) -> CorsLayer {
+        let allow_origin = match &self.origins {
+            // AllowOrigin::list meng-echo origin request hanya jika ada di allowlist
+            Some(origins) => AllowOrigin::list(origins.clone()),
+            None => AllowOrigin::any(),
+        };
+
+        layer.allow_origin(allow_origin).allow_credentials(self.allow_credentials)
+    }
+}
+
+// Origin harus scheme://host[:port] tanpa path, sama persis dengan header Origin dari browser
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let host = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .ok_or_else(|| format!("Origin CORS harus diawali http:// atau https://: {}", origin))?;
+
+    if host.is_empty() || host.contains('/') || host.contains('*') {
+        return Err(format!("Origin CORS tidak valid (tanpa path/wildcard): {}", origin));
+    }
+
+    origin
+        .parse::<HeaderValue>()
+        .map_err(|_| format!("Origin CORS tidak valid: {}", origin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn request_from(policy: &CorsPolicy, origin: &str) -> axum::http::HeaderMap {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(policy.apply(CorsLayer::new()));
+
+        let request = Request::builder().uri("/").header("origin", origin).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap().headers().clone()
+    }
+
+    #[tokio::test]
+    async fn test_allowed_origin_is_echoed_with_credentials() {
+        let policy = CorsPolicy::new("https://bigauto.com, https://admin.bigauto.com", true).unwrap();
+
+        let headers = request_from(&policy, "https://admin.bigauto.com").await;
+        assert_eq!(headers["access-control-allow-origin"], "https://admin.bigauto.com");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_origin_is_not_echoed() {
+        let policy = CorsPolicy::new("https://bigauto.com", true).unwrap();
+
+        let headers = request_from(&policy, "https://evil.example.com").await;
+        assert!(headers.get("access-control-allow-origin").is_none());
+
+        let headers = request_from(&policy, "https://bigauto.com.evil.example.com").await;
+        assert!(headers.get("access-control-allow-origin").is_none());
+    }
+
+    #[test]
+    fn test_rejects_wildcard_with_credentials_and_invalid_origins() {
+        assert!(CorsPolicy::new("*", true).is_err());
+        assert!(CorsPolicy::new("*", false).is_ok());
+        assert!(CorsPolicy::new("https://bigauto.com,*", false).is_err());
+        assert!(CorsPolicy::new("", false).is_err());
+        assert!(CorsPolicy::new("bigauto.com", false).is_err());
+        assert!(CorsPolicy::new("https://bigauto.com/app", false).is_err());
+        assert!(CorsPolicy::new("https://*.bigauto.com", true).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+// Response endpoint pembuatan resource
+//
+// Resource baru dikembalikan dengan 201 Created + header Location yang menunjuk ke URL GET
+// resource tersebut. Jika request mengembalikan resource yang sudah ada (dedup / idempoten),
+// response-nya 200 OK dengan body yang sama tanpa Location.
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Creation<T> {
+    Created { location: String, body: T },
+    Existing(T),
+}
+
+impl<T> Creation<T> {
+    // location berupa path GET resource, contoh "/api/sales/orders/42"
+    pub fn created(location: impl Into<String>, body: T) -> Self {
+        Creation::Created { location: location.into(), body }
+    }
+
+    pub fn existing(body: T) -> Self {
+        Creation::Existing(body)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Creation::Created { .. } => StatusCode::CREATED,
+            Creation::Existing(_) => StatusCode::OK,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Creation<T> {
+    fn into_response(self) -> Response {
+        match self {
+            Creation::Created { location, body } => {
+                let mut response = (StatusCode::CREATED, Json(body)).into_response();
+                match HeaderValue::try_from(location.as_str()) {
+                    Ok(value) => {
+                        response.headers_mut().insert(header::LOCATION, value);
+                    }
+                    Err(_) => tracing::warn!("Location header tidak valid: {}", location),
+                }
+                response
+            }
+            Creation::Existing(body) => (StatusCode::OK, Json(body)).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_created_sets_201_and_location() {
+        let creation = Creation::created("/api/sales/orders/42", json!({ "id": 42 }));
+        assert_eq!(creation.status(), StatusCode::CREATED);
+
+        let response = creation.into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::LOCATION], "/api/sales/orders/42");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[test]
+    fn test_existing_returns_200_without_location() {
+        let response = Creation::existing(json!({ "id": 7 })).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::LOCATION).is_none());
+    }
+}
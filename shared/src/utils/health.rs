@@ -0,0 +1,146 @@
+// Health check per dependency: status, latency probe, dan waktu pengecekan
+//
+// Probe yang berhasil tapi lambat dilaporkan degraded, supaya database yang mulai
+// melambat terlihat sebelum benar-benar mati. Setiap probe dibatasi timeout
+// agar endpoint /health tetap murah dipanggil berkala.
+//
+// /health publik, jadi pesan error probe (host, user, detail driver) hanya ditulis ke log
+// dan tidak pernah ikut diserialisasi ke response.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+// Probe lebih lambat dari ini dianggap degraded
+pub const SLOW_PROBE_MS: u64 = 250;
+
+// Probe yang belum selesai setelah ini dianggap gagal
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Urutan variant = tingkat keparahan, dipakai untuk mencari status terburuk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthLevel {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+// Hasil probe satu dependency
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DependencyHealth {
+    pub status: HealthLevel,
+    #[schema(example = 3)]
+    pub latency_ms: u64,
+    pub checked_at: DateTime<Utc>,
+    // Alasan jika tidak healthy (error probe, timeout, atau probe lambat), hanya untuk log
+    #[serde(skip)]
+    pub detail: Option<String>,
+}
+
+impl DependencyHealth {
+    fn from_probe(result: Result<(), String>, latency: Duration, checked_at: DateTime<Utc>) -> Self {
+        let latency_ms = latency.as_millis() as u64;
+
+        let (status, detail) = match result {
+            Err(e) => (HealthLevel::Unhealthy, Some(e)),
+            Ok(()) if latency_ms > SLOW_PROBE_MS => (
+                HealthLevel::Degraded,
+                Some(format!("Probe lambat (> {} ms)", SLOW_PROBE_MS)),
+            ),
+            Ok(()) => (HealthLevel::Healthy, None),
+        };
+
+        Self {
+            status,
+            latency_ms,
+            checked_at,
+            detail,
+        }
+    }
+}
+
+// Jalankan probe dengan timeout dan ukur latency-nya; alasan tidak healthy ditulis ke log
+pub async fn probe<F>(dependency: &'static str, check: F) -> DependencyHealth
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let checked_at = Utc::now();
+    let started = Instant::now();
+
+    let result = tokio::time::timeout(PROBE_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("Timeout setelah {} ms", PROBE_TIMEOUT.as_millis())));
+
+    let health = DependencyHealth::from_probe(result, started.elapsed(), checked_at);
+    if let Some(detail) = &health.detail {
+        tracing::warn!(dependency, status = ?health.status, latency_ms = health.latency_ms, "Health probe: {}", detail);
+    }
+    health
+}
+
+// Status keseluruhan = status dependency terburuk
+pub fn worst_status(dependencies: &[&DependencyHealth]) -> HealthLevel {
+    dependencies
+        .iter()
+        .map(|dependency| dependency.status)
+        .max()
+        .unwrap_or(HealthLevel::Healthy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(result: Result<(), String>, latency_ms: u64) -> DependencyHealth {
+        DependencyHealth::from_probe(result, Duration::from_millis(latency_ms), Utc::now())
+    }
+
+    #[test]
+    fn test_slow_probe_is_degraded() {
+        let fast = health(Ok(()), 3);
+        assert_eq!(fast.status, HealthLevel::Healthy);
+        assert_eq!(fast.latency_ms, 3);
+        assert!(fast.detail.is_none());
+
+        assert_eq!(health(Ok(()), SLOW_PROBE_MS).status, HealthLevel::Healthy);
+        assert_eq!(health(Ok(()), SLOW_PROBE_MS + 1).status, HealthLevel::Degraded);
+        assert_eq!(health(Err("connection refused".to_string()), 1).status, HealthLevel::Unhealthy);
+    }
+
+    #[test]
+    fn test_overall_is_worst_status() {
+        let healthy = health(Ok(()), 1);
+        let degraded = health(Ok(()), 900);
+        let unhealthy = health(Err("down".to_string()), 1);
+
+        assert_eq!(worst_status(&[&healthy, &healthy]), HealthLevel::Healthy);
+        assert_eq!(worst_status(&[&healthy, &degraded]), HealthLevel::Degraded);
+        assert_eq!(worst_status(&[&degraded, &unhealthy, &healthy]), HealthLevel::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_failure() {
+        let ok = probe("database", async { Ok(()) }).await;
+        assert_eq!(ok.status, HealthLevel::Healthy);
+
+        let failed = probe("database", async { Err("connection refused".to_string()) }).await;
+        assert_eq!(failed.status, HealthLevel::Unhealthy);
+        assert_eq!(failed.detail.as_deref(), Some("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_detail_not_serialized() {
+        let failed = probe("database", async {
+            Err("password authentication failed for user \"postgres\"".to_string())
+        })
+        .await;
+
+        let body = serde_json::to_value(&failed).unwrap();
+        assert_eq!(body["status"], "unhealthy");
+        assert!(body.get("detail").is_none());
+        assert!(!body.to_string().contains("postgres"));
+    }
+}
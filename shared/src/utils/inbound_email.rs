@@ -0,0 +1,191 @@
+// Reply-by-email: token bertanda tangan di alamat reply-to dan verifikasi webhook inbound provider
+//
+// Alamat reply: reply+{conversation_id}.{user_id}.{expires_at}.{signature}@{domain}
+// Email notifikasi chat memakai alamat ini sebagai Reply-To; balasan customer masuk lewat
+// inbound parse provider dan token dipakai untuk menentukan conversation + pengirim.
+// expires_at (unix detik) ikut ditandatangani, alamat lama yang bocor tidak berlaku selamanya.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const REPLY_PREFIX: &str = "reply+";
+
+// Panjang signature hex di token (128 bit cukup, alamat tetap pendek)
+const TOKEN_SIGNATURE_LEN: usize = 32;
+
+fn token_mac(secret: &[u8], conversation_id: i32, user_id: i32, expires_at: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC menerima key dengan panjang berapa pun");
+    mac.update(format!("chat-reply:{}:{}:{}", conversation_id, user_id, expires_at).as_bytes());
+    mac
+}
+
+// Token reply untuk satu user di satu conversation, berlaku sampai expires_at (unix detik)
+pub fn sign_reply_token(secret: &[u8], conversation_id: i32, user_id: i32, expires_at: i64) -> String {
+    let mut signature = hex::encode(token_mac(secret, conversation_id, user_id, expires_at).finalize().into_bytes());
+    signature.truncate(TOKEN_SIGNATURE_LEN);
+    format!("{}.{}.{}.{}", conversation_id, user_id, expires_at, signature)
+}
+
+// Verifikasi token (constant-time) dan masa berlakunya, return (conversation_id, user_id)
+pub fn verify_reply_token(secret: &[u8], token: &str, now: i64) -> Option<(i32, i32)> {
+    let mut parts = token.split('.');
+    let conversation_id = parts.next()?.parse::<i32>().ok()?;
+    let user_id = parts.next()?.parse::<i32>().ok()?;
+    let expires_at = parts.next()?.parse::<i64>().ok()?;
+    let signature = hex::decode(parts.next()?.to_ascii_lowercase()).ok()?;
+    if parts.next().is_some() || signature.len() * 2 != TOKEN_SIGNATURE_LEN {
+        return None;
+    }
+
+    token_mac(secret, conversation_id, user_id, expires_at)
+        .verify_truncated_left(&signature)
+        .ok()?;
+
+    (now <= expires_at).then_some((conversation_id, user_id))
+}
+
+// Alamat Reply-To untuk email notifikasi chat
+pub fn reply_address(secret: &[u8], domain: &str, conversation_id: i32, user_id: i32, expires_at: i64) -> String {
+    format!("{}{}@{}", REPLY_PREFIX, sign_reply_token(secret, conversation_id, user_id, expires_at), domain)
+}
+
+// Ambil token dari alamat "reply+{token}@domain" (boleh dengan display name)
+pub fn reply_token_from_address(address: &str) -> Option<&str> {
+    let address = address.trim();
+    let address = match (address.rfind('<'), address.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &address[start + 1..end],
+        _ => address,
+    };
+
+    let (local, _domain) = address.rsplit_once('@')?;
+    let token = local.strip_prefix(REPLY_PREFIX)?;
+    (!token.is_empty()).then_some(token)
+}
+
+// Verifikasi signature webhook Svix (dipakai Resend): header svix-id, svix-timestamp, svix-signature.
+// Secret berformat "whsec_{base64}", signature "v1,{base64}" (bisa lebih dari satu, dipisah spasi).
+pub fn verify_svix_signature(
+    secret: &str,
+    message_id: &str,
+    timestamp: i64,
+    body: &[u8],
+    signature_header: &str,
+    now: i64,
+    tolerance_secs: i64,
+) -> bool {
+    if (now - timestamp).abs() > tolerance_secs {
+        return false;
+    }
+
+    let Ok(key) = STANDARD.decode(secret.strip_prefix("whsec_").unwrap_or(secret)) else {
+        return false;
+    };
+
+    signature_header
+        .split_whitespace()
+        .filter_map(|signature| signature.strip_prefix("v1,"))
+        .filter_map(|signature| STANDARD.decode(signature).ok())
+        .any(|expected| {
+            let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(&key) else {
+                return false;
+            };
+            mac.update(format!("{}.{}.", message_id, timestamp).as_bytes());
+            mac.update(body);
+            mac.verify_slice(&expected).is_ok()
+        })
+}
+
+// Verifikasi header "Authorization: Basic ..." dari inbound parse (SendGrid) terhadap "user:password"
+pub fn verify_basic_auth(authorization: &str, expected_credentials: &str) -> bool {
+    let Some(encoded) = authorization.trim().strip_prefix("Basic ") else {
+        return false;
+    };
+    let Ok(decoded) = STANDARD.decode(encoded.trim()) else {
+        return false;
+    };
+
+    // Bandingkan constant-time agar kredensial tidak bocor lewat timing
+    decoded.len() == expected_credentials.len()
+        && decoded
+            .iter()
+            .zip(expected_credentials.as_bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"reply-secret";
+    const NOW: i64 = 1_700_000_000;
+    const EXPIRES_AT: i64 = NOW + 30 * 86_400;
+
+    #[test]
+    fn test_reply_token_roundtrip() {
+        let token = sign_reply_token(SECRET, 42, 7, EXPIRES_AT);
+        assert_eq!(verify_reply_token(SECRET, &token, NOW), Some((42, 7)));
+        assert_eq!(verify_reply_token(b"other-secret", &token, NOW), None);
+    }
+
+    #[test]
+    fn test_reply_token_expires() {
+        let token = sign_reply_token(SECRET, 42, 7, EXPIRES_AT);
+        assert_eq!(verify_reply_token(SECRET, &token, EXPIRES_AT), Some((42, 7)));
+        assert_eq!(verify_reply_token(SECRET, &token, EXPIRES_AT + 1), None);
+    }
+
+    #[test]
+    fn test_reply_token_rejects_tampering() {
+        let token = sign_reply_token(SECRET, 42, 7, EXPIRES_AT);
+        let signature = token.rsplit('.').next().unwrap();
+
+        // Ganti conversation/user/masa berlaku dengan signature lama
+        assert_eq!(verify_reply_token(SECRET, &format!("43.7.{}.{}", EXPIRES_AT, signature), NOW), None);
+        assert_eq!(verify_reply_token(SECRET, &format!("42.8.{}.{}", EXPIRES_AT, signature), NOW), None);
+        assert_eq!(verify_reply_token(SECRET, &format!("42.7.{}.{}", EXPIRES_AT + 86_400, signature), NOW), None);
+        assert_eq!(verify_reply_token(SECRET, "42.7", NOW), None);
+        assert_eq!(verify_reply_token(SECRET, &format!("{}.extra", token), NOW), None);
+    }
+
+    #[test]
+    fn test_reply_address_parsing() {
+        let address = reply_address(SECRET, "reply.bigauto.com", 42, 7, EXPIRES_AT);
+        let token = reply_token_from_address(&address).unwrap();
+        assert_eq!(verify_reply_token(SECRET, token, NOW), Some((42, 7)));
+
+        let named = format!("Big Auto Chat <{}>", address);
+        assert_eq!(reply_token_from_address(&named), Some(token));
+
+        assert_eq!(reply_token_from_address("support@bigauto.com"), None);
+        assert_eq!(reply_token_from_address("reply+@bigauto.com"), None);
+    }
+
+    #[test]
+    fn test_verify_svix_signature() {
+        let key = b"svix-test-key";
+        let secret = format!("whsec_{}", STANDARD.encode(key));
+        let body = br#"{"type":"email.received"}"#;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(b"msg_1.1700000000.");
+        mac.update(body);
+        let signature = format!("v1,{}", STANDARD.encode(mac.finalize().into_bytes()));
+        let header = format!("v1,invalid {}", signature);
+
+        assert!(verify_svix_signature(&secret, "msg_1", 1_700_000_000, body, &header, 1_700_000_060, 300));
+        assert!(!verify_svix_signature(&secret, "msg_2", 1_700_000_000, body, &header, 1_700_000_060, 300));
+        assert!(!verify_svix_signature(&secret, "msg_1", 1_700_000_000, b"{}", &header, 1_700_000_060, 300));
+        assert!(!verify_svix_signature(&secret, "msg_1", 1_700_000_000, body, &header, 1_700_001_000, 300));
+    }
+
+    #[test]
+    fn test_verify_basic_auth() {
+        let header = format!("Basic {}", STANDARD.encode("sendgrid:s3cret"));
+        assert!(verify_basic_auth(&header, "sendgrid:s3cret"));
+        assert!(!verify_basic_auth(&header, "sendgrid:other"));
+        assert!(!verify_basic_auth("Bearer abc", "sendgrid:s3cret"));
+        assert!(!verify_basic_auth("Basic !!!", "sendgrid:s3cret"));
+    }
+}
@@ -0,0 +1,106 @@
+// Notifikasi dari service lain lewat notification-service
+//
+// Service lain tidak menulis tabel notifications langsung: notifikasi dikirim ke
+// POST /internal/notifications di notification-service, ditandatangani HMAC dengan
+// INTERNAL_SERVICE_SECRET (format signature sama dengan outbound webhook).
+
+use axum::http::{header::CONTENT_TYPE, HeaderMap};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::utils::webhook_signature::{sign_webhook, verify_webhook, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+pub const INTERNAL_SERVICE_SECRET_ENV: &str = "INTERNAL_SERVICE_SECRET";
+pub const NOTIFICATIONS_PATH: &str = "/internal/notifications";
+
+// Batas jumlah notifikasi per request, pengirim memecah batch yang lebih besar
+pub const MAX_NOTIFICATIONS_PER_REQUEST: usize = 500;
+
+// Selisih jam maksimal antara pengirim dan notification-service (anti-replay)
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+// Satu notifikasi in-app untuk satu user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct NewNotification {
+    pub user_id: i32,
+    #[serde(rename = "type")]
+    pub notification_type: String,
+    pub title: String,
+    pub message: String,
+    pub related_id: Option<i32>,
+    pub related_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateNotificationsRequest {
+    pub notifications: Vec<NewNotification>,
+}
+
+// Secret dari INTERNAL_SERVICE_SECRET, None jika belum diset
+pub fn secret_from_env() -> Option<String> {
+    std::env::var(INTERNAL_SERVICE_SECRET_ENV).ok().filter(|s| !s.trim().is_empty())
+}
+
+// Kirim notifikasi ke notification-service, Err jika gagal atau ditolak (pengirim retry)
+pub async fn send_notifications(
+    client: &reqwest::Client,
+    base_url: &str,
+    secret: &str,
+    notifications: &[NewNotification],
+) -> Result<(), String> {
+    for batch in notifications.chunks(MAX_NOTIFICATIONS_PER_REQUEST) {
+        let body = serde_json::to_vec(&CreateNotificationsRequest { notifications: batch.to_vec() })
+            .map_err(|e| format!("Gagal serialisasi notifikasi: {}", e))?;
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = sign_webhook(secret.as_bytes(), timestamp, &body);
+
+        let response = client
+            .post(format!("{}{}", base_url.trim_end_matches('/'), NOTIFICATIONS_PATH))
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("Notification service tidak bisa dihubungi: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Notification service menolak notifikasi: HTTP {}", response.status()));
+        }
+    }
+
+    Ok(())
+}
+
+// Verifikasi signature request internal di sisi notification-service
+pub fn verify_request(secret: &str, headers: &HeaderMap, body: &[u8], now: i64) -> bool {
+    let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok());
+
+    let (Some(signature), Some(timestamp)) = (
+        header(SIGNATURE_HEADER),
+        header(TIMESTAMP_HEADER).and_then(|ts| ts.parse::<i64>().ok()),
+    ) else {
+        return false;
+    };
+
+    verify_webhook(secret.as_bytes(), timestamp, body, signature, now, SIGNATURE_TOLERANCE_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_request_headers() {
+        let body = br#"{"notifications":[]}"#;
+        let now = 1_760_000_000;
+        let mut headers = HeaderMap::new();
+        assert!(!verify_request("rahasia", &headers, body, now));
+
+        headers.insert(SIGNATURE_HEADER, sign_webhook(b"rahasia", now, body).parse().unwrap());
+        headers.insert(TIMESTAMP_HEADER, now.to_string().parse().unwrap());
+        assert!(verify_request("rahasia", &headers, body, now));
+        assert!(!verify_request("secret-lain", &headers, body, now));
+        assert!(!verify_request("rahasia", &headers, br#"{"notifications":[1]}"#, now));
+        assert!(!verify_request("rahasia", &headers, body, now + SIGNATURE_TOLERANCE_SECS + 1));
+    }
+}
@@ -0,0 +1,139 @@
+// Format output log untuk semua service
+//
+// LOG_FORMAT=json menulis satu objek JSON per baris agar log bisa di-query di agregator log,
+// selain itu (default) tetap teks biasa yang enak dibaca saat development. Filter level tetap
+// lewat RUST_LOG.
+//
+// Field standar di output JSON:
+// - timestamp, level, target: metadata event dari tracing
+// - message: pesan log yang bisa dibaca manusia
+// - event: nama business event (mis. booking_created, payment_status_changed, message_sent)
+// - id entitas: user_id, seller_id, customer_id, booking_id, order_id, payment_id,
+//   conversation_id, message_id, vehicle_id, refund_id
+// - status / previous_status: status entitas sesudah dan sebelum perubahan
+// - span, spans: span aktif (mis. request dari TraceLayer) beserta field-nya
+// Field event ditulis di level atas (bukan di bawah "fields") supaya query cukup `event = ...`.
+
+use std::io;
+
+use tracing::Subscriber;
+use tracing_subscriber::{fmt::MakeWriter, registry::LookupSpan, Layer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("LOG_FORMAT").ok().as_deref())
+    }
+
+    fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()) {
+            Some(v) if v == "json" => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+}
+
+// Layer fmt sesuai LOG_FORMAT, dipasang di tracing_subscriber::registry() tiap service
+pub fn fmt_layer<S>() -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fmt_layer_with_writer(LogFormat::from_env(), io::stdout)
+}
+
+fn fmt_layer_with_writer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn capture(format: LogFormat) -> String {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(fmt_layer_with_writer(format, buffer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", method = "POST");
+            let _guard = span.enter();
+            tracing::info!(event = "booking_created", booking_id = 42, user_id = 7, "Booking dibuat");
+        });
+
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_log_format_from_value() {
+        assert_eq!(LogFormat::parse(Some("json")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some(" JSON ")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some("text")), LogFormat::Text);
+        assert_eq!(LogFormat::parse(None), LogFormat::Text);
+    }
+
+    #[test]
+    fn test_json_output_has_standard_fields() {
+        let output = capture(LogFormat::Json);
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+
+        assert_eq!(line["level"], "INFO");
+        assert!(line["timestamp"].is_string());
+        assert!(line["target"].is_string());
+        assert_eq!(line["message"], "Booking dibuat");
+        assert_eq!(line["event"], "booking_created");
+        assert_eq!(line["booking_id"], 42);
+        assert_eq!(line["user_id"], 7);
+        assert_eq!(line["span"]["name"], "request");
+        assert_eq!(line["span"]["method"], "POST");
+    }
+
+    #[test]
+    fn test_text_output_is_not_json() {
+        let output = capture(LogFormat::Text);
+        assert!(output.contains("Booking dibuat"));
+        assert!(output.contains("booking_created"));
+        assert!(serde_json::from_str::<serde_json::Value>(output.trim()).is_err());
+    }
+}
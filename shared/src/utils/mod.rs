@@ -1,4 +1,23 @@
 pub mod cloudinary;
 pub mod validation;
 pub mod http_client;
-pub mod token_extraction;
\ No newline at end of file
+pub mod token_extraction;
+pub mod pdf;
+pub mod storage;
+pub mod webhook_signature;
+pub mod inbound_email;
+pub mod pagination;
+pub mod schema_check;
+pub mod cors;
+pub mod creation;
+pub mod scheduler;
+pub mod request_timeout;
+pub mod bind_addr;
+pub mod startup_gate;
+pub mod rate_limit;
+pub mod logging;
+pub mod health;
+pub mod clock;
+pub mod request_id;
+pub mod client_ip;
+pub mod internal_notifications;
@@ -0,0 +1,164 @@
+// Extractor paginasi untuk endpoint list
+//
+// page/limit/offset divalidasi sebelum handler jalan: nilai negatif, limit di atas batas,
+// atau input yang bukan angka ditolak 400 dengan pesan per field (bukan di-clamp diam-diam).
+// Default dan batas limit per endpoint diatur lewat const generic, contoh `Pagination<50, 200>`.
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
+
+use super::validation::{errors_by_field, FieldError};
+
+// Query string paginasi, dipakai juga untuk dokumentasi OpenAPI via `params(PaginationParams)`
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaginationParams {
+    /// Nomor halaman, mulai dari 1 (tidak boleh digabung dengan offset)
+    pub page: Option<i64>,
+    /// Jumlah item per halaman
+    pub limit: Option<i64>,
+    /// Jumlah item yang dilewati, alternatif dari page
+    pub offset: Option<i64>,
+}
+
+// Paginasi yang sudah tervalidasi, offset selalu terisi (dari page jika yang dikirim page)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination<const DEFAULT_LIMIT: i64 = 20, const MAX_LIMIT: i64 = 100> {
+    pub page: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl<const DEFAULT_LIMIT: i64, const MAX_LIMIT: i64> Pagination<DEFAULT_LIMIT, MAX_LIMIT> {
+    pub fn from_params(params: &PaginationParams) -> Result<Self, PaginationError> {
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(PaginationError::new("limit", format!("limit harus antara 1 dan {}", MAX_LIMIT)));
+        }
+
+        let offset = match (params.page, params.offset) {
+            (Some(_), Some(_)) => {
+                return Err(PaginationError::new("offset", "Gunakan page atau offset, tidak keduanya"));
+            }
+            (_, Some(offset)) if offset < 0 => {
+                return Err(PaginationError::new("offset", "offset tidak boleh negatif"));
+            }
+            (_, Some(offset)) => offset,
+            (Some(page), None) if page < 1 => {
+                return Err(PaginationError::new("page", "page minimal 1"));
+            }
+            (Some(page), None) => (page - 1)
+                .checked_mul(limit)
+                .ok_or_else(|| PaginationError::new("page", "page terlalu besar"))?,
+            (None, None) => 0,
+        };
+
+        Ok(Self {
+            page: offset / limit + 1,
+            limit,
+            offset,
+        })
+    }
+}
+
+impl<S, const DEFAULT_LIMIT: i64, const MAX_LIMIT: i64> FromRequestParts<S> for Pagination<DEFAULT_LIMIT, MAX_LIMIT>
+where
+    S: Send + Sync,
+{
+    type Rejection = PaginationError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PaginationParams>::try_from_uri(&parts.uri).map_err(|rejection| {
+            PaginationError::new(
+                "pagination",
+                format!("Parameter paginasi tidak valid: {}", rejection.body_text()),
+            )
+        })?;
+
+        Self::from_params(&params)
+    }
+}
+
+// Rejection paginasi: 400 dengan format validation_error yang sama dengan AppError::fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaginationError(pub FieldError);
+
+impl PaginationError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self(FieldError::new(field, message))
+    }
+}
+
+impl IntoResponse for PaginationError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": "validation_error",
+            "message": self.0.message,
+            "errors": errors_by_field(std::slice::from_ref(&self.0)),
+        });
+
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(page: Option<i64>, limit: Option<i64>, offset: Option<i64>) -> PaginationParams {
+        PaginationParams { page, limit, offset }
+    }
+
+    async fn extract(uri: &str) -> Result<Pagination<50, 200>, PaginationError> {
+        let (mut parts, _) = axum::http::Request::builder().uri(uri).body(()).unwrap().into_parts();
+        Pagination::from_request_parts(&mut parts, &()).await
+    }
+
+    #[test]
+    fn test_defaults_and_page_to_offset() {
+        let pagination = Pagination::<20, 100>::from_params(&PaginationParams::default()).unwrap();
+        assert_eq!(pagination, Pagination { page: 1, limit: 20, offset: 0 });
+
+        let pagination = Pagination::<20, 100>::from_params(&params(Some(3), Some(10), None)).unwrap();
+        assert_eq!(pagination, Pagination { page: 3, limit: 10, offset: 20 });
+
+        let pagination = Pagination::<20, 100>::from_params(&params(None, Some(10), Some(25))).unwrap();
+        assert_eq!(pagination, Pagination { page: 3, limit: 10, offset: 25 });
+    }
+
+    #[test]
+    fn test_rejects_negative_and_over_max_values() {
+        let field = |p: PaginationParams| Pagination::<20, 100>::from_params(&p).unwrap_err().0.field;
+
+        assert_eq!(field(params(None, Some(101), None)), "limit");
+        assert_eq!(field(params(None, Some(0), None)), "limit");
+        assert_eq!(field(params(None, Some(-5), None)), "limit");
+        assert_eq!(field(params(Some(0), None, None)), "page");
+        assert_eq!(field(params(Some(-1), None, None)), "page");
+        assert_eq!(field(params(None, None, Some(-1))), "offset");
+        assert_eq!(field(params(Some(2), None, Some(0))), "offset");
+        assert_eq!(field(params(Some(i64::MAX), Some(100), None)), "page");
+
+        assert!(Pagination::<20, 100>::from_params(&params(None, Some(100), None)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_extractor_uses_endpoint_bounds() {
+        assert_eq!(extract("/items").await.unwrap().limit, 50);
+        assert_eq!(extract("/items?limit=200&page=2").await.unwrap().offset, 200);
+
+        let err = extract("/items?limit=201").await.unwrap_err();
+        assert_eq!(err.0.message, "limit harus antara 1 dan 200");
+
+        let err = extract("/items?limit=abc").await.unwrap_err();
+        assert_eq!(err.0.field, "pagination");
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,210 @@
+// PDF writer minimal (teks saja) untuk dokumen seperti receipt dan invoice
+use std::fmt::Write as _;
+
+// Ukuran halaman A4 dalam point
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const LINE_SPACING: f32 = 1.4;
+
+// Satu baris teks di dokumen
+#[derive(Debug, Clone)]
+struct PdfLine {
+    text: String,
+    size: f32,
+    bold: bool,
+}
+
+// Builder dokumen PDF berbasis baris teks (font Helvetica bawaan PDF)
+#[derive(Debug, Clone, Default)]
+pub struct PdfDocument {
+    title: String,
+    lines: Vec<PdfLine>,
+}
+
+impl PdfDocument {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            lines: Vec::new(),
+        }
+    }
+
+    // Judul besar dengan huruf tebal
+    pub fn heading(mut self, text: impl Into<String>) -> Self {
+        self.lines.push(PdfLine { text: text.into(), size: 16.0, bold: true });
+        self
+    }
+
+    // Label section dengan huruf tebal
+    pub fn section(mut self, text: impl Into<String>) -> Self {
+        self.lines.push(PdfLine { text: text.into(), size: 11.0, bold: true });
+        self
+    }
+
+    // Baris teks biasa
+    pub fn line(mut self, text: impl Into<String>) -> Self {
+        self.lines.push(PdfLine { text: text.into(), size: 10.0, bold: false });
+        self
+    }
+
+    // Baris kosong sebagai pemisah
+    pub fn blank(self) -> Self {
+        self.line("")
+    }
+
+    // Render dokumen menjadi bytes PDF 1.4
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let pages = self.layout_pages();
+
+        // Object 1-4 tetap: catalog, pages, font regular, font bold, info
+        let mut objects: Vec<String> = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            String::new(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+            format!("<< /Title ({}) /Producer (Big Auto) >>", escape_text(&self.title)),
+        ];
+
+        let mut page_refs = Vec::new();
+        for content in pages {
+            let page_id = objects.len() + 1;
+            let content_id = page_id + 1;
+            page_refs.push(format!("{} 0 R", page_id));
+
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT, content_id
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content));
+        }
+
+        objects[1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_refs.join(" "),
+            page_refs.len()
+        );
+
+        let mut output = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(output.len());
+            let _ = write!(output, "{} 0 obj\n{}\nendobj\n", index + 1, object);
+        }
+
+        let xref_offset = output.len();
+        let _ = write!(output, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(output, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            output,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        );
+
+        output.into_bytes()
+    }
+
+    // Susun baris ke content stream per halaman
+    fn layout_pages(&self) -> Vec<String> {
+        let mut pages = Vec::new();
+        let mut content = String::new();
+        let mut y = PAGE_HEIGHT - MARGIN;
+
+        for line in &self.lines {
+            let height = line.size * LINE_SPACING;
+            if y - height < MARGIN {
+                pages.push(std::mem::take(&mut content));
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            y -= height;
+
+            if !line.text.is_empty() {
+                let font = if line.bold { "F2" } else { "F1" };
+                let _ = writeln!(
+                    content,
+                    "BT /{} {} Tf {} {:.2} Td ({}) Tj ET",
+                    font,
+                    line.size,
+                    MARGIN,
+                    y,
+                    escape_text(&line.text)
+                );
+            }
+        }
+
+        pages.push(content);
+        pages
+    }
+}
+
+// Escape karakter khusus string PDF, karakter non-ASCII diganti '?'
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_ascii() && !c.is_ascii_control() => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_structure() {
+        let bytes = PdfDocument::new("Test")
+            .heading("INVOICE")
+            .line("Total: Rp 1.000")
+            .to_bytes();
+        let text = String::from_utf8(bytes).unwrap();
+
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("(INVOICE) Tj"));
+        assert!(text.contains("/Count 1"));
+    }
+
+    #[test]
+    fn test_xref_offsets_point_to_objects() {
+        let bytes = PdfDocument::new("Test").line("a").to_bytes();
+        let text = String::from_utf8(bytes).unwrap();
+
+        let xref_start = text.find("xref\n").unwrap();
+        let offsets: Vec<usize> = text[xref_start..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .map(|l| l[..10].parse().unwrap())
+            .collect();
+
+        for (index, offset) in offsets.iter().enumerate() {
+            assert!(text[*offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+    }
+
+    #[test]
+    fn test_long_document_splits_pages() {
+        let mut doc = PdfDocument::new("Long");
+        for i in 0..100 {
+            doc = doc.line(format!("Baris {}", i));
+        }
+        let text = String::from_utf8(doc.to_bytes()).unwrap();
+        assert!(text.contains("/Count 2"));
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a(b)c\\"), "a\\(b\\)c\\\\");
+        assert_eq!(escape_text("Rp 1.000 ✓"), "Rp 1.000 ?");
+    }
+}
@@ -0,0 +1,162 @@
+// Limiter sliding window dan response 429 untuk rate limiter middleware semua service
+//
+// Retry-After dihitung dari reset window limiter, bukan angka tetap. Limiter sliding window
+// (Redis sorted set) reset saat request tertua di window keluar, jadi reset_time dihitung dari
+// score entry tertua, bukan dari waktu request sekarang.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use redis::aio::ConnectionLike;
+use serde_json::Value;
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+// Waktu (unix detik) slot sliding window kembali tersedia: request tertua + panjang window
+pub fn sliding_reset_time(oldest_in_window: Option<u64>, now: u64, window_seconds: u64) -> u64 {
+    oldest_in_window.unwrap_or(now) + window_seconds
+}
+
+// Detik sampai reset, minimal 1 agar client tidak langsung retry
+pub fn retry_after_secs(reset_time: u64, now: u64) -> u64 {
+    reset_time.saturating_sub(now).max(1)
+}
+
+// Hasil satu request yang dicatat di limiter sliding window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingWindowHit {
+    pub allowed: bool,
+    // Jumlah request di window termasuk request ini
+    pub current_count: u32,
+    pub remaining: u32,
+    pub reset_time: u64,
+}
+
+impl SlidingWindowHit {
+    // count_before = jumlah request di window sebelum request ini dicatat
+    fn evaluate(count_before: usize, oldest_in_window: Option<u64>, now: u64, max_requests: u32, window_seconds: u64) -> Self {
+        let allowed = count_before < max_requests as usize;
+        let current_count = count_before as u32 + 1;
+
+        Self {
+            allowed,
+            current_count,
+            remaining: if allowed { max_requests.saturating_sub(current_count) } else { 0 },
+            reset_time: sliding_reset_time(oldest_in_window, now, window_seconds),
+        }
+    }
+}
+
+// Catat satu request di sorted set `key` dan nilai terhadap max_requests per window_seconds.
+// Semua langkah (buang entry lama, hitung, tambah, expire, ambil entry tertua) dalam satu MULTI
+// agar request bersamaan dari instance lain tidak menyisip di antaranya
+pub async fn sliding_window_hit<C: ConnectionLike>(
+    conn: &mut C,
+    key: &str,
+    max_requests: u32,
+    window_seconds: u64,
+) -> redis::RedisResult<SlidingWindowHit> {
+    let now = unix_now();
+    let window_start = now.saturating_sub(window_seconds) + 1;
+    // Member unik agar beberapa request di detik yang sama tetap dihitung terpisah
+    let member = format!("{}:{}", now, uuid::Uuid::new_v4());
+
+    let (count_before, oldest): (usize, Vec<(String, f64)>) = redis::pipe()
+        .atomic()
+        .zrembyscore(key, "-inf", window_start - 1).ignore()
+        .zcard(key)
+        .zadd(key, member, now).ignore()
+        .expire(key, window_seconds as i64).ignore()
+        .zrange_withscores(key, 0, 0)
+        .query_async(conn)
+        .await?;
+
+    Ok(SlidingWindowHit::evaluate(
+        count_before,
+        oldest.first().map(|(_, score)| *score as u64),
+        now,
+        max_requests,
+        window_seconds,
+    ))
+}
+
+// 429 dengan header Retry-After dan field retry_after (detik) di body JSON milik service
+pub fn too_many_requests(reset_time: u64, mut body: Value) -> Response {
+    let retry_after = retry_after_secs(reset_time, unix_now());
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("retry_after".to_string(), retry_after.into());
+    }
+
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from(0));
+    headers.insert("X-RateLimit-Reset", HeaderValue::from(reset_time));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sliding_reset_from_oldest_request() {
+        // Window 60 detik, request tertua 45 detik lalu: slot kosong 15 detik lagi
+        let now = 1_700_000_000;
+        let reset = sliding_reset_time(Some(now - 45), now, 60);
+        assert_eq!(retry_after_secs(reset, now), 15);
+
+        assert_eq!(sliding_reset_time(None, now, 60), now + 60);
+    }
+
+    #[test]
+    fn test_sliding_window_hit_counts_and_remaining() {
+        let now = 1_700_000_000;
+
+        let first = SlidingWindowHit::evaluate(0, Some(now), now, 3, 60);
+        assert_eq!(first, SlidingWindowHit { allowed: true, current_count: 1, remaining: 2, reset_time: now + 60 });
+
+        let last = SlidingWindowHit::evaluate(2, Some(now - 10), now, 3, 60);
+        assert!(last.allowed);
+        assert_eq!(last.remaining, 0);
+        assert_eq!(last.reset_time, now + 50);
+
+        let over = SlidingWindowHit::evaluate(3, Some(now - 10), now, 3, 60);
+        assert!(!over.allowed);
+        assert_eq!(over.current_count, 4);
+        assert_eq!(over.remaining, 0);
+    }
+
+    #[test]
+    fn test_retry_after_never_zero() {
+        assert_eq!(retry_after_secs(100, 100), 1);
+        assert_eq!(retry_after_secs(90, 100), 1);
+        assert_eq!(retry_after_secs(130, 100), 30);
+    }
+
+    #[tokio::test]
+    async fn test_429_has_retry_after_header_and_body() {
+        let reset_time = unix_now() + 30;
+        let response = too_many_requests(reset_time, json!({ "error": "rate_limit_exceeded" }));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let header_secs: u64 = response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!((29..=30).contains(&header_secs), "{}", header_secs);
+        assert_eq!(response.headers()["X-RateLimit-Reset"], reset_time.to_string());
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "rate_limit_exceeded");
+        assert_eq!(body["retry_after"].as_u64(), Some(header_secs));
+    }
+}
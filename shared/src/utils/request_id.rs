@@ -0,0 +1,114 @@
+// request_id per request untuk semua service
+//
+// `apply` dipasang sekali di router paling luar: X-Request-ID dari gateway dipakai (atau UUID
+// baru dibuat), dicatat di span log request, dan dikirim balik di header response. Body error
+// JSON membaca id yang sama lewat `current`, supaya laporan error dari client bisa dicocokkan
+// dengan log.
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Router;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::Span;
+
+pub const HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    // request_id request yang sedang diproses, diisi `scope_request_id`
+    static CURRENT: String;
+}
+
+// Bungkus router dengan SetRequestId -> PropagateRequestId -> TraceLayer (span berisi request_id),
+// dipanggil paling akhir supaya semua middleware service ikut di dalam scope request_id
+pub fn apply(router: Router) -> Router {
+    router
+        .layer(axum::middleware::from_fn(scope_request_id))
+        .layer(TraceLayer::new_for_http().make_span_with(make_span))
+        .layer(PropagateRequestIdLayer::new(HEADER))
+        .layer(SetRequestIdLayer::new(HEADER, MakeRequestUuid))
+}
+
+// request_id request yang sedang diproses; di luar request (scheduler, test) dibuat baru
+pub fn current() -> String {
+    CURRENT.try_with(Clone::clone).unwrap_or_else(|_| uuid::Uuid::new_v4().to_string())
+}
+
+// Header X-Request-ID berisi request_id yang sama dengan body
+pub fn set_header(response: &mut Response, request_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(HEADER, value);
+    }
+}
+
+fn extension_id(request: &Request<Body>) -> Option<String> {
+    request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .map(str::to_string)
+}
+
+fn make_span(request: &Request<Body>) -> Span {
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = %extension_id(request).unwrap_or_default(),
+    )
+}
+
+// Simpan request_id dari extension SetRequestIdLayer selama handler (dan IntoResponse error) berjalan
+async fn scope_request_id(request: Request, next: Next) -> Response {
+    match extension_id(&request) {
+        Some(request_id) => CURRENT.scope(request_id, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse, routing::get};
+    use tower::ServiceExt;
+
+    // Error di handler memakai request_id yang sama dengan header response
+    async fn failing() -> Response {
+        let request_id = current();
+        let mut response = (StatusCode::BAD_REQUEST, request_id.clone()).into_response();
+        set_header(&mut response, &request_id);
+        response
+    }
+
+    async fn body_of(response: Response) -> String {
+        String::from_utf8(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_body_matches_propagated_header() {
+        let app = apply(Router::new().route("/fail", get(failing)).route("/ok", get(|| async { "ok" })));
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/fail").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let header = response.headers()["X-Request-ID"].to_str().unwrap().to_string();
+        assert_eq!(header.len(), 36);
+        assert_eq!(body_of(response).await, header);
+
+        // Id dari gateway dipertahankan, juga untuk response sukses
+        let response = app
+            .oneshot(Request::builder().uri("/ok").header("x-request-id", "gw-123").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["X-Request-ID"], "gw-123");
+    }
+
+    #[test]
+    fn test_current_outside_request_is_fresh() {
+        assert_ne!(current(), current());
+    }
+}
@@ -0,0 +1,90 @@
+// Timeout per request untuk semua service
+//
+// Handler yang menggantung (mis. menunggu upstream yang mati) dihentikan setelah batas waktu
+// dan dijawab 504 Gateway Timeout, sehingga koneksi tidak tertahan tanpa batas.
+// Pasang dengan `Router::route_layer` setelah route biasa didaftarkan: route_layer hanya
+// membungkus route yang sudah ada, jadi route berumur panjang (WebSocket, SSE, export
+// streaming) didaftarkan sesudahnya dan tidak terkena timeout. Route yang butuh batas
+// berbeda (upload multipart) memasang layer sendiri dengan `route_timeout`.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use tower_http::timeout::TimeoutLayer;
+
+pub const REQUEST_TIMEOUT_ENV: &str = "REQUEST_TIMEOUT_SECS";
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+// Timeout default semua route dari REQUEST_TIMEOUT_SECS
+pub fn default_timeout() -> Duration {
+    route_timeout(REQUEST_TIMEOUT_ENV, DEFAULT_REQUEST_TIMEOUT_SECS)
+}
+
+// Timeout route tertentu dari env `env_key` (detik), default jika kosong, tidak valid, atau 0
+pub fn route_timeout(env_key: &str, default_secs: u64) -> Duration {
+    parse_secs(std::env::var(env_key).ok().as_deref(), default_secs)
+}
+
+fn parse_secs(value: Option<&str>, default_secs: u64) -> Duration {
+    let secs = value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(default_secs);
+
+    Duration::from_secs(secs)
+}
+
+// Request yang melewati `timeout` dijawab 504
+pub fn layer(timeout: Duration) -> TimeoutLayer {
+    TimeoutLayer::with_status_code(StatusCode::GATEWAY_TIMEOUT, timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn slow() -> &'static str {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        "done"
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/slow", get(slow))
+            .route("/fast", get(|| async { "ok" }))
+            .route_layer(layer(Duration::from_millis(50)))
+            // Timeout per route, lebih longgar dari default
+            .route("/upload", get(slow).layer(layer(Duration::from_secs(5))))
+            // Didaftarkan setelah route_layer, tidak terkena timeout
+            .route("/stream", get(slow))
+    }
+
+    async fn status_of(path: &str) -> StatusCode {
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        app().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_is_shed_with_504() {
+        assert_eq!(status_of("/slow").await, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(status_of("/fast").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_per_route_timeout_and_long_lived_route_exempt() {
+        assert_eq!(status_of("/upload").await, StatusCode::OK);
+        assert_eq!(status_of("/stream").await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_timeout_from_env_value() {
+        assert_eq!(parse_secs(Some("90"), 30), Duration::from_secs(90));
+
+        // Kosong, tidak valid, atau 0 memakai default
+        for raw in [None, Some("abc"), Some("0"), Some("-5")] {
+            assert_eq!(parse_secs(raw, 30), Duration::from_secs(30));
+        }
+    }
+}
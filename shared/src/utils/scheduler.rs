@@ -0,0 +1,219 @@
+// Helper background scheduler
+//
+// Interval tiap job bisa diatur lewat env (detik). Job jalan sekali saat service start lalu tiap
+// interval; SCHEDULER_RUN_ON_START=false menunda run pertama satu interval. Di test,
+// Ticker::manual() memberi trigger one-shot sehingga satu tick job service bisa dijalankan
+// deterministik tanpa menunggu waktu asli.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+pub const RUN_ON_START_ENV: &str = "SCHEDULER_RUN_ON_START";
+
+// Jeda antar percobaan ulang job yang gagal
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobSchedule {
+    pub interval: Duration,
+    pub run_on_start: bool,
+}
+
+impl JobSchedule {
+    // Interval dari env `interval_env` (detik), default jika kosong atau tidak valid
+    pub fn from_env(interval_env: &str, default_secs: u64) -> Self {
+        Self::parse(
+            std::env::var(interval_env).ok().as_deref(),
+            std::env::var(RUN_ON_START_ENV).ok().as_deref(),
+            default_secs,
+        )
+    }
+
+    fn parse(interval: Option<&str>, run_on_start: Option<&str>, default_secs: u64) -> Self {
+        let secs = interval
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(default_secs);
+
+        Self {
+            interval: Duration::from_secs(secs),
+            // Default true, sama seperti tokio::time::interval yang tick pertama langsung
+            run_on_start: !run_on_start.is_some_and(|v| v.trim().eq_ignore_ascii_case("false")),
+        }
+    }
+}
+
+// Sumber tick job: interval tokio di production, trigger manual di test
+pub enum Ticker {
+    Interval(Interval),
+    Manual(mpsc::UnboundedReceiver<()>),
+}
+
+// Pemicu tick manual, tick loop berhenti saat semua trigger di-drop
+#[derive(Clone)]
+pub struct TickTrigger(mpsc::UnboundedSender<()>);
+
+impl TickTrigger {
+    pub fn fire(&self) -> bool {
+        self.0.send(()).is_ok()
+    }
+}
+
+impl Ticker {
+    pub fn interval(schedule: JobSchedule) -> Self {
+        // Tanpa run_on_start, tick pertama baru terjadi satu interval setelah start
+        let now = time::Instant::now();
+        let start = if schedule.run_on_start { now } else { now + schedule.interval };
+
+        let mut interval = time::interval_at(start, schedule.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Ticker::Interval(interval)
+    }
+
+    pub fn manual() -> (TickTrigger, Self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (TickTrigger(tx), Ticker::Manual(rx))
+    }
+
+    // false jika ticker sudah selesai (trigger manual di-drop)
+    pub async fn tick(&mut self) -> bool {
+        match self {
+            Ticker::Interval(interval) => {
+                interval.tick().await;
+                true
+            }
+            Ticker::Manual(rx) => rx.recv().await.is_some(),
+        }
+    }
+}
+
+// Jalankan job di setiap tick sampai ticker selesai
+pub async fn run_loop<S, F, Fut>(mut ticker: Ticker, state: S, job: F)
+where
+    S: Clone,
+    F: Fn(S) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    while ticker.tick().await {
+        job(state.clone()).await;
+    }
+}
+
+// Production: Ticker::interval(JobSchedule::from_env(..)), test: Ticker::manual()
+pub fn spawn_job<S, F, Fut>(ticker: Ticker, state: S, job: F) -> JoinHandle<()>
+where
+    S: Clone + Send + 'static,
+    F: Fn(S) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(run_loop(ticker, state, job))
+}
+
+// Jalankan satu job dengan retry, log durasi dan jumlah baris yang terpengaruh
+pub async fn run_job<F, Fut, E>(name: &str, attempts: u32, mut job: F) -> Result<u64, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<u64, E>>,
+    E: Display,
+{
+    let started = Instant::now();
+    let mut attempt = 1;
+
+    loop {
+        match job().await {
+            Ok(affected) => {
+                let elapsed_ms = started.elapsed().as_millis();
+                if affected > 0 {
+                    tracing::info!("✅ {}: {} rows affected in {} ms", name, affected, elapsed_ms);
+                } else {
+                    tracing::debug!("{}: no rows affected in {} ms", name, elapsed_ms);
+                }
+                return Ok(affected);
+            }
+            Err(e) if attempt >= attempts => {
+                tracing::error!(
+                    "❌ {} failed after {} attempts in {} ms: {}",
+                    name, attempt, started.elapsed().as_millis(), e
+                );
+                return Err(e);
+            }
+            Err(_) => {
+                attempt += 1;
+                time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_schedule_from_env_values() {
+        let schedule = JobSchedule::parse(Some("90"), Some("true"), 3600);
+        assert_eq!(schedule.interval, Duration::from_secs(90));
+        assert!(schedule.run_on_start);
+
+        // Kosong, tidak valid, atau 0 memakai default
+        for raw in [None, Some("abc"), Some("0")] {
+            assert_eq!(JobSchedule::parse(raw, None, 3600).interval, Duration::from_secs(3600));
+        }
+        // Run pertama langsung kecuali dimatikan eksplisit
+        assert!(JobSchedule::parse(None, None, 60).run_on_start);
+        assert!(JobSchedule::parse(None, Some("no"), 60).run_on_start);
+        assert!(!JobSchedule::parse(None, Some("FALSE"), 60).run_on_start);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_job_runs_once_per_manual_tick() {
+        let runs = Arc::new(Mutex::new(0));
+        let (trigger, ticker) = Ticker::manual();
+        let handle = spawn_job(ticker, runs.clone(), |runs| async move {
+            *runs.lock().unwrap() += 1;
+        });
+
+        assert!(trigger.fire());
+        assert!(trigger.fire());
+        drop(trigger);
+        handle.await.unwrap();
+
+        assert_eq!(*runs.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_job_retries_then_reports_affected_rows() {
+        let calls = Arc::new(Mutex::new(0));
+        let result = run_job("flaky job", 3, || {
+            let calls = calls.clone();
+            async move {
+                let mut calls = calls.lock().unwrap();
+                *calls += 1;
+                if *calls < 2 { Err("db timeout".to_string()) } else { Ok(4) }
+            }
+        })
+        .await;
+
+        assert_eq!(result, Ok(4));
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        let result = run_job("broken job", 1, || async { Err::<u64, _>("db down") }).await;
+        assert_eq!(result, Err("db down"));
+    }
+
+    #[tokio::test]
+    async fn test_interval_run_on_start_ticks_immediately() {
+        let mut ticker = Ticker::interval(JobSchedule { interval: Duration::from_secs(3600), run_on_start: true });
+        let ticked = time::timeout(Duration::from_millis(100), ticker.tick()).await;
+        assert_eq!(ticked, Ok(true));
+
+        let mut ticker = Ticker::interval(JobSchedule { interval: Duration::from_secs(3600), run_on_start: false });
+        assert!(time::timeout(Duration::from_millis(50), ticker.tick()).await.is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// Self-check schema database saat startup
+//
+// Migration yang lupa dijalankan biasanya baru ketahuan sebagai 500 saat handler pertama
+// kali menyentuh tabel/kolom yang hilang. Setiap service mendaftarkan tabel dan kolom kritis
+// yang dipakai, lalu dicek lewat information_schema sebelum service menerima request.
+// Di production service menolak start, di environment lain cukup warning.
+
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+// Tabel beserta kolom yang wajib ada
+pub type SchemaRequirements = &'static [(&'static str, &'static [&'static str])];
+
+// Set SKIP_SCHEMA_CHECK=true untuk test atau database sementara tanpa schema lengkap
+pub const SKIP_SCHEMA_CHECK_ENV: &str = "SKIP_SCHEMA_CHECK";
+
+// Daftar tabel/kolom yang tidak ditemukan, format "tabel" atau "tabel.kolom"
+pub fn missing_items(required: SchemaRequirements, present: &HashMap<String, HashSet<String>>) -> Vec<String> {
+    required
+        .iter()
+        .flat_map(|(table, columns)| match present.get(*table) {
+            None => vec![format!("tabel {}", table)],
+            Some(existing) => columns
+                .iter()
+                .filter(|column| !existing.contains(**column))
+                .map(|column| format!("kolom {}.{}", table, column))
+                .collect(),
+        })
+        .collect()
+}
+
+// Cek schema dan log detail yang hilang. Err hanya di production agar development tetap bisa jalan
+pub async fn verify_schema(
+    db: &PgPool,
+    service: &str,
+    required: SchemaRequirements,
+    is_production: bool,
+) -> Result<(), String> {
+    let skip = std::env::var(SKIP_SCHEMA_CHECK_ENV)
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false);
+    if skip {
+        tracing::warn!("⚠️ Schema self-check {} dilewati ({}=true)", service, SKIP_SCHEMA_CHECK_ENV);
+        return Ok(());
+    }
+
+    let tables: Vec<&str> = required.iter().map(|(table, _)| *table).collect();
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT table_name::text, column_name::text
+         FROM information_schema.columns
+         WHERE table_schema = current_schema() AND table_name = ANY($1)",
+    )
+    .bind(&tables)
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Gagal membaca information_schema: {}", e))?;
+
+    let mut present: HashMap<String, HashSet<String>> = HashMap::new();
+    for (table, column) in rows {
+        present.entry(table).or_default().insert(column);
+    }
+
+    let missing = missing_items(required, &present);
+    if missing.is_empty() {
+        tracing::info!("✅ Schema self-check {}: {} tabel lengkap", service, required.len());
+        return Ok(());
+    }
+
+    tracing::error!(
+        "❌ Schema database {} tidak lengkap, jalankan migration. Tidak ditemukan: {}",
+        service,
+        missing.join(", ")
+    );
+
+    if is_production {
+        return Err(format!(
+            "Schema database tidak lengkap ({} item hilang): {}",
+            missing.len(),
+            missing.join(", ")
+        ));
+    }
+
+    tracing::warn!("⚠️ {} tetap berjalan karena bukan production, endpoint terkait akan gagal", service);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUIRED: SchemaRequirements = &[
+        ("rental_bookings", &["id", "seller_id"]),
+        ("conversations", &["id", "vehicle_id"]),
+        ("audit_logs", &["id"]),
+    ];
+
+    fn present(tables: &[(&str, &[&str])]) -> HashMap<String, HashSet<String>> {
+        tables
+            .iter()
+            .map(|(table, columns)| (table.to_string(), columns.iter().map(|c| c.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_missing_tables_and_columns() {
+        let complete = present(&[
+            ("rental_bookings", &["id", "seller_id", "status"]),
+            ("conversations", &["id", "vehicle_id"]),
+            ("audit_logs", &["id"]),
+        ]);
+        assert!(missing_items(REQUIRED, &complete).is_empty());
+
+        let partial = present(&[("rental_bookings", &["id"]), ("conversations", &["id", "vehicle_id"])]);
+        assert_eq!(
+            missing_items(REQUIRED, &partial),
+            vec!["kolom rental_bookings.seller_id".to_string(), "tabel audit_logs".to_string()]
+        );
+    }
+}
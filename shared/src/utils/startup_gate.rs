@@ -0,0 +1,129 @@
+// Gate startup untuk semua service
+//
+// Listener dibind sebelum koneksi DB/Redis/NATS selesai dibuat. Selama dependency belum siap,
+// request dijawab 503 dengan header Retry-After (bukan connection refused atau error yang
+// membingungkan), health check dijawab status "starting" agar readiness probe belum lolos.
+// Setelah AppState siap, router asli dipasang lewat `StartupGate::ready` dan semua request
+// diteruskan apa adanya (termasuk upgrade WebSocket dan ConnectInfo).
+
+use std::convert::Infallible;
+use std::sync::{Arc, OnceLock};
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde_json::json;
+use tower::ServiceExt;
+
+// Perkiraan detik sampai service siap, dikirim di header Retry-After
+pub const STARTUP_RETRY_AFTER_SECS: u64 = 5;
+
+#[derive(Clone)]
+pub struct StartupGate {
+    app: Arc<OnceLock<Router>>,
+    health_paths: Arc<Vec<String>>,
+}
+
+impl StartupGate {
+    // `health_paths` dijawab status starting (tanpa Retry-After) selama belum siap
+    pub fn new(health_paths: &[&str]) -> Self {
+        Self {
+            app: Arc::new(OnceLock::new()),
+            health_paths: Arc::new(health_paths.iter().map(|path| path.to_string()).collect()),
+        }
+    }
+
+    // Router yang di-serve sejak listener dibind
+    pub fn router(&self) -> Router {
+        let gate = self.clone();
+
+        Router::new().fallback_service(tower::service_fn(move |req: Request| {
+            let gate = gate.clone();
+            async move {
+                match gate.app.get() {
+                    Some(app) => app.clone().oneshot(req).await,
+                    None => Ok::<_, Infallible>(gate.starting_response(req.uri().path())),
+                }
+            }
+        }))
+    }
+
+    // Pasang router asli setelah semua dependency siap
+    pub fn ready(&self, app: Router) {
+        if self.app.set(app).is_err() {
+            tracing::warn!("StartupGate::ready dipanggil lebih dari sekali, router pertama tetap dipakai");
+            return;
+        }
+
+        tracing::info!(event = "service_ready", "Dependency siap, request mulai dilayani");
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.app.get().is_some()
+    }
+
+    fn starting_response(&self, path: &str) -> Response {
+        if self.health_paths.iter().any(|health| health == path) {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "status": "starting", "ready": false })),
+            )
+                .into_response();
+        }
+
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, STARTUP_RETRY_AFTER_SECS.to_string())],
+            Json(json!({
+                "error": "service_starting",
+                "pesan": "Service sedang dimulai, coba lagi sebentar",
+            })),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get};
+
+    async fn call(router: &Router, path: &str) -> Response {
+        router
+            .clone()
+            .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_503_before_ready_then_served() {
+        let gate = StartupGate::new(&["/health"]);
+        let router = gate.router();
+
+        let response = call(&router, "/api/orders").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], STARTUP_RETRY_AFTER_SECS.to_string());
+
+        // Health check tidak lolos selama starting, tanpa Retry-After
+        let response = call(&router, "/health").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+        assert!(!gate.is_ready());
+
+        gate.ready(
+            Router::new()
+                .route("/api/orders", get(|| async { "orders" }))
+                .route("/health", get(|| async { "healthy" })),
+        );
+        assert!(gate.is_ready());
+
+        // Router yang sudah di-serve sebelum ready ikut meneruskan request
+        assert_eq!(call(&router, "/api/orders").await.status(), StatusCode::OK);
+        assert_eq!(call(&router, "/health").await.status(), StatusCode::OK);
+        assert_eq!(call(&router, "/unknown").await.status(), StatusCode::NOT_FOUND);
+    }
+}
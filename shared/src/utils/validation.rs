@@ -1,5 +1,49 @@
 use regex::Regex;
 use chrono::Datelike;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// Error validasi untuk satu field request, supaya frontend bisa menandai input yang salah
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+// Error validasi per field untuk AppError semua service: service cukup menyediakan
+// `from_field_errors`, pesan utama `fields` diambil dari error pertama
+pub trait FromFieldErrors: Sized {
+    fn from_field_errors(message: String, errors: Vec<FieldError>) -> Self;
+
+    fn fields(errors: Vec<FieldError>) -> Self {
+        let message = errors
+            .first()
+            .map(|e| e.message.clone())
+            .unwrap_or_else(|| "Data tidak valid".to_string());
+        Self::from_field_errors(message, errors)
+    }
+}
+
+// Kelompokkan error per field untuk response JSON (pesan pertama per field yang dipakai)
+pub fn errors_by_field(errors: &[FieldError]) -> BTreeMap<String, String> {
+    let mut by_field = BTreeMap::new();
+    for error in errors {
+        by_field
+            .entry(error.field.clone())
+            .or_insert_with(|| error.message.clone());
+    }
+    by_field
+}
 
 // Validate format email
 pub fn is_valid_email(email: &str) -> bool {
@@ -59,10 +103,152 @@ pub fn sanitize_html(input: &str) -> String {
 }
 
 
+// Host storage yang dipercaya untuk URL gambar hasil upload
+const TRUSTED_IMAGE_HOSTS: &[&str] = &["res.cloudinary.com"];
+
+// Ekstensi gambar yang diterima untuk dokumen (KTP/SIM) dan avatar
+const ALLOWED_IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "heic", "heif"];
+
+// Batas tunggu HEAD request saat cek content type
+const IMAGE_HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Validate URL gambar upload: hanya Cloudinary milik kita, bukan URL eksternal sembarang.
+// HEAD check content type aktif jika VERIFY_UPLOAD_CONTENT_TYPE=true
+pub async fn validate_uploaded_image_url(url: &str) -> Result<(), String> {
+    let cloud_name = std::env::var("CLOUDINARY_CLOUD_NAME").ok();
+    let parsed = check_image_url(url, cloud_name.as_deref())?;
+
+    let verify_content_type = std::env::var("VERIFY_UPLOAD_CONTENT_TYPE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(false);
+
+    if verify_content_type {
+        verify_image_content_type(parsed).await?;
+    }
+
+    Ok(())
+}
+
+// Cek format URL tanpa network: https, host allowlist, cloud name, resource image, ekstensi
+fn check_image_url(url: &str, cloud_name: Option<&str>) -> Result<reqwest::Url, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("URL foto harus diisi".to_string());
+    }
+
+    let parsed = reqwest::Url::parse(url).map_err(|_| "URL foto tidak valid".to_string())?;
+
+    if parsed.scheme() != "https" {
+        return Err("URL foto harus menggunakan https".to_string());
+    }
+
+    // Tolak credential dan port custom agar URL tidak bisa diarahkan ke tempat lain
+    if !parsed.username().is_empty() || parsed.password().is_some() || parsed.port().is_some() {
+        return Err("URL foto tidak valid".to_string());
+    }
+
+    let host = parsed.host_str().unwrap_or_default().to_ascii_lowercase();
+    if !TRUSTED_IMAGE_HOSTS.contains(&host.as_str()) {
+        return Err("URL foto harus berasal dari storage BigAuto (Cloudinary)".to_string());
+    }
+
+    // Format path Cloudinary: /{cloud_name}/image/upload/...
+    let segments: Vec<&str> = parsed.path_segments().map(|s| s.collect()).unwrap_or_default();
+    if segments.len() < 4 || segments[1] != "image" || segments[2] != "upload" {
+        return Err("URL foto harus berupa gambar yang diupload ke Cloudinary".to_string());
+    }
+
+    if let Some(expected) = cloud_name.filter(|name| !name.is_empty()) {
+        if segments[0] != expected {
+            return Err("URL foto harus berasal dari storage BigAuto (Cloudinary)".to_string());
+        }
+    }
+
+    let extension = segments
+        .last()
+        .and_then(|file| file.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    if !ALLOWED_IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "Format foto tidak didukung, gunakan {}",
+            ALLOWED_IMAGE_EXTENSIONS.join("/")
+        ));
+    }
+
+    Ok(parsed)
+}
+
+// HEAD request ke URL (tanpa follow redirect) dan pastikan content type image/*
+async fn verify_image_content_type(url: reqwest::Url) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(IMAGE_HEAD_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("Gagal memverifikasi foto: {}", e))?;
+
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|_| "Foto tidak dapat diakses".to_string())?;
+
+    if !response.status().is_success() {
+        return Err("Foto tidak ditemukan di storage".to_string());
+    }
+
+    let is_image = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("image/"));
+
+    if !is_image {
+        return Err("File yang diupload bukan gambar".to_string());
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_errors_by_field_keeps_first_message() {
+        let errors = vec![
+            FieldError::new("email", "Format email tidak valid"),
+            FieldError::new("password", "Password minimal 8 karakter"),
+            FieldError::new("email", "Email sudah terdaftar"),
+        ];
+
+        let by_field = errors_by_field(&errors);
+        assert_eq!(by_field.len(), 2);
+        assert_eq!(by_field["email"], "Format email tidak valid");
+        assert_eq!(by_field["password"], "Password minimal 8 karakter");
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Validation(String, usize);
+
+    impl FromFieldErrors for Validation {
+        fn from_field_errors(message: String, errors: Vec<FieldError>) -> Self {
+            Validation(message, errors.len())
+        }
+    }
+
+    #[test]
+    fn test_fields_uses_first_message() {
+        let errors = vec![
+            FieldError::new("slot_id", "Slot tidak ditemukan"),
+            FieldError::new("notes", "Catatan terlalu panjang"),
+        ];
+
+        assert_eq!(Validation::fields(errors), Validation("Slot tidak ditemukan".to_string(), 2));
+        assert_eq!(Validation::fields(Vec::new()), Validation("Data tidak valid".to_string(), 0));
+    }
+
     #[test]
     fn test_email_validation() {
         assert!(is_valid_email("test@example.com"));
@@ -113,4 +299,28 @@ mod tests {
         assert!(!is_valid_rating(0));
         assert!(!is_valid_rating(6));
     }
+
+    #[test]
+    fn test_uploaded_image_url_allowlist() {
+        let url = "https://res.cloudinary.com/bigauto/image/upload/v123/documents/ktp-1.jpg";
+        assert!(check_image_url(url, Some("bigauto")).is_ok());
+        assert!(check_image_url(url, None).is_ok());
+
+        // Cloud lain, host lain, http, dan URL internal ditolak
+        assert!(check_image_url(url, Some("other-cloud")).is_err());
+        assert!(check_image_url("https://evil.com/bigauto/image/upload/ktp.jpg", None).is_err());
+        assert!(check_image_url("https://res.cloudinary.com.evil.com/x/image/upload/a.jpg", None).is_err());
+        assert!(check_image_url("http://res.cloudinary.com/bigauto/image/upload/ktp.jpg", None).is_err());
+        assert!(check_image_url("https://169.254.169.254/latest/meta-data", None).is_err());
+        assert!(check_image_url("https://user@res.cloudinary.com/bigauto/image/upload/a.jpg", None).is_err());
+        assert!(check_image_url("", None).is_err());
+    }
+
+    #[test]
+    fn test_uploaded_image_url_must_be_image() {
+        assert!(check_image_url("https://res.cloudinary.com/bigauto/raw/upload/doc.pdf", None).is_err());
+        assert!(check_image_url("https://res.cloudinary.com/bigauto/image/upload/v1/ktp.pdf", None).is_err());
+        assert!(check_image_url("https://res.cloudinary.com/bigauto/image/upload/v1/ktp", None).is_err());
+        assert!(check_image_url("https://res.cloudinary.com/bigauto/image/upload/v1/SIM.PNG", None).is_ok());
+    }
   }
\ No newline at end of file